@@ -1,5 +1,7 @@
 use std::fs::File;
+use std::net::TcpListener;
 use std::process::Command;
+use std::time::Duration;
 
 use ethers::utils::{Ganache, GanacheInstance};
 use pretty_assertions::assert_eq;
@@ -9,7 +11,12 @@ use starknet_api::stark_felt;
 use tar::Archive;
 use tempfile::{tempdir, TempDir};
 
-use crate::ethereum_base_layer_contract::{EthereumBaseLayerConfig, EthereumBaseLayerContract};
+use crate::ethereum_base_layer_contract::{
+    agreed_answer,
+    EthereumBaseLayerConfig,
+    EthereumBaseLayerContract,
+    EthereumBaseLayerError,
+};
 use crate::BaseLayerContract;
 
 type EthereumContractAddress = String;
@@ -59,8 +66,11 @@ fn get_test_ethereum_node() -> (TestEthereumNodeHandle, EthereumContractAddress)
 // Note: the test requires ganache-cli installed, otherwise it is ignored.
 async fn latest_proved_block_ethereum() {
     let (node_handle, starknet_contract_address) = get_test_ethereum_node();
-    let config =
-        EthereumBaseLayerConfig { node_url: node_handle.0.endpoint(), starknet_contract_address };
+    let config = EthereumBaseLayerConfig {
+        node_url: node_handle.0.endpoint(),
+        starknet_contract_address,
+        ..Default::default()
+    };
     let contract = EthereumBaseLayerContract::new(config).unwrap();
 
     let first_sn_state_update = (BlockNumber(100), BlockHash(stark_felt!("0x100")));
@@ -79,4 +89,58 @@ async fn latest_proved_block_ethereum() {
         let latest_block = contract.latest_proved_block(scenario).await.unwrap();
         assert_eq!(latest_block, expected);
     }
+
+    // The block is read from the last Ethereum block.
+    let (_block_number, _block_hash, l1_block) =
+        contract.latest_proved_block_with_l1_block(None).await.unwrap().unwrap();
+    assert_eq!(l1_block.number, 31);
+    assert_eq!(contract.l1_block_hash(31).await.unwrap(), Some(l1_block.hash));
+    assert_eq!(contract.l1_block_hash(1000).await.unwrap(), None);
+}
+
+#[test]
+fn agreed_answer_requires_quorum() {
+    assert_eq!(agreed_answer(&[1, 2, 1], 2), Some(1));
+    assert_eq!(agreed_answer(&[1, 2, 3], 2), None);
+    assert_eq!(agreed_answer::<u8>(&[], 1), None);
+    // The most common answer wins, and from equally common ones the first.
+    assert_eq!(agreed_answer(&[1, 2, 2, 3], 1), Some(2));
+    assert_eq!(agreed_answer(&[3, 1, 1, 3], 1), Some(3));
+}
+
+#[test]
+fn quorum_is_validated() {
+    let config = |quorum| EthereumBaseLayerConfig {
+        node_url: "http://localhost:8545".to_owned(),
+        additional_node_urls: vec!["http://localhost:8546".to_owned()],
+        quorum,
+        ..Default::default()
+    };
+    assert!(EthereumBaseLayerContract::new(config(1)).is_ok());
+    assert!(EthereumBaseLayerContract::new(config(2)).is_ok());
+    assert!(matches!(
+        EthereumBaseLayerContract::new(config(0)),
+        Err(EthereumBaseLayerError::InvalidQuorum { quorum: 0, n_nodes: 2 })
+    ));
+    assert!(matches!(
+        EthereumBaseLayerContract::new(config(3)),
+        Err(EthereumBaseLayerError::InvalidQuorum { quorum: 3, n_nodes: 2 })
+    ));
+}
+
+#[tokio::test]
+async fn unresponsive_node_times_out() {
+    // Accepts connections but never answers.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let node_timeout = Duration::from_millis(100);
+    let config = EthereumBaseLayerConfig {
+        node_url: format!("http://{}", listener.local_addr().unwrap()),
+        node_timeout,
+        ..Default::default()
+    };
+    let contract = EthereumBaseLayerContract::new(config).unwrap();
+    assert!(matches!(
+        contract.l1_block_hash(1).await,
+        Err(EthereumBaseLayerError::Timeout(timeout)) if timeout == node_timeout
+    ));
 }
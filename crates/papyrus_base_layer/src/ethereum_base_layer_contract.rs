@@ -1,21 +1,31 @@
 use std::collections::BTreeMap;
+use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use ethers::abi::{Abi, AbiEncode};
 use ethers::contract::Contract;
 use ethers::prelude::{AbiError, Address, ContractError, Http, Middleware, Provider};
 use ethers::providers::ProviderError;
-use ethers::types::{I256, U256};
-use papyrus_config::dumping::{ser_param, ser_required_param, SerializeConfig};
+use ethers::types::{BlockId, H256, I256, U256};
+use papyrus_common::outbound_connection::{OutboundConnectionConfig, OutboundConnectionError};
+use papyrus_config::converters::deserialize_seconds_to_duration;
+use papyrus_config::dumping::{
+    append_sub_config_name,
+    ser_param,
+    ser_required_param,
+    SerializeConfig,
+};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializationType, SerializedParam};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use starknet_api::block::{BlockHash, BlockNumber};
 use starknet_api::hash::StarkHash;
 use starknet_api::StarknetApiError;
-use url::ParseError;
+use tokio::task::JoinSet;
+use url::{ParseError, Url};
 
-use crate::BaseLayerContract;
+use crate::{BaseLayerContract, L1BlockHash, L1BlockReference};
 
 #[derive(thiserror::Error, Debug)]
 pub enum EthereumBaseLayerError {
@@ -33,31 +43,71 @@ pub enum EthereumBaseLayerError {
     BadContract(#[from] ContractError<Provider<Http>>),
     #[error(transparent)]
     StarknetApi(#[from] StarknetApiError),
+    #[error(transparent)]
+    OutboundConnection(#[from] OutboundConnectionError),
+    #[error(transparent)]
+    HttpClient(#[from] reqwest::Error),
+    #[error(
+        "The quorum {quorum} should be between 1 and the number of Ethereum nodes, {n_nodes}."
+    )]
+    InvalidQuorum { quorum: usize, n_nodes: usize },
+    #[error("The Ethereum node didn't answer within {0:?}.")]
+    Timeout(Duration),
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct EthereumBaseLayerConfig {
     // TODO(yair): consider using types.
     pub node_url: String,
+    #[serde(deserialize_with = "deserialize_urls")]
+    pub additional_node_urls: Vec<String>,
+    pub quorum: usize,
+    #[serde(deserialize_with = "deserialize_seconds_to_duration")]
+    pub node_timeout: Duration,
     pub starknet_contract_address: String,
+    pub outbound_connection: OutboundConnectionConfig,
 }
 
 impl SerializeConfig for EthereumBaseLayerConfig {
     fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
-        BTreeMap::from_iter([
+        let mut dump = BTreeMap::from_iter([
             ser_required_param(
                 "node_url",
                 SerializationType::String,
                 "Ethereum node URL. A schema to match to Infura node: https://mainnet.infura.io/v3/<your_api_key>, but any other node can be used.",
                 ParamPrivacyInput::Private,
             ),
+            ser_param(
+                "additional_node_urls",
+                &self.additional_node_urls.join(" "),
+                "'url1 url2 ...' URLs of more Ethereum nodes. The base layer is read from all the \
+                 nodes, so that a node that fails is replaced by the others and a node that \
+                 reports a wrong state is outvoted.",
+                ParamPrivacyInput::Private,
+            ),
+            ser_param(
+                "quorum",
+                &self.quorum,
+                "The number of Ethereum nodes that have to agree on the base layer state for it to \
+                 be accepted. Between 1 and the number of nodes.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "node_timeout",
+                &self.node_timeout.as_secs(),
+                "Time in seconds to wait for each Ethereum node to answer, after which the node is \
+                 treated as failed.",
+                ParamPrivacyInput::Public,
+            ),
             ser_param(
                 "starknet_contract_address",
                 &self.starknet_contract_address,
                 "Starknet contract address in ethereum.",
                 ParamPrivacyInput::Public,
             ),
-        ])
+        ]);
+        dump.extend(append_sub_config_name(self.outbound_connection.dump(), "outbound_connection"));
+        dump
     }
 }
 
@@ -65,23 +115,180 @@ impl Default for EthereumBaseLayerConfig {
     fn default() -> Self {
         Self {
             node_url: "https://mainnet.infura.io/v3/<your_api_key>".to_string(),
+            additional_node_urls: vec![],
+            quorum: 1,
+            node_timeout: Duration::from_secs(10),
             starknet_contract_address: "0xc662c410C0ECf747543f5bA90660f6ABeBD9C8c4".to_string(),
+            outbound_connection: OutboundConnectionConfig::default(),
         }
     }
 }
 
+fn deserialize_urls<'de, D>(de: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw_str: String = Deserialize::deserialize(de)?;
+    Ok(raw_str.split_whitespace().map(str::to_owned).collect())
+}
+
+/// Reads the base layer from one or more Ethereum nodes. A node that fails is ignored as long as
+/// enough other nodes answer, and a result is accepted only if the quorum of nodes agree on it.
 pub struct EthereumBaseLayerContract {
-    contract: Contract<Provider<Http>>,
+    // The contract as seen by each node.
+    contracts: Vec<Contract<Provider<Http>>>,
+    quorum: usize,
+    node_timeout: Duration,
 }
 
 impl EthereumBaseLayerContract {
     pub fn new(config: EthereumBaseLayerConfig) -> Result<Self, EthereumBaseLayerError> {
+        let n_nodes = config.additional_node_urls.len() + 1;
+        if config.quorum == 0 || config.quorum > n_nodes {
+            return Err(EthereumBaseLayerError::InvalidQuorum { quorum: config.quorum, n_nodes });
+        }
         let address = config.starknet_contract_address.parse::<Address>()?;
-        let client: Provider<Http> = Provider::<Http>::try_from(config.node_url)?;
+        let http_client =
+            config.outbound_connection.configure(reqwest::Client::builder())?.build()?;
         // The solidity contract was pre-compiled, and only the relevant functions were kept.
         let abi: Abi = serde_json::from_str::<Abi>(include_str!("core_contract_latest_block.abi"))?;
-        Ok(Self { contract: Contract::new(address, abi, Arc::new(client)) })
+        let contracts = std::iter::once(&config.node_url)
+            .chain(config.additional_node_urls.iter())
+            .map(|node_url| -> Result<_, EthereumBaseLayerError> {
+                let client = Provider::new(Http::new_with_client(
+                    Url::parse(node_url)?,
+                    http_client.clone(),
+                ));
+                Ok(Contract::new(address, abi.clone(), Arc::new(client)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { contracts, quorum: config.quorum, node_timeout: config.node_timeout })
     }
+
+    // Sends the query to all the nodes concurrently and returns their results in the order of the
+    // nodes. A node that doesn't answer within the timeout fails.
+    async fn query_nodes<T, Query, QueryFuture>(
+        &self,
+        query: Query,
+    ) -> Vec<Result<T, EthereumBaseLayerError>>
+    where
+        T: Send + 'static,
+        Query: Fn(Contract<Provider<Http>>) -> QueryFuture,
+        QueryFuture: Future<Output = Result<T, EthereumBaseLayerError>> + Send + 'static,
+    {
+        let mut queries = JoinSet::new();
+        for (index, contract) in self.contracts.iter().enumerate() {
+            let query = tokio::time::timeout(self.node_timeout, query(contract.clone()));
+            queries.spawn(async move { (index, query.await) });
+        }
+        let mut results = Vec::with_capacity(self.contracts.len());
+        while let Some(joined) = queries.join_next().await {
+            let (index, result) = joined.expect("Querying an Ethereum node shouldn't panic.");
+            let result = result
+                .unwrap_or_else(|_elapsed| Err(EthereumBaseLayerError::Timeout(self.node_timeout)));
+            results.push((index, result));
+        }
+        results.sort_by_key(|(index, _result)| *index);
+        results.into_iter().map(|(_index, result)| result).collect()
+    }
+
+    // Returns the answers of the nodes that answered. Fails if less than the quorum answered.
+    fn answers<T>(
+        &self,
+        results: Vec<Result<T, EthereumBaseLayerError>>,
+    ) -> Result<Vec<T>, EthereumBaseLayerError> {
+        let mut answers = Vec::with_capacity(results.len());
+        let mut last_error = None;
+        for result in results {
+            match result {
+                Ok(answer) => answers.push(answer),
+                Err(err) => last_error = Some(err),
+            }
+        }
+        match last_error {
+            Some(err) if answers.len() < self.quorum => Err(err),
+            _ => Ok(answers),
+        }
+    }
+}
+
+/// Returns the answer that at least `quorum` of the answers are equal to, or None if there isn't
+/// one. If there are several, the most common is returned, and from the equally common the one
+/// that appears first.
+pub(crate) fn agreed_answer<T: Eq + Clone>(answers: &[T], quorum: usize) -> Option<T> {
+    let mut counts: Vec<(&T, usize)> = Vec::new();
+    for answer in answers {
+        match counts.iter_mut().find(|(counted, _count)| *counted == answer) {
+            Some((_counted, count)) => *count += 1,
+            None => counts.push((answer, 1)),
+        }
+    }
+    counts
+        .into_iter()
+        .filter(|(_answer, count)| *count >= quorum)
+        .fold(None, |agreed, (answer, count)| match agreed {
+            Some((_agreed, agreed_count)) if agreed_count >= count => agreed,
+            _ => Some((answer, count)),
+        })
+        .map(|(answer, _count)| answer.clone())
+}
+
+async fn latest_proved_block_of(
+    contract: &Contract<Provider<Http>>,
+    min_confirmations: Option<u64>,
+) -> Result<Option<(BlockNumber, BlockHash, L1BlockReference)>, EthereumBaseLayerError> {
+    let ethereum_block_number = contract
+        .client()
+        .get_block_number()
+        .await?
+        .checked_sub(min_confirmations.unwrap_or(0).into());
+    let Some(ethereum_block_number) = ethereum_block_number else {
+        return Ok(None);
+    };
+    // The block may have been removed by a reorg since its number was read.
+    let Some(ethereum_block_hash) =
+        l1_block_hash_of(contract, ethereum_block_number.as_u64()).await?
+    else {
+        return Ok(None);
+    };
+
+    // The state is read at the hash of the block rather than at its number, so that it matches
+    // the returned block even if the base layer reorgs meanwhile.
+    let block_id = BlockId::Hash(H256(ethereum_block_hash.0));
+    let call_state_block_number =
+        contract.method::<_, I256>("stateBlockNumber", ())?.block(block_id);
+    let call_state_block_hash = contract.method::<_, U256>("stateBlockHash", ())?.block(block_id);
+    let (state_block_number, state_block_hash) =
+        tokio::try_join!(call_state_block_number.call(), call_state_block_hash.call())?;
+
+    Ok(Some((
+        BlockNumber(state_block_number.as_u64()),
+        BlockHash(StarkHash::try_from(state_block_hash.encode_hex().as_str())?),
+        L1BlockReference { number: ethereum_block_number.as_u64(), hash: ethereum_block_hash },
+    )))
+}
+
+async fn l1_block_hash_of(
+    contract: &Contract<Provider<Http>>,
+    l1_block_number: u64,
+) -> Result<Option<L1BlockHash>, EthereumBaseLayerError> {
+    let block = contract.client().get_block(l1_block_number).await?;
+    Ok(block.and_then(|block| block.hash).map(|hash| L1BlockHash(hash.0)))
+}
+
+// Like l1_block_hash_of, but returns None if the node hasn't reached the block yet, since then it
+// can't tell whether the block was removed.
+async fn l1_block_hash_if_reached(
+    contract: &Contract<Provider<Http>>,
+    l1_block_number: u64,
+) -> Result<Option<Option<L1BlockHash>>, EthereumBaseLayerError> {
+    let block_hash = l1_block_hash_of(contract, l1_block_number).await?;
+    if block_hash.is_none()
+        && contract.client().get_block_number().await?.as_u64() < l1_block_number
+    {
+        return Ok(None);
+    }
+    Ok(Some(block_hash))
 }
 
 #[async_trait]
@@ -92,26 +299,61 @@ impl BaseLayerContract for EthereumBaseLayerContract {
         &self,
         min_confirmations: Option<u64>,
     ) -> Result<Option<(BlockNumber, BlockHash)>, Self::Error> {
-        let ethereum_block_number = self
-            .contract
-            .client()
-            .get_block_number()
+        Ok(self
+            .latest_proved_block_with_l1_block(min_confirmations)
             .await?
-            .checked_sub(min_confirmations.unwrap_or(0).into());
-        let Some(ethereum_block_number) = ethereum_block_number else {
+            .map(|(block_number, block_hash, _l1_block)| (block_number, block_hash)))
+    }
+
+    async fn latest_proved_block_with_l1_block(
+        &self,
+        min_confirmations: Option<u64>,
+    ) -> Result<Option<(BlockNumber, BlockHash, L1BlockReference)>, Self::Error> {
+        let results = self
+            .query_nodes(move |contract| async move {
+                latest_proved_block_of(&contract, min_confirmations).await
+            })
+            .await;
+        let answers = self.answers(results)?;
+        // The nodes may be at different Ethereum blocks, so they agree on the Starknet block.
+        let proved_blocks = answers
+            .iter()
+            .map(|answer| {
+                answer.map(|(block_number, block_hash, _l1_block)| (block_number, block_hash))
+            })
+            .collect::<Vec<_>>();
+        // Without an agreement nothing is known to be proved.
+        let Some(Some((block_number, block_hash))) = agreed_answer(&proved_blocks, self.quorum)
+        else {
             return Ok(None);
         };
+        // The earliest of the Ethereum blocks the agreeing nodes read the Starknet block from,
+        // which all of them have.
+        let l1_block = answers
+            .into_iter()
+            .flatten()
+            .filter(|(answer_number, answer_hash, _l1_block)| {
+                (*answer_number, *answer_hash) == (block_number, block_hash)
+            })
+            .map(|(_block_number, _block_hash, l1_block)| l1_block)
+            .min_by_key(|l1_block| l1_block.number)
+            .expect("The agreed block should be an answer of some node.");
+        Ok(Some((block_number, block_hash, l1_block)))
+    }
 
-        let call_state_block_number =
-            self.contract.method::<_, I256>("stateBlockNumber", ())?.block(ethereum_block_number);
-        let call_state_block_hash =
-            self.contract.method::<_, U256>("stateBlockHash", ())?.block(ethereum_block_number);
-        let (state_block_number, state_block_hash) =
-            tokio::try_join!(call_state_block_number.call(), call_state_block_hash.call())?;
-
-        Ok(Some((
-            BlockNumber(state_block_number.as_u64()),
-            BlockHash(StarkHash::try_from(state_block_hash.encode_hex().as_str())?),
-        )))
+    async fn l1_block_hash(
+        &self,
+        l1_block_number: u64,
+    ) -> Result<Option<L1BlockHash>, Self::Error> {
+        let results = self
+            .query_nodes(move |contract| async move {
+                l1_block_hash_if_reached(&contract, l1_block_number).await
+            })
+            .await;
+        // The nodes that haven't reached the block don't vote on it.
+        let answers = self.answers(results)?.into_iter().flatten().collect::<Vec<_>>();
+        // Without an agreement the block is treated as removed, which is the safe side since it
+        // only revokes the acceptance of Starknet blocks.
+        Ok(agreed_answer(&answers, self.quorum).flatten())
     }
 }
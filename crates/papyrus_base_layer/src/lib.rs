@@ -6,6 +6,17 @@ mod base_layer_test;
 
 pub mod ethereum_base_layer_contract;
 
+/// The hash of a block of the base layer.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct L1BlockHash(pub [u8; 32]);
+
+/// A block of the base layer.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct L1BlockReference {
+    pub number: u64,
+    pub hash: L1BlockHash,
+}
+
 /// Interface for getting data from the Starknet base contract.
 #[async_trait]
 pub trait BaseLayerContract {
@@ -17,4 +28,16 @@ pub trait BaseLayerContract {
         &self,
         min_confirmations: Option<u64>,
     ) -> Result<Option<(BlockNumber, BlockHash)>, Self::Error>;
+
+    /// Get the latest Starknet block that is proved on the base layer, along with the base layer
+    /// block it was read from. Optionally, require minimum confirmations.
+    async fn latest_proved_block_with_l1_block(
+        &self,
+        min_confirmations: Option<u64>,
+    ) -> Result<Option<(BlockNumber, BlockHash, L1BlockReference)>, Self::Error>;
+
+    /// Get the hash of the base layer block with the given number, or None if the base layer
+    /// doesn't have such a block.
+    async fn l1_block_hash(&self, l1_block_number: u64)
+        -> Result<Option<L1BlockHash>, Self::Error>;
 }
@@ -0,0 +1,19 @@
+use std::io::Result;
+
+fn main() -> Result<()> {
+    println!("Building");
+    // Only the crates that use the messages of the p2p specs compile them, since it requires
+    // protoc.
+    #[cfg(feature = "protobuf")]
+    prost_build::compile_protos(
+        &[
+            "src/proto/p2p/proto/header.proto",
+            "src/proto/p2p/proto/transaction.proto",
+            "src/proto/p2p/proto/receipt.proto",
+            "src/proto/p2p/proto/event.proto",
+            "src/proto/p2p/proto/state.proto",
+        ],
+        &["src/proto/"],
+    )?;
+    Ok(())
+}
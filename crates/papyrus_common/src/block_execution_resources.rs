@@ -0,0 +1,78 @@
+//! The execution resources that the transactions of a block consumed together, computed from their
+//! receipts, for watching the load of the chain.
+#[cfg(test)]
+#[path = "block_execution_resources_test.rs"]
+mod block_execution_resources_test;
+
+use serde::{Deserialize, Serialize};
+use starknet_api::block::GasPricePerToken;
+use starknet_api::transaction::{
+    ExecutionResources,
+    Transaction,
+    TransactionOutput,
+    TransactionVersion,
+};
+
+/// The sum of the execution resources of the transactions of a block.
+///
+/// The receipts don't record the gas that a transaction consumed, so the L1 gas is derived from the
+/// fee and the L1 gas price of the block, in the unit the transaction paid its fee in, like the gas
+/// consumed of a fee estimation. Blocks whose gas price is zero consumed no gas.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct BlockExecutionResources {
+    pub n_transactions: u64,
+    pub steps: u64,
+    pub memory_holes: u64,
+    pub l1_gas: u128,
+}
+
+impl BlockExecutionResources {
+    /// Sums the execution resources of the given transactions and their outputs, in the order of
+    /// the block.
+    pub fn new(
+        transactions: &[Transaction],
+        transaction_outputs: &[TransactionOutput],
+        l1_gas_price: GasPricePerToken,
+    ) -> Self {
+        let mut resources = Self::default();
+        for (transaction, transaction_output) in transactions.iter().zip(transaction_outputs) {
+            let (fee, execution_resources) = fee_and_execution_resources(transaction_output);
+            resources.n_transactions += 1;
+            resources.steps += execution_resources.steps;
+            resources.memory_holes += execution_resources.memory_holes;
+            // Transactions before version 3 pay their fee in Wei and transactions of version 3 pay
+            // it in Fri.
+            let gas_price = if transaction_version(transaction) == TransactionVersion::THREE {
+                l1_gas_price.price_in_fri
+            } else {
+                l1_gas_price.price_in_wei
+            };
+            resources.l1_gas += fee.checked_div(gas_price.0).unwrap_or_default();
+        }
+        resources
+    }
+}
+
+fn fee_and_execution_resources(
+    transaction_output: &TransactionOutput,
+) -> (u128, &ExecutionResources) {
+    match transaction_output {
+        TransactionOutput::Declare(output) => (output.actual_fee.0, &output.execution_resources),
+        TransactionOutput::Deploy(output) => (output.actual_fee.0, &output.execution_resources),
+        TransactionOutput::DeployAccount(output) => {
+            (output.actual_fee.0, &output.execution_resources)
+        }
+        TransactionOutput::Invoke(output) => (output.actual_fee.0, &output.execution_resources),
+        TransactionOutput::L1Handler(output) => (output.actual_fee.0, &output.execution_resources),
+    }
+}
+
+fn transaction_version(transaction: &Transaction) -> TransactionVersion {
+    match transaction {
+        Transaction::Declare(tx) => tx.version(),
+        Transaction::Deploy(tx) => tx.version,
+        Transaction::DeployAccount(tx) => tx.version(),
+        Transaction::Invoke(tx) => tx.version(),
+        Transaction::L1Handler(tx) => tx.version,
+    }
+}
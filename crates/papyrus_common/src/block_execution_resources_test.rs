@@ -0,0 +1,54 @@
+use pretty_assertions::assert_eq;
+use starknet_api::block::{GasPrice, GasPricePerToken};
+use starknet_api::transaction::{
+    ExecutionResources,
+    Fee,
+    InvokeTransaction,
+    InvokeTransactionOutput,
+    InvokeTransactionV1,
+    InvokeTransactionV3,
+    Transaction,
+    TransactionOutput,
+};
+use test_utils::{get_rng, GetTestInstance};
+
+use crate::block_execution_resources::BlockExecutionResources;
+
+fn invoke_output(fee: u128, steps: u64, memory_holes: u64) -> TransactionOutput {
+    TransactionOutput::Invoke(InvokeTransactionOutput {
+        actual_fee: Fee(fee),
+        execution_resources: ExecutionResources { steps, memory_holes, ..Default::default() },
+        ..Default::default()
+    })
+}
+
+#[test]
+fn sums_the_resources_of_the_transactions() {
+    let mut rng = get_rng();
+    let transactions = vec![
+        Transaction::Invoke(InvokeTransaction::V1(InvokeTransactionV1::get_test_instance(
+            &mut rng,
+        ))),
+        Transaction::Invoke(InvokeTransaction::V3(InvokeTransactionV3::get_test_instance(
+            &mut rng,
+        ))),
+    ];
+    let transaction_outputs = vec![invoke_output(100, 10, 1), invoke_output(60, 20, 2)];
+    let l1_gas_price = GasPricePerToken { price_in_wei: GasPrice(10), price_in_fri: GasPrice(20) };
+
+    assert_eq!(
+        BlockExecutionResources::new(&transactions, &transaction_outputs, l1_gas_price),
+        BlockExecutionResources { n_transactions: 2, steps: 30, memory_holes: 3, l1_gas: 13 }
+    );
+
+    // Without a gas price, the transactions consumed no gas.
+    assert_eq!(
+        BlockExecutionResources::new(
+            &transactions,
+            &transaction_outputs,
+            GasPricePerToken::default()
+        )
+        .l1_gas,
+        0
+    );
+}
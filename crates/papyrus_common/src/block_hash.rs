@@ -16,8 +16,9 @@ use starknet_api::transaction::{
 };
 use starknet_api::StarknetApiError;
 
+use crate::chain_id::{ChainIdExt, KnownChain};
 use crate::patricia_hash_tree::calculate_root;
-use crate::transaction_hash::{ascii_as_felt, HashChain, ZERO};
+use crate::transaction_hash::{HashChain, ZERO};
 
 #[derive(Debug, Eq, PartialEq, PartialOrd, Ord)]
 enum BlockHashVersion {
@@ -30,10 +31,15 @@ enum BlockHashVersion {
 /// Validates hash of a starknet block.
 /// A hash is valid if it is the result of one of the hash functions that were ever used in
 /// Starknet.
+/// Chains without historic blocks of hash version 2 (e.g. custom chains) are validated only
+/// against the other versions.
 pub fn validate_block_hash(block: &Block, chain_id: &ChainId) -> Result<bool, StarknetApiError> {
     for version in
         [BlockHashVersion::V3, BlockHashVersion::V2, BlockHashVersion::V1, BlockHashVersion::V0]
     {
+        if version == BlockHashVersion::V2 && get_chain_sequencer_address(chain_id).is_none() {
+            continue;
+        }
         if calculate_block_hash_by_version(block, version, chain_id)? == block.header.block_hash.0 {
             return Ok(true);
         }
@@ -57,7 +63,7 @@ fn calculate_block_hash_by_version(
         .chain(&block.header.block_number.0.into())
         .chain(&block.header.state_root.0)
         .chain_if_else(
-            &get_chain_sequencer_address(chain_id),
+            &get_chain_sequencer_address(chain_id).unwrap_or_default(),
             block.header.sequencer.0.key(),
             version == BlockHashVersion::V2,
         )
@@ -68,7 +74,7 @@ fn calculate_block_hash_by_version(
         .chain(&events_patricia_root)
         .chain(&ZERO) // Not implemented Element.
         .chain(&ZERO) // Not implemented Element.
-        .chain_if(&ascii_as_felt(chain_id.0.as_str())?, version == BlockHashVersion::V0)
+        .chain_if(&chain_id.to_felt()?, version == BlockHashVersion::V0)
         .chain(&block.header.parent_hash.0).get_pedersen_hash())
 }
 
@@ -148,15 +154,18 @@ fn get_event_leaf(event: &Event) -> StarkHash {
 }
 
 // The fixed sequencer addresses of the chains that have historic blocks with block hash version 2.
-fn get_chain_sequencer_address(chain_id: &ChainId) -> StarkHash {
-    match chain_id.to_string().as_str() {
-        "SN_MAIN" => StarkHash::try_from(
-            "0x021f4b90b0377c82bf330b7b5295820769e72d79d8acd0effa0ebde6e9988bc5",
-        )
-        .expect("should be a Stark felt in hex representation"),
+// Returns None for chains that don't have such blocks.
+fn get_chain_sequencer_address(chain_id: &ChainId) -> Option<StarkHash> {
+    match chain_id.known_chain()? {
+        KnownChain::Mainnet => Some(
+            StarkHash::try_from(
+                "0x021f4b90b0377c82bf330b7b5295820769e72d79d8acd0effa0ebde6e9988bc5",
+            )
+            .expect("should be a Stark felt in hex representation"),
+        ),
         // TODO(yoav): Add sequencers for the rest of the supported chains that have historic blocks
         // with block hash version 2.
-        _ => unimplemented!("Sequencer address for chain"),
+        _ => None,
     }
 }
 
@@ -3,7 +3,7 @@ use starknet_api::core::ChainId;
 use test_utils::read_json_file;
 
 use super::calculate_block_hash_by_version;
-use crate::block_hash::BlockHashVersion;
+use crate::block_hash::{validate_block_hash, BlockHashVersion};
 
 fn validate_block_hash_util(file_name: &str, version: BlockHashVersion) -> bool {
     let chain_id = ChainId("SN_MAIN".to_owned());
@@ -39,3 +39,12 @@ fn test_deprecated_block_hash_v1() {
 fn test_deprecated_block_hash_v0() {
     assert!(validate_block_hash_util("deprecated_block_hash_v0.json", BlockHashVersion::V0));
 }
+
+#[test]
+fn test_block_hash_custom_chain() {
+    // Custom chains don't have blocks with hash version 2, so validation shouldn't depend on a
+    // known sequencer address.
+    let chain_id = ChainId("MY_APPCHAIN".to_owned());
+    let block: Block = serde_json::from_value(read_json_file("block_hash.json")).unwrap();
+    assert!(validate_block_hash(&block, &chain_id).unwrap());
+}
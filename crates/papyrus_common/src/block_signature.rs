@@ -0,0 +1,39 @@
+#[cfg(test)]
+#[path = "block_signature_test.rs"]
+mod block_signature_test;
+
+use starknet_api::block::{BlockHash, BlockSignature};
+use starknet_api::core::{GlobalRoot, SequencerPublicKey};
+use starknet_api::crypto::PublicKey;
+use starknet_api::hash::StarkHash;
+use starknet_crypto::{FieldElement, VerifyError};
+
+use crate::hashing::poseidon_hash_many;
+
+/// Returns the message the sequencer signs for a block: the poseidon hash of the block hash and the
+/// state diff commitment of the block.
+pub fn block_signature_message(
+    block_hash: &BlockHash,
+    state_diff_commitment: &GlobalRoot,
+) -> StarkHash {
+    poseidon_hash_many(&[block_hash.0, state_diff_commitment.0])
+}
+
+/// Verifies that the signature of a block was signed by the sequencer.
+/// Returns an error if the signature isn't a valid signature at all (e.g. out of range), and false
+/// if it's valid but wasn't signed by the sequencer over the given block.
+pub fn verify_block_signature(
+    sequencer_pub_key: &SequencerPublicKey,
+    signature: &BlockSignature,
+    state_diff_commitment: &GlobalRoot,
+    block_hash: &BlockHash,
+) -> Result<bool, VerifyError> {
+    let SequencerPublicKey(PublicKey(public_key)) = sequencer_pub_key;
+    let message = block_signature_message(block_hash, state_diff_commitment);
+    starknet_crypto::verify(
+        &FieldElement::from(*public_key),
+        &FieldElement::from(message),
+        &FieldElement::from(signature.0.r),
+        &FieldElement::from(signature.0.s),
+    )
+}
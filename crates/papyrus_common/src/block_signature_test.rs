@@ -0,0 +1,44 @@
+use starknet_api::block::{BlockHash, BlockSignature};
+use starknet_api::core::{GlobalRoot, SequencerPublicKey};
+use starknet_api::crypto::{PublicKey, Signature};
+use starknet_api::hash::StarkFelt;
+use starknet_api::stark_felt;
+use starknet_crypto::{get_public_key, rfc6979_generate_k, sign, FieldElement};
+
+use crate::block_signature::{block_signature_message, verify_block_signature};
+
+// Signs the block as the sequencer with the given private key does.
+fn sign_block(
+    private_key: &FieldElement,
+    block_hash: &BlockHash,
+    state_diff_commitment: &GlobalRoot,
+) -> BlockSignature {
+    let message = FieldElement::from(block_signature_message(block_hash, state_diff_commitment));
+    let k = rfc6979_generate_k(&message, private_key, None);
+    let signature = sign(private_key, &message, &k).unwrap();
+    BlockSignature(Signature { r: signature.r.into(), s: signature.s.into() })
+}
+
+#[test]
+fn verify_signature_of_the_sequencer() {
+    let private_key = FieldElement::from(0x1234_u64);
+    let sequencer_pub_key =
+        SequencerPublicKey(PublicKey(StarkFelt::from(get_public_key(&private_key))));
+    let block_hash = BlockHash(stark_felt!("0x10"));
+    let state_diff_commitment = GlobalRoot(stark_felt!("0x20"));
+    let verify = |signature: &BlockSignature, block_hash: &BlockHash| {
+        verify_block_signature(&sequencer_pub_key, signature, &state_diff_commitment, block_hash)
+            .unwrap()
+    };
+
+    let signature = sign_block(&private_key, &block_hash, &state_diff_commitment);
+    assert!(verify(&signature, &block_hash));
+
+    // A signature over a different block.
+    assert!(!verify(&signature, &BlockHash(stark_felt!("0x11"))));
+
+    // A signature of someone else.
+    let other_private_key = FieldElement::from(0x5678_u64);
+    let other_signature = sign_block(&other_private_key, &block_hash, &state_diff_commitment);
+    assert!(!verify(&other_signature, &block_hash));
+}
@@ -0,0 +1,110 @@
+#[cfg(test)]
+#[path = "chain_id_test.rs"]
+mod chain_id_test;
+
+use starknet_api::core::ChainId;
+use starknet_api::hash::StarkFelt;
+use starknet_api::StarknetApiError;
+use validator::ValidationError;
+
+// A felt holds at most 31 full bytes.
+const MAX_ASCII_FELT_LENGTH: usize = 31;
+
+/// The chains whose chain ids the node knows.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum KnownChain {
+    Mainnet,
+    Sepolia,
+    SepoliaIntegration,
+    Goerli,
+    Goerli2,
+    GoerliIntegration,
+}
+
+impl KnownChain {
+    /// All the known chains.
+    pub const ALL: [KnownChain; 6] = [
+        KnownChain::Mainnet,
+        KnownChain::Sepolia,
+        KnownChain::SepoliaIntegration,
+        KnownChain::Goerli,
+        KnownChain::Goerli2,
+        KnownChain::GoerliIntegration,
+    ];
+
+    /// The chain id of the chain.
+    pub fn chain_id(&self) -> ChainId {
+        let chain_id = match self {
+            KnownChain::Mainnet => "SN_MAIN",
+            KnownChain::Sepolia => "SN_SEPOLIA",
+            KnownChain::SepoliaIntegration => "SN_INTEGRATION_SEPOLIA",
+            KnownChain::Goerli => "SN_GOERLI",
+            KnownChain::Goerli2 => "SN_GOERLI2",
+            KnownChain::GoerliIntegration => "SN_INTEGRATION",
+        };
+        ChainId(chain_id.to_owned())
+    }
+}
+
+/// Conversions of a chain id to and from the felt that represents it in hashes, and detection of
+/// the known chains.
+pub trait ChainIdExt: Sized {
+    /// The felt whose bytes are the ASCII characters of the chain id.
+    fn to_felt(&self) -> Result<StarkFelt, StarknetApiError>;
+
+    /// The chain id whose ASCII characters are the bytes of the felt.
+    fn from_felt(felt: &StarkFelt) -> Result<Self, StarknetApiError>;
+
+    /// The known chain with this chain id, or None for custom chains.
+    fn known_chain(&self) -> Option<KnownChain>;
+
+    /// Whether this is the chain id of Starknet mainnet.
+    fn is_mainnet(&self) -> bool {
+        self.known_chain() == Some(KnownChain::Mainnet)
+    }
+}
+
+impl ChainIdExt for ChainId {
+    fn to_felt(&self) -> Result<StarkFelt, StarknetApiError> {
+        ascii_as_felt(&self.0)
+    }
+
+    fn from_felt(felt: &StarkFelt) -> Result<Self, StarknetApiError> {
+        Ok(ChainId(felt_as_ascii(felt)?))
+    }
+
+    fn known_chain(&self) -> Option<KnownChain> {
+        KnownChain::ALL.into_iter().find(|known_chain| &known_chain.chain_id() == self)
+    }
+}
+
+/// Returns the felt whose big-endian bytes are the characters of an ASCII string (a Cairo short
+/// string). Fails if the string isn't ASCII or is too long to fit in a felt.
+pub fn ascii_as_felt(ascii_str: &str) -> Result<StarkFelt, StarknetApiError> {
+    if !ascii_str.is_ascii() || ascii_str.len() > MAX_ASCII_FELT_LENGTH {
+        return Err(StarknetApiError::OutOfRange { string: ascii_str.to_owned() });
+    }
+    StarkFelt::try_from(hex::encode(ascii_str).as_str())
+}
+
+/// Returns the ASCII string whose characters are the big-endian bytes of a felt, without its
+/// leading zero bytes. Fails if one of the bytes isn't an ASCII character.
+pub fn felt_as_ascii(felt: &StarkFelt) -> Result<String, StarknetApiError> {
+    let bytes: Vec<u8> = felt.bytes().iter().copied().skip_while(|byte| *byte == 0).collect();
+    if !bytes.is_ascii() {
+        return Err(StarknetApiError::OutOfRange { string: format!("{felt}") });
+    }
+    // ASCII bytes are valid UTF-8.
+    Ok(bytes.into_iter().map(char::from).collect())
+}
+
+/// Custom config validation of a chain id: it must be representable as a felt.
+pub fn validate_chain_id(chain_id: &ChainId) -> Result<(), ValidationError> {
+    if chain_id.to_felt().is_err() {
+        let mut error = ValidationError::new("invalid chain id");
+        error.message =
+            Some("The chain id should be an ASCII string of at most 31 characters.".into());
+        return Err(error);
+    }
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use pretty_assertions::assert_eq;
+use starknet_api::core::ChainId;
+use starknet_api::hash::StarkFelt;
+
+use crate::chain_id::{ascii_as_felt, validate_chain_id, ChainIdExt, KnownChain};
+
+#[test]
+fn test_ascii_as_felt() {
+    let sn_main_id = ChainId("SN_MAIN".to_owned());
+    let sn_main_felt = ascii_as_felt(sn_main_id.0.as_str()).unwrap();
+    // This is the result of the Python snippet from the Chain-Id documentation.
+    let expected_sn_main = StarkFelt::from(23448594291968334_u128);
+    assert_eq!(sn_main_felt, expected_sn_main);
+    assert_eq!(sn_main_id.to_felt().unwrap(), expected_sn_main);
+
+    assert!(ascii_as_felt("ÿ").is_err());
+    assert!(ascii_as_felt(&"a".repeat(32)).is_err());
+}
+
+#[test]
+fn chain_id_felt_round_trip() {
+    for known_chain in KnownChain::ALL {
+        let chain_id = known_chain.chain_id();
+        let felt = chain_id.to_felt().unwrap();
+        assert_eq!(ChainId::from_felt(&felt).unwrap(), chain_id);
+    }
+    let custom_chain_id = ChainId("MY_APP_CHAIN".to_owned());
+    assert_eq!(ChainId::from_felt(&custom_chain_id.to_felt().unwrap()).unwrap(), custom_chain_id);
+
+    // A felt whose bytes aren't ASCII.
+    assert!(ChainId::from_felt(&StarkFelt::from(u128::MAX)).is_err());
+}
+
+#[test]
+fn known_chains() {
+    assert_eq!(ChainId("SN_MAIN".to_owned()).known_chain(), Some(KnownChain::Mainnet));
+    assert_eq!(ChainId("SN_SEPOLIA".to_owned()).known_chain(), Some(KnownChain::Sepolia));
+    assert_eq!(ChainId("MY_APP_CHAIN".to_owned()).known_chain(), None);
+    assert!(ChainId("SN_MAIN".to_owned()).is_mainnet());
+    assert!(!ChainId("SN_SEPOLIA".to_owned()).is_mainnet());
+}
+
+#[test]
+fn chain_id_validation() {
+    assert!(validate_chain_id(&ChainId("SN_MAIN".to_owned())).is_ok());
+    assert!(validate_chain_id(&ChainId("a".repeat(32))).is_err());
+}
@@ -0,0 +1,102 @@
+//! The cache of the classes that the node keeps in memory.
+//!
+//! The storage reader, the sync and the execution all keep classes in memory, each in its own
+//! representation: the decompressed classes read from the storage, the classes downloaded from the
+//! central source and the compiled classes converted for the execution. They share a single cache,
+//! so that one configuration bounds the number of classes and the memory they take together. A
+//! class hash determines its class, so a cached class is valid in every state in which the class is
+//! declared.
+#[cfg(test)]
+#[path = "class_cache_test.rs"]
+mod class_cache_test;
+
+use std::any::{Any, TypeId};
+use std::collections::HashSet;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use starknet_api::core::ClassHash;
+
+use crate::memory_budget::{MemoryBoundedLruCache, MemorySize};
+
+// The name of the cache in its metrics.
+const CACHE_NAME: &str = "classes";
+
+// A class is cached once for each of its representations, identified by their types.
+type CacheKey = (ClassHash, TypeId);
+type CachedValue = Arc<dyn Any + Send + Sync>;
+
+/// The classes that were recently used, in any of their representations.
+#[derive(Debug, Default)]
+pub struct ClassCache {
+    // None if the cache is disabled.
+    entries: Option<Mutex<MemoryBoundedLruCache<CacheKey, CachedValue>>>,
+}
+
+impl ClassCache {
+    /// Creates a cache that holds up to `max_entries` classes, whose memory is up to `max_bytes`.
+    /// A `max_entries` of 0 disables the cache.
+    pub fn new(max_entries: usize, max_bytes: Option<usize>) -> Self {
+        let entries = NonZeroUsize::new(max_entries).map(|max_entries| {
+            Mutex::new(MemoryBoundedLruCache::new(CACHE_NAME, max_entries, max_bytes))
+        });
+        Self { entries }
+    }
+
+    /// Returns the cached representation of the class, if any.
+    pub fn get<V: Any + Send + Sync>(&self, class_hash: &ClassHash) -> Option<Arc<V>> {
+        let value = self.lock()?.get(&(*class_hash, TypeId::of::<V>()))?.clone();
+        Some(value.downcast().expect("A cached value should have the type of its key."))
+    }
+
+    /// Caches a representation of the class whose estimated memory is `memory_size`.
+    pub fn insert<V: Any + Send + Sync>(
+        &self,
+        class_hash: ClassHash,
+        value: Arc<V>,
+        memory_size: usize,
+    ) {
+        if let Some(mut entries) = self.lock() {
+            entries.put((class_hash, TypeId::of::<V>()), value, memory_size);
+        }
+    }
+
+    /// Returns the cached representation of the class, or reads it and caches it. The cache isn't
+    /// locked while reading, so that reads of different classes don't wait for each other.
+    pub fn get_or_read<V: MemorySize + Any + Send + Sync, E>(
+        &self,
+        class_hash: &ClassHash,
+        read: impl FnOnce() -> Result<V, E>,
+    ) -> Result<Arc<V>, E> {
+        if let Some(value) = self.get(class_hash) {
+            return Ok(value);
+        }
+        let value = read()?;
+        let memory_size = value.memory_size();
+        let value = Arc::new(value);
+        self.insert(*class_hash, value.clone(), memory_size);
+        Ok(value)
+    }
+
+    /// Removes every representation of the classes, e.g. after their declarations were reverted.
+    pub fn remove<'a>(&self, class_hashes: impl IntoIterator<Item = &'a ClassHash>) {
+        let Some(mut entries) = self.lock() else {
+            return;
+        };
+        let class_hashes: HashSet<&ClassHash> = class_hashes.into_iter().collect();
+        entries.remove_where(|(class_hash, _)| class_hashes.contains(class_hash));
+    }
+
+    /// Changes the memory bound of the cache, evicting classes if needed.
+    pub fn set_max_bytes(&self, max_bytes: Option<usize>) {
+        if let Some(mut entries) = self.lock() {
+            entries.set_max_bytes(max_bytes);
+        }
+    }
+
+    fn lock(&self) -> Option<MutexGuard<'_, MemoryBoundedLruCache<CacheKey, CachedValue>>> {
+        self.entries
+            .as_ref()
+            .map(|entries| entries.lock().expect("Class cache lock should not be poisoned."))
+    }
+}
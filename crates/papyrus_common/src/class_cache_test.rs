@@ -0,0 +1,67 @@
+use std::sync::Arc;
+
+use pretty_assertions::assert_eq;
+use starknet_api::core::ClassHash;
+use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
+use starknet_api::hash::StarkHash;
+use starknet_api::state::ContractClass;
+
+use crate::class_cache::ClassCache;
+use crate::memory_budget::MemorySize;
+
+const CLASS_HASH: ClassHash = ClassHash(StarkHash::ZERO);
+
+#[test]
+fn cached_class_is_read_once() {
+    let cache = ClassCache::new(10, None);
+    let mut reads = 0;
+    for _ in 0..2 {
+        let class = cache
+            .get_or_read(&CLASS_HASH, || {
+                reads += 1;
+                Ok::<_, ()>(DeprecatedContractClass::default())
+            })
+            .unwrap();
+        assert_eq!(*class, DeprecatedContractClass::default());
+    }
+    assert_eq!(reads, 1);
+}
+
+#[test]
+fn representations_are_cached_separately_and_removed_together() {
+    let cache = ClassCache::new(10, None);
+    let class = Arc::new(ContractClass { abi: "abi".to_owned(), ..Default::default() });
+    cache.insert(CLASS_HASH, class.clone(), class.memory_size());
+    cache.insert(CLASS_HASH, Arc::new(7_u8), 1);
+    assert_eq!(cache.get::<ContractClass>(&CLASS_HASH), Some(class));
+    assert_eq!(cache.get::<u8>(&CLASS_HASH), Some(Arc::new(7)));
+    assert_eq!(cache.get::<DeprecatedContractClass>(&CLASS_HASH), None);
+
+    cache.remove([&CLASS_HASH]);
+    assert_eq!(cache.get::<ContractClass>(&CLASS_HASH), None);
+    assert_eq!(cache.get::<u8>(&CLASS_HASH), None);
+}
+
+#[test]
+fn representations_share_the_memory_budget() {
+    let cache = ClassCache::new(10, Some(3));
+    cache.insert(CLASS_HASH, Arc::new(1_u8), 2);
+    cache.insert(CLASS_HASH, Arc::new(2_u16), 2);
+    assert_eq!(cache.get::<u8>(&CLASS_HASH), None);
+    assert_eq!(cache.get::<u16>(&CLASS_HASH), Some(Arc::new(2)));
+}
+
+#[test]
+fn disabled_cache_always_reads() {
+    let cache = ClassCache::new(0, None);
+    let mut reads = 0;
+    for _ in 0..2 {
+        cache
+            .get_or_read(&CLASS_HASH, || {
+                reads += 1;
+                Ok::<_, ()>(ContractClass::default())
+            })
+            .unwrap();
+    }
+    assert_eq!(reads, 2);
+}
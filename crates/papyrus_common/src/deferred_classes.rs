@@ -0,0 +1,59 @@
+use starknet_api::core::ClassHash;
+use tokio::sync::{broadcast, mpsc, Mutex};
+
+// The number of stored class notifications kept for requesters that didn't receive them yet.
+const STORED_CLASSES_CAPACITY: usize = 1000;
+
+/// Requests for the definitions of classes whose download the sync deferred, shared between the
+/// RPC server, which requests a class the first time it's needed, and the sync, which downloads
+/// and stores the requested classes.
+#[derive(Debug)]
+pub struct DeferredClassRequests {
+    request_sender: mpsc::UnboundedSender<ClassHash>,
+    request_receiver: Mutex<mpsc::UnboundedReceiver<ClassHash>>,
+    stored_sender: broadcast::Sender<ClassHash>,
+}
+
+impl Default for DeferredClassRequests {
+    fn default() -> Self {
+        let (request_sender, request_receiver) = mpsc::unbounded_channel();
+        let (stored_sender, _) = broadcast::channel(STORED_CLASSES_CAPACITY);
+        Self { request_sender, request_receiver: Mutex::new(request_receiver), stored_sender }
+    }
+}
+
+impl DeferredClassRequests {
+    /// Requests the class and resolves once the sync notifies that it's stored. Never resolves if
+    /// no sync handles the requests, so callers should bound the wait.
+    pub async fn request_class(&self, class_hash: ClassHash) {
+        // Subscribing before requesting, so that the notification can't be missed.
+        let mut stored_receiver = self.stored_sender.subscribe();
+        // Sending can't fail since the receiver is owned by self.
+        let _ = self.request_sender.send(class_hash);
+        loop {
+            match stored_receiver.recv().await {
+                Ok(stored_class_hash) if stored_class_hash == class_hash => return,
+                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                // Can't happen since the sender is owned by self.
+                Err(broadcast::error::RecvError::Closed) => return,
+            }
+        }
+    }
+
+    /// Returns the next requested class. A class may be requested more than once.
+    pub async fn next_request(&self) -> ClassHash {
+        self.request_receiver
+            .lock()
+            .await
+            .recv()
+            .await
+            .expect("The request sender should be owned by the requests.")
+    }
+
+    /// Notifies the requesters of the class that it's stored, or that it doesn't need to be
+    /// downloaded.
+    pub fn notify_stored(&self, class_hash: ClassHash) {
+        // Sending fails only when nobody waits for a class.
+        let _ = self.stored_sender.send(class_hash);
+    }
+}
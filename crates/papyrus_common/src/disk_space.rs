@@ -0,0 +1,20 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// A signal shared between the disk space monitor, which checks the free space on the storage
+/// volume, and the sync, which stops writing to the storage while the space is running out.
+#[derive(Debug, Default)]
+pub struct DiskSpaceStatus {
+    low: AtomicBool,
+}
+
+impl DiskSpaceStatus {
+    /// Sets whether the free space is too low to keep writing to the storage.
+    pub fn set_low(&self, low: bool) {
+        self.low.store(low, Ordering::Relaxed);
+    }
+
+    /// Returns true if the free space is too low to keep writing to the storage.
+    pub fn is_low(&self) -> bool {
+        self.low.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,37 @@
+use std::collections::BTreeSet;
+use std::sync::RwLock;
+
+/// The addresses on which other peers can dial the node, shared between the P2P network, which
+/// detects them, and the monitoring gateway, which presents them.
+#[derive(Debug, Default)]
+pub struct ExternalAddresses {
+    addresses: RwLock<BTreeSet<String>>,
+}
+
+impl ExternalAddresses {
+    /// Adds an address that was confirmed to be reachable from outside.
+    pub fn add(&self, address: String) {
+        self.addresses
+            .write()
+            .expect("ExternalAddresses lock should not be poisoned")
+            .insert(address);
+    }
+
+    /// Removes an address that is no longer reachable from outside.
+    pub fn remove(&self, address: &str) {
+        self.addresses
+            .write()
+            .expect("ExternalAddresses lock should not be poisoned")
+            .remove(address);
+    }
+
+    /// Returns the external addresses, sorted.
+    pub fn get(&self) -> Vec<String> {
+        self.addresses
+            .read()
+            .expect("ExternalAddresses lock should not be poisoned")
+            .iter()
+            .cloned()
+            .collect()
+    }
+}
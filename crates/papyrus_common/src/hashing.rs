@@ -0,0 +1,34 @@
+#[cfg(test)]
+#[path = "hashing_test.rs"]
+mod hashing_test;
+
+use sha3::{Digest, Keccak256};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_crypto::{pedersen_hash, FieldElement};
+
+/// Returns the pedersen hash of an array of felts: the felts are hashed one after the other
+/// starting from zero, and the result is hashed with the length of the array.
+pub fn pedersen_hash_array(felts: &[StarkFelt]) -> StarkHash {
+    let current_hash = felts.iter().fold(FieldElement::ZERO, |current_hash, felt| {
+        pedersen_hash(&current_hash, &FieldElement::from(*felt))
+    });
+    let n_elements = FieldElement::from(felts.len());
+    pedersen_hash(&current_hash, &n_elements).into()
+}
+
+/// Returns the poseidon hash of many felts.
+pub fn poseidon_hash_many(felts: &[StarkFelt]) -> StarkHash {
+    let felts: Vec<FieldElement> = felts.iter().map(|felt| FieldElement::from(*felt)).collect();
+    starknet_crypto::poseidon_hash_many(&felts).into()
+}
+
+/// Returns the Starknet keccak of the data: the first 250 bits of its Keccak256 hash. Selectors are
+/// the Starknet keccak of the names of their functions.
+pub fn starknet_keccak(data: &[u8]) -> StarkFelt {
+    let mut keccak = Keccak256::default();
+    keccak.update(data);
+    let mut bytes: [u8; 32] = keccak.finalize().into();
+    // Discard the six MSBs.
+    bytes[0] &= 0b00000011_u8;
+    StarkFelt::new(bytes).expect("A 250 bits number should be a valid felt.")
+}
@@ -0,0 +1,37 @@
+use pretty_assertions::assert_eq;
+use starknet_api::hash::{pedersen_hash, poseidon_hash_array, StarkFelt};
+use starknet_api::stark_felt;
+
+use crate::hashing::{pedersen_hash_array, poseidon_hash_many, starknet_keccak};
+
+#[test]
+fn pedersen_hash_array_hashes_the_length() {
+    let felts = [stark_felt!("0x1"), stark_felt!("0x2"), stark_felt!("0x3")];
+    let expected = pedersen_hash(
+        &pedersen_hash(
+            &pedersen_hash(&pedersen_hash(&StarkFelt::ZERO, &felts[0]), &felts[1]),
+            &felts[2],
+        ),
+        &stark_felt!("0x3"),
+    );
+    assert_eq!(pedersen_hash_array(&felts), expected);
+    assert_eq!(pedersen_hash_array(&[]), pedersen_hash(&StarkFelt::ZERO, &StarkFelt::ZERO));
+}
+
+#[test]
+fn poseidon_hash_many_matches_starknet_api() {
+    let felts = [stark_felt!("0x1"), stark_felt!("0x2"), stark_felt!("0x3")];
+    assert_eq!(poseidon_hash_many(&felts), poseidon_hash_array(&felts).0);
+}
+
+#[test]
+fn starknet_keccak_of_selectors() {
+    assert_eq!(
+        starknet_keccak(b"transfer"),
+        stark_felt!("0x83afd3f4caedc6eebf44246fe54e38c95e3179a5ec9ea81740eca5b482d12e")
+    );
+    assert_eq!(
+        starknet_keccak(b"constructor"),
+        stark_felt!("0x28ffe4ff0f226a9107253e17a904099aa4f63a02a5621de0576e5aa71bc5194")
+    );
+}
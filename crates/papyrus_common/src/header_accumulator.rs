@@ -0,0 +1,141 @@
+//! A Merkle mountain range (MMR) over the hashes of the blocks, ordered by block number.
+//!
+//! The accumulator is a list of perfect binary Merkle trees, one for each set bit of the number of
+//! leaves, from the highest to the lowest. Appending a leaf only adds the leaf and the parents it
+//! completes, so the accumulator can be maintained as the chain grows, and a block is proven to be
+//! an ancestor of the head with a logarithmic number of hashes. The root of the accumulator is the
+//! poseidon hash of the roots of its trees (its peaks), and the parent of two nodes is their
+//! poseidon hash.
+#[cfg(test)]
+#[path = "header_accumulator_test.rs"]
+mod header_accumulator_test;
+
+use serde::{Deserialize, Serialize};
+use starknet_api::hash::StarkFelt;
+
+use crate::hashing::poseidon_hash_many;
+
+/// The id of a node in the accumulator: its height above the leaves and its index among the nodes
+/// of that height. The leaf of a block has height 0 and the block number as index.
+pub type NodeId = (u8, u64);
+
+/// A proof that a leaf is in the accumulator with the given number of leaves.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct AccumulatorProof {
+    /// The number of leaves in the accumulator the leaf is proven against.
+    pub leaf_count: u64,
+    /// The siblings of the nodes on the path from the leaf to the peak of its tree, from the
+    /// bottom up.
+    pub siblings: Vec<StarkFelt>,
+    /// The peaks of the accumulator, from the highest tree to the lowest.
+    pub peaks: Vec<StarkFelt>,
+}
+
+impl AccumulatorProof {
+    /// Returns the root of the accumulator the proof is against.
+    pub fn root(&self) -> StarkFelt {
+        bag_peaks(&self.peaks)
+    }
+
+    /// Returns whether the proof shows that the leaf with the given index is in the accumulator
+    /// with the given root.
+    pub fn verify(&self, leaf_index: u64, leaf: StarkFelt, root: StarkFelt) -> bool {
+        let Some((peak_position, height)) = peak_of_leaf(leaf_index, self.leaf_count) else {
+            return false;
+        };
+        if self.peaks.len() != self.leaf_count.count_ones() as usize
+            || self.siblings.len() != usize::from(height)
+        {
+            return false;
+        }
+        let mut node = leaf;
+        for (level, sibling) in self.siblings.iter().enumerate() {
+            node = if (leaf_index >> level) & 1 == 0 {
+                hash_nodes(&node, sibling)
+            } else {
+                hash_nodes(sibling, &node)
+            };
+        }
+        node == self.peaks[peak_position] && self.root() == root
+    }
+}
+
+/// Returns the parent of two nodes.
+pub fn hash_nodes(left: &StarkFelt, right: &StarkFelt) -> StarkFelt {
+    poseidon_hash_many(&[*left, *right])
+}
+
+/// Returns the root of the accumulator with the given peaks.
+pub fn bag_peaks(peaks: &[StarkFelt]) -> StarkFelt {
+    poseidon_hash_many(peaks)
+}
+
+/// Returns the nodes that are added to the accumulator when the leaf with the given index is
+/// appended: the leaf itself and the parents it completes. `get_node` is called with the ids of
+/// nodes that were added before.
+pub fn appended_nodes<E>(
+    leaf_index: u64,
+    leaf: StarkFelt,
+    mut get_node: impl FnMut(NodeId) -> Result<StarkFelt, E>,
+) -> Result<Vec<(NodeId, StarkFelt)>, E> {
+    let mut nodes = vec![((0, leaf_index), leaf)];
+    let mut node = leaf;
+    for height in 0..completed_parents(leaf_index) {
+        let index = leaf_index >> height;
+        let left_sibling = get_node((height, index - 1))?;
+        node = hash_nodes(&left_sibling, &node);
+        nodes.push(((height + 1, index >> 1), node));
+    }
+    Ok(nodes)
+}
+
+/// Returns the ids of the nodes that were added to the accumulator when the leaf with the given
+/// index was appended, and should be removed when it's reverted.
+pub fn appended_node_ids(leaf_index: u64) -> Vec<NodeId> {
+    (0..=completed_parents(leaf_index)).map(|height| (height, leaf_index >> height)).collect()
+}
+
+/// Returns the ids of the peaks of the accumulator with the given number of leaves, from the
+/// highest tree to the lowest.
+pub fn peak_ids(leaf_count: u64) -> Vec<NodeId> {
+    let mut peaks = vec![];
+    let mut leaves_before_peak = 0;
+    for height in (0..u64::BITS as u8).rev() {
+        if leaf_count & (1 << height) != 0 {
+            peaks.push((height, leaves_before_peak >> height));
+            leaves_before_peak += 1 << height;
+        }
+    }
+    peaks
+}
+
+/// Returns the ids of the siblings needed to prove the leaf with the given index against the
+/// accumulator with the given number of leaves, from the bottom up. Returns None if the leaf isn't
+/// in the accumulator.
+pub fn sibling_ids(leaf_index: u64, leaf_count: u64) -> Option<Vec<NodeId>> {
+    let (_, height) = peak_of_leaf(leaf_index, leaf_count)?;
+    Some((0..height).map(|level| (level, (leaf_index >> level) ^ 1)).collect())
+}
+
+// The number of parents that the leaf with the given index completes when it's appended. A parent
+// is completed by its right child, and the nodes on the path of a leaf are right children as long
+// as the bits of its index are set.
+fn completed_parents(leaf_index: u64) -> u8 {
+    leaf_index.trailing_ones() as u8
+}
+
+// Returns the position among the peaks of the peak of the tree that contains the leaf, and the
+// height of that tree.
+fn peak_of_leaf(leaf_index: u64, leaf_count: u64) -> Option<(usize, u8)> {
+    if leaf_index >= leaf_count {
+        return None;
+    }
+    let mut leaves_before_peak = 0;
+    for (position, (height, _)) in peak_ids(leaf_count).into_iter().enumerate() {
+        leaves_before_peak += 1 << height;
+        if leaf_index < leaves_before_peak {
+            return Some((position, height));
+        }
+    }
+    unreachable!("The peaks cover all the leaves.")
+}
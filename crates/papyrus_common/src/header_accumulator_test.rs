@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+
+use pretty_assertions::assert_eq;
+use starknet_api::hash::StarkFelt;
+
+use crate::header_accumulator::{
+    appended_node_ids,
+    appended_nodes,
+    hash_nodes,
+    peak_ids,
+    sibling_ids,
+    AccumulatorProof,
+    NodeId,
+};
+
+fn leaf(leaf_index: u64) -> StarkFelt {
+    StarkFelt::from(leaf_index + 100)
+}
+
+// Returns the nodes of an accumulator with the given number of leaves.
+fn accumulator(leaf_count: u64) -> HashMap<NodeId, StarkFelt> {
+    let mut nodes = HashMap::new();
+    for leaf_index in 0..leaf_count {
+        let appended = appended_nodes(leaf_index, leaf(leaf_index), |node_id| {
+            nodes.get(&node_id).copied().ok_or(node_id)
+        })
+        .unwrap();
+        nodes.extend(appended);
+    }
+    nodes
+}
+
+fn proof(nodes: &HashMap<NodeId, StarkFelt>, leaf_index: u64, leaf_count: u64) -> AccumulatorProof {
+    AccumulatorProof {
+        leaf_count,
+        siblings: sibling_ids(leaf_index, leaf_count)
+            .unwrap()
+            .into_iter()
+            .map(|node_id| nodes[&node_id])
+            .collect(),
+        peaks: peak_ids(leaf_count).into_iter().map(|node_id| nodes[&node_id]).collect(),
+    }
+}
+
+#[test]
+fn appended_nodes_complete_parents() {
+    let nodes = accumulator(4);
+    assert_eq!(nodes.len(), 7);
+    let left = hash_nodes(&leaf(0), &leaf(1));
+    let right = hash_nodes(&leaf(2), &leaf(3));
+    assert_eq!(nodes[&(1, 0)], left);
+    assert_eq!(nodes[&(1, 1)], right);
+    assert_eq!(nodes[&(2, 0)], hash_nodes(&left, &right));
+
+    assert_eq!(appended_node_ids(2), vec![(0, 2)]);
+    assert_eq!(appended_node_ids(3), vec![(0, 3), (1, 1), (2, 0)]);
+}
+
+#[test]
+fn peaks() {
+    assert_eq!(peak_ids(0), vec![]);
+    assert_eq!(peak_ids(1), vec![(0, 0)]);
+    assert_eq!(peak_ids(7), vec![(2, 0), (1, 2), (0, 6)]);
+    assert_eq!(peak_ids(8), vec![(3, 0)]);
+}
+
+#[test]
+fn every_leaf_is_proven_against_every_accumulator_that_contains_it() {
+    const MAX_LEAF_COUNT: u64 = 20;
+    let nodes = accumulator(MAX_LEAF_COUNT);
+    for leaf_count in 1..=MAX_LEAF_COUNT {
+        // An accumulator's nodes don't change when leaves are appended after it.
+        let root = proof(&nodes, 0, leaf_count).root();
+        for leaf_index in 0..leaf_count {
+            let proof = proof(&nodes, leaf_index, leaf_count);
+            assert!(proof.verify(leaf_index, leaf(leaf_index), root));
+            assert!(!proof.verify(leaf_index, leaf(leaf_index + 1), root));
+        }
+        assert_eq!(sibling_ids(leaf_count, leaf_count), None);
+    }
+}
+
+#[test]
+fn tampered_proof_fails() {
+    let nodes = accumulator(6);
+    let proof = proof(&nodes, 2, 6);
+    let root = proof.root();
+
+    let mut wrong_sibling = proof.clone();
+    wrong_sibling.siblings[0] = StarkFelt::from(1_u8);
+    assert!(!wrong_sibling.verify(2, leaf(2), root));
+
+    let mut missing_peak = proof.clone();
+    missing_peak.peaks.pop();
+    assert!(!missing_peak.verify(2, leaf(2), root));
+
+    // The leaf is proven at its own index only.
+    assert!(!proof.verify(3, leaf(2), root));
+    assert!(!proof.verify(6, leaf(2), root));
+    assert!(!proof.verify(2, leaf(2), StarkFelt::from(1_u8)));
+}
@@ -1,15 +1,41 @@
 use serde::{Deserialize, Serialize};
 use starknet_api::block::{BlockHash, BlockNumber};
 
+pub mod block_execution_resources;
 pub mod block_hash;
+pub mod block_signature;
+pub mod chain_id;
+pub mod class_cache;
+pub mod deferred_classes;
 pub mod deprecated_class_abi;
+pub mod disk_space;
+pub mod external_addresses;
+pub mod hashing;
+pub mod header_accumulator;
+pub mod maintenance_requests;
+pub mod memory_budget;
 pub mod metrics;
+pub mod outbound_connection;
 pub mod patricia_hash_tree;
 pub mod pending_classes;
+#[cfg(feature = "protobuf")]
+pub mod protobuf_conversion;
+pub mod recent_state;
+pub mod rpc_load;
+pub mod source_reports;
 pub mod state;
 pub mod state_diff_commitment;
+pub mod sync_notifications;
 pub mod transaction_hash;
 
+/// The messages of the [`Starknet p2p specs`], generated from the protobuf schemas in `src/proto`.
+///
+/// [`Starknet p2p specs`]: https://github.com/starknet-io/starknet-p2p-specs/
+#[cfg(feature = "protobuf")]
+pub mod protobuf {
+    include!(concat!(env!("OUT_DIR"), "/_.rs"));
+}
+
 #[derive(Copy, Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub struct BlockHashAndNumber {
     pub block_hash: BlockHash,
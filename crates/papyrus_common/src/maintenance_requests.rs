@@ -0,0 +1,50 @@
+use starknet_api::block::BlockNumber;
+use tokio::sync::{mpsc, oneshot, Mutex};
+
+/// A request to prune the history of the state below a block, so that the state right before it
+/// becomes the oldest queryable state.
+#[derive(Debug)]
+pub struct PruneRequest {
+    /// The block below which the state history is pruned.
+    pub below: BlockNumber,
+    /// Receives the result of the pruning, or the error that failed it.
+    pub response: oneshot::Sender<Result<(), String>>,
+}
+
+/// Requests of maintenance tasks that write to the storage, shared between the component that
+/// receives them from the operators but can't write to the storage, i.e. the monitoring gateway,
+/// and the component that owns the storage writer and runs them.
+#[derive(Debug)]
+pub struct MaintenanceRequests {
+    request_sender: mpsc::UnboundedSender<PruneRequest>,
+    request_receiver: Mutex<mpsc::UnboundedReceiver<PruneRequest>>,
+}
+
+impl Default for MaintenanceRequests {
+    fn default() -> Self {
+        let (request_sender, request_receiver) = mpsc::unbounded_channel();
+        Self { request_sender, request_receiver: Mutex::new(request_receiver) }
+    }
+}
+
+impl MaintenanceRequests {
+    /// Requests to prune the state history below the block, and waits until it's pruned.
+    pub async fn prune_state_history(&self, below: BlockNumber) -> Result<(), String> {
+        let (response, response_receiver) = oneshot::channel();
+        // Sending can't fail since the receiver is owned by self.
+        let _ = self.request_sender.send(PruneRequest { below, response });
+        response_receiver
+            .await
+            .map_err(|_| "The storage writer stopped before handling the request.".to_owned())?
+    }
+
+    /// Returns the next request to handle.
+    pub async fn next_request(&self) -> PruneRequest {
+        self.request_receiver
+            .lock()
+            .await
+            .recv()
+            .await
+            .expect("The request sender should be owned by the requests.")
+    }
+}
@@ -0,0 +1,223 @@
+//! Bounds on the memory of the in-memory caches of the node, so that the node fits in the memory
+//! of small machines.
+#[cfg(test)]
+#[path = "memory_budget_test.rs"]
+mod memory_budget_test;
+
+use std::hash::Hash;
+use std::mem::{size_of, size_of_val};
+use std::num::NonZeroUsize;
+
+use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
+use lru::LruCache;
+use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::ContractClass;
+
+use crate::metrics::{PAPYRUS_CACHE_HITS, PAPYRUS_CACHE_MEMORY_USAGE, PAPYRUS_CACHE_MISSES};
+
+/// The memory, in bytes, that each kind of cache of the node may take. None means that the caches
+/// are bounded only by their number of entries.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct MemoryBudget {
+    /// The state writes of the recent blocks that the RPC server answers from.
+    pub recent_state: Option<usize>,
+    /// The class cache shared by the storage, the sync and the execution.
+    pub class_caches: Option<usize>,
+    /// The classes of the pending block.
+    pub pending_data: Option<usize>,
+}
+
+/// An estimation of the memory, in bytes, that a value takes, computed from the lengths of its
+/// contents rather than by serializing it.
+pub trait MemorySize {
+    fn memory_size(&self) -> usize;
+}
+
+impl MemorySize for ContractClass {
+    fn memory_size(&self) -> usize {
+        size_of_val(self.sierra_program.as_slice())
+            + self
+                .entry_point_by_type
+                .values()
+                .map(|entry_points| size_of_val(entry_points.as_slice()))
+                .sum::<usize>()
+            + self.abi.len()
+    }
+}
+
+impl MemorySize for DeprecatedContractClass {
+    fn memory_size(&self) -> usize {
+        let program = &self.program;
+        [
+            &program.attributes,
+            &program.builtins,
+            &program.compiler_version,
+            &program.data,
+            &program.debug_info,
+            &program.hints,
+            &program.identifiers,
+            &program.main_scope,
+            &program.prime,
+            &program.reference_manager,
+        ]
+        .into_iter()
+        .map(json_memory_size)
+        .sum::<usize>()
+            + self.abi.as_ref().map_or(0, |abi| size_of_val(abi.as_slice()))
+            + self
+                .entry_points_by_type
+                .values()
+                .map(|entry_points| size_of_val(entry_points.as_slice()))
+                .sum::<usize>()
+    }
+}
+
+impl MemorySize for CasmContractClass {
+    fn memory_size(&self) -> usize {
+        let entry_points = &self.entry_points_by_type;
+        // The digits of each bytecode word are on the heap.
+        size_of_val(self.bytecode.as_slice())
+            + self.bytecode.len() * size_of::<StarkFelt>()
+            + self
+                .hints
+                .iter()
+                .map(|(_, hints)| size_of::<usize>() + size_of_val(hints.as_slice()))
+                .sum::<usize>()
+            + self
+                .pythonic_hints
+                .iter()
+                .flatten()
+                .flat_map(|(_, hints)| hints)
+                .map(String::len)
+                .sum::<usize>()
+            + [&entry_points.external, &entry_points.l1_handler, &entry_points.constructor]
+                .into_iter()
+                .map(|entry_points| {
+                    size_of_val(entry_points.as_slice())
+                        + entry_points
+                            .iter()
+                            .flat_map(|entry_point| &entry_point.builtins)
+                            .map(String::len)
+                            .sum::<usize>()
+                })
+                .sum::<usize>()
+    }
+}
+
+// The programs of deprecated classes are kept as JSON values.
+fn json_memory_size(value: &serde_json::Value) -> usize {
+    size_of::<serde_json::Value>()
+        + match value {
+            serde_json::Value::String(string) => string.len(),
+            serde_json::Value::Array(values) => values.iter().map(json_memory_size).sum(),
+            serde_json::Value::Object(map) => {
+                map.iter().map(|(key, value)| key.len() + json_memory_size(value)).sum()
+            }
+            serde_json::Value::Null | serde_json::Value::Bool(_) | serde_json::Value::Number(_) => {
+                0
+            }
+        }
+}
+
+/// An LRU cache that is bounded both by its number of entries and by the memory of its values,
+/// and reports its hit rate and its memory usage as metrics labeled with the name of the cache.
+#[derive(Debug)]
+pub struct MemoryBoundedLruCache<K: Hash + Eq, V> {
+    name: &'static str,
+    // The values with their estimated memory size.
+    entries: LruCache<K, (V, usize)>,
+    max_bytes: Option<usize>,
+    used_bytes: usize,
+}
+
+impl<K: Hash + Eq, V> MemoryBoundedLruCache<K, V> {
+    /// Creates a cache that holds up to `max_entries` values, whose memory is up to `max_bytes`.
+    pub fn new(name: &'static str, max_entries: NonZeroUsize, max_bytes: Option<usize>) -> Self {
+        Self { name, entries: LruCache::new(max_entries), max_bytes, used_bytes: 0 }
+    }
+
+    /// Returns the cached value of the key and marks it as the most recently used.
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let value = self.entries.get(key).map(|(value, _)| value);
+        match value {
+            Some(_) => metrics::increment_counter!(PAPYRUS_CACHE_HITS, "cache" => self.name),
+            None => metrics::increment_counter!(PAPYRUS_CACHE_MISSES, "cache" => self.name),
+        }
+        value
+    }
+
+    /// Caches a value whose estimated memory is `memory_size`, evicting the least recently used
+    /// values until the cache is within its bounds. A value that is larger than the whole budget
+    /// isn't cached.
+    pub fn put(&mut self, key: K, value: V, memory_size: usize) {
+        if self.max_bytes.is_some_and(|max_bytes| memory_size > max_bytes) {
+            self.remove(&key);
+            return;
+        }
+        // Replacing the value of an existing key returns it. Otherwise, if the cache is full, the
+        // least recently used value is returned.
+        if let Some((_, (_, removed_size))) = self.entries.push(key, (value, memory_size)) {
+            self.used_bytes -= removed_size;
+        }
+        self.used_bytes += memory_size;
+        self.evict_to_budget();
+    }
+
+    /// Removes the value of the key from the cache.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let (value, memory_size) = self.entries.pop(key)?;
+        self.used_bytes -= memory_size;
+        self.report_usage();
+        Some(value)
+    }
+
+    /// Removes the values of the keys that match the predicate from the cache.
+    pub fn remove_where(&mut self, predicate: impl Fn(&K) -> bool)
+    where
+        K: Clone,
+    {
+        let keys: Vec<K> =
+            self.entries.iter().map(|(key, _)| key).filter(|key| predicate(key)).cloned().collect();
+        for key in keys {
+            self.remove(&key);
+        }
+    }
+
+    /// Changes the memory bound of the cache, evicting values if needed.
+    pub fn set_max_bytes(&mut self, max_bytes: Option<usize>) {
+        self.max_bytes = max_bytes;
+        self.evict_to_budget();
+    }
+
+    /// The estimated memory, in bytes, of the cached values.
+    pub fn used_bytes(&self) -> usize {
+        self.used_bytes
+    }
+
+    /// The number of cached values.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the cache holds no values.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn evict_to_budget(&mut self) {
+        if let Some(max_bytes) = self.max_bytes {
+            while self.used_bytes > max_bytes {
+                let Some((_, (_, removed_size))) = self.entries.pop_lru() else {
+                    break;
+                };
+                self.used_bytes -= removed_size;
+            }
+        }
+        self.report_usage();
+    }
+
+    fn report_usage(&self) {
+        metrics::gauge!(PAPYRUS_CACHE_MEMORY_USAGE, self.used_bytes as f64, "cache" => self.name);
+    }
+}
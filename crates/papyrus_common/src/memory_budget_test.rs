@@ -0,0 +1,93 @@
+use std::mem::size_of;
+use std::num::NonZeroUsize;
+
+use pretty_assertions::assert_eq;
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::ContractClass;
+
+use crate::memory_budget::{MemoryBoundedLruCache, MemorySize};
+
+fn cache(max_entries: usize, max_bytes: Option<usize>) -> MemoryBoundedLruCache<u8, u8> {
+    MemoryBoundedLruCache::new("test", NonZeroUsize::new(max_entries).unwrap(), max_bytes)
+}
+
+#[test]
+fn evicts_least_recently_used_beyond_memory_budget() {
+    let mut cache = cache(10, Some(100));
+    cache.put(1, 1, 40);
+    cache.put(2, 2, 40);
+    // Using the first value makes the second the least recently used.
+    assert_eq!(cache.get(&1), Some(&1));
+    cache.put(3, 3, 40);
+
+    assert_eq!(cache.get(&2), None);
+    assert_eq!(cache.get(&1), Some(&1));
+    assert_eq!(cache.get(&3), Some(&3));
+    assert_eq!(cache.used_bytes(), 80);
+}
+
+#[test]
+fn evicts_beyond_max_entries() {
+    let mut cache = cache(2, None);
+    cache.put(1, 1, 40);
+    cache.put(2, 2, 40);
+    cache.put(3, 3, 40);
+
+    assert_eq!(cache.get(&1), None);
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.used_bytes(), 80);
+}
+
+#[test]
+fn replaced_value_memory_is_released() {
+    let mut cache = cache(10, Some(100));
+    cache.put(1, 1, 40);
+    cache.put(1, 2, 10);
+    assert_eq!(cache.get(&1), Some(&2));
+    assert_eq!(cache.used_bytes(), 10);
+
+    // A value larger than the budget isn't cached, and removes the old value of its key.
+    cache.put(1, 3, 101);
+    assert!(cache.is_empty());
+    assert_eq!(cache.used_bytes(), 0);
+}
+
+#[test]
+fn shrinking_budget_evicts() {
+    let mut cache = cache(10, None);
+    cache.put(1, 1, 40);
+    cache.put(2, 2, 40);
+    cache.set_max_bytes(Some(50));
+
+    assert_eq!(cache.get(&1), None);
+    assert_eq!(cache.get(&2), Some(&2));
+    assert_eq!(cache.used_bytes(), 40);
+}
+
+#[test]
+fn remove_where_removes_matching_keys() {
+    let mut cache = cache(10, None);
+    for key in 1..=4 {
+        cache.put(key, key, 10);
+    }
+    cache.remove_where(|key| key % 2 == 0);
+
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.get(&2), None);
+    assert_eq!(cache.get(&3), Some(&3));
+    assert_eq!(cache.used_bytes(), 20);
+}
+
+#[test]
+fn class_memory_size_grows_with_its_contents() {
+    let class = ContractClass::default();
+    let larger_class = ContractClass {
+        sierra_program: vec![StarkFelt::ONE; 10],
+        abi: "abi".to_owned(),
+        ..Default::default()
+    };
+    assert_eq!(
+        larger_class.memory_size(),
+        class.memory_size() + 10 * size_of::<StarkFelt>() + "abi".len()
+    );
+}
@@ -7,6 +7,10 @@ pub const PAPYRUS_HEADER_MARKER: &str = "papyrus_header_marker";
 /// The body marker is the first block number for which the node does not have a body.
 pub const PAPYRUS_BODY_MARKER: &str = "papyrus_body_marker";
 
+/// The receipts marker is the first block number for which the node does not have the transaction
+/// outputs.
+pub const PAPYRUS_RECEIPTS_MARKER: &str = "papyrus_receipts_marker";
+
 /// The state marker is the first block number for which the node does not have a state body.
 pub const PAPYRUS_STATE_MARKER: &str = "papyrus_state_marker";
 
@@ -21,3 +25,55 @@ pub const PAPYRUS_BASE_LAYER_MARKER: &str = "papyrus_base_layer_marker";
 /// The latency, in seconds, between a block timestamp (as state in its header) and the time the
 /// node stores the header.
 pub const PAPYRUS_HEADER_LATENCY_SEC: &str = "papyrus_header_latency";
+
+/// The free space, in bytes, on the volume of the storage.
+pub const PAPYRUS_STORAGE_FREE_DISK_SPACE: &str = "papyrus_storage_free_disk_space";
+
+/// The number of lookups that found their value in an in-memory cache, labeled by the cache.
+pub const PAPYRUS_CACHE_HITS: &str = "papyrus_cache_hits";
+
+/// The number of lookups that didn't find their value in an in-memory cache, labeled by the cache.
+pub const PAPYRUS_CACHE_MISSES: &str = "papyrus_cache_misses";
+
+/// The estimated memory, in bytes, of the values in an in-memory cache, labeled by the cache.
+pub const PAPYRUS_CACHE_MEMORY_USAGE: &str = "papyrus_cache_memory_usage";
+
+/// The number of historical blocks that were re-validated by the integrity check.
+pub const PAPYRUS_INTEGRITY_CHECKED_BLOCKS: &str = "papyrus_integrity_checked_blocks";
+
+/// The number of discrepancies the integrity check found between the stored data and its
+/// commitments, labeled by the check that failed.
+pub const PAPYRUS_INTEGRITY_DISCREPANCIES: &str = "papyrus_integrity_discrepancies";
+
+/// The number of times the sync was restarted by its supervisor after a recoverable error.
+pub const PAPYRUS_SYNC_RESTARTS: &str = "papyrus_sync_restarts";
+
+/// The number of blocks whose body or state diff the sync quarantined because it failed to be
+/// parsed or validated, labeled by the quarantined part.
+pub const PAPYRUS_QUARANTINED_BLOCKS: &str = "papyrus_quarantined_blocks";
+
+/// The number of peers the P2P network is connected to.
+pub const PAPYRUS_NUM_CONNECTED_PEERS: &str = "papyrus_num_connected_peers";
+
+/// The number of messages sent to peers over the P2P network, labeled by the protocol.
+pub const PAPYRUS_P2P_SENT_MESSAGES: &str = "papyrus_p2p_sent_messages";
+
+/// The number of messages received from peers over the P2P network, labeled by the protocol.
+pub const PAPYRUS_P2P_RECEIVED_MESSAGES: &str = "papyrus_p2p_received_messages";
+
+/// The size, in bytes, of the messages sent to peers over the P2P network, labeled by the protocol.
+pub const PAPYRUS_P2P_SENT_BYTES: &str = "papyrus_p2p_sent_bytes";
+
+/// The size, in bytes, of the messages received from peers over the P2P network, labeled by the
+/// protocol.
+pub const PAPYRUS_P2P_RECEIVED_BYTES: &str = "papyrus_p2p_received_bytes";
+
+/// The number of Cairo steps that the transactions of the last block whose receipts were stored
+/// executed.
+pub const PAPYRUS_BLOCK_STEPS: &str = "papyrus_block_steps";
+
+/// The L1 gas that the transactions of the last block whose receipts were stored consumed.
+pub const PAPYRUS_BLOCK_L1_GAS: &str = "papyrus_block_l1_gas";
+
+/// The number of transactions in the last block whose receipts were stored.
+pub const PAPYRUS_BLOCK_TRANSACTIONS: &str = "papyrus_block_transactions";
@@ -0,0 +1,111 @@
+#[cfg(test)]
+#[path = "outbound_connection_test.rs"]
+mod outbound_connection_test;
+
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Formatter};
+use std::path::{Path, PathBuf};
+
+use papyrus_config::dumping::{ser_optional_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use reqwest::{Certificate, ClientBuilder, Proxy};
+use serde::{Deserialize, Serialize};
+
+const PEM_CERTIFICATE_END: &str = "-----END CERTIFICATE-----";
+// Replaces the proxy URL when the config is formatted with Debug.
+const REDACTED: &str = "<redacted>";
+
+/// Configuration of the outbound HTTP connections of a client, for environments in which the
+/// egress goes through a proxy or the servers' certificates are signed by a private CA.
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct OutboundConnectionConfig {
+    /// The proxy through which all the requests are sent. Its scheme is http, https or socks5.
+    pub proxy_url: Option<String>,
+    /// A PEM file of CA certificates that are trusted in addition to the system's roots.
+    pub ca_certificates_path: Option<PathBuf>,
+}
+
+impl SerializeConfig for OutboundConnectionConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        let mut dump = ser_optional_param(
+            &self.proxy_url,
+            String::from("http://localhost:3128"),
+            "proxy_url",
+            "URL of an http, https or socks5 proxy through which the requests are sent. If not \
+             set, the requests are sent directly.",
+            // The URL may contain the credentials of the proxy.
+            ParamPrivacyInput::Private,
+        );
+        dump.extend(ser_optional_param(
+            &self.ca_certificates_path,
+            PathBuf::from("ca_certificates.pem"),
+            "ca_certificates_path",
+            "Path to a PEM file of CA certificates to trust in addition to the system's root \
+             certificates. If not set, only the system's root certificates are trusted.",
+            ParamPrivacyInput::Public,
+        ));
+        dump
+    }
+}
+
+// The proxy URL may contain credentials, so it isn't written to the logs.
+impl Debug for OutboundConnectionConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OutboundConnectionConfig")
+            .field("proxy_url", &self.proxy_url.as_ref().map(|_| REDACTED))
+            .field("ca_certificates_path", &self.ca_certificates_path)
+            .finish()
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum OutboundConnectionError {
+    #[error("Invalid proxy URL: {0}.")]
+    InvalidProxy(reqwest::Error),
+    #[error("Failed reading the CA certificates file {}: {source}.", path.display())]
+    ReadCaCertificates { path: PathBuf, source: std::io::Error },
+    #[error("Invalid CA certificate in {}: {source}.", path.display())]
+    InvalidCaCertificate { path: PathBuf, source: reqwest::Error },
+    #[error("The CA certificates file {} contains no certificates.", path.display())]
+    NoCaCertificates { path: PathBuf },
+}
+
+impl OutboundConnectionConfig {
+    /// Applies the proxy and the CA certificates to a client that is being built.
+    pub fn configure(
+        &self,
+        mut client_builder: ClientBuilder,
+    ) -> Result<ClientBuilder, OutboundConnectionError> {
+        if let Some(proxy_url) = &self.proxy_url {
+            let proxy = Proxy::all(proxy_url).map_err(OutboundConnectionError::InvalidProxy)?;
+            client_builder = client_builder.proxy(proxy);
+        }
+        if let Some(path) = &self.ca_certificates_path {
+            for certificate in read_ca_certificates(path)? {
+                client_builder = client_builder.add_root_certificate(certificate);
+            }
+        }
+        Ok(client_builder)
+    }
+}
+
+// Returns all the certificates of a PEM bundle.
+fn read_ca_certificates(path: &Path) -> Result<Vec<Certificate>, OutboundConnectionError> {
+    let pem = std::fs::read_to_string(path).map_err(|source| {
+        OutboundConnectionError::ReadCaCertificates { path: path.to_owned(), source }
+    })?;
+    // Certificate::from_pem parses only the first certificate of a bundle.
+    let certificates = pem
+        .split_inclusive(PEM_CERTIFICATE_END)
+        .filter(|block| block.contains(PEM_CERTIFICATE_END))
+        .map(|block| {
+            Certificate::from_pem(block.trim().as_bytes()).map_err(|source| {
+                OutboundConnectionError::InvalidCaCertificate { path: path.to_owned(), source }
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if certificates.is_empty() {
+        return Err(OutboundConnectionError::NoCaCertificates { path: path.to_owned() });
+    }
+    Ok(certificates)
+}
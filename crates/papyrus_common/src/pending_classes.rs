@@ -1,3 +1,7 @@
+#[cfg(test)]
+#[path = "pending_classes_test.rs"]
+mod pending_classes_test;
+
 use std::collections::HashMap;
 use std::sync::Arc;
 
@@ -5,6 +9,13 @@ use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 use starknet_api::core::ClassHash;
 use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
 use starknet_api::state::ContractClass;
+use tracing::warn;
+
+use crate::memory_budget::MemorySize;
+use crate::metrics::PAPYRUS_CACHE_MEMORY_USAGE;
+
+// The name of the classes in the cache metrics.
+const CACHE_NAME: &str = "pending_classes";
 
 pub trait PendingClassesTrait {
     // TODO(shahak) Return an Arc to avoid cloning the class. This requires to re-implement
@@ -28,6 +39,39 @@ pub struct PendingClasses {
     // PendingClasses struct.
     pub classes: HashMap<ClassHash, Arc<ApiContractClass>>,
     pub compiled_classes: HashMap<ClassHash, Arc<CasmContractClass>>,
+    // The memory, in bytes, that the classes may take. None means no bound.
+    max_bytes: Option<usize>,
+    used_bytes: usize,
+}
+
+impl PendingClasses {
+    /// Creates an empty container whose classes should take up to `max_bytes` of memory. The
+    /// classes are part of the pending state, so classes beyond that are still kept, and a warning
+    /// is logged so that the budget can be raised.
+    pub fn new(max_bytes: Option<usize>) -> Self {
+        Self { max_bytes, ..Default::default() }
+    }
+
+    /// The estimated memory, in bytes, of the classes.
+    pub fn used_bytes(&self) -> usize {
+        self.used_bytes
+    }
+
+    // Accounts for the memory of a new class, warning once per pending block when the classes
+    // exceed the budget.
+    fn reserve(&mut self, memory_size: usize) {
+        let used_bytes = self.used_bytes + memory_size;
+        if let Some(max_bytes) = self.max_bytes {
+            if self.used_bytes <= max_bytes && used_bytes > max_bytes {
+                warn!(
+                    "The classes of the pending block take more than their memory budget of \
+                     {max_bytes} bytes."
+                );
+            }
+        }
+        self.used_bytes = used_bytes;
+        metrics::gauge!(PAPYRUS_CACHE_MEMORY_USAGE, used_bytes as f64, "cache" => CACHE_NAME);
+    }
 }
 
 #[derive(Debug, Eq, PartialEq, Clone)]
@@ -52,12 +96,25 @@ impl ApiContractClass {
     }
 }
 
+impl MemorySize for ApiContractClass {
+    fn memory_size(&self) -> usize {
+        match self {
+            Self::DeprecatedContractClass(class) => class.memory_size(),
+            Self::ContractClass(class) => class.memory_size(),
+        }
+    }
+}
+
 impl PendingClassesTrait for PendingClasses {
     fn get_class(&self, class_hash: ClassHash) -> Option<ApiContractClass> {
         self.classes.get(&class_hash).map(|class| (**class).clone())
     }
 
     fn add_class(&mut self, class_hash: ClassHash, class: ApiContractClass) {
+        // A class hash determines its class, so a replaced class takes the same memory.
+        if !self.classes.contains_key(&class_hash) {
+            self.reserve(class.memory_size());
+        }
         self.classes.insert(class_hash, Arc::new(class));
     }
 
@@ -66,11 +123,16 @@ impl PendingClassesTrait for PendingClasses {
     }
 
     fn add_compiled_class(&mut self, class_hash: ClassHash, compiled_class: CasmContractClass) {
+        if !self.compiled_classes.contains_key(&class_hash) {
+            self.reserve(compiled_class.memory_size());
+        }
         self.compiled_classes.insert(class_hash, Arc::new(compiled_class));
     }
 
     fn clear(&mut self) {
         self.classes.clear();
         self.compiled_classes.clear();
+        self.used_bytes = 0;
+        metrics::gauge!(PAPYRUS_CACHE_MEMORY_USAGE, 0.0, "cache" => CACHE_NAME);
     }
 }
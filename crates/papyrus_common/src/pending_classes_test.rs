@@ -0,0 +1,31 @@
+use pretty_assertions::assert_eq;
+use starknet_api::core::ClassHash;
+use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
+use starknet_api::hash::StarkHash;
+
+use crate::memory_budget::MemorySize;
+use crate::pending_classes::{ApiContractClass, PendingClasses, PendingClassesTrait};
+
+#[test]
+fn classes_beyond_memory_budget_are_kept() {
+    let class = DeprecatedContractClass::default();
+    let class_size = class.memory_size();
+    let mut pending_classes = PendingClasses::new(Some(class_size));
+    let first_class_hash = ClassHash(StarkHash::from(1_u8));
+    let second_class_hash = ClassHash(StarkHash::from(2_u8));
+
+    pending_classes
+        .add_class(first_class_hash, ApiContractClass::DeprecatedContractClass(class.clone()));
+    pending_classes
+        .add_class(second_class_hash, ApiContractClass::DeprecatedContractClass(class.clone()));
+    // The classes are part of the pending state, so the budget is only reported.
+    assert!(pending_classes.get_class(first_class_hash).is_some());
+    assert!(pending_classes.get_class(second_class_hash).is_some());
+    assert_eq!(pending_classes.used_bytes(), 2 * class_size);
+
+    // Clearing the classes of the previous pending block releases their memory.
+    pending_classes.clear();
+    pending_classes.add_class(second_class_hash, ApiContractClass::DeprecatedContractClass(class));
+    assert!(pending_classes.get_class(second_class_hash).is_some());
+    assert_eq!(pending_classes.used_bytes(), class_size);
+}
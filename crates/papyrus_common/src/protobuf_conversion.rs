@@ -0,0 +1,625 @@
+//! Conversions between the starknet_api types and the protobuf messages of the
+//! [`Starknet p2p specs`], used by the P2P protocols and by streaming APIs.
+//!
+//! [`Starknet p2p specs`]: https://github.com/starknet-io/starknet-p2p-specs/
+#[cfg(test)]
+#[path = "protobuf_conversion_test.rs"]
+mod protobuf_conversion_test;
+
+use std::collections::BTreeMap;
+
+use indexmap::IndexMap;
+use starknet_api::block::{BlockHeader, BlockSignature};
+use starknet_api::core::{ContractAddress, GlobalRoot, Nonce};
+use starknet_api::crypto::Signature;
+use starknet_api::data_availability::{DataAvailabilityMode, L1DataAvailabilityMode};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::ThinStateDiff;
+use starknet_api::transaction::{
+    Builtin,
+    Calldata,
+    DeclareTransaction,
+    DeployAccountTransaction,
+    Event,
+    ExecutionResources,
+    Fee,
+    InvokeTransaction,
+    MessageToL1,
+    Resource,
+    ResourceBoundsMapping,
+    Transaction,
+    TransactionExecutionStatus,
+    TransactionHash,
+    TransactionOutput,
+    TransactionSignature,
+    TransactionVersion,
+};
+
+use crate::protobuf;
+use crate::state_diff_commitment::{calculate_state_diff_commitment, StateDiffVersion};
+
+#[derive(thiserror::Error, Debug)]
+pub enum ProtobufConversionError {
+    #[error("Out of range value")]
+    OutOfRangeValue,
+    #[error("Missing field")]
+    MissingField,
+    #[error("Bytes data length mismatch")]
+    BytesDataLengthMismatch,
+}
+
+impl TryFrom<protobuf::Felt252> for starknet_api::hash::StarkFelt {
+    type Error = ProtobufConversionError;
+    fn try_from(value: protobuf::Felt252) -> Result<Self, Self::Error> {
+        let mut felt = [0; 32];
+        if value.elements.len() != 32 {
+            return Err(ProtobufConversionError::BytesDataLengthMismatch);
+        }
+        felt.copy_from_slice(&value.elements);
+        if let Ok(stark_felt) = Self::new(felt) {
+            Ok(stark_felt)
+        } else {
+            Err(ProtobufConversionError::OutOfRangeValue)
+        }
+    }
+}
+
+impl From<starknet_api::hash::StarkFelt> for protobuf::Felt252 {
+    fn from(value: starknet_api::hash::StarkFelt) -> Self {
+        Self { elements: value.bytes().to_vec() }
+    }
+}
+
+impl From<starknet_api::block::BlockHash> for protobuf::Hash {
+    fn from(value: starknet_api::block::BlockHash) -> Self {
+        Self { elements: value.0.bytes().to_vec() }
+    }
+}
+
+impl From<starknet_api::hash::StarkHash> for protobuf::Hash {
+    fn from(value: starknet_api::hash::StarkHash) -> Self {
+        Self { elements: value.bytes().to_vec() }
+    }
+}
+
+impl From<starknet_api::core::ContractAddress> for protobuf::Address {
+    fn from(value: starknet_api::core::ContractAddress) -> Self {
+        Self { elements: value.0.key().bytes().to_vec() }
+    }
+}
+
+impl From<u128> for protobuf::Uint128 {
+    fn from(value: u128) -> Self {
+        Self { high: (value >> 64) as u64, low: value as u64 }
+    }
+}
+
+impl From<protobuf::Uint128> for u128 {
+    fn from(value: protobuf::Uint128) -> Self {
+        u128::from(value.low) + (u128::from(value.high) << 64)
+    }
+}
+
+impl TryFrom<protobuf::Hash> for starknet_api::hash::StarkHash {
+    type Error = ProtobufConversionError;
+    fn try_from(value: protobuf::Hash) -> Result<Self, Self::Error> {
+        let mut felt = [0; 32];
+        if value.elements.len() != 32 {
+            return Err(ProtobufConversionError::BytesDataLengthMismatch);
+        }
+        felt.copy_from_slice(&value.elements);
+        if let Ok(stark_hash) = Self::new(felt) {
+            Ok(stark_hash)
+        } else {
+            Err(ProtobufConversionError::OutOfRangeValue)
+        }
+    }
+}
+
+impl TryFrom<protobuf::Address> for starknet_api::core::ContractAddress {
+    type Error = ProtobufConversionError;
+    fn try_from(value: protobuf::Address) -> Result<Self, Self::Error> {
+        let mut felt = [0; 32];
+        if value.elements.len() != 32 {
+            return Err(ProtobufConversionError::BytesDataLengthMismatch);
+        }
+        felt.copy_from_slice(&value.elements);
+        if let Ok(hash) = starknet_api::hash::StarkHash::new(felt) {
+            if let Ok(stark_felt) = starknet_api::core::PatriciaKey::try_from(hash) {
+                Ok(starknet_api::core::ContractAddress(stark_felt))
+            } else {
+                Err(ProtobufConversionError::OutOfRangeValue)
+            }
+        } else {
+            Err(ProtobufConversionError::OutOfRangeValue)
+        }
+    }
+}
+
+pub fn enum_int_to_l1_data_availability_mode(
+    value: i32,
+) -> Result<L1DataAvailabilityMode, ProtobufConversionError> {
+    match value {
+        0 => Ok(L1DataAvailabilityMode::Calldata),
+        1 => Ok(L1DataAvailabilityMode::Blob),
+        _ => Err(ProtobufConversionError::OutOfRangeValue),
+    }
+}
+
+pub fn l1_data_availability_mode_to_enum_int(value: L1DataAvailabilityMode) -> i32 {
+    match value {
+        L1DataAvailabilityMode::Calldata => 0,
+        L1DataAvailabilityMode::Blob => 1,
+    }
+}
+
+pub fn data_availability_mode_to_enum_int(value: DataAvailabilityMode) -> i32 {
+    match value {
+        DataAvailabilityMode::L1 => protobuf::VolitionDomain::L1 as i32,
+        DataAvailabilityMode::L2 => protobuf::VolitionDomain::L2 as i32,
+    }
+}
+
+// The height of the contracts trie, whose root is the state root of the block.
+const CONTRACTS_TREE_HEIGHT: u32 = 251;
+
+/// The parts of a block's state diff that its signed header commits to.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct StateDiffSummary {
+    pub commitment: GlobalRoot,
+    pub num_storage_diffs: u64,
+    pub num_nonce_updates: u64,
+    pub num_declared_classes: u64,
+    pub num_deployed_contracts: u64,
+}
+
+impl From<&ThinStateDiff> for StateDiffSummary {
+    fn from(state_diff: &ThinStateDiff) -> Self {
+        let as_u64 = |len: usize| u64::try_from(len).expect("Converting usize to u64 failed");
+        Self {
+            commitment: calculate_state_diff_commitment(state_diff, StateDiffVersion::V0),
+            num_storage_diffs: as_u64(state_diff.storage_diffs.values().map(IndexMap::len).sum()),
+            num_nonce_updates: as_u64(state_diff.nonces.len()),
+            num_declared_classes: as_u64(
+                state_diff.declared_classes.len() + state_diff.deprecated_declared_classes.len(),
+            ),
+            num_deployed_contracts: as_u64(
+                state_diff.deployed_contracts.len() + state_diff.replaced_classes.len(),
+            ),
+        }
+    }
+}
+
+/// Converts a block header to the signed header of the p2p specs. The headers are synced ahead of
+/// the state diffs, so a header whose state diff wasn't synced yet is sent without the state diff
+/// commitment and with zero state diff sizes.
+impl From<(BlockHeader, BlockSignature, Option<StateDiffSummary>)> for protobuf::SignedBlockHeader {
+    fn from(
+        (header, signature, state_diff_summary): (
+            BlockHeader,
+            BlockSignature,
+            Option<StateDiffSummary>,
+        ),
+    ) -> Self {
+        let state_diff_commitment = state_diff_summary.map(|summary| summary.commitment.0.into());
+        let state_diff_summary = state_diff_summary.unwrap_or_default();
+        Self {
+            block_hash: Some(header.block_hash.into()),
+            parent_hash: Some(header.parent_hash.into()),
+            number: header.block_number.0,
+            time: header.timestamp.0,
+            sequencer_address: Some(header.sequencer.0.into()),
+            state_diff_commitment,
+            state: Some(protobuf::Patricia {
+                height: CONTRACTS_TREE_HEIGHT,
+                root: Some(header.state_root.0.into()),
+            }),
+            transactions: Some(protobuf::Merkle {
+                n_leaves: header.n_transactions.try_into().expect("Converting usize to u64 failed"),
+                root: Some(header.transaction_commitment.0.into()),
+            }),
+            events: Some(protobuf::Merkle {
+                n_leaves: header.n_events.try_into().expect("Converting usize to u64 failed"),
+                root: Some(header.event_commitment.0.into()),
+            }),
+            // The receipts commitment isn't part of the block hash of the supported Starknet
+            // versions, so the stored headers don't have it.
+            receipts: None,
+            protocol_version: header.starknet_version.0,
+            gas_price_wei: Some(header.l1_gas_price.price_in_wei.0.into()),
+            gas_price_fri: Some(header.l1_gas_price.price_in_fri.0.into()),
+            data_gas_price_wei: Some(header.l1_data_gas_price.price_in_wei.0.into()),
+            data_gas_price_fri: Some(header.l1_data_gas_price.price_in_fri.0.into()),
+            l1_data_availability_mode: l1_data_availability_mode_to_enum_int(header.l1_da_mode),
+            num_storage_diffs: state_diff_summary.num_storage_diffs,
+            num_nonce_updates: state_diff_summary.num_nonce_updates,
+            num_declared_classes: state_diff_summary.num_declared_classes,
+            num_deployed_contracts: state_diff_summary.num_deployed_contracts,
+            signatures: vec![signature.into()],
+        }
+    }
+}
+
+impl TryFrom<protobuf::ConsensusSignature> for starknet_api::block::BlockSignature {
+    type Error = ProtobufConversionError;
+    fn try_from(value: protobuf::ConsensusSignature) -> Result<Self, Self::Error> {
+        Ok(Self(Signature {
+            r: value.r.ok_or(ProtobufConversionError::MissingField)?.try_into()?,
+            s: value.s.ok_or(ProtobufConversionError::MissingField)?.try_into()?,
+        }))
+    }
+}
+
+impl From<starknet_api::block::BlockSignature> for protobuf::ConsensusSignature {
+    fn from(value: starknet_api::block::BlockSignature) -> Self {
+        Self { r: Some(value.0.r.into()), s: Some(value.0.s.into()) }
+    }
+}
+
+impl From<TransactionSignature> for protobuf::AccountSignature {
+    fn from(value: TransactionSignature) -> Self {
+        Self { parts: felts_to_protobuf(value.0) }
+    }
+}
+
+impl From<ResourceBoundsMapping> for protobuf::ResourceBounds {
+    fn from(value: ResourceBoundsMapping) -> Self {
+        let resource_limits = |resource| {
+            value.0.get(&resource).map(|bounds| protobuf::ResourceLimits {
+                max_amount: Some(StarkFelt::from(u128::from(bounds.max_amount)).into()),
+                max_price_per_unit: Some(StarkFelt::from(bounds.max_price_per_unit).into()),
+            })
+        };
+        Self { l1_gas: resource_limits(Resource::L1Gas), l2_gas: resource_limits(Resource::L2Gas) }
+    }
+}
+
+impl From<(Transaction, TransactionHash)> for protobuf::Transaction {
+    fn from((transaction, transaction_hash): (Transaction, TransactionHash)) -> Self {
+        let txn = match transaction {
+            Transaction::Declare(DeclareTransaction::V0(declare)) => {
+                protobuf::transaction::Txn::DeclareV0(protobuf::transaction::DeclareV0 {
+                    sender: Some(declare.sender_address.into()),
+                    max_fee: Some(fee_to_protobuf(declare.max_fee)),
+                    signature: Some(declare.signature.into()),
+                    class_hash: Some(declare.class_hash.0.into()),
+                })
+            }
+            Transaction::Declare(DeclareTransaction::V1(declare)) => {
+                protobuf::transaction::Txn::DeclareV1(protobuf::transaction::DeclareV1 {
+                    sender: Some(declare.sender_address.into()),
+                    max_fee: Some(fee_to_protobuf(declare.max_fee)),
+                    signature: Some(declare.signature.into()),
+                    class_hash: Some(declare.class_hash.0.into()),
+                    nonce: Some(declare.nonce.0.into()),
+                })
+            }
+            Transaction::Declare(DeclareTransaction::V2(declare)) => {
+                protobuf::transaction::Txn::DeclareV2(protobuf::transaction::DeclareV2 {
+                    sender: Some(declare.sender_address.into()),
+                    max_fee: Some(fee_to_protobuf(declare.max_fee)),
+                    signature: Some(declare.signature.into()),
+                    class_hash: Some(declare.class_hash.0.into()),
+                    nonce: Some(declare.nonce.0.into()),
+                    compiled_class_hash: Some(declare.compiled_class_hash.0.into()),
+                })
+            }
+            Transaction::Declare(DeclareTransaction::V3(declare)) => {
+                protobuf::transaction::Txn::DeclareV3(protobuf::transaction::DeclareV3 {
+                    sender: Some(declare.sender_address.into()),
+                    signature: Some(declare.signature.into()),
+                    class_hash: Some(declare.class_hash.0.into()),
+                    nonce: Some(declare.nonce.0.into()),
+                    compiled_class_hash: Some(declare.compiled_class_hash.0.into()),
+                    resource_bounds: Some(declare.resource_bounds.into()),
+                    tip: declare.tip.0,
+                    paymaster_data: felts_to_protobuf(declare.paymaster_data.0),
+                    account_deployment_data: felts_to_protobuf(declare.account_deployment_data.0),
+                    nonce_data_availability_mode: data_availability_mode_to_enum_int(
+                        declare.nonce_data_availability_mode,
+                    ),
+                    fee_data_availability_mode: data_availability_mode_to_enum_int(
+                        declare.fee_data_availability_mode,
+                    ),
+                })
+            }
+            Transaction::Deploy(deploy) => {
+                protobuf::transaction::Txn::Deploy(protobuf::transaction::Deploy {
+                    class_hash: Some(deploy.class_hash.0.into()),
+                    address_salt: Some(deploy.contract_address_salt.0.into()),
+                    calldata: calldata_to_protobuf(deploy.constructor_calldata),
+                    version: deploy_version_to_u32(deploy.version),
+                })
+            }
+            Transaction::DeployAccount(DeployAccountTransaction::V1(deploy_account)) => {
+                protobuf::transaction::Txn::DeployAccountV1(
+                    protobuf::transaction::DeployAccountV1 {
+                        max_fee: Some(fee_to_protobuf(deploy_account.max_fee)),
+                        signature: Some(deploy_account.signature.into()),
+                        class_hash: Some(deploy_account.class_hash.0.into()),
+                        nonce: Some(deploy_account.nonce.0.into()),
+                        address_salt: Some(deploy_account.contract_address_salt.0.into()),
+                        calldata: calldata_to_protobuf(deploy_account.constructor_calldata),
+                    },
+                )
+            }
+            Transaction::DeployAccount(DeployAccountTransaction::V3(deploy_account)) => {
+                protobuf::transaction::Txn::DeployAccountV3(
+                    protobuf::transaction::DeployAccountV3 {
+                        signature: Some(deploy_account.signature.into()),
+                        class_hash: Some(deploy_account.class_hash.0.into()),
+                        nonce: Some(deploy_account.nonce.0.into()),
+                        address_salt: Some(deploy_account.contract_address_salt.0.into()),
+                        calldata: calldata_to_protobuf(deploy_account.constructor_calldata),
+                        resource_bounds: Some(deploy_account.resource_bounds.into()),
+                        tip: deploy_account.tip.0,
+                        paymaster_data: felts_to_protobuf(deploy_account.paymaster_data.0),
+                        nonce_data_availability_mode: data_availability_mode_to_enum_int(
+                            deploy_account.nonce_data_availability_mode,
+                        ),
+                        fee_data_availability_mode: data_availability_mode_to_enum_int(
+                            deploy_account.fee_data_availability_mode,
+                        ),
+                    },
+                )
+            }
+            Transaction::Invoke(InvokeTransaction::V0(invoke)) => {
+                protobuf::transaction::Txn::InvokeV0(protobuf::transaction::InvokeV0 {
+                    max_fee: Some(fee_to_protobuf(invoke.max_fee)),
+                    signature: Some(invoke.signature.into()),
+                    address: Some(invoke.contract_address.into()),
+                    entry_point_selector: Some(invoke.entry_point_selector.0.into()),
+                    calldata: calldata_to_protobuf(invoke.calldata),
+                })
+            }
+            Transaction::Invoke(InvokeTransaction::V1(invoke)) => {
+                protobuf::transaction::Txn::InvokeV1(protobuf::transaction::InvokeV1 {
+                    sender: Some(invoke.sender_address.into()),
+                    max_fee: Some(fee_to_protobuf(invoke.max_fee)),
+                    signature: Some(invoke.signature.into()),
+                    calldata: calldata_to_protobuf(invoke.calldata),
+                    nonce: Some(invoke.nonce.0.into()),
+                })
+            }
+            Transaction::Invoke(InvokeTransaction::V3(invoke)) => {
+                protobuf::transaction::Txn::InvokeV3(protobuf::transaction::InvokeV3 {
+                    sender: Some(invoke.sender_address.into()),
+                    signature: Some(invoke.signature.into()),
+                    calldata: calldata_to_protobuf(invoke.calldata),
+                    resource_bounds: Some(invoke.resource_bounds.into()),
+                    tip: invoke.tip.0,
+                    paymaster_data: felts_to_protobuf(invoke.paymaster_data.0),
+                    account_deployment_data: felts_to_protobuf(invoke.account_deployment_data.0),
+                    nonce_data_availability_mode: data_availability_mode_to_enum_int(
+                        invoke.nonce_data_availability_mode,
+                    ),
+                    fee_data_availability_mode: data_availability_mode_to_enum_int(
+                        invoke.fee_data_availability_mode,
+                    ),
+                    nonce: Some(invoke.nonce.0.into()),
+                })
+            }
+            Transaction::L1Handler(l1_handler) => {
+                protobuf::transaction::Txn::L1Handler(protobuf::transaction::L1HandlerV0 {
+                    nonce: Some(l1_handler.nonce.0.into()),
+                    address: Some(l1_handler.contract_address.into()),
+                    entry_point_selector: Some(l1_handler.entry_point_selector.0.into()),
+                    calldata: calldata_to_protobuf(l1_handler.calldata),
+                })
+            }
+        };
+        Self { txn: Some(txn), transaction_hash: Some(transaction_hash.0.into()) }
+    }
+}
+
+impl From<(TransactionOutput, TransactionHash)> for protobuf::Receipt {
+    fn from((output, transaction_hash): (TransactionOutput, TransactionHash)) -> Self {
+        let common = |actual_fee: Fee,
+                      messages_sent: Vec<MessageToL1>,
+                      execution_status: TransactionExecutionStatus,
+                      execution_resources: ExecutionResources| {
+            Some(protobuf::receipt::Common {
+                transaction_hash: Some(transaction_hash.0.into()),
+                actual_fee: Some(fee_to_protobuf(actual_fee)),
+                messages_sent: messages_sent.into_iter().map(Into::into).collect(),
+                execution_resources: Some(execution_resources.into()),
+                revert_reason: match execution_status {
+                    TransactionExecutionStatus::Succeeded => None,
+                    // The revert reason isn't stored, so a reverted transaction has an empty one.
+                    TransactionExecutionStatus::Reverted => Some(String::new()),
+                },
+            })
+        };
+        let r#type = match output {
+            TransactionOutput::Declare(output) => {
+                protobuf::receipt::Type::Declare(protobuf::receipt::Declare {
+                    common: common(
+                        output.actual_fee,
+                        output.messages_sent,
+                        output.execution_status,
+                        output.execution_resources,
+                    ),
+                })
+            }
+            TransactionOutput::Deploy(output) => {
+                protobuf::receipt::Type::DeprecatedDeploy(protobuf::receipt::Deploy {
+                    common: common(
+                        output.actual_fee,
+                        output.messages_sent,
+                        output.execution_status,
+                        output.execution_resources,
+                    ),
+                    contract_address: Some(contract_address_to_felt(output.contract_address)),
+                })
+            }
+            TransactionOutput::DeployAccount(output) => {
+                protobuf::receipt::Type::DeployAccount(protobuf::receipt::DeployAccount {
+                    common: common(
+                        output.actual_fee,
+                        output.messages_sent,
+                        output.execution_status,
+                        output.execution_resources,
+                    ),
+                    contract_address: Some(contract_address_to_felt(output.contract_address)),
+                })
+            }
+            TransactionOutput::Invoke(output) => {
+                protobuf::receipt::Type::Invoke(protobuf::receipt::Invoke {
+                    common: common(
+                        output.actual_fee,
+                        output.messages_sent,
+                        output.execution_status,
+                        output.execution_resources,
+                    ),
+                })
+            }
+            TransactionOutput::L1Handler(output) => {
+                protobuf::receipt::Type::L1Handler(protobuf::receipt::L1Handler {
+                    common: common(
+                        output.actual_fee,
+                        output.messages_sent,
+                        output.execution_status,
+                        output.execution_resources,
+                    ),
+                    // The hash of the L1 message isn't part of the output, and computing it
+                    // requires the transaction.
+                    // TODO: fill this once the receipts are encoded together with the transactions.
+                    msg_hash: None,
+                })
+            }
+        };
+        Self { r#type: Some(r#type) }
+    }
+}
+
+impl From<MessageToL1> for protobuf::MessageToL1 {
+    fn from(value: MessageToL1) -> Self {
+        Self {
+            from_address: Some(contract_address_to_felt(value.from_address)),
+            payload: felts_to_protobuf(value.payload.0),
+            to_address: Some(protobuf::EthereumAddress {
+                elements: value.to_address.0.as_bytes().to_vec(),
+            }),
+        }
+    }
+}
+
+impl From<ExecutionResources> for protobuf::receipt::ExecutionResources {
+    fn from(value: ExecutionResources) -> Self {
+        let mut builtins = protobuf::receipt::execution_resources::BuiltinCounter::default();
+        for (builtin, count) in value.builtin_instance_counter {
+            let count = u64_to_u32(count);
+            match builtin {
+                Builtin::RangeCheck => builtins.range_check = count,
+                Builtin::Pedersen => builtins.pedersen = count,
+                Builtin::Poseidon => builtins.poseidon = count,
+                Builtin::EcOp => builtins.ec_op = count,
+                Builtin::Ecdsa => builtins.ecdsa = count,
+                Builtin::Bitwise => builtins.bitwise = count,
+                Builtin::Keccak => builtins.keccak = count,
+                // The specs have no counter for the segment arena builtin.
+                Builtin::SegmentArena => {}
+            }
+        }
+        Self {
+            builtins: Some(builtins),
+            steps: u64_to_u32(value.steps),
+            memory_holes: u64_to_u32(value.memory_holes),
+        }
+    }
+}
+
+impl From<(Event, TransactionHash)> for protobuf::Event {
+    fn from((event, transaction_hash): (Event, TransactionHash)) -> Self {
+        Self {
+            transaction_hash: Some(transaction_hash.0.into()),
+            from_address: Some(contract_address_to_felt(event.from_address)),
+            keys: event.content.keys.into_iter().map(|key| key.0.into()).collect(),
+            data: felts_to_protobuf(event.content.data.0),
+        }
+    }
+}
+
+/// Returns the parts of the state diff as they are sent in a state diffs response: a diff for each
+/// contract whose nonce, class or storage changed, ordered by address, followed by the declared
+/// classes. The [`Fin`](protobuf::Fin) that ends the block isn't included.
+pub fn state_diff_to_protobuf_parts(
+    state_diff: ThinStateDiff,
+) -> Vec<protobuf::state_diffs_response::StateDiffMessage> {
+    let mut contract_diffs = BTreeMap::<ContractAddress, protobuf::ContractDiff>::new();
+    for (address, class_hash) in
+        state_diff.deployed_contracts.into_iter().chain(state_diff.replaced_classes)
+    {
+        contract_diff(&mut contract_diffs, address).class_hash = Some(class_hash.0.into());
+    }
+    for (address, Nonce(nonce)) in state_diff.nonces {
+        contract_diff(&mut contract_diffs, address).nonce = Some(nonce.into());
+    }
+    for (address, storage_diff) in state_diff.storage_diffs {
+        contract_diff(&mut contract_diffs, address).values.extend(storage_diff.into_iter().map(
+            |(key, value)| protobuf::ContractStoredValue {
+                key: Some((*key.0.key()).into()),
+                value: Some(value.into()),
+            },
+        ));
+    }
+
+    let declared_classes = state_diff
+        .declared_classes
+        .into_iter()
+        .map(|(class_hash, compiled_class_hash)| protobuf::DeclaredClass {
+            class_hash: Some(class_hash.0.into()),
+            compiled_class_hash: Some(compiled_class_hash.0.into()),
+        })
+        .chain(state_diff.deprecated_declared_classes.into_iter().map(|class_hash| {
+            protobuf::DeclaredClass {
+                class_hash: Some(class_hash.0.into()),
+                compiled_class_hash: None,
+            }
+        }));
+
+    contract_diffs
+        .into_values()
+        .map(protobuf::state_diffs_response::StateDiffMessage::ContractDiff)
+        .chain(
+            declared_classes.map(protobuf::state_diffs_response::StateDiffMessage::DeclaredClass),
+        )
+        .collect()
+}
+
+fn contract_diff(
+    contract_diffs: &mut BTreeMap<ContractAddress, protobuf::ContractDiff>,
+    address: ContractAddress,
+) -> &mut protobuf::ContractDiff {
+    contract_diffs.entry(address).or_insert_with(|| protobuf::ContractDiff {
+        address: Some(address.into()),
+        ..Default::default()
+    })
+}
+
+fn felts_to_protobuf(felts: Vec<StarkFelt>) -> Vec<protobuf::Felt252> {
+    felts.into_iter().map(Into::into).collect()
+}
+
+fn calldata_to_protobuf(calldata: Calldata) -> Vec<protobuf::Felt252> {
+    calldata.0.iter().map(|felt| (*felt).into()).collect()
+}
+
+fn fee_to_protobuf(fee: Fee) -> protobuf::Felt252 {
+    StarkFelt::from(fee.0).into()
+}
+
+// The specs send the addresses that aren't the subject of the message as plain felts.
+fn contract_address_to_felt(address: ContractAddress) -> protobuf::Felt252 {
+    (*address.0.key()).into()
+}
+
+// Deploy transactions were deprecated while their versions were still small numbers.
+fn deploy_version_to_u32(version: TransactionVersion) -> u32 {
+    let bytes = version.0.bytes();
+    u32::from_be_bytes(bytes[28..].try_into().expect("The slice should have 4 bytes."))
+}
+
+fn u64_to_u32(value: u64) -> u32 {
+    value.try_into().expect("Converting u64 to u32 failed")
+}
@@ -0,0 +1,228 @@
+use std::collections::{BTreeMap, HashMap};
+
+use assert_matches::assert_matches;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockHeader, BlockSignature};
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce, PatriciaKey};
+use starknet_api::data_availability::DataAvailabilityMode;
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::{StorageKey, ThinStateDiff};
+use starknet_api::transaction::{
+    Builtin,
+    ExecutionResources,
+    Fee,
+    InvokeTransaction,
+    InvokeTransactionOutput,
+    InvokeTransactionV3,
+    Resource,
+    ResourceBounds,
+    ResourceBoundsMapping,
+    Tip,
+    Transaction,
+    TransactionExecutionStatus,
+    TransactionHash,
+    TransactionOutput,
+};
+use starknet_api::{class_hash, contract_address, patricia_key, stark_felt};
+use test_utils::{get_rng, GetTestInstance};
+
+use crate::protobuf;
+use crate::protobuf_conversion::{
+    state_diff_to_protobuf_parts,
+    ProtobufConversionError,
+    StateDiffSummary,
+};
+use crate::state_diff_commitment::{calculate_state_diff_commitment, StateDiffVersion};
+
+#[test]
+fn felt_round_trip() {
+    let felt = stark_felt!("0x1234");
+    let protobuf_felt = protobuf::Felt252::from(felt);
+    assert_eq!(protobuf_felt.elements.len(), 32);
+    assert_eq!(StarkFelt::try_from(protobuf_felt).unwrap(), felt);
+
+    let short_felt = protobuf::Felt252 { elements: vec![1; 31] };
+    assert_matches!(
+        StarkFelt::try_from(short_felt),
+        Err(ProtobufConversionError::BytesDataLengthMismatch)
+    );
+}
+
+#[test]
+fn uint128_round_trip() {
+    let value = u128::MAX - 5;
+    assert_eq!(u128::from(protobuf::Uint128::from(value)), value);
+}
+
+#[test]
+fn invoke_v3_transaction() {
+    let transaction_hash = TransactionHash(stark_felt!("0x99"));
+    let invoke = InvokeTransactionV3 {
+        resource_bounds: ResourceBoundsMapping(BTreeMap::from([
+            (Resource::L1Gas, ResourceBounds { max_amount: 5, max_price_per_unit: 7 }),
+            (Resource::L2Gas, ResourceBounds { max_amount: 0, max_price_per_unit: 0 }),
+        ])),
+        tip: Tip(3),
+        nonce: Nonce(stark_felt!("0x2")),
+        sender_address: contract_address!("0x100"),
+        fee_data_availability_mode: DataAvailabilityMode::L2,
+        ..InvokeTransactionV3::get_test_instance(&mut get_rng())
+    };
+
+    let protobuf_transaction = protobuf::Transaction::from((
+        Transaction::Invoke(InvokeTransaction::V3(invoke.clone())),
+        transaction_hash,
+    ));
+    assert_eq!(protobuf_transaction.transaction_hash, Some(transaction_hash.0.into()));
+    let Some(protobuf::transaction::Txn::InvokeV3(protobuf_invoke)) = protobuf_transaction.txn
+    else {
+        panic!("Wrong transaction type, expected InvokeV3.");
+    };
+    assert_eq!(protobuf_invoke.sender, Some(invoke.sender_address.into()));
+    assert_eq!(protobuf_invoke.nonce, Some(invoke.nonce.0.into()));
+    assert_eq!(protobuf_invoke.tip, 3);
+    assert_eq!(protobuf_invoke.calldata.len(), invoke.calldata.0.len());
+    assert_eq!(
+        protobuf_invoke.resource_bounds.unwrap().l1_gas,
+        Some(protobuf::ResourceLimits {
+            max_amount: Some(StarkFelt::from(5_u128).into()),
+            max_price_per_unit: Some(StarkFelt::from(7_u128).into()),
+        })
+    );
+    assert_eq!(protobuf_invoke.fee_data_availability_mode, protobuf::VolitionDomain::L2 as i32);
+}
+
+#[test]
+fn reverted_invoke_receipt() {
+    let transaction_hash = TransactionHash(stark_felt!("0x99"));
+    let output = TransactionOutput::Invoke(InvokeTransactionOutput {
+        actual_fee: Fee(12),
+        execution_status: TransactionExecutionStatus::Reverted,
+        execution_resources: ExecutionResources {
+            steps: 100,
+            builtin_instance_counter: HashMap::from([
+                (Builtin::Pedersen, 2),
+                (Builtin::SegmentArena, 1),
+            ]),
+            memory_holes: 4,
+        },
+        ..Default::default()
+    });
+
+    let receipt = protobuf::Receipt::from((output, transaction_hash));
+    let Some(protobuf::receipt::Type::Invoke(protobuf::receipt::Invoke { common: Some(common) })) =
+        receipt.r#type
+    else {
+        panic!("Wrong receipt type, expected Invoke.");
+    };
+    assert_eq!(common.transaction_hash, Some(transaction_hash.0.into()));
+    assert_eq!(common.actual_fee, Some(StarkFelt::from(12_u128).into()));
+    assert_eq!(common.revert_reason, Some(String::new()));
+    assert_eq!(
+        common.execution_resources,
+        Some(protobuf::receipt::ExecutionResources {
+            builtins: Some(protobuf::receipt::execution_resources::BuiltinCounter {
+                pedersen: 2,
+                ..Default::default()
+            }),
+            steps: 100,
+            memory_holes: 4,
+        })
+    );
+}
+
+#[test]
+fn state_diff_parts_are_grouped_by_contract() {
+    let deployed_address = contract_address!("0x2");
+    let updated_address = contract_address!("0x1");
+    let state_diff = ThinStateDiff {
+        deployed_contracts: [(deployed_address, class_hash!("0x20"))].into(),
+        storage_diffs: [
+            (deployed_address, [(StorageKey(patricia_key!("0x5")), stark_felt!("0x6"))].into()),
+            (updated_address, [(StorageKey(patricia_key!("0x7")), stark_felt!("0x8"))].into()),
+        ]
+        .into(),
+        declared_classes: [(class_hash!("0x30"), CompiledClassHash(stark_felt!("0x31")))].into(),
+        deprecated_declared_classes: vec![class_hash!("0x40")],
+        nonces: [(deployed_address, Nonce(stark_felt!("0x1")))].into(),
+        replaced_classes: [(updated_address, class_hash!("0x21"))].into(),
+    };
+
+    let expected_parts = vec![
+        protobuf::state_diffs_response::StateDiffMessage::ContractDiff(protobuf::ContractDiff {
+            address: Some(updated_address.into()),
+            nonce: None,
+            class_hash: Some(stark_felt!("0x21").into()),
+            values: vec![protobuf::ContractStoredValue {
+                key: Some(stark_felt!("0x7").into()),
+                value: Some(stark_felt!("0x8").into()),
+            }],
+        }),
+        protobuf::state_diffs_response::StateDiffMessage::ContractDiff(protobuf::ContractDiff {
+            address: Some(deployed_address.into()),
+            nonce: Some(stark_felt!("0x1").into()),
+            class_hash: Some(stark_felt!("0x20").into()),
+            values: vec![protobuf::ContractStoredValue {
+                key: Some(stark_felt!("0x5").into()),
+                value: Some(stark_felt!("0x6").into()),
+            }],
+        }),
+        protobuf::state_diffs_response::StateDiffMessage::DeclaredClass(protobuf::DeclaredClass {
+            class_hash: Some(stark_felt!("0x30").into()),
+            compiled_class_hash: Some(stark_felt!("0x31").into()),
+        }),
+        protobuf::state_diffs_response::StateDiffMessage::DeclaredClass(protobuf::DeclaredClass {
+            class_hash: Some(stark_felt!("0x40").into()),
+            compiled_class_hash: None,
+        }),
+    ];
+    assert_eq!(state_diff_to_protobuf_parts(state_diff), expected_parts);
+}
+
+#[test]
+fn signed_header_commits_to_the_state_diff() {
+    let state_diff = ThinStateDiff {
+        deployed_contracts: [(contract_address!("0x1"), class_hash!("0x10"))].into(),
+        storage_diffs: [(
+            contract_address!("0x1"),
+            [
+                (StorageKey(patricia_key!("0x5")), stark_felt!("0x6")),
+                (StorageKey(patricia_key!("0x7")), stark_felt!("0x8")),
+            ]
+            .into(),
+        )]
+        .into(),
+        declared_classes: [(class_hash!("0x30"), CompiledClassHash(stark_felt!("0x31")))].into(),
+        deprecated_declared_classes: vec![class_hash!("0x40")],
+        nonces: [(contract_address!("0x1"), Nonce(stark_felt!("0x1")))].into(),
+        replaced_classes: [(contract_address!("0x2"), class_hash!("0x21"))].into(),
+    };
+    let state_diff_summary = StateDiffSummary::from(&state_diff);
+    assert_eq!(
+        state_diff_summary,
+        StateDiffSummary {
+            commitment: calculate_state_diff_commitment(&state_diff, StateDiffVersion::V0),
+            num_storage_diffs: 2,
+            num_nonce_updates: 1,
+            num_declared_classes: 2,
+            num_deployed_contracts: 2,
+        }
+    );
+
+    let header = BlockHeader::default();
+    let signed_header = protobuf::SignedBlockHeader::from((
+        header.clone(),
+        BlockSignature::default(),
+        Some(state_diff_summary),
+    ));
+    assert_eq!(signed_header.state_diff_commitment, Some(state_diff_summary.commitment.0.into()));
+    assert_eq!(signed_header.state.unwrap().height, 251);
+    assert_eq!(signed_header.num_storage_diffs, 2);
+    assert_eq!(signed_header.num_deployed_contracts, 2);
+
+    // A header whose state diff wasn't synced yet doesn't commit to it.
+    let signed_header =
+        protobuf::SignedBlockHeader::from((header, BlockSignature::default(), None));
+    assert_eq!(signed_header.state_diff_commitment, None);
+    assert_eq!(signed_header.num_storage_diffs, 0);
+}
@@ -0,0 +1,219 @@
+#[cfg(test)]
+#[path = "recent_state_test.rs"]
+mod recent_state_test;
+
+use std::collections::{HashMap, VecDeque};
+use std::mem::size_of;
+use std::sync::RwLock;
+
+use indexmap::IndexMap;
+use starknet_api::block::BlockNumber;
+use starknet_api::core::{ClassHash, ContractAddress, Nonce};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::{StateDiff, StorageKey, ThinStateDiff};
+
+use crate::metrics::{PAPYRUS_CACHE_HITS, PAPYRUS_CACHE_MEMORY_USAGE, PAPYRUS_CACHE_MISSES};
+
+// The name of the cache in its metrics.
+const CACHE_NAME: &str = "recent_state";
+
+/// The state writes of a single block, indexed for lookups.
+#[derive(Debug, Default)]
+pub struct BlockStateWrites {
+    storage: HashMap<(ContractAddress, StorageKey), StarkFelt>,
+    nonces: HashMap<ContractAddress, Nonce>,
+    // The classes of the contracts that were deployed or whose class was replaced.
+    class_hashes: HashMap<ContractAddress, ClassHash>,
+}
+
+impl From<&StateDiff> for BlockStateWrites {
+    fn from(state_diff: &StateDiff) -> Self {
+        Self::new(
+            &state_diff.storage_diffs,
+            &state_diff.nonces,
+            &state_diff.deployed_contracts,
+            &state_diff.replaced_classes,
+        )
+    }
+}
+
+impl From<&ThinStateDiff> for BlockStateWrites {
+    fn from(thin_state_diff: &ThinStateDiff) -> Self {
+        Self::new(
+            &thin_state_diff.storage_diffs,
+            &thin_state_diff.nonces,
+            &thin_state_diff.deployed_contracts,
+            &thin_state_diff.replaced_classes,
+        )
+    }
+}
+
+impl BlockStateWrites {
+    fn new(
+        storage_diffs: &IndexMap<ContractAddress, IndexMap<StorageKey, StarkFelt>>,
+        nonces: &IndexMap<ContractAddress, Nonce>,
+        deployed_contracts: &IndexMap<ContractAddress, ClassHash>,
+        replaced_classes: &IndexMap<ContractAddress, ClassHash>,
+    ) -> Self {
+        let storage = storage_diffs
+            .iter()
+            .flat_map(|(address, storage_entries)| {
+                storage_entries.iter().map(move |(key, value)| ((*address, *key), *value))
+            })
+            .collect();
+        let nonces = nonces.iter().map(|(address, nonce)| (*address, *nonce)).collect();
+        // A contract that was deployed and then replaced in the block has the replaced class.
+        let class_hashes = deployed_contracts
+            .iter()
+            .chain(replaced_classes)
+            .map(|(address, class_hash)| (*address, *class_hash))
+            .collect();
+        Self { storage, nonces, class_hashes }
+    }
+
+    /// The estimated memory, in bytes, that the writes take.
+    pub fn memory_size(&self) -> usize {
+        // Each entry of a hash map takes a control byte in addition to the key and the value.
+        fn map_size<K, V>(map: &HashMap<K, V>) -> usize {
+            map.capacity() * (size_of::<(K, V)>() + 1)
+        }
+        size_of::<Self>()
+            + map_size(&self.storage)
+            + map_size(&self.nonces)
+            + map_size(&self.class_hashes)
+    }
+}
+
+/// An in-memory overlay of the state writes of the most recent blocks, shared between the sync,
+/// which adds every block whose state diff it stores, and the RPC server, which answers state
+/// queries on recent blocks from it before going to the storage.
+///
+/// The default cache has no capacity and never holds blocks.
+#[derive(Debug, Default)]
+pub struct RecentStateCache {
+    capacity: usize,
+    // The memory, in bytes, that the cached writes may take. None means no bound.
+    max_bytes: Option<usize>,
+    // Consecutive blocks, ordered by the block number.
+    blocks: RwLock<VecDeque<(BlockNumber, BlockStateWrites)>>,
+}
+
+impl RecentStateCache {
+    /// Creates a cache that holds the writes of up to `capacity` blocks.
+    pub fn new(capacity: usize) -> Self {
+        Self::with_max_bytes(capacity, None)
+    }
+
+    /// Creates a cache that holds the writes of up to `capacity` blocks, whose memory is up to
+    /// `max_bytes`. The oldest blocks are evicted first.
+    pub fn with_max_bytes(capacity: usize, max_bytes: Option<usize>) -> Self {
+        Self { capacity, max_bytes, blocks: RwLock::new(VecDeque::with_capacity(capacity)) }
+    }
+
+    /// Adds the writes of a block whose state diff was stored. If the block doesn't follow the
+    /// last cached block, the cache restarts from it.
+    pub fn push_block(&self, block_number: BlockNumber, writes: BlockStateWrites) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut blocks = self.blocks.write().expect("RecentStateCache lock should not be poisoned");
+        if blocks
+            .back()
+            .is_some_and(|(last_block_number, _)| last_block_number.next() != block_number)
+        {
+            blocks.clear();
+        }
+        if blocks.len() == self.capacity {
+            blocks.pop_front();
+        }
+        blocks.push_back((block_number, writes));
+        let mut used_bytes = memory_size(&blocks);
+        if let Some(max_bytes) = self.max_bytes {
+            while used_bytes > max_bytes {
+                let Some((_, writes)) = blocks.pop_front() else {
+                    break;
+                };
+                used_bytes -= writes.memory_size();
+            }
+        }
+        report_usage(used_bytes);
+    }
+
+    /// Removes the given block and all the blocks after it. Should be called before the blocks are
+    /// reverted in the storage.
+    pub fn revert_from(&self, block_number: BlockNumber) {
+        let mut blocks = self.blocks.write().expect("RecentStateCache lock should not be poisoned");
+        while blocks.back().is_some_and(|(last_block_number, _)| *last_block_number >= block_number)
+        {
+            blocks.pop_back();
+        }
+        report_usage(memory_size(&blocks));
+    }
+
+    /// Returns the value of the storage key right after the given block, or None if the answer
+    /// isn't in the cache.
+    pub fn get_storage_at(
+        &self,
+        block_number: BlockNumber,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> Option<StarkFelt> {
+        self.find_latest_write(block_number, |writes| {
+            writes.storage.get(&(contract_address, key)).copied()
+        })
+    }
+
+    /// Returns the nonce of the contract right after the given block, or None if the answer isn't
+    /// in the cache.
+    pub fn get_nonce_at(
+        &self,
+        block_number: BlockNumber,
+        contract_address: ContractAddress,
+    ) -> Option<Nonce> {
+        self.find_latest_write(block_number, |writes| writes.nonces.get(&contract_address).copied())
+    }
+
+    /// Returns the class hash of the contract right after the given block, or None if the answer
+    /// isn't in the cache.
+    pub fn get_class_hash_at(
+        &self,
+        block_number: BlockNumber,
+        contract_address: ContractAddress,
+    ) -> Option<ClassHash> {
+        self.find_latest_write(block_number, |writes| {
+            writes.class_hashes.get(&contract_address).copied()
+        })
+    }
+
+    // Returns the latest write in the cached blocks up to the given block. A write that isn't found
+    // might have happened in a block that isn't cached, so there's an answer only if the given
+    // block itself is cached.
+    fn find_latest_write<T>(
+        &self,
+        block_number: BlockNumber,
+        get_write: impl Fn(&BlockStateWrites) -> Option<T>,
+    ) -> Option<T> {
+        let blocks = self.blocks.read().expect("RecentStateCache lock should not be poisoned");
+        let write = match blocks.back() {
+            Some((last_block_number, _)) if block_number <= *last_block_number => blocks
+                .iter()
+                .rev()
+                .skip_while(|(cached_block_number, _)| *cached_block_number > block_number)
+                .find_map(|(_, writes)| get_write(writes)),
+            _ => None,
+        };
+        match write {
+            Some(_) => metrics::increment_counter!(PAPYRUS_CACHE_HITS, "cache" => CACHE_NAME),
+            None => metrics::increment_counter!(PAPYRUS_CACHE_MISSES, "cache" => CACHE_NAME),
+        }
+        write
+    }
+}
+
+fn memory_size(blocks: &VecDeque<(BlockNumber, BlockStateWrites)>) -> usize {
+    blocks.iter().map(|(_, writes)| writes.memory_size()).sum()
+}
+
+fn report_usage(used_bytes: usize) {
+    metrics::gauge!(PAPYRUS_CACHE_MEMORY_USAGE, used_bytes as f64, "cache" => CACHE_NAME);
+}
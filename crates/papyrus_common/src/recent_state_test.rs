@@ -0,0 +1,139 @@
+use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
+use starknet_api::core::{ClassHash, ContractAddress, Nonce, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::{StateDiff, StorageKey};
+use starknet_api::{class_hash, contract_address, patricia_key, stark_felt};
+
+use crate::recent_state::{BlockStateWrites, RecentStateCache};
+
+fn storage_write(value: u64) -> BlockStateWrites {
+    let state_diff = StateDiff {
+        storage_diffs: [(
+            contract_address!("0x1"),
+            [(StorageKey(patricia_key!("0x2")), StarkFelt::from(value))].into(),
+        )]
+        .into(),
+        ..Default::default()
+    };
+    BlockStateWrites::from(&state_diff)
+}
+
+fn get_storage_value(cache: &RecentStateCache, block_number: u64) -> Option<StarkFelt> {
+    cache.get_storage_at(
+        BlockNumber(block_number),
+        contract_address!("0x1"),
+        StorageKey(patricia_key!("0x2")),
+    )
+}
+
+#[test]
+fn latest_write_up_to_block() {
+    let cache = RecentStateCache::new(10);
+    cache.push_block(BlockNumber(5), storage_write(50));
+    cache.push_block(BlockNumber(6), BlockStateWrites::default());
+    cache.push_block(BlockNumber(7), storage_write(70));
+
+    assert_eq!(get_storage_value(&cache, 5), Some(StarkFelt::from(50_u64)));
+    assert_eq!(get_storage_value(&cache, 6), Some(StarkFelt::from(50_u64)));
+    assert_eq!(get_storage_value(&cache, 7), Some(StarkFelt::from(70_u64)));
+    // Blocks that aren't cached can't be answered.
+    assert_eq!(get_storage_value(&cache, 4), None);
+    assert_eq!(get_storage_value(&cache, 8), None);
+}
+
+#[test]
+fn oldest_blocks_are_evicted() {
+    let cache = RecentStateCache::new(2);
+    cache.push_block(BlockNumber(0), storage_write(1));
+    cache.push_block(BlockNumber(1), BlockStateWrites::default());
+    cache.push_block(BlockNumber(2), BlockStateWrites::default());
+
+    // The write of block 0 was evicted, so its value at block 2 is unknown.
+    assert_eq!(get_storage_value(&cache, 2), None);
+}
+
+#[test]
+fn revert_and_gap_drop_blocks() {
+    let cache = RecentStateCache::new(10);
+    cache.push_block(BlockNumber(0), storage_write(1));
+    cache.push_block(BlockNumber(1), storage_write(2));
+
+    cache.revert_from(BlockNumber(1));
+    assert_eq!(get_storage_value(&cache, 1), None);
+    assert_eq!(get_storage_value(&cache, 0), Some(StarkFelt::from(1_u64)));
+
+    // A block that doesn't follow the last cached block restarts the cache.
+    cache.push_block(BlockNumber(3), BlockStateWrites::default());
+    assert_eq!(get_storage_value(&cache, 0), None);
+    assert_eq!(get_storage_value(&cache, 3), None);
+}
+
+#[test]
+fn nonces_are_cached() {
+    let cache = RecentStateCache::new(10);
+    let state_diff = StateDiff {
+        nonces: [(contract_address!("0x1"), Nonce(stark_felt!("0x3")))].into(),
+        ..Default::default()
+    };
+    cache.push_block(BlockNumber(0), BlockStateWrites::from(&state_diff));
+
+    assert_eq!(
+        cache.get_nonce_at(BlockNumber(0), contract_address!("0x1")),
+        Some(Nonce(stark_felt!("0x3")))
+    );
+}
+
+#[test]
+fn class_hashes_are_cached() {
+    let cache = RecentStateCache::new(10);
+    let state_diff = StateDiff {
+        deployed_contracts: [
+            (contract_address!("0x1"), class_hash!("0x10")),
+            (contract_address!("0x2"), class_hash!("0x20")),
+        ]
+        .into(),
+        ..Default::default()
+    };
+    cache.push_block(BlockNumber(0), BlockStateWrites::from(&state_diff));
+    let state_diff = StateDiff {
+        replaced_classes: [(contract_address!("0x1"), class_hash!("0x11"))].into(),
+        ..Default::default()
+    };
+    cache.push_block(BlockNumber(1), BlockStateWrites::from(&state_diff));
+
+    assert_eq!(
+        cache.get_class_hash_at(BlockNumber(0), contract_address!("0x1")),
+        Some(class_hash!("0x10"))
+    );
+    assert_eq!(
+        cache.get_class_hash_at(BlockNumber(1), contract_address!("0x1")),
+        Some(class_hash!("0x11"))
+    );
+    assert_eq!(
+        cache.get_class_hash_at(BlockNumber(1), contract_address!("0x2")),
+        Some(class_hash!("0x20"))
+    );
+    assert_eq!(cache.get_class_hash_at(BlockNumber(1), contract_address!("0x3")), None);
+}
+
+#[test]
+fn default_cache_holds_nothing() {
+    let cache = RecentStateCache::default();
+    cache.push_block(BlockNumber(0), storage_write(1));
+    assert_eq!(get_storage_value(&cache, 0), None);
+}
+
+#[test]
+fn oldest_blocks_evicted_beyond_memory_budget() {
+    let block_size = storage_write(0).memory_size();
+    let cache = RecentStateCache::with_max_bytes(10, Some(2 * block_size));
+    cache.push_block(BlockNumber(0), storage_write(1));
+    cache.push_block(BlockNumber(1), storage_write(2));
+    cache.push_block(BlockNumber(2), storage_write(3));
+
+    // The writes of the oldest block were evicted to make room for the newest.
+    assert_eq!(get_storage_value(&cache, 0), None);
+    assert_eq!(get_storage_value(&cache, 1), Some(StarkFelt::from(2_u64)));
+    assert_eq!(get_storage_value(&cache, 2), Some(StarkFelt::from(3_u64)));
+}
@@ -0,0 +1,74 @@
+#[cfg(test)]
+#[path = "rpc_load_test.rs"]
+mod rpc_load_test;
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// The time window in which request latencies are taken into account.
+const DEFAULT_LATENCY_WINDOW: Duration = Duration::from_secs(60);
+// The maximal number of latency samples kept, to bound the memory usage under heavy load.
+const MAX_LATENCY_SAMPLES: usize = 10000;
+
+/// A load signal shared between the RPC server, which records the latency of every request it
+/// serves, and the sync, which slows down while the RPC server is under heavy load.
+#[derive(Debug)]
+pub struct RpcLoad {
+    window: Duration,
+    // Pairs of (request end time, request latency), ordered by the request end time.
+    latencies: Mutex<VecDeque<(Instant, Duration)>>,
+}
+
+impl Default for RpcLoad {
+    fn default() -> Self {
+        Self::new(DEFAULT_LATENCY_WINDOW)
+    }
+}
+
+impl RpcLoad {
+    /// Creates a load signal that takes into account requests that ended in the last `window`.
+    pub fn new(window: Duration) -> Self {
+        Self { window, latencies: Mutex::new(VecDeque::new()) }
+    }
+
+    /// Records the latency of a request that just ended.
+    pub fn record_latency(&self, latency: Duration) {
+        let now = Instant::now();
+        let mut latencies = self.latencies.lock().expect("RpcLoad lock should not be poisoned");
+        Self::remove_old_samples(&mut latencies, now, self.window);
+        if latencies.len() == MAX_LATENCY_SAMPLES {
+            latencies.pop_front();
+        }
+        latencies.push_back((now, latency));
+    }
+
+    /// Returns the 95th percentile of the latency of the recent requests, or None if there were no
+    /// recent requests.
+    pub fn p95_latency(&self) -> Option<Duration> {
+        // The samples are copied so that the recording of latencies doesn't wait for the selection.
+        let mut recent_latencies: Vec<_> = {
+            let mut latencies = self.latencies.lock().expect("RpcLoad lock should not be poisoned");
+            Self::remove_old_samples(&mut latencies, Instant::now(), self.window);
+            latencies.iter().map(|(_, latency)| *latency).collect()
+        };
+        if recent_latencies.is_empty() {
+            return None;
+        }
+        let index = (recent_latencies.len() * 95).div_ceil(100) - 1;
+        Some(*recent_latencies.select_nth_unstable(index).1)
+    }
+
+    fn remove_old_samples(
+        latencies: &mut VecDeque<(Instant, Duration)>,
+        now: Instant,
+        window: Duration,
+    ) {
+        while let Some((end_time, _)) = latencies.front() {
+            if now.duration_since(*end_time) <= window {
+                break;
+            }
+            latencies.pop_front();
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use std::time::Duration;
+
+use pretty_assertions::assert_eq;
+
+use crate::rpc_load::RpcLoad;
+
+#[test]
+fn p95_latency() {
+    let rpc_load = RpcLoad::default();
+    assert_eq!(rpc_load.p95_latency(), None);
+
+    for millis in 1..=100 {
+        rpc_load.record_latency(Duration::from_millis(millis));
+    }
+    assert_eq!(rpc_load.p95_latency(), Some(Duration::from_millis(95)));
+}
+
+#[test]
+fn old_latencies_are_ignored() {
+    let rpc_load = RpcLoad::new(Duration::from_millis(10));
+    rpc_load.record_latency(Duration::from_secs(5));
+    assert_eq!(rpc_load.p95_latency(), Some(Duration::from_secs(5)));
+
+    std::thread::sleep(Duration::from_millis(20));
+    assert_eq!(rpc_load.p95_latency(), None);
+}
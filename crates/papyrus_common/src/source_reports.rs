@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use tokio::sync::{mpsc, Mutex};
+
+/// A report about a request to a data source, e.g. a central feeder gateway or a P2P peer, to
+/// record in its reputation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SourceReport {
+    /// A request to the source succeeded and took the given time.
+    Success { source_id: String, latency: Duration },
+    /// A request to the source failed.
+    Error { source_id: String },
+    /// The source was banned at the given unix timestamp, in seconds.
+    Ban { source_id: String, timestamp: u64 },
+}
+
+/// Reports about data sources shared between the components that fetch data from the sources but
+/// can't write to the storage, e.g. the central source and the P2P network, and the component that
+/// owns the storage writer and stores them. The sources are identified as keyed in the reputation
+/// storage.
+#[derive(Debug)]
+pub struct SourceReports {
+    report_sender: mpsc::UnboundedSender<SourceReport>,
+    report_receiver: Mutex<mpsc::UnboundedReceiver<SourceReport>>,
+}
+
+impl Default for SourceReports {
+    fn default() -> Self {
+        let (report_sender, report_receiver) = mpsc::unbounded_channel();
+        Self { report_sender, report_receiver: Mutex::new(report_receiver) }
+    }
+}
+
+impl SourceReports {
+    /// Queues a successful request that took the given time to be stored.
+    pub fn report_success(&self, source_id: String, latency: Duration) {
+        self.report(SourceReport::Success { source_id, latency });
+    }
+
+    /// Queues a failed request to be stored.
+    pub fn report_error(&self, source_id: String) {
+        self.report(SourceReport::Error { source_id });
+    }
+
+    /// Queues the ban to be stored.
+    pub fn ban(&self, source_id: String, timestamp: u64) {
+        self.report(SourceReport::Ban { source_id, timestamp });
+    }
+
+    /// Waits for a report to store, and returns it along with the rest of the queued reports, so
+    /// that they're stored together.
+    pub async fn next_reports(&self) -> Vec<SourceReport> {
+        let mut report_receiver = self.report_receiver.lock().await;
+        let first_report = report_receiver
+            .recv()
+            .await
+            .expect("The report sender should be owned by the reports.");
+        let mut reports = vec![first_report];
+        while let Ok(report) = report_receiver.try_recv() {
+            reports.push(report);
+        }
+        reports
+    }
+
+    fn report(&self, report: SourceReport) {
+        // Sending can't fail since the receiver is owned by self.
+        let _ = self.report_sender.send(report);
+    }
+}
@@ -2,13 +2,19 @@
 #[path = "state_diff_commitment_test.rs"]
 mod state_diff_commitment_test;
 
+use starknet_api::block::StarknetVersion;
 use starknet_api::core::GlobalRoot;
 use starknet_api::data_availability::DataAvailabilityMode;
-use starknet_api::hash::{poseidon_hash_array, StarkFelt};
+use starknet_api::hash::StarkFelt;
 use starknet_api::state::ThinStateDiff;
 
+use crate::hashing::poseidon_hash_many;
+
 const SUPPORTED_DA_MODES_STATE_DIFF_V0: [DataAvailabilityMode; 1] = [DataAvailabilityMode::L1];
 
+// The first Starknet version whose blocks are signed over a state diff commitment of version V0.
+const FIRST_STARKNET_VERSION_STATE_DIFF_V0: [u64; 3] = [0, 12, 2];
+
 /// The version of the state diff for the state diff commitment.
 // The version is used to support different data availability modes, currently only L1.
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq, PartialOrd, Ord)]
@@ -18,6 +24,20 @@ pub enum StateDiffVersion {
 }
 
 impl StateDiffVersion {
+    /// Returns the state diff version of the commitment that blocks of the given Starknet version
+    /// are signed over, or None if the commitment of the version can't be computed, e.g. for blocks
+    /// that predate the state diff commitment.
+    pub fn of_starknet_version(starknet_version: &StarknetVersion) -> Option<Self> {
+        // The early blocks have an empty version, which has no commitment.
+        let version = starknet_version
+            .0
+            .split('.')
+            .map(|component| component.parse().ok())
+            .collect::<Option<Vec<u64>>>()?;
+        (version.as_slice() >= FIRST_STARKNET_VERSION_STATE_DIFF_V0.as_slice())
+            .then_some(StateDiffVersion::V0)
+    }
+
     /// Returns the supported data availability modes for the given state diff version.
     pub fn supported_da_modes(&self) -> Vec<DataAvailabilityMode> {
         match self {
@@ -54,8 +74,8 @@ pub fn calculate_state_diff_commitment(
         flattened_deployed_contracts.push(*contract_address.0.key());
         flattened_deployed_contracts.push(class_hash.0);
     }
-    let hash_of_deployed_contracts = poseidon_hash_array(&flattened_deployed_contracts);
-    flattened_total_state_diff.push(hash_of_deployed_contracts.0);
+    let hash_of_deployed_contracts = poseidon_hash_many(&flattened_deployed_contracts);
+    flattened_total_state_diff.push(hash_of_deployed_contracts);
 
     let mut flattened_declared_classes =
         vec![StarkFelt::from(state_diff.declared_classes.len() as u64)];
@@ -63,8 +83,8 @@ pub fn calculate_state_diff_commitment(
         flattened_declared_classes.push(class_hash.0);
         flattened_declared_classes.push(compiled_class_hash.0);
     }
-    let hash_of_declared_classes = poseidon_hash_array(&flattened_declared_classes);
-    flattened_total_state_diff.push(hash_of_declared_classes.0);
+    let hash_of_declared_classes = poseidon_hash_many(&flattened_declared_classes);
+    flattened_total_state_diff.push(hash_of_declared_classes);
 
     let mut flattened_deprecated_declared_classes =
         vec![StarkFelt::from(state_diff.deprecated_declared_classes.len() as u64)];
@@ -72,8 +92,8 @@ pub fn calculate_state_diff_commitment(
         flattened_deprecated_declared_classes.push(class_hash.0);
     }
     let hash_of_deprecated_declared_classes =
-        poseidon_hash_array(&flattened_deprecated_declared_classes);
-    flattened_total_state_diff.push(hash_of_deprecated_declared_classes.0);
+        poseidon_hash_many(&flattened_deprecated_declared_classes);
+    flattened_total_state_diff.push(hash_of_deprecated_declared_classes);
 
     let da_modes = state_diff_version.supported_da_modes();
     flattened_total_state_diff.push(StarkFelt::from(da_modes.len() as u64));
@@ -100,9 +120,9 @@ pub fn calculate_state_diff_commitment(
         let storage_diffs_and_nonces: Vec<_> =
             flattened_storage_diffs.into_iter().chain(flattened_nonces).collect();
         let hash_of_storage_domain_state_diff =
-            poseidon_hash_array(storage_diffs_and_nonces.as_slice());
-        flattened_total_state_diff.push(hash_of_storage_domain_state_diff.0);
+            poseidon_hash_many(storage_diffs_and_nonces.as_slice());
+        flattened_total_state_diff.push(hash_of_storage_domain_state_diff);
     }
 
-    GlobalRoot(poseidon_hash_array(flattened_total_state_diff.as_slice()).0)
+    GlobalRoot(poseidon_hash_many(flattened_total_state_diff.as_slice()))
 }
@@ -1,4 +1,5 @@
 use pretty_assertions::assert_eq;
+use starknet_api::block::StarknetVersion;
 use starknet_api::core::{
     ClassHash,
     CompiledClassHash,
@@ -59,3 +60,21 @@ fn empty_storage_diff() {
         calculate_state_diff_commitment(&state_diff_with_empty_storage_diff, StateDiffVersion::V0)
     );
 }
+
+#[test]
+fn state_diff_version_of_starknet_version() {
+    for (starknet_version, expected_state_diff_version) in [
+        ("", None),
+        ("0.11.0", None),
+        ("0.12.1", None),
+        ("0.12.2", Some(StateDiffVersion::V0)),
+        ("0.13.1", Some(StateDiffVersion::V0)),
+        ("not a version", None),
+    ] {
+        assert_eq!(
+            StateDiffVersion::of_starknet_version(&StarknetVersion(starknet_version.to_owned())),
+            expected_state_diff_version,
+            "Starknet version {starknet_version:?}"
+        );
+    }
+}
@@ -0,0 +1,54 @@
+#[cfg(test)]
+#[path = "sync_notifications_test.rs"]
+mod sync_notifications_test;
+
+use starknet_api::block::{BlockHash, BlockNumber};
+use tokio::sync::broadcast;
+
+// The number of notifications kept for subscribers that didn't receive them yet. Subscribers that
+// fall further behind miss the oldest notifications.
+const NOTIFICATIONS_CAPACITY: usize = 1000;
+
+/// A change the sync made to the chain in the storage.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SyncNotification {
+    /// The header and the body of the block were stored.
+    BlockStored { block_number: BlockNumber, block_hash: BlockHash },
+    /// The state diff of the block was stored.
+    StateDiffStored { block_number: BlockNumber },
+    /// The block, and all the blocks before it, were accepted on the base layer.
+    BlockAcceptedOnBaseLayer { block_number: BlockNumber },
+    /// The block, and all the blocks after it, are no longer accepted on the base layer because of
+    /// a reorg of the base layer.
+    BlockRevertedOnBaseLayer { block_number: BlockNumber },
+    /// The block was removed from the storage because of a reorg.
+    BlockReverted { block_number: BlockNumber },
+}
+
+/// A channel shared between the sync, which notifies about every change it makes to the chain, and
+/// the RPC server, whose subscriptions are driven by these notifications.
+#[derive(Debug)]
+pub struct SyncNotifier {
+    sender: broadcast::Sender<SyncNotification>,
+}
+
+impl Default for SyncNotifier {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(NOTIFICATIONS_CAPACITY);
+        Self { sender }
+    }
+}
+
+impl SyncNotifier {
+    /// Sends the notification to all the current subscribers.
+    pub fn notify(&self, notification: SyncNotification) {
+        // Sending fails only when there are no subscribers, in which case nobody is interested in
+        // the notification.
+        let _ = self.sender.send(notification);
+    }
+
+    /// Returns a receiver of all the notifications sent from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<SyncNotification> {
+        self.sender.subscribe()
+    }
+}
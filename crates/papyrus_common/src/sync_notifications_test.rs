@@ -0,0 +1,27 @@
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockHash, BlockNumber};
+
+use crate::sync_notifications::{SyncNotification, SyncNotifier};
+
+#[test]
+fn subscribers_receive_notifications_sent_after_subscribing() {
+    let notifier = SyncNotifier::default();
+    // Notifying without subscribers doesn't fail.
+    notifier.notify(SyncNotification::BlockReverted { block_number: BlockNumber(0) });
+
+    let mut first_subscriber = notifier.subscribe();
+    let stored = SyncNotification::BlockStored {
+        block_number: BlockNumber(0),
+        block_hash: BlockHash::default(),
+    };
+    notifier.notify(stored);
+    let mut second_subscriber = notifier.subscribe();
+    let accepted = SyncNotification::BlockAcceptedOnBaseLayer { block_number: BlockNumber(0) };
+    notifier.notify(accepted);
+
+    assert_eq!(first_subscriber.try_recv().unwrap(), stored);
+    assert_eq!(first_subscriber.try_recv().unwrap(), accepted);
+    assert!(first_subscriber.try_recv().is_err());
+    assert_eq!(second_subscriber.try_recv().unwrap(), accepted);
+    assert!(second_subscriber.try_recv().is_err());
+}
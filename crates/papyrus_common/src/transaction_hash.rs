@@ -32,8 +32,9 @@ use starknet_api::transaction::{
     TransactionVersion,
 };
 use starknet_api::StarknetApiError;
-use starknet_crypto::{pedersen_hash, poseidon_hash_many, FieldElement};
 
+use crate::chain_id::{ascii_as_felt, ChainIdExt};
+use crate::hashing::{pedersen_hash_array, poseidon_hash_many};
 use crate::TransactionOptions;
 
 type ResourceName = [u8; 7];
@@ -126,34 +127,30 @@ fn get_deprecated_transaction_hashes(
     transaction_options: &TransactionOptions,
 ) -> Result<Vec<TransactionHash>, StarknetApiError> {
     let tx_version = get_tx_version(transaction, transaction_options);
-    Ok(
-        if chain_id == &ChainId("SN_MAIN".to_string())
-            && block_number > &MAINNET_TRANSACTION_HASH_WITH_VERSION
-        {
-            vec![]
-        } else {
-            match transaction {
-                Transaction::Declare(_) => vec![],
-                Transaction::Deploy(deploy) => {
-                    vec![get_deprecated_deploy_transaction_hash(deploy, chain_id, &tx_version)?]
-                }
-                Transaction::DeployAccount(_) => vec![],
-                Transaction::Invoke(invoke) => match invoke {
-                    InvokeTransaction::V0(invoke_v0) => {
-                        vec![get_deprecated_invoke_transaction_v0_hash(
-                            invoke_v0,
-                            chain_id,
-                            &tx_version,
-                        )?]
-                    }
-                    InvokeTransaction::V1(_) | InvokeTransaction::V3(_) => vec![],
-                },
-                Transaction::L1Handler(l1_handler) => {
-                    get_deprecated_l1_handler_transaction_hashes(l1_handler, chain_id, &tx_version)?
+    Ok(if chain_id.is_mainnet() && block_number > &MAINNET_TRANSACTION_HASH_WITH_VERSION {
+        vec![]
+    } else {
+        match transaction {
+            Transaction::Declare(_) => vec![],
+            Transaction::Deploy(deploy) => {
+                vec![get_deprecated_deploy_transaction_hash(deploy, chain_id, &tx_version)?]
+            }
+            Transaction::DeployAccount(_) => vec![],
+            Transaction::Invoke(invoke) => match invoke {
+                InvokeTransaction::V0(invoke_v0) => {
+                    vec![get_deprecated_invoke_transaction_v0_hash(
+                        invoke_v0,
+                        chain_id,
+                        &tx_version,
+                    )?]
                 }
+                InvokeTransaction::V1(_) | InvokeTransaction::V3(_) => vec![],
+            },
+            Transaction::L1Handler(l1_handler) => {
+                get_deprecated_l1_handler_transaction_hashes(l1_handler, chain_id, &tx_version)?
             }
-        },
-    )
+        }
+    })
 }
 
 /// Validates the hash of a starknet transaction.
@@ -180,7 +177,7 @@ pub fn validate_transaction_hash(
 
 // Collect elements for applying hash chain.
 pub(crate) struct HashChain {
-    elements: Vec<FieldElement>,
+    elements: Vec<StarkFelt>,
 }
 
 impl HashChain {
@@ -190,7 +187,7 @@ impl HashChain {
 
     // Chains a felt to the hash chain.
     pub fn chain(mut self, felt: &StarkFelt) -> Self {
-        self.elements.push(FieldElement::from(*felt));
+        self.elements.push(*felt);
         self
     }
 
@@ -216,24 +213,15 @@ impl HashChain {
 
     // Returns the pedersen hash of the chained felts, hashed with the length of the chain.
     pub fn get_pedersen_hash(&self) -> StarkHash {
-        let current_hash = self
-            .elements
-            .iter()
-            .fold(FieldElement::ZERO, |current_hash, felt| pedersen_hash(&current_hash, felt));
-        let n_elements = FieldElement::from(self.elements.len());
-        pedersen_hash(&current_hash, &n_elements).into()
+        pedersen_hash_array(&self.elements)
     }
 
     // Returns the poseidon hash of the chained felts.
     pub fn get_poseidon_hash(&self) -> StarkHash {
-        poseidon_hash_many(&self.elements).into()
+        poseidon_hash_many(&self.elements)
     }
 }
 
-pub(crate) fn ascii_as_felt(ascii_str: &str) -> Result<StarkFelt, StarknetApiError> {
-    StarkFelt::try_from(hex::encode(ascii_str).as_str())
-}
-
 // An implementation of the SNIP: https://github.com/EvyatarO/SNIPs/blob/snip-8/SNIPS/snip-8.md
 fn get_tip_resource_bounds_hash(
     resource_bounds_mapping: &ResourceBoundsMapping,
@@ -329,7 +317,7 @@ fn get_common_deploy_transaction_hash(
                 .get_pedersen_hash(),
         )
         .chain_if(&ZERO, !is_deprecated) // No fee in deploy transaction.
-        .chain(&ascii_as_felt(chain_id.0.as_str())?)
+        .chain(&chain_id.to_felt()?)
         .get_pedersen_hash(),
     ))
 }
@@ -364,7 +352,7 @@ fn get_common_invoke_transaction_v0_hash(
         .chain(&transaction.entry_point_selector.0)
         .chain(&HashChain::new().chain_iter(transaction.calldata.0.iter()).get_pedersen_hash())
         .chain_if(&transaction.max_fee.0.into(), !is_deprecated)
-        .chain(&ascii_as_felt(chain_id.0.as_str())?)
+        .chain(&chain_id.to_felt()?)
         .get_pedersen_hash(),
     ))
 }
@@ -382,7 +370,7 @@ fn get_invoke_transaction_v1_hash(
         .chain(&ZERO) // No entry point selector in invoke transaction.
         .chain(&HashChain::new().chain_iter(transaction.calldata.0.iter()).get_pedersen_hash())
         .chain(&transaction.max_fee.0.into())
-        .chain(&ascii_as_felt(chain_id.0.as_str())?)
+        .chain(&chain_id.to_felt()?)
         .chain(&transaction.nonce.0)
         .get_pedersen_hash(),
     ))
@@ -414,7 +402,7 @@ fn get_invoke_transaction_v3_hash(
             .chain(transaction.sender_address.0.key())
             .chain(&tip_resource_bounds_hash)
             .chain(&paymaster_data_hash)
-            .chain(&ascii_as_felt(chain_id.0.as_str())?)
+            .chain(&chain_id.to_felt()?)
             .chain(&transaction.nonce.0)
             .chain(&data_availability_mode)
             .chain(&account_deployment_data_hash)
@@ -478,7 +466,7 @@ fn get_common_l1_handler_transaction_hash(
         .chain(&transaction.entry_point_selector.0)
         .chain(&HashChain::new().chain_iter(transaction.calldata.0.iter()).get_pedersen_hash())
         .chain_if(&ZERO, version > L1HandlerVersions::V0Deprecated) // No fee in l1 handler transaction.
-        .chain(&ascii_as_felt(chain_id.0.as_str())?)
+        .chain(&chain_id.to_felt()?)
         .chain_if(&transaction.nonce.0, version > L1HandlerVersions::AsInvoke)
         .get_pedersen_hash(),
     ))
@@ -497,7 +485,7 @@ fn get_declare_transaction_v0_hash(
         .chain(&ZERO ) // No entry point selector in declare transaction.
         .chain(&HashChain::new().get_pedersen_hash())
         .chain(&transaction.max_fee.0.into())
-        .chain(&ascii_as_felt(chain_id.0.as_str())?)
+        .chain(&chain_id.to_felt()?)
         .chain(&transaction.class_hash.0)
         .get_pedersen_hash(),
     ))
@@ -516,7 +504,7 @@ fn get_declare_transaction_v1_hash(
         .chain(&ZERO) // No entry point selector in declare transaction.
         .chain(&HashChain::new().chain(&transaction.class_hash.0).get_pedersen_hash())
         .chain(&transaction.max_fee.0.into())
-        .chain(&ascii_as_felt(chain_id.0.as_str())?)
+        .chain(&chain_id.to_felt()?)
         .chain(&transaction.nonce.0)
         .get_pedersen_hash(),
     ))
@@ -535,7 +523,7 @@ fn get_declare_transaction_v2_hash(
         .chain(&ZERO) // No entry point selector in declare transaction.
         .chain(&HashChain::new().chain(&transaction.class_hash.0).get_pedersen_hash())
         .chain(&transaction.max_fee.0.into())
-        .chain(&ascii_as_felt(chain_id.0.as_str())?)
+        .chain(&chain_id.to_felt()?)
         .chain(&transaction.nonce.0)
         .chain(&transaction.compiled_class_hash.0)
         .get_pedersen_hash(),
@@ -566,7 +554,7 @@ fn get_declare_transaction_v3_hash(
             .chain(transaction.sender_address.0.key())
             .chain(&tip_resource_bounds_hash)
             .chain(&paymaster_data_hash)
-            .chain(&ascii_as_felt(chain_id.0.as_str())?)
+            .chain(&chain_id.to_felt()?)
             .chain(&transaction.nonce.0)
             .chain(&data_availability_mode)
             .chain(&account_deployment_data_hash)
@@ -602,7 +590,7 @@ fn get_deploy_account_transaction_v1_hash(
         .chain(&ZERO) // No entry point selector in deploy account transaction.
         .chain(&calldata_hash)
         .chain(&transaction.max_fee.0.into())
-        .chain(&ascii_as_felt(chain_id.0.as_str())?)
+        .chain(&chain_id.to_felt()?)
         .chain(&transaction.nonce.0)
         .get_pedersen_hash(),
     ))
@@ -637,7 +625,7 @@ fn get_deploy_account_transaction_v3_hash(
             .chain(contract_address.0.key())
             .chain(&tip_resource_bounds_hash)
             .chain(&paymaster_data_hash)
-            .chain(&ascii_as_felt(chain_id.0.as_str())?)
+            .chain(&chain_id.to_felt()?)
             .chain(&data_availability_mode)
             .chain(&transaction.nonce.0)
             .chain(&constructor_calldata_hash)
@@ -1,37 +1,17 @@
 use pretty_assertions::assert_eq;
 use serde::{Deserialize, Serialize};
-use sha3::{Digest, Keccak256};
 use starknet_api::block::BlockNumber;
 use starknet_api::core::ChainId;
-use starknet_api::hash::StarkFelt;
 use starknet_api::transaction::{Transaction, TransactionHash};
-use test_utils::read_json_file;
+use test_utils::{get_rng, get_test_transactions_with_hashes, read_json_file};
 
-use super::{
-    ascii_as_felt,
-    get_transaction_hash,
-    validate_transaction_hash,
-    CONSTRUCTOR_ENTRY_POINT_SELECTOR,
-};
+use super::{get_transaction_hash, validate_transaction_hash, CONSTRUCTOR_ENTRY_POINT_SELECTOR};
+use crate::hashing::starknet_keccak;
 use crate::TransactionOptions;
 
-#[test]
-fn test_ascii_as_felt() {
-    let sn_main_id = ChainId("SN_MAIN".to_owned());
-    let sn_main_felt = ascii_as_felt(sn_main_id.0.as_str()).unwrap();
-    // This is the result of the Python snippet from the Chain-Id documentation.
-    let expected_sn_main = StarkFelt::from(23448594291968334_u128);
-    assert_eq!(sn_main_felt, expected_sn_main);
-}
-
 #[test]
 fn test_constructor_selector() {
-    let mut keccak = Keccak256::default();
-    keccak.update(b"constructor");
-    let mut constructor_bytes: [u8; 32] = keccak.finalize().into();
-    constructor_bytes[0] &= 0b00000011_u8; // Discard the six MSBs.
-    let constructor_felt = StarkFelt::new(constructor_bytes).unwrap();
-    assert_eq!(constructor_felt, *CONSTRUCTOR_ENTRY_POINT_SELECTOR);
+    assert_eq!(starknet_keccak(b"constructor"), *CONSTRUCTOR_ENTRY_POINT_SELECTOR);
 }
 
 #[derive(Deserialize, Serialize)]
@@ -125,3 +105,23 @@ fn test_only_query_transaction_hash() {
         );
     }
 }
+
+#[test]
+fn test_generated_transactions_hash() {
+    let chain_id = ChainId("SN_GOERLI".to_owned());
+    for (transaction, transaction_hash) in
+        get_test_transactions_with_hashes(&mut get_rng(), &chain_id)
+    {
+        assert!(
+            validate_transaction_hash(
+                &transaction,
+                &BlockNumber(0),
+                &chain_id,
+                transaction_hash,
+                &TransactionOptions::default(),
+            )
+            .unwrap(),
+            "Generated transaction {transaction:?} has an invalid hash."
+        );
+    }
+}
@@ -11,7 +11,7 @@ use blockifier::versioned_constants::VersionedConstants;
 use indexmap::indexmap;
 use papyrus_storage::test_utils::get_test_storage;
 use pretty_assertions::assert_eq;
-use starknet_api::block::BlockNumber;
+use starknet_api::block::{BlockNumber, GasPrice, GasPricePerToken};
 use starknet_api::core::{
     ChainId,
     ClassHash,
@@ -20,6 +20,7 @@ use starknet_api::core::{
     Nonce,
     PatriciaKey,
 };
+use starknet_api::data_availability::L1DataAvailabilityMode;
 use starknet_api::hash::{StarkFelt, StarkHash};
 use starknet_api::state::{StateNumber, ThinStateDiff};
 use starknet_api::transaction::{Calldata, Fee};
@@ -31,7 +32,9 @@ use crate::objects::{
     DeployAccountTransactionTrace,
     FunctionInvocationResult,
     InvokeTransactionTrace,
+    PendingData,
     PriceUnit,
+    StateOverride,
     TransactionSimulationOutput,
     TransactionTrace,
 };
@@ -42,6 +45,7 @@ use crate::test_utils::{
     ACCOUNT_ADDRESS,
     ACCOUNT_CLASS_HASH,
     ACCOUNT_INITIAL_BALANCE,
+    BLOCK_TIMESTAMP,
     CHAIN_ID,
     CONTRACT_ADDRESS,
     DEPRECATED_CONTRACT_ADDRESS,
@@ -54,6 +58,8 @@ use crate::testing_instances::{test_block_execution_config, test_get_default_exe
 use crate::{
     estimate_fee,
     execute_call,
+    execute_call_with_state_override,
+    get_versioned_constants,
     BlockExecutionConfig,
     ExecutableTransactionInput,
     ExecutionConfigByBlock,
@@ -164,6 +170,90 @@ fn execute_call_cairo1() {
     assert_eq!(retdata, Retdata(vec![value]));
 }
 
+#[test]
+fn execute_call_with_overridden_state() {
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage(storage_writer);
+
+    let call = |contract_address: ContractAddress,
+                entry_point: &str,
+                calldata: Calldata,
+                state_override: &StateOverride| {
+        execute_call_with_state_override(
+            storage_reader.clone(),
+            None,
+            &CHAIN_ID,
+            StateNumber::right_after_block(BlockNumber(0)),
+            BlockNumber(0),
+            &contract_address,
+            selector_from_name(entry_point),
+            calldata,
+            &test_block_execution_config(),
+            state_override,
+        )
+        .map(|call_execution| call_execution.retdata)
+    };
+    let balance_of = |state_override: &StateOverride| {
+        call(
+            *TEST_ERC20_CONTRACT_ADDRESS,
+            "balanceOf",
+            calldata![*ACCOUNT_ADDRESS.0.key()],
+            state_override,
+        )
+        .unwrap()
+    };
+
+    // Without an override, the balance is read from the storage.
+    assert_eq!(
+        balance_of(&StateOverride::default()),
+        Retdata(vec![*ACCOUNT_INITIAL_BALANCE, StarkFelt::ZERO])
+    );
+
+    // Overriding the balance.
+    let balance = stark_felt!(1234_u16);
+    let balance_override =
+        StateOverride { balances: indexmap!(*ACCOUNT_ADDRESS => balance), ..Default::default() };
+    assert_eq!(balance_of(&balance_override), Retdata(vec![balance, StarkFelt::ZERO]));
+
+    // A balance that doesn't fit in 128 bits isn't split into the high word.
+    let overflowing_override = StateOverride {
+        balances: indexmap!(*ACCOUNT_ADDRESS => stark_felt!("0x100000000000000000000000000000000")),
+        ..Default::default()
+    };
+    assert_matches!(
+        call(
+            *TEST_ERC20_CONTRACT_ADDRESS,
+            "balanceOf",
+            calldata![*ACCOUNT_ADDRESS.0.key()],
+            &overflowing_override,
+        ),
+        Err(ExecutionError::BalanceOverflow { contract_address }) if contract_address == *ACCOUNT_ADDRESS
+    );
+
+    // Overriding the storage of the balance.
+    let balance_key = get_storage_var_address("ERC20_balances", &[*ACCOUNT_ADDRESS.0.key()]);
+    let storage_override = StateOverride {
+        storage: indexmap!(*TEST_ERC20_CONTRACT_ADDRESS => indexmap!(balance_key => balance)),
+        ..Default::default()
+    };
+    assert_eq!(balance_of(&storage_override), Retdata(vec![balance, StarkFelt::ZERO]));
+
+    // Deploying a contract by overriding the class of an address that isn't deployed.
+    let undeployed_address = contract_address!("0x999");
+    assert_matches!(
+        call(undeployed_address, "return_result", calldata![balance], &StateOverride::default()),
+        Err(ExecutionError::ContractNotFound { .. })
+    );
+    let class_override = StateOverride {
+        class_hashes: indexmap!(undeployed_address => class_hash!("0x1")),
+        ..Default::default()
+    };
+    assert_eq!(
+        call(undeployed_address, "return_result", calldata![balance], &class_override).unwrap(),
+        Retdata(vec![balance])
+    );
+}
+
 // TODO(yair): Compare to the expected fee instead of asserting that it is not zero (all
 // estimate_fee tests).
 #[test]
@@ -239,6 +329,47 @@ fn estimate_fee_reverted() {
     assert_matches!(failed_estimation, RevertedTransaction { index: 1, revert_reason: _ })
 }
 
+#[test]
+fn estimate_fee_charges_data_gas_of_blob_blocks() {
+    let tx = TxsScenarioBuilder::default()
+        .invoke_deprecated(*ACCOUNT_ADDRESS, *DEPRECATED_CONTRACT_ADDRESS, None, false)
+        .collect();
+    let estimate_fee_in_pending_block = |l1_da_mode| {
+        let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+        prepare_storage(storage_writer);
+        let pending_data = PendingData {
+            l1_gas_price: *GAS_PRICE,
+            l1_data_gas_price: GasPricePerToken {
+                price_in_wei: GasPrice(1000 * GAS_PRICE.price_in_wei.0),
+                price_in_fri: GasPrice::default(),
+            },
+            l1_da_mode,
+            sequencer: *SEQUENCER_ADDRESS,
+            timestamp: *BLOCK_TIMESTAMP,
+            ..Default::default()
+        };
+        estimate_fee(
+            tx.clone(),
+            &CHAIN_ID,
+            storage_reader,
+            Some(pending_data),
+            StateNumber::right_after_block(BlockNumber(1)),
+            BlockNumber(1),
+            &test_block_execution_config(),
+            false,
+        )
+        .unwrap()
+        .expect("Fee estimation should succeed.")[0]
+            .1
+    };
+
+    // The data of a block published in calldata is charged by the gas price, and the data of a
+    // block published in blobs by the much higher data gas price.
+    let calldata_fee = estimate_fee_in_pending_block(L1DataAvailabilityMode::Calldata);
+    let blob_fee = estimate_fee_in_pending_block(L1DataAvailabilityMode::Blob);
+    assert!(blob_fee > calldata_fee);
+}
+
 fn estimate_fees(txs: Vec<ExecutableTransactionInput>) -> FeeEstimationResult {
     let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
     prepare_storage(storage_writer);
@@ -666,6 +797,9 @@ fn test_default_execution_config() {
         fee_contract_address: contract_address!(
             "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7"
         ),
+        strk_fee_contract_address: contract_address!(
+            "0x04718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d"
+        ),
         invoke_tx_max_n_steps: 3_000_000,
         validate_tx_max_n_steps: 1_000_000,
         max_recursion_depth: 50,
@@ -681,11 +815,23 @@ fn test_default_execution_config() {
     assert_eq!(expected_config, config_from_file);
 }
 
+// Execution configs that were written before the STRK fee token was supported still load.
+#[test]
+fn strk_fee_contract_address_has_a_default() {
+    let mut config = serde_json::to_value(test_get_default_execution_config()).unwrap();
+    let segment = &mut config["execution_config_segments"]["0"];
+    segment.as_object_mut().unwrap().remove("strk_fee_contract_address").unwrap();
+
+    let config: ExecutionConfigByBlock = serde_json::from_value(config).unwrap();
+    assert_eq!(config, test_get_default_execution_config());
+}
+
 fn fill_up_block_execution_config_segment_with_value(value: usize) -> BlockExecutionConfig {
     let vm_resource_fee_cost = HashMap::new();
     let vm_resource_fee_cost = Arc::new(vm_resource_fee_cost);
     BlockExecutionConfig {
         fee_contract_address: contract_address!(format!("{:x}", value).as_str()),
+        strk_fee_contract_address: contract_address!(format!("{:x}", value).as_str()),
         invoke_tx_max_n_steps: value as u32,
         validate_tx_max_n_steps: value as u32,
         max_recursion_depth: value,
@@ -735,6 +881,19 @@ fn test_get_execution_config_for_block() {
     );
 }
 
+#[test]
+fn versioned_constants_use_the_execution_config_limits() {
+    let mut execution_config = test_block_execution_config();
+    execution_config.invoke_tx_max_n_steps = 17;
+    execution_config.validate_tx_max_n_steps = 13;
+    execution_config.max_recursion_depth = 7;
+
+    let versioned_constants = get_versioned_constants(&execution_config, None).unwrap();
+    assert_eq!(versioned_constants.invoke_tx_max_n_steps, 17);
+    assert_eq!(versioned_constants.validate_max_n_steps, 13);
+    assert_eq!(versioned_constants.max_recursion_depth, 7);
+}
+
 #[test]
 fn induced_state_diff() {
     let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
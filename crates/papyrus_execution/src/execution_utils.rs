@@ -1,7 +1,9 @@
 //! Utilities for executing contracts and transactions.
 use std::fs::File;
 use std::path::PathBuf;
+use std::sync::Arc;
 
+use blockifier::abi::abi_utils::get_storage_var_address;
 // Expose the tool for creating entry point selectors from function names.
 pub use blockifier::abi::abi_utils::selector_from_name;
 use blockifier::execution::contract_class::{
@@ -14,6 +16,7 @@ use blockifier::state::state_api::{State, StateReader};
 use blockifier::transaction::objects::TransactionExecutionInfo;
 use cairo_vm::types::errors::program_errors::ProgramError;
 use indexmap::IndexMap;
+use papyrus_common::memory_budget::MemorySize;
 use papyrus_common::state::{DeployedContract, ReplacedClass, StorageEntry};
 use papyrus_storage::compiled_class::CasmStorageReader;
 use papyrus_storage::db::{TransactionKind, RO};
@@ -63,20 +66,36 @@ pub(crate) fn get_contract_class(
     match txn.get_state_reader()?.get_class_definition_block_number(class_hash)? {
         Some(block_number) if state_number.is_before(block_number) => return Ok(None),
         Some(_block_number) => {
+            // Converting a compiled class to its blockifier representation is expensive, so the
+            // converted classes are cached along with the classes read from the storage.
+            if let Some(class) = txn.class_cache().get::<ContractClassV1>(class_hash) {
+                return Ok(Some(BlockifierContractClass::V1(ContractClassV1::clone(&class))));
+            }
             let Some(casm) = txn.get_casm(class_hash)? else {
                 return Err(ExecutionUtilsError::CasmTableNotSynced);
             };
-            return Ok(Some(BlockifierContractClass::V1(
-                ContractClassV1::try_from(casm).map_err(ExecutionUtilsError::ProgramError)?,
-            )));
+            // The blockifier representation holds the same data as the compiled class.
+            let memory_size = casm.memory_size();
+            let class =
+                ContractClassV1::try_from(casm).map_err(ExecutionUtilsError::ProgramError)?;
+            txn.class_cache().insert(*class_hash, Arc::new(class.clone()), memory_size);
+            return Ok(Some(BlockifierContractClass::V1(class)));
         }
         None => {}
     };
 
+    let state_reader = txn.get_state_reader()?;
     let Some(deprecated_class) =
-        txn.get_state_reader()?.get_deprecated_class_definition_at(state_number, class_hash)?
+        state_reader.get_deprecated_class_definition_at(state_number, class_hash)?
     else {
-        return Ok(None);
+        // A deprecated class whose download the sync deferred is missing like a compiled class
+        // that wasn't synced yet.
+        return match state_reader.get_deferred_class_block_number(class_hash)? {
+            Some(block_number) if !state_number.is_before(block_number) => {
+                Err(ExecutionUtilsError::CasmTableNotSynced)
+            }
+            _ => Ok(None),
+        };
     };
     Ok(Some(BlockifierContractClass::V0(
         ContractClassV0::try_from(deprecated_class).map_err(ExecutionUtilsError::ProgramError)?,
@@ -188,6 +207,26 @@ pub fn get_nonce_at<Mode: TransactionKind>(
     txn.get_state_reader()?.get_nonce_at(state_number, &contract_address)
 }
 
+/// Get the balance of the given contract in the ERC20 fee token at the given address, as the low
+/// and high 128 bits of its u256 value. The balance is read directly from the storage slots of the
+/// token's `ERC20_balances` variable without executing the token contract.
+pub fn get_fee_token_balance<Mode: TransactionKind>(
+    txn: &StorageTxn<'_, Mode>,
+    state_number: StateNumber,
+    fee_token_address: ContractAddress,
+    contract_address: ContractAddress,
+) -> StorageResult<(StarkFelt, StarkFelt)> {
+    let low_key = get_storage_var_address("ERC20_balances", &[*contract_address.0.key()]);
+    let high_key = low_key
+        .next_storage_key()
+        .expect("The address of a storage variable should be smaller than the maximal key.");
+    let state_reader = txn.get_state_reader()?;
+    Ok((
+        state_reader.get_storage_at(state_number, &fee_token_address, &low_key)?,
+        state_reader.get_storage_at(state_number, &fee_token_address, &high_key)?,
+    ))
+}
+
 /// Get the class hash of the contract at the given address, if it exists. If there's a given
 /// pending deployed contracts, search in them as well.
 pub fn get_class_hash_at<Mode: TransactionKind>(
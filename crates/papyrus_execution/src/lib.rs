@@ -12,6 +12,7 @@
 mod execution_test;
 pub mod execution_utils;
 mod state_reader;
+mod versioned_constants;
 
 #[cfg(test)]
 mod test_utils;
@@ -45,14 +46,16 @@ use blockifier::versioned_constants::VersionedConstants;
 use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 use cairo_vm::vm::runners::cairo_runner::ExecutionResources;
 use execution_utils::{get_trace_constructor, induced_state_diff};
-use objects::{PriceUnit, TransactionSimulationOutput};
+use objects::{PriceUnit, StateOverride, TransactionSimulationOutput};
 use papyrus_common::transaction_hash::get_transaction_hash;
 use papyrus_common::TransactionOptions;
+use papyrus_storage::db::RO;
 use papyrus_storage::header::HeaderStorageReader;
-use papyrus_storage::{StorageError, StorageReader};
+use papyrus_storage::{StorageError, StorageReader, StorageTxn};
 use serde::{Deserialize, Serialize};
-use starknet_api::block::{BlockNumber, GasPrice};
-use starknet_api::core::{ChainId, ClassHash, ContractAddress, EntryPointSelector, PatriciaKey};
+use starknet_api::block::{BlockNumber, GasPrice, StarknetVersion};
+use starknet_api::core::{ChainId, ClassHash, ContractAddress, EntryPointSelector};
+use starknet_api::data_availability::L1DataAvailabilityMode;
 // TODO: merge multiple EntryPointType structs in SN_API into one.
 use starknet_api::deprecated_contract_class::{
     ContractClass as DeprecatedContractClass,
@@ -74,7 +77,7 @@ use starknet_api::transaction::{
     TransactionHash,
     TransactionVersion,
 };
-use starknet_api::{contract_address, patricia_key, StarknetApiError};
+use starknet_api::StarknetApiError;
 use state_reader::ExecutionStateReader;
 use tracing::trace;
 
@@ -83,9 +86,8 @@ use crate::objects::PendingData;
 // TODO(yair): understand what it is and whether the use of this constant should change.
 const GLOBAL_CONTRACT_CACHE_SIZE: usize = 100;
 
-// TODO(Eitan): get from config.
-const STRK_FEE_TOKEN_ADDRESS: &str =
-    "0x04718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d";
+const STRK_FEE_CONTRACT_ADDRESS: &str =
+    "0x4718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d";
 
 /// Result type for execution functions.
 pub type ExecutionResult<T> = Result<T, ExecutionError>;
@@ -96,6 +98,9 @@ pub type ExecutionResult<T> = Result<T, ExecutionError>;
 pub struct BlockExecutionConfig {
     /// The adress to receive fees
     pub fee_contract_address: ContractAddress,
+    /// The address of the STRK fee token contract
+    #[serde(default = "default_strk_fee_contract_address")]
+    pub strk_fee_contract_address: ContractAddress,
     /// The maximum number of steps for an invoke transaction
     pub invoke_tx_max_n_steps: u32,
     /// The maximum number of steps for a validate transaction
@@ -110,6 +115,16 @@ pub struct BlockExecutionConfig {
     pub initial_gas_cost: u64,
 }
 
+// The STRK fee token has the same address in all the networks. It's the default for the execution
+// configs that were written before the STRK fee token was supported.
+fn default_strk_fee_contract_address() -> ContractAddress {
+    ContractAddress::try_from(
+        StarkHash::try_from(STRK_FEE_CONTRACT_ADDRESS)
+            .expect("The STRK fee contract address should be a valid felt."),
+    )
+    .expect("The STRK fee contract address should be a valid contract address.")
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 /// Holds a mapping from the block number, to the corresponding execution configuration.
 pub struct ExecutionConfigByBlock {
@@ -152,6 +167,8 @@ pub enum ExecutionError {
         #[source]
         err: blockifier::execution::errors::ContractClassError,
     },
+    #[error("Unknown Starknet version {starknet_version:?}.")]
+    BadStarknetVersion { starknet_version: String },
     #[error("Execution config file does not contain a configuration for all blocks")]
     ConfigContentError,
     #[error(transparent)]
@@ -181,6 +198,8 @@ pub enum ExecutionError {
     TransactionHashCalculationFailed(StarknetApiError),
     #[error("Unknown builtin name: {builtin_name}")]
     UnknownBuiltin { builtin_name: String },
+    #[error("The overridden balance of {contract_address:?} doesn't fit in 128 bits.")]
+    BalanceOverflow { contract_address: ContractAddress },
 }
 
 /// Whether the only-query bit of the transaction version is on.
@@ -202,12 +221,54 @@ pub fn execute_call(
     calldata: Calldata,
     execution_config: &BlockExecutionConfig,
 ) -> ExecutionResult<CallExecution> {
-    verify_contract_exists(
-        *contract_address,
-        &storage_reader,
+    execute_call_with_state_override(
+        storage_reader,
+        maybe_pending_data,
+        chain_id,
         state_number,
-        maybe_pending_data.as_ref(),
-    )?;
+        block_context_number,
+        contract_address,
+        entry_point_selector,
+        calldata,
+        execution_config,
+        &StateOverride::default(),
+    )
+}
+
+/// Executes a StarkNet call on top of the state with the given override applied, and returns the
+/// execution result. The override isn't written to the storage.
+#[allow(clippy::too_many_arguments)]
+pub fn execute_call_with_state_override(
+    storage_reader: StorageReader,
+    maybe_pending_data: Option<PendingData>,
+    chain_id: &ChainId,
+    state_number: StateNumber,
+    block_context_number: BlockNumber,
+    contract_address: &ContractAddress,
+    entry_point_selector: EntryPointSelector,
+    calldata: Calldata,
+    execution_config: &BlockExecutionConfig,
+    state_override: &StateOverride,
+) -> ExecutionResult<CallExecution> {
+    let mut cached_state = CachedState::new(
+        create_state_reader(
+            &storage_reader,
+            state_number,
+            maybe_pending_data.clone(),
+            execution_config,
+            state_override,
+        )?,
+        GlobalContractCache::new(GLOBAL_CONTRACT_CACHE_SIZE),
+    );
+    // A contract whose class is overridden is deployed by the override.
+    if cached_state.state.overridden_class_hash(contract_address).is_none() {
+        verify_contract_exists(
+            *contract_address,
+            cached_state.state.txn(),
+            state_number,
+            maybe_pending_data.as_ref(),
+        )?;
+    }
 
     let call_entry_point = CallEntryPoint {
         class_hash: None,
@@ -222,21 +283,10 @@ pub fn execute_call(
         initial_gas: execution_config.initial_gas_cost,
     };
 
-    let mut cached_state = CachedState::new(
-        ExecutionStateReader {
-            storage_reader: storage_reader.clone(),
-            state_number,
-            maybe_pending_data: maybe_pending_data.clone(),
-            missing_compiled_class: None,
-        },
-        GlobalContractCache::new(GLOBAL_CONTRACT_CACHE_SIZE),
-    );
-
     let block_context = create_block_context(
         &mut cached_state,
         block_context_number,
         chain_id.clone(),
-        &storage_reader,
         maybe_pending_data.as_ref(),
         execution_config,
     )?;
@@ -264,14 +314,31 @@ pub fn execute_call(
     Ok(res.execution)
 }
 
+fn create_state_reader<'env>(
+    storage_reader: &'env StorageReader,
+    state_number: StateNumber,
+    maybe_pending_data: Option<PendingData>,
+    execution_config: &BlockExecutionConfig,
+    state_override: &StateOverride,
+) -> ExecutionResult<ExecutionStateReader<'env>> {
+    if let Some(contract_address) = state_override.balance_overflow() {
+        return Err(ExecutionError::BalanceOverflow { contract_address });
+    }
+    Ok(ExecutionStateReader::new(storage_reader, state_number, maybe_pending_data)?
+        .with_state_override(
+            state_override,
+            &[execution_config.fee_contract_address, execution_config.strk_fee_contract_address],
+        ))
+}
+
 fn verify_contract_exists(
     contract_address: ContractAddress,
-    storage_reader: &StorageReader,
+    txn: &StorageTxn<'_, RO>,
     state_number: StateNumber,
     maybe_pending_data: Option<&PendingData>,
 ) -> ExecutionResult<()> {
     execution_utils::get_class_hash_at(
-        &storage_reader.begin_ro_txn()?,
+        txn,
         state_number,
         maybe_pending_data.map(|pending_state_diff| {
             (&pending_state_diff.deployed_contracts, &pending_state_diff.replaced_classes)
@@ -286,42 +353,55 @@ fn create_block_context(
     cached_state: &mut CachedState<ExecutionStateReader>,
     block_context_number: BlockNumber,
     chain_id: ChainId,
-    storage_reader: &StorageReader,
     maybe_pending_data: Option<&PendingData>,
     execution_config: &BlockExecutionConfig,
 ) -> ExecutionResult<BlockContext> {
-    let (block_number, block_timestamp, l1_gas_price, l1_data_gas_price, sequencer_address) =
-        match maybe_pending_data {
-            Some(pending_data) => (
-                block_context_number.next(),
-                pending_data.timestamp,
-                pending_data.l1_gas_price,
-                pending_data.l1_data_gas_price,
-                pending_data.sequencer,
-            ),
-            None => {
-                let header = storage_reader
-                    .begin_ro_txn()?
-                    .get_block_header(block_context_number)?
-                    .expect("Should have block header.");
-                (
-                    header.block_number,
-                    header.timestamp,
-                    header.l1_gas_price,
-                    header.l1_data_gas_price,
-                    header.sequencer,
-                )
-            }
-        };
+    // The pending block is executed with the latest constants.
+    let (
+        block_number,
+        block_timestamp,
+        l1_gas_price,
+        l1_data_gas_price,
+        l1_da_mode,
+        sequencer_address,
+        starknet_version,
+    ) = match maybe_pending_data {
+        Some(pending_data) => (
+            block_context_number.next(),
+            pending_data.timestamp,
+            pending_data.l1_gas_price,
+            pending_data.l1_data_gas_price,
+            pending_data.l1_da_mode,
+            pending_data.sequencer,
+            None,
+        ),
+        None => {
+            let header = cached_state
+                .state
+                .txn()
+                .get_block_header(block_context_number)?
+                .expect("Should have block header.");
+            (
+                header.block_number,
+                header.timestamp,
+                header.l1_gas_price,
+                header.l1_data_gas_price,
+                header.l1_da_mode,
+                header.sequencer,
+                Some(header.starknet_version),
+            )
+        }
+    };
     let ten_blocks_ago = get_10_blocks_ago(&block_context_number, cached_state)?;
 
     let block_info = BlockInfo {
         block_timestamp,
         sequencer_address: sequencer_address.0,
-        // TODO(yair): set to true when da mode is Blob (not supported yet).
-        use_kzg_da: false,
+        // The data of blocks that are published in blobs is charged by the data gas price.
+        use_kzg_da: l1_da_mode == L1DataAvailabilityMode::Blob,
         block_number,
-        // TODO(yair): What to do about blocks pre 0.13.1 where the data gas price were 0?
+        // Blocks before Starknet 0.13.1 have no data gas price, but their data is published in
+        // calldata, so the price isn't used.
         gas_prices: GasPrices {
             eth_l1_gas_price: NonZeroU128::new(l1_gas_price.price_in_wei.0)
                 .unwrap_or(NonZeroU128::MIN),
@@ -335,25 +415,36 @@ fn create_block_context(
     };
     let chain_info = ChainInfo {
         chain_id,
-        // TODO(Eitan): add the correct fee token addresses to the execution config.
         fee_token_addresses: FeeTokenAddresses {
-            strk_fee_token_address: contract_address!(STRK_FEE_TOKEN_ADDRESS),
+            strk_fee_token_address: execution_config.strk_fee_contract_address,
             eth_fee_token_address: execution_config.fee_contract_address,
         },
     };
 
-    // TODO(yair): set the correct versioned constants for re-execution.
-    let versioned_constants = VersionedConstants::latest_constants().clone();
-
     Ok(pre_process_block(
         cached_state,
         ten_blocks_ago,
         block_info,
         chain_info,
-        versioned_constants,
+        get_versioned_constants(execution_config, starknet_version.as_ref())?,
     )?)
 }
 
+// Returns the constants of the protocol version of the block with the limits of the given execution
+// config, so that a block is executed with the constants and limits that were in use when it was
+// created.
+fn get_versioned_constants(
+    execution_config: &BlockExecutionConfig,
+    starknet_version: Option<&StarknetVersion>,
+) -> ExecutionResult<VersionedConstants> {
+    let mut versioned_constants =
+        versioned_constants::get_constants_of_version(starknet_version)?.clone();
+    versioned_constants.invoke_tx_max_n_steps = execution_config.invoke_tx_max_n_steps;
+    versioned_constants.validate_max_n_steps = execution_config.validate_tx_max_n_steps;
+    versioned_constants.max_recursion_depth = execution_config.max_recursion_depth;
+    Ok(versioned_constants)
+}
+
 /// The size of the json string representing the abi of a class or deprecated class.
 pub type AbiSize = usize;
 
@@ -530,6 +621,7 @@ pub fn estimate_fee(
         execution_config,
         false,
         validate,
+        &StateOverride::default(),
     )?;
     Ok(txs_execution_info
         .into_iter()
@@ -577,15 +669,17 @@ fn execute_transactions(
     execution_config: &BlockExecutionConfig,
     charge_fee: bool,
     validate: bool,
+    state_override: &StateOverride,
 ) -> ExecutionResult<(Vec<TransactionExecutionOutput>, BlockContext)> {
     // The starknet state will be from right before the block in which the transactions should run.
     let mut cached_state = CachedState::new(
-        ExecutionStateReader {
-            storage_reader: storage_reader.clone(),
+        create_state_reader(
+            &storage_reader,
             state_number,
-            maybe_pending_data: maybe_pending_data.clone(),
-            missing_compiled_class: None,
-        },
+            maybe_pending_data.clone(),
+            execution_config,
+            state_override,
+        )?,
         GlobalContractCache::new(GLOBAL_CONTRACT_CACHE_SIZE),
     );
 
@@ -593,7 +687,6 @@ fn execute_transactions(
         &mut cached_state,
         block_context_block_number,
         chain_id.clone(),
-        &storage_reader,
         maybe_pending_data.as_ref(),
         execution_config,
     )?;
@@ -673,8 +766,7 @@ fn get_10_blocks_ago(
         return Ok(None);
     }
     let block_min_10 = BlockNumber(block_number.0 - 10);
-    let Some(header_10_blocks_ago) =
-        cached_state.state.storage_reader.begin_ro_txn()?.get_block_header(block_min_10)?
+    let Some(header_10_blocks_ago) = cached_state.state.txn().get_block_header(block_min_10)?
     else {
         return Ok(None);
     };
@@ -851,6 +943,38 @@ pub fn simulate_transactions(
     execution_config: &BlockExecutionConfig,
     charge_fee: bool,
     validate: bool,
+) -> ExecutionResult<Vec<TransactionSimulationOutput>> {
+    simulate_transactions_with_state_override(
+        txs,
+        tx_hashes,
+        chain_id,
+        storage_reader,
+        maybe_pending_data,
+        state_number,
+        block_context_block_number,
+        execution_config,
+        charge_fee,
+        validate,
+        &StateOverride::default(),
+    )
+}
+
+/// Simulates a series of transactions on top of the state with the given override applied, and
+/// returns the transaction traces and the fee estimations. The override isn't written to the
+/// storage.
+#[allow(clippy::too_many_arguments)]
+pub fn simulate_transactions_with_state_override(
+    txs: Vec<ExecutableTransactionInput>,
+    tx_hashes: Option<Vec<TransactionHash>>,
+    chain_id: &ChainId,
+    storage_reader: StorageReader,
+    maybe_pending_data: Option<PendingData>,
+    state_number: StateNumber,
+    block_context_block_number: BlockNumber,
+    execution_config: &BlockExecutionConfig,
+    charge_fee: bool,
+    validate: bool,
+    state_override: &StateOverride,
 ) -> ExecutionResult<Vec<TransactionSimulationOutput>> {
     let trace_constructors = txs.iter().map(get_trace_constructor).collect::<Vec<_>>();
     let (execution_results, block_context) = execute_transactions(
@@ -864,6 +988,7 @@ pub fn simulate_transactions(
         execution_config,
         charge_fee,
         validate,
+        state_override,
     )?;
     execution_results
         .into_iter()
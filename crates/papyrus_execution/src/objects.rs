@@ -39,9 +39,10 @@ use starknet_api::core::{
     Nonce,
     SequencerContractAddress,
 };
+use starknet_api::data_availability::L1DataAvailabilityMode;
 use starknet_api::deprecated_contract_class::EntryPointType;
 use starknet_api::hash::StarkFelt;
-use starknet_api::state::ThinStateDiff;
+use starknet_api::state::{StorageKey, ThinStateDiff};
 use starknet_api::transaction::{
     Builtin,
     Calldata,
@@ -434,12 +435,42 @@ pub struct PendingData {
     pub l1_gas_price: GasPricePerToken,
     /// The data price of the pending block.
     pub l1_data_gas_price: GasPricePerToken,
+    /// The data availability mode of the pending block, which determines whether its data is
+    /// charged by the data gas price.
+    pub l1_da_mode: L1DataAvailabilityMode,
     /// The sequencer address of the pending block.
     pub sequencer: SequencerContractAddress,
     /// The classes and casms that were declared in the pending block.
     pub classes: PendingClasses,
 }
 
+/// Changes to the state that an execution sees on top of the state it runs on, for executing
+/// "what-if" scenarios against the real state. Nothing is written to the storage.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StateOverride {
+    /// Storage values that replace the values in the storage of contracts, by contract and key.
+    pub storage: IndexMap<ContractAddress, IndexMap<StorageKey, StarkFelt>>,
+    /// Balances of contracts in the fee tokens. A balance is set in both the ETH and the STRK fee
+    /// tokens, as the low 128 bits of the u256 balance whose high bits are zero.
+    pub balances: IndexMap<ContractAddress, StarkFelt>,
+    /// Classes that replace the classes of contracts. A contract that isn't deployed is deployed
+    /// with the given class.
+    pub class_hashes: IndexMap<ContractAddress, ClassHash>,
+}
+
+impl StateOverride {
+    /// Returns the first contract whose balance doesn't fit in 128 bits, if there's one.
+    pub fn balance_overflow(&self) -> Option<ContractAddress> {
+        self.balances
+            .iter()
+            .find(|(_contract_address, balance)| {
+                balance.bytes()[..16].iter().any(|byte| *byte != 0)
+            })
+            .map(|(contract_address, _balance)| *contract_address)
+    }
+}
+
 /// The unit of the fee.
 #[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
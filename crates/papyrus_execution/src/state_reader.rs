@@ -2,6 +2,9 @@
 #[path = "state_reader_test.rs"]
 mod state_reader_test;
 
+use std::collections::HashMap;
+
+use blockifier::abi::abi_utils::get_storage_var_address;
 use blockifier::execution::contract_class::{
     ContractClass as BlockifierContractClass,
     ContractClassV0,
@@ -11,34 +14,101 @@ use blockifier::state::errors::StateError;
 use blockifier::state::state_api::{StateReader as BlockifierStateReader, StateResult};
 use papyrus_common::pending_classes::{ApiContractClass, PendingClassesTrait};
 use papyrus_common::state::DeclaredClassHashEntry;
+use papyrus_storage::db::RO;
 use papyrus_storage::state::StateStorageReader;
-use papyrus_storage::{StorageError, StorageReader};
+use papyrus_storage::{StorageError, StorageReader, StorageResult, StorageTxn};
 use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
 use starknet_api::hash::StarkFelt;
 use starknet_api::state::{StateNumber, StorageKey};
 
 use crate::execution_utils;
 use crate::execution_utils::{get_contract_class, ExecutionUtilsError};
-use crate::objects::PendingData;
+use crate::objects::{PendingData, StateOverride};
 
-/// A view into the state at a specific state number.
-pub struct ExecutionStateReader {
-    pub storage_reader: StorageReader,
+/// A view into the state at a specific state number. All the reads of the state are done in a
+/// single storage transaction, so an execution sees the same state even if blocks are written
+/// while it runs.
+pub struct ExecutionStateReader<'env> {
+    txn: StorageTxn<'env, RO>,
     pub state_number: StateNumber,
     pub maybe_pending_data: Option<PendingData>,
     // We want to return a custom error when missing a compiled class, but we need to return
     // Blockifier's error, so we store the missing class's hash in case of error.
     pub missing_compiled_class: Option<ClassHash>,
+    // Values that are read instead of the values in the state.
+    overridden_storage: HashMap<(ContractAddress, StorageKey), StarkFelt>,
+    overridden_class_hashes: HashMap<ContractAddress, ClassHash>,
+}
+
+impl<'env> ExecutionStateReader<'env> {
+    /// Creates a view into the state at the given state number, with the pending data on top of it
+    /// if given. Fails if the state isn't queryable in the node mode of the storage.
+    pub fn new(
+        storage_reader: &'env StorageReader,
+        state_number: StateNumber,
+        maybe_pending_data: Option<PendingData>,
+    ) -> StorageResult<Self> {
+        let txn = storage_reader.begin_ro_txn()?;
+        txn.verify_state_queryable(state_number)?;
+        Ok(Self {
+            txn,
+            state_number,
+            maybe_pending_data,
+            missing_compiled_class: None,
+            overridden_storage: HashMap::new(),
+            overridden_class_hashes: HashMap::new(),
+        })
+    }
+
+    /// Applies the given state override on top of the state. The balances are set in each of the
+    /// given fee token contracts.
+    pub fn with_state_override(
+        mut self,
+        state_override: &StateOverride,
+        fee_token_addresses: &[ContractAddress],
+    ) -> Self {
+        for (contract_address, storage) in &state_override.storage {
+            for (key, value) in storage {
+                self.overridden_storage.insert((*contract_address, *key), *value);
+            }
+        }
+        for (contract_address, balance) in &state_override.balances {
+            let low_key = get_storage_var_address("ERC20_balances", &[*contract_address.0.key()]);
+            let high_key = low_key.next_storage_key().expect(
+                "The address of a storage variable should be smaller than the maximal key.",
+            );
+            for fee_token_address in fee_token_addresses {
+                self.overridden_storage.insert((*fee_token_address, low_key), *balance);
+                self.overridden_storage.insert((*fee_token_address, high_key), StarkFelt::ZERO);
+            }
+        }
+        self.overridden_class_hashes
+            .extend(state_override.class_hashes.iter().map(|(address, hash)| (*address, *hash)));
+        self
+    }
+
+    /// Returns the class hash that overrides the class of the given contract, if there's one.
+    pub fn overridden_class_hash(&self, contract_address: &ContractAddress) -> Option<ClassHash> {
+        self.overridden_class_hashes.get(contract_address).copied()
+    }
+
+    /// The storage transaction the state is read from.
+    pub fn txn(&self) -> &StorageTxn<'env, RO> {
+        &self.txn
+    }
 }
 
-impl BlockifierStateReader for ExecutionStateReader {
+impl BlockifierStateReader for ExecutionStateReader<'_> {
     fn get_storage_at(
         &mut self,
         contract_address: ContractAddress,
         key: StorageKey,
     ) -> StateResult<StarkFelt> {
+        if let Some(value) = self.overridden_storage.get(&(contract_address, key)) {
+            return Ok(*value);
+        }
         execution_utils::get_storage_at(
-            &self.storage_reader.begin_ro_txn().map_err(storage_err_to_state_err)?,
+            &self.txn,
             self.state_number,
             self.maybe_pending_data.as_ref().map(|pending_data| &pending_data.storage_diffs),
             contract_address,
@@ -50,7 +120,7 @@ impl BlockifierStateReader for ExecutionStateReader {
     // Returns the default value if the contract address is not found.
     fn get_nonce_at(&mut self, contract_address: ContractAddress) -> StateResult<Nonce> {
         Ok(execution_utils::get_nonce_at(
-            &self.storage_reader.begin_ro_txn().map_err(storage_err_to_state_err)?,
+            &self.txn,
             self.state_number,
             self.maybe_pending_data.as_ref().map(|pending_data| &pending_data.nonces),
             contract_address,
@@ -61,8 +131,11 @@ impl BlockifierStateReader for ExecutionStateReader {
 
     // Returns the default value if the contract address is not found.
     fn get_class_hash_at(&mut self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        if let Some(class_hash) = self.overridden_class_hash(&contract_address) {
+            return Ok(class_hash);
+        }
         Ok(execution_utils::get_class_hash_at(
-            &self.storage_reader.begin_ro_txn().map_err(storage_err_to_state_err)?,
+            &self.txn,
             self.state_number,
             self.maybe_pending_data.as_ref().map(|pending_data| {
                 (&pending_data.deployed_contracts, &pending_data.replaced_classes)
@@ -96,11 +169,7 @@ impl BlockifierStateReader for ExecutionStateReader {
                     .map_err(StateError::ProgramError)?,
             ));
         }
-        match get_contract_class(
-            &self.storage_reader.begin_ro_txn().map_err(storage_err_to_state_err)?,
-            &class_hash,
-            self.state_number,
-        ) {
+        match get_contract_class(&self.txn, &class_hash, self.state_number) {
             Ok(Some(contract_class)) => Ok(contract_class),
             Ok(None) => Err(StateError::UndeclaredClassHash(class_hash)),
             Err(ExecutionUtilsError::CasmTableNotSynced) => {
@@ -123,24 +192,19 @@ impl BlockifierStateReader for ExecutionStateReader {
             }
         }
         let block_number = self
-            .storage_reader
-            .begin_ro_txn()
-            .map_err(storage_err_to_state_err)?
+            .txn
             .get_state_reader()
             .map_err(storage_err_to_state_err)?
             .get_class_definition_block_number(&class_hash)
             .map_err(storage_err_to_state_err)?
             .ok_or(StateError::UndeclaredClassHash(class_hash))?;
 
-        let state_diff = self
-            .storage_reader
-            .begin_ro_txn()
-            .map_err(storage_err_to_state_err)?
-            .get_state_diff(block_number)
-            .map_err(storage_err_to_state_err)?
-            .ok_or(StateError::StateReadError(format!(
-                "Inner storage error. Missing state diff at block {block_number}."
-            )))?;
+        let state_diff =
+            self.txn.get_state_diff(block_number).map_err(storage_err_to_state_err)?.ok_or(
+                StateError::StateReadError(format!(
+                    "Inner storage error. Missing state diff at block {block_number}."
+                )),
+            )?;
 
         let compiled_class_hash = state_diff.declared_classes.get(&class_hash).ok_or(
             StateError::StateReadError(format!(
@@ -136,12 +136,8 @@ fn read_state() {
         .unwrap();
 
     let state_number0 = StateNumber::right_after_block(BlockNumber(0));
-    let mut state_reader0 = ExecutionStateReader {
-        storage_reader: storage_reader.clone(),
-        state_number: state_number0,
-        maybe_pending_data: None,
-        missing_compiled_class: None,
-    };
+    let mut state_reader0 =
+        ExecutionStateReader::new(&storage_reader, state_number0, None).unwrap();
     let storage_after_block_0 = state_reader0.get_storage_at(address0, storage_key0).unwrap();
     assert_eq!(storage_after_block_0, StarkFelt::default());
     let nonce_after_block_0 = state_reader0.get_nonce_at(address0).unwrap();
@@ -157,12 +153,8 @@ fn read_state() {
     assert_eq!(state_reader0.get_compiled_class_hash(class_hash0).unwrap(), compiled_class_hash0);
 
     let state_number1 = StateNumber::right_after_block(BlockNumber(1));
-    let mut state_reader1 = ExecutionStateReader {
-        storage_reader: storage_reader.clone(),
-        state_number: state_number1,
-        maybe_pending_data: None,
-        missing_compiled_class: None,
-    };
+    let mut state_reader1 =
+        ExecutionStateReader::new(&storage_reader, state_number1, None).unwrap();
     let storage_after_block_1 = state_reader1.get_storage_at(address0, storage_key0).unwrap();
     assert_eq!(storage_after_block_1, storage_value0);
     let nonce_after_block_1 = state_reader1.get_nonce_at(address0).unwrap();
@@ -179,12 +171,8 @@ fn read_state() {
     assert_eq!(state_reader1.missing_compiled_class.unwrap(), class_hash5);
 
     let state_number2 = StateNumber::right_after_block(BlockNumber(2));
-    let mut state_reader2 = ExecutionStateReader {
-        storage_reader,
-        state_number: state_number2,
-        maybe_pending_data: None,
-        missing_compiled_class: None,
-    };
+    let mut state_reader2 =
+        ExecutionStateReader::new(&storage_reader, state_number2, None).unwrap();
     let nonce_after_block_2 = state_reader2.get_nonce_at(address0).unwrap();
     assert_eq!(nonce_after_block_2, nonce0);
 
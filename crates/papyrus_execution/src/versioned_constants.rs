@@ -0,0 +1,70 @@
+//! The execution constants of the Starknet protocol versions. A block is executed with the
+//! constants of the version it was created in, so that re-executing a historical block gives the
+//! results the sequencer computed when it created it.
+//!
+//! The constants of every version are shipped in full in `resources/versioned_constants_*.json`,
+//! keyed by the first version that uses them.
+#[cfg(test)]
+#[path = "versioned_constants_test.rs"]
+mod versioned_constants_test;
+
+use std::collections::BTreeMap;
+
+use blockifier::versioned_constants::VersionedConstants;
+use lazy_static::lazy_static;
+use starknet_api::block::StarknetVersion;
+
+use crate::{ExecutionError, ExecutionResult};
+
+// The constants of each protocol version, as JSON, keyed by the first version that uses them.
+const VERSIONED_CONSTANTS_JSONS: [(&str, &str); 2] = [
+    ("0.13.0", include_str!("../resources/versioned_constants_13_0.json")),
+    ("0.13.1", include_str!("../resources/versioned_constants_13_1.json")),
+];
+
+// The components of a version, for example [0, 13, 1] for "0.13.1".
+type VersionComponents = Vec<u64>;
+
+lazy_static! {
+    // The constants of each protocol version, keyed by the first version that uses them.
+    static ref VERSIONED_CONSTANTS: BTreeMap<VersionComponents, VersionedConstants> =
+        VERSIONED_CONSTANTS_JSONS
+            .into_iter()
+            .map(|(version, constants_json)| {
+                let version = parse_version(version)
+                    .expect("The versioned constants should be keyed by valid versions.");
+                let versioned_constants = serde_json::from_str(constants_json)
+                    .expect("The versioned constants of every version should be valid.");
+                (version, versioned_constants)
+            })
+            .collect();
+}
+
+/// Returns the constants of the given protocol version, or the latest constants if the version
+/// isn't known, as for the pending block. Blocks that predate the first shipped version are
+/// executed with the constants of that version.
+pub(crate) fn get_constants_of_version(
+    starknet_version: Option<&StarknetVersion>,
+) -> ExecutionResult<&'static VersionedConstants> {
+    let Some(starknet_version) = starknet_version else {
+        return Ok(VersionedConstants::latest_constants());
+    };
+    let version = parse_version(&starknet_version.0).ok_or_else(|| {
+        ExecutionError::BadStarknetVersion { starknet_version: starknet_version.0.clone() }
+    })?;
+    Ok(VERSIONED_CONSTANTS
+        .range(..=version)
+        .next_back()
+        .or_else(|| VERSIONED_CONSTANTS.first_key_value())
+        .map(|(_, versioned_constants)| versioned_constants)
+        .expect("There should be constants for at least one version."))
+}
+
+// Parses a version like "0.13.1". The early blocks have an empty version, which is parsed as no
+// components and thus precedes every version.
+fn parse_version(version: &str) -> Option<VersionComponents> {
+    if version.is_empty() {
+        return Some(VersionComponents::new());
+    }
+    version.split('.').map(|component| component.parse().ok()).collect()
+}
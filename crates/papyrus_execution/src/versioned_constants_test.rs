@@ -0,0 +1,54 @@
+use assert_matches::assert_matches;
+use blockifier::versioned_constants::VersionedConstants;
+use pretty_assertions::assert_eq;
+use starknet_api::block::StarknetVersion;
+
+use crate::versioned_constants::get_constants_of_version;
+use crate::ExecutionError;
+
+fn constants_of_version(starknet_version: Option<&str>) -> &'static VersionedConstants {
+    let starknet_version = starknet_version.map(|version| StarknetVersion(version.to_owned()));
+    get_constants_of_version(starknet_version.as_ref()).unwrap()
+}
+
+fn max_n_emitted_events(starknet_version: Option<&str>) -> usize {
+    constants_of_version(starknet_version).tx_event_limits.max_n_emitted_events
+}
+
+#[test]
+fn constants_are_selected_by_version() {
+    let latest = VersionedConstants::latest_constants();
+    // The number of events wasn't limited before 0.13.1.
+    assert_eq!(max_n_emitted_events(Some("0.12.3")), usize::MAX);
+    assert_eq!(max_n_emitted_events(Some("0.13.0")), usize::MAX);
+    assert_eq!(constants_of_version(Some("0.13.0")).invoke_tx_max_n_steps, 3_000_000);
+    assert_eq!(max_n_emitted_events(Some("0.13.1")), latest.tx_event_limits.max_n_emitted_events);
+    assert_eq!(constants_of_version(Some("0.13.1")).invoke_tx_max_n_steps, 4_000_000);
+    assert_eq!(max_n_emitted_events(Some("0.13.1.1")), latest.tx_event_limits.max_n_emitted_events);
+    // Versions newer than the shipped ones use the constants of the newest shipped version.
+    assert_eq!(
+        constants_of_version(Some("1.0.0")).invoke_tx_max_n_steps,
+        latest.invoke_tx_max_n_steps
+    );
+}
+
+#[test]
+fn unversioned_blocks_use_the_first_version() {
+    assert_eq!(max_n_emitted_events(Some("")), usize::MAX);
+}
+
+#[test]
+fn unknown_version_uses_the_latest_constants() {
+    assert_eq!(
+        max_n_emitted_events(None),
+        VersionedConstants::latest_constants().tx_event_limits.max_n_emitted_events
+    );
+}
+
+#[test]
+fn bad_version() {
+    assert_matches!(
+        get_constants_of_version(Some(&StarknetVersion("0.13.x".to_owned()))),
+        Err(ExecutionError::BadStarknetVersion { starknet_version }) if starknet_version == "0.13.x"
+    );
+}
@@ -0,0 +1,44 @@
+// Sends identical queries about a range of blocks to two nodes and prints the fields in which their
+// responses differ. To compare the responses of two nodes on blocks [start_block, end_block), run:
+//      cargo run -r -p papyrus_load_test --bin compare_nodes -- http://127.0.0.1:8080 <other_node_url> <start_block> <end_block> [<version_id>]
+// The default version_id is v0_7.
+
+use std::env;
+
+use papyrus_load_test::compare::compare_nodes;
+
+const DEFAULT_VERSION_ID: &str = "v0_7";
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 5 {
+        anyhow::bail!(
+            "Usage: {} <node_url> <other_node_url> <start_block> <end_block> [<version_id>]",
+            args[0]
+        );
+    }
+    let start_block = args[3].parse::<u64>()?;
+    let end_block = args[4].parse::<u64>()?;
+    let version_id = args.get(5).map(String::as_str).unwrap_or(DEFAULT_VERSION_ID);
+
+    let summary = compare_nodes(&args[1], &args[2], version_id, start_block, end_block).await;
+    for query_diff in &summary.query_diffs {
+        println!("{} {}:", query_diff.method, query_diff.params);
+        for diff in &query_diff.diffs {
+            println!(
+                "    {}: {} != {}",
+                diff.path,
+                diff.value.as_ref().map_or("<missing>".to_owned(), ToString::to_string),
+                diff.other_value.as_ref().map_or("<missing>".to_owned(), ToString::to_string),
+            );
+        }
+    }
+    println!(
+        "Compared {} queries: {} failed, {} have different responses.",
+        summary.compared_queries,
+        summary.failed_queries,
+        summary.query_diffs.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,39 @@
+// Sends a realistic mix of JSON-RPC requests to a node and prints the latency percentiles of each
+// method. The mix is described by a load profile, e.g. resources/mixed_load_profile.json. Run:
+//      cargo run -r -p papyrus_load_test --bin mixed_load -- <profile_file> http://127.0.0.1:8080 [<version_id>]
+// The default version_id is v0_7.
+
+use std::env;
+
+use papyrus_load_test::mixed_load::{read_load_profile, run_mixed_load};
+
+const DEFAULT_VERSION_ID: &str = "v0_7";
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 3 {
+        anyhow::bail!("Usage: {} <profile_file> <node_url> [<version_id>]", args[0]);
+    }
+    let profile = read_load_profile(&args[1])?;
+    let version_id = args.get(3).map(String::as_str).unwrap_or(DEFAULT_VERSION_ID);
+
+    let reports = run_mixed_load(profile, &args[2], version_id).await?;
+    println!(
+        "{:<45} {:>8} {:>8} {:>10} {:>10} {:>10} {:>10}",
+        "method", "sent", "failed", "p50", "p90", "p99", "max"
+    );
+    for (method, report) in reports {
+        println!(
+            "{:<45} {:>8} {:>8} {:>10.2?} {:>10.2?} {:>10.2?} {:>10.2?}",
+            method,
+            report.sent_requests,
+            report.failed_requests,
+            report.p50,
+            report.p90,
+            report.p99,
+            report.max,
+        );
+    }
+    Ok(())
+}
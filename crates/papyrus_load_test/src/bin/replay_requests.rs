@@ -0,0 +1,30 @@
+// Replays the requests recorded by a node against a node, and optionally compares the responses
+// with those of a reference node. To record requests, run the node with the
+// rpc.record_requests_path config param. Then run:
+//      cargo run -r -p papyrus_load_test --bin replay_requests -- <recorded_file> http://127.0.0.1:8080 [<reference_node_url>]
+
+use std::env;
+
+use papyrus_load_test::replay::{read_recorded_requests, replay_requests};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 3 {
+        anyhow::bail!("Usage: {} <recorded_file> <node_url> [<reference_node_url>]", args[0]);
+    }
+    let requests = read_recorded_requests(&args[1])?;
+    let summary = replay_requests(&requests, &args[2], args.get(3).map(String::as_str)).await;
+
+    println!("Sent {} requests, {} failed.", summary.sent_requests, summary.failed_requests);
+    if summary.sent_requests > 0 {
+        println!(
+            "Average latency: {:?}.",
+            summary.total_latency / u32::try_from(summary.sent_requests)?
+        );
+    }
+    if args.len() > 3 {
+        println!("{} responses differ from the reference node.", summary.mismatched_responses);
+    }
+    Ok(())
+}
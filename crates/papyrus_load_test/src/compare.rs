@@ -0,0 +1,180 @@
+#[cfg(test)]
+#[path = "compare_test.rs"]
+mod compare_test;
+
+// Sends identical queries about a range of blocks to two nodes (e.g. Papyrus and another
+// implementation of the Starknet JSON-RPC spec) and reports the fields in which their responses
+// differ.
+
+use std::cmp::max;
+
+use reqwest::Client;
+use serde_json::{json, Value as jsonVal};
+
+use crate::jsonrpc_request;
+
+// A field whose value differs between the responses of the two nodes. A value of None means that
+// the field is missing from the corresponding response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldDiff {
+    // The path of the field inside the response, e.g. "result.transactions[3].max_fee".
+    pub path: String,
+    pub value: Option<jsonVal>,
+    pub other_value: Option<jsonVal>,
+}
+
+// The differences found in the responses to a single query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryDiff {
+    pub method: String,
+    pub params: jsonVal,
+    pub diffs: Vec<FieldDiff>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ComparisonSummary {
+    pub compared_queries: usize,
+    pub failed_queries: usize,
+    pub query_diffs: Vec<QueryDiff>,
+}
+
+// Compares the responses of the nodes at node_url and other_node_url (e.g. "http://127.0.0.1:8080")
+// to queries about the blocks in [start_block, end_block). The queries are sent to the path of the
+// given version, e.g. "v0_7". For each block, the block, its state update and the receipts of its
+// transactions are compared.
+pub async fn compare_nodes(
+    node_url: &str,
+    other_node_url: &str,
+    version_id: &str,
+    start_block: u64,
+    end_block: u64,
+) -> ComparisonSummary {
+    let client = Client::new();
+    let node_url = format!("{}/rpc/{version_id}", node_url.trim_end_matches('/'));
+    let other_node_url = format!("{}/rpc/{version_id}", other_node_url.trim_end_matches('/'));
+    let mut summary = ComparisonSummary::default();
+    for block_number in start_block..end_block {
+        let block_id = json!({ "block_id": { "block_number": block_number } });
+        let queries = [
+            ("starknet_getBlockWithTxHashes", block_id.clone()),
+            ("starknet_getBlockWithTxs", block_id.clone()),
+            ("starknet_getStateUpdate", block_id),
+        ];
+        let mut transaction_hashes = vec![];
+        for (method, params) in queries {
+            let Some(response) =
+                compare_query(&client, &node_url, &other_node_url, method, params, &mut summary)
+                    .await
+            else {
+                continue;
+            };
+            if method == "starknet_getBlockWithTxHashes" {
+                if let Some(hashes) = response["result"]["transactions"].as_array() {
+                    transaction_hashes = hashes.clone();
+                }
+            }
+        }
+        for transaction_hash in transaction_hashes {
+            let params = json!({ "transaction_hash": transaction_hash });
+            compare_query(
+                &client,
+                &node_url,
+                &other_node_url,
+                "starknet_getTransactionReceipt",
+                params,
+                &mut summary,
+            )
+            .await;
+        }
+    }
+    summary
+}
+
+// Sends the query to both nodes and records the differences between the responses. Returns the
+// response of the first node.
+async fn compare_query(
+    client: &Client,
+    node_url: &str,
+    other_node_url: &str,
+    method: &str,
+    params: jsonVal,
+    summary: &mut ComparisonSummary,
+) -> Option<jsonVal> {
+    summary.compared_queries += 1;
+    let request = jsonrpc_request(method, params.clone());
+    let (response, other_response) = match (
+        send_query(client, node_url, &request).await,
+        send_query(client, other_node_url, &request).await,
+    ) {
+        (Ok(response), Ok(other_response)) => (response, other_response),
+        (Err(err), _) | (_, Err(err)) => {
+            summary.failed_queries += 1;
+            println!("Query {request} failed: {err}");
+            return None;
+        }
+    };
+    let mut diffs = vec![];
+    diff_json("", &response, &other_response, &mut diffs);
+    if !diffs.is_empty() {
+        summary.query_diffs.push(QueryDiff { method: method.to_owned(), params, diffs });
+    }
+    Some(response)
+}
+
+async fn send_query(client: &Client, url: &str, request: &jsonVal) -> anyhow::Result<jsonVal> {
+    let response = client
+        .post(url)
+        .header("Content-Type", "application/json")
+        .body(serde_json::to_vec(request)?)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    Ok(serde_json::from_slice(&response)?)
+}
+
+// Appends to diffs the fields in which the two values differ. Objects and arrays are compared
+// field by field, so that a diff points at the innermost field that differs.
+pub fn diff_json(path: &str, value: &jsonVal, other_value: &jsonVal, diffs: &mut Vec<FieldDiff>) {
+    match (value, other_value) {
+        (jsonVal::Object(fields), jsonVal::Object(other_fields)) => {
+            let other_only_keys = other_fields.keys().filter(|key| !fields.contains_key(*key));
+            for key in fields.keys().chain(other_only_keys) {
+                let field_path =
+                    if path.is_empty() { key.to_owned() } else { format!("{path}.{key}") };
+                diff_optional_json(field_path, fields.get(key), other_fields.get(key), diffs);
+            }
+        }
+        (jsonVal::Array(elements), jsonVal::Array(other_elements)) => {
+            for index in 0..max(elements.len(), other_elements.len()) {
+                diff_optional_json(
+                    format!("{path}[{index}]"),
+                    elements.get(index),
+                    other_elements.get(index),
+                    diffs,
+                );
+            }
+        }
+        _ if value != other_value => diffs.push(FieldDiff {
+            path: path.to_owned(),
+            value: Some(value.clone()),
+            other_value: Some(other_value.clone()),
+        }),
+        _ => {}
+    }
+}
+
+fn diff_optional_json(
+    path: String,
+    value: Option<&jsonVal>,
+    other_value: Option<&jsonVal>,
+    diffs: &mut Vec<FieldDiff>,
+) {
+    match (value, other_value) {
+        (Some(value), Some(other_value)) => diff_json(&path, value, other_value, diffs),
+        _ => {
+            diffs.push(FieldDiff { path, value: value.cloned(), other_value: other_value.cloned() })
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use pretty_assertions::assert_eq;
+use serde_json::json;
+
+use crate::compare::{diff_json, FieldDiff};
+
+#[test]
+fn diff_json_of_equal_values_is_empty() {
+    let value = json!({ "result": { "block_number": 1, "transactions": ["0x1", "0x2"] } });
+    let mut diffs = vec![];
+    diff_json("", &value, &value.clone(), &mut diffs);
+    assert!(diffs.is_empty());
+}
+
+#[test]
+fn diff_json_reports_innermost_fields() {
+    let value = json!({
+        "result": {
+            "block_number": 1,
+            "transactions": [{ "max_fee": "0x1" }, { "max_fee": "0x2" }],
+            "version": "0.13.1"
+        }
+    });
+    let other_value = json!({
+        "result": {
+            "block_number": 1,
+            "transactions": [{ "max_fee": "0x1" }, { "max_fee": "0x3" }, { "max_fee": "0x4" }],
+            "l1_da_mode": "CALLDATA"
+        }
+    });
+
+    let mut diffs = vec![];
+    diff_json("", &value, &other_value, &mut diffs);
+    assert_eq!(
+        diffs,
+        vec![
+            FieldDiff {
+                path: "result.transactions[1].max_fee".to_owned(),
+                value: Some(json!("0x2")),
+                other_value: Some(json!("0x3")),
+            },
+            FieldDiff {
+                path: "result.transactions[2]".to_owned(),
+                value: None,
+                other_value: Some(json!({ "max_fee": "0x4" })),
+            },
+            FieldDiff {
+                path: "result.version".to_owned(),
+                value: Some(json!("0.13.1")),
+                other_value: None,
+            },
+            FieldDiff {
+                path: "result.l1_da_mode".to_owned(),
+                value: None,
+                other_value: Some(json!("CALLDATA")),
+            },
+        ]
+    );
+}
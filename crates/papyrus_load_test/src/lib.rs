@@ -3,10 +3,13 @@
 // within this crate
 #![cfg_attr(coverage_nightly, feature(coverage_attribute))]
 
+pub mod compare;
 pub mod create_files;
 pub mod create_request;
+pub mod mixed_load;
 #[cfg(test)]
 mod precision_test;
+pub mod replay;
 pub mod scenarios;
 pub mod transactions;
 
@@ -0,0 +1,228 @@
+#[cfg(test)]
+#[path = "mixed_load_test.rs"]
+mod mixed_load_test;
+
+// Sends a realistic mix of JSON-RPC requests to a node for a given duration and reports the latency
+// percentiles of each method, e.g. to validate changes to the caching and threading of the node.
+// The mix is described by a load profile: the weight of each method, the parameters of its
+// requests and the distribution of the block heights they query.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rand::distributions::WeightedIndex;
+use rand::prelude::Distribution;
+use rand::Rng;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value as jsonVal};
+
+use crate::jsonrpc_request;
+
+// The placeholder, in the params of a method, that is replaced by a sampled block number.
+pub const BLOCK_NUMBER_PLACEHOLDER: &str = "$BLOCK_NUMBER";
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct LoadProfile {
+    // The number of requests that are sent concurrently.
+    pub concurrency: usize,
+    pub duration_secs: u64,
+    pub block_heights: BlockHeightDistribution,
+    pub methods: Vec<WeightedMethod>,
+}
+
+// A method and its share of the requests.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct WeightedMethod {
+    pub method: String,
+    pub weight: u32,
+    // The params of the requests, in which every occurrence of BLOCK_NUMBER_PLACEHOLDER is
+    // replaced by a sampled block number.
+    pub params: jsonVal,
+}
+
+// The distribution of the queried block heights. Most requests are about the latest blocks, and
+// the rest are spread over the history of the chain.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct BlockHeightDistribution {
+    // The weight of the requests about the latest block.
+    pub latest_weight: u32,
+    // The weight of the requests about one of the recent_blocks blocks before the latest block.
+    pub recent_weight: u32,
+    pub recent_blocks: u64,
+    // The weight of the requests about any block.
+    pub historical_weight: u32,
+}
+
+impl BlockHeightDistribution {
+    pub fn sample<R: Rng>(&self, rng: &mut R, latest_block: u64) -> u64 {
+        let index =
+            WeightedIndex::new([self.latest_weight, self.recent_weight, self.historical_weight])
+                .expect("The block height distribution should have a positive weight.")
+                .sample(rng);
+        match index {
+            0 => latest_block,
+            1 => rng.gen_range(latest_block.saturating_sub(self.recent_blocks)..=latest_block),
+            _ => rng.gen_range(0..=latest_block),
+        }
+    }
+}
+
+// Reads a load profile from a JSON file.
+pub fn read_load_profile(path: &str) -> anyhow::Result<LoadProfile> {
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+// Returns the params with every occurrence of BLOCK_NUMBER_PLACEHOLDER replaced by the block
+// number.
+pub fn fill_params(params: &jsonVal, block_number: u64) -> jsonVal {
+    match params {
+        jsonVal::String(value) if value == BLOCK_NUMBER_PLACEHOLDER => json!(block_number),
+        jsonVal::Array(values) => {
+            jsonVal::Array(values.iter().map(|value| fill_params(value, block_number)).collect())
+        }
+        jsonVal::Object(fields) => jsonVal::Object(
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), fill_params(value, block_number)))
+                .collect(),
+        ),
+        _ => params.clone(),
+    }
+}
+
+// The latencies of the requests of a single method.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatencyReport {
+    pub sent_requests: usize,
+    pub failed_requests: usize,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl LatencyReport {
+    // The latencies include those of the failed requests.
+    pub fn new(mut latencies: Vec<Duration>, failed_requests: usize) -> Self {
+        latencies.sort_unstable();
+        Self {
+            sent_requests: latencies.len(),
+            failed_requests,
+            p50: percentile(&latencies, 50),
+            p90: percentile(&latencies, 90),
+            p99: percentile(&latencies, 99),
+            max: latencies.last().copied().unwrap_or_default(),
+        }
+    }
+}
+
+// Returns the nearest-rank percentile of the sorted latencies.
+pub fn percentile(sorted_latencies: &[Duration], percent: usize) -> Duration {
+    if sorted_latencies.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (sorted_latencies.len() * percent).div_ceil(100).max(1);
+    sorted_latencies[rank - 1]
+}
+
+// The result of a single request.
+struct Sample {
+    method_index: usize,
+    latency: Duration,
+    failed: bool,
+}
+
+// Sends the requests of the profile to the node at node_url (e.g. "http://127.0.0.1:8080") on the
+// path of the given version, e.g. "v0_7", and returns the latency report of each method. The block
+// heights are sampled up to the latest block of the node when the load starts.
+pub async fn run_mixed_load(
+    profile: LoadProfile,
+    node_url: &str,
+    version_id: &str,
+) -> anyhow::Result<BTreeMap<String, LatencyReport>> {
+    let client = Client::new();
+    let url = format!("{}/rpc/{version_id}", node_url.trim_end_matches('/'));
+    let latest_block = send_request(&client, &url, "starknet_blockNumber", &json!([]))
+        .await?
+        .as_u64()
+        .ok_or_else(|| anyhow::anyhow!("The block number of the node isn't a number."))?;
+    let method_distribution =
+        WeightedIndex::new(profile.methods.iter().map(|method| method.weight))?;
+
+    let profile = Arc::new(profile);
+    let deadline = Instant::now() + Duration::from_secs(profile.duration_secs);
+    let mut workers = Vec::with_capacity(profile.concurrency);
+    for _ in 0..profile.concurrency {
+        let client = client.clone();
+        let url = url.clone();
+        let profile = profile.clone();
+        let method_distribution = method_distribution.clone();
+        workers.push(tokio::spawn(async move {
+            let mut samples = vec![];
+            while Instant::now() < deadline {
+                let (method_index, params) = {
+                    // The thread rng isn't Send, so it's not held across the request.
+                    let mut rng = rand::thread_rng();
+                    let method_index = method_distribution.sample(&mut rng);
+                    let block_number = profile.block_heights.sample(&mut rng, latest_block);
+                    (method_index, fill_params(&profile.methods[method_index].params, block_number))
+                };
+                let start = Instant::now();
+                let result =
+                    send_request(&client, &url, &profile.methods[method_index].method, &params)
+                        .await;
+                samples.push(Sample {
+                    method_index,
+                    latency: start.elapsed(),
+                    failed: result.is_err(),
+                });
+            }
+            samples
+        }));
+    }
+
+    // A method may appear more than once in the profile, with different params, so the samples are
+    // grouped by the method name.
+    let mut samples_by_method: BTreeMap<String, (Vec<Duration>, usize)> = BTreeMap::new();
+    for worker in workers {
+        for sample in worker.await? {
+            let method = profile.methods[sample.method_index].method.clone();
+            let (latencies, failed_requests) = samples_by_method.entry(method).or_default();
+            latencies.push(sample.latency);
+            *failed_requests += usize::from(sample.failed);
+        }
+    }
+    let reports = samples_by_method
+        .into_iter()
+        .map(|(method, (latencies, failed_requests))| {
+            (method, LatencyReport::new(latencies, failed_requests))
+        })
+        .collect();
+    Ok(reports)
+}
+
+// Sends a request and returns its result. A JSON-RPC error is a failure of the request.
+async fn send_request(
+    client: &Client,
+    url: &str,
+    method: &str,
+    params: &jsonVal,
+) -> anyhow::Result<jsonVal> {
+    let response = client
+        .post(url)
+        .header("Content-Type", "application/json")
+        .body(serde_json::to_vec(&jsonrpc_request(method, params.clone()))?)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    let response: jsonVal = serde_json::from_slice(&response)?;
+    match response.get("result") {
+        Some(result) => Ok(result.clone()),
+        None => anyhow::bail!("{method} failed: {}", response["error"]),
+    }
+}
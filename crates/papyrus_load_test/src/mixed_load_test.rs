@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use test_utils::get_rng;
+
+use crate::mixed_load::{
+    fill_params,
+    percentile,
+    read_load_profile,
+    BlockHeightDistribution,
+    LatencyReport,
+};
+use crate::path_in_resources;
+
+#[test]
+fn fill_params_replaces_nested_placeholders() {
+    let params = json!([{ "block_number": "$BLOCK_NUMBER" }, "0x1", { "to_block": "latest" }]);
+    assert_eq!(
+        fill_params(&params, 7),
+        json!([{ "block_number": 7 }, "0x1", { "to_block": "latest" }])
+    );
+}
+
+#[test]
+fn percentiles_of_latencies() {
+    let latencies: Vec<_> = (1..=100).map(Duration::from_millis).collect();
+    assert_eq!(percentile(&latencies, 50), Duration::from_millis(50));
+    assert_eq!(percentile(&latencies, 99), Duration::from_millis(99));
+    assert_eq!(percentile(&[], 50), Duration::ZERO);
+
+    let report = LatencyReport::new((1..=10).rev().map(Duration::from_millis).collect(), 1);
+    assert_eq!(
+        report,
+        LatencyReport {
+            sent_requests: 10,
+            failed_requests: 1,
+            p50: Duration::from_millis(5),
+            p90: Duration::from_millis(9),
+            p99: Duration::from_millis(10),
+            max: Duration::from_millis(10),
+        }
+    );
+}
+
+#[test]
+fn sampled_block_heights_follow_the_distribution() {
+    let mut rng = get_rng();
+    let latest_block = 1000;
+    let only_recent = BlockHeightDistribution {
+        latest_weight: 0,
+        recent_weight: 1,
+        recent_blocks: 10,
+        historical_weight: 0,
+    };
+    for _ in 0..100 {
+        let block_number = only_recent.sample(&mut rng, latest_block);
+        assert!((990..=1000).contains(&block_number));
+    }
+    let only_latest = BlockHeightDistribution { latest_weight: 1, ..only_recent };
+    assert_eq!(only_latest.sample(&mut rng, latest_block), latest_block);
+}
+
+#[test]
+fn default_profile_is_valid() {
+    let profile = read_load_profile(&path_in_resources("mixed_load_profile.json")).unwrap();
+    assert!(profile.methods.iter().all(|method| method.weight > 0));
+}
@@ -0,0 +1,95 @@
+// Replays requests recorded by a node (see the rpc.record_requests_path config param) against a
+// node. If a reference node is given, the same requests are sent to it too and the responses of
+// the two nodes are compared, e.g. to find regressions between versions.
+
+use std::fs;
+use std::time::{Duration, Instant};
+
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::Value as jsonVal;
+
+// A request as recorded by the node, one per line in the recorded file.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RecordedRequest {
+    // The path the request was sent to, e.g. "/rpc/v0_7".
+    pub path: String,
+    // The JSON-RPC request body, either a single request or a batch.
+    pub body: jsonVal,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ReplaySummary {
+    pub sent_requests: usize,
+    pub failed_requests: usize,
+    pub mismatched_responses: usize,
+    pub total_latency: Duration,
+}
+
+// Reads the requests from a file written by the request recorder of the node.
+pub fn read_recorded_requests(path: &str) -> anyhow::Result<Vec<RecordedRequest>> {
+    fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).map_err(anyhow::Error::from))
+        .collect()
+}
+
+// Sends the requests one after the other to the node at node_url (e.g. "http://127.0.0.1:8080").
+// Requests that the node fails to answer, and responses that differ from the ones of the reference
+// node, are printed.
+pub async fn replay_requests(
+    requests: &[RecordedRequest],
+    node_url: &str,
+    reference_node_url: Option<&str>,
+) -> ReplaySummary {
+    let client = Client::new();
+    let mut summary = ReplaySummary::default();
+    for request in requests {
+        summary.sent_requests += 1;
+        let start = Instant::now();
+        let response = send_recorded_request(&client, node_url, request).await;
+        summary.total_latency += start.elapsed();
+        let response = match response {
+            Ok(response) => response,
+            Err(err) => {
+                summary.failed_requests += 1;
+                println!("Request {} failed: {err}", request.body);
+                continue;
+            }
+        };
+        let Some(reference_node_url) = reference_node_url else {
+            continue;
+        };
+        match send_recorded_request(&client, reference_node_url, request).await {
+            Ok(reference_response) if reference_response == response => {}
+            Ok(reference_response) => {
+                summary.mismatched_responses += 1;
+                println!(
+                    "Responses differ for request {}:\nnode: {response}\nreference node: \
+                     {reference_response}",
+                    request.body
+                );
+            }
+            Err(err) => println!("Request {} failed on the reference node: {err}", request.body),
+        }
+    }
+    summary
+}
+
+async fn send_recorded_request(
+    client: &Client,
+    node_url: &str,
+    request: &RecordedRequest,
+) -> anyhow::Result<jsonVal> {
+    let response = client
+        .post(format!("{}{}", node_url.trim_end_matches('/'), request.path))
+        .header("Content-Type", "application/json")
+        .body(serde_json::to_vec(&request.body)?)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    Ok(serde_json::from_slice(&response)?)
+}
@@ -0,0 +1,137 @@
+//! Labels the metrics with the chain they're recorded for.
+//!
+//! The node may run the nodes of several chains in one process, and the metrics of all of them are
+//! recorded to the single recorder of the process. The components of each chain run in a tracing
+//! span named [`CHAIN_SPAN_NAME`], and a metric is labeled with the chain of the span it's recorded
+//! in, so that the metrics of different chains don't overwrite each other. Metrics that are
+//! recorded outside of any chain span are labeled with the chain that installed the recorder.
+#[cfg(test)]
+#[path = "chain_label_test.rs"]
+mod chain_label_test;
+
+use std::fmt::Debug;
+
+use metrics::{
+    Counter,
+    Gauge,
+    Histogram,
+    Key,
+    KeyName,
+    Label,
+    Metadata,
+    Recorder,
+    SharedString,
+    Unit,
+};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::Subscriber;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Registry;
+
+/// The name of the tracing span in which the components of a chain run.
+pub const CHAIN_SPAN_NAME: &str = "chain";
+/// The field of the chain span that holds the id of the chain.
+pub const CHAIN_ID_FIELD: &str = "chain_id";
+/// The label of the metrics that holds the id of their chain.
+pub const CHAIN_LABEL: &str = "chain";
+
+// The id of the chain of a chain span, kept in the extensions of the span.
+struct SpanChainId(String);
+
+/// A tracing layer that keeps the chain ids of the chain spans, so that the metrics recorded in
+/// them are labeled with their chain. It should be added to the subscriber of the process.
+#[derive(Debug, Default)]
+pub struct ChainSpanLayer;
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> tracing_subscriber::Layer<S> for ChainSpanLayer {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if attrs.metadata().name() != CHAIN_SPAN_NAME {
+            return;
+        }
+        let mut visitor = ChainIdVisitor::default();
+        attrs.record(&mut visitor);
+        if let (Some(chain_id), Some(span)) = (visitor.chain_id, ctx.span(id)) {
+            span.extensions_mut().insert(SpanChainId(chain_id));
+        }
+    }
+}
+
+#[derive(Default)]
+struct ChainIdVisitor {
+    chain_id: Option<String>,
+}
+
+impl Visit for ChainIdVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == CHAIN_ID_FIELD {
+            self.chain_id = Some(value.to_owned());
+        }
+    }
+
+    // Values recorded with their display representation are visited here.
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == CHAIN_ID_FIELD {
+            self.chain_id = Some(format!("{value:?}"));
+        }
+    }
+}
+
+/// Returns the chain id of the innermost chain span that the current span is in, if any.
+pub fn current_chain_id() -> Option<String> {
+    tracing::Span::current()
+        .with_subscriber(|(id, dispatch)| {
+            let registry = dispatch.downcast_ref::<Registry>()?;
+            registry.span(id)?.scope().find_map(|span| {
+                span.extensions().get::<SpanChainId>().map(|chain_id| chain_id.0.clone())
+            })
+        })
+        .flatten()
+}
+
+/// A recorder that labels the metrics with the chain of the current span, or with the default
+/// chain outside of chain spans.
+pub struct ChainLabelRecorder<R> {
+    inner: R,
+    default_chain_id: Option<String>,
+}
+
+impl<R: Recorder> ChainLabelRecorder<R> {
+    pub fn new(inner: R, default_chain_id: Option<String>) -> Self {
+        Self { inner, default_chain_id }
+    }
+
+    fn labeled(&self, key: &Key) -> Key {
+        match current_chain_id().or_else(|| self.default_chain_id.clone()) {
+            Some(chain_id) => key.with_extra_labels(vec![Label::new(CHAIN_LABEL, chain_id)]),
+            None => key.clone(),
+        }
+    }
+}
+
+impl<R: Recorder> Recorder for ChainLabelRecorder<R> {
+    fn describe_counter(&self, key: KeyName, unit: Option<Unit>, description: SharedString) {
+        self.inner.describe_counter(key, unit, description)
+    }
+
+    fn describe_gauge(&self, key: KeyName, unit: Option<Unit>, description: SharedString) {
+        self.inner.describe_gauge(key, unit, description)
+    }
+
+    fn describe_histogram(&self, key: KeyName, unit: Option<Unit>, description: SharedString) {
+        self.inner.describe_histogram(key, unit, description)
+    }
+
+    fn register_counter(&self, key: &Key, metadata: &Metadata<'_>) -> Counter {
+        self.inner.register_counter(&self.labeled(key), metadata)
+    }
+
+    fn register_gauge(&self, key: &Key, metadata: &Metadata<'_>) -> Gauge {
+        self.inner.register_gauge(&self.labeled(key), metadata)
+    }
+
+    fn register_histogram(&self, key: &Key, metadata: &Metadata<'_>) -> Histogram {
+        self.inner.register_histogram(&self.labeled(key), metadata)
+    }
+}
@@ -0,0 +1,85 @@
+use std::sync::{Arc, Mutex};
+
+use metrics::{
+    Counter,
+    Gauge,
+    Histogram,
+    Key,
+    KeyName,
+    Label,
+    Metadata,
+    Recorder,
+    SharedString,
+    Unit,
+};
+use pretty_assertions::assert_eq;
+use tracing::{error_span, info_span};
+use tracing_subscriber::prelude::*;
+
+use crate::chain_label::{ChainLabelRecorder, ChainSpanLayer, CHAIN_LABEL};
+
+// A recorder that keeps the keys of the registered counters.
+#[derive(Clone, Default)]
+struct KeysRecorder(Arc<Mutex<Vec<Key>>>);
+
+impl Recorder for KeysRecorder {
+    fn describe_counter(&self, _key: KeyName, _unit: Option<Unit>, _description: SharedString) {}
+
+    fn describe_gauge(&self, _key: KeyName, _unit: Option<Unit>, _description: SharedString) {}
+
+    fn describe_histogram(&self, _key: KeyName, _unit: Option<Unit>, _description: SharedString) {}
+
+    fn register_counter(&self, key: &Key, _metadata: &Metadata<'_>) -> Counter {
+        self.0.lock().unwrap().push(key.clone());
+        Counter::noop()
+    }
+
+    fn register_gauge(&self, _key: &Key, _metadata: &Metadata<'_>) -> Gauge {
+        Gauge::noop()
+    }
+
+    fn register_histogram(&self, _key: &Key, _metadata: &Metadata<'_>) -> Histogram {
+        Histogram::noop()
+    }
+}
+
+fn chain_labels(keys: &[Key]) -> Vec<Option<String>> {
+    keys.iter()
+        .map(|key| {
+            key.labels()
+                .find(|label| label.key() == CHAIN_LABEL)
+                .map(|label| label.value().to_owned())
+        })
+        .collect()
+}
+
+#[test]
+fn metrics_are_labeled_with_the_chain_of_the_span() {
+    let keys_recorder = KeysRecorder::default();
+    let recorder = ChainLabelRecorder::new(keys_recorder.clone(), Some("SN_MAIN".to_owned()));
+    let metadata = Metadata::new(module_path!(), metrics::Level::INFO, None);
+    let key = Key::from_parts("requests", vec![Label::new("method", "getBlock")]);
+
+    let subscriber = tracing_subscriber::registry().with(ChainSpanLayer);
+    tracing::subscriber::with_default(subscriber, || {
+        // Outside of any chain span the metrics are labeled with the default chain.
+        recorder.register_counter(&key, &metadata);
+        error_span!("chain", chain_id = %"SN_SEPOLIA").in_scope(|| {
+            recorder.register_counter(&key, &metadata);
+            // The spans nested in a chain span belong to its chain.
+            info_span!("rpc_request").in_scope(|| recorder.register_counter(&key, &metadata));
+        });
+    });
+
+    let keys = keys_recorder.0.lock().unwrap().clone();
+    assert_eq!(
+        chain_labels(&keys),
+        vec![
+            Some("SN_MAIN".to_owned()),
+            Some("SN_SEPOLIA".to_owned()),
+            Some("SN_SEPOLIA".to_owned())
+        ]
+    );
+    // The labels of the metric are kept.
+    assert!(keys.iter().all(|key| key.labels().any(|label| label.key() == "method")));
+}
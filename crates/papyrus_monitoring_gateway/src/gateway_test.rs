@@ -4,23 +4,33 @@ use std::sync::Arc;
 use axum::body::Body;
 use axum::http::{Request, StatusCode};
 use axum::response::Response;
-use axum::Router;
+use axum::{Json, Router};
 use http_body::combinators::UnsyncBoxBody;
 use metrics::{absolute_counter, describe_counter, register_counter};
 use metrics_exporter_prometheus::PrometheusBuilder;
+use papyrus_common::external_addresses::ExternalAddresses;
+use papyrus_common::BlockHashAndNumber;
+use papyrus_storage::node_identity::{NodeIdentityStorageWriter, NodeRun};
+use papyrus_storage::quarantine::QuarantineStorageWriter;
+use papyrus_storage::state::StateStorageWriter;
 use papyrus_storage::{table_names, test_utils};
 use pretty_assertions::assert_eq;
 use serde_json::{json, Value};
+use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::state::ThinStateDiff;
 use starknet_client::reader::MockStarknetReader;
 use starknet_client::writer::MockStarknetWriter;
+use tokio::sync::RwLock;
 use tower::ServiceExt;
+use validator::Validate;
 
-use crate::{app, is_ready, MONITORING_PREFIX};
+use crate::{app, is_ready, MonitoringGatewayConfig, ReadyStatus, MONITORING_PREFIX};
 
 const TEST_CONFIG_PRESENTATION: &str = "full_general_config_presentation";
 const PUBLIC_TEST_CONFIG_PRESENTATION: &str = "public_general_config_presentation";
 const SECRET: &str = "abcd";
 const TEST_VERSION: &str = "1.2.3-dev";
+const MAX_BLOCKS_BEHIND_CENTRAL: u64 = 10;
 
 // TODO(dan): consider using a proper fixture.
 fn setup_app() -> Router {
@@ -33,6 +43,10 @@ fn setup_app() -> Router {
         serde_json::to_value(PUBLIC_TEST_CONFIG_PRESENTATION).unwrap(),
         SECRET.to_string(),
         None,
+        Arc::default(),
+        Arc::default(),
+        Arc::default(),
+        MAX_BLOCKS_BEHIND_CENTRAL,
     )
 }
 
@@ -68,6 +82,88 @@ async fn db_stats() {
     }
 }
 
+#[tokio::test]
+async fn quarantined_blocks() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = test_utils::get_test_storage();
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .quarantine_body(BlockNumber(3), "Invalid transaction.")
+        .unwrap()
+        .commit()
+        .unwrap();
+    let app = app(
+        String::from("https://default_url"),
+        storage_reader,
+        TEST_VERSION,
+        serde_json::to_value(TEST_CONFIG_PRESENTATION).unwrap(),
+        serde_json::to_value(PUBLIC_TEST_CONFIG_PRESENTATION).unwrap(),
+        SECRET.to_string(),
+        None,
+        Arc::default(),
+        Arc::default(),
+        Arc::default(),
+        MAX_BLOCKS_BEHIND_CENTRAL,
+    );
+    let response = request_app(app, "quarantinedBlocks").await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        body,
+        json!({"3": {"body_error": "Invalid transaction.", "state_diff_error": null}})
+    );
+}
+
+#[tokio::test]
+async fn node_identity() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = test_utils::get_test_storage();
+    let run = NodeRun {
+        start_timestamp: 5,
+        version: TEST_VERSION.to_owned(),
+        build_commit: "abcdef".to_owned(),
+        config_hash: "0x1".to_owned(),
+    };
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .record_node_run("node_id", run)
+        .unwrap()
+        .commit()
+        .unwrap();
+    let app = app(
+        String::from("https://default_url"),
+        storage_reader,
+        TEST_VERSION,
+        serde_json::to_value(TEST_CONFIG_PRESENTATION).unwrap(),
+        serde_json::to_value(PUBLIC_TEST_CONFIG_PRESENTATION).unwrap(),
+        SECRET.to_string(),
+        None,
+        Arc::default(),
+        Arc::default(),
+        Arc::default(),
+        MAX_BLOCKS_BEHIND_CENTRAL,
+    );
+    let response = request_app(app, "nodeIdentity").await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        body,
+        json!({
+            "node_id": "node_id",
+            "runs": [{
+                "start_timestamp": 5,
+                "version": TEST_VERSION,
+                "build_commit": "abcdef",
+                "config_hash": "0x1",
+            }],
+        })
+    );
+}
+
 #[tokio::test]
 async fn version() {
     let app = setup_app();
@@ -108,6 +204,53 @@ async fn node_config_invalid_secret() {
     assert_eq!(response.status(), StatusCode::FORBIDDEN);
 }
 
+#[tokio::test]
+async fn maintenance_task() {
+    let app = setup_app();
+    let post_task = |task_and_secret: &str| {
+        app.clone().oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!("/{MONITORING_PREFIX}/maintenance/{task_and_secret}").as_str())
+                .body(Body::empty())
+                .unwrap(),
+        )
+    };
+
+    let response = post_task("verify/zzz").await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = request_app(app.clone(), "maintenanceStatus/zzz").await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = post_task(format!("unknown/{SECRET}").as_str()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    // Pruning needs the block below which to prune.
+    let response = post_task(format!("prune/{SECRET}").as_str()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = post_task(format!("compact/{SECRET}").as_str()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_IMPLEMENTED);
+
+    let response = post_task(format!("verify/{SECRET}").as_str()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+
+    // Verifying an empty storage ends immediately.
+    loop {
+        let response =
+            request_app(app.clone(), format!("maintenanceStatus/{SECRET}").as_str()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["task"], json!("verify"));
+        if body["state"] == json!("running") {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            continue;
+        }
+        assert_eq!(body["state"], json!("done"));
+        assert_eq!(body["inconsistencies"], json!([]));
+        break;
+    }
+}
+
 #[tokio::test]
 async fn alive() {
     let app = setup_app();
@@ -117,16 +260,79 @@ async fn alive() {
 }
 
 #[tokio::test]
-async fn ready() {
-    let mut gateway_client_mock = MockStarknetWriter::new();
-    let mut feeder_gateway_client_mock = MockStarknetReader::new();
+async fn p2p_external_addresses() {
+    let ((storage_reader, _), _temp_dir) = test_utils::get_test_storage();
+    let p2p_external_addresses = Arc::new(ExternalAddresses::default());
+    let app = app(
+        String::from("https://default_url"),
+        storage_reader,
+        TEST_VERSION,
+        serde_json::to_value(TEST_CONFIG_PRESENTATION).unwrap(),
+        serde_json::to_value(PUBLIC_TEST_CONFIG_PRESENTATION).unwrap(),
+        SECRET.to_string(),
+        None,
+        p2p_external_addresses.clone(),
+        Arc::default(),
+        Arc::default(),
+        MAX_BLOCKS_BEHIND_CENTRAL,
+    );
 
-    gateway_client_mock.expect_is_alive().times(1).returning(|| true);
-    feeder_gateway_client_mock.expect_is_alive().times(1).returning(|| true);
+    let response = request_app(app.clone(), "p2pExternalAddresses").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body, json!([]));
+
+    p2p_external_addresses.add("/ip4/1.2.3.4/tcp/10000".to_owned());
+    let response = request_app(app, "p2pExternalAddresses").await;
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body, json!(["/ip4/1.2.3.4/tcp/10000"]));
+}
 
-    let response: String =
-        is_ready(Arc::new(gateway_client_mock), Arc::new(feeder_gateway_client_mock)).await;
-    assert_eq!(response, StatusCode::OK.to_string());
+#[tokio::test]
+async fn ready() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = test_utils::get_test_storage();
+    let shared_highest_block = Arc::new(RwLock::new(None));
+    let check_ready = || {
+        let mut gateway_client_mock = MockStarknetWriter::new();
+        let mut feeder_gateway_client_mock = MockStarknetReader::new();
+        gateway_client_mock.expect_is_alive().times(1).returning(|| true);
+        feeder_gateway_client_mock.expect_is_alive().times(1).returning(|| true);
+        is_ready(
+            Arc::new(gateway_client_mock),
+            Arc::new(feeder_gateway_client_mock),
+            storage_reader.clone(),
+            shared_highest_block.clone(),
+            MAX_BLOCKS_BEHIND_CENTRAL,
+        )
+    };
+
+    // The lag is unknown before the sync sees the central head.
+    let (status, Json(ready_status)) = check_ready().await.unwrap();
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(ready_status, ReadyStatus { serving: true });
+
+    let central_head = |block_number| BlockHashAndNumber {
+        block_hash: BlockHash::default(),
+        block_number: BlockNumber(block_number),
+    };
+    *shared_highest_block.write().await = Some(central_head(MAX_BLOCKS_BEHIND_CENTRAL));
+    let (status, Json(ready_status)) = check_ready().await.unwrap();
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(ready_status, ReadyStatus { serving: false });
+
+    // Catching up to the allowed lag.
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_thin_state_diff(BlockNumber(0), ThinStateDiff::default())
+        .unwrap()
+        .commit()
+        .unwrap();
+    let (status, Json(ready_status)) = check_ready().await.unwrap();
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(ready_status, ReadyStatus { serving: true });
 }
 
 #[tokio::test]
@@ -152,6 +358,10 @@ async fn with_metrics() {
         serde_json::Value::default(),
         String::new(),
         Some(prometheus_handle),
+        Arc::default(),
+        Arc::default(),
+        Arc::default(),
+        MAX_BLOCKS_BEHIND_CENTRAL,
     );
 
     // Register a metric.
@@ -211,3 +421,19 @@ fn serialization_precision() {
     let deserialized = serde_json::to_string(&serialized).unwrap();
     assert_eq!(input, deserialized);
 }
+
+#[test]
+fn server_address_validation() {
+    for (server_address, is_valid) in [
+        ("0.0.0.0:8081", true),
+        ("0.0.0.0:8081 [::]:8081", true),
+        ("", false),
+        ("0.0.0.0:8081 localhost:8081", false),
+    ] {
+        let config = MonitoringGatewayConfig {
+            server_address: server_address.to_owned(),
+            ..Default::default()
+        };
+        assert_eq!(config.validate().is_ok(), is_valid, "{server_address}");
+    }
+}
@@ -2,8 +2,10 @@
 // within this crate
 #![cfg_attr(coverage_nightly, feature(coverage_attribute))]
 
+pub mod chain_label;
 #[cfg(test)]
 mod gateway_test;
+mod maintenance;
 
 use std::collections::{BTreeMap, HashMap};
 use std::fmt::Display;
@@ -11,31 +13,52 @@ use std::net::SocketAddr;
 use std::str::FromStr;
 use std::sync::Arc;
 
-use axum::extract::Path;
+use axum::extract::{Path, Query};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
-use axum::routing::get;
+use axum::routing::{get, post};
 use axum::{Json, Router};
+use futures_util::future::try_join_all;
 use metrics_exporter_prometheus::{BuildError, PrometheusBuilder, PrometheusHandle};
 use metrics_process::Collector;
+use papyrus_common::external_addresses::ExternalAddresses;
+use papyrus_common::maintenance_requests::MaintenanceRequests;
+use papyrus_common::BlockHashAndNumber;
 use papyrus_config::converters::{deserialize_optional_map, serialize_optional_map};
 use papyrus_config::dumping::{ser_generated_param, ser_param, SerializeConfig};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializationType, SerializedParam};
+use papyrus_storage::node_identity::{NodeIdentity, NodeIdentityStorageReader};
+use papyrus_storage::quarantine::{QuarantineStorageReader, QuarantinedBlock};
+use papyrus_storage::state::StateStorageReader;
 use papyrus_storage::{DbStats, StorageError, StorageReader};
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
 use serde::{Deserialize, Serialize};
+use starknet_api::block::BlockNumber;
 use starknet_client::reader::{StarknetFeederGatewayClient, StarknetReader};
 use starknet_client::writer::{StarknetGatewayClient, StarknetWriter};
 use starknet_client::RetryConfig;
+use tokio::sync::RwLock;
 use tracing::{debug, info, instrument};
-use validator::Validate;
+use validator::{Validate, ValidationError};
+
+use crate::chain_label::{current_chain_id, ChainLabelRecorder};
+use crate::maintenance::{
+    MaintenanceParams,
+    MaintenanceStatus,
+    MaintenanceTask,
+    SharedMaintenanceStatus,
+};
 
 const MONITORING_PREFIX: &str = "monitoring";
 const PROCESS_METRICS_PREFIX: &str = "papyrus_";
+const NODE_ID_LABEL: &str = "node_id";
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Validate)]
 pub struct MonitoringGatewayConfig {
+    /// The addresses on which the server listens, separated by whitespace. For example,
+    /// "0.0.0.0:8081 [::]:8081" listens on both IPv4 and IPv6.
+    #[validate(custom = "validate_server_address")]
     pub server_address: String,
     pub collect_metrics: bool,
     #[serde(deserialize_with = "deserialize_optional_map")]
@@ -44,6 +67,9 @@ pub struct MonitoringGatewayConfig {
     #[serde(default = "random_secret")]
     pub present_full_config_secret: String,
     pub starknet_url: String,
+    /// The number of blocks the node may be behind the head of the central chain while /ready
+    /// still reports it as serving.
+    pub max_blocks_behind_central: u64,
 }
 
 fn random_secret() -> String {
@@ -61,6 +87,7 @@ impl Default for MonitoringGatewayConfig {
             // A constant value for testing purposes.
             present_full_config_secret: String::from("qwerty"),
             starknet_url: String::from("https://alpha-mainnet.starknet.io/"),
+            max_blocks_behind_central: 10,
         }
     }
 }
@@ -71,7 +98,8 @@ impl SerializeConfig for MonitoringGatewayConfig {
             ser_param(
                 "server_address",
                 &self.server_address,
-                "node's monitoring server.",
+                "'IP1:PORT1 IP2:PORT2 ...' addresses of the node's monitoring server. The server \
+                 listens on all of them, for example on both IPv4 and IPv6.",
                 ParamPrivacyInput::Public,
             ),
             ser_param(
@@ -98,10 +126,35 @@ impl SerializeConfig for MonitoringGatewayConfig {
                 "The URL of a centralized Starknet gateway.",
                 ParamPrivacyInput::Public,
             ),
+            ser_param(
+                "max_blocks_behind_central",
+                &self.max_blocks_behind_central,
+                "The number of blocks the node may be behind the head of the central chain while \
+                 /ready still reports it as serving. A node that is further behind responds to \
+                 /ready with status code 503, so that load balancers stop routing to it.",
+                ParamPrivacyInput::Public,
+            ),
         ])
     }
 }
 
+fn validate_server_address(server_address: &str) -> Result<(), ValidationError> {
+    let mut server_addresses = server_address.split_whitespace().peekable();
+    if server_addresses.peek().is_none() {
+        let mut error = ValidationError::new("no server address");
+        error.message = Some("The server should listen on at least one address.".into());
+        return Err(error);
+    }
+    for server_address in server_addresses {
+        if SocketAddr::from_str(server_address).is_err() {
+            let mut error = ValidationError::new("invalid server address");
+            error.message = Some(format!("{server_address} isn't an IP:PORT address.").into());
+            return Err(error);
+        }
+    }
+    Ok(())
+}
+
 impl Display for MonitoringGatewayConfig {
     #[cfg_attr(coverage_nightly, coverage_attribute)]
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -118,6 +171,12 @@ pub struct MonitoringServer {
     storage_reader: StorageReader,
     version: &'static str,
     prometheus_handle: Option<PrometheusHandle>,
+    // The addresses on which other peers can dial the node's P2P network.
+    p2p_external_addresses: Arc<ExternalAddresses>,
+    // The head of the central chain, as last seen by the sync.
+    shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
+    // The requests of the maintenance tasks that the owner of the storage writer runs.
+    maintenance_requests: Arc<MaintenanceRequests>,
 }
 
 impl MonitoringServer {
@@ -127,15 +186,27 @@ impl MonitoringServer {
         public_general_config_presentation: serde_json::Value,
         storage_reader: StorageReader,
         version: &'static str,
+        node_id: &str,
+        p2p_external_addresses: Arc<ExternalAddresses>,
+        shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
+        maintenance_requests: Arc<MaintenanceRequests>,
     ) -> Result<Self, BuildError> {
         let prometheus_handle = if config.collect_metrics {
-            let mut builder = PrometheusBuilder::new();
+            // Labels the metrics with the id of the node, unless the config overrides it.
+            let mut builder = PrometheusBuilder::new().add_global_label(NODE_ID_LABEL, node_id);
             if let Some(metric_labels) = &config.metric_labels {
                 for (label, value) in metric_labels {
                     builder = builder.add_global_label(label, value);
                 }
             }
-            Some(builder.install_recorder()?)
+            // The recorder is installed in the span of the chain whose metrics are collected.
+            let recorder = builder.build_recorder();
+            let handle = recorder.handle();
+            metrics::set_boxed_recorder(Box::new(ChainLabelRecorder::new(
+                recorder,
+                current_chain_id(),
+            )))?;
+            Some(handle)
         } else {
             None
         };
@@ -146,12 +217,20 @@ impl MonitoringServer {
             public_general_config_presentation,
             version,
             prometheus_handle,
+            p2p_external_addresses,
+            shared_highest_block,
+            maintenance_requests,
         })
     }
 
     /// Spawns a monitoring server.
     pub async fn spawn_server(self) -> tokio::task::JoinHandle<Result<(), hyper::Error>> {
-        tokio::spawn(async move { self.run_server().await })
+        tokio::spawn(self.run())
+    }
+
+    /// Runs the monitoring server until it fails.
+    pub async fn run(self) -> Result<(), hyper::Error> {
+        self.run_server().await
     }
 
     #[instrument(
@@ -164,8 +243,15 @@ impl MonitoringServer {
             present_full_config_secret = %self.config.present_full_config_secret),
         level = "debug")]
     async fn run_server(&self) -> std::result::Result<(), hyper::Error> {
-        let server_address = SocketAddr::from_str(&self.config.server_address)
-            .expect("Configuration value for monitor server address should be valid");
+        let server_addresses = self
+            .config
+            .server_address
+            .split_whitespace()
+            .map(|server_address| {
+                SocketAddr::from_str(server_address)
+                    .expect("Configuration value for monitor server address should be valid")
+            })
+            .collect::<Vec<_>>();
         let app = app(
             self.config.starknet_url.clone(),
             self.storage_reader.clone(),
@@ -174,9 +260,17 @@ impl MonitoringServer {
             self.public_general_config_presentation.clone(),
             self.config.present_full_config_secret.clone(),
             self.prometheus_handle.clone(),
+            self.p2p_external_addresses.clone(),
+            self.shared_highest_block.clone(),
+            self.maintenance_requests.clone(),
+            self.config.max_blocks_behind_central,
         );
         debug!("Starting monitoring gateway.");
-        axum::Server::bind(&server_address).serve(app.into_make_service()).await
+        let servers = server_addresses.iter().map(|server_address| {
+            axum::Server::bind(server_address).serve(app.clone().into_make_service())
+        });
+        try_join_all(servers).await?;
+        Ok(())
     }
 }
 
@@ -188,6 +282,10 @@ fn app(
     public_general_config_presentation: serde_json::Value,
     present_full_config_secret: String,
     prometheus_handle: Option<PrometheusHandle>,
+    p2p_external_addresses: Arc<ExternalAddresses>,
+    shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
+    maintenance_requests: Arc<MaintenanceRequests>,
+    max_blocks_behind_central: u64,
 ) -> Router {
     let is_ready_retry_config =
         RetryConfig { retry_base_millis: 50, retry_max_delay_millis: 1000, max_retries: 0 };
@@ -204,12 +302,24 @@ fn app(
         StarknetGatewayClient::new(starknet_url.as_str(), version, is_ready_retry_config)
             .expect("Failed creating Starknet client."),
     );
+    let maintenance_status = SharedMaintenanceStatus::default();
+    let maintenance_storage_reader = storage_reader.clone();
+    let quarantine_storage_reader = storage_reader.clone();
+    let identity_storage_reader = storage_reader.clone();
+    let ready_storage_reader = storage_reader.clone();
+    let maintenance_status_secret = present_full_config_secret.clone();
+    let maintenance_task_secret = present_full_config_secret.clone();
+    let maintenance_task_status = maintenance_status.clone();
 
     Router::new()
         .route(
             format!("/{MONITORING_PREFIX}/dbTablesStats").as_str(),
             get(move || db_tables_stats(storage_reader)),
         )
+        .route(
+            format!("/{MONITORING_PREFIX}/quarantinedBlocks").as_str(),
+            get(move || quarantined_blocks(quarantine_storage_reader)),
+        )
         .route(
             format!("/{MONITORING_PREFIX}/nodeConfig").as_str(),
             get(move || node_config(public_general_config_presentation)),
@@ -229,6 +339,10 @@ fn app(
             format!("/{MONITORING_PREFIX}/nodeVersion").as_str(),
             get(move || node_version(version)),
         )
+        .route(
+            format!("/{MONITORING_PREFIX}/nodeIdentity").as_str(),
+            get(move || node_identity(identity_storage_reader)),
+        )
         .route(
             format!("/{MONITORING_PREFIX}/alive").as_str(),
             get(move || async { StatusCode::OK.to_string() }),
@@ -237,23 +351,78 @@ fn app(
             format!("/{MONITORING_PREFIX}/metrics").as_str(),
             get(move || metrics(prometheus_handle)),
         )
+        .route(
+            format!("/{MONITORING_PREFIX}/p2pExternalAddresses").as_str(),
+            get(move || node_p2p_external_addresses(p2p_external_addresses)),
+        )
         .route(
             format!("/{MONITORING_PREFIX}/ready").as_str(),
-            get(move || is_ready(starknet_client, starknet_feeder_client)),
+            get(move || {
+                is_ready(
+                    starknet_client,
+                    starknet_feeder_client,
+                    ready_storage_reader,
+                    shared_highest_block,
+                    max_blocks_behind_central,
+                )
+            }),
         )
+        .route(
+            format!("/{MONITORING_PREFIX}/maintenance/:task/*secret").as_str(),
+            post(move |task_and_secret, params| {
+                start_maintenance_task(
+                    maintenance_storage_reader,
+                    maintenance_requests,
+                    maintenance_task_status,
+                    task_and_secret,
+                    params,
+                    maintenance_task_secret,
+                )
+            }),
+        )
+        .route(
+            format!("/{MONITORING_PREFIX}/maintenanceStatus/*secret").as_str(),
+            get(move |secret| {
+                maintenance_status_by_secret(maintenance_status, secret, maintenance_status_secret)
+            }),
+        )
+}
+
+/// Whether the node should receive requests.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+struct ReadyStatus {
+    /// False if the node is more than the configured number of blocks behind the head of the
+    /// central chain.
+    serving: bool,
 }
 
+/// Returns whether the node is serving. Responds with status code 503 if it isn't, so that load
+/// balancers stop routing requests to a node that lags behind the chain.
 async fn is_ready<TStarknetWriter: StarknetWriter, TStarknetReader: StarknetReader>(
     starknet_client: Arc<TStarknetWriter>,
     starknet_feeder_client: Arc<TStarknetReader>,
-) -> String {
+    storage_reader: StorageReader,
+    shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
+    max_blocks_behind_central: u64,
+) -> Result<(StatusCode, Json<ReadyStatus>), ServerError> {
     let response = starknet_feeder_client.is_alive().await;
     assert!(response);
 
     let response = starknet_client.is_alive().await;
     assert!(response);
 
-    StatusCode::OK.to_string()
+    // The lag is unknown until the sync sees the central head, and isn't measured if the node
+    // doesn't sync from the central chain.
+    let serving = match *shared_highest_block.read().await {
+        None => true,
+        Some(central_head) => {
+            let state_marker = storage_reader.begin_ro_txn()?.get_state_marker()?;
+            central_head.block_number.next().0.saturating_sub(state_marker.0)
+                <= max_blocks_behind_central
+        }
+    };
+    let status = if serving { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    Ok((status, Json(ReadyStatus { serving })))
 }
 
 /// Returns DB statistics.
@@ -262,6 +431,22 @@ async fn db_tables_stats(storage_reader: StorageReader) -> Result<Json<DbStats>,
     Ok(storage_reader.db_tables_stats()?.into())
 }
 
+/// Returns the blocks that the sync quarantined, with the errors because of which they were.
+#[instrument(skip(storage_reader), level = "debug", ret)]
+async fn quarantined_blocks(
+    storage_reader: StorageReader,
+) -> Result<Json<BTreeMap<BlockNumber, QuarantinedBlock>>, ServerError> {
+    Ok(storage_reader.begin_ro_txn()?.get_quarantined_blocks()?.into())
+}
+
+/// Returns the id of the node and the history of its runs, or null if no run was recorded.
+#[instrument(skip(storage_reader), level = "debug", ret)]
+async fn node_identity(
+    storage_reader: StorageReader,
+) -> Result<Json<Option<NodeIdentity>>, ServerError> {
+    Ok(storage_reader.begin_ro_txn()?.get_node_identity()?.into())
+}
+
 /// Returns the node config.
 #[instrument(level = "debug", ret)]
 async fn node_config(
@@ -284,6 +469,53 @@ async fn node_config_by_secret(
     }
 }
 
+/// Returns the addresses on which other peers can dial the node's P2P network, as detected by the
+/// network. Empty if the network is disabled or no address was confirmed yet.
+#[instrument(skip(p2p_external_addresses), level = "debug", ret)]
+async fn node_p2p_external_addresses(
+    p2p_external_addresses: Arc<ExternalAddresses>,
+) -> Json<Vec<String>> {
+    p2p_external_addresses.get().into()
+}
+
+/// Starts a maintenance task on the storage in the background. Responds with status code 202 if
+/// the task started, 409 if another task is running, 400 if the task is missing a parameter (the
+/// block number of a prune, e.g. `maintenance/prune/<secret>?block_number=1000`) and 501 if the
+/// node doesn't support the task.
+#[instrument(
+    skip(storage_reader, maintenance_requests, maintenance_status, expected_secret),
+    level = "debug",
+    ret
+)]
+async fn start_maintenance_task(
+    storage_reader: StorageReader,
+    maintenance_requests: Arc<MaintenanceRequests>,
+    maintenance_status: SharedMaintenanceStatus,
+    Path((task, given_secret)): Path<(MaintenanceTask, String)>,
+    Query(params): Query<MaintenanceParams>,
+    expected_secret: String,
+) -> StatusCode {
+    if given_secret != expected_secret {
+        return StatusCode::FORBIDDEN;
+    }
+    maintenance::start_task(task, params, storage_reader, maintenance_requests, maintenance_status)
+}
+
+/// Returns the progress of the last maintenance task.
+#[instrument(skip(maintenance_status, expected_secret), level = "debug", ret)]
+async fn maintenance_status_by_secret(
+    maintenance_status: SharedMaintenanceStatus,
+    given_secret: Path<String>,
+    expected_secret: String,
+) -> Result<Json<MaintenanceStatus>, StatusCode> {
+    if given_secret.to_string() != expected_secret {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let status =
+        maintenance_status.lock().expect("Maintenance status lock should not be poisoned.").clone();
+    Ok(status.into())
+}
+
 /// Returns prometheus metrics.
 /// In case the node doesn’t collect metrics returns an empty response with status code 405: method
 /// not allowed.
@@ -0,0 +1,249 @@
+//! Maintenance tasks that operators can run on the storage of a live node through the monitoring
+//! gateway. Only one task runs at a time, and its progress is kept until the next task starts.
+//!
+//! The gateway only reads the storage. Tasks that write to it are sent as requests to the owner of
+//! the storage writer, which is the sync, and run between its writes.
+#[cfg(test)]
+#[path = "maintenance_test.rs"]
+mod maintenance_test;
+
+use std::cmp::min;
+use std::sync::{Arc, Mutex};
+
+use axum::http::StatusCode;
+use papyrus_common::maintenance_requests::MaintenanceRequests;
+use papyrus_storage::body::BodyStorageReader;
+use papyrus_storage::header::HeaderStorageReader;
+use papyrus_storage::state::StateStorageReader;
+use papyrus_storage::{NodeMode, StorageReader, StorageResult};
+use serde::{Deserialize, Serialize};
+use starknet_api::block::{BlockHash, BlockNumber};
+use tracing::{info, warn};
+
+// The number of blocks verified in a single read transaction, so that a verification of the whole
+// storage doesn't hold a read transaction open for its entire duration.
+const VERIFICATION_CHUNK_SIZE: u64 = 1000;
+// The number of blocks pruned in a single request to the storage writer, so that the sync isn't
+// held back from storing new blocks for the entire pruning.
+const PRUNING_CHUNK_SIZE: u64 = 1000;
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum MaintenanceTask {
+    /// Checks that the stored blocks are consistent with each other and with the markers.
+    Verify,
+    /// Prunes the history of the state below the block given in the request, so that the state
+    /// right before it becomes the oldest queryable state. Supported only in Full mode.
+    Prune,
+    /// Compacts the database file. Not supported: MDBX compacts a database only into a copy, and
+    /// the storage keeps part of its data in files beside the database that a copy would have to
+    /// match, so compacting still requires stopping the node and using the MDBX tools.
+    Compact,
+}
+
+/// The parameters of a maintenance task, given in the query of the request.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, Eq, PartialEq)]
+pub(crate) struct MaintenanceParams {
+    /// The block below which [`MaintenanceTask::Prune`] prunes the state history.
+    pub block_number: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum TaskState {
+    #[default]
+    Idle,
+    Running,
+    Done,
+    Failed {
+        error: String,
+    },
+}
+
+/// The progress of the last maintenance task.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, Eq, PartialEq)]
+pub(crate) struct MaintenanceStatus {
+    pub task: Option<MaintenanceTask>,
+    pub state: TaskState,
+    pub processed_blocks: u64,
+    pub total_blocks: u64,
+    pub inconsistencies: Vec<String>,
+}
+
+pub(crate) type SharedMaintenanceStatus = Arc<Mutex<MaintenanceStatus>>;
+
+/// Starts the task in the background unless another task is running. Returns the status code of
+/// the response to the request that started the task.
+pub(crate) fn start_task(
+    task: MaintenanceTask,
+    params: MaintenanceParams,
+    storage_reader: StorageReader,
+    maintenance_requests: Arc<MaintenanceRequests>,
+    status: SharedMaintenanceStatus,
+) -> StatusCode {
+    let prune_below = match task {
+        MaintenanceTask::Verify => None,
+        MaintenanceTask::Prune => {
+            let Some(block_number) = params.block_number else {
+                return StatusCode::BAD_REQUEST;
+            };
+            if storage_reader.get_node_mode() != NodeMode::Full {
+                return StatusCode::NOT_IMPLEMENTED;
+            }
+            Some(BlockNumber(block_number))
+        }
+        MaintenanceTask::Compact => return StatusCode::NOT_IMPLEMENTED,
+    };
+    {
+        let mut status = status.lock().expect("Maintenance status lock should not be poisoned.");
+        if status.state == TaskState::Running {
+            return StatusCode::CONFLICT;
+        }
+        *status =
+            MaintenanceStatus { task: Some(task), state: TaskState::Running, ..Default::default() };
+    }
+
+    info!("Starting maintenance task {task:?}.");
+    match prune_below {
+        None => {
+            tokio::task::spawn_blocking(move || {
+                let result =
+                    verify_storage(&storage_reader, &status).map_err(|err| err.to_string());
+                finish_task(task, result, &status);
+            });
+        }
+        Some(below) => {
+            tokio::spawn(async move {
+                let result =
+                    prune_state_history(&storage_reader, &maintenance_requests, below, &status)
+                        .await;
+                finish_task(task, result, &status);
+            });
+        }
+    }
+    StatusCode::ACCEPTED
+}
+
+fn finish_task(
+    task: MaintenanceTask,
+    result: Result<(), String>,
+    status: &Mutex<MaintenanceStatus>,
+) {
+    let mut status = status.lock().expect("Maintenance status lock should not be poisoned.");
+    status.state = match result {
+        Ok(()) => {
+            info!(
+                "Maintenance task {task:?} done. Found {} inconsistencies.",
+                status.inconsistencies.len()
+            );
+            TaskState::Done
+        }
+        Err(error) => {
+            warn!("Maintenance task {task:?} failed: {error}.");
+            TaskState::Failed { error }
+        }
+    };
+}
+
+// Prunes the state history below the block, a chunk of blocks per request to the storage writer.
+// Pruning stops at the state marker, as the writer does.
+async fn prune_state_history(
+    storage_reader: &StorageReader,
+    maintenance_requests: &MaintenanceRequests,
+    below: BlockNumber,
+    status: &Mutex<MaintenanceStatus>,
+) -> Result<(), String> {
+    let txn = storage_reader.begin_ro_txn().map_err(|err| err.to_string())?;
+    let first_block = txn.get_pruned_state_marker().map_err(|err| err.to_string())?;
+    let below = min(below, txn.get_state_marker().map_err(|err| err.to_string())?);
+    drop(txn);
+
+    let total_blocks = below.0.saturating_sub(first_block.0);
+    report_progress(status, 0, total_blocks, vec![]);
+    for chunk_start in (first_block.0..below.0).step_by(PRUNING_CHUNK_SIZE as usize) {
+        let chunk_end = min(chunk_start + PRUNING_CHUNK_SIZE, below.0);
+        maintenance_requests.prune_state_history(BlockNumber(chunk_end)).await?;
+        report_progress(status, chunk_end - first_block.0, total_blocks, vec![]);
+    }
+    Ok(())
+}
+
+// Verifies that every block below the header marker has a header that points to the previous
+// block, and that the bodies and state diffs exist below their markers. The inconsistencies are
+// reported through the status.
+fn verify_storage(
+    storage_reader: &StorageReader,
+    status: &Mutex<MaintenanceStatus>,
+) -> StorageResult<()> {
+    let txn = storage_reader.begin_ro_txn()?;
+    let header_marker = txn.get_header_marker()?;
+    let body_marker = txn.get_body_marker()?;
+    let state_marker = txn.get_state_marker()?;
+    drop(txn);
+
+    let mut inconsistencies = vec![];
+    if body_marker > header_marker {
+        inconsistencies
+            .push(format!("Body marker {body_marker} is above header marker {header_marker}."));
+    }
+    if state_marker > header_marker {
+        inconsistencies
+            .push(format!("State marker {state_marker} is above header marker {header_marker}."));
+    }
+    report_progress(status, 0, header_marker.0, inconsistencies);
+
+    let mut parent_hash: Option<BlockHash> = None;
+    for chunk_start in (0..header_marker.0).step_by(VERIFICATION_CHUNK_SIZE as usize) {
+        let chunk_end = min(chunk_start + VERIFICATION_CHUNK_SIZE, header_marker.0);
+        let txn = storage_reader.begin_ro_txn()?;
+        let mut inconsistencies = vec![];
+        for block_number in (chunk_start..chunk_end).map(BlockNumber) {
+            let Some(header) = txn.get_block_header(block_number)? else {
+                inconsistencies.push(format!("Missing header of block {block_number}."));
+                parent_hash = None;
+                continue;
+            };
+            if header.block_number != block_number {
+                inconsistencies.push(format!(
+                    "Header of block {block_number} has block number {}.",
+                    header.block_number
+                ));
+            }
+            if let Some(parent_hash) = parent_hash {
+                if header.parent_hash != parent_hash {
+                    inconsistencies.push(format!(
+                        "Parent hash of block {block_number} is {}, but the hash of the previous \
+                         block is {parent_hash}.",
+                        header.parent_hash
+                    ));
+                }
+            }
+            parent_hash = Some(header.block_hash);
+            if block_number < body_marker
+                && txn.get_block_transactions_count(block_number)?.is_none()
+            {
+                inconsistencies.push(format!("Missing body of block {block_number}."));
+            }
+            if block_number < state_marker && txn.get_state_diff(block_number)?.is_none() {
+                inconsistencies.push(format!("Missing state diff of block {block_number}."));
+            }
+        }
+        report_progress(status, chunk_end, header_marker.0, inconsistencies);
+    }
+    Ok(())
+}
+
+fn report_progress(
+    status: &Mutex<MaintenanceStatus>,
+    processed_blocks: u64,
+    total_blocks: u64,
+    mut inconsistencies: Vec<String>,
+) {
+    for inconsistency in &inconsistencies {
+        warn!("Storage inconsistency: {inconsistency}");
+    }
+    let mut status = status.lock().expect("Maintenance status lock should not be poisoned.");
+    status.processed_blocks = processed_blocks;
+    status.total_blocks = total_blocks;
+    status.inconsistencies.append(&mut inconsistencies);
+}
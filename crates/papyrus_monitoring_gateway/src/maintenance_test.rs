@@ -0,0 +1,174 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use papyrus_common::maintenance_requests::{MaintenanceRequests, PruneRequest};
+use papyrus_storage::header::HeaderStorageWriter;
+use papyrus_storage::state::{StateStorageReader, StateStorageWriter};
+use papyrus_storage::test_utils::{get_test_config, get_test_storage};
+use papyrus_storage::{open_storage, NodeMode};
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockHash, BlockHeader, BlockNumber};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StateDiff;
+
+use crate::maintenance::{
+    start_task,
+    verify_storage,
+    MaintenanceParams,
+    MaintenanceStatus,
+    MaintenanceTask,
+    SharedMaintenanceStatus,
+    TaskState,
+};
+
+fn header(block_number: u64, parent_hash: u64) -> BlockHeader {
+    BlockHeader {
+        block_number: BlockNumber(block_number),
+        block_hash: BlockHash(StarkFelt::from(block_number + 1)),
+        parent_hash: BlockHash(StarkFelt::from(parent_hash)),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn verify_finds_broken_parent_hash_link() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &header(0, 0))
+        .unwrap()
+        .append_header(BlockNumber(1), &header(1, 1))
+        .unwrap()
+        // The parent hash of block 2 isn't the hash of block 1.
+        .append_header(BlockNumber(2), &header(2, 5))
+        .unwrap()
+        .append_state_diff(BlockNumber(0), StateDiff::default(), Default::default())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let status = Mutex::new(MaintenanceStatus::default());
+    verify_storage(&storage_reader, &status).unwrap();
+    assert_eq!(
+        status.into_inner().unwrap(),
+        MaintenanceStatus {
+            processed_blocks: 3,
+            total_blocks: 3,
+            inconsistencies: vec![format!(
+                "Parent hash of block 2 is {}, but the hash of the previous block is {}.",
+                BlockHash(StarkFelt::from(5_u64)),
+                BlockHash(StarkFelt::from(2_u64)),
+            )],
+            ..Default::default()
+        }
+    );
+}
+
+#[tokio::test]
+async fn one_task_at_a_time() {
+    let ((storage_reader, _), _temp_dir) = get_test_storage();
+    let status = SharedMaintenanceStatus::default();
+    status.lock().unwrap().state = TaskState::Running;
+    assert_eq!(
+        start_task(
+            MaintenanceTask::Verify,
+            MaintenanceParams::default(),
+            storage_reader.clone(),
+            Arc::default(),
+            status.clone()
+        ),
+        StatusCode::CONFLICT
+    );
+
+    status.lock().unwrap().state = TaskState::Done;
+    assert_eq!(
+        start_task(
+            MaintenanceTask::Verify,
+            MaintenanceParams::default(),
+            storage_reader,
+            Arc::default(),
+            status.clone()
+        ),
+        StatusCode::ACCEPTED
+    );
+    assert_eq!(status.lock().unwrap().task, Some(MaintenanceTask::Verify));
+}
+
+#[tokio::test]
+async fn unsupported_tasks_are_rejected() {
+    // The storage of the tests is in Archive mode.
+    let ((storage_reader, _), _temp_dir) = get_test_storage();
+    let status = SharedMaintenanceStatus::default();
+    let prune_params = MaintenanceParams { block_number: Some(1) };
+    for (task, params, expected_status_code) in [
+        (MaintenanceTask::Prune, MaintenanceParams::default(), StatusCode::BAD_REQUEST),
+        (MaintenanceTask::Prune, prune_params, StatusCode::NOT_IMPLEMENTED),
+        (MaintenanceTask::Compact, MaintenanceParams::default(), StatusCode::NOT_IMPLEMENTED),
+    ] {
+        assert_eq!(
+            start_task(task, params, storage_reader.clone(), Arc::default(), status.clone()),
+            expected_status_code
+        );
+    }
+    assert_eq!(*status.lock().unwrap(), MaintenanceStatus::default());
+}
+
+#[tokio::test]
+async fn prune_is_run_by_the_owner_of_the_storage_writer() {
+    let (mut config, _temp_dir) = get_test_config(None);
+    config.mode = NodeMode::Full;
+    let (storage_reader, mut storage_writer) = open_storage(config).unwrap();
+    let mut txn = storage_writer.begin_rw_txn().unwrap();
+    for block_number in 0..3 {
+        txn = txn
+            .append_state_diff(BlockNumber(block_number), StateDiff::default(), Default::default())
+            .unwrap();
+    }
+    txn.commit().unwrap();
+
+    // Runs the requests like the sync, which owns the storage writer.
+    let maintenance_requests = Arc::new(MaintenanceRequests::default());
+    let writer_requests = maintenance_requests.clone();
+    tokio::spawn(async move {
+        loop {
+            let PruneRequest { below, response } = writer_requests.next_request().await;
+            let result = storage_writer
+                .begin_rw_txn()
+                .and_then(|txn| txn.prune_state_history_below(below))
+                .and_then(|txn| txn.commit())
+                .map_err(|err| err.to_string());
+            response.send(result).unwrap();
+        }
+    });
+
+    let status = SharedMaintenanceStatus::default();
+    assert_eq!(
+        start_task(
+            MaintenanceTask::Prune,
+            MaintenanceParams { block_number: Some(2) },
+            storage_reader.clone(),
+            maintenance_requests,
+            status.clone()
+        ),
+        StatusCode::ACCEPTED
+    );
+    while status.lock().unwrap().state == TaskState::Running {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(
+        *status.lock().unwrap(),
+        MaintenanceStatus {
+            task: Some(MaintenanceTask::Prune),
+            state: TaskState::Done,
+            processed_blocks: 2,
+            total_blocks: 2,
+            inconsistencies: vec![],
+        }
+    );
+    assert_eq!(
+        storage_reader.begin_ro_txn().unwrap().get_pruned_state_marker().unwrap(),
+        BlockNumber(2)
+    );
+}
@@ -2,9 +2,10 @@ use std::io::Result;
 
 fn main() -> Result<()> {
     println!("Building");
+    // The messages of the Starknet p2p specs are compiled in papyrus_common.
     prost_build::compile_protos(
-        &["src/messages/proto/p2p/proto/header.proto", "src/messages/proto_test/util.proto"],
-        &["src/messages/proto/", "src/messages/proto_test"],
+        &["src/messages/proto_test/util.proto"],
+        &["src/messages/proto_test"],
     )?;
     Ok(())
 }
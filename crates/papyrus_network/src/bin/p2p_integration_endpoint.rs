@@ -39,9 +39,16 @@ async fn main() {
             idle_connection_timeout: Duration::from_secs(args.idle_connection_timeout),
             header_buffer_size: 100000,
             peer_id: None,
+            bootnodes: String::new(),
+            bootnode_dns_domain: None,
+            peer_manager: Default::default(),
+            enable_upnp: false,
         },
         storage_reader,
-    );
+        Default::default(),
+        Default::default(),
+    )
+    .expect("failed to create the network manager");
     if let Some(dial_address) = args.dial_address.as_ref() {
         network_manager.dial(dial_address);
     }
@@ -209,7 +209,7 @@ async fn main() {
     let mut swarm = build_swarm(
         vec![args.listen_address.clone()],
         Duration::from_secs(args.idle_connection_timeout),
-        Behaviour::new(config),
+        |_| Behaviour::new(config),
     );
 
     let mut outbound_session_measurements = HashMap::new();
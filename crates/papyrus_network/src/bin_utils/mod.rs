@@ -4,12 +4,12 @@ use std::time::Duration;
 use libp2p::identity::Keypair;
 use libp2p::swarm::dial_opts::DialOpts;
 use libp2p::swarm::NetworkBehaviour;
-use libp2p::{noise, yamux, Multiaddr, Swarm, SwarmBuilder};
+use libp2p::{noise, tcp, yamux, Multiaddr, Swarm, SwarmBuilder};
 
 pub fn build_swarm<Behaviour: NetworkBehaviour>(
     listen_addresses: Vec<String>,
     idle_connection_timeout: Duration,
-    behaviour: Behaviour,
+    behaviour: impl FnOnce(&Keypair) -> Behaviour,
 ) -> Swarm<Behaviour>
 where
 {
@@ -21,11 +21,13 @@ where
     let key_pair = Keypair::generate_ed25519();
     let mut swarm = SwarmBuilder::with_existing_identity(key_pair)
         .with_tokio()
-        .with_tcp(Default::default(), noise::Config::new, yamux::Config::default)
+        // Reusing the listening port for outbound connections makes the address that peers observe
+        // the node on dialable.
+        .with_tcp(tcp::Config::default().port_reuse(true), noise::Config::new, yamux::Config::default)
         .expect("Error building TCP transport")
         // TODO: quic transpot does not work (failure appears in the command line when running in debug mode)
         // .with_quic()
-        .with_behaviour(|_| behaviour)
+        .with_behaviour(behaviour)
         .expect("Error while building the swarm")
         .with_swarm_config(|cfg| cfg.with_idle_connection_timeout(idle_connection_timeout))
         .build();
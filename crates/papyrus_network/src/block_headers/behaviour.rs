@@ -74,10 +74,15 @@ impl Behaviour {
         inbound_session_id: InboundSessionId,
     ) -> Result<(), SessionIdNotFoundError> {
         let protobuf_data = match data {
-            Data::BlockHeaderAndSignature { header, signature } => {
-                protobuf::block_headers_response::HeaderMessage::Header((header, signature).into())
+            Data::BlockHeaderAndSignature { header, signature, state_diff_summary } => {
+                protobuf::block_headers_response::HeaderMessage::Header(
+                    (header, signature, state_diff_summary).into(),
+                )
             }
             Data::Fin {} => protobuf::block_headers_response::HeaderMessage::Fin(protobuf::Fin {}),
+            Data::StateDiffChunk(_) => {
+                unreachable!("State diffs are sent through the state diffs protocol.")
+            }
         };
         let is_fin = matches!(
             protobuf_data,
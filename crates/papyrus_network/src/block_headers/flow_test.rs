@@ -62,6 +62,7 @@ async fn one_sends_to_the_other() {
                     Data::BlockHeaderAndSignature {
                         header: BlockHeader { block_number: BlockNumber(i), ..Default::default() },
                         signature: BlockSignature::default(),
+                        state_diff_summary: None,
                     },
                     inbound_session_id,
                 )
@@ -0,0 +1,47 @@
+use std::str::FromStr;
+
+use libp2p::Multiaddr;
+use pretty_assertions::assert_eq;
+
+use super::{bootnode_addresses, parse_dnsaddr_records, parse_static_bootnodes};
+
+const FIRST_BOOTNODE: &str =
+    "/ip4/10.0.0.1/tcp/10000/p2p/QmYyQSo1c1Ym7orWxLYvCrM2EmxFTANf8wXmmE7DWjhx5N";
+const SECOND_BOOTNODE: &str = "/dns4/bootnode.example.com/tcp/10000";
+
+#[test]
+fn parse_records() {
+    let records = [
+        format!("dnsaddr={FIRST_BOOTNODE}"),
+        "v=spf1 -all".to_owned(),
+        "dnsaddr=not-a-multiaddr".to_owned(),
+        format!("dnsaddr={SECOND_BOOTNODE}"),
+    ];
+    assert_eq!(
+        parse_dnsaddr_records(records),
+        vec![
+            Multiaddr::from_str(FIRST_BOOTNODE).unwrap(),
+            Multiaddr::from_str(SECOND_BOOTNODE).unwrap()
+        ]
+    );
+}
+
+#[test]
+fn parse_static() {
+    assert_eq!(parse_static_bootnodes(""), vec![]);
+    assert_eq!(
+        parse_static_bootnodes(&format!(" {FIRST_BOOTNODE}  {SECOND_BOOTNODE} ")),
+        vec![
+            Multiaddr::from_str(FIRST_BOOTNODE).unwrap(),
+            Multiaddr::from_str(SECOND_BOOTNODE).unwrap()
+        ]
+    );
+}
+
+#[tokio::test]
+async fn static_bootnodes_without_dns_domain() {
+    assert_eq!(
+        bootnode_addresses(FIRST_BOOTNODE, None).await,
+        vec![Multiaddr::from_str(FIRST_BOOTNODE).unwrap()]
+    );
+}
@@ -0,0 +1,89 @@
+//! Finding the bootnodes the node connects to on startup. Besides the static bootnodes in the
+//! config, bootnodes can be published in the DNS TXT records of a domain, following the
+//! [`dnsaddr`] convention: each record of `_dnsaddr.<domain>` has the form `dnsaddr=<multiaddr>`.
+//! This lets the bootnode set rotate without changing the config.
+//!
+//! [`dnsaddr`]: https://github.com/multiformats/multiaddr/blob/master/protocols/DNSADDR.md
+#[cfg(test)]
+mod bootnodes_test;
+
+use std::str::FromStr;
+
+use hickory_resolver::error::ResolveError;
+use hickory_resolver::TokioAsyncResolver;
+use libp2p::Multiaddr;
+use tracing::{debug, error, warn};
+
+const DNSADDR_RECORD_PREFIX: &str = "dnsaddr=";
+
+#[derive(thiserror::Error, Debug)]
+pub enum BootnodeDiscoveryError {
+    #[error(transparent)]
+    Resolve(#[from] ResolveError),
+}
+
+/// Returns the addresses of the static bootnodes, separated by whitespace, followed by the
+/// bootnodes published in the DNS TXT records of the given domain. A failure to resolve the domain
+/// is logged, and only the static bootnodes are returned.
+pub async fn bootnode_addresses(bootnodes: &str, dns_domain: Option<&str>) -> Vec<Multiaddr> {
+    let mut addresses = parse_static_bootnodes(bootnodes);
+    if let Some(dns_domain) = dns_domain {
+        match resolve_dns_bootnodes(dns_domain).await {
+            Ok(dns_addresses) => {
+                debug!(
+                    "Found {} bootnodes in the DNS records of {dns_domain}.",
+                    dns_addresses.len()
+                );
+                for address in dns_addresses {
+                    // A bootnode may be listed both statically and in the DNS records.
+                    if !addresses.contains(&address) {
+                        addresses.push(address);
+                    }
+                }
+            }
+            Err(err) => error!("Failed to resolve the bootnodes of {dns_domain}: {err}."),
+        }
+    }
+    addresses
+}
+
+/// Parses whitespace separated multiaddrs. Panics if one of them is invalid.
+pub fn parse_static_bootnodes(bootnodes: &str) -> Vec<Multiaddr> {
+    bootnodes
+        .split_whitespace()
+        .map(|bootnode| {
+            Multiaddr::from_str(bootnode)
+                .unwrap_or_else(|_| panic!("Unable to parse bootnode address {}", bootnode))
+        })
+        .collect()
+}
+
+/// Resolves the TXT records of `_dnsaddr.<dns_domain>` and returns the bootnodes they list.
+pub async fn resolve_dns_bootnodes(
+    dns_domain: &str,
+) -> Result<Vec<Multiaddr>, BootnodeDiscoveryError> {
+    let resolver = TokioAsyncResolver::tokio_from_system_conf()?;
+    let txt_lookup = resolver.txt_lookup(format!("_dnsaddr.{dns_domain}")).await?;
+    let records = txt_lookup.iter().map(|txt| {
+        txt.txt_data().iter().map(|data| String::from_utf8_lossy(data)).collect::<String>()
+    });
+    Ok(parse_dnsaddr_records(records))
+}
+
+/// Returns the multiaddrs of the records of the form `dnsaddr=<multiaddr>`. Other records are
+/// ignored, and invalid multiaddrs are logged and skipped.
+pub fn parse_dnsaddr_records(records: impl IntoIterator<Item = String>) -> Vec<Multiaddr> {
+    records
+        .into_iter()
+        .filter_map(|record| {
+            let address = record.strip_prefix(DNSADDR_RECORD_PREFIX)?;
+            match Multiaddr::from_str(address) {
+                Ok(address) => Some(address),
+                Err(err) => {
+                    warn!("Skipping the invalid bootnode address {address} in DNS: {err}.");
+                    None
+                }
+            }
+        })
+        .collect()
+}
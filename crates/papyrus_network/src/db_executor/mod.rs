@@ -6,12 +6,15 @@ use futures::channel::mpsc::Sender;
 use futures::future::poll_fn;
 use futures::stream::FuturesUnordered;
 use futures::{Stream, StreamExt};
+use papyrus_common::protobuf_conversion::StateDiffSummary;
 use papyrus_storage::header::HeaderStorageReader;
+use papyrus_storage::state::StateStorageReader;
 use papyrus_storage::StorageReader;
 use starknet_api::block::{BlockHeader, BlockNumber, BlockSignature};
 use tokio::task::JoinHandle;
 
-use crate::{BlockHashOrNumber, InternalQuery};
+use crate::state_diffs::{split_state_diff, StateDiffChunk, MAX_STORAGE_VALUES_PER_CHUNK};
+use crate::{BlockHashOrNumber, DataType, InternalQuery};
 
 #[cfg(test)]
 mod test;
@@ -24,7 +27,13 @@ pub struct QueryId(pub usize);
 #[cfg_attr(test, derive(Debug, Clone, PartialEq, Eq))]
 pub enum Data {
     // TODO(shahak): Consider uniting with SignedBlockHeader.
-    BlockHeaderAndSignature { header: BlockHeader, signature: BlockSignature },
+    /// The summary of the state diff is None if the state diff of the block wasn't synced yet.
+    BlockHeaderAndSignature {
+        header: BlockHeader,
+        signature: BlockSignature,
+        state_diff_summary: Option<StateDiffSummary>,
+    },
+    StateDiffChunk(StateDiffChunk),
     Fin,
 }
 
@@ -85,10 +94,15 @@ impl DBExecutorError {
 /// The stream is never exhausted, and it is the responsibility of the user to poll it.
 pub trait DBExecutor: Stream<Item = Result<QueryId, DBExecutorError>> + Unpin {
     // TODO: add writer functionality
-    fn register_query(&mut self, query: InternalQuery, sender: Sender<Data>) -> QueryId;
+    fn register_query(
+        &mut self,
+        query: InternalQuery,
+        data_type: DataType,
+        sender: Sender<Data>,
+    ) -> QueryId;
 }
 
-// TODO: currently this executor returns only block headers and signatures.
+// TODO(shahak): Rename once this executor returns more than block headers and state diffs.
 pub struct BlockHeaderDBExecutor {
     next_query_id: usize,
     storage_reader: StorageReader,
@@ -103,7 +117,12 @@ impl BlockHeaderDBExecutor {
 }
 
 impl DBExecutor for BlockHeaderDBExecutor {
-    fn register_query(&mut self, query: InternalQuery, mut sender: Sender<Data>) -> QueryId {
+    fn register_query(
+        &mut self,
+        query: InternalQuery,
+        data_type: DataType,
+        mut sender: Sender<Data>,
+    ) -> QueryId {
         // TODO: consider create a sized vector and increase its size when needed.
         let query_id = QueryId(self.next_query_id);
         self.next_query_id += 1;
@@ -135,38 +154,68 @@ impl DBExecutor for BlockHeaderDBExecutor {
                         block_counter,
                         query_id,
                     )?);
-                    let header = txn
-                        .get_block_header(block_number)
-                        .map_err(|err| DBExecutorError::DBInternalError {
-                            query_id,
-                            storage_error: err,
-                        })?
-                        .ok_or(DBExecutorError::BlockNotFound {
-                            block_hash_or_number: BlockHashOrNumber::Number(block_number),
-                            query_id,
-                        })?;
-                    let signature = txn
-                        .get_block_signature(block_number)
-                        .map_err(|err| DBExecutorError::DBInternalError {
-                            query_id,
-                            storage_error: err,
-                        })?
-                        .ok_or(DBExecutorError::SignatureNotFound { block_number, query_id })?;
-                    // Using poll_fn because Sender::poll_ready is not a future
-                    match poll_fn(|cx| sender.poll_ready(cx)).await {
-                        Ok(()) => {
-                            if let Err(e) = sender
-                                .start_send(Data::BlockHeaderAndSignature { header, signature })
-                            {
-                                // TODO: consider implement retry mechanism.
-                                return Err(DBExecutorError::SendError { query_id, send_error: e });
-                            };
+                    let block_data = match data_type {
+                        DataType::SignedBlockHeader => {
+                            let header = txn
+                                .get_block_header(block_number)
+                                .map_err(|err| DBExecutorError::DBInternalError {
+                                    query_id,
+                                    storage_error: err,
+                                })?
+                                .ok_or(DBExecutorError::BlockNotFound {
+                                    block_hash_or_number: BlockHashOrNumber::Number(block_number),
+                                    query_id,
+                                })?;
+                            let signature = txn
+                                .get_block_signature(block_number)
+                                .map_err(|err| DBExecutorError::DBInternalError {
+                                    query_id,
+                                    storage_error: err,
+                                })?
+                                .ok_or(DBExecutorError::SignatureNotFound {
+                                    block_number,
+                                    query_id,
+                                })?;
+                            let state_diff_summary = txn
+                                .get_state_diff(block_number)
+                                .map_err(|err| DBExecutorError::DBInternalError {
+                                    query_id,
+                                    storage_error: err,
+                                })?
+                                .as_ref()
+                                .map(StateDiffSummary::from);
+                            vec![Data::BlockHeaderAndSignature {
+                                header,
+                                signature,
+                                state_diff_summary,
+                            }]
                         }
-                        Err(e) => {
-                            return Err(DBExecutorError::SendError { query_id, send_error: e });
+                        DataType::StateDiff => {
+                            let state_diff = txn
+                                .get_state_diff(block_number)
+                                .map_err(|err| DBExecutorError::DBInternalError {
+                                    query_id,
+                                    storage_error: err,
+                                })?
+                                .ok_or(DBExecutorError::BlockNotFound {
+                                    block_hash_or_number: BlockHashOrNumber::Number(block_number),
+                                    query_id,
+                                })?;
+                            split_state_diff(state_diff, MAX_STORAGE_VALUES_PER_CHUNK)
+                                .into_iter()
+                                .map(Data::StateDiffChunk)
+                                .collect()
                         }
+                    };
+                    for data in block_data {
+                        send_data(&mut sender, data, query_id).await?;
                     }
                 }
+                // The peer knows that the empty state diffs at the end of the query were sent only
+                // once it receives Fin.
+                if data_type == DataType::StateDiff {
+                    send_data(&mut sender, Data::Fin, query_id).await?;
+                }
                 Ok(query_id)
             }
         }));
@@ -174,6 +223,19 @@ impl DBExecutor for BlockHeaderDBExecutor {
     }
 }
 
+async fn send_data(
+    sender: &mut Sender<Data>,
+    data: Data,
+    query_id: QueryId,
+) -> Result<(), DBExecutorError> {
+    // Using poll_fn because Sender::poll_ready is not a future
+    poll_fn(|cx| sender.poll_ready(cx))
+        .await
+        .map_err(|e| DBExecutorError::SendError { query_id, send_error: e })?;
+    // TODO: consider implement retry mechanism.
+    sender.start_send(data).map_err(|e| DBExecutorError::SendError { query_id, send_error: e })
+}
+
 impl Stream for BlockHeaderDBExecutor {
     type Item = Result<QueryId, DBExecutorError>;
 
@@ -3,15 +3,22 @@ use std::task::Poll;
 use assert_matches::assert_matches;
 use futures::future::poll_fn;
 use futures::{FutureExt, StreamExt};
+use papyrus_common::protobuf_conversion::StateDiffSummary;
 use papyrus_storage::header::{HeaderStorageReader, HeaderStorageWriter};
+use papyrus_storage::state::StateStorageWriter;
 use papyrus_storage::test_utils::get_test_storage;
 use papyrus_storage::StorageWriter;
 use rand::random;
 use starknet_api::block::{BlockHash, BlockHeader, BlockNumber, BlockSignature};
+use starknet_api::core::Nonce;
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::{StateDiff, ThinStateDiff};
+use starknet_api::{class_hash, contract_address, stark_felt};
 
 use super::Data::BlockHeaderAndSignature;
-use crate::db_executor::{DBExecutor, DBExecutorError};
-use crate::{BlockHashOrNumber, Direction, InternalQuery};
+use crate::db_executor::{DBExecutor, DBExecutorError, Data};
+use crate::state_diffs::{split_state_diff, MAX_STORAGE_VALUES_PER_CHUNK};
+use crate::{BlockHashOrNumber, DataType, Direction, InternalQuery};
 const BUFFER_SIZE: usize = 10;
 
 #[tokio::test]
@@ -31,7 +38,7 @@ async fn header_db_executor_can_register_and_run_a_query() {
         limit: NUM_OF_BLOCKS,
         step: 1,
     };
-    let query_id = db_executor.register_query(query, sender);
+    let query_id = db_executor.register_query(query, DataType::SignedBlockHeader, sender);
 
     // run the executor and collect query results.
     tokio::select! {
@@ -75,7 +82,7 @@ async fn header_db_executor_start_block_given_by_hash() {
         limit: NUM_OF_BLOCKS,
         step: 1,
     };
-    let query_id = db_executor.register_query(query, sender);
+    let query_id = db_executor.register_query(query, DataType::SignedBlockHeader, sender);
 
     // run the executor and collect query results.
     tokio::select! {
@@ -109,7 +116,7 @@ async fn header_db_executor_query_of_missing_block() {
         limit: NUM_OF_BLOCKS,
         step: 1,
     };
-    let _query_id = db_executor.register_query(query, sender);
+    let _query_id = db_executor.register_query(query, DataType::SignedBlockHeader, sender);
 
     tokio::select! {
         res = db_executor.next() => {
@@ -148,7 +155,7 @@ async fn header_db_executor_can_receive_queries_after_stream_is_exhausted() {
             limit: NUM_OF_BLOCKS,
             step: 1,
         };
-        let query_id = db_executor.register_query(query, sender);
+        let query_id = db_executor.register_query(query, DataType::SignedBlockHeader, sender);
 
         // run the executor and collect query results.
         receiver.collect::<Vec<_>>().await;
@@ -183,13 +190,93 @@ async fn header_db_executor_drop_receiver_before_query_is_done() {
     drop(receiver);
 
     // register a query.
-    let _query_id = db_executor.register_query(query, sender);
+    let _query_id = db_executor.register_query(query, DataType::SignedBlockHeader, sender);
 
     // executor should return an error.
     let res = db_executor.next().await;
     assert!(res.unwrap().is_err());
 }
 
+#[tokio::test]
+async fn header_db_executor_sends_the_state_diff_summary() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let mut db_executor = super::BlockHeaderDBExecutor::new(storage_reader);
+
+    // Only the state diff of block 0 was synced.
+    insert_to_storage_test_blocks_up_to(2, &mut storage_writer);
+    let state_diff = ThinStateDiff {
+        nonces: [(contract_address!("0x2"), Nonce(stark_felt!("0x1")))].into(),
+        ..ThinStateDiff::from(StateDiff::default())
+    };
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_thin_state_diff(BlockNumber(0), state_diff.clone())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let (sender, receiver) = futures::channel::mpsc::channel(BUFFER_SIZE);
+    let query = InternalQuery {
+        start_block: BlockHashOrNumber::Number(BlockNumber(0)),
+        direction: Direction::Forward,
+        limit: 2,
+        step: 1,
+    };
+    let query_id = db_executor.register_query(query, DataType::SignedBlockHeader, sender);
+    assert_eq!(db_executor.next().await.unwrap().unwrap(), query_id);
+
+    let summaries = receiver
+        .map(|data| {
+            let BlockHeaderAndSignature { state_diff_summary, .. } = data else {
+                panic!("Expected a header, got {data:?}.");
+            };
+            state_diff_summary
+        })
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(summaries, vec![Some(StateDiffSummary::from(&state_diff)), None]);
+}
+
+#[tokio::test]
+async fn state_diff_db_executor_sends_the_chunks_and_fin() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let mut db_executor = super::BlockHeaderDBExecutor::new(storage_reader);
+
+    let state_diff = ThinStateDiff {
+        deployed_contracts: [(contract_address!("0x1"), class_hash!("0x10"))].into(),
+        nonces: [(contract_address!("0x2"), Nonce(stark_felt!("0x1")))].into(),
+        ..ThinStateDiff::from(StateDiff::default())
+    };
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_thin_state_diff(BlockNumber(0), state_diff.clone())
+        .unwrap()
+        .append_thin_state_diff(BlockNumber(1), ThinStateDiff::from(StateDiff::default()))
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let (sender, receiver) = futures::channel::mpsc::channel(BUFFER_SIZE);
+    let query = InternalQuery {
+        start_block: BlockHashOrNumber::Number(BlockNumber(0)),
+        direction: Direction::Forward,
+        limit: 2,
+        step: 1,
+    };
+    let query_id = db_executor.register_query(query, DataType::StateDiff, sender);
+    assert_eq!(db_executor.next().await.unwrap().unwrap(), query_id);
+
+    // The empty state diff has no chunks.
+    let mut expected_data = split_state_diff(state_diff, MAX_STORAGE_VALUES_PER_CHUNK)
+        .into_iter()
+        .map(Data::StateDiffChunk)
+        .collect::<Vec<_>>();
+    expected_data.push(Data::Fin);
+    assert_eq!(receiver.collect::<Vec<_>>().await, expected_data);
+}
+
 fn insert_to_storage_test_blocks_up_to(num_of_blocks: u64, storage_writer: &mut StorageWriter) {
     for i in 0..num_of_blocks {
         let block_header = BlockHeader {
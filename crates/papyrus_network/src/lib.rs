@@ -4,9 +4,13 @@
 /// [`Starknet p2p specs`]: https://github.com/starknet-io/starknet-p2p-specs/
 pub mod bin_utils;
 pub mod block_headers;
+pub mod bootnodes;
 mod db_executor;
 pub mod messages;
+pub mod mixed_behaviour;
 pub mod network_manager;
+pub mod peer_manager;
+pub mod state_diffs;
 pub mod streamed_data;
 #[cfg(test)]
 mod test_utils;
@@ -18,10 +22,20 @@ use std::usize;
 use futures::channel::mpsc::{Receiver, Sender};
 use libp2p::PeerId;
 use papyrus_config::converters::deserialize_seconds_to_duration;
-use papyrus_config::dumping::{ser_optional_param, ser_param, SerializeConfig};
+use papyrus_config::dumping::{
+    append_sub_config_name,
+    ser_optional_param,
+    ser_param,
+    SerializeConfig,
+};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 use serde::{Deserialize, Serialize};
 use starknet_api::block::{BlockHash, BlockHeader, BlockNumber, BlockSignature};
+use starknet_api::hash::StarkHash;
+
+use crate::messages::{protobuf, ProtobufConversionError};
+use crate::peer_manager::PeerManagerConfig;
+use crate::state_diffs::{ReceivedStateDiff, StateDiffExpectation};
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct NetworkConfig {
@@ -33,12 +47,19 @@ pub struct NetworkConfig {
     pub idle_connection_timeout: Duration,
     pub header_buffer_size: usize,
     pub peer_id: Option<PeerId>,
+    /// The addresses of the bootnodes to connect to on startup, separated by whitespace.
+    pub bootnodes: String,
+    /// A domain whose DNS TXT records list more bootnodes, see [`bootnodes`].
+    pub bootnode_dns_domain: Option<String>,
+    pub peer_manager: PeerManagerConfig,
+    pub enable_upnp: bool,
 }
 
-#[derive(Default, Debug, PartialEq, Eq)]
+#[derive(Default, Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub enum DataType {
     #[default]
     SignedBlockHeader,
+    StateDiff,
 }
 
 #[derive(Default, Debug, PartialEq, Eq)]
@@ -48,6 +69,9 @@ pub struct Query {
     pub limit: usize,
     pub step: usize,
     pub data_type: DataType,
+    /// What each of the queried state diffs is verified against, in the order of the query. Only
+    /// used when querying state diffs.
+    pub state_diff_expectations: Vec<StateDiffExpectation>,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
@@ -83,10 +107,12 @@ pub enum BlockHashOrNumber {
 
 pub struct ResponseReceivers {
     pub signed_headers_receiver: Receiver<SignedBlockHeader>,
+    pub state_diffs_receiver: Receiver<ReceivedStateDiff>,
 }
 
 struct ResponseSenders {
     pub signed_headers_sender: Sender<SignedBlockHeader>,
+    pub state_diffs_sender: Sender<ReceivedStateDiff>,
 }
 
 impl SerializeConfig for NetworkConfig {
@@ -123,6 +149,19 @@ impl SerializeConfig for NetworkConfig {
                 "Size of the buffer for headers read from the storage.",
                 ParamPrivacyInput::Public,
             ),
+            ser_param(
+                "enable_upnp",
+                &self.enable_upnp,
+                "Whether to map the listening port on the gateway with UPnP, so that peers can \
+                 dial the node when it runs behind a NAT.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "bootnodes",
+                &self.bootnodes,
+                "Multiaddrs of the bootnodes to connect to on startup, separated by whitespace.",
+                ParamPrivacyInput::Public,
+            ),
         ]);
         let peer_id_example = PeerId::from_str("QmYyQSo1c1Ym7orWxLYvCrM2EmxFTANf8wXmmE7DWjhx5N")
             .expect("failed to parse peer id");
@@ -133,6 +172,16 @@ impl SerializeConfig for NetworkConfig {
                 "Peer ID to send requests to. If not set, the node will not send requests. for info: https://docs.libp2p.io/concepts/fundamentals/peers/",
                 ParamPrivacyInput::Public,
             ));
+        config.extend(ser_optional_param(
+            &self.bootnode_dns_domain,
+            "bootnodes.example.com".to_owned(),
+            "bootnode_dns_domain",
+            "A domain whose DNS TXT records list bootnodes to connect to on startup, in addition \
+             to the static bootnodes. Each record of _dnsaddr.<domain> should have the form \
+             dnsaddr=<multiaddr>.",
+            ParamPrivacyInput::Public,
+        ));
+        config.extend(append_sub_config_name(self.peer_manager.dump(), "peer_manager"));
         config
     }
 }
@@ -146,13 +195,20 @@ impl Default for NetworkConfig {
             idle_connection_timeout: Duration::from_secs(10),
             header_buffer_size: 100000,
             peer_id: None,
+            bootnodes: String::new(),
+            bootnode_dns_domain: None,
+            peer_manager: PeerManagerConfig::default(),
+            enable_upnp: true,
         }
     }
 }
 
 impl ResponseReceivers {
-    fn new(signed_headers_receiver: Receiver<SignedBlockHeader>) -> Self {
-        Self { signed_headers_receiver }
+    fn new(
+        signed_headers_receiver: Receiver<SignedBlockHeader>,
+        state_diffs_receiver: Receiver<ReceivedStateDiff>,
+    ) -> Self {
+        Self { signed_headers_receiver, state_diffs_receiver }
     }
 }
 
@@ -166,3 +222,56 @@ impl From<Query> for InternalQuery {
         }
     }
 }
+
+impl TryFrom<protobuf::Iteration> for InternalQuery {
+    type Error = ProtobufConversionError;
+    fn try_from(value: protobuf::Iteration) -> Result<Self, Self::Error> {
+        let start_block = match value.start.ok_or(ProtobufConversionError::MissingField)? {
+            protobuf::iteration::Start::BlockNumber(block_number) => {
+                BlockHashOrNumber::Number(BlockNumber(block_number))
+            }
+            protobuf::iteration::Start::Header(protobuf::Hash { elements: bytes }) => {
+                let bytes: [u8; 32] = bytes
+                    .try_into()
+                    .map_err(|_| ProtobufConversionError::BytesDataLengthMismatch)?;
+                let block_hash = BlockHash(StarkHash::new(bytes).map_err(|_| {
+                    // OutOfRange is the only StarknetApiError that StarkHash::new will practically
+                    // return
+                    // TODO(shahak): Enforce StarkHash::new to return only OutOfRange by defining a
+                    // more limited StarknetApiError.
+                    ProtobufConversionError::OutOfRangeValue
+                })?);
+                BlockHashOrNumber::Hash(block_hash)
+            }
+        };
+        let direction = match value.direction {
+            0 => Direction::Forward,
+            1 => Direction::Backward,
+            _ => return Err(ProtobufConversionError::OutOfRangeValue),
+        };
+        Ok(Self { start_block, direction, limit: value.limit, step: value.step })
+    }
+}
+
+impl From<InternalQuery> for protobuf::Iteration {
+    fn from(value: InternalQuery) -> Self {
+        protobuf::Iteration {
+            direction: match value.direction {
+                Direction::Forward => 0,
+                Direction::Backward => 1,
+            },
+            limit: value.limit,
+            step: value.step,
+            start: match value.start_block {
+                BlockHashOrNumber::Number(BlockNumber(num)) => {
+                    Some(protobuf::iteration::Start::BlockNumber(num))
+                }
+                BlockHashOrNumber::Hash(BlockHash(stark_hash)) => {
+                    Some(protobuf::iteration::Start::Header(protobuf::Hash {
+                        elements: stark_hash.bytes().to_vec(),
+                    }))
+                }
+            },
+        }
+    }
+}
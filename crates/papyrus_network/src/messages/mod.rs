@@ -2,6 +2,8 @@
 mod messages_test;
 
 pub mod protobuf {
+    // The messages of the Starknet p2p specs are shared with the rest of the node.
+    pub use papyrus_common::protobuf::*;
     include!(concat!(env!("OUT_DIR"), "/_.rs"));
 }
 
@@ -9,8 +11,9 @@ use std::io;
 
 use futures::io::{ReadHalf, WriteHalf};
 use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+pub(crate) use papyrus_common::protobuf_conversion::enum_int_to_l1_data_availability_mode;
+pub use papyrus_common::protobuf_conversion::ProtobufConversionError;
 use prost::Message;
-use starknet_api::data_availability::L1DataAvailabilityMode;
 use unsigned_varint::encode::usize_buffer;
 
 pub const MAX_MESSAGE_SIZE: usize = 1 << 20;
@@ -116,118 +119,6 @@ pub async fn write_usize<Stream: AsyncWrite + Unpin>(
     Ok(())
 }
 
-#[derive(thiserror::Error, Debug)]
-pub enum ProtobufConversionError {
-    #[error("Out of range value")]
-    OutOfRangeValue,
-    #[error("Missing field")]
-    MissingField,
-    #[error("Bytes data length mismatch")]
-    BytesDataLengthMismatch,
-}
-
-impl TryFrom<protobuf::Felt252> for starknet_api::hash::StarkFelt {
-    type Error = ProtobufConversionError;
-    fn try_from(value: protobuf::Felt252) -> Result<Self, Self::Error> {
-        let mut felt = [0; 32];
-        felt.copy_from_slice(&value.elements);
-        if let Ok(stark_felt) = Self::new(felt) {
-            Ok(stark_felt)
-        } else {
-            Err(ProtobufConversionError::OutOfRangeValue)
-        }
-    }
-}
-
-impl From<starknet_api::hash::StarkFelt> for protobuf::Felt252 {
-    fn from(value: starknet_api::hash::StarkFelt) -> Self {
-        Self { elements: value.bytes().to_vec() }
-    }
-}
-
-impl From<starknet_api::block::BlockHash> for protobuf::Hash {
-    fn from(value: starknet_api::block::BlockHash) -> Self {
-        Self { elements: value.0.bytes().to_vec() }
-    }
-}
-
-impl From<starknet_api::hash::StarkHash> for protobuf::Hash {
-    fn from(value: starknet_api::hash::StarkHash) -> Self {
-        Self { elements: value.bytes().to_vec() }
-    }
-}
-
-impl From<starknet_api::core::ContractAddress> for protobuf::Address {
-    fn from(value: starknet_api::core::ContractAddress) -> Self {
-        Self { elements: value.0.key().bytes().to_vec() }
-    }
-}
-
-impl From<u128> for protobuf::Uint128 {
-    fn from(value: u128) -> Self {
-        Self { high: (value >> 64) as u64, low: value as u64 }
-    }
-}
-
-impl From<protobuf::Uint128> for u128 {
-    fn from(value: protobuf::Uint128) -> Self {
-        u128::from(value.low) + (u128::from(value.high) << 64)
-    }
-}
-
-impl TryFrom<protobuf::Hash> for starknet_api::hash::StarkHash {
-    type Error = ProtobufConversionError;
-    fn try_from(value: protobuf::Hash) -> Result<Self, Self::Error> {
-        let mut felt = [0; 32];
-        if value.elements.len() != 32 {
-            return Err(ProtobufConversionError::BytesDataLengthMismatch);
-        }
-        felt.copy_from_slice(&value.elements);
-        if let Ok(stark_hash) = Self::new(felt) {
-            Ok(stark_hash)
-        } else {
-            Err(ProtobufConversionError::OutOfRangeValue)
-        }
-    }
-}
-
-impl TryFrom<protobuf::Address> for starknet_api::core::ContractAddress {
-    type Error = ProtobufConversionError;
-    fn try_from(value: protobuf::Address) -> Result<Self, Self::Error> {
-        let mut felt = [0; 32];
-        if value.elements.len() != 32 {
-            return Err(ProtobufConversionError::BytesDataLengthMismatch);
-        }
-        felt.copy_from_slice(&value.elements);
-        if let Ok(hash) = starknet_api::hash::StarkHash::new(felt) {
-            if let Ok(stark_felt) = starknet_api::core::PatriciaKey::try_from(hash) {
-                Ok(starknet_api::core::ContractAddress(stark_felt))
-            } else {
-                Err(ProtobufConversionError::OutOfRangeValue)
-            }
-        } else {
-            Err(ProtobufConversionError::OutOfRangeValue)
-        }
-    }
-}
-
-pub(crate) fn enum_int_to_l1_data_availability_mode(
-    value: i32,
-) -> Result<L1DataAvailabilityMode, ProtobufConversionError> {
-    match value {
-        0 => Ok(L1DataAvailabilityMode::Calldata),
-        1 => Ok(L1DataAvailabilityMode::Blob),
-        _ => Err(ProtobufConversionError::OutOfRangeValue),
-    }
-}
-
-pub(crate) fn l1_data_availability_mode_to_enum_int(value: L1DataAvailabilityMode) -> i32 {
-    match value {
-        L1DataAvailabilityMode::Calldata => 0,
-        L1DataAvailabilityMode::Blob => 1,
-    }
-}
-
 #[cfg(test)]
 pub(crate) trait TestInstance {
     fn test_instance() -> Self;
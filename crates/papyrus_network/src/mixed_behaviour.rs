@@ -0,0 +1,73 @@
+//! The behaviour of the node's swarm, which combines the behaviours of all the protocols the node
+//! speaks.
+use libp2p::identity::Keypair;
+use libp2p::swarm::behaviour::toggle::Toggle;
+use libp2p::swarm::NetworkBehaviour;
+use libp2p::{identify, upnp};
+
+use crate::{block_headers, state_diffs};
+
+// The version of the protocols the node speaks, reported to other peers through identify.
+pub const IDENTIFY_PROTOCOL_VERSION: &str = "/starknet/0.1.0";
+
+#[derive(NetworkBehaviour)]
+#[behaviour(to_swarm = "Event")]
+pub struct MixedBehaviour {
+    pub block_headers: block_headers::behaviour::Behaviour,
+    pub state_diffs: state_diffs::behaviour::Behaviour,
+    // Learns from the peers the addresses they see the node on.
+    pub identify: identify::Behaviour,
+    // Maps the listening ports of the node on the gateway, for nodes that run behind a NAT.
+    pub upnp: Toggle<upnp::tokio::Behaviour>,
+}
+
+#[derive(Debug)]
+pub enum Event {
+    BlockHeaders(block_headers::Event),
+    StateDiffs(state_diffs::Event),
+    Identify(identify::Event),
+    Upnp(upnp::Event),
+}
+
+impl MixedBehaviour {
+    pub fn new(
+        key_pair: &Keypair,
+        block_headers: block_headers::behaviour::Behaviour,
+        state_diffs: state_diffs::behaviour::Behaviour,
+        enable_upnp: bool,
+    ) -> Self {
+        Self {
+            block_headers,
+            state_diffs,
+            identify: identify::Behaviour::new(identify::Config::new(
+                IDENTIFY_PROTOCOL_VERSION.to_owned(),
+                key_pair.public(),
+            )),
+            upnp: enable_upnp.then(upnp::tokio::Behaviour::default).into(),
+        }
+    }
+}
+
+impl From<block_headers::Event> for Event {
+    fn from(event: block_headers::Event) -> Self {
+        Self::BlockHeaders(event)
+    }
+}
+
+impl From<state_diffs::Event> for Event {
+    fn from(event: state_diffs::Event) -> Self {
+        Self::StateDiffs(event)
+    }
+}
+
+impl From<identify::Event> for Event {
+    fn from(event: identify::Event) -> Self {
+        Self::Identify(event)
+    }
+}
+
+impl From<upnp::Event> for Event {
+    fn from(event: upnp::Event) -> Self {
+        Self::Upnp(event)
+    }
+}
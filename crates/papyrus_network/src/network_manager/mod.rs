@@ -3,26 +3,48 @@ mod swarm_trait;
 #[cfg(test)]
 mod test;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Instant;
 
 use futures::channel::mpsc::{Receiver, Sender};
 use futures::future::pending;
 use futures::stream::{BoxStream, SelectAll};
 use futures::{FutureExt, StreamExt};
+use libp2p::swarm::dial_opts::DialOpts;
 use libp2p::swarm::SwarmEvent;
-use libp2p::{PeerId, Swarm};
-use papyrus_storage::StorageReader;
-use tracing::{debug, error, trace};
+use libp2p::{identify, upnp, Multiaddr, PeerId, Swarm};
+use papyrus_common::external_addresses::ExternalAddresses;
+use papyrus_common::metrics::PAPYRUS_NUM_CONNECTED_PEERS;
+use papyrus_common::source_reports::SourceReports;
+use papyrus_storage::{StorageReader, StorageResult};
+use tracing::{debug, error, info, trace};
 
 use self::swarm_trait::SwarmTrait;
 use crate::bin_utils::{build_swarm, dial};
 use crate::block_headers::behaviour::Behaviour as BlockHeadersBehaviour;
-use crate::block_headers::Event;
+use crate::block_headers::{Event, SessionError};
+use crate::bootnodes::bootnode_addresses;
 use crate::db_executor::{self, BlockHeaderDBExecutor, DBExecutor, Data, QueryId};
-use crate::streamed_data::InboundSessionId;
-use crate::{NetworkConfig, Query, ResponseReceivers, ResponseSenders};
+use crate::mixed_behaviour::{self, MixedBehaviour};
+use crate::peer_manager::{self, Misconduct, PeerManager};
+use crate::state_diffs::behaviour::Behaviour as StateDiffsBehaviour;
+use crate::streamed_data::{self, InboundSessionId, OutboundSessionId, SessionId};
+use crate::{
+    state_diffs,
+    DataType,
+    InternalQuery,
+    NetworkConfig,
+    Query,
+    ResponseReceivers,
+    ResponseSenders,
+};
 
-type StreamCollection = SelectAll<BoxStream<'static, (Data, InboundSessionId)>>;
+// The number of peers that should observe the node on an address before it's advertised as an
+// external address.
+const OBSERVED_ADDRESS_CONFIRMATIONS: usize = 2;
+
+type StreamCollection = SelectAll<BoxStream<'static, (Data, DataType, InboundSessionId)>>;
 type SyncSubscriberChannels = (Receiver<Query>, ResponseSenders);
 
 pub struct GenericNetworkManager<DBExecutorT: DBExecutor, SwarmT: SwarmTrait> {
@@ -31,8 +53,16 @@ pub struct GenericNetworkManager<DBExecutorT: DBExecutor, SwarmT: SwarmTrait> {
     header_buffer_size: usize,
     query_results_router: StreamCollection,
     sync_subscriber_channels: Option<SyncSubscriberChannels>,
-    query_id_to_inbound_session_id: HashMap<QueryId, InboundSessionId>,
+    // The sessions of each protocol are numbered separately, so they're identified along with the
+    // type of the data they're for.
+    query_id_to_inbound_session_id: HashMap<QueryId, (DataType, InboundSessionId)>,
     peer_id: Option<PeerId>,
+    peer_manager: PeerManager,
+    // The peer of each outbound session, and the time the session started.
+    outbound_session_id_to_peer_id: HashMap<(DataType, OutboundSessionId), (PeerId, Instant)>,
+    external_addresses: Arc<ExternalAddresses>,
+    // The peers that observed the node on each address that isn't confirmed as external yet.
+    observed_addresses: HashMap<Multiaddr, HashSet<PeerId>>,
 }
 
 impl<DBExecutorT: DBExecutor, SwarmT: SwarmTrait> GenericNetworkManager<DBExecutorT, SwarmT> {
@@ -54,6 +84,8 @@ impl<DBExecutorT: DBExecutor, SwarmT: SwarmTrait> GenericNetworkManager<DBExecut
         db_executor: DBExecutorT,
         header_buffer_size: usize,
         peer_id: Option<PeerId>,
+        peer_manager: PeerManager,
+        external_addresses: Arc<ExternalAddresses>,
     ) -> Self {
         Self {
             swarm,
@@ -63,29 +95,70 @@ impl<DBExecutorT: DBExecutor, SwarmT: SwarmTrait> GenericNetworkManager<DBExecut
             sync_subscriber_channels: None,
             query_id_to_inbound_session_id: HashMap::new(),
             peer_id,
+            peer_manager,
+            outbound_session_id_to_peer_id: HashMap::new(),
+            external_addresses,
+            observed_addresses: HashMap::new(),
         }
     }
 
     pub fn register_subscriber(&mut self) -> (Sender<Query>, ResponseReceivers) {
         let (sender, query_receiver) = futures::channel::mpsc::channel(self.header_buffer_size);
-        let (response_sender, response_receiver) =
+        let (signed_headers_sender, signed_headers_receiver) =
+            futures::channel::mpsc::channel(self.header_buffer_size);
+        let (state_diffs_sender, state_diffs_receiver) =
             futures::channel::mpsc::channel(self.header_buffer_size);
         self.sync_subscriber_channels =
-            Some((query_receiver, ResponseSenders { signed_headers_sender: response_sender }));
-        (sender, ResponseReceivers::new(response_receiver))
+            Some((query_receiver, ResponseSenders { signed_headers_sender, state_diffs_sender }));
+        (sender, ResponseReceivers::new(signed_headers_receiver, state_diffs_receiver))
     }
 
-    fn handle_swarm_event(&mut self, event: SwarmEvent<Event>) {
+    fn handle_swarm_event(&mut self, event: swarm_trait::Event) {
         match event {
-            SwarmEvent::ConnectionEstablished { .. } => {
+            SwarmEvent::ConnectionEstablished { peer_id, num_established, .. } => {
+                // Only the first connection to a peer is counted as a new peer.
+                if num_established.get() == 1 {
+                    metrics::increment_gauge!(PAPYRUS_NUM_CONNECTED_PEERS, 1.0);
+                }
+                if self.peer_manager.is_banned(&peer_id, peer_manager::now()) {
+                    debug!("Disconnecting from banned peer {peer_id}.");
+                    self.swarm.disconnect_peer(peer_id);
+                    return;
+                }
                 debug!("Connected to a peer!");
             }
+            SwarmEvent::ConnectionClosed { num_established, .. } => {
+                if num_established == 0 {
+                    metrics::decrement_gauge!(PAPYRUS_NUM_CONNECTED_PEERS, 1.0);
+                }
+            }
             SwarmEvent::NewListenAddr { .. }
             | SwarmEvent::IncomingConnection { .. }
-            | SwarmEvent::ConnectionClosed { .. } => {}
-            SwarmEvent::Behaviour(event) => {
+            | SwarmEvent::Dialing { .. }
+            | SwarmEvent::NewExternalAddrCandidate { .. } => {}
+            SwarmEvent::OutgoingConnectionError { peer_id, error, .. } => {
+                debug!("Failed to connect to peer {peer_id:?}: {error}.");
+            }
+            SwarmEvent::ExternalAddrConfirmed { address } => {
+                info!("The node is reachable on the external address {address}.");
+                self.external_addresses.add(address.to_string());
+            }
+            SwarmEvent::ExternalAddrExpired { address } => {
+                info!("The node is no longer reachable on the external address {address}.");
+                self.external_addresses.remove(&address.to_string());
+            }
+            SwarmEvent::Behaviour(mixed_behaviour::Event::BlockHeaders(event)) => {
                 self.handle_behaviour_event(event);
             }
+            SwarmEvent::Behaviour(mixed_behaviour::Event::StateDiffs(event)) => {
+                self.handle_state_diffs_event(event);
+            }
+            SwarmEvent::Behaviour(mixed_behaviour::Event::Identify(event)) => {
+                self.handle_identify_event(event);
+            }
+            SwarmEvent::Behaviour(mixed_behaviour::Event::Upnp(event)) => {
+                handle_upnp_event(event);
+            }
             _ => {
                 panic!("Unexpected event {event:?}");
             }
@@ -109,13 +182,13 @@ impl<DBExecutorT: DBExecutor, SwarmT: SwarmTrait> GenericNetworkManager<DBExecut
                 }
                 if let Some(query_id) = err.query_id() {
                     // TODO: Consider retrying based on error.
-                    let Some(inbound_session_id) =
+                    let Some((data_type, inbound_session_id)) =
                         self.query_id_to_inbound_session_id.remove(&query_id)
                     else {
                         error!("Received error on non existing query");
                         return;
                     };
-                    if self.swarm.send_data(Data::Fin, inbound_session_id).is_err() {
+                    if !self.send_data(Data::Fin, data_type, inbound_session_id) {
                         error!(
                             "Tried to close inbound session {inbound_session_id:?} due to {err:?} \
                              but the session was already closed"
@@ -129,15 +202,7 @@ impl<DBExecutorT: DBExecutor, SwarmT: SwarmTrait> GenericNetworkManager<DBExecut
     fn handle_behaviour_event(&mut self, event: Event) {
         match event {
             Event::NewInboundQuery { query, inbound_session_id } => {
-                trace!(
-                    "Received new inbound query: {query:?} for session id: {inbound_session_id:?}"
-                );
-                let (sender, receiver) = futures::channel::mpsc::channel(self.header_buffer_size);
-                // TODO: use query id for bookkeeping.
-                let query_id = self.db_executor.register_query(query, sender);
-                self.query_id_to_inbound_session_id.insert(query_id, inbound_session_id);
-                self.query_results_router
-                    .push(receiver.map(move |data| (data, inbound_session_id)).boxed());
+                self.handle_inbound_query(query, DataType::SignedBlockHeader, inbound_session_id);
             }
             Event::ReceivedData { signed_header, outbound_session_id } => {
                 debug!(
@@ -152,52 +217,253 @@ impl<DBExecutorT: DBExecutor, SwarmT: SwarmTrait> GenericNetworkManager<DBExecut
             }
             Event::SessionFailed { session_id, session_error } => {
                 debug!("Session {session_id:?} failed on {session_error:?}");
-                // TODO: Handle reputation and retry.
+                self.handle_session_failed(
+                    DataType::SignedBlockHeader,
+                    session_id,
+                    session_error_misconduct(&session_error),
+                );
             }
             Event::QueryConversionError(error) => {
                 debug!("Failed to convert incoming query on {error:?}");
                 // TODO: Consider adding peer_id to event and handling reputation.
             }
             Event::SessionFinishedSuccessfully { session_id } => {
-                debug!("Session completed successfully. session_id: {session_id:?}");
+                self.handle_session_finished(DataType::SignedBlockHeader, session_id);
+            }
+        }
+    }
+
+    fn handle_state_diffs_event(&mut self, event: state_diffs::Event) {
+        match event {
+            state_diffs::Event::NewInboundQuery { query, inbound_session_id } => {
+                self.handle_inbound_query(query, DataType::StateDiff, inbound_session_id);
+            }
+            state_diffs::Event::ReceivedStateDiffs { state_diffs, outbound_session_id } => {
+                debug!(
+                    "Received state diffs from peer for session id: {outbound_session_id:?}. \
+                     sending to sync subscriber."
+                );
+                if let Some((_, response_senders)) = self.sync_subscriber_channels.as_mut() {
+                    for state_diff in state_diffs {
+                        if let Err(e) = response_senders.state_diffs_sender.try_send(state_diff) {
+                            error!("Failed to send state diff to sync subscriber. error: {e:?}");
+                        }
+                    }
+                }
+            }
+            state_diffs::Event::SessionFailed { session_id, session_error } => {
+                debug!("Session {session_id:?} failed on {session_error:?}");
+                self.handle_session_failed(
+                    DataType::StateDiff,
+                    session_id,
+                    state_diffs_session_error_misconduct(&session_error),
+                );
+            }
+            state_diffs::Event::QueryConversionError(error) => {
+                debug!("Failed to convert incoming query on {error:?}");
+            }
+            state_diffs::Event::SessionFinishedSuccessfully { session_id } => {
+                self.handle_session_finished(DataType::StateDiff, session_id);
+            }
+        }
+    }
+
+    fn handle_inbound_query(
+        &mut self,
+        query: InternalQuery,
+        data_type: DataType,
+        inbound_session_id: InboundSessionId,
+    ) {
+        trace!(
+            "Received new inbound query: {query:?} of {data_type:?} for session id: \
+             {inbound_session_id:?}"
+        );
+        let (sender, receiver) = futures::channel::mpsc::channel(self.header_buffer_size);
+        // TODO: use query id for bookkeeping.
+        let query_id = self.db_executor.register_query(query, data_type, sender);
+        self.query_id_to_inbound_session_id.insert(query_id, (data_type, inbound_session_id));
+        self.query_results_router
+            .push(receiver.map(move |data| (data, data_type, inbound_session_id)).boxed());
+    }
+
+    fn handle_session_failed(
+        &mut self,
+        data_type: DataType,
+        session_id: SessionId,
+        misconduct: Option<Misconduct>,
+    ) {
+        // TODO: Handle retry.
+        let SessionId::OutboundSessionId(outbound_session_id) = session_id else {
+            return;
+        };
+        let Some((peer_id, _)) =
+            self.outbound_session_id_to_peer_id.remove(&(data_type, outbound_session_id))
+        else {
+            return;
+        };
+        self.peer_manager.report_failure(peer_id);
+        if let Some(misconduct) = misconduct {
+            self.report_misconduct(peer_id, misconduct);
+        }
+    }
+
+    fn handle_session_finished(&mut self, data_type: DataType, session_id: SessionId) {
+        debug!("Session completed successfully. session_id: {session_id:?}");
+        if let SessionId::OutboundSessionId(outbound_session_id) = session_id {
+            if let Some((peer_id, start_time)) =
+                self.outbound_session_id_to_peer_id.remove(&(data_type, outbound_session_id))
+            {
+                self.peer_manager.report_success(peer_id, start_time.elapsed());
+            }
+        }
+    }
+
+    // Sends the data through the behaviour of the protocol the session belongs to. Returns false
+    // if there's no such session.
+    fn send_data(
+        &mut self,
+        data: Data,
+        data_type: DataType,
+        inbound_session_id: InboundSessionId,
+    ) -> bool {
+        match (data_type, data) {
+            (DataType::SignedBlockHeader, data) => {
+                self.swarm.send_data(data, inbound_session_id).is_ok()
+            }
+            (DataType::StateDiff, Data::StateDiffChunk(chunk)) => {
+                self.swarm.send_state_diff_chunk(chunk, inbound_session_id).is_ok()
+            }
+            (DataType::StateDiff, Data::Fin) => {
+                self.swarm.send_state_diff_fin(inbound_session_id).is_ok()
+            }
+            (DataType::StateDiff, Data::BlockHeaderAndSignature { .. }) => {
+                unreachable!("Only state diff chunks are read for a state diff query.")
             }
         }
     }
 
-    fn handle_query_result_routing(&mut self, res: (Data, InboundSessionId)) {
+    fn handle_query_result_routing(&mut self, res: (Data, DataType, InboundSessionId)) {
         if self.query_results_router.is_empty() {
             // We're done handling all the queries we had and the stream is exhausted.
             // Creating a new stream collection to process new queries.
             self.query_results_router = StreamCollection::new();
         }
-        let (data, inbound_session_id) = res;
-        self.swarm.send_data(data, inbound_session_id).unwrap_or_else(|e| {
-            error!("Failed to send data to peer. Session id not found error: {e:?}");
-        })
+        let (data, data_type, inbound_session_id) = res;
+        if !self.send_data(data, data_type, inbound_session_id) {
+            error!("Failed to send data to peer. Session id {inbound_session_id:?} not found.");
+        }
     }
 
-    fn handle_sync_subscriber_query(&mut self, query: Query) {
+    fn handle_sync_subscriber_query(&mut self, mut query: Query) {
         let peer_id = self.peer_id.expect("cannot send query without peer id");
+        if self.peer_manager.is_banned(&peer_id, peer_manager::now()) {
+            error!("Failed to send query to peer. Peer {peer_id} is banned.");
+            return;
+        }
+        let data_type = query.data_type;
+        let expectations = std::mem::take(&mut query.state_diff_expectations);
         let internal_query = query.into();
-        match self.swarm.send_query(internal_query, peer_id) {
+        let sent_query = match data_type {
+            DataType::SignedBlockHeader => {
+                self.swarm.send_query(internal_query, peer_id).map_err(|e| e.to_string())
+            }
+            DataType::StateDiff => self
+                .swarm
+                .send_state_diff_query(internal_query, expectations, peer_id)
+                .map_err(|e| e.to_string()),
+        };
+        match sent_query {
             Ok(outbound_session_id) => {
+                self.outbound_session_id_to_peer_id
+                    .insert((data_type, outbound_session_id), (peer_id, Instant::now()));
                 debug!(
                     "Sent query to peer. query: {internal_query:?}, peer_id: {peer_id:?}, \
                      outbound_session_id: {outbound_session_id:?}"
                 );
             }
-            Err(e) => error!("Failed to send query to peer. Peer not connected error: {e:?}"),
+            Err(e) => error!("Failed to send query to peer. Peer not connected error: {e}"),
+        }
+    }
+
+    // A peer reported the address it sees the node on. Since the node may be behind a NAT, the
+    // address is advertised only once enough peers observed the node on it.
+    fn handle_identify_event(&mut self, event: identify::Event) {
+        let identify::Event::Received { peer_id, info } = event else {
+            return;
+        };
+        let observed_address = info.observed_addr;
+        if self.external_addresses.get().contains(&observed_address.to_string()) {
+            return;
+        }
+        let observing_peers = self.observed_addresses.entry(observed_address.clone()).or_default();
+        observing_peers.insert(peer_id);
+        if observing_peers.len() >= OBSERVED_ADDRESS_CONFIRMATIONS {
+            self.observed_addresses.remove(&observed_address);
+            self.swarm.add_external_address(observed_address);
+        }
+    }
+
+    fn report_misconduct(&mut self, peer_id: PeerId, misconduct: Misconduct) {
+        if self.peer_manager.report_misconduct(peer_id, misconduct, peer_manager::now()) {
+            self.swarm.disconnect_peer(peer_id);
         }
     }
 }
 
-pub type NetworkManager =
-    GenericNetworkManager<BlockHeaderDBExecutor, Swarm<BlockHeadersBehaviour>>;
+fn handle_upnp_event(event: upnp::Event) {
+    match event {
+        // The swarm reports the new and expired external addresses by itself.
+        upnp::Event::NewExternalAddr(address) => {
+            debug!("Mapped the external address {address} on the gateway.")
+        }
+        upnp::Event::ExpiredExternalAddr(address) => {
+            debug!("The mapping of the external address {address} on the gateway expired.")
+        }
+        upnp::Event::GatewayNotFound => info!("No UPnP gateway found."),
+        upnp::Event::NonRoutableGateway => {
+            info!("The UPnP gateway isn't exposed directly to the public internet.")
+        }
+    }
+}
+
+// Returns the misconduct of the peer that caused the session to fail, if it was the peer's fault.
+fn session_error_misconduct(session_error: &SessionError) -> Option<Misconduct> {
+    match session_error {
+        SessionError::ProtobufConversionError(_) => Some(Misconduct::InvalidData),
+        SessionError::ReceivedMessageAfterFin => Some(Misconduct::ProtocolViolation),
+        SessionError::StreamedData(streamed_data::behaviour::SessionError::Timeout { .. }) => {
+            Some(Misconduct::SlowResponse)
+        }
+        SessionError::StreamedData(_) | SessionError::SessionClosedUnexpectedly => None,
+    }
+}
+
+fn state_diffs_session_error_misconduct(
+    session_error: &state_diffs::SessionError,
+) -> Option<Misconduct> {
+    match session_error {
+        state_diffs::SessionError::ProtobufConversionError(_) => Some(Misconduct::InvalidData),
+        state_diffs::SessionError::StateDiffVerification(_) => Some(Misconduct::InvalidData),
+        state_diffs::SessionError::ReceivedMessageAfterFin => Some(Misconduct::ProtocolViolation),
+        state_diffs::SessionError::StreamedData(
+            streamed_data::behaviour::SessionError::Timeout { .. },
+        ) => Some(Misconduct::SlowResponse),
+        state_diffs::SessionError::StreamedData(_)
+        | state_diffs::SessionError::SessionClosedUnexpectedly => None,
+    }
+}
+
+pub type NetworkManager = GenericNetworkManager<BlockHeaderDBExecutor, Swarm<MixedBehaviour>>;
 
 impl NetworkManager {
     // TODO: add tests for this struct.
     // TODO: make sure errors are handled and not just paniced.
-    pub fn new(config: NetworkConfig, storage_reader: StorageReader) -> Self {
+    pub fn new(
+        config: NetworkConfig,
+        storage_reader: StorageReader,
+        external_addresses: Arc<ExternalAddresses>,
+        source_reports: Arc<SourceReports>,
+    ) -> StorageResult<Self> {
         let NetworkConfig {
             tcp_port,
             quic_port: _,
@@ -205,6 +471,10 @@ impl NetworkManager {
             idle_connection_timeout,
             header_buffer_size,
             peer_id,
+            bootnodes: _,
+            bootnode_dns_domain: _,
+            peer_manager: peer_manager_config,
+            enable_upnp,
         } = config;
 
         let listen_addresses = vec![
@@ -212,14 +482,25 @@ impl NetworkManager {
             // format!("/ip4/127.0.0.1/udp/{quic_port}/quic-v1"),
             format!("/ip4/127.0.0.1/tcp/{tcp_port}"),
         ];
-        let swarm = build_swarm(
-            listen_addresses,
-            idle_connection_timeout,
-            BlockHeadersBehaviour::new(session_timeout),
-        );
+        let swarm = build_swarm(listen_addresses, idle_connection_timeout, |key_pair| {
+            MixedBehaviour::new(
+                key_pair,
+                BlockHeadersBehaviour::new(session_timeout),
+                StateDiffsBehaviour::new(session_timeout),
+                enable_upnp,
+            )
+        });
 
+        let peer_manager = PeerManager::new(peer_manager_config, &storage_reader, source_reports)?;
         let db_executor = BlockHeaderDBExecutor::new(storage_reader);
-        Self::generic_new(swarm, db_executor, header_buffer_size, peer_id)
+        Ok(Self::generic_new(
+            swarm,
+            db_executor,
+            header_buffer_size,
+            peer_id,
+            peer_manager,
+            external_addresses,
+        ))
     }
 
     // TODO(shahak): Move this to the constructor and add the address to the config once we have
@@ -227,4 +508,17 @@ impl NetworkManager {
     pub fn dial(&mut self, dial_address: &str) {
         dial(&mut self.swarm, dial_address);
     }
+
+    /// Dials the static bootnodes and the bootnodes listed in the DNS records of the bootnode
+    /// domain. A bootnode that can't be dialed is skipped.
+    pub async fn dial_bootnodes(&mut self, bootnodes: &str, bootnode_dns_domain: Option<&str>) {
+        for address in bootnode_addresses(bootnodes, bootnode_dns_domain).await {
+            debug!("Dialing bootnode {address}.");
+            if let Err(err) =
+                self.swarm.dial(DialOpts::unknown_peer_id().address(address.clone()).build())
+            {
+                error!("Failed to dial bootnode {address}: {err}.");
+            }
+        }
+    }
 }
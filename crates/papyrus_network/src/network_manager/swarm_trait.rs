@@ -1,17 +1,19 @@
 use futures::stream::Stream;
 use libp2p::swarm::{NetworkBehaviour, SwarmEvent};
-use libp2p::{PeerId, Swarm};
+use libp2p::{Multiaddr, PeerId, Swarm};
 
-use crate::block_headers::behaviour::{
-    Behaviour as BlockHeadersBehaviour,
-    PeerNotConnected,
-    SessionIdNotFoundError,
-};
+use crate::block_headers::behaviour::{PeerNotConnected, SessionIdNotFoundError};
 use crate::db_executor::Data;
+use crate::mixed_behaviour::MixedBehaviour;
+use crate::state_diffs::behaviour::{
+    PeerNotConnected as StateDiffsPeerNotConnected,
+    SessionIdNotFoundError as StateDiffsSessionIdNotFoundError,
+};
+use crate::state_diffs::{StateDiffChunk, StateDiffExpectation};
 use crate::streamed_data::{InboundSessionId, OutboundSessionId};
 use crate::InternalQuery;
 
-pub type Event = SwarmEvent<<BlockHeadersBehaviour as NetworkBehaviour>::ToSwarm>;
+pub type Event = SwarmEvent<<MixedBehaviour as NetworkBehaviour>::ToSwarm>;
 
 pub trait SwarmTrait: Stream<Item = Event> + Unpin {
     fn send_data(
@@ -25,15 +27,37 @@ pub trait SwarmTrait: Stream<Item = Event> + Unpin {
         query: InternalQuery,
         peer_id: PeerId,
     ) -> Result<OutboundSessionId, PeerNotConnected>;
+
+    fn send_state_diff_chunk(
+        &mut self,
+        chunk: StateDiffChunk,
+        inbound_session_id: InboundSessionId,
+    ) -> Result<(), StateDiffsSessionIdNotFoundError>;
+
+    fn send_state_diff_fin(
+        &mut self,
+        inbound_session_id: InboundSessionId,
+    ) -> Result<(), StateDiffsSessionIdNotFoundError>;
+
+    fn send_state_diff_query(
+        &mut self,
+        query: InternalQuery,
+        expectations: Vec<StateDiffExpectation>,
+        peer_id: PeerId,
+    ) -> Result<OutboundSessionId, StateDiffsPeerNotConnected>;
+
+    fn disconnect_peer(&mut self, peer_id: PeerId);
+
+    fn add_external_address(&mut self, address: Multiaddr);
 }
 
-impl SwarmTrait for Swarm<BlockHeadersBehaviour> {
+impl SwarmTrait for Swarm<MixedBehaviour> {
     fn send_data(
         &mut self,
         data: Data,
         inbound_session_id: InboundSessionId,
     ) -> Result<(), SessionIdNotFoundError> {
-        self.behaviour_mut().send_data(data, inbound_session_id)
+        self.behaviour_mut().block_headers.send_data(data, inbound_session_id)
     }
 
     fn send_query(
@@ -41,6 +65,39 @@ impl SwarmTrait for Swarm<BlockHeadersBehaviour> {
         query: InternalQuery,
         peer_id: PeerId,
     ) -> Result<OutboundSessionId, PeerNotConnected> {
-        self.behaviour_mut().send_query(query, peer_id)
+        self.behaviour_mut().block_headers.send_query(query, peer_id)
+    }
+
+    fn send_state_diff_chunk(
+        &mut self,
+        chunk: StateDiffChunk,
+        inbound_session_id: InboundSessionId,
+    ) -> Result<(), StateDiffsSessionIdNotFoundError> {
+        self.behaviour_mut().state_diffs.send_chunk(chunk, inbound_session_id)
+    }
+
+    fn send_state_diff_fin(
+        &mut self,
+        inbound_session_id: InboundSessionId,
+    ) -> Result<(), StateDiffsSessionIdNotFoundError> {
+        self.behaviour_mut().state_diffs.send_fin(inbound_session_id)
+    }
+
+    fn send_state_diff_query(
+        &mut self,
+        query: InternalQuery,
+        expectations: Vec<StateDiffExpectation>,
+        peer_id: PeerId,
+    ) -> Result<OutboundSessionId, StateDiffsPeerNotConnected> {
+        self.behaviour_mut().state_diffs.send_query(query, expectations, peer_id)
+    }
+
+    fn disconnect_peer(&mut self, peer_id: PeerId) {
+        // Ignoring errors because an error here means we're not connected to the peer.
+        let _ = self.disconnect_peer_id(peer_id);
+    }
+
+    fn add_external_address(&mut self, address: Multiaddr) {
+        Swarm::add_external_address(self, address);
     }
 }
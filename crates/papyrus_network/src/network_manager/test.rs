@@ -1,5 +1,7 @@
 use std::collections::HashMap;
 use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::time::Duration;
 
@@ -8,8 +10,14 @@ use futures::channel::mpsc::{unbounded, Sender, UnboundedSender};
 use futures::future::poll_fn;
 use futures::stream::{FuturesUnordered, Stream};
 use futures::{pin_mut, Future, FutureExt, SinkExt, StreamExt};
-use libp2p::PeerId;
+use libp2p::{identify, identity, Multiaddr, PeerId};
+use papyrus_common::external_addresses::ExternalAddresses;
+use papyrus_storage::test_utils::get_test_storage;
 use starknet_api::block::{BlockHeader, BlockNumber, BlockSignature};
+use starknet_api::core::GlobalRoot;
+use starknet_api::state::{StateDiff, ThinStateDiff};
+use starknet_api::{class_hash, contract_address};
+use tempfile::TempDir;
 use tokio::select;
 use tokio::task::JoinHandle;
 use tokio::time::sleep;
@@ -17,8 +25,22 @@ use tokio::time::sleep;
 use super::swarm_trait::{Event, SwarmTrait};
 use super::GenericNetworkManager;
 use crate::block_headers::behaviour::{PeerNotConnected, SessionIdNotFoundError};
-use crate::block_headers::Event as BehaviourEvent;
+use crate::block_headers::{Event as BehaviourEvent, SessionError};
 use crate::db_executor::{poll_query_execution_set, DBExecutor, DBExecutorError, Data, QueryId};
+use crate::messages::ProtobufConversionError;
+use crate::mixed_behaviour::IDENTIFY_PROTOCOL_VERSION;
+use crate::peer_manager::{PeerManager, PeerManagerConfig};
+use crate::state_diffs::behaviour::{
+    PeerNotConnected as StateDiffsPeerNotConnected,
+    SessionIdNotFoundError as StateDiffsSessionIdNotFoundError,
+};
+use crate::state_diffs::{
+    self,
+    ContractDiff,
+    ReceivedStateDiff,
+    StateDiffChunk,
+    StateDiffExpectation,
+};
 use crate::streamed_data::{InboundSessionId, OutboundSessionId};
 use crate::{BlockHashOrNumber, DataType, Direction, InternalQuery, Query, SignedBlockHeader};
 
@@ -26,6 +48,9 @@ use crate::{BlockHashOrNumber, DataType, Direction, InternalQuery, Query, Signed
 struct MockSwarm {
     pub pending_events: Queue<Event>,
     pub sent_queries: Vec<(InternalQuery, PeerId)>,
+    pub sent_state_diff_queries: Vec<(InternalQuery, Vec<StateDiffExpectation>, PeerId)>,
+    pub disconnected_peers: Vec<PeerId>,
+    pub external_addresses: Vec<Multiaddr>,
     inbound_session_id_to_data_sender: HashMap<InboundSessionId, UnboundedSender<Data>>,
     next_outbound_session_id: usize,
 }
@@ -72,10 +97,9 @@ impl MockSwarm {
                 },
                 signatures: vec![],
             };
-            self.pending_events.push(Event::Behaviour(BehaviourEvent::ReceivedData {
-                signed_header,
-                outbound_session_id,
-            }));
+            self.pending_events.push(Event::Behaviour(
+                BehaviourEvent::ReceivedData { signed_header, outbound_session_id }.into(),
+            ));
         }
     }
 }
@@ -109,12 +133,52 @@ impl SwarmTrait for MockSwarm {
         self.next_outbound_session_id += 1;
         Ok(outbound_session_id)
     }
+
+    fn send_state_diff_chunk(
+        &mut self,
+        chunk: StateDiffChunk,
+        inbound_session_id: InboundSessionId,
+    ) -> Result<(), StateDiffsSessionIdNotFoundError> {
+        self.send_data(Data::StateDiffChunk(chunk), inbound_session_id)
+            .expect("The mock swarm doesn't fail sending data");
+        Ok(())
+    }
+
+    fn send_state_diff_fin(
+        &mut self,
+        inbound_session_id: InboundSessionId,
+    ) -> Result<(), StateDiffsSessionIdNotFoundError> {
+        self.send_data(Data::Fin, inbound_session_id)
+            .expect("The mock swarm doesn't fail sending data");
+        Ok(())
+    }
+
+    fn send_state_diff_query(
+        &mut self,
+        query: InternalQuery,
+        expectations: Vec<StateDiffExpectation>,
+        peer_id: PeerId,
+    ) -> Result<OutboundSessionId, StateDiffsPeerNotConnected> {
+        self.sent_state_diff_queries.push((query, expectations, peer_id));
+        let outbound_session_id = OutboundSessionId { value: self.next_outbound_session_id };
+        self.next_outbound_session_id += 1;
+        Ok(outbound_session_id)
+    }
+
+    fn disconnect_peer(&mut self, peer_id: PeerId) {
+        self.disconnected_peers.push(peer_id);
+    }
+
+    fn add_external_address(&mut self, address: Multiaddr) {
+        self.external_addresses.push(address);
+    }
 }
 
 #[derive(Default)]
 struct MockDBExecutor {
     next_query_id: usize,
     pub query_to_headers: HashMap<InternalQuery, Vec<BlockHeader>>,
+    pub query_to_state_diff_chunks: HashMap<InternalQuery, Vec<StateDiffChunk>>,
     query_execution_set: FuturesUnordered<JoinHandle<Result<QueryId, DBExecutorError>>>,
 }
 
@@ -128,19 +192,42 @@ impl Stream for MockDBExecutor {
 
 impl DBExecutor for MockDBExecutor {
     // TODO(shahak): Consider fixing code duplication with BlockHeaderDBExecutor.
-    fn register_query(&mut self, query: InternalQuery, mut sender: Sender<Data>) -> QueryId {
+    fn register_query(
+        &mut self,
+        query: InternalQuery,
+        data_type: DataType,
+        mut sender: Sender<Data>,
+    ) -> QueryId {
         let query_id = QueryId(self.next_query_id);
         self.next_query_id += 1;
-        let headers = self.query_to_headers.get(&query).unwrap().clone();
+        let data = match data_type {
+            DataType::SignedBlockHeader => self
+                .query_to_headers
+                .get(&query)
+                .unwrap()
+                .iter()
+                .cloned()
+                .map(|header| Data::BlockHeaderAndSignature {
+                    header,
+                    signature: BlockSignature::default(),
+                    state_diff_summary: None,
+                })
+                .collect::<Vec<_>>(),
+            DataType::StateDiff => self
+                .query_to_state_diff_chunks
+                .get(&query)
+                .unwrap()
+                .iter()
+                .cloned()
+                .map(Data::StateDiffChunk)
+                .collect(),
+        };
         self.query_execution_set.push(tokio::task::spawn(async move {
             {
-                for header in headers.iter().cloned() {
+                for data in data {
                     // Using poll_fn because Sender::poll_ready is not a future
                     if let Ok(()) = poll_fn(|cx| sender.poll_ready(cx)).await {
-                        if let Err(e) = sender.start_send(Data::BlockHeaderAndSignature {
-                            header,
-                            signature: BlockSignature::default(),
-                        }) {
+                        if let Err(e) = sender.start_send(data) {
                             return Err(DBExecutorError::SendError { query_id, send_error: e });
                         };
                     }
@@ -159,14 +246,24 @@ impl DBExecutor for MockDBExecutor {
 
 const HEADER_BUFFER_SIZE: usize = 100;
 
+fn get_test_peer_manager() -> (PeerManager, TempDir) {
+    let ((storage_reader, _), dir) = get_test_storage();
+    let peer_manager =
+        PeerManager::new(PeerManagerConfig::default(), &storage_reader, Arc::default()).unwrap();
+    (peer_manager, dir)
+}
+
 #[tokio::test]
 async fn register_subscriber_and_use_channels() {
     // create mocked network manager
+    let (peer_manager, _dir) = get_test_peer_manager();
     let mut network_manager = GenericNetworkManager::generic_new(
         MockSwarm::default(),
         MockDBExecutor::default(),
         HEADER_BUFFER_SIZE,
         Some(PeerId::random()),
+        peer_manager,
+        Arc::default(),
     );
     // define query
     let query_limit = 5;
@@ -177,6 +274,7 @@ async fn register_subscriber_and_use_channels() {
         limit: query_limit,
         step: 1,
         data_type: DataType::SignedBlockHeader,
+        state_diff_expectations: vec![],
     };
 
     // register subscriber and send query
@@ -222,22 +320,29 @@ async fn process_incoming_query() {
     // Setup mock swarm and tell it to return an event of new inbound query.
     let mut mock_swarm = MockSwarm::default();
     let inbound_session_id = InboundSessionId { value: 0 };
-    mock_swarm
-        .pending_events
-        .push(Event::Behaviour(BehaviourEvent::NewInboundQuery { query, inbound_session_id }));
+    mock_swarm.pending_events.push(Event::Behaviour(
+        BehaviourEvent::NewInboundQuery { query, inbound_session_id }.into(),
+    ));
 
     // Create a future that will return when Fin is sent with the data sent on the swarm.
     let get_data_fut = mock_swarm.get_data_sent_to_inbound_session(inbound_session_id);
 
-    let network_manager =
-        GenericNetworkManager::generic_new(mock_swarm, mock_db_executor, HEADER_BUFFER_SIZE, None);
+    let (peer_manager, _dir) = get_test_peer_manager();
+    let network_manager = GenericNetworkManager::generic_new(
+        mock_swarm,
+        mock_db_executor,
+        HEADER_BUFFER_SIZE,
+        None,
+        peer_manager,
+        Arc::default(),
+    );
 
     select! {
         inbound_session_data = get_data_fut => {
             let mut expected_data = headers
                 .into_iter()
                 .map(|header| Data::BlockHeaderAndSignature {
-                    header, signature: BlockSignature::default()
+                    header, signature: BlockSignature::default(), state_diff_summary: None
                 })
                 .collect::<Vec<_>>();
             expected_data.push(Data::Fin);
@@ -251,3 +356,195 @@ async fn process_incoming_query() {
         }
     }
 }
+
+#[tokio::test]
+async fn process_incoming_state_diff_query() {
+    let query = InternalQuery {
+        start_block: BlockHashOrNumber::Number(BlockNumber(0)),
+        direction: Direction::Forward,
+        limit: 1,
+        step: 1,
+    };
+    let chunks = vec![StateDiffChunk::ContractDiff(ContractDiff {
+        contract_address: contract_address!("0x1"),
+        class_hash: Some(class_hash!("0x10")),
+        ..Default::default()
+    })];
+
+    let mut mock_db_executor = MockDBExecutor::default();
+    mock_db_executor.query_to_state_diff_chunks.insert(query, chunks.clone());
+
+    // The sessions of the state diffs protocol are numbered separately from the sessions of the
+    // block headers protocol.
+    let mut mock_swarm = MockSwarm::default();
+    let inbound_session_id = InboundSessionId { value: 0 };
+    mock_swarm.pending_events.push(Event::Behaviour(
+        state_diffs::Event::NewInboundQuery { query, inbound_session_id }.into(),
+    ));
+    let get_data_fut = mock_swarm.get_data_sent_to_inbound_session(inbound_session_id);
+
+    let (peer_manager, _dir) = get_test_peer_manager();
+    let network_manager = GenericNetworkManager::generic_new(
+        mock_swarm,
+        mock_db_executor,
+        HEADER_BUFFER_SIZE,
+        None,
+        peer_manager,
+        Arc::default(),
+    );
+
+    select! {
+        inbound_session_data = get_data_fut => {
+            let mut expected_data =
+                chunks.into_iter().map(Data::StateDiffChunk).collect::<Vec<_>>();
+            expected_data.push(Data::Fin);
+            assert_eq!(inbound_session_data, expected_data);
+        }
+        _ = network_manager.run() => {
+            panic!("GenericNetworkManager::run finished before the session finished");
+        }
+        _ = sleep(Duration::from_secs(5)) => {
+            panic!("Test timed out");
+        }
+    }
+}
+
+#[tokio::test]
+async fn state_diff_query_of_subscriber() {
+    let peer_id = PeerId::random();
+    let (peer_manager, _dir) = get_test_peer_manager();
+    let mut network_manager = GenericNetworkManager::generic_new(
+        MockSwarm::default(),
+        MockDBExecutor::default(),
+        HEADER_BUFFER_SIZE,
+        Some(peer_id),
+        peer_manager,
+        Arc::default(),
+    );
+    let (_query_sender, mut response_receivers) = network_manager.register_subscriber();
+
+    let expectation = StateDiffExpectation {
+        block_number: BlockNumber(0),
+        state_diff_commitment: GlobalRoot::default(),
+        state_diff_length: 0,
+    };
+    network_manager.handle_sync_subscriber_query(Query {
+        start_block: BlockNumber(0),
+        direction: Direction::Forward,
+        limit: 1,
+        step: 1,
+        data_type: DataType::StateDiff,
+        state_diff_expectations: vec![expectation],
+    });
+    assert!(network_manager.swarm.sent_queries.is_empty());
+    let [(_, expectations, sent_peer_id)] = &network_manager.swarm.sent_state_diff_queries[..]
+    else {
+        panic!("Expected a single state diff query.");
+    };
+    assert_eq!(expectations, &vec![expectation]);
+    assert_eq!(sent_peer_id, &peer_id);
+
+    // The received state diffs are passed to the subscriber.
+    let received_state_diff = ReceivedStateDiff {
+        block_number: BlockNumber(0),
+        state_diff: ThinStateDiff::from(StateDiff::default()),
+        class_hashes: [(contract_address!("0x1"), class_hash!("0x10"))].into(),
+    };
+    network_manager.handle_swarm_event(Event::Behaviour(
+        state_diffs::Event::ReceivedStateDiffs {
+            state_diffs: vec![received_state_diff.clone()],
+            outbound_session_id: OutboundSessionId { value: 0 },
+        }
+        .into(),
+    ));
+    assert_eq!(
+        response_receivers.state_diffs_receiver.next().now_or_never(),
+        Some(Some(received_state_diff))
+    );
+}
+
+#[test]
+fn ban_peer_that_sent_invalid_data() {
+    let peer_id = PeerId::random();
+    let (peer_manager, _dir) = get_test_peer_manager();
+    let mut network_manager = GenericNetworkManager::generic_new(
+        MockSwarm::default(),
+        MockDBExecutor::default(),
+        HEADER_BUFFER_SIZE,
+        Some(peer_id),
+        peer_manager,
+        Arc::default(),
+    );
+    let query = || Query {
+        start_block: BlockNumber(0),
+        direction: Direction::Forward,
+        limit: 1,
+        step: 1,
+        data_type: DataType::SignedBlockHeader,
+        state_diff_expectations: vec![],
+    };
+
+    network_manager.handle_sync_subscriber_query(query());
+    assert_eq!(network_manager.swarm.sent_queries.len(), 1);
+
+    // The default penalty of invalid data is enough to ban the peer.
+    network_manager.handle_behaviour_event(BehaviourEvent::SessionFailed {
+        session_id: OutboundSessionId { value: 0 }.into(),
+        session_error: SessionError::ProtobufConversionError(ProtobufConversionError::MissingField),
+    });
+    assert_eq!(network_manager.swarm.disconnected_peers, vec![peer_id]);
+
+    // Queries aren't sent to a banned peer.
+    network_manager.handle_sync_subscriber_query(query());
+    assert_eq!(network_manager.swarm.sent_queries.len(), 1);
+}
+
+#[test]
+fn external_address_is_added_once_observed_by_enough_peers() {
+    let (peer_manager, _dir) = get_test_peer_manager();
+    let external_addresses = Arc::new(ExternalAddresses::default());
+    let mut network_manager = GenericNetworkManager::generic_new(
+        MockSwarm::default(),
+        MockDBExecutor::default(),
+        HEADER_BUFFER_SIZE,
+        None,
+        peer_manager,
+        external_addresses.clone(),
+    );
+    let observed_address = Multiaddr::from_str("/ip4/1.2.3.4/tcp/10000").unwrap();
+    let identify_event = |peer_id| {
+        let public_key = identity::Keypair::generate_ed25519().public();
+        Event::Behaviour(
+            identify::Event::Received {
+                peer_id,
+                info: identify::Info {
+                    public_key,
+                    protocol_version: IDENTIFY_PROTOCOL_VERSION.to_owned(),
+                    agent_version: String::new(),
+                    listen_addrs: vec![],
+                    protocols: vec![],
+                    observed_addr: observed_address.clone(),
+                },
+            }
+            .into(),
+        )
+    };
+
+    // The same peer reporting the address twice isn't enough.
+    let peer_id = PeerId::random();
+    network_manager.handle_swarm_event(identify_event(peer_id));
+    network_manager.handle_swarm_event(identify_event(peer_id));
+    assert!(network_manager.swarm.external_addresses.is_empty());
+
+    network_manager.handle_swarm_event(identify_event(PeerId::random()));
+    assert_eq!(network_manager.swarm.external_addresses, vec![observed_address.clone()]);
+
+    // The address is advertised only after the swarm confirms it.
+    assert!(external_addresses.get().is_empty());
+    network_manager
+        .handle_swarm_event(Event::ExternalAddrConfirmed { address: observed_address.clone() });
+    assert_eq!(external_addresses.get(), vec![observed_address.to_string()]);
+
+    network_manager.handle_swarm_event(Event::ExternalAddrExpired { address: observed_address });
+    assert!(external_addresses.get().is_empty());
+}
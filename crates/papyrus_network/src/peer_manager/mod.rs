@@ -0,0 +1,221 @@
+//! Scoring of the peers the node syncs from. Every misconduct of a peer (a protocol violation,
+//! invalid data or a slow response) adds a penalty to its score, and a peer whose score reaches
+//! the ban threshold is banned for a while. The bans are recorded in the reputation of the peers in
+//! the storage, so that a malicious peer isn't trusted again when the node restarts, along with the
+//! successful and failed sessions of the peers.
+#[cfg(test)]
+mod peer_manager_test;
+
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use libp2p::PeerId;
+use papyrus_common::source_reports::SourceReports;
+use papyrus_config::converters::deserialize_seconds_to_duration;
+use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use papyrus_storage::source_reputation::SourceReputationStorageReader;
+use papyrus_storage::{StorageReader, StorageResult};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+// The prefix of the ids of peers in the reputation storage, which is shared with the other data
+// sources of the node.
+const PEER_SOURCE_ID_PREFIX: &str = "p2p/";
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct PeerManagerConfig {
+    pub protocol_violation_penalty: u64,
+    pub invalid_data_penalty: u64,
+    pub slow_response_penalty: u64,
+    pub successful_session_reward: u64,
+    pub ban_threshold: u64,
+    #[serde(deserialize_with = "deserialize_seconds_to_duration")]
+    pub ban_duration: Duration,
+}
+
+impl SerializeConfig for PeerManagerConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([
+            ser_param(
+                "protocol_violation_penalty",
+                &self.protocol_violation_penalty,
+                "The penalty added to the score of a peer that violated the protocol, e.g. sent a \
+                 message after Fin.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "invalid_data_penalty",
+                &self.invalid_data_penalty,
+                "The penalty added to the score of a peer that sent data that failed to decode or \
+                 to verify.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "slow_response_penalty",
+                &self.slow_response_penalty,
+                "The penalty added to the score of a peer whose session timed out.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "successful_session_reward",
+                &self.successful_session_reward,
+                "The amount subtracted from the score of a peer for each session that finished \
+                 successfully.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "ban_threshold",
+                &self.ban_threshold,
+                "A peer whose score reaches this threshold is banned.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "ban_duration",
+                &self.ban_duration.as_secs(),
+                "The time in seconds a peer stays banned.",
+                ParamPrivacyInput::Public,
+            ),
+        ])
+    }
+}
+
+impl Default for PeerManagerConfig {
+    fn default() -> Self {
+        Self {
+            protocol_violation_penalty: 50,
+            invalid_data_penalty: 100,
+            slow_response_penalty: 10,
+            successful_session_reward: 1,
+            ban_threshold: 100,
+            ban_duration: Duration::from_secs(3600),
+        }
+    }
+}
+
+/// A misconduct of a peer that lowers its reputation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Misconduct {
+    /// The peer didn't follow the protocol, e.g. sent a message after Fin.
+    ProtocolViolation,
+    /// The peer sent data that couldn't be decoded or didn't pass verification.
+    InvalidData,
+    /// The peer didn't respond in time.
+    SlowResponse,
+}
+
+pub struct PeerManager {
+    config: PeerManagerConfig,
+    scores: HashMap<PeerId, u64>,
+    // The unix timestamp, in seconds, at which the ban of each banned peer ends.
+    banned_until: HashMap<PeerId, u64>,
+    // The sessions and the bans are queued for the sync, which owns the storage writer, to store.
+    source_reports: Arc<SourceReports>,
+}
+
+impl PeerManager {
+    /// Creates a peer manager with the bans that are recorded in the reputation of the peers in the
+    /// storage and didn't expire yet.
+    pub fn new(
+        config: PeerManagerConfig,
+        storage_reader: &StorageReader,
+        source_reports: Arc<SourceReports>,
+    ) -> StorageResult<Self> {
+        let now = now();
+        let mut banned_until = HashMap::new();
+        for (source_id, reputation) in
+            storage_reader.begin_ro_txn()?.get_all_source_reputations()?
+        {
+            let Some(peer_id) = source_id.strip_prefix(PEER_SOURCE_ID_PREFIX) else {
+                continue;
+            };
+            let Ok(peer_id) = PeerId::from_str(peer_id) else {
+                warn!("Skipping the reputation of {source_id}, which isn't a valid peer id.");
+                continue;
+            };
+            if !reputation.is_banned(now, config.ban_duration) {
+                continue;
+            }
+            if let Some(banned_at) = reputation.last_ban_timestamp {
+                banned_until
+                    .insert(peer_id, banned_at.saturating_add(config.ban_duration.as_secs()));
+            }
+        }
+        info!("Loaded {} banned peers.", banned_until.len());
+        Ok(Self { config, scores: HashMap::new(), banned_until, source_reports })
+    }
+
+    /// Adds the penalty of the misconduct to the score of the peer, and bans the peer if its score
+    /// reached the ban threshold. Returns true if the peer was banned due to this misconduct.
+    pub fn report_misconduct(&mut self, peer_id: PeerId, misconduct: Misconduct, now: u64) -> bool {
+        if self.is_banned(&peer_id, now) {
+            return false;
+        }
+        let penalty = match misconduct {
+            Misconduct::ProtocolViolation => self.config.protocol_violation_penalty,
+            Misconduct::InvalidData => self.config.invalid_data_penalty,
+            Misconduct::SlowResponse => self.config.slow_response_penalty,
+        };
+        let score = self.scores.entry(peer_id).or_default();
+        *score = score.saturating_add(penalty);
+        debug!("Peer {peer_id} misbehaved with {misconduct:?}. Its score is now {score}.");
+        if *score < self.config.ban_threshold {
+            return false;
+        }
+
+        info!("Banning peer {peer_id} for {} seconds.", self.config.ban_duration.as_secs());
+        self.scores.remove(&peer_id);
+        self.banned_until.insert(peer_id, now.saturating_add(self.config.ban_duration.as_secs()));
+        self.remove_expired_bans(now);
+        self.source_reports.ban(peer_source_id(&peer_id), now);
+        true
+    }
+
+    /// Lowers the score of the peer after it completed a session successfully, which took the given
+    /// time.
+    pub fn report_success(&mut self, peer_id: PeerId, latency: Duration) {
+        self.source_reports.report_success(peer_source_id(&peer_id), latency);
+        if let Some(score) = self.scores.get_mut(&peer_id) {
+            *score = score.saturating_sub(self.config.successful_session_reward);
+            if *score == 0 {
+                self.scores.remove(&peer_id);
+            }
+        }
+    }
+
+    /// Records that a session with the peer failed. Whether the failure is a misconduct of the peer
+    /// is reported separately.
+    pub fn report_failure(&mut self, peer_id: PeerId) {
+        self.source_reports.report_error(peer_source_id(&peer_id));
+    }
+
+    /// Returns true if the peer is banned at the given unix timestamp, in seconds.
+    pub fn is_banned(&self, peer_id: &PeerId, now: u64) -> bool {
+        self.banned_until.get(peer_id).is_some_and(|banned_until| now < *banned_until)
+    }
+
+    /// Returns the current score of the peer. A peer is banned once its score reaches the ban
+    /// threshold.
+    pub fn score(&self, peer_id: &PeerId) -> u64 {
+        self.scores.get(peer_id).copied().unwrap_or_default()
+    }
+
+    fn remove_expired_bans(&mut self, now: u64) {
+        self.banned_until.retain(|_, banned_until| now < *banned_until);
+    }
+}
+
+/// The id of the peer in the reputation storage.
+pub fn peer_source_id(peer_id: &PeerId) -> String {
+    format!("{PEER_SOURCE_ID_PREFIX}{peer_id}")
+}
+
+/// The current unix timestamp, in seconds.
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Current time is before unix epoch.")
+        .as_secs()
+}
@@ -0,0 +1,138 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use libp2p::PeerId;
+use papyrus_common::source_reports::{SourceReport, SourceReports};
+use papyrus_storage::source_reputation::SourceReputationStorageWriter;
+use papyrus_storage::test_utils::get_test_storage;
+use papyrus_storage::StorageReader;
+use pretty_assertions::assert_eq;
+
+use super::{peer_source_id, Misconduct, PeerManager, PeerManagerConfig};
+
+const NOW: u64 = 1000;
+const BAN_DURATION_SECONDS: u64 = 60;
+
+fn config() -> PeerManagerConfig {
+    PeerManagerConfig {
+        protocol_violation_penalty: 50,
+        invalid_data_penalty: 100,
+        slow_response_penalty: 10,
+        successful_session_reward: 5,
+        ban_threshold: 100,
+        ban_duration: Duration::from_secs(BAN_DURATION_SECONDS),
+    }
+}
+
+fn new_peer_manager(storage_reader: &StorageReader) -> PeerManager {
+    PeerManager::new(config(), storage_reader, Arc::default()).unwrap()
+}
+
+#[test]
+fn misconducts_accumulate_until_ban() {
+    let ((storage_reader, _), _temp_dir) = get_test_storage();
+    let mut peer_manager = new_peer_manager(&storage_reader);
+    let peer_id = PeerId::random();
+
+    assert!(!peer_manager.report_misconduct(peer_id, Misconduct::SlowResponse, NOW));
+    assert!(!peer_manager.report_misconduct(peer_id, Misconduct::ProtocolViolation, NOW));
+    assert_eq!(peer_manager.score(&peer_id), 60);
+    peer_manager.report_success(peer_id, Duration::from_millis(10));
+    assert_eq!(peer_manager.score(&peer_id), 55);
+    assert!(!peer_manager.is_banned(&peer_id, NOW));
+
+    assert!(peer_manager.report_misconduct(peer_id, Misconduct::ProtocolViolation, NOW));
+    assert!(peer_manager.is_banned(&peer_id, NOW));
+    assert!(peer_manager.is_banned(&peer_id, NOW + BAN_DURATION_SECONDS - 1));
+    assert!(!peer_manager.is_banned(&peer_id, NOW + BAN_DURATION_SECONDS));
+    assert_eq!(peer_manager.score(&peer_id), 0);
+
+    // Other peers aren't affected.
+    assert!(!peer_manager.is_banned(&PeerId::random(), NOW));
+}
+
+#[test]
+fn misconduct_of_banned_peer_does_not_extend_the_ban() {
+    let ((storage_reader, _), _temp_dir) = get_test_storage();
+    let mut peer_manager = new_peer_manager(&storage_reader);
+    let peer_id = PeerId::random();
+
+    assert!(peer_manager.report_misconduct(peer_id, Misconduct::InvalidData, NOW));
+    assert!(!peer_manager.report_misconduct(peer_id, Misconduct::InvalidData, NOW + 1));
+    assert!(!peer_manager.is_banned(&peer_id, NOW + BAN_DURATION_SECONDS));
+}
+
+#[tokio::test]
+async fn bans_are_recorded_in_the_reputation_of_the_peers() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let source_reports = Arc::new(SourceReports::default());
+    let banned_peer_id = PeerId::random();
+    let now = super::now();
+    let mut peer_manager =
+        PeerManager::new(config(), &storage_reader, source_reports.clone()).unwrap();
+    assert!(peer_manager.report_misconduct(banned_peer_id, Misconduct::InvalidData, now));
+
+    // The sync stores the queued ban.
+    let source_id = peer_source_id(&banned_peer_id);
+    assert_eq!(
+        source_reports.next_reports().await,
+        vec![SourceReport::Ban { source_id: source_id.clone(), timestamp: now }]
+    );
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .record_source_ban(&source_id, now)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let peer_manager = new_peer_manager(&storage_reader);
+    assert!(peer_manager.is_banned(&banned_peer_id, now));
+    assert!(!peer_manager.is_banned(&banned_peer_id, now + BAN_DURATION_SECONDS));
+}
+
+#[test]
+fn expired_bans_and_other_sources_are_not_loaded() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let now = super::now();
+    let expired_peer_id = PeerId::random();
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .record_source_ban(&peer_source_id(&expired_peer_id), now - BAN_DURATION_SECONDS)
+        .unwrap()
+        .record_source_ban("https://feeder.example", now)
+        .unwrap()
+        .record_source_ban("p2p/not_a_peer_id", now)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let peer_manager = new_peer_manager(&storage_reader);
+    assert!(!peer_manager.is_banned(&expired_peer_id, now));
+    assert!(peer_manager.banned_until.is_empty());
+}
+
+#[tokio::test]
+async fn sessions_are_reported_for_the_reputation_of_the_peers() {
+    let ((storage_reader, _), _temp_dir) = get_test_storage();
+    let source_reports = Arc::new(SourceReports::default());
+    let mut peer_manager =
+        PeerManager::new(config(), &storage_reader, source_reports.clone()).unwrap();
+    let peer_id = PeerId::random();
+
+    peer_manager.report_success(peer_id, Duration::from_millis(10));
+    peer_manager.report_failure(peer_id);
+
+    let source_id = peer_source_id(&peer_id);
+    assert_eq!(
+        source_reports.next_reports().await,
+        vec![
+            SourceReport::Success {
+                source_id: source_id.clone(),
+                latency: Duration::from_millis(10)
+            },
+            SourceReport::Error { source_id },
+        ]
+    );
+}
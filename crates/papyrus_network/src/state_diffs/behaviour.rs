@@ -0,0 +1,319 @@
+use std::collections::{HashMap, HashSet};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use libp2p::core::Endpoint;
+use libp2p::swarm::{
+    ConnectionDenied,
+    ConnectionHandler,
+    ConnectionId,
+    FromSwarm,
+    NetworkBehaviour,
+    ToSwarm,
+};
+use libp2p::{Multiaddr, PeerId, StreamProtocol};
+
+use super::{
+    ContractDiff,
+    DeclaredClass,
+    Event,
+    SessionError,
+    StateDiffAssembler,
+    StateDiffChunk,
+    StateDiffExpectation,
+};
+use crate::messages::{protobuf, ProtobufConversionError};
+use crate::streamed_data::behaviour::Event as StreamedDataEvent;
+use crate::streamed_data::{self, Config, InboundSessionId, OutboundSessionId, SessionId};
+use crate::InternalQuery;
+
+#[cfg(test)]
+#[path = "behaviour_test.rs"]
+mod behaviour_test;
+
+const PROTOCOL_NAME: &str = "/starknet/state_diffs/1";
+
+// TODO(shahak): Internalize this when we have a mixed behaviour.
+pub struct Behaviour {
+    streamed_data_behaviour: streamed_data::behaviour::Behaviour<
+        protobuf::StateDiffsRequest,
+        protobuf::StateDiffsResponse,
+    >,
+    sessions_pending_termination: HashSet<SessionId>,
+    // The assemblers of the state diffs that are received in each outbound session.
+    state_diff_assemblers: HashMap<OutboundSessionId, StateDiffAssembler>,
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error(transparent)]
+pub struct SessionIdNotFoundError(#[from] crate::streamed_data::behaviour::SessionIdNotFoundError);
+
+#[derive(thiserror::Error, Debug)]
+#[error(transparent)]
+pub struct PeerNotConnected(#[from] crate::streamed_data::behaviour::PeerNotConnected);
+
+impl Behaviour {
+    pub fn new(session_timeout: Duration) -> Self {
+        Self {
+            streamed_data_behaviour: streamed_data::behaviour::Behaviour::new(Config {
+                session_timeout,
+                protocol_name: StreamProtocol::new(PROTOCOL_NAME),
+            }),
+            sessions_pending_termination: HashSet::new(),
+            state_diff_assemblers: HashMap::new(),
+        }
+    }
+
+    /// Send a query for the state diffs of the given blocks. The expectations are what each of the
+    /// queried state diffs is verified against, in the order of the query.
+    pub(crate) fn send_query(
+        &mut self,
+        query: InternalQuery,
+        expectations: Vec<StateDiffExpectation>,
+        peer_id: PeerId,
+    ) -> Result<OutboundSessionId, PeerNotConnected> {
+        let outbound_session_id = self.streamed_data_behaviour.send_query(query.into(), peer_id)?;
+        self.state_diff_assemblers
+            .insert(outbound_session_id, StateDiffAssembler::new(expectations));
+        Ok(outbound_session_id)
+    }
+
+    /// Send a chunk of a state diff to the session with the given id. Return Error if no such
+    /// session exists.
+    pub(crate) fn send_chunk(
+        &mut self,
+        chunk: StateDiffChunk,
+        inbound_session_id: InboundSessionId,
+    ) -> Result<(), SessionIdNotFoundError> {
+        self.streamed_data_behaviour.send_data(
+            protobuf::StateDiffsResponse { state_diff_message: Some(chunk.into()) },
+            inbound_session_id,
+        )?;
+        Ok(())
+    }
+
+    /// Send Fin to the session with the given id and close it. Return Error if no such session
+    /// exists.
+    pub(crate) fn send_fin(
+        &mut self,
+        inbound_session_id: InboundSessionId,
+    ) -> Result<(), SessionIdNotFoundError> {
+        self.streamed_data_behaviour.send_data(
+            protobuf::StateDiffsResponse {
+                state_diff_message: Some(protobuf::state_diffs_response::StateDiffMessage::Fin(
+                    protobuf::Fin {},
+                )),
+            },
+            inbound_session_id,
+        )?;
+        self.sessions_pending_termination.insert(inbound_session_id.into());
+        // TODO(shahak): handle error.
+        let _ = self.streamed_data_behaviour.close_inbound_session(inbound_session_id);
+        Ok(())
+    }
+
+    fn handle_received_data(
+        &mut self,
+        data: protobuf::StateDiffsResponse,
+        outbound_session_id: OutboundSessionId,
+    ) -> Option<Event> {
+        if self.sessions_pending_termination.contains(&outbound_session_id.into()) {
+            return Some(
+                self.fail_session(outbound_session_id, SessionError::ReceivedMessageAfterFin),
+            );
+        }
+        let chunk = match data.state_diff_message {
+            Some(protobuf::state_diffs_response::StateDiffMessage::ContractDiff(contract_diff)) => {
+                ContractDiff::try_from(contract_diff).map(StateDiffChunk::ContractDiff)
+            }
+            Some(protobuf::state_diffs_response::StateDiffMessage::DeclaredClass(
+                declared_class,
+            )) => DeclaredClass::try_from(declared_class).map(StateDiffChunk::DeclaredClass),
+            Some(protobuf::state_diffs_response::StateDiffMessage::Fin(protobuf::Fin {})) => {
+                self.sessions_pending_termination.insert(outbound_session_id.into());
+                return self.handle_fin(outbound_session_id);
+            }
+            None => Err(ProtobufConversionError::MissingField),
+        };
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(error) => return Some(self.fail_session(outbound_session_id, error.into())),
+        };
+        let Some(state_diff_assembler) = self.state_diff_assemblers.get_mut(&outbound_session_id)
+        else {
+            return Some(
+                self.fail_session(outbound_session_id, SessionError::SessionClosedUnexpectedly),
+            );
+        };
+        match state_diff_assembler.add_chunk(chunk) {
+            Ok(state_diffs) if state_diffs.is_empty() => None,
+            Ok(state_diffs) => Some(Event::ReceivedStateDiffs { state_diffs, outbound_session_id }),
+            Err(error) => Some(self.fail_session(outbound_session_id, error.into())),
+        }
+    }
+
+    // Returns the empty state diffs that the peer completed by sending Fin.
+    fn handle_fin(&mut self, outbound_session_id: OutboundSessionId) -> Option<Event> {
+        let state_diffs = match self
+            .state_diff_assemblers
+            .get_mut(&outbound_session_id)
+            .map(StateDiffAssembler::finish)
+            .transpose()
+        {
+            Ok(state_diffs) => state_diffs.unwrap_or_default(),
+            Err(error) => return Some(self.fail_session(outbound_session_id, error.into())),
+        };
+        if state_diffs.is_empty() {
+            None
+        } else {
+            Some(Event::ReceivedStateDiffs { state_diffs, outbound_session_id })
+        }
+    }
+
+    fn fail_session(
+        &mut self,
+        outbound_session_id: OutboundSessionId,
+        session_error: SessionError,
+    ) -> Event {
+        self.drop_session(outbound_session_id.into());
+        Event::SessionFailed { session_id: outbound_session_id.into(), session_error }
+    }
+
+    fn drop_session(&mut self, session_id: SessionId) {
+        if let SessionId::OutboundSessionId(outbound_session_id) = session_id {
+            self.state_diff_assemblers.remove(&outbound_session_id);
+        }
+        // Ignoring errors if they occur because an error here means the session doesn't exist, and
+        // if the session doesn't exist we don't need to drop it.
+        let _ = self.streamed_data_behaviour.drop_session(session_id);
+    }
+
+    fn handle_session_finished(&mut self, session_id: SessionId) -> Option<Event> {
+        if let SessionId::OutboundSessionId(outbound_session_id) = session_id {
+            self.state_diff_assemblers.remove(&outbound_session_id);
+        }
+        if self.sessions_pending_termination.remove(&session_id) {
+            Some(Event::SessionFinishedSuccessfully { session_id })
+        } else {
+            Some(Event::SessionFailed {
+                session_id,
+                session_error: SessionError::SessionClosedUnexpectedly,
+            })
+        }
+    }
+
+    fn map_streamed_data_behaviour_event_to_own_event(
+        &mut self,
+        in_event: StreamedDataEvent<protobuf::StateDiffsRequest, protobuf::StateDiffsResponse>,
+    ) -> Option<Event> {
+        match in_event {
+            StreamedDataEvent::NewInboundSession { query, inbound_session_id, peer_id: _ } => {
+                match query.try_into() {
+                    Ok(query) => Some(Event::NewInboundQuery { query, inbound_session_id }),
+                    Err(e) => {
+                        self.drop_session(inbound_session_id.into());
+                        Some(Event::QueryConversionError(e))
+                    }
+                }
+            }
+            StreamedDataEvent::SessionFailed { session_id, error } => {
+                if let SessionId::OutboundSessionId(outbound_session_id) = session_id {
+                    self.state_diff_assemblers.remove(&outbound_session_id);
+                }
+                Some(Event::SessionFailed {
+                    session_id,
+                    session_error: SessionError::StreamedData(error),
+                })
+            }
+            StreamedDataEvent::SessionFinishedSuccessfully { session_id } => {
+                self.handle_session_finished(session_id)
+            }
+            StreamedDataEvent::ReceivedData { data, outbound_session_id } => {
+                self.handle_received_data(data, outbound_session_id)
+            }
+        }
+    }
+}
+
+impl NetworkBehaviour for Behaviour {
+    type ConnectionHandler =
+        streamed_data::handler::Handler<protobuf::StateDiffsRequest, protobuf::StateDiffsResponse>;
+    type ToSwarm = Event;
+
+    fn handle_established_inbound_connection(
+        &mut self,
+        connection_id: ConnectionId,
+        peer: PeerId,
+        local_addr: &Multiaddr,
+        remote_addr: &Multiaddr,
+    ) -> Result<Self::ConnectionHandler, ConnectionDenied> {
+        self.streamed_data_behaviour.handle_established_inbound_connection(
+            connection_id,
+            peer,
+            local_addr,
+            remote_addr,
+        )
+    }
+
+    fn handle_established_outbound_connection(
+        &mut self,
+        connection_id: ConnectionId,
+        peer: PeerId,
+        addr: &Multiaddr,
+        role_override: Endpoint,
+    ) -> Result<Self::ConnectionHandler, ConnectionDenied> {
+        self.streamed_data_behaviour.handle_established_outbound_connection(
+            connection_id,
+            peer,
+            addr,
+            role_override,
+        )
+    }
+
+    fn on_swarm_event(&mut self, event: FromSwarm<'_>) {
+        self.streamed_data_behaviour.on_swarm_event(event)
+    }
+
+    fn on_connection_handler_event(
+        &mut self,
+        peer_id: PeerId,
+        connection_id: ConnectionId,
+        event: <Self::ConnectionHandler as ConnectionHandler>::ToBehaviour,
+    ) {
+        self.streamed_data_behaviour.on_connection_handler_event(peer_id, connection_id, event)
+    }
+
+    fn poll(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<ToSwarm<Self::ToSwarm, <Self::ConnectionHandler as ConnectionHandler>::FromBehaviour>>
+    {
+        match self.streamed_data_behaviour.poll(cx) {
+            Poll::Ready(streamed_data_event) => {
+                let mut ignore_event_and_return_pending = false;
+                let event = streamed_data_event.map_out(|streamed_data_event| {
+                    // Due to the use of "map_out" functionality of libp2p we must return an event
+                    // from this function. Therefore in the case where we want to ignore the event
+                    // and return a pending poll we mark it and return a dummy event.
+                    if let Some(event) =
+                        self.map_streamed_data_behaviour_event_to_own_event(streamed_data_event)
+                    {
+                        event
+                    } else {
+                        ignore_event_and_return_pending = true;
+                        Event::SessionFailed {
+                            session_id: OutboundSessionId::default().into(),
+                            session_error: SessionError::SessionClosedUnexpectedly,
+                        }
+                    }
+                });
+                if ignore_event_and_return_pending {
+                    Poll::Pending
+                } else {
+                    Poll::Ready(event)
+                }
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use std::time::Duration;
+
+use assert_matches::assert_matches;
+use libp2p::PeerId;
+use papyrus_common::state_diff_commitment::{calculate_state_diff_commitment, StateDiffVersion};
+use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
+use starknet_api::core::{GlobalRoot, Nonce};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::ThinStateDiff;
+use starknet_api::{class_hash, contract_address, stark_felt};
+
+use super::super::{
+    split_state_diff,
+    state_diff_length,
+    Event,
+    SessionError,
+    StateDiffAssembler,
+    StateDiffExpectation,
+    StateDiffVerificationError,
+};
+use super::Behaviour;
+use crate::messages::{protobuf, ProtobufConversionError};
+use crate::streamed_data::{self, OutboundSessionId};
+use crate::InternalQuery;
+
+type StreamedDataEvent =
+    streamed_data::behaviour::Event<protobuf::StateDiffsRequest, protobuf::StateDiffsResponse>;
+
+fn state_diff() -> ThinStateDiff {
+    ThinStateDiff {
+        deployed_contracts: [(contract_address!("0x1"), class_hash!("0x10"))].into(),
+        nonces: [(contract_address!("0x1"), Nonce(stark_felt!("0x1")))].into(),
+        deprecated_declared_classes: vec![class_hash!("0x40")],
+        ..super::super::empty_state_diff()
+    }
+}
+
+fn expectation(state_diff: &ThinStateDiff) -> StateDiffExpectation {
+    StateDiffExpectation {
+        block_number: BlockNumber(0),
+        state_diff_commitment: calculate_state_diff_commitment(state_diff, StateDiffVersion::V0),
+        state_diff_length: state_diff_length(state_diff),
+    }
+}
+
+fn behaviour_with_outbound_session(
+    expectation: StateDiffExpectation,
+) -> (Behaviour, OutboundSessionId) {
+    let mut behaviour = Behaviour::new(Duration::from_secs(1));
+    let outbound_session_id = OutboundSessionId { value: rand::random() };
+    behaviour
+        .state_diff_assemblers
+        .insert(outbound_session_id, StateDiffAssembler::new([expectation]));
+    (behaviour, outbound_session_id)
+}
+
+fn received_data_event(
+    state_diff_message: Option<protobuf::state_diffs_response::StateDiffMessage>,
+    outbound_session_id: OutboundSessionId,
+) -> StreamedDataEvent {
+    streamed_data::behaviour::Event::ReceivedData {
+        outbound_session_id,
+        data: protobuf::StateDiffsResponse { state_diff_message },
+    }
+}
+
+fn fin() -> Option<protobuf::state_diffs_response::StateDiffMessage> {
+    Some(protobuf::state_diffs_response::StateDiffMessage::Fin(protobuf::Fin {}))
+}
+
+#[test]
+fn map_streamed_data_behaviour_event_to_own_event_new_inbound_session() {
+    let mut behaviour = Behaviour::new(Duration::from_secs(1));
+
+    let query = protobuf::StateDiffsRequest {
+        iteration: Some(protobuf::Iteration {
+            start: Some(protobuf::iteration::Start::BlockNumber(1)),
+            direction: 0,
+            limit: 1,
+            step: 1,
+        }),
+    };
+    let inbound_session_id = streamed_data::InboundSessionId { value: rand::random() };
+    let streamed_data_event: StreamedDataEvent =
+        streamed_data::behaviour::Event::NewInboundSession {
+            inbound_session_id,
+            peer_id: PeerId::random(),
+            query: query.clone(),
+        };
+    let converted_query: InternalQuery = query.try_into().unwrap();
+    assert_matches!(
+        behaviour.map_streamed_data_behaviour_event_to_own_event(streamed_data_event),
+        Some(Event::NewInboundQuery { query, inbound_session_id: session_id })
+        if query == converted_query && session_id == inbound_session_id
+    );
+
+    let streamed_data_event: StreamedDataEvent =
+        streamed_data::behaviour::Event::NewInboundSession {
+            inbound_session_id,
+            peer_id: PeerId::random(),
+            query: protobuf::StateDiffsRequest::default(),
+        };
+    assert_matches!(
+        behaviour.map_streamed_data_behaviour_event_to_own_event(streamed_data_event),
+        Some(Event::QueryConversionError(ProtobufConversionError::MissingField))
+    );
+}
+
+#[test]
+fn map_streamed_data_behaviour_event_to_own_event_receive_state_diff_happy_flow() {
+    let state_diff = state_diff();
+    let (mut behaviour, outbound_session_id) =
+        behaviour_with_outbound_session(expectation(&state_diff));
+
+    let mut chunks = split_state_diff(state_diff.clone(), 1);
+    let last_chunk = chunks.pop().unwrap();
+    for chunk in chunks {
+        let event = received_data_event(Some(chunk.into()), outbound_session_id);
+        assert_matches!(behaviour.map_streamed_data_behaviour_event_to_own_event(event), None);
+    }
+    let event = received_data_event(Some(last_chunk.into()), outbound_session_id);
+    assert_matches!(
+        behaviour.map_streamed_data_behaviour_event_to_own_event(event),
+        Some(Event::ReceivedStateDiffs { state_diffs, outbound_session_id: session_id }) => {
+            let [received_state_diff] = &state_diffs[..] else {
+                panic!("Expected a single state diff, got {state_diffs:?}.");
+            };
+            assert_eq!(received_state_diff.block_number, BlockNumber(0));
+            assert_eq!(
+                received_state_diff.clone().resolve(|_| Ok::<_, ()>(false)).unwrap(),
+                state_diff
+            );
+            assert_eq!(session_id, outbound_session_id);
+        }
+    );
+
+    let event = received_data_event(fin(), outbound_session_id);
+    assert_matches!(behaviour.map_streamed_data_behaviour_event_to_own_event(event), None);
+
+    // Any message after Fin fails the session.
+    let event = received_data_event(fin(), outbound_session_id);
+    assert_matches!(
+        behaviour.map_streamed_data_behaviour_event_to_own_event(event),
+        Some(Event::SessionFailed { session_id, session_error: SessionError::ReceivedMessageAfterFin })
+        if session_id == outbound_session_id.into()
+    );
+}
+
+#[test]
+fn map_streamed_data_behaviour_event_to_own_event_receive_protobuf_conversion_error() {
+    let (mut behaviour, outbound_session_id) =
+        behaviour_with_outbound_session(expectation(&state_diff()));
+
+    let event = received_data_event(None, outbound_session_id);
+    assert_matches!(
+        behaviour.map_streamed_data_behaviour_event_to_own_event(event),
+        Some(Event::SessionFailed {
+            session_id,
+            session_error: SessionError::ProtobufConversionError(ProtobufConversionError::MissingField),
+        }) if session_id == outbound_session_id.into()
+    );
+    assert!(behaviour.state_diff_assemblers.is_empty());
+}
+
+#[test]
+fn map_streamed_data_behaviour_event_to_own_event_receive_state_diff_with_wrong_commitment() {
+    let state_diff = state_diff();
+    let (mut behaviour, outbound_session_id) =
+        behaviour_with_outbound_session(StateDiffExpectation {
+            state_diff_commitment: GlobalRoot(StarkFelt::from(1_u8)),
+            ..expectation(&state_diff)
+        });
+
+    let mut chunks = split_state_diff(state_diff, 1);
+    let last_chunk = chunks.pop().unwrap();
+    for chunk in chunks {
+        let event = received_data_event(Some(chunk.into()), outbound_session_id);
+        assert_matches!(behaviour.map_streamed_data_behaviour_event_to_own_event(event), None);
+    }
+    let event = received_data_event(Some(last_chunk.into()), outbound_session_id);
+    assert_matches!(
+        behaviour.map_streamed_data_behaviour_event_to_own_event(event),
+        Some(Event::SessionFailed {
+            session_id,
+            session_error: SessionError::StateDiffVerification(
+                StateDiffVerificationError::WrongCommitment { .. }
+            ),
+        }) if session_id == outbound_session_id.into()
+    );
+    assert!(behaviour.state_diff_assemblers.is_empty());
+}
+
+#[test]
+fn map_streamed_data_behaviour_event_to_own_event_receive_fin_in_the_middle_of_a_state_diff() {
+    let state_diff = state_diff();
+    let (mut behaviour, outbound_session_id) =
+        behaviour_with_outbound_session(expectation(&state_diff));
+
+    let chunk = split_state_diff(state_diff, 1).remove(0);
+    let event = received_data_event(Some(chunk.into()), outbound_session_id);
+    assert_matches!(behaviour.map_streamed_data_behaviour_event_to_own_event(event), None);
+
+    let event = received_data_event(fin(), outbound_session_id);
+    assert_matches!(
+        behaviour.map_streamed_data_behaviour_event_to_own_event(event),
+        Some(Event::SessionFailed {
+            session_id,
+            session_error: SessionError::StateDiffVerification(
+                StateDiffVerificationError::IncompleteStateDiff { block_number: BlockNumber(0) }
+            ),
+        }) if session_id == outbound_session_id.into()
+    );
+}
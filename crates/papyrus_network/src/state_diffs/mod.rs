@@ -0,0 +1,505 @@
+//! Syncing state diffs from peers. A block's state diff may be too large for a single message, so
+//! it's sent in chunks, each holding a part of the diff. The receiver verifies every chunk against
+//! the length of the diff that the block's header declares as it arrives, and verifies the
+//! assembled diff against the block's state diff commitment.
+pub mod behaviour;
+
+#[cfg(test)]
+mod state_diffs_test;
+
+use std::collections::{BTreeMap, VecDeque};
+
+use papyrus_common::state_diff_commitment::{calculate_state_diff_commitment, StateDiffVersion};
+use starknet_api::block::BlockNumber;
+use starknet_api::core::{
+    ClassHash,
+    CompiledClassHash,
+    ContractAddress,
+    GlobalRoot,
+    Nonce,
+    PatriciaKey,
+};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::{StateDiff, StorageKey, ThinStateDiff};
+
+use crate::messages::{protobuf, ProtobufConversionError};
+use crate::streamed_data::{self, SessionId};
+use crate::InternalQuery;
+
+/// The maximal number of storage values in a chunk. A contract diff with more storage values is
+/// split into several chunks.
+pub const MAX_STORAGE_VALUES_PER_CHUNK: usize = 1000;
+
+/// A part of the state diff of a block that is sent in a single message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StateDiffChunk {
+    ContractDiff(ContractDiff),
+    DeclaredClass(DeclaredClass),
+}
+
+/// The changes to a contract. The changes to a contract with many storage values may be split
+/// among several consecutive chunks.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ContractDiff {
+    pub contract_address: ContractAddress,
+    /// The class of the contract, if it was deployed or its class was replaced in the block.
+    pub class_hash: Option<ClassHash>,
+    pub nonce: Option<Nonce>,
+    pub storage_diffs: Vec<(StorageKey, StarkFelt)>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeclaredClass {
+    pub class_hash: ClassHash,
+    /// None for Cairo 0 classes.
+    pub compiled_class_hash: Option<CompiledClassHash>,
+}
+
+impl StateDiffChunk {
+    /// The number of entries of the state diff that the chunk holds, as counted by
+    /// [`state_diff_length`].
+    pub fn num_entries(&self) -> usize {
+        match self {
+            StateDiffChunk::ContractDiff(contract_diff) => {
+                usize::from(contract_diff.class_hash.is_some())
+                    + usize::from(contract_diff.nonce.is_some())
+                    + contract_diff.storage_diffs.len()
+            }
+            StateDiffChunk::DeclaredClass(_) => 1,
+        }
+    }
+}
+
+/// Returns the number of entries in the state diff: the deployed contracts and replaced classes,
+/// the nonce updates, the storage values and the declared classes.
+pub fn state_diff_length(state_diff: &ThinStateDiff) -> usize {
+    state_diff.deployed_contracts.len()
+        + state_diff.replaced_classes.len()
+        + state_diff.nonces.len()
+        + state_diff.storage_diffs.values().map(|storage_diff| storage_diff.len()).sum::<usize>()
+        + state_diff.declared_classes.len()
+        + state_diff.deprecated_declared_classes.len()
+}
+
+/// Splits the state diff into the chunks it's sent in: the changes to each contract, ordered by
+/// address, followed by the declared classes. The changes to a contract with more than
+/// `max_storage_values_per_chunk` storage values are split into several chunks, the first of which
+/// holds the class hash and the nonce of the contract.
+pub fn split_state_diff(
+    state_diff: ThinStateDiff,
+    max_storage_values_per_chunk: usize,
+) -> Vec<StateDiffChunk> {
+    let mut contract_diffs = BTreeMap::<ContractAddress, ContractDiff>::new();
+    for (contract_address, class_hash) in
+        state_diff.deployed_contracts.into_iter().chain(state_diff.replaced_classes)
+    {
+        contract_diff_entry(&mut contract_diffs, contract_address).class_hash = Some(class_hash);
+    }
+    for (contract_address, nonce) in state_diff.nonces {
+        contract_diff_entry(&mut contract_diffs, contract_address).nonce = Some(nonce);
+    }
+    // Contracts with an empty storage diff have nothing to send.
+    for (contract_address, storage_diff) in
+        state_diff.storage_diffs.into_iter().filter(|(_, storage_diff)| !storage_diff.is_empty())
+    {
+        contract_diff_entry(&mut contract_diffs, contract_address)
+            .storage_diffs
+            .extend(storage_diff);
+    }
+
+    let mut chunks = vec![];
+    for (contract_address, contract_diff) in contract_diffs {
+        let mut storage_chunks =
+            contract_diff.storage_diffs.chunks(max_storage_values_per_chunk.max(1));
+        chunks.push(StateDiffChunk::ContractDiff(ContractDiff {
+            contract_address,
+            class_hash: contract_diff.class_hash,
+            nonce: contract_diff.nonce,
+            storage_diffs: storage_chunks.next().map(<[_]>::to_vec).unwrap_or_default(),
+        }));
+        chunks.extend(storage_chunks.map(|storage_chunk| {
+            StateDiffChunk::ContractDiff(ContractDiff {
+                contract_address,
+                storage_diffs: storage_chunk.to_vec(),
+                ..Default::default()
+            })
+        }));
+    }
+    chunks.extend(
+        state_diff
+            .declared_classes
+            .into_iter()
+            .map(|(class_hash, compiled_class_hash)| DeclaredClass {
+                class_hash,
+                compiled_class_hash: Some(compiled_class_hash),
+            })
+            .chain(
+                state_diff
+                    .deprecated_declared_classes
+                    .into_iter()
+                    .map(|class_hash| DeclaredClass { class_hash, compiled_class_hash: None }),
+            )
+            .map(StateDiffChunk::DeclaredClass),
+    );
+    chunks
+}
+
+fn contract_diff_entry(
+    contract_diffs: &mut BTreeMap<ContractAddress, ContractDiff>,
+    contract_address: ContractAddress,
+) -> &mut ContractDiff {
+    contract_diffs
+        .entry(contract_address)
+        .or_insert_with(|| ContractDiff { contract_address, ..Default::default() })
+}
+
+/// What the state diff of a block is verified against, as declared in the block's header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StateDiffExpectation {
+    pub block_number: BlockNumber,
+    pub state_diff_commitment: GlobalRoot,
+    pub state_diff_length: usize,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum StateDiffVerificationError {
+    #[error("Received a state diff chunk after all the requested state diffs were received.")]
+    UnexpectedChunk,
+    #[error("Received an empty chunk of the state diff of block {block_number}.")]
+    EmptyChunk { block_number: BlockNumber },
+    #[error("The state diff of block {block_number} is longer than its length {expected_length}.")]
+    StateDiffTooLong { block_number: BlockNumber, expected_length: usize },
+    #[error(
+        "A chunk of the state diff of block {block_number} sets a value that a previous chunk \
+         already set."
+    )]
+    ConflictingChunk { block_number: BlockNumber },
+    #[error(
+        "The state diff of block {block_number} doesn't match its commitment. Expected \
+         {expected:?}, got {actual:?}."
+    )]
+    WrongCommitment { block_number: BlockNumber, expected: GlobalRoot, actual: GlobalRoot },
+    #[error("The peer stopped sending in the middle of the state diff of block {block_number}.")]
+    IncompleteStateDiff { block_number: BlockNumber },
+}
+
+/// A state diff that was assembled from its chunks. The chunks don't tell the contracts that were
+/// deployed in the block apart from the contracts whose class was replaced, so the class hashes of
+/// both are kept aside until the diff is resolved against the state before the block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReceivedStateDiff {
+    pub block_number: BlockNumber,
+    /// The state diff, without the deployed contracts and the replaced classes.
+    pub state_diff: ThinStateDiff,
+    /// The class hashes of the contracts that were deployed or whose class was replaced.
+    pub class_hashes: BTreeMap<ContractAddress, ClassHash>,
+}
+
+impl ReceivedStateDiff {
+    /// Returns the state diff, where the class hash of a contract that `is_deployed` reports as
+    /// deployed before the block is a replaced class, and the class hash of any other contract is
+    /// a deployed contract.
+    pub fn resolve<E>(
+        self,
+        mut is_deployed: impl FnMut(ContractAddress) -> Result<bool, E>,
+    ) -> Result<ThinStateDiff, E> {
+        let mut state_diff = self.state_diff;
+        for (contract_address, class_hash) in self.class_hashes {
+            if is_deployed(contract_address)? {
+                state_diff.replaced_classes.insert(contract_address, class_hash);
+            } else {
+                state_diff.deployed_contracts.insert(contract_address, class_hash);
+            }
+        }
+        Ok(state_diff)
+    }
+}
+
+/// Assembles the state diffs of consecutive blocks from their chunks, verifying each chunk as it
+/// arrives.
+pub struct StateDiffAssembler {
+    expectations: VecDeque<StateDiffExpectation>,
+    current_state_diff: ThinStateDiff,
+    current_class_hashes: BTreeMap<ContractAddress, ClassHash>,
+    current_length: usize,
+}
+
+impl StateDiffAssembler {
+    /// Creates an assembler of the state diffs of the given blocks, in the order they're sent.
+    pub fn new(expectations: impl IntoIterator<Item = StateDiffExpectation>) -> Self {
+        Self {
+            expectations: expectations.into_iter().collect(),
+            current_state_diff: empty_state_diff(),
+            current_class_hashes: BTreeMap::new(),
+            current_length: 0,
+        }
+    }
+
+    /// Verifies the chunk and adds it to the state diff it belongs to. Returns the state diffs
+    /// that were completed by the chunk, after verifying them against their commitments. These
+    /// include the empty state diffs of the blocks that follow.
+    pub fn add_chunk(
+        &mut self,
+        chunk: StateDiffChunk,
+    ) -> Result<Vec<ReceivedStateDiff>, StateDiffVerificationError> {
+        let mut completed_state_diffs = self.complete_empty_state_diffs()?;
+        let expectation =
+            *self.expectations.front().ok_or(StateDiffVerificationError::UnexpectedChunk)?;
+        let block_number = expectation.block_number;
+        let chunk_length = chunk.num_entries();
+        if chunk_length == 0 {
+            return Err(StateDiffVerificationError::EmptyChunk { block_number });
+        }
+        if self.current_length + chunk_length > expectation.state_diff_length {
+            return Err(StateDiffVerificationError::StateDiffTooLong {
+                block_number,
+                expected_length: expectation.state_diff_length,
+            });
+        }
+        if !self.add_to_current_state_diff(chunk) {
+            return Err(StateDiffVerificationError::ConflictingChunk { block_number });
+        }
+        self.current_length += chunk_length;
+        if self.current_length == expectation.state_diff_length {
+            completed_state_diffs.push(self.complete_current_state_diff()?);
+            completed_state_diffs.extend(self.complete_empty_state_diffs()?);
+        }
+        Ok(completed_state_diffs)
+    }
+
+    /// Called once the peer stopped sending chunks. Returns the empty state diffs that weren't
+    /// returned yet. The peer may stop before sending all the requested state diffs, but not in
+    /// the middle of one.
+    pub fn finish(&mut self) -> Result<Vec<ReceivedStateDiff>, StateDiffVerificationError> {
+        if self.current_length > 0 {
+            let expectation =
+                self.expectations.front().expect("A state diff should be assembled for a block.");
+            return Err(StateDiffVerificationError::IncompleteStateDiff {
+                block_number: expectation.block_number,
+            });
+        }
+        self.complete_empty_state_diffs()
+    }
+
+    // Returns false if the chunk sets a value that was already set.
+    fn add_to_current_state_diff(&mut self, chunk: StateDiffChunk) -> bool {
+        let state_diff = &mut self.current_state_diff;
+        match chunk {
+            StateDiffChunk::ContractDiff(ContractDiff {
+                contract_address,
+                class_hash,
+                nonce,
+                storage_diffs,
+            }) => {
+                if let Some(class_hash) = class_hash {
+                    if self.current_class_hashes.insert(contract_address, class_hash).is_some() {
+                        return false;
+                    }
+                }
+                if let Some(nonce) = nonce {
+                    if state_diff.nonces.insert(contract_address, nonce).is_some() {
+                        return false;
+                    }
+                }
+                if !storage_diffs.is_empty() {
+                    let contract_storage_diffs =
+                        state_diff.storage_diffs.entry(contract_address).or_default();
+                    for (key, value) in storage_diffs {
+                        if contract_storage_diffs.insert(key, value).is_some() {
+                            return false;
+                        }
+                    }
+                }
+            }
+            StateDiffChunk::DeclaredClass(DeclaredClass {
+                class_hash,
+                compiled_class_hash: Some(compiled_class_hash),
+            }) => {
+                if state_diff.declared_classes.insert(class_hash, compiled_class_hash).is_some() {
+                    return false;
+                }
+            }
+            StateDiffChunk::DeclaredClass(DeclaredClass {
+                class_hash,
+                compiled_class_hash: None,
+            }) => {
+                if state_diff.deprecated_declared_classes.contains(&class_hash) {
+                    return false;
+                }
+                state_diff.deprecated_declared_classes.push(class_hash);
+            }
+        }
+        true
+    }
+
+    fn complete_current_state_diff(
+        &mut self,
+    ) -> Result<ReceivedStateDiff, StateDiffVerificationError> {
+        let expectation =
+            self.expectations.pop_front().expect("A state diff should be assembled for a block.");
+        let mut state_diff = std::mem::replace(&mut self.current_state_diff, empty_state_diff());
+        let class_hashes = std::mem::take(&mut self.current_class_hashes);
+        self.current_length = 0;
+        // The commitment squashes the deployed contracts and the replaced classes, so it's
+        // calculated as if they were all deployed.
+        state_diff.deployed_contracts = class_hashes
+            .iter()
+            .map(|(contract_address, class_hash)| (*contract_address, *class_hash))
+            .collect();
+        let commitment = calculate_state_diff_commitment(&state_diff, StateDiffVersion::V0);
+        state_diff.deployed_contracts.clear();
+        if commitment != expectation.state_diff_commitment {
+            return Err(StateDiffVerificationError::WrongCommitment {
+                block_number: expectation.block_number,
+                expected: expectation.state_diff_commitment,
+                actual: commitment,
+            });
+        }
+        Ok(ReceivedStateDiff { block_number: expectation.block_number, state_diff, class_hashes })
+    }
+
+    fn complete_empty_state_diffs(
+        &mut self,
+    ) -> Result<Vec<ReceivedStateDiff>, StateDiffVerificationError> {
+        let mut completed_state_diffs = vec![];
+        while self.expectations.front().is_some_and(|expectation| {
+            expectation.state_diff_length == 0 && self.current_length == 0
+        }) {
+            completed_state_diffs.push(self.complete_current_state_diff()?);
+        }
+        Ok(completed_state_diffs)
+    }
+}
+
+// TODO: derive default in ThinStateDiff.
+fn empty_state_diff() -> ThinStateDiff {
+    ThinStateDiff::from(StateDiff::default())
+}
+
+// TODO(shahak) Consider splitting to InboundSessionError and OutboundSessionError.
+#[derive(thiserror::Error, Debug)]
+pub enum SessionError {
+    #[error(transparent)]
+    StreamedData(#[from] streamed_data::behaviour::SessionError),
+    // This error can only appear in outbound sessions
+    #[error(transparent)]
+    ProtobufConversionError(#[from] ProtobufConversionError),
+    // This error can only appear in outbound sessions
+    #[error(transparent)]
+    StateDiffVerification(#[from] StateDiffVerificationError),
+    #[error("Session closed unexpectedly")]
+    SessionClosedUnexpectedly,
+    // This error can only appear in outbound sessions
+    #[error("Received a message after Fin")]
+    ReceivedMessageAfterFin,
+}
+
+#[derive(Debug)]
+// TODO(shahak): Internalize this when we have a mixed behaviour.
+pub enum Event {
+    NewInboundQuery {
+        query: InternalQuery,
+        inbound_session_id: streamed_data::InboundSessionId,
+    },
+    /// State diffs that were fully received and verified, in the order of their blocks.
+    ReceivedStateDiffs {
+        state_diffs: Vec<ReceivedStateDiff>,
+        outbound_session_id: streamed_data::OutboundSessionId,
+    },
+    SessionFailed {
+        session_id: SessionId,
+        session_error: SessionError,
+    },
+    QueryConversionError(ProtobufConversionError),
+    SessionFinishedSuccessfully {
+        session_id: SessionId,
+    },
+}
+
+impl TryFrom<protobuf::StateDiffsRequest> for InternalQuery {
+    type Error = ProtobufConversionError;
+    fn try_from(value: protobuf::StateDiffsRequest) -> Result<Self, Self::Error> {
+        value.iteration.ok_or(ProtobufConversionError::MissingField)?.try_into()
+    }
+}
+
+impl From<InternalQuery> for protobuf::StateDiffsRequest {
+    fn from(value: InternalQuery) -> Self {
+        protobuf::StateDiffsRequest { iteration: Some(value.into()) }
+    }
+}
+
+impl From<StateDiffChunk> for protobuf::state_diffs_response::StateDiffMessage {
+    fn from(value: StateDiffChunk) -> Self {
+        match value {
+            StateDiffChunk::ContractDiff(contract_diff) => {
+                Self::ContractDiff(protobuf::ContractDiff {
+                    address: Some(contract_diff.contract_address.into()),
+                    nonce: contract_diff.nonce.map(|nonce| nonce.0.into()),
+                    class_hash: contract_diff.class_hash.map(|class_hash| class_hash.0.into()),
+                    values: contract_diff
+                        .storage_diffs
+                        .into_iter()
+                        .map(|(key, value)| protobuf::ContractStoredValue {
+                            key: Some((*key.0.key()).into()),
+                            value: Some(value.into()),
+                        })
+                        .collect(),
+                })
+            }
+            StateDiffChunk::DeclaredClass(declared_class) => {
+                Self::DeclaredClass(protobuf::DeclaredClass {
+                    class_hash: Some(declared_class.class_hash.0.into()),
+                    compiled_class_hash: declared_class
+                        .compiled_class_hash
+                        .map(|compiled_class_hash| compiled_class_hash.0.into()),
+                })
+            }
+        }
+    }
+}
+
+impl TryFrom<protobuf::ContractDiff> for ContractDiff {
+    type Error = ProtobufConversionError;
+    fn try_from(value: protobuf::ContractDiff) -> Result<Self, Self::Error> {
+        Ok(Self {
+            contract_address: value
+                .address
+                .ok_or(ProtobufConversionError::MissingField)?
+                .try_into()?,
+            class_hash: value.class_hash.map(StarkHash::try_from).transpose()?.map(ClassHash),
+            nonce: value.nonce.map(StarkFelt::try_from).transpose()?.map(Nonce),
+            storage_diffs: value
+                .values
+                .into_iter()
+                .map(|stored_value| {
+                    let key = StarkHash::try_from(
+                        stored_value.key.ok_or(ProtobufConversionError::MissingField)?,
+                    )?;
+                    let key = PatriciaKey::try_from(key)
+                        .map_err(|_| ProtobufConversionError::OutOfRangeValue)?;
+                    let value = StarkFelt::try_from(
+                        stored_value.value.ok_or(ProtobufConversionError::MissingField)?,
+                    )?;
+                    Ok((StorageKey(key), value))
+                })
+                .collect::<Result<_, ProtobufConversionError>>()?,
+        })
+    }
+}
+
+impl TryFrom<protobuf::DeclaredClass> for DeclaredClass {
+    type Error = ProtobufConversionError;
+    fn try_from(value: protobuf::DeclaredClass) -> Result<Self, Self::Error> {
+        Ok(Self {
+            class_hash: ClassHash(
+                value.class_hash.ok_or(ProtobufConversionError::MissingField)?.try_into()?,
+            ),
+            compiled_class_hash: value
+                .compiled_class_hash
+                .map(StarkHash::try_from)
+                .transpose()?
+                .map(CompiledClassHash),
+        })
+    }
+}
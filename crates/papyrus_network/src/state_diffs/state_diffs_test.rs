@@ -0,0 +1,310 @@
+use assert_matches::assert_matches;
+use papyrus_common::state_diff_commitment::{calculate_state_diff_commitment, StateDiffVersion};
+use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
+use starknet_api::core::{CompiledClassHash, ContractAddress, GlobalRoot, Nonce};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::{StorageKey, ThinStateDiff};
+use starknet_api::{class_hash, contract_address, stark_felt};
+
+use super::{
+    split_state_diff,
+    state_diff_length,
+    ContractDiff,
+    DeclaredClass,
+    ReceivedStateDiff,
+    StateDiffAssembler,
+    StateDiffChunk,
+    StateDiffExpectation,
+    StateDiffVerificationError,
+};
+use crate::messages::protobuf;
+
+fn storage_diff(num_values: u64) -> Vec<(StorageKey, StarkFelt)> {
+    (0..num_values)
+        .map(|i| (StorageKey(StarkFelt::from(i).try_into().unwrap()), StarkFelt::from(i + 1)))
+        .collect()
+}
+
+// A state diff whose entries are ordered the way they're assembled, so that it's equal to the
+// state diff assembled from its chunks.
+fn state_diff() -> ThinStateDiff {
+    ThinStateDiff {
+        deployed_contracts: [
+            (contract_address!("0x1"), class_hash!("0x10")),
+            (contract_address!("0x2"), class_hash!("0x20")),
+        ]
+        .into(),
+        storage_diffs: [
+            (contract_address!("0x2"), storage_diff(5).into_iter().collect()),
+            (contract_address!("0x3"), storage_diff(2).into_iter().collect()),
+        ]
+        .into(),
+        declared_classes: [(class_hash!("0x30"), CompiledClassHash(stark_felt!("0x31")))].into(),
+        deprecated_declared_classes: vec![class_hash!("0x40")],
+        nonces: [
+            (contract_address!("0x1"), Nonce(stark_felt!("0x1"))),
+            (contract_address!("0x3"), Nonce(stark_felt!("0x7"))),
+        ]
+        .into(),
+        replaced_classes: Default::default(),
+    }
+}
+
+fn expectation(block_number: u64, state_diff: &ThinStateDiff) -> StateDiffExpectation {
+    StateDiffExpectation {
+        block_number: BlockNumber(block_number),
+        state_diff_commitment: calculate_state_diff_commitment(state_diff, StateDiffVersion::V0),
+        state_diff_length: state_diff_length(state_diff),
+    }
+}
+
+fn empty_state_diff() -> ThinStateDiff {
+    super::empty_state_diff()
+}
+
+// The state diff as it's assembled, with the deployed contracts and the replaced classes kept
+// aside.
+fn received_state_diff(block_number: u64, mut state_diff: ThinStateDiff) -> ReceivedStateDiff {
+    let class_hashes = state_diff
+        .deployed_contracts
+        .drain(..)
+        .chain(state_diff.replaced_classes.drain(..))
+        .collect();
+    ReceivedStateDiff { block_number: BlockNumber(block_number), state_diff, class_hashes }
+}
+
+#[test]
+fn split_state_diff_into_chunks() {
+    let state_diff = state_diff();
+    let chunks = split_state_diff(state_diff.clone(), 3);
+
+    let expected_chunks = vec![
+        StateDiffChunk::ContractDiff(ContractDiff {
+            contract_address: contract_address!("0x1"),
+            class_hash: Some(class_hash!("0x10")),
+            nonce: Some(Nonce(stark_felt!("0x1"))),
+            storage_diffs: vec![],
+        }),
+        StateDiffChunk::ContractDiff(ContractDiff {
+            contract_address: contract_address!("0x2"),
+            class_hash: Some(class_hash!("0x20")),
+            nonce: None,
+            storage_diffs: storage_diff(5)[..3].to_vec(),
+        }),
+        StateDiffChunk::ContractDiff(ContractDiff {
+            contract_address: contract_address!("0x2"),
+            storage_diffs: storage_diff(5)[3..].to_vec(),
+            ..Default::default()
+        }),
+        StateDiffChunk::ContractDiff(ContractDiff {
+            contract_address: contract_address!("0x3"),
+            class_hash: None,
+            nonce: Some(Nonce(stark_felt!("0x7"))),
+            storage_diffs: storage_diff(2),
+        }),
+        StateDiffChunk::DeclaredClass(DeclaredClass {
+            class_hash: class_hash!("0x30"),
+            compiled_class_hash: Some(CompiledClassHash(stark_felt!("0x31"))),
+        }),
+        StateDiffChunk::DeclaredClass(DeclaredClass {
+            class_hash: class_hash!("0x40"),
+            compiled_class_hash: None,
+        }),
+    ];
+    assert_eq!(chunks, expected_chunks);
+    assert_eq!(
+        chunks.iter().map(StateDiffChunk::num_entries).sum::<usize>(),
+        state_diff_length(&state_diff)
+    );
+}
+
+#[test]
+fn assemble_state_diffs() {
+    let state_diff = state_diff();
+    let mut assembler = StateDiffAssembler::new([
+        expectation(0, &empty_state_diff()),
+        expectation(1, &state_diff),
+        expectation(2, &empty_state_diff()),
+        expectation(3, &state_diff),
+    ]);
+
+    let mut chunks = split_state_diff(state_diff.clone(), 3);
+    let last_chunk = chunks.pop().unwrap();
+    // The empty state diff of block 0 is completed by the first chunk of block 1.
+    let mut assembled_state_diffs = vec![];
+    for chunk in chunks.clone() {
+        assembled_state_diffs.extend(assembler.add_chunk(chunk).unwrap());
+    }
+    assert_eq!(assembled_state_diffs, vec![received_state_diff(0, empty_state_diff())]);
+
+    // The last chunk completes block 1, and the empty state diff of block 2 that follows it.
+    assert_eq!(
+        assembler.add_chunk(last_chunk.clone()).unwrap(),
+        vec![
+            received_state_diff(1, state_diff.clone()),
+            received_state_diff(2, empty_state_diff())
+        ]
+    );
+
+    for chunk in chunks {
+        assert_eq!(assembler.add_chunk(chunk).unwrap(), vec![]);
+    }
+    assert_eq!(
+        assembler.add_chunk(last_chunk.clone()).unwrap(),
+        vec![received_state_diff(3, state_diff)]
+    );
+    assert_eq!(assembler.finish().unwrap(), vec![]);
+    assert_eq!(assembler.add_chunk(last_chunk), Err(StateDiffVerificationError::UnexpectedChunk));
+}
+
+#[test]
+fn resolve_received_state_diff() {
+    let state_diff = state_diff();
+    // Contract 0x1 was deployed before the block, so its class was replaced in the block.
+    let resolved_state_diff = received_state_diff(0, state_diff.clone())
+        .resolve(|contract_address| Ok::<_, ()>(contract_address == contract_address!("0x1")))
+        .unwrap();
+    assert_eq!(
+        resolved_state_diff,
+        ThinStateDiff {
+            deployed_contracts: [(contract_address!("0x2"), class_hash!("0x20"))].into(),
+            replaced_classes: [(contract_address!("0x1"), class_hash!("0x10"))].into(),
+            ..state_diff
+        }
+    );
+}
+
+#[test]
+fn assembled_state_diff_with_wrong_commitment() {
+    let state_diff = state_diff();
+    let mut assembler = StateDiffAssembler::new([StateDiffExpectation {
+        state_diff_commitment: GlobalRoot(stark_felt!("0x1234")),
+        ..expectation(0, &state_diff)
+    }]);
+
+    let mut chunks = split_state_diff(state_diff.clone(), 3);
+    let last_chunk = chunks.pop().unwrap();
+    for chunk in chunks {
+        assert_eq!(assembler.add_chunk(chunk).unwrap(), vec![]);
+    }
+    assert_matches!(
+        assembler.add_chunk(last_chunk),
+        Err(StateDiffVerificationError::WrongCommitment { block_number: BlockNumber(0), expected, .. })
+        if expected == GlobalRoot(stark_felt!("0x1234"))
+    );
+}
+
+#[test]
+fn state_diff_longer_than_expected() {
+    let state_diff = state_diff();
+    // The first three chunks hold 8 entries, and the fourth holds 3 more.
+    let expected_length = 10;
+    let mut assembler = StateDiffAssembler::new([StateDiffExpectation {
+        state_diff_length: expected_length,
+        ..expectation(0, &state_diff)
+    }]);
+
+    let mut chunks = split_state_diff(state_diff, 3).into_iter();
+    for chunk in chunks.by_ref().take(3) {
+        assert_eq!(assembler.add_chunk(chunk).unwrap(), vec![]);
+    }
+    assert_eq!(
+        assembler.add_chunk(chunks.next().unwrap()),
+        Err(StateDiffVerificationError::StateDiffTooLong {
+            block_number: BlockNumber(0),
+            expected_length
+        })
+    );
+}
+
+#[test]
+fn chunk_conflicting_with_previous_chunk() {
+    let state_diff = state_diff();
+    let mut assembler = StateDiffAssembler::new([expectation(0, &state_diff)]);
+
+    let chunk = split_state_diff(state_diff, 3).remove(1);
+    assert_eq!(assembler.add_chunk(chunk.clone()).unwrap(), vec![]);
+    assert_eq!(
+        assembler.add_chunk(chunk),
+        Err(StateDiffVerificationError::ConflictingChunk { block_number: BlockNumber(0) })
+    );
+}
+
+#[test]
+fn empty_chunk() {
+    let mut assembler = StateDiffAssembler::new([expectation(0, &state_diff())]);
+    assert_eq!(
+        assembler.add_chunk(StateDiffChunk::ContractDiff(ContractDiff::default())),
+        Err(StateDiffVerificationError::EmptyChunk { block_number: BlockNumber(0) })
+    );
+}
+
+#[test]
+fn finish_in_the_middle_of_a_state_diff() {
+    let state_diff = state_diff();
+    let mut assembler = StateDiffAssembler::new([expectation(0, &state_diff)]);
+
+    let chunk = split_state_diff(state_diff, 3).remove(0);
+    assert_eq!(assembler.add_chunk(chunk).unwrap(), vec![]);
+    assert_eq!(
+        assembler.finish(),
+        Err(StateDiffVerificationError::IncompleteStateDiff { block_number: BlockNumber(0) })
+    );
+}
+
+#[test]
+fn finish_returns_remaining_empty_state_diffs() {
+    let mut assembler = StateDiffAssembler::new([
+        expectation(0, &empty_state_diff()),
+        expectation(1, &empty_state_diff()),
+        expectation(2, &state_diff()),
+    ]);
+    assert_eq!(
+        assembler.finish().unwrap(),
+        vec![
+            received_state_diff(0, empty_state_diff()),
+            received_state_diff(1, empty_state_diff())
+        ]
+    );
+}
+
+#[test]
+fn chunk_protobuf_round_trip() {
+    for chunk in split_state_diff(state_diff(), 3) {
+        let protobuf_chunk = protobuf::state_diffs_response::StateDiffMessage::from(chunk.clone());
+        let converted_chunk = match protobuf_chunk {
+            protobuf::state_diffs_response::StateDiffMessage::ContractDiff(contract_diff) => {
+                StateDiffChunk::ContractDiff(contract_diff.try_into().unwrap())
+            }
+            protobuf::state_diffs_response::StateDiffMessage::DeclaredClass(declared_class) => {
+                StateDiffChunk::DeclaredClass(declared_class.try_into().unwrap())
+            }
+            protobuf::state_diffs_response::StateDiffMessage::Fin(_) => {
+                panic!("A chunk shouldn't be converted to Fin.")
+            }
+        };
+        assert_eq!(converted_chunk, chunk);
+    }
+}
+
+#[test]
+fn contract_diff_with_out_of_range_storage_key() {
+    let contract_diff = protobuf::ContractDiff {
+        address: Some(ContractAddress::default().into()),
+        values: vec![protobuf::ContractStoredValue {
+            key: Some(
+                stark_felt!("0x800000000000000000000000000000000000000000000000000000000000000")
+                    .into(),
+            ),
+            value: Some(stark_felt!("0x1").into()),
+        }],
+        ..Default::default()
+    };
+    assert!(ContractDiff::try_from(contract_diff).is_err());
+}
+
+#[test]
+fn declared_class_without_class_hash() {
+    assert!(DeclaredClass::try_from(protobuf::DeclaredClass::default()).is_err());
+}
@@ -27,6 +27,13 @@ use libp2p::swarm::{
     SubstreamProtocol,
 };
 use libp2p::PeerId;
+use papyrus_common::metrics::{
+    PAPYRUS_P2P_RECEIVED_BYTES,
+    PAPYRUS_P2P_RECEIVED_MESSAGES,
+    PAPYRUS_P2P_SENT_BYTES,
+    PAPYRUS_P2P_SENT_MESSAGES,
+};
+use prost::Message;
 use tracing::debug;
 
 use self::inbound_session::InboundSession;
@@ -332,13 +339,17 @@ impl<Query: QueryBound, Data: DataBound> ConnectionHandler for Handler<Query, Da
                 if self.dropped_outbound_sessions_non_negotiated.remove(&outbound_session_id) {
                     return;
                 }
+                let protocol_name = self.config.protocol_name.clone();
                 self.id_to_outbound_session.insert(
                     outbound_session_id,
                     stream! {
                         loop {
                             let result_opt = read_message::<Data, _>(&mut read_stream).await;
                             let result = match result_opt {
-                                Ok(Some(data)) => Ok(data),
+                                Ok(Some(data)) => {
+                                    record_received_data(&protocol_name, &data);
+                                    Ok(data)
+                                }
                                 Ok(None) => break,
                                 Err(error) => Err(error),
                             };
@@ -365,8 +376,10 @@ impl<Query: QueryBound, Data: DataBound> ConnectionHandler for Handler<Query, Da
                         peer_id: self.peer_id,
                     }),
                 ));
-                self.id_to_inbound_session
-                    .insert(inbound_session_id, InboundSession::new(write_stream));
+                self.id_to_inbound_session.insert(
+                    inbound_session_id,
+                    InboundSession::new(write_stream, self.config.protocol_name.clone()),
+                );
             }
             ConnectionEvent::DialUpgradeError(DialUpgradeError {
                 info: outbound_session_id,
@@ -401,3 +414,17 @@ impl<Query: QueryBound, Data: DataBound> ConnectionHandler for Handler<Query, Da
         }
     }
 }
+
+// Accounts a message received in an outbound session in the metrics of its protocol.
+fn record_received_data(protocol_name: &StreamProtocol, data: &impl Message) {
+    let protocol = protocol_name.to_string();
+    metrics::increment_counter!(PAPYRUS_P2P_RECEIVED_MESSAGES, "protocol" => protocol.clone());
+    metrics::counter!(PAPYRUS_P2P_RECEIVED_BYTES, data.encoded_len() as u64, "protocol" => protocol);
+}
+
+// Accounts a message sent in an inbound session in the metrics of its protocol.
+fn record_sent_data(protocol_name: &StreamProtocol, data: &impl Message) {
+    let protocol = protocol_name.to_string();
+    metrics::increment_counter!(PAPYRUS_P2P_SENT_MESSAGES, "protocol" => protocol.clone());
+    metrics::counter!(PAPYRUS_P2P_SENT_BYTES, data.encoded_len() as u64, "protocol" => protocol);
+}
@@ -7,16 +7,18 @@ use std::task::{ready, Context, Poll, Waker};
 use futures::future::BoxFuture;
 use futures::io::WriteHalf;
 use futures::{AsyncWriteExt, FutureExt};
-use libp2p::swarm::Stream;
+use libp2p::swarm::{Stream, StreamProtocol};
 use replace_with::replace_with_or_abort;
 
 use super::super::DataBound;
+use super::record_sent_data;
 use crate::messages::write_message;
 
 pub(super) struct InboundSession<Data: DataBound> {
     pending_messages: VecDeque<Data>,
     current_task: WriteMessageTask,
     wakers_waiting_for_new_message: Vec<Waker>,
+    protocol_name: StreamProtocol,
 }
 
 enum FinishReason {
@@ -31,11 +33,12 @@ enum WriteMessageTask {
 }
 
 impl<Data: DataBound> InboundSession<Data> {
-    pub fn new(write_stream: WriteHalf<Stream>) -> Self {
+    pub fn new(write_stream: WriteHalf<Stream>, protocol_name: StreamProtocol) -> Self {
         Self {
             pending_messages: Default::default(),
             current_task: WriteMessageTask::Waiting(write_stream),
             wakers_waiting_for_new_message: Default::default(),
+            protocol_name,
         }
     }
 
@@ -62,6 +65,7 @@ impl<Data: DataBound> InboundSession<Data> {
 
     fn handle_waiting(&mut self, cx: &mut Context<'_>) -> Poll<()> {
         if let Some(data) = self.pending_messages.pop_front() {
+            record_sent_data(&self.protocol_name, &data);
             replace_with_or_abort(&mut self.current_task, |current_task| {
                 let WriteMessageTask::Waiting(mut write_stream) = current_task else {
                     panic!("Called handle_waiting while not waiting.");
@@ -0,0 +1,202 @@
+//! An end-to-end test of the node against Sepolia. Syncs the first blocks of the chain from the
+//! feeder gateway, serves them over JSON-RPC and compares selected responses to the ones recorded
+//! in `resources/sepolia_integration_test_expectations.json`, so that changes in the formats of
+//! Starknet are caught before they reach the users of the node.
+//!
+//! The test depends on the network, so it only runs on demand, from the root of the repository:
+//! `cargo run -r -p papyrus_node --bin sepolia_integration_test`. With `RECORD_EXPECTATIONS=1`, the
+//! responses are recorded as the new expectations instead of being compared to them.
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use std::{env, fs};
+
+use jsonrpsee::core::client::ClientT;
+use jsonrpsee::core::params::ArrayParams;
+use jsonrpsee::http_client::HttpClientBuilder;
+use papyrus_common::deferred_classes::DeferredClassRequests;
+use papyrus_common::disk_space::DiskSpaceStatus;
+use papyrus_common::pending_classes::PendingClasses;
+use papyrus_common::recent_state::RecentStateCache;
+use papyrus_common::rpc_load::RpcLoad;
+use papyrus_common::sync_notifications::SyncNotifier;
+use papyrus_node::config::NodeConfig;
+use papyrus_node::version::VERSION_FULL;
+use papyrus_rpc::{run_server, RpcSharedState};
+use papyrus_storage::open_storage;
+use papyrus_storage::state::StateStorageReader;
+use papyrus_sync::sources::base_layer::EthereumBaseLayerSource;
+use papyrus_sync::sources::central::CentralSource;
+use papyrus_sync::sources::pending::PendingSource;
+use papyrus_sync::StateSync;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use starknet_api::block::BlockNumber;
+use tokio::sync::RwLock;
+
+// The number of blocks, from the genesis, that are synced and queried.
+const SYNCED_BLOCKS: u64 = 10;
+const SYNC_TIMEOUT: Duration = Duration::from_secs(600);
+const SYNC_POLL_INTERVAL: Duration = Duration::from_secs(1);
+const EXPECTATIONS_PATH: &str =
+    concat!(env!("CARGO_MANIFEST_DIR"), "/resources/sepolia_integration_test_expectations.json");
+const RECORD_EXPECTATIONS_ENV_VAR: &str = "RECORD_EXPECTATIONS";
+
+// A JSON-RPC request and its recorded result.
+#[derive(Debug, Serialize, Deserialize)]
+struct Expectation {
+    method: String,
+    params: Vec<Value>,
+    // None until the result is recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+}
+
+#[tokio::main]
+async fn main() {
+    let mut path = env::temp_dir();
+    path.push("sepolia_integration_test_data");
+    let _ = fs::remove_dir_all(path.clone());
+    fs::create_dir_all(path.clone()).expect("Should make a temporary data directory");
+    let config = NodeConfig::load_and_process(vec![
+        "papyrus_node".to_owned(),
+        "--config_file=config/presets/sepolia_testnet.json".to_owned(),
+        "--base_layer.node_url=https://sepolia.infura.io/v3/1234".to_owned(),
+        // The base layer isn't needed for the synced blocks, so it's never polled.
+        "--sync.base_layer_propagation_sleep_duration=86400".to_owned(),
+        "--rpc.server_address=127.0.0.1:0".to_owned(),
+        format!("--storage.db_config.path_prefix={}", path.display()),
+    ])
+    .expect("Load config");
+    let (storage_reader, storage_writer) =
+        open_storage(config.storage.clone()).expect("Open storage");
+
+    let shared_highest_block = Arc::new(RwLock::new(None));
+    let pending_data = Arc::new(RwLock::new(Default::default()));
+    let memory_budget = config.memory_budget.budget();
+    let pending_classes = Arc::new(RwLock::new(PendingClasses::new(memory_budget.pending_data)));
+    let rpc_load = Arc::new(RpcLoad::default());
+    let sync_notifier = Arc::new(SyncNotifier::default());
+    let recent_state = Arc::new(RecentStateCache::with_max_bytes(
+        config.rpc.recent_state_cache_blocks,
+        memory_budget.recent_state,
+    ));
+    let deferred_class_requests = Arc::new(DeferredClassRequests::default());
+
+    let central_source = CentralSource::new(
+        config.central.clone(),
+        VERSION_FULL,
+        storage_reader.clone(),
+        Arc::default(),
+    )
+    .expect("Create central source");
+    let pending_source =
+        PendingSource::new(config.central.clone(), VERSION_FULL).expect("Create pending source");
+    let base_layer_source =
+        EthereumBaseLayerSource::new(config.base_layer).expect("Create base layer source");
+    let mut sync = StateSync::new(
+        config.sync.expect("Sync should be enabled"),
+        shared_highest_block.clone(),
+        pending_data.clone(),
+        pending_classes.clone(),
+        central_source,
+        pending_source,
+        base_layer_source,
+        rpc_load.clone(),
+        sync_notifier.clone(),
+        Arc::new(DiskSpaceStatus::default()),
+        recent_state.clone(),
+        deferred_class_requests.clone(),
+        Arc::default(),
+        storage_reader.clone(),
+        storage_writer,
+    );
+    let sync_handle = tokio::spawn(async move { sync.run().await });
+
+    // Wait for the state of the queried blocks, which is synced after their headers and bodies.
+    let sync_started_at = Instant::now();
+    loop {
+        let state_marker = storage_reader
+            .begin_ro_txn()
+            .expect("Begin txn")
+            .get_state_marker()
+            .expect("Get state marker");
+        if state_marker >= BlockNumber(SYNCED_BLOCKS) {
+            break;
+        }
+        assert!(!sync_handle.is_finished(), "Sync stopped at block {state_marker}.");
+        assert!(
+            sync_started_at.elapsed() < SYNC_TIMEOUT,
+            "Sync didn't reach block {SYNCED_BLOCKS} in {SYNC_TIMEOUT:?}, it's at block \
+             {state_marker}."
+        );
+        tokio::time::sleep(SYNC_POLL_INTERVAL).await;
+    }
+
+    let (server_address, _server_handle) = run_server(
+        &config.rpc,
+        RpcSharedState {
+            shared_highest_block,
+            pending_data,
+            pending_classes,
+            rpc_load,
+            sync_notifier,
+            recent_state,
+            deferred_class_requests,
+        },
+        storage_reader,
+        VERSION_FULL,
+    )
+    .await
+    .expect("Run JSON-RPC server");
+    let client = HttpClientBuilder::default()
+        .build(format!("http://{server_address}/rpc/v0_7"))
+        .expect("Create JSON-RPC client");
+
+    let mut expectations: Vec<Expectation> =
+        serde_json::from_str(&fs::read_to_string(EXPECTATIONS_PATH).expect("Read expectations"))
+            .expect("Parse expectations");
+    let record = env::var(RECORD_EXPECTATIONS_ENV_VAR).is_ok_and(|value| value == "1");
+    let mut mismatches = Vec::new();
+    for expectation in &mut expectations {
+        let mut params = ArrayParams::new();
+        for param in &expectation.params {
+            params.insert(param).expect("Serialize param");
+        }
+        let result: Value = client
+            .request(&expectation.method, params)
+            .await
+            .unwrap_or_else(|err| panic!("{} failed: {err}", expectation.method));
+        if record {
+            expectation.result = Some(result);
+            continue;
+        }
+        match &expectation.result {
+            Some(expected) if *expected == result => {}
+            Some(expected) => mismatches.push(format!(
+                "{} with params {:?}:\nexpected: {expected}\nactual: {result}",
+                expectation.method, expectation.params
+            )),
+            None => mismatches.push(format!(
+                "{} with params {:?} has no recorded result, run with \
+                 {RECORD_EXPECTATIONS_ENV_VAR}=1 to record it.",
+                expectation.method, expectation.params
+            )),
+        }
+    }
+    sync_handle.abort();
+
+    if record {
+        fs::write(
+            EXPECTATIONS_PATH,
+            serde_json::to_string_pretty(&expectations).expect("Serialize expectations") + "\n",
+        )
+        .expect("Write expectations");
+        println!("Recorded {} expectations in {EXPECTATIONS_PATH}.", expectations.len());
+        return;
+    }
+    assert!(
+        mismatches.is_empty(),
+        "Responses don't match the expectations:\n{}",
+        mismatches.join("\n")
+    );
+}
@@ -21,6 +21,7 @@ use papyrus_network::NetworkConfig;
 use papyrus_rpc::RpcConfig;
 use papyrus_storage::db::DbConfig;
 use papyrus_storage::StorageConfig;
+use papyrus_sync::genesis::GenesisConfig;
 use papyrus_sync::sources::central::CentralSourceConfig;
 use papyrus_sync::SyncConfig;
 use serde::{Deserialize, Serialize};
@@ -29,7 +30,15 @@ use starknet_api::core::ChainId;
 use starknet_client::RetryConfig;
 use validator::Validate;
 
+use crate::disk_space_monitor::DiskSpaceMonitorConfig;
+use crate::event_stream::EventStreamConfig;
+use crate::integrity_check::IntegrityCheckConfig;
+use crate::lifecycle::ShutdownConfig;
+use crate::memory_budget::MemoryBudgetConfig;
+use crate::runtime::RuntimeConfig;
+use crate::sync_supervisor::SyncSupervisorConfig;
 use crate::version::VERSION_FULL;
+use crate::webhook_notifier::WebhookNotifierConfig;
 
 // The path of the default configuration file, provided as part of the crate.
 pub const DEFAULT_CONFIG_PATH: &str = "config/default_config.json";
@@ -46,7 +55,21 @@ pub struct NodeConfig {
     pub storage: StorageConfig,
     /// None if the syncing should be disabled.
     pub sync: Option<SyncConfig>,
+    /// None if the chain doesn't start from an imported state.
+    pub genesis: Option<GenesisConfig>,
+    pub sync_supervisor: SyncSupervisorConfig,
     pub network: Option<NetworkConfig>,
+    #[validate]
+    pub runtime: RuntimeConfig,
+    pub disk_space_monitor: DiskSpaceMonitorConfig,
+    pub integrity_check: IntegrityCheckConfig,
+    #[validate]
+    pub memory_budget: MemoryBudgetConfig,
+    pub shutdown: ShutdownConfig,
+    /// None if the chain shouldn't be streamed to a message broker.
+    pub event_stream: Option<EventStreamConfig>,
+    /// None if the chain progress shouldn't be POSTed to webhooks.
+    pub webhook_notifier: Option<WebhookNotifierConfig>,
 }
 
 // Default configuration values.
@@ -59,7 +82,16 @@ impl Default for NodeConfig {
             monitoring_gateway: MonitoringGatewayConfig::default(),
             storage: StorageConfig::default(),
             sync: Some(SyncConfig::default()),
+            genesis: None,
+            sync_supervisor: SyncSupervisorConfig::default(),
             network: None,
+            runtime: RuntimeConfig::default(),
+            disk_space_monitor: DiskSpaceMonitorConfig::default(),
+            integrity_check: IntegrityCheckConfig::default(),
+            memory_budget: MemoryBudgetConfig::default(),
+            shutdown: ShutdownConfig::default(),
+            event_stream: None,
+            webhook_notifier: None,
         }
     }
 }
@@ -73,7 +105,16 @@ impl SerializeConfig for NodeConfig {
             append_sub_config_name(self.monitoring_gateway.dump(), "monitoring_gateway"),
             append_sub_config_name(self.storage.dump(), "storage"),
             ser_optional_sub_config(&self.sync, "sync"),
+            ser_optional_sub_config(&self.genesis, "genesis"),
+            append_sub_config_name(self.sync_supervisor.dump(), "sync_supervisor"),
             ser_optional_sub_config(&self.network, "network"),
+            append_sub_config_name(self.runtime.dump(), "runtime"),
+            append_sub_config_name(self.disk_space_monitor.dump(), "disk_space_monitor"),
+            append_sub_config_name(self.integrity_check.dump(), "integrity_check"),
+            append_sub_config_name(self.memory_budget.dump(), "memory_budget"),
+            append_sub_config_name(self.shutdown.dump(), "shutdown"),
+            ser_optional_sub_config(&self.event_stream, "event_stream"),
+            ser_optional_sub_config(&self.webhook_notifier, "webhook_notifier"),
         )
         .collect()
     }
@@ -0,0 +1,167 @@
+//! The `db inspect` command of the node, which prints data from the storage of a node that isn't
+//! running, so that databases can be debugged without writing ad-hoc programs. For example:
+//! `papyrus_node db inspect block 100 -- --config_file my_config.json`.
+#[cfg(test)]
+#[path = "db_inspect_test.rs"]
+mod db_inspect_test;
+
+use std::io::Write;
+
+use clap::builder::PossibleValuesParser;
+use clap::{value_parser, Arg, ArgMatches, Command};
+use papyrus_storage::base_layer::BaseLayerStorageReader;
+use papyrus_storage::body::BodyStorageReader;
+use papyrus_storage::compiled_class::CasmStorageReader;
+use papyrus_storage::header::HeaderStorageReader;
+use papyrus_storage::state::StateStorageReader;
+use papyrus_storage::{table_names, StorageError, StorageReader};
+use serde_json::json;
+use starknet_api::block::BlockNumber;
+use starknet_api::hash::StarkHash;
+use starknet_api::transaction::TransactionHash;
+use starknet_api::StarknetApiError;
+
+use crate::offline_storage::{open_node_storage, split_offline_command_args, OfflineStorageError};
+
+/// The arguments that run the `db inspect` command, following the name of the executable.
+pub const DB_INSPECT_ARGS: [&str; 2] = ["db", "inspect"];
+
+#[derive(thiserror::Error, Debug)]
+pub enum DbInspectError {
+    #[error(transparent)]
+    OfflineStorage(#[from] OfflineStorageError),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error(transparent)]
+    StarknetApi(#[from] StarknetApiError),
+    #[error(transparent)]
+    Hex(#[from] hex::FromHexError),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("Block {0} doesn't exist.")]
+    BlockNotFound(BlockNumber),
+    #[error("Transaction {0} doesn't exist.")]
+    TransactionNotFound(TransactionHash),
+    #[error("State diff of block {0} doesn't exist.")]
+    StateDiffNotFound(BlockNumber),
+    #[error("Table {table} has no entry with key {key}.")]
+    RawEntryNotFound { table: String, key: String },
+}
+
+/// The command line interface of the `db inspect` command.
+pub fn db_inspect_command() -> Command {
+    let block_number_arg = Arg::new("block_number").required(true).value_parser(value_parser!(u64));
+    Command::new("db inspect")
+        .about(
+            "Prints data from the storage of a node that isn't running. The configuration \
+             arguments of the node follow a `--` separator.",
+        )
+        .subcommand_required(true)
+        .subcommand(Command::new("markers").about("Prints the storage markers."))
+        .subcommand(Command::new("tables").about("Prints the statistics of the storage tables."))
+        .subcommand(
+            Command::new("block")
+                .about("Prints the header, signature and body of a block.")
+                .arg(block_number_arg.clone()),
+        )
+        .subcommand(
+            Command::new("transaction")
+                .about("Prints a transaction and its output.")
+                .arg(Arg::new("transaction_hash").required(true)),
+        )
+        .subcommand(
+            Command::new("state_diff")
+                .about("Prints the state diff of a block.")
+                .arg(block_number_arg),
+        )
+        .subcommand(
+            Command::new("raw")
+                .about("Prints the serialized value of a table entry, in hex.")
+                .arg(
+                    Arg::new("table")
+                        .required(true)
+                        .value_parser(PossibleValuesParser::new(table_names().iter().copied())),
+                )
+                .arg(Arg::new("key").required(true).help("The serialized key, in hex.")),
+        )
+}
+
+/// Runs the `db inspect` command given all the command line arguments of the node executable.
+pub fn run_db_inspect(args: Vec<String>) -> Result<(), DbInspectError> {
+    let (command_args, node_args) = split_offline_command_args(&args, &DB_INSPECT_ARGS);
+    let matches = db_inspect_command().get_matches_from(command_args);
+    let (storage_reader, _storage_writer) = open_node_storage(node_args)?;
+    inspect(&storage_reader, &matches, &mut std::io::stdout())
+}
+
+// Prints the data that the subcommand asks for as JSON.
+fn inspect(
+    storage_reader: &StorageReader,
+    matches: &ArgMatches,
+    out: &mut impl Write,
+) -> Result<(), DbInspectError> {
+    let txn = storage_reader.begin_ro_txn()?;
+    let output = match matches.subcommand() {
+        Some(("markers", _)) => json!({
+            "header": txn.get_header_marker()?,
+            "body": txn.get_body_marker()?,
+            "state": txn.get_state_marker()?,
+            "compiled_class": txn.get_compiled_class_marker()?,
+            "base_layer": txn.get_base_layer_block_marker()?,
+        }),
+        Some(("tables", _)) => serde_json::to_value(storage_reader.db_tables_stats()?)?,
+        Some(("block", args)) => {
+            let block_number = get_block_number(args);
+            let header = txn
+                .get_block_header(block_number)?
+                .ok_or(DbInspectError::BlockNotFound(block_number))?;
+            json!({
+                "header": header,
+                "signature": txn.get_block_signature(block_number)?,
+                "transaction_hashes": txn.get_block_transaction_hashes(block_number)?,
+                "transactions": txn.get_block_transactions(block_number)?,
+                "transaction_outputs": txn.get_block_transaction_outputs(block_number)?,
+            })
+        }
+        Some(("transaction", args)) => {
+            let transaction_hash = TransactionHash(StarkHash::try_from(
+                args.get_one::<String>("transaction_hash").expect("Argument is required.").as_str(),
+            )?);
+            let transaction_index = txn
+                .get_transaction_idx_by_hash(&transaction_hash)?
+                .ok_or(DbInspectError::TransactionNotFound(transaction_hash))?;
+            json!({
+                "transaction_index": transaction_index,
+                "transaction": txn.get_transaction(transaction_index)?,
+                "transaction_output": txn.get_transaction_output(transaction_index)?,
+            })
+        }
+        Some(("state_diff", args)) => {
+            let block_number = get_block_number(args);
+            serde_json::to_value(
+                txn.get_state_diff(block_number)?
+                    .ok_or(DbInspectError::StateDiffNotFound(block_number))?,
+            )?
+        }
+        Some(("raw", args)) => {
+            let table = args.get_one::<String>("table").expect("Argument is required.");
+            let key = args.get_one::<String>("key").expect("Argument is required.");
+            let value = storage_reader
+                .get_raw_value(table, &hex::decode(key.trim_start_matches("0x"))?)?
+                .ok_or_else(|| DbInspectError::RawEntryNotFound {
+                    table: table.clone(),
+                    key: key.clone(),
+                })?;
+            json!(format!("0x{}", hex::encode(value)))
+        }
+        _ => unreachable!("A subcommand is required."),
+    };
+    writeln!(out, "{}", serde_json::to_string_pretty(&output)?)?;
+    Ok(())
+}
+
+fn get_block_number(args: &ArgMatches) -> BlockNumber {
+    BlockNumber(*args.get_one::<u64>("block_number").expect("Argument is required."))
+}
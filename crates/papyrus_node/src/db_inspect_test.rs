@@ -0,0 +1,56 @@
+use papyrus_storage::db::serialization::StorageSerde;
+use papyrus_storage::header::HeaderStorageWriter;
+use papyrus_storage::test_utils::get_test_storage;
+use papyrus_storage::StorageReader;
+use pretty_assertions::assert_eq;
+use serde_json::{json, Value};
+use starknet_api::block::{BlockHash, BlockHeader, BlockNumber};
+use starknet_api::hash::StarkHash;
+
+use crate::db_inspect::{db_inspect_command, inspect, DbInspectError};
+
+fn inspect_output(
+    storage_reader: &StorageReader,
+    command_args: &[&str],
+) -> Result<Value, DbInspectError> {
+    let matches = db_inspect_command()
+        .get_matches_from(std::iter::once("papyrus_node").chain(command_args.iter().copied()));
+    let mut out = Vec::new();
+    inspect(storage_reader, &matches, &mut out)?;
+    Ok(serde_json::from_slice(&out).unwrap())
+}
+
+#[test]
+fn inspect_storage() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let block_hash = BlockHash(StarkHash::from(5_u8));
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &BlockHeader { block_hash, ..Default::default() })
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    assert_eq!(
+        inspect_output(&storage_reader, &["markers"]).unwrap(),
+        json!({"header": 1, "body": 0, "state": 0, "compiled_class": 0, "base_layer": 0})
+    );
+
+    let block = inspect_output(&storage_reader, &["block", "0"]).unwrap();
+    assert_eq!(block["header"]["block_hash"], json!(block_hash));
+    assert!(inspect_output(&storage_reader, &["block", "1"]).is_err());
+
+    // The block_hash_to_number table maps the block hash to the block number.
+    let key = serialize_to_hex(&block_hash);
+    assert_eq!(
+        inspect_output(&storage_reader, &["raw", "block_hash_to_number", &key]).unwrap(),
+        json!(serialize_to_hex(&BlockNumber(0)))
+    );
+}
+
+fn serialize_to_hex(value: &impl StorageSerde) -> String {
+    let mut bytes = Vec::new();
+    value.serialize_into(&mut bytes).unwrap();
+    format!("0x{}", hex::encode(bytes))
+}
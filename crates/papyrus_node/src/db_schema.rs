@@ -0,0 +1,37 @@
+//! The `db schema` command of the node, which prints as JSON the on-disk layout of the storage that
+//! this version of the node reads and writes, so that tools that read the database directly can
+//! detect layout changes. For example: `papyrus_node db schema > schema.json`.
+#[cfg(test)]
+#[path = "db_schema_test.rs"]
+mod db_schema_test;
+
+use std::io::Write;
+
+use clap::Command;
+use papyrus_storage::schema::storage_schema;
+
+use crate::offline_storage::split_offline_command_args;
+
+/// The arguments that run the `db schema` command, following the name of the executable.
+pub const DB_SCHEMA_ARGS: [&str; 2] = ["db", "schema"];
+
+/// The command line interface of the `db schema` command.
+pub fn db_schema_command() -> Command {
+    Command::new("db schema").about(
+        "Prints the on-disk layout of the storage as JSON: its versions and the key and value \
+         types of every table.",
+    )
+}
+
+/// Runs the `db schema` command given all the command line arguments of the node executable.
+pub fn run_db_schema(args: Vec<String>) -> std::io::Result<()> {
+    let (command_args, _node_args) = split_offline_command_args(&args, &DB_SCHEMA_ARGS);
+    db_schema_command().get_matches_from(command_args);
+    print_schema(&mut std::io::stdout())
+}
+
+fn print_schema(out: &mut impl Write) -> std::io::Result<()> {
+    let schema = serde_json::to_string_pretty(&storage_schema())
+        .expect("The storage schema should be serializable.");
+    writeln!(out, "{schema}")
+}
@@ -0,0 +1,18 @@
+use papyrus_storage::table_names;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+
+use crate::db_schema::print_schema;
+
+#[test]
+fn prints_every_table() {
+    let mut out = Vec::new();
+    print_schema(&mut out).unwrap();
+    let schema: Value = serde_json::from_slice(&out).unwrap();
+
+    let tables = schema["tables"].as_array().unwrap();
+    assert_eq!(tables.len(), table_names().len());
+    let headers = tables.iter().find(|table| table["name"] == "headers").unwrap();
+    assert_eq!(headers["key"], "starknet_api::block::BlockNumber");
+    assert_eq!(headers["value_version"], 0);
+}
@@ -0,0 +1,103 @@
+//! Monitoring of the free space on the volume of the storage. The database is corrupted if the
+//! disk fills up in the middle of a write, so the sync can be paused before that happens.
+#[cfg(test)]
+#[path = "disk_space_monitor_test.rs"]
+mod disk_space_monitor_test;
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use papyrus_common::disk_space::DiskSpaceStatus;
+use papyrus_common::metrics::PAPYRUS_STORAGE_FREE_DISK_SPACE;
+use papyrus_config::converters::deserialize_seconds_to_duration;
+use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub struct DiskSpaceMonitorConfig {
+    #[serde(deserialize_with = "deserialize_seconds_to_duration")]
+    pub check_interval: Duration,
+    /// Free space, in bytes, below which a warning is logged on every check.
+    pub warning_threshold: u64,
+    /// Free space, in bytes, below which the sync stops writing to the storage. 0 means that the
+    /// sync is never paused.
+    pub pause_sync_threshold: u64,
+}
+
+impl Default for DiskSpaceMonitorConfig {
+    fn default() -> Self {
+        DiskSpaceMonitorConfig {
+            check_interval: Duration::from_secs(60),
+            warning_threshold: 1 << 34,    // 16GB
+            pause_sync_threshold: 1 << 30, // 1GB
+        }
+    }
+}
+
+impl SerializeConfig for DiskSpaceMonitorConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([
+            ser_param(
+                "check_interval",
+                &self.check_interval.as_secs(),
+                "Time in seconds between checks of the free space on the volume of the storage.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "warning_threshold",
+                &self.warning_threshold,
+                "Free disk space, in bytes, below which a warning is logged.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "pause_sync_threshold",
+                &self.pause_sync_threshold,
+                "Free disk space, in bytes, below which the sync is paused. 0 means that the sync \
+                 is never paused.",
+                ParamPrivacyInput::Public,
+            ),
+        ])
+    }
+}
+
+/// Checks the free space on the volume of the given path periodically, and updates the status that
+/// the sync reads accordingly. Never returns.
+pub async fn run_disk_space_monitor(
+    config: DiskSpaceMonitorConfig,
+    path: PathBuf,
+    status: Arc<DiskSpaceStatus>,
+) {
+    loop {
+        match fs2::available_space(&path) {
+            Ok(free_space) => update_disk_space_status(&config, free_space, &status),
+            Err(err) => warn!("Failed to get the free disk space of {}: {err}", path.display()),
+        }
+        tokio::time::sleep(config.check_interval).await;
+    }
+}
+
+fn update_disk_space_status(
+    config: &DiskSpaceMonitorConfig,
+    free_space: u64,
+    status: &DiskSpaceStatus,
+) {
+    metrics::gauge!(PAPYRUS_STORAGE_FREE_DISK_SPACE, free_space as f64);
+    let low = free_space < config.pause_sync_threshold;
+    if low {
+        warn!(
+            "Free disk space is {free_space} bytes, below the threshold of {} bytes. The sync is \
+             paused.",
+            config.pause_sync_threshold
+        );
+    } else if free_space < config.warning_threshold {
+        warn!(
+            "Free disk space is {free_space} bytes, below the warning threshold of {} bytes.",
+            config.warning_threshold
+        );
+    }
+    status.set_low(low);
+}
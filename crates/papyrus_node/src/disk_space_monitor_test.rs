@@ -0,0 +1,29 @@
+use papyrus_common::disk_space::DiskSpaceStatus;
+
+use crate::disk_space_monitor::{update_disk_space_status, DiskSpaceMonitorConfig};
+
+#[test]
+fn pauses_sync_below_threshold() {
+    let config = DiskSpaceMonitorConfig {
+        warning_threshold: 100,
+        pause_sync_threshold: 10,
+        ..Default::default()
+    };
+    let status = DiskSpaceStatus::default();
+
+    update_disk_space_status(&config, 50, &status);
+    assert!(!status.is_low());
+    update_disk_space_status(&config, 5, &status);
+    assert!(status.is_low());
+    // The sync resumes once space is freed.
+    update_disk_space_status(&config, 50, &status);
+    assert!(!status.is_low());
+}
+
+#[test]
+fn zero_threshold_never_pauses_sync() {
+    let config = DiskSpaceMonitorConfig { pause_sync_threshold: 0, ..Default::default() };
+    let status = DiskSpaceStatus::default();
+    update_disk_space_status(&config, 0, &status);
+    assert!(!status.is_low());
+}
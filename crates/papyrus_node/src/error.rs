@@ -0,0 +1,72 @@
+//! The errors that stop the node, classified as recoverable or fatal.
+#[cfg(test)]
+#[path = "error_test.rs"]
+mod error_test;
+
+use papyrus_config::ConfigError;
+use papyrus_storage::StorageError;
+use papyrus_sync::genesis::GenesisStateError;
+use papyrus_sync::StateSyncError;
+
+use crate::db_inspect::DbInspectError;
+use crate::event_stream::EventStreamError;
+use crate::multi_chain::MultiChainError;
+use crate::offline_storage::OfflineStorageError;
+use crate::webhook_notifier::WebhookNotifierError;
+
+#[derive(thiserror::Error, Debug)]
+pub enum NodeError {
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    #[error(transparent)]
+    DbInspect(#[from] DbInspectError),
+    #[error(transparent)]
+    EventStream(#[from] EventStreamError),
+    #[error(transparent)]
+    GenesisState(#[from] GenesisStateError),
+    #[error(transparent)]
+    MetricsRecorder(#[from] metrics_exporter_prometheus::BuildError),
+    #[error(transparent)]
+    MonitoringServer(#[from] hyper::Error),
+    #[error(transparent)]
+    MultiChain(#[from] MultiChainError),
+    #[error(transparent)]
+    OfflineStorage(#[from] OfflineStorageError),
+    // The RPC server reports its errors untyped.
+    #[error(transparent)]
+    RpcServer(anyhow::Error),
+    #[error(transparent)]
+    Runtime(#[from] std::io::Error),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error(transparent)]
+    Sync(#[from] StateSyncError),
+    #[error(transparent)]
+    TaskPanicked(#[from] tokio::task::JoinError),
+    #[error(transparent)]
+    WebhookNotifier(#[from] WebhookNotifierError),
+}
+
+impl NodeError {
+    /// Whether the failed component might succeed if started again after a while. Any other
+    /// error is fatal and requires a change of the configuration, the storage or the binary.
+    pub fn is_recoverable(&self) -> bool {
+        match self {
+            NodeError::Sync(err) => err.is_recoverable(),
+            NodeError::Storage(StorageError::InnerError(_)) => true,
+            NodeError::MonitoringServer(_) => true,
+            NodeError::Config(_)
+            | NodeError::DbInspect(_)
+            | NodeError::EventStream(_)
+            | NodeError::GenesisState(_)
+            | NodeError::MetricsRecorder(_)
+            | NodeError::MultiChain(_)
+            | NodeError::OfflineStorage(_)
+            | NodeError::RpcServer(_)
+            | NodeError::Runtime(_)
+            | NodeError::Storage(_)
+            | NodeError::TaskPanicked(_)
+            | NodeError::WebhookNotifier(_) => false,
+        }
+    }
+}
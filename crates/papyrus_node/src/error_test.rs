@@ -0,0 +1,23 @@
+use papyrus_storage::StorageError;
+use papyrus_sync::StateSyncError;
+use starknet_api::block::BlockNumber;
+
+use crate::error::NodeError;
+use crate::multi_chain::MultiChainError;
+
+#[test]
+fn sync_errors_keep_their_classification() {
+    assert!(NodeError::from(StateSyncError::NoProgress).is_recoverable());
+    assert!(
+        !NodeError::from(StateSyncError::StorageError(StorageError::MarkerMismatch {
+            expected: BlockNumber(1),
+            found: BlockNumber(0),
+        }))
+        .is_recoverable()
+    );
+}
+
+#[test]
+fn configuration_errors_are_fatal() {
+    assert!(!NodeError::from(MultiChainError::NoChains).is_recoverable());
+}
@@ -0,0 +1,573 @@
+//! Streaming of the chain to a Kafka or NATS topic, for indexers that consume the chain from a
+//! message broker instead of polling the node.
+//!
+//! Once both the body and the state diff of a block are stored, the block is published as a JSON
+//! message with its transactions, their receipts and its state diff. When published blocks are
+//! reverted, a reorg message with the first reverted block is published before the blocks that
+//! replace them. The messages are published in order, and a message that fails to be published is
+//! retried until it succeeds, so that the consumers never miss a block. The only exception is a
+//! message that is larger than the broker accepts, which can never succeed, so it's dropped with an
+//! error. The schema of the messages is documented in the README.
+//!
+//! Kafka is reached through a Kafka REST proxy, and NATS through its text protocol over TCP,
+//! optionally upgraded to TLS.
+#[cfg(test)]
+#[path = "event_stream_test.rs"]
+mod event_stream_test;
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use papyrus_common::sync_notifications::SyncNotification;
+use papyrus_config::converters::{
+    deserialize_milliseconds_to_duration,
+    deserialize_seconds_to_duration,
+};
+use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use papyrus_storage::body::events::ThinTransactionOutput;
+use papyrus_storage::body::{BodyStorageReader, TransactionIndex};
+use papyrus_storage::db::RO;
+use papyrus_storage::header::HeaderStorageReader;
+use papyrus_storage::state::StateStorageReader;
+use papyrus_storage::{StorageError, StorageReader, StorageResult, StorageTxn};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use starknet_api::block::{BlockHash, BlockHeader, BlockNumber};
+use starknet_api::core::ChainId;
+use starknet_api::state::ThinStateDiff;
+use starknet_api::transaction::{Event, Transaction, TransactionHash, TransactionOffsetInBlock};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufStream};
+use tokio::net::TcpStream;
+use tokio::sync::broadcast;
+use tokio_native_tls::{native_tls, TlsConnector};
+use tracing::{debug, error, warn};
+
+// The number of published blocks whose hashes are kept to detect that they were reverted. Deeper
+// reorgs are published as reverting the oldest kept block.
+const PUBLISHED_BLOCKS_CAPACITY: usize = 1000;
+// The maximal time between retries of a message that fails to be published.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+// The content type of JSON records in the Kafka REST proxy API.
+const KAFKA_JSON_CONTENT_TYPE: &str = "application/vnd.kafka.json.v2+json";
+// The error the NATS server replies with to a message larger than its maximal payload.
+const NATS_MAX_PAYLOAD_VIOLATION: &str = "-ERR 'Maximum Payload Violation'";
+
+/// The broker to which the chain is streamed.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum EventStreamSink {
+    /// A Kafka cluster, reached through a Kafka REST proxy.
+    KafkaRest,
+    Nats,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct EventStreamConfig {
+    pub sink: EventStreamSink,
+    /// The URL of the Kafka REST proxy, or the host:port of the NATS server.
+    pub url: String,
+    /// The Kafka topic, or the NATS subject, to which the messages are published.
+    pub topic: String,
+    /// The delay before the first retry of a failed message, doubled on every further retry.
+    #[serde(deserialize_with = "deserialize_milliseconds_to_duration")]
+    pub retry_delay: Duration,
+    #[serde(deserialize_with = "deserialize_seconds_to_duration")]
+    pub request_timeout: Duration,
+    /// Messages larger than this, in bytes, are dropped instead of published.
+    pub max_message_size: usize,
+    /// The user and password to authenticate to the NATS server with. Unused if empty.
+    pub nats_user: String,
+    pub nats_password: String,
+    /// The token to authenticate to the NATS server with. Unused if empty.
+    pub nats_token: String,
+    /// Whether to upgrade the connection to the NATS server to TLS.
+    pub nats_tls: bool,
+}
+
+impl Default for EventStreamConfig {
+    fn default() -> Self {
+        EventStreamConfig {
+            sink: EventStreamSink::KafkaRest,
+            url: String::from("http://localhost:8082"),
+            topic: String::from("papyrus.blocks"),
+            retry_delay: Duration::from_millis(500),
+            request_timeout: Duration::from_secs(10),
+            // The default maximal message size of both Kafka and NATS.
+            max_message_size: 1 << 20,
+            nats_user: String::new(),
+            nats_password: String::new(),
+            nats_token: String::new(),
+            nats_tls: false,
+        }
+    }
+}
+
+impl SerializeConfig for EventStreamConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([
+            ser_param(
+                "sink",
+                &self.sink,
+                "The broker to which the chain is streamed: KafkaRest for Kafka through a Kafka \
+                 REST proxy, or Nats.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "url",
+                &self.url,
+                "URL of the Kafka REST proxy, or host:port of the NATS server.",
+                // The URL may contain the credentials of the proxy.
+                ParamPrivacyInput::Private,
+            ),
+            ser_param(
+                "topic",
+                &self.topic,
+                "The Kafka topic, or the NATS subject, to which the messages are published.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "retry_delay",
+                &serialize_milliseconds(self.retry_delay),
+                "Time in milliseconds before the first retry of a message that failed to be \
+                 published. The time is doubled on every further retry, up to a minute.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "request_timeout",
+                &self.request_timeout.as_secs(),
+                "Time in seconds after which a publication that wasn't acknowledged fails.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_message_size",
+                &self.max_message_size,
+                "Maximal size in bytes of a message. Larger messages can't be published, so they \
+                 are dropped with an error. Should not exceed the maximal message size of the \
+                 broker.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "nats_user",
+                &self.nats_user,
+                "The user to authenticate to the NATS server with. Unused if empty.",
+                ParamPrivacyInput::Private,
+            ),
+            ser_param(
+                "nats_password",
+                &self.nats_password,
+                "The password of the user to authenticate to the NATS server with.",
+                ParamPrivacyInput::Private,
+            ),
+            ser_param(
+                "nats_token",
+                &self.nats_token,
+                "The token to authenticate to the NATS server with. Unused if empty.",
+                ParamPrivacyInput::Private,
+            ),
+            ser_param(
+                "nats_tls",
+                &self.nats_tls,
+                "Whether to upgrade the connection to the NATS server to TLS.",
+                ParamPrivacyInput::Public,
+            ),
+        ])
+    }
+}
+
+fn serialize_milliseconds(duration: Duration) -> u64 {
+    duration.as_millis().try_into().expect("The retry delay should fit in u64 milliseconds.")
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum EventStreamError {
+    #[error("Failed creating the Kafka REST proxy client: {0}.")]
+    ClientCreation(reqwest::Error),
+    #[error("Failed creating the TLS connector of the NATS connection: {0}.")]
+    TlsConnectorCreation(native_tls::Error),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+}
+
+/// A message published to the topic, tagged by its type.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamMessage {
+    Block(Box<StreamedBlock>),
+    /// The block, and all the blocks after it, were reverted.
+    Reorg {
+        reverted_block_number: BlockNumber,
+    },
+}
+
+/// A block with all its data, as published to the topic.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct StreamedBlock {
+    pub block_number: BlockNumber,
+    pub header: BlockHeader,
+    pub transactions: Vec<StreamedTransaction>,
+    pub state_diff: ThinStateDiff,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct StreamedTransaction {
+    pub transaction_hash: TransactionHash,
+    pub transaction: Transaction,
+    /// None if the storage doesn't store receipts.
+    pub receipt: Option<StreamedReceipt>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct StreamedReceipt {
+    pub output: ThinTransactionOutput,
+    pub events: Vec<Event>,
+}
+
+/// Publishes the blocks that are stored from now on, and the reorgs that revert them, to the topic.
+/// The storage is checked on every sync notification. Returns only on a storage error or once the
+/// sync stops notifying.
+pub async fn run_event_stream(
+    config: EventStreamConfig,
+    chain_id: ChainId,
+    storage_reader: StorageReader,
+    mut notifications: broadcast::Receiver<SyncNotification>,
+) -> Result<(), EventStreamError> {
+    let mut publisher = Publisher::new(&config, &chain_id)?;
+    let mut next_block = stored_blocks_marker(&storage_reader.begin_ro_txn()?)?;
+    let mut published_blocks = BTreeMap::new();
+    loop {
+        let reverted_block_number =
+            find_reverted_block(&storage_reader.begin_ro_txn()?, &published_blocks)?;
+        if let Some(reverted_block_number) = reverted_block_number {
+            publisher.publish(&StreamMessage::Reorg { reverted_block_number }).await;
+            published_blocks.retain(|block_number, _| *block_number < reverted_block_number);
+            next_block = reverted_block_number;
+        }
+        loop {
+            // A new transaction for every block, so that the storage isn't held while publishing.
+            let block = {
+                let txn = storage_reader.begin_ro_txn()?;
+                if next_block >= stored_blocks_marker(&txn)? {
+                    break;
+                }
+                read_block(&txn, next_block)?
+            };
+            let block_hash = block.header.block_hash;
+            publisher.publish(&StreamMessage::Block(Box::new(block))).await;
+            published_blocks.insert(next_block, block_hash);
+            if published_blocks.len() > PUBLISHED_BLOCKS_CAPACITY {
+                published_blocks.pop_first();
+            }
+            next_block = next_block.next();
+        }
+
+        // The storage is read after every notification, so missed notifications (when lagging
+        // behind the sync) don't matter.
+        if let Err(broadcast::error::RecvError::Closed) = notifications.recv().await {
+            return Ok(());
+        }
+    }
+}
+
+// The first block whose body or state diff isn't stored yet.
+fn stored_blocks_marker(txn: &StorageTxn<'_, RO>) -> StorageResult<BlockNumber> {
+    Ok(txn.get_body_marker()?.min(txn.get_state_marker()?))
+}
+
+// Returns the first of the published blocks whose header was reverted, if any.
+fn find_reverted_block(
+    txn: &StorageTxn<'_, RO>,
+    published_blocks: &BTreeMap<BlockNumber, BlockHash>,
+) -> StorageResult<Option<BlockNumber>> {
+    let mut reverted_block_number = None;
+    for (block_number, block_hash) in published_blocks.iter().rev() {
+        match txn.get_block_header(*block_number)? {
+            Some(header) if header.block_hash == *block_hash => break,
+            _ => reverted_block_number = Some(*block_number),
+        }
+    }
+    Ok(reverted_block_number)
+}
+
+fn read_block(txn: &StorageTxn<'_, RO>, block_number: BlockNumber) -> StorageResult<StreamedBlock> {
+    let missing = |part: &str| StorageError::DBInconsistency {
+        msg: format!("Missing {part} of block {block_number} which is below the markers."),
+    };
+    let header = txn.get_block_header(block_number)?.ok_or_else(|| missing("header"))?;
+    let transactions =
+        txn.get_block_transactions(block_number)?.ok_or_else(|| missing("transactions"))?;
+    let transaction_hashes = txn
+        .get_block_transaction_hashes(block_number)?
+        .ok_or_else(|| missing("transaction hashes"))?;
+    let state_diff = txn.get_state_diff(block_number)?.ok_or_else(|| missing("state diff"))?;
+    let mut streamed_transactions = Vec::with_capacity(transactions.len());
+    for (offset, (transaction, transaction_hash)) in
+        transactions.into_iter().zip(transaction_hashes).enumerate()
+    {
+        let transaction_index = TransactionIndex(block_number, TransactionOffsetInBlock(offset));
+        let receipt = match txn.get_transaction_output(transaction_index)? {
+            Some(output) => Some(StreamedReceipt {
+                output,
+                events: txn.get_transaction_events(transaction_index)?.unwrap_or_default(),
+            }),
+            None => None,
+        };
+        streamed_transactions.push(StreamedTransaction { transaction_hash, transaction, receipt });
+    }
+    Ok(StreamedBlock { block_number, header, transactions: streamed_transactions, state_diff })
+}
+
+// Publishes the messages to the configured broker.
+struct Publisher {
+    sink: Sink,
+    retry_delay: Duration,
+    max_message_size: usize,
+}
+
+enum Sink {
+    KafkaRest(KafkaRestSink),
+    Nats(NatsSink),
+}
+
+#[derive(thiserror::Error, Debug)]
+enum SinkError {
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Tls(#[from] native_tls::Error),
+    #[error("The NATS server replied with an error: {0}.")]
+    Nats(String),
+    #[error("The publication wasn't acknowledged in time.")]
+    Timeout,
+    #[error("The message is larger than the broker accepts.")]
+    MessageTooLarge,
+}
+
+impl Publisher {
+    fn new(config: &EventStreamConfig, chain_id: &ChainId) -> Result<Self, EventStreamError> {
+        let sink = match config.sink {
+            EventStreamSink::KafkaRest => Sink::KafkaRest(KafkaRestSink {
+                client: Client::builder()
+                    .timeout(config.request_timeout)
+                    .build()
+                    .map_err(EventStreamError::ClientCreation)?,
+                topic_url: format!("{}/topics/{}", config.url.trim_end_matches('/'), config.topic),
+                key: chain_id.0.clone(),
+            }),
+            EventStreamSink::Nats => Sink::Nats(NatsSink {
+                address: config.url.clone(),
+                subject: config.topic.clone(),
+                timeout: config.request_timeout,
+                connect_message: nats_connect_message(config),
+                tls_connector: if config.nats_tls {
+                    Some(TlsConnector::from(
+                        native_tls::TlsConnector::new()
+                            .map_err(EventStreamError::TlsConnectorCreation)?,
+                    ))
+                } else {
+                    None
+                },
+                connection: None,
+            }),
+        };
+        Ok(Self {
+            sink,
+            retry_delay: config.retry_delay,
+            max_message_size: config.max_message_size,
+        })
+    }
+
+    // Publishes the message, retrying until it succeeds. A message that is too large to be
+    // published is dropped.
+    async fn publish(&mut self, message: &StreamMessage) {
+        let payload = serde_json::to_string(message).expect("Messages should be serializable.");
+        if payload.len() > self.max_message_size {
+            error!(
+                "Dropping the {} message, whose size of {} bytes exceeds the maximal message size \
+                 of {} bytes.",
+                message_description(message),
+                payload.len(),
+                self.max_message_size
+            );
+            return;
+        }
+        let mut retry_delay = self.retry_delay;
+        loop {
+            let result = match &mut self.sink {
+                Sink::KafkaRest(sink) => sink.send(&payload).await,
+                Sink::Nats(sink) => sink.send(payload.as_bytes()).await,
+            };
+            match result {
+                Ok(()) => return,
+                Err(SinkError::MessageTooLarge) => {
+                    error!(
+                        "Dropping the {} message, which the broker rejected as too large.",
+                        message_description(message)
+                    );
+                    return;
+                }
+                Err(err) => {
+                    warn!("Failed publishing a message to the event stream, retrying: {err}");
+                    tokio::time::sleep(retry_delay).await;
+                    retry_delay = (retry_delay * 2).min(MAX_RETRY_DELAY);
+                }
+            }
+        }
+    }
+}
+
+fn message_description(message: &StreamMessage) -> String {
+    match message {
+        StreamMessage::Block(block) => format!("block {}", block.block_number),
+        StreamMessage::Reorg { reverted_block_number } => {
+            format!("reorg of block {reverted_block_number}")
+        }
+    }
+}
+
+struct KafkaRestSink {
+    client: Client,
+    topic_url: String,
+    // All the records have the same key, so that they go to the same partition and keep their
+    // order.
+    key: String,
+}
+
+impl KafkaRestSink {
+    // The payload is the serialized message, which is embedded as is so that a large block isn't
+    // serialized twice.
+    async fn send(&self, payload: &str) -> Result<(), SinkError> {
+        let body = format!("{{\"records\":[{{\"key\":{},\"value\":{payload}}}]}}", json!(self.key));
+        let response = self
+            .client
+            .post(&self.topic_url)
+            .header(reqwest::header::CONTENT_TYPE, KAFKA_JSON_CONTENT_TYPE)
+            .body(body)
+            .send()
+            .await
+            // The URL is omitted from the error since it may contain credentials.
+            .map_err(|err| SinkError::Http(err.without_url()))?;
+        if response.status() == reqwest::StatusCode::PAYLOAD_TOO_LARGE {
+            return Err(SinkError::MessageTooLarge);
+        }
+        response.error_for_status().map_err(|err| SinkError::Http(err.without_url()))?;
+        Ok(())
+    }
+}
+
+// The stream of a NATS connection, which is either plain TCP or TLS.
+trait NatsStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> NatsStream for T {}
+
+type NatsConnection = BufStream<Box<dyn NatsStream>>;
+
+struct NatsSink {
+    address: String,
+    subject: String,
+    timeout: Duration,
+    connect_message: Vec<u8>,
+    // None if the connection isn't upgraded to TLS.
+    tls_connector: Option<TlsConnector>,
+    // Connected on the first message, and again after a failure.
+    connection: Option<NatsConnection>,
+}
+
+impl NatsSink {
+    async fn send(&mut self, payload: &[u8]) -> Result<(), SinkError> {
+        tokio::time::timeout(self.timeout, self.try_send(payload))
+            .await
+            .unwrap_or(Err(SinkError::Timeout))
+    }
+
+    // The connection is kept only if the message is acknowledged, so that a broken connection is
+    // replaced on the next attempt.
+    async fn try_send(&mut self, payload: &[u8]) -> Result<(), SinkError> {
+        let mut connection = match self.connection.take() {
+            Some(connection) => connection,
+            None => {
+                nats_connect(&self.address, self.tls_connector.as_ref(), &self.connect_message)
+                    .await?
+            }
+        };
+        nats_publish(&mut connection, &self.subject, payload).await?;
+        self.connection = Some(connection);
+        Ok(())
+    }
+}
+
+// The first message on a NATS connection, which asks the server not to acknowledge every message
+// and holds the credentials.
+fn nats_connect_message(config: &EventStreamConfig) -> Vec<u8> {
+    let mut options = json!({
+        "verbose": false,
+        "pedantic": false,
+        "tls_required": config.nats_tls,
+    });
+    if !config.nats_user.is_empty() {
+        options["user"] = json!(config.nats_user);
+        options["pass"] = json!(config.nats_password);
+    }
+    if !config.nats_token.is_empty() {
+        options["auth_token"] = json!(config.nats_token);
+    }
+    format!("CONNECT {options}\r\n").into_bytes()
+}
+
+async fn nats_connect(
+    address: &str,
+    tls_connector: Option<&TlsConnector>,
+    connect_message: &[u8],
+) -> Result<NatsConnection, SinkError> {
+    let mut connection = BufStream::new(TcpStream::connect(address).await?);
+    // The server greets with its INFO, and waits for the TLS handshake if TLS is used.
+    nats_read_line(&mut connection).await?;
+    let mut connection: NatsConnection = match tls_connector {
+        Some(tls_connector) => {
+            let host = address.rsplit_once(':').map_or(address, |(host, _port)| host);
+            let tls_stream = tls_connector.connect(host, connection.into_inner()).await?;
+            BufStream::new(Box::new(tls_stream))
+        }
+        None => BufStream::new(Box::new(connection.into_inner())),
+    };
+    connection.write_all(connect_message).await?;
+    Ok(connection)
+}
+
+async fn nats_publish(
+    connection: &mut NatsConnection,
+    subject: &str,
+    payload: &[u8],
+) -> Result<(), SinkError> {
+    connection.write_all(format!("PUB {subject} {}\r\n", payload.len()).as_bytes()).await?;
+    connection.write_all(payload).await?;
+    // The server answers the PING after it processed the message, so the PONG acknowledges it.
+    connection.write_all(b"\r\nPING\r\n").await?;
+    connection.flush().await?;
+    loop {
+        let line = nats_read_line(connection).await?;
+        match line.as_str() {
+            "PONG" => return Ok(()),
+            "PING" => {
+                connection.write_all(b"PONG\r\n").await?;
+                connection.flush().await?;
+            }
+            NATS_MAX_PAYLOAD_VIOLATION => return Err(SinkError::MessageTooLarge),
+            _ if line.starts_with("-ERR") => return Err(SinkError::Nats(line)),
+            // INFO updates and +OK acknowledgements.
+            _ => debug!("Ignoring NATS server message: {line}"),
+        }
+    }
+}
+
+async fn nats_read_line(
+    connection: &mut BufStream<impl AsyncRead + AsyncWrite + Unpin>,
+) -> Result<String, SinkError> {
+    let mut line = String::new();
+    if connection.read_line(&mut line).await? == 0 {
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+    }
+    Ok(line.trim_end().to_owned())
+}
@@ -0,0 +1,193 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use mockito::{mock, Matcher};
+use papyrus_storage::body::BodyStorageWriter;
+use papyrus_storage::header::HeaderStorageWriter;
+use papyrus_storage::state::{StateStorageReader, StateStorageWriter};
+use papyrus_storage::test_utils::get_test_storage;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use starknet_api::block::{BlockHash, BlockHeader, BlockNumber};
+use starknet_api::core::ChainId;
+use starknet_api::hash::StarkHash;
+use test_utils::{get_rng, get_test_body_with_valid_transaction_hashes, get_test_state_diff};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufStream};
+use tokio::net::TcpListener;
+
+use crate::event_stream::{
+    find_reverted_block,
+    read_block,
+    EventStreamConfig,
+    EventStreamSink,
+    Publisher,
+    StreamMessage,
+};
+
+fn header(block_number: u64, block_hash: u8) -> BlockHeader {
+    BlockHeader {
+        block_number: BlockNumber(block_number),
+        block_hash: BlockHash(StarkHash::from(block_hash)),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn reads_block_with_receipts() {
+    let chain_id = ChainId("SN_MAIN".to_owned());
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let body = get_test_body_with_valid_transaction_hashes(&mut get_rng(), &chain_id);
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &header(0, 1))
+        .unwrap()
+        .append_body(BlockNumber(0), body.clone())
+        .unwrap()
+        .append_state_diff(BlockNumber(0), get_test_state_diff(), Default::default())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = storage_reader.begin_ro_txn().unwrap();
+    let block = read_block(&txn, BlockNumber(0)).unwrap();
+    assert_eq!(block.header, header(0, 1));
+    assert_eq!(Some(block.state_diff), txn.get_state_diff(BlockNumber(0)).unwrap());
+    assert_eq!(block.transactions.len(), body.transactions.len());
+    for (streamed_transaction, (transaction_hash, output)) in block
+        .transactions
+        .iter()
+        .zip(body.transaction_hashes.iter().zip(body.transaction_outputs.iter()))
+    {
+        assert_eq!(streamed_transaction.transaction_hash, *transaction_hash);
+        let receipt = streamed_transaction.receipt.as_ref().unwrap();
+        assert_eq!(receipt.events, *output.events());
+    }
+}
+
+#[test]
+fn finds_first_reverted_block() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let mut txn = storage_writer.begin_rw_txn().unwrap();
+    for block_number in 0..3 {
+        txn = txn.append_header(BlockNumber(block_number), &header(block_number, 1)).unwrap();
+    }
+    txn.commit().unwrap();
+    let txn = storage_reader.begin_ro_txn().unwrap();
+
+    let mut published_blocks: BTreeMap<_, _> = (0..3)
+        .map(|block_number| (BlockNumber(block_number), BlockHash(StarkHash::from(1_u8))))
+        .collect();
+    assert_eq!(find_reverted_block(&txn, &published_blocks).unwrap(), None);
+
+    // Block 1 was replaced, and block 2 was reverted and not stored again yet.
+    published_blocks.insert(BlockNumber(1), BlockHash(StarkHash::from(2_u8)));
+    published_blocks.insert(BlockNumber(3), BlockHash(StarkHash::from(2_u8)));
+    assert_eq!(find_reverted_block(&txn, &published_blocks).unwrap(), Some(BlockNumber(1)));
+}
+
+#[tokio::test]
+async fn publishes_to_kafka_rest_proxy() {
+    let proxy = mock("POST", "/topics/blocks")
+        .match_header("content-type", "application/vnd.kafka.json.v2+json")
+        .match_body(Matcher::Json(json!({
+            "records": [{
+                "key": "SN_MAIN",
+                "value": { "type": "reorg", "reverted_block_number": 5 },
+            }],
+        })))
+        .with_status(200)
+        .expect(1)
+        .create();
+    let config = EventStreamConfig {
+        sink: EventStreamSink::KafkaRest,
+        url: mockito::server_url(),
+        topic: "blocks".to_owned(),
+        ..Default::default()
+    };
+
+    let mut publisher = Publisher::new(&config, &ChainId("SN_MAIN".to_owned())).unwrap();
+    publisher.publish(&StreamMessage::Reorg { reverted_block_number: BlockNumber(5) }).await;
+    proxy.assert();
+}
+
+#[tokio::test]
+async fn publishes_to_nats_and_waits_for_acknowledgement() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let config = EventStreamConfig {
+        sink: EventStreamSink::Nats,
+        url: listener.local_addr().unwrap().to_string(),
+        topic: "blocks".to_owned(),
+        retry_delay: Duration::from_millis(1),
+        nats_user: "user".to_owned(),
+        nats_password: "password".to_owned(),
+        ..Default::default()
+    };
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut stream = BufStream::new(stream);
+        stream.write_all(b"INFO {}\r\n").await.unwrap();
+        stream.flush().await.unwrap();
+        let mut lines = vec![];
+        for _ in 0..2 {
+            let mut line = String::new();
+            stream.read_line(&mut line).await.unwrap();
+            lines.push(line);
+        }
+        let payload_length: usize =
+            lines[1].trim_end().rsplit(' ').next().unwrap().parse().unwrap();
+        let mut payload = vec![0; payload_length + 2];
+        stream.read_exact(&mut payload).await.unwrap();
+        let mut ping = String::new();
+        stream.read_line(&mut ping).await.unwrap();
+        stream.write_all(b"PONG\r\n").await.unwrap();
+        stream.flush().await.unwrap();
+        (lines, payload, ping)
+    });
+
+    let mut publisher = Publisher::new(&config, &ChainId("SN_MAIN".to_owned())).unwrap();
+    publisher.publish(&StreamMessage::Reorg { reverted_block_number: BlockNumber(5) }).await;
+
+    let (lines, payload, ping) = server.await.unwrap();
+    let connect_options: serde_json::Value =
+        serde_json::from_str(lines[0].trim_end().strip_prefix("CONNECT ").unwrap()).unwrap();
+    assert_eq!(connect_options["user"], "user");
+    assert_eq!(connect_options["pass"], "password");
+    assert!(lines[1].starts_with("PUB blocks "));
+    let payload: serde_json::Value = serde_json::from_slice(&payload[..payload.len() - 2]).unwrap();
+    assert_eq!(payload, json!({ "type": "reorg", "reverted_block_number": 5 }));
+    assert_eq!(ping, "PING\r\n");
+}
+
+#[tokio::test]
+async fn drops_message_larger_than_max_message_size() {
+    let proxy = mock("POST", "/topics/blocks").expect(0).create();
+    let config = EventStreamConfig {
+        sink: EventStreamSink::KafkaRest,
+        url: mockito::server_url(),
+        topic: "blocks".to_owned(),
+        max_message_size: 10,
+        ..Default::default()
+    };
+
+    let mut publisher = Publisher::new(&config, &ChainId("SN_MAIN".to_owned())).unwrap();
+    publisher.publish(&StreamMessage::Reorg { reverted_block_number: BlockNumber(5) }).await;
+    proxy.assert();
+}
+
+#[tokio::test]
+async fn drops_message_rejected_as_too_large() {
+    let proxy = mock("POST", "/topics/blocks").with_status(413).expect(1).create();
+    let config = EventStreamConfig {
+        sink: EventStreamSink::KafkaRest,
+        url: mockito::server_url(),
+        topic: "blocks".to_owned(),
+        retry_delay: Duration::from_millis(1),
+        ..Default::default()
+    };
+
+    // Returns without retrying.
+    let mut publisher = Publisher::new(&config, &ChainId("SN_MAIN".to_owned())).unwrap();
+    publisher.publish(&StreamMessage::Reorg { reverted_block_number: BlockNumber(5) }).await;
+    proxy.assert();
+}
@@ -0,0 +1,251 @@
+//! Re-validation of random historical blocks against the commitments they were stored with, so
+//! that a silent corruption of the data on the disk is noticed by the operator of the node.
+#[cfg(test)]
+#[path = "integrity_check_test.rs"]
+mod integrity_check_test;
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use papyrus_common::block_hash::validate_block_hash;
+use papyrus_common::metrics::{PAPYRUS_INTEGRITY_CHECKED_BLOCKS, PAPYRUS_INTEGRITY_DISCREPANCIES};
+use papyrus_common::transaction_hash::validate_transaction_hash;
+use papyrus_common::TransactionOptions;
+use papyrus_config::converters::deserialize_seconds_to_duration;
+use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use papyrus_storage::body::{BodyStorageReader, TransactionIndex};
+use papyrus_storage::db::RO;
+use papyrus_storage::header::HeaderStorageReader;
+use papyrus_storage::state::StateStorageReader;
+use papyrus_storage::{StorageError, StorageReader, StorageTxn};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use starknet_api::block::{Block, BlockBody, BlockNumber};
+use starknet_api::core::ChainId;
+use starknet_api::state::StateNumber;
+use starknet_api::transaction::TransactionOffsetInBlock;
+use starknet_api::StarknetApiError;
+use tracing::{debug, warn};
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub struct IntegrityCheckConfig {
+    #[serde(deserialize_with = "deserialize_seconds_to_duration")]
+    pub check_interval: Duration,
+    /// The number of random blocks that are re-validated on every check. 0 means that the check
+    /// is disabled.
+    pub blocks_per_check: usize,
+}
+
+impl Default for IntegrityCheckConfig {
+    fn default() -> Self {
+        IntegrityCheckConfig { check_interval: Duration::from_secs(60), blocks_per_check: 1 }
+    }
+}
+
+impl SerializeConfig for IntegrityCheckConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([
+            ser_param(
+                "check_interval",
+                &self.check_interval.as_secs(),
+                "Time in seconds between re-validations of random historical blocks of the \
+                 storage.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "blocks_per_check",
+                &self.blocks_per_check,
+                "The number of random historical blocks that are re-validated on every check. 0 \
+                 means that the check is disabled.",
+                ParamPrivacyInput::Public,
+            ),
+        ])
+    }
+}
+
+/// A validation of a stored block, used as the label of the discrepancies metric.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum IntegrityCheck {
+    /// The block hash matches the stored header, transactions and events.
+    BlockHash,
+    /// The hash of every transaction matches its stored content.
+    TransactionHash,
+    /// The state after the block holds every value of the stored state diff of the block.
+    StateDiff,
+}
+
+impl IntegrityCheck {
+    fn as_str(&self) -> &'static str {
+        match self {
+            IntegrityCheck::BlockHash => "block_hash",
+            IntegrityCheck::TransactionHash => "transaction_hash",
+            IntegrityCheck::StateDiff => "state_diff",
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum IntegrityCheckError {
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error(transparent)]
+    StarknetApi(#[from] StarknetApiError),
+}
+
+/// Re-validates random blocks below the body and state markers periodically, and reports the
+/// discrepancies it finds in the metrics. Never returns.
+pub async fn run_integrity_check(
+    config: IntegrityCheckConfig,
+    chain_id: ChainId,
+    storage_reader: StorageReader,
+) {
+    if config.blocks_per_check == 0 {
+        return std::future::pending().await;
+    }
+    loop {
+        tokio::time::sleep(config.check_interval).await;
+        let storage_reader = storage_reader.clone();
+        let chain_id = chain_id.clone();
+        // The check reads whole blocks from the storage, so it doesn't run on the async threads.
+        let res = tokio::task::spawn_blocking(move || {
+            check_random_blocks(&storage_reader, &chain_id, config.blocks_per_check)
+        })
+        .await;
+        match res {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => warn!("Integrity check failed: {err}"),
+            Err(err) => warn!("Integrity check panicked: {err}"),
+        }
+    }
+}
+
+fn check_random_blocks(
+    storage_reader: &StorageReader,
+    chain_id: &ChainId,
+    blocks_per_check: usize,
+) -> Result<(), IntegrityCheckError> {
+    let mut rng = rand::thread_rng();
+    for _ in 0..blocks_per_check {
+        let txn = storage_reader.begin_ro_txn()?;
+        let marker = txn.get_body_marker()?.min(txn.get_state_marker()?);
+        if marker == BlockNumber(0) {
+            return Ok(());
+        }
+        let block_number = BlockNumber(rng.gen_range(0..marker.0));
+        let discrepancies = check_block(&txn, chain_id, block_number)?;
+        metrics::increment_counter!(PAPYRUS_INTEGRITY_CHECKED_BLOCKS);
+        for check in discrepancies {
+            warn!(
+                "Integrity check of block {block_number} found a discrepancy in the {}.",
+                check.as_str()
+            );
+            metrics::increment_counter!(PAPYRUS_INTEGRITY_DISCREPANCIES, "check" => check.as_str());
+        }
+    }
+    Ok(())
+}
+
+/// Returns the checks that the stored data of the given block fails. A block whose data is
+/// missing from the storage isn't checked.
+pub(crate) fn check_block(
+    txn: &StorageTxn<'_, RO>,
+    chain_id: &ChainId,
+    block_number: BlockNumber,
+) -> Result<Vec<IntegrityCheck>, IntegrityCheckError> {
+    let Some(block) = read_block(txn, block_number)? else {
+        debug!("Block {block_number} is missing from the storage, skipping its integrity check.");
+        return Ok(vec![]);
+    };
+
+    let mut discrepancies = vec![];
+    if !validate_block_hash(&block, chain_id)? {
+        discrepancies.push(IntegrityCheck::BlockHash);
+    }
+    for (transaction, transaction_hash) in
+        block.body.transactions.iter().zip(block.body.transaction_hashes.iter())
+    {
+        if !validate_transaction_hash(
+            transaction,
+            &block_number,
+            chain_id,
+            *transaction_hash,
+            &TransactionOptions::default(),
+        )? {
+            discrepancies.push(IntegrityCheck::TransactionHash);
+            break;
+        }
+    }
+    if !state_diff_applied(txn, block_number)? {
+        discrepancies.push(IntegrityCheck::StateDiff);
+    }
+    Ok(discrepancies)
+}
+
+// Rebuilds the block from its header, transactions and transaction outputs with their events.
+fn read_block(
+    txn: &StorageTxn<'_, RO>,
+    block_number: BlockNumber,
+) -> Result<Option<Block>, IntegrityCheckError> {
+    let (Some(header), Some(transactions), Some(transaction_hashes), Some(thin_outputs)) = (
+        txn.get_block_header(block_number)?,
+        txn.get_block_transactions(block_number)?,
+        txn.get_block_transaction_hashes(block_number)?,
+        txn.get_block_transaction_outputs(block_number)?,
+    ) else {
+        return Ok(None);
+    };
+
+    let mut transaction_outputs = Vec::with_capacity(thin_outputs.len());
+    for (offset, thin_output) in thin_outputs.into_iter().enumerate() {
+        let transaction_index = TransactionIndex(block_number, TransactionOffsetInBlock(offset));
+        let events = txn.get_transaction_events(transaction_index)?.unwrap_or_default();
+        transaction_outputs.push(thin_output.into_transaction_output(events));
+    }
+    Ok(Some(Block {
+        header,
+        body: BlockBody { transactions, transaction_outputs, transaction_hashes },
+    }))
+}
+
+// Returns whether the state right after the block holds every value that the stored state diff of
+// the block sets. The state of blocks that isn't queryable in the node mode is considered valid.
+fn state_diff_applied(
+    txn: &StorageTxn<'_, RO>,
+    block_number: BlockNumber,
+) -> Result<bool, IntegrityCheckError> {
+    let state_number = StateNumber::right_after_block(block_number);
+    if txn.verify_state_queryable(state_number).is_err() {
+        return Ok(true);
+    }
+    let Some(state_diff) = txn.get_state_diff(block_number)? else {
+        return Ok(true);
+    };
+    let state_reader = txn.get_state_reader()?;
+
+    for (address, storage_diffs) in &state_diff.storage_diffs {
+        for (key, value) in storage_diffs {
+            if state_reader.get_storage_at(state_number, address, key)? != *value {
+                return Ok(false);
+            }
+        }
+    }
+    for (address, nonce) in &state_diff.nonces {
+        if state_reader.get_nonce_at(state_number, address)? != Some(*nonce) {
+            return Ok(false);
+        }
+    }
+    for (address, class_hash) in
+        state_diff.deployed_contracts.iter().chain(state_diff.replaced_classes.iter())
+    {
+        if state_reader.get_class_hash_at(state_number, address)? != Some(*class_hash) {
+            return Ok(false);
+        }
+    }
+    for class_hash in state_diff.declared_classes.keys() {
+        if state_reader.get_class_definition_block_number(class_hash)? != Some(block_number) {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
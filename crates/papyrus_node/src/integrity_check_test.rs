@@ -0,0 +1,66 @@
+use papyrus_storage::body::BodyStorageWriter;
+use papyrus_storage::header::HeaderStorageWriter;
+use papyrus_storage::state::StateStorageWriter;
+use papyrus_storage::test_utils::get_test_storage;
+use papyrus_storage::StorageWriter;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockBody, BlockHeader, BlockNumber};
+use starknet_api::core::ChainId;
+use starknet_api::hash::StarkHash;
+use starknet_api::transaction::TransactionHash;
+use test_utils::{get_rng, get_test_body_with_valid_transaction_hashes, get_test_state_diff};
+
+use crate::integrity_check::{check_block, IntegrityCheck};
+
+fn write_block(storage_writer: &mut StorageWriter, body: BlockBody) {
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &BlockHeader::default())
+        .unwrap()
+        .append_body(BlockNumber(0), body)
+        .unwrap()
+        .append_state_diff(BlockNumber(0), get_test_state_diff(), Default::default())
+        .unwrap()
+        .commit()
+        .unwrap();
+}
+
+#[test]
+fn valid_transactions_and_state_diff() {
+    let chain_id = ChainId("SN_MAIN".to_owned());
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    write_block(
+        &mut storage_writer,
+        get_test_body_with_valid_transaction_hashes(&mut get_rng(), &chain_id),
+    );
+
+    let txn = storage_reader.begin_ro_txn().unwrap();
+    // The default header doesn't hold the hash of the block.
+    assert_eq!(
+        check_block(&txn, &chain_id, BlockNumber(0)).unwrap(),
+        vec![IntegrityCheck::BlockHash]
+    );
+}
+
+#[test]
+fn wrong_transaction_hash() {
+    let chain_id = ChainId("SN_MAIN".to_owned());
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let mut body = get_test_body_with_valid_transaction_hashes(&mut get_rng(), &chain_id);
+    body.transaction_hashes[0] = TransactionHash(StarkHash::from(1_u8));
+    write_block(&mut storage_writer, body);
+
+    let txn = storage_reader.begin_ro_txn().unwrap();
+    assert_eq!(
+        check_block(&txn, &chain_id, BlockNumber(0)).unwrap(),
+        vec![IntegrityCheck::BlockHash, IntegrityCheck::TransactionHash]
+    );
+}
+
+#[test]
+fn missing_block_is_skipped() {
+    let ((storage_reader, _), _temp_dir) = get_test_storage();
+    let txn = storage_reader.begin_ro_txn().unwrap();
+    assert_eq!(check_block(&txn, &ChainId("SN_MAIN".to_owned()), BlockNumber(0)).unwrap(), vec![]);
+}
@@ -4,6 +4,22 @@
 
 #[allow(unused_imports)]
 pub mod config;
+pub mod db_inspect;
+pub mod db_schema;
+pub mod disk_space_monitor;
+pub mod error;
+pub mod event_stream;
+pub mod integrity_check;
+pub mod lifecycle;
+pub mod memory_budget;
+pub mod multi_chain;
+pub mod node_identity;
+pub mod offline_storage;
 #[cfg(test)]
 mod precision_test;
+pub mod rebuild_index;
+pub mod revert;
+pub mod runtime;
+pub mod sync_supervisor;
 pub mod version;
+pub mod webhook_notifier;
@@ -0,0 +1,216 @@
+//! The lifecycle of the components of the node. The components are started, the node is ready
+//! once all of them run, and on shutdown they are stopped layer by layer: the gateways are drained
+//! first so that no request reads a storage that is going away, then the sync stops writing, and
+//! only then the storage is closed.
+#[cfg(test)]
+#[path = "lifecycle_test.rs"]
+mod lifecycle_test;
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::Duration;
+
+use futures_util::future::{join_all, select_all};
+use papyrus_config::converters::deserialize_seconds_to_duration;
+use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use crate::error::NodeError;
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ShutdownConfig {
+    /// The time the gateways are given to finish the requests they are serving.
+    #[serde(deserialize_with = "deserialize_seconds_to_duration")]
+    pub drain_timeout: Duration,
+    /// The time every other component is given to stop, after which it's aborted.
+    #[serde(deserialize_with = "deserialize_seconds_to_duration")]
+    pub stop_timeout: Duration,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        ShutdownConfig {
+            drain_timeout: Duration::from_secs(10),
+            stop_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+impl SerializeConfig for ShutdownConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([
+            ser_param(
+                "drain_timeout",
+                &self.drain_timeout.as_secs(),
+                "Time in seconds that the gateways are given on shutdown to finish the requests \
+                 they are serving.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "stop_timeout",
+                &self.stop_timeout.as_secs(),
+                "Time in seconds that every other component is given on shutdown to stop, after \
+                 which it is aborted.",
+                ParamPrivacyInput::Public,
+            ),
+        ])
+    }
+}
+
+/// The phases of the lifecycle, logged as the node enters each of them.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Phase {
+    /// The components are being started.
+    Start,
+    /// All the components run.
+    Ready,
+    /// The gateways stop accepting requests and finish the ones they are serving.
+    Drain,
+    /// The rest of the components are stopped.
+    Stop,
+}
+
+/// The layers of the node, in the order in which they are shut down.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Layer {
+    /// The servers that answer the clients of the node.
+    Gateway,
+    /// The components that write to the storage or that the sync depends on.
+    Sync,
+    /// The components that only watch the storage.
+    Storage,
+}
+
+const SHUTDOWN_ORDER: [Layer; 3] = [Layer::Gateway, Layer::Sync, Layer::Storage];
+
+/// A task of the node that is stopped on shutdown.
+struct Component {
+    name: &'static str,
+    layer: Layer,
+    handle: JoinHandle<Result<(), NodeError>>,
+    // Asks the task to stop gracefully. Tasks without one are aborted.
+    stop: Option<Box<dyn FnOnce() + Send>>,
+}
+
+impl Component {
+    async fn shutdown(mut self, timeout: Duration) {
+        match self.stop.take() {
+            Some(stop) => stop(),
+            None => self.handle.abort(),
+        }
+        match tokio::time::timeout(timeout, &mut self.handle).await {
+            Ok(_) => info!("{} stopped.", self.name),
+            Err(_) => {
+                warn!("{} didn't stop within {timeout:?}, aborting it.", self.name);
+                self.handle.abort();
+            }
+        }
+    }
+}
+
+/// The components of the node, which are run until the node shuts down.
+pub struct Lifecycle {
+    config: ShutdownConfig,
+    components: Vec<Component>,
+}
+
+impl Lifecycle {
+    pub fn new(config: ShutdownConfig) -> Self {
+        info!(phase = ?Phase::Start, "Starting the components.");
+        Lifecycle { config, components: Vec::new() }
+    }
+
+    /// Adds a started component, which is aborted on shutdown.
+    pub fn add(
+        &mut self,
+        name: &'static str,
+        layer: Layer,
+        handle: JoinHandle<Result<(), NodeError>>,
+    ) {
+        info!("{name} started.");
+        self.components.push(Component { name, layer, handle, stop: None });
+    }
+
+    /// Adds a started component, which is stopped on shutdown by calling `stop` and is aborted
+    /// only if it doesn't stop in time.
+    pub fn add_with_stop(
+        &mut self,
+        name: &'static str,
+        layer: Layer,
+        handle: JoinHandle<Result<(), NodeError>>,
+        stop: impl FnOnce() + Send + 'static,
+    ) {
+        info!("{name} started.");
+        self.components.push(Component { name, layer, handle, stop: Some(Box::new(stop)) });
+    }
+
+    /// Runs the components until the shutdown signal resolves or until one of them stops, and
+    /// then shuts down the rest layer by layer. Returns the error of the component that stopped,
+    /// if any.
+    pub async fn run(self, shutdown_signal: impl Future<Output = ()>) -> Result<(), NodeError> {
+        let Lifecycle { config, mut components } = self;
+        info!(phase = ?Phase::Ready, "All the components are running.");
+
+        let stopped_component = if components.is_empty() {
+            shutdown_signal.await;
+            None
+        } else {
+            let component_exits =
+                select_all(components.iter_mut().map(|component| &mut component.handle));
+            tokio::select! {
+                _ = shutdown_signal => None,
+                (exit, index, _) = component_exits => Some((index, exit)),
+            }
+        };
+        let result = match stopped_component {
+            None => {
+                info!("Received a shutdown signal.");
+                Ok(())
+            }
+            Some((index, exit)) => {
+                let component = components.remove(index);
+                let result = exit.map_err(NodeError::from).and_then(|result| result);
+                match &result {
+                    Ok(()) => error!("{} stopped unexpectedly.", component.name),
+                    Err(err) => error!("{} stopped: {err}", component.name),
+                }
+                result
+            }
+        };
+
+        for layer in SHUTDOWN_ORDER {
+            let (phase, timeout) = match layer {
+                Layer::Gateway => (Phase::Drain, config.drain_timeout),
+                Layer::Sync | Layer::Storage => (Phase::Stop, config.stop_timeout),
+            };
+            info!(phase = ?phase, layer = ?layer, "Shutting down the {layer:?} layer.");
+            let (layer_components, rest) =
+                components.into_iter().partition::<Vec<_>, _>(|component| component.layer == layer);
+            components = rest;
+            join_all(layer_components.into_iter().map(|component| component.shutdown(timeout)))
+                .await;
+        }
+        info!("All the components stopped.");
+        result
+    }
+}
+
+/// Resolves when the process is asked to terminate, by Ctrl-C or by SIGTERM.
+pub async fn termination_signal() {
+    let mut sigterm = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+    {
+        Ok(sigterm) => sigterm,
+        Err(err) => {
+            warn!("Failed to listen to SIGTERM, shutting down only on Ctrl-C: {err}");
+            let _ = tokio::signal::ctrl_c().await;
+            return;
+        }
+    };
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {},
+        _ = sigterm.recv() => {},
+    }
+}
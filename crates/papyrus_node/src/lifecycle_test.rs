@@ -0,0 +1,74 @@
+use std::future::pending;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use pretty_assertions::assert_eq;
+use tokio::sync::oneshot;
+
+use crate::error::NodeError;
+use crate::lifecycle::{Layer, Lifecycle, ShutdownConfig};
+
+const TEST_SHUTDOWN_CONFIG: ShutdownConfig = ShutdownConfig {
+    drain_timeout: Duration::from_millis(100),
+    stop_timeout: Duration::from_millis(100),
+};
+
+// Adds a component that runs until it's stopped, and then records its name.
+fn add_stoppable(
+    lifecycle: &mut Lifecycle,
+    name: &'static str,
+    layer: Layer,
+    stopped: Arc<Mutex<Vec<&'static str>>>,
+) {
+    let (stop_sender, stop_receiver) = oneshot::channel();
+    let handle = tokio::spawn(async move {
+        let _ = stop_receiver.await;
+        stopped.lock().unwrap().push(name);
+        Ok(())
+    });
+    lifecycle.add_with_stop(name, layer, handle, move || {
+        let _ = stop_sender.send(());
+    });
+}
+
+#[tokio::test]
+async fn shutdown_stops_the_layers_in_order() {
+    let stopped = Arc::new(Mutex::new(Vec::new()));
+    let mut lifecycle = Lifecycle::new(TEST_SHUTDOWN_CONFIG);
+    // The components are added out of order.
+    add_stoppable(&mut lifecycle, "storage", Layer::Storage, stopped.clone());
+    add_stoppable(&mut lifecycle, "sync", Layer::Sync, stopped.clone());
+    add_stoppable(&mut lifecycle, "gateway", Layer::Gateway, stopped.clone());
+
+    lifecycle.run(async {}).await.unwrap();
+    assert_eq!(*stopped.lock().unwrap(), vec!["gateway", "sync", "storage"]);
+}
+
+#[tokio::test]
+async fn failed_component_shuts_down_the_rest() {
+    let stopped = Arc::new(Mutex::new(Vec::new()));
+    let mut lifecycle = Lifecycle::new(TEST_SHUTDOWN_CONFIG);
+    add_stoppable(&mut lifecycle, "gateway", Layer::Gateway, stopped.clone());
+    lifecycle.add(
+        "sync",
+        Layer::Sync,
+        tokio::spawn(async { Err(NodeError::RpcServer(anyhow::anyhow!("failure"))) }),
+    );
+    // Aborted, since it has no way to stop.
+    lifecycle.add("storage", Layer::Storage, tokio::spawn(pending()));
+
+    let result = lifecycle.run(pending()).await;
+    assert!(matches!(result, Err(NodeError::RpcServer(_))));
+    assert_eq!(*stopped.lock().unwrap(), vec!["gateway"]);
+}
+
+#[tokio::test]
+async fn component_that_ignores_stop_is_aborted() {
+    let mut lifecycle = Lifecycle::new(TEST_SHUTDOWN_CONFIG);
+    lifecycle.add_with_stop("gateway", Layer::Gateway, tokio::spawn(pending()), || {});
+
+    tokio::time::timeout(Duration::from_secs(5), lifecycle.run(async {}))
+        .await
+        .expect("The shutdown should end after the drain timeout.")
+        .unwrap();
+}
@@ -2,35 +2,61 @@
 mod main_test;
 
 use std::env::args;
-use std::future::{self, pending};
+use std::future::{pending, Future};
 use std::process::exit;
 use std::sync::Arc;
 use std::time::Duration;
 
+use futures_util::future::join_all;
+use papyrus_common::deferred_classes::DeferredClassRequests;
+use papyrus_common::disk_space::DiskSpaceStatus;
+use papyrus_common::external_addresses::ExternalAddresses;
+use papyrus_common::maintenance_requests::MaintenanceRequests;
 use papyrus_common::pending_classes::PendingClasses;
-use papyrus_common::BlockHashAndNumber;
+use papyrus_common::recent_state::RecentStateCache;
+use papyrus_common::rpc_load::RpcLoad;
+use papyrus_common::source_reports::SourceReports;
+use papyrus_common::sync_notifications::SyncNotifier;
 use papyrus_config::presentation::get_config_presentation;
 use papyrus_config::validators::config_validate;
 use papyrus_config::ConfigError;
+use papyrus_monitoring_gateway::chain_label::ChainSpanLayer;
 use papyrus_monitoring_gateway::MonitoringServer;
 use papyrus_network::{network_manager, NetworkConfig};
 use papyrus_node::config::NodeConfig;
+use papyrus_node::db_inspect::{run_db_inspect, DB_INSPECT_ARGS};
+use papyrus_node::db_schema::{run_db_schema, DB_SCHEMA_ARGS};
+use papyrus_node::disk_space_monitor::run_disk_space_monitor;
+use papyrus_node::error::NodeError;
+use papyrus_node::event_stream::run_event_stream;
+use papyrus_node::integrity_check::run_integrity_check;
+use papyrus_node::lifecycle::{termination_signal, Layer, Lifecycle, Phase};
+use papyrus_node::multi_chain::{load_chain_configs, MultiChainError};
+use papyrus_node::node_identity::register_node_run;
+use papyrus_node::offline_storage::is_offline_command;
+use papyrus_node::rebuild_index::{run_rebuild_index, REBUILD_INDEX_ARGS};
+use papyrus_node::revert::{run_revert, REVERT_ARGS};
+use papyrus_node::sync_supervisor::SyncSupervisor;
 use papyrus_node::version::VERSION_FULL;
-use papyrus_rpc::run_server;
+use papyrus_node::webhook_notifier::run_webhook_notifier;
+use papyrus_rpc::{run_server, RpcSharedState};
+use papyrus_storage::state::StateStorageReader;
 use papyrus_storage::{open_storage, update_storage_metrics, StorageReader, StorageWriter};
+use papyrus_sync::genesis::load_genesis_state;
 use papyrus_sync::sources::base_layer::{BaseLayerSourceError, EthereumBaseLayerSource};
 use papyrus_sync::sources::central::{CentralError, CentralSource};
 use papyrus_sync::sources::pending::PendingSource;
-use papyrus_sync::{StateSync, StateSyncError};
-use starknet_api::block::BlockHash;
+use papyrus_sync::{prune_state_history, store_source_reports, StateSync, StateSyncError};
+use starknet_api::block::{BlockHash, BlockNumber};
 use starknet_api::hash::{StarkFelt, GENESIS_HASH};
 use starknet_api::stark_felt;
 use starknet_client::reader::objects::pending_data::{PendingBlock, PendingBlockOrDeprecated};
 use starknet_client::reader::PendingData;
-use tokio::sync::RwLock;
+use tokio::runtime::Handle;
+use tokio::sync::{watch, RwLock};
 use tokio::task::JoinHandle;
 use tracing::metadata::LevelFilter;
-use tracing::{debug_span, error, info, warn, Instrument};
+use tracing::{debug_span, error, error_span, info, warn, Instrument};
 use tracing_subscriber::prelude::*;
 use tracing_subscriber::{fmt, EnvFilter};
 
@@ -41,27 +67,90 @@ const DEFAULT_LEVEL: LevelFilter = LevelFilter::INFO;
 // Duration between updates to the storage metrics (those in the collect_storage_metrics function).
 const STORAGE_METRICS_UPDATE_INTERVAL: Duration = Duration::from_secs(10);
 
-async fn run_threads(config: NodeConfig) -> anyhow::Result<()> {
-    let (storage_reader, storage_writer) = open_storage(config.storage.clone())?;
+// Runs the components of the node on the current runtime, except for the sync which runs on the
+// given runtime if there is one, until the shutdown signal resolves or until a component stops.
+async fn run_threads(
+    config: NodeConfig,
+    sync_runtime: Option<Handle>,
+    shutdown_signal: impl Future<Output = ()>,
+) -> Result<(), NodeError> {
+    let (storage_reader, mut storage_writer) = open_storage(config.storage.clone())?;
+    let mut lifecycle = Lifecycle::new(config.shutdown);
 
-    let storage_metrics_handle = if config.monitoring_gateway.collect_metrics {
-        spawn_storage_metrics_collector(storage_reader.clone(), STORAGE_METRICS_UPDATE_INTERVAL)
-    } else {
-        tokio::spawn(future::pending())
-    };
+    if config.monitoring_gateway.collect_metrics {
+        lifecycle.add(
+            "Storage metrics collector",
+            Layer::Storage,
+            spawn_storage_metrics_collector(
+                storage_reader.clone(),
+                STORAGE_METRICS_UPDATE_INTERVAL,
+            ),
+        );
+    }
+
+    // Written by the disk space monitor and read by the sync, which pauses while the space is low.
+    let disk_space = Arc::new(DiskSpaceStatus::default());
+    let disk_space_monitor = run_disk_space_monitor(
+        config.disk_space_monitor,
+        config.storage.db_config.path(),
+        disk_space.clone(),
+    );
+    lifecycle.add(
+        "Disk space monitor",
+        Layer::Sync,
+        spawn_in_chain(async move {
+            disk_space_monitor.await;
+            Ok(())
+        }),
+    );
+
+    // Integrity check of historical blocks.
+    let integrity_check = run_integrity_check(
+        config.integrity_check,
+        config.rpc.chain_id.clone(),
+        storage_reader.clone(),
+    );
+    lifecycle.add(
+        "Integrity check",
+        Layer::Storage,
+        spawn_in_chain(async move {
+            integrity_check.await;
+            Ok(())
+        }),
+    );
+
+    // Written by the network and read by the monitoring server.
+    let p2p_external_addresses = Arc::new(ExternalAddresses::default());
+    // The sync is the only writer of the syncing state, which the monitoring server reads to tell
+    // whether the node is serving.
+    let shared_highest_block = Arc::new(RwLock::new(None));
+    // Written by the monitoring server, which receives the maintenance tasks of the operators, and
+    // read by the sync, which owns the storage writer and runs the tasks that write to the storage.
+    let maintenance_requests = Arc::new(MaintenanceRequests::default());
+
+    let public_config_presentation = get_config_presentation(&config, false)?;
+    let node_identity =
+        register_node_run(&storage_reader, &mut storage_writer, &public_config_presentation)?;
+    info!(node_id = %node_identity.node_id, "Registered the run of the node.");
 
     // Monitoring server.
     let monitoring_server = MonitoringServer::new(
         config.monitoring_gateway.clone(),
         get_config_presentation(&config, true)?,
-        get_config_presentation(&config, false)?,
+        public_config_presentation,
         storage_reader.clone(),
         VERSION_FULL,
+        &node_identity.node_id,
+        p2p_external_addresses.clone(),
+        shared_highest_block.clone(),
+        maintenance_requests.clone(),
     )?;
-    let monitoring_server_handle = monitoring_server.spawn_server().await;
+    lifecycle.add(
+        "Monitoring server",
+        Layer::Gateway,
+        spawn_in_chain(async move { Ok(monitoring_server.run().await?) }),
+    );
 
-    // The sync is the only writer of the syncing state.
-    let shared_highest_block = Arc::new(RwLock::new(None));
     let pending_data = Arc::new(RwLock::new(PendingData {
         // The pending data might change later to DeprecatedPendingBlock, depending on the response
         // from the feeder gateway.
@@ -71,76 +160,160 @@ async fn run_threads(config: NodeConfig) -> anyhow::Result<()> {
         }),
         ..Default::default()
     }));
-    let pending_classes = Arc::new(RwLock::new(PendingClasses::default()));
+    let memory_budget = config.memory_budget.budget();
+    let pending_classes = Arc::new(RwLock::new(PendingClasses::new(memory_budget.pending_data)));
+    // Written by the RPC server and read by the sync, which slows down when the server is loaded.
+    let rpc_load = Arc::new(RpcLoad::default());
+    // Written by the sync and read by the RPC server, which notifies its subscribers on changes.
+    let sync_notifier = Arc::new(SyncNotifier::default());
+    // Written by the sync and read by the RPC server, which answers state queries from it.
+    let recent_state = Arc::new(RecentStateCache::with_max_bytes(
+        config.rpc.recent_state_cache_blocks,
+        memory_budget.recent_state,
+    ));
+    // Written by the RPC server, which requests the classes whose download the sync deferred, and
+    // read by the sync, which downloads them.
+    let deferred_class_requests = Arc::new(DeferredClassRequests::default());
+    // Written by the central source and the network, which report the requests to the sources and
+    // ban misbehaving peers, and read by the sync, which records the reports in the storage.
+    let source_reports = Arc::new(SourceReports::default());
+    // The class cache is shared by the storage, the sync and the execution.
+    storage_reader.class_cache().set_max_bytes(memory_budget.class_caches);
+
+    // The server and the sync share the same state.
+    let shared_state = RpcSharedState {
+        shared_highest_block,
+        pending_data,
+        pending_classes,
+        rpc_load,
+        sync_notifier,
+        recent_state,
+        deferred_class_requests,
+    };
 
     // JSON-RPC server.
-    let (_, server_handle) = run_server(
-        &config.rpc,
-        shared_highest_block.clone(),
-        pending_data.clone(),
-        pending_classes.clone(),
-        storage_reader.clone(),
-        VERSION_FULL,
-    )
-    .await?;
-    let server_handle_future = tokio::spawn(server_handle.stopped());
+    let (_, server_handle) =
+        run_server(&config.rpc, shared_state.clone(), storage_reader.clone(), VERSION_FULL)
+            .await
+            .map_err(NodeError::RpcServer)?;
+    // Stopping the server lets it finish the requests it's serving.
+    let server_stopped = server_handle.clone().stopped();
+    lifecycle.add_with_stop(
+        "JSON-RPC server",
+        Layer::Gateway,
+        spawn_in_chain(async move {
+            server_stopped.await;
+            Ok(())
+        }),
+        move || {
+            // The server may have stopped already.
+            let _ = server_handle.stop();
+        },
+    );
+
+    // Event stream. Subscribed only when configured, so that an unread subscription doesn't hold
+    // notifications.
+    if let Some(event_stream_config) = config.event_stream.clone() {
+        let event_stream = run_event_stream(
+            event_stream_config,
+            config.rpc.chain_id.clone(),
+            storage_reader.clone(),
+            shared_state.sync_notifier.subscribe(),
+        );
+        lifecycle.add(
+            "Event stream",
+            Layer::Gateway,
+            spawn_in_chain(async move { Ok(event_stream.await?) }),
+        );
+    }
+
+    // Webhook notifier, also subscribed only when configured.
+    if let Some(webhook_notifier_config) = config.webhook_notifier.clone() {
+        let webhook_notifier =
+            run_webhook_notifier(webhook_notifier_config, shared_state.sync_notifier.subscribe());
+        lifecycle.add(
+            "Webhook notifier",
+            Layer::Gateway,
+            spawn_in_chain(async move { Ok(webhook_notifier.await?) }),
+        );
+    }
 
     // P2P network.
-    let network_future = run_network(config.network.clone(), storage_reader.clone());
-    let network_handle = tokio::spawn(network_future);
+    let network_future = run_network(
+        config.network.clone(),
+        storage_reader.clone(),
+        p2p_external_addresses,
+        source_reports.clone(),
+    );
+    lifecycle.add("Network", Layer::Sync, spawn_in_chain(network_future));
 
     // Sync task.
     let sync_future = run_sync(
         config,
-        shared_highest_block,
-        pending_data,
-        pending_classes,
+        shared_state,
+        disk_space,
+        source_reports,
+        maintenance_requests,
         storage_reader.clone(),
         storage_writer,
     );
-    let sync_handle = tokio::spawn(sync_future);
-
-    tokio::select! {
-        res = storage_metrics_handle => {
-            error!("collecting storage metrics stopped.");
-            res?
-        }
-        res = server_handle_future => {
-            error!("RPC server stopped.");
-            res?
-        }
-        res = monitoring_server_handle => {
-            error!("Monitoring server stopped.");
-            res??
-        }
-        res = sync_handle => {
-            error!("Sync stopped.");
-            res??
-        }
-        res = network_handle => {
-            error!("Network stopped.");
-            res?
-        }
+    let sync_handle = match sync_runtime {
+        Some(sync_runtime) => sync_runtime.spawn(sync_future.in_current_span()),
+        None => spawn_in_chain(sync_future),
     };
-    error!("Task ended with unexpected Ok.");
-    return Ok(());
+    // The sync writes to the storage in transactions, so aborting it never leaves a partial write.
+    lifecycle.add("Sync", Layer::Sync, sync_handle);
+
+    let result = lifecycle.run(shutdown_signal).await;
+    // The components that read the storage stopped, so this closes it.
+    drop(storage_reader);
+    info!(phase = ?Phase::Stop, "Closed the storage.");
+    return result;
 
     async fn run_sync(
         config: NodeConfig,
-        shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
-        pending_data: Arc<RwLock<PendingData>>,
-        pending_classes: Arc<RwLock<PendingClasses>>,
+        shared_state: RpcSharedState,
+        disk_space: Arc<DiskSpaceStatus>,
+        source_reports: Arc<SourceReports>,
+        maintenance_requests: Arc<MaintenanceRequests>,
         storage_reader: StorageReader,
-        storage_writer: StorageWriter,
-    ) -> Result<(), StateSyncError> {
-        let Some(sync_config) = config.sync else { return pending().await };
-        let central_source =
-            CentralSource::new(config.central.clone(), VERSION_FULL, storage_reader.clone())
-                .map_err(CentralError::ClientCreation)?;
+        mut storage_writer: StorageWriter,
+    ) -> Result<(), NodeError> {
+        let Some(sync_config) = config.sync else {
+            // Without the sync, the reports of the network are recorded and the maintenance
+            // requests are handled here.
+            loop {
+                tokio::select! {
+                    reports = source_reports.next_reports() => {
+                        store_source_reports(&mut storage_writer, reports)?;
+                    }
+                    request = maintenance_requests.next_request() => {
+                        prune_state_history(&mut storage_writer, request);
+                    }
+                }
+            }
+        };
+        let central_source = CentralSource::new(
+            config.central.clone(),
+            VERSION_FULL,
+            storage_reader.clone(),
+            source_reports.clone(),
+        )
+        .map_err(|e| StateSyncError::from(CentralError::ClientCreation(e)))?;
         let pending_source = PendingSource::new(config.central, VERSION_FULL)
-            .map_err(CentralError::ClientCreation)?;
-        let base_layer_source = EthereumBaseLayerSource::new(config.base_layer)
-            .map_err(|e| BaseLayerSourceError::BaseLayerSourceCreationError(e.to_string()))?;
+            .map_err(|e| StateSyncError::from(CentralError::ClientCreation(e)))?;
+        let base_layer_source = EthereumBaseLayerSource::new(config.base_layer).map_err(|e| {
+            StateSyncError::from(BaseLayerSourceError::BaseLayerSourceCreationError(e.to_string()))
+        })?;
+        let RpcSharedState {
+            shared_highest_block,
+            pending_data,
+            pending_classes,
+            rpc_load,
+            sync_notifier,
+            recent_state,
+            deferred_class_requests,
+        } = shared_state;
         let mut sync = StateSync::new(
             sync_config,
             shared_highest_block,
@@ -149,18 +322,53 @@ async fn run_threads(config: NodeConfig) -> anyhow::Result<()> {
             central_source,
             pending_source,
             base_layer_source,
+            rpc_load,
+            sync_notifier,
+            disk_space,
+            recent_state,
+            deferred_class_requests,
+            source_reports,
+            maintenance_requests,
             storage_reader.clone(),
             storage_writer,
         );
-        sync.run().await
+        // The genesis state is only needed until block 0 is stored, so the file isn't required
+        // afterwards.
+        if let Some(genesis_config) = config.genesis {
+            if storage_reader.begin_ro_txn()?.get_state_marker()? == BlockNumber(0) {
+                info!("Loading the genesis state from {:?}.", genesis_config.state_path);
+                sync = sync.with_genesis_state(load_genesis_state(&genesis_config.state_path)?);
+            }
+        }
+        // The sync is restarted by its supervisor after recoverable errors.
+        let mut supervisor = SyncSupervisor::new(config.sync_supervisor);
+        loop {
+            let Err(err) = sync.run_until_error().await else {
+                unreachable!("Sync should either return with an error or continue forever.")
+            };
+            supervisor.handle_error(err.into()).await?;
+        }
     }
 }
 
-async fn run_network(config: Option<NetworkConfig>, storage_reader: StorageReader) {
+async fn run_network(
+    config: Option<NetworkConfig>,
+    storage_reader: StorageReader,
+    external_addresses: Arc<ExternalAddresses>,
+    source_reports: Arc<SourceReports>,
+) -> Result<(), NodeError> {
     let Some(network_config) = config else { return pending().await };
-    let network_manager =
-        network_manager::NetworkManager::new(network_config.clone(), storage_reader.clone());
-    network_manager.run().await
+    let mut network_manager = network_manager::NetworkManager::new(
+        network_config.clone(),
+        storage_reader.clone(),
+        external_addresses,
+        source_reports,
+    )?;
+    network_manager
+        .dial_bootnodes(&network_config.bootnodes, network_config.bootnode_dns_domain.as_deref())
+        .await;
+    network_manager.run().await;
+    Ok(())
 }
 
 // TODO(yair): add dynamic level filtering.
@@ -173,14 +381,26 @@ fn configure_tracing() {
 
     // This sets a single subscriber to all of the threads. We may want to implement different
     // subscriber for some threads and use set_global_default instead of init.
-    tracing_subscriber::registry().with(fmt_layer).with(level_filter_layer).init();
+    tracing_subscriber::registry()
+        .with(ChainSpanLayer)
+        .with(fmt_layer)
+        .with(level_filter_layer)
+        .init();
+}
+
+// Spawns a component of the chain in the current span, which is the span of the chain, so that
+// the logs and the metrics of the component are labeled with its chain.
+fn spawn_in_chain<T: Send + 'static>(
+    future: impl Future<Output = T> + Send + 'static,
+) -> JoinHandle<T> {
+    tokio::spawn(future.in_current_span())
 }
 
 fn spawn_storage_metrics_collector(
     storage_reader: StorageReader,
     update_interval: Duration,
-) -> JoinHandle<()> {
-    tokio::spawn(
+) -> JoinHandle<Result<(), NodeError>> {
+    spawn_in_chain(
         async move {
             loop {
                 if let Err(error) = update_storage_metrics(&storage_reader) {
@@ -193,21 +413,69 @@ fn spawn_storage_metrics_collector(
     )
 }
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    let config = NodeConfig::load_and_process(args().collect());
-    if let Err(ConfigError::CommandInput(clap_err)) = config {
+fn main() -> Result<(), NodeError> {
+    let args: Vec<String> = args().collect();
+    if is_offline_command(&args, &DB_INSPECT_ARGS) {
+        return Ok(run_db_inspect(args)?);
+    }
+    if is_offline_command(&args, &DB_SCHEMA_ARGS) {
+        return Ok(run_db_schema(args)?);
+    }
+    if is_offline_command(&args, &REVERT_ARGS) {
+        return Ok(run_revert(args)?);
+    }
+    if is_offline_command(&args, &REBUILD_INDEX_ARGS) {
+        return Ok(run_rebuild_index(args)?);
+    }
+
+    let configs = load_chain_configs(args);
+    if let Err(MultiChainError::Config(ConfigError::CommandInput(clap_err))) = configs {
         clap_err.exit();
     }
 
     configure_tracing();
 
-    let config = config?;
-    if let Err(errors) = config_validate(&config) {
-        error!("{}", errors);
-        exit(1);
+    let configs = configs?;
+    for config in &configs {
+        if let Err(errors) = config_validate(config) {
+            error!("{}", errors);
+            exit(1);
+        }
     }
 
     info!("Booting up.");
-    run_threads(config).await
+    // The chains share the runtimes, which are configured by the first chain.
+    let main_runtime = configs[0].runtime.build_main_runtime()?;
+    let sync_runtime = configs[0].runtime.build_sync_runtime()?;
+    let sync_runtime_handle = sync_runtime.as_ref().map(|runtime| runtime.handle().clone());
+    main_runtime.block_on(run_chains(configs, sync_runtime_handle))
+}
+
+// Runs the node of every chain until the process is asked to terminate or until one of them stops,
+// which shuts down the rest too.
+async fn run_chains(
+    configs: Vec<NodeConfig>,
+    sync_runtime: Option<Handle>,
+) -> Result<(), NodeError> {
+    let (chain_stopped_sender, chain_stopped_receiver) = watch::channel(false);
+    let chains = configs.into_iter().map(|config| {
+        // The metrics are labeled by the chain span, so it's enabled at every log level.
+        let span = error_span!("chain", chain_id = %config.rpc.chain_id.0);
+        let chain_stopped_sender = &chain_stopped_sender;
+        let mut chain_stopped_receiver = chain_stopped_receiver.clone();
+        let shutdown_signal = async move {
+            tokio::select! {
+                _ = termination_signal() => {},
+                _ = chain_stopped_receiver.changed() => {},
+            }
+        };
+        let sync_runtime = sync_runtime.clone();
+        async move {
+            let result = run_threads(config, sync_runtime, shutdown_signal).await;
+            chain_stopped_sender.send_replace(true);
+            result
+        }
+        .instrument(span)
+    });
+    join_all(chains).await.into_iter().collect()
 }
@@ -25,7 +25,7 @@ async fn run_threads_stop() {
 
     // Error when not supplying legal central URL.
     config.central.url = "_not_legal_url".to_string();
-    let error = run_threads(config).await.expect_err("Should be an error.");
+    let error = run_threads(config, None).await.expect_err("Should be an error.");
     assert_eq!("relative URL without a base", error.to_string());
 }
 
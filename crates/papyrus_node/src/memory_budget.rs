@@ -0,0 +1,98 @@
+//! Configuration of the memory that the in-memory caches of the node may take, so that the node
+//! can run on machines with little memory without being killed.
+#[cfg(test)]
+#[path = "memory_budget_test.rs"]
+mod memory_budget_test;
+
+use std::collections::BTreeMap;
+
+use papyrus_common::memory_budget::MemoryBudget;
+use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use serde::{Deserialize, Serialize};
+use validator::{Validate, ValidationError};
+
+/// A global memory budget for the caches of the node, split between them by percentages.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Validate)]
+#[validate(schema(function = "validate_memory_budget_config"))]
+pub struct MemoryBudgetConfig {
+    /// The memory, in bytes, that the caches may take together. 0 means that the caches are
+    /// bounded only by their number of entries.
+    pub max_bytes: usize,
+    /// The percentage of the budget for the state writes of the recent blocks.
+    pub recent_state_percentage: u8,
+    /// The percentage of the budget for the class cache shared by the storage, the sync and the
+    /// execution.
+    pub class_caches_percentage: u8,
+    /// The percentage of the budget for the classes of the pending block.
+    pub pending_data_percentage: u8,
+}
+
+impl Default for MemoryBudgetConfig {
+    fn default() -> Self {
+        MemoryBudgetConfig {
+            max_bytes: 0,
+            recent_state_percentage: 30,
+            class_caches_percentage: 50,
+            pending_data_percentage: 20,
+        }
+    }
+}
+
+impl SerializeConfig for MemoryBudgetConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([
+            ser_param(
+                "max_bytes",
+                &self.max_bytes,
+                "The memory, in bytes, that the caches of the node may take together. 0 means \
+                 that the caches are bounded only by their number of entries.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "recent_state_percentage",
+                &self.recent_state_percentage,
+                "The percentage of the memory budget for the state writes of the recent blocks.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "class_caches_percentage",
+                &self.class_caches_percentage,
+                "The percentage of the memory budget for the class cache shared by the storage, \
+                 the sync and the execution.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "pending_data_percentage",
+                &self.pending_data_percentage,
+                "The percentage of the memory budget for the classes of the pending block.",
+                ParamPrivacyInput::Public,
+            ),
+        ])
+    }
+}
+
+impl MemoryBudgetConfig {
+    /// Splits the budget between the caches.
+    pub fn budget(&self) -> MemoryBudget {
+        if self.max_bytes == 0 {
+            return MemoryBudget::default();
+        }
+        let share = |percentage: u8| Some(self.max_bytes / 100 * usize::from(percentage));
+        MemoryBudget {
+            recent_state: share(self.recent_state_percentage),
+            class_caches: share(self.class_caches_percentage),
+            pending_data: share(self.pending_data_percentage),
+        }
+    }
+}
+
+fn validate_memory_budget_config(config: &MemoryBudgetConfig) -> Result<(), ValidationError> {
+    let total_percentage = u16::from(config.recent_state_percentage)
+        + u16::from(config.class_caches_percentage)
+        + u16::from(config.pending_data_percentage);
+    if total_percentage > 100 {
+        return Err(ValidationError::new("The memory budget percentages sum to more than 100."));
+    }
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use papyrus_common::memory_budget::MemoryBudget;
+use pretty_assertions::assert_eq;
+use validator::Validate;
+
+use crate::memory_budget::MemoryBudgetConfig;
+
+#[test]
+fn budget_split_by_percentages() {
+    let config = MemoryBudgetConfig { max_bytes: 1000, ..Default::default() };
+    assert_eq!(
+        config.budget(),
+        MemoryBudget { recent_state: Some(300), class_caches: Some(500), pending_data: Some(200) }
+    );
+}
+
+#[test]
+fn no_budget_by_default() {
+    assert_eq!(MemoryBudgetConfig::default().budget(), MemoryBudget::default());
+}
+
+#[test]
+fn percentages_must_not_exceed_budget() {
+    let config = MemoryBudgetConfig { pending_data_percentage: 21, ..Default::default() };
+    assert!(config.validate().is_err());
+    assert!(MemoryBudgetConfig::default().validate().is_ok());
+}
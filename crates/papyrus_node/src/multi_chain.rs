@@ -0,0 +1,132 @@
+//! The `multi_chain` command of the node, which runs the nodes of several chains in one process,
+//! sharing its runtimes. Each chain is given its configuration arguments after a `--` separator,
+//! for example: `papyrus_node multi_chain -- --config_file mainnet.json -- --config_file
+//! sepolia.json`. The runtimes are configured by the first chain. The metrics of the process are
+//! collected by at most one chain, and the metrics of every chain are labeled with its chain id.
+#[cfg(test)]
+#[path = "multi_chain_test.rs"]
+mod multi_chain_test;
+
+use papyrus_config::ConfigError;
+
+use crate::config::NodeConfig;
+use crate::offline_storage::is_offline_command;
+
+/// The arguments that run the `multi_chain` command, following the name of the executable.
+pub const MULTI_CHAIN_ARGS: [&str; 1] = ["multi_chain"];
+
+// Separates the configuration arguments of the chains.
+const CHAIN_ARGS_SEPARATOR: &str = "--";
+
+#[derive(thiserror::Error, Debug)]
+pub enum MultiChainError {
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    #[error(
+        "No chain was given. The configuration arguments of every chain follow a `--` separator."
+    )]
+    NoChains,
+    #[error("Chains {first} and {second} have the same {resource}: {value}.")]
+    SharedResource { first: String, second: String, resource: &'static str, value: String },
+    #[error(
+        "Chains {first} and {second} both collect metrics. The metrics of all the chains are \
+         collected for the whole process, labeled by their chain, so only one chain may collect \
+         them."
+    )]
+    MetricsCollectedTwice { first: String, second: String },
+}
+
+/// Loads the configuration of every chain given the command line arguments of the node
+/// executable. Without the `multi_chain` command, the arguments configure a single chain.
+pub fn load_chain_configs(args: Vec<String>) -> Result<Vec<NodeConfig>, MultiChainError> {
+    if !is_offline_command(&args, &MULTI_CHAIN_ARGS) {
+        return Ok(vec![NodeConfig::load_and_process(args)?]);
+    }
+    let configs = split_chain_args(&args)
+        .into_iter()
+        .map(NodeConfig::load_and_process)
+        .collect::<Result<Vec<_>, _>>()?;
+    if configs.is_empty() {
+        return Err(MultiChainError::NoChains);
+    }
+    verify_chains_separated(&configs)?;
+    Ok(configs)
+}
+
+// Splits the arguments of the `multi_chain` command into the configuration arguments of every
+// chain. Each starts with the name of the executable, as clap expects.
+fn split_chain_args(args: &[String]) -> Vec<Vec<String>> {
+    let executable = args.first().cloned().unwrap_or_default();
+    let command_args = args.get(MULTI_CHAIN_ARGS.len() + 1..).unwrap_or_default();
+    command_args
+        .split(|arg| arg == CHAIN_ARGS_SEPARATOR)
+        // The arguments before the first separator don't belong to any chain.
+        .skip(1)
+        .map(|chain_args| std::iter::once(executable.clone()).chain(chain_args.to_vec()).collect())
+        .collect()
+}
+
+// Verifies that no two chains use the same data directory or port, and that the metrics, which
+// are process-wide, are collected by at most one chain.
+fn verify_chains_separated(configs: &[NodeConfig]) -> Result<(), MultiChainError> {
+    for (i, first) in configs.iter().enumerate() {
+        for second in &configs[i + 1..] {
+            let shared_resource =
+                |resource: &'static str, value: String| MultiChainError::SharedResource {
+                    first: first.rpc.chain_id.0.clone(),
+                    second: second.rpc.chain_id.0.clone(),
+                    resource,
+                    value,
+                };
+            let (first_path, second_path) =
+                (first.storage.db_config.path(), second.storage.db_config.path());
+            if first_path == second_path {
+                return Err(shared_resource("data directory", first_path.display().to_string()));
+            }
+            if let Some(address) =
+                shared_address(&first.rpc.server_address, &second.rpc.server_address)
+            {
+                return Err(shared_resource("RPC address", address.to_owned()));
+            }
+            if let (Some(first_socket), Some(second_socket)) =
+                (&first.rpc.unix_socket_path, &second.rpc.unix_socket_path)
+            {
+                if first_socket == second_socket {
+                    return Err(shared_resource(
+                        "RPC Unix domain socket",
+                        first_socket.display().to_string(),
+                    ));
+                }
+            }
+            if let Some(address) = shared_address(
+                &first.monitoring_gateway.server_address,
+                &second.monitoring_gateway.server_address,
+            ) {
+                return Err(shared_resource("monitoring gateway address", address.to_owned()));
+            }
+            if let (Some(first_network), Some(second_network)) = (&first.network, &second.network) {
+                let first_ports = [first_network.tcp_port, first_network.quic_port];
+                for port in [second_network.tcp_port, second_network.quic_port] {
+                    if first_ports.contains(&port) {
+                        return Err(shared_resource("network port", port.to_string()));
+                    }
+                }
+            }
+            if first.monitoring_gateway.collect_metrics && second.monitoring_gateway.collect_metrics
+            {
+                return Err(MultiChainError::MetricsCollectedTwice {
+                    first: first.rpc.chain_id.0.clone(),
+                    second: second.rpc.chain_id.0.clone(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+// Returns an address that is in both whitespace separated lists of server addresses, if any.
+fn shared_address<'a>(first: &'a str, second: &str) -> Option<&'a str> {
+    first
+        .split_whitespace()
+        .find(|address| second.split_whitespace().any(|other| other == *address))
+}
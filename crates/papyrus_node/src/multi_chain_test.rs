@@ -0,0 +1,75 @@
+use pretty_assertions::assert_eq;
+use starknet_api::core::ChainId;
+
+use crate::config::NodeConfig;
+use crate::multi_chain::{split_chain_args, verify_chains_separated, MultiChainError};
+
+fn to_args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+fn chain_config(chain_id: &str, port: u16) -> NodeConfig {
+    let mut config = NodeConfig::default();
+    config.rpc.chain_id = ChainId(chain_id.to_owned());
+    config.storage.db_config.chain_id = ChainId(chain_id.to_owned());
+    config.rpc.server_address = format!("0.0.0.0:{port}");
+    config.monitoring_gateway.server_address = format!("0.0.0.0:{}", port + 1);
+    config
+}
+
+#[test]
+fn splits_chain_args() {
+    let args = to_args(&[
+        "papyrus_node",
+        "multi_chain",
+        "--",
+        "--config_file",
+        "mainnet.json",
+        "--",
+        "--config_file",
+        "sepolia.json",
+    ]);
+    assert_eq!(
+        split_chain_args(&args),
+        vec![
+            to_args(&["papyrus_node", "--config_file", "mainnet.json"]),
+            to_args(&["papyrus_node", "--config_file", "sepolia.json"]),
+        ]
+    );
+
+    // Without separators, no chain is given.
+    assert!(split_chain_args(&to_args(&["papyrus_node", "multi_chain"])).is_empty());
+}
+
+#[test]
+fn separated_chains() {
+    let mut configs = vec![chain_config("SN_MAIN", 8080), chain_config("SN_SEPOLIA", 8090)];
+    // The data directories differ by the chain id, even under the same path prefix.
+    verify_chains_separated(&configs).unwrap();
+
+    // A chain that listens on several addresses conflicts with a chain that listens on any of them.
+    configs[1].rpc.server_address = format!("[::]:8090 {}", configs[0].rpc.server_address);
+    assert!(matches!(
+        verify_chains_separated(&configs),
+        Err(MultiChainError::SharedResource { resource: "RPC address", .. })
+    ));
+
+    let configs = vec![chain_config("SN_MAIN", 8080), chain_config("SN_MAIN", 8090)];
+    assert!(matches!(
+        verify_chains_separated(&configs),
+        Err(MultiChainError::SharedResource { resource: "data directory", .. })
+    ));
+}
+
+#[test]
+fn metrics_collected_by_one_chain() {
+    let mut configs = vec![chain_config("SN_MAIN", 8080), chain_config("SN_SEPOLIA", 8090)];
+    configs[0].monitoring_gateway.collect_metrics = true;
+    verify_chains_separated(&configs).unwrap();
+
+    configs[1].monitoring_gateway.collect_metrics = true;
+    assert!(matches!(
+        verify_chains_separated(&configs),
+        Err(MultiChainError::MetricsCollectedTwice { .. })
+    ));
+}
@@ -0,0 +1,58 @@
+//! Registration of the runs of the node in its identity, so that fleet operators can correlate the
+//! metrics and logs of an instance across restarts. The id of the node is a random UUID generated
+//! at its first boot.
+#[cfg(test)]
+#[path = "node_identity_test.rs"]
+mod node_identity_test;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use papyrus_common::hashing::starknet_keccak;
+use papyrus_storage::node_identity::{
+    NodeIdentity,
+    NodeIdentityStorageReader,
+    NodeIdentityStorageWriter,
+    NodeRun,
+};
+use papyrus_storage::{StorageReader, StorageResult, StorageWriter};
+
+use crate::version::{BUILD_COMMIT, VERSION_FULL};
+
+/// Records the start of a run of the node and returns its identity. The node gets an id if this
+/// is its first run.
+pub fn register_node_run(
+    storage_reader: &StorageReader,
+    storage_writer: &mut StorageWriter,
+    public_config_presentation: &serde_json::Value,
+) -> StorageResult<NodeIdentity> {
+    let run = NodeRun {
+        start_timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("The current time should be after the unix epoch.")
+            .as_secs(),
+        version: VERSION_FULL.to_owned(),
+        build_commit: BUILD_COMMIT.to_owned(),
+        config_hash: config_hash(public_config_presentation),
+    };
+    storage_writer.begin_rw_txn()?.record_node_run(&new_node_id(), run)?.commit()?;
+    Ok(storage_reader
+        .begin_ro_txn()?
+        .get_node_identity()?
+        .expect("The identity should exist after recording a run."))
+}
+
+// The hash of the config only depends on its public parameters, so that it can be shown without
+// revealing the private ones.
+fn config_hash(public_config_presentation: &serde_json::Value) -> String {
+    starknet_keccak(public_config_presentation.to_string().as_bytes()).to_string()
+}
+
+// A random (version 4) UUID.
+fn new_node_id() -> String {
+    let mut bytes: [u8; 16] = rand::random();
+    // Set the version and the variant bits.
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = hex::encode(bytes);
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
@@ -0,0 +1,33 @@
+use papyrus_storage::test_utils::get_test_storage;
+use pretty_assertions::{assert_eq, assert_ne};
+use serde_json::json;
+
+use crate::node_identity::{config_hash, new_node_id, register_node_run};
+use crate::version::{BUILD_COMMIT, VERSION_FULL};
+
+#[test]
+fn node_id_is_stable_across_runs() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let config = json!({"rpc.chain_id": "SN_MAIN"});
+
+    let first = register_node_run(&storage_reader, &mut storage_writer, &config).unwrap();
+    assert_eq!(first.runs.len(), 1);
+    assert_eq!(first.runs[0].version, VERSION_FULL);
+    assert_eq!(first.runs[0].build_commit, BUILD_COMMIT);
+    assert_eq!(first.runs[0].config_hash, config_hash(&config));
+
+    let other_config = json!({"rpc.chain_id": "SN_GOERLI"});
+    let second = register_node_run(&storage_reader, &mut storage_writer, &other_config).unwrap();
+    assert_eq!(second.node_id, first.node_id);
+    assert_eq!(second.runs.len(), 2);
+    assert_ne!(second.runs[1].config_hash, second.runs[0].config_hash);
+}
+
+#[test]
+fn node_id_is_a_uuid() {
+    let node_id = new_node_id();
+    let groups = node_id.split('-').map(str::len).collect::<Vec<_>>();
+    assert_eq!(groups, vec![8, 4, 4, 4, 12]);
+    assert_eq!(node_id.chars().nth(14), Some('4'));
+    assert_ne!(new_node_id(), node_id);
+}
@@ -0,0 +1,59 @@
+//! Helpers for the commands of the node that work on its storage while the node isn't running.
+//! The arguments of such a command are followed by a `--` separator and the configuration
+//! arguments of the node, which are used to locate and open the storage exactly as the node does.
+#[cfg(test)]
+#[path = "offline_storage_test.rs"]
+mod offline_storage_test;
+
+use papyrus_config::ConfigError;
+use papyrus_storage::{open_storage, StorageError, StorageReader, StorageWriter};
+
+use crate::config::NodeConfig;
+
+// Separates the arguments of the command from the configuration arguments of the node.
+const NODE_ARGS_SEPARATOR: &str = "--";
+
+/// Returns whether the command line arguments run the command with the given name, which may
+/// consist of several words.
+pub fn is_offline_command(args: &[String], command: &[&str]) -> bool {
+    args.get(1..=command.len())
+        .is_some_and(|args| args.iter().map(String::as_str).eq(command.iter().copied()))
+}
+
+// Splits the command line arguments of the node executable that run the given command into the
+// arguments of the command and the configuration arguments of the node. Both start with the name
+// of the executable, as clap expects.
+pub(crate) fn split_offline_command_args(
+    args: &[String],
+    command: &[&str],
+) -> (Vec<String>, Vec<String>) {
+    let (command_args, node_args) = match args.iter().position(|arg| arg == NODE_ARGS_SEPARATOR) {
+        Some(separator) => (&args[..separator], &args[separator + 1..]),
+        None => (args, &args[args.len()..]),
+    };
+    let executable = args.first().cloned().unwrap_or_default();
+    (
+        std::iter::once(executable.clone())
+            .chain(command_args.iter().skip(command.len() + 1).cloned())
+            .collect(),
+        std::iter::once(executable).chain(node_args.iter().cloned()).collect(),
+    )
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum OfflineStorageError {
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+}
+
+// Opens the storage of the node with the given configuration arguments.
+pub(crate) fn open_node_storage(
+    node_args: Vec<String>,
+) -> Result<(StorageReader, StorageWriter), OfflineStorageError> {
+    let mut config = NodeConfig::load_and_process(node_args)?;
+    // Never create a new storage when the configuration points to the wrong path.
+    config.storage.db_config.enforce_file_exists = true;
+    Ok(open_storage(config.storage)?)
+}
@@ -0,0 +1,33 @@
+use crate::offline_storage::{is_offline_command, split_offline_command_args};
+
+fn to_args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[test]
+fn detects_offline_command() {
+    let command = ["db", "inspect"];
+    assert!(is_offline_command(&to_args(&["papyrus_node", "db", "inspect", "markers"]), &command));
+    assert!(!is_offline_command(&to_args(&["papyrus_node", "--config_file", "a.json"]), &command));
+    assert!(!is_offline_command(&to_args(&["papyrus_node", "db"]), &command));
+}
+
+#[test]
+fn splits_node_args() {
+    let command = ["revert"];
+    let args = to_args(&["papyrus_node", "revert", "--to", "5", "--", "--config_file", "a.json"]);
+    assert_eq!(
+        split_offline_command_args(&args, &command),
+        (
+            to_args(&["papyrus_node", "--to", "5"]),
+            to_args(&["papyrus_node", "--config_file", "a.json"])
+        )
+    );
+
+    // Without node arguments, the node uses its default configuration.
+    let args = to_args(&["papyrus_node", "revert", "--to", "5"]);
+    assert_eq!(
+        split_offline_command_args(&args, &command),
+        (to_args(&["papyrus_node", "--to", "5"]), to_args(&["papyrus_node"]))
+    );
+}
@@ -0,0 +1,53 @@
+//! The `rebuild-index` command of the node, which rebuilds a secondary index of the storage from
+//! the tables it's derived from while the node isn't running, so that an index that was added or
+//! fixed in a new version of the node doesn't require syncing again. For example:
+//! `papyrus_node rebuild-index transaction_hash_to_idx -- --config_file my_config.json`.
+#[cfg(test)]
+#[path = "rebuild_index_test.rs"]
+mod rebuild_index_test;
+
+use clap::builder::PossibleValuesParser;
+use clap::{Arg, Command};
+use papyrus_storage::secondary_index::SecondaryIndexKind;
+
+use crate::offline_storage::{open_node_storage, split_offline_command_args, OfflineStorageError};
+
+/// The arguments that run the `rebuild-index` command, following the name of the executable.
+pub const REBUILD_INDEX_ARGS: [&str; 1] = ["rebuild-index"];
+
+/// The command line interface of the `rebuild-index` command.
+pub fn rebuild_index_command() -> Command {
+    Command::new("rebuild-index")
+        .about(
+            "Rebuilds a secondary index of the storage of a node that isn't running. A rebuild \
+             that is interrupted is resumed the next time the storage is opened. The \
+             configuration arguments of the node follow a `--` separator.",
+        )
+        .arg(
+            Arg::new("name")
+                .required(true)
+                .value_parser(PossibleValuesParser::new(
+                    SecondaryIndexKind::ALL.iter().map(|index| index.name()),
+                ))
+                .help("The name of the table of the index."),
+        )
+}
+
+/// Runs the `rebuild-index` command given all the command line arguments of the node executable.
+pub fn run_rebuild_index(args: Vec<String>) -> Result<(), OfflineStorageError> {
+    let (command_args, node_args) = split_offline_command_args(&args, &REBUILD_INDEX_ARGS);
+    let matches = rebuild_index_command().get_matches_from(command_args);
+    let index = index_by_name(matches.get_one::<String>("name").expect("Argument is required."));
+    let (_storage_reader, mut storage_writer) = open_node_storage(node_args)?;
+    storage_writer.rebuild_secondary_index(index)?;
+    println!("Rebuilt the index {}.", index.name());
+    Ok(())
+}
+
+// The name is one of the possible values of the command, so it's the name of an index.
+fn index_by_name(name: &str) -> SecondaryIndexKind {
+    SecondaryIndexKind::ALL
+        .into_iter()
+        .find(|index| index.name() == name)
+        .expect("The name should be validated by the command.")
+}
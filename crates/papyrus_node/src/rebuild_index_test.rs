@@ -0,0 +1,41 @@
+use papyrus_storage::header::{HeaderStorageReader, HeaderStorageWriter};
+use papyrus_storage::secondary_index::SecondaryIndexKind;
+use papyrus_storage::test_utils::get_test_storage;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockHash, BlockHeader, BlockNumber};
+use starknet_api::hash::StarkHash;
+
+use crate::rebuild_index::{index_by_name, rebuild_index_command};
+
+#[test]
+fn command_accepts_only_index_names() {
+    for index in SecondaryIndexKind::ALL {
+        let matches =
+            rebuild_index_command().try_get_matches_from(["rebuild-index", index.name()]).unwrap();
+        let name = matches.get_one::<String>("name").unwrap();
+        assert_eq!(index_by_name(name), index);
+    }
+    assert!(rebuild_index_command().try_get_matches_from(["rebuild-index", "headers"]).is_err());
+    assert!(rebuild_index_command().try_get_matches_from(["rebuild-index"]).is_err());
+}
+
+#[test]
+fn rebuilt_index_answers_lookups() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let header = BlockHeader {
+        block_number: BlockNumber(0),
+        block_hash: BlockHash(StarkHash::from(1_u64)),
+        ..Default::default()
+    };
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &header)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    storage_writer.rebuild_secondary_index(index_by_name("block_hash_to_number")).unwrap();
+    let txn = storage_reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_block_number_by_hash(&header.block_hash).unwrap(), Some(BlockNumber(0)));
+}
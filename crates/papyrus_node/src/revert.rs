@@ -0,0 +1,74 @@
+//! The `revert` command of the node, which reverts the blocks of the storage from a given block
+//! onwards while the node isn't running, for recovering from bad data or testing the handling of
+//! reorgs. For example: `papyrus_node revert --to 100 -- --config_file my_config.json`.
+#[cfg(test)]
+#[path = "revert_test.rs"]
+mod revert_test;
+
+use clap::{value_parser, Arg, Command};
+use papyrus_storage::base_layer::BaseLayerStorageWriter;
+use papyrus_storage::body::BodyStorageWriter;
+use papyrus_storage::header::{HeaderStorageReader, HeaderStorageWriter};
+use papyrus_storage::state::StateStorageWriter;
+use papyrus_storage::{NodeMode, StorageReader, StorageResult, StorageWriter};
+use starknet_api::block::BlockNumber;
+
+use crate::offline_storage::{open_node_storage, split_offline_command_args, OfflineStorageError};
+
+/// The arguments that run the `revert` command, following the name of the executable.
+pub const REVERT_ARGS: [&str; 1] = ["revert"];
+
+/// The command line interface of the `revert` command.
+pub fn revert_command() -> Command {
+    Command::new("revert")
+        .about(
+            "Reverts the blocks of the storage of a node that isn't running. The configuration \
+             arguments of the node follow a `--` separator.",
+        )
+        .arg(
+            Arg::new("to")
+                .long("to")
+                .required(true)
+                .value_parser(value_parser!(u64))
+                .help("The first block to revert. The storage keeps only the blocks below it."),
+        )
+}
+
+/// Runs the `revert` command given all the command line arguments of the node executable.
+pub fn run_revert(args: Vec<String>) -> Result<(), OfflineStorageError> {
+    let (command_args, node_args) = split_offline_command_args(&args, &REVERT_ARGS);
+    let matches = revert_command().get_matches_from(command_args);
+    let first_reverted_block =
+        BlockNumber(*matches.get_one::<u64>("to").expect("Argument is required."));
+    let (storage_reader, mut storage_writer) = open_node_storage(node_args)?;
+    let header_marker = storage_reader.begin_ro_txn()?.get_header_marker()?;
+    if header_marker <= first_reverted_block {
+        println!("The storage has no blocks from block {first_reverted_block} onwards.");
+        return Ok(());
+    }
+    revert_blocks(&storage_reader, &mut storage_writer, first_reverted_block)?;
+    println!("Reverted blocks {first_reverted_block} to {}.", header_marker.0 - 1);
+    Ok(())
+}
+
+// Reverts the blocks from the given block up to the last block in the storage. Like the sync, the
+// blocks are reverted from the last one down, each in its own transaction, so that the storage is
+// consistent if the command is stopped in the middle.
+fn revert_blocks(
+    storage_reader: &StorageReader,
+    storage_writer: &mut StorageWriter,
+    first_reverted_block: BlockNumber,
+) -> StorageResult<()> {
+    let header_marker = storage_reader.begin_ro_txn()?.get_header_marker()?;
+    for block_number in (first_reverted_block.0..header_marker.0).rev().map(BlockNumber) {
+        let txn = storage_writer.begin_rw_txn()?.try_revert_base_layer_marker(block_number)?;
+        let (mut txn, reverted_header, _) = txn.revert_header(block_number)?;
+        // A light node has no body and state to revert.
+        if reverted_header.is_some() && storage_reader.get_node_mode() != NodeMode::Light {
+            txn = txn.revert_body(block_number)?.0;
+            txn = txn.revert_state_diff(block_number)?.0;
+        }
+        txn.commit()?;
+    }
+    Ok(())
+}
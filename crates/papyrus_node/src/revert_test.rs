@@ -0,0 +1,33 @@
+use papyrus_storage::header::{HeaderStorageReader, HeaderStorageWriter};
+use papyrus_storage::test_utils::get_test_storage;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockHash, BlockHeader, BlockNumber};
+use starknet_api::hash::StarkHash;
+
+use crate::revert::revert_blocks;
+
+#[test]
+fn revert_blocks_from_given_block() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let mut txn = storage_writer.begin_rw_txn().unwrap();
+    for i in 0..4_u64 {
+        let header = BlockHeader {
+            block_number: BlockNumber(i),
+            block_hash: BlockHash(StarkHash::from(i + 1)),
+            ..Default::default()
+        };
+        txn = txn.append_header(BlockNumber(i), &header).unwrap();
+    }
+    txn.commit().unwrap();
+
+    revert_blocks(&storage_reader, &mut storage_writer, BlockNumber(2)).unwrap();
+    let txn = storage_reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_header_marker().unwrap(), BlockNumber(2));
+    assert!(txn.get_block_header(BlockNumber(1)).unwrap().is_some());
+    assert!(txn.get_block_header(BlockNumber(2)).unwrap().is_none());
+    drop(txn);
+
+    // Reverting from a block above the last block does nothing.
+    revert_blocks(&storage_reader, &mut storage_writer, BlockNumber(5)).unwrap();
+    assert_eq!(storage_reader.begin_ro_txn().unwrap().get_header_marker().unwrap(), BlockNumber(2));
+}
@@ -0,0 +1,90 @@
+#[cfg(test)]
+#[path = "runtime_test.rs"]
+mod runtime_test;
+
+use std::collections::BTreeMap;
+use std::io;
+
+use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use serde::{Deserialize, Serialize};
+use tokio::runtime::{Builder, Runtime};
+use validator::Validate;
+
+/// The threads layout of the tokio runtimes of the node.
+///
+/// By default all the components run on a single runtime. Giving the sync its own runtime
+/// guarantees it worker threads that heavy RPC traffic can't take over, and vice versa.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Validate)]
+pub struct RuntimeConfig {
+    /// The number of worker threads of the main runtime. 0 means a thread per CPU core.
+    pub worker_threads: usize,
+    /// The maximal number of threads of the blocking pool of each runtime, which runs the storage
+    /// reads and the serialization of large responses.
+    #[validate(range(min = 1))]
+    pub max_blocking_threads: usize,
+    /// The number of worker threads of a dedicated runtime for the sync. 0 means that the sync
+    /// runs on the main runtime.
+    pub sync_worker_threads: usize,
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        RuntimeConfig { worker_threads: 0, max_blocking_threads: 512, sync_worker_threads: 0 }
+    }
+}
+
+impl SerializeConfig for RuntimeConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([
+            ser_param(
+                "worker_threads",
+                &self.worker_threads,
+                "The number of worker threads of the main runtime. 0 means a thread per CPU core.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_blocking_threads",
+                &self.max_blocking_threads,
+                "The maximal number of threads of the blocking pool of each runtime.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "sync_worker_threads",
+                &self.sync_worker_threads,
+                "The number of worker threads of a dedicated runtime for the sync. 0 means that \
+                 the sync runs on the main runtime.",
+                ParamPrivacyInput::Public,
+            ),
+        ])
+    }
+}
+
+impl RuntimeConfig {
+    /// Builds the runtime that runs all the components of the node except a sync with a dedicated
+    /// runtime.
+    pub fn build_main_runtime(&self) -> io::Result<Runtime> {
+        self.build_runtime("papyrus-main", self.worker_threads)
+    }
+
+    /// Builds the dedicated runtime of the sync, or returns None if the sync runs on the main
+    /// runtime.
+    pub fn build_sync_runtime(&self) -> io::Result<Option<Runtime>> {
+        if self.sync_worker_threads == 0 {
+            return Ok(None);
+        }
+        self.build_runtime("papyrus-sync", self.sync_worker_threads).map(Some)
+    }
+
+    fn build_runtime(&self, thread_name: &str, worker_threads: usize) -> io::Result<Runtime> {
+        let mut builder = Builder::new_multi_thread();
+        builder
+            .enable_all()
+            .thread_name(thread_name)
+            .max_blocking_threads(self.max_blocking_threads);
+        if worker_threads > 0 {
+            builder.worker_threads(worker_threads);
+        }
+        builder.build()
+    }
+}
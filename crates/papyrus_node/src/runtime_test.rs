@@ -0,0 +1,31 @@
+use validator::Validate;
+
+use crate::runtime::RuntimeConfig;
+
+#[test]
+fn sync_runtime_only_with_dedicated_threads() {
+    let config = RuntimeConfig::default();
+    assert!(config.build_sync_runtime().unwrap().is_none());
+
+    let config = RuntimeConfig { sync_worker_threads: 2, ..Default::default() };
+    let sync_runtime = config.build_sync_runtime().unwrap().unwrap();
+    assert_eq!(sync_runtime.block_on(async { 1 + 1 }), 2);
+}
+
+#[test]
+fn main_runtime_with_fixed_number_of_threads() {
+    let config = RuntimeConfig { worker_threads: 1, ..Default::default() };
+    let main_runtime = config.build_main_runtime().unwrap();
+    let result = main_runtime.block_on(async {
+        tokio::task::spawn_blocking(|| std::thread::current().name().map(String::from))
+            .await
+            .unwrap()
+    });
+    assert_eq!(result.as_deref(), Some("papyrus-main"));
+}
+
+#[test]
+fn blocking_pool_must_not_be_empty() {
+    let config = RuntimeConfig { max_blocking_threads: 0, ..Default::default() };
+    assert!(config.validate().is_err());
+}
@@ -0,0 +1,146 @@
+//! Supervision of the sync task. The sync is restarted after recoverable errors, backing off
+//! between consecutive restarts, and the node stops only on fatal errors or, if the restarts are
+//! limited, once the sync keeps failing. Reorgs restart the sync right away and aren't counted as
+//! failures.
+#[cfg(test)]
+#[path = "sync_supervisor_test.rs"]
+mod sync_supervisor_test;
+
+use std::cmp::min;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use papyrus_common::metrics::PAPYRUS_SYNC_RESTARTS;
+use papyrus_config::converters::deserialize_seconds_to_duration;
+use papyrus_config::dumping::{ser_optional_param, ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::error::NodeError;
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub struct SyncSupervisorConfig {
+    /// The number of consecutive restarts after which the node stops. None means that the sync is
+    /// restarted as long as its errors are recoverable.
+    pub max_restarts: Option<u64>,
+    #[serde(deserialize_with = "deserialize_seconds_to_duration")]
+    pub initial_backoff: Duration,
+    #[serde(deserialize_with = "deserialize_seconds_to_duration")]
+    pub max_backoff: Duration,
+    /// A sync that ran for this long before failing is considered healthy, so the restarts are no
+    /// longer consecutive.
+    #[serde(deserialize_with = "deserialize_seconds_to_duration")]
+    pub healthy_run_duration: Duration,
+}
+
+impl Default for SyncSupervisorConfig {
+    fn default() -> Self {
+        SyncSupervisorConfig {
+            max_restarts: None,
+            initial_backoff: Duration::from_secs(3),
+            max_backoff: Duration::from_secs(300),
+            healthy_run_duration: Duration::from_secs(600),
+        }
+    }
+}
+
+impl SerializeConfig for SyncSupervisorConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        let mut config = BTreeMap::from_iter([
+            ser_param(
+                "initial_backoff",
+                &self.initial_backoff.as_secs(),
+                "Time in seconds to wait before the first of consecutive restarts of the sync. \
+                 The waiting time doubles on every consecutive restart.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_backoff",
+                &self.max_backoff.as_secs(),
+                "Maximal time in seconds to wait before restarting the sync.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "healthy_run_duration",
+                &self.healthy_run_duration.as_secs(),
+                "Time in seconds after which a running sync is considered healthy, so that its \
+                 next restart isn't consecutive to the previous ones.",
+                ParamPrivacyInput::Public,
+            ),
+        ]);
+        config.extend(ser_optional_param(
+            &self.max_restarts,
+            10,
+            "max_restarts",
+            "The number of consecutive restarts of the sync after recoverable errors before the \
+             node stops. If not set, the sync is restarted as long as its errors are recoverable. \
+             Restarts after reorgs aren't counted.",
+            ParamPrivacyInput::Public,
+        ));
+        config
+    }
+}
+
+/// Decides whether to restart the sync after each of its errors, according to the restart policy
+/// of the configuration.
+pub struct SyncSupervisor {
+    config: SyncSupervisorConfig,
+    consecutive_restarts: u64,
+    backoff: Duration,
+    // The time the sync was last (re)started.
+    started_at: Instant,
+}
+
+impl SyncSupervisor {
+    /// Creates a supervisor of a sync that is started now.
+    pub fn new(config: SyncSupervisorConfig) -> Self {
+        SyncSupervisor {
+            config,
+            consecutive_restarts: 0,
+            backoff: config.initial_backoff,
+            started_at: Instant::now(),
+        }
+    }
+
+    /// Handles an error of the sync. If the sync should be restarted, waits for the backoff and
+    /// returns Ok. Otherwise, returns the error: the first fatal one, or the last recoverable one
+    /// once the consecutive restarts are exhausted. After a reorg, returns Ok right away.
+    pub async fn handle_error(&mut self, err: NodeError) -> Result<(), NodeError> {
+        if !err.is_recoverable() {
+            error!("Fatal error while syncing: {err}");
+            return Err(err);
+        }
+        if matches!(&err, NodeError::Sync(sync_error) if sync_error.is_reorg()) {
+            info!("Detected a reorg while syncing, restarting the sync: {err}");
+            metrics::increment_counter!(PAPYRUS_SYNC_RESTARTS);
+            return Ok(());
+        }
+        if self.started_at.elapsed() >= self.config.healthy_run_duration {
+            self.consecutive_restarts = 0;
+            self.backoff = self.config.initial_backoff;
+        }
+        if self
+            .config
+            .max_restarts
+            .is_some_and(|max_restarts| self.consecutive_restarts >= max_restarts)
+        {
+            error!(
+                "Recoverable error while syncing after {} consecutive restarts, giving up: {err}",
+                self.consecutive_restarts
+            );
+            return Err(err);
+        }
+        self.consecutive_restarts += 1;
+        metrics::increment_counter!(PAPYRUS_SYNC_RESTARTS);
+        warn!(
+            "Recoverable error while syncing, restarting the sync in {:?} (consecutive restart \
+             {}): {err}",
+            self.backoff, self.consecutive_restarts
+        );
+        tokio::time::sleep(self.backoff).await;
+        self.backoff = min(self.backoff * 2, self.config.max_backoff);
+        self.started_at = Instant::now();
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use papyrus_storage::StorageError;
+use papyrus_sync::StateSyncError;
+use starknet_api::block::{BlockHash, BlockNumber};
+
+use crate::error::NodeError;
+use crate::sync_supervisor::{SyncSupervisor, SyncSupervisorConfig};
+
+fn no_backoff_config(max_restarts: Option<u64>) -> SyncSupervisorConfig {
+    SyncSupervisorConfig {
+        max_restarts,
+        initial_backoff: Duration::ZERO,
+        max_backoff: Duration::ZERO,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn restarts_until_max_restarts() {
+    let mut supervisor = SyncSupervisor::new(no_backoff_config(Some(2)));
+    for _ in 0..2 {
+        supervisor.handle_error(StateSyncError::NoProgress.into()).await.unwrap();
+    }
+    let err = supervisor.handle_error(StateSyncError::NoProgress.into()).await.unwrap_err();
+    assert!(matches!(err, NodeError::Sync(StateSyncError::NoProgress)));
+}
+
+#[tokio::test]
+async fn never_restarts_after_fatal_error() {
+    let mut supervisor = SyncSupervisor::new(no_backoff_config(Some(2)));
+    let fatal_error = StateSyncError::StorageError(StorageError::MarkerMismatch {
+        expected: BlockNumber(1),
+        found: BlockNumber(0),
+    });
+    let err = supervisor.handle_error(fatal_error.into()).await.unwrap_err();
+    assert!(matches!(err, NodeError::Sync(StateSyncError::StorageError(_))));
+}
+
+#[tokio::test]
+async fn healthy_run_resets_consecutive_restarts() {
+    let mut supervisor = SyncSupervisor::new(SyncSupervisorConfig {
+        healthy_run_duration: Duration::ZERO,
+        ..no_backoff_config(Some(1))
+    });
+    for _ in 0..3 {
+        supervisor.handle_error(StateSyncError::NoProgress.into()).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn backoff_doubles_up_to_max() {
+    let mut supervisor = SyncSupervisor::new(SyncSupervisorConfig {
+        initial_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(3),
+        ..no_backoff_config(Some(5))
+    });
+    let mut backoffs = vec![];
+    for _ in 0..3 {
+        backoffs.push(supervisor.backoff);
+        supervisor.handle_error(StateSyncError::NoProgress.into()).await.unwrap();
+    }
+    backoffs.push(supervisor.backoff);
+    assert_eq!(backoffs, [1, 2, 3, 3].map(Duration::from_millis).to_vec());
+}
+
+#[tokio::test]
+async fn restarts_are_unlimited_by_default() {
+    let mut supervisor = SyncSupervisor::new(no_backoff_config(None));
+    for _ in 0..100 {
+        supervisor.handle_error(StateSyncError::NoProgress.into()).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn reorgs_are_not_counted_as_restarts() {
+    let mut supervisor = SyncSupervisor::new(SyncSupervisorConfig {
+        initial_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(1),
+        ..no_backoff_config(Some(1))
+    });
+    supervisor.handle_error(StateSyncError::NoProgress.into()).await.unwrap();
+    for _ in 0..3 {
+        let reorg = StateSyncError::ParentBlockHashMismatch {
+            block_number: BlockNumber(1),
+            expected_parent_block_hash: BlockHash::default(),
+            stored_parent_block_hash: BlockHash::default(),
+        };
+        supervisor.handle_error(reorg.into()).await.unwrap();
+    }
+    assert_eq!(supervisor.consecutive_restarts, 1);
+}
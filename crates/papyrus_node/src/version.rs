@@ -20,6 +20,12 @@ const VERSION_META: Metadata = Metadata::Dev;
 pub const VERSION: &str = version_str();
 /// Textual version string including the metadata.
 pub const VERSION_FULL: &str = full_version_str();
+/// The commit the node was built from, taken from the `PAPYRUS_BUILD_COMMIT` environment variable
+/// at compile time.
+pub const BUILD_COMMIT: &str = match option_env!("PAPYRUS_BUILD_COMMIT") {
+    Some(build_commit) => build_commit,
+    None => "unknown",
+};
 
 #[allow(dead_code)]
 const DEV_VERSION_META: &str = "dev";
@@ -0,0 +1,245 @@
+//! Notifications of the chain progress to HTTP webhooks, for downstream services that can't
+//! subscribe to the node over WebSocket. The new heads and the reorgs are POSTed as JSON to each of
+//! the configured URLs.
+//!
+//! Every URL has its own queue of events, so a webhook that is slow or down doesn't hold back the
+//! others. Consecutive new heads that are waiting in the queue of a URL are coalesced into the
+//! latest of them, and so are consecutive reorgs, so a webhook that fell behind, for example while
+//! the node catches up with the chain, gets the latest state of the chain instead of every block.
+#[cfg(test)]
+#[path = "webhook_notifier_test.rs"]
+mod webhook_notifier_test;
+
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+
+use futures_util::future::{join, join_all};
+use papyrus_common::outbound_connection::{OutboundConnectionConfig, OutboundConnectionError};
+use papyrus_common::sync_notifications::SyncNotification;
+use papyrus_config::converters::{
+    deserialize_milliseconds_to_duration,
+    deserialize_seconds_to_duration,
+};
+use papyrus_config::dumping::{append_sub_config_name, ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use reqwest::Client;
+use serde::{Deserialize, Deserializer, Serialize};
+use starknet_api::block::{BlockHash, BlockNumber};
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, warn};
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct WebhookNotifierConfig {
+    /// The URLs to which the events are POSTed.
+    #[serde(deserialize_with = "deserialize_urls")]
+    pub urls: Vec<String>,
+    /// The number of times a failed delivery is retried before the event is dropped for its URL.
+    pub max_retries: usize,
+    /// The delay before the first retry, doubled on every further retry.
+    #[serde(deserialize_with = "deserialize_milliseconds_to_duration")]
+    pub retry_delay: Duration,
+    #[serde(deserialize_with = "deserialize_seconds_to_duration")]
+    pub request_timeout: Duration,
+    pub outbound_connection: OutboundConnectionConfig,
+}
+
+impl Default for WebhookNotifierConfig {
+    fn default() -> Self {
+        WebhookNotifierConfig {
+            urls: vec![],
+            max_retries: 5,
+            retry_delay: Duration::from_millis(500),
+            request_timeout: Duration::from_secs(10),
+            outbound_connection: OutboundConnectionConfig::default(),
+        }
+    }
+}
+
+impl SerializeConfig for WebhookNotifierConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        let mut dump = BTreeMap::from_iter([
+            ser_param(
+                "urls",
+                &self.urls.join(" "),
+                "'url1 url2 ...' the URLs to which the new head and reorg events are POSTed.",
+                // The URLs may contain the credentials of the webhooks.
+                ParamPrivacyInput::Private,
+            ),
+            ser_param(
+                "max_retries",
+                &self.max_retries,
+                "Maximum number of times a failed delivery of an event is retried before the \
+                 event is dropped for its URL.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "retry_delay",
+                &serialize_milliseconds(self.retry_delay),
+                "Time in milliseconds before the first retry of a failed delivery. The time is \
+                 doubled on every further retry.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "request_timeout",
+                &self.request_timeout.as_secs(),
+                "Time in seconds after which a delivery that wasn't answered fails.",
+                ParamPrivacyInput::Public,
+            ),
+        ]);
+        dump.extend(append_sub_config_name(self.outbound_connection.dump(), "outbound_connection"));
+        dump
+    }
+}
+
+fn serialize_milliseconds(duration: Duration) -> u64 {
+    duration.as_millis().try_into().expect("The retry delay should fit in u64 milliseconds.")
+}
+
+fn deserialize_urls<'de, D>(de: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw_str: String = Deserialize::deserialize(de)?;
+    Ok(raw_str.split_whitespace().map(str::to_owned).collect())
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum WebhookNotifierError {
+    #[error("Failed creating the webhook client: {0}.")]
+    ClientCreation(#[from] reqwest::Error),
+    #[error(transparent)]
+    OutboundConnection(#[from] OutboundConnectionError),
+}
+
+/// An event that is POSTed to the webhooks, tagged by its type.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// The block was added at the head of the chain.
+    NewHead { block_number: BlockNumber, block_hash: BlockHash },
+    /// The blocks from the block on were removed from the head of the chain by a reorg.
+    Reorg { reverted_block_number: BlockNumber },
+    /// Notifications were skipped because the notifier fell behind the sync, so the receiver
+    /// should get the state of the chain from the node again.
+    Resync { skipped_notifications: u64 },
+}
+
+impl WebhookEvent {
+    // Returns the event of the notification, or None if the webhooks aren't notified of it.
+    fn from_notification(notification: SyncNotification) -> Option<Self> {
+        match notification {
+            SyncNotification::BlockStored { block_number, block_hash } => {
+                Some(WebhookEvent::NewHead { block_number, block_hash })
+            }
+            SyncNotification::BlockReverted { block_number } => {
+                Some(WebhookEvent::Reorg { reverted_block_number: block_number })
+            }
+            SyncNotification::StateDiffStored { .. }
+            | SyncNotification::BlockAcceptedOnBaseLayer { .. }
+            | SyncNotification::BlockRevertedOnBaseLayer { .. } => None,
+        }
+    }
+}
+
+/// POSTs the events of the sync notifications to the webhooks, in the order of the notifications.
+/// Returns only if the client can't be created or once the sync stops notifying and the queued
+/// events were delivered.
+pub async fn run_webhook_notifier(
+    config: WebhookNotifierConfig,
+    mut notifications: broadcast::Receiver<SyncNotification>,
+) -> Result<(), WebhookNotifierError> {
+    let client = config
+        .outbound_connection
+        .configure(Client::builder().timeout(config.request_timeout))?
+        .build()?;
+    let (queues, queued_events): (Vec<_>, Vec<_>) =
+        config.urls.iter().map(|_| mpsc::unbounded_channel()).unzip();
+    let deliveries = join_all(
+        config
+            .urls
+            .iter()
+            .zip(queued_events)
+            .map(|(url, events)| deliver_queued_events(&client, &config, url, events)),
+    );
+    // Dropping the queues once the sync stops notifying ends the deliveries.
+    let dispatch = async move {
+        loop {
+            let event = match notifications.recv().await {
+                Ok(notification) => match WebhookEvent::from_notification(notification) {
+                    Some(event) => event,
+                    None => continue,
+                },
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("The webhook notifier fell behind and skipped {skipped} notifications.");
+                    WebhookEvent::Resync { skipped_notifications: skipped }
+                }
+                Err(broadcast::error::RecvError::Closed) => return,
+            };
+            for queue in &queues {
+                // The receiver is dropped only once the queue is dropped.
+                let _ = queue.send(event);
+            }
+        }
+    };
+    join(dispatch, deliveries).await;
+    Ok(())
+}
+
+// Delivers the events of the queue of the URL one after the other, until the queue is closed.
+async fn deliver_queued_events(
+    client: &Client,
+    config: &WebhookNotifierConfig,
+    url: &str,
+    mut events: mpsc::UnboundedReceiver<WebhookEvent>,
+) {
+    let mut pending = VecDeque::new();
+    loop {
+        if pending.is_empty() {
+            let Some(event) = events.recv().await else { return };
+            push_coalesced(&mut pending, event);
+        }
+        // The events that were queued while the previous event was delivered.
+        while let Ok(event) = events.try_recv() {
+            push_coalesced(&mut pending, event);
+        }
+        if let Some(event) = pending.pop_front() {
+            deliver(client, config, url, event).await;
+        }
+    }
+}
+
+// Adds the event to the events that wait for delivery. A new head replaces the new head that waits
+// right before it, and a reorg replaces the reorg that waits right before it, since the later
+// reorg reverts from an earlier block.
+fn push_coalesced(pending: &mut VecDeque<WebhookEvent>, event: WebhookEvent) {
+    match (pending.back_mut(), event) {
+        (Some(last @ WebhookEvent::NewHead { .. }), WebhookEvent::NewHead { .. })
+        | (Some(last @ WebhookEvent::Reorg { .. }), WebhookEvent::Reorg { .. }) => *last = event,
+        _ => pending.push_back(event),
+    }
+}
+
+// POSTs the event to the URL, retrying failed deliveries. Returns whether the event was delivered.
+// The event is dropped for the URL once the retries are exhausted, so that a webhook that is down
+// doesn't hold back the following events forever.
+async fn deliver(
+    client: &Client,
+    config: &WebhookNotifierConfig,
+    url: &str,
+    event: WebhookEvent,
+) -> bool {
+    let mut retry_delay = config.retry_delay;
+    for attempt in 0..=config.max_retries {
+        if attempt > 0 {
+            tokio::time::sleep(retry_delay).await;
+            retry_delay *= 2;
+        }
+        match client.post(url).json(&event).send().await.and_then(|res| res.error_for_status()) {
+            Ok(_) => return true,
+            // The URL is omitted from the error since it may contain credentials.
+            Err(err) => debug!("Failed delivering {event:?} to a webhook: {}.", err.without_url()),
+        }
+    }
+    warn!("Dropped {event:?} for a webhook after {} failed deliveries.", config.max_retries + 1);
+    false
+}
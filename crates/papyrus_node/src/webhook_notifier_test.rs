@@ -0,0 +1,148 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use mockito::{mock, Matcher};
+use papyrus_common::sync_notifications::SyncNotification;
+use pretty_assertions::assert_eq;
+use reqwest::Client;
+use serde_json::json;
+use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::hash::StarkFelt;
+use starknet_api::stark_felt;
+use tokio::sync::broadcast;
+
+use crate::webhook_notifier::{
+    deliver,
+    push_coalesced,
+    run_webhook_notifier,
+    WebhookEvent,
+    WebhookNotifierConfig,
+};
+
+fn test_config() -> WebhookNotifierConfig {
+    WebhookNotifierConfig {
+        max_retries: 2,
+        retry_delay: Duration::from_millis(1),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn events_of_notifications() {
+    let block_hash = BlockHash(stark_felt!("0x12"));
+    assert_eq!(
+        WebhookEvent::from_notification(SyncNotification::BlockStored {
+            block_number: BlockNumber(5),
+            block_hash
+        }),
+        Some(WebhookEvent::NewHead { block_number: BlockNumber(5), block_hash })
+    );
+    assert_eq!(
+        WebhookEvent::from_notification(SyncNotification::BlockReverted {
+            block_number: BlockNumber(5)
+        }),
+        Some(WebhookEvent::Reorg { reverted_block_number: BlockNumber(5) })
+    );
+    assert_eq!(
+        WebhookEvent::from_notification(SyncNotification::BlockAcceptedOnBaseLayer {
+            block_number: BlockNumber(5)
+        }),
+        None
+    );
+}
+
+#[tokio::test]
+async fn delivers_event_as_json() {
+    let event = WebhookEvent::NewHead {
+        block_number: BlockNumber(5),
+        block_hash: BlockHash(stark_felt!("0x12")),
+    };
+    let webhook = mock("POST", "/new_head")
+        .match_body(Matcher::Json(json!({
+            "type": "new_head",
+            "block_number": 5,
+            "block_hash": "0x12",
+        })))
+        .with_status(200)
+        .expect(1)
+        .create();
+
+    let url = format!("{}/new_head", mockito::server_url());
+    assert!(deliver(&Client::new(), &test_config(), &url, event).await);
+    webhook.assert();
+}
+
+#[tokio::test]
+async fn retries_failed_deliveries() {
+    let event = WebhookEvent::Reorg { reverted_block_number: BlockNumber(5) };
+    // The first attempt and the two retries fail.
+    let webhook = mock("POST", "/reorg").with_status(500).expect(3).create();
+
+    let url = format!("{}/reorg", mockito::server_url());
+    assert!(!deliver(&Client::new(), &test_config(), &url, event).await);
+    webhook.assert();
+}
+
+#[test]
+fn waiting_events_are_coalesced() {
+    let new_head = |block_number| WebhookEvent::NewHead {
+        block_number: BlockNumber(block_number),
+        block_hash: BlockHash(StarkFelt::from(block_number)),
+    };
+    let reorg =
+        |block_number| WebhookEvent::Reorg { reverted_block_number: BlockNumber(block_number) };
+    let resync = WebhookEvent::Resync { skipped_notifications: 3 };
+
+    let mut pending = VecDeque::new();
+    for event in [
+        new_head(1),
+        new_head(2),
+        reorg(2),
+        reorg(1),
+        new_head(1),
+        resync,
+        new_head(2),
+        new_head(3),
+    ] {
+        push_coalesced(&mut pending, event);
+    }
+    assert_eq!(pending, [new_head(2), reorg(1), new_head(1), resync, new_head(3)]);
+}
+
+#[tokio::test]
+async fn resync_is_sent_when_notifications_are_skipped() {
+    let block_hash = BlockHash(stark_felt!("0x12"));
+    let resync = mock("POST", "/resync")
+        .match_body(Matcher::Json(json!({"type": "resync", "skipped_notifications": 2})))
+        .with_status(200)
+        .expect(1)
+        .create();
+    let new_head = mock("POST", "/resync")
+        .match_body(Matcher::Json(json!({
+            "type": "new_head",
+            "block_number": 2,
+            "block_hash": "0x12",
+        })))
+        .with_status(200)
+        .expect(1)
+        .create();
+
+    // The notifier is behind by the two notifications that don't fit in the channel.
+    let (sender, notifications) = broadcast::channel(1);
+    for block_number in 0..3 {
+        sender
+            .send(SyncNotification::BlockStored {
+                block_number: BlockNumber(block_number),
+                block_hash,
+            })
+            .unwrap();
+    }
+    drop(sender);
+    let config = WebhookNotifierConfig {
+        urls: vec![format!("{}/resync", mockito::server_url())],
+        ..test_config()
+    };
+    run_webhook_notifier(config, notifications).await.unwrap();
+    resync.assert();
+    new_head.assert();
+}
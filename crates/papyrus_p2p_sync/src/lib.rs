@@ -107,6 +107,7 @@ impl P2PSync {
                     limit: self.config.num_headers_per_query,
                     step: STEP,
                     data_type: DataType::SignedBlockHeader,
+                    state_diff_expectations: vec![],
                 })
                 .await?;
             self.parse_headers(&mut current_block_number, end_block_number).await?;
@@ -30,12 +30,13 @@ fn setup() -> (P2PSync, StorageReader, Receiver<Query>, Sender<SignedBlockHeader
     let (query_sender, query_receiver) = futures::channel::mpsc::channel(BUFFER_SIZE);
     let (signed_headers_sender, signed_headers_receiver) =
         futures::channel::mpsc::channel(BUFFER_SIZE);
+    let (_state_diffs_sender, state_diffs_receiver) = futures::channel::mpsc::channel(BUFFER_SIZE);
     let p2p_sync = P2PSync::new(
         TEST_CONFIG.clone(),
         storage_reader.clone(),
         storage_writer,
         query_sender,
-        ResponseReceivers { signed_headers_receiver },
+        ResponseReceivers { signed_headers_receiver, state_diffs_receiver },
     );
     (p2p_sync, storage_reader, query_receiver, signed_headers_sender)
 }
@@ -80,6 +81,7 @@ async fn signed_headers_basic_flow() {
                     limit: QUERY_LENGTH,
                     step: 1,
                     data_type: DataType::SignedBlockHeader,
+                    state_diff_expectations: vec![],
                 }
             );
 
@@ -178,6 +180,7 @@ async fn sync_sends_new_query_if_it_got_partial_responses() {
                 limit: QUERY_LENGTH,
                 step: 1,
                 data_type: DataType::SignedBlockHeader,
+                state_diff_expectations: vec![],
             }
         );
     };
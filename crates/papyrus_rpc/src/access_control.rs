@@ -0,0 +1,250 @@
+#[cfg(test)]
+#[path = "access_control_test.rs"]
+mod access_control_test;
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use hyper::{Body, Request};
+use jsonrpsee::core::http_helpers::read_body;
+use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use serde::de::Error as DeserializationError;
+use serde::{Deserialize, Deserializer, Serialize};
+use tower::BoxError;
+use tracing::debug;
+
+use crate::middleware::{routed_method, RoutedMethod};
+use crate::SERVER_MAX_BODY_SIZE;
+
+/// The header in which the clients send their API key.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+// The window in which the calls of an API key are counted for its rate limit.
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(1);
+
+/// Configuration of the methods that can be called only with an API key. Methods that aren't in a
+/// protected group are open to all the clients.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct AccessControlConfig {
+    pub protect_write_methods: bool,
+    pub protect_trace_methods: bool,
+    /// The API keys that can call the protected methods, each with the number of protected calls
+    /// it may make per second. A limit of 0 means no limit.
+    #[serde(deserialize_with = "deserialize_api_keys")]
+    pub api_keys: HashMap<String, u32>,
+}
+
+impl SerializeConfig for AccessControlConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([
+            ser_param(
+                "protect_write_methods",
+                &self.protect_write_methods,
+                "If true, the methods that pass transactions through to the Starknet gateway can \
+                 be called only with an API key.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "protect_trace_methods",
+                &self.protect_trace_methods,
+                "If true, the trace, simulate and state override methods can be called only with \
+                 an API key.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "api_keys",
+                &serialize_api_keys(&self.api_keys),
+                "'key1:limit1 key2:limit2 ...' the API keys that can call the protected methods, \
+                 each with the number of protected calls it may make per second (0 for no limit).",
+                ParamPrivacyInput::Private,
+            ),
+        ])
+    }
+}
+
+/// Serializes the API keys to a "key1:limit1 key2:limit2" string.
+pub fn serialize_api_keys(api_keys: &HashMap<String, u32>) -> String {
+    let mut pairs =
+        api_keys.iter().map(|(key, limit)| format!("{key}:{limit}")).collect::<Vec<_>>();
+    pairs.sort();
+    pairs.join(" ")
+}
+
+/// Deserializes the API keys from a "key1:limit1 key2:limit2" string.
+pub fn deserialize_api_keys<'de, D>(de: D) -> Result<HashMap<String, u32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw_str: String = Deserialize::deserialize(de)?;
+    let mut api_keys = HashMap::new();
+    for raw_pair in raw_str.split_whitespace() {
+        let Some((key, limit)) = raw_pair.split_once(':') else {
+            return Err(D::Error::custom(format!(
+                "pair \"{raw_pair}\" is not valid. The Expected format is key:limit"
+            )));
+        };
+        let limit = limit.parse::<u32>().map_err(|_| {
+            D::Error::custom(format!(
+                "The rate limit of API key pair \"{raw_pair}\" isn't a number"
+            ))
+        })?;
+        if key.is_empty() || api_keys.insert(key.to_owned(), limit).is_some() {
+            return Err(D::Error::custom(format!("API key pair \"{raw_pair}\" is not valid")));
+        }
+    }
+    Ok(api_keys)
+}
+
+/// The groups of methods that can be protected by an API key.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum MethodGroup {
+    /// Methods that pass transactions through to the Starknet gateway.
+    Write,
+    /// Methods that execute transactions.
+    Trace,
+}
+
+impl MethodGroup {
+    // Returns the group of a method name as sent by the client, before it was versioned, or None
+    // if the method isn't in any group. The method is classified by the method the request is
+    // routed to, whatever namespace the client sent it with.
+    fn of_method(method: &str) -> Option<Self> {
+        match routed_method(method)? {
+            RoutedMethod::Starknet(
+                "addInvokeTransaction" | "addDeclareTransaction" | "addDeployAccountTransaction",
+            ) => Some(Self::Write),
+            RoutedMethod::Starknet(
+                "traceTransaction" | "traceBlockTransactions" | "simulateTransactions",
+            ) => Some(Self::Trace),
+            RoutedMethod::Papyrus(
+                "callWithStateOverride" | "simulateTransactionsWithStateOverride",
+            ) => Some(Self::Trace),
+            _ => None,
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub(crate) enum AccessControlError {
+    #[error("Method {method} requires an API key in the {API_KEY_HEADER} header.")]
+    MissingApiKey { method: String },
+    #[error("Unknown API key.")]
+    UnknownApiKey,
+    #[error("The API key exceeded its rate limit of {limit} calls per second.")]
+    RateLimitExceeded { limit: u32 },
+}
+
+// Counts the protected calls of an API key in the current rate limit window.
+struct RateLimiter {
+    limit: u32,
+    window: Mutex<(Instant, u32)>,
+}
+
+impl RateLimiter {
+    fn try_acquire(&self, n_calls: u32, now: Instant) -> Result<(), AccessControlError> {
+        if self.limit == 0 {
+            return Ok(());
+        }
+        let mut window = self.window.lock().expect("Rate limiter lock should not be poisoned.");
+        let (window_start, calls_in_window) = &mut *window;
+        if now.duration_since(*window_start) >= RATE_LIMIT_WINDOW {
+            *window_start = now;
+            *calls_in_window = 0;
+        }
+        if calls_in_window.saturating_add(n_calls) > self.limit {
+            return Err(AccessControlError::RateLimitExceeded { limit: self.limit });
+        }
+        *calls_in_window += n_calls;
+        Ok(())
+    }
+}
+
+/// [`Tower`] middleware that lets only clients with a known API key call the methods of the
+/// protected groups, and enforces the rate limit of each key. Requests that don't call protected
+/// methods are left unchanged. It sees only HTTP requests, which is why none of the protected
+/// methods is served over WebSocket (see [`crate::websocket`]).
+///
+/// [`Tower`]: https://crates.io/crates/tower
+#[derive(Clone)]
+pub(crate) struct AccessControl {
+    protected_groups: Vec<MethodGroup>,
+    rate_limiters: Arc<HashMap<String, RateLimiter>>,
+}
+
+impl AccessControl {
+    /// Returns None if no method group is protected.
+    pub(crate) fn new(config: &AccessControlConfig) -> Option<Self> {
+        let protected_groups = [
+            (config.protect_write_methods, MethodGroup::Write),
+            (config.protect_trace_methods, MethodGroup::Trace),
+        ]
+        .into_iter()
+        .filter_map(|(is_protected, group)| is_protected.then_some(group))
+        .collect::<Vec<_>>();
+        if protected_groups.is_empty() {
+            return None;
+        }
+        let now = Instant::now();
+        let rate_limiters = config
+            .api_keys
+            .iter()
+            .map(|(key, limit)| {
+                (key.clone(), RateLimiter { limit: *limit, window: Mutex::new((now, 0)) })
+            })
+            .collect();
+        Some(Self { protected_groups, rate_limiters: Arc::new(rate_limiters) })
+    }
+
+    pub(crate) async fn check(&self, req: Request<Body>) -> Result<Request<Body>, BoxError> {
+        let (parts, body) = req.into_parts();
+        let (body_bytes, _is_single) =
+            read_body(&parts.headers, body, SERVER_MAX_BODY_SIZE).await.map_err(BoxError::from)?;
+        // Invalid bodies are rejected by the server, with the appropriate JSON-RPC error.
+        if let Ok(body) = serde_json::from_slice::<serde_json::Value>(&body_bytes) {
+            let methods = match &body {
+                serde_json::Value::Array(requests) => requests.iter().collect(),
+                request => vec![request],
+            }
+            .into_iter()
+            .filter_map(|request| request.get("method")?.as_str())
+            .collect::<Vec<_>>();
+            let api_key = parts.headers.get(API_KEY_HEADER).and_then(|key| key.to_str().ok());
+            self.authorize(api_key, &methods, Instant::now()).map_err(|err| {
+                debug!("Denied a request: {err}");
+                BoxError::from(err)
+            })?;
+        }
+        Ok(Request::from_parts(parts, body_bytes.into()))
+    }
+
+    // Checks that the API key may call the methods, and counts the calls of protected methods
+    // towards its rate limit.
+    fn authorize(
+        &self,
+        api_key: Option<&str>,
+        methods: &[&str],
+        now: Instant,
+    ) -> Result<(), AccessControlError> {
+        let protected_methods = methods
+            .iter()
+            .filter(|method| {
+                MethodGroup::of_method(method)
+                    .is_some_and(|group| self.protected_groups.contains(&group))
+            })
+            .collect::<Vec<_>>();
+        let Some(first_protected_method) = protected_methods.first() else {
+            return Ok(());
+        };
+        let Some(api_key) = api_key else {
+            return Err(AccessControlError::MissingApiKey {
+                method: first_protected_method.to_string(),
+            });
+        };
+        let rate_limiter =
+            self.rate_limiters.get(api_key).ok_or(AccessControlError::UnknownApiKey)?;
+        let n_calls = u32::try_from(protected_methods.len()).unwrap_or(u32::MAX);
+        rate_limiter.try_acquire(n_calls, now)
+    }
+}
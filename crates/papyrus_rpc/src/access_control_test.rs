@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use hyper::{Body, Request};
+use jsonrpsee::core::http_helpers::read_body;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+
+use super::{
+    serialize_api_keys,
+    AccessControl,
+    AccessControlConfig,
+    AccessControlError,
+    API_KEY_HEADER,
+    RATE_LIMIT_WINDOW,
+};
+use crate::SERVER_MAX_BODY_SIZE;
+
+const ADD_INVOKE: &str = "starknet_addInvokeTransaction";
+const SIMULATE: &str = "starknet_simulateTransactions";
+const GET_NONCE: &str = "starknet_getNonce";
+
+fn access_control(protect_write_methods: bool, api_keys: &[(&str, u32)]) -> AccessControl {
+    AccessControl::new(&AccessControlConfig {
+        protect_write_methods,
+        protect_trace_methods: true,
+        api_keys: api_keys.iter().map(|(key, limit)| (key.to_string(), *limit)).collect(),
+    })
+    .unwrap()
+}
+
+#[test]
+fn api_keys_serialization() {
+    let config: AccessControlConfig = serde_json::from_value(json!({
+        "protect_write_methods": true,
+        "protect_trace_methods": false,
+        "api_keys": "key_b:0 key_a:10",
+    }))
+    .unwrap();
+    assert_eq!(config.api_keys, HashMap::from([("key_a".to_owned(), 10), ("key_b".to_owned(), 0)]));
+    assert_eq!(serialize_api_keys(&config.api_keys), "key_a:10 key_b:0");
+
+    for invalid_api_keys in ["key_a", "key_a:ten", ":10", "key_a:1 key_a:2"] {
+        let config = serde_json::from_value::<AccessControlConfig>(json!({
+            "protect_write_methods": true,
+            "protect_trace_methods": false,
+            "api_keys": invalid_api_keys,
+        }));
+        assert!(config.is_err(), "{invalid_api_keys} should be invalid.");
+    }
+}
+
+#[test]
+fn nothing_protected() {
+    assert!(AccessControl::new(&AccessControlConfig::default()).is_none());
+}
+
+#[test]
+fn protected_methods_require_a_known_api_key() {
+    let access_control = access_control(false, &[("key", 0)]);
+    let now = Instant::now();
+
+    // Open methods, including the ones of groups that aren't protected.
+    assert_eq!(access_control.authorize(None, &[GET_NONCE, ADD_INVOKE], now), Ok(()));
+    assert_eq!(
+        access_control.authorize(None, &[GET_NONCE, SIMULATE], now),
+        Err(AccessControlError::MissingApiKey { method: SIMULATE.to_owned() })
+    );
+    assert_eq!(
+        access_control.authorize(Some("other_key"), &[SIMULATE], now),
+        Err(AccessControlError::UnknownApiKey)
+    );
+    assert_eq!(access_control.authorize(Some("key"), &[SIMULATE, SIMULATE], now), Ok(()));
+}
+
+#[test]
+fn protected_methods_are_classified_by_the_method_they_are_routed_to() {
+    let access_control = access_control(true, &[("key", 0)]);
+    let now = Instant::now();
+
+    // The router routes these names to the protected methods, whatever their namespace is.
+    for method in [
+        "x_addInvokeTransaction",
+        "foo_traceTransaction",
+        "starknet_simulateTransactions_x",
+        "papyrus_callWithStateOverride",
+        "papyrus_simulateTransactionsWithStateOverride",
+    ] {
+        assert_eq!(
+            access_control.authorize(None, &[method], now),
+            Err(AccessControlError::MissingApiKey { method: method.to_owned() }),
+            "{method} should be protected."
+        );
+    }
+    assert_eq!(access_control.authorize(None, &["x_getNonce"], now), Ok(()));
+}
+
+#[test]
+fn rate_limit_per_api_key() {
+    let access_control = access_control(true, &[("limited", 3), ("unlimited", 0)]);
+    let now = Instant::now();
+
+    // Only the calls of protected methods count towards the limit.
+    assert_eq!(
+        access_control.authorize(Some("limited"), &[ADD_INVOKE, SIMULATE, GET_NONCE], now),
+        Ok(())
+    );
+    assert_eq!(
+        access_control.authorize(Some("limited"), &[ADD_INVOKE, ADD_INVOKE], now),
+        Err(AccessControlError::RateLimitExceeded { limit: 3 })
+    );
+    assert_eq!(access_control.authorize(Some("limited"), &[ADD_INVOKE], now), Ok(()));
+    assert_eq!(
+        access_control.authorize(Some("limited"), &[ADD_INVOKE], now),
+        Err(AccessControlError::RateLimitExceeded { limit: 3 })
+    );
+    // The limits of the keys are independent.
+    assert_eq!(access_control.authorize(Some("unlimited"), &[ADD_INVOKE; 100], now), Ok(()));
+
+    // The calls are counted again in the next window.
+    let next_window = now + RATE_LIMIT_WINDOW + Duration::from_millis(1);
+    assert_eq!(access_control.authorize(Some("limited"), &[ADD_INVOKE], next_window), Ok(()));
+}
+
+#[tokio::test]
+async fn check_request() {
+    let access_control = access_control(true, &[("key", 0)]);
+    let body = json!([
+        {"jsonrpc": "2.0", "id": 1, "method": GET_NONCE, "params": []},
+        {"jsonrpc": "2.0", "id": 2, "method": ADD_INVOKE, "params": []},
+    ])
+    .to_string();
+
+    let request = Request::post("/rpc/v0_7").body(Body::from(body.clone())).unwrap();
+    assert!(access_control.check(request).await.is_err());
+
+    // A protected method sent under another namespace is still protected.
+    let other_namespace_body =
+        json!({"jsonrpc": "2.0", "id": 1, "method": "x_addInvokeTransaction", "params": []})
+            .to_string();
+    let request = Request::post("/rpc/v0_7").body(Body::from(other_namespace_body)).unwrap();
+    assert!(access_control.check(request).await.is_err());
+
+    let request = Request::post("/rpc/v0_7")
+        .header(API_KEY_HEADER, "key")
+        .body(Body::from(body.clone()))
+        .unwrap();
+    let (parts, checked_body) = access_control.check(request).await.unwrap().into_parts();
+    let (checked_body, _is_single) =
+        read_body(&parts.headers, checked_body, SERVER_MAX_BODY_SIZE).await.unwrap();
+    assert_eq!(checked_body, body.into_bytes());
+}
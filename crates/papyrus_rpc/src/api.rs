@@ -1,11 +1,15 @@
 use std::sync::Arc;
 
+use hmac::{Hmac, Mac};
 use jsonrpsee::{Methods, RpcModule};
+use papyrus_common::deferred_classes::DeferredClassRequests;
 use papyrus_common::pending_classes::PendingClasses;
+use papyrus_common::recent_state::RecentStateCache;
 use papyrus_common::BlockHashAndNumber;
 use papyrus_execution::ExecutionConfigByBlock;
 use papyrus_storage::StorageReader;
 use serde::{Deserialize, Serialize};
+use sha3::Sha3_256;
 use starknet_api::block::{BlockHash, BlockNumber};
 use starknet_api::core::{ChainId, ContractAddress, EntryPointSelector};
 use starknet_api::transaction::Calldata;
@@ -13,11 +17,19 @@ use starknet_client::reader::PendingData;
 use starknet_client::writer::StarknetWriter;
 use tokio::sync::RwLock;
 
+use crate::execution_pool::ExecutionPool;
+use crate::papyrus_api::api_impl::PapyrusJsonRpcServerImpl;
+use crate::papyrus_api::{PapyrusJsonRpcServer, PAPYRUS_METHOD_PREFIX};
+use crate::streaming::{StreamedMethods, StreamedMethodsByVersion};
+use crate::trace_cache::TraceCache;
 use crate::v0_4::api::api_impl::JsonRpcServerV0_4Impl;
 use crate::v0_5::api::api_impl::JsonRpcServerV0_5Impl;
 use crate::v0_6::api::api_impl::JsonRpcServerV0_6Impl;
 use crate::v0_7::api::api_impl::JsonRpcServerV0_7Impl;
-use crate::version_config;
+use crate::v0_7::fee_estimate_cache::FeeEstimateCache;
+use crate::v0_7::submitted_transactions::SubmittedTransactions;
+use crate::version_config::VersionCapabilities;
+use crate::{version_config, CONTINUATION_TOKEN_SIGNATURE_LEN};
 
 #[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum Tag {
@@ -27,6 +39,9 @@ pub enum Tag {
     /// Currently constructed block
     #[serde(rename = "pending")]
     Pending,
+    /// The most recent block that was accepted on L1
+    #[serde(rename = "l1_accepted")]
+    L1Accepted,
 }
 
 #[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -52,7 +67,36 @@ pub struct CallRequest {
     pub calldata: Calldata,
 }
 
-/// Returns a `Methods` object with all the methods from the supported APIs.
+/// The key with which the continuation tokens of getEvents are signed, an HMAC-SHA3 of the
+/// configured secret.
+#[derive(Clone)]
+pub struct ContinuationTokenKey(Hmac<Sha3_256>);
+
+impl ContinuationTokenKey {
+    /// Creates the key of the given secret, or of a random secret if none is given.
+    pub fn new(secret: Option<&str>) -> Self {
+        let secret = match secret {
+            Some(secret) => secret.as_bytes().to_vec(),
+            None => rand::random::<[u8; 32]>().to_vec(),
+        };
+        Self(Hmac::new_from_slice(&secret).expect("HMAC accepts keys of any length."))
+    }
+
+    pub(crate) fn sign(&self, payload: &[u8]) -> [u8; CONTINUATION_TOKEN_SIGNATURE_LEN] {
+        self.0.clone().chain_update(payload).finalize().into_bytes()
+            [..CONTINUATION_TOKEN_SIGNATURE_LEN]
+            .try_into()
+            .expect("The MAC is longer than the signature")
+    }
+
+    // Compares the signature in constant time, so that it can't be guessed byte by byte.
+    pub(crate) fn verify(&self, payload: &[u8], signature: &[u8]) -> bool {
+        self.0.clone().chain_update(payload).verify_truncated_left(signature).is_ok()
+    }
+}
+
+/// Returns a `Methods` object with all the methods from the supported APIs, and the methods of each
+/// version whose responses are streamed.
 /// Whenever adding a new API version we need to add the new version mapping here.
 #[allow(clippy::too_many_arguments)]
 pub fn get_methods_from_supported_apis(
@@ -61,23 +105,46 @@ pub fn get_methods_from_supported_apis(
     storage_reader: StorageReader,
     max_events_chunk_size: usize,
     max_events_keys: usize,
+    fee_estimate_cache: Arc<FeeEstimateCache>,
+    submitted_transactions: Arc<SubmittedTransactions>,
     starting_block: BlockHashAndNumber,
     shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
     pending_data: Arc<RwLock<PendingData>>,
     pending_classes: Arc<RwLock<PendingClasses>>,
+    recent_state: Arc<RecentStateCache>,
+    trace_cache: Arc<TraceCache>,
+    deferred_class_requests: Arc<DeferredClassRequests>,
+    execution_pool: Arc<ExecutionPool>,
+    continuation_token_key: ContinuationTokenKey,
     starknet_writer: Arc<dyn StarknetWriter>,
-) -> Methods {
+) -> (Methods, StreamedMethodsByVersion) {
     let mut methods: Methods = Methods::new();
+    let mut streamed_methods = StreamedMethodsByVersion::new();
+    let papyrus_server = PapyrusJsonRpcServerImpl {
+        storage_reader: storage_reader.clone(),
+        execution_config: execution_config.clone(),
+        chain_id: chain_id.clone(),
+        capabilities: VersionCapabilities::default(),
+        deferred_class_requests: deferred_class_requests.clone(),
+        execution_pool: execution_pool.clone(),
+    };
     let server_gen = JsonRpcServerImplGenerator {
         chain_id: chain_id.clone(),
         execution_config,
         storage_reader,
         max_events_chunk_size,
         max_events_keys,
+        fee_estimate_cache,
+        submitted_transactions,
         starting_block,
         shared_highest_block,
         pending_data,
         pending_classes,
+        recent_state,
+        trace_cache,
+        deferred_class_requests,
+        execution_pool,
+        continuation_token_key,
         starknet_writer,
     };
     version_config::VERSION_CONFIG
@@ -87,7 +154,7 @@ pub fn get_methods_from_supported_apis(
             match version_state {
                 version_config::VersionState::Deprecated => None,
                 version_config::VersionState::Supported => {
-                    let methods = match *version {
+                    let (mut methods, version_streamed_methods) = match *version {
                         version_config::VERSION_0_4 => {
                             server_gen.clone().generator::<JsonRpcServerV0_4Impl>()
                         }
@@ -103,6 +170,8 @@ pub fn get_methods_from_supported_apis(
                         // TODO(yair): remove this once the version is an enum instead of a string.
                         _ => unreachable!("Unrecognized RPC spec version: {}", version),
                     };
+                    let _res = methods.merge(versioned_papyrus_methods(&papyrus_server, version));
+                    streamed_methods.insert(version.name, version_streamed_methods);
                     Some(methods)
                 }
             }
@@ -111,7 +180,37 @@ pub fn get_methods_from_supported_apis(
             let _res = methods.merge(new_methods);
             methods
         });
-    methods
+    (methods, streamed_methods)
+}
+
+// Returns the Papyrus methods for the given version, named like the middleware renames the requests
+// to them, e.g. "papyrus_V0_7_getMarkers".
+fn versioned_papyrus_methods(
+    papyrus_server: &PapyrusJsonRpcServerImpl,
+    version: &version_config::VersionId,
+) -> Methods {
+    let mut module = PapyrusJsonRpcServerImpl {
+        storage_reader: papyrus_server.storage_reader.clone(),
+        execution_config: papyrus_server.execution_config.clone(),
+        chain_id: papyrus_server.chain_id.clone(),
+        capabilities: version.capabilities(),
+        deferred_class_requests: papyrus_server.deferred_class_requests.clone(),
+        execution_pool: papyrus_server.execution_pool.clone(),
+    }
+    .into_rpc();
+    let method_names = module.method_names().collect::<Vec<_>>();
+    for method_name in method_names {
+        let Some(stripped_method) = method_name.strip_prefix(PAPYRUS_METHOD_PREFIX) else {
+            continue;
+        };
+        // The method names are registered once per version when the server starts.
+        let versioned_method_name: &'static str = Box::leak(
+            format!("{PAPYRUS_METHOD_PREFIX}{}_{stripped_method}", version.name).into_boxed_str(),
+        );
+        let _res = module.register_alias(versioned_method_name, method_name);
+        module.remove_method(method_name);
+    }
+    module.into()
 }
 
 pub trait JsonRpcServerImpl: Sized {
@@ -122,10 +221,17 @@ pub trait JsonRpcServerImpl: Sized {
         storage_reader: StorageReader,
         max_events_chunk_size: usize,
         max_events_keys: usize,
+        fee_estimate_cache: Arc<FeeEstimateCache>,
+        submitted_transactions: Arc<SubmittedTransactions>,
         starting_block: BlockHashAndNumber,
         shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
         pending_data: Arc<RwLock<PendingData>>,
         pending_classes: Arc<RwLock<PendingClasses>>,
+        recent_state: Arc<RecentStateCache>,
+        trace_cache: Arc<TraceCache>,
+        deferred_class_requests: Arc<DeferredClassRequests>,
+        execution_pool: Arc<ExecutionPool>,
+        continuation_token_key: ContinuationTokenKey,
         starknet_writer: Arc<dyn StarknetWriter>,
     ) -> Self;
 
@@ -139,10 +245,17 @@ struct JsonRpcServerImplGenerator {
     storage_reader: StorageReader,
     max_events_chunk_size: usize,
     max_events_keys: usize,
+    fee_estimate_cache: Arc<FeeEstimateCache>,
+    submitted_transactions: Arc<SubmittedTransactions>,
     starting_block: BlockHashAndNumber,
     shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
     pending_data: Arc<RwLock<PendingData>>,
     pending_classes: Arc<RwLock<PendingClasses>>,
+    recent_state: Arc<RecentStateCache>,
+    trace_cache: Arc<TraceCache>,
+    deferred_class_requests: Arc<DeferredClassRequests>,
+    execution_pool: Arc<ExecutionPool>,
+    continuation_token_key: ContinuationTokenKey,
     // TODO(shahak): Change this struct to be with a generic type of StarknetWriter.
     starknet_writer: Arc<dyn StarknetWriter>,
 }
@@ -153,10 +266,17 @@ type JsonRpcServerImplParams = (
     StorageReader,
     usize,
     usize,
+    Arc<FeeEstimateCache>,
+    Arc<SubmittedTransactions>,
     BlockHashAndNumber,
     Arc<RwLock<Option<BlockHashAndNumber>>>,
     Arc<RwLock<PendingData>>,
     Arc<RwLock<PendingClasses>>,
+    Arc<RecentStateCache>,
+    Arc<TraceCache>,
+    Arc<DeferredClassRequests>,
+    Arc<ExecutionPool>,
+    ContinuationTokenKey,
     Arc<dyn StarknetWriter>,
 );
 
@@ -168,15 +288,32 @@ impl JsonRpcServerImplGenerator {
             self.storage_reader,
             self.max_events_chunk_size,
             self.max_events_keys,
+            self.fee_estimate_cache,
+            self.submitted_transactions,
             self.starting_block,
             self.shared_highest_block,
             self.pending_data,
             self.pending_classes,
+            self.recent_state,
+            self.trace_cache,
+            self.deferred_class_requests,
+            self.execution_pool,
+            self.continuation_token_key,
             self.starknet_writer,
         )
     }
 
-    fn generator<T>(self) -> Methods
+    // Returns the methods of the version, and its methods whose responses are streamed. Both are
+    // served by the same state.
+    fn generator<T>(self) -> (Methods, Arc<dyn StreamedMethods>)
+    where
+        T: JsonRpcServerImpl + StreamedMethods + 'static,
+    {
+        let streamed_methods: Arc<dyn StreamedMethods> = Arc::new(self.clone().server::<T>());
+        (self.server::<T>().into_rpc_module().into(), streamed_methods)
+    }
+
+    fn server<T>(self) -> T
     where
         T: JsonRpcServerImpl,
     {
@@ -186,26 +323,37 @@ impl JsonRpcServerImplGenerator {
             storage_reader,
             max_events_chunk_size,
             max_events_keys,
+            fee_estimate_cache,
+            submitted_transactions,
             starting_block,
             shared_highest_block,
             pending_data,
             pending_classes,
+            recent_state,
+            trace_cache,
+            deferred_class_requests,
+            execution_pool,
+            continuation_token_key,
             starknet_writer,
         ) = self.get_params();
-        Into::<Methods>::into(
-            T::new(
-                chain_id,
-                fee_contract_address,
-                storage_reader,
-                max_events_chunk_size,
-                max_events_keys,
-                starting_block,
-                shared_highest_block,
-                pending_data,
-                pending_classes,
-                starknet_writer,
-            )
-            .into_rpc_module(),
+        T::new(
+            chain_id,
+            fee_contract_address,
+            storage_reader,
+            max_events_chunk_size,
+            max_events_keys,
+            fee_estimate_cache,
+            submitted_transactions,
+            starting_block,
+            shared_highest_block,
+            pending_data,
+            pending_classes,
+            recent_state,
+            trace_cache,
+            deferred_class_requests,
+            execution_pool,
+            continuation_token_key,
+            starknet_writer,
         )
     }
 }
@@ -0,0 +1,168 @@
+//! Resolution of block ids to block numbers, shared by the methods of all the API versions.
+//!
+//! The sync downloads headers and bodies ahead of state diffs, so a block may exist in the storage
+//! without its state diff. Each method states which data of the block it needs with
+//! [`RequiredBlockData`], and a block hash or number is resolved only if that data was already
+//! downloaded. The `latest` and `l1_accepted` tags are always resolved to blocks whose state diff
+//! was downloaded, so that all the methods agree on which block is the latest. A block whose
+//! header was downloaded but whose required data wasn't is reported with a dedicated error, so
+//! that clients can tell a block that isn't synced yet from one that doesn't exist.
+
+#[cfg(test)]
+#[path = "block_id_test.rs"]
+mod block_id_test;
+
+use std::cmp::min;
+
+use jsonrpsee::types::error::ErrorCode::InvalidParams;
+use jsonrpsee::types::ErrorObjectOwned;
+use papyrus_storage::base_layer::BaseLayerStorageReader;
+use papyrus_storage::body::BodyStorageReader;
+use papyrus_storage::db::TransactionKind;
+use papyrus_storage::header::HeaderStorageReader;
+use papyrus_storage::state::StateStorageReader;
+use papyrus_storage::{StorageResult, StorageTxn};
+use serde::{Deserialize, Serialize};
+use starknet_api::block::BlockNumber;
+
+use crate::api::{BlockHashOrNumber, BlockId, Tag};
+use crate::internal_server_error;
+use crate::v0_7::error::block_not_fully_synced;
+
+/// The data of a block a method needs in order to handle a request for that block.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum RequiredBlockData {
+    /// The header and the body of the block.
+    Body,
+    /// The state diff of the block, in addition to its header and body.
+    State,
+    /// The receipts of the block, in addition to its header and body.
+    Receipts,
+}
+
+impl RequiredBlockData {
+    // Returns the first block for which the required data wasn't downloaded yet.
+    fn marker<Mode: TransactionKind>(
+        self,
+        txn: &StorageTxn<'_, Mode>,
+    ) -> StorageResult<BlockNumber> {
+        match self {
+            RequiredBlockData::Body => txn.get_body_marker(),
+            RequiredBlockData::State => txn.get_state_marker(),
+            RequiredBlockData::Receipts => txn.get_receipts_marker(),
+        }
+    }
+}
+
+/// A part of a block that wasn't downloaded yet.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub(crate) enum MissingBlockData {
+    Body,
+    StateDiff,
+    Receipts,
+}
+
+/// The data of the error for a block that isn't fully synced.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub(crate) struct BlockNotFullySynced {
+    pub block_number: BlockNumber,
+    pub missing: MissingBlockData,
+}
+
+impl From<BlockNotFullySynced> for ErrorObjectOwned {
+    fn from(data: BlockNotFullySynced) -> Self {
+        block_not_fully_synced(data).into()
+    }
+}
+
+/// Resolves the block id to the number of an accepted block (i.e not pending). Returns None if
+/// there's no matching block with the required data.
+///
+/// * A block hash or number is resolved to its block.
+/// * `latest` is resolved to the most advanced block whose state diff was downloaded.
+/// * `l1_accepted` is resolved to the most advanced block that was accepted on the base layer and
+///   whose state diff was downloaded.
+/// * `pending` is resolved like `latest`, to the closest accepted block. Methods that support the
+///   pending block should handle it before resolving the block id.
+pub(crate) fn resolve_accepted_block_number<Mode: TransactionKind>(
+    txn: &StorageTxn<'_, Mode>,
+    block_id: BlockId,
+    required_data: RequiredBlockData,
+) -> StorageResult<Option<BlockNumber>> {
+    Ok(match block_id {
+        BlockId::HashOrNumber(BlockHashOrNumber::Hash(block_hash)) => {
+            let marker = required_data.marker(txn)?;
+            txn.get_block_number_by_hash(&block_hash)?.filter(|block_number| *block_number < marker)
+        }
+        BlockId::HashOrNumber(BlockHashOrNumber::Number(block_number)) => {
+            let marker = required_data.marker(txn)?;
+            (block_number < marker).then_some(block_number)
+        }
+        BlockId::Tag(Tag::Latest | Tag::Pending) => txn.get_state_marker()?.prev(),
+        BlockId::Tag(Tag::L1Accepted) => {
+            min(txn.get_base_layer_block_marker()?, txn.get_state_marker()?).prev()
+        }
+    })
+}
+
+/// Returns the data the method needs that wasn't downloaded yet for a block whose header was
+/// downloaded. The bodies are downloaded before the state diffs, so a block that misses its state
+/// diff may miss its body too. Returns None if the block's header wasn't downloaded either, or for
+/// tags, which are always resolved to fully synced blocks.
+pub(crate) fn get_missing_block_data<Mode: TransactionKind>(
+    txn: &StorageTxn<'_, Mode>,
+    block_id: BlockId,
+    required_data: RequiredBlockData,
+) -> StorageResult<Option<BlockNotFullySynced>> {
+    let block_number = match block_id {
+        BlockId::HashOrNumber(BlockHashOrNumber::Hash(block_hash)) => {
+            txn.get_block_number_by_hash(&block_hash)?
+        }
+        BlockId::HashOrNumber(BlockHashOrNumber::Number(block_number)) => {
+            (block_number < txn.get_header_marker()?).then_some(block_number)
+        }
+        BlockId::Tag(_) => None,
+    };
+    let Some(block_number) = block_number else {
+        return Ok(None);
+    };
+    if block_number < required_data.marker(txn)? {
+        return Ok(None);
+    }
+    let missing = match required_data {
+        RequiredBlockData::Body => MissingBlockData::Body,
+        RequiredBlockData::State => MissingBlockData::StateDiff,
+        RequiredBlockData::Receipts => MissingBlockData::Receipts,
+    };
+    Ok(Some(BlockNotFullySynced { block_number, missing }))
+}
+
+/// Returns the error for a block id that wasn't resolved: a [`BlockNotFullySynced`] error if the
+/// block's header was downloaded, and otherwise the given error of the API version for a block
+/// that wasn't found.
+pub(crate) fn unresolved_block_error<Mode: TransactionKind>(
+    txn: &StorageTxn<'_, Mode>,
+    block_id: BlockId,
+    required_data: RequiredBlockData,
+    block_not_found: ErrorObjectOwned,
+) -> ErrorObjectOwned {
+    match get_missing_block_data(txn, block_id, required_data) {
+        Ok(Some(block_not_fully_synced)) => block_not_fully_synced.into(),
+        Ok(None) => block_not_found,
+        Err(err) => internal_server_error(err),
+    }
+}
+
+/// Rejects the `l1_accepted` tag, which was added in v0_7, with an invalid params error. The older
+/// API versions reject it like any other tag they don't know.
+pub(crate) fn reject_l1_accepted_tag(block_id: BlockId) -> Result<(), ErrorObjectOwned> {
+    match block_id {
+        BlockId::Tag(Tag::L1Accepted) => Err(ErrorObjectOwned::owned(
+            InvalidParams.code(),
+            "The l1_accepted block tag is supported only from v0_7.",
+            None::<()>,
+        )),
+        _ => Ok(()),
+    }
+}
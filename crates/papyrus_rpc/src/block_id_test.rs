@@ -0,0 +1,131 @@
+use papyrus_storage::base_layer::BaseLayerStorageWriter;
+use papyrus_storage::body::BodyStorageWriter;
+use papyrus_storage::header::HeaderStorageWriter;
+use papyrus_storage::state::StateStorageWriter;
+use papyrus_storage::test_utils::get_test_storage;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockBody, BlockHash, BlockHeader, BlockNumber};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StateDiff;
+
+use super::{
+    get_missing_block_data,
+    resolve_accepted_block_number,
+    BlockNotFullySynced,
+    MissingBlockData,
+    RequiredBlockData,
+};
+use crate::api::{BlockHashOrNumber, BlockId, Tag};
+
+fn block_hash(block_number: u64) -> BlockHash {
+    BlockHash(StarkFelt::from(u128::from(block_number) + 1))
+}
+
+#[test]
+fn resolve_block_ids() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+
+    let txn = storage_reader.begin_ro_txn().unwrap();
+    for block_id in [BlockId::Tag(Tag::Latest), BlockId::Tag(Tag::L1Accepted)] {
+        assert_eq!(
+            resolve_accepted_block_number(&txn, block_id, RequiredBlockData::State).unwrap(),
+            None
+        );
+    }
+    drop(txn);
+
+    // Blocks 0 to 2 have a header and a body, only blocks 0 and 1 have a state diff and only
+    // block 0 was accepted on the base layer.
+    let mut txn = storage_writer.begin_rw_txn().unwrap();
+    for block_number in 0..3 {
+        let header = BlockHeader {
+            block_number: BlockNumber(block_number),
+            block_hash: block_hash(block_number),
+            ..Default::default()
+        };
+        txn = txn
+            .append_header(BlockNumber(block_number), &header)
+            .unwrap()
+            .append_body(BlockNumber(block_number), BlockBody::default())
+            .unwrap();
+    }
+    for block_number in 0..2 {
+        txn = txn
+            .append_state_diff(BlockNumber(block_number), StateDiff::default(), Default::default())
+            .unwrap();
+    }
+    txn.update_base_layer_block_marker(&BlockNumber(1)).unwrap().commit().unwrap();
+
+    let txn = storage_reader.begin_ro_txn().unwrap();
+    let resolve = |block_id, required_data| {
+        resolve_accepted_block_number(&txn, block_id, required_data).unwrap()
+    };
+
+    for required_data in [RequiredBlockData::Body, RequiredBlockData::State] {
+        assert_eq!(resolve(BlockId::Tag(Tag::Latest), required_data), Some(BlockNumber(1)));
+        assert_eq!(resolve(BlockId::Tag(Tag::Pending), required_data), Some(BlockNumber(1)));
+        assert_eq!(resolve(BlockId::Tag(Tag::L1Accepted), required_data), Some(BlockNumber(0)));
+    }
+
+    // Block 2 exists only for methods that don't need its state diff.
+    let by_number = BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(2)));
+    let by_hash = BlockId::HashOrNumber(BlockHashOrNumber::Hash(block_hash(2)));
+    for block_id in [by_number, by_hash] {
+        assert_eq!(resolve(block_id, RequiredBlockData::Body), Some(BlockNumber(2)));
+        assert_eq!(resolve(block_id, RequiredBlockData::State), None);
+    }
+
+    // Block 3 doesn't exist.
+    let by_number = BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(3)));
+    let by_hash = BlockId::HashOrNumber(BlockHashOrNumber::Hash(block_hash(3)));
+    for block_id in [by_number, by_hash] {
+        assert_eq!(resolve(block_id, RequiredBlockData::Body), None);
+    }
+}
+
+#[test]
+fn missing_block_data() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+
+    // Block 0 is fully synced, block 1 has a header and a body and block 2 has only a header.
+    let mut txn = storage_writer.begin_rw_txn().unwrap();
+    for block_number in 0..3 {
+        let header = BlockHeader {
+            block_number: BlockNumber(block_number),
+            block_hash: block_hash(block_number),
+            ..Default::default()
+        };
+        txn = txn.append_header(BlockNumber(block_number), &header).unwrap();
+    }
+    for block_number in 0..2 {
+        txn = txn.append_body(BlockNumber(block_number), BlockBody::default()).unwrap();
+    }
+    txn.append_state_diff(BlockNumber(0), StateDiff::default(), Default::default())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = storage_reader.begin_ro_txn().unwrap();
+    let missing = |block_number: u64, required_data| {
+        let by_number = BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(block_number)));
+        let by_hash = BlockId::HashOrNumber(BlockHashOrNumber::Hash(block_hash(block_number)));
+        let missing = get_missing_block_data(&txn, by_number, required_data).unwrap();
+        assert_eq!(get_missing_block_data(&txn, by_hash, required_data).unwrap(), missing);
+        missing.map(|BlockNotFullySynced { missing, .. }| missing)
+    };
+
+    assert_eq!(missing(0, RequiredBlockData::State), None);
+    assert_eq!(missing(1, RequiredBlockData::Body), None);
+    assert_eq!(missing(1, RequiredBlockData::State), Some(MissingBlockData::StateDiff));
+    assert_eq!(missing(2, RequiredBlockData::Body), Some(MissingBlockData::Body));
+    assert_eq!(missing(2, RequiredBlockData::State), Some(MissingBlockData::StateDiff));
+    // The receipts are stored with the bodies.
+    assert_eq!(missing(1, RequiredBlockData::Receipts), None);
+    assert_eq!(missing(2, RequiredBlockData::Receipts), Some(MissingBlockData::Receipts));
+    // A block that doesn't exist isn't missing data.
+    assert_eq!(missing(3, RequiredBlockData::Body), None);
+    assert_eq!(
+        get_missing_block_data(&txn, BlockId::Tag(Tag::Latest), RequiredBlockData::State).unwrap(),
+        None
+    );
+}
@@ -0,0 +1,112 @@
+//! A bounded pool for the requests that execute transactions: calls, fee estimations, simulations
+//! and traces. Executions are CPU heavy, so only a limited number of them run at once and the rest
+//! wait in a bounded queue. A request that arrives when the queue is full is rejected with a
+//! "server is busy" error, so that a burst of executions can't exhaust the node.
+#[cfg(test)]
+#[path = "execution_pool_test.rs"]
+mod execution_pool_test;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use jsonrpsee::core::RpcResult;
+use jsonrpsee::types::error::ErrorCode::ServerIsBusy;
+use jsonrpsee::types::error::SERVER_IS_BUSY_MSG;
+use jsonrpsee::types::ErrorObjectOwned;
+use tokio::sync::Semaphore;
+
+use crate::rpc_metrics::{
+    increment_rejected_requests,
+    record_execution_time,
+    set_execution_queue_depth,
+    RejectionReason,
+};
+use crate::{internal_server_error, spawn_blocking_in_current_span, RpcConfig};
+
+/// Runs executions in the blocking thread pool, at most a given number of them at once.
+#[derive(Debug)]
+pub struct ExecutionPool {
+    permits: Arc<Semaphore>,
+    max_queued_executions: usize,
+    queued_executions: AtomicUsize,
+}
+
+impl Default for ExecutionPool {
+    fn default() -> Self {
+        let config = RpcConfig::default();
+        Self::new(config.max_concurrent_executions, config.max_queued_executions)
+    }
+}
+
+impl ExecutionPool {
+    pub fn new(max_concurrent_executions: usize, max_queued_executions: usize) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(max_concurrent_executions)),
+            max_queued_executions,
+            queued_executions: AtomicUsize::new(0),
+        }
+    }
+
+    /// Runs the execution once there is a free execution slot. Fails without running it if all the
+    /// slots are taken and the queue is full.
+    pub async fn run<T: Send + 'static>(
+        &self,
+        execute: impl FnOnce() -> T + Send + 'static,
+    ) -> RpcResult<T> {
+        let permit = match self.permits.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => {
+                let _queue_slot = self.enqueue()?;
+                self.permits
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .expect("The execution semaphore should never be closed.")
+            }
+        };
+        // The permit is moved into the execution, so that it's held until the execution ends even
+        // if the request is dropped in the meantime.
+        spawn_blocking_in_current_span(move || {
+            let _permit = permit;
+            let start = Instant::now();
+            let result = execute();
+            record_execution_time(start.elapsed().as_secs_f64());
+            result
+        })
+        .await
+        .map_err(internal_server_error)
+    }
+
+    // Takes a place in the queue, which is freed when the returned slot is dropped.
+    fn enqueue(&self) -> RpcResult<QueueSlot<'_>> {
+        let queued =
+            self.queued_executions.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |queued| {
+                (queued < self.max_queued_executions).then_some(queued + 1)
+            });
+        match queued {
+            Ok(queued) => {
+                set_execution_queue_depth(queued + 1);
+                Ok(QueueSlot(self))
+            }
+            Err(_) => {
+                increment_rejected_requests(RejectionReason::ExecutionQueueFull);
+                Err(server_busy_error())
+            }
+        }
+    }
+}
+
+// A place in the queue of an execution that waits for a free execution slot.
+struct QueueSlot<'a>(&'a ExecutionPool);
+
+impl Drop for QueueSlot<'_> {
+    fn drop(&mut self) {
+        let queued = self.0.queued_executions.fetch_sub(1, Ordering::SeqCst);
+        set_execution_queue_depth(queued - 1);
+    }
+}
+
+fn server_busy_error() -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(ServerIsBusy.code(), SERVER_IS_BUSY_MSG, None::<()>)
+}
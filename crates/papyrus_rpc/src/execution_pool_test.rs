@@ -0,0 +1,71 @@
+use std::sync::mpsc;
+
+use assert_matches::assert_matches;
+use futures_util::FutureExt;
+use jsonrpsee::types::error::ErrorCode::ServerIsBusy;
+use pretty_assertions::assert_eq;
+use validator::Validate;
+
+use crate::execution_pool::ExecutionPool;
+use crate::RpcConfig;
+
+// Occupies an execution slot of the pool until the returned sender is dropped. Returns once the
+// execution started.
+async fn occupy_slot(pool: &'static ExecutionPool) -> mpsc::Sender<()> {
+    let (started_sender, started_receiver) = tokio::sync::oneshot::channel();
+    let (release_sender, release_receiver) = mpsc::channel::<()>();
+    tokio::spawn(pool.run(move || {
+        started_sender.send(()).unwrap();
+        let _ = release_receiver.recv();
+    }));
+    started_receiver.await.unwrap();
+    release_sender
+}
+
+#[tokio::test]
+async fn runs_the_execution() {
+    let pool = ExecutionPool::new(1, 0);
+    assert_eq!(pool.run(|| 7).await.unwrap(), 7);
+    // The slot is freed when the execution ends.
+    assert_eq!(pool.run(|| 8).await.unwrap(), 8);
+}
+
+#[tokio::test]
+async fn rejects_when_the_queue_is_full() {
+    let pool: &'static ExecutionPool = Box::leak(Box::new(ExecutionPool::new(1, 1)));
+    let release = occupy_slot(pool).await;
+
+    // Waits in the queue for the slot.
+    let mut queued_execution = Box::pin(pool.run(|| 7));
+    assert!((&mut queued_execution).now_or_never().is_none());
+
+    let err = pool.run(|| 8).await.unwrap_err();
+    assert_eq!(err.code(), ServerIsBusy.code());
+
+    drop(release);
+    assert_eq!(queued_execution.await.unwrap(), 7);
+    assert_matches!(pool.run(|| 8).await, Ok(8));
+}
+
+#[tokio::test]
+async fn dropped_request_frees_its_place_in_the_queue() {
+    let pool: &'static ExecutionPool = Box::leak(Box::new(ExecutionPool::new(1, 1)));
+    let release = occupy_slot(pool).await;
+
+    let mut queued_execution = Box::pin(pool.run(|| 7));
+    assert!((&mut queued_execution).now_or_never().is_none());
+    drop(queued_execution);
+
+    // Would have been rejected if the dropped request still held its place.
+    let mut queued_execution = Box::pin(pool.run(|| 8));
+    assert!((&mut queued_execution).now_or_never().is_none());
+    drop(release);
+    assert_eq!(queued_execution.await.unwrap(), 8);
+}
+
+#[test]
+fn executions_must_have_a_slot() {
+    let config = RpcConfig { max_concurrent_executions: 0, ..Default::default() };
+    let errors = config.validate().unwrap_err();
+    assert!(errors.field_errors().contains_key("max_concurrent_executions"));
+}
@@ -0,0 +1,86 @@
+//! Utilities shared by the methods of all the versions that read classes or execute transactions.
+//!
+//! When the sync defers the download of class definitions, a class may be declared in the state
+//! without its definition being stored yet. These utilities ask the sync to download such a class
+//! the first time it's needed, and wait until it's stored.
+#[cfg(test)]
+#[path = "execution_utils_test.rs"]
+mod execution_utils_test;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use jsonrpsee::core::RpcResult;
+use papyrus_common::deferred_classes::DeferredClassRequests;
+use papyrus_execution::{ExecutionError, ExecutionResult};
+use papyrus_storage::state::StateStorageReader;
+use papyrus_storage::StorageReader;
+use starknet_api::core::ClassHash;
+
+use crate::execution_pool::ExecutionPool;
+use crate::{internal_server_error, internal_server_error_with_msg};
+
+// The time a request waits for the sync to download a class whose download it deferred.
+const DEFERRED_CLASS_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Returns whether the sync deferred the download of the class.
+fn is_class_deferred(storage_reader: &StorageReader, class_hash: ClassHash) -> RpcResult<bool> {
+    Ok(storage_reader
+        .begin_ro_txn()
+        .map_err(internal_server_error)?
+        .get_state_reader()
+        .map_err(internal_server_error)?
+        .get_deferred_class_block_number(&class_hash)
+        .map_err(internal_server_error)?
+        .is_some())
+}
+
+/// If the sync deferred the download of the class, asks it to download the class and waits until
+/// it's stored.
+pub(crate) async fn download_class_if_deferred(
+    storage_reader: &StorageReader,
+    deferred_class_requests: &DeferredClassRequests,
+    class_hash: ClassHash,
+) -> RpcResult<()> {
+    if is_class_deferred(storage_reader, class_hash)? {
+        download_deferred_class(deferred_class_requests, class_hash).await?;
+    }
+    Ok(())
+}
+
+// Asks the sync to download a class whose download it deferred, and waits until it's stored.
+async fn download_deferred_class(
+    deferred_class_requests: &DeferredClassRequests,
+    class_hash: ClassHash,
+) -> RpcResult<()> {
+    tokio::time::timeout(DEFERRED_CLASS_TIMEOUT, deferred_class_requests.request_class(class_hash))
+        .await
+        .map_err(|_| {
+            internal_server_error_with_msg(format!(
+                "Timed out while downloading the class {class_hash}."
+            ))
+        })
+}
+
+/// Runs the execution in the pool, and whenever it fails because a class whose download the sync
+/// deferred is missing, downloads the class and runs the execution again.
+pub(crate) async fn execute_with_deferred_classes<T: Send + 'static>(
+    execution_pool: &ExecutionPool,
+    storage_reader: &StorageReader,
+    deferred_class_requests: &DeferredClassRequests,
+    execute: impl Fn() -> ExecutionResult<T> + Send + Sync + 'static,
+) -> RpcResult<ExecutionResult<T>> {
+    let execute = Arc::new(execute);
+    loop {
+        let execute = execute.clone();
+        let result = execution_pool.run(move || execute()).await?;
+        match result {
+            Err(ExecutionError::MissingCompiledClass { class_hash })
+                if is_class_deferred(storage_reader, class_hash)? =>
+            {
+                download_deferred_class(deferred_class_requests, class_hash).await?;
+            }
+            result => return Ok(result),
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use assert_matches::assert_matches;
+use papyrus_common::deferred_classes::DeferredClassRequests;
+use papyrus_execution::ExecutionError;
+use papyrus_storage::header::HeaderStorageWriter;
+use papyrus_storage::state::{StateStorageReader, StateStorageWriter};
+use papyrus_storage::test_utils::get_test_storage;
+use papyrus_storage::{StorageReader, StorageWriter};
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockHeader, BlockNumber};
+use starknet_api::core::ClassHash;
+use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
+use starknet_api::hash::StarkFelt;
+use starknet_api::stark_felt;
+use starknet_api::state::ThinStateDiff;
+
+use crate::execution_pool::ExecutionPool;
+use crate::execution_utils::{download_class_if_deferred, execute_with_deferred_classes};
+
+// Declares the class without its definition, as the sync does when it defers the download.
+fn declare_deferred_class(storage_writer: &mut StorageWriter, class_hash: ClassHash) {
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &BlockHeader::default())
+        .unwrap()
+        .append_thin_state_diff(
+            BlockNumber(0),
+            ThinStateDiff { deprecated_declared_classes: vec![class_hash], ..Default::default() },
+        )
+        .unwrap()
+        .commit()
+        .unwrap();
+}
+
+// Plays the part of the sync, which downloads and stores the requested class.
+fn serve_class_request(
+    mut storage_writer: StorageWriter,
+    deferred_class_requests: Arc<DeferredClassRequests>,
+    class_hash: ClassHash,
+) {
+    tokio::spawn(async move {
+        let requested_class_hash = deferred_class_requests.next_request().await;
+        assert_eq!(requested_class_hash, class_hash);
+        storage_writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_deferred_deprecated_class(&class_hash, DeprecatedContractClass::default())
+            .unwrap()
+            .commit()
+            .unwrap();
+        deferred_class_requests.notify_stored(class_hash);
+    });
+}
+
+fn is_class_stored(storage_reader: &StorageReader, class_hash: ClassHash) -> bool {
+    storage_reader
+        .begin_ro_txn()
+        .unwrap()
+        .get_state_reader()
+        .unwrap()
+        .get_deferred_class_block_number(&class_hash)
+        .unwrap()
+        .is_none()
+}
+
+#[tokio::test]
+async fn downloads_deferred_class() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let class_hash = ClassHash(stark_felt!("0x1"));
+    declare_deferred_class(&mut storage_writer, class_hash);
+    let deferred_class_requests = Arc::new(DeferredClassRequests::default());
+    serve_class_request(storage_writer, deferred_class_requests.clone(), class_hash);
+
+    download_class_if_deferred(&storage_reader, &deferred_class_requests, class_hash)
+        .await
+        .unwrap();
+    assert!(is_class_stored(&storage_reader, class_hash));
+}
+
+#[tokio::test]
+async fn execution_is_retried_after_downloading_a_deferred_class() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let class_hash = ClassHash(stark_felt!("0x1"));
+    declare_deferred_class(&mut storage_writer, class_hash);
+    let deferred_class_requests = Arc::new(DeferredClassRequests::default());
+    serve_class_request(storage_writer, deferred_class_requests.clone(), class_hash);
+
+    // The execution fails until the class is stored.
+    let executions = Arc::new(AtomicUsize::new(0));
+    let execution_counter = executions.clone();
+    let execution_reader = storage_reader.clone();
+    let result = execute_with_deferred_classes(
+        &ExecutionPool::default(),
+        &storage_reader,
+        &deferred_class_requests,
+        move || {
+            execution_counter.fetch_add(1, Ordering::SeqCst);
+            if is_class_stored(&execution_reader, class_hash) {
+                Ok(7)
+            } else {
+                Err(ExecutionError::MissingCompiledClass { class_hash })
+            }
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(result.unwrap(), 7);
+    assert_eq!(executions.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn missing_class_that_is_not_deferred_fails_the_execution() {
+    let ((storage_reader, _storage_writer), _temp_dir) = get_test_storage();
+    let class_hash = ClassHash(stark_felt!("0x1"));
+
+    let result = execute_with_deferred_classes(
+        &ExecutionPool::default(),
+        &storage_reader,
+        &DeferredClassRequests::default(),
+        move || Err::<(), _>(ExecutionError::MissingCompiledClass { class_hash }),
+    )
+    .await
+    .unwrap();
+    assert_matches!(
+        result,
+        Err(ExecutionError::MissingCompiledClass { class_hash: missing_class_hash })
+            if missing_class_hash == class_hash
+    );
+}
@@ -2,79 +2,179 @@
 // within this crate
 #![cfg_attr(coverage_nightly, feature(coverage_attribute))]
 
+mod access_control;
 mod api;
+mod block_id;
 mod compression_utils;
+mod execution_pool;
+mod execution_utils;
 mod middleware;
+mod papyrus_api;
 mod pending;
+mod response_redaction;
 mod rpc_metrics;
 #[cfg(test)]
 mod rpc_test;
+#[cfg(test)]
+mod spec_conformance_test;
+mod streaming;
+mod subscriptions;
 mod syncing_state;
 #[cfg(test)]
 mod test_utils;
+mod trace_cache;
+pub mod types;
+mod unix_socket;
 mod v0_4;
 mod v0_5;
 mod v0_6;
 mod v0_7;
+mod validation;
 mod version_config;
+mod websocket;
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::Display;
+use std::future::Future;
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use jsonrpsee::core::RpcResult;
-use jsonrpsee::server::{ServerBuilder, ServerHandle};
+use jsonrpsee::server::{stop_channel, ServerBuilder, ServerHandle};
 use jsonrpsee::types::error::ErrorCode::InternalError;
 use jsonrpsee::types::error::INTERNAL_ERROR_MSG;
 use jsonrpsee::types::ErrorObjectOwned;
+use papyrus_common::chain_id::validate_chain_id;
+use papyrus_common::deferred_classes::DeferredClassRequests;
 use papyrus_common::pending_classes::PendingClasses;
+use papyrus_common::recent_state::RecentStateCache;
+use papyrus_common::rpc_load::RpcLoad;
+use papyrus_common::sync_notifications::SyncNotifier;
 use papyrus_common::BlockHashAndNumber;
-use papyrus_config::dumping::{append_sub_config_name, ser_param, SerializeConfig};
-use papyrus_config::validators::{validate_ascii, validate_path_exists};
+use papyrus_config::converters::deserialize_milliseconds_to_duration;
+use papyrus_config::dumping::{
+    append_sub_config_name,
+    ser_optional_param,
+    ser_param,
+    SerializeConfig,
+};
+use papyrus_config::validators::validate_path_exists;
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 use papyrus_storage::base_layer::BaseLayerStorageReader;
 use papyrus_storage::body::events::EventIndex;
-use papyrus_storage::db::TransactionKind;
+use papyrus_storage::body::{BodyStorageReader, TransactionIndex};
+use papyrus_storage::db::{TransactionKind, RO};
+use papyrus_storage::header::HeaderStorageReader;
 use papyrus_storage::state::StateStorageReader;
-use papyrus_storage::{StorageReader, StorageScope, StorageTxn};
+use papyrus_storage::{NodeMode, StorageError, StorageReader, StorageScope, StorageTxn};
 use rpc_metrics::MetricLogger;
 use serde::{Deserialize, Serialize};
-use starknet_api::block::{BlockNumber, BlockStatus};
+use starknet_api::block::{BlockHash, BlockNumber, BlockStatus};
 use starknet_api::core::ChainId;
+use starknet_api::hash::StarkHash;
+use starknet_api::state::StateNumber;
+use starknet_api::transaction::{EventIndexInTransactionOutput, TransactionOffsetInBlock};
 use starknet_client::reader::PendingData;
 use starknet_client::writer::StarknetGatewayClient;
 use starknet_client::RetryConfig;
 use tokio::sync::RwLock;
-use tracing::{debug, error, info, instrument};
-use validator::Validate;
+use tokio::task::JoinHandle;
+use tower::filter::AsyncFilterLayer;
+use tower::util::BoxCloneService;
+use tracing::{debug, error, info, instrument, Instrument, Span};
+use validator::{Validate, ValidationError};
 
-use crate::api::get_methods_from_supported_apis;
-use crate::middleware::{deny_requests_with_unsupported_path, proxy_rpc_request};
+use crate::access_control::AccessControl;
+pub use crate::access_control::{AccessControlConfig, API_KEY_HEADER};
+use crate::api::{
+    get_methods_from_supported_apis,
+    BlockHashOrNumber,
+    BlockId,
+    ContinuationTokenKey,
+};
+use crate::block_id::{get_missing_block_data, RequiredBlockData};
+use crate::execution_pool::ExecutionPool;
+use crate::middleware::{
+    deny_requests_with_unsupported_path,
+    proxy_rpc_request,
+    RequestLimits,
+    RequestRecorder,
+    RequestSpanLayer,
+};
+pub use crate::response_redaction::REDACTED_FIELD_MARKER;
+use crate::response_redaction::{
+    deserialize_redacted_fields,
+    serialize_redacted_fields,
+    ResponseRedactionLayer,
+};
+use crate::streaming::StreamingLayer;
+use crate::subscriptions::api_impl::SubscriptionsJsonRpcServerImpl;
+use crate::subscriptions::SubscriptionsJsonRpcServer;
 use crate::syncing_state::get_last_synced_block;
+use crate::trace_cache::{invalidate_reverted_blocks, TraceCache};
+use crate::unix_socket::{bind_unix_socket, serve_unix_socket, validate_unix_socket_permissions};
 pub use crate::v0_4::transaction::{
     InvokeTransaction as InvokeTransactionRPC0_4,
     InvokeTransactionV1 as InvokeTransactionV1RPC0_4,
     TransactionVersion1 as TransactionVersion1RPC0_4,
 };
 pub use crate::v0_4::write_api_result::AddInvokeOkResult as AddInvokeOkResultRPC0_4;
+use crate::v0_7::error::{not_kept_in_node_mode, state_quarantined};
+use crate::v0_7::fee_estimate_cache::FeeEstimateCache;
+use crate::v0_7::submitted_transactions::SubmittedTransactions;
+use crate::websocket::WebSocketLayer;
 
 /// Maximum size of a supported transaction body - 10MB.
 pub const SERVER_MAX_BODY_SIZE: u32 = 10 * 1024 * 1024;
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Validate)]
 pub struct RpcConfig {
-    #[validate(custom = "validate_ascii")]
+    #[validate(custom = "validate_chain_id")]
     pub chain_id: ChainId,
+    /// The addresses on which the server listens, separated by whitespace. For example,
+    /// "0.0.0.0:8080 [::]:8080" listens on both IPv4 and IPv6.
+    #[validate(custom = "validate_server_address")]
     pub server_address: String,
+    /// A Unix domain socket on which the server listens too, for clients on the same machine.
+    pub unix_socket_path: Option<PathBuf>,
+    /// The permissions of the Unix domain socket in octal, like the mode of chmod.
+    #[validate(custom = "validate_unix_socket_permissions")]
+    pub unix_socket_permissions: String,
     pub max_events_chunk_size: usize,
     pub max_events_keys: usize,
+    #[validate(custom = "validate_max_request_body_size")]
+    pub max_request_body_size: u32,
+    pub max_request_nesting_depth: usize,
     pub collect_metrics: bool,
     pub starknet_url: String,
     pub starknet_gateway_retry_config: RetryConfig,
     #[validate(custom = "validate_path_exists")]
     pub execution_config: PathBuf,
+    pub recent_state_cache_blocks: usize,
+    #[serde(deserialize_with = "deserialize_milliseconds_to_duration")]
+    pub fee_estimate_cache_ttl: Duration,
+    pub fee_estimate_cache_size: usize,
+    pub submitted_transactions_cache_size: usize,
+    /// The maximum number of calls, fee estimations, simulations and traces that are executed at
+    /// once.
+    #[validate(range(min = 1))]
+    pub max_concurrent_executions: usize,
+    /// The maximum number of executions that wait for a free execution slot. More are rejected.
+    pub max_queued_executions: usize,
+    pub trace_cache_path: Option<PathBuf>,
+    pub trace_cache_blocks: usize,
+    pub record_requests_path: Option<PathBuf>,
+    /// The secret with which the continuation tokens of getEvents are signed. Nodes that share the
+    /// secret accept each other's tokens. If not set, a random secret is generated when the node
+    /// starts.
+    pub continuation_token_secret: Option<String>,
+    pub access_control: AccessControlConfig,
+    /// The fields that are redacted from the results of each method, by the name of the method.
+    /// They are redacted under whatever name the clients reach the method.
+    #[serde(deserialize_with = "deserialize_redacted_fields")]
+    pub redacted_response_fields: BTreeMap<String, BTreeSet<String>>,
 }
 
 impl Default for RpcConfig {
@@ -82,8 +182,12 @@ impl Default for RpcConfig {
         RpcConfig {
             chain_id: ChainId("SN_MAIN".to_string()),
             server_address: String::from("0.0.0.0:8080"),
+            unix_socket_path: None,
+            unix_socket_permissions: String::from("660"),
             max_events_chunk_size: 1000,
             max_events_keys: 100,
+            max_request_body_size: SERVER_MAX_BODY_SIZE,
+            max_request_nesting_depth: 64,
             collect_metrics: false,
             starknet_url: String::from("https://alpha-mainnet.starknet.io/"),
             starknet_gateway_retry_config: RetryConfig {
@@ -92,6 +196,18 @@ impl Default for RpcConfig {
                 max_retries: 5,
             },
             execution_config: PathBuf::from("config/execution/mainnet.json"),
+            recent_state_cache_blocks: 128,
+            fee_estimate_cache_ttl: Duration::ZERO,
+            fee_estimate_cache_size: 1000,
+            submitted_transactions_cache_size: 1000,
+            max_concurrent_executions: 8,
+            max_queued_executions: 100,
+            trace_cache_path: None,
+            trace_cache_blocks: 100,
+            record_requests_path: None,
+            continuation_token_secret: None,
+            access_control: AccessControlConfig::default(),
+            redacted_response_fields: BTreeMap::new(),
         }
     }
 }
@@ -108,7 +224,15 @@ impl SerializeConfig for RpcConfig {
             ser_param(
                 "server_address",
                 &self.server_address,
-                "IP:PORT of the node`s JSON-RPC server.",
+                "'IP1:PORT1 IP2:PORT2 ...' addresses of the node`s JSON-RPC server. The server \
+                 listens on all of them, for example on both IPv4 and IPv6.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "unix_socket_permissions",
+                &self.unix_socket_permissions,
+                "Permissions of the Unix domain socket in octal, like the mode of chmod. Only the \
+                 users that they allow can connect to the server through the socket.",
                 ParamPrivacyInput::Public,
             ),
             ser_param(
@@ -123,6 +247,20 @@ impl SerializeConfig for RpcConfig {
                 "Maximum number of keys supported by the node in get_events requests.",
                 ParamPrivacyInput::Public,
             ),
+            ser_param(
+                "max_request_body_size",
+                &self.max_request_body_size,
+                "Maximum size in bytes of a request body. Larger requests are rejected. Can't be \
+                 more than 10MB.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_request_nesting_depth",
+                &self.max_request_nesting_depth,
+                "Maximum nesting depth of the JSON arrays and objects in a request body. Deeper \
+                 requests are rejected.",
+                ParamPrivacyInput::Public,
+            ),
             ser_param(
                 "collect_metrics",
                 &self.collect_metrics,
@@ -141,6 +279,65 @@ impl SerializeConfig for RpcConfig {
                 "Path to the execution configuration file.",
                 ParamPrivacyInput::Public,
             ),
+            ser_param(
+                "recent_state_cache_blocks",
+                &self.recent_state_cache_blocks,
+                "The number of most recent blocks whose state writes are kept in memory to answer \
+                 state queries. 0 means that the state is always read from the storage.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "fee_estimate_cache_ttl",
+                &(self.fee_estimate_cache_ttl.as_millis() as u64),
+                "Time in milliseconds for which the fee estimation of transactions on a block is \
+                 reused for identical estimations on the same block. 0 means that fee estimations \
+                 are not cached.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "fee_estimate_cache_size",
+                &self.fee_estimate_cache_size,
+                "The number of most recent fee estimations that are cached. 0 means that fee \
+                 estimations are not cached.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "submitted_transactions_cache_size",
+                &self.submitted_transactions_cache_size,
+                "The number of most recent transactions submitted through the node that are \
+                 reported as received until they are in a block. 0 means that they are not kept.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_concurrent_executions",
+                &self.max_concurrent_executions,
+                "Maximum number of calls, fee estimations, simulations and traces that are \
+                 executed at once.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_queued_executions",
+                &self.max_queued_executions,
+                "Maximum number of executions that wait for a free execution slot. Executions \
+                 that arrive when the queue is full are rejected with a server busy error.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "redacted_response_fields",
+                &serialize_redacted_fields(&self.redacted_response_fields),
+                "'method1:field1 method1:field2 ...' fields that are replaced by \"truncated\" \
+                 wherever they appear in the results of the methods, to save bandwidth, e.g. \
+                 'starknet_getBlockWithTxs:calldata'. The fields are redacted under whatever \
+                 name the clients reach the methods.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "trace_cache_blocks",
+                &self.trace_cache_blocks,
+                "The number of most recently traced blocks whose traces are kept in the trace \
+                 cache. 0 means that traces are not cached.",
+                ParamPrivacyInput::Public,
+            ),
         ]);
         let mut retry_config_dump = append_sub_config_name(
             self.starknet_gateway_retry_config.dump(),
@@ -154,10 +351,66 @@ impl SerializeConfig for RpcConfig {
             );
         }
         self_params_dump.append(&mut retry_config_dump);
+        self_params_dump
+            .append(&mut append_sub_config_name(self.access_control.dump(), "access_control"));
+        self_params_dump.extend(ser_optional_param(
+            &self.record_requests_path,
+            PathBuf::from("rpc_requests.jsonl"),
+            "record_requests_path",
+            "Path to a file to which the incoming requests are appended, for replaying them \
+             later. If not set, the requests are not recorded.",
+            ParamPrivacyInput::Public,
+        ));
+        self_params_dump.extend(ser_optional_param(
+            &self.continuation_token_secret,
+            String::new(),
+            "continuation_token_secret",
+            "The secret with which the continuation tokens of getEvents are signed. Nodes that \
+             share the secret, e.g. the replicas behind a load balancer, accept each other's \
+             tokens. If not set, a random secret is generated when the node starts, and the \
+             tokens are accepted only by the node that issued them and until it restarts.",
+            ParamPrivacyInput::Private,
+        ));
+        self_params_dump.extend(ser_optional_param(
+            &self.trace_cache_path,
+            PathBuf::from("trace_cache"),
+            "trace_cache_path",
+            "Path to a directory in which the traces of recently traced blocks are cached, so \
+             that tracing them again doesn't execute them. The directory is emptied when the node \
+             starts. If not set, traces are not cached.",
+            ParamPrivacyInput::Public,
+        ));
+        self_params_dump.extend(ser_optional_param(
+            &self.unix_socket_path,
+            PathBuf::from("papyrus_rpc.sock"),
+            "unix_socket_path",
+            "Path of a Unix domain socket on which the server listens in addition to its \
+             addresses, for clients on the same machine. If not set, the server doesn't listen on \
+             a Unix domain socket.",
+            ParamPrivacyInput::Public,
+        ));
         self_params_dump
     }
 }
 
+fn validate_server_address(server_address: &str) -> Result<(), ValidationError> {
+    if server_address.split_whitespace().next().is_none() {
+        let mut error = ValidationError::new("no server address");
+        error.message = Some("The server should listen on at least one address.".into());
+        return Err(error);
+    }
+    Ok(())
+}
+
+fn validate_max_request_body_size(max_request_body_size: u32) -> Result<(), ValidationError> {
+    if max_request_body_size > SERVER_MAX_BODY_SIZE {
+        let mut error = ValidationError::new("max request body size too large");
+        error.message = Some(format!("The maximum is {SERVER_MAX_BODY_SIZE} bytes.").into());
+        return Err(error);
+    }
+    Ok(())
+}
+
 fn internal_server_error(err: impl Display) -> ErrorObjectOwned {
     error!("{}: {}", INTERNAL_ERROR_MSG, err);
     ErrorObjectOwned::owned(InternalError.code(), INTERNAL_ERROR_MSG, None::<()>)
@@ -169,6 +422,9 @@ fn internal_server_error_with_msg(err: impl Display) -> ErrorObjectOwned {
 }
 
 fn verify_storage_scope(storage_reader: &StorageReader) -> RpcResult<()> {
+    if storage_reader.get_node_mode() == NodeMode::Light {
+        return Err(not_kept_in_node_mode("Unsupported method in light mode.".to_owned()).into());
+    }
     match storage_reader.get_scope() {
         StorageScope::StateOnly => {
             Err(internal_server_error_with_msg("Unsupported method in state-only scope."))
@@ -177,6 +433,61 @@ fn verify_storage_scope(storage_reader: &StorageReader) -> RpcResult<()> {
     }
 }
 
+fn verify_state_queryable(txn: &StorageTxn<'_, RO>, state_number: StateNumber) -> RpcResult<()> {
+    txn.verify_state_queryable(state_number).map_err(storage_error_to_error_object_owned)
+}
+
+// Converts a storage error, telling the errors of a state that isn't queryable apart from the
+// internal errors.
+fn storage_error_to_error_object_owned(err: StorageError) -> ErrorObjectOwned {
+    match err {
+        StorageError::StateNotQueryable { .. } | StorageError::NodeModeError { .. } => {
+            not_kept_in_node_mode(err.to_string()).into()
+        }
+        StorageError::StateQuarantined { .. } => state_quarantined(err.to_string()).into(),
+        _ => internal_server_error(err),
+    }
+}
+
+// Fails if the receipts of the block aren't stored, either because the node doesn't store receipts
+// or because they weren't synced yet, so that it's told apart from a missing transaction or event.
+fn verify_receipts_synced(txn: &StorageTxn<'_, RO>, block_number: BlockNumber) -> RpcResult<()> {
+    let block_id = BlockId::HashOrNumber(BlockHashOrNumber::Number(block_number));
+    match get_missing_block_data(txn, block_id, RequiredBlockData::Receipts)
+        .map_err(internal_server_error)?
+    {
+        Some(block_not_fully_synced) => Err(block_not_fully_synced.into()),
+        None => Ok(()),
+    }
+}
+
+// Runs the given read on a read transaction in the blocking thread pool. Reading and deserializing
+// big objects from the storage can take long, and running it on the executor threads would stall
+// unrelated requests.
+async fn read_storage<T: Send + 'static>(
+    storage_reader: &StorageReader,
+    read: impl FnOnce(&StorageTxn<'_, RO>) -> RpcResult<T> + Send + 'static,
+) -> RpcResult<T> {
+    let storage_reader = storage_reader.clone();
+    spawn_blocking_in_current_span(move || {
+        let txn = storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        read(&txn)
+    })
+    .await
+    .map_err(internal_server_error)?
+}
+
+// Runs the given function in the blocking thread pool, inside the span of the caller, so that the
+// spans of the storage operations it causes are nested in the span of the request.
+pub(crate) fn spawn_blocking_in_current_span<F, R>(function: F) -> JoinHandle<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let span = Span::current();
+    tokio::task::spawn_blocking(move || span.in_scope(function))
+}
+
 /// Get the latest block that we've downloaded and that we've downloaded its state diff.
 fn get_latest_block_number<Mode: TransactionKind>(
     txn: &StorageTxn<'_, Mode>,
@@ -184,6 +495,9 @@ fn get_latest_block_number<Mode: TransactionKind>(
     Ok(txn.get_state_marker().map_err(internal_server_error)?.prev())
 }
 
+/// The status of a stored block: ACCEPTED_ON_L1 once the base layer marker passed it, and
+/// ACCEPTED_ON_L2 before that. Pending blocks are returned without a status, and REJECTED is never
+/// returned since only accepted blocks are stored.
 fn get_block_status<Mode: TransactionKind>(
     txn: &StorageTxn<'_, Mode>,
     block_number: BlockNumber,
@@ -198,57 +512,306 @@ fn get_block_status<Mode: TransactionKind>(
     Ok(status)
 }
 
+// A continuation token points to the next event to return and holds the hash of the last accepted
+// block that was scanned, so that tokens issued before a reorg are detected as stale.
 #[derive(Clone, Debug, PartialEq)]
-struct ContinuationTokenAsStruct(EventIndex);
+struct ContinuationTokenAsStruct(EventIndex, BlockHash);
+
+// The encoded event index is the block number, the transaction offset and the event offset, each
+// as 8 big-endian bytes, followed by the 32 bytes of the block hash.
+const CONTINUATION_TOKEN_PAYLOAD_LEN: usize = 56;
+pub(crate) const CONTINUATION_TOKEN_SIGNATURE_LEN: usize = 16;
+
+impl ContinuationTokenAsStruct {
+    // Creates a token that points to the given event. If the event is in an accepted block the
+    // token holds the hash of that block, and if it's in the pending block the token holds the
+    // hash of the pending block's parent.
+    fn new<Mode: TransactionKind>(
+        txn: &StorageTxn<'_, Mode>,
+        event_index: EventIndex,
+    ) -> Result<Self, ErrorObjectOwned> {
+        let block_number = event_index.0.0;
+        let header = match txn.get_block_header(block_number).map_err(internal_server_error)? {
+            Some(header) => Some(header),
+            None => match block_number.prev() {
+                Some(parent_block_number) => {
+                    txn.get_block_header(parent_block_number).map_err(internal_server_error)?
+                }
+                None => None,
+            },
+        };
+        let header = header.ok_or_else(|| {
+            internal_server_error(format!(
+                "Creating a continuation token for block {block_number} without its parent."
+            ))
+        })?;
+        Ok(Self(event_index, header.block_hash))
+    }
+
+    // Returns whether the token was issued on a chain that has since been reorged. The token's
+    // block hash should be the hash of the block it points to, or of its parent if it pointed to
+    // the pending block (which may have been accepted since).
+    fn is_stale<Mode: TransactionKind>(
+        &self,
+        txn: &StorageTxn<'_, Mode>,
+    ) -> Result<bool, ErrorObjectOwned> {
+        let block_number = self.0.0.0;
+        let Some(hash_block_number) =
+            txn.get_block_number_by_hash(&self.1).map_err(internal_server_error)?
+        else {
+            return Ok(true);
+        };
+        Ok(hash_block_number != block_number && hash_block_number.next() != block_number)
+    }
+
+    // Encodes the token as an opaque hex string, signed with the key so that clients can't forge
+    // tokens that point to an arbitrary event.
+    fn encode(&self, key: &ContinuationTokenKey) -> String {
+        let EventIndex(TransactionIndex(block_number, tx_offset), event_offset) = self.0;
+        let mut payload = Vec::with_capacity(CONTINUATION_TOKEN_PAYLOAD_LEN);
+        payload.extend(block_number.0.to_be_bytes());
+        payload.extend((tx_offset.0 as u64).to_be_bytes());
+        payload.extend((event_offset.0 as u64).to_be_bytes());
+        payload.extend(self.1 .0.bytes());
+        let signature = key.sign(&payload);
+        hex::encode([payload.as_slice(), signature.as_slice()].concat())
+    }
+
+    // Returns None if the token wasn't encoded with the key.
+    fn decode(token: &str, key: &ContinuationTokenKey) -> Option<Self> {
+        let bytes = hex::decode(token).ok()?;
+        if bytes.len() != CONTINUATION_TOKEN_PAYLOAD_LEN + CONTINUATION_TOKEN_SIGNATURE_LEN {
+            return None;
+        }
+        let (payload, signature) = bytes.split_at(CONTINUATION_TOKEN_PAYLOAD_LEN);
+        if !key.verify(payload, signature) {
+            return None;
+        }
+        let read_u64 = |i: usize| {
+            u64::from_be_bytes(payload[8 * i..8 * (i + 1)].try_into().expect("8 bytes slice"))
+        };
+        let block_hash_bytes: [u8; 32] = payload[24..].try_into().expect("32 bytes slice");
+        Some(Self(
+            EventIndex(
+                TransactionIndex(
+                    BlockNumber(read_u64(0)),
+                    TransactionOffsetInBlock(usize::try_from(read_u64(1)).ok()?),
+                ),
+                EventIndexInTransactionOutput(usize::try_from(read_u64(2)).ok()?),
+            ),
+            BlockHash(StarkHash::new(block_hash_bytes).ok()?),
+        ))
+    }
+}
+
+/// The state the server shares with the other components of the node.
+#[derive(Clone, Default)]
+pub struct RpcSharedState {
+    /// The highest block of the chain, written by the sync.
+    pub shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
+    /// The pending block and its state, written by the sync.
+    pub pending_data: Arc<RwLock<PendingData>>,
+    /// The classes declared in the pending block, written by the sync.
+    pub pending_classes: Arc<RwLock<PendingClasses>>,
+    /// The load of the server, read by the sync which slows down when the server is loaded.
+    pub rpc_load: Arc<RpcLoad>,
+    /// The notifications of the sync on changes of the chain.
+    pub sync_notifier: Arc<SyncNotifier>,
+    /// The state writes of the recent blocks, written by the sync.
+    pub recent_state: Arc<RecentStateCache>,
+    /// The classes whose download the sync deferred and the server requested.
+    pub deferred_class_requests: Arc<DeferredClassRequests>,
+}
 
-#[instrument(skip(storage_reader), level = "debug", err)]
+#[instrument(skip(shared_state, storage_reader), level = "debug", err)]
 pub async fn run_server(
     config: &RpcConfig,
-    shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
-    pending_data: Arc<RwLock<PendingData>>,
-    pending_classes: Arc<RwLock<PendingClasses>>,
+    shared_state: RpcSharedState,
     storage_reader: StorageReader,
     node_version: &'static str,
 ) -> anyhow::Result<(SocketAddr, ServerHandle)> {
+    let RpcSharedState {
+        shared_highest_block,
+        pending_data,
+        pending_classes,
+        rpc_load,
+        sync_notifier,
+        recent_state,
+        deferred_class_requests,
+    } = shared_state;
     let starting_block = get_last_synced_block(storage_reader.clone())?;
     debug!("Starting JSON-RPC.");
-    let methods = get_methods_from_supported_apis(
+    let trace_cache = Arc::new(match &config.trace_cache_path {
+        Some(path) => TraceCache::new(path.clone(), config.trace_cache_blocks)?,
+        None => TraceCache::default(),
+    });
+    if trace_cache.is_enabled() {
+        tokio::spawn(
+            invalidate_reverted_blocks(trace_cache.clone(), sync_notifier.subscribe())
+                .in_current_span(),
+        );
+    }
+    let subscription_methods =
+        SubscriptionsJsonRpcServerImpl { storage_reader: storage_reader.clone(), sync_notifier }
+            .into_rpc();
+    let (methods, streamed_methods) = get_methods_from_supported_apis(
         &config.chain_id,
         config.execution_config.clone().try_into()?,
         storage_reader,
         config.max_events_chunk_size,
         config.max_events_keys,
+        Arc::new(FeeEstimateCache::new(
+            config.fee_estimate_cache_ttl,
+            config.fee_estimate_cache_size,
+        )),
+        Arc::new(SubmittedTransactions::new(config.submitted_transactions_cache_size)),
         starting_block,
         shared_highest_block,
         pending_data,
         pending_classes,
+        recent_state,
+        trace_cache,
+        deferred_class_requests,
+        Arc::new(ExecutionPool::new(
+            config.max_concurrent_executions,
+            config.max_queued_executions,
+        )),
+        ContinuationTokenKey::new(config.continuation_token_secret.as_deref()),
         Arc::new(StarknetGatewayClient::new(
             &config.starknet_url,
             node_version,
             config.starknet_gateway_retry_config,
         )?),
     );
-    let addr;
-    let handle;
-    let server_builder =
-        ServerBuilder::default().max_request_body_size(SERVER_MAX_BODY_SIZE).set_middleware(
-            tower::ServiceBuilder::new()
-                .filter_async(deny_requests_with_unsupported_path)
-                .filter_async(proxy_rpc_request),
-        );
-
-    if config.collect_metrics {
-        let server = server_builder
-            .set_logger(MetricLogger::new(&methods))
-            .build(&config.server_address)
-            .await?;
-        addr = server.local_addr()?;
-        handle = server.start(methods);
+    let request_recorder = config
+        .record_requests_path
+        .as_ref()
+        .map(|path| {
+            info!(path = %path.display(), "Recording the incoming requests.");
+            RequestRecorder::new(path)
+        })
+        .transpose()?;
+    let request_limits = RequestLimits {
+        max_body_size: config.max_request_body_size,
+        max_nesting_depth: config.max_request_nesting_depth,
+    };
+    let access_control = AccessControl::new(&config.access_control);
+    let response_redaction = ResponseRedactionLayer::new(&config.redacted_response_fields);
+    let streaming = StreamingLayer::new(streamed_methods, config.collect_metrics);
+    // Only the subscriptions are served over WebSocket, by a server of their own that is stopped
+    // together with the main server.
+    let (websocket_stop_handle, websocket_handle) = stop_channel();
+    let websocket_builder =
+        ServerBuilder::default().ws_only().max_request_body_size(config.max_request_body_size);
+    let websocket = WebSocketLayer::new(if config.collect_metrics {
+        BoxCloneService::new(
+            websocket_builder
+                .set_logger(MetricLogger::new(&subscription_methods.clone().into()))
+                .to_service_builder()
+                .build(subscription_methods, websocket_stop_handle),
+        )
     } else {
-        let server = server_builder.build(&config.server_address).await?;
-        addr = server.local_addr()?;
-        handle = server.start(methods);
+        BoxCloneService::new(
+            websocket_builder
+                .to_service_builder()
+                .build(subscription_methods, websocket_stop_handle),
+        )
+    });
+    // Each address gets a server of its own, since a server listens on a single address.
+    let request_span = RequestSpanLayer::new();
+    let server_builder = || {
+        let rpc_load = rpc_load.clone();
+        let access_control = access_control.clone();
+        let request_recorder = request_recorder.clone();
+        let response_redaction = response_redaction.clone();
+        let streaming = streaming.clone();
+        let websocket = websocket.clone();
+        ServerBuilder::default()
+            .http_only()
+            .max_request_body_size(config.max_request_body_size)
+            .set_middleware(
+                tower::ServiceBuilder::new()
+                    .map_future(move |request_future| {
+                        record_request_latency(rpc_load.clone(), request_future)
+                    })
+                    .layer(request_span.clone())
+                    .filter_async(deny_requests_with_unsupported_path)
+                    .layer(websocket)
+                    .filter_async(move |request| async move { request_limits.check(request).await })
+                    .option_layer(access_control.map(|access_control| {
+                        AsyncFilterLayer::new(move |request| {
+                            let access_control = access_control.clone();
+                            async move { access_control.check(request).await }
+                        })
+                    }))
+                    .option_layer(response_redaction)
+                    .option_layer(request_recorder.map(|recorder| {
+                        AsyncFilterLayer::new(move |request| {
+                            let recorder = recorder.clone();
+                            async move { recorder.record(request).await }
+                        })
+                    }))
+                    .filter_async(proxy_rpc_request)
+                    .layer(streaming),
+            )
+    };
+
+    let mut servers = Vec::new();
+    for server_address in config.server_address.split_whitespace() {
+        let (addr, handle) = if config.collect_metrics {
+            let server = server_builder()
+                .set_logger(MetricLogger::new(&methods))
+                .build(server_address)
+                .await?;
+            (server.local_addr()?, server.start(methods.clone()))
+        } else {
+            let server = server_builder().build(server_address).await?;
+            (server.local_addr()?, server.start(methods.clone()))
+        };
+        info!(local_address = %addr, "JSON-RPC is running.");
+        servers.push((addr, handle));
     }
-    info!(local_address = %addr, "JSON-RPC is running.");
+    let mut servers = servers.into_iter();
+    let (addr, handle) =
+        servers.next().ok_or_else(|| anyhow::anyhow!("The JSON-RPC server has no address."))?;
+    let mut additional_handles = servers.map(|(_addr, handle)| handle).collect::<Vec<_>>();
+    additional_handles.push(websocket_handle);
+
+    if let Some(unix_socket_path) = &config.unix_socket_path {
+        let listener = bind_unix_socket(unix_socket_path, &config.unix_socket_permissions)?;
+        let (stop_handle, unix_socket_handle) = stop_channel();
+        let path = unix_socket_path.clone();
+        if config.collect_metrics {
+            let service = server_builder()
+                .set_logger(MetricLogger::new(&methods))
+                .to_service_builder()
+                .build(methods.clone(), stop_handle.clone());
+            tokio::spawn(serve_unix_socket(listener, path, service, stop_handle).in_current_span());
+        } else {
+            let service =
+                server_builder().to_service_builder().build(methods.clone(), stop_handle.clone());
+            tokio::spawn(serve_unix_socket(listener, path, service, stop_handle).in_current_span());
+        }
+        info!(path = %unix_socket_path.display(), "JSON-RPC is running on a Unix domain socket.");
+        additional_handles.push(unix_socket_handle);
+    }
+
+    // The servers of the other addresses and of WebSocket are stopped together with the first one.
+    let handle_to_watch = handle.clone();
+    tokio::spawn(async move {
+        handle_to_watch.stopped().await;
+        for additional_handle in additional_handles {
+            // The server may have stopped already.
+            let _ = additional_handle.stop();
+        }
+    });
     Ok((addr, handle))
 }
+
+// Awaits the given request and records its latency in the RPC load signal.
+async fn record_request_latency<F: Future>(rpc_load: Arc<RpcLoad>, request_future: F) -> F::Output {
+    let start = Instant::now();
+    let response = request_future.await;
+    rpc_load.record_latency(start.elapsed());
+    response
+}
@@ -1,15 +1,78 @@
+use std::fs::{File, OpenOptions};
+use std::io::{LineWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use hyper::header::UPGRADE;
 use hyper::{Body, Request};
+use jsonrpsee::core::error::GenericTransportError;
 use jsonrpsee::core::http_helpers::read_body;
 use regex::Regex;
-use tower::BoxError;
-use tracing::{debug, instrument};
+use serde::{Deserialize, Serialize};
+use tower::{BoxError, Layer, Service};
+use tracing::instrument::Instrumented;
+use tracing::{debug, debug_span, field, instrument, warn, Instrument, Span};
 
+use crate::papyrus_api::PAPYRUS_METHOD_PREFIX;
+use crate::rpc_metrics::{increment_rejected_requests, RejectionReason};
 use crate::version_config::{VersionState, VERSION_CONFIG, VERSION_PATTERN};
 use crate::SERVER_MAX_BODY_SIZE;
 
+/// [`Tower`] layer that runs each request inside a span, so that the spans of the methods it calls
+/// and of the storage operations they cause are nested in it. [`proxy_rpc_request`] records the
+/// methods of the request in the span. The requests are served in tasks of the server, so they're
+/// also run in the span in which the layer was created, e.g. the span of the chain of the node.
+///
+/// [`Tower`]: https://crates.io/crates/tower
+#[derive(Clone)]
+pub(crate) struct RequestSpanLayer {
+    parent: Span,
+}
+
+impl RequestSpanLayer {
+    pub(crate) fn new() -> Self {
+        Self { parent: Span::current() }
+    }
+}
+
+impl<S> Layer<S> for RequestSpanLayer {
+    type Service = RequestSpan<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestSpan { inner, parent: self.parent.clone() }
+    }
+}
+
+/// The service of [`RequestSpanLayer`].
+#[derive(Clone)]
+pub(crate) struct RequestSpan<S> {
+    inner: S,
+    parent: Span,
+}
+
+impl<S: Service<Request<Body>>> Service<Request<Body>> for RequestSpan<S> {
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Instrumented<Instrumented<S::Future>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let _parent_guard = self.parent.enter();
+        let span = debug_span!("rpc_request", path = %req.uri().path(), methods = field::Empty);
+        let request_future = span.in_scope(|| self.inner.call(req));
+        request_future.instrument(span).instrument(self.parent.clone())
+    }
+}
+
 /// [`Tower`] middleware intended to proxy method requests to the right version of the API.
 /// The middleware reads the JsonRPC request body and request path
 /// then prefixes the method name with the appropriate version identifier.
+/// Papyrus specific methods (prefixed with `papyrus_`) keep their prefix and get the version
+/// identifier after it, so that the shared handlers know the version of the request.
 /// It returns a new [`hyper::Request`] object with the new method name.
 ///
 /// # Arguments
@@ -55,18 +118,149 @@ pub(crate) async fn deny_requests_with_unsupported_path(
     }
 }
 
+/// [`Tower`] middleware that rejects requests whose body is larger than the configured size or
+/// whose JSON is nested deeper than the configured depth, before the server parses them, and counts
+/// the rejected requests in the metrics. The messages of WebSocket connections don't pass through
+/// the middleware, and their size is limited by the WebSocket server instead, which serves only the
+/// subscriptions.
+///
+/// [`Tower`]: https://crates.io/crates/tower
+#[derive(Clone, Copy)]
+pub(crate) struct RequestLimits {
+    pub max_body_size: u32,
+    pub max_nesting_depth: usize,
+}
+
+impl RequestLimits {
+    pub(crate) async fn check(&self, req: Request<Body>) -> Result<Request<Body>, BoxError> {
+        let (parts, body) = req.into_parts();
+        let (body_bytes, _is_single) =
+            read_body(&parts.headers, body, self.max_body_size).await.map_err(|err| {
+                if matches!(err, GenericTransportError::TooLarge) {
+                    increment_rejected_requests(RejectionReason::BodySize);
+                }
+                BoxError::from(err)
+            })?;
+        if exceeds_nesting_depth(&body_bytes, self.max_nesting_depth) {
+            increment_rejected_requests(RejectionReason::NestingDepth);
+            return Err(BoxError::from(format!(
+                "The request is nested deeper than {} levels",
+                self.max_nesting_depth
+            )));
+        }
+        Ok(Request::from_parts(parts, body_bytes.into()))
+    }
+}
+
+// Returns whether the arrays and objects of a JSON text are nested deeper than the given depth.
+// The text isn't parsed, so that the check is cheap and can't overflow the stack.
+pub(crate) fn exceeds_nesting_depth(json: &[u8], max_depth: usize) -> bool {
+    let mut depth = 0_usize;
+    let mut in_string = false;
+    let mut is_escaped = false;
+    for byte in json {
+        if in_string {
+            match byte {
+                _ if is_escaped => is_escaped = false,
+                b'\\' => is_escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match byte {
+            b'"' => in_string = true,
+            b'[' | b'{' => {
+                depth += 1;
+                if depth > max_depth {
+                    return true;
+                }
+            }
+            b']' | b'}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    false
+}
+
+/// A request as it was received by the server, before its method name was versioned. The recorder
+/// writes the requests to its file as JSON lines in this format.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct RecordedRequest {
+    /// The path the request was sent to, e.g. "/rpc/v0_7".
+    pub path: String,
+    /// The JSON-RPC request body, either a single request or a batch.
+    pub body: serde_json::Value,
+}
+
+/// [`Tower`] middleware that appends the incoming requests to a file, so that they can be replayed
+/// against a node for load testing and for comparing the responses of different versions.
+///
+/// [`Tower`]: https://crates.io/crates/tower
+#[derive(Clone)]
+pub(crate) struct RequestRecorder {
+    writer: Arc<Mutex<LineWriter<File>>>,
+}
+
+impl RequestRecorder {
+    /// Opens the file the requests are appended to, creating it if needed.
+    pub(crate) fn new(path: &Path) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { writer: Arc::new(Mutex::new(LineWriter::new(file))) })
+    }
+
+    pub(crate) async fn record(&self, req: Request<Body>) -> Result<Request<Body>, BoxError> {
+        let path = req.uri().path().to_owned();
+        let (parts, body) = req.into_parts();
+        let (body_bytes, _is_single) =
+            read_body(&parts.headers, body, SERVER_MAX_BODY_SIZE).await.map_err(BoxError::from)?;
+        match serde_json::from_slice(&body_bytes) {
+            // Failing to record a request shouldn't fail the request itself.
+            Ok(body) => {
+                if let Err(err) = self.write(&RecordedRequest { path, body }) {
+                    warn!("Failed recording a request: {err}.");
+                }
+            }
+            Err(err) => debug!("Not recording a request with an invalid body: {err}."),
+        }
+        Ok(Request::from_parts(parts, body_bytes.into()))
+    }
+
+    fn write(&self, request: &RecordedRequest) -> std::io::Result<()> {
+        let mut line = serde_json::to_vec(request)?;
+        line.push(b'\n');
+        self.writer.lock().expect("Request recorder lock should not be poisoned.").write_all(&line)
+    }
+}
+
+/// Returns whether the request asks to upgrade the connection to WebSocket. These requests are
+/// served by [`crate::websocket::WebSocketLayer`] and never reach the rest of the middleware.
+pub(crate) fn is_websocket_upgrade(req: &Request<Body>) -> bool {
+    req.headers()
+        .get(UPGRADE)
+        .and_then(|upgrade| upgrade.to_str().ok())
+        .is_some_and(|upgrade| upgrade.eq_ignore_ascii_case("websocket"))
+}
+
 fn add_version_to_method_name_in_body(
     mut vec_body: Vec<jsonrpsee::types::Request<'_>>,
     prefix: &str,
     is_single: bool,
 ) -> Result<Vec<u8>, BoxError> {
+    // The methods are recorded in the span of the request as the client sent them.
+    let methods = vec_body.iter().map(|body| body.method.as_ref()).collect::<Vec<_>>().join(" ");
+    Span::current().record("methods", methods.as_str());
     let Ok(vec_body) = vec_body
         .iter_mut()
         .map(|body| {
-            let Some(stripped_method) = strip_starknet_from_method(body.method.as_ref()) else {
-                return Err(BoxError::from("Method name has unexpected format"));
-            };
-            body.method = format!("starknet_{prefix}_{stripped_method}").into();
+            body.method = match routed_method(body.method.as_ref()) {
+                Some(RoutedMethod::Papyrus(method)) => {
+                    format!("{PAPYRUS_METHOD_PREFIX}{prefix}_{method}")
+                }
+                Some(RoutedMethod::Starknet(method)) => format!("starknet_{prefix}_{method}"),
+                None => return Err(BoxError::from("Method name has unexpected format")),
+            }
+            .into();
             Ok(body)
         })
         .collect::<Result<Vec<_>, _>>()
@@ -80,6 +274,24 @@ fn add_version_to_method_name_in_body(
     serialized.map_err(BoxError::from)
 }
 
+/// The method that [`proxy_rpc_request`] routes a request to, by the name of the method without
+/// its namespace.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum RoutedMethod<'a> {
+    Starknet(&'a str),
+    Papyrus(&'a str),
+}
+
+/// Returns the method that [`proxy_rpc_request`] routes the method name sent by the client to, or
+/// None if the name is rejected. Middleware that acts by the method of a request must use it, so
+/// that a method can't be reached under a name the middleware doesn't recognize.
+pub(crate) fn routed_method(method: &str) -> Option<RoutedMethod<'_>> {
+    if let Some(stripped_method) = method.strip_prefix(PAPYRUS_METHOD_PREFIX) {
+        return Some(RoutedMethod::Papyrus(stripped_method));
+    }
+    strip_starknet_from_method(method).map(RoutedMethod::Starknet)
+}
+
 /// this assumes that all methods are of the form:
 /// starknet_OnlyOneUnderScoreAndMethodNameIsCamleCased
 fn strip_starknet_from_method(method: &str) -> Option<&str> {
@@ -0,0 +1,835 @@
+use std::cmp::min;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use jsonrpsee::core::RpcResult;
+use jsonrpsee::types::error::ErrorCode::InvalidParams;
+use jsonrpsee::types::ErrorObjectOwned;
+use papyrus_common::block_execution_resources::BlockExecutionResources;
+use papyrus_common::deferred_classes::DeferredClassRequests;
+use papyrus_common::state_diff_commitment::{calculate_state_diff_commitment, StateDiffVersion};
+use papyrus_execution::execution_utils::{get_fee_token_balance, selector_from_name};
+use papyrus_execution::objects::StateOverride;
+use papyrus_execution::{
+    execute_call,
+    execute_call_with_state_override,
+    simulate_transactions_with_state_override,
+    ExecutableTransactionInput,
+    ExecutionConfigByBlock,
+    ExecutionError,
+    ExecutionResult,
+};
+use papyrus_storage::base_layer::BaseLayerStorageReader;
+use papyrus_storage::body::{BodyStorageReader, TransactionIndex};
+use papyrus_storage::class_usage::ClassUsageStorageReader;
+use papyrus_storage::compiled_class::CasmStorageReader;
+use papyrus_storage::db::RO;
+use papyrus_storage::header::HeaderStorageReader;
+use papyrus_storage::state::StateStorageReader;
+use papyrus_storage::{StorageReader, StorageTxn};
+use starknet_api::block::BlockNumber;
+use starknet_api::core::{ChainId, ClassHash, ContractAddress, EntryPointSelector};
+use starknet_api::deprecated_contract_class::EntryPointType as DeprecatedEntryPointType;
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::{EntryPointType, StateNumber, StorageKey};
+use starknet_api::transaction::{Calldata, Transaction, TransactionHash, TransactionOffsetInBlock};
+use tracing::instrument;
+
+use super::{
+    AddressTransaction,
+    AddressTransactionsChunk,
+    AncestryProof,
+    BlockExecutionResourcesData,
+    BlockSignatureData,
+    BlockStateDiffStats,
+    BlockTransactionsPage,
+    ClassUsage,
+    EntryPointInfo,
+    FeeTokenBalance,
+    HeaderAccumulatorProof,
+    Markers,
+    PapyrusJsonRpcServer,
+    SignedBlockHeader,
+    StorageChange,
+    TokenMetadata,
+    TokenStandard,
+    TransactionPosition,
+    VersionedTransactionWithHash,
+    MAX_ANCESTRY_PROOF_DISTANCE,
+    MAX_BLOCK_TRANSACTIONS_PAGE_SIZE,
+    MAX_CLASS_USAGE_BLOCK_RANGE,
+    MAX_HEADER_CHAIN_LENGTH,
+    MAX_MOST_USED_CLASSES_LENGTH,
+    MAX_STATE_DIFF_STATS_LENGTH,
+    MAX_STORAGE_HISTORY_LENGTH,
+    MAX_TRANSACTIONS_BY_ADDRESS_CHUNK_SIZE,
+};
+use crate::api::{BlockId, CallRequest};
+use crate::execution_pool::ExecutionPool;
+use crate::execution_utils::execute_with_deferred_classes;
+use crate::v0_5::transaction::TransactionWithHash as TransactionWithHashWithoutV3;
+use crate::v0_7::api::{
+    execution_error_to_error_object_owned,
+    FeeEstimate,
+    SimulatedTransaction,
+    SimulationFlag,
+};
+use crate::v0_7::block::{
+    get_accepted_block_number,
+    get_accepted_block_number_with_body,
+    BlockNotRevertedValidator,
+};
+use crate::v0_7::broadcasted_transaction::BroadcastedTransaction;
+use crate::v0_7::error::{BLOCK_NOT_FOUND, CONTRACT_NOT_FOUND};
+use crate::v0_7::transaction::TransactionWithHash;
+use crate::validation::validate_call_request;
+use crate::version_config::VersionCapabilities;
+use crate::{
+    internal_server_error,
+    verify_receipts_synced,
+    verify_state_queryable,
+    verify_storage_scope,
+};
+
+/// Rpc server of the Papyrus specific methods.
+pub struct PapyrusJsonRpcServerImpl {
+    pub storage_reader: StorageReader,
+    pub execution_config: ExecutionConfigByBlock,
+    pub chain_id: ChainId,
+    /// The capabilities of the version the methods are served for.
+    pub capabilities: VersionCapabilities,
+    pub deferred_class_requests: Arc<DeferredClassRequests>,
+    pub execution_pool: Arc<ExecutionPool>,
+}
+
+#[async_trait]
+impl PapyrusJsonRpcServer for PapyrusJsonRpcServerImpl {
+    #[instrument(skip(self), level = "debug", err, ret)]
+    fn get_markers(&self) -> RpcResult<Markers> {
+        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        Ok(Markers {
+            header: txn.get_header_marker().map_err(internal_server_error)?,
+            body: txn.get_body_marker().map_err(internal_server_error)?,
+            receipts: txn.get_receipts_marker().map_err(internal_server_error)?,
+            state: txn.get_state_marker().map_err(internal_server_error)?,
+            compiled_class: txn.get_compiled_class_marker().map_err(internal_server_error)?,
+            base_layer: txn.get_base_layer_block_marker().map_err(internal_server_error)?,
+        })
+    }
+
+    #[instrument(skip(self), level = "debug", err, ret)]
+    fn get_block_signature(&self, block_number: BlockNumber) -> RpcResult<BlockSignatureData> {
+        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        let SignedBlockHeader { header, state_diff_commitment, signature } =
+            get_signed_header(&txn, block_number)?.ok_or(BLOCK_NOT_FOUND)?;
+        Ok(BlockSignatureData { block_hash: header.block_hash, state_diff_commitment, signature })
+    }
+
+    #[instrument(skip(self), level = "debug", err)]
+    fn get_header_chain(
+        &self,
+        start_block_number: BlockNumber,
+        length: usize,
+    ) -> RpcResult<Vec<SignedBlockHeader>> {
+        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        let mut headers = vec![];
+        let mut block_number = start_block_number;
+        while headers.len() < min(length, MAX_HEADER_CHAIN_LENGTH) {
+            let Some(signed_header) = get_signed_header(&txn, block_number)? else {
+                break;
+            };
+            headers.push(signed_header);
+            block_number = block_number.next();
+        }
+        Ok(headers)
+    }
+
+    #[instrument(skip(self), level = "debug", err)]
+    fn get_ancestry_proof(
+        &self,
+        block_number: BlockNumber,
+        ancestor_block_number: BlockNumber,
+    ) -> RpcResult<AncestryProof> {
+        if ancestor_block_number > block_number {
+            return Err(ErrorObjectOwned::owned(
+                InvalidParams.code(),
+                "The ancestor block number is greater than the block number.",
+                None::<()>,
+            ));
+        }
+        if block_number.0 - ancestor_block_number.0 > MAX_ANCESTRY_PROOF_DISTANCE {
+            return Err(ErrorObjectOwned::owned(
+                InvalidParams.code(),
+                format!(
+                    "The ancestor is more than {MAX_ANCESTRY_PROOF_DISTANCE} blocks below the \
+                     block."
+                ),
+                None::<()>,
+            ));
+        }
+        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        let block = get_signed_header(&txn, block_number)?.ok_or(BLOCK_NOT_FOUND)?;
+        let mut ancestor_headers = vec![];
+        for ancestor in (ancestor_block_number.0..block_number.0).rev() {
+            let header = txn
+                .get_block_header(BlockNumber(ancestor))
+                .map_err(internal_server_error)?
+                .ok_or(BLOCK_NOT_FOUND)?;
+            ancestor_headers.push(header);
+        }
+        Ok(AncestryProof { block, ancestor_headers })
+    }
+
+    #[instrument(skip(self), level = "debug", err, ret)]
+    fn get_balance(
+        &self,
+        contract_address: ContractAddress,
+        block_id: BlockId,
+    ) -> RpcResult<FeeTokenBalance> {
+        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        let block_number = get_accepted_block_number(&txn, block_id)?;
+        let state_number = StateNumber::right_after_block(block_number);
+        verify_state_queryable(&txn, state_number)?;
+        let fee_token_address = self
+            .execution_config
+            .get_execution_config_for_block(block_number)
+            .map_err(internal_server_error)?
+            .fee_contract_address;
+        let (low, high) =
+            get_fee_token_balance(&txn, state_number, fee_token_address, contract_address)
+                .map_err(internal_server_error)?;
+        Ok(FeeTokenBalance { low, high })
+    }
+
+    #[instrument(skip(self), level = "debug", err, ret)]
+    async fn get_token_metadata(
+        &self,
+        contract_address: ContractAddress,
+        block_id: BlockId,
+    ) -> RpcResult<TokenMetadata> {
+        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        let block_number = get_accepted_block_number(&txn, block_id)?;
+        let block_not_reverted_validator = BlockNotRevertedValidator::new(block_number, &txn)?;
+        let state_number = StateNumber::right_after_block(block_number);
+        verify_state_queryable(&txn, state_number)?;
+        let selectors = get_external_selectors(&txn, state_number, contract_address)?;
+        drop(txn);
+        let block_execution_config = self
+            .execution_config
+            .get_execution_config_for_block(block_number)
+            .map_err(|err| {
+                internal_server_error(format!("Failed to get execution config: {}", err))
+            })?
+            .clone();
+        let chain_id = self.chain_id.clone();
+        let reader = self.storage_reader.clone();
+
+        let token_metadata = execute_with_deferred_classes(
+            &self.execution_pool,
+            &self.storage_reader,
+            &self.deferred_class_requests,
+            move || {
+                // Calls a view function of the contract without arguments, if the contract has it.
+                let call = |function_name: &str| -> ExecutionResult<Option<Vec<StarkFelt>>> {
+                    let selector = selector_from_name(function_name);
+                    if !selectors.contains(&selector) {
+                        return Ok(None);
+                    }
+                    match execute_call(
+                        reader.clone(),
+                        None,
+                        &chain_id,
+                        state_number,
+                        block_number,
+                        &contract_address,
+                        selector,
+                        Calldata::default(),
+                        &block_execution_config,
+                    ) {
+                        Ok(call_execution) => Ok(Some(call_execution.retdata.0)),
+                        // Returned so that the class is downloaded if its download was deferred.
+                        Err(err @ ExecutionError::MissingCompiledClass { .. }) => Err(err),
+                        Err(_) => Ok(None),
+                    }
+                };
+                Ok(TokenMetadata {
+                    standard: detect_token_standard(&selectors),
+                    name: call("name")?.and_then(|retdata| decode_string(&retdata)),
+                    symbol: call("symbol")?.and_then(|retdata| decode_string(&retdata)),
+                    decimals: call("decimals")?.and_then(|retdata| {
+                        u8::try_from(u64::try_from(*retdata.first()?).ok()?).ok()
+                    }),
+                })
+            },
+        )
+        .await?
+        .map_err(execution_error_to_error_object_owned)?;
+
+        block_not_reverted_validator.validate(&self.storage_reader)?;
+
+        Ok(token_metadata)
+    }
+
+    #[instrument(skip(self), level = "debug", err, ret)]
+    fn get_entry_point(
+        &self,
+        class_hash: ClassHash,
+        selector: EntryPointSelector,
+    ) -> RpcResult<Option<EntryPointInfo>> {
+        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        let state_reader = txn.get_state_reader().map_err(internal_server_error)?;
+        Ok(state_reader
+            .get_entry_point(&class_hash, &selector)
+            .map_err(internal_server_error)?
+            .map(EntryPointInfo::from))
+    }
+
+    #[instrument(skip(self), level = "debug", err)]
+    fn get_state_diff_stats(
+        &self,
+        start_block_number: BlockNumber,
+        length: usize,
+    ) -> RpcResult<Vec<BlockStateDiffStats>> {
+        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        let mut block_stats = vec![];
+        let mut block_number = start_block_number;
+        while block_stats.len() < min(length, MAX_STATE_DIFF_STATS_LENGTH) {
+            let Some(stats) =
+                txn.get_state_diff_stats(block_number).map_err(internal_server_error)?
+            else {
+                break;
+            };
+            block_stats.push(BlockStateDiffStats { block_number, stats });
+            block_number = block_number.next();
+        }
+        Ok(block_stats)
+    }
+
+    #[instrument(skip(self), level = "debug", err, ret)]
+    fn get_header_accumulator_proof(
+        &self,
+        block_number: BlockNumber,
+    ) -> RpcResult<HeaderAccumulatorProof> {
+        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        let header = txn
+            .get_block_header(block_number)
+            .map_err(internal_server_error)?
+            .ok_or(BLOCK_NOT_FOUND)?;
+        let proof = txn
+            .get_header_accumulator_proof(block_number)
+            .map_err(internal_server_error)?
+            .ok_or(BLOCK_NOT_FOUND)?;
+        Ok(HeaderAccumulatorProof { block_hash: header.block_hash, root: proof.root(), proof })
+    }
+
+    #[instrument(skip(self), level = "debug", err, ret)]
+    fn get_block_execution_resources(
+        &self,
+        block_id: BlockId,
+    ) -> RpcResult<BlockExecutionResourcesData> {
+        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        let block_number = get_accepted_block_number(&txn, block_id)?;
+        verify_receipts_synced(&txn, block_number)?;
+        let header = txn
+            .get_block_header(block_number)
+            .map_err(internal_server_error)?
+            .ok_or(BLOCK_NOT_FOUND)?;
+        let transactions = txn
+            .get_block_transactions(block_number)
+            .map_err(internal_server_error)?
+            .ok_or(BLOCK_NOT_FOUND)?;
+        let transaction_outputs = txn
+            .get_block_transaction_outputs(block_number)
+            .map_err(internal_server_error)?
+            .ok_or(BLOCK_NOT_FOUND)?;
+        Ok(BlockExecutionResourcesData {
+            block_number,
+            resources: BlockExecutionResources::new(
+                &transactions,
+                &transaction_outputs,
+                header.l1_gas_price,
+            ),
+        })
+    }
+
+    #[instrument(skip(self), level = "debug", err, ret)]
+    async fn call_with_state_override(
+        &self,
+        request: CallRequest,
+        block_id: BlockId,
+        state_override: StateOverride,
+    ) -> RpcResult<Vec<StarkFelt>> {
+        validate_call_request(&request)?;
+        validate_state_override(&state_override)?;
+        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        let block_number = get_accepted_block_number(&txn, block_id)?;
+        let block_not_reverted_validator = BlockNotRevertedValidator::new(block_number, &txn)?;
+        drop(txn);
+        let state_number = StateNumber::right_after_block(block_number);
+        let block_execution_config = self
+            .execution_config
+            .get_execution_config_for_block(block_number)
+            .map_err(|err| {
+                internal_server_error(format!("Failed to get execution config: {}", err))
+            })?
+            .clone();
+        let chain_id = self.chain_id.clone();
+        let reader = self.storage_reader.clone();
+
+        let res = execute_with_deferred_classes(
+            &self.execution_pool,
+            &self.storage_reader,
+            &self.deferred_class_requests,
+            move || {
+                execute_call_with_state_override(
+                    reader.clone(),
+                    None,
+                    &chain_id,
+                    state_number,
+                    block_number,
+                    &request.contract_address,
+                    request.entry_point_selector,
+                    request.calldata.clone(),
+                    &block_execution_config,
+                    &state_override,
+                )
+            },
+        )
+        .await?
+        .map_err(execution_error_to_error_object_owned)?;
+
+        block_not_reverted_validator.validate(&self.storage_reader)?;
+
+        Ok(res.retdata.0)
+    }
+
+    #[instrument(skip(self), level = "debug", err, ret)]
+    async fn simulate_transactions_with_state_override(
+        &self,
+        block_id: BlockId,
+        transactions: Vec<BroadcastedTransaction>,
+        simulation_flags: Vec<SimulationFlag>,
+        state_override: StateOverride,
+    ) -> RpcResult<Vec<SimulatedTransaction>> {
+        validate_state_override(&state_override)?;
+        let executable_txns = transactions
+            .into_iter()
+            .map(ExecutableTransactionInput::try_from)
+            .collect::<Result<_, _>>()?;
+        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        let block_number = get_accepted_block_number(&txn, block_id)?;
+        let block_not_reverted_validator = BlockNotRevertedValidator::new(block_number, &txn)?;
+        drop(txn);
+        let state_number = StateNumber::right_after_block(block_number);
+        let block_execution_config = self
+            .execution_config
+            .get_execution_config_for_block(block_number)
+            .map_err(|err| {
+                internal_server_error(format!("Failed to get execution config: {}", err))
+            })?
+            .clone();
+        let chain_id = self.chain_id.clone();
+        let reader = self.storage_reader.clone();
+
+        let charge_fee = !simulation_flags.contains(&SimulationFlag::SkipFeeCharge);
+        let validate = !simulation_flags.contains(&SimulationFlag::SkipValidate);
+
+        let simulation_results = execute_with_deferred_classes(
+            &self.execution_pool,
+            &self.storage_reader,
+            &self.deferred_class_requests,
+            move || {
+                simulate_transactions_with_state_override(
+                    executable_txns.clone(),
+                    None,
+                    &chain_id,
+                    reader.clone(),
+                    None,
+                    state_number,
+                    block_number,
+                    &block_execution_config,
+                    charge_fee,
+                    validate,
+                    &state_override,
+                )
+            },
+        )
+        .await?
+        .map_err(execution_error_to_error_object_owned)?;
+
+        block_not_reverted_validator.validate(&self.storage_reader)?;
+
+        Ok(simulation_results
+            .into_iter()
+            .map(|simulation_output| SimulatedTransaction {
+                transaction_trace: simulation_output.transaction_trace,
+                fee_estimation: FeeEstimate::from(
+                    simulation_output.gas_price,
+                    simulation_output.fee,
+                    simulation_output.price_unit,
+                ),
+            })
+            .collect())
+    }
+
+    #[instrument(skip(self), level = "debug", err)]
+    fn get_transactions_by_address(
+        &self,
+        address: ContractAddress,
+        chunk_size: usize,
+        continuation_token: Option<TransactionPosition>,
+    ) -> RpcResult<AddressTransactionsChunk> {
+        if chunk_size == 0 {
+            return Err(ErrorObjectOwned::owned(
+                InvalidParams.code(),
+                "The chunk size must be positive.",
+                None::<()>,
+            ));
+        }
+        verify_storage_scope(&self.storage_reader)?;
+        let chunk_size = min(chunk_size, MAX_TRANSACTIONS_BY_ADDRESS_CHUNK_SIZE);
+        let from_position = continuation_token
+            .unwrap_or(TransactionPosition { block_number: BlockNumber(0), transaction_index: 0 });
+        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        // One more transaction is read to tell whether there are more transactions after the chunk.
+        let mut indexed_transactions = txn
+            .get_transactions_by_sender(address, transaction_index(from_position), chunk_size + 1)
+            .map_err(internal_server_error)?;
+        let continuation_token = if indexed_transactions.len() > chunk_size {
+            indexed_transactions.pop().map(|(index, _nonce)| transaction_position(index))
+        } else {
+            None
+        };
+        let transactions = indexed_transactions
+            .into_iter()
+            .map(|(index, nonce)| {
+                let transaction_hash = txn
+                    .get_transaction_hash_by_idx(&index)
+                    .map_err(internal_server_error)?
+                    .ok_or_else(|| {
+                        internal_server_error(format!(
+                            "Missing hash of indexed transaction {index:?}."
+                        ))
+                    })?;
+                Ok(AddressTransaction {
+                    transaction_hash,
+                    position: transaction_position(index),
+                    nonce,
+                })
+            })
+            .collect::<RpcResult<_>>()?;
+        Ok(AddressTransactionsChunk { transactions, continuation_token })
+    }
+
+    #[instrument(skip(self), level = "debug", err)]
+    fn get_storage_history(
+        &self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+        from_block: BlockNumber,
+        to_block: BlockNumber,
+    ) -> RpcResult<Vec<StorageChange>> {
+        if from_block > to_block {
+            return Err(ErrorObjectOwned::owned(
+                InvalidParams.code(),
+                "The from block is greater than the to block.",
+                None::<()>,
+            ));
+        }
+        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        verify_state_queryable(&txn, StateNumber::right_after_block(from_block))?;
+        let history = txn
+            .get_state_reader()
+            .map_err(internal_server_error)?
+            .get_storage_history(
+                &contract_address,
+                &key,
+                from_block,
+                to_block,
+                MAX_STORAGE_HISTORY_LENGTH,
+            )
+            .map_err(internal_server_error)?;
+        Ok(history
+            .into_iter()
+            .map(|(block_number, value)| StorageChange { block_number, value })
+            .collect())
+    }
+
+    #[instrument(skip(self), level = "debug", err)]
+    fn get_most_used_classes(
+        &self,
+        from_block: BlockNumber,
+        to_block: BlockNumber,
+        limit: usize,
+    ) -> RpcResult<Vec<ClassUsage>> {
+        if from_block > to_block {
+            return Err(ErrorObjectOwned::owned(
+                InvalidParams.code(),
+                "The from block is greater than the to block.",
+                None::<()>,
+            ));
+        }
+        if to_block.0 - from_block.0 >= MAX_CLASS_USAGE_BLOCK_RANGE {
+            return Err(ErrorObjectOwned::owned(
+                InvalidParams.code(),
+                format!("The block range is longer than {MAX_CLASS_USAGE_BLOCK_RANGE} blocks."),
+                None::<()>,
+            ));
+        }
+        verify_storage_scope(&self.storage_reader)?;
+        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        let most_used_classes = txn
+            .get_most_used_classes(from_block, to_block, min(limit, MAX_MOST_USED_CLASSES_LENGTH))
+            .map_err(internal_server_error)?;
+        Ok(most_used_classes
+            .into_iter()
+            .map(|(class_hash, invocations)| ClassUsage { class_hash, invocations })
+            .collect())
+    }
+
+    #[instrument(skip(self), level = "debug", err)]
+    fn get_block_transactions_page(
+        &self,
+        block_id: BlockId,
+        page_size: usize,
+        continuation_token: Option<usize>,
+    ) -> RpcResult<BlockTransactionsPage> {
+        if page_size == 0 {
+            return Err(ErrorObjectOwned::owned(
+                InvalidParams.code(),
+                "The page size must be positive.",
+                None::<()>,
+            ));
+        }
+        verify_storage_scope(&self.storage_reader)?;
+        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        let block_number = get_accepted_block_number_with_body(&txn, block_id)?;
+        let block_hash = txn
+            .get_block_header(block_number)
+            .map_err(internal_server_error)?
+            .ok_or(BLOCK_NOT_FOUND)?
+            .block_hash;
+        let transactions_count = txn
+            .get_block_transactions_count(block_number)
+            .map_err(internal_server_error)?
+            .ok_or(BLOCK_NOT_FOUND)?;
+        // A continuation token is only returned when it points to a transaction of the block.
+        let start = match continuation_token {
+            Some(index) if index >= transactions_count => {
+                return Err(ErrorObjectOwned::owned(
+                    InvalidParams.code(),
+                    format!(
+                        "The continuation token {index} is past the last transaction of the block."
+                    ),
+                    None::<()>,
+                ));
+            }
+            Some(index) => index,
+            None => 0,
+        };
+        let end = min(start + min(page_size, MAX_BLOCK_TRANSACTIONS_PAGE_SIZE), transactions_count);
+        // Only the transactions of the page are read, so that the heaviest blocks aren't loaded
+        // for every page.
+        let transactions = (start..end)
+            .map(|index| {
+                let transaction_index =
+                    TransactionIndex(block_number, TransactionOffsetInBlock(index));
+                let transaction = txn
+                    .get_transaction(transaction_index)
+                    .map_err(internal_server_error)?
+                    .ok_or_else(|| {
+                        internal_server_error(format!("Missing transaction {transaction_index:?}."))
+                    })?;
+                let transaction_hash = txn
+                    .get_transaction_hash_by_idx(&transaction_index)
+                    .map_err(internal_server_error)?
+                    .ok_or_else(|| {
+                        internal_server_error(format!(
+                            "Missing hash of transaction {transaction_index:?}."
+                        ))
+                    })?;
+                versioned_transaction_with_hash(transaction, transaction_hash, self.capabilities)
+            })
+            .collect::<RpcResult<_>>()?;
+        Ok(BlockTransactionsPage {
+            block_hash,
+            block_number,
+            transactions,
+            continuation_token: (end < transactions_count).then_some(end),
+        })
+    }
+}
+
+fn versioned_transaction_with_hash(
+    transaction: Transaction,
+    transaction_hash: TransactionHash,
+    capabilities: VersionCapabilities,
+) -> RpcResult<VersionedTransactionWithHash> {
+    Ok(if capabilities.v3_transactions {
+        VersionedTransactionWithHash::Latest(TransactionWithHash {
+            transaction: transaction.try_into()?,
+            transaction_hash,
+        })
+    } else {
+        VersionedTransactionWithHash::WithoutV3(TransactionWithHashWithoutV3 {
+            transaction: transaction.try_into()?,
+            transaction_hash,
+        })
+    })
+}
+
+fn transaction_index(position: TransactionPosition) -> TransactionIndex {
+    TransactionIndex(position.block_number, TransactionOffsetInBlock(position.transaction_index))
+}
+
+fn transaction_position(
+    TransactionIndex(block_number, offset): TransactionIndex,
+) -> TransactionPosition {
+    TransactionPosition { block_number, transaction_index: offset.0 }
+}
+
+// The overridden balances are the low 128 bits of u256 balances, so larger values are rejected
+// instead of being silently truncated.
+fn validate_state_override(state_override: &StateOverride) -> RpcResult<()> {
+    if let Some(contract_address) = state_override.balance_overflow() {
+        return Err(ErrorObjectOwned::owned(
+            InvalidParams.code(),
+            format!("The balance of {} doesn't fit in 128 bits.", contract_address.0.key()),
+            None::<()>,
+        ));
+    }
+    Ok(())
+}
+
+// Returns the header of the block with the signature of the sequencer over it, or None if the
+// block isn't synced up to its state diff, whose commitment is part of the signed data.
+fn get_signed_header(
+    txn: &StorageTxn<'_, RO>,
+    block_number: BlockNumber,
+) -> RpcResult<Option<SignedBlockHeader>> {
+    let (Some(header), Some(signature), Some(state_diff)) = (
+        txn.get_block_header(block_number).map_err(internal_server_error)?,
+        txn.get_block_signature(block_number).map_err(internal_server_error)?,
+        txn.get_state_diff(block_number).map_err(internal_server_error)?,
+    ) else {
+        return Ok(None);
+    };
+    Ok(Some(SignedBlockHeader {
+        header,
+        state_diff_commitment: calculate_state_diff_commitment(&state_diff, StateDiffVersion::V0),
+        signature,
+    }))
+}
+
+// Returns the selectors of the external entry points of the contract's class, which may be either a
+// Sierra class or a deprecated class.
+fn get_external_selectors(
+    txn: &StorageTxn<'_, RO>,
+    state_number: StateNumber,
+    contract_address: ContractAddress,
+) -> RpcResult<HashSet<EntryPointSelector>> {
+    let state_reader = txn.get_state_reader().map_err(internal_server_error)?;
+    let class_hash = state_reader
+        .get_class_hash_at(state_number, &contract_address)
+        .map_err(internal_server_error)?
+        .ok_or_else(|| ErrorObjectOwned::from(CONTRACT_NOT_FOUND))?;
+    if let Some(class) = state_reader
+        .get_class_definition_at(state_number, &class_hash)
+        .map_err(internal_server_error)?
+    {
+        return Ok(class
+            .entry_points_by_type
+            .get(&EntryPointType::External)
+            .into_iter()
+            .flatten()
+            .map(|entry_point| entry_point.selector)
+            .collect());
+    }
+    let class = state_reader
+        .get_deprecated_class_definition_at(state_number, &class_hash)
+        .map_err(internal_server_error)?
+        .ok_or_else(|| {
+            internal_server_error(format!("Class {class_hash} of a deployed contract is missing."))
+        })?;
+    Ok(class
+        .entry_points_by_type
+        .get(&DeprecatedEntryPointType::External)
+        .into_iter()
+        .flatten()
+        .map(|entry_point| entry_point.selector)
+        .collect())
+}
+
+// The functions a contract must have to look like a token of each standard. A function may be
+// named either in camelCase or in snake_case.
+const ERC721_FUNCTIONS: [[&str; 2]; 5] = [
+    ["balanceOf", "balance_of"],
+    ["ownerOf", "owner_of"],
+    ["transferFrom", "transfer_from"],
+    ["approve", "approve"],
+    ["getApproved", "get_approved"],
+];
+const ERC20_FUNCTIONS: [[&str; 2]; 6] = [
+    ["totalSupply", "total_supply"],
+    ["balanceOf", "balance_of"],
+    ["allowance", "allowance"],
+    ["transfer", "transfer"],
+    ["transferFrom", "transfer_from"],
+    ["approve", "approve"],
+];
+
+// Returns the token standard whose functions are all external entry points of the class. ERC-721
+// is checked first since an ERC-721 contract may expose a subset of the ERC-20 functions.
+fn detect_token_standard(selectors: &HashSet<EntryPointSelector>) -> Option<TokenStandard> {
+    let has_functions = |functions: &[[&str; 2]]| {
+        functions
+            .iter()
+            .all(|names| names.iter().any(|name| selectors.contains(&selector_from_name(name))))
+    };
+    if has_functions(&ERC721_FUNCTIONS) {
+        Some(TokenStandard::Erc721)
+    } else if has_functions(&ERC20_FUNCTIONS) {
+        Some(TokenStandard::Erc20)
+    } else {
+        None
+    }
+}
+
+// The number of bytes in a full word of a serialized ByteArray.
+const BYTES_IN_BYTE_ARRAY_WORD: usize = 31;
+
+// Decodes a string returned by a contract, either a Cairo short string (a single felt) or a
+// serialized ByteArray: the number of full words, the full words, the pending word and the number
+// of bytes in the pending word.
+fn decode_string(retdata: &[StarkFelt]) -> Option<String> {
+    let felt_to_usize = |felt: &StarkFelt| usize::try_from(u64::try_from(*felt).ok()?).ok();
+    let bytes = match retdata {
+        [] => return None,
+        [short_string] => {
+            short_string.bytes().iter().copied().skip_while(|byte| *byte == 0).collect()
+        }
+        [n_full_words, rest @ ..] => {
+            let [full_words @ .., pending_word, pending_word_len] = rest else {
+                return None;
+            };
+            let pending_word_len = felt_to_usize(pending_word_len)?;
+            if felt_to_usize(n_full_words)? != full_words.len()
+                || pending_word_len >= BYTES_IN_BYTE_ARRAY_WORD
+            {
+                return None;
+            }
+            let mut bytes = vec![];
+            for word in full_words {
+                bytes.extend_from_slice(
+                    &word.bytes()[word.bytes().len() - BYTES_IN_BYTE_ARRAY_WORD..],
+                );
+            }
+            bytes.extend_from_slice(
+                &pending_word.bytes()[pending_word.bytes().len() - pending_word_len..],
+            );
+            bytes
+        }
+    };
+    String::from_utf8(bytes).ok()
+}
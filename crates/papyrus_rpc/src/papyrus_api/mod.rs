@@ -0,0 +1,408 @@
+//! Papyrus specific JSON-RPC methods that are not part of the Starknet specification.
+//!
+//! These methods are shared by all the versions. They are exposed under every supported version
+//! path and their names are prefixed with `papyrus_` instead of `starknet_`. Responses whose shape
+//! differs between the versions follow the capabilities of the version of the request.
+use async_trait::async_trait;
+use jsonrpsee::core::RpcResult;
+use jsonrpsee::proc_macros::rpc;
+use papyrus_common::block_execution_resources::BlockExecutionResources;
+use papyrus_common::header_accumulator::AccumulatorProof;
+use papyrus_execution::objects::StateOverride;
+use papyrus_storage::state::data::{EntryPointLocation, IndexedEntryPoint, StateDiffStats};
+use serde::{Deserialize, Serialize};
+use starknet_api::block::{BlockHash, BlockHeader, BlockNumber, BlockSignature};
+use starknet_api::core::{ClassHash, ContractAddress, EntryPointSelector, GlobalRoot, Nonce};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::{EntryPointType, StorageKey};
+use starknet_api::transaction::TransactionHash;
+
+use crate::api::{BlockId, CallRequest};
+use crate::v0_5::transaction::TransactionWithHash as TransactionWithHashWithoutV3;
+use crate::v0_7::api::{SimulatedTransaction, SimulationFlag};
+use crate::v0_7::broadcasted_transaction::BroadcastedTransaction;
+use crate::v0_7::transaction::TransactionWithHash;
+
+pub mod api_impl;
+#[cfg(test)]
+mod test;
+
+/// The prefix of the names of the Papyrus specific methods.
+pub(crate) const PAPYRUS_METHOD_PREFIX: &str = "papyrus_";
+
+/// The maximal number of headers returned by `papyrus_getHeaderChain`. The commitment of the state
+/// diff of each header is computed on request, so the chains are kept short.
+pub(crate) const MAX_HEADER_CHAIN_LENGTH: usize = 100;
+
+/// The maximal number of blocks between a block and its ancestor in `papyrus_getAncestryProof`.
+pub(crate) const MAX_ANCESTRY_PROOF_DISTANCE: u64 = 1000;
+
+/// The maximal number of blocks whose stats are returned by `papyrus_getStateDiffStats`.
+pub(crate) const MAX_STATE_DIFF_STATS_LENGTH: usize = 1000;
+
+/// The maximal number of transactions returned by `papyrus_getTransactionsByAddress`.
+pub(crate) const MAX_TRANSACTIONS_BY_ADDRESS_CHUNK_SIZE: usize = 1000;
+
+/// The maximal number of changes returned by `papyrus_getStorageHistory`.
+pub(crate) const MAX_STORAGE_HISTORY_LENGTH: usize = 1000;
+
+/// The maximal number of blocks whose class usage is summed by `papyrus_getMostUsedClasses`.
+pub(crate) const MAX_CLASS_USAGE_BLOCK_RANGE: u64 = 10000;
+
+/// The maximal number of classes returned by `papyrus_getMostUsedClasses`.
+pub(crate) const MAX_MOST_USED_CLASSES_LENGTH: usize = 1000;
+
+/// The maximal number of transactions returned by `papyrus_getBlockTransactionsPage`.
+pub(crate) const MAX_BLOCK_TRANSACTIONS_PAGE_SIZE: usize = 1000;
+
+#[rpc(server, namespace = "papyrus")]
+#[async_trait]
+pub trait PapyrusJsonRpc {
+    /// Returns the storage markers of the node, i.e the first block for which each type of data
+    /// wasn't downloaded yet.
+    #[method(name = "getMarkers")]
+    fn get_markers(&self) -> RpcResult<Markers>;
+
+    /// Returns the signature of the sequencer over a block together with the data it signs, so
+    /// that light clients can verify the block hash without syncing the chain. A block is found
+    /// only once its state diff is synced, since the signed data includes its commitment.
+    #[method(name = "getBlockSignature")]
+    fn get_block_signature(&self, block_number: BlockNumber) -> RpcResult<BlockSignatureData>;
+
+    /// Returns the signed headers of consecutive blocks starting at the given block, for light
+    /// clients that sync the header chain and verify the parent hash links between the headers.
+    /// Returns at most `length` headers (capped by MAX_HEADER_CHAIN_LENGTH), and stops at the
+    /// first block whose state diff isn't synced.
+    #[method(name = "getHeaderChain")]
+    fn get_header_chain(
+        &self,
+        start_block_number: BlockNumber,
+        length: usize,
+    ) -> RpcResult<Vec<SignedBlockHeader>>;
+
+    /// Returns a proof that a block descends from one of its ancestors: the signed header of the
+    /// block and the headers of the blocks below it down to the ancestor, which are linked by their
+    /// parent hashes. The ancestor can be at most MAX_ANCESTRY_PROOF_DISTANCE blocks below the
+    /// block, so a light client that trusts an old block moves to a recent one in several proofs.
+    #[method(name = "getAncestryProof")]
+    fn get_ancestry_proof(
+        &self,
+        block_number: BlockNumber,
+        ancestor_block_number: BlockNumber,
+    ) -> RpcResult<AncestryProof>;
+
+    /// Returns the balance of a contract in the fee token configured for the given block, read
+    /// directly from the token's storage. The balance is a u256 split to its low and high 128 bits,
+    /// like the two storage slots that hold it.
+    #[method(name = "getBalance")]
+    fn get_balance(
+        &self,
+        contract_address: ContractAddress,
+        block_id: BlockId,
+    ) -> RpcResult<FeeTokenBalance>;
+
+    /// Inspects the external entry points of a contract's class to report whether it looks like
+    /// an ERC-20 or an ERC-721 token, and returns its name, symbol and decimals by calling the
+    /// contract. Metadata that the contract doesn't expose, or whose call fails, is omitted.
+    #[method(name = "getTokenMetadata")]
+    async fn get_token_metadata(
+        &self,
+        contract_address: ContractAddress,
+        block_id: BlockId,
+    ) -> RpcResult<TokenMetadata>;
+
+    /// Returns the entry point of a declared class with the given selector, so that debuggers can
+    /// map a selector to the function it calls. Returns null if the class isn't declared or has no
+    /// entry point with this selector.
+    #[method(name = "getEntryPoint")]
+    fn get_entry_point(
+        &self,
+        class_hash: ClassHash,
+        selector: EntryPointSelector,
+    ) -> RpcResult<Option<EntryPointInfo>>;
+
+    /// Returns the number of changes of each kind in the state diffs of consecutive blocks starting
+    /// at the given block, for dashboards of the chain activity. Returns the stats of at most
+    /// `length` blocks (capped by MAX_STATE_DIFF_STATS_LENGTH), and stops at the first block whose
+    /// state diff isn't synced.
+    #[method(name = "getStateDiffStats")]
+    fn get_state_diff_stats(
+        &self,
+        start_block_number: BlockNumber,
+        length: usize,
+    ) -> RpcResult<Vec<BlockStateDiffStats>>;
+
+    /// Returns a proof that the block is an ancestor of the head, against the root of the header
+    /// accumulator: a Merkle mountain range over the hashes of all the blocks up to the head. L1
+    /// contracts and light clients that trust the root can verify the proof with a logarithmic
+    /// number of poseidon hashes.
+    #[method(name = "getHeaderAccumulatorProof")]
+    fn get_header_accumulator_proof(
+        &self,
+        block_number: BlockNumber,
+    ) -> RpcResult<HeaderAccumulatorProof>;
+
+    /// Returns the execution resources that the transactions of a block consumed together, summed
+    /// from their receipts, for capacity dashboards that watch the load of the chain.
+    #[method(name = "getBlockExecutionResources")]
+    fn get_block_execution_resources(
+        &self,
+        block_id: BlockId,
+    ) -> RpcResult<BlockExecutionResourcesData>;
+
+    /// Calls a function like `starknet_call`, on top of the state of the given block with the given
+    /// override applied, to test "what-if" scenarios against the real state. The override is only
+    /// seen by this call. The overridden balances must fit in 128 bits.
+    #[method(name = "callWithStateOverride")]
+    async fn call_with_state_override(
+        &self,
+        request: CallRequest,
+        block_id: BlockId,
+        state_override: StateOverride,
+    ) -> RpcResult<Vec<StarkFelt>>;
+
+    /// Simulates transactions like `starknet_simulateTransactions`, on top of the state of the
+    /// given block with the given override applied. The override is only seen by this simulation.
+    /// The overridden balances must fit in 128 bits.
+    #[method(name = "simulateTransactionsWithStateOverride")]
+    async fn simulate_transactions_with_state_override(
+        &self,
+        block_id: BlockId,
+        transactions: Vec<BroadcastedTransaction>,
+        simulation_flags: Vec<SimulationFlag>,
+        state_override: StateOverride,
+    ) -> RpcResult<Vec<SimulatedTransaction>>;
+
+    /// Returns the transactions sent by an address in the order of the chain, for explorers that
+    /// list the activity of an account. The sender of a deploy account transaction is the account
+    /// it deploys, and these transactions are listed once their receipts are synced. Returns at
+    /// most `chunk_size` transactions (capped by MAX_TRANSACTIONS_BY_ADDRESS_CHUNK_SIZE), and
+    /// the continuation token to pass to get the next transactions if there are more.
+    #[method(name = "getTransactionsByAddress")]
+    fn get_transactions_by_address(
+        &self,
+        address: ContractAddress,
+        chunk_size: usize,
+        continuation_token: Option<TransactionPosition>,
+    ) -> RpcResult<AddressTransactionsChunk>;
+
+    /// Returns the values written to a storage slot of a contract by the blocks in the given range
+    /// (both ends included), in the order of the blocks, for audit and debugging tools. Returns at
+    /// most MAX_STORAGE_HISTORY_LENGTH changes, so a caller that gets that many continues from the
+    /// block after the last change.
+    #[method(name = "getStorageHistory")]
+    fn get_storage_history(
+        &self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+        from_block: BlockNumber,
+        to_block: BlockNumber,
+    ) -> RpcResult<Vec<StorageChange>>;
+
+    /// Returns the classes invoked by the most transactions in the blocks in the given range (both
+    /// ends included), from the most invoked, for protocol analytics. A transaction invokes the
+    /// classes of the contracts that emitted its events apart from its sender account, of the
+    /// contract that handles it for an L1 handler, or the class it deploys. The range is limited to
+    /// MAX_CLASS_USAGE_BLOCK_RANGE blocks, and at most `limit` classes are returned (capped by
+    /// MAX_MOST_USED_CLASSES_LENGTH).
+    #[method(name = "getMostUsedClasses")]
+    fn get_most_used_classes(
+        &self,
+        from_block: BlockNumber,
+        to_block: BlockNumber,
+        limit: usize,
+    ) -> RpcResult<Vec<ClassUsage>>;
+
+    /// Returns the transactions of a block like `starknet_getBlockWithTxs`, a page at a time, for
+    /// consumers that can't handle the response for the heaviest blocks at once. Returns at most
+    /// `page_size` transactions (capped by MAX_BLOCK_TRANSACTIONS_PAGE_SIZE) starting at the
+    /// index of the continuation token, and the continuation token to pass to get the next page if
+    /// there are more. The hash of the block is returned with every page, so that a caller can
+    /// tell whether the block was reverted between the pages. The transactions are shown like
+    /// `starknet_getBlockWithTxs` of the version of the request shows them.
+    #[method(name = "getBlockTransactionsPage")]
+    fn get_block_transactions_page(
+        &self,
+        block_id: BlockId,
+        page_size: usize,
+        continuation_token: Option<usize>,
+    ) -> RpcResult<BlockTransactionsPage>;
+}
+
+/// The storage markers of the node. Each marker is the first block number for which the
+/// corresponding data doesn't exist yet in the storage.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct Markers {
+    pub header: BlockNumber,
+    pub body: BlockNumber,
+    /// Lags behind the body marker when bodies are stored without their receipts.
+    pub receipts: BlockNumber,
+    pub state: BlockNumber,
+    pub compiled_class: BlockNumber,
+    pub base_layer: BlockNumber,
+}
+
+/// The signature of the sequencer over a block. The signed message is the poseidon hash of the
+/// block hash and the state diff commitment.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct BlockSignatureData {
+    pub block_hash: BlockHash,
+    pub state_diff_commitment: GlobalRoot,
+    pub signature: BlockSignature,
+}
+
+/// A block header with the signature of the sequencer over it.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct SignedBlockHeader {
+    pub header: BlockHeader,
+    pub state_diff_commitment: GlobalRoot,
+    pub signature: BlockSignature,
+}
+
+/// A proof that a block descends from an ancestor.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct AncestryProof {
+    pub block: SignedBlockHeader,
+    /// The headers from the parent of the block down to the ancestor. The hash of each header is
+    /// the parent hash of the header before it.
+    pub ancestor_headers: Vec<BlockHeader>,
+}
+
+/// A balance in the fee token, as the low and high 128 bits of its u256 value.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct FeeTokenBalance {
+    pub low: StarkFelt,
+    pub high: StarkFelt,
+}
+
+/// The token standards that `papyrus_getTokenMetadata` detects.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+pub enum TokenStandard {
+    #[serde(rename = "ERC20")]
+    Erc20,
+    #[serde(rename = "ERC721")]
+    Erc721,
+}
+
+/// The token standard that a contract looks like and its metadata. Strings are decoded from either
+/// a Cairo short string or a serialized ByteArray.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct TokenMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub standard: Option<TokenStandard>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decimals: Option<u8>,
+}
+
+/// An entry point of a class. Exactly one of the function index, for Cairo 1 classes, and the
+/// offset, for deprecated classes, is set.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct EntryPointInfo {
+    pub entry_point_type: EntryPointType,
+    /// The index of the entry point's function in the Sierra program of the class.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function_idx: Option<usize>,
+    /// The offset of the entry point in the program of the class.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<usize>,
+}
+
+impl From<IndexedEntryPoint> for EntryPointInfo {
+    fn from(entry_point: IndexedEntryPoint) -> Self {
+        let (function_idx, offset) = match entry_point.location {
+            EntryPointLocation::FunctionIndex(function_idx) => (Some(function_idx.0), None),
+            EntryPointLocation::Offset(offset) => (None, Some(offset.0)),
+        };
+        Self { entry_point_type: entry_point.entry_point_type, function_idx, offset }
+    }
+}
+
+/// The number of changes of each kind in the state diff of a block.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+pub struct BlockStateDiffStats {
+    pub block_number: BlockNumber,
+    #[serde(flatten)]
+    pub stats: StateDiffStats,
+}
+
+/// A proof that a block is in the header accumulator with the given root. The accumulator contains
+/// the hashes of the blocks up to the head, whose number is the leaf count minus one.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct HeaderAccumulatorProof {
+    pub block_hash: BlockHash,
+    pub root: StarkFelt,
+    #[serde(flatten)]
+    pub proof: AccumulatorProof,
+}
+
+/// The execution resources that the transactions of a block consumed together.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+pub struct BlockExecutionResourcesData {
+    pub block_number: BlockNumber,
+    #[serde(flatten)]
+    pub resources: BlockExecutionResources,
+}
+
+/// The position of a transaction in the chain.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+pub struct TransactionPosition {
+    pub block_number: BlockNumber,
+    pub transaction_index: usize,
+}
+
+/// A transaction sent by an address. Invoke transactions of version 0 have no nonce.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct AddressTransaction {
+    pub transaction_hash: TransactionHash,
+    #[serde(flatten)]
+    pub position: TransactionPosition,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<Nonce>,
+}
+
+/// A chunk of the transactions sent by an address, with the position of the next transaction if
+/// there are more.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct AddressTransactionsChunk {
+    pub transactions: Vec<AddressTransaction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub continuation_token: Option<TransactionPosition>,
+}
+
+/// A value written to a storage slot by a block.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+pub struct StorageChange {
+    pub block_number: BlockNumber,
+    pub value: StarkFelt,
+}
+
+/// The number of transactions that invoked a class.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+pub struct ClassUsage {
+    pub class_hash: ClassHash,
+    pub invocations: u64,
+}
+
+/// A page of the transactions of a block, with the index of the next transaction if there are
+/// more.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct BlockTransactionsPage {
+    pub block_hash: BlockHash,
+    pub block_number: BlockNumber,
+    pub transactions: Vec<VersionedTransactionWithHash>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub continuation_token: Option<usize>,
+}
+
+/// A transaction with its hash, in the shape of the version of the request.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum VersionedTransactionWithHash {
+    Latest(TransactionWithHash),
+    /// For versions without transactions of version 3.
+    WithoutV3(TransactionWithHashWithoutV3),
+}
@@ -0,0 +1,1161 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use assert_matches::assert_matches;
+use indexmap::indexmap;
+use jsonrpsee::core::Error;
+use jsonrpsee::types::error::ErrorCode::InvalidParams;
+use papyrus_common::block_execution_resources::BlockExecutionResources;
+use papyrus_common::state_diff_commitment::{calculate_state_diff_commitment, StateDiffVersion};
+use papyrus_execution::execution_utils::selector_from_name;
+use papyrus_execution::objects::StateOverride;
+use papyrus_execution::testing_instances::{
+    get_storage_var_address,
+    test_get_default_execution_config,
+};
+use papyrus_storage::base_layer::BaseLayerStorageWriter;
+use papyrus_storage::body::BodyStorageWriter;
+use papyrus_storage::header::HeaderStorageWriter;
+use papyrus_storage::state::data::StateDiffStats;
+use papyrus_storage::state::StateStorageWriter;
+use papyrus_storage::test_utils::get_test_storage;
+use papyrus_storage::StorageWriter;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{
+    BlockBody,
+    BlockHash,
+    BlockHeader,
+    BlockNumber,
+    BlockSignature,
+    GasPrice,
+    GasPricePerToken,
+};
+use starknet_api::core::{
+    ChainId,
+    ClassHash,
+    ContractAddress,
+    EntryPointSelector,
+    Nonce,
+    PatriciaKey,
+};
+use starknet_api::crypto::Signature;
+use starknet_api::deprecated_contract_class::{
+    ContractClass as DeprecatedContractClass,
+    EntryPoint as DeprecatedEntryPoint,
+    EntryPointOffset,
+    EntryPointType as DeprecatedEntryPointType,
+};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::{
+    ContractClass,
+    EntryPoint,
+    EntryPointType,
+    FunctionIndex,
+    StateDiff,
+    StorageKey,
+    ThinStateDiff,
+};
+use starknet_api::transaction::{
+    Calldata,
+    Event,
+    EventContent,
+    EventData,
+    Fee,
+    InvokeTransaction,
+    InvokeTransactionOutput,
+    InvokeTransactionV1,
+    InvokeTransactionV3,
+    Resource,
+    ResourceBounds,
+    ResourceBoundsMapping,
+    Transaction,
+    TransactionHash,
+    TransactionOutput,
+};
+use starknet_api::{calldata, class_hash, contract_address, patricia_key, stark_felt};
+use test_utils::{get_rng, get_test_body, get_test_state_diff, read_json_file, GetTestInstance};
+
+use super::api_impl::PapyrusJsonRpcServerImpl;
+use super::{
+    AddressTransaction,
+    AddressTransactionsChunk,
+    AncestryProof,
+    BlockExecutionResourcesData,
+    BlockSignatureData,
+    BlockStateDiffStats,
+    BlockTransactionsPage,
+    ClassUsage,
+    EntryPointInfo,
+    FeeTokenBalance,
+    HeaderAccumulatorProof,
+    Markers,
+    PapyrusJsonRpcServer,
+    SignedBlockHeader,
+    StorageChange,
+    TokenMetadata,
+    TokenStandard,
+    TransactionPosition,
+    VersionedTransactionWithHash,
+    MAX_ANCESTRY_PROOF_DISTANCE,
+};
+use crate::api::{BlockHashOrNumber, BlockId, CallRequest, Tag};
+use crate::v0_7::error::{BLOCK_NOT_FOUND, CONTRACT_NOT_FOUND};
+use crate::v0_7::transaction::TransactionWithHash;
+use crate::version_config::{VersionCapabilities, VERSION_0_5};
+
+#[tokio::test]
+async fn get_markers() {
+    let method_name = "papyrus_getMarkers";
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let module = PapyrusJsonRpcServerImpl {
+        storage_reader,
+        execution_config: test_get_default_execution_config(),
+        chain_id: ChainId("SN_GOERLI".to_string()),
+        capabilities: VersionCapabilities::default(),
+        deferred_class_requests: Arc::default(),
+        execution_pool: Arc::default(),
+    }
+    .into_rpc();
+
+    let res = module.call::<_, Markers>(method_name, Vec::<u8>::new()).await.unwrap();
+    assert_eq!(res, Markers::default());
+
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &BlockHeader::default())
+        .unwrap()
+        .append_header(
+            BlockNumber(1),
+            &BlockHeader { block_number: BlockNumber(1), ..Default::default() },
+        )
+        .unwrap()
+        .append_body(BlockNumber(0), BlockBody::default())
+        .unwrap()
+        .append_state_diff(BlockNumber(0), StateDiff::default(), Default::default())
+        .unwrap()
+        .update_base_layer_block_marker(&BlockNumber(1))
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let res = module.call::<_, Markers>(method_name, Vec::<u8>::new()).await.unwrap();
+    assert_eq!(
+        res,
+        Markers {
+            header: BlockNumber(2),
+            body: BlockNumber(1),
+            // The receipts are stored with the bodies.
+            receipts: BlockNumber(1),
+            state: BlockNumber(1),
+            // There are no declared classes in the state diff, so the compiled class marker
+            // advances with the state marker.
+            compiled_class: BlockNumber(1),
+            base_layer: BlockNumber(1),
+        }
+    );
+}
+
+#[tokio::test]
+async fn get_block_signature() {
+    let method_name = "papyrus_getBlockSignature";
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let module = PapyrusJsonRpcServerImpl {
+        storage_reader,
+        execution_config: test_get_default_execution_config(),
+        chain_id: ChainId("SN_GOERLI".to_string()),
+        capabilities: VersionCapabilities::default(),
+        deferred_class_requests: Arc::default(),
+        execution_pool: Arc::default(),
+    }
+    .into_rpc();
+
+    let header = BlockHeader { block_hash: BlockHash(stark_felt!("0x1")), ..Default::default() };
+    let signature = BlockSignature(Signature { r: stark_felt!("0x2"), s: stark_felt!("0x3") });
+    let state_diff = get_test_state_diff();
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &header)
+        .unwrap()
+        .append_block_signature(BlockNumber(0), &signature)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    // The block isn't found until its state diff is stored.
+    let err =
+        module.call::<_, BlockSignatureData>(method_name, [BlockNumber(0)]).await.unwrap_err();
+    assert_matches!(err, Error::Call(err) if err == BLOCK_NOT_FOUND.into());
+
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(BlockNumber(0), state_diff.clone(), Default::default())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let res = module.call::<_, BlockSignatureData>(method_name, [BlockNumber(0)]).await.unwrap();
+    assert_eq!(
+        res,
+        BlockSignatureData {
+            block_hash: header.block_hash,
+            state_diff_commitment: calculate_state_diff_commitment(
+                &ThinStateDiff::from(state_diff),
+                StateDiffVersion::V0,
+            ),
+            signature,
+        }
+    );
+
+    let err =
+        module.call::<_, BlockSignatureData>(method_name, [BlockNumber(1)]).await.unwrap_err();
+    assert_matches!(err, Error::Call(err) if err == BLOCK_NOT_FOUND.into());
+}
+
+// Stores blocks with headers, signatures and empty state diffs, and returns their signed headers.
+fn store_signed_blocks(
+    storage_writer: &mut StorageWriter,
+    n_blocks: u64,
+) -> Vec<SignedBlockHeader> {
+    let mut signed_headers = vec![];
+    for i in 0..n_blocks {
+        let block_number = BlockNumber(i);
+        let header = BlockHeader {
+            block_number,
+            block_hash: BlockHash(StarkFelt::from(i + 1)),
+            parent_hash: BlockHash(StarkFelt::from(i)),
+            ..Default::default()
+        };
+        let signature = BlockSignature(Signature { r: StarkFelt::from(i), s: StarkFelt::from(i) });
+        storage_writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_header(block_number, &header)
+            .unwrap()
+            .append_block_signature(block_number, &signature)
+            .unwrap()
+            .append_state_diff(block_number, StateDiff::default(), Default::default())
+            .unwrap()
+            .commit()
+            .unwrap();
+        signed_headers.push(SignedBlockHeader {
+            header,
+            state_diff_commitment: calculate_state_diff_commitment(
+                &ThinStateDiff::default(),
+                StateDiffVersion::V0,
+            ),
+            signature,
+        });
+    }
+    signed_headers
+}
+
+#[tokio::test]
+async fn get_header_chain() {
+    let method_name = "papyrus_getHeaderChain";
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let module = PapyrusJsonRpcServerImpl {
+        storage_reader,
+        execution_config: test_get_default_execution_config(),
+        chain_id: ChainId("SN_GOERLI".to_string()),
+        capabilities: VersionCapabilities::default(),
+        deferred_class_requests: Arc::default(),
+        execution_pool: Arc::default(),
+    }
+    .into_rpc();
+    let signed_headers = store_signed_blocks(&mut storage_writer, 5);
+
+    let res =
+        module.call::<_, Vec<SignedBlockHeader>>(method_name, (BlockNumber(1), 2)).await.unwrap();
+    assert_eq!(res, signed_headers[1..3]);
+
+    // The chain stops at the last synced block.
+    let res =
+        module.call::<_, Vec<SignedBlockHeader>>(method_name, (BlockNumber(3), 10)).await.unwrap();
+    assert_eq!(res, signed_headers[3..]);
+
+    let res =
+        module.call::<_, Vec<SignedBlockHeader>>(method_name, (BlockNumber(5), 10)).await.unwrap();
+    assert!(res.is_empty());
+}
+
+#[tokio::test]
+async fn get_ancestry_proof() {
+    let method_name = "papyrus_getAncestryProof";
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let module = PapyrusJsonRpcServerImpl {
+        storage_reader,
+        execution_config: test_get_default_execution_config(),
+        chain_id: ChainId("SN_GOERLI".to_string()),
+        capabilities: VersionCapabilities::default(),
+        deferred_class_requests: Arc::default(),
+        execution_pool: Arc::default(),
+    }
+    .into_rpc();
+    let signed_headers = store_signed_blocks(&mut storage_writer, 11);
+
+    let res = module
+        .call::<_, AncestryProof>(method_name, (BlockNumber(10), BlockNumber(7)))
+        .await
+        .unwrap();
+    let expected = AncestryProof {
+        block: signed_headers[10].clone(),
+        ancestor_headers: [9, 8, 7].map(|i| signed_headers[i].header.clone()).to_vec(),
+    };
+    assert_eq!(res, expected);
+
+    let res = module
+        .call::<_, AncestryProof>(method_name, (BlockNumber(4), BlockNumber(4)))
+        .await
+        .unwrap();
+    assert_eq!(res, AncestryProof { block: signed_headers[4].clone(), ancestor_headers: vec![] });
+
+    let err = module
+        .call::<_, AncestryProof>(method_name, (BlockNumber(11), BlockNumber(0)))
+        .await
+        .unwrap_err();
+    assert_matches!(err, Error::Call(err) if err == BLOCK_NOT_FOUND.into());
+
+    // The ancestor can't be too far below the block.
+    let err = module
+        .call::<_, AncestryProof>(
+            method_name,
+            (BlockNumber(MAX_ANCESTRY_PROOF_DISTANCE + 1), BlockNumber(0)),
+        )
+        .await
+        .unwrap_err();
+    assert_matches!(err, Error::Call(err) if err.code() == InvalidParams.code());
+
+    // The ancestor can't be above the block.
+    let err = module
+        .call::<_, AncestryProof>(method_name, (BlockNumber(0), BlockNumber(1)))
+        .await
+        .unwrap_err();
+    assert_matches!(err, Error::Call(err) if err.code() == InvalidParams.code());
+}
+
+#[tokio::test]
+async fn get_balance() {
+    let method_name = "papyrus_getBalance";
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let execution_config = test_get_default_execution_config();
+    let fee_token_address = execution_config
+        .get_execution_config_for_block(BlockNumber(0))
+        .unwrap()
+        .fee_contract_address;
+    let module = PapyrusJsonRpcServerImpl {
+        storage_reader,
+        execution_config,
+        chain_id: ChainId("SN_GOERLI".to_string()),
+        capabilities: VersionCapabilities::default(),
+        deferred_class_requests: Arc::default(),
+        execution_pool: Arc::default(),
+    }
+    .into_rpc();
+
+    let account = contract_address!("0x123");
+    let low_key = get_storage_var_address("ERC20_balances", &[*account.0.key()]);
+    let high_key = low_key.next_storage_key().unwrap();
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &BlockHeader::default())
+        .unwrap()
+        .append_state_diff(
+            BlockNumber(0),
+            StateDiff {
+                storage_diffs: indexmap!(fee_token_address => indexmap!(
+                    low_key => stark_felt!("0x5"),
+                    high_key => stark_felt!("0x1"),
+                )),
+                ..Default::default()
+            },
+            Default::default(),
+        )
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let res = module
+        .call::<_, FeeTokenBalance>(
+            method_name,
+            (account, BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(0)))),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res, FeeTokenBalance { low: stark_felt!("0x5"), high: stark_felt!("0x1") });
+
+    // A contract without a balance.
+    let res = module
+        .call::<_, FeeTokenBalance>(
+            method_name,
+            (contract_address!("0x456"), BlockId::Tag(Tag::Latest)),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res, FeeTokenBalance::default());
+
+    let err = module
+        .call::<_, FeeTokenBalance>(
+            method_name,
+            (account, BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(1)))),
+        )
+        .await
+        .unwrap_err();
+    assert_matches!(err, Error::Call(err) if err == BLOCK_NOT_FOUND.into());
+}
+
+#[tokio::test]
+async fn get_token_metadata() {
+    let method_name = "papyrus_getTokenMetadata";
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let module = PapyrusJsonRpcServerImpl {
+        storage_reader,
+        execution_config: test_get_default_execution_config(),
+        chain_id: ChainId("SN_GOERLI".to_string()),
+        capabilities: VersionCapabilities::default(),
+        deferred_class_requests: Arc::default(),
+        execution_pool: Arc::default(),
+    }
+    .into_rpc();
+
+    let erc20_address = contract_address!("0x1001");
+    let erc20_class_hash = class_hash!("0x1001");
+    let other_address = contract_address!("0x1");
+    let other_class_hash = class_hash!("0x1");
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &BlockHeader::default())
+        .unwrap()
+        .append_body(BlockNumber(0), BlockBody::default())
+        .unwrap()
+        .append_state_diff(
+            BlockNumber(0),
+            StateDiff {
+                deployed_contracts: indexmap!(
+                    erc20_address => erc20_class_hash,
+                    other_address => other_class_hash,
+                ),
+                storage_diffs: indexmap!(erc20_address => indexmap!(
+                    // "Ether" and "ETH" as short strings.
+                    get_storage_var_address("ERC20_name", &[]) => stark_felt!("0x4574686572"),
+                    get_storage_var_address("ERC20_symbol", &[]) => stark_felt!("0x455448"),
+                    get_storage_var_address("ERC20_decimals", &[]) => stark_felt!(18_u128),
+                )),
+                deprecated_declared_classes: indexmap!(
+                    erc20_class_hash =>
+                        serde_json::from_value(read_json_file("erc20_fee_contract_class.json"))
+                            .unwrap(),
+                    other_class_hash =>
+                        serde_json::from_value(read_json_file("deprecated_class.json")).unwrap(),
+                ),
+                ..Default::default()
+            },
+            Default::default(),
+        )
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let res = module
+        .call::<_, TokenMetadata>(method_name, (erc20_address, BlockId::Tag(Tag::Latest)))
+        .await
+        .unwrap();
+    assert_eq!(
+        res,
+        TokenMetadata {
+            standard: Some(TokenStandard::Erc20),
+            name: Some("Ether".to_string()),
+            symbol: Some("ETH".to_string()),
+            decimals: Some(18),
+        }
+    );
+
+    // A contract that isn't a token.
+    let res = module
+        .call::<_, TokenMetadata>(method_name, (other_address, BlockId::Tag(Tag::Latest)))
+        .await
+        .unwrap();
+    assert_eq!(res, TokenMetadata::default());
+
+    let err = module
+        .call::<_, TokenMetadata>(
+            method_name,
+            (contract_address!("0x2"), BlockId::Tag(Tag::Latest)),
+        )
+        .await
+        .unwrap_err();
+    assert_matches!(err, Error::Call(err) if err == CONTRACT_NOT_FOUND.into());
+}
+
+#[tokio::test]
+async fn get_entry_point() {
+    let method_name = "papyrus_getEntryPoint";
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let module = PapyrusJsonRpcServerImpl {
+        storage_reader,
+        execution_config: test_get_default_execution_config(),
+        chain_id: ChainId("SN_GOERLI".to_string()),
+        capabilities: VersionCapabilities::default(),
+        deferred_class_requests: Arc::default(),
+        execution_pool: Arc::default(),
+    }
+    .into_rpc();
+
+    let selector = EntryPointSelector(stark_felt!("0x1234"));
+    let class_hash = class_hash!("0x10");
+    let mut class = ContractClass::default();
+    class.entry_points_by_type.insert(
+        EntryPointType::Constructor,
+        vec![EntryPoint { function_idx: FunctionIndex(2), selector }],
+    );
+    let deprecated_class_hash = class_hash!("0x20");
+    let mut deprecated_class = DeprecatedContractClass::default();
+    deprecated_class.entry_points_by_type.insert(
+        DeprecatedEntryPointType::External,
+        vec![DeprecatedEntryPoint { selector, offset: EntryPointOffset(5) }],
+    );
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(
+            BlockNumber(0),
+            StateDiff {
+                declared_classes: indexmap!(class_hash => (Default::default(), class)),
+                deprecated_declared_classes: indexmap!(deprecated_class_hash => deprecated_class),
+                ..Default::default()
+            },
+            Default::default(),
+        )
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let res = module
+        .call::<_, Option<EntryPointInfo>>(method_name, (class_hash, selector))
+        .await
+        .unwrap();
+    assert_eq!(
+        res,
+        Some(EntryPointInfo {
+            entry_point_type: EntryPointType::Constructor,
+            function_idx: Some(2),
+            offset: None,
+        })
+    );
+
+    let res = module
+        .call::<_, Option<EntryPointInfo>>(method_name, (deprecated_class_hash, selector))
+        .await
+        .unwrap();
+    assert_eq!(
+        res,
+        Some(EntryPointInfo {
+            entry_point_type: EntryPointType::External,
+            function_idx: None,
+            offset: Some(5),
+        })
+    );
+
+    // A class that isn't declared.
+    let res = module
+        .call::<_, Option<EntryPointInfo>>(method_name, (class_hash!("0x30"), selector))
+        .await
+        .unwrap();
+    assert_eq!(res, None);
+}
+
+#[tokio::test]
+async fn get_state_diff_stats() {
+    let method_name = "papyrus_getStateDiffStats";
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let module = PapyrusJsonRpcServerImpl {
+        storage_reader,
+        execution_config: test_get_default_execution_config(),
+        chain_id: ChainId("SN_GOERLI".to_string()),
+        capabilities: VersionCapabilities::default(),
+        deferred_class_requests: Arc::default(),
+        execution_pool: Arc::default(),
+    }
+    .into_rpc();
+    let state_diff = get_test_state_diff();
+    let expected_stats = StateDiffStats::from(&ThinStateDiff::from(state_diff.clone()));
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(BlockNumber(0), StateDiff::default(), Default::default())
+        .unwrap()
+        .append_state_diff(BlockNumber(1), state_diff, Default::default())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let res = module
+        .call::<_, Vec<BlockStateDiffStats>>(method_name, (BlockNumber(0), 10))
+        .await
+        .unwrap();
+    assert_eq!(
+        res,
+        vec![
+            BlockStateDiffStats { block_number: BlockNumber(0), stats: StateDiffStats::default() },
+            BlockStateDiffStats { block_number: BlockNumber(1), stats: expected_stats },
+        ]
+    );
+    assert_ne!(expected_stats, StateDiffStats::default());
+
+    let res =
+        module.call::<_, Vec<BlockStateDiffStats>>(method_name, (BlockNumber(1), 0)).await.unwrap();
+    assert!(res.is_empty());
+}
+
+#[tokio::test]
+async fn get_header_accumulator_proof() {
+    let method_name = "papyrus_getHeaderAccumulatorProof";
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let module = PapyrusJsonRpcServerImpl {
+        storage_reader,
+        execution_config: test_get_default_execution_config(),
+        chain_id: ChainId("SN_GOERLI".to_string()),
+        capabilities: VersionCapabilities::default(),
+        deferred_class_requests: Arc::default(),
+        execution_pool: Arc::default(),
+    }
+    .into_rpc();
+    let block_hash = |block_number: u64| BlockHash(StarkHash::from(block_number + 1));
+    let mut txn = storage_writer.begin_rw_txn().unwrap();
+    for block_number in 0..5 {
+        txn = txn
+            .append_header(
+                BlockNumber(block_number),
+                &BlockHeader {
+                    block_number: BlockNumber(block_number),
+                    block_hash: block_hash(block_number),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+    }
+    txn.commit().unwrap();
+
+    let mut roots = vec![];
+    for block_number in 0..5 {
+        let res = module
+            .call::<_, HeaderAccumulatorProof>(method_name, [BlockNumber(block_number)])
+            .await
+            .unwrap();
+        assert_eq!(res.block_hash, block_hash(block_number));
+        assert_eq!(res.proof.leaf_count, 5);
+        assert!(res.proof.verify(block_number, res.block_hash.0, res.root));
+        roots.push(res.root);
+    }
+    // All the blocks are proven against the same head.
+    roots.dedup();
+    assert_eq!(roots.len(), 1);
+
+    let err =
+        module.call::<_, HeaderAccumulatorProof>(method_name, [BlockNumber(5)]).await.unwrap_err();
+    assert_matches!(err, Error::Call(err) if err == BLOCK_NOT_FOUND.into());
+}
+
+#[tokio::test]
+async fn call_with_state_override() {
+    let method_name = "papyrus_callWithStateOverride";
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let execution_config = test_get_default_execution_config();
+    let fee_token_address = execution_config
+        .get_execution_config_for_block(BlockNumber(0))
+        .unwrap()
+        .fee_contract_address;
+    let module = PapyrusJsonRpcServerImpl {
+        storage_reader,
+        execution_config,
+        chain_id: ChainId("SN_GOERLI".to_string()),
+        capabilities: VersionCapabilities::default(),
+        deferred_class_requests: Arc::default(),
+        execution_pool: Arc::default(),
+    }
+    .into_rpc();
+
+    let fee_token_class_hash = class_hash!("0x1001");
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &BlockHeader::default())
+        .unwrap()
+        .append_body(BlockNumber(0), BlockBody::default())
+        .unwrap()
+        .append_state_diff(
+            BlockNumber(0),
+            StateDiff {
+                deployed_contracts: indexmap!(fee_token_address => fee_token_class_hash),
+                deprecated_declared_classes: indexmap!(
+                    fee_token_class_hash =>
+                        serde_json::from_value(read_json_file("erc20_fee_contract_class.json"))
+                            .unwrap(),
+                ),
+                ..Default::default()
+            },
+            Default::default(),
+        )
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let account = contract_address!("0x123");
+    let balance_of = CallRequest {
+        contract_address: fee_token_address,
+        entry_point_selector: selector_from_name("balanceOf"),
+        calldata: calldata![*account.0.key()],
+    };
+    let balance = stark_felt!("0x5");
+    let res = module
+        .call::<_, Vec<StarkFelt>>(
+            method_name,
+            (
+                balance_of.clone(),
+                BlockId::Tag(Tag::Latest),
+                StateOverride { balances: indexmap!(account => balance), ..Default::default() },
+            ),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res, vec![balance, StarkFelt::ZERO]);
+
+    // The override isn't written to the storage.
+    let res = module
+        .call::<_, Vec<StarkFelt>>(
+            method_name,
+            (balance_of.clone(), BlockId::Tag(Tag::Latest), StateOverride::default()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res, vec![StarkFelt::ZERO, StarkFelt::ZERO]);
+
+    // A balance that doesn't fit in 128 bits.
+    let err = module
+        .call::<_, Vec<StarkFelt>>(
+            method_name,
+            (
+                balance_of,
+                BlockId::Tag(Tag::Latest),
+                StateOverride {
+                    balances: indexmap!(account => stark_felt!("0x100000000000000000000000000000000")),
+                    ..Default::default()
+                },
+            ),
+        )
+        .await
+        .unwrap_err();
+    assert_matches!(err, Error::Call(err) if err.code() == InvalidParams.code());
+}
+
+#[tokio::test]
+async fn get_block_execution_resources() {
+    let method_name = "papyrus_getBlockExecutionResources";
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let module = PapyrusJsonRpcServerImpl {
+        storage_reader,
+        execution_config: test_get_default_execution_config(),
+        chain_id: ChainId("SN_GOERLI".to_string()),
+        capabilities: VersionCapabilities::default(),
+        deferred_class_requests: Arc::default(),
+        execution_pool: Arc::default(),
+    }
+    .into_rpc();
+
+    let l1_gas_price = GasPricePerToken { price_in_wei: GasPrice(1), price_in_fri: GasPrice(1) };
+    let body = get_test_body(3, None, None, None);
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &BlockHeader { l1_gas_price, ..Default::default() })
+        .unwrap()
+        .append_body(BlockNumber(0), body.clone())
+        .unwrap()
+        .append_header(
+            BlockNumber(1),
+            &BlockHeader { block_number: BlockNumber(1), ..Default::default() },
+        )
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let res = module
+        .call::<_, BlockExecutionResourcesData>(
+            method_name,
+            [BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(0)))],
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        res,
+        BlockExecutionResourcesData {
+            block_number: BlockNumber(0),
+            resources: BlockExecutionResources::new(
+                &body.transactions,
+                &body.transaction_outputs,
+                l1_gas_price
+            ),
+        }
+    );
+    assert_eq!(res.resources.n_transactions, 3);
+
+    // The receipts of the latest block aren't synced.
+    let err = module
+        .call::<_, BlockExecutionResourcesData>(method_name, [BlockId::Tag(Tag::Latest)])
+        .await
+        .unwrap_err();
+    assert_matches!(err, Error::Call(_));
+}
+
+#[tokio::test]
+async fn get_transactions_by_address() {
+    let method_name = "papyrus_getTransactionsByAddress";
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let module = PapyrusJsonRpcServerImpl {
+        storage_reader,
+        execution_config: test_get_default_execution_config(),
+        chain_id: ChainId("SN_GOERLI".to_string()),
+        capabilities: VersionCapabilities::default(),
+        deferred_class_requests: Arc::default(),
+        execution_pool: Arc::default(),
+    }
+    .into_rpc();
+
+    let sender = contract_address!("0x1");
+    let other_sender = contract_address!("0x2");
+    let invoke = |sender_address, nonce: u64| {
+        Transaction::Invoke(InvokeTransaction::V1(InvokeTransactionV1 {
+            sender_address,
+            nonce: Nonce(StarkFelt::from(nonce)),
+            ..Default::default()
+        }))
+    };
+    let body = BlockBody {
+        transactions: vec![invoke(sender, 0), invoke(other_sender, 0), invoke(sender, 1)],
+        transaction_outputs: vec![TransactionOutput::Invoke(InvokeTransactionOutput::default()); 3],
+        transaction_hashes: (1..=3_u64)
+            .map(|hash| TransactionHash(StarkFelt::from(hash)))
+            .collect(),
+    };
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_body(BlockNumber(0), body)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let position =
+        |transaction_index| TransactionPosition { block_number: BlockNumber(0), transaction_index };
+    let first_chunk =
+        module.call::<_, AddressTransactionsChunk>(method_name, (sender, 1)).await.unwrap();
+    assert_eq!(
+        first_chunk,
+        AddressTransactionsChunk {
+            transactions: vec![AddressTransaction {
+                transaction_hash: TransactionHash(StarkFelt::from(1_u64)),
+                position: position(0),
+                nonce: Some(Nonce(StarkFelt::from(0_u64))),
+            }],
+            continuation_token: Some(position(2)),
+        }
+    );
+
+    let second_chunk = module
+        .call::<_, AddressTransactionsChunk>(
+            method_name,
+            (sender, 1, first_chunk.continuation_token),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        second_chunk,
+        AddressTransactionsChunk {
+            transactions: vec![AddressTransaction {
+                transaction_hash: TransactionHash(StarkFelt::from(3_u64)),
+                position: position(2),
+                nonce: Some(Nonce(StarkFelt::from(1_u64))),
+            }],
+            continuation_token: None,
+        }
+    );
+
+    let err =
+        module.call::<_, AddressTransactionsChunk>(method_name, (sender, 0)).await.unwrap_err();
+    assert_matches!(err, Error::Call(err) if err.code() == InvalidParams.code());
+}
+
+#[tokio::test]
+async fn get_storage_history() {
+    let method_name = "papyrus_getStorageHistory";
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let module = PapyrusJsonRpcServerImpl {
+        storage_reader,
+        execution_config: test_get_default_execution_config(),
+        chain_id: ChainId("SN_GOERLI".to_string()),
+        capabilities: VersionCapabilities::default(),
+        deferred_class_requests: Arc::default(),
+        execution_pool: Arc::default(),
+    }
+    .into_rpc();
+
+    let address = contract_address!("0x1");
+    let key = StorageKey(patricia_key!("0x10"));
+    let state_diff = |value: Option<&str>| StateDiff {
+        storage_diffs: value
+            .map(|value| indexmap! { address => indexmap! { key => stark_felt!(value) } })
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+    let mut txn = storage_writer.begin_rw_txn().unwrap();
+    for (block_number, value) in [Some("0x1"), None, Some("0x2")].into_iter().enumerate() {
+        txn = txn
+            .append_state_diff(
+                BlockNumber(block_number as u64),
+                state_diff(value),
+                Default::default(),
+            )
+            .unwrap();
+    }
+    txn.commit().unwrap();
+
+    let res = module
+        .call::<_, Vec<StorageChange>>(method_name, (address, key, BlockNumber(0), BlockNumber(5)))
+        .await
+        .unwrap();
+    assert_eq!(
+        res,
+        vec![
+            StorageChange { block_number: BlockNumber(0), value: stark_felt!("0x1") },
+            StorageChange { block_number: BlockNumber(2), value: stark_felt!("0x2") },
+        ]
+    );
+
+    let res = module
+        .call::<_, Vec<StorageChange>>(method_name, (address, key, BlockNumber(1), BlockNumber(1)))
+        .await
+        .unwrap();
+    assert!(res.is_empty());
+
+    let err = module
+        .call::<_, Vec<StorageChange>>(method_name, (address, key, BlockNumber(2), BlockNumber(1)))
+        .await
+        .unwrap_err();
+    assert_matches!(err, Error::Call(err) if err.code() == InvalidParams.code());
+}
+
+#[tokio::test]
+async fn get_most_used_classes() {
+    let method_name = "papyrus_getMostUsedClasses";
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let module = PapyrusJsonRpcServerImpl {
+        storage_reader,
+        execution_config: test_get_default_execution_config(),
+        chain_id: ChainId("SN_GOERLI".to_string()),
+        capabilities: VersionCapabilities::default(),
+        deferred_class_requests: Arc::default(),
+        execution_pool: Arc::default(),
+    }
+    .into_rpc();
+
+    let account = contract_address!("0x1");
+    let account_class = class_hash!("0x10");
+    let contract = contract_address!("0x2");
+    let contract_class = class_hash!("0x20");
+    let invoke = Transaction::Invoke(InvokeTransaction::V1(InvokeTransactionV1 {
+        sender_address: account,
+        ..Default::default()
+    }));
+    // The account emits an event too, but only the contract it calls is counted.
+    let invoke_output = TransactionOutput::Invoke(InvokeTransactionOutput {
+        events: [account, contract]
+            .into_iter()
+            .map(|from_address| Event {
+                from_address,
+                content: EventContent { keys: vec![], data: EventData(vec![]) },
+            })
+            .collect(),
+        ..Default::default()
+    });
+    let body = BlockBody {
+        transactions: vec![invoke.clone(), invoke],
+        transaction_outputs: vec![invoke_output; 2],
+        transaction_hashes: (1..=2_u64)
+            .map(|hash| TransactionHash(StarkFelt::from(hash)))
+            .collect(),
+    };
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(
+            BlockNumber(0),
+            StateDiff {
+                deployed_contracts: indexmap! {
+                    account => account_class,
+                    contract => contract_class,
+                },
+                ..Default::default()
+            },
+            Default::default(),
+        )
+        .unwrap()
+        .append_body(BlockNumber(0), body)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let res = module
+        .call::<_, Vec<ClassUsage>>(method_name, (BlockNumber(0), BlockNumber(10), 5))
+        .await
+        .unwrap();
+    assert_eq!(res, vec![ClassUsage { class_hash: contract_class, invocations: 2 }]);
+
+    let err = module
+        .call::<_, Vec<ClassUsage>>(method_name, (BlockNumber(0), BlockNumber(100000), 5))
+        .await
+        .unwrap_err();
+    assert_matches!(err, Error::Call(err) if err.code() == InvalidParams.code());
+}
+
+#[tokio::test]
+async fn get_block_transactions_page() {
+    let method_name = "papyrus_getBlockTransactionsPage";
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let module = PapyrusJsonRpcServerImpl {
+        storage_reader,
+        execution_config: test_get_default_execution_config(),
+        chain_id: ChainId("SN_GOERLI".to_string()),
+        capabilities: VersionCapabilities::default(),
+        deferred_class_requests: Arc::default(),
+        execution_pool: Arc::default(),
+    }
+    .into_rpc();
+
+    let block_hash = BlockHash(stark_felt!("0x1234"));
+    let invoke = |nonce: u64| {
+        Transaction::Invoke(InvokeTransaction::V1(InvokeTransactionV1 {
+            nonce: Nonce(StarkFelt::from(nonce)),
+            ..Default::default()
+        }))
+    };
+    let body = BlockBody {
+        transactions: (0..3_u64).map(invoke).collect(),
+        transaction_outputs: vec![TransactionOutput::Invoke(InvokeTransactionOutput::default()); 3],
+        transaction_hashes: (1..=3_u64)
+            .map(|hash| TransactionHash(StarkFelt::from(hash)))
+            .collect(),
+    };
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &BlockHeader { block_hash, ..Default::default() })
+        .unwrap()
+        .append_body(BlockNumber(0), body.clone())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let transaction_with_hash = |index: usize| {
+        VersionedTransactionWithHash::Latest(TransactionWithHash {
+            transaction: body.transactions[index].clone().try_into().unwrap(),
+            transaction_hash: body.transaction_hashes[index],
+        })
+    };
+    let block_id = BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(0)));
+    let first_page =
+        module.call::<_, BlockTransactionsPage>(method_name, (block_id, 2)).await.unwrap();
+    assert_eq!(
+        first_page,
+        BlockTransactionsPage {
+            block_hash,
+            block_number: BlockNumber(0),
+            transactions: vec![transaction_with_hash(0), transaction_with_hash(1)],
+            continuation_token: Some(2),
+        }
+    );
+
+    let second_page = module
+        .call::<_, BlockTransactionsPage>(method_name, (block_id, 2, first_page.continuation_token))
+        .await
+        .unwrap();
+    assert_eq!(
+        second_page,
+        BlockTransactionsPage {
+            block_hash,
+            block_number: BlockNumber(0),
+            transactions: vec![transaction_with_hash(2)],
+            continuation_token: None,
+        }
+    );
+
+    // A continuation token past the transactions of the block.
+    let err = module
+        .call::<_, BlockTransactionsPage>(method_name, (block_id, 2, Some(3)))
+        .await
+        .unwrap_err();
+    assert_matches!(err, Error::Call(err) if err.code() == InvalidParams.code());
+
+    let err =
+        module.call::<_, BlockTransactionsPage>(method_name, (block_id, 0)).await.unwrap_err();
+    assert_matches!(err, Error::Call(err) if err.code() == InvalidParams.code());
+
+    let err = module
+        .call::<_, BlockTransactionsPage>(
+            method_name,
+            (BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(1))), 2),
+        )
+        .await
+        .unwrap_err();
+    assert_matches!(err, Error::Call(err) if err == BLOCK_NOT_FOUND.into());
+}
+
+#[tokio::test]
+async fn get_block_transactions_page_by_version_capabilities() {
+    let method_name = "papyrus_getBlockTransactionsPage";
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let body = BlockBody {
+        transactions: vec![Transaction::Invoke(InvokeTransaction::V3(InvokeTransactionV3 {
+            resource_bounds: ResourceBoundsMapping(BTreeMap::from([
+                (Resource::L1Gas, ResourceBounds { max_amount: 10, max_price_per_unit: 2 }),
+                (Resource::L2Gas, ResourceBounds::default()),
+            ])),
+            ..InvokeTransactionV3::get_test_instance(&mut get_rng())
+        }))],
+        transaction_outputs: vec![TransactionOutput::Invoke(InvokeTransactionOutput::default())],
+        transaction_hashes: vec![TransactionHash(StarkFelt::from(1_u64))],
+    };
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &BlockHeader::default())
+        .unwrap()
+        .append_body(BlockNumber(0), body)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let block_id = BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(0)));
+    for (capabilities, expected_version, expected_max_fee) in [
+        (VersionCapabilities::default(), "0x3", None),
+        (VERSION_0_5.capabilities(), "0x1", Some(serde_json::to_value(Fee(20)).unwrap())),
+    ] {
+        let module = PapyrusJsonRpcServerImpl {
+            storage_reader: storage_reader.clone(),
+            execution_config: test_get_default_execution_config(),
+            chain_id: ChainId("SN_GOERLI".to_string()),
+            capabilities,
+            deferred_class_requests: Arc::default(),
+            execution_pool: Arc::default(),
+        }
+        .into_rpc();
+        let page = module.call::<_, serde_json::Value>(method_name, (block_id, 1)).await.unwrap();
+        let transaction = &page["transactions"][0];
+        assert_eq!(transaction["version"], expected_version);
+        assert_eq!(transaction.get("max_fee"), expected_max_fee.as_ref());
+        assert_eq!(transaction.get("resource_bounds").is_some(), capabilities.v3_transactions);
+    }
+}
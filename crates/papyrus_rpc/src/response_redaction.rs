@@ -0,0 +1,219 @@
+#[cfg(test)]
+#[path = "response_redaction_test.rs"]
+mod response_redaction_test;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use hyper::header::CONTENT_LENGTH;
+use hyper::{Body, Request, Response};
+use jsonrpsee::core::http_helpers::read_body;
+use serde::de::Error as DeserializationError;
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+use tower::{BoxError, Layer, Service};
+
+use crate::middleware::{routed_method, RoutedMethod};
+use crate::papyrus_api::PAPYRUS_METHOD_PREFIX;
+use crate::SERVER_MAX_BODY_SIZE;
+
+/// The value that replaces the redacted fields in the responses.
+pub const REDACTED_FIELD_MARKER: &str = "truncated";
+
+// The fields to redact from the result of each method, by the name of the method.
+type RedactedFields = BTreeMap<String, BTreeSet<String>>;
+
+/// Serializes the redacted fields to a "method1:field1 method1:field2 method2:field1" string.
+pub fn serialize_redacted_fields(redacted_fields: &RedactedFields) -> String {
+    redacted_fields
+        .iter()
+        .flat_map(|(method, fields)| fields.iter().map(move |field| format!("{method}:{field}")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Deserializes the redacted fields from a "method1:field1 method1:field2 method2:field1" string.
+pub fn deserialize_redacted_fields<'de, D>(de: D) -> Result<RedactedFields, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw_str: String = Deserialize::deserialize(de)?;
+    let mut redacted_fields = RedactedFields::new();
+    for raw_pair in raw_str.split_whitespace() {
+        let Some((method, field)) = raw_pair.split_once(':') else {
+            return Err(D::Error::custom(format!(
+                "pair \"{raw_pair}\" is not valid. The Expected format is method:field"
+            )));
+        };
+        if method.is_empty() || field.is_empty() {
+            return Err(D::Error::custom(format!(
+                "Redacted field pair \"{raw_pair}\" is not valid"
+            )));
+        }
+        redacted_fields.entry(method.to_owned()).or_default().insert(field.to_owned());
+    }
+    Ok(redacted_fields)
+}
+
+/// [`Tower`] layer that replaces chosen fields in the results of chosen methods with
+/// [`REDACTED_FIELD_MARKER`], for replicas that serve the public and can't afford to send heavy
+/// fields such as the calldata and the signatures of transactions. A field is redacted wherever it
+/// appears in the result. Errors are left unchanged.
+///
+/// [`Tower`]: https://crates.io/crates/tower
+#[derive(Clone)]
+pub(crate) struct ResponseRedactionLayer {
+    redacted_fields: Arc<RedactedFields>,
+}
+
+impl ResponseRedactionLayer {
+    /// Returns None if no field is redacted.
+    pub(crate) fn new(redacted_fields: &RedactedFields) -> Option<Self> {
+        if redacted_fields.is_empty() {
+            return None;
+        }
+        // The methods are kept by the name of the method they are routed to, so that a method's
+        // fields are redacted under whatever name the client reaches it. Names that aren't routed
+        // to any method can't be reached.
+        let mut routed_redacted_fields = RedactedFields::new();
+        for (method, fields) in redacted_fields {
+            if let Some(method) = routed_method_name(method) {
+                routed_redacted_fields.entry(method).or_default().extend(fields.iter().cloned());
+            }
+        }
+        Some(Self { redacted_fields: Arc::new(routed_redacted_fields) })
+    }
+
+    // Returns the fields to redact from the response to each request in the body, by the id of the
+    // request. Requests without redacted fields are omitted.
+    fn redacted_fields_by_id(&self, body_bytes: &[u8]) -> Vec<(Value, &BTreeSet<String>)> {
+        // Invalid bodies are rejected by the server, with the appropriate JSON-RPC error.
+        let Ok(body) = serde_json::from_slice::<Value>(body_bytes) else {
+            return vec![];
+        };
+        let requests = match body {
+            Value::Array(requests) => requests,
+            request => vec![request],
+        };
+        requests
+            .into_iter()
+            .filter_map(|mut request| {
+                let method = routed_method_name(request.get("method")?.as_str()?)?;
+                let fields = self.redacted_fields.get(&method)?;
+                Some((request.get_mut("id")?.take(), fields))
+            })
+            .collect()
+    }
+
+    // Redacts the results in the response body. Returns None if the body isn't valid JSON.
+    fn redact(
+        &self,
+        body_bytes: &[u8],
+        redacted_fields_by_id: &[(Value, &BTreeSet<String>)],
+    ) -> Option<Vec<u8>> {
+        let mut body = serde_json::from_slice::<Value>(body_bytes).ok()?;
+        let responses = match &mut body {
+            Value::Array(responses) => responses.iter_mut().collect(),
+            response => vec![response],
+        };
+        for response in responses {
+            let Some(fields) = redacted_fields_by_id
+                .iter()
+                .find(|(id, _fields)| response.get("id") == Some(id))
+                .map(|(_id, fields)| *fields)
+            else {
+                continue;
+            };
+            if let Some(result) = response.get_mut("result") {
+                redact_fields(result, fields);
+            }
+        }
+        serde_json::to_vec(&body).ok()
+    }
+}
+
+// Returns the full name of the method that a method name sent by the client is routed to.
+fn routed_method_name(method: &str) -> Option<String> {
+    Some(match routed_method(method)? {
+        RoutedMethod::Starknet(method) => format!("starknet_{method}"),
+        RoutedMethod::Papyrus(method) => format!("{PAPYRUS_METHOD_PREFIX}{method}"),
+    })
+}
+
+fn redact_fields(value: &mut Value, fields: &BTreeSet<String>) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                if fields.contains(key) {
+                    *value = Value::String(REDACTED_FIELD_MARKER.to_owned());
+                } else {
+                    redact_fields(value, fields);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(|value| redact_fields(value, fields)),
+        _ => {}
+    }
+}
+
+impl<S> Layer<S> for ResponseRedactionLayer {
+    type Service = ResponseRedaction<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ResponseRedaction { layer: self.clone(), inner }
+    }
+}
+
+/// The service of [`ResponseRedactionLayer`].
+#[derive(Clone)]
+pub(crate) struct ResponseRedaction<S> {
+    layer: ResponseRedactionLayer,
+    inner: S,
+}
+
+impl<S> Service<Request<Body>> for ResponseRedaction<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send,
+    S::Error: Into<BoxError>,
+{
+    type Response = Response<Body>;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Response<Body>, BoxError>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        // The service that was polled ready handles the request, and a clone takes its place.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let layer = self.layer.clone();
+        Box::pin(async move {
+            let (parts, body) = req.into_parts();
+            let (body_bytes, _is_single) = read_body(&parts.headers, body, SERVER_MAX_BODY_SIZE)
+                .await
+                .map_err(BoxError::from)?;
+            let redacted_fields_by_id = layer.redacted_fields_by_id(&body_bytes);
+            let response = inner
+                .call(Request::from_parts(parts, body_bytes.into()))
+                .await
+                .map_err(Into::into)?;
+            if redacted_fields_by_id.is_empty() {
+                return Ok(response);
+            }
+            let (mut parts, body) = response.into_parts();
+            let body_bytes = hyper::body::to_bytes(body).await?;
+            let Some(redacted_body) = layer.redact(&body_bytes, &redacted_fields_by_id) else {
+                return Ok(Response::from_parts(parts, body_bytes.into()));
+            };
+            // The length of the redacted body is set by the server when it's sent.
+            parts.headers.remove(CONTENT_LENGTH);
+            Ok(Response::from_parts(parts, redacted_body.into()))
+        })
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use hyper::{Body, Request, Response};
+use pretty_assertions::assert_eq;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tower::{BoxError, Layer, ServiceExt};
+
+use super::{
+    deserialize_redacted_fields,
+    serialize_redacted_fields,
+    ResponseRedactionLayer,
+    REDACTED_FIELD_MARKER,
+};
+
+const GET_BLOCK: &str = "starknet_getBlockWithTxs";
+
+#[derive(Deserialize)]
+struct RedactedFieldsWrapper {
+    #[serde(deserialize_with = "deserialize_redacted_fields")]
+    redacted_fields: BTreeMap<String, BTreeSet<String>>,
+}
+
+fn redacted_fields(raw_str: &str) -> serde_json::Result<BTreeMap<String, BTreeSet<String>>> {
+    serde_json::from_value::<RedactedFieldsWrapper>(json!({ "redacted_fields": raw_str }))
+        .map(|wrapper| wrapper.redacted_fields)
+}
+
+#[test]
+fn redacted_fields_serialization() {
+    let fields = redacted_fields("m2:f1 m1:f2 m1:f1").unwrap();
+    assert_eq!(
+        fields,
+        BTreeMap::from([
+            ("m1".to_owned(), BTreeSet::from(["f1".to_owned(), "f2".to_owned()])),
+            ("m2".to_owned(), BTreeSet::from(["f1".to_owned()])),
+        ])
+    );
+    assert_eq!(serialize_redacted_fields(&fields), "m1:f1 m1:f2 m2:f1");
+
+    for invalid_fields in ["m1", ":f1", "m1:"] {
+        assert!(redacted_fields(invalid_fields).is_err(), "{invalid_fields} should be invalid.");
+    }
+}
+
+#[test]
+fn nothing_redacted() {
+    assert!(ResponseRedactionLayer::new(&BTreeMap::new()).is_none());
+}
+
+// Sends the request through the layer to a server that answers with the given response body, and
+// returns the body that the client gets.
+async fn redacted_response(request_body: Value, response_body: Value) -> Value {
+    let layer =
+        ResponseRedactionLayer::new(&redacted_fields(&format!("{GET_BLOCK}:calldata")).unwrap())
+            .unwrap();
+    let service = layer.layer(tower::service_fn(move |_request: Request<Body>| {
+        let response_body = response_body.to_string();
+        async move { Ok::<_, BoxError>(Response::new(Body::from(response_body))) }
+    }));
+    let request = Request::post("http://localhost:8080/rpc/V0_7")
+        .header(hyper::header::CONTENT_TYPE, "application/json")
+        .body(Body::from(request_body.to_string()))
+        .unwrap();
+    let response = service.oneshot(request).await.unwrap();
+    let body_bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    serde_json::from_slice(&body_bytes).unwrap()
+}
+
+#[tokio::test]
+async fn fields_are_redacted_from_the_results_of_the_methods() {
+    let result = json!({"transactions": [{"calldata": ["0x1", "0x2"], "nonce": "0x0"}]});
+    let redacted_result =
+        json!({"transactions": [{"calldata": REDACTED_FIELD_MARKER, "nonce": "0x0"}]});
+
+    let request = json!({"jsonrpc": "2.0", "id": 1, "method": GET_BLOCK, "params": []});
+    let response = json!({"jsonrpc": "2.0", "id": 1, "result": result});
+    assert_eq!(
+        redacted_response(request, response).await,
+        json!({"jsonrpc": "2.0", "id": 1, "result": redacted_result})
+    );
+
+    // Only the responses of the methods with redacted fields are redacted in a batch.
+    let request = json!([
+        {"jsonrpc": "2.0", "id": 1, "method": GET_BLOCK, "params": []},
+        {"jsonrpc": "2.0", "id": 2, "method": "starknet_getTransactionByHash", "params": []},
+    ]);
+    let response = json!([
+        {"jsonrpc": "2.0", "id": 2, "result": result},
+        {"jsonrpc": "2.0", "id": 1, "result": result},
+    ]);
+    assert_eq!(
+        redacted_response(request, response).await,
+        json!([
+            {"jsonrpc": "2.0", "id": 2, "result": result},
+            {"jsonrpc": "2.0", "id": 1, "result": redacted_result},
+        ])
+    );
+
+    // The method is redacted under whatever namespace the client reaches it.
+    let request = json!({"jsonrpc": "2.0", "id": 1, "method": "foo_getBlockWithTxs", "params": []});
+    let response = json!({"jsonrpc": "2.0", "id": 1, "result": result});
+    assert_eq!(
+        redacted_response(request, response).await,
+        json!({"jsonrpc": "2.0", "id": 1, "result": redacted_result})
+    );
+
+    // The responses of other methods are left unchanged.
+    let request =
+        json!({"jsonrpc": "2.0", "id": 1, "method": "starknet_getTransactionByHash", "params": []});
+    let response = json!({"jsonrpc": "2.0", "id": 1, "result": result});
+    assert_eq!(redacted_response(request, response.clone()).await, response);
+}
@@ -8,23 +8,39 @@ use std::time::Instant;
 use jsonrpsee::server::logger::{HttpRequest, Logger, MethodKind, TransportProtocol};
 use jsonrpsee::types::Params;
 use jsonrpsee::Methods;
-use metrics::{histogram, increment_counter, register_counter, register_histogram};
+use metrics::{gauge, histogram, increment_counter, register_counter, register_histogram};
+
+use crate::papyrus_api::PAPYRUS_METHOD_PREFIX;
+use crate::subscriptions::SUBSCRIPTION_METHODS;
 
 // Name of the metrics.
 const INCOMING_REQUEST: &str = "rpc_incoming_requests";
 const FAILED_REQUESTS: &str = "rpc_failed_requests";
 const REQUEST_LATENCY: &str = "rpc_request_latency_seconds";
+const REJECTED_REQUESTS: &str = "rpc_rejected_requests";
+const EXECUTION_QUEUE_DEPTH: &str = "rpc_execution_queue_depth";
+const EXECUTION_TIME: &str = "rpc_execution_time_seconds";
 
 // Labels for the metrics.
 const METHOD_LABEL: &str = "method";
 const VERSION_LABEL: &str = "version";
+const REASON_LABEL: &str = "reason";
 const ILLEGAL_METHOD: &str = "illegal_method";
+const PAPYRUS_VERSION_LABEL_VALUE: &str = "papyrus";
+const SUBSCRIPTIONS_VERSION_LABEL_VALUE: &str = "subscriptions";
 
 // Register the metrics and returns a set of the method names.
 fn init_metrics(methods: &Methods) -> HashSet<String> {
     let mut methods_set: HashSet<String> = HashSet::new();
     register_counter!(INCOMING_REQUEST, METHOD_LABEL => ILLEGAL_METHOD);
     register_counter!(FAILED_REQUESTS, METHOD_LABEL => ILLEGAL_METHOD);
+    for reason in [
+        RejectionReason::BodySize,
+        RejectionReason::NestingDepth,
+        RejectionReason::ExecutionQueueFull,
+    ] {
+        register_counter!(REJECTED_REQUESTS, REASON_LABEL => reason.as_label());
+    }
     for method in methods.method_names() {
         methods_set.insert(method.to_string());
         let (method_name, version) = get_method_and_version(method);
@@ -34,6 +50,52 @@ fn init_metrics(methods: &Methods) -> HashSet<String> {
     }
     methods_set
 }
+/// The reasons for rejecting a request without serving it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum RejectionReason {
+    BodySize,
+    NestingDepth,
+    // An execution request arrived when the execution queue was full.
+    ExecutionQueueFull,
+}
+
+impl RejectionReason {
+    fn as_label(&self) -> &'static str {
+        match self {
+            RejectionReason::BodySize => "body_size",
+            RejectionReason::NestingDepth => "nesting_depth",
+            RejectionReason::ExecutionQueueFull => "execution_queue_full",
+        }
+    }
+}
+
+// Counts a request that was rejected without being served.
+pub(crate) fn increment_rejected_requests(reason: RejectionReason) {
+    increment_counter!(REJECTED_REQUESTS, REASON_LABEL => reason.as_label());
+}
+
+// Records the number of execution requests waiting for a free execution slot.
+pub(crate) fn set_execution_queue_depth(queue_depth: usize) {
+    gauge!(EXECUTION_QUEUE_DEPTH, queue_depth as f64);
+}
+
+// Records the time it took to run an execution, not including the time it waited in the queue.
+pub(crate) fn record_execution_time(execution_time_secs: f64) {
+    histogram!(EXECUTION_TIME, execution_time_secs);
+}
+
+// Records a request to one of the methods the node supports, e.g. one that was served without
+// going through the server.
+pub(crate) fn record_request(method_name: &str, failed: bool, started_at: Instant) {
+    let (method, version) = get_method_and_version(method_name);
+    if failed {
+        increment_counter!(FAILED_REQUESTS, METHOD_LABEL=> method.clone(), VERSION_LABEL=> version.clone());
+    }
+    increment_counter!(INCOMING_REQUEST, METHOD_LABEL=> method.clone(), VERSION_LABEL=> version.clone());
+    let latency = started_at.elapsed().as_secs_f64();
+    histogram!(REQUEST_LATENCY, latency,METHOD_LABEL=> method, VERSION_LABEL=> version);
+}
+
 #[derive(Clone)]
 pub(crate) struct MetricLogger {
     // A set of all the method names the node support.
@@ -59,13 +121,9 @@ impl Logger for MetricLogger {
     ) {
         // To prevent creating metrics for illegal methods.
         if self.methods_set.contains(method_name) {
-            let (method, version) = get_method_and_version(method_name);
-            if let jsonrpsee::helpers::MethodResponseResult::Failed(_) = success_or_error {
-                increment_counter!(FAILED_REQUESTS, METHOD_LABEL=> method.clone(), VERSION_LABEL=> version.clone());
-            }
-            increment_counter!(INCOMING_REQUEST, METHOD_LABEL=> method.clone(), VERSION_LABEL=> version.clone());
-            let latency = started_at.elapsed().as_secs_f64();
-            histogram!(REQUEST_LATENCY, latency,METHOD_LABEL=> method, VERSION_LABEL=> version);
+            let failed =
+                matches!(success_or_error, jsonrpsee::helpers::MethodResponseResult::Failed(_));
+            record_request(method_name, failed, started_at);
         } else {
             increment_counter!(INCOMING_REQUEST, METHOD_LABEL => ILLEGAL_METHOD);
             increment_counter!(FAILED_REQUESTS, METHOD_LABEL => ILLEGAL_METHOD);
@@ -106,7 +164,20 @@ impl Logger for MetricLogger {
 
 // Given method_name returns (method, version).
 // Example: method_name: starknet_V0_6_0_blockNumber; output: (blockNumber, V0_6_0).
+// Papyrus specific methods are shared by the versions and their version is "papyrus".
+// Example: method_name: papyrus_V0_6_getMarkers; output: (getMarkers, papyrus).
+// Subscriptions are not versioned either and their version is "subscriptions".
+// Example: method_name: starknet_subscribeTransactionStatus; output: (subscribeTransactionStatus,
+// subscriptions).
 fn get_method_and_version(method_name: &str) -> (String, String) {
+    if let Some(method) = method_name.strip_prefix(PAPYRUS_METHOD_PREFIX) {
+        let method = method.rsplit_once('_').map_or(method, |(_version, method)| method);
+        return (method.to_string(), PAPYRUS_VERSION_LABEL_VALUE.to_string());
+    }
+    if SUBSCRIPTION_METHODS.contains(&method_name) {
+        let method = method_name.trim_start_matches("starknet_");
+        return (method.to_string(), SUBSCRIPTIONS_VERSION_LABEL_VALUE.to_string());
+    }
     // The structure of method_name is in the following format: "starknet_V0_6_0_blockNumber".
     // Only method in this format will arrive to this point in the code.
     let last_underscore_index = method_name
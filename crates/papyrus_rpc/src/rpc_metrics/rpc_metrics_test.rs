@@ -23,13 +23,13 @@ use crate::rpc_metrics::{
     METHOD_LABEL,
     VERSION_LABEL,
 };
-use crate::run_server;
 use crate::test_utils::{
     get_test_highest_block,
     get_test_pending_classes,
     get_test_pending_data,
     get_test_rpc_config,
 };
+use crate::{run_server, RpcSharedState};
 
 #[test]
 fn get_method_and_version_test() {
@@ -37,6 +37,18 @@ fn get_method_and_version_test() {
     let (method, version) = get_method_and_version(method_name);
     assert_eq!(method, "blockNumber");
     assert_eq!(version, "V0_6_0");
+
+    let (method, version) = get_method_and_version("papyrus_getMarkers");
+    assert_eq!(method, "getMarkers");
+    assert_eq!(version, "papyrus");
+
+    let (method, version) = get_method_and_version("papyrus_V0_6_getMarkers");
+    assert_eq!(method, "getMarkers");
+    assert_eq!(version, "papyrus");
+
+    let (method, version) = get_method_and_version("starknet_subscribeTransactionStatus");
+    assert_eq!(method, "subscribeTransactionStatus");
+    assert_eq!(version, "subscriptions");
 }
 
 // Ignored because server_metrics test is running in parallel and we are unable to install multiple
@@ -160,9 +172,12 @@ async fn server_metrics() {
     gateway_config.collect_metrics = true;
     let (server_address, _handle) = run_server(
         &gateway_config,
-        get_test_highest_block(),
-        get_test_pending_data(),
-        get_test_pending_classes(),
+        RpcSharedState {
+            shared_highest_block: get_test_highest_block(),
+            pending_data: get_test_pending_data(),
+            pending_classes: get_test_pending_classes(),
+            ..Default::default()
+        },
         storage_reader,
         "NODE VERSION",
     )
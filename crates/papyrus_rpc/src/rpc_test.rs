@@ -1,24 +1,44 @@
 use std::error::Error as StdError;
+use std::net::SocketAddr;
+use std::time::Duration;
 use std::{panic, vec};
 
 use assert_matches::assert_matches;
 use futures_util::future::join_all;
 use hyper::{header, Body, Request};
-use jsonrpsee::core::client::ClientT;
+use jsonrpsee::core::client::{ClientT, SubscriptionClientT};
 use jsonrpsee::core::http_helpers::read_body;
 use jsonrpsee::core::{Error, RpcResult};
 use jsonrpsee::http_client::HttpClientBuilder;
+use jsonrpsee::rpc_params;
+use jsonrpsee::types::error::METHOD_NOT_FOUND_CODE;
 use jsonrpsee::types::ErrorObjectOwned;
+use jsonrpsee::ws_client::WsClientBuilder;
 use papyrus_storage::base_layer::BaseLayerStorageWriter;
+use papyrus_storage::body::events::EventIndex;
+use papyrus_storage::body::TransactionIndex;
 use papyrus_storage::header::HeaderStorageWriter;
 use papyrus_storage::test_utils::get_test_storage;
 use pretty_assertions::assert_eq;
 use rand::seq::SliceRandom;
 use starknet_api::block::{BlockHash, BlockHeader, BlockNumber, BlockStatus};
+use starknet_api::transaction::{
+    EventIndexInTransactionOutput,
+    TransactionHash,
+    TransactionOffsetInBlock,
+};
 use test_utils::get_rng;
 use tower::BoxError;
 
-use crate::middleware::proxy_rpc_request;
+use crate::api::ContinuationTokenKey;
+use crate::middleware::{
+    exceeds_nesting_depth,
+    proxy_rpc_request,
+    RecordedRequest,
+    RequestLimits,
+    RequestRecorder,
+};
+use crate::subscriptions::TransactionStatusNotification;
 use crate::test_utils::{
     get_test_highest_block,
     get_test_pending_classes,
@@ -26,7 +46,14 @@ use crate::test_utils::{
     get_test_rpc_config,
 };
 use crate::version_config::VERSION_CONFIG;
-use crate::{get_block_status, run_server, SERVER_MAX_BODY_SIZE};
+use crate::{
+    get_block_status,
+    run_server,
+    ContinuationTokenAsStruct,
+    RpcConfig,
+    RpcSharedState,
+    SERVER_MAX_BODY_SIZE,
+};
 
 #[tokio::test]
 async fn run_server_no_blocks() {
@@ -37,9 +64,12 @@ async fn run_server_no_blocks() {
     let pending_classes = get_test_pending_classes();
     let (addr, _handle) = run_server(
         &gateway_config,
-        shared_highest_block,
-        pending_data,
-        pending_classes,
+        RpcSharedState {
+            shared_highest_block,
+            pending_data,
+            pending_classes,
+            ..Default::default()
+        },
         storage_reader,
         "NODE VERSION",
     )
@@ -59,6 +89,137 @@ async fn run_server_no_blocks() {
     };
 }
 
+#[tokio::test]
+async fn run_server_on_multiple_addresses() {
+    let ((storage_reader, _), _temp_dir) = get_test_storage();
+    // A free port for the second address, whose port has to be known in advance.
+    let second_port =
+        std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let gateway_config = RpcConfig {
+        server_address: format!("127.0.0.1:0 127.0.0.1:{second_port}"),
+        ..get_test_rpc_config()
+    };
+    let (addr, handle) = run_server(
+        &gateway_config,
+        RpcSharedState {
+            shared_highest_block: get_test_highest_block(),
+            pending_data: get_test_pending_data(),
+            pending_classes: get_test_pending_classes(),
+            ..Default::default()
+        },
+        storage_reader,
+        "NODE VERSION",
+    )
+    .await
+    .unwrap();
+    let second_addr = SocketAddr::from(([127, 0, 0, 1], second_port));
+    for addr in [addr, second_addr] {
+        assert!(tokio::net::TcpStream::connect(addr).await.is_ok(), "No server listens on {addr}.");
+    }
+
+    // Stopping the server stops the servers of all the addresses.
+    handle.stop().unwrap();
+    handle.stopped().await;
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while tokio::net::TcpStream::connect(second_addr).await.is_ok() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("The server of the second address should stop.");
+}
+
+#[tokio::test]
+async fn run_server_on_unix_socket() {
+    let ((storage_reader, _), _temp_dir) = get_test_storage();
+    let socket_dir = tempfile::tempdir().unwrap();
+    let socket_path = socket_dir.path().join("papyrus_rpc.sock");
+    let gateway_config =
+        RpcConfig { unix_socket_path: Some(socket_path.clone()), ..get_test_rpc_config() };
+    let (_addr, handle) = run_server(
+        &gateway_config,
+        RpcSharedState {
+            shared_highest_block: get_test_highest_block(),
+            pending_data: get_test_pending_data(),
+            pending_classes: get_test_pending_classes(),
+            ..Default::default()
+        },
+        storage_reader,
+        "NODE VERSION",
+    )
+    .await
+    .unwrap();
+
+    let stream = tokio::net::UnixStream::connect(&socket_path).await.unwrap();
+    let (mut sender, connection) = hyper::client::conn::handshake(stream).await.unwrap();
+    tokio::spawn(connection);
+    let request = Request::post("/rpc/v0_7")
+        .header(header::HOST, "localhost")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(r#"{"jsonrpc":"2.0","id":1,"method":"starknet_specVersion"}"#))
+        .unwrap();
+    let response = sender.send_request(request).await.unwrap();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert!(body["result"].is_string(), "{body}");
+
+    // The socket file is removed when the server stops.
+    handle.stop().unwrap();
+    handle.stopped().await;
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while socket_path.exists() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("The Unix domain socket should be removed.");
+}
+
+#[tokio::test]
+async fn websocket_serves_only_subscriptions() {
+    let ((storage_reader, _), _temp_dir) = get_test_storage();
+    let (addr, _handle) = run_server(
+        &get_test_rpc_config(),
+        RpcSharedState {
+            shared_highest_block: get_test_highest_block(),
+            pending_data: get_test_pending_data(),
+            pending_classes: get_test_pending_classes(),
+            ..Default::default()
+        },
+        storage_reader,
+        "NODE VERSION",
+    )
+    .await
+    .unwrap();
+    let client = WsClientBuilder::default().build(format!("ws://{addr}/rpc/v0_7")).await.unwrap();
+
+    let subscription = client
+        .subscribe::<TransactionStatusNotification, _>(
+            "starknet_subscribeTransactionStatus",
+            rpc_params![TransactionHash::default()],
+            "starknet_unsubscribeTransactionStatus",
+        )
+        .await;
+    assert!(subscription.is_ok(), "{subscription:?}");
+
+    // The other methods aren't served over WebSocket, neither by the names the clients send over
+    // HTTP nor by the names the middleware versions them to.
+    for method in [
+        "starknet_specVersion",
+        "starknet_V0_7_specVersion",
+        "starknet_V0_7_addInvokeTransaction",
+        "papyrus_getMarkers",
+        "papyrus_V0_7_getMarkers",
+    ] {
+        let res = client.request::<serde_json::Value, _>(method, rpc_params![]).await;
+        assert_matches!(
+            res,
+            Err(Error::Call(err)) if err.code() == METHOD_NOT_FOUND_CODE,
+            "{method} should not be served over WebSocket."
+        );
+    }
+}
+
 /// Given an HTTP request, using the "read_body" function from jsonrpsee library,
 /// parse the body, make sure it's a formatted JSON and within the MAX_BODY_SIZE length.
 async fn get_json_rpc_body(request: Request<Body>) -> Vec<u8> {
@@ -190,6 +351,96 @@ async fn test_version_middleware() {
     };
 }
 
+#[tokio::test]
+async fn papyrus_methods_are_versioned_by_middleware() {
+    let method_name = "papyrus_getMarkers";
+    let request_body = serde_json::to_string(&jsonrpsee::types::Request::new(
+        method_name.into(),
+        None,
+        jsonrpsee::types::Id::Number(0),
+    ))
+    .unwrap();
+    let req = Request::post("http://localhost:8080/rpc/V0_7")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(request_body))
+        .unwrap();
+    let res = proxy_rpc_request(req).await.unwrap();
+    let body_bytes = get_json_rpc_body(res).await;
+    let body = serde_json::from_slice::<jsonrpsee::types::Request<'_>>(&body_bytes).unwrap();
+    assert_eq!(body.method, "papyrus_V0_7_getMarkers");
+}
+
+#[tokio::test]
+async fn request_recorder_appends_requests_and_keeps_them_intact() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("requests.jsonl");
+    let recorder = RequestRecorder::new(&path).unwrap();
+    let params = serde_json::from_str(r#"[{"myParam": "myValue"}]"#).unwrap();
+
+    let mut expected_requests = vec![];
+    for is_batch_request in [false, true] {
+        let request_body = get_request_body(is_batch_request, params, "myMethod").unwrap();
+        let req = Request::post("http://localhost:8080/rpc/V0_7")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(request_body.clone()))
+            .unwrap();
+        let res = recorder.record(req).await.unwrap();
+        assert_eq!(get_json_rpc_body(res).await, request_body.as_bytes());
+        expected_requests.push(RecordedRequest {
+            path: "/rpc/V0_7".to_owned(),
+            body: serde_json::from_str(&request_body).unwrap(),
+        });
+    }
+
+    let recorded_requests = std::fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<RecordedRequest>(line).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(recorded_requests, expected_requests);
+}
+
+#[test]
+fn nesting_depth() {
+    assert!(!exceeds_nesting_depth(br#"{"params": [[1], {"a": []}]}"#, 3));
+    assert!(exceeds_nesting_depth(br#"{"params": [[1], {"a": [[]]}]}"#, 3));
+    // Brackets in strings, including after escaped quotes, aren't counted.
+    assert!(!exceeds_nesting_depth(br#"{"params": ["[[[", "\"{{{"]}"#, 2));
+}
+
+#[tokio::test]
+async fn request_limits_reject_large_and_deep_requests() {
+    let request_limits = RequestLimits { max_body_size: 100, max_nesting_depth: 4 };
+    let request = |body: String| {
+        Request::post("http://localhost:8080/rpc/V0_7")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    };
+
+    let request_body =
+        get_request_body(false, serde_json::from_str("[[1]]").unwrap(), "myMethod").unwrap();
+    let res = request_limits.check(request(request_body.clone())).await.unwrap();
+    assert_eq!(get_json_rpc_body(res).await, request_body.as_bytes());
+
+    let deep_request_body =
+        get_request_body(false, serde_json::from_str("[[[[1]]]]").unwrap(), "myMethod").unwrap();
+    assert!(request_limits.check(request(deep_request_body)).await.is_err());
+
+    let large_request_body = get_request_body(
+        false,
+        serde_json::from_str(&format!("[\"{}\"]", "a".repeat(100))).unwrap(),
+        "myMethod",
+    )
+    .unwrap();
+    assert!(request_limits.check(request(large_request_body.clone())).await.is_err());
+
+    // Asking to upgrade the connection to WebSocket doesn't exempt a request from the limits.
+    let mut upgrade_request = request(large_request_body);
+    upgrade_request.headers_mut().insert(header::UPGRADE, "websocket".parse().unwrap());
+    assert!(request_limits.check(upgrade_request).await.is_err());
+}
+
 #[test]
 fn get_block_status_test() {
     let (reader, mut writer) = get_test_storage().0;
@@ -232,3 +483,31 @@ fn serialization_precision() {
     let deserialized = serde_json::to_string(&serialized).unwrap();
     assert_eq!(input, deserialized);
 }
+
+#[test]
+fn continuation_token_is_signed() {
+    let continuation_token = ContinuationTokenAsStruct(
+        EventIndex(
+            TransactionIndex(BlockNumber(5), TransactionOffsetInBlock(3)),
+            EventIndexInTransactionOutput(7),
+        ),
+        BlockHash(0x1234_u64.into()),
+    );
+    let key = ContinuationTokenKey::new(Some("secret"));
+    let encoded = continuation_token.encode(&key);
+    assert_eq!(ContinuationTokenAsStruct::decode(&encoded, &key), Some(continuation_token.clone()));
+
+    // Nodes that share the secret accept each other's tokens.
+    let same_secret_key = ContinuationTokenKey::new(Some("secret"));
+    assert_eq!(
+        ContinuationTokenAsStruct::decode(&encoded, &same_secret_key),
+        Some(continuation_token)
+    );
+    assert_eq!(ContinuationTokenAsStruct::decode(&encoded, &ContinuationTokenKey::new(None)), None);
+
+    // Changing the encoded event index invalidates the signature.
+    let mut forged = hex::decode(&encoded).unwrap();
+    forged[7] += 1;
+    assert_eq!(ContinuationTokenAsStruct::decode(&hex::encode(forged), &key), None);
+    assert_eq!(ContinuationTokenAsStruct::decode("junk", &key), None);
+}
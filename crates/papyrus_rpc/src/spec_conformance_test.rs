@@ -0,0 +1,201 @@
+//! Calls every method of the specs of every supported version on a live server over a seeded
+//! storage, and validates that it succeeds with a response that conforms to the specs. A method
+//! that is added to the specs fails this test until it's either covered or explicitly listed as
+//! uncovered.
+
+use std::net::SocketAddr;
+
+use indexmap::IndexMap;
+use papyrus_storage::body::BodyStorageWriter;
+use papyrus_storage::header::HeaderStorageWriter;
+use papyrus_storage::state::StateStorageWriter;
+use papyrus_storage::test_utils::get_test_storage;
+use papyrus_storage::StorageWriter;
+use pretty_assertions::assert_eq;
+use serde_json::{json, Value};
+use starknet_api::core::{ClassHash, ContractAddress, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::StorageKey;
+use starknet_api::transaction::TransactionHash;
+use starknet_api::{contract_address, patricia_key, stark_felt};
+use test_utils::{get_test_block, get_test_state_diff, send_request};
+
+use crate::test_utils::{
+    get_methods_with_param_names_from_spec,
+    get_starknet_spec_api_schema_for_method_results,
+    get_test_highest_block,
+    get_test_pending_classes,
+    get_test_pending_data,
+    get_test_rpc_config,
+    validate_schema,
+    SpecFile,
+};
+use crate::version_config::{VersionId, VersionState, VERSION_CONFIG};
+use crate::{run_server, RpcSharedState};
+
+// The spec files whose methods are called by this test.
+const SPEC_FILES: [SpecFile; 3] =
+    [SpecFile::StarknetApiOpenrpc, SpecFile::TraceApi, SpecFile::WriteApi];
+
+// Methods that aren't called by this test.
+const UNCOVERED_METHODS: &[&str] = &[
+    // Executing transactions requires a state with deployed accounts and their classes, which the
+    // seeded storage doesn't have. These methods are covered by the tests of each version.
+    "starknet_call",
+    "starknet_estimateFee",
+    "starknet_estimateMessageFee",
+    "starknet_simulateTransactions",
+    "starknet_traceBlockTransactions",
+    "starknet_traceTransaction",
+    // Forwarded to the gateway, which is mocked by the tests of each version.
+    "starknet_addDeclareTransaction",
+    "starknet_addDeployAccountTransaction",
+    "starknet_addInvokeTransaction",
+    // Not supported by the node.
+    "starknet_pendingTransactions",
+];
+
+// The values the params of the methods are taken from. All of them refer to block 0.
+struct SeededData {
+    transaction_hash: TransactionHash,
+    contract_address: ContractAddress,
+    storage_key: StorageKey,
+    class_hash: ClassHash,
+}
+
+impl SeededData {
+    // Returns the serialized value of the param with the given name in the specs.
+    fn param(&self, param_name: &str) -> Value {
+        match param_name {
+            "block_id" => json!({ "block_number": 0 }),
+            "transaction_hash" => json!(self.transaction_hash),
+            "index" => json!(0),
+            "contract_address" => json!(self.contract_address),
+            "key" => json!(self.storage_key),
+            "class_hash" => json!(self.class_hash),
+            "filter" => json!({
+                "from_block": { "block_number": 0 },
+                "to_block": "latest",
+                "chunk_size": 10,
+            }),
+            _ => panic!("There's no seeded value for param {param_name}."),
+        }
+    }
+}
+
+// Stores a block with transactions, events and a state diff that deploys a contract of a declared
+// class and writes to its storage.
+fn seed_storage(storage_writer: &mut StorageWriter) -> SeededData {
+    let block = get_test_block(2, Some(1), None, None);
+    let mut state_diff = get_test_state_diff();
+    let class_hash = *state_diff.deprecated_declared_classes.get_index(0).unwrap().0;
+    let contract_address = contract_address!("0x2");
+    let storage_key = StorageKey(patricia_key!("0x3"));
+    state_diff.deployed_contracts.insert(contract_address, class_hash);
+    state_diff
+        .storage_diffs
+        .insert(contract_address, IndexMap::from([(storage_key, stark_felt!("0x4"))]));
+
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block.header.block_number, &block.header)
+        .unwrap()
+        .append_body(block.header.block_number, block.body.clone())
+        .unwrap()
+        .append_state_diff(block.header.block_number, state_diff, IndexMap::new())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    SeededData {
+        transaction_hash: block.body.transaction_hashes[0],
+        contract_address,
+        storage_key,
+        class_hash,
+    }
+}
+
+// Calls the method and validates that it succeeded with a result that conforms to the specs.
+async fn assert_method_conforms_to_spec(
+    server_address: SocketAddr,
+    version_id: &VersionId,
+    spec_file: SpecFile,
+    method: &str,
+    params: &[Value],
+) {
+    let serialized_params =
+        params.iter().map(|param| param.to_string()).collect::<Vec<_>>().join(", ");
+    let response = send_request(server_address, method, &serialized_params, version_id.name).await;
+
+    let Some(result) = response.get("result") else {
+        panic!(
+            "Method {method} of version {} failed over the seeded storage.\nParams: \
+             [{serialized_params}]\nResponse:\n{}",
+            version_id.name,
+            serde_json::to_string_pretty(&response).unwrap(),
+        );
+    };
+    let schema =
+        get_starknet_spec_api_schema_for_method_results(&[(spec_file, &[method])], version_id);
+    assert!(
+        validate_schema(&schema, result),
+        "Response of method {method} of version {} does not correspond to the specs.\nParams: \
+         [{serialized_params}]\nResponse:\n{}",
+        version_id.name,
+        serde_json::to_string_pretty(&response).unwrap(),
+    );
+}
+
+#[tokio::test]
+async fn all_methods_conform_to_spec() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let seeded_data = seed_storage(&mut storage_writer);
+    let (server_address, _handle) = run_server(
+        &get_test_rpc_config(),
+        RpcSharedState {
+            shared_highest_block: get_test_highest_block(),
+            pending_data: get_test_pending_data(),
+            pending_classes: get_test_pending_classes(),
+            ..Default::default()
+        },
+        storage_reader,
+        "NODE VERSION",
+    )
+    .await
+    .unwrap();
+
+    let mut served_versions = 0;
+    for (version_id, version_state) in VERSION_CONFIG {
+        // The server doesn't serve deprecated versions.
+        if let VersionState::Deprecated = version_state {
+            continue;
+        }
+        served_versions += 1;
+        let mut covered_methods = 0;
+        for spec_file in SPEC_FILES {
+            for (method, param_names) in
+                get_methods_with_param_names_from_spec(version_id, spec_file)
+            {
+                if UNCOVERED_METHODS.contains(&method.as_str()) {
+                    continue;
+                }
+                let params = param_names
+                    .iter()
+                    .map(|param_name| seeded_data.param(param_name))
+                    .collect::<Vec<_>>();
+                assert_method_conforms_to_spec(
+                    server_address,
+                    version_id,
+                    spec_file,
+                    &method,
+                    &params,
+                )
+                .await;
+                covered_methods += 1;
+            }
+        }
+        assert!(covered_methods > 0, "No method of version {} was called.", version_id.name);
+    }
+    assert_eq!(served_versions, VERSION_CONFIG.len());
+}
@@ -0,0 +1,353 @@
+//! Streaming of large responses.
+//!
+//! Responses such as a block with thousands of transactions are read from the storage and then
+//! converted to the representation of the RPC version. Converting the whole response before
+//! serializing it keeps both representations in memory at once. A [`StreamedSeq`] instead holds
+//! the storage representation and converts a single item at a time, right before the serializer
+//! writes it.
+//!
+//! The server serializes a whole response before it sends any of it, so the methods whose
+//! responses can be huge are served by [`StreamingLayer`] instead. It calls the method of the
+//! requested version itself and sends the response body in chunks while the result is serialized,
+//! so that neither the converted result nor its serialization is ever held in memory whole.
+
+#[cfg(test)]
+#[path = "streaming_test.rs"]
+mod streaming_test;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::future::Future;
+use std::io::{self, Write};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use async_trait::async_trait;
+use futures_util::{stream, StreamExt};
+use hyper::header::{HeaderValue, CONTENT_TYPE};
+use hyper::{Body, Request, Response};
+use jsonrpsee::core::http_helpers::read_body;
+use jsonrpsee::core::RpcResult;
+use jsonrpsee::types::error::ErrorCode::InvalidParams;
+use jsonrpsee::types::{ErrorObjectOwned, Id, Params, Request as RpcRequest};
+use serde::de::DeserializeOwned;
+use serde::ser::{Error as SerError, SerializeSeq};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tower::{BoxError, Layer, Service};
+use tracing::Span;
+
+use crate::rpc_metrics::record_request;
+use crate::{internal_server_error, SERVER_MAX_BODY_SIZE};
+
+// The size of the chunks in which a streamed response body is sent.
+const CHUNK_SIZE: usize = 64 * 1024;
+// The number of chunks that are serialized ahead of the client reading them.
+const MAX_PENDING_CHUNKS: usize = 4;
+
+thread_local! {
+    // The error of the last item of a StreamedSeq that failed to convert on this thread. The
+    // serializer only keeps the message of the error, and the response is sent with the error
+    // itself.
+    static CONVERSION_ERROR: RefCell<Option<ErrorObjectOwned>> = const { RefCell::new(None) };
+}
+
+/// A sequence of `T` that is serialized by converting items of type `S` to `T` one by one.
+///
+/// It's serialized exactly like a `Vec<T>`. Deserializing it (as done by the RPC client) yields an
+/// already converted sequence.
+#[derive(Debug, Clone)]
+pub struct StreamedSeq<S, T>(Items<S, T>);
+
+#[derive(Debug, Clone)]
+enum Items<S, T> {
+    Unconverted { items: Vec<S>, convert: fn(S) -> Result<T, ErrorObjectOwned> },
+    Converted(Vec<T>),
+}
+
+impl<S, T> StreamedSeq<S, T> {
+    /// Creates a sequence that converts each of the given items with `convert` when it's
+    /// serialized.
+    pub fn new(items: Vec<S>, convert: fn(S) -> Result<T, ErrorObjectOwned>) -> Self {
+        Self(Items::Unconverted { items, convert })
+    }
+
+    /// Converts all the items of the sequence.
+    pub fn into_converted(self) -> Result<Vec<T>, ErrorObjectOwned> {
+        match self.0 {
+            Items::Unconverted { items, convert } => items.into_iter().map(convert).collect(),
+            Items::Converted(items) => Ok(items),
+        }
+    }
+}
+
+impl<S, T> From<Vec<T>> for StreamedSeq<S, T> {
+    fn from(items: Vec<T>) -> Self {
+        Self(Items::Converted(items))
+    }
+}
+
+impl<S: Clone, T: Serialize> Serialize for StreamedSeq<S, T> {
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        match &self.0 {
+            Items::Unconverted { items, convert } => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    // Only a single converted item is alive at any point of the serialization.
+                    let converted = convert(item.clone()).map_err(|err| {
+                        let message = err.message().to_owned();
+                        CONVERSION_ERROR.with(|conversion_error| {
+                            *conversion_error.borrow_mut() = Some(err);
+                        });
+                        Ser::Error::custom(message)
+                    })?;
+                    seq.serialize_element(&converted)?;
+                }
+                seq.end()
+            }
+            Items::Converted(items) => items.serialize(serializer),
+        }
+    }
+}
+
+impl<'de, S, T: Deserialize<'de>> Deserialize<'de> for StreamedSeq<S, T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Vec::<T>::deserialize(deserializer)?.into())
+    }
+}
+
+/// The result of a method, serialized only while it's written to the response body.
+pub struct StreamedResult(Box<dyn FnOnce(&mut dyn Write) -> serde_json::Result<()> + Send>);
+
+impl StreamedResult {
+    pub fn new<T: Serialize + Send + 'static>(result: T) -> Self {
+        Self(Box::new(move |writer| serde_json::to_writer(writer, &result)))
+    }
+
+    // Writes the serialized result. If an item of the result failed to convert, returns the error
+    // of its conversion.
+    fn write(self, writer: &mut dyn Write) -> Result<(), WriteError> {
+        CONVERSION_ERROR.with(|conversion_error| conversion_error.borrow_mut().take());
+        (self.0)(writer).map_err(|err| {
+            if err.is_io() {
+                return WriteError::Disconnected;
+            }
+            let conversion_error =
+                CONVERSION_ERROR.with(|conversion_error| conversion_error.borrow_mut().take());
+            WriteError::Failed(conversion_error.unwrap_or_else(|| internal_server_error(err)))
+        })
+    }
+}
+
+enum WriteError {
+    // The client stopped reading the response.
+    Disconnected,
+    Failed(ErrorObjectOwned),
+}
+
+/// The methods of an RPC version whose responses are streamed.
+#[async_trait]
+pub trait StreamedMethods: Send + Sync {
+    /// Calls the method with the given name, without its namespace and version, e.g.
+    /// "getBlockWithTxs". Returns None if the responses of the method aren't streamed.
+    async fn call(&self, method: &str, params: Params<'_>) -> RpcResult<Option<StreamedResult>>;
+}
+
+/// The streamed methods of each version, by the name of the version, e.g. "V0_7".
+pub type StreamedMethodsByVersion = HashMap<&'static str, Arc<dyn StreamedMethods>>;
+
+/// Parses the only parameter of a method, given either by position or by name.
+pub fn single_param<T: DeserializeOwned>(params: &Params<'_>, name: &str) -> RpcResult<T> {
+    if !params.is_object() {
+        return params.one();
+    }
+    let mut named_params: Map<String, Value> = params.parse()?;
+    let param = named_params.remove(name).ok_or_else(|| {
+        ErrorObjectOwned::owned(InvalidParams.code(), format!("Missing {name}."), None::<()>)
+    })?;
+    serde_json::from_value(param)
+        .map_err(|err| ErrorObjectOwned::owned(InvalidParams.code(), err.to_string(), None::<()>))
+}
+
+/// [`Tower`] layer that serves the requests to the streamed methods of the versions, sending the
+/// response body while the result is serialized. Batches and requests to other methods are passed
+/// on to the server.
+///
+/// A failure to serialize the result before the first chunk of the body is sent is responded with
+/// its JSON-RPC error. A later failure can't be responded with, and the response body is aborted.
+///
+/// [`Tower`]: https://crates.io/crates/tower
+#[derive(Clone)]
+pub(crate) struct StreamingLayer {
+    methods: Arc<StreamedMethodsByVersion>,
+    collect_metrics: bool,
+}
+
+impl StreamingLayer {
+    pub(crate) fn new(methods: StreamedMethodsByVersion, collect_metrics: bool) -> Self {
+        Self { methods: Arc::new(methods), collect_metrics }
+    }
+
+    // Serves the request if it's to a streamed method. The method name is the one the middleware
+    // routed the request to, e.g. "starknet_V0_7_getBlockWithTxs".
+    async fn serve(&self, request: &RpcRequest<'_>) -> Option<Response<Body>> {
+        let (version, method) = request.method.strip_prefix("starknet_")?.rsplit_once('_')?;
+        let methods = self.methods.get(version)?;
+        let started_at = Instant::now();
+        let params = Params::new(request.params.as_ref().map(|params| params.get()));
+        let response = match methods.call(method, params).await {
+            Ok(Some(result)) => stream_response(request.id.clone().into_owned(), result).await,
+            Ok(None) => return None,
+            Err(err) => Err(err),
+        };
+        if self.collect_metrics {
+            record_request(&request.method, response.is_err(), started_at);
+        }
+        Some(response.unwrap_or_else(|err| {
+            json_response(Body::from(error_response_body(request.id.clone(), err)))
+        }))
+    }
+}
+
+// Sends the result in chunks while it's serialized. Returns the error of the method if it failed
+// before the first chunk was sent.
+async fn stream_response(
+    id: Id<'static>,
+    result: StreamedResult,
+) -> Result<Response<Body>, ErrorObjectOwned> {
+    let (sender, mut receiver) = mpsc::channel(MAX_PENDING_CHUNKS);
+    let span = Span::current();
+    tokio::task::spawn_blocking(move || span.in_scope(|| write_response(id, result, sender)));
+
+    let first_chunk = receiver
+        .recv()
+        .await
+        .unwrap_or_else(|| Err(internal_server_error("The response was dropped unwritten.")))?;
+    let next_chunks = ReceiverStream::new(receiver)
+        .map(|chunk| chunk.map_err(|err| io::Error::other(err.message().to_owned())));
+    let chunks = stream::once(async { Ok::<_, io::Error>(first_chunk) }).chain(next_chunks);
+    Ok(json_response(Body::wrap_stream(chunks)))
+}
+
+fn write_response(
+    id: Id<'static>,
+    result: StreamedResult,
+    sender: mpsc::Sender<Result<Vec<u8>, ErrorObjectOwned>>,
+) {
+    let id = serde_json::to_string(&id).expect("Request ids should be serializable.");
+    let mut writer = ChunkWriter { chunk: Vec::with_capacity(CHUNK_SIZE), sender };
+    let written = writer
+        .write_all(br#"{"jsonrpc":"2.0","result":"#)
+        .map_err(|_| WriteError::Disconnected)
+        .and_then(|()| result.write(&mut writer))
+        .and_then(|()| {
+            writer
+                .write_all(format!(r#","id":{id}}}"#).as_bytes())
+                .and_then(|()| writer.flush())
+                .map_err(|_| WriteError::Disconnected)
+        });
+    if let Err(WriteError::Failed(err)) = written {
+        // The client may have disconnected already.
+        let _ = writer.sender.blocking_send(Err(err));
+    }
+}
+
+// Sends the written bytes in chunks of CHUNK_SIZE bytes. Fails once the response is dropped.
+struct ChunkWriter {
+    chunk: Vec<u8>,
+    sender: mpsc::Sender<Result<Vec<u8>, ErrorObjectOwned>>,
+}
+
+impl Write for ChunkWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.chunk.extend_from_slice(buf);
+        if self.chunk.len() >= CHUNK_SIZE {
+            self.flush()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.chunk.is_empty() {
+            return Ok(());
+        }
+        let chunk = std::mem::replace(&mut self.chunk, Vec::with_capacity(CHUNK_SIZE));
+        self.sender.blocking_send(Ok(chunk)).map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorResponse<'a> {
+    jsonrpc: &'static str,
+    error: ErrorObjectOwned,
+    id: Id<'a>,
+}
+
+fn error_response_body(id: Id<'_>, error: ErrorObjectOwned) -> String {
+    serde_json::to_string(&ErrorResponse { jsonrpc: "2.0", error, id })
+        .expect("Error responses should be serializable.")
+}
+
+fn json_response(body: Body) -> Response<Body> {
+    let mut response = Response::new(body);
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json; charset=utf-8"));
+    response
+}
+
+impl<S> Layer<S> for StreamingLayer {
+    type Service = Streaming<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Streaming { layer: self.clone(), inner }
+    }
+}
+
+/// The service of [`StreamingLayer`].
+#[derive(Clone)]
+pub(crate) struct Streaming<S> {
+    layer: StreamingLayer,
+    inner: S,
+}
+
+impl<S> Service<Request<Body>> for Streaming<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send,
+    S::Error: Into<BoxError>,
+{
+    type Response = Response<Body>;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Response<Body>, BoxError>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        // The service that was polled ready handles the request, and a clone takes its place.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let layer = self.layer.clone();
+        Box::pin(async move {
+            let (parts, body) = req.into_parts();
+            let (body_bytes, is_single) = read_body(&parts.headers, body, SERVER_MAX_BODY_SIZE)
+                .await
+                .map_err(BoxError::from)?;
+            // Invalid requests are rejected by the server, with the appropriate JSON-RPC error.
+            if is_single {
+                if let Ok(request) = serde_json::from_slice::<RpcRequest<'_>>(&body_bytes) {
+                    if let Some(response) = layer.serve(&request).await {
+                        return Ok(response);
+                    }
+                }
+            }
+            inner.call(Request::from_parts(parts, body_bytes.into())).await.map_err(Into::into)
+        })
+    }
+}
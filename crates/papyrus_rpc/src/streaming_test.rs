@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use hyper::{Body, Request, Response};
+use jsonrpsee::core::RpcResult;
+use jsonrpsee::types::error::ErrorCode::InvalidParams;
+use jsonrpsee::types::{ErrorObjectOwned, Params};
+use pretty_assertions::assert_eq;
+use serde_json::{json, Value};
+use tower::{BoxError, Layer, ServiceExt};
+
+use super::{single_param, StreamedMethods, StreamedResult, StreamedSeq, StreamingLayer};
+
+const TOO_BIG_CODE: i32 = 1;
+const INNER_RESPONSE: &str = "Served by the server.";
+
+fn checked_double(item: u32) -> Result<u64, ErrorObjectOwned> {
+    if item > 100 {
+        return Err(ErrorObjectOwned::owned(
+            TOO_BIG_CODE,
+            format!("{item} is too big."),
+            None::<()>,
+        ));
+    }
+    Ok(u64::from(item) * 2)
+}
+
+#[test]
+fn serialized_like_converted_vec() {
+    let seq = StreamedSeq::new(vec![1, 2, 3], checked_double);
+    assert_eq!(serde_json::to_string(&seq).unwrap(), serde_json::to_string(&[2, 4, 6]).unwrap());
+    assert_eq!(seq.into_converted().unwrap(), vec![2, 4, 6]);
+}
+
+#[test]
+fn conversion_error_fails_serialization() {
+    let seq = StreamedSeq::new(vec![1, 200], checked_double);
+    let err = serde_json::to_string(&seq).unwrap_err();
+    assert_eq!(err.to_string(), "200 is too big.");
+}
+
+#[test]
+fn deserialized_as_converted() {
+    let seq: StreamedSeq<u32, u64> = serde_json::from_str("[2,4,6]").unwrap();
+    assert_eq!(seq.into_converted().unwrap(), vec![2, 4, 6]);
+}
+
+struct TestMethods;
+
+#[async_trait]
+impl StreamedMethods for TestMethods {
+    async fn call(&self, method: &str, params: Params<'_>) -> RpcResult<Option<StreamedResult>> {
+        if method != "getDoubles" {
+            return Ok(None);
+        }
+        let items: Vec<u32> = single_param(&params, "items")?;
+        Ok(Some(StreamedResult::new(StreamedSeq::new(items, checked_double))))
+    }
+}
+
+// Sends the request through the layer to a server that answers every request with
+// INNER_RESPONSE, and returns the body that the client gets.
+async fn streamed_response(request_body: Value) -> Result<Vec<u8>, hyper::Error> {
+    let methods = HashMap::from([("V0_7", Arc::new(TestMethods) as Arc<dyn StreamedMethods>)]);
+    let service = StreamingLayer::new(methods, false).layer(tower::service_fn(
+        |_request: Request<Body>| async {
+            Ok::<_, BoxError>(Response::new(Body::from(INNER_RESPONSE)))
+        },
+    ));
+    let request = Request::post("http://localhost:8080/rpc/V0_7")
+        .header(hyper::header::CONTENT_TYPE, "application/json")
+        .body(Body::from(request_body.to_string()))
+        .unwrap();
+    let response = service.oneshot(request).await.unwrap();
+    Ok(hyper::body::to_bytes(response.into_body()).await?.to_vec())
+}
+
+fn get_doubles_request(params: Value) -> Value {
+    json!({"jsonrpc": "2.0", "id": 1, "method": "starknet_V0_7_getDoubles", "params": params})
+}
+
+#[tokio::test]
+async fn result_is_streamed() {
+    // The response is bigger than a single chunk.
+    let items = vec![1; 100_000];
+    let response = streamed_response(get_doubles_request(json!([items]))).await.unwrap();
+    assert_eq!(
+        serde_json::from_slice::<Value>(&response).unwrap(),
+        json!({"jsonrpc": "2.0", "id": 1, "result": vec![2; 100_000]})
+    );
+
+    let response = streamed_response(get_doubles_request(json!({"items": [1, 2]}))).await.unwrap();
+    assert_eq!(
+        serde_json::from_slice::<Value>(&response).unwrap(),
+        json!({"jsonrpc": "2.0", "id": 1, "result": [2, 4]})
+    );
+}
+
+#[tokio::test]
+async fn errors_before_streaming_are_responded() {
+    let response = streamed_response(get_doubles_request(json!([[1, 200]]))).await.unwrap();
+    assert_eq!(
+        serde_json::from_slice::<Value>(&response).unwrap(),
+        json!({"jsonrpc": "2.0", "id": 1, "error": {"code": TOO_BIG_CODE, "message": "200 is too big."}})
+    );
+
+    let response = streamed_response(get_doubles_request(json!({"other": [1]}))).await.unwrap();
+    let response = serde_json::from_slice::<Value>(&response).unwrap();
+    assert_eq!(response["error"]["code"], json!(InvalidParams.code()));
+}
+
+#[tokio::test]
+async fn errors_while_streaming_abort_the_response() {
+    let mut items = vec![1; 100_000];
+    items.push(200);
+    assert!(streamed_response(get_doubles_request(json!([items]))).await.is_err());
+}
+
+#[tokio::test]
+async fn other_requests_are_served_by_the_server() {
+    let other_method = json!({"jsonrpc": "2.0", "id": 1, "method": "starknet_V0_7_blockNumber"});
+    let other_version = json!({"jsonrpc": "2.0", "id": 1, "method": "starknet_V0_6_getDoubles"});
+    let batch = json!([get_doubles_request(json!([[1]]))]);
+    for request in [other_method, other_version, batch] {
+        assert_eq!(streamed_response(request).await.unwrap(), INNER_RESPONSE.as_bytes());
+    }
+}
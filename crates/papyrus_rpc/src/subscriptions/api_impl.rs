@@ -0,0 +1,91 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use jsonrpsee::core::SubscriptionResult;
+use jsonrpsee::{PendingSubscriptionSink, SubscriptionMessage};
+use papyrus_common::sync_notifications::SyncNotifier;
+use papyrus_storage::base_layer::BaseLayerStorageReader;
+use papyrus_storage::body::{BodyStorageReader, TransactionIndex};
+use papyrus_storage::header::HeaderStorageReader;
+use papyrus_storage::{StorageError, StorageReader, StorageResult};
+use starknet_api::transaction::TransactionHash;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::instrument;
+
+use super::{
+    SubscribedTransactionStatus,
+    SubscriptionsJsonRpcServer,
+    TransactionStatusNotification,
+};
+
+/// Rpc server of the subscriptions.
+pub struct SubscriptionsJsonRpcServerImpl {
+    pub storage_reader: StorageReader,
+    pub sync_notifier: Arc<SyncNotifier>,
+}
+
+#[async_trait]
+impl SubscriptionsJsonRpcServer for SubscriptionsJsonRpcServerImpl {
+    #[instrument(skip(self, pending), level = "debug")]
+    async fn subscribe_transaction_status(
+        &self,
+        pending: PendingSubscriptionSink,
+        transaction_hash: TransactionHash,
+    ) -> SubscriptionResult {
+        // Subscribe before reading the storage, so that changes made after the read aren't missed.
+        let mut notifications = self.sync_notifier.subscribe();
+        let sink = pending.accept().await?;
+        let mut last_status = None;
+        loop {
+            let status = get_transaction_status(&self.storage_reader, transaction_hash)?;
+            if status != last_status {
+                // A transaction that was in the chain and isn't anymore was removed by a reorg.
+                let notification = TransactionStatusNotification {
+                    transaction_hash,
+                    status: status.unwrap_or(SubscribedTransactionStatus::Reorged),
+                };
+                sink.send(SubscriptionMessage::from_json(&notification)?).await?;
+                if let Some(SubscribedTransactionStatus::AcceptedOnL1 { .. }) = status {
+                    return Ok(());
+                }
+                last_status = status;
+            }
+
+            tokio::select! {
+                _ = sink.closed() => return Ok(()),
+                notification = notifications.recv() => {
+                    // The status is read from the storage after every notification, so missed
+                    // notifications (when lagging behind the sync) don't matter.
+                    if let Err(RecvError::Closed) = notification {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Returns the status of the transaction according to the storage, or None if it isn't in the chain.
+fn get_transaction_status(
+    storage_reader: &StorageReader,
+    transaction_hash: TransactionHash,
+) -> StorageResult<Option<SubscribedTransactionStatus>> {
+    let txn = storage_reader.begin_ro_txn()?;
+    let Some(TransactionIndex(block_number, _)) =
+        txn.get_transaction_idx_by_hash(&transaction_hash)?
+    else {
+        return Ok(None);
+    };
+    let block_hash = txn
+        .get_block_header(block_number)?
+        .ok_or(StorageError::DBInconsistency {
+            msg: format!("Missing header of block {block_number} which has a body."),
+        })?
+        .block_hash;
+    let status = if block_number < txn.get_base_layer_block_marker()? {
+        SubscribedTransactionStatus::AcceptedOnL1 { block_hash, block_number }
+    } else {
+        SubscribedTransactionStatus::AcceptedOnL2 { block_hash, block_number }
+    };
+    Ok(Some(status))
+}
@@ -0,0 +1,55 @@
+//! Subscriptions to changes of the chain, available only over WebSocket.
+//!
+//! Subscriptions are driven by the notifications the sync sends whenever it changes the chain in
+//! the storage. They are not versioned, and they are exposed under every supported version path.
+use jsonrpsee::core::SubscriptionResult;
+use jsonrpsee::proc_macros::rpc;
+use serde::{Deserialize, Serialize};
+use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::transaction::TransactionHash;
+
+pub mod api_impl;
+#[cfg(test)]
+mod test;
+
+/// The methods of the subscriptions. They aren't versioned, so the metrics label them by this table
+/// rather than by the version in their name.
+pub(crate) const SUBSCRIPTION_METHODS: [&str; 2] =
+    ["starknet_subscribeTransactionStatus", "starknet_unsubscribeTransactionStatus"];
+
+#[rpc(server, namespace = "starknet")]
+pub trait SubscriptionsJsonRpc {
+    /// Notifies on every change of the finality status of a transaction: when it's included in a
+    /// block, when its block is accepted on L1 and when a reorg removes it from the chain. If the
+    /// transaction is already included in a block, its current status is notified immediately.
+    /// The subscription ends once the transaction is accepted on L1.
+    #[subscription(
+        name = "subscribeTransactionStatus",
+        unsubscribe = "unsubscribeTransactionStatus",
+        item = TransactionStatusNotification
+    )]
+    async fn subscribe_transaction_status(
+        &self,
+        transaction_hash: TransactionHash,
+    ) -> SubscriptionResult;
+}
+
+/// A notification on a change of the finality status of a transaction.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct TransactionStatusNotification {
+    pub transaction_hash: TransactionHash,
+    #[serde(flatten)]
+    pub status: SubscribedTransactionStatus,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "finality_status")]
+pub enum SubscribedTransactionStatus {
+    #[serde(rename = "ACCEPTED_ON_L2")]
+    AcceptedOnL2 { block_hash: BlockHash, block_number: BlockNumber },
+    #[serde(rename = "ACCEPTED_ON_L1")]
+    AcceptedOnL1 { block_hash: BlockHash, block_number: BlockNumber },
+    /// The block of the transaction was removed from the chain by a reorg.
+    #[serde(rename = "REORGED")]
+    Reorged,
+}
@@ -0,0 +1,128 @@
+use std::sync::Arc;
+
+use jsonrpsee::core::server::Subscription;
+use papyrus_common::sync_notifications::{SyncNotification, SyncNotifier};
+use papyrus_storage::base_layer::BaseLayerStorageWriter;
+use papyrus_storage::body::BodyStorageWriter;
+use papyrus_storage::header::HeaderStorageWriter;
+use papyrus_storage::test_utils::get_test_storage;
+use papyrus_storage::StorageWriter;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockBody, BlockHash, BlockHeader, BlockNumber};
+use test_utils::get_test_body;
+
+use super::api_impl::SubscriptionsJsonRpcServerImpl;
+use super::{
+    SubscribedTransactionStatus,
+    SubscriptionsJsonRpcServer,
+    TransactionStatusNotification,
+    SUBSCRIPTION_METHODS,
+};
+
+const METHOD_NAME: &str = "starknet_subscribeTransactionStatus";
+
+#[test]
+fn subscription_methods_are_listed() {
+    let ((storage_reader, _), _temp_dir) = get_test_storage();
+    let module =
+        SubscriptionsJsonRpcServerImpl { storage_reader, sync_notifier: Arc::default() }.into_rpc();
+    let mut method_names = module.method_names().collect::<Vec<_>>();
+    method_names.sort();
+    assert_eq!(method_names, SUBSCRIPTION_METHODS);
+}
+
+// Stores block 0 with the given body and notifies about it.
+fn store_block(storage_writer: &mut StorageWriter, sync_notifier: &SyncNotifier, body: BlockBody) {
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &BlockHeader::default())
+        .unwrap()
+        .append_body(BlockNumber(0), body)
+        .unwrap()
+        .commit()
+        .unwrap();
+    sync_notifier.notify(SyncNotification::BlockStored {
+        block_number: BlockNumber(0),
+        block_hash: BlockHash::default(),
+    });
+}
+
+async fn next_status(subscription: &mut Subscription) -> SubscribedTransactionStatus {
+    let (notification, _) =
+        subscription.next::<TransactionStatusNotification>().await.unwrap().unwrap();
+    notification.status
+}
+
+#[tokio::test]
+async fn transaction_status_until_accepted_on_l1() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let sync_notifier = Arc::new(SyncNotifier::default());
+    let module =
+        SubscriptionsJsonRpcServerImpl { storage_reader, sync_notifier: sync_notifier.clone() }
+            .into_rpc();
+
+    // The transaction is included in the next block.
+    let body = get_test_body(1, None, None, None);
+    let transaction_hash = body.transaction_hashes[0];
+    let mut subscription =
+        module.subscribe_unbounded(METHOD_NAME, [transaction_hash]).await.unwrap();
+
+    store_block(&mut storage_writer, &sync_notifier, body);
+    assert_eq!(
+        next_status(&mut subscription).await,
+        SubscribedTransactionStatus::AcceptedOnL2 {
+            block_hash: BlockHash::default(),
+            block_number: BlockNumber(0),
+        }
+    );
+
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .update_base_layer_block_marker(&BlockNumber(1))
+        .unwrap()
+        .commit()
+        .unwrap();
+    sync_notifier
+        .notify(SyncNotification::BlockAcceptedOnBaseLayer { block_number: BlockNumber(0) });
+    assert_eq!(
+        next_status(&mut subscription).await,
+        SubscribedTransactionStatus::AcceptedOnL1 {
+            block_hash: BlockHash::default(),
+            block_number: BlockNumber(0),
+        }
+    );
+
+    // The subscription ends once the transaction is accepted on L1.
+    assert!(subscription.next::<TransactionStatusNotification>().await.is_none());
+}
+
+#[tokio::test]
+async fn transaction_removed_by_reorg() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let sync_notifier = Arc::new(SyncNotifier::default());
+    let body = get_test_body(1, None, None, None);
+    let transaction_hash = body.transaction_hashes[0];
+    store_block(&mut storage_writer, &sync_notifier, body);
+    let module =
+        SubscriptionsJsonRpcServerImpl { storage_reader, sync_notifier: sync_notifier.clone() }
+            .into_rpc();
+
+    // The transaction is already in the chain, so its status is notified immediately.
+    let mut subscription =
+        module.subscribe_unbounded(METHOD_NAME, [transaction_hash]).await.unwrap();
+    assert_eq!(
+        next_status(&mut subscription).await,
+        SubscribedTransactionStatus::AcceptedOnL2 {
+            block_hash: BlockHash::default(),
+            block_number: BlockNumber(0),
+        }
+    );
+
+    let (txn, _) = storage_writer.begin_rw_txn().unwrap().revert_body(BlockNumber(0)).unwrap();
+    let (txn, _, _) = txn.revert_header(BlockNumber(0)).unwrap();
+    txn.commit().unwrap();
+    sync_notifier.notify(SyncNotification::BlockReverted { block_number: BlockNumber(0) });
+    assert_eq!(next_status(&mut subscription).await, SubscribedTransactionStatus::Reorged);
+}
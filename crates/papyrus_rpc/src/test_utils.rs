@@ -21,7 +21,9 @@ use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 use tokio::sync::RwLock;
 
-use crate::api::JsonRpcServerImpl;
+use crate::api::{ContinuationTokenKey, JsonRpcServerImpl};
+use crate::v0_7::fee_estimate_cache::FeeEstimateCache;
+use crate::v0_7::submitted_transactions::SubmittedTransactions;
 use crate::version_config::{VersionId, VERSION_PATTERN};
 use crate::RpcConfig;
 
@@ -36,10 +38,16 @@ pub fn get_test_rpc_config() -> RpcConfig {
         max_events_chunk_size: 10,
         max_events_keys: 10,
         collect_metrics: false,
+        continuation_token_secret: Some("test_continuation_token_secret".to_owned()),
         ..Default::default()
     }
 }
 
+/// The key with which the test servers sign their continuation tokens.
+pub(crate) fn get_test_continuation_token_key() -> ContinuationTokenKey {
+    ContinuationTokenKey::new(get_test_rpc_config().continuation_token_secret.as_deref())
+}
+
 pub(crate) fn get_test_highest_block() -> Arc<RwLock<Option<BlockHashAndNumber>>> {
     Arc::new(RwLock::new(None))
 }
@@ -52,8 +60,8 @@ pub(crate) fn get_test_pending_classes() -> Arc<RwLock<PendingClasses>> {
     Arc::new(RwLock::new(PendingClasses::default()))
 }
 
-pub(crate) fn get_test_rpc_server_and_storage_writer<T: JsonRpcServerImpl>()
--> (RpcModule<T>, StorageWriter) {
+pub(crate) fn get_test_rpc_server_and_storage_writer<T: JsonRpcServerImpl>(
+) -> (RpcModule<T>, StorageWriter) {
     get_test_rpc_server_and_storage_writer_from_params(None, None, None, None, None)
 }
 
@@ -80,10 +88,20 @@ pub(crate) fn get_test_rpc_server_and_storage_writer_from_params<T: JsonRpcServe
             storage_reader,
             config.max_events_chunk_size,
             config.max_events_keys,
+            Arc::new(FeeEstimateCache::new(
+                config.fee_estimate_cache_ttl,
+                config.fee_estimate_cache_size,
+            )),
+            Arc::new(SubmittedTransactions::new(config.submitted_transactions_cache_size)),
             BlockHashAndNumber::default(),
             shared_highest_block,
             pending_data,
             pending_classes,
+            Arc::default(),
+            Arc::default(),
+            Arc::default(),
+            Arc::default(),
+            ContinuationTokenKey::new(config.continuation_token_secret.as_deref()),
             mock_client_arc,
         )
         .into_rpc_module(),
@@ -450,6 +468,28 @@ pub fn get_method_names_from_spec(version_id: &VersionId) -> Vec<String> {
         .collect::<Vec<_>>()
 }
 
+/// Returns the methods of the spec file, each with the names of its params.
+pub fn get_methods_with_param_names_from_spec(
+    version_id: &VersionId,
+    spec_file: SpecFile,
+) -> Vec<(String, Vec<String>)> {
+    let spec: serde_json::Value = read_spec(format!("./resources/{}/{spec_file}", version_id.name));
+    spec["methods"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|method_object| {
+            let param_names = method_object["params"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|param_object| param_object["name"].as_str().unwrap().to_owned())
+                .collect();
+            (method_object["name"].as_str().unwrap().to_owned(), param_names)
+        })
+        .collect()
+}
+
 // We implement this trait because `Serialize` and `Clone` are not object safe. For more info see
 // https://doc.rust-lang.org/reference/items/traits.html#object-safety
 pub trait SerializeJsonValue: Send {
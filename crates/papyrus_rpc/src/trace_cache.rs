@@ -0,0 +1,213 @@
+//! An optional disk cache of the traces of recently traced blocks.
+//!
+//! Tracing a block executes all of its transactions again, and explorers tend to trace the same
+//! recent blocks repeatedly, so the traces of the most recently traced blocks are kept in files and
+//! served from there. The traces are keyed by the hash of their block, so a block that replaced a
+//! reverted block is never answered with the traces of the reverted one, and the files of reverted
+//! blocks are removed once the sync reports the revert. The cache doesn't survive restarts: its
+//! files are removed when the node starts.
+#[cfg(test)]
+#[path = "trace_cache_test.rs"]
+mod trace_cache_test;
+
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use lru::LruCache;
+use papyrus_common::metrics::{PAPYRUS_CACHE_HITS, PAPYRUS_CACHE_MISSES};
+use papyrus_common::sync_notifications::SyncNotification;
+use papyrus_execution::objects::TransactionTrace;
+use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::transaction::TransactionHash;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, warn};
+
+// The name of the cache in its metrics.
+const CACHE_NAME: &str = "block_traces";
+
+// The extension of the files of the cache. Only these files are removed from the directory of the
+// cache when the node starts.
+const FILE_EXTENSION: &str = "traces.json";
+
+/// The traces of the transactions of a block, in the order of the transactions.
+pub type BlockTraces = Vec<(TransactionHash, TransactionTrace)>;
+
+/// The traces of the most recently traced blocks, each stored in a file.
+#[derive(Debug, Default)]
+pub struct TraceCache {
+    // None if the cache is disabled.
+    dir: Option<PathBuf>,
+    // The cached blocks and their numbers, ordered by their last use.
+    blocks: Mutex<Option<LruCache<BlockHash, BlockNumber>>>,
+}
+
+impl TraceCache {
+    /// Creates a cache that keeps the traces of up to `max_blocks` blocks in the given directory,
+    /// and removes the traces that were left there. A zero `max_blocks` disables the cache.
+    pub fn new(dir: PathBuf, max_blocks: usize) -> std::io::Result<Self> {
+        let Some(max_blocks) = NonZeroUsize::new(max_blocks) else {
+            return Ok(Self::default());
+        };
+        std::fs::create_dir_all(&dir)?;
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let is_traces_file = path
+                .file_name()
+                .is_some_and(|file_name| file_name.to_string_lossy().ends_with(FILE_EXTENSION));
+            if is_traces_file {
+                std::fs::remove_file(path)?;
+            }
+        }
+        Ok(Self { dir: Some(dir), blocks: Mutex::new(Some(LruCache::new(max_blocks))) })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.dir.is_some()
+    }
+
+    /// Returns the cached traces of the block with the given hash.
+    pub async fn get(&self, block_hash: BlockHash) -> Option<BlockTraces> {
+        let dir = self.dir.as_ref()?;
+        let is_cached = self.with_blocks(|blocks| blocks.get(&block_hash).is_some());
+        let traces = if is_cached { self.read(dir, block_hash).await } else { None };
+        match traces {
+            Some(_) => metrics::increment_counter!(PAPYRUS_CACHE_HITS, "cache" => CACHE_NAME),
+            None => metrics::increment_counter!(PAPYRUS_CACHE_MISSES, "cache" => CACHE_NAME),
+        }
+        traces
+    }
+
+    /// Caches the traces of the given block. When the cache is full, the traces of the least
+    /// recently used block are removed.
+    pub async fn insert(
+        &self,
+        block_number: BlockNumber,
+        block_hash: BlockHash,
+        traces: &BlockTraces,
+    ) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+        let bytes = serde_json::to_vec(traces).expect("Traces should be serializable.");
+        // The traces are written to a temporary file that is renamed, so that a concurrent read
+        // never sees a partially written file.
+        let path = file_path(dir, block_hash);
+        let temp_path = path.with_extension("json.tmp");
+        let written = async {
+            tokio::fs::write(&temp_path, bytes).await?;
+            tokio::fs::rename(&temp_path, &path).await
+        }
+        .await;
+        if let Err(err) = written {
+            warn!("Failed caching the traces of block {block_number}: {err}.");
+            return;
+        }
+        let evicted = self.with_blocks(|blocks| blocks.push(block_hash, block_number));
+        if let Some((evicted_hash, _)) =
+            evicted.filter(|(evicted_hash, _)| *evicted_hash != block_hash)
+        {
+            remove_file(dir, evicted_hash).await;
+        }
+    }
+
+    /// Removes the traces of the blocks with the given number, after the block was reverted.
+    pub async fn invalidate_block(&self, block_number: BlockNumber) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+        let reverted_hashes = self.with_blocks(|blocks| {
+            let reverted_hashes = blocks
+                .iter()
+                .filter(|(_, cached_block_number)| **cached_block_number == block_number)
+                .map(|(block_hash, _)| *block_hash)
+                .collect::<Vec<_>>();
+            for block_hash in &reverted_hashes {
+                blocks.pop(block_hash);
+            }
+            reverted_hashes
+        });
+        for block_hash in reverted_hashes {
+            debug!("Removing the cached traces of the reverted block {block_number}.");
+            remove_file(dir, block_hash).await;
+        }
+    }
+
+    /// Removes the traces of all the blocks.
+    pub async fn clear(&self) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+        let block_hashes = self.with_blocks(|blocks| {
+            let mut block_hashes = vec![];
+            while let Some((block_hash, _)) = blocks.pop_lru() {
+                block_hashes.push(block_hash);
+            }
+            block_hashes
+        });
+        for block_hash in block_hashes {
+            remove_file(dir, block_hash).await;
+        }
+    }
+
+    async fn read(&self, dir: &Path, block_hash: BlockHash) -> Option<BlockTraces> {
+        let traces = tokio::fs::read(file_path(dir, block_hash))
+            .await
+            .map_err(|err| err.to_string())
+            .and_then(|bytes| serde_json::from_slice(&bytes).map_err(|err| err.to_string()));
+        match traces {
+            Ok(traces) => Some(traces),
+            // The file may have been removed meanwhile by an invalidation.
+            Err(err) => {
+                debug!("Failed reading the cached traces of block {block_hash}: {err}.");
+                self.with_blocks(|blocks| blocks.pop(&block_hash));
+                None
+            }
+        }
+    }
+
+    fn with_blocks<T: Default>(
+        &self,
+        f: impl FnOnce(&mut LruCache<BlockHash, BlockNumber>) -> T,
+    ) -> T {
+        self.blocks
+            .lock()
+            .expect("Trace cache lock should not be poisoned.")
+            .as_mut()
+            .map(f)
+            .unwrap_or_default()
+    }
+}
+
+/// Removes the traces of the blocks that the sync reverts, until the sync stops.
+pub async fn invalidate_reverted_blocks(
+    trace_cache: Arc<TraceCache>,
+    mut notifications: broadcast::Receiver<SyncNotification>,
+) {
+    loop {
+        match notifications.recv().await {
+            Ok(SyncNotification::BlockReverted { block_number }) => {
+                trace_cache.invalidate_block(block_number).await;
+            }
+            Ok(_) => {}
+            // The missed notifications may include reverts, so nothing that's cached can be
+            // trusted.
+            Err(RecvError::Lagged(missed)) => {
+                warn!("Missed {missed} sync notifications. Clearing the trace cache.");
+                trace_cache.clear().await;
+            }
+            Err(RecvError::Closed) => return,
+        }
+    }
+}
+
+fn file_path(dir: &Path, block_hash: BlockHash) -> PathBuf {
+    dir.join(format!("{}.{FILE_EXTENSION}", block_hash.0))
+}
+
+async fn remove_file(dir: &Path, block_hash: BlockHash) {
+    if let Err(err) = tokio::fs::remove_file(file_path(dir, block_hash)).await {
+        warn!("Failed removing the cached traces of block {block_hash}: {err}.");
+    }
+}
@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use papyrus_common::sync_notifications::{SyncNotification, SyncNotifier};
+use papyrus_execution::objects::{
+    FunctionInvocationResult,
+    InvokeTransactionTrace,
+    RevertReason,
+    TransactionTrace,
+};
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::hash::StarkFelt;
+use starknet_api::transaction::TransactionHash;
+
+use super::{invalidate_reverted_blocks, BlockTraces, TraceCache};
+
+fn block_traces(block_number: u64) -> BlockTraces {
+    vec![(
+        TransactionHash(StarkFelt::from(block_number)),
+        TransactionTrace::Invoke(InvokeTransactionTrace {
+            validate_invocation: None,
+            execute_invocation: FunctionInvocationResult::Err(RevertReason::RevertReason(format!(
+                "Reverted in block {block_number}."
+            ))),
+            fee_transfer_invocation: None,
+        }),
+    )]
+}
+
+fn block_hash(block_number: u64) -> BlockHash {
+    BlockHash(StarkFelt::from(block_number + 1000))
+}
+
+fn cached_files(dir: &std::path::Path) -> usize {
+    std::fs::read_dir(dir).unwrap().count()
+}
+
+#[tokio::test]
+async fn evicts_least_recently_used_block() {
+    let dir = tempfile::tempdir().unwrap();
+    let trace_cache = TraceCache::new(dir.path().to_path_buf(), 2).unwrap();
+    for block_number in 0..2 {
+        trace_cache
+            .insert(
+                BlockNumber(block_number),
+                block_hash(block_number),
+                &block_traces(block_number),
+            )
+            .await;
+    }
+    // Using block 0 makes block 1 the least recently used.
+    assert_eq!(trace_cache.get(block_hash(0)).await, Some(block_traces(0)));
+    trace_cache.insert(BlockNumber(2), block_hash(2), &block_traces(2)).await;
+
+    assert_eq!(trace_cache.get(block_hash(0)).await, Some(block_traces(0)));
+    assert_eq!(trace_cache.get(block_hash(1)).await, None);
+    assert_eq!(trace_cache.get(block_hash(2)).await, Some(block_traces(2)));
+    assert_eq!(cached_files(dir.path()), 2);
+}
+
+#[tokio::test]
+async fn reverted_block_is_invalidated() {
+    let dir = tempfile::tempdir().unwrap();
+    let trace_cache = Arc::new(TraceCache::new(dir.path().to_path_buf(), 10).unwrap());
+    trace_cache.insert(BlockNumber(0), block_hash(0), &block_traces(0)).await;
+    trace_cache.insert(BlockNumber(1), block_hash(1), &block_traces(1)).await;
+
+    let sync_notifier = SyncNotifier::default();
+    let invalidation =
+        tokio::spawn(invalidate_reverted_blocks(trace_cache.clone(), sync_notifier.subscribe()));
+    sync_notifier.notify(SyncNotification::BlockReverted { block_number: BlockNumber(1) });
+    // Dropping the notifier closes the channel, which stops the invalidation once it handled the
+    // revert.
+    drop(sync_notifier);
+    invalidation.await.unwrap();
+
+    assert_eq!(trace_cache.get(block_hash(0)).await, Some(block_traces(0)));
+    assert_eq!(trace_cache.get(block_hash(1)).await, None);
+    assert_eq!(cached_files(dir.path()), 1);
+}
+
+#[tokio::test]
+async fn files_are_removed_on_startup() {
+    let dir = tempfile::tempdir().unwrap();
+    let unrelated_file = dir.path().join("unrelated.txt");
+    std::fs::write(&unrelated_file, "unrelated").unwrap();
+    // Only the files that end with the extension of the traces are removed.
+    let backup_file = dir.path().join("backup.traces.json.bak");
+    std::fs::write(&backup_file, "backup").unwrap();
+    let trace_cache = TraceCache::new(dir.path().to_path_buf(), 10).unwrap();
+    trace_cache.insert(BlockNumber(0), block_hash(0), &block_traces(0)).await;
+    drop(trace_cache);
+
+    let trace_cache = TraceCache::new(dir.path().to_path_buf(), 10).unwrap();
+    assert_eq!(trace_cache.get(block_hash(0)).await, None);
+    assert!(unrelated_file.exists());
+    assert!(backup_file.exists());
+    assert_eq!(cached_files(dir.path()), 2);
+}
+
+#[tokio::test]
+async fn disabled_cache() {
+    let dir = tempfile::tempdir().unwrap();
+    let trace_cache = TraceCache::new(dir.path().join("traces"), 0).unwrap();
+    assert!(!trace_cache.is_enabled());
+    trace_cache.insert(BlockNumber(0), block_hash(0), &block_traces(0)).await;
+    assert_eq!(trace_cache.get(block_hash(0)).await, None);
+    assert!(!dir.path().join("traces").exists());
+}
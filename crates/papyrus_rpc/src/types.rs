@@ -0,0 +1,160 @@
+//! The objects that the JSON-RPC server returns, by the version of the API.
+//!
+//! Every version of the API has its own representation of blocks, transactions, receipts, events,
+//! state updates and traces. The modules here expose the representation of each version, so that
+//! other tools can deserialize the responses of Papyrus into typed objects instead of raw JSON.
+//! Every object serializes exactly as Papyrus returns it, and deserializes from any response that
+//! conforms to the specification of its version.
+#[cfg(test)]
+#[path = "types_test.rs"]
+mod types_test;
+
+/// The objects of version 0.4 of the API.
+pub mod v0_4 {
+    pub use crate::v0_4::api::{
+        EventsChunk,
+        FeeEstimate,
+        SimulatedTransaction,
+        TransactionTraceWithHash,
+    };
+    pub use crate::v0_4::block::{Block, BlockHeader, GeneralBlockHeader, PendingBlockHeader};
+    pub use crate::v0_4::execution::TransactionTrace;
+    pub use crate::v0_4::state::{
+        AcceptedStateUpdate,
+        PendingStateUpdate,
+        StateUpdate,
+        ThinStateDiff,
+    };
+    pub use crate::v0_4::transaction::{
+        DeclareTransaction,
+        DeployAccountTransaction,
+        Event,
+        GeneralTransactionReceipt,
+        InvokeTransaction,
+        PendingTransactionReceipt,
+        Transaction,
+        TransactionOutput,
+        TransactionReceipt,
+        TransactionWithHash,
+        Transactions,
+    };
+}
+
+/// The objects of version 0.5 of the API.
+pub mod v0_5 {
+    pub use papyrus_execution::objects::TransactionTrace;
+
+    pub use crate::v0_5::api::{
+        EventsChunk,
+        FeeEstimate,
+        SimulatedTransaction,
+        TransactionTraceWithHash,
+    };
+    pub use crate::v0_5::block::{
+        Block,
+        BlockHeader,
+        GeneralBlockHeader,
+        PendingBlockHeader,
+        ResourcePrice,
+    };
+    pub use crate::v0_5::state::{
+        AcceptedStateUpdate,
+        PendingStateUpdate,
+        StateUpdate,
+        ThinStateDiff,
+    };
+    pub use crate::v0_5::transaction::{
+        DeclareTransaction,
+        DeployAccountTransaction,
+        Event,
+        ExecutionResources,
+        GeneralTransactionReceipt,
+        InvokeTransaction,
+        PendingTransactionReceipt,
+        Transaction,
+        TransactionOutput,
+        TransactionReceipt,
+        TransactionWithHash,
+        Transactions,
+    };
+}
+
+/// The objects of version 0.6 of the API.
+pub mod v0_6 {
+    pub use papyrus_execution::objects::TransactionTrace;
+
+    pub use crate::v0_6::api::{
+        EventsChunk,
+        FeeEstimate,
+        SimulatedTransaction,
+        TransactionTraceWithHash,
+    };
+    pub use crate::v0_6::block::{
+        Block,
+        BlockHeader,
+        GeneralBlockHeader,
+        PendingBlockHeader,
+        ResourcePrice,
+    };
+    pub use crate::v0_6::state::{
+        AcceptedStateUpdate,
+        PendingStateUpdate,
+        StateUpdate,
+        ThinStateDiff,
+    };
+    pub use crate::v0_6::transaction::{
+        DeclareTransaction,
+        DeployAccountTransaction,
+        Event,
+        ExecutionResources,
+        FeePayment,
+        GeneralTransactionReceipt,
+        InvokeTransaction,
+        PendingTransactionReceipt,
+        Transaction,
+        TransactionOutput,
+        TransactionReceipt,
+        TransactionWithHash,
+        Transactions,
+    };
+}
+
+/// The objects of version 0.7 of the API.
+pub mod v0_7 {
+    pub use papyrus_execution::objects::TransactionTrace;
+
+    pub use crate::v0_7::api::{
+        EventsChunk,
+        FeeEstimate,
+        SimulatedTransaction,
+        TransactionTraceWithHash,
+    };
+    pub use crate::v0_7::block::{
+        Block,
+        BlockHeader,
+        GeneralBlockHeader,
+        PendingBlockHeader,
+        ResourcePrice,
+    };
+    pub use crate::v0_7::state::{
+        AcceptedStateUpdate,
+        PendingStateUpdate,
+        StateUpdate,
+        ThinStateDiff,
+    };
+    pub use crate::v0_7::transaction::{
+        DeclareTransaction,
+        DeployAccountTransaction,
+        Event,
+        ExecutionResources,
+        FeePayment,
+        GeneralTransactionReceipt,
+        InvokeTransaction,
+        PendingTransactionReceipt,
+        Transaction,
+        TransactionOutput,
+        TransactionReceipt,
+        TransactionWithHash,
+        Transactions,
+    };
+}
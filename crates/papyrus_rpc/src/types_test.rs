@@ -0,0 +1,206 @@
+use std::fmt::Debug;
+
+use pretty_assertions::assert_eq;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use test_utils::read_json_file;
+
+use super::{v0_4, v0_5, v0_6, v0_7};
+use crate::test_utils::{
+    get_starknet_spec_api_schema_for_method_results,
+    validate_schema,
+    SpecFile,
+};
+use crate::version_config::{VersionId, VERSION_0_4, VERSION_0_5, VERSION_0_6, VERSION_0_7};
+
+// Checks that a response fixture conforms to the specification of the given version, deserializes
+// into the typed object, and that the object serializes back into a response that conforms to the
+// specification and deserializes into the same object.
+fn assert_fixture_round_trip<T: Serialize + DeserializeOwned + Debug + PartialEq>(
+    version_id: &VersionId,
+    fixture: &str,
+    spec_file: SpecFile,
+    method: &str,
+) {
+    let schema =
+        get_starknet_spec_api_schema_for_method_results(&[(spec_file, &[method])], version_id);
+    let response = read_json_file(&format!("fixtures/{}/{fixture}", version_id.name));
+    assert!(
+        validate_schema(&schema, &response),
+        "The fixture {fixture} doesn't fit the spec of {}.",
+        version_id.name
+    );
+
+    let object = serde_json::from_value::<T>(response).unwrap();
+    let serialized = serde_json::to_value(&object).unwrap();
+    assert!(validate_schema(&schema, &serialized), "{object:?} doesn't serialize by the spec.");
+    assert_eq!(serde_json::from_value::<T>(serialized).unwrap(), object);
+}
+
+#[test]
+fn block_with_txs_round_trip() {
+    assert_fixture_round_trip::<v0_4::Block>(
+        &VERSION_0_4,
+        "block_with_txs.json",
+        SpecFile::StarknetApiOpenrpc,
+        "starknet_getBlockWithTxs",
+    );
+    assert_fixture_round_trip::<v0_5::Block>(
+        &VERSION_0_5,
+        "block_with_txs.json",
+        SpecFile::StarknetApiOpenrpc,
+        "starknet_getBlockWithTxs",
+    );
+    assert_fixture_round_trip::<v0_6::Block>(
+        &VERSION_0_6,
+        "block_with_txs.json",
+        SpecFile::StarknetApiOpenrpc,
+        "starknet_getBlockWithTxs",
+    );
+    assert_fixture_round_trip::<v0_7::Block>(
+        &VERSION_0_7,
+        "block_with_txs.json",
+        SpecFile::StarknetApiOpenrpc,
+        "starknet_getBlockWithTxs",
+    );
+}
+
+#[test]
+fn transaction_receipt_round_trip() {
+    assert_fixture_round_trip::<v0_4::GeneralTransactionReceipt>(
+        &VERSION_0_4,
+        "transaction_receipt.json",
+        SpecFile::StarknetApiOpenrpc,
+        "starknet_getTransactionReceipt",
+    );
+    assert_fixture_round_trip::<v0_5::GeneralTransactionReceipt>(
+        &VERSION_0_5,
+        "transaction_receipt.json",
+        SpecFile::StarknetApiOpenrpc,
+        "starknet_getTransactionReceipt",
+    );
+    assert_fixture_round_trip::<v0_6::GeneralTransactionReceipt>(
+        &VERSION_0_6,
+        "transaction_receipt.json",
+        SpecFile::StarknetApiOpenrpc,
+        "starknet_getTransactionReceipt",
+    );
+    assert_fixture_round_trip::<v0_7::GeneralTransactionReceipt>(
+        &VERSION_0_7,
+        "transaction_receipt.json",
+        SpecFile::StarknetApiOpenrpc,
+        "starknet_getTransactionReceipt",
+    );
+}
+
+#[test]
+fn events_chunk_round_trip() {
+    assert_fixture_round_trip::<v0_4::EventsChunk>(
+        &VERSION_0_4,
+        "events_chunk.json",
+        SpecFile::StarknetApiOpenrpc,
+        "starknet_getEvents",
+    );
+    assert_fixture_round_trip::<v0_5::EventsChunk>(
+        &VERSION_0_5,
+        "events_chunk.json",
+        SpecFile::StarknetApiOpenrpc,
+        "starknet_getEvents",
+    );
+    assert_fixture_round_trip::<v0_6::EventsChunk>(
+        &VERSION_0_6,
+        "events_chunk.json",
+        SpecFile::StarknetApiOpenrpc,
+        "starknet_getEvents",
+    );
+    assert_fixture_round_trip::<v0_7::EventsChunk>(
+        &VERSION_0_7,
+        "events_chunk.json",
+        SpecFile::StarknetApiOpenrpc,
+        "starknet_getEvents",
+    );
+}
+
+#[test]
+fn state_update_round_trip() {
+    assert_fixture_round_trip::<v0_4::StateUpdate>(
+        &VERSION_0_4,
+        "state_update.json",
+        SpecFile::StarknetApiOpenrpc,
+        "starknet_getStateUpdate",
+    );
+    assert_fixture_round_trip::<v0_5::StateUpdate>(
+        &VERSION_0_5,
+        "state_update.json",
+        SpecFile::StarknetApiOpenrpc,
+        "starknet_getStateUpdate",
+    );
+    assert_fixture_round_trip::<v0_6::StateUpdate>(
+        &VERSION_0_6,
+        "state_update.json",
+        SpecFile::StarknetApiOpenrpc,
+        "starknet_getStateUpdate",
+    );
+    assert_fixture_round_trip::<v0_7::StateUpdate>(
+        &VERSION_0_7,
+        "state_update.json",
+        SpecFile::StarknetApiOpenrpc,
+        "starknet_getStateUpdate",
+    );
+}
+
+#[test]
+fn fee_estimates_round_trip() {
+    assert_fixture_round_trip::<Vec<v0_4::FeeEstimate>>(
+        &VERSION_0_4,
+        "fee_estimates.json",
+        SpecFile::StarknetApiOpenrpc,
+        "starknet_estimateFee",
+    );
+    assert_fixture_round_trip::<Vec<v0_5::FeeEstimate>>(
+        &VERSION_0_5,
+        "fee_estimates.json",
+        SpecFile::StarknetApiOpenrpc,
+        "starknet_estimateFee",
+    );
+    assert_fixture_round_trip::<Vec<v0_6::FeeEstimate>>(
+        &VERSION_0_6,
+        "fee_estimates.json",
+        SpecFile::StarknetApiOpenrpc,
+        "starknet_estimateFee",
+    );
+    assert_fixture_round_trip::<Vec<v0_7::FeeEstimate>>(
+        &VERSION_0_7,
+        "fee_estimates.json",
+        SpecFile::StarknetApiOpenrpc,
+        "starknet_estimateFee",
+    );
+}
+
+#[test]
+fn transaction_trace_round_trip() {
+    assert_fixture_round_trip::<v0_4::TransactionTrace>(
+        &VERSION_0_4,
+        "transaction_trace.json",
+        SpecFile::TraceApi,
+        "starknet_traceTransaction",
+    );
+    assert_fixture_round_trip::<v0_5::TransactionTrace>(
+        &VERSION_0_5,
+        "transaction_trace.json",
+        SpecFile::TraceApi,
+        "starknet_traceTransaction",
+    );
+    assert_fixture_round_trip::<v0_6::TransactionTrace>(
+        &VERSION_0_6,
+        "transaction_trace.json",
+        SpecFile::TraceApi,
+        "starknet_traceTransaction",
+    );
+    assert_fixture_round_trip::<v0_7::TransactionTrace>(
+        &VERSION_0_7,
+        "transaction_trace.json",
+        SpecFile::TraceApi,
+        "starknet_traceTransaction",
+    );
+}
@@ -0,0 +1,100 @@
+#[cfg(test)]
+#[path = "unix_socket_test.rs"]
+mod unix_socket_test;
+
+use std::error::Error as StdError;
+use std::fs::Permissions;
+use std::io;
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+use hyper::server::conn::Http;
+use hyper::service::Service;
+use hyper::{Body, Request, Response};
+use jsonrpsee::server::StopHandle;
+use tokio::net::UnixListener;
+use tracing::{debug, warn, Instrument};
+use validator::ValidationError;
+
+/// Validates that the permissions of the Unix domain socket are given as octal digits, like the
+/// mode of chmod.
+pub(crate) fn validate_unix_socket_permissions(permissions: &str) -> Result<(), ValidationError> {
+    if parse_permissions(permissions).is_none() {
+        let mut error = ValidationError::new("invalid unix socket permissions");
+        error.message =
+            Some(format!("{permissions} aren't octal permissions, such as 660.").into());
+        return Err(error);
+    }
+    Ok(())
+}
+
+fn parse_permissions(permissions: &str) -> Option<u32> {
+    u32::from_str_radix(permissions, 8).ok().filter(|mode| *mode <= 0o777)
+}
+
+/// Binds a Unix domain socket at the given path and sets its permissions, so that only the users
+/// that the permissions allow can connect to the server. A socket left by a previous run is
+/// replaced.
+pub(crate) fn bind_unix_socket(path: &Path, permissions: &str) -> io::Result<UnixListener> {
+    let mode = parse_permissions(permissions).ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid permissions {permissions}."))
+    })?;
+    // Only a socket is replaced, so that a misconfigured path doesn't delete a regular file.
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path)?,
+        Ok(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} exists and isn't a Unix domain socket.", path.display()),
+            ));
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err),
+    }
+    let listener = UnixListener::bind(path)?;
+    std::fs::set_permissions(path, Permissions::from_mode(mode))?;
+    Ok(listener)
+}
+
+/// Serves the connections of the Unix domain socket with the JSON-RPC service until the server is
+/// stopped, and then removes the socket file.
+pub(crate) async fn serve_unix_socket<S>(
+    listener: UnixListener,
+    path: PathBuf,
+    service: S,
+    stop_handle: StopHandle,
+) where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send,
+    S::Error: Into<Box<dyn StdError + Send + Sync>>,
+{
+    let stopped = stop_handle.shutdown();
+    tokio::pin!(stopped);
+    loop {
+        let stream = tokio::select! {
+            _ = &mut stopped => break,
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _addr)) => stream,
+                Err(err) => {
+                    warn!("Failed to accept a connection on the Unix domain socket: {err}");
+                    continue;
+                }
+            },
+        };
+        let service = service.clone();
+        tokio::spawn(
+            async move {
+                // Upgrades are allowed so that subscriptions work over WebSocket on the socket too.
+                if let Err(err) =
+                    Http::new().serve_connection(stream, service).with_upgrades().await
+                {
+                    debug!("A connection on the Unix domain socket failed: {err}");
+                }
+            }
+            .in_current_span(),
+        );
+    }
+    if let Err(err) = std::fs::remove_file(&path) {
+        warn!("Failed to remove the Unix domain socket {}: {err}", path.display());
+    }
+}
@@ -0,0 +1,34 @@
+use std::os::unix::fs::PermissionsExt;
+
+use tempfile::tempdir;
+
+use super::{bind_unix_socket, validate_unix_socket_permissions};
+
+#[test]
+fn unix_socket_permissions_validation() {
+    for permissions in ["600", "660", "0777"] {
+        assert!(validate_unix_socket_permissions(permissions).is_ok(), "{permissions}");
+    }
+    for permissions in ["", "rw", "680", "1777"] {
+        assert!(validate_unix_socket_permissions(permissions).is_err(), "{permissions}");
+    }
+}
+
+#[tokio::test]
+async fn bind_sets_permissions_and_replaces_only_sockets() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("papyrus_rpc.sock");
+
+    let listener = bind_unix_socket(&path, "600").unwrap();
+    assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+    drop(listener);
+
+    // The socket left by the previous listener is replaced.
+    let _listener = bind_unix_socket(&path, "660").unwrap();
+    assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o660);
+
+    let regular_file = dir.path().join("regular_file");
+    std::fs::write(&regular_file, "content").unwrap();
+    assert!(bind_unix_socket(&regular_file, "660").is_err());
+    assert_eq!(std::fs::read_to_string(&regular_file).unwrap(), "content");
+}
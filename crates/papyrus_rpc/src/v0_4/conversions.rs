@@ -0,0 +1,481 @@
+//! Conversions between the types of starknet_api and Papyrus and the objects of this version of
+//! the API.
+//!
+//! The objects of each version of the API differ from the internal types by the fields that the
+//! version added or dropped, such as the max fee of a transaction of version 3, which this version
+//! doesn't support. All of these differences are resolved here, so the handlers of the API only
+//! call `into` and `try_into`.
+
+use jsonrpsee::types::ErrorObjectOwned;
+use papyrus_storage::body::events::ThinTransactionOutput;
+use starknet_api::block::BlockStatus;
+use starknet_api::state::ThinStateDiff as starknet_api_ThinStateDiff;
+use starknet_api::transaction::{Fee, Resource};
+use starknet_client::reader::objects::pending_data::PendingBlockOrDeprecated;
+
+use super::block::{BlockHeader, PendingBlockHeader};
+use super::state::{
+    ClassHashes,
+    ContractNonce,
+    DeployedContract,
+    ReplacedClasses,
+    StorageDiff,
+    StorageEntry,
+    ThinStateDiff,
+};
+use super::transaction::{
+    DeclareTransaction,
+    DeclareTransactionOutput,
+    DeclareTransactionV0,
+    DeclareTransactionV1,
+    DeclareTransactionV2,
+    DeployAccountTransaction,
+    DeployAccountTransactionOutput,
+    DeployAccountTransactionV1,
+    DeployTransactionOutput,
+    InvokeTransaction,
+    InvokeTransactionOutput,
+    InvokeTransactionV0,
+    InvokeTransactionV1,
+    L1HandlerTransactionOutput,
+    Transaction,
+    TransactionFinalityStatus,
+    TransactionOutput,
+    TransactionVersion0,
+    TransactionVersion1,
+    TransactionVersion2,
+};
+use crate::internal_server_error;
+
+impl From<starknet_api::block::BlockHeader> for BlockHeader {
+    fn from(header: starknet_api::block::BlockHeader) -> Self {
+        BlockHeader {
+            block_hash: header.block_hash,
+            parent_hash: header.parent_hash,
+            block_number: header.block_number,
+            sequencer_address: header.sequencer,
+            new_root: header.state_root,
+            timestamp: header.timestamp,
+        }
+    }
+}
+
+// This version doesn't have the gas price and the Starknet version in the block header.
+impl From<&PendingBlockOrDeprecated> for PendingBlockHeader {
+    fn from(block: &PendingBlockOrDeprecated) -> Self {
+        PendingBlockHeader {
+            parent_hash: block.parent_block_hash(),
+            sequencer_address: block.sequencer_address(),
+            timestamp: block.timestamp(),
+        }
+    }
+}
+
+impl From<starknet_api_ThinStateDiff> for ThinStateDiff {
+    fn from(diff: starknet_api_ThinStateDiff) -> Self {
+        Self {
+            deployed_contracts: Vec::from_iter(
+                diff.deployed_contracts
+                    .into_iter()
+                    .map(|(address, class_hash)| DeployedContract { address, class_hash }),
+            ),
+            storage_diffs: Vec::from_iter(diff.storage_diffs.into_iter().map(
+                |(address, entries)| {
+                    let storage_entries = Vec::from_iter(
+                        entries.into_iter().map(|(key, value)| StorageEntry { key, value }),
+                    );
+                    StorageDiff { address, storage_entries }
+                },
+            )),
+            declared_classes: diff
+                .declared_classes
+                .into_iter()
+                .map(|(class_hash, compiled_class_hash)| ClassHashes {
+                    class_hash,
+                    compiled_class_hash,
+                })
+                .collect(),
+            deprecated_declared_classes: diff.deprecated_declared_classes,
+            nonces: Vec::from_iter(
+                diff.nonces
+                    .into_iter()
+                    .map(|(contract_address, nonce)| ContractNonce { contract_address, nonce }),
+            ),
+            replaced_classes: Vec::from_iter(diff.replaced_classes.into_iter().map(
+                |(contract_address, class_hash)| ReplacedClasses { contract_address, class_hash },
+            )),
+        }
+    }
+}
+
+impl From<starknet_api::transaction::DeclareTransactionV2> for DeclareTransactionV2 {
+    fn from(tx: starknet_api::transaction::DeclareTransactionV2) -> Self {
+        Self {
+            class_hash: tx.class_hash,
+            compiled_class_hash: tx.compiled_class_hash,
+            sender_address: tx.sender_address,
+            nonce: tx.nonce,
+            max_fee: tx.max_fee,
+            version: TransactionVersion2::default(),
+            signature: tx.signature,
+        }
+    }
+}
+
+impl TryFrom<starknet_api::transaction::DeployAccountTransaction> for DeployAccountTransaction {
+    type Error = ErrorObjectOwned;
+
+    fn try_from(
+        tx: starknet_api::transaction::DeployAccountTransaction,
+    ) -> Result<Self, Self::Error> {
+        match tx {
+            starknet_api::transaction::DeployAccountTransaction::V1(
+                starknet_api::transaction::DeployAccountTransactionV1 {
+                    max_fee,
+                    signature,
+                    nonce,
+                    class_hash,
+                    contract_address_salt,
+                    constructor_calldata,
+                },
+            ) => Ok(Self::Version1(DeployAccountTransactionV1 {
+                max_fee,
+                signature,
+                nonce,
+                class_hash,
+                contract_address_salt,
+                constructor_calldata,
+                version: TransactionVersion1::default(),
+            })),
+            starknet_api::transaction::DeployAccountTransaction::V3(
+                starknet_api::transaction::DeployAccountTransactionV3 {
+                    resource_bounds,
+                    signature,
+                    nonce,
+                    class_hash,
+                    contract_address_salt,
+                    constructor_calldata,
+                    ..
+                },
+            ) => {
+                let l1_gas_bounds = resource_bounds
+                    .0
+                    .get(&Resource::L1Gas)
+                    .ok_or(internal_server_error("Got a v3 transaction with no L1 gas bounds."))?;
+                Ok(Self::Version1(DeployAccountTransactionV1 {
+                    max_fee: Fee(
+                        l1_gas_bounds.max_price_per_unit * u128::from(l1_gas_bounds.max_amount)
+                    ),
+                    signature,
+                    nonce,
+                    class_hash,
+                    contract_address_salt,
+                    constructor_calldata,
+                    version: TransactionVersion1::default(),
+                }))
+            }
+        }
+    }
+}
+
+impl TryFrom<starknet_api::transaction::InvokeTransaction> for InvokeTransaction {
+    type Error = ErrorObjectOwned;
+
+    fn try_from(tx: starknet_api::transaction::InvokeTransaction) -> Result<Self, Self::Error> {
+        match tx {
+            starknet_api::transaction::InvokeTransaction::V0(
+                starknet_api::transaction::InvokeTransactionV0 {
+                    max_fee,
+                    signature,
+                    contract_address,
+                    entry_point_selector,
+                    calldata,
+                },
+            ) => Ok(Self::Version0(InvokeTransactionV0 {
+                max_fee,
+                version: TransactionVersion0::default(),
+                signature,
+                contract_address,
+                entry_point_selector,
+                calldata,
+            })),
+            starknet_api::transaction::InvokeTransaction::V1(
+                starknet_api::transaction::InvokeTransactionV1 {
+                    max_fee,
+                    signature,
+                    nonce,
+                    sender_address,
+                    calldata,
+                },
+            ) => Ok(Self::Version1(InvokeTransactionV1 {
+                max_fee,
+                version: TransactionVersion1::default(),
+                signature,
+                nonce,
+                sender_address,
+                calldata,
+            })),
+            starknet_api::transaction::InvokeTransaction::V3(
+                starknet_api::transaction::InvokeTransactionV3 {
+                    resource_bounds,
+                    signature,
+                    nonce,
+                    sender_address,
+                    calldata,
+                    ..
+                },
+            ) => {
+                let l1_gas_bounds = resource_bounds
+                    .0
+                    .get(&Resource::L1Gas)
+                    .ok_or(internal_server_error("Got a v3 transaction with no L1 gas bounds."))?;
+                Ok(Self::Version1(InvokeTransactionV1 {
+                    max_fee: Fee(
+                        l1_gas_bounds.max_price_per_unit * u128::from(l1_gas_bounds.max_amount)
+                    ),
+                    version: TransactionVersion1::default(),
+                    signature,
+                    nonce,
+                    sender_address,
+                    calldata,
+                }))
+            }
+        }
+    }
+}
+
+impl TryFrom<starknet_api::transaction::Transaction> for Transaction {
+    type Error = ErrorObjectOwned;
+
+    fn try_from(tx: starknet_api::transaction::Transaction) -> Result<Self, Self::Error> {
+        match tx {
+            starknet_api::transaction::Transaction::Declare(declare_tx) => match declare_tx {
+                starknet_api::transaction::DeclareTransaction::V0(tx) => {
+                    Ok(Self::Declare(DeclareTransaction::Version0(DeclareTransactionV0 {
+                        class_hash: tx.class_hash,
+                        sender_address: tx.sender_address,
+                        nonce: tx.nonce,
+                        max_fee: tx.max_fee,
+                        version: TransactionVersion0::default(),
+                        signature: tx.signature,
+                    })))
+                }
+                starknet_api::transaction::DeclareTransaction::V1(tx) => {
+                    Ok(Self::Declare(DeclareTransaction::Version1(DeclareTransactionV1 {
+                        class_hash: tx.class_hash,
+                        sender_address: tx.sender_address,
+                        nonce: tx.nonce,
+                        max_fee: tx.max_fee,
+                        version: TransactionVersion1::default(),
+                        signature: tx.signature,
+                    })))
+                }
+                starknet_api::transaction::DeclareTransaction::V2(tx) => {
+                    Ok(Self::Declare(DeclareTransaction::Version2(tx.into())))
+                }
+                starknet_api::transaction::DeclareTransaction::V3(tx) => {
+                    let l1_gas_bounds = tx.resource_bounds.0.get(&Resource::L1Gas).ok_or(
+                        internal_server_error("Got a v3 transaction with no L1 gas bounds."),
+                    )?;
+                    Ok(Self::Declare(DeclareTransaction::Version2(DeclareTransactionV2 {
+                        class_hash: tx.class_hash,
+                        compiled_class_hash: tx.compiled_class_hash,
+                        sender_address: tx.sender_address,
+                        nonce: tx.nonce,
+                        max_fee: Fee(
+                            l1_gas_bounds.max_price_per_unit * u128::from(l1_gas_bounds.max_amount)
+                        ),
+                        version: TransactionVersion2::default(),
+                        signature: tx.signature,
+                    })))
+                }
+            },
+            starknet_api::transaction::Transaction::Deploy(deploy_tx) => {
+                Ok(Transaction::Deploy(deploy_tx))
+            }
+            starknet_api::transaction::Transaction::DeployAccount(deploy_account_tx) => {
+                Ok(Self::DeployAccount(deploy_account_tx.try_into()?))
+            }
+            starknet_api::transaction::Transaction::Invoke(invoke_tx) => {
+                Ok(Self::Invoke(invoke_tx.try_into()?))
+            }
+            starknet_api::transaction::Transaction::L1Handler(l1_handler_tx) => {
+                Ok(Transaction::L1Handler(l1_handler_tx))
+            }
+        }
+    }
+}
+
+impl From<BlockStatus> for TransactionFinalityStatus {
+    fn from(status: BlockStatus) -> Self {
+        match status {
+            BlockStatus::AcceptedOnL1 => TransactionFinalityStatus::AcceptedOnL1,
+            BlockStatus::AcceptedOnL2 => TransactionFinalityStatus::AcceptedOnL2,
+            BlockStatus::Pending => TransactionFinalityStatus::AcceptedOnL2, /* for backward compatibility pending transactions are considered accepted on L2 */
+            // we convert the block status to transaction status only in the creation of
+            // TransactionReceiptWithStatus before that we verify that the block is not
+            // rejected so this conversion should never happen
+            BlockStatus::Rejected => unreachable!("Rejected blocks are not returned by the API"),
+        }
+    }
+}
+
+impl TransactionOutput {
+    pub fn from_thin_transaction_output(
+        thin_tx_output: ThinTransactionOutput,
+        events: Vec<starknet_api::transaction::Event>,
+    ) -> Self {
+        match thin_tx_output {
+            ThinTransactionOutput::Declare(thin_declare) => {
+                TransactionOutput::Declare(DeclareTransactionOutput {
+                    actual_fee: thin_declare.actual_fee,
+                    messages_sent: thin_declare.messages_sent,
+                    events,
+                    execution_status: thin_declare.execution_status,
+                })
+            }
+            ThinTransactionOutput::Deploy(thin_deploy) => {
+                TransactionOutput::Deploy(DeployTransactionOutput {
+                    actual_fee: thin_deploy.actual_fee,
+                    messages_sent: thin_deploy.messages_sent,
+                    events,
+                    contract_address: thin_deploy.contract_address,
+                    execution_status: thin_deploy.execution_status,
+                })
+            }
+            ThinTransactionOutput::DeployAccount(thin_deploy) => {
+                TransactionOutput::DeployAccount(DeployAccountTransactionOutput {
+                    actual_fee: thin_deploy.actual_fee,
+                    messages_sent: thin_deploy.messages_sent,
+                    events,
+                    contract_address: thin_deploy.contract_address,
+                    execution_status: thin_deploy.execution_status,
+                })
+            }
+            ThinTransactionOutput::Invoke(thin_invoke) => {
+                TransactionOutput::Invoke(InvokeTransactionOutput {
+                    actual_fee: thin_invoke.actual_fee,
+                    messages_sent: thin_invoke.messages_sent,
+                    events,
+                    execution_status: thin_invoke.execution_status,
+                })
+            }
+            ThinTransactionOutput::L1Handler(thin_l1handler) => {
+                TransactionOutput::L1Handler(L1HandlerTransactionOutput {
+                    actual_fee: thin_l1handler.actual_fee,
+                    messages_sent: thin_l1handler.messages_sent,
+                    events,
+                    execution_status: thin_l1handler.execution_status,
+                })
+            }
+        }
+    }
+}
+
+impl From<starknet_api::transaction::TransactionOutput> for TransactionOutput {
+    #[cfg_attr(coverage_nightly, coverage_attribute)]
+    fn from(tx_output: starknet_api::transaction::TransactionOutput) -> Self {
+        match tx_output {
+            starknet_api::transaction::TransactionOutput::Declare(declare_tx_output) => {
+                TransactionOutput::Declare(DeclareTransactionOutput {
+                    actual_fee: declare_tx_output.actual_fee,
+                    messages_sent: declare_tx_output.messages_sent,
+                    events: declare_tx_output.events,
+                    execution_status: declare_tx_output.execution_status,
+                })
+            }
+            starknet_api::transaction::TransactionOutput::Deploy(deploy_tx_output) => {
+                TransactionOutput::Deploy(DeployTransactionOutput {
+                    actual_fee: deploy_tx_output.actual_fee,
+                    messages_sent: deploy_tx_output.messages_sent,
+                    events: deploy_tx_output.events,
+                    contract_address: deploy_tx_output.contract_address,
+                    execution_status: deploy_tx_output.execution_status,
+                })
+            }
+            starknet_api::transaction::TransactionOutput::DeployAccount(deploy_tx_output) => {
+                TransactionOutput::DeployAccount(DeployAccountTransactionOutput {
+                    actual_fee: deploy_tx_output.actual_fee,
+                    messages_sent: deploy_tx_output.messages_sent,
+                    events: deploy_tx_output.events,
+                    contract_address: deploy_tx_output.contract_address,
+                    execution_status: deploy_tx_output.execution_status,
+                })
+            }
+            starknet_api::transaction::TransactionOutput::Invoke(invoke_tx_output) => {
+                TransactionOutput::Invoke(InvokeTransactionOutput {
+                    actual_fee: invoke_tx_output.actual_fee,
+                    messages_sent: invoke_tx_output.messages_sent,
+                    events: invoke_tx_output.events,
+                    execution_status: invoke_tx_output.execution_status,
+                })
+            }
+            starknet_api::transaction::TransactionOutput::L1Handler(l1_handler_tx_output) => {
+                TransactionOutput::L1Handler(L1HandlerTransactionOutput {
+                    actual_fee: l1_handler_tx_output.actual_fee,
+                    messages_sent: l1_handler_tx_output.messages_sent,
+                    events: l1_handler_tx_output.events,
+                    execution_status: l1_handler_tx_output.execution_status,
+                })
+            }
+        }
+    }
+}
+
+impl From<DeployAccountTransaction> for starknet_api::transaction::DeployAccountTransaction {
+    fn from(tx: DeployAccountTransaction) -> Self {
+        match tx {
+            DeployAccountTransaction::Version1(DeployAccountTransactionV1 {
+                max_fee,
+                signature,
+                nonce,
+                class_hash,
+                contract_address_salt,
+                constructor_calldata,
+                version: _,
+            }) => Self::V1(starknet_api::transaction::DeployAccountTransactionV1 {
+                max_fee,
+                signature,
+                nonce,
+                class_hash,
+                contract_address_salt,
+                constructor_calldata,
+            }),
+        }
+    }
+}
+
+impl From<InvokeTransaction> for starknet_api::transaction::InvokeTransaction {
+    fn from(value: InvokeTransaction) -> Self {
+        match value {
+            InvokeTransaction::Version0(InvokeTransactionV0 {
+                max_fee,
+                version: _,
+                signature,
+                contract_address,
+                entry_point_selector,
+                calldata,
+            }) => Self::V0(starknet_api::transaction::InvokeTransactionV0 {
+                max_fee,
+                signature,
+                contract_address,
+                entry_point_selector,
+                calldata,
+            }),
+            InvokeTransaction::Version1(InvokeTransactionV1 {
+                max_fee,
+                version: _,
+                signature,
+                nonce,
+                sender_address,
+                calldata,
+            }) => Self::V1(starknet_api::transaction::InvokeTransactionV1 {
+                max_fee,
+                signature,
+                nonce,
+                sender_address,
+                calldata,
+            }),
+        }
+    }
+}
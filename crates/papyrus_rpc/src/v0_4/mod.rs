@@ -1,6 +1,7 @@
 pub mod api;
 pub mod block;
 pub mod broadcasted_transaction;
+mod conversions;
 pub mod deprecated_contract_class;
 pub mod error;
 pub mod execution;
@@ -5,12 +5,11 @@ mod transaction_test;
 use std::sync::Arc;
 
 use jsonrpsee::types::ErrorObjectOwned;
-use papyrus_storage::body::events::ThinTransactionOutput;
 use papyrus_storage::body::BodyStorageReader;
 use papyrus_storage::db::TransactionKind;
 use papyrus_storage::StorageTxn;
 use serde::{Deserialize, Serialize};
-use starknet_api::block::{BlockHash, BlockNumber, BlockStatus};
+use starknet_api::block::{BlockHash, BlockNumber};
 use starknet_api::core::{
     ClassHash,
     CompiledClassHash,
@@ -27,7 +26,6 @@ use starknet_api::transaction::{
     Fee,
     L1HandlerTransaction,
     MessageToL1,
-    Resource,
     TransactionExecutionStatus,
     TransactionHash,
     TransactionSignature,
@@ -37,6 +35,7 @@ use starknet_client::writer::objects::transaction as client_transaction;
 
 use super::error::BLOCK_NOT_FOUND;
 use crate::internal_server_error;
+use crate::streaming::StreamedSeq;
 
 #[derive(
     Debug, Deserialize, Serialize, Default, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord,
@@ -72,6 +71,27 @@ pub enum Transactions {
     Full(Vec<TransactionWithHash>),
 }
 
+/// Full transactions of a block that are converted to their RPC representation only while they're
+/// serialized.
+pub type StreamedTransactions =
+    StreamedSeq<(starknet_api::transaction::Transaction, TransactionHash), TransactionWithHash>;
+
+pub fn stream_transactions_with_hash(
+    transactions: Vec<starknet_api::transaction::Transaction>,
+    transaction_hashes: Vec<TransactionHash>,
+) -> StreamedTransactions {
+    StreamedSeq::new(
+        transactions.into_iter().zip(transaction_hashes).collect(),
+        to_transaction_with_hash,
+    )
+}
+
+fn to_transaction_with_hash(
+    (transaction, transaction_hash): (starknet_api::transaction::Transaction, TransactionHash),
+) -> Result<TransactionWithHash, ErrorObjectOwned> {
+    Ok(TransactionWithHash { transaction: transaction.try_into()?, transaction_hash })
+}
+
 #[derive(Debug, Clone, Default, Eq, PartialEq, Hash, Deserialize, Serialize, PartialOrd, Ord)]
 #[serde(deny_unknown_fields)]
 pub struct DeclareTransactionV0 {
@@ -103,20 +123,6 @@ pub struct DeclareTransactionV2 {
     pub signature: TransactionSignature,
 }
 
-impl From<starknet_api::transaction::DeclareTransactionV2> for DeclareTransactionV2 {
-    fn from(tx: starknet_api::transaction::DeclareTransactionV2) -> Self {
-        Self {
-            class_hash: tx.class_hash,
-            compiled_class_hash: tx.compiled_class_hash,
-            sender_address: tx.sender_address,
-            nonce: tx.nonce,
-            max_fee: tx.max_fee,
-            version: TransactionVersion2::default(),
-            signature: tx.signature,
-        }
-    }
-}
-
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, PartialOrd, Ord)]
 #[serde(untagged)]
 pub enum DeclareTransaction {
@@ -142,62 +148,6 @@ pub enum DeployAccountTransaction {
     Version1(DeployAccountTransactionV1),
 }
 
-impl TryFrom<starknet_api::transaction::DeployAccountTransaction> for DeployAccountTransaction {
-    type Error = ErrorObjectOwned;
-
-    fn try_from(
-        tx: starknet_api::transaction::DeployAccountTransaction,
-    ) -> Result<Self, Self::Error> {
-        match tx {
-            starknet_api::transaction::DeployAccountTransaction::V1(
-                starknet_api::transaction::DeployAccountTransactionV1 {
-                    max_fee,
-                    signature,
-                    nonce,
-                    class_hash,
-                    contract_address_salt,
-                    constructor_calldata,
-                },
-            ) => Ok(Self::Version1(DeployAccountTransactionV1 {
-                max_fee,
-                signature,
-                nonce,
-                class_hash,
-                contract_address_salt,
-                constructor_calldata,
-                version: TransactionVersion1::default(),
-            })),
-            starknet_api::transaction::DeployAccountTransaction::V3(
-                starknet_api::transaction::DeployAccountTransactionV3 {
-                    resource_bounds,
-                    signature,
-                    nonce,
-                    class_hash,
-                    contract_address_salt,
-                    constructor_calldata,
-                    ..
-                },
-            ) => {
-                let l1_gas_bounds = resource_bounds
-                    .0
-                    .get(&Resource::L1Gas)
-                    .ok_or(internal_server_error("Got a v3 transaction with no L1 gas bounds."))?;
-                Ok(Self::Version1(DeployAccountTransactionV1 {
-                    max_fee: Fee(
-                        l1_gas_bounds.max_price_per_unit * u128::from(l1_gas_bounds.max_amount)
-                    ),
-                    signature,
-                    nonce,
-                    class_hash,
-                    contract_address_salt,
-                    constructor_calldata,
-                    version: TransactionVersion1::default(),
-                }))
-            }
-        }
-    }
-}
-
 impl From<DeployAccountTransaction> for client_transaction::DeployAccountTransaction {
     fn from(tx: DeployAccountTransaction) -> Self {
         match tx {
@@ -258,72 +208,6 @@ pub enum InvokeTransaction {
     Version1(InvokeTransactionV1),
 }
 
-impl TryFrom<starknet_api::transaction::InvokeTransaction> for InvokeTransaction {
-    type Error = ErrorObjectOwned;
-
-    fn try_from(tx: starknet_api::transaction::InvokeTransaction) -> Result<Self, Self::Error> {
-        match tx {
-            starknet_api::transaction::InvokeTransaction::V0(
-                starknet_api::transaction::InvokeTransactionV0 {
-                    max_fee,
-                    signature,
-                    contract_address,
-                    entry_point_selector,
-                    calldata,
-                },
-            ) => Ok(Self::Version0(InvokeTransactionV0 {
-                max_fee,
-                version: TransactionVersion0::default(),
-                signature,
-                contract_address,
-                entry_point_selector,
-                calldata,
-            })),
-            starknet_api::transaction::InvokeTransaction::V1(
-                starknet_api::transaction::InvokeTransactionV1 {
-                    max_fee,
-                    signature,
-                    nonce,
-                    sender_address,
-                    calldata,
-                },
-            ) => Ok(Self::Version1(InvokeTransactionV1 {
-                max_fee,
-                version: TransactionVersion1::default(),
-                signature,
-                nonce,
-                sender_address,
-                calldata,
-            })),
-            starknet_api::transaction::InvokeTransaction::V3(
-                starknet_api::transaction::InvokeTransactionV3 {
-                    resource_bounds,
-                    signature,
-                    nonce,
-                    sender_address,
-                    calldata,
-                    ..
-                },
-            ) => {
-                let l1_gas_bounds = resource_bounds
-                    .0
-                    .get(&Resource::L1Gas)
-                    .ok_or(internal_server_error("Got a v3 transaction with no L1 gas bounds."))?;
-                Ok(Self::Version1(InvokeTransactionV1 {
-                    max_fee: Fee(
-                        l1_gas_bounds.max_price_per_unit * u128::from(l1_gas_bounds.max_amount)
-                    ),
-                    version: TransactionVersion1::default(),
-                    signature,
-                    nonce,
-                    sender_address,
-                    calldata,
-                }))
-            }
-        }
-    }
-}
-
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize, PartialOrd, Ord)]
 pub struct TransactionWithHash {
     pub transaction_hash: TransactionHash,
@@ -346,68 +230,6 @@ pub enum Transaction {
     L1Handler(L1HandlerTransaction),
 }
 
-impl TryFrom<starknet_api::transaction::Transaction> for Transaction {
-    type Error = ErrorObjectOwned;
-
-    fn try_from(tx: starknet_api::transaction::Transaction) -> Result<Self, Self::Error> {
-        match tx {
-            starknet_api::transaction::Transaction::Declare(declare_tx) => match declare_tx {
-                starknet_api::transaction::DeclareTransaction::V0(tx) => {
-                    Ok(Self::Declare(DeclareTransaction::Version0(DeclareTransactionV0 {
-                        class_hash: tx.class_hash,
-                        sender_address: tx.sender_address,
-                        nonce: tx.nonce,
-                        max_fee: tx.max_fee,
-                        version: TransactionVersion0::default(),
-                        signature: tx.signature,
-                    })))
-                }
-                starknet_api::transaction::DeclareTransaction::V1(tx) => {
-                    Ok(Self::Declare(DeclareTransaction::Version1(DeclareTransactionV1 {
-                        class_hash: tx.class_hash,
-                        sender_address: tx.sender_address,
-                        nonce: tx.nonce,
-                        max_fee: tx.max_fee,
-                        version: TransactionVersion1::default(),
-                        signature: tx.signature,
-                    })))
-                }
-                starknet_api::transaction::DeclareTransaction::V2(tx) => {
-                    Ok(Self::Declare(DeclareTransaction::Version2(tx.into())))
-                }
-                starknet_api::transaction::DeclareTransaction::V3(tx) => {
-                    let l1_gas_bounds = tx.resource_bounds.0.get(&Resource::L1Gas).ok_or(
-                        internal_server_error("Got a v3 transaction with no L1 gas bounds."),
-                    )?;
-                    Ok(Self::Declare(DeclareTransaction::Version2(DeclareTransactionV2 {
-                        class_hash: tx.class_hash,
-                        compiled_class_hash: tx.compiled_class_hash,
-                        sender_address: tx.sender_address,
-                        nonce: tx.nonce,
-                        max_fee: Fee(
-                            l1_gas_bounds.max_price_per_unit * u128::from(l1_gas_bounds.max_amount)
-                        ),
-                        version: TransactionVersion2::default(),
-                        signature: tx.signature,
-                    })))
-                }
-            },
-            starknet_api::transaction::Transaction::Deploy(deploy_tx) => {
-                Ok(Transaction::Deploy(deploy_tx))
-            }
-            starknet_api::transaction::Transaction::DeployAccount(deploy_account_tx) => {
-                Ok(Self::DeployAccount(deploy_account_tx.try_into()?))
-            }
-            starknet_api::transaction::Transaction::Invoke(invoke_tx) => {
-                Ok(Self::Invoke(invoke_tx.try_into()?))
-            }
-            starknet_api::transaction::Transaction::L1Handler(l1_handler_tx) => {
-                Ok(Transaction::L1Handler(l1_handler_tx))
-            }
-        }
-    }
-}
-
 /// Transaction Finality status on starknet.
 #[derive(
     Debug, Copy, Clone, Eq, PartialEq, Hash, Deserialize, Serialize, PartialOrd, Ord, Default,
@@ -432,20 +254,6 @@ pub enum PendingTransactionFinalityStatus {
     AcceptedOnL2,
 }
 
-impl From<BlockStatus> for TransactionFinalityStatus {
-    fn from(status: BlockStatus) -> Self {
-        match status {
-            BlockStatus::AcceptedOnL1 => TransactionFinalityStatus::AcceptedOnL1,
-            BlockStatus::AcceptedOnL2 => TransactionFinalityStatus::AcceptedOnL2,
-            BlockStatus::Pending => TransactionFinalityStatus::AcceptedOnL2, /* for backward compatibility pending transactions are considered accepted on L2 */
-            // we convert the block status to transaction status only in the creation of
-            // TransactionReceiptWithStatus before that we verify that the block is not
-            // rejected so this conversion should never happen
-            BlockStatus::Rejected => unreachable!("Rejected blocks are not returned by the API"),
-        }
-    }
-}
-
 #[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum GeneralTransactionReceipt {
@@ -566,106 +374,6 @@ impl TransactionOutput {
             TransactionOutput::L1Handler(tx_output) => &tx_output.execution_status,
         }
     }
-
-    pub fn from_thin_transaction_output(
-        thin_tx_output: ThinTransactionOutput,
-        events: Vec<starknet_api::transaction::Event>,
-    ) -> Self {
-        match thin_tx_output {
-            ThinTransactionOutput::Declare(thin_declare) => {
-                TransactionOutput::Declare(DeclareTransactionOutput {
-                    actual_fee: thin_declare.actual_fee,
-                    messages_sent: thin_declare.messages_sent,
-                    events,
-                    execution_status: thin_declare.execution_status,
-                })
-            }
-            ThinTransactionOutput::Deploy(thin_deploy) => {
-                TransactionOutput::Deploy(DeployTransactionOutput {
-                    actual_fee: thin_deploy.actual_fee,
-                    messages_sent: thin_deploy.messages_sent,
-                    events,
-                    contract_address: thin_deploy.contract_address,
-                    execution_status: thin_deploy.execution_status,
-                })
-            }
-            ThinTransactionOutput::DeployAccount(thin_deploy) => {
-                TransactionOutput::DeployAccount(DeployAccountTransactionOutput {
-                    actual_fee: thin_deploy.actual_fee,
-                    messages_sent: thin_deploy.messages_sent,
-                    events,
-                    contract_address: thin_deploy.contract_address,
-                    execution_status: thin_deploy.execution_status,
-                })
-            }
-            ThinTransactionOutput::Invoke(thin_invoke) => {
-                TransactionOutput::Invoke(InvokeTransactionOutput {
-                    actual_fee: thin_invoke.actual_fee,
-                    messages_sent: thin_invoke.messages_sent,
-                    events,
-                    execution_status: thin_invoke.execution_status,
-                })
-            }
-            ThinTransactionOutput::L1Handler(thin_l1handler) => {
-                TransactionOutput::L1Handler(L1HandlerTransactionOutput {
-                    actual_fee: thin_l1handler.actual_fee,
-                    messages_sent: thin_l1handler.messages_sent,
-                    events,
-                    execution_status: thin_l1handler.execution_status,
-                })
-            }
-        }
-    }
-}
-
-impl From<starknet_api::transaction::TransactionOutput> for TransactionOutput {
-    #[cfg_attr(coverage_nightly, coverage_attribute)]
-    fn from(tx_output: starknet_api::transaction::TransactionOutput) -> Self {
-        match tx_output {
-            starknet_api::transaction::TransactionOutput::Declare(declare_tx_output) => {
-                TransactionOutput::Declare(DeclareTransactionOutput {
-                    actual_fee: declare_tx_output.actual_fee,
-                    messages_sent: declare_tx_output.messages_sent,
-                    events: declare_tx_output.events,
-                    execution_status: declare_tx_output.execution_status,
-                })
-            }
-            starknet_api::transaction::TransactionOutput::Deploy(deploy_tx_output) => {
-                TransactionOutput::Deploy(DeployTransactionOutput {
-                    actual_fee: deploy_tx_output.actual_fee,
-                    messages_sent: deploy_tx_output.messages_sent,
-                    events: deploy_tx_output.events,
-                    contract_address: deploy_tx_output.contract_address,
-                    execution_status: deploy_tx_output.execution_status,
-                })
-            }
-            starknet_api::transaction::TransactionOutput::DeployAccount(deploy_tx_output) => {
-                TransactionOutput::DeployAccount(DeployAccountTransactionOutput {
-                    actual_fee: deploy_tx_output.actual_fee,
-                    messages_sent: deploy_tx_output.messages_sent,
-                    events: deploy_tx_output.events,
-                    contract_address: deploy_tx_output.contract_address,
-                    execution_status: deploy_tx_output.execution_status,
-                })
-            }
-            starknet_api::transaction::TransactionOutput::Invoke(invoke_tx_output) => {
-                TransactionOutput::Invoke(InvokeTransactionOutput {
-                    actual_fee: invoke_tx_output.actual_fee,
-                    messages_sent: invoke_tx_output.messages_sent,
-                    events: invoke_tx_output.events,
-                    execution_status: invoke_tx_output.execution_status,
-                })
-            }
-            starknet_api::transaction::TransactionOutput::L1Handler(l1_handler_tx_output) => {
-                TransactionOutput::L1Handler(L1HandlerTransactionOutput {
-                    actual_fee: l1_handler_tx_output.actual_fee,
-                    messages_sent: l1_handler_tx_output.messages_sent,
-                    events: l1_handler_tx_output.events,
-                    execution_status: l1_handler_tx_output.execution_status,
-                })
-            }
-        }
-    }
 }
 
 impl TryFrom<TransactionOutput> for PendingTransactionOutput {
@@ -709,19 +417,17 @@ pub struct Event {
     pub event: starknet_api::transaction::Event,
 }
 
-pub fn get_block_txs_by_number<
-    Mode: TransactionKind,
-    Transaction: TryFrom<starknet_api::transaction::Transaction, Error = ErrorObjectOwned>,
->(
+pub fn get_block_streamed_txs_by_number<Mode: TransactionKind>(
     txn: &StorageTxn<'_, Mode>,
     block_number: BlockNumber,
-) -> Result<Vec<Transaction>, ErrorObjectOwned> {
+) -> Result<StreamedTransactions, ErrorObjectOwned> {
     let transactions = txn
         .get_block_transactions(block_number)
         .map_err(internal_server_error)?
         .ok_or_else(|| ErrorObjectOwned::from(BLOCK_NOT_FOUND))?;
+    let transaction_hashes = get_block_tx_hashes_by_number(txn, block_number)?;
 
-    transactions.into_iter().map(Transaction::try_from).collect()
+    Ok(stream_transactions_with_hash(transactions, transaction_hashes))
 }
 
 pub fn get_block_tx_hashes_by_number<Mode: TransactionKind>(
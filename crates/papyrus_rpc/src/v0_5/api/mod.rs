@@ -25,7 +25,7 @@ use starknet_api::state::{StateNumber, StorageKey};
 use starknet_api::transaction::{EventKey, Fee, TransactionHash, TransactionOffsetInBlock};
 use tracing::debug;
 
-use super::block::Block;
+use super::block::{Block, StreamedBlock};
 use super::broadcasted_transaction::{
     BroadcastedDeclareTransaction,
     BroadcastedDeclareV1Transaction,
@@ -33,15 +33,10 @@ use super::broadcasted_transaction::{
 };
 use super::deprecated_contract_class::ContractClass as DeprecatedContractClass;
 use super::error::{JsonRpcError, BLOCK_NOT_FOUND, INVALID_CONTINUATION_TOKEN};
-use super::state::{ContractClass, StateUpdate};
+use super::state::{ContractClass, StreamedStateUpdate};
 use super::transaction::{
-    DeployAccountTransaction,
-    DeployAccountTransactionV1,
     Event,
     GeneralTransactionReceipt,
-    InvokeTransaction,
-    InvokeTransactionV0,
-    InvokeTransactionV1,
     MessageFromL1,
     TransactionStatus,
     TransactionWithHash,
@@ -49,7 +44,7 @@ use super::transaction::{
     TypedInvokeTransactionV1,
 };
 use super::write_api_result::{AddDeclareOkResult, AddDeployAccountOkResult, AddInvokeOkResult};
-use crate::api::{BlockId, CallRequest};
+use crate::api::{BlockId, CallRequest, ContinuationTokenKey};
 use crate::syncing_state::SyncingState;
 use crate::{internal_server_error, ContinuationTokenAsStruct};
 
@@ -78,7 +73,7 @@ pub trait JsonRpc {
 
     /// Gets block information with full transactions given a block identifier.
     #[method(name = "getBlockWithTxs")]
-    async fn get_block_w_full_transactions(&self, block_id: BlockId) -> RpcResult<Block>;
+    async fn get_block_w_full_transactions(&self, block_id: BlockId) -> RpcResult<StreamedBlock>;
 
     /// Gets the value of the storage at the given address, key, and block.
     #[method(name = "getStorageAt")]
@@ -110,7 +105,7 @@ pub trait JsonRpc {
 
     /// Gets the information about the result of executing the requested block.
     #[method(name = "getStateUpdate")]
-    async fn get_state_update(&self, block_id: BlockId) -> RpcResult<StateUpdate>;
+    async fn get_state_update(&self, block_id: BlockId) -> RpcResult<StreamedStateUpdate>;
 
     /// Gets the transaction status.
     #[method(name = "getTransactionStatus")]
@@ -271,15 +266,19 @@ pub struct EventFilter {
 pub struct ContinuationToken(pub String);
 
 impl ContinuationToken {
-    fn parse(&self) -> Result<ContinuationTokenAsStruct, ErrorObjectOwned> {
-        let ct = serde_json::from_str(&self.0)
-            .map_err(|_| ErrorObjectOwned::from(INVALID_CONTINUATION_TOKEN))?;
-
-        Ok(ContinuationTokenAsStruct(ct))
+    fn parse(
+        &self,
+        key: &ContinuationTokenKey,
+    ) -> Result<ContinuationTokenAsStruct, ErrorObjectOwned> {
+        ContinuationTokenAsStruct::decode(&self.0, key)
+            .ok_or_else(|| ErrorObjectOwned::from(INVALID_CONTINUATION_TOKEN))
     }
 
-    fn new(ct: ContinuationTokenAsStruct) -> Result<Self, ErrorObjectOwned> {
-        Ok(Self(serde_json::to_string(&ct.0).map_err(internal_server_error)?))
+    fn new(
+        ct: ContinuationTokenAsStruct,
+        key: &ContinuationTokenKey,
+    ) -> Result<Self, ErrorObjectOwned> {
+        Ok(Self(ct.encode(key)))
     }
 }
 
@@ -500,64 +499,6 @@ fn user_deprecated_contract_class_to_sn_api(
     })
 }
 
-impl From<DeployAccountTransaction> for starknet_api::transaction::DeployAccountTransaction {
-    fn from(tx: DeployAccountTransaction) -> Self {
-        match tx {
-            DeployAccountTransaction::Version1(DeployAccountTransactionV1 {
-                max_fee,
-                signature,
-                nonce,
-                class_hash,
-                contract_address_salt,
-                constructor_calldata,
-                version: _,
-            }) => Self::V1(starknet_api::transaction::DeployAccountTransactionV1 {
-                max_fee,
-                signature,
-                nonce,
-                class_hash,
-                contract_address_salt,
-                constructor_calldata,
-            }),
-        }
-    }
-}
-
-impl From<InvokeTransaction> for starknet_api::transaction::InvokeTransaction {
-    fn from(value: InvokeTransaction) -> Self {
-        match value {
-            InvokeTransaction::Version0(InvokeTransactionV0 {
-                max_fee,
-                version: _,
-                signature,
-                contract_address,
-                entry_point_selector,
-                calldata,
-            }) => Self::V0(starknet_api::transaction::InvokeTransactionV0 {
-                max_fee,
-                signature,
-                contract_address,
-                entry_point_selector,
-                calldata,
-            }),
-            InvokeTransaction::Version1(InvokeTransactionV1 {
-                max_fee,
-                version: _,
-                signature,
-                nonce,
-                sender_address,
-                calldata,
-            }) => Self::V1(starknet_api::transaction::InvokeTransactionV1 {
-                max_fee,
-                signature,
-                nonce,
-                sender_address,
-                calldata,
-            }),
-        }
-    }
-}
-
 impl TryFrom<ApiContractClass> for GatewayContractClass {
     type Error = StorageSerdeError;
     fn try_from(class: ApiContractClass) -> Result<Self, Self::Error> {
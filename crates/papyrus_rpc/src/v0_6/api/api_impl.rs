@@ -2,10 +2,12 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use jsonrpsee::core::RpcResult;
-use jsonrpsee::types::ErrorObjectOwned;
+use jsonrpsee::types::{ErrorObjectOwned, Params};
 use jsonrpsee::RpcModule;
 use lazy_static::lazy_static;
+use papyrus_common::deferred_classes::DeferredClassRequests;
 use papyrus_common::pending_classes::{PendingClasses, PendingClassesTrait};
+use papyrus_common::recent_state::RecentStateCache;
 use papyrus_execution::objects::{PendingData as ExecutionPendingData, TransactionTrace};
 use papyrus_execution::{
     estimate_fee as exec_estimate_fee,
@@ -38,6 +40,7 @@ use starknet_client::reader::objects::pending_data::{
     PendingStateUpdate as ClientPendingStateUpdate,
 };
 use starknet_client::reader::PendingData;
+use starknet_client::writer::objects::transaction::DeclareTransaction as ClientDeclareTransaction;
 use starknet_client::writer::{StarknetWriter, WriterClientError};
 use starknet_client::ClientError;
 use tokio::sync::RwLock;
@@ -51,7 +54,7 @@ use super::super::block::{
     BlockNotRevertedValidator,
     GeneralBlockHeader,
     PendingBlockHeader,
-    ResourcePrice,
+    StreamedBlock,
 };
 use super::super::broadcasted_transaction::{
     BroadcastedDeclareTransaction,
@@ -64,17 +67,17 @@ use super::super::error::{
     BLOCK_NOT_FOUND,
     CLASS_HASH_NOT_FOUND,
     CONTRACT_NOT_FOUND,
+    INVALID_CONTINUATION_TOKEN,
     INVALID_TRANSACTION_HASH,
     INVALID_TRANSACTION_INDEX,
     NO_BLOCKS,
-    PAGE_SIZE_TOO_BIG,
-    TOO_MANY_KEYS_IN_FILTER,
     TRANSACTION_HASH_NOT_FOUND,
 };
-use super::super::state::{AcceptedStateUpdate, PendingStateUpdate, StateUpdate};
+use super::super::state::{PendingStateUpdate, StreamedAcceptedStateUpdate, StreamedStateUpdate};
 use super::super::transaction::{
+    get_block_streamed_txs_by_number,
     get_block_tx_hashes_by_number,
-    get_block_txs_by_number,
+    stream_transactions_with_hash,
     Event,
     GeneralTransactionReceipt,
     L1HandlerMsgHash,
@@ -116,14 +119,31 @@ use super::{
     SimulationFlag,
     TransactionTraceWithHash,
 };
-use crate::api::{BlockHashOrNumber, JsonRpcServerImpl, Tag};
+use crate::api::{BlockHashOrNumber, ContinuationTokenKey, JsonRpcServerImpl, Tag};
+use crate::execution_pool::ExecutionPool;
+use crate::execution_utils::{download_class_if_deferred, execute_with_deferred_classes};
 use crate::pending::client_pending_data_to_execution_pending_data;
+use crate::streaming::{single_param, StreamedMethods, StreamedResult};
 use crate::syncing_state::{get_last_synced_block, SyncStatus, SyncingState};
+use crate::trace_cache::TraceCache;
+use crate::v0_7::fee_estimate_cache::FeeEstimateCache;
+use crate::v0_7::submitted_transactions::SubmittedTransactions;
+use crate::validation::{
+    validate_call_request,
+    validate_contract_address,
+    validate_declare_transaction,
+    validate_deploy_account_transaction,
+    validate_event_filter,
+    validate_invoke_transaction,
+};
 use crate::version_config::VERSION_0_6 as VERSION;
 use crate::{
     get_block_status,
     get_latest_block_number,
     internal_server_error,
+    read_storage,
+    verify_receipts_synced,
+    verify_state_queryable,
     verify_storage_scope,
     ContinuationTokenAsStruct,
 };
@@ -144,6 +164,10 @@ pub struct JsonRpcServerV0_6Impl {
     pub shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
     pub pending_data: Arc<RwLock<PendingData>>,
     pub pending_classes: Arc<RwLock<PendingClasses>>,
+    pub recent_state: Arc<RecentStateCache>,
+    pub deferred_class_requests: Arc<DeferredClassRequests>,
+    pub execution_pool: Arc<ExecutionPool>,
+    pub continuation_token_key: ContinuationTokenKey,
     pub writer_client: Arc<dyn StarknetWriter>,
 }
 
@@ -174,20 +198,10 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
     async fn get_block_w_transaction_hashes(&self, block_id: BlockId) -> RpcResult<Block> {
         verify_storage_scope(&self.storage_reader)?;
 
-        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
         if let BlockId::Tag(Tag::Pending) = block_id {
+            let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
             let block = read_pending_data(&self.pending_data, &txn).await?.block;
-            let pending_block_header = PendingBlockHeader {
-                parent_hash: block.parent_block_hash(),
-                sequencer_address: block.sequencer_address(),
-                timestamp: block.timestamp(),
-                l1_gas_price: ResourcePrice {
-                    price_in_wei: block.l1_gas_price().price_in_wei,
-                    price_in_fri: block.l1_gas_price().price_in_fri,
-                },
-                starknet_version: block.starknet_version(),
-            };
-            let header = GeneralBlockHeader::PendingBlockHeader(pending_block_header);
+            let header = GeneralBlockHeader::PendingBlockHeader(PendingBlockHeader::from(&block));
             let client_transactions = block.transactions();
             let transaction_hashes = client_transactions
                 .iter()
@@ -200,80 +214,62 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
             });
         }
 
-        let block_number = get_accepted_block_number(&txn, block_id)?;
-        let status = get_block_status(&txn, block_number)?;
-        let header =
-            GeneralBlockHeader::BlockHeader(get_block_header_by_number(&txn, block_number)?.into());
-        let transaction_hashes = get_block_tx_hashes_by_number(&txn, block_number)?;
-
-        Ok(Block {
-            status: Some(status),
-            header,
-            transactions: Transactions::Hashes(transaction_hashes),
+        read_storage(&self.storage_reader, move |txn| {
+            let block_number = get_accepted_block_number(txn, block_id)?;
+            let status = get_block_status(txn, block_number)?;
+            let header = GeneralBlockHeader::BlockHeader(
+                get_block_header_by_number(txn, block_number)?.into(),
+            );
+            let transaction_hashes = get_block_tx_hashes_by_number(txn, block_number)?;
+
+            Ok(Block {
+                status: Some(status),
+                header,
+                transactions: Transactions::Hashes(transaction_hashes),
+            })
         })
+        .await
     }
 
     #[instrument(skip(self), level = "debug", err, ret)]
-    async fn get_block_w_full_transactions(&self, block_id: BlockId) -> RpcResult<Block> {
+    async fn get_block_w_full_transactions(&self, block_id: BlockId) -> RpcResult<StreamedBlock> {
         verify_storage_scope(&self.storage_reader)?;
 
-        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
         if let BlockId::Tag(Tag::Pending) = block_id {
+            let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
             let block = read_pending_data(&self.pending_data, &txn).await?.block;
-            let pending_block_header = PendingBlockHeader {
-                parent_hash: block.parent_block_hash(),
-                sequencer_address: block.sequencer_address(),
-                timestamp: block.timestamp(),
-                l1_gas_price: ResourcePrice {
-                    price_in_wei: block.l1_gas_price().price_in_wei,
-                    price_in_fri: block.l1_gas_price().price_in_fri,
-                },
-                starknet_version: block.starknet_version(),
-            };
-            let header = GeneralBlockHeader::PendingBlockHeader(pending_block_header);
+            let header = GeneralBlockHeader::PendingBlockHeader(PendingBlockHeader::from(&block));
             let client_transactions = block.transactions();
             let transactions = client_transactions
                 .iter()
                 .map(|client_transaction| {
-                    let starknet_api_transaction: StarknetApiTransaction =
-                        client_transaction.clone().try_into().map_err(internal_server_error)?;
-                    Ok(TransactionWithHash {
-                        transaction: starknet_api_transaction
-                            .try_into()
-                            .map_err(internal_server_error)?,
-                        transaction_hash: client_transaction.transaction_hash(),
-                    })
+                    client_transaction.clone().try_into().map_err(internal_server_error)
                 })
-                .collect::<Result<Vec<_>, ErrorObjectOwned>>()?;
-            return Ok(Block {
+                .collect::<Result<Vec<StarknetApiTransaction>, ErrorObjectOwned>>()?;
+            let transaction_hashes = client_transactions
+                .iter()
+                .map(|client_transaction| client_transaction.transaction_hash())
+                .collect();
+            return Ok(StreamedBlock {
                 status: None,
                 header,
-                transactions: Transactions::Full(transactions),
+                transactions: stream_transactions_with_hash(transactions, transaction_hashes),
             });
         }
 
-        let block_number = get_accepted_block_number(&txn, block_id)?;
-        let status = get_block_status(&txn, block_number)?;
-        let header =
-            GeneralBlockHeader::BlockHeader(get_block_header_by_number(&txn, block_number)?.into());
-        // TODO(dvir): consider create a vector of (transaction, transaction_index) first and get
-        // the transaction hashes by the index.
-        let transactions = get_block_txs_by_number(&txn, block_number)?;
-        let transaction_hashes = get_block_tx_hashes_by_number(&txn, block_number)?;
-        let transactions_with_hash = transactions
-            .into_iter()
-            .zip(transaction_hashes)
-            .map(|(transaction, transaction_hash)| TransactionWithHash {
-                transaction,
-                transaction_hash,
-            })
-            .collect();
+        read_storage(&self.storage_reader, move |txn| {
+            let block_number = get_accepted_block_number(txn, block_id)?;
+            let status = get_block_status(txn, block_number)?;
+            let header = GeneralBlockHeader::BlockHeader(
+                get_block_header_by_number(txn, block_number)?.into(),
+            );
+            // The transactions are converted to their RPC representation while the response is
+            // serialized, so that big blocks aren't held in memory in both representations.
+            let transactions = get_block_streamed_txs_by_number(txn, block_number)?;
 
-        Ok(Block {
-            status: Some(status),
-            header,
-            transactions: Transactions::Full(transactions_with_hash),
+            Ok(StreamedBlock { status: Some(status), header, transactions })
         })
+        .await
     }
 
     #[instrument(skip(self), level = "debug", err, ret)]
@@ -283,6 +279,7 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
         key: StorageKey,
         block_id: BlockId,
     ) -> RpcResult<StarkFelt> {
+        validate_contract_address(&contract_address)?;
         let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
         let maybe_pending_storage_diffs = if let BlockId::Tag(Tag::Pending) = block_id {
             Some(
@@ -299,14 +296,28 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
         // Check that the block is valid and get the state number.
         let block_number = get_accepted_block_number(&txn, block_id)?;
         let state_number = StateNumber::right_after_block(block_number);
-        let res = execution_utils::get_storage_at(
-            &txn,
-            state_number,
-            maybe_pending_storage_diffs.as_ref(),
-            contract_address,
-            key,
-        )
-        .map_err(internal_server_error)?;
+        verify_state_queryable(&txn, state_number)?;
+        // Values written in the pending block take precedence over the recent state cache.
+        let written_in_pending = maybe_pending_storage_diffs
+            .as_ref()
+            .and_then(|storage_diffs| storage_diffs.get(&contract_address))
+            .is_some_and(|storage_entries| storage_entries.iter().any(|entry| entry.key == key));
+        let cached_value = if written_in_pending {
+            None
+        } else {
+            self.recent_state.get_storage_at(block_number, contract_address, key)
+        };
+        let res = match cached_value {
+            Some(value) => value,
+            None => execution_utils::get_storage_at(
+                &txn,
+                state_number,
+                maybe_pending_storage_diffs.as_ref(),
+                contract_address,
+                key,
+            )
+            .map_err(internal_server_error)?,
+        };
 
         // If the contract is not deployed, res will be 0. Checking if that's the case so that
         // we'll return an error instead.
@@ -422,46 +433,52 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
     }
 
     #[instrument(skip(self), level = "debug", err, ret)]
-    async fn get_state_update(&self, block_id: BlockId) -> RpcResult<StateUpdate> {
-        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+    async fn get_state_update(&self, block_id: BlockId) -> RpcResult<StreamedStateUpdate> {
         if let BlockId::Tag(Tag::Pending) = block_id {
+            let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
             let state_update = read_pending_data(&self.pending_data, &txn).await?.state_update;
-            return Ok(StateUpdate::PendingStateUpdate(PendingStateUpdate {
+            return Ok(StreamedStateUpdate::PendingStateUpdate(PendingStateUpdate {
                 old_root: state_update.old_root,
                 state_diff: state_update.state_diff.into(),
             }));
         }
 
-        // Get the block header for the block hash and state root.
-        let block_number = get_accepted_block_number(&txn, block_id)?;
-        let header: BlockHeader = get_block_header_by_number(&txn, block_number)?.into();
-
-        // Get the old root.
-        let old_root = match get_accepted_block_number(
-            &txn,
-            BlockId::HashOrNumber(BlockHashOrNumber::Hash(header.parent_hash)),
-        ) {
-            Ok(parent_block_number) => {
-                BlockHeader::from(get_block_header_by_number(&txn, parent_block_number)?).new_root
-            }
-            Err(_) => GlobalRoot(StarkHash::try_from(GENESIS_HASH).map_err(internal_server_error)?),
-        };
+        read_storage(&self.storage_reader, move |txn| {
+            // Get the block header for the block hash and state root.
+            let block_number = get_accepted_block_number(txn, block_id)?;
+            let header: BlockHeader = get_block_header_by_number(txn, block_number)?.into();
+
+            // Get the old root.
+            let old_root = match get_accepted_block_number(
+                txn,
+                BlockId::HashOrNumber(BlockHashOrNumber::Hash(header.parent_hash)),
+            ) {
+                Ok(parent_block_number) => {
+                    BlockHeader::from(get_block_header_by_number(txn, parent_block_number)?)
+                        .new_root
+                }
+                Err(_) => {
+                    GlobalRoot(StarkHash::try_from(GENESIS_HASH).map_err(internal_server_error)?)
+                }
+            };
 
-        // Get the block state diff.
-        let mut thin_state_diff = txn
-            .get_state_diff(block_number)
-            .map_err(internal_server_error)?
-            .ok_or_else(|| ErrorObjectOwned::from(BLOCK_NOT_FOUND))?;
-        // Remove empty storage diffs. Some blocks contain empty storage diffs that must be kept for
-        // the computation of state diff commitment.
-        thin_state_diff.storage_diffs.retain(|_k, v| !v.is_empty());
-
-        Ok(StateUpdate::AcceptedStateUpdate(AcceptedStateUpdate {
-            block_hash: header.block_hash,
-            new_root: header.new_root,
-            old_root,
-            state_diff: thin_state_diff.into(),
-        }))
+            // Get the block state diff.
+            let mut thin_state_diff = txn
+                .get_state_diff(block_number)
+                .map_err(internal_server_error)?
+                .ok_or_else(|| ErrorObjectOwned::from(BLOCK_NOT_FOUND))?;
+            // Remove empty storage diffs. Some blocks contain empty storage diffs that must be kept
+            // for the computation of state diff commitment.
+            thin_state_diff.storage_diffs.retain(|_k, v| !v.is_empty());
+
+            Ok(StreamedStateUpdate::AcceptedStateUpdate(StreamedAcceptedStateUpdate {
+                block_hash: header.block_hash,
+                new_root: header.new_root,
+                old_root,
+                state_diff: thin_state_diff.into(),
+            }))
+        })
+        .await
     }
 
     async fn get_transaction_status(
@@ -511,6 +528,7 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
                 StarknetApiTransaction::L1Handler(tx) => tx.version,
             };
 
+            verify_receipts_synced(&txn, block_number)?;
             let thin_tx_output = txn
                 .get_transaction_output(transaction_index)
                 .map_err(internal_server_error)?
@@ -602,27 +620,31 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
         } else {
             block_id
         };
+        download_class_if_deferred(&self.storage_reader, &self.deferred_class_requests, class_hash)
+            .await?;
 
-        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
-
-        let block_number = get_accepted_block_number(&txn, block_id)?;
-        let state_number = StateNumber::right_after_block(block_number);
-        let state_reader = txn.get_state_reader().map_err(internal_server_error)?;
+        read_storage(&self.storage_reader, move |txn| {
+            let block_number = get_accepted_block_number(txn, block_id)?;
+            let state_number = StateNumber::right_after_block(block_number);
+            verify_state_queryable(txn, state_number)?;
+            let state_reader = txn.get_state_reader().map_err(internal_server_error)?;
 
-        // The class might be a deprecated class. Search it first in the declared classes and if not
-        // found, search in the deprecated classes.
-        if let Some(class) = state_reader
-            .get_class_definition_at(state_number, &class_hash)
-            .map_err(internal_server_error)?
-        {
-            Ok(GatewayContractClass::Sierra(class.into()))
-        } else {
-            let class = state_reader
-                .get_deprecated_class_definition_at(state_number, &class_hash)
+            // The class might be a deprecated class. Search it first in the declared classes and if
+            // not found, search in the deprecated classes.
+            if let Some(class) = state_reader
+                .get_class_definition_at(state_number, &class_hash)
                 .map_err(internal_server_error)?
-                .ok_or_else(|| ErrorObjectOwned::from(CLASS_HASH_NOT_FOUND))?;
-            Ok(GatewayContractClass::Cairo0(class.try_into().map_err(internal_server_error)?))
-        }
+            {
+                Ok(GatewayContractClass::Sierra(class.into()))
+            } else {
+                let class = state_reader
+                    .get_deprecated_class_definition_at(state_number, &class_hash)
+                    .map_err(internal_server_error)?
+                    .ok_or_else(|| ErrorObjectOwned::from(CLASS_HASH_NOT_FOUND))?;
+                Ok(GatewayContractClass::Cairo0(class.try_into().map_err(internal_server_error)?))
+            }
+        })
+        .await
     }
 
     #[instrument(skip(self), level = "debug", err, ret)]
@@ -641,6 +663,7 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
         block_id: BlockId,
         contract_address: ContractAddress,
     ) -> RpcResult<ClassHash> {
+        validate_contract_address(&contract_address)?;
         let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
 
         let maybe_pending_deployed_contracts_and_replaced_classes =
@@ -654,6 +677,22 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
 
         let block_number = get_accepted_block_number(&txn, block_id)?;
         let state_number = StateNumber::right_after_block(block_number);
+        verify_state_queryable(&txn, state_number)?;
+        // Classes of contracts deployed or replaced in the pending block take precedence over the
+        // recent state cache.
+        let written_in_pending = maybe_pending_deployed_contracts_and_replaced_classes
+            .as_ref()
+            .is_some_and(|(deployed_contracts, replaced_classes)| {
+                deployed_contracts.iter().any(|contract| contract.address == contract_address)
+                    || replaced_classes.iter().any(|replaced| replaced.address == contract_address)
+            });
+        if !written_in_pending {
+            if let Some(class_hash) =
+                self.recent_state.get_class_hash_at(block_number, contract_address)
+            {
+                return Ok(class_hash);
+            }
+        }
         execution_utils::get_class_hash_at(
             &txn,
             state_number,
@@ -671,6 +710,7 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
         block_id: BlockId,
         contract_address: ContractAddress,
     ) -> RpcResult<Nonce> {
+        validate_contract_address(&contract_address)?;
         let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
 
         let maybe_pending_nonces = if let BlockId::Tag(Tag::Pending) = block_id {
@@ -682,6 +722,16 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
         // Check that the block is valid and get the state number.
         let block_number = get_accepted_block_number(&txn, block_id)?;
         let state_number = StateNumber::right_after_block(block_number);
+        verify_state_queryable(&txn, state_number)?;
+        // Nonces written in the pending block take precedence over the recent state cache.
+        let written_in_pending = maybe_pending_nonces
+            .as_ref()
+            .is_some_and(|pending_nonces| pending_nonces.contains_key(&contract_address));
+        if !written_in_pending {
+            if let Some(nonce) = self.recent_state.get_nonce_at(block_number, contract_address) {
+                return Ok(nonce);
+            }
+        }
         execution_utils::get_nonce_at(
             &txn,
             state_number,
@@ -700,15 +750,13 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
     #[instrument(skip(self), level = "debug", err, ret)]
     async fn get_events(&self, filter: EventFilter) -> RpcResult<EventsChunk> {
         verify_storage_scope(&self.storage_reader)?;
-
-        // Check the chunk size.
-        if filter.chunk_size > self.max_events_chunk_size {
-            return Err(ErrorObjectOwned::from(PAGE_SIZE_TOO_BIG));
-        }
-        // Check the number of keys.
-        if filter.keys.len() > self.max_events_keys {
-            return Err(ErrorObjectOwned::from(TOO_MANY_KEYS_IN_FILTER));
-        }
+        validate_event_filter(
+            filter.chunk_size,
+            filter.address.as_ref(),
+            &filter.keys,
+            self.max_events_chunk_size,
+            self.max_events_keys,
+        )?;
 
         // Get the requested block numbers.
         let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
@@ -739,7 +787,19 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
         // Get the event index. If there's a continuation token we take the event index from there.
         // Otherwise, we take the first index in the from_block_number.
         let start_event_index = match &filter.continuation_token {
-            Some(token) => token.parse()?.0,
+            Some(token) => {
+                let token = token.parse(&self.continuation_token_key)?;
+                // A token issued before a reorg may point to events of blocks that were reverted.
+                if token.is_stale(&txn)? {
+                    return Err(ErrorObjectOwned::from(INVALID_CONTINUATION_TOKEN));
+                }
+                let event_index = token.0;
+                // A token of another request may point to an event before the requested blocks.
+                if event_index.0 .0 < from_block_number {
+                    return Err(ErrorObjectOwned::from(INVALID_CONTINUATION_TOKEN));
+                }
+                event_index
+            }
             None => EventIndex(
                 TransactionIndex(from_block_number, TransactionOffsetInBlock(0)),
                 EventIndexInTransactionOutput(0),
@@ -758,7 +818,9 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
         // corresponding to the requested filter. If there are, we return a continuation token
         // pointing to the next relevant event. Otherwise, we return a continuation token None.
         let mut filtered_events = vec![];
-        if start_event_index.0.0 <= latest_block_number {
+        if start_event_index.0 .0 <= latest_block_number {
+            // The events are stored with the receipts.
+            verify_receipts_synced(&txn, to_block_number)?;
             for ((from_address, event_index), content) in txn
                 .iter_events(filter.address, start_event_index, to_block_number)
                 .map_err(internal_server_error)?
@@ -781,7 +843,8 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
                         return Ok(EventsChunk {
                             events: filtered_events,
                             continuation_token: Some(ContinuationToken::new(
-                                ContinuationTokenAsStruct(event_index),
+                                ContinuationTokenAsStruct::new(&txn, event_index)?,
+                                &self.continuation_token_key,
                             )?),
                         });
                     }
@@ -807,8 +870,8 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
             let pending_block = read_pending_data(&self.pending_data, &txn).await?.block;
             let pending_transaction_receipts = pending_block.transaction_receipts();
             // Extract the first transaction offset and event offset from the starting EventIndex.
-            let (transaction_start, event_start) = if start_event_index.0.0 > latest_block_number {
-                (start_event_index.0.1.0, start_event_index.1.0)
+            let (transaction_start, event_start) = if start_event_index.0 .0 > latest_block_number {
+                (start_event_index.0 .1 .0, start_event_index.1 .0)
             } else {
                 (0, 0)
             };
@@ -825,13 +888,17 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
                         return Ok(EventsChunk {
                             events: filtered_events,
                             continuation_token: Some(ContinuationToken::new(
-                                ContinuationTokenAsStruct(EventIndex(
-                                    TransactionIndex(
-                                        latest_block_number.next(),
-                                        TransactionOffsetInBlock(transaction_offset),
+                                ContinuationTokenAsStruct::new(
+                                    &txn,
+                                    EventIndex(
+                                        TransactionIndex(
+                                            latest_block_number.next(),
+                                            TransactionOffsetInBlock(transaction_offset),
+                                        ),
+                                        EventIndexInTransactionOutput(event_offset),
                                     ),
-                                    EventIndexInTransactionOutput(event_offset),
-                                )),
+                                )?,
+                                &self.continuation_token_key,
                             )?),
                         });
                     }
@@ -878,6 +945,7 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
 
     #[instrument(skip(self), level = "debug", err, ret)]
     async fn call(&self, request: CallRequest, block_id: BlockId) -> RpcResult<Vec<StarkFelt>> {
+        validate_call_request(&request)?;
         let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
         let maybe_pending_data = if let BlockId::Tag(Tag::Pending) = block_id {
             Some(client_pending_data_to_execution_pending_data(
@@ -902,21 +970,25 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
         let reader = self.storage_reader.clone();
         let contract_address_copy = request.contract_address;
 
-        let res = tokio::task::spawn_blocking(move || {
-            execute_call(
-                reader,
-                maybe_pending_data,
-                &chain_id,
-                state_number,
-                block_number,
-                &contract_address_copy,
-                request.entry_point_selector,
-                request.calldata,
-                &block_execution_config,
-            )
-        })
-        .await
-        .map_err(internal_server_error)?
+        let res = execute_with_deferred_classes(
+            &self.execution_pool,
+            &self.storage_reader,
+            &self.deferred_class_requests,
+            move || {
+                execute_call(
+                    reader.clone(),
+                    maybe_pending_data.clone(),
+                    &chain_id,
+                    state_number,
+                    block_number,
+                    &contract_address_copy,
+                    request.entry_point_selector,
+                    request.calldata.clone(),
+                    &block_execution_config,
+                )
+            },
+        )
+        .await?
         .map_err(execution_error_to_error_object_owned)?;
 
         block_not_reverted_validator.validate(&self.storage_reader)?;
@@ -929,7 +1001,9 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
         &self,
         invoke_transaction: TypedInvokeTransaction,
     ) -> RpcResult<AddInvokeOkResult> {
-        let result = self.writer_client.add_invoke_transaction(&invoke_transaction.into()).await;
+        let invoke_transaction = invoke_transaction.into();
+        validate_invoke_transaction(&invoke_transaction)?;
+        let result = self.writer_client.add_invoke_transaction(&invoke_transaction).await;
         match result {
             Ok(res) => Ok(res.into()),
             Err(WriterClientError::ClientError(ClientError::StarknetError(starknet_error))) => {
@@ -944,10 +1018,10 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
         &self,
         deploy_account_transaction: TypedDeployAccountTransaction,
     ) -> RpcResult<AddDeployAccountOkResult> {
-        let result = self
-            .writer_client
-            .add_deploy_account_transaction(&deploy_account_transaction.into())
-            .await;
+        let deploy_account_transaction = deploy_account_transaction.into();
+        validate_deploy_account_transaction(&deploy_account_transaction)?;
+        let result =
+            self.writer_client.add_deploy_account_transaction(&deploy_account_transaction).await;
         match result {
             Ok(res) => Ok(res.into()),
             Err(WriterClientError::ClientError(ClientError::StarknetError(starknet_error))) => {
@@ -962,12 +1036,10 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
         &self,
         declare_transaction: BroadcastedDeclareTransaction,
     ) -> RpcResult<AddDeclareOkResult> {
-        let result = self
-            .writer_client
-            .add_declare_transaction(
-                &declare_transaction.try_into().map_err(internal_server_error)?,
-            )
-            .await;
+        let declare_transaction: ClientDeclareTransaction =
+            declare_transaction.try_into().map_err(internal_server_error)?;
+        validate_declare_transaction(&declare_transaction)?;
+        let result = self.writer_client.add_declare_transaction(&declare_transaction).await;
         match result {
             Ok(res) => Ok(res.into()),
             Err(WriterClientError::ClientError(ClientError::StarknetError(starknet_error))) => {
@@ -1016,20 +1088,24 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
         let chain_id = self.chain_id.clone();
         let reader = self.storage_reader.clone();
 
-        let estimate_fee_result = tokio::task::spawn_blocking(move || {
-            exec_estimate_fee(
-                executable_txns,
-                &chain_id,
-                reader,
-                maybe_pending_data,
-                state_number,
-                block_number,
-                &block_execution_config,
-                validate,
-            )
-        })
-        .await
-        .map_err(internal_server_error)?;
+        let estimate_fee_result = execute_with_deferred_classes(
+            &self.execution_pool,
+            &self.storage_reader,
+            &self.deferred_class_requests,
+            move || {
+                exec_estimate_fee(
+                    executable_txns.clone(),
+                    &chain_id,
+                    reader.clone(),
+                    maybe_pending_data.clone(),
+                    state_number,
+                    block_number,
+                    &block_execution_config,
+                    validate,
+                )
+            },
+        )
+        .await?;
 
         block_not_reverted_validator.validate(&self.storage_reader)?;
 
@@ -1090,22 +1166,26 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
         let charge_fee = !simulation_flags.contains(&SimulationFlag::SkipFeeCharge);
         let validate = !simulation_flags.contains(&SimulationFlag::SkipValidate);
 
-        let simulation_results = tokio::task::spawn_blocking(move || {
-            exec_simulate_transactions(
-                executable_txns,
-                None,
-                &chain_id,
-                reader,
-                maybe_pending_data,
-                state_number,
-                block_number,
-                &block_execution_config,
-                charge_fee,
-                validate,
-            )
-        })
-        .await
-        .map_err(internal_server_error)?
+        let simulation_results = execute_with_deferred_classes(
+            &self.execution_pool,
+            &self.storage_reader,
+            &self.deferred_class_requests,
+            move || {
+                exec_simulate_transactions(
+                    executable_txns.clone(),
+                    None,
+                    &chain_id,
+                    reader.clone(),
+                    maybe_pending_data.clone(),
+                    state_number,
+                    block_number,
+                    &block_execution_config,
+                    charge_fee,
+                    validate,
+                )
+            },
+        )
+        .await?
         .map_err(execution_error_to_error_object_owned)?;
 
         block_not_reverted_validator.validate(&self.storage_reader)?;
@@ -1172,6 +1252,7 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
                 timestamp: pending_block.timestamp(),
                 l1_gas_price: pending_block.l1_gas_price(),
                 l1_data_gas_price: pending_block.l1_data_gas_price(),
+                l1_da_mode: pending_block.l1_da_mode(),
                 sequencer: pending_block.sequencer_address(),
                 // The pending state diff should be empty since we look at the state in the
                 // start of the pending block.
@@ -1241,22 +1322,26 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
         let chain_id = self.chain_id.clone();
         let reader = self.storage_reader.clone();
 
-        let mut simulation_results = tokio::task::spawn_blocking(move || {
-            exec_simulate_transactions(
-                executable_transactions,
-                Some(transaction_hashes),
-                &chain_id,
-                reader,
-                maybe_pending_data,
-                state_number,
-                block_number,
-                &block_execution_config,
-                true,
-                true,
-            )
-        })
-        .await
-        .map_err(internal_server_error)?
+        let mut simulation_results = execute_with_deferred_classes(
+            &self.execution_pool,
+            &self.storage_reader,
+            &self.deferred_class_requests,
+            move || {
+                exec_simulate_transactions(
+                    executable_transactions.clone(),
+                    Some(transaction_hashes.clone()),
+                    &chain_id,
+                    reader.clone(),
+                    maybe_pending_data.clone(),
+                    state_number,
+                    block_number,
+                    &block_execution_config,
+                    true,
+                    true,
+                )
+            },
+        )
+        .await?
         .map_err(execution_error_to_error_object_owned)?;
 
         block_not_reverted_validator.validate(&self.storage_reader)?;
@@ -1292,6 +1377,7 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
                         timestamp: client_pending_data.block.timestamp(),
                         l1_gas_price: client_pending_data.block.l1_gas_price(),
                         l1_data_gas_price: client_pending_data.block.l1_data_gas_price(),
+                        l1_da_mode: client_pending_data.block.l1_da_mode(),
                         sequencer: client_pending_data.block.sequencer_address(),
                         // The pending state diff should be empty since we look at the state in the
                         // start of the pending block.
@@ -1360,22 +1446,26 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
         let reader = self.storage_reader.clone();
         let transaction_hashes_clone = transaction_hashes.clone();
 
-        let simulation_results = tokio::task::spawn_blocking(move || {
-            exec_simulate_transactions(
-                executable_txns,
-                Some(transaction_hashes_clone),
-                &chain_id,
-                reader,
-                maybe_pending_data,
-                state_number,
-                block_number,
-                &block_execution_config,
-                true,
-                true,
-            )
-        })
-        .await
-        .map_err(internal_server_error)?
+        let simulation_results = execute_with_deferred_classes(
+            &self.execution_pool,
+            &self.storage_reader,
+            &self.deferred_class_requests,
+            move || {
+                exec_simulate_transactions(
+                    executable_txns.clone(),
+                    Some(transaction_hashes_clone.clone()),
+                    &chain_id,
+                    reader.clone(),
+                    maybe_pending_data.clone(),
+                    state_number,
+                    block_number,
+                    &block_execution_config,
+                    true,
+                    true,
+                )
+            },
+        )
+        .await?
         .map_err(execution_error_to_error_object_owned)?;
 
         block_not_reverted_validator.validate(&self.storage_reader)?;
@@ -1428,20 +1518,24 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
         let chain_id = self.chain_id.clone();
         let reader = self.storage_reader.clone();
 
-        let estimate_fee_result = tokio::task::spawn_blocking(move || {
-            exec_estimate_fee(
-                executable_txns,
-                &chain_id,
-                reader,
-                maybe_pending_data,
-                state_number,
-                block_number,
-                &block_execution_config,
-                false,
-            )
-        })
-        .await
-        .map_err(internal_server_error)?;
+        let estimate_fee_result = execute_with_deferred_classes(
+            &self.execution_pool,
+            &self.storage_reader,
+            &self.deferred_class_requests,
+            move || {
+                exec_estimate_fee(
+                    executable_txns.clone(),
+                    &chain_id,
+                    reader.clone(),
+                    maybe_pending_data.clone(),
+                    state_number,
+                    block_number,
+                    &block_execution_config,
+                    false,
+                )
+            },
+        )
+        .await?;
 
         block_not_reverted_validator.validate(&self.storage_reader)?;
 
@@ -1519,10 +1613,17 @@ impl JsonRpcServerImpl for JsonRpcServerV0_6Impl {
         storage_reader: StorageReader,
         max_events_chunk_size: usize,
         max_events_keys: usize,
+        _fee_estimate_cache: Arc<FeeEstimateCache>,
+        _submitted_transactions: Arc<SubmittedTransactions>,
         starting_block: BlockHashAndNumber,
         shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
         pending_data: Arc<RwLock<PendingData>>,
         pending_classes: Arc<RwLock<PendingClasses>>,
+        recent_state: Arc<RecentStateCache>,
+        _trace_cache: Arc<TraceCache>,
+        deferred_class_requests: Arc<DeferredClassRequests>,
+        execution_pool: Arc<ExecutionPool>,
+        continuation_token_key: ContinuationTokenKey,
         writer_client: Arc<dyn StarknetWriter>,
     ) -> Self {
         Self {
@@ -1535,6 +1636,10 @@ impl JsonRpcServerImpl for JsonRpcServerV0_6Impl {
             shared_highest_block,
             pending_data,
             pending_classes,
+            recent_state,
+            deferred_class_requests,
+            execution_pool,
+            continuation_token_key,
             writer_client,
         }
     }
@@ -1543,3 +1648,18 @@ impl JsonRpcServerImpl for JsonRpcServerV0_6Impl {
         self.into_rpc()
     }
 }
+
+#[async_trait]
+impl StreamedMethods for JsonRpcServerV0_6Impl {
+    async fn call(&self, method: &str, params: Params<'_>) -> RpcResult<Option<StreamedResult>> {
+        Ok(Some(match method {
+            "getBlockWithTxs" => StreamedResult::new(
+                self.get_block_w_full_transactions(single_param(&params, "block_id")?).await?,
+            ),
+            "getStateUpdate" => StreamedResult::new(
+                self.get_state_update(single_param(&params, "block_id")?).await?,
+            ),
+            _ => return Ok(None),
+        }))
+    }
+}
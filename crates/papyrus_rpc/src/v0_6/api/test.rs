@@ -3,17 +3,21 @@ use std::fmt::Debug;
 use std::iter;
 use std::net::SocketAddr;
 use std::ops::Index;
+use std::sync::Arc;
 
 use assert_matches::assert_matches;
 use async_trait::async_trait;
 use indexmap::{indexmap, IndexMap};
 use itertools::Itertools;
 use jsonrpsee::core::Error;
+use jsonrpsee::types::error::ErrorCode::InvalidParams;
+use jsonrpsee::types::ErrorObjectOwned;
 use jsonrpsee::Methods;
 use jsonschema::JSONSchema;
 use lazy_static::lazy_static;
 use mockall::predicate::eq;
 use papyrus_common::pending_classes::{ApiContractClass, PendingClassesTrait};
+use papyrus_common::recent_state::{BlockStateWrites, RecentStateCache};
 use papyrus_common::BlockHashAndNumber;
 use papyrus_storage::base_layer::BaseLayerStorageWriter;
 use papyrus_storage::body::events::EventIndex;
@@ -41,6 +45,7 @@ use starknet_api::block::{
 use starknet_api::core::{
     ClassHash,
     ContractAddress,
+    EntryPointSelector,
     GlobalRoot,
     Nonce,
     PatriciaKey,
@@ -54,6 +59,7 @@ use starknet_api::deprecated_contract_class::{
 use starknet_api::hash::{StarkFelt, StarkHash, GENESIS_HASH};
 use starknet_api::state::{ContractClass as StarknetApiContractClass, StateDiff, StorageKey};
 use starknet_api::transaction::{
+    Calldata,
     Event as StarknetApiEvent,
     EventContent,
     EventData,
@@ -65,7 +71,7 @@ use starknet_api::transaction::{
     TransactionOffsetInBlock,
     TransactionOutput as StarknetApiTransactionOutput,
 };
-use starknet_api::{patricia_key, stark_felt};
+use starknet_api::{calldata, patricia_key, stark_felt};
 use starknet_client::reader::objects::pending_data::{
     DeprecatedPendingBlock,
     PendingBlockOrDeprecated,
@@ -163,8 +169,8 @@ use super::super::write_api_result::{
     AddInvokeOkResult,
 };
 use super::api_impl::{JsonRpcServerV0_6Impl as JsonRpcServerImpl, BLOCK_HASH_TABLE_ADDRESS};
-use super::{ContinuationToken, EventFilter, GatewayContractClass};
-use crate::api::{BlockHashOrNumber, BlockId, Tag};
+use super::{ContinuationToken, EventFilter, GatewayContractClass, JsonRpcServer};
+use crate::api::{BlockHashOrNumber, BlockId, CallRequest, Tag};
 use crate::syncing_state::SyncStatus;
 use crate::test_utils::{
     call_api_then_assert_and_validate_schema_for_err,
@@ -172,6 +178,7 @@ use crate::test_utils::{
     get_method_names_from_spec,
     get_starknet_spec_api_schema_for_components,
     get_starknet_spec_api_schema_for_method_results,
+    get_test_continuation_token_key,
     get_test_highest_block,
     get_test_pending_classes,
     get_test_pending_data,
@@ -183,12 +190,14 @@ use crate::test_utils::{
     validate_schema,
     SpecFile,
 };
+use crate::v0_7::error::{invalid_contract_address, invalid_felt};
 use crate::version_config::VERSION_0_6 as VERSION;
 use crate::{
     internal_server_error,
     internal_server_error_with_msg,
     run_server,
     ContinuationTokenAsStruct,
+    RpcSharedState,
 };
 
 const NODE_VERSION: &str = "NODE VERSION";
@@ -434,6 +443,11 @@ async fn get_block_transaction_count() {
     let res = module.call::<_, usize>(method_name, [BlockId::Tag(Tag::Latest)]).await.unwrap();
     assert_eq!(res, transaction_count);
 
+    // The l1_accepted tag was added in v0_7.
+    let err =
+        module.call::<_, usize>(method_name, [BlockId::Tag(Tag::L1Accepted)]).await.unwrap_err();
+    assert_matches!(err, Error::Call(err) if err.code() == InvalidParams.code());
+
     // Ask for pending block
     let pending_transaction_count = 3;
     let mut rng = get_rng();
@@ -1720,6 +1734,71 @@ async fn get_nonce() {
     assert_matches!(err, Error::Call(err) if err == BLOCK_NOT_FOUND.into());
 }
 
+#[tokio::test]
+async fn get_state_from_recent_state_cache() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let header = BlockHeader::default();
+    let diff = get_test_state_diff();
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(header.block_number, &header)
+        .unwrap()
+        .append_state_diff(header.block_number, diff.clone(), IndexMap::new())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    // The cached writes differ from the stored ones, to tell where the answers come from.
+    let (address, storage_entries) = diff.storage_diffs.get_index(0).unwrap();
+    let (key, _) = storage_entries.get_index(0).unwrap();
+    let cached_value = StarkFelt::from(1234_u128);
+    let cached_nonce = Nonce(StarkFelt::from(5678_u128));
+    let cached_class_hash = ClassHash(StarkFelt::from(9012_u128));
+    let cached_diff = StateDiff {
+        storage_diffs: [(*address, [(*key, cached_value)].into())].into(),
+        nonces: [(*address, cached_nonce)].into(),
+        replaced_classes: [(*address, cached_class_hash)].into(),
+        ..Default::default()
+    };
+    let recent_state = Arc::new(RecentStateCache::new(1));
+    recent_state.push_block(header.block_number, BlockStateWrites::from(&cached_diff));
+
+    let config = get_test_rpc_config();
+    let module = JsonRpcServerImpl {
+        chain_id: config.chain_id,
+        execution_config: config.execution_config.try_into().unwrap(),
+        storage_reader,
+        max_events_chunk_size: config.max_events_chunk_size,
+        max_events_keys: config.max_events_keys,
+        starting_block: BlockHashAndNumber::default(),
+        shared_highest_block: get_test_highest_block(),
+        pending_data: get_test_pending_data(),
+        pending_classes: get_test_pending_classes(),
+        recent_state,
+        deferred_class_requests: Arc::default(),
+        execution_pool: Arc::default(),
+        continuation_token_key: get_test_continuation_token_key(),
+        writer_client: Arc::new(MockStarknetWriter::new()),
+    }
+    .into_rpc();
+
+    let block_id = BlockId::HashOrNumber(BlockHashOrNumber::Number(header.block_number));
+    let res = module
+        .call::<_, StarkFelt>("starknet_V0_6_getStorageAt", (*address, *key, block_id))
+        .await
+        .unwrap();
+    assert_eq!(res, cached_value);
+    let res =
+        module.call::<_, Nonce>("starknet_V0_6_getNonce", (block_id, *address)).await.unwrap();
+    assert_eq!(res, cached_nonce);
+    let res = module
+        .call::<_, ClassHash>("starknet_V0_6_getClassHashAt", (block_id, *address))
+        .await
+        .unwrap();
+    assert_eq!(res, cached_class_hash);
+}
+
 #[tokio::test]
 async fn get_storage_at() {
     let method_name = "starknet_V0_6_getStorageAt";
@@ -2494,7 +2573,7 @@ async fn test_get_events(
     pending_block_metadata: Option<BlockMetadata>,
     is_pending_up_to_date: bool,
     mut filter: EventFilter,
-    expected_result_by_index: Vec<(Vec<EventIndex>, Option<ContinuationTokenAsStruct>)>,
+    expected_result_by_index: Vec<(Vec<EventIndex>, Option<EventIndex>)>,
 ) {
     let method_name = "starknet_V0_6_getEvents";
     let pending_data = get_test_pending_data();
@@ -2504,6 +2583,7 @@ async fn test_get_events(
     let mut rng = get_rng();
 
     let mut event_index_to_event = HashMap::<EventIndex, Event>::new();
+    let mut block_hashes = vec![];
     let mut parent_hash = BlockHash(stark_felt!(GENESIS_HASH));
     let mut rw_txn = storage_writer.begin_rw_txn().unwrap();
     for (i, block_metadata) in block_metadatas.iter().enumerate() {
@@ -2511,6 +2591,7 @@ async fn test_get_events(
         let block = block_metadata.generate_block(&mut rng, parent_hash, block_number);
 
         parent_hash = block.header.block_hash;
+        block_hashes.push(block.header.block_hash);
 
         for (i_transaction, (output, transaction_hash)) in block
             .body
@@ -2585,8 +2666,16 @@ async fn test_get_events(
                 .map(|event_index| event_index_to_event.get(event_index).unwrap())
                 .cloned()
                 .collect(),
-            continuation_token: expected_continuation_token
-                .map(|x| ContinuationToken::new(x).unwrap()),
+            continuation_token: expected_continuation_token.map(|event_index| {
+                // Tokens of the pending block hold the hash of the latest accepted block.
+                let block_number = event_index.0.0.0 as usize;
+                let block_hash = block_hashes.get(block_number).or(block_hashes.last()).unwrap();
+                ContinuationToken::new(
+                    ContinuationTokenAsStruct(event_index, *block_hash),
+                    &get_test_continuation_token_key(),
+                )
+                .unwrap()
+            }),
         };
         call_api_then_assert_and_validate_schema_for_result(
             &module,
@@ -2613,7 +2702,7 @@ lazy_static! {
     ];
     static ref EVENT_FILTER_FOR_CHUNK_ACROSS_2_BLOCKS_TEST: EventFilter =
         EventFilter { chunk_size: 3, ..Default::default() };
-    static ref EXPECTED_RESULT_BY_INDEX_FOR_CHUNK_ACROSS_2_BLOCKS_TEST: Vec<(Vec<EventIndex>, Option<ContinuationTokenAsStruct>,)> = vec![
+    static ref EXPECTED_RESULT_BY_INDEX_FOR_CHUNK_ACROSS_2_BLOCKS_TEST: Vec<(Vec<EventIndex>, Option<EventIndex>,)> = vec![
         (
             vec![
                 EventIndex(
@@ -2629,10 +2718,10 @@ lazy_static! {
                     EventIndexInTransactionOutput(0),
                 ),
             ],
-            Some(ContinuationTokenAsStruct(EventIndex(
+            Some(EventIndex(
                 TransactionIndex(BlockNumber(1), TransactionOffsetInBlock(0)),
                 EventIndexInTransactionOutput(1),
-            ))),
+            )),
         ),
         (
             vec![
@@ -2649,10 +2738,10 @@ lazy_static! {
                     EventIndexInTransactionOutput(0),
                 ),
             ],
-            Some(ContinuationTokenAsStruct(EventIndex(
+            Some(EventIndex(
                 TransactionIndex(BlockNumber(1), TransactionOffsetInBlock(1)),
                 EventIndexInTransactionOutput(1),
-            ))),
+            )),
         ),
         (
             vec![EventIndex(
@@ -2797,7 +2886,7 @@ lazy_static! {
         ],
         ..Default::default()
     };
-    static ref EXPECTED_RESULT_BY_INDEX_FOR_KEYS_FILTER_TEST: Vec<(Vec<EventIndex>, Option<ContinuationTokenAsStruct>,)> =
+    static ref EXPECTED_RESULT_BY_INDEX_FOR_KEYS_FILTER_TEST: Vec<(Vec<EventIndex>, Option<EventIndex>,)> =
         vec![(
             vec![
                 EventIndex(
@@ -3057,6 +3146,58 @@ async fn get_events_too_many_keys() {
     .await;
 }
 
+#[tokio::test]
+async fn out_of_range_params() {
+    // The smallest values that aren't a felt and a contract address.
+    const FIELD_PRIME: &str = "0x800000000000011000000000000000000000000000000000000000000000001";
+    const CONTRACT_ADDRESS_UPPER_BOUND: &str =
+        "0x7ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff00";
+    let (module, _) = get_test_rpc_server_and_storage_writer::<JsonRpcServerImpl>();
+    let address = ContractAddress(patricia_key!(CONTRACT_ADDRESS_UPPER_BOUND));
+    let block_id = BlockId::Tag(Tag::Latest);
+    let address_error: ErrorObjectOwned =
+        invalid_contract_address(stark_felt!(CONTRACT_ADDRESS_UPPER_BOUND).to_string()).into();
+    let felt_error: ErrorObjectOwned = invalid_felt(stark_felt!(FIELD_PRIME).to_string()).into();
+
+    let err = module
+        .call::<_, StarkFelt>(
+            "starknet_V0_6_getStorageAt",
+            (address, StorageKey(patricia_key!("0x1")), block_id),
+        )
+        .await
+        .unwrap_err();
+    assert_matches!(err, Error::Call(err) if err == address_error);
+
+    let err =
+        module.call::<_, Nonce>("starknet_V0_6_getNonce", (block_id, address)).await.unwrap_err();
+    assert_matches!(err, Error::Call(err) if err == address_error);
+
+    let err = module
+        .call::<_, ClassHash>("starknet_V0_6_getClassHashAt", (block_id, address))
+        .await
+        .unwrap_err();
+    assert_matches!(err, Error::Call(err) if err == address_error);
+
+    let request = CallRequest {
+        contract_address: ContractAddress(patricia_key!("0x1")),
+        entry_point_selector: EntryPointSelector(stark_felt!("0x2")),
+        calldata: calldata![stark_felt!(FIELD_PRIME)],
+    };
+    let err = module
+        .call::<_, Vec<StarkFelt>>("starknet_V0_6_call", (request, block_id))
+        .await
+        .unwrap_err();
+    assert_matches!(err, Error::Call(err) if err == felt_error);
+
+    let filter = EventFilter {
+        chunk_size: 2,
+        keys: vec![HashSet::from([EventKey(stark_felt!(FIELD_PRIME))])],
+        ..Default::default()
+    };
+    let err = module.call::<_, EventsChunk>("starknet_V0_6_getEvents", [filter]).await.unwrap_err();
+    assert_matches!(err, Error::Call(err) if err == felt_error);
+}
+
 #[tokio::test]
 async fn get_events_invalid_ct() {
     let (module, mut storage_writer) =
@@ -3099,6 +3240,58 @@ async fn get_events_invalid_ct() {
     .await;
 }
 
+#[tokio::test]
+async fn get_events_ct_before_from_block() {
+    let (module, mut storage_writer) =
+        get_test_rpc_server_and_storage_writer::<JsonRpcServerImpl>();
+    let mut rw_txn = storage_writer.begin_rw_txn().unwrap();
+    for block_number in [BlockNumber(0), BlockNumber(1)] {
+        let block_hash = BlockHash((block_number.0 + 1).into());
+        let header = BlockHeader { block_number, block_hash, ..Default::default() };
+        rw_txn = rw_txn
+            .append_header(block_number, &header)
+            .unwrap()
+            .append_body(block_number, starknet_api::block::BlockBody::default())
+            .unwrap()
+            .append_state_diff(
+                block_number,
+                starknet_api::state::StateDiff::default(),
+                IndexMap::new(),
+            )
+            .unwrap();
+    }
+    rw_txn.commit().unwrap();
+
+    // A validly signed token that points to a block before the filter's from_block.
+    let continuation_token = ContinuationToken::new(
+        ContinuationTokenAsStruct(
+            EventIndex(
+                TransactionIndex(BlockNumber(0), TransactionOffsetInBlock(0)),
+                EventIndexInTransactionOutput(0),
+            ),
+            BlockHash(1_u64.into()),
+        ),
+        &get_test_continuation_token_key(),
+    )
+    .unwrap();
+    let filter = EventFilter {
+        from_block: Some(BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(1)))),
+        continuation_token: Some(continuation_token),
+        chunk_size: 2,
+        ..Default::default()
+    };
+
+    call_api_then_assert_and_validate_schema_for_err::<_, EventsChunk>(
+        &module,
+        "starknet_V0_6_getEvents",
+        vec![Box::new(filter)],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &INVALID_CONTINUATION_TOKEN.into(),
+    )
+    .await;
+}
+
 #[tokio::test]
 async fn serialize_returns_valid_json() {
     let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
@@ -3147,9 +3340,12 @@ async fn serialize_returns_valid_json() {
     let gateway_config = get_test_rpc_config();
     let (server_address, _handle) = run_server(
         &gateway_config,
-        get_test_highest_block(),
-        get_test_pending_data(),
-        get_test_pending_classes(),
+        RpcSharedState {
+            shared_highest_block: get_test_highest_block(),
+            pending_data: get_test_pending_data(),
+            pending_classes: get_test_pending_classes(),
+            ..Default::default()
+        },
         storage_reader,
         NODE_VERSION,
     )
@@ -7,9 +7,10 @@ use starknet_api::block::{BlockHash, BlockNumber, BlockStatus, BlockTimestamp, G
 use starknet_api::core::{GlobalRoot, SequencerContractAddress};
 
 use super::error::BLOCK_NOT_FOUND;
-use super::transaction::Transactions;
-use crate::api::{BlockHashOrNumber, BlockId, Tag};
-use crate::{get_latest_block_number, internal_server_error};
+use super::transaction::{StreamedTransactions, Transactions};
+use crate::api::BlockId;
+use crate::block_id::{reject_l1_accepted_tag, resolve_accepted_block_number, RequiredBlockData};
+use crate::internal_server_error;
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize, PartialOrd, Ord)]
 pub struct BlockHeader {
@@ -40,24 +41,6 @@ pub enum GeneralBlockHeader {
     PendingBlockHeader(PendingBlockHeader),
 }
 
-impl From<starknet_api::block::BlockHeader> for BlockHeader {
-    fn from(header: starknet_api::block::BlockHeader) -> Self {
-        BlockHeader {
-            block_hash: header.block_hash,
-            parent_hash: header.parent_hash,
-            block_number: header.block_number,
-            sequencer_address: header.sequencer,
-            new_root: header.state_root,
-            timestamp: header.timestamp,
-            l1_gas_price: ResourcePrice {
-                price_in_wei: header.l1_gas_price.price_in_wei,
-                price_in_fri: header.l1_gas_price.price_in_fri,
-            },
-            starknet_version: header.starknet_version.0,
-        }
-    }
-}
-
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize, PartialOrd, Ord)]
 pub struct ResourcePrice {
     pub price_in_wei: GasPrice,
@@ -73,6 +56,18 @@ pub struct Block {
     pub transactions: Transactions,
 }
 
+/// A block with full transactions. It's serialized exactly like a [`Block`] with
+/// [`Transactions::Full`], but its transactions are converted to their RPC representation only
+/// while the block is serialized.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StreamedBlock {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<BlockStatus>,
+    #[serde(flatten)]
+    pub header: GeneralBlockHeader,
+    pub transactions: StreamedTransactions,
+}
+
 pub fn get_block_header_by_number<Mode: TransactionKind>(
     txn: &StorageTxn<'_, Mode>,
     block_number: BlockNumber,
@@ -87,39 +82,15 @@ pub fn get_block_header_by_number<Mode: TransactionKind>(
 
 /// Return the closest block number that corresponds to the given block id and is accepted (i.e not
 /// pending). Latest block means the most advanced block that we've downloaded and that we've
-/// downloaded its state diff.
+/// downloaded its state diff. The `l1_accepted` tag isn't supported in this version.
 pub(crate) fn get_accepted_block_number<Mode: TransactionKind>(
     txn: &StorageTxn<'_, Mode>,
     block_id: BlockId,
 ) -> Result<BlockNumber, ErrorObjectOwned> {
-    Ok(match block_id {
-        BlockId::HashOrNumber(BlockHashOrNumber::Hash(block_hash)) => {
-            let block_number = txn
-                .get_block_number_by_hash(&block_hash)
-                .map_err(internal_server_error)?
-                .ok_or_else(|| ErrorObjectOwned::from(BLOCK_NOT_FOUND))?;
-
-            // Check that the block has state diff.
-            let last_block_number = get_latest_block_number(txn)?
-                .ok_or_else(|| ErrorObjectOwned::from(BLOCK_NOT_FOUND))?;
-            if block_number > last_block_number {
-                return Err(ErrorObjectOwned::from(BLOCK_NOT_FOUND));
-            }
-            block_number
-        }
-        BlockId::HashOrNumber(BlockHashOrNumber::Number(block_number)) => {
-            // Check that the block exists and has state diff.
-            let last_block_number = get_latest_block_number(txn)?
-                .ok_or_else(|| ErrorObjectOwned::from(BLOCK_NOT_FOUND))?;
-            if block_number > last_block_number {
-                return Err(ErrorObjectOwned::from(BLOCK_NOT_FOUND));
-            }
-            block_number
-        }
-        BlockId::Tag(Tag::Latest | Tag::Pending) => {
-            get_latest_block_number(txn)?.ok_or_else(|| ErrorObjectOwned::from(BLOCK_NOT_FOUND))?
-        }
-    })
+    reject_l1_accepted_tag(block_id)?;
+    resolve_accepted_block_number(txn, block_id, RequiredBlockData::State)
+        .map_err(internal_server_error)?
+        .ok_or_else(|| ErrorObjectOwned::from(BLOCK_NOT_FOUND))
 }
 
 /// Validates that a given block wasn't reverted. Given an instance of this class, we can call its
@@ -180,10 +180,8 @@ impl TryFrom<BroadcastedDeclareTransaction> for client_transaction::DeclareTrans
                     nonce: declare_v3.nonce,
                     compiled_class_hash: declare_v3.compiled_class_hash,
                     sender_address: declare_v3.sender_address,
-                    nonce_data_availability_mode:
-                        client_transaction::ReservedDataAvailabilityMode::Reserved,
-                    fee_data_availability_mode:
-                        client_transaction::ReservedDataAvailabilityMode::Reserved,
+                    nonce_data_availability_mode: declare_v3.nonce_data_availability_mode.into(),
+                    fee_data_availability_mode: declare_v3.fee_data_availability_mode.into(),
                     paymaster_data: declare_v3.paymaster_data,
                     account_deployment_data: declare_v3.account_deployment_data,
                     version: TransactionVersion::THREE,
@@ -10,12 +10,11 @@ use ethers::core::abi::{encode_packed, Token};
 use ethers::core::utils::keccak256;
 use jsonrpsee::types::ErrorObjectOwned;
 use papyrus_execution::objects::PriceUnit;
-use papyrus_storage::body::events::ThinTransactionOutput;
 use papyrus_storage::body::BodyStorageReader;
 use papyrus_storage::db::TransactionKind;
 use papyrus_storage::StorageTxn;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use starknet_api::block::{BlockHash, BlockNumber, BlockStatus};
+use starknet_api::block::{BlockHash, BlockNumber};
 use starknet_api::core::{
     ClassHash,
     CompiledClassHash,
@@ -36,7 +35,6 @@ use starknet_api::transaction::{
     L1HandlerTransaction,
     MessageToL1,
     PaymasterData,
-    Resource,
     ResourceBounds,
     Tip,
     TransactionExecutionStatus,
@@ -48,6 +46,7 @@ use starknet_client::writer::objects::transaction as client_transaction;
 
 use super::error::BLOCK_NOT_FOUND;
 use crate::internal_server_error;
+use crate::streaming::StreamedSeq;
 
 #[derive(
     Debug, Deserialize, Serialize, Default, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord,
@@ -100,6 +99,27 @@ pub enum Transactions {
     Full(Vec<TransactionWithHash>),
 }
 
+/// Full transactions of a block that are converted to their RPC representation only while they're
+/// serialized.
+pub type StreamedTransactions =
+    StreamedSeq<(starknet_api::transaction::Transaction, TransactionHash), TransactionWithHash>;
+
+pub fn stream_transactions_with_hash(
+    transactions: Vec<starknet_api::transaction::Transaction>,
+    transaction_hashes: Vec<TransactionHash>,
+) -> StreamedTransactions {
+    StreamedSeq::new(
+        transactions.into_iter().zip(transaction_hashes).collect(),
+        to_transaction_with_hash,
+    )
+}
+
+fn to_transaction_with_hash(
+    (transaction, transaction_hash): (starknet_api::transaction::Transaction, TransactionHash),
+) -> Result<TransactionWithHash, ErrorObjectOwned> {
+    Ok(TransactionWithHash { transaction: transaction.try_into()?, transaction_hash })
+}
+
 #[derive(Debug, Clone, Default, Eq, PartialEq, Hash, Deserialize, Serialize, PartialOrd, Ord)]
 #[serde(deny_unknown_fields)]
 pub struct DeclareTransactionV0 {
@@ -131,20 +151,6 @@ pub struct DeclareTransactionV2 {
     pub signature: TransactionSignature,
 }
 
-impl From<starknet_api::transaction::DeclareTransactionV2> for DeclareTransactionV2 {
-    fn from(tx: starknet_api::transaction::DeclareTransactionV2) -> Self {
-        Self {
-            class_hash: tx.class_hash,
-            compiled_class_hash: tx.compiled_class_hash,
-            sender_address: tx.sender_address,
-            nonce: tx.nonce,
-            max_fee: tx.max_fee,
-            version: TransactionVersion2::Version2,
-            signature: tx.signature,
-        }
-    }
-}
-
 // The serialization of the struct in SN_API is in capital letters, not following the spec.
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize, PartialOrd, Ord)]
 pub struct ResourceBoundsMapping {
@@ -152,21 +158,6 @@ pub struct ResourceBoundsMapping {
     pub l2_gas: ResourceBounds,
 }
 
-impl From<ResourceBoundsMapping> for starknet_api::transaction::ResourceBoundsMapping {
-    fn from(value: ResourceBoundsMapping) -> Self {
-        Self([(Resource::L1Gas, value.l1_gas), (Resource::L2Gas, value.l2_gas)].into())
-    }
-}
-
-impl From<starknet_api::transaction::ResourceBoundsMapping> for ResourceBoundsMapping {
-    fn from(value: starknet_api::transaction::ResourceBoundsMapping) -> Self {
-        Self {
-            l1_gas: value.0.get(&Resource::L1Gas).cloned().unwrap_or_default(),
-            l2_gas: value.0.get(&Resource::L2Gas).cloned().unwrap_or_default(),
-        }
-    }
-}
-
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize, PartialOrd, Ord)]
 pub struct DeclareTransactionV3 {
     pub resource_bounds: ResourceBoundsMapping,
@@ -183,25 +174,6 @@ pub struct DeclareTransactionV3 {
     pub version: TransactionVersion3,
 }
 
-impl From<starknet_api::transaction::DeclareTransactionV3> for DeclareTransactionV3 {
-    fn from(tx: starknet_api::transaction::DeclareTransactionV3) -> Self {
-        Self {
-            resource_bounds: tx.resource_bounds.into(),
-            tip: tx.tip,
-            signature: tx.signature,
-            nonce: tx.nonce,
-            class_hash: tx.class_hash,
-            compiled_class_hash: tx.compiled_class_hash,
-            sender_address: tx.sender_address,
-            nonce_data_availability_mode: tx.nonce_data_availability_mode,
-            fee_data_availability_mode: tx.fee_data_availability_mode,
-            paymaster_data: tx.paymaster_data,
-            account_deployment_data: tx.account_deployment_data,
-            version: TransactionVersion3::Version3,
-        }
-    }
-}
-
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, PartialOrd, Ord)]
 #[serde(untagged)]
 pub enum DeclareTransaction {
@@ -244,61 +216,6 @@ pub enum DeployAccountTransaction {
     Version3(DeployAccountTransactionV3),
 }
 
-impl TryFrom<starknet_api::transaction::DeployAccountTransaction> for DeployAccountTransaction {
-    type Error = ErrorObjectOwned;
-
-    fn try_from(
-        tx: starknet_api::transaction::DeployAccountTransaction,
-    ) -> Result<Self, Self::Error> {
-        match tx {
-            starknet_api::transaction::DeployAccountTransaction::V1(
-                starknet_api::transaction::DeployAccountTransactionV1 {
-                    max_fee,
-                    signature,
-                    nonce,
-                    class_hash,
-                    contract_address_salt,
-                    constructor_calldata,
-                },
-            ) => Ok(Self::Version1(DeployAccountTransactionV1 {
-                max_fee,
-                signature,
-                nonce,
-                class_hash,
-                contract_address_salt,
-                constructor_calldata,
-                version: TransactionVersion1::Version1,
-            })),
-            starknet_api::transaction::DeployAccountTransaction::V3(
-                starknet_api::transaction::DeployAccountTransactionV3 {
-                    resource_bounds,
-                    signature,
-                    nonce,
-                    class_hash,
-                    contract_address_salt,
-                    constructor_calldata,
-                    tip,
-                    nonce_data_availability_mode,
-                    fee_data_availability_mode,
-                    paymaster_data,
-                },
-            ) => Ok(Self::Version3(DeployAccountTransactionV3 {
-                signature,
-                nonce,
-                class_hash,
-                contract_address_salt,
-                constructor_calldata,
-                version: TransactionVersion3::Version3,
-                resource_bounds: resource_bounds.into(),
-                tip,
-                nonce_data_availability_mode,
-                fee_data_availability_mode,
-                paymaster_data,
-            })),
-        }
-    }
-}
-
 impl From<DeployAccountTransaction> for client_transaction::DeployAccountTransaction {
     fn from(tx: DeployAccountTransaction) -> Self {
         match tx {
@@ -324,10 +241,10 @@ impl From<DeployAccountTransaction> for client_transaction::DeployAccountTransac
                     version: TransactionVersion::THREE,
                     resource_bounds: deploy_account_tx.resource_bounds.into(),
                     tip: deploy_account_tx.tip,
-                    nonce_data_availability_mode:
-                        client_transaction::ReservedDataAvailabilityMode::Reserved,
-                    fee_data_availability_mode:
-                        client_transaction::ReservedDataAvailabilityMode::Reserved,
+                    nonce_data_availability_mode: deploy_account_tx
+                        .nonce_data_availability_mode
+                        .into(),
+                    fee_data_availability_mode: deploy_account_tx.fee_data_availability_mode.into(),
                     paymaster_data: deploy_account_tx.paymaster_data,
                     r#type: client_transaction::DeployAccountType::DeployAccount,
                 })
@@ -409,9 +326,8 @@ impl From<InvokeTransactionV3> for client_transaction::InvokeTransaction {
             nonce: tx.nonce,
             resource_bounds: tx.resource_bounds.into(),
             tip: tx.tip,
-            nonce_data_availability_mode:
-                client_transaction::ReservedDataAvailabilityMode::Reserved,
-            fee_data_availability_mode: client_transaction::ReservedDataAvailabilityMode::Reserved,
+            nonce_data_availability_mode: tx.nonce_data_availability_mode.into(),
+            fee_data_availability_mode: tx.fee_data_availability_mode.into(),
             paymaster_data: tx.paymaster_data,
             account_deployment_data: tx.account_deployment_data,
             r#type: client_transaction::InvokeType::Invoke,
@@ -437,73 +353,6 @@ pub enum InvokeTransaction {
     Version3(InvokeTransactionV3),
 }
 
-impl TryFrom<starknet_api::transaction::InvokeTransaction> for InvokeTransaction {
-    type Error = ErrorObjectOwned;
-
-    fn try_from(tx: starknet_api::transaction::InvokeTransaction) -> Result<Self, Self::Error> {
-        match tx {
-            starknet_api::transaction::InvokeTransaction::V0(
-                starknet_api::transaction::InvokeTransactionV0 {
-                    max_fee,
-                    signature,
-                    contract_address,
-                    entry_point_selector,
-                    calldata,
-                },
-            ) => Ok(Self::Version0(InvokeTransactionV0 {
-                max_fee,
-                version: TransactionVersion0::Version0,
-                signature,
-                contract_address,
-                entry_point_selector,
-                calldata,
-            })),
-            starknet_api::transaction::InvokeTransaction::V1(
-                starknet_api::transaction::InvokeTransactionV1 {
-                    max_fee,
-                    signature,
-                    nonce,
-                    sender_address,
-                    calldata,
-                },
-            ) => Ok(Self::Version1(InvokeTransactionV1 {
-                max_fee,
-                version: TransactionVersion1::Version1,
-                signature,
-                nonce,
-                sender_address,
-                calldata,
-            })),
-            starknet_api::transaction::InvokeTransaction::V3(
-                starknet_api::transaction::InvokeTransactionV3 {
-                    resource_bounds,
-                    tip,
-                    signature,
-                    nonce,
-                    sender_address,
-                    calldata,
-                    nonce_data_availability_mode,
-                    fee_data_availability_mode,
-                    paymaster_data,
-                    account_deployment_data,
-                },
-            ) => Ok(Self::Version3(InvokeTransactionV3 {
-                sender_address,
-                calldata,
-                version: TransactionVersion3::Version3,
-                signature,
-                nonce,
-                resource_bounds: resource_bounds.into(),
-                tip,
-                nonce_data_availability_mode,
-                fee_data_availability_mode,
-                paymaster_data,
-                account_deployment_data,
-            })),
-        }
-    }
-}
-
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize, PartialOrd, Ord)]
 pub struct TransactionWithHash {
     pub transaction_hash: TransactionHash,
@@ -526,55 +375,6 @@ pub enum Transaction {
     L1Handler(L1HandlerTransaction),
 }
 
-impl TryFrom<starknet_api::transaction::Transaction> for Transaction {
-    type Error = ErrorObjectOwned;
-
-    fn try_from(tx: starknet_api::transaction::Transaction) -> Result<Self, Self::Error> {
-        match tx {
-            starknet_api::transaction::Transaction::Declare(declare_tx) => match declare_tx {
-                starknet_api::transaction::DeclareTransaction::V0(tx) => {
-                    Ok(Self::Declare(DeclareTransaction::Version0(DeclareTransactionV0 {
-                        class_hash: tx.class_hash,
-                        sender_address: tx.sender_address,
-                        nonce: tx.nonce,
-                        max_fee: tx.max_fee,
-                        version: TransactionVersion0::Version0,
-                        signature: tx.signature,
-                    })))
-                }
-                starknet_api::transaction::DeclareTransaction::V1(tx) => {
-                    Ok(Self::Declare(DeclareTransaction::Version1(DeclareTransactionV1 {
-                        class_hash: tx.class_hash,
-                        sender_address: tx.sender_address,
-                        nonce: tx.nonce,
-                        max_fee: tx.max_fee,
-                        version: TransactionVersion1::Version1,
-                        signature: tx.signature,
-                    })))
-                }
-                starknet_api::transaction::DeclareTransaction::V2(tx) => {
-                    Ok(Self::Declare(DeclareTransaction::Version2(tx.into())))
-                }
-                starknet_api::transaction::DeclareTransaction::V3(tx) => {
-                    Ok(Self::Declare(DeclareTransaction::Version3(tx.into())))
-                }
-            },
-            starknet_api::transaction::Transaction::Deploy(deploy_tx) => {
-                Ok(Transaction::Deploy(deploy_tx))
-            }
-            starknet_api::transaction::Transaction::DeployAccount(deploy_account_tx) => {
-                Ok(Self::DeployAccount(deploy_account_tx.try_into()?))
-            }
-            starknet_api::transaction::Transaction::Invoke(invoke_tx) => {
-                Ok(Self::Invoke(invoke_tx.try_into()?))
-            }
-            starknet_api::transaction::Transaction::L1Handler(l1_handler_tx) => {
-                Ok(Transaction::L1Handler(l1_handler_tx))
-            }
-        }
-    }
-}
-
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize, PartialOrd, Ord, Default)]
 pub struct TransactionStatus {
     pub finality_status: TransactionFinalityStatus,
@@ -605,20 +405,6 @@ pub enum PendingTransactionFinalityStatus {
     AcceptedOnL2,
 }
 
-impl From<BlockStatus> for TransactionFinalityStatus {
-    fn from(status: BlockStatus) -> Self {
-        match status {
-            BlockStatus::AcceptedOnL1 => TransactionFinalityStatus::AcceptedOnL1,
-            BlockStatus::AcceptedOnL2 => TransactionFinalityStatus::AcceptedOnL2,
-            BlockStatus::Pending => TransactionFinalityStatus::AcceptedOnL2, /* for backward compatibility pending transactions are considered accepted on L2 */
-            // we convert the block status to transaction status only in the creation of
-            // TransactionReceiptWithStatus before that we verify that the block is not
-            // rejected so this conversion should never happen
-            BlockStatus::Rejected => unreachable!("Rejected blocks are not returned by the API"),
-        }
-    }
-}
-
 #[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum GeneralTransactionReceipt {
@@ -760,21 +546,6 @@ pub enum Builtin {
     SegmentArena,
 }
 
-impl From<starknet_api::transaction::Builtin> for Builtin {
-    fn from(builtin: starknet_api::transaction::Builtin) -> Self {
-        match builtin {
-            starknet_api::transaction::Builtin::RangeCheck => Builtin::RangeCheck,
-            starknet_api::transaction::Builtin::Pedersen => Builtin::Pedersen,
-            starknet_api::transaction::Builtin::Poseidon => Builtin::Poseidon,
-            starknet_api::transaction::Builtin::EcOp => Builtin::EcOp,
-            starknet_api::transaction::Builtin::Ecdsa => Builtin::Ecdsa,
-            starknet_api::transaction::Builtin::Bitwise => Builtin::Bitwise,
-            starknet_api::transaction::Builtin::Keccak => Builtin::Keccak,
-            starknet_api::transaction::Builtin::SegmentArena => Builtin::SegmentArena,
-        }
-    }
-}
-
 // Note: This is not the same as the ExecutionResources in starknet_api, will be the same in V0.6.
 #[derive(Debug, Clone, Default, Eq, PartialEq, Deserialize, Serialize)]
 pub struct ExecutionResources {
@@ -785,26 +556,6 @@ pub struct ExecutionResources {
     pub memory_holes: Option<u64>,
 }
 
-impl From<starknet_api::transaction::ExecutionResources> for ExecutionResources {
-    fn from(value: starknet_api::transaction::ExecutionResources) -> Self {
-        Self {
-            steps: value.steps,
-            builtin_instance_counter: value
-                .builtin_instance_counter
-                .into_iter()
-                .filter_map(|(k, v)| match v {
-                    0 => None,
-                    _ => Some((k.into(), v)),
-                })
-                .collect(),
-            memory_holes: match value.memory_holes {
-                0 => None,
-                _ => Some(value.memory_holes),
-            },
-        }
-    }
-}
-
 #[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
 #[serde(tag = "type")]
 // Applying deny_unknown_fields on the inner type instead of on PendingTransactionReceipt because
@@ -843,149 +594,6 @@ impl TransactionOutput {
             TransactionOutput::L1Handler(tx_output) => &tx_output.execution_status,
         }
     }
-
-    pub fn from_thin_transaction_output(
-        thin_tx_output: ThinTransactionOutput,
-        tx_version: TransactionVersion,
-        events: Vec<starknet_api::transaction::Event>,
-        message_hash: Option<L1L2MsgHash>,
-    ) -> Self {
-        let actual_fee = match tx_version {
-            TransactionVersion::ZERO | TransactionVersion::ONE | TransactionVersion::TWO => {
-                FeePayment { amount: thin_tx_output.actual_fee(), unit: PriceUnit::Wei }
-            }
-            TransactionVersion::THREE => {
-                FeePayment { amount: thin_tx_output.actual_fee(), unit: PriceUnit::Fri }
-            }
-            _ => unreachable!("Invalid transaction version."),
-        };
-        match thin_tx_output {
-            ThinTransactionOutput::Declare(thin_declare) => {
-                TransactionOutput::Declare(DeclareTransactionOutput {
-                    actual_fee,
-                    messages_sent: thin_declare.messages_sent,
-                    events,
-                    execution_status: thin_declare.execution_status,
-                    execution_resources: thin_declare.execution_resources.into(),
-                })
-            }
-            ThinTransactionOutput::Deploy(thin_deploy) => {
-                TransactionOutput::Deploy(DeployTransactionOutput {
-                    actual_fee,
-                    messages_sent: thin_deploy.messages_sent,
-                    events,
-                    contract_address: thin_deploy.contract_address,
-                    execution_status: thin_deploy.execution_status,
-                    execution_resources: thin_deploy.execution_resources.into(),
-                })
-            }
-            ThinTransactionOutput::DeployAccount(thin_deploy) => {
-                TransactionOutput::DeployAccount(DeployAccountTransactionOutput {
-                    actual_fee,
-                    messages_sent: thin_deploy.messages_sent,
-                    events,
-                    contract_address: thin_deploy.contract_address,
-                    execution_status: thin_deploy.execution_status,
-                    execution_resources: thin_deploy.execution_resources.into(),
-                })
-            }
-            ThinTransactionOutput::Invoke(thin_invoke) => {
-                TransactionOutput::Invoke(InvokeTransactionOutput {
-                    actual_fee,
-                    messages_sent: thin_invoke.messages_sent,
-                    events,
-                    execution_status: thin_invoke.execution_status,
-                    execution_resources: thin_invoke.execution_resources.into(),
-                })
-            }
-            ThinTransactionOutput::L1Handler(thin_l1handler) => {
-                TransactionOutput::L1Handler(L1HandlerTransactionOutput {
-                    actual_fee,
-                    messages_sent: thin_l1handler.messages_sent,
-                    events,
-                    execution_status: thin_l1handler.execution_status,
-                    execution_resources: thin_l1handler.execution_resources.into(),
-                    message_hash: message_hash
-                        .expect("Missing message hash to construct L1Handler output."),
-                })
-            }
-        }
-    }
-}
-
-impl From<(starknet_api::transaction::TransactionOutput, TransactionVersion, Option<L1L2MsgHash>)>
-    for TransactionOutput
-{
-    #[cfg_attr(coverage_nightly, coverage_attribute)]
-    fn from(
-        tx_output_msg_hash: (
-            starknet_api::transaction::TransactionOutput,
-            TransactionVersion,
-            Option<L1L2MsgHash>,
-        ),
-    ) -> Self {
-        let (tx_output, tx_version, maybe_msg_hash) = tx_output_msg_hash;
-        let actual_fee = match tx_version {
-            TransactionVersion::ZERO | TransactionVersion::ONE | TransactionVersion::TWO => {
-                FeePayment { amount: tx_output.actual_fee(), unit: PriceUnit::Wei }
-            }
-            TransactionVersion::THREE => {
-                FeePayment { amount: tx_output.actual_fee(), unit: PriceUnit::Fri }
-            }
-            _ => unreachable!("Invalid transaction version."),
-        };
-        match tx_output {
-            starknet_api::transaction::TransactionOutput::Declare(declare_tx_output) => {
-                TransactionOutput::Declare(DeclareTransactionOutput {
-                    actual_fee,
-                    messages_sent: declare_tx_output.messages_sent,
-                    events: declare_tx_output.events,
-                    execution_status: declare_tx_output.execution_status,
-                    execution_resources: declare_tx_output.execution_resources.into(),
-                })
-            }
-            starknet_api::transaction::TransactionOutput::Deploy(deploy_tx_output) => {
-                TransactionOutput::Deploy(DeployTransactionOutput {
-                    actual_fee,
-                    messages_sent: deploy_tx_output.messages_sent,
-                    events: deploy_tx_output.events,
-                    contract_address: deploy_tx_output.contract_address,
-                    execution_status: deploy_tx_output.execution_status,
-                    execution_resources: deploy_tx_output.execution_resources.into(),
-                })
-            }
-            starknet_api::transaction::TransactionOutput::DeployAccount(deploy_tx_output) => {
-                TransactionOutput::DeployAccount(DeployAccountTransactionOutput {
-                    actual_fee,
-                    messages_sent: deploy_tx_output.messages_sent,
-                    events: deploy_tx_output.events,
-                    contract_address: deploy_tx_output.contract_address,
-                    execution_status: deploy_tx_output.execution_status,
-                    execution_resources: deploy_tx_output.execution_resources.into(),
-                })
-            }
-            starknet_api::transaction::TransactionOutput::Invoke(invoke_tx_output) => {
-                TransactionOutput::Invoke(InvokeTransactionOutput {
-                    actual_fee,
-                    messages_sent: invoke_tx_output.messages_sent,
-                    events: invoke_tx_output.events,
-                    execution_status: invoke_tx_output.execution_status,
-                    execution_resources: invoke_tx_output.execution_resources.into(),
-                })
-            }
-            starknet_api::transaction::TransactionOutput::L1Handler(l1_handler_tx_output) => {
-                TransactionOutput::L1Handler(L1HandlerTransactionOutput {
-                    actual_fee,
-                    messages_sent: l1_handler_tx_output.messages_sent,
-                    events: l1_handler_tx_output.events,
-                    execution_status: l1_handler_tx_output.execution_status,
-                    execution_resources: l1_handler_tx_output.execution_resources.into(),
-                    message_hash: maybe_msg_hash
-                        .expect("Missing message hash to construct L1Handler output."),
-                })
-            }
-        }
-    }
 }
 
 impl TryFrom<TransactionOutput> for PendingTransactionOutput {
@@ -1029,19 +637,17 @@ pub struct Event {
     pub event: starknet_api::transaction::Event,
 }
 
-pub fn get_block_txs_by_number<
-    Mode: TransactionKind,
-    Transaction: TryFrom<starknet_api::transaction::Transaction, Error = ErrorObjectOwned>,
->(
+pub fn get_block_streamed_txs_by_number<Mode: TransactionKind>(
     txn: &StorageTxn<'_, Mode>,
     block_number: BlockNumber,
-) -> Result<Vec<Transaction>, ErrorObjectOwned> {
+) -> Result<StreamedTransactions, ErrorObjectOwned> {
     let transactions = txn
         .get_block_transactions(block_number)
         .map_err(internal_server_error)?
         .ok_or_else(|| ErrorObjectOwned::from(BLOCK_NOT_FOUND))?;
+    let transaction_hashes = get_block_tx_hashes_by_number(txn, block_number)?;
 
-    transactions.into_iter().map(Transaction::try_from).collect()
+    Ok(stream_transactions_with_hash(transactions, transaction_hashes))
 }
 
 pub fn get_block_tx_hashes_by_number<Mode: TransactionKind>(
@@ -2,10 +2,12 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use jsonrpsee::core::RpcResult;
-use jsonrpsee::types::ErrorObjectOwned;
+use jsonrpsee::types::{ErrorObjectOwned, Params};
 use jsonrpsee::RpcModule;
 use lazy_static::lazy_static;
+use papyrus_common::deferred_classes::DeferredClassRequests;
 use papyrus_common::pending_classes::{PendingClasses, PendingClassesTrait};
+use papyrus_common::recent_state::RecentStateCache;
 use papyrus_execution::objects::{PendingData as ExecutionPendingData, TransactionTrace};
 use papyrus_execution::{
     estimate_fee as exec_estimate_fee,
@@ -15,11 +17,11 @@ use papyrus_execution::{
     ExecutableTransactionInput,
     ExecutionConfigByBlock,
 };
-use papyrus_storage::body::events::{EventIndex, EventsReader};
+use papyrus_storage::body::events::{EventIndex, EventsReader, ThinTransactionOutput};
 use papyrus_storage::body::{BodyStorageReader, TransactionIndex};
 use papyrus_storage::db::TransactionKind;
 use papyrus_storage::state::StateStorageReader;
-use papyrus_storage::{StorageError, StorageReader, StorageTxn};
+use papyrus_storage::{StorageError, StorageReader, StorageResult, StorageTxn};
 use starknet_api::block::{BlockHash, BlockNumber, BlockStatus};
 use starknet_api::core::{ChainId, ClassHash, ContractAddress, GlobalRoot, Nonce};
 use starknet_api::hash::{StarkFelt, StarkHash, GENESIS_HASH};
@@ -37,21 +39,27 @@ use starknet_client::reader::objects::pending_data::{
     PendingBlockOrDeprecated,
     PendingStateUpdate as ClientPendingStateUpdate,
 };
+use starknet_client::reader::objects::transaction::{
+    Transaction as ClientTransaction,
+    TransactionReceipt as ClientTransactionReceipt,
+};
 use starknet_client::reader::PendingData;
-use starknet_client::writer::{StarknetWriter, WriterClientError};
-use starknet_client::ClientError;
+use starknet_client::writer::objects::transaction::DeclareTransaction as ClientDeclareTransaction;
+use starknet_client::writer::StarknetWriter;
 use tokio::sync::RwLock;
 use tracing::{instrument, trace, warn};
 
 use super::super::block::{
     get_accepted_block_number,
+    get_accepted_block_number_with_body,
     get_block_header_by_number,
     Block,
     BlockHeader,
     BlockNotRevertedValidator,
+    BlockWithReceipts,
     GeneralBlockHeader,
     PendingBlockHeader,
-    ResourcePrice,
+    StreamedBlock,
 };
 use super::super::broadcasted_transaction::{
     BroadcastedDeclareTransaction,
@@ -64,17 +72,24 @@ use super::super::error::{
     BLOCK_NOT_FOUND,
     CLASS_HASH_NOT_FOUND,
     CONTRACT_NOT_FOUND,
+    INVALID_CONTINUATION_TOKEN,
     INVALID_TRANSACTION_HASH,
     INVALID_TRANSACTION_INDEX,
     NO_BLOCKS,
-    PAGE_SIZE_TOO_BIG,
-    TOO_MANY_KEYS_IN_FILTER,
+    STALE_CONTINUATION_TOKEN,
     TRANSACTION_HASH_NOT_FOUND,
 };
-use super::super::state::{AcceptedStateUpdate, PendingStateUpdate, StateUpdate};
+use super::super::fee_estimate_cache::{serialize_transactions, FeeEstimateCache, FeeEstimateKey};
+use super::super::state::{
+    PendingStateUpdate,
+    StreamedAcceptedStateUpdate,
+    StreamedStateUpdate,
+};
+use super::super::submitted_transactions::SubmittedTransactions;
 use super::super::transaction::{
+    get_block_streamed_txs_by_number,
     get_block_tx_hashes_by_number,
-    get_block_txs_by_number,
+    stream_transactions_with_hash,
     Event,
     GeneralTransactionReceipt,
     L1HandlerMsgHash,
@@ -82,6 +97,8 @@ use super::super::transaction::{
     PendingTransactionFinalityStatus,
     PendingTransactionOutput,
     PendingTransactionReceipt,
+    Transaction,
+    TransactionAndReceipt,
     TransactionOutput,
     TransactionReceipt,
     TransactionStatus,
@@ -94,6 +111,7 @@ use super::super::write_api_error::{
     starknet_error_to_declare_error,
     starknet_error_to_deploy_account_error,
     starknet_error_to_invoke_error,
+    writer_client_error_to_rpc_error,
 };
 use super::super::write_api_result::{
     AddDeclareOkResult,
@@ -116,14 +134,29 @@ use super::{
     SimulationFlag,
     TransactionTraceWithHash,
 };
-use crate::api::{BlockHashOrNumber, JsonRpcServerImpl, Tag};
+use crate::api::{BlockHashOrNumber, ContinuationTokenKey, JsonRpcServerImpl, Tag};
+use crate::execution_pool::ExecutionPool;
+use crate::execution_utils::{download_class_if_deferred, execute_with_deferred_classes};
 use crate::pending::client_pending_data_to_execution_pending_data;
+use crate::streaming::{single_param, StreamedMethods, StreamedResult};
 use crate::syncing_state::{get_last_synced_block, SyncStatus, SyncingState};
+use crate::trace_cache::{BlockTraces, TraceCache};
+use crate::validation::{
+    validate_call_request,
+    validate_contract_address,
+    validate_declare_transaction,
+    validate_deploy_account_transaction,
+    validate_event_filter,
+    validate_invoke_transaction,
+};
 use crate::version_config::VERSION_0_7 as VERSION;
 use crate::{
     get_block_status,
     get_latest_block_number,
     internal_server_error,
+    read_storage,
+    verify_receipts_synced,
+    verify_state_queryable,
     verify_storage_scope,
     ContinuationTokenAsStruct,
 };
@@ -144,7 +177,14 @@ pub struct JsonRpcServerV0_7Impl {
     pub shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
     pub pending_data: Arc<RwLock<PendingData>>,
     pub pending_classes: Arc<RwLock<PendingClasses>>,
+    pub recent_state: Arc<RecentStateCache>,
+    pub deferred_class_requests: Arc<DeferredClassRequests>,
+    pub execution_pool: Arc<ExecutionPool>,
+    pub continuation_token_key: ContinuationTokenKey,
     pub writer_client: Arc<dyn StarknetWriter>,
+    pub submitted_transactions: Arc<SubmittedTransactions>,
+    pub fee_estimate_cache: Arc<FeeEstimateCache>,
+    pub trace_cache: Arc<TraceCache>,
 }
 
 #[async_trait]
@@ -174,20 +214,10 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
     async fn get_block_w_transaction_hashes(&self, block_id: BlockId) -> RpcResult<Block> {
         verify_storage_scope(&self.storage_reader)?;
 
-        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
         if let BlockId::Tag(Tag::Pending) = block_id {
+            let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
             let block = read_pending_data(&self.pending_data, &txn).await?.block;
-            let pending_block_header = PendingBlockHeader {
-                parent_hash: block.parent_block_hash(),
-                sequencer_address: block.sequencer_address(),
-                timestamp: block.timestamp(),
-                l1_gas_price: ResourcePrice {
-                    price_in_wei: block.l1_gas_price().price_in_wei,
-                    price_in_fri: block.l1_gas_price().price_in_fri,
-                },
-                starknet_version: block.starknet_version(),
-            };
-            let header = GeneralBlockHeader::PendingBlockHeader(pending_block_header);
+            let header = GeneralBlockHeader::PendingBlockHeader(PendingBlockHeader::from(&block));
             let client_transactions = block.transactions();
             let transaction_hashes = client_transactions
                 .iter()
@@ -200,80 +230,148 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
             });
         }
 
-        let block_number = get_accepted_block_number(&txn, block_id)?;
-        let status = get_block_status(&txn, block_number)?;
-        let header =
-            GeneralBlockHeader::BlockHeader(get_block_header_by_number(&txn, block_number)?.into());
-        let transaction_hashes = get_block_tx_hashes_by_number(&txn, block_number)?;
-
-        Ok(Block {
-            status: Some(status),
-            header,
-            transactions: Transactions::Hashes(transaction_hashes),
+        read_storage(&self.storage_reader, move |txn| {
+            let block_number = get_accepted_block_number_with_body(txn, block_id)?;
+            let status = get_block_status(txn, block_number)?;
+            let header = GeneralBlockHeader::BlockHeader(
+                get_block_header_by_number(txn, block_number)?.into(),
+            );
+            let transaction_hashes = get_block_tx_hashes_by_number(txn, block_number)?;
+
+            Ok(Block {
+                status: Some(status),
+                header,
+                transactions: Transactions::Hashes(transaction_hashes),
+            })
         })
+        .await
     }
 
     #[instrument(skip(self), level = "debug", err, ret)]
-    async fn get_block_w_full_transactions(&self, block_id: BlockId) -> RpcResult<Block> {
+    async fn get_block_w_full_transactions(&self, block_id: BlockId) -> RpcResult<StreamedBlock> {
         verify_storage_scope(&self.storage_reader)?;
 
-        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
         if let BlockId::Tag(Tag::Pending) = block_id {
+            let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
             let block = read_pending_data(&self.pending_data, &txn).await?.block;
-            let pending_block_header = PendingBlockHeader {
-                parent_hash: block.parent_block_hash(),
-                sequencer_address: block.sequencer_address(),
-                timestamp: block.timestamp(),
-                l1_gas_price: ResourcePrice {
-                    price_in_wei: block.l1_gas_price().price_in_wei,
-                    price_in_fri: block.l1_gas_price().price_in_fri,
-                },
-                starknet_version: block.starknet_version(),
-            };
-            let header = GeneralBlockHeader::PendingBlockHeader(pending_block_header);
+            let header = GeneralBlockHeader::PendingBlockHeader(PendingBlockHeader::from(&block));
             let client_transactions = block.transactions();
             let transactions = client_transactions
                 .iter()
                 .map(|client_transaction| {
-                    let starknet_api_transaction: StarknetApiTransaction =
-                        client_transaction.clone().try_into().map_err(internal_server_error)?;
-                    Ok(TransactionWithHash {
-                        transaction: starknet_api_transaction
-                            .try_into()
-                            .map_err(internal_server_error)?,
-                        transaction_hash: client_transaction.transaction_hash(),
-                    })
+                    client_transaction.clone().try_into().map_err(internal_server_error)
                 })
-                .collect::<Result<Vec<_>, ErrorObjectOwned>>()?;
-            return Ok(Block {
+                .collect::<Result<Vec<StarknetApiTransaction>, ErrorObjectOwned>>()?;
+            let transaction_hashes = client_transactions
+                .iter()
+                .map(|client_transaction| client_transaction.transaction_hash())
+                .collect();
+            return Ok(StreamedBlock {
                 status: None,
                 header,
-                transactions: Transactions::Full(transactions),
+                transactions: stream_transactions_with_hash(transactions, transaction_hashes),
             });
         }
 
-        let block_number = get_accepted_block_number(&txn, block_id)?;
-        let status = get_block_status(&txn, block_number)?;
-        let header =
-            GeneralBlockHeader::BlockHeader(get_block_header_by_number(&txn, block_number)?.into());
-        // TODO(dvir): consider create a vector of (transaction, transaction_index) first and get
-        // the transaction hashes by the index.
-        let transactions = get_block_txs_by_number(&txn, block_number)?;
-        let transaction_hashes = get_block_tx_hashes_by_number(&txn, block_number)?;
-        let transactions_with_hash = transactions
-            .into_iter()
-            .zip(transaction_hashes)
-            .map(|(transaction, transaction_hash)| TransactionWithHash {
-                transaction,
-                transaction_hash,
-            })
-            .collect();
+        read_storage(&self.storage_reader, move |txn| {
+            let block_number = get_accepted_block_number_with_body(txn, block_id)?;
+            let status = get_block_status(txn, block_number)?;
+            let header = GeneralBlockHeader::BlockHeader(
+                get_block_header_by_number(txn, block_number)?.into(),
+            );
+            // The transactions are converted to their RPC representation while the response is
+            // serialized, so that big blocks aren't held in memory in both representations.
+            let transactions = get_block_streamed_txs_by_number(txn, block_number)?;
 
-        Ok(Block {
-            status: Some(status),
-            header,
-            transactions: Transactions::Full(transactions_with_hash),
+            Ok(StreamedBlock { status: Some(status), header, transactions })
         })
+        .await
+    }
+
+    #[instrument(skip(self), level = "debug", err, ret)]
+    async fn get_block_w_receipts(&self, block_id: BlockId) -> RpcResult<BlockWithReceipts> {
+        verify_storage_scope(&self.storage_reader)?;
+
+        if let BlockId::Tag(Tag::Pending) = block_id {
+            let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+            let block = read_pending_data(&self.pending_data, &txn).await?.block;
+            let header = GeneralBlockHeader::PendingBlockHeader(PendingBlockHeader::from(&block));
+            // The receipts of the pending block are ordered like its transactions.
+            let transactions = block
+                .transactions()
+                .iter()
+                .zip(block.transaction_receipts())
+                .map(|(client_transaction, client_transaction_receipt)| {
+                    let transaction: StarknetApiTransaction =
+                        client_transaction.clone().try_into().map_err(internal_server_error)?;
+                    Ok(TransactionAndReceipt {
+                        transaction: transaction.try_into()?,
+                        receipt: GeneralTransactionReceipt::PendingTransactionReceipt(
+                            pending_transaction_receipt(
+                                client_transaction,
+                                client_transaction_receipt.clone(),
+                            )?,
+                        ),
+                    })
+                })
+                .collect::<RpcResult<Vec<_>>>()?;
+            return Ok(BlockWithReceipts { status: None, header, transactions });
+        }
+
+        // The block and its receipts are read in a single transaction, so that they're consistent
+        // even if the block is reverted meanwhile.
+        read_storage(&self.storage_reader, move |txn| {
+            let block_number = get_accepted_block_number_with_body(txn, block_id)?;
+            verify_receipts_synced(txn, block_number)?;
+            let status = get_block_status(txn, block_number)?;
+            let header = get_block_header_by_number(txn, block_number)?;
+            let block_hash = header.block_hash;
+            let block_transactions = txn
+                .get_block_transactions(block_number)
+                .map_err(internal_server_error)?
+                .ok_or_else(|| ErrorObjectOwned::from(BLOCK_NOT_FOUND))?;
+            let transaction_hashes = get_block_tx_hashes_by_number(txn, block_number)?;
+            let thin_tx_outputs = txn
+                .get_block_transaction_outputs(block_number)
+                .map_err(internal_server_error)?
+                .ok_or_else(|| ErrorObjectOwned::from(BLOCK_NOT_FOUND))?;
+
+            let transactions = block_transactions
+                .into_iter()
+                .zip(transaction_hashes)
+                .zip(thin_tx_outputs)
+                .enumerate()
+                .map(|(offset, ((transaction, transaction_hash), thin_tx_output))| {
+                    let transaction_index =
+                        TransactionIndex(block_number, TransactionOffsetInBlock(offset));
+                    let output = get_transaction_output(
+                        txn,
+                        transaction_index,
+                        &transaction,
+                        thin_tx_output,
+                    )?;
+                    Ok(TransactionAndReceipt {
+                        transaction: transaction.try_into()?,
+                        receipt: GeneralTransactionReceipt::TransactionReceipt(
+                            TransactionReceipt {
+                                finality_status: status.into(),
+                                transaction_hash,
+                                block_hash,
+                                block_number,
+                                output,
+                            },
+                        ),
+                    })
+                })
+                .collect::<RpcResult<Vec<_>>>()?;
+
+            Ok(BlockWithReceipts {
+                status: Some(status),
+                header: GeneralBlockHeader::BlockHeader(header.into()),
+                transactions,
+            })
+        })
+        .await
     }
 
     #[instrument(skip(self), level = "debug", err, ret)]
@@ -283,6 +381,7 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
         key: StorageKey,
         block_id: BlockId,
     ) -> RpcResult<StarkFelt> {
+        validate_contract_address(&contract_address)?;
         let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
         let maybe_pending_storage_diffs = if let BlockId::Tag(Tag::Pending) = block_id {
             Some(
@@ -299,14 +398,28 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
         // Check that the block is valid and get the state number.
         let block_number = get_accepted_block_number(&txn, block_id)?;
         let state_number = StateNumber::right_after_block(block_number);
-        let res = execution_utils::get_storage_at(
-            &txn,
-            state_number,
-            maybe_pending_storage_diffs.as_ref(),
-            contract_address,
-            key,
-        )
-        .map_err(internal_server_error)?;
+        verify_state_queryable(&txn, state_number)?;
+        // Values written in the pending block take precedence over the recent state cache.
+        let written_in_pending = maybe_pending_storage_diffs
+            .as_ref()
+            .and_then(|storage_diffs| storage_diffs.get(&contract_address))
+            .is_some_and(|storage_entries| storage_entries.iter().any(|entry| entry.key == key));
+        let cached_value = if written_in_pending {
+            None
+        } else {
+            self.recent_state.get_storage_at(block_number, contract_address, key)
+        };
+        let res = match cached_value {
+            Some(value) => value,
+            None => execution_utils::get_storage_at(
+                &txn,
+                state_number,
+                maybe_pending_storage_diffs.as_ref(),
+                contract_address,
+                key,
+            )
+            .map_err(internal_server_error)?,
+        };
 
         // If the contract is not deployed, res will be 0. Checking if that's the case so that
         // we'll return an error instead.
@@ -342,16 +455,26 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
 
             Ok(TransactionWithHash { transaction: transaction.try_into()?, transaction_hash })
         } else {
-            // The transaction is not in any non-pending block. Search for it in the pending block
-            // and if it's not found, return error.
-            let client_transaction = read_pending_data(&self.pending_data, &txn)
+            // The transaction is not in any non-pending block. Search for it in the pending block,
+            // then in the transactions submitted through this node, and if it's not found, return
+            // error.
+            let Some(client_transaction) = read_pending_data(&self.pending_data, &txn)
                 .await?
                 .block
                 .transactions()
                 .iter()
                 .find(|transaction| transaction.transaction_hash() == transaction_hash)
-                .ok_or_else(|| ErrorObjectOwned::from(TRANSACTION_HASH_NOT_FOUND))?
-                .clone();
+                .cloned()
+            else {
+                let transaction = self
+                    .submitted_transactions
+                    .get(&transaction_hash)
+                    .ok_or_else(|| ErrorObjectOwned::from(TRANSACTION_HASH_NOT_FOUND))?;
+                return Ok(TransactionWithHash {
+                    transaction: Transaction::clone(&transaction),
+                    transaction_hash,
+                });
+            };
 
             let starknet_api_transaction: StarknetApiTransaction =
                 client_transaction.try_into().map_err(internal_server_error)?;
@@ -383,7 +506,7 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
                 let transaction_hash = client_transaction.transaction_hash();
                 (client_transaction.try_into().map_err(internal_server_error)?, transaction_hash)
             } else {
-                let block_number = get_accepted_block_number(&txn, block_id)?;
+                let block_number = get_accepted_block_number_with_body(&txn, block_id)?;
 
                 let tx_index = TransactionIndex(block_number, index);
                 let transaction = txn
@@ -413,7 +536,7 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
                 read_pending_data(&self.pending_data, &txn).await?.block.transactions().len();
             Ok(transactions_len)
         } else {
-            let block_number = get_accepted_block_number(&txn, block_id)?;
+            let block_number = get_accepted_block_number_with_body(&txn, block_id)?;
             Ok(txn
                 .get_block_transactions_count(block_number)
                 .map_err(internal_server_error)?
@@ -422,53 +545,70 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
     }
 
     #[instrument(skip(self), level = "debug", err, ret)]
-    async fn get_state_update(&self, block_id: BlockId) -> RpcResult<StateUpdate> {
-        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+    async fn get_state_update(&self, block_id: BlockId) -> RpcResult<StreamedStateUpdate> {
         if let BlockId::Tag(Tag::Pending) = block_id {
+            let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
             let state_update = read_pending_data(&self.pending_data, &txn).await?.state_update;
-            return Ok(StateUpdate::PendingStateUpdate(PendingStateUpdate {
+            return Ok(StreamedStateUpdate::PendingStateUpdate(PendingStateUpdate {
                 old_root: state_update.old_root,
                 state_diff: state_update.state_diff.into(),
             }));
         }
 
-        // Get the block header for the block hash and state root.
-        let block_number = get_accepted_block_number(&txn, block_id)?;
-        let header: BlockHeader = get_block_header_by_number(&txn, block_number)?.into();
-
-        // Get the old root.
-        let old_root = match get_accepted_block_number(
-            &txn,
-            BlockId::HashOrNumber(BlockHashOrNumber::Hash(header.parent_hash)),
-        ) {
-            Ok(parent_block_number) => {
-                BlockHeader::from(get_block_header_by_number(&txn, parent_block_number)?).new_root
-            }
-            Err(_) => GlobalRoot(StarkHash::try_from(GENESIS_HASH).map_err(internal_server_error)?),
-        };
+        read_storage(&self.storage_reader, move |txn| {
+            // Get the block header for the block hash and state root.
+            let block_number = get_accepted_block_number(txn, block_id)?;
+            let header: BlockHeader = get_block_header_by_number(txn, block_number)?.into();
+
+            // Get the old root.
+            let old_root = match get_accepted_block_number(
+                txn,
+                BlockId::HashOrNumber(BlockHashOrNumber::Hash(header.parent_hash)),
+            ) {
+                Ok(parent_block_number) => {
+                    BlockHeader::from(get_block_header_by_number(txn, parent_block_number)?)
+                        .new_root
+                }
+                Err(_) => {
+                    GlobalRoot(StarkHash::try_from(GENESIS_HASH).map_err(internal_server_error)?)
+                }
+            };
 
-        // Get the block state diff.
-        let mut thin_state_diff = txn
-            .get_state_diff(block_number)
-            .map_err(internal_server_error)?
-            .ok_or_else(|| ErrorObjectOwned::from(BLOCK_NOT_FOUND))?;
-        // Remove empty storage diffs. Some blocks contain empty storage diffs that must be kept for
-        // the computation of state diff commitment.
-        thin_state_diff.storage_diffs.retain(|_k, v| !v.is_empty());
-
-        Ok(StateUpdate::AcceptedStateUpdate(AcceptedStateUpdate {
-            block_hash: header.block_hash,
-            new_root: header.new_root,
-            old_root,
-            state_diff: thin_state_diff.into(),
-        }))
+            // Get the block state diff.
+            let mut thin_state_diff = txn
+                .get_state_diff(block_number)
+                .map_err(internal_server_error)?
+                .ok_or_else(|| ErrorObjectOwned::from(BLOCK_NOT_FOUND))?;
+            // Remove empty storage diffs. Some blocks contain empty storage diffs that must be kept
+            // for the computation of state diff commitment.
+            thin_state_diff.storage_diffs.retain(|_k, v| !v.is_empty());
+
+            Ok(StreamedStateUpdate::AcceptedStateUpdate(StreamedAcceptedStateUpdate {
+                block_hash: header.block_hash,
+                new_root: header.new_root,
+                old_root,
+                state_diff: thin_state_diff.into(),
+            }))
+        })
+        .await
     }
 
     async fn get_transaction_status(
         &self,
         transaction_hash: TransactionHash,
     ) -> RpcResult<TransactionStatus> {
-        Ok(self.get_transaction_receipt(transaction_hash).await?.transaction_status())
+        match self.get_transaction_receipt(transaction_hash).await {
+            Ok(receipt) => Ok(receipt.transaction_status()),
+            // Transactions that were submitted through this node are known before they're
+            // included in a block.
+            Err(err)
+                if err.code() == TRANSACTION_HASH_NOT_FOUND.code
+                    && self.submitted_transactions.get(&transaction_hash).is_some() =>
+            {
+                Ok(TransactionStatus::received())
+            }
+            Err(err) => Err(err),
+        }
     }
 
     #[instrument(skip(self), level = "debug", err, ret)]
@@ -502,41 +642,12 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
                 .map_err(internal_server_error)?
                 .unwrap_or_else(|| panic!("Should have tx {}", transaction_hash));
 
-            // TODO: Add version function to transaction in SN_API.
-            let tx_version = match &tx {
-                StarknetApiTransaction::Declare(tx) => tx.version(),
-                StarknetApiTransaction::Deploy(tx) => tx.version,
-                StarknetApiTransaction::DeployAccount(tx) => tx.version(),
-                StarknetApiTransaction::Invoke(tx) => tx.version(),
-                StarknetApiTransaction::L1Handler(tx) => tx.version,
-            };
-
+            verify_receipts_synced(&txn, block_number)?;
             let thin_tx_output = txn
                 .get_transaction_output(transaction_index)
                 .map_err(internal_server_error)?
                 .ok_or_else(|| ErrorObjectOwned::from(TRANSACTION_HASH_NOT_FOUND))?;
-
-            let events = txn
-                .get_transaction_events(transaction_index)
-                .map_err(internal_server_error)?
-                .ok_or_else(|| ErrorObjectOwned::from(TRANSACTION_HASH_NOT_FOUND))?;
-
-            let msg_hash = match thin_tx_output {
-                papyrus_storage::body::events::ThinTransactionOutput::L1Handler(_) => {
-                    let starknet_api::transaction::Transaction::L1Handler(tx) = tx else {
-                        panic!("tx {} should be L1 handler", transaction_hash);
-                    };
-                    Some(tx.calc_msg_hash())
-                }
-                _ => None,
-            };
-
-            let output = TransactionOutput::from_thin_transaction_output(
-                thin_tx_output,
-                tx_version,
-                events,
-                msg_hash,
-            );
+            let output = get_transaction_output(&txn, transaction_index, &tx, thin_tx_output)?;
 
             Ok(GeneralTransactionReceipt::TransactionReceipt(TransactionReceipt {
                 finality_status: status.into(),
@@ -564,25 +675,10 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
                 .iter()
                 .find(|transaction| transaction.transaction_hash() == transaction_hash)
                 .ok_or_else(|| ErrorObjectOwned::from(TRANSACTION_HASH_NOT_FOUND))?;
-            let starknet_api_output =
-                client_transaction_receipt.into_starknet_api_transaction_output(client_transaction);
-            let msg_hash = match client_transaction {
-                starknet_client::reader::objects::transaction::Transaction::L1Handler(tx) => {
-                    Some(tx.calc_msg_hash())
-                }
-                _ => None,
-            };
-            let output = PendingTransactionOutput::try_from(TransactionOutput::from((
-                starknet_api_output,
-                client_transaction.transaction_version(),
-                msg_hash,
-            )))?;
-            Ok(GeneralTransactionReceipt::PendingTransactionReceipt(PendingTransactionReceipt {
-                // ACCEPTED_ON_L2 is the only finality status of a pending transaction.
-                finality_status: PendingTransactionFinalityStatus::AcceptedOnL2,
-                transaction_hash,
-                output,
-            }))
+            Ok(GeneralTransactionReceipt::PendingTransactionReceipt(pending_transaction_receipt(
+                client_transaction,
+                client_transaction_receipt,
+            )?))
         }
     }
 
@@ -602,27 +698,31 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
         } else {
             block_id
         };
+        download_class_if_deferred(&self.storage_reader, &self.deferred_class_requests, class_hash)
+            .await?;
 
-        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
-
-        let block_number = get_accepted_block_number(&txn, block_id)?;
-        let state_number = StateNumber::right_after_block(block_number);
-        let state_reader = txn.get_state_reader().map_err(internal_server_error)?;
+        read_storage(&self.storage_reader, move |txn| {
+            let block_number = get_accepted_block_number(txn, block_id)?;
+            let state_number = StateNumber::right_after_block(block_number);
+            verify_state_queryable(txn, state_number)?;
+            let state_reader = txn.get_state_reader().map_err(internal_server_error)?;
 
-        // The class might be a deprecated class. Search it first in the declared classes and if not
-        // found, search in the deprecated classes.
-        if let Some(class) = state_reader
-            .get_class_definition_at(state_number, &class_hash)
-            .map_err(internal_server_error)?
-        {
-            Ok(GatewayContractClass::Sierra(class.into()))
-        } else {
-            let class = state_reader
-                .get_deprecated_class_definition_at(state_number, &class_hash)
+            // The class might be a deprecated class. Search it first in the declared classes and if
+            // not found, search in the deprecated classes.
+            if let Some(class) = state_reader
+                .get_class_definition_at(state_number, &class_hash)
                 .map_err(internal_server_error)?
-                .ok_or_else(|| ErrorObjectOwned::from(CLASS_HASH_NOT_FOUND))?;
-            Ok(GatewayContractClass::Cairo0(class.try_into().map_err(internal_server_error)?))
-        }
+            {
+                Ok(GatewayContractClass::Sierra(class.into()))
+            } else {
+                let class = state_reader
+                    .get_deprecated_class_definition_at(state_number, &class_hash)
+                    .map_err(internal_server_error)?
+                    .ok_or_else(|| ErrorObjectOwned::from(CLASS_HASH_NOT_FOUND))?;
+                Ok(GatewayContractClass::Cairo0(class.try_into().map_err(internal_server_error)?))
+            }
+        })
+        .await
     }
 
     #[instrument(skip(self), level = "debug", err, ret)]
@@ -641,6 +741,7 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
         block_id: BlockId,
         contract_address: ContractAddress,
     ) -> RpcResult<ClassHash> {
+        validate_contract_address(&contract_address)?;
         let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
 
         let maybe_pending_deployed_contracts_and_replaced_classes =
@@ -654,6 +755,22 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
 
         let block_number = get_accepted_block_number(&txn, block_id)?;
         let state_number = StateNumber::right_after_block(block_number);
+        verify_state_queryable(&txn, state_number)?;
+        // Classes of contracts deployed or replaced in the pending block take precedence over the
+        // recent state cache.
+        let written_in_pending = maybe_pending_deployed_contracts_and_replaced_classes
+            .as_ref()
+            .is_some_and(|(deployed_contracts, replaced_classes)| {
+                deployed_contracts.iter().any(|contract| contract.address == contract_address)
+                    || replaced_classes.iter().any(|replaced| replaced.address == contract_address)
+            });
+        if !written_in_pending {
+            if let Some(class_hash) =
+                self.recent_state.get_class_hash_at(block_number, contract_address)
+            {
+                return Ok(class_hash);
+            }
+        }
         execution_utils::get_class_hash_at(
             &txn,
             state_number,
@@ -671,6 +788,7 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
         block_id: BlockId,
         contract_address: ContractAddress,
     ) -> RpcResult<Nonce> {
+        validate_contract_address(&contract_address)?;
         let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
 
         let maybe_pending_nonces = if let BlockId::Tag(Tag::Pending) = block_id {
@@ -682,6 +800,16 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
         // Check that the block is valid and get the state number.
         let block_number = get_accepted_block_number(&txn, block_id)?;
         let state_number = StateNumber::right_after_block(block_number);
+        verify_state_queryable(&txn, state_number)?;
+        // Nonces written in the pending block take precedence over the recent state cache.
+        let written_in_pending = maybe_pending_nonces
+            .as_ref()
+            .is_some_and(|pending_nonces| pending_nonces.contains_key(&contract_address));
+        if !written_in_pending {
+            if let Some(nonce) = self.recent_state.get_nonce_at(block_number, contract_address) {
+                return Ok(nonce);
+            }
+        }
         execution_utils::get_nonce_at(
             &txn,
             state_number,
@@ -700,15 +828,13 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
     #[instrument(skip(self), level = "debug", err, ret)]
     async fn get_events(&self, filter: EventFilter) -> RpcResult<EventsChunk> {
         verify_storage_scope(&self.storage_reader)?;
-
-        // Check the chunk size.
-        if filter.chunk_size > self.max_events_chunk_size {
-            return Err(ErrorObjectOwned::from(PAGE_SIZE_TOO_BIG));
-        }
-        // Check the number of keys.
-        if filter.keys.len() > self.max_events_keys {
-            return Err(ErrorObjectOwned::from(TOO_MANY_KEYS_IN_FILTER));
-        }
+        validate_event_filter(
+            filter.chunk_size,
+            filter.address.as_ref(),
+            &filter.keys,
+            self.max_events_chunk_size,
+            self.max_events_keys,
+        )?;
 
         // Get the requested block numbers.
         let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
@@ -739,7 +865,19 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
         // Get the event index. If there's a continuation token we take the event index from there.
         // Otherwise, we take the first index in the from_block_number.
         let start_event_index = match &filter.continuation_token {
-            Some(token) => token.parse()?.0,
+            Some(token) => {
+                let token = token.parse(&self.continuation_token_key)?;
+                // A token issued before a reorg may point to events of blocks that were reverted.
+                if token.is_stale(&txn)? {
+                    return Err(ErrorObjectOwned::from(STALE_CONTINUATION_TOKEN));
+                }
+                let event_index = token.0;
+                // A token of another request may point to an event before the requested blocks.
+                if event_index.0.0 < from_block_number {
+                    return Err(ErrorObjectOwned::from(INVALID_CONTINUATION_TOKEN));
+                }
+                event_index
+            }
             None => EventIndex(
                 TransactionIndex(from_block_number, TransactionOffsetInBlock(0)),
                 EventIndexInTransactionOutput(0),
@@ -759,10 +897,32 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
         // pointing to the next relevant event. Otherwise, we return a continuation token None.
         let mut filtered_events = vec![];
         if start_event_index.0.0 <= latest_block_number {
-            for ((from_address, event_index), content) in txn
-                .iter_events(filter.address, start_event_index, to_block_number)
-                .map_err(internal_server_error)?
+            // The events are stored with the receipts.
+            verify_receipts_synced(&txn, to_block_number)?;
+            let events_iter: Box<dyn Iterator<Item = StorageResult<_>>> = match filter.keys.first()
             {
+                // Without an address, the events are taken from the index of events by their first
+                // key instead of going over all the events in the requested blocks.
+                Some(first_keys) if filter.address.is_none() && !first_keys.is_empty() => {
+                    let first_keys = first_keys.iter().cloned().collect::<Vec<_>>();
+                    Box::new(
+                        txn.iter_events_by_first_keys(
+                            &first_keys,
+                            start_event_index,
+                            to_block_number,
+                        )
+                        .map_err(internal_server_error)?,
+                    )
+                }
+                _ => Box::new(
+                    txn.iter_events(filter.address, start_event_index, to_block_number)
+                        .map_err(internal_server_error)?
+                        .map(Ok),
+                ),
+            };
+            for event in events_iter {
+                let ((from_address, event_index), content) =
+                    event.map_err(internal_server_error)?;
                 let block_number = (event_index.0).0;
                 if block_number > to_block_number {
                     break;
@@ -781,7 +941,8 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
                         return Ok(EventsChunk {
                             events: filtered_events,
                             continuation_token: Some(ContinuationToken::new(
-                                ContinuationTokenAsStruct(event_index),
+                                ContinuationTokenAsStruct::new(&txn, event_index)?,
+                                &self.continuation_token_key,
                             )?),
                         });
                     }
@@ -825,13 +986,17 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
                         return Ok(EventsChunk {
                             events: filtered_events,
                             continuation_token: Some(ContinuationToken::new(
-                                ContinuationTokenAsStruct(EventIndex(
-                                    TransactionIndex(
-                                        latest_block_number.next(),
-                                        TransactionOffsetInBlock(transaction_offset),
+                                ContinuationTokenAsStruct::new(
+                                    &txn,
+                                    EventIndex(
+                                        TransactionIndex(
+                                            latest_block_number.next(),
+                                            TransactionOffsetInBlock(transaction_offset),
+                                        ),
+                                        EventIndexInTransactionOutput(event_offset),
                                     ),
-                                    EventIndexInTransactionOutput(event_offset),
-                                )),
+                                )?,
+                                &self.continuation_token_key,
                             )?),
                         });
                     }
@@ -878,6 +1043,7 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
 
     #[instrument(skip(self), level = "debug", err, ret)]
     async fn call(&self, request: CallRequest, block_id: BlockId) -> RpcResult<Vec<StarkFelt>> {
+        validate_call_request(&request)?;
         let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
         let maybe_pending_data = if let BlockId::Tag(Tag::Pending) = block_id {
             Some(client_pending_data_to_execution_pending_data(
@@ -902,21 +1068,25 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
         let reader = self.storage_reader.clone();
         let contract_address_copy = request.contract_address;
 
-        let res = tokio::task::spawn_blocking(move || {
-            execute_call(
-                reader,
-                maybe_pending_data,
-                &chain_id,
-                state_number,
-                block_number,
-                &contract_address_copy,
-                request.entry_point_selector,
-                request.calldata,
-                &block_execution_config,
-            )
-        })
-        .await
-        .map_err(internal_server_error)?
+        let res = execute_with_deferred_classes(
+            &self.execution_pool,
+            &self.storage_reader,
+            &self.deferred_class_requests,
+            move || {
+                execute_call(
+                    reader.clone(),
+                    maybe_pending_data.clone(),
+                    &chain_id,
+                    state_number,
+                    block_number,
+                    &contract_address_copy,
+                    request.entry_point_selector,
+                    request.calldata.clone(),
+                    &block_execution_config,
+                )
+            },
+        )
+        .await?
         .map_err(execution_error_to_error_object_owned)?;
 
         block_not_reverted_validator.validate(&self.storage_reader)?;
@@ -929,13 +1099,17 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
         &self,
         invoke_transaction: TypedInvokeTransaction,
     ) -> RpcResult<AddInvokeOkResult> {
-        let result = self.writer_client.add_invoke_transaction(&invoke_transaction.into()).await;
+        let client_transaction = invoke_transaction.clone().into();
+        validate_invoke_transaction(&client_transaction)?;
+        let result = self.writer_client.add_invoke_transaction(&client_transaction).await;
         match result {
-            Ok(res) => Ok(res.into()),
-            Err(WriterClientError::ClientError(ClientError::StarknetError(starknet_error))) => {
-                Err(ErrorObjectOwned::from(starknet_error_to_invoke_error(starknet_error)))
+            Ok(res) => {
+                let TypedInvokeTransaction::Invoke(transaction) = invoke_transaction;
+                self.submitted_transactions
+                    .insert(res.transaction_hash, Transaction::Invoke(transaction));
+                Ok(res.into())
             }
-            Err(err) => Err(internal_server_error(err)),
+            Err(err) => Err(writer_client_error_to_rpc_error(err, starknet_error_to_invoke_error)),
         }
     }
 
@@ -944,16 +1118,20 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
         &self,
         deploy_account_transaction: TypedDeployAccountTransaction,
     ) -> RpcResult<AddDeployAccountOkResult> {
-        let result = self
-            .writer_client
-            .add_deploy_account_transaction(&deploy_account_transaction.into())
-            .await;
+        let client_transaction = deploy_account_transaction.clone().into();
+        validate_deploy_account_transaction(&client_transaction)?;
+        let result = self.writer_client.add_deploy_account_transaction(&client_transaction).await;
         match result {
-            Ok(res) => Ok(res.into()),
-            Err(WriterClientError::ClientError(ClientError::StarknetError(starknet_error))) => {
-                Err(ErrorObjectOwned::from(starknet_error_to_deploy_account_error(starknet_error)))
+            Ok(res) => {
+                let TypedDeployAccountTransaction::DeployAccount(transaction) =
+                    deploy_account_transaction;
+                self.submitted_transactions
+                    .insert(res.transaction_hash, Transaction::DeployAccount(transaction));
+                Ok(res.into())
+            }
+            Err(err) => {
+                Err(writer_client_error_to_rpc_error(err, starknet_error_to_deploy_account_error))
             }
-            Err(err) => Err(internal_server_error(err)),
         }
     }
 
@@ -962,18 +1140,18 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
         &self,
         declare_transaction: BroadcastedDeclareTransaction,
     ) -> RpcResult<AddDeclareOkResult> {
-        let result = self
-            .writer_client
-            .add_declare_transaction(
-                &declare_transaction.try_into().map_err(internal_server_error)?,
-            )
-            .await;
+        let client_transaction: ClientDeclareTransaction =
+            declare_transaction.clone().try_into().map_err(internal_server_error)?;
+        validate_declare_transaction(&client_transaction)?;
+        let result = self.writer_client.add_declare_transaction(&client_transaction).await;
         match result {
-            Ok(res) => Ok(res.into()),
-            Err(WriterClientError::ClientError(ClientError::StarknetError(starknet_error))) => {
-                Err(ErrorObjectOwned::from(starknet_error_to_declare_error(starknet_error)))
+            Ok(res) => {
+                let transaction = declare_transaction.into_declare_transaction(res.class_hash);
+                self.submitted_transactions
+                    .insert(res.transaction_hash, Transaction::Declare(transaction));
+                Ok(res.into())
             }
-            Err(err) => Err(internal_server_error(err)),
+            Err(err) => Err(writer_client_error_to_rpc_error(err, starknet_error_to_declare_error)),
         }
     }
 
@@ -986,6 +1164,11 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
     ) -> RpcResult<Vec<FeeEstimate>> {
         trace!("Estimating fee of transactions: {:#?}", transactions);
         let validate = !simulation_flags.contains(&SimulationFlag::SkipValidate);
+        // The state of the pending block changes while it's the same block.
+        let cacheable =
+            self.fee_estimate_cache.is_enabled() && !matches!(block_id, BlockId::Tag(Tag::Pending));
+        let serialized_transactions =
+            if cacheable { Some(serialize_transactions(&transactions)) } else { None };
 
         let storage_txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
 
@@ -1002,6 +1185,17 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
             transactions.into_iter().map(|tx| tx.try_into()).collect::<Result<_, _>>()?;
 
         let block_number = get_accepted_block_number(&storage_txn, block_id)?;
+        let cache_key = match serialized_transactions {
+            Some(serialized_transactions) => {
+                let block_hash = get_block_header_by_number(&storage_txn, block_number)?.block_hash;
+                let cache_key = FeeEstimateKey::new(block_hash, validate, serialized_transactions);
+                if let Some(fee_estimates) = self.fee_estimate_cache.get(&cache_key) {
+                    return Ok(fee_estimates);
+                }
+                Some(cache_key)
+            }
+            None => None,
+        };
         let block_not_reverted_validator =
             BlockNotRevertedValidator::new(block_number, &storage_txn)?;
         drop(storage_txn);
@@ -1016,28 +1210,38 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
         let chain_id = self.chain_id.clone();
         let reader = self.storage_reader.clone();
 
-        let estimate_fee_result = tokio::task::spawn_blocking(move || {
-            exec_estimate_fee(
-                executable_txns,
-                &chain_id,
-                reader,
-                maybe_pending_data,
-                state_number,
-                block_number,
-                &block_execution_config,
-                validate,
-            )
-        })
-        .await
-        .map_err(internal_server_error)?;
+        let estimate_fee_result = execute_with_deferred_classes(
+            &self.execution_pool,
+            &self.storage_reader,
+            &self.deferred_class_requests,
+            move || {
+                exec_estimate_fee(
+                    executable_txns.clone(),
+                    &chain_id,
+                    reader.clone(),
+                    maybe_pending_data.clone(),
+                    state_number,
+                    block_number,
+                    &block_execution_config,
+                    validate,
+                )
+            },
+        )
+        .await?;
 
         block_not_reverted_validator.validate(&self.storage_reader)?;
 
         match estimate_fee_result {
-            Ok(Ok(fees)) => Ok(fees
-                .into_iter()
-                .map(|(gas_price, fee, unit)| FeeEstimate::from(gas_price, fee, unit))
-                .collect()),
+            Ok(Ok(fees)) => {
+                let fee_estimates: Vec<_> = fees
+                    .into_iter()
+                    .map(|(gas_price, fee, unit)| FeeEstimate::from(gas_price, fee, unit))
+                    .collect();
+                if let Some(cache_key) = cache_key {
+                    self.fee_estimate_cache.insert(cache_key, fee_estimates.clone());
+                }
+                Ok(fee_estimates)
+            }
             Ok(Err(reverted_tx)) => {
                 Err(ErrorObjectOwned::from(JsonRpcError::<TransactionExecutionError>::from(
                     TransactionExecutionError {
@@ -1090,22 +1294,26 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
         let charge_fee = !simulation_flags.contains(&SimulationFlag::SkipFeeCharge);
         let validate = !simulation_flags.contains(&SimulationFlag::SkipValidate);
 
-        let simulation_results = tokio::task::spawn_blocking(move || {
-            exec_simulate_transactions(
-                executable_txns,
-                None,
-                &chain_id,
-                reader,
-                maybe_pending_data,
-                state_number,
-                block_number,
-                &block_execution_config,
-                charge_fee,
-                validate,
-            )
-        })
-        .await
-        .map_err(internal_server_error)?
+        let simulation_results = execute_with_deferred_classes(
+            &self.execution_pool,
+            &self.storage_reader,
+            &self.deferred_class_requests,
+            move || {
+                exec_simulate_transactions(
+                    executable_txns.clone(),
+                    None,
+                    &chain_id,
+                    reader.clone(),
+                    maybe_pending_data.clone(),
+                    state_number,
+                    block_number,
+                    &block_execution_config,
+                    charge_fee,
+                    validate,
+                )
+            },
+        )
+        .await?
         .map_err(execution_error_to_error_object_owned)?;
 
         block_not_reverted_validator.validate(&self.storage_reader)?;
@@ -1172,6 +1380,7 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
                 timestamp: pending_block.timestamp(),
                 l1_gas_price: pending_block.l1_gas_price(),
                 l1_data_gas_price: pending_block.l1_data_gas_price(),
+                l1_da_mode: pending_block.l1_da_mode(),
                 sequencer: pending_block.sequencer_address(),
                 // The pending state diff should be empty since we look at the state in the
                 // start of the pending block.
@@ -1241,22 +1450,26 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
         let chain_id = self.chain_id.clone();
         let reader = self.storage_reader.clone();
 
-        let mut simulation_results = tokio::task::spawn_blocking(move || {
-            exec_simulate_transactions(
-                executable_transactions,
-                Some(transaction_hashes),
-                &chain_id,
-                reader,
-                maybe_pending_data,
-                state_number,
-                block_number,
-                &block_execution_config,
-                true,
-                true,
-            )
-        })
-        .await
-        .map_err(internal_server_error)?
+        let mut simulation_results = execute_with_deferred_classes(
+            &self.execution_pool,
+            &self.storage_reader,
+            &self.deferred_class_requests,
+            move || {
+                exec_simulate_transactions(
+                    executable_transactions.clone(),
+                    Some(transaction_hashes.clone()),
+                    &chain_id,
+                    reader.clone(),
+                    maybe_pending_data.clone(),
+                    state_number,
+                    block_number,
+                    &block_execution_config,
+                    true,
+                    true,
+                )
+            },
+        )
+        .await?
         .map_err(execution_error_to_error_object_owned)?;
 
         block_not_reverted_validator.validate(&self.storage_reader)?;
@@ -1282,6 +1495,26 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
 
         let block_number = get_accepted_block_number(&storage_txn, block_id)?;
 
+        // Only the traces of accepted blocks are cached, since the pending block changes while its
+        // parent stays the same.
+        let maybe_cached_block_hash =
+            if maybe_client_pending_data.is_none() && self.trace_cache.is_enabled() {
+                Some(get_block_header_by_number(&storage_txn, block_number)?.block_hash)
+            } else {
+                None
+            };
+        if let Some(block_hash) = maybe_cached_block_hash {
+            if let Some(block_traces) = self.trace_cache.get(block_hash).await {
+                return Ok(block_traces
+                    .into_iter()
+                    .map(|(transaction_hash, trace_root)| TransactionTraceWithHash {
+                        transaction_hash,
+                        trace_root,
+                    })
+                    .collect());
+            }
+        }
+
         let block_not_reverted_validator =
             BlockNotRevertedValidator::new(block_number, &storage_txn)?;
 
@@ -1292,6 +1525,7 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
                         timestamp: client_pending_data.block.timestamp(),
                         l1_gas_price: client_pending_data.block.l1_gas_price(),
                         l1_data_gas_price: client_pending_data.block.l1_data_gas_price(),
+                        l1_da_mode: client_pending_data.block.l1_da_mode(),
                         sequencer: client_pending_data.block.sequencer_address(),
                         // The pending state diff should be empty since we look at the state in the
                         // start of the pending block.
@@ -1360,32 +1594,46 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
         let reader = self.storage_reader.clone();
         let transaction_hashes_clone = transaction_hashes.clone();
 
-        let simulation_results = tokio::task::spawn_blocking(move || {
-            exec_simulate_transactions(
-                executable_txns,
-                Some(transaction_hashes_clone),
-                &chain_id,
-                reader,
-                maybe_pending_data,
-                state_number,
-                block_number,
-                &block_execution_config,
-                true,
-                true,
-            )
-        })
-        .await
-        .map_err(internal_server_error)?
+        let simulation_results = execute_with_deferred_classes(
+            &self.execution_pool,
+            &self.storage_reader,
+            &self.deferred_class_requests,
+            move || {
+                exec_simulate_transactions(
+                    executable_txns.clone(),
+                    Some(transaction_hashes_clone.clone()),
+                    &chain_id,
+                    reader.clone(),
+                    maybe_pending_data.clone(),
+                    state_number,
+                    block_number,
+                    &block_execution_config,
+                    true,
+                    true,
+                )
+            },
+        )
+        .await?
         .map_err(execution_error_to_error_object_owned)?;
 
         block_not_reverted_validator.validate(&self.storage_reader)?;
 
-        Ok(simulation_results
+        let block_traces: BlockTraces = transaction_hashes
             .into_iter()
-            .zip(transaction_hashes)
-            .map(|(simulation_output, transaction_hash)| TransactionTraceWithHash {
+            .zip(simulation_results)
+            .map(|(transaction_hash, simulation_output)| {
+                (transaction_hash, simulation_output.transaction_trace)
+            })
+            .collect();
+        if let Some(block_hash) = maybe_cached_block_hash {
+            self.trace_cache.insert(block_number, block_hash, &block_traces).await;
+        }
+
+        Ok(block_traces
+            .into_iter()
+            .map(|(transaction_hash, trace_root)| TransactionTraceWithHash {
                 transaction_hash,
-                trace_root: simulation_output.transaction_trace,
+                trace_root,
             })
             .collect())
     }
@@ -1428,20 +1676,24 @@ impl JsonRpcServer for JsonRpcServerV0_7Impl {
         let chain_id = self.chain_id.clone();
         let reader = self.storage_reader.clone();
 
-        let estimate_fee_result = tokio::task::spawn_blocking(move || {
-            exec_estimate_fee(
-                executable_txns,
-                &chain_id,
-                reader,
-                maybe_pending_data,
-                state_number,
-                block_number,
-                &block_execution_config,
-                false,
-            )
-        })
-        .await
-        .map_err(internal_server_error)?;
+        let estimate_fee_result = execute_with_deferred_classes(
+            &self.execution_pool,
+            &self.storage_reader,
+            &self.deferred_class_requests,
+            move || {
+                exec_estimate_fee(
+                    executable_txns.clone(),
+                    &chain_id,
+                    reader.clone(),
+                    maybe_pending_data.clone(),
+                    state_number,
+                    block_number,
+                    &block_execution_config,
+                    false,
+                )
+            },
+        )
+        .await?;
 
         block_not_reverted_validator.validate(&self.storage_reader)?;
 
@@ -1506,6 +1758,70 @@ async fn read_pending_data<Mode: TransactionKind>(
     }
 }
 
+// Returns the output of the accepted transaction at the given index, given its thin output from
+// the receipt tables.
+fn get_transaction_output<Mode: TransactionKind>(
+    txn: &StorageTxn<'_, Mode>,
+    transaction_index: TransactionIndex,
+    transaction: &StarknetApiTransaction,
+    thin_tx_output: ThinTransactionOutput,
+) -> RpcResult<TransactionOutput> {
+    // TODO: Add version function to transaction in SN_API.
+    let tx_version = match transaction {
+        StarknetApiTransaction::Declare(tx) => tx.version(),
+        StarknetApiTransaction::Deploy(tx) => tx.version,
+        StarknetApiTransaction::DeployAccount(tx) => tx.version(),
+        StarknetApiTransaction::Invoke(tx) => tx.version(),
+        StarknetApiTransaction::L1Handler(tx) => tx.version,
+    };
+
+    let events = txn
+        .get_transaction_events(transaction_index)
+        .map_err(internal_server_error)?
+        .ok_or_else(|| ErrorObjectOwned::from(TRANSACTION_HASH_NOT_FOUND))?;
+
+    let msg_hash = match thin_tx_output {
+        ThinTransactionOutput::L1Handler(_) => {
+            let StarknetApiTransaction::L1Handler(tx) = transaction else {
+                panic!("Transaction {transaction_index:?} should be L1 handler");
+            };
+            Some(tx.calc_msg_hash())
+        }
+        _ => None,
+    };
+
+    Ok(TransactionOutput::from_thin_transaction_output(
+        thin_tx_output,
+        tx_version,
+        events,
+        msg_hash,
+    ))
+}
+
+fn pending_transaction_receipt(
+    client_transaction: &ClientTransaction,
+    client_transaction_receipt: ClientTransactionReceipt,
+) -> RpcResult<PendingTransactionReceipt> {
+    let transaction_hash = client_transaction_receipt.transaction_hash;
+    let starknet_api_output =
+        client_transaction_receipt.into_starknet_api_transaction_output(client_transaction);
+    let msg_hash = match client_transaction {
+        ClientTransaction::L1Handler(tx) => Some(tx.calc_msg_hash()),
+        _ => None,
+    };
+    let output = PendingTransactionOutput::try_from(TransactionOutput::from((
+        starknet_api_output,
+        client_transaction.transaction_version(),
+        msg_hash,
+    )))?;
+    Ok(PendingTransactionReceipt {
+        // ACCEPTED_ON_L2 is the only finality status of a pending transaction.
+        finality_status: PendingTransactionFinalityStatus::AcceptedOnL2,
+        transaction_hash,
+        output,
+    })
+}
+
 fn do_event_keys_match_filter(event_content: &EventContent, filter: &EventFilter) -> bool {
     filter.keys.iter().enumerate().all(|(i, keys)| {
         event_content.keys.len() > i && (keys.is_empty() || keys.contains(&event_content.keys[i]))
@@ -1519,10 +1835,17 @@ impl JsonRpcServerImpl for JsonRpcServerV0_7Impl {
         storage_reader: StorageReader,
         max_events_chunk_size: usize,
         max_events_keys: usize,
+        fee_estimate_cache: Arc<FeeEstimateCache>,
+        submitted_transactions: Arc<SubmittedTransactions>,
         starting_block: BlockHashAndNumber,
         shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
         pending_data: Arc<RwLock<PendingData>>,
         pending_classes: Arc<RwLock<PendingClasses>>,
+        recent_state: Arc<RecentStateCache>,
+        trace_cache: Arc<TraceCache>,
+        deferred_class_requests: Arc<DeferredClassRequests>,
+        execution_pool: Arc<ExecutionPool>,
+        continuation_token_key: ContinuationTokenKey,
         writer_client: Arc<dyn StarknetWriter>,
     ) -> Self {
         Self {
@@ -1535,7 +1858,14 @@ impl JsonRpcServerImpl for JsonRpcServerV0_7Impl {
             shared_highest_block,
             pending_data,
             pending_classes,
+            recent_state,
+            deferred_class_requests,
+            execution_pool,
+            continuation_token_key,
             writer_client,
+            submitted_transactions,
+            fee_estimate_cache,
+            trace_cache,
         }
     }
 
@@ -1543,3 +1873,18 @@ impl JsonRpcServerImpl for JsonRpcServerV0_7Impl {
         self.into_rpc()
     }
 }
+
+#[async_trait]
+impl StreamedMethods for JsonRpcServerV0_7Impl {
+    async fn call(&self, method: &str, params: Params<'_>) -> RpcResult<Option<StreamedResult>> {
+        Ok(Some(match method {
+            "getBlockWithTxs" => StreamedResult::new(
+                self.get_block_w_full_transactions(single_param(&params, "block_id")?).await?,
+            ),
+            "getStateUpdate" => StreamedResult::new(
+                self.get_state_update(single_param(&params, "block_id")?).await?,
+            ),
+            _ => return Ok(None),
+        }))
+    }
+}
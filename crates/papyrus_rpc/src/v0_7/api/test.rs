@@ -3,6 +3,7 @@ use std::fmt::Debug;
 use std::iter;
 use std::net::SocketAddr;
 use std::ops::Index;
+use std::sync::Arc;
 
 use assert_matches::assert_matches;
 use async_trait::async_trait;
@@ -13,15 +14,18 @@ use jsonrpsee::Methods;
 use jsonschema::JSONSchema;
 use lazy_static::lazy_static;
 use mockall::predicate::eq;
+use papyrus_common::deferred_classes::DeferredClassRequests;
 use papyrus_common::pending_classes::{ApiContractClass, PendingClassesTrait};
+use papyrus_common::recent_state::{BlockStateWrites, RecentStateCache};
 use papyrus_common::BlockHashAndNumber;
 use papyrus_storage::base_layer::BaseLayerStorageWriter;
 use papyrus_storage::body::events::EventIndex;
 use papyrus_storage::body::{BodyStorageWriter, TransactionIndex};
+use papyrus_storage::db::RW;
 use papyrus_storage::header::HeaderStorageWriter;
 use papyrus_storage::state::StateStorageWriter;
 use papyrus_storage::test_utils::get_test_storage;
-use papyrus_storage::StorageScope;
+use papyrus_storage::{StorageScope, StorageTxn};
 use pretty_assertions::assert_eq;
 use rand::{random, RngCore};
 use rand_chacha::ChaCha8Rng;
@@ -46,13 +50,19 @@ use starknet_api::core::{
     PatriciaKey,
     SequencerContractAddress,
 };
+use starknet_api::data_availability::L1DataAvailabilityMode;
 use starknet_api::deprecated_contract_class::{
     ContractClassAbiEntry,
     FunctionAbiEntry,
     FunctionStateMutability,
 };
 use starknet_api::hash::{StarkFelt, StarkHash, GENESIS_HASH};
-use starknet_api::state::{ContractClass as StarknetApiContractClass, StateDiff, StorageKey};
+use starknet_api::state::{
+    ContractClass as StarknetApiContractClass,
+    StateDiff,
+    StorageKey,
+    ThinStateDiff,
+};
 use starknet_api::transaction::{
     Event as StarknetApiEvent,
     EventContent,
@@ -94,7 +104,7 @@ use starknet_client::writer::objects::transaction::{
     InvokeTransaction as ClientInvokeTransaction,
 };
 use starknet_client::writer::{MockStarknetWriter, WriterClientError, WriterClientResult};
-use starknet_client::ClientError;
+use starknet_client::{ClientError, RetryErrorCode};
 use test_utils::{
     auto_impl_get_test_instance,
     get_number_of_variants,
@@ -107,11 +117,18 @@ use test_utils::{
 };
 
 use super::super::api::EventsChunk;
-use super::super::block::{Block, GeneralBlockHeader, PendingBlockHeader, ResourcePrice};
+use super::super::block::{
+    Block,
+    BlockWithReceipts,
+    GeneralBlockHeader,
+    PendingBlockHeader,
+    ResourcePrice,
+};
 use super::super::broadcasted_transaction::BroadcastedDeclareTransaction;
 use super::super::deprecated_contract_class::ContractClass as DeprecatedContractClass;
 use super::super::error::{
     unexpected_error,
+    validation_failure,
     JsonRpcError,
     BLOCK_NOT_FOUND,
     CLASS_HASH_NOT_FOUND,
@@ -122,6 +139,7 @@ use super::super::error::{
     INVALID_TRANSACTION_INDEX,
     NO_BLOCKS,
     PAGE_SIZE_TOO_BIG,
+    STALE_CONTINUATION_TOKEN,
     TOO_MANY_KEYS_IN_FILTER,
     TRANSACTION_HASH_NOT_FOUND,
 };
@@ -148,10 +166,13 @@ use super::super::transaction::{
     PendingTransactionFinalityStatus,
     PendingTransactionOutput,
     PendingTransactionReceipt,
+    Transaction,
+    TransactionAndReceipt,
     TransactionFinalityStatus,
     TransactionOutput,
     TransactionReceipt,
     TransactionStatus,
+    TransactionStatusFinality,
     TransactionWithHash,
     Transactions,
     TypedDeployAccountTransaction,
@@ -163,7 +184,7 @@ use super::super::write_api_result::{
     AddInvokeOkResult,
 };
 use super::api_impl::{JsonRpcServerV0_7Impl as JsonRpcServerImpl, BLOCK_HASH_TABLE_ADDRESS};
-use super::{ContinuationToken, EventFilter, GatewayContractClass};
+use super::{ContinuationToken, EventFilter, GatewayContractClass, JsonRpcServer};
 use crate::api::{BlockHashOrNumber, BlockId, Tag};
 use crate::syncing_state::SyncStatus;
 use crate::test_utils::{
@@ -172,6 +193,7 @@ use crate::test_utils::{
     get_method_names_from_spec,
     get_starknet_spec_api_schema_for_components,
     get_starknet_spec_api_schema_for_method_results,
+    get_test_continuation_token_key,
     get_test_highest_block,
     get_test_pending_classes,
     get_test_pending_data,
@@ -189,6 +211,7 @@ use crate::{
     internal_server_error_with_msg,
     run_server,
     ContinuationTokenAsStruct,
+    RpcSharedState,
 };
 
 const NODE_VERSION: &str = "NODE VERSION";
@@ -609,6 +632,10 @@ async fn get_block_w_full_transactions() {
                 price_in_wei: pending_l1_gas_price.price_in_wei,
                 price_in_fri: pending_l1_gas_price.price_in_fri,
             },
+            // The pending block is of the deprecated version, which has no data gas price and
+            // publishes its data as calldata.
+            l1_data_gas_price: GasPricePerToken::default().into(),
+            l1_da_mode: L1DataAvailabilityMode::Calldata,
             starknet_version: starknet_version.0.clone(),
         }),
         status: None,
@@ -653,6 +680,167 @@ async fn get_block_w_full_transactions() {
     };
 }
 
+#[tokio::test]
+async fn get_block_w_receipts() {
+    let method_name = "starknet_V0_7_getBlockWithReceipts";
+    let pending_data = get_test_pending_data();
+    let (module, mut storage_writer) = get_test_rpc_server_and_storage_writer_from_params::<
+        JsonRpcServerImpl,
+    >(None, None, Some(pending_data.clone()), None, None);
+
+    let block = get_test_block(2, None, None, None);
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block.header.block_number, &block.header)
+        .unwrap()
+        .append_body(block.header.block_number, block.body.clone())
+        .unwrap()
+        .append_state_diff(
+            block.header.block_number,
+            starknet_api::state::StateDiff::default(),
+            IndexMap::new(),
+        )
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let expected_transactions = block
+        .body
+        .transactions
+        .iter()
+        .zip(&block.body.transaction_hashes)
+        .zip(&block.body.transaction_outputs)
+        .map(|((transaction, transaction_hash), output)| {
+            let transaction_version = match transaction {
+                StarknetApiTransaction::Declare(tx) => tx.version(),
+                StarknetApiTransaction::Deploy(tx) => tx.version,
+                StarknetApiTransaction::DeployAccount(tx) => tx.version(),
+                StarknetApiTransaction::Invoke(tx) => tx.version(),
+                StarknetApiTransaction::L1Handler(tx) => tx.version,
+            };
+            let msg_hash = match transaction {
+                StarknetApiTransaction::L1Handler(tx) => Some(tx.calc_msg_hash()),
+                _ => None,
+            };
+            TransactionAndReceipt {
+                transaction: transaction.clone().try_into().unwrap(),
+                receipt: GeneralTransactionReceipt::TransactionReceipt(TransactionReceipt {
+                    finality_status: TransactionFinalityStatus::AcceptedOnL2,
+                    transaction_hash: *transaction_hash,
+                    block_hash: block.header.block_hash,
+                    block_number: block.header.block_number,
+                    output: TransactionOutput::from((
+                        output.clone(),
+                        transaction_version,
+                        msg_hash,
+                    )),
+                }),
+            }
+        })
+        .collect();
+    let expected_block = BlockWithReceipts {
+        status: Some(BlockStatus::AcceptedOnL2),
+        header: GeneralBlockHeader::BlockHeader(block.header.clone().into()),
+        transactions: expected_transactions,
+    };
+
+    // Get block by hash and by number. The returned jsons of some transaction outputs are the
+    // same, so the serialized data is compared (see get_transaction_receipt).
+    for block_hash_or_number in [
+        BlockHashOrNumber::Hash(block.header.block_hash),
+        BlockHashOrNumber::Number(block.header.block_number),
+    ] {
+        let (json_response, res) = raw_call::<_, _, BlockWithReceipts>(
+            &module,
+            method_name,
+            &[BlockId::HashOrNumber(block_hash_or_number)],
+        )
+        .await;
+        assert_eq!(
+            serde_json::to_value(res.unwrap()).unwrap(),
+            serde_json::to_value(&expected_block).unwrap(),
+        );
+        assert!(validate_schema(
+            &get_starknet_spec_api_schema_for_method_results(
+                &[(
+                    SpecFile::StarknetApiOpenrpc,
+                    &[method_name_to_spec_method_name(method_name).as_str()]
+                )],
+                &VERSION,
+            ),
+            &json_response["result"],
+        ));
+    }
+
+    // Ask for a block that was accepted on L1.
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .update_base_layer_block_marker(&block.header.block_number.next())
+        .unwrap()
+        .commit()
+        .unwrap();
+    let res = module
+        .call::<_, BlockWithReceipts>(method_name, [BlockId::Tag(Tag::Latest)])
+        .await
+        .unwrap();
+    assert_eq!(res.status, Some(BlockStatus::AcceptedOnL1));
+    for transaction in res.transactions {
+        let GeneralTransactionReceipt::TransactionReceipt(receipt) = transaction.receipt else {
+            panic!("Unexpected receipt type. Expected TransactionReceipt.");
+        };
+        assert_eq!(receipt.finality_status, TransactionFinalityStatus::AcceptedOnL1);
+    }
+
+    // Ask for an invalid block number.
+    call_api_then_assert_and_validate_schema_for_err::<_, BlockWithReceipts>(
+        &module,
+        method_name,
+        vec![Box::new(BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(1))))],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &BLOCK_NOT_FOUND.into(),
+    )
+    .await;
+
+    // Get the pending block.
+    let mut rng = get_rng();
+    let (client_transaction, client_transaction_receipt, expected_receipt) =
+        generate_client_transaction_client_receipt_and_rpc_receipt(&mut rng);
+    {
+        let pending_block = &mut pending_data.write().await.block;
+        *pending_block.parent_block_hash_mutable() = block.header.block_hash;
+        pending_block.transactions_mutable().push(client_transaction.clone());
+        pending_block.transaction_receipts_mutable().push(client_transaction_receipt);
+    }
+    let starknet_api_transaction: StarknetApiTransaction = client_transaction.try_into().unwrap();
+    let expected_transactions = vec![TransactionAndReceipt {
+        transaction: starknet_api_transaction.try_into().unwrap(),
+        receipt: GeneralTransactionReceipt::PendingTransactionReceipt(expected_receipt),
+    }];
+    let (json_response, res) =
+        raw_call::<_, _, BlockWithReceipts>(&module, method_name, &[BlockId::Tag(Tag::Pending)])
+            .await;
+    let res = res.unwrap();
+    assert_eq!(res.status, None);
+    assert_matches!(res.header, GeneralBlockHeader::PendingBlockHeader(_));
+    assert_eq!(
+        serde_json::to_value(res.transactions).unwrap(),
+        serde_json::to_value(&expected_transactions).unwrap(),
+    );
+    assert!(validate_schema(
+        &get_starknet_spec_api_schema_for_method_results(
+            &[(
+                SpecFile::StarknetApiOpenrpc,
+                &[method_name_to_spec_method_name(method_name).as_str()]
+            )],
+            &VERSION,
+        ),
+        &json_response["result"],
+    ));
+}
+
 #[tokio::test]
 async fn get_block_w_transaction_hashes() {
     let method_name = "starknet_V0_7_getBlockWithTxHashes";
@@ -783,6 +971,10 @@ async fn get_block_w_transaction_hashes() {
                 price_in_wei: pending_l1_gas_price.price_in_wei,
                 price_in_fri: pending_l1_gas_price.price_in_fri,
             },
+            // The pending block is of the deprecated version, which has no data gas price and
+            // publishes its data as calldata.
+            l1_data_gas_price: GasPricePerToken::default().into(),
+            l1_da_mode: L1DataAvailabilityMode::Calldata,
             starknet_version: starknet_version.0.clone(),
         }),
         status: None,
@@ -1021,8 +1213,8 @@ async fn get_transaction_status() {
     };
     let output = TransactionOutput::from((tx, transaction_version, msg_hash));
     let expected_status = TransactionStatus {
-        finality_status: TransactionFinalityStatus::AcceptedOnL2,
-        execution_status: output.execution_status().clone(),
+        finality_status: TransactionStatusFinality::AcceptedOnL2,
+        execution_status: Some(output.execution_status().clone()),
     };
     let (json_response, res) =
         raw_call::<_, _, TransactionStatus>(&module, method_name, &[transaction_hash]).await;
@@ -1047,16 +1239,16 @@ async fn get_transaction_status() {
         .commit()
         .unwrap();
     let res = module.call::<_, TransactionStatus>(method_name, [transaction_hash]).await.unwrap();
-    assert_eq!(res.finality_status, TransactionFinalityStatus::AcceptedOnL1);
-    assert_eq!(res.execution_status, TransactionExecutionStatus::Succeeded);
+    assert_eq!(res.finality_status, TransactionStatusFinality::AcceptedOnL1);
+    assert_eq!(res.execution_status, Some(TransactionExecutionStatus::Succeeded));
 
     // Add a pending transaction and ask for its status.
     let mut rng = get_rng();
     let (client_transaction, client_transaction_receipt, expected_receipt) =
         generate_client_transaction_client_receipt_and_rpc_receipt(&mut rng);
     let expected_status = TransactionStatus {
-        finality_status: TransactionFinalityStatus::AcceptedOnL2,
-        execution_status: expected_receipt.output.execution_status().clone(),
+        finality_status: TransactionStatusFinality::AcceptedOnL2,
+        execution_status: Some(expected_receipt.output.execution_status().clone()),
     };
 
     {
@@ -1907,6 +2099,139 @@ async fn get_storage_at() {
     assert_matches!(err, Error::Call(err) if err == BLOCK_NOT_FOUND.into());
 }
 
+#[tokio::test]
+async fn get_state_from_recent_state_cache() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let header = BlockHeader::default();
+    let diff = get_test_state_diff();
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(header.block_number, &header)
+        .unwrap()
+        .append_state_diff(header.block_number, diff.clone(), IndexMap::new())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    // The cached writes differ from the stored ones, to tell where the answers come from.
+    let (address, storage_entries) = diff.storage_diffs.get_index(0).unwrap();
+    let (key, _) = storage_entries.get_index(0).unwrap();
+    let cached_value = StarkFelt::from(1234_u128);
+    let cached_nonce = Nonce(StarkFelt::from(5678_u128));
+    let cached_class_hash = ClassHash(StarkFelt::from(9012_u128));
+    let cached_diff = StateDiff {
+        storage_diffs: [(*address, [(*key, cached_value)].into())].into(),
+        nonces: [(*address, cached_nonce)].into(),
+        replaced_classes: [(*address, cached_class_hash)].into(),
+        ..Default::default()
+    };
+    let recent_state = Arc::new(RecentStateCache::new(1));
+    recent_state.push_block(header.block_number, BlockStateWrites::from(&cached_diff));
+
+    let config = get_test_rpc_config();
+    let module = JsonRpcServerImpl {
+        chain_id: config.chain_id,
+        execution_config: config.execution_config.try_into().unwrap(),
+        storage_reader,
+        max_events_chunk_size: config.max_events_chunk_size,
+        max_events_keys: config.max_events_keys,
+        starting_block: BlockHashAndNumber::default(),
+        shared_highest_block: get_test_highest_block(),
+        pending_data: get_test_pending_data(),
+        pending_classes: get_test_pending_classes(),
+        recent_state,
+        deferred_class_requests: Arc::default(),
+        execution_pool: Arc::default(),
+        writer_client: Arc::new(MockStarknetWriter::new()),
+        submitted_transactions: Arc::default(),
+        fee_estimate_cache: Arc::default(),
+        trace_cache: Arc::default(),
+    }
+    .into_rpc();
+
+    let block_id = BlockId::HashOrNumber(BlockHashOrNumber::Number(header.block_number));
+    let res = module
+        .call::<_, StarkFelt>("starknet_V0_7_getStorageAt", (*address, *key, block_id))
+        .await
+        .unwrap();
+    assert_eq!(res, cached_value);
+    let res =
+        module.call::<_, Nonce>("starknet_V0_7_getNonce", (block_id, *address)).await.unwrap();
+    assert_eq!(res, cached_nonce);
+    let res = module
+        .call::<_, ClassHash>("starknet_V0_7_getClassHashAt", (block_id, *address))
+        .await
+        .unwrap();
+    assert_eq!(res, cached_class_hash);
+}
+
+#[tokio::test]
+async fn get_class_downloads_deferred_class() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let header = BlockHeader::default();
+    let diff = get_test_state_diff();
+    let (class_hash, contract_class) = diff.deprecated_declared_classes.get_index(0).unwrap();
+    let (class_hash, contract_class) = (*class_hash, contract_class.clone());
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(header.block_number, &header)
+        .unwrap()
+        .append_thin_state_diff(
+            header.block_number,
+            ThinStateDiff { deprecated_declared_classes: vec![class_hash], ..Default::default() },
+        )
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    // Plays the part of the sync, which downloads and stores the requested class.
+    let deferred_class_requests = Arc::new(DeferredClassRequests::default());
+    let sync_requests = deferred_class_requests.clone();
+    let stored_class = contract_class.clone();
+    tokio::spawn(async move {
+        let requested_class_hash = sync_requests.next_request().await;
+        assert_eq!(requested_class_hash, class_hash);
+        storage_writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_deferred_deprecated_class(&class_hash, stored_class)
+            .unwrap()
+            .commit()
+            .unwrap();
+        sync_requests.notify_stored(class_hash);
+    });
+
+    let config = get_test_rpc_config();
+    let module = JsonRpcServerImpl {
+        chain_id: config.chain_id,
+        execution_config: config.execution_config.try_into().unwrap(),
+        storage_reader,
+        max_events_chunk_size: config.max_events_chunk_size,
+        max_events_keys: config.max_events_keys,
+        starting_block: BlockHashAndNumber::default(),
+        shared_highest_block: get_test_highest_block(),
+        pending_data: get_test_pending_data(),
+        pending_classes: get_test_pending_classes(),
+        recent_state: Arc::default(),
+        deferred_class_requests,
+        execution_pool: Arc::default(),
+        writer_client: Arc::new(MockStarknetWriter::new()),
+        submitted_transactions: Arc::default(),
+        fee_estimate_cache: Arc::default(),
+        trace_cache: Arc::default(),
+    }
+    .into_rpc();
+
+    let block_id = BlockId::HashOrNumber(BlockHashOrNumber::Number(header.block_number));
+    let res = module
+        .call::<_, GatewayContractClass>("starknet_V0_7_getClass", (block_id, class_hash))
+        .await
+        .unwrap();
+    assert_eq!(res, GatewayContractClass::Cairo0(contract_class.try_into().unwrap()));
+}
+
 fn generate_client_transaction_client_receipt_and_rpc_receipt(
     rng: &mut ChaCha8Rng,
 ) -> (ClientTransaction, ClientTransactionReceipt, PendingTransactionReceipt) {
@@ -2494,7 +2819,7 @@ async fn test_get_events(
     pending_block_metadata: Option<BlockMetadata>,
     is_pending_up_to_date: bool,
     mut filter: EventFilter,
-    expected_result_by_index: Vec<(Vec<EventIndex>, Option<ContinuationTokenAsStruct>)>,
+    expected_result_by_index: Vec<(Vec<EventIndex>, Option<EventIndex>)>,
 ) {
     let method_name = "starknet_V0_7_getEvents";
     let pending_data = get_test_pending_data();
@@ -2504,6 +2829,7 @@ async fn test_get_events(
     let mut rng = get_rng();
 
     let mut event_index_to_event = HashMap::<EventIndex, Event>::new();
+    let mut block_hashes = vec![];
     let mut parent_hash = BlockHash(stark_felt!(GENESIS_HASH));
     let mut rw_txn = storage_writer.begin_rw_txn().unwrap();
     for (i, block_metadata) in block_metadatas.iter().enumerate() {
@@ -2511,6 +2837,7 @@ async fn test_get_events(
         let block = block_metadata.generate_block(&mut rng, parent_hash, block_number);
 
         parent_hash = block.header.block_hash;
+        block_hashes.push(block.header.block_hash);
 
         for (i_transaction, (output, transaction_hash)) in block
             .body
@@ -2585,8 +2912,16 @@ async fn test_get_events(
                 .map(|event_index| event_index_to_event.get(event_index).unwrap())
                 .cloned()
                 .collect(),
-            continuation_token: expected_continuation_token
-                .map(|x| ContinuationToken::new(x).unwrap()),
+            continuation_token: expected_continuation_token.map(|event_index| {
+                // Tokens of the pending block hold the hash of the latest accepted block.
+                let block_number = event_index.0 .0 .0 as usize;
+                let block_hash = block_hashes.get(block_number).or(block_hashes.last()).unwrap();
+                ContinuationToken::new(
+                    ContinuationTokenAsStruct(event_index, *block_hash),
+                    &get_test_continuation_token_key(),
+                )
+                .unwrap()
+            }),
         };
         call_api_then_assert_and_validate_schema_for_result(
             &module,
@@ -2613,7 +2948,7 @@ lazy_static! {
     ];
     static ref EVENT_FILTER_FOR_CHUNK_ACROSS_2_BLOCKS_TEST: EventFilter =
         EventFilter { chunk_size: 3, ..Default::default() };
-    static ref EXPECTED_RESULT_BY_INDEX_FOR_CHUNK_ACROSS_2_BLOCKS_TEST: Vec<(Vec<EventIndex>, Option<ContinuationTokenAsStruct>,)> = vec![
+    static ref EXPECTED_RESULT_BY_INDEX_FOR_CHUNK_ACROSS_2_BLOCKS_TEST: Vec<(Vec<EventIndex>, Option<EventIndex>,)> = vec![
         (
             vec![
                 EventIndex(
@@ -2629,10 +2964,10 @@ lazy_static! {
                     EventIndexInTransactionOutput(0),
                 ),
             ],
-            Some(ContinuationTokenAsStruct(EventIndex(
+            Some(EventIndex(
                 TransactionIndex(BlockNumber(1), TransactionOffsetInBlock(0)),
                 EventIndexInTransactionOutput(1),
-            ))),
+            )),
         ),
         (
             vec![
@@ -2649,10 +2984,10 @@ lazy_static! {
                     EventIndexInTransactionOutput(0),
                 ),
             ],
-            Some(ContinuationTokenAsStruct(EventIndex(
+            Some(EventIndex(
                 TransactionIndex(BlockNumber(1), TransactionOffsetInBlock(1)),
                 EventIndexInTransactionOutput(1),
-            ))),
+            )),
         ),
         (
             vec![EventIndex(
@@ -2797,7 +3132,7 @@ lazy_static! {
         ],
         ..Default::default()
     };
-    static ref EXPECTED_RESULT_BY_INDEX_FOR_KEYS_FILTER_TEST: Vec<(Vec<EventIndex>, Option<ContinuationTokenAsStruct>,)> =
+    static ref EXPECTED_RESULT_BY_INDEX_FOR_KEYS_FILTER_TEST: Vec<(Vec<EventIndex>, Option<EventIndex>,)> =
         vec![(
             vec![
                 EventIndex(
@@ -2827,6 +3162,28 @@ async fn get_events_keys_filter() {
     .await;
 }
 
+#[tokio::test]
+async fn get_events_keys_filter_with_continuation_token() {
+    let pending_block_metadata = None;
+    let is_pending_up_to_date = true;
+    let event_index_1 = EventIndex(
+        TransactionIndex(BlockNumber(1), TransactionOffsetInBlock(0)),
+        EventIndexInTransactionOutput(1),
+    );
+    let event_index_4 = EventIndex(
+        TransactionIndex(BlockNumber(1), TransactionOffsetInBlock(0)),
+        EventIndexInTransactionOutput(4),
+    );
+    test_get_events(
+        BLOCKS_METADATA_FOR_KEYS_FILTER_TEST.clone(),
+        pending_block_metadata,
+        is_pending_up_to_date,
+        EventFilter { chunk_size: 1, ..EVENT_FILTER_FOR_KEYS_FILTER_TEST.clone() },
+        vec![(vec![event_index_1], Some(event_index_4)), (vec![event_index_4], None)],
+    )
+    .await;
+}
+
 #[tokio::test]
 async fn get_events_pending_keys_filter() {
     let mut blocks_metadata = BLOCKS_METADATA_FOR_KEYS_FILTER_TEST.clone();
@@ -3099,6 +3456,133 @@ async fn get_events_invalid_ct() {
     .await;
 }
 
+#[tokio::test]
+async fn get_events_ct_before_from_block() {
+    let (module, mut storage_writer) =
+        get_test_rpc_server_and_storage_writer::<JsonRpcServerImpl>();
+    let mut rw_txn = storage_writer.begin_rw_txn().unwrap();
+    for block_number in [BlockNumber(0), BlockNumber(1)] {
+        let block_hash = BlockHash((block_number.0 + 1).into());
+        let header = BlockHeader { block_number, block_hash, ..Default::default() };
+        rw_txn = rw_txn
+            .append_header(block_number, &header)
+            .unwrap()
+            .append_body(block_number, starknet_api::block::BlockBody::default())
+            .unwrap()
+            .append_state_diff(
+                block_number,
+                starknet_api::state::StateDiff::default(),
+                IndexMap::new(),
+            )
+            .unwrap();
+    }
+    rw_txn.commit().unwrap();
+
+    // A validly signed token that points to a block before the filter's from_block.
+    let continuation_token = ContinuationToken::new(
+        ContinuationTokenAsStruct(
+            EventIndex(
+                TransactionIndex(BlockNumber(0), TransactionOffsetInBlock(0)),
+                EventIndexInTransactionOutput(0),
+            ),
+            BlockHash(1_u64.into()),
+        ),
+        &get_test_continuation_token_key(),
+    )
+    .unwrap();
+    let filter = EventFilter {
+        from_block: Some(BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(1)))),
+        continuation_token: Some(continuation_token),
+        chunk_size: 2,
+        ..Default::default()
+    };
+
+    call_api_then_assert_and_validate_schema_for_err::<_, EventsChunk>(
+        &module,
+        "starknet_V0_7_getEvents",
+        vec![Box::new(filter)],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &INVALID_CONTINUATION_TOKEN.into(),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn get_events_ct_after_reorg() {
+    let method_name = "starknet_V0_7_getEvents";
+    let (module, mut storage_writer) =
+        get_test_rpc_server_and_storage_writer::<JsonRpcServerImpl>();
+    fn append_block(
+        rw_txn: StorageTxn<'_, RW>,
+        block_number: BlockNumber,
+        hash: u64,
+    ) -> StorageTxn<'_, RW> {
+        let header =
+            BlockHeader { block_number, block_hash: BlockHash(hash.into()), ..Default::default() };
+        rw_txn
+            .append_header(block_number, &header)
+            .unwrap()
+            .append_body(block_number, starknet_api::block::BlockBody::default())
+            .unwrap()
+            .append_state_diff(
+                block_number,
+                starknet_api::state::StateDiff::default(),
+                IndexMap::new(),
+            )
+            .unwrap()
+    }
+    let mut rw_txn = storage_writer.begin_rw_txn().unwrap();
+    rw_txn = append_block(rw_txn, BlockNumber(0), 1);
+    rw_txn = append_block(rw_txn, BlockNumber(1), 2);
+    rw_txn.commit().unwrap();
+
+    // Tokens that point to block 1, issued when it was accepted and when it was pending.
+    let event_index = EventIndex(
+        TransactionIndex(BlockNumber(1), TransactionOffsetInBlock(0)),
+        EventIndexInTransactionOutput(0),
+    );
+    let accepted_block_filter = EventFilter {
+        continuation_token: Some(
+            ContinuationToken::new(
+                ContinuationTokenAsStruct(event_index, BlockHash(2_u64.into())),
+                &get_test_continuation_token_key(),
+            )
+            .unwrap(),
+        ),
+        chunk_size: 2,
+        ..Default::default()
+    };
+    let pending_block_filter = EventFilter {
+        continuation_token: Some(
+            ContinuationToken::new(
+                ContinuationTokenAsStruct(event_index, BlockHash(1_u64.into())),
+                &get_test_continuation_token_key(),
+            )
+            .unwrap(),
+        ),
+        chunk_size: 2,
+        ..Default::default()
+    };
+    for filter in [&accepted_block_filter, &pending_block_filter] {
+        module.call::<_, EventsChunk>(method_name, [filter.clone()]).await.unwrap();
+    }
+
+    // Reorg block 1.
+    let (rw_txn, _, _) =
+        storage_writer.begin_rw_txn().unwrap().revert_state_diff(BlockNumber(1)).unwrap();
+    let (rw_txn, _) = rw_txn.revert_body(BlockNumber(1)).unwrap();
+    let (rw_txn, _, _) = rw_txn.revert_header(BlockNumber(1)).unwrap();
+    append_block(rw_txn, BlockNumber(1), 3).commit().unwrap();
+
+    // The events of the reverted block were partly returned, so its token is stale. The token of
+    // the pending block is still valid since its parent wasn't reverted.
+    let err =
+        module.call::<_, EventsChunk>(method_name, [accepted_block_filter]).await.unwrap_err();
+    assert_matches!(err, Error::Call(err) if err == STALE_CONTINUATION_TOKEN.into());
+    module.call::<_, EventsChunk>(method_name, [pending_block_filter]).await.unwrap();
+}
+
 #[tokio::test]
 async fn serialize_returns_valid_json() {
     let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
@@ -3147,9 +3631,12 @@ async fn serialize_returns_valid_json() {
     let gateway_config = get_test_rpc_config();
     let (server_address, _handle) = run_server(
         &gateway_config,
-        get_test_highest_block(),
-        get_test_pending_data(),
-        get_test_pending_classes(),
+        RpcSharedState {
+            shared_highest_block: get_test_highest_block(),
+            pending_data: get_test_pending_data(),
+            pending_classes: get_test_pending_classes(),
+            ..Default::default()
+        },
         storage_reader,
         NODE_VERSION,
     )
@@ -3437,6 +3924,36 @@ where
         assert_eq!(error, expected_error);
     }
 
+    async fn test_rate_limited_error() {
+        let mut rng = get_rng();
+        let tx = Self::Transaction::get_test_instance(&mut rng);
+        const MESSAGE: &str = "Too many requests.";
+        let client_error = WriterClientError::ClientError(ClientError::RetryError {
+            code: RetryErrorCode::TooManyRequests,
+            message: MESSAGE.to_owned(),
+        });
+
+        let mut client_mock = MockStarknetWriter::new();
+        Self::expect_add_transaction(
+            &mut client_mock,
+            Self::ClientTransaction::try_from(tx.clone()).unwrap(),
+            Err(client_error),
+        );
+
+        let (module, _) = get_test_rpc_server_and_storage_writer_from_params::<JsonRpcServerImpl>(
+            Some(client_mock),
+            None,
+            None,
+            None,
+            None,
+        );
+        let result = module.call::<_, Self::Response>(Self::METHOD_NAME, [tx]).await;
+        let jsonrpsee::core::Error::Call(error) = result.unwrap_err() else {
+            panic!("Got an error which is not a call error");
+        };
+        assert_eq!(error, unexpected_error(MESSAGE.to_owned()).into());
+    }
+
     async fn test_known_starknet_error(
         known_starknet_error_code: KnownStarknetErrorCode,
         expected_error: JsonRpcError<String>,
@@ -3590,6 +4107,20 @@ async fn add_invoke_known_starknet_error() {
     .await;
 }
 
+#[tokio::test]
+async fn add_invoke_rejected_on_validate() {
+    AddInvokeTest::test_known_starknet_error(
+        KnownStarknetErrorCode::UnauthorizedActionOnValidate,
+        validation_failure("message".to_owned()),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn add_invoke_rate_limited_error() {
+    AddInvokeTest::test_rate_limited_error().await;
+}
+
 #[tokio::test]
 async fn add_invoke_unexpected_error() {
     // Choosing error codes that map under the other transaction types into an expected error in
@@ -3654,6 +4185,62 @@ async fn add_declare_unexpected_error() {
     AddDeclareTest::test_unexpected_error(KnownStarknetErrorCode::UndeclaredClass).await;
 }
 
+#[tokio::test]
+async fn submitted_transaction_is_received() {
+    let mut rng = get_rng();
+    let tx = TypedInvokeTransaction::get_test_instance(&mut rng);
+    let client_resp = InvokeResponse::get_test_instance(&mut rng);
+    let transaction_hash = client_resp.transaction_hash;
+
+    let mut client_mock = MockStarknetWriter::new();
+    AddInvokeTest::expect_add_transaction(&mut client_mock, tx.clone().into(), Ok(client_resp));
+    let (module, _) = get_test_rpc_server_and_storage_writer_from_params::<JsonRpcServerImpl>(
+        Some(client_mock),
+        None,
+        None,
+        None,
+        None,
+    );
+
+    // Before the transaction is submitted, it's unknown to the node.
+    call_api_then_assert_and_validate_schema_for_err::<_, TransactionStatus>(
+        &module,
+        "starknet_V0_7_getTransactionStatus",
+        vec![Box::new(transaction_hash)],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &TRANSACTION_HASH_NOT_FOUND.into(),
+    )
+    .await;
+
+    module
+        .call::<_, AddInvokeOkResult>("starknet_V0_7_addInvokeTransaction", [tx.clone()])
+        .await
+        .unwrap();
+
+    call_api_then_assert_and_validate_schema_for_result(
+        &module,
+        "starknet_V0_7_getTransactionStatus",
+        vec![Box::new(transaction_hash)],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &TransactionStatus::received(),
+    )
+    .await;
+    let TypedInvokeTransaction::Invoke(invoke_transaction) = tx;
+    let res = module
+        .call::<_, TransactionWithHash>("starknet_V0_7_getTransactionByHash", [transaction_hash])
+        .await
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionWithHash {
+            transaction: Transaction::Invoke(invoke_transaction),
+            transaction_hash
+        }
+    );
+}
+
 #[test]
 fn spec_api_methods_coverage() {
     let (module, _) = get_test_rpc_server_and_storage_writer::<JsonRpcServerImpl>();
@@ -3685,6 +4272,8 @@ auto_impl_get_test_instance! {
         pub sequencer_address: SequencerContractAddress,
         pub timestamp: BlockTimestamp,
         pub l1_gas_price: ResourcePrice,
+        pub l1_data_gas_price: ResourcePrice,
+        pub l1_da_mode: L1DataAvailabilityMode,
         pub starknet_version: String,
     }
     pub struct ResourcePrice {
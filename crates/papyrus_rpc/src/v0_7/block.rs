@@ -5,11 +5,13 @@ use papyrus_storage::{StorageError, StorageReader, StorageTxn};
 use serde::{Deserialize, Serialize};
 use starknet_api::block::{BlockHash, BlockNumber, BlockStatus, BlockTimestamp, GasPrice};
 use starknet_api::core::{GlobalRoot, SequencerContractAddress};
+use starknet_api::data_availability::L1DataAvailabilityMode;
 
 use super::error::BLOCK_NOT_FOUND;
-use super::transaction::Transactions;
-use crate::api::{BlockHashOrNumber, BlockId, Tag};
-use crate::{get_latest_block_number, internal_server_error};
+use super::transaction::{StreamedTransactions, TransactionAndReceipt, Transactions};
+use crate::api::BlockId;
+use crate::block_id::{resolve_accepted_block_number, unresolved_block_error, RequiredBlockData};
+use crate::internal_server_error;
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize, PartialOrd, Ord)]
 pub struct BlockHeader {
@@ -20,6 +22,8 @@ pub struct BlockHeader {
     pub new_root: GlobalRoot,
     pub timestamp: BlockTimestamp,
     pub l1_gas_price: ResourcePrice,
+    pub l1_data_gas_price: ResourcePrice,
+    pub l1_da_mode: L1DataAvailabilityMode,
     pub starknet_version: String,
 }
 
@@ -30,6 +34,8 @@ pub struct PendingBlockHeader {
     pub sequencer_address: SequencerContractAddress,
     pub timestamp: BlockTimestamp,
     pub l1_gas_price: ResourcePrice,
+    pub l1_data_gas_price: ResourcePrice,
+    pub l1_da_mode: L1DataAvailabilityMode,
     pub starknet_version: String,
 }
 
@@ -40,24 +46,6 @@ pub enum GeneralBlockHeader {
     PendingBlockHeader(PendingBlockHeader),
 }
 
-impl From<starknet_api::block::BlockHeader> for BlockHeader {
-    fn from(header: starknet_api::block::BlockHeader) -> Self {
-        BlockHeader {
-            block_hash: header.block_hash,
-            parent_hash: header.parent_hash,
-            block_number: header.block_number,
-            sequencer_address: header.sequencer,
-            new_root: header.state_root,
-            timestamp: header.timestamp,
-            l1_gas_price: ResourcePrice {
-                price_in_wei: header.l1_gas_price.price_in_wei,
-                price_in_fri: header.l1_gas_price.price_in_fri,
-            },
-            starknet_version: header.starknet_version.0,
-        }
-    }
-}
-
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize, PartialOrd, Ord)]
 pub struct ResourcePrice {
     pub price_in_wei: GasPrice,
@@ -73,6 +61,28 @@ pub struct Block {
     pub transactions: Transactions,
 }
 
+/// A block with full transactions. It's serialized exactly like a [`Block`] with
+/// [`Transactions::Full`], but its transactions are converted to their RPC representation only
+/// while the block is serialized.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StreamedBlock {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<BlockStatus>,
+    #[serde(flatten)]
+    pub header: GeneralBlockHeader,
+    pub transactions: StreamedTransactions,
+}
+
+/// A block with its transactions and their receipts.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct BlockWithReceipts {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<BlockStatus>,
+    #[serde(flatten)]
+    pub header: GeneralBlockHeader,
+    pub transactions: Vec<TransactionAndReceipt>,
+}
+
 pub fn get_block_header_by_number<Mode: TransactionKind>(
     txn: &StorageTxn<'_, Mode>,
     block_number: BlockNumber,
@@ -92,34 +102,25 @@ pub(crate) fn get_accepted_block_number<Mode: TransactionKind>(
     txn: &StorageTxn<'_, Mode>,
     block_id: BlockId,
 ) -> Result<BlockNumber, ErrorObjectOwned> {
-    Ok(match block_id {
-        BlockId::HashOrNumber(BlockHashOrNumber::Hash(block_hash)) => {
-            let block_number = txn
-                .get_block_number_by_hash(&block_hash)
-                .map_err(internal_server_error)?
-                .ok_or_else(|| ErrorObjectOwned::from(BLOCK_NOT_FOUND))?;
+    resolve_accepted_block_number(txn, block_id, RequiredBlockData::State)
+        .map_err(internal_server_error)?
+        .ok_or_else(|| {
+            unresolved_block_error(txn, block_id, RequiredBlockData::State, BLOCK_NOT_FOUND.into())
+        })
+}
 
-            // Check that the block has state diff.
-            let last_block_number = get_latest_block_number(txn)?
-                .ok_or_else(|| ErrorObjectOwned::from(BLOCK_NOT_FOUND))?;
-            if block_number > last_block_number {
-                return Err(ErrorObjectOwned::from(BLOCK_NOT_FOUND));
-            }
-            block_number
-        }
-        BlockId::HashOrNumber(BlockHashOrNumber::Number(block_number)) => {
-            // Check that the block exists and has state diff.
-            let last_block_number = get_latest_block_number(txn)?
-                .ok_or_else(|| ErrorObjectOwned::from(BLOCK_NOT_FOUND))?;
-            if block_number > last_block_number {
-                return Err(ErrorObjectOwned::from(BLOCK_NOT_FOUND));
-            }
-            block_number
-        }
-        BlockId::Tag(Tag::Latest | Tag::Pending) => {
-            get_latest_block_number(txn)?.ok_or_else(|| ErrorObjectOwned::from(BLOCK_NOT_FOUND))?
-        }
-    })
+/// Like [`get_accepted_block_number`], but a block that is requested by its hash or number is
+/// found once its header and body were downloaded, even if its state diff wasn't downloaded yet.
+/// Used by methods that don't need the state diff of the block.
+pub(crate) fn get_accepted_block_number_with_body<Mode: TransactionKind>(
+    txn: &StorageTxn<'_, Mode>,
+    block_id: BlockId,
+) -> Result<BlockNumber, ErrorObjectOwned> {
+    resolve_accepted_block_number(txn, block_id, RequiredBlockData::Body)
+        .map_err(internal_server_error)?
+        .ok_or_else(|| {
+            unresolved_block_error(txn, block_id, RequiredBlockData::Body, BLOCK_NOT_FOUND.into())
+        })
 }
 
 /// Validates that a given block wasn't reverted. Given an instance of this class, we can call its
@@ -157,6 +158,10 @@ impl BlockNotRevertedValidator {
             .map_err(internal_server_error)?
             .ok_or(error.clone())?
             .block_hash;
-        if new_block_hash == self.old_block_hash { Ok(()) } else { Err(error) }
+        if new_block_hash == self.old_block_hash {
+            Ok(())
+        } else {
+            Err(error)
+        }
     }
 }
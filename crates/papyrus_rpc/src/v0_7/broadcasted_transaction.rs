@@ -12,7 +12,7 @@ mod broadcasted_transaction_test;
 
 use papyrus_storage::db::serialization::StorageSerdeError;
 use serde::{Deserialize, Serialize};
-use starknet_api::core::{CompiledClassHash, ContractAddress, Nonce};
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
 use starknet_api::data_availability::DataAvailabilityMode;
 use starknet_api::transaction::{
     AccountDeploymentData,
@@ -26,7 +26,18 @@ use starknet_client::writer::objects::transaction as client_transaction;
 use starknet_client::writer::objects::transaction::DeprecatedContractClass;
 
 use super::state::ContractClass;
-use super::transaction::{DeployAccountTransaction, InvokeTransaction, ResourceBoundsMapping};
+use super::transaction::{
+    DeclareTransaction,
+    DeclareTransactionV1,
+    DeclareTransactionV2,
+    DeclareTransactionV3,
+    DeployAccountTransaction,
+    InvokeTransaction,
+    ResourceBoundsMapping,
+    TransactionVersion1,
+    TransactionVersion2,
+    TransactionVersion3,
+};
 use crate::compression_utils::compress_and_encode;
 
 /// Transactions that are ready to be broadcasted to the network and are not included in a block.
@@ -180,10 +191,8 @@ impl TryFrom<BroadcastedDeclareTransaction> for client_transaction::DeclareTrans
                     nonce: declare_v3.nonce,
                     compiled_class_hash: declare_v3.compiled_class_hash,
                     sender_address: declare_v3.sender_address,
-                    nonce_data_availability_mode:
-                        client_transaction::ReservedDataAvailabilityMode::Reserved,
-                    fee_data_availability_mode:
-                        client_transaction::ReservedDataAvailabilityMode::Reserved,
+                    nonce_data_availability_mode: declare_v3.nonce_data_availability_mode.into(),
+                    fee_data_availability_mode: declare_v3.fee_data_availability_mode.into(),
                     paymaster_data: declare_v3.paymaster_data,
                     account_deployment_data: declare_v3.account_deployment_data,
                     version: TransactionVersion::THREE,
@@ -193,3 +202,49 @@ impl TryFrom<BroadcastedDeclareTransaction> for client_transaction::DeclareTrans
         }
     }
 }
+
+impl BroadcastedDeclareTransaction {
+    /// Returns the declare transaction as it appears in a block, where the contract class is
+    /// replaced by its hash.
+    pub fn into_declare_transaction(self, class_hash: ClassHash) -> DeclareTransaction {
+        match self {
+            BroadcastedDeclareTransaction::V1(declare_v1) => {
+                DeclareTransaction::Version1(DeclareTransactionV1 {
+                    class_hash,
+                    sender_address: declare_v1.sender_address,
+                    nonce: declare_v1.nonce,
+                    max_fee: declare_v1.max_fee,
+                    version: TransactionVersion1::Version1,
+                    signature: declare_v1.signature,
+                })
+            }
+            BroadcastedDeclareTransaction::V2(declare_v2) => {
+                DeclareTransaction::Version2(DeclareTransactionV2 {
+                    class_hash,
+                    compiled_class_hash: declare_v2.compiled_class_hash,
+                    sender_address: declare_v2.sender_address,
+                    nonce: declare_v2.nonce,
+                    max_fee: declare_v2.max_fee,
+                    version: TransactionVersion2::Version2,
+                    signature: declare_v2.signature,
+                })
+            }
+            BroadcastedDeclareTransaction::V3(declare_v3) => {
+                DeclareTransaction::Version3(DeclareTransactionV3 {
+                    resource_bounds: declare_v3.resource_bounds,
+                    tip: declare_v3.tip,
+                    signature: declare_v3.signature,
+                    nonce: declare_v3.nonce,
+                    class_hash,
+                    compiled_class_hash: declare_v3.compiled_class_hash,
+                    sender_address: declare_v3.sender_address,
+                    nonce_data_availability_mode: declare_v3.nonce_data_availability_mode,
+                    fee_data_availability_mode: declare_v3.fee_data_availability_mode,
+                    paymaster_data: declare_v3.paymaster_data,
+                    account_deployment_data: declare_v3.account_deployment_data,
+                    version: TransactionVersion3::Version3,
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,649 @@
+//! Conversions between the types of starknet_api and Papyrus and the objects of this version of
+//! the API.
+//!
+//! The objects of each version of the API differ from the internal types by the fields that the
+//! version added or dropped, such as the gas prices of a block header or the unit of the fee of a
+//! receipt. All of these differences are resolved here, so the handlers of the API only call
+//! `into` and `try_into`.
+
+#[cfg(test)]
+#[path = "conversions_test.rs"]
+mod conversions_test;
+
+use jsonrpsee::types::ErrorObjectOwned;
+use papyrus_execution::objects::PriceUnit;
+use papyrus_storage::body::events::ThinTransactionOutput;
+use starknet_api::block::{BlockStatus, GasPricePerToken};
+use starknet_api::state::ThinStateDiff as starknet_api_ThinStateDiff;
+use starknet_api::transaction::{Fee, Resource, TransactionVersion};
+use starknet_client::reader::objects::pending_data::PendingBlockOrDeprecated;
+
+use super::block::{BlockHeader, PendingBlockHeader, ResourcePrice};
+use super::state::{
+    ClassHashes,
+    ContractNonce,
+    DeployedContract,
+    ReplacedClasses,
+    StorageDiff,
+    StorageEntry,
+    ThinStateDiff,
+};
+use super::transaction::{
+    Builtin,
+    DeclareTransaction,
+    DeclareTransactionOutput,
+    DeclareTransactionV0,
+    DeclareTransactionV1,
+    DeclareTransactionV2,
+    DeclareTransactionV3,
+    DeployAccountTransaction,
+    DeployAccountTransactionOutput,
+    DeployAccountTransactionV1,
+    DeployAccountTransactionV3,
+    DeployTransactionOutput,
+    ExecutionResources,
+    FeePayment,
+    InvokeTransaction,
+    InvokeTransactionOutput,
+    InvokeTransactionV0,
+    InvokeTransactionV1,
+    InvokeTransactionV3,
+    L1HandlerTransactionOutput,
+    L1L2MsgHash,
+    ResourceBoundsMapping,
+    Transaction,
+    TransactionFinalityStatus,
+    TransactionOutput,
+    TransactionVersion0,
+    TransactionVersion1,
+    TransactionVersion2,
+    TransactionVersion3,
+};
+
+impl From<starknet_api::block::BlockHeader> for BlockHeader {
+    fn from(header: starknet_api::block::BlockHeader) -> Self {
+        BlockHeader {
+            block_hash: header.block_hash,
+            parent_hash: header.parent_hash,
+            block_number: header.block_number,
+            sequencer_address: header.sequencer,
+            new_root: header.state_root,
+            timestamp: header.timestamp,
+            l1_gas_price: header.l1_gas_price.into(),
+            l1_data_gas_price: header.l1_data_gas_price.into(),
+            l1_da_mode: header.l1_da_mode,
+            starknet_version: header.starknet_version.0,
+        }
+    }
+}
+
+impl From<&PendingBlockOrDeprecated> for PendingBlockHeader {
+    fn from(block: &PendingBlockOrDeprecated) -> Self {
+        PendingBlockHeader {
+            parent_hash: block.parent_block_hash(),
+            sequencer_address: block.sequencer_address(),
+            timestamp: block.timestamp(),
+            l1_gas_price: block.l1_gas_price().into(),
+            l1_data_gas_price: block.l1_data_gas_price().into(),
+            l1_da_mode: block.l1_da_mode(),
+            starknet_version: block.starknet_version(),
+        }
+    }
+}
+
+impl From<GasPricePerToken> for ResourcePrice {
+    fn from(gas_price: GasPricePerToken) -> Self {
+        ResourcePrice { price_in_wei: gas_price.price_in_wei, price_in_fri: gas_price.price_in_fri }
+    }
+}
+
+impl From<starknet_api_ThinStateDiff> for ThinStateDiff {
+    fn from(diff: starknet_api_ThinStateDiff) -> Self {
+        Self {
+            deployed_contracts: Vec::from_iter(
+                diff.deployed_contracts
+                    .into_iter()
+                    .map(|(address, class_hash)| DeployedContract { address, class_hash }),
+            ),
+            storage_diffs: Vec::from_iter(diff.storage_diffs.into_iter().map(
+                |(address, entries)| {
+                    let storage_entries = Vec::from_iter(
+                        entries.into_iter().map(|(key, value)| StorageEntry { key, value }),
+                    );
+                    StorageDiff { address, storage_entries }
+                },
+            )),
+            declared_classes: diff
+                .declared_classes
+                .into_iter()
+                .map(|(class_hash, compiled_class_hash)| ClassHashes {
+                    class_hash,
+                    compiled_class_hash,
+                })
+                .collect(),
+            deprecated_declared_classes: diff.deprecated_declared_classes,
+            nonces: Vec::from_iter(
+                diff.nonces
+                    .into_iter()
+                    .map(|(contract_address, nonce)| ContractNonce { contract_address, nonce }),
+            ),
+            replaced_classes: Vec::from_iter(diff.replaced_classes.into_iter().map(
+                |(contract_address, class_hash)| ReplacedClasses { contract_address, class_hash },
+            )),
+        }
+    }
+}
+
+impl From<starknet_api::transaction::DeclareTransactionV2> for DeclareTransactionV2 {
+    fn from(tx: starknet_api::transaction::DeclareTransactionV2) -> Self {
+        Self {
+            class_hash: tx.class_hash,
+            compiled_class_hash: tx.compiled_class_hash,
+            sender_address: tx.sender_address,
+            nonce: tx.nonce,
+            max_fee: tx.max_fee,
+            version: TransactionVersion2::Version2,
+            signature: tx.signature,
+        }
+    }
+}
+
+impl From<ResourceBoundsMapping> for starknet_api::transaction::ResourceBoundsMapping {
+    fn from(value: ResourceBoundsMapping) -> Self {
+        Self([(Resource::L1Gas, value.l1_gas), (Resource::L2Gas, value.l2_gas)].into())
+    }
+}
+
+impl From<starknet_api::transaction::ResourceBoundsMapping> for ResourceBoundsMapping {
+    fn from(value: starknet_api::transaction::ResourceBoundsMapping) -> Self {
+        Self {
+            l1_gas: value.0.get(&Resource::L1Gas).cloned().unwrap_or_default(),
+            l2_gas: value.0.get(&Resource::L2Gas).cloned().unwrap_or_default(),
+        }
+    }
+}
+
+impl From<starknet_api::transaction::DeclareTransactionV3> for DeclareTransactionV3 {
+    fn from(tx: starknet_api::transaction::DeclareTransactionV3) -> Self {
+        Self {
+            resource_bounds: tx.resource_bounds.into(),
+            tip: tx.tip,
+            signature: tx.signature,
+            nonce: tx.nonce,
+            class_hash: tx.class_hash,
+            compiled_class_hash: tx.compiled_class_hash,
+            sender_address: tx.sender_address,
+            nonce_data_availability_mode: tx.nonce_data_availability_mode,
+            fee_data_availability_mode: tx.fee_data_availability_mode,
+            paymaster_data: tx.paymaster_data,
+            account_deployment_data: tx.account_deployment_data,
+            version: TransactionVersion3::Version3,
+        }
+    }
+}
+
+impl TryFrom<starknet_api::transaction::DeployAccountTransaction> for DeployAccountTransaction {
+    type Error = ErrorObjectOwned;
+
+    fn try_from(
+        tx: starknet_api::transaction::DeployAccountTransaction,
+    ) -> Result<Self, Self::Error> {
+        match tx {
+            starknet_api::transaction::DeployAccountTransaction::V1(
+                starknet_api::transaction::DeployAccountTransactionV1 {
+                    max_fee,
+                    signature,
+                    nonce,
+                    class_hash,
+                    contract_address_salt,
+                    constructor_calldata,
+                },
+            ) => Ok(Self::Version1(DeployAccountTransactionV1 {
+                max_fee,
+                signature,
+                nonce,
+                class_hash,
+                contract_address_salt,
+                constructor_calldata,
+                version: TransactionVersion1::Version1,
+            })),
+            starknet_api::transaction::DeployAccountTransaction::V3(
+                starknet_api::transaction::DeployAccountTransactionV3 {
+                    resource_bounds,
+                    signature,
+                    nonce,
+                    class_hash,
+                    contract_address_salt,
+                    constructor_calldata,
+                    tip,
+                    nonce_data_availability_mode,
+                    fee_data_availability_mode,
+                    paymaster_data,
+                },
+            ) => Ok(Self::Version3(DeployAccountTransactionV3 {
+                signature,
+                nonce,
+                class_hash,
+                contract_address_salt,
+                constructor_calldata,
+                version: TransactionVersion3::Version3,
+                resource_bounds: resource_bounds.into(),
+                tip,
+                nonce_data_availability_mode,
+                fee_data_availability_mode,
+                paymaster_data,
+            })),
+        }
+    }
+}
+
+impl TryFrom<starknet_api::transaction::InvokeTransaction> for InvokeTransaction {
+    type Error = ErrorObjectOwned;
+
+    fn try_from(tx: starknet_api::transaction::InvokeTransaction) -> Result<Self, Self::Error> {
+        match tx {
+            starknet_api::transaction::InvokeTransaction::V0(
+                starknet_api::transaction::InvokeTransactionV0 {
+                    max_fee,
+                    signature,
+                    contract_address,
+                    entry_point_selector,
+                    calldata,
+                },
+            ) => Ok(Self::Version0(InvokeTransactionV0 {
+                max_fee,
+                version: TransactionVersion0::Version0,
+                signature,
+                contract_address,
+                entry_point_selector,
+                calldata,
+            })),
+            starknet_api::transaction::InvokeTransaction::V1(
+                starknet_api::transaction::InvokeTransactionV1 {
+                    max_fee,
+                    signature,
+                    nonce,
+                    sender_address,
+                    calldata,
+                },
+            ) => Ok(Self::Version1(InvokeTransactionV1 {
+                max_fee,
+                version: TransactionVersion1::Version1,
+                signature,
+                nonce,
+                sender_address,
+                calldata,
+            })),
+            starknet_api::transaction::InvokeTransaction::V3(
+                starknet_api::transaction::InvokeTransactionV3 {
+                    resource_bounds,
+                    tip,
+                    signature,
+                    nonce,
+                    sender_address,
+                    calldata,
+                    nonce_data_availability_mode,
+                    fee_data_availability_mode,
+                    paymaster_data,
+                    account_deployment_data,
+                },
+            ) => Ok(Self::Version3(InvokeTransactionV3 {
+                sender_address,
+                calldata,
+                version: TransactionVersion3::Version3,
+                signature,
+                nonce,
+                resource_bounds: resource_bounds.into(),
+                tip,
+                nonce_data_availability_mode,
+                fee_data_availability_mode,
+                paymaster_data,
+                account_deployment_data,
+            })),
+        }
+    }
+}
+
+impl TryFrom<starknet_api::transaction::Transaction> for Transaction {
+    type Error = ErrorObjectOwned;
+
+    fn try_from(tx: starknet_api::transaction::Transaction) -> Result<Self, Self::Error> {
+        match tx {
+            starknet_api::transaction::Transaction::Declare(declare_tx) => match declare_tx {
+                starknet_api::transaction::DeclareTransaction::V0(tx) => {
+                    Ok(Self::Declare(DeclareTransaction::Version0(DeclareTransactionV0 {
+                        class_hash: tx.class_hash,
+                        sender_address: tx.sender_address,
+                        nonce: tx.nonce,
+                        max_fee: tx.max_fee,
+                        version: TransactionVersion0::Version0,
+                        signature: tx.signature,
+                    })))
+                }
+                starknet_api::transaction::DeclareTransaction::V1(tx) => {
+                    Ok(Self::Declare(DeclareTransaction::Version1(DeclareTransactionV1 {
+                        class_hash: tx.class_hash,
+                        sender_address: tx.sender_address,
+                        nonce: tx.nonce,
+                        max_fee: tx.max_fee,
+                        version: TransactionVersion1::Version1,
+                        signature: tx.signature,
+                    })))
+                }
+                starknet_api::transaction::DeclareTransaction::V2(tx) => {
+                    Ok(Self::Declare(DeclareTransaction::Version2(tx.into())))
+                }
+                starknet_api::transaction::DeclareTransaction::V3(tx) => {
+                    Ok(Self::Declare(DeclareTransaction::Version3(tx.into())))
+                }
+            },
+            starknet_api::transaction::Transaction::Deploy(deploy_tx) => {
+                Ok(Transaction::Deploy(deploy_tx))
+            }
+            starknet_api::transaction::Transaction::DeployAccount(deploy_account_tx) => {
+                Ok(Self::DeployAccount(deploy_account_tx.try_into()?))
+            }
+            starknet_api::transaction::Transaction::Invoke(invoke_tx) => {
+                Ok(Self::Invoke(invoke_tx.try_into()?))
+            }
+            starknet_api::transaction::Transaction::L1Handler(l1_handler_tx) => {
+                Ok(Transaction::L1Handler(l1_handler_tx))
+            }
+        }
+    }
+}
+
+impl From<BlockStatus> for TransactionFinalityStatus {
+    fn from(status: BlockStatus) -> Self {
+        match status {
+            BlockStatus::AcceptedOnL1 => TransactionFinalityStatus::AcceptedOnL1,
+            BlockStatus::AcceptedOnL2 => TransactionFinalityStatus::AcceptedOnL2,
+            BlockStatus::Pending => TransactionFinalityStatus::AcceptedOnL2, /* for backward compatibility pending transactions are considered accepted on L2 */
+            // we convert the block status to transaction status only in the creation of
+            // TransactionReceiptWithStatus before that we verify that the block is not
+            // rejected so this conversion should never happen
+            BlockStatus::Rejected => unreachable!("Rejected blocks are not returned by the API"),
+        }
+    }
+}
+
+impl TransactionOutput {
+    pub fn from_thin_transaction_output(
+        thin_tx_output: ThinTransactionOutput,
+        tx_version: TransactionVersion,
+        events: Vec<starknet_api::transaction::Event>,
+        message_hash: Option<L1L2MsgHash>,
+    ) -> Self {
+        let actual_fee = fee_payment(thin_tx_output.actual_fee(), tx_version);
+        match thin_tx_output {
+            ThinTransactionOutput::Declare(thin_declare) => {
+                TransactionOutput::Declare(DeclareTransactionOutput {
+                    actual_fee,
+                    messages_sent: thin_declare.messages_sent,
+                    events,
+                    execution_status: thin_declare.execution_status,
+                    execution_resources: thin_declare.execution_resources.into(),
+                })
+            }
+            ThinTransactionOutput::Deploy(thin_deploy) => {
+                TransactionOutput::Deploy(DeployTransactionOutput {
+                    actual_fee,
+                    messages_sent: thin_deploy.messages_sent,
+                    events,
+                    contract_address: thin_deploy.contract_address,
+                    execution_status: thin_deploy.execution_status,
+                    execution_resources: thin_deploy.execution_resources.into(),
+                })
+            }
+            ThinTransactionOutput::DeployAccount(thin_deploy) => {
+                TransactionOutput::DeployAccount(DeployAccountTransactionOutput {
+                    actual_fee,
+                    messages_sent: thin_deploy.messages_sent,
+                    events,
+                    contract_address: thin_deploy.contract_address,
+                    execution_status: thin_deploy.execution_status,
+                    execution_resources: thin_deploy.execution_resources.into(),
+                })
+            }
+            ThinTransactionOutput::Invoke(thin_invoke) => {
+                TransactionOutput::Invoke(InvokeTransactionOutput {
+                    actual_fee,
+                    messages_sent: thin_invoke.messages_sent,
+                    events,
+                    execution_status: thin_invoke.execution_status,
+                    execution_resources: thin_invoke.execution_resources.into(),
+                })
+            }
+            ThinTransactionOutput::L1Handler(thin_l1handler) => {
+                TransactionOutput::L1Handler(L1HandlerTransactionOutput {
+                    actual_fee,
+                    messages_sent: thin_l1handler.messages_sent,
+                    events,
+                    execution_status: thin_l1handler.execution_status,
+                    execution_resources: thin_l1handler.execution_resources.into(),
+                    message_hash: message_hash
+                        .expect("Missing message hash to construct L1Handler output."),
+                })
+            }
+        }
+    }
+}
+
+impl From<starknet_api::transaction::Builtin> for Builtin {
+    fn from(builtin: starknet_api::transaction::Builtin) -> Self {
+        match builtin {
+            starknet_api::transaction::Builtin::RangeCheck => Builtin::RangeCheck,
+            starknet_api::transaction::Builtin::Pedersen => Builtin::Pedersen,
+            starknet_api::transaction::Builtin::Poseidon => Builtin::Poseidon,
+            starknet_api::transaction::Builtin::EcOp => Builtin::EcOp,
+            starknet_api::transaction::Builtin::Ecdsa => Builtin::Ecdsa,
+            starknet_api::transaction::Builtin::Bitwise => Builtin::Bitwise,
+            starknet_api::transaction::Builtin::Keccak => Builtin::Keccak,
+            starknet_api::transaction::Builtin::SegmentArena => Builtin::SegmentArena,
+        }
+    }
+}
+
+impl From<starknet_api::transaction::ExecutionResources> for ExecutionResources {
+    fn from(value: starknet_api::transaction::ExecutionResources) -> Self {
+        Self {
+            steps: value.steps,
+            builtin_instance_counter: value
+                .builtin_instance_counter
+                .into_iter()
+                .filter_map(|(k, v)| match v {
+                    0 => None,
+                    _ => Some((k.into(), v)),
+                })
+                .collect(),
+            memory_holes: match value.memory_holes {
+                0 => None,
+                _ => Some(value.memory_holes),
+            },
+        }
+    }
+}
+
+impl From<(starknet_api::transaction::TransactionOutput, TransactionVersion, Option<L1L2MsgHash>)>
+    for TransactionOutput
+{
+    #[cfg_attr(coverage_nightly, coverage_attribute)]
+    fn from(
+        tx_output_msg_hash: (
+            starknet_api::transaction::TransactionOutput,
+            TransactionVersion,
+            Option<L1L2MsgHash>,
+        ),
+    ) -> Self {
+        let (tx_output, tx_version, maybe_msg_hash) = tx_output_msg_hash;
+        let actual_fee = fee_payment(tx_output.actual_fee(), tx_version);
+        match tx_output {
+            starknet_api::transaction::TransactionOutput::Declare(declare_tx_output) => {
+                TransactionOutput::Declare(DeclareTransactionOutput {
+                    actual_fee,
+                    messages_sent: declare_tx_output.messages_sent,
+                    events: declare_tx_output.events,
+                    execution_status: declare_tx_output.execution_status,
+                    execution_resources: declare_tx_output.execution_resources.into(),
+                })
+            }
+            starknet_api::transaction::TransactionOutput::Deploy(deploy_tx_output) => {
+                TransactionOutput::Deploy(DeployTransactionOutput {
+                    actual_fee,
+                    messages_sent: deploy_tx_output.messages_sent,
+                    events: deploy_tx_output.events,
+                    contract_address: deploy_tx_output.contract_address,
+                    execution_status: deploy_tx_output.execution_status,
+                    execution_resources: deploy_tx_output.execution_resources.into(),
+                })
+            }
+            starknet_api::transaction::TransactionOutput::DeployAccount(deploy_tx_output) => {
+                TransactionOutput::DeployAccount(DeployAccountTransactionOutput {
+                    actual_fee,
+                    messages_sent: deploy_tx_output.messages_sent,
+                    events: deploy_tx_output.events,
+                    contract_address: deploy_tx_output.contract_address,
+                    execution_status: deploy_tx_output.execution_status,
+                    execution_resources: deploy_tx_output.execution_resources.into(),
+                })
+            }
+            starknet_api::transaction::TransactionOutput::Invoke(invoke_tx_output) => {
+                TransactionOutput::Invoke(InvokeTransactionOutput {
+                    actual_fee,
+                    messages_sent: invoke_tx_output.messages_sent,
+                    events: invoke_tx_output.events,
+                    execution_status: invoke_tx_output.execution_status,
+                    execution_resources: invoke_tx_output.execution_resources.into(),
+                })
+            }
+            starknet_api::transaction::TransactionOutput::L1Handler(l1_handler_tx_output) => {
+                TransactionOutput::L1Handler(L1HandlerTransactionOutput {
+                    actual_fee,
+                    messages_sent: l1_handler_tx_output.messages_sent,
+                    events: l1_handler_tx_output.events,
+                    execution_status: l1_handler_tx_output.execution_status,
+                    execution_resources: l1_handler_tx_output.execution_resources.into(),
+                    message_hash: maybe_msg_hash
+                        .expect("Missing message hash to construct L1Handler output."),
+                })
+            }
+        }
+    }
+}
+
+impl From<DeployAccountTransaction> for starknet_api::transaction::DeployAccountTransaction {
+    fn from(tx: DeployAccountTransaction) -> Self {
+        match tx {
+            DeployAccountTransaction::Version1(DeployAccountTransactionV1 {
+                max_fee,
+                signature,
+                nonce,
+                class_hash,
+                contract_address_salt,
+                constructor_calldata,
+                version: _,
+            }) => Self::V1(starknet_api::transaction::DeployAccountTransactionV1 {
+                max_fee,
+                signature,
+                nonce,
+                class_hash,
+                contract_address_salt,
+                constructor_calldata,
+            }),
+            DeployAccountTransaction::Version3(DeployAccountTransactionV3 {
+                signature,
+                nonce,
+                class_hash,
+                contract_address_salt,
+                constructor_calldata,
+                version: _,
+                resource_bounds,
+                tip,
+                paymaster_data,
+                nonce_data_availability_mode,
+                fee_data_availability_mode,
+            }) => Self::V3(starknet_api::transaction::DeployAccountTransactionV3 {
+                resource_bounds: resource_bounds.into(),
+                tip,
+                signature,
+                nonce,
+                class_hash,
+                contract_address_salt,
+                constructor_calldata,
+                nonce_data_availability_mode,
+                fee_data_availability_mode,
+                paymaster_data,
+            }),
+        }
+    }
+}
+
+impl From<InvokeTransaction> for starknet_api::transaction::InvokeTransaction {
+    fn from(value: InvokeTransaction) -> Self {
+        match value {
+            InvokeTransaction::Version0(InvokeTransactionV0 {
+                max_fee,
+                version: _,
+                signature,
+                contract_address,
+                entry_point_selector,
+                calldata,
+            }) => Self::V0(starknet_api::transaction::InvokeTransactionV0 {
+                max_fee,
+                signature,
+                contract_address,
+                entry_point_selector,
+                calldata,
+            }),
+            InvokeTransaction::Version1(InvokeTransactionV1 {
+                max_fee,
+                version: _,
+                signature,
+                nonce,
+                sender_address,
+                calldata,
+            }) => Self::V1(starknet_api::transaction::InvokeTransactionV1 {
+                max_fee,
+                signature,
+                nonce,
+                sender_address,
+                calldata,
+            }),
+            InvokeTransaction::Version3(InvokeTransactionV3 {
+                sender_address,
+                calldata,
+                version: _,
+                signature,
+                nonce,
+                resource_bounds,
+                tip,
+                paymaster_data,
+                account_deployment_data,
+                nonce_data_availability_mode,
+                fee_data_availability_mode,
+            }) => Self::V3(starknet_api::transaction::InvokeTransactionV3 {
+                resource_bounds: resource_bounds.into(),
+                tip,
+                signature,
+                nonce,
+                sender_address,
+                calldata,
+                nonce_data_availability_mode,
+                fee_data_availability_mode,
+                paymaster_data,
+                account_deployment_data,
+            }),
+        }
+    }
+}
+
+// Transactions before version 3 pay their fee in Wei and transactions of version 3 pay it in Fri.
+fn fee_payment(amount: Fee, tx_version: TransactionVersion) -> FeePayment {
+    match tx_version {
+        TransactionVersion::ZERO | TransactionVersion::ONE | TransactionVersion::TWO => {
+            FeePayment { amount, unit: PriceUnit::Wei }
+        }
+        TransactionVersion::THREE => FeePayment { amount, unit: PriceUnit::Fri },
+        _ => unreachable!("Invalid transaction version."),
+    }
+}
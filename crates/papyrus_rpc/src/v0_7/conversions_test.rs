@@ -0,0 +1,31 @@
+use papyrus_execution::objects::PriceUnit;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{GasPrice, GasPricePerToken};
+use starknet_api::transaction::{Fee, TransactionVersion};
+
+use super::super::block::ResourcePrice;
+use super::super::transaction::FeePayment;
+use super::fee_payment;
+
+#[test]
+fn fee_unit_by_transaction_version() {
+    for tx_version in [TransactionVersion::ZERO, TransactionVersion::ONE, TransactionVersion::TWO] {
+        assert_eq!(
+            fee_payment(Fee(5), tx_version),
+            FeePayment { amount: Fee(5), unit: PriceUnit::Wei }
+        );
+    }
+    assert_eq!(
+        fee_payment(Fee(5), TransactionVersion::THREE),
+        FeePayment { amount: Fee(5), unit: PriceUnit::Fri }
+    );
+}
+
+#[test]
+fn resource_price_from_gas_price() {
+    let gas_price = GasPricePerToken { price_in_wei: GasPrice(1), price_in_fri: GasPrice(2) };
+    assert_eq!(
+        ResourcePrice::from(gas_price),
+        ResourcePrice { price_in_wei: GasPrice(1), price_in_fri: GasPrice(2) }
+    );
+}
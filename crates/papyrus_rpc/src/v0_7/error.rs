@@ -125,6 +125,57 @@ pub fn unexpected_error(data: String) -> JsonRpcError<String> {
     JsonRpcError { code: 63, message: "An unexpected error occurred", data: Some(data) }
 }
 
+// Papyrus specific errors for request parameters that can be parsed but aren't valid Starknet
+// values. Their codes are in the range JSON-RPC reserves for implementation-defined server errors.
+
+pub fn invalid_felt(data: String) -> JsonRpcError<String> {
+    JsonRpcError {
+        code: -32010,
+        message: "Felt is not smaller than the field prime",
+        data: Some(data),
+    }
+}
+
+pub fn invalid_contract_address(data: String) -> JsonRpcError<String> {
+    JsonRpcError {
+        code: -32011,
+        message: "Contract address is out of the contract address range",
+        data: Some(data),
+    }
+}
+
+// A continuation token that was valid when issued but whose blocks were reverted since.
+pub const STALE_CONTINUATION_TOKEN: JsonRpcError<String> = JsonRpcError {
+    code: -32012,
+    message: "The continuation token was issued before a reorg of the blocks it points to",
+    data: None,
+};
+
+// A block whose header was downloaded but whose data that the method needs wasn't downloaded yet.
+pub fn block_not_fully_synced<T: Serialize>(data: T) -> JsonRpcError<T> {
+    JsonRpcError { code: -32013, message: "Block not fully synced", data: Some(data) }
+}
+
+// The state after a block whose state diff the sync quarantined, which is wrong since the state
+// diff of that block is missing.
+pub fn state_quarantined(data: String) -> JsonRpcError<String> {
+    JsonRpcError {
+        code: -32014,
+        message: "The state is after a block whose state diff was quarantined",
+        data: Some(data),
+    }
+}
+
+// Data that the node doesn't keep in the mode it runs in, e.g. the state of an old block in Full
+// mode.
+pub fn not_kept_in_node_mode(data: String) -> JsonRpcError<String> {
+    JsonRpcError {
+        code: -32015,
+        message: "The data isn't kept in the mode the node runs in",
+        data: Some(data),
+    }
+}
+
 impl<T: Serialize> From<JsonRpcError<T>> for ErrorObjectOwned {
     fn from(err: JsonRpcError<T>) -> Self {
         ErrorObjectOwned::owned(err.code, err.message, err.data)
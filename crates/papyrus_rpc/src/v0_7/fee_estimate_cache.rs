@@ -0,0 +1,102 @@
+//! A short-lived cache of the results of fee estimations.
+//!
+//! Wallets poll the fee estimation of the same transactions while their users look at the
+//! confirmation screen, so an estimation of identical transactions on the same block is answered
+//! from the cache instead of being executed again. Estimations on the pending block aren't cached,
+//! since its state changes while it's the same block.
+#[cfg(test)]
+#[path = "fee_estimate_cache_test.rs"]
+mod fee_estimate_cache_test;
+
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use lru::LruCache;
+use papyrus_common::metrics::{PAPYRUS_CACHE_HITS, PAPYRUS_CACHE_MISSES};
+use starknet_api::block::BlockHash;
+
+use super::api::FeeEstimate;
+use super::broadcasted_transaction::BroadcastedTransaction;
+
+// The name of the cache in its metrics.
+const CACHE_NAME: &str = "fee_estimates";
+
+/// Identifies a fee estimation by the block it's on, whether the transactions are validated and
+/// the transactions themselves. The transactions are kept in full rather than hashed, so that
+/// different transactions never share an estimation.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct FeeEstimateKey {
+    block_hash: BlockHash,
+    validate: bool,
+    transactions: Vec<u8>,
+}
+
+impl FeeEstimateKey {
+    pub fn new(block_hash: BlockHash, validate: bool, transactions: Vec<u8>) -> Self {
+        Self { block_hash, validate, transactions }
+    }
+}
+
+/// Returns the serialization of the transactions, which covers the classes and entry points they
+/// call and their calldata.
+pub fn serialize_transactions(transactions: &[BroadcastedTransaction]) -> Vec<u8> {
+    serde_json::to_vec(transactions).expect("Broadcasted transactions should be serializable.")
+}
+
+/// The results of the most recent fee estimations, each kept for a fixed time.
+#[derive(Default)]
+pub struct FeeEstimateCache {
+    ttl: Duration,
+    // None if the cache is disabled.
+    estimates: Option<Mutex<LruCache<FeeEstimateKey, (Instant, Vec<FeeEstimate>)>>>,
+}
+
+impl FeeEstimateCache {
+    /// Creates a cache that keeps up to `size` of the most recent estimations, each for the given
+    /// time. A zero time or size disables the cache.
+    pub fn new(ttl: Duration, size: usize) -> Self {
+        let estimates = match NonZeroUsize::new(size) {
+            Some(size) if !ttl.is_zero() => Some(Mutex::new(LruCache::new(size))),
+            _ => None,
+        };
+        Self { ttl, estimates }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.estimates.is_some()
+    }
+
+    pub fn get(&self, key: &FeeEstimateKey) -> Option<Vec<FeeEstimate>> {
+        let mut estimates = self
+            .estimates
+            .as_ref()?
+            .lock()
+            .expect("Fee estimate cache lock should not be poisoned.");
+        let fee_estimates = match estimates.get(key) {
+            Some((inserted_at, fee_estimates)) if inserted_at.elapsed() < self.ttl => {
+                Some(fee_estimates.clone())
+            }
+            Some(_) => {
+                estimates.pop(key);
+                None
+            }
+            None => None,
+        };
+        match fee_estimates {
+            Some(_) => metrics::increment_counter!(PAPYRUS_CACHE_HITS, "cache" => CACHE_NAME),
+            None => metrics::increment_counter!(PAPYRUS_CACHE_MISSES, "cache" => CACHE_NAME),
+        }
+        fee_estimates
+    }
+
+    pub fn insert(&self, key: FeeEstimateKey, fee_estimates: Vec<FeeEstimate>) {
+        let Some(estimates) = &self.estimates else {
+            return;
+        };
+        estimates
+            .lock()
+            .expect("Fee estimate cache lock should not be poisoned.")
+            .put(key, (Instant::now(), fee_estimates));
+    }
+}
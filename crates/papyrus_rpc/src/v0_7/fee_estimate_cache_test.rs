@@ -0,0 +1,47 @@
+use std::time::Duration;
+
+use pretty_assertions::assert_eq;
+use starknet_api::block::BlockHash;
+use starknet_api::hash::StarkHash;
+
+use super::{FeeEstimateCache, FeeEstimateKey};
+use crate::v0_7::api::FeeEstimate;
+
+fn key(validate: bool, transactions: &[u8]) -> FeeEstimateKey {
+    FeeEstimateKey::new(BlockHash(StarkHash::ZERO), validate, transactions.to_vec())
+}
+
+#[test]
+fn estimates_expire() {
+    let cache = FeeEstimateCache::new(Duration::from_millis(50), 10);
+    let fee_estimates = vec![FeeEstimate::default()];
+    cache.insert(key(true, b"txs"), fee_estimates.clone());
+    assert_eq!(cache.get(&key(true, b"txs")), Some(fee_estimates));
+    assert_eq!(cache.get(&key(false, b"txs")), None);
+    assert_eq!(cache.get(&key(true, b"other txs")), None);
+
+    std::thread::sleep(Duration::from_millis(60));
+    assert_eq!(cache.get(&key(true, b"txs")), None);
+}
+
+#[test]
+fn disabled_cache_keeps_nothing() {
+    for cache in [
+        FeeEstimateCache::default(),
+        FeeEstimateCache::new(Duration::ZERO, 10),
+        FeeEstimateCache::new(Duration::from_secs(1), 0),
+    ] {
+        assert!(!cache.is_enabled());
+        cache.insert(key(true, b"txs"), vec![FeeEstimate::default()]);
+        assert_eq!(cache.get(&key(true, b"txs")), None);
+    }
+}
+
+#[test]
+fn least_recently_used_estimates_are_evicted() {
+    let cache = FeeEstimateCache::new(Duration::from_secs(60), 1);
+    cache.insert(key(true, b"txs"), vec![FeeEstimate::default()]);
+    cache.insert(key(true, b"other txs"), vec![FeeEstimate::default()]);
+    assert_eq!(cache.get(&key(true, b"txs")), None);
+    assert!(cache.get(&key(true, b"other txs")).is_some());
+}
@@ -1,11 +1,14 @@
 pub mod api;
 pub mod block;
 pub mod broadcasted_transaction;
+mod conversions;
 pub mod deprecated_contract_class;
 pub mod error;
 #[cfg(test)]
 mod execution_test;
+pub mod fee_estimate_cache;
 pub mod state;
+pub mod submitted_transactions;
 pub mod transaction;
 pub mod write_api_error;
 pub mod write_api_result;
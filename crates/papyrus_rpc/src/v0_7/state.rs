@@ -1,5 +1,7 @@
 use std::collections::HashMap;
 
+use indexmap::IndexMap;
+use jsonrpsee::types::ErrorObjectOwned;
 use serde::{Deserialize, Serialize};
 use starknet_api::block::BlockHash;
 use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, GlobalRoot, Nonce};
@@ -18,6 +20,8 @@ use starknet_client::reader::objects::state::{
     StorageEntry as ClientStorageEntry,
 };
 
+use crate::streaming::StreamedSeq;
+
 const CONTRACT_CLASS_VERSION: &str = "0.1.0";
 
 #[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
@@ -42,53 +46,75 @@ pub struct PendingStateUpdate {
     pub state_diff: ThinStateDiff,
 }
 
-#[derive(Debug, Default, Clone, Eq, PartialEq, Deserialize, Serialize)]
-pub struct ThinStateDiff {
+/// A state update, serialized exactly like a [`StateUpdate`]. The state diff of an accepted state
+/// update is converted to its RPC representation only while it's serialized.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum StreamedStateUpdate {
+    AcceptedStateUpdate(StreamedAcceptedStateUpdate),
+    PendingStateUpdate(PendingStateUpdate),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StreamedAcceptedStateUpdate {
+    pub block_hash: BlockHash,
+    pub new_root: GlobalRoot,
+    pub old_root: GlobalRoot,
+    pub state_diff: StreamedThinStateDiff,
+}
+
+/// A [`ThinStateDiff`] whose storage diffs, which are the bulk of big state diffs, are converted
+/// only while they're serialized.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StreamedThinStateDiff {
     pub deployed_contracts: Vec<DeployedContract>,
-    pub storage_diffs: Vec<StorageDiff>,
+    pub storage_diffs: StreamedSeq<(ContractAddress, IndexMap<StorageKey, StarkFelt>), StorageDiff>,
     pub declared_classes: Vec<ClassHashes>,
     pub deprecated_declared_classes: Vec<ClassHash>,
     pub nonces: Vec<ContractNonce>,
     pub replaced_classes: Vec<ReplacedClasses>,
 }
 
-impl From<starknet_api_ThinStateDiff> for ThinStateDiff {
-    fn from(diff: starknet_api_ThinStateDiff) -> Self {
+impl From<starknet_api_ThinStateDiff> for StreamedThinStateDiff {
+    fn from(mut diff: starknet_api_ThinStateDiff) -> Self {
+        let storage_diffs = std::mem::take(&mut diff.storage_diffs);
+        let ThinStateDiff {
+            deployed_contracts,
+            storage_diffs: _,
+            declared_classes,
+            deprecated_declared_classes,
+            nonces,
+            replaced_classes,
+        } = diff.into();
         Self {
-            deployed_contracts: Vec::from_iter(
-                diff.deployed_contracts
-                    .into_iter()
-                    .map(|(address, class_hash)| DeployedContract { address, class_hash }),
-            ),
-            storage_diffs: Vec::from_iter(diff.storage_diffs.into_iter().map(
-                |(address, entries)| {
-                    let storage_entries = Vec::from_iter(
-                        entries.into_iter().map(|(key, value)| StorageEntry { key, value }),
-                    );
-                    StorageDiff { address, storage_entries }
-                },
-            )),
-            declared_classes: diff
-                .declared_classes
-                .into_iter()
-                .map(|(class_hash, compiled_class_hash)| ClassHashes {
-                    class_hash,
-                    compiled_class_hash,
-                })
-                .collect(),
-            deprecated_declared_classes: diff.deprecated_declared_classes,
-            nonces: Vec::from_iter(
-                diff.nonces
-                    .into_iter()
-                    .map(|(contract_address, nonce)| ContractNonce { contract_address, nonce }),
-            ),
-            replaced_classes: Vec::from_iter(diff.replaced_classes.into_iter().map(
-                |(contract_address, class_hash)| ReplacedClasses { contract_address, class_hash },
-            )),
+            deployed_contracts,
+            storage_diffs: StreamedSeq::new(storage_diffs.into_iter().collect(), to_storage_diff),
+            declared_classes,
+            deprecated_declared_classes,
+            nonces,
+            replaced_classes,
         }
     }
 }
 
+fn to_storage_diff(
+    (address, entries): (ContractAddress, IndexMap<StorageKey, StarkFelt>),
+) -> Result<StorageDiff, ErrorObjectOwned> {
+    let storage_entries =
+        Vec::from_iter(entries.into_iter().map(|(key, value)| StorageEntry { key, value }));
+    Ok(StorageDiff { address, storage_entries })
+}
+
+#[derive(Debug, Default, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct ThinStateDiff {
+    pub deployed_contracts: Vec<DeployedContract>,
+    pub storage_diffs: Vec<StorageDiff>,
+    pub declared_classes: Vec<ClassHashes>,
+    pub deprecated_declared_classes: Vec<ClassHash>,
+    pub nonces: Vec<ContractNonce>,
+    pub replaced_classes: Vec<ReplacedClasses>,
+}
+
 impl From<ClientStateDiff> for ThinStateDiff {
     fn from(diff: ClientStateDiff) -> Self {
         Self {
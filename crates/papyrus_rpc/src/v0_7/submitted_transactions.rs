@@ -0,0 +1,46 @@
+//! A cache of the transactions that were submitted to the sequencer through this node.
+//!
+//! A submitted transaction is known to the sequencer before it's included in a block, so the cache
+//! lets the node report such transactions as received instead of not found.
+
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+
+use lru::LruCache;
+use starknet_api::transaction::TransactionHash;
+
+use super::transaction::Transaction;
+
+/// The most recently submitted transactions.
+#[derive(Default)]
+pub struct SubmittedTransactions {
+    // None if the cache is disabled.
+    transactions: Option<Mutex<LruCache<TransactionHash, Arc<Transaction>>>>,
+}
+
+impl SubmittedTransactions {
+    /// Creates a cache that keeps up to `size` of the most recently submitted transactions. A zero
+    /// size disables the cache.
+    pub fn new(size: usize) -> Self {
+        Self { transactions: NonZeroUsize::new(size).map(|size| Mutex::new(LruCache::new(size))) }
+    }
+
+    pub fn insert(&self, transaction_hash: TransactionHash, transaction: Transaction) {
+        let Some(transactions) = &self.transactions else {
+            return;
+        };
+        transactions
+            .lock()
+            .expect("Submitted transactions lock should not be poisoned.")
+            .put(transaction_hash, Arc::new(transaction));
+    }
+
+    pub fn get(&self, transaction_hash: &TransactionHash) -> Option<Arc<Transaction>> {
+        self.transactions
+            .as_ref()?
+            .lock()
+            .expect("Submitted transactions lock should not be poisoned.")
+            .get(transaction_hash)
+            .cloned()
+    }
+}
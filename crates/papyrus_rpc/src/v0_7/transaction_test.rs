@@ -9,7 +9,14 @@ use papyrus_storage::body::events::{
     ThinTransactionOutput,
 };
 use pretty_assertions::assert_eq;
-use starknet_api::core::{ClassHash, ContractAddress, EntryPointSelector, Nonce, PatriciaKey};
+use starknet_api::core::{
+    ChainId,
+    ClassHash,
+    ContractAddress,
+    EntryPointSelector,
+    Nonce,
+    PatriciaKey,
+};
 use starknet_api::data_availability::DataAvailabilityMode;
 use starknet_api::hash::{StarkFelt, StarkHash};
 use starknet_api::transaction::{
@@ -26,7 +33,13 @@ use starknet_api::transaction::{
 };
 use starknet_api::{calldata, contract_address, patricia_key, stark_felt};
 use starknet_client::writer::objects::transaction as client_transaction;
-use test_utils::{auto_impl_get_test_instance, get_number_of_variants, get_rng, GetTestInstance};
+use test_utils::{
+    auto_impl_get_test_instance,
+    get_number_of_variants,
+    get_rng,
+    get_test_transactions_with_hashes,
+    GetTestInstance,
+};
 
 use super::super::transaction::{L1HandlerMsgHash, L1L2MsgHash};
 use super::{
@@ -258,6 +271,61 @@ fn test_invoke_transaction_to_client_transaction() {
         InvokeTransactionV3::get_test_instance(&mut get_rng()).into();
 }
 
+// The data availability modes and resource bounds of V3 transactions are part of their hash, so
+// they must reach the gateway as they were signed.
+#[test]
+fn v3_transactions_to_client_transactions_keep_every_field() {
+    let mut rng = get_rng();
+    let mut invoke_tx = InvokeTransactionV3::get_test_instance(&mut rng);
+    invoke_tx.nonce_data_availability_mode = DataAvailabilityMode::L2;
+    invoke_tx.fee_data_availability_mode = DataAvailabilityMode::L1;
+    let client_transaction::InvokeTransaction::InvokeV3(client_invoke_tx) =
+        invoke_tx.clone().into()
+    else {
+        panic!("Expected an invoke V3 transaction.");
+    };
+    assert_eq!(
+        client_invoke_tx.nonce_data_availability_mode,
+        client_transaction::DataAvailabilityMode::L2
+    );
+    assert_eq!(
+        client_invoke_tx.fee_data_availability_mode,
+        client_transaction::DataAvailabilityMode::L1
+    );
+    assert_eq!(client_invoke_tx.resource_bounds, invoke_tx.resource_bounds.into());
+
+    let mut deploy_account_tx = DeployAccountTransactionV3::get_test_instance(&mut rng);
+    deploy_account_tx.nonce_data_availability_mode = DataAvailabilityMode::L1;
+    deploy_account_tx.fee_data_availability_mode = DataAvailabilityMode::L2;
+    let client_transaction::DeployAccountTransaction::DeployAccountV3(client_deploy_account_tx) =
+        DeployAccountTransaction::Version3(deploy_account_tx.clone()).into()
+    else {
+        panic!("Expected a deploy account V3 transaction.");
+    };
+    assert_eq!(
+        client_deploy_account_tx.nonce_data_availability_mode,
+        client_transaction::DataAvailabilityMode::L1
+    );
+    assert_eq!(
+        client_deploy_account_tx.fee_data_availability_mode,
+        client_transaction::DataAvailabilityMode::L2
+    );
+    assert_eq!(client_deploy_account_tx.resource_bounds, deploy_account_tx.resource_bounds.into());
+}
+
+// The versions of a transaction type are untagged, so every version must be loaded back as itself
+// and not as another version that lacks some of its fields.
+#[test]
+fn transaction_of_every_type_and_version_serde_round_trip() {
+    let transactions =
+        get_test_transactions_with_hashes(&mut get_rng(), &ChainId("SN_MAIN".to_owned()));
+    for (transaction, _transaction_hash) in transactions {
+        let transaction: super::Transaction = transaction.try_into().unwrap();
+        let serialized = serde_json::to_value(&transaction).unwrap();
+        assert_eq!(serde_json::from_value::<super::Transaction>(serialized).unwrap(), transaction);
+    }
+}
+
 #[test]
 fn l1handler_msg_hash() {
     let msg_hash = format!("{}", L1_HANDLER_TX.calc_msg_hash());
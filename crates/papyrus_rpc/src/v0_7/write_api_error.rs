@@ -1,4 +1,7 @@
+use jsonrpsee::types::ErrorObjectOwned;
 use starknet_client::starknet_error::{KnownStarknetErrorCode, StarknetError, StarknetErrorCode};
+use starknet_client::writer::WriterClientError;
+use starknet_client::{ClientError, RetryErrorCode};
 
 use super::error::{
     unexpected_error,
@@ -17,11 +20,33 @@ use super::error::{
     UNSUPPORTED_CONTRACT_CLASS_VERSION,
     UNSUPPORTED_TX_VERSION,
 };
+use crate::internal_server_error;
 
 #[cfg(test)]
 #[path = "write_api_error_test.rs"]
 mod write_api_error_test;
 
+/// Converts an error of adding a transaction through the gateway to the error of the write method.
+/// A transaction the gateway rejected is reported with the RPC error of the rejection reason,
+/// translated by `starknet_error_to_rpc_error`, and errors of reaching the gateway are reported as
+/// internal errors.
+pub(crate) fn writer_client_error_to_rpc_error(
+    error: WriterClientError,
+    starknet_error_to_rpc_error: fn(StarknetError) -> JsonRpcError<String>,
+) -> ErrorObjectOwned {
+    match error {
+        WriterClientError::ClientError(ClientError::StarknetError(starknet_error)) => {
+            starknet_error_to_rpc_error(starknet_error).into()
+        }
+        // The gateway kept rejecting the transaction for exceeding its rate limit while retrying.
+        WriterClientError::ClientError(ClientError::RetryError {
+            code: RetryErrorCode::TooManyRequests,
+            message,
+        }) => unexpected_error(message).into(),
+        err => internal_server_error(err),
+    }
+}
+
 pub(crate) fn starknet_error_to_invoke_error(error: StarknetError) -> JsonRpcError<String> {
     let StarknetErrorCode::KnownErrorCode(known_error_code) = error.code else {
         return unexpected_error(error.message);
@@ -38,7 +63,9 @@ pub(crate) fn starknet_error_to_invoke_error(error: StarknetError) -> JsonRpcErr
         KnownStarknetErrorCode::InsufficientMaxFee => INSUFFICIENT_MAX_FEE,
         KnownStarknetErrorCode::InvalidTransactionNonce => INVALID_TRANSACTION_NONCE,
         KnownStarknetErrorCode::InvalidTransactionVersion => UNSUPPORTED_TX_VERSION,
-        KnownStarknetErrorCode::ValidateFailure => validation_failure(error.message),
+        // A validation that called a syscall which isn't allowed in __validate__ failed.
+        KnownStarknetErrorCode::UnauthorizedActionOnValidate
+        | KnownStarknetErrorCode::ValidateFailure => validation_failure(error.message),
         _ => unexpected_error(error.message),
     }
 }
@@ -61,7 +88,8 @@ pub(crate) fn starknet_error_to_declare_error(error: StarknetError) -> JsonRpcEr
         KnownStarknetErrorCode::InvalidContractClassVersion => UNSUPPORTED_CONTRACT_CLASS_VERSION,
         KnownStarknetErrorCode::InvalidTransactionNonce => INVALID_TRANSACTION_NONCE,
         KnownStarknetErrorCode::InvalidTransactionVersion => UNSUPPORTED_TX_VERSION,
-        KnownStarknetErrorCode::ValidateFailure => validation_failure(error.message),
+        KnownStarknetErrorCode::UnauthorizedActionOnValidate
+        | KnownStarknetErrorCode::ValidateFailure => validation_failure(error.message),
         _ => unexpected_error(error.message),
     }
 }
@@ -79,7 +107,8 @@ pub(crate) fn starknet_error_to_deploy_account_error(error: StarknetError) -> Js
         KnownStarknetErrorCode::InvalidTransactionNonce => INVALID_TRANSACTION_NONCE,
         KnownStarknetErrorCode::InvalidTransactionVersion => UNSUPPORTED_TX_VERSION,
         KnownStarknetErrorCode::UndeclaredClass => CLASS_HASH_NOT_FOUND,
-        KnownStarknetErrorCode::ValidateFailure => validation_failure(error.message),
+        KnownStarknetErrorCode::UnauthorizedActionOnValidate
+        | KnownStarknetErrorCode::ValidateFailure => validation_failure(error.message),
         _ => unexpected_error(error.message),
     }
 }
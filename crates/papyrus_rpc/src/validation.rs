@@ -0,0 +1,145 @@
+//! Validation of request parameters that are well formed but don't represent valid Starknet
+//! values. The validation is done before the request is handled, so that such requests fail fast
+//! with an informative error. It's shared by all the versions, which report the same errors.
+
+#[cfg(test)]
+#[path = "validation_test.rs"]
+mod validation_test;
+
+use std::collections::HashSet;
+
+use jsonrpsee::types::ErrorObjectOwned;
+use lazy_static::lazy_static;
+use starknet_api::core::ContractAddress;
+use starknet_api::hash::StarkFelt;
+use starknet_api::transaction::{Calldata, EventKey, TransactionSignature};
+use starknet_client::writer::objects::transaction::{
+    DeclareTransaction,
+    DeployAccountTransaction,
+    InvokeTransaction,
+};
+
+use crate::api::CallRequest;
+use crate::v0_7::error::{
+    invalid_contract_address,
+    invalid_felt,
+    PAGE_SIZE_TOO_BIG,
+    TOO_MANY_KEYS_IN_FILTER,
+};
+
+lazy_static! {
+    // The prime of the field the felts are elements of, 2^251 + 17 * 2^192 + 1.
+    static ref FIELD_PRIME: StarkFelt = StarkFelt::try_from(
+        "0x800000000000011000000000000000000000000000000000000000000000001"
+    ).expect("Field prime should be a valid StarkFelt");
+    // The upper bound (exclusive) of contract addresses, 2^251 - 256.
+    static ref CONTRACT_ADDRESS_UPPER_BOUND: StarkFelt = StarkFelt::try_from(
+        "0x7ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff00"
+    ).expect("Contract address upper bound should be a valid StarkFelt");
+}
+
+/// Returns an error if the felt isn't an element of the field, i.e it isn't smaller than the field
+/// prime.
+pub fn validate_felt(felt: &StarkFelt) -> Result<(), ErrorObjectOwned> {
+    if felt >= &*FIELD_PRIME {
+        return Err(invalid_felt(felt.to_string()).into());
+    }
+    Ok(())
+}
+
+/// Returns an error if the contract address isn't in the range of contract addresses.
+pub fn validate_contract_address(address: &ContractAddress) -> Result<(), ErrorObjectOwned> {
+    if address.0.key() >= &*CONTRACT_ADDRESS_UPPER_BOUND {
+        return Err(invalid_contract_address(address.0.key().to_string()).into());
+    }
+    Ok(())
+}
+
+/// Validates the values and the size of an events filter, given by its chunk size, address and
+/// keys since each version has its own filter type.
+pub fn validate_event_filter(
+    chunk_size: usize,
+    address: Option<&ContractAddress>,
+    keys: &[HashSet<EventKey>],
+    max_events_chunk_size: usize,
+    max_events_keys: usize,
+) -> Result<(), ErrorObjectOwned> {
+    if chunk_size > max_events_chunk_size {
+        return Err(PAGE_SIZE_TOO_BIG.into());
+    }
+    // Both the number of key positions and the number of keys in each position are limited.
+    if keys.len() > max_events_keys || keys.iter().any(|keys| keys.len() > max_events_keys) {
+        return Err(TOO_MANY_KEYS_IN_FILTER.into());
+    }
+    for keys in keys {
+        for key in keys {
+            validate_felt(&key.0)?;
+        }
+    }
+    if let Some(address) = address {
+        validate_contract_address(address)?;
+    }
+    Ok(())
+}
+
+/// Validates the contract address and the felts of a call request.
+pub fn validate_call_request(request: &CallRequest) -> Result<(), ErrorObjectOwned> {
+    validate_contract_address(&request.contract_address)?;
+    validate_felt(&request.entry_point_selector.0)?;
+    validate_calldata(&request.calldata)
+}
+
+/// Validates the sender address, the calldata and the signature of an invoke transaction, after it
+/// was converted to the gateway format.
+pub fn validate_invoke_transaction(
+    transaction: &InvokeTransaction,
+) -> Result<(), ErrorObjectOwned> {
+    let (address, calldata, signature) = match transaction {
+        InvokeTransaction::InvokeV0(tx) => (&tx.contract_address, &tx.calldata, &tx.signature),
+        InvokeTransaction::InvokeV1(tx) => (&tx.sender_address, &tx.calldata, &tx.signature),
+        InvokeTransaction::InvokeV3(tx) => (&tx.sender_address, &tx.calldata, &tx.signature),
+    };
+    validate_contract_address(address)?;
+    validate_calldata(calldata)?;
+    validate_signature(signature)
+}
+
+/// Validates the sender address and the signature of a declare transaction, after it was converted
+/// to the gateway format.
+pub fn validate_declare_transaction(
+    transaction: &DeclareTransaction,
+) -> Result<(), ErrorObjectOwned> {
+    let (address, signature) = match transaction {
+        DeclareTransaction::DeclareV1(tx) => (&tx.sender_address, &tx.signature),
+        DeclareTransaction::DeclareV2(tx) => (&tx.sender_address, &tx.signature),
+        DeclareTransaction::DeclareV3(tx) => (&tx.sender_address, &tx.signature),
+    };
+    validate_contract_address(address)?;
+    validate_signature(signature)
+}
+
+/// Validates the salt, the constructor calldata and the signature of a deploy account transaction,
+/// after it was converted to the gateway format.
+pub fn validate_deploy_account_transaction(
+    transaction: &DeployAccountTransaction,
+) -> Result<(), ErrorObjectOwned> {
+    let (salt, calldata, signature) = match transaction {
+        DeployAccountTransaction::DeployAccountV1(tx) => {
+            (&tx.contract_address_salt, &tx.constructor_calldata, &tx.signature)
+        }
+        DeployAccountTransaction::DeployAccountV3(tx) => {
+            (&tx.contract_address_salt, &tx.constructor_calldata, &tx.signature)
+        }
+    };
+    validate_felt(&salt.0)?;
+    validate_calldata(calldata)?;
+    validate_signature(signature)
+}
+
+fn validate_calldata(calldata: &Calldata) -> Result<(), ErrorObjectOwned> {
+    calldata.0.iter().try_for_each(validate_felt)
+}
+
+fn validate_signature(signature: &TransactionSignature) -> Result<(), ErrorObjectOwned> {
+    signature.0.iter().try_for_each(validate_felt)
+}
@@ -0,0 +1,215 @@
+use std::collections::HashSet;
+
+use jsonrpsee::types::ErrorObjectOwned;
+use pretty_assertions::assert_eq;
+use starknet_api::core::{ContractAddress, EntryPointSelector, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::transaction::{Calldata, ContractAddressSalt, EventKey, TransactionSignature};
+use starknet_api::{calldata, contract_address, patricia_key, stark_felt};
+use starknet_client::writer::objects::transaction::{
+    DeclareTransaction,
+    DeclareV1Transaction,
+    DeployAccountTransaction,
+    DeployAccountV1Transaction,
+    InvokeTransaction,
+    InvokeV1Transaction,
+};
+
+use super::{
+    validate_call_request,
+    validate_contract_address,
+    validate_declare_transaction,
+    validate_deploy_account_transaction,
+    validate_event_filter,
+    validate_felt,
+    validate_invoke_transaction,
+};
+use crate::api::CallRequest;
+use crate::v0_7::error::{
+    invalid_contract_address,
+    invalid_felt,
+    PAGE_SIZE_TOO_BIG,
+    TOO_MANY_KEYS_IN_FILTER,
+};
+
+const FIELD_PRIME: &str = "0x800000000000011000000000000000000000000000000000000000000000001";
+const MAX_FELT: &str = "0x800000000000011000000000000000000000000000000000000000000000000";
+const MAX_CONTRACT_ADDRESS: &str =
+    "0x7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffeff";
+const CONTRACT_ADDRESS_UPPER_BOUND: &str =
+    "0x7ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff00";
+
+#[test]
+fn felt_range() {
+    validate_felt(&stark_felt!(MAX_FELT)).unwrap();
+    assert_eq!(
+        validate_felt(&stark_felt!(FIELD_PRIME)).unwrap_err(),
+        ErrorObjectOwned::from(invalid_felt(stark_felt!(FIELD_PRIME).to_string()))
+    );
+}
+
+#[test]
+fn contract_address_range() {
+    validate_contract_address(&contract_address!(MAX_CONTRACT_ADDRESS)).unwrap();
+    assert_eq!(
+        validate_contract_address(&contract_address!(CONTRACT_ADDRESS_UPPER_BOUND)).unwrap_err(),
+        ErrorObjectOwned::from(invalid_contract_address(
+            stark_felt!(CONTRACT_ADDRESS_UPPER_BOUND).to_string(),
+        ))
+    );
+}
+
+#[test]
+fn event_filter() {
+    const MAX_CHUNK_SIZE: usize = 10;
+    const MAX_KEYS: usize = 2;
+    let address = contract_address!("0x1");
+    let keys = vec![HashSet::from([EventKey(stark_felt!("0x1")), EventKey(stark_felt!("0x2"))])];
+    validate_event_filter(MAX_CHUNK_SIZE, Some(&address), &keys, MAX_CHUNK_SIZE, MAX_KEYS).unwrap();
+
+    assert_eq!(
+        validate_event_filter(MAX_CHUNK_SIZE + 1, Some(&address), &keys, MAX_CHUNK_SIZE, MAX_KEYS)
+            .unwrap_err(),
+        ErrorObjectOwned::from(PAGE_SIZE_TOO_BIG)
+    );
+
+    let too_many_keys_in_position = vec![HashSet::from([
+        EventKey(stark_felt!("0x1")),
+        EventKey(stark_felt!("0x2")),
+        EventKey(stark_felt!("0x3")),
+    ])];
+    assert_eq!(
+        validate_event_filter(
+            MAX_CHUNK_SIZE,
+            Some(&address),
+            &too_many_keys_in_position,
+            MAX_CHUNK_SIZE,
+            MAX_KEYS,
+        )
+        .unwrap_err(),
+        ErrorObjectOwned::from(TOO_MANY_KEYS_IN_FILTER)
+    );
+
+    let invalid_keys = vec![HashSet::new(), HashSet::from([EventKey(stark_felt!(FIELD_PRIME))])];
+    assert_eq!(
+        validate_event_filter(
+            MAX_CHUNK_SIZE,
+            Some(&address),
+            &invalid_keys,
+            MAX_CHUNK_SIZE,
+            MAX_KEYS
+        )
+        .unwrap_err(),
+        ErrorObjectOwned::from(invalid_felt(stark_felt!(FIELD_PRIME).to_string()))
+    );
+
+    let invalid_address = contract_address!(CONTRACT_ADDRESS_UPPER_BOUND);
+    assert_eq!(
+        validate_event_filter(
+            MAX_CHUNK_SIZE,
+            Some(&invalid_address),
+            &keys,
+            MAX_CHUNK_SIZE,
+            MAX_KEYS
+        )
+        .unwrap_err(),
+        ErrorObjectOwned::from(invalid_contract_address(
+            stark_felt!(CONTRACT_ADDRESS_UPPER_BOUND).to_string(),
+        ))
+    );
+}
+
+#[test]
+fn call_request() {
+    let valid_request = CallRequest {
+        contract_address: contract_address!("0x1"),
+        entry_point_selector: EntryPointSelector(stark_felt!("0x2")),
+        calldata: calldata![stark_felt!("0x3")],
+    };
+    validate_call_request(&valid_request).unwrap();
+
+    let invalid_calldata_request =
+        CallRequest { calldata: calldata![stark_felt!(FIELD_PRIME)], ..valid_request };
+    assert_eq!(
+        validate_call_request(&invalid_calldata_request).unwrap_err(),
+        ErrorObjectOwned::from(invalid_felt(stark_felt!(FIELD_PRIME).to_string()))
+    );
+}
+
+#[test]
+fn invoke_transaction() {
+    let valid_transaction = InvokeV1Transaction {
+        sender_address: contract_address!("0x1"),
+        calldata: calldata![stark_felt!("0x2")],
+        signature: TransactionSignature(vec![stark_felt!("0x3")]),
+        ..Default::default()
+    };
+    validate_invoke_transaction(&InvokeTransaction::InvokeV1(valid_transaction.clone())).unwrap();
+
+    let invalid_sender_transaction = InvokeV1Transaction {
+        sender_address: contract_address!(CONTRACT_ADDRESS_UPPER_BOUND),
+        ..valid_transaction.clone()
+    };
+    assert_eq!(
+        validate_invoke_transaction(&InvokeTransaction::InvokeV1(invalid_sender_transaction))
+            .unwrap_err(),
+        ErrorObjectOwned::from(invalid_contract_address(
+            stark_felt!(CONTRACT_ADDRESS_UPPER_BOUND).to_string(),
+        ))
+    );
+
+    let invalid_signature_transaction = InvokeV1Transaction {
+        signature: TransactionSignature(vec![stark_felt!(FIELD_PRIME)]),
+        ..valid_transaction
+    };
+    assert_eq!(
+        validate_invoke_transaction(&InvokeTransaction::InvokeV1(invalid_signature_transaction))
+            .unwrap_err(),
+        ErrorObjectOwned::from(invalid_felt(stark_felt!(FIELD_PRIME).to_string()))
+    );
+}
+
+#[test]
+fn declare_transaction() {
+    let valid_transaction =
+        DeclareV1Transaction { sender_address: contract_address!("0x1"), ..Default::default() };
+    validate_declare_transaction(&DeclareTransaction::DeclareV1(valid_transaction.clone()))
+        .unwrap();
+
+    let invalid_sender_transaction = DeclareV1Transaction {
+        sender_address: contract_address!(CONTRACT_ADDRESS_UPPER_BOUND),
+        ..valid_transaction
+    };
+    assert_eq!(
+        validate_declare_transaction(&DeclareTransaction::DeclareV1(invalid_sender_transaction))
+            .unwrap_err(),
+        ErrorObjectOwned::from(invalid_contract_address(
+            stark_felt!(CONTRACT_ADDRESS_UPPER_BOUND).to_string(),
+        ))
+    );
+}
+
+#[test]
+fn deploy_account_transaction() {
+    let valid_transaction = DeployAccountV1Transaction {
+        contract_address_salt: ContractAddressSalt(stark_felt!("0x1")),
+        constructor_calldata: calldata![stark_felt!("0x2")],
+        ..Default::default()
+    };
+    validate_deploy_account_transaction(&DeployAccountTransaction::DeployAccountV1(
+        valid_transaction.clone(),
+    ))
+    .unwrap();
+
+    let invalid_calldata_transaction = DeployAccountV1Transaction {
+        constructor_calldata: calldata![stark_felt!(FIELD_PRIME)],
+        ..valid_transaction
+    };
+    assert_eq!(
+        validate_deploy_account_transaction(&DeployAccountTransaction::DeployAccountV1(
+            invalid_calldata_transaction,
+        ))
+        .unwrap_err(),
+        ErrorObjectOwned::from(invalid_felt(stark_felt!(FIELD_PRIME).to_string()))
+    );
+}
@@ -28,6 +28,16 @@ pub struct VersionId {
     pub patch: u8,
 }
 
+impl VersionId {
+    /// Returns the capabilities of the version.
+    pub fn capabilities(&self) -> VersionCapabilities {
+        match *self {
+            VERSION_0_4 | VERSION_0_5 => VersionCapabilities { v3_transactions: false },
+            _ => VersionCapabilities { v3_transactions: true },
+        }
+    }
+}
+
 impl fmt::Display for VersionId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}_{}", self.name, self.patch)
@@ -45,3 +55,20 @@ pub const VERSION_0_4: VersionId = VersionId { name: "V0_4", patch: 0 };
 pub const VERSION_0_5: VersionId = VersionId { name: "V0_5", patch: 1 };
 pub const VERSION_0_6: VersionId = VersionId { name: "V0_6", patch: 0 };
 pub const VERSION_0_7: VersionId = VersionId { name: "V0_7", patch: 0 };
+
+/// The behavior that differs between the versions, for handlers that are shared by all of them
+/// (e.g. the Papyrus specific methods) and shape their responses by the version of the request.
+/// The default is the capabilities of the latest version.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct VersionCapabilities {
+    /// Whether transactions of version 3 are shown as such (V0_6 and later). Older versions show
+    /// them like their own methods do, as the latest transaction version they know with the max
+    /// fee their L1 gas bounds allow.
+    pub v3_transactions: bool,
+}
+
+impl Default for VersionCapabilities {
+    fn default() -> Self {
+        VERSION_0_7.capabilities()
+    }
+}
@@ -2,7 +2,15 @@ use std::collections::HashMap;
 
 use pretty_assertions::assert_eq;
 
-use super::{VersionState, VERSION_CONFIG};
+use super::{
+    VersionCapabilities,
+    VersionState,
+    VERSION_0_4,
+    VERSION_0_5,
+    VERSION_0_6,
+    VERSION_0_7,
+    VERSION_CONFIG,
+};
 
 #[tokio::test]
 async fn validate_version_configuration() {
@@ -17,3 +25,15 @@ async fn validate_version_configuration() {
     // verify each version is listed once
     config_version_counter.iter().for_each(|version_counter| assert_eq!(*version_counter.1, 1))
 }
+
+#[test]
+fn v3_transactions_capability() {
+    for (version_id, expected) in
+        [(VERSION_0_4, false), (VERSION_0_5, false), (VERSION_0_6, true), (VERSION_0_7, true)]
+    {
+        assert_eq!(version_id.capabilities().v3_transactions, expected, "{version_id}");
+    }
+    // The default capabilities are of the latest version.
+    let latest_version = VERSION_CONFIG.last().unwrap().0;
+    assert_eq!(VersionCapabilities::default(), latest_version.capabilities());
+}
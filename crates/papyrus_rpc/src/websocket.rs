@@ -0,0 +1,76 @@
+//! The WebSocket transport of the server.
+//!
+//! Only the subscriptions are available over WebSocket. The middleware of the server reads the body
+//! of each HTTP request to version its methods, enforce the limits and check the API key, but it
+//! never sees the messages of a WebSocket connection. So [`WebSocketLayer`] serves the WebSocket
+//! upgrade requests with a server of their own, whose module has only the subscription methods, and
+//! the main server is HTTP only.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use hyper::{Body, Request, Response};
+use tower::util::BoxCloneService;
+use tower::{BoxError, Layer, Service, ServiceExt};
+
+use crate::middleware::is_websocket_upgrade;
+
+/// The service that serves the WebSocket connections.
+pub(crate) type WebSocketService = BoxCloneService<Request<Body>, Response<Body>, BoxError>;
+
+/// [`Tower`] layer that sends the WebSocket upgrade requests to the subscriptions server, instead
+/// of the rest of the middleware and the main server.
+///
+/// [`Tower`]: https://crates.io/crates/tower
+#[derive(Clone)]
+pub(crate) struct WebSocketLayer {
+    subscriptions: WebSocketService,
+}
+
+impl WebSocketLayer {
+    pub(crate) fn new(subscriptions: WebSocketService) -> Self {
+        Self { subscriptions }
+    }
+}
+
+impl<S> Layer<S> for WebSocketLayer {
+    type Service = WebSocketRouting<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        WebSocketRouting { subscriptions: self.subscriptions.clone(), inner }
+    }
+}
+
+/// The service of [`WebSocketLayer`].
+#[derive(Clone)]
+pub(crate) struct WebSocketRouting<S> {
+    subscriptions: WebSocketService,
+    inner: S,
+}
+
+impl<S> Service<Request<Body>> for WebSocketRouting<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send,
+    S::Error: Into<BoxError>,
+{
+    type Response = Response<Body>;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Response<Body>, BoxError>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        if is_websocket_upgrade(&req) {
+            // The subscriptions server isn't polled by the server, so it's polled here.
+            return Box::pin(self.subscriptions.clone().oneshot(req));
+        }
+        // The service that was polled ready handles the request, and a clone takes its place.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        Box::pin(async move { inner.call(req).await.map_err(Into::into) })
+    }
+}
@@ -0,0 +1,333 @@
+#![allow(clippy::unwrap_used)]
+//! Benchmarks of the storage, for evaluating changes to its serialization and schema.
+//!
+//! The blocks are shaped like recent mainnet blocks: many transactions with a few events each, and
+//! state diffs that write to the storage of a popular subset of the contracts. The appends are
+//! measured one block per transaction, as the sync writes them, and the reads are measured on a
+//! storage that was filled in advance.
+//!
+//! Run with `cargo bench -p papyrus_storage`.
+use std::time::{Duration, Instant};
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use indexmap::IndexMap;
+use papyrus_storage::body::events::{EventIndex, EventsReader};
+use papyrus_storage::body::{BodyStorageReader, BodyStorageWriter, TransactionIndex};
+use papyrus_storage::header::{HeaderStorageReader, HeaderStorageWriter};
+use papyrus_storage::state::{StateStorageReader, StateStorageWriter};
+use papyrus_storage::{open_storage, StorageConfig, StorageReader, StorageWriter};
+use rand::Rng;
+use rand_chacha::ChaCha8Rng;
+use starknet_api::block::{BlockBody, BlockHash, BlockHeader, BlockNumber};
+use starknet_api::core::{ContractAddress, Nonce, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::{StateDiff, StateNumber, StorageKey};
+use starknet_api::transaction::{
+    EventIndexInTransactionOutput,
+    EventKey,
+    TransactionHash,
+    TransactionOffsetInBlock,
+};
+use tempfile::{tempdir, TempDir};
+use test_utils::{get_rng, get_test_body};
+
+const TRANSACTIONS_PER_BLOCK: usize = 100;
+const EVENTS_PER_TRANSACTION: usize = 3;
+// The contracts that emit events and whose storage is written, like the tokens and the popular
+// applications of mainnet.
+const ACTIVE_CONTRACTS: u64 = 200;
+const EVENT_FIRST_KEYS: u64 = 20;
+const STORAGE_WRITES_PER_BLOCK: usize = 400;
+const STORAGE_KEYS_PER_CONTRACT: u64 = 1000;
+// The number of blocks in the storage that the reads are measured on.
+const PREFILLED_BLOCKS: u64 = 200;
+
+fn felt(value: u64) -> StarkFelt {
+    StarkFelt::from(value)
+}
+
+fn contract_address(index: u64) -> ContractAddress {
+    // Skips the low addresses, which are reserved.
+    ContractAddress(PatriciaKey::try_from(felt(0x1000 + index)).expect("Valid address."))
+}
+
+fn storage_key(index: u64) -> StorageKey {
+    StorageKey(PatriciaKey::try_from(felt(index)).expect("Valid storage key."))
+}
+
+// The data of a block, without its signature and classes.
+struct TestBlock {
+    header: BlockHeader,
+    body: BlockBody,
+    state_diff: StateDiff,
+}
+
+struct BlockGenerator {
+    rng: ChaCha8Rng,
+    // The body of every block, with unique transaction hashes set per block. Generating a body is
+    // slower than storing it, so it's generated once.
+    body: BlockBody,
+}
+
+impl BlockGenerator {
+    fn new() -> Self {
+        let from_addresses = (0..ACTIVE_CONTRACTS).map(contract_address).collect();
+        let first_keys = (0..EVENT_FIRST_KEYS).map(|key| EventKey(felt(key))).collect();
+        let body = get_test_body(
+            TRANSACTIONS_PER_BLOCK,
+            Some(EVENTS_PER_TRANSACTION),
+            Some(from_addresses),
+            Some(vec![first_keys]),
+        );
+        Self { rng: get_rng(), body }
+    }
+
+    fn block(&mut self, block_number: BlockNumber) -> TestBlock {
+        let header = BlockHeader {
+            block_hash: block_hash(block_number),
+            block_number,
+            parent_hash: block_number.prev().map(block_hash).unwrap_or_default(),
+            ..Default::default()
+        };
+
+        let mut body = self.body.clone();
+        for (offset, hash) in body.transaction_hashes.iter_mut().enumerate() {
+            *hash = transaction_hash(block_number, offset);
+        }
+
+        let mut state_diff = StateDiff::default();
+        for _ in 0..STORAGE_WRITES_PER_BLOCK {
+            let address = contract_address(self.rng.gen_range(0..ACTIVE_CONTRACTS));
+            let key = storage_key(self.rng.gen_range(0..STORAGE_KEYS_PER_CONTRACT));
+            state_diff.storage_diffs.entry(address).or_default().insert(key, felt(self.rng.gen()));
+        }
+        // Every transaction advances the nonce of its sender.
+        for _ in 0..TRANSACTIONS_PER_BLOCK {
+            let address = contract_address(self.rng.gen_range(0..ACTIVE_CONTRACTS));
+            state_diff.nonces.insert(address, Nonce(felt(block_number.0 + 1)));
+        }
+        state_diff.storage_diffs.sort_unstable_keys();
+        state_diff.nonces.sort_unstable_keys();
+
+        TestBlock { header, body, state_diff }
+    }
+}
+
+fn block_hash(block_number: BlockNumber) -> BlockHash {
+    BlockHash(StarkHash::from(block_number.0 + 1))
+}
+
+fn transaction_hash(block_number: BlockNumber, offset: usize) -> TransactionHash {
+    TransactionHash(StarkHash::from(
+        block_number.0 * TRANSACTIONS_PER_BLOCK as u64 + offset as u64 + 1,
+    ))
+}
+
+// Opens a storage with the default configuration in a temporary directory.
+fn open_test_storage() -> ((StorageReader, StorageWriter), TempDir) {
+    let temp_dir = tempdir().expect("Should create a temporary directory.");
+    let mut config = StorageConfig::default();
+    config.db_config.path_prefix = temp_dir.path().to_path_buf();
+    (open_storage(config).expect("Should open the storage."), temp_dir)
+}
+
+fn append_block(writer: &mut StorageWriter, block: TestBlock) {
+    let block_number = block.header.block_number;
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block_number, &block.header)
+        .unwrap()
+        .append_body(block_number, block.body)
+        .unwrap()
+        .append_state_diff(block_number, block.state_diff, IndexMap::new())
+        .unwrap()
+        .commit()
+        .unwrap();
+}
+
+fn prefilled_storage() -> ((StorageReader, StorageWriter), TempDir) {
+    let ((reader, mut writer), temp_dir) = open_test_storage();
+    let mut generator = BlockGenerator::new();
+    for block_number in (0..PREFILLED_BLOCKS).map(BlockNumber) {
+        append_block(&mut writer, generator.block(block_number));
+    }
+    ((reader, writer), temp_dir)
+}
+
+// Measures appending a part of the next block to a storage that grows over the iterations, each
+// block in its own transaction.
+fn bench_append(c: &mut Criterion, name: &str, append: impl Fn(&mut StorageWriter, TestBlock)) {
+    let ((_reader, mut writer), _temp_dir) = open_test_storage();
+    let mut generator = BlockGenerator::new();
+    let mut next_block = BlockNumber(0);
+    c.bench_function(name, |b| {
+        b.iter_custom(|iters| {
+            let mut elapsed = Duration::ZERO;
+            for _ in 0..iters {
+                let block = generator.block(next_block);
+                next_block = next_block.next();
+                let started_at = Instant::now();
+                append(&mut writer, block);
+                elapsed += started_at.elapsed();
+            }
+            elapsed
+        })
+    });
+}
+
+fn append_benchmarks(c: &mut Criterion) {
+    bench_append(c, "append_header", |writer, block| {
+        let block_number = block.header.block_number;
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_header(block_number, &block.header)
+            .unwrap()
+            .commit()
+            .unwrap();
+    });
+    bench_append(c, "append_body", |writer, block| {
+        let block_number = block.header.block_number;
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_body(block_number, block.body)
+            .unwrap()
+            .commit()
+            .unwrap();
+    });
+    bench_append(c, "append_state_diff", |writer, block| {
+        let block_number = block.header.block_number;
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_state_diff(block_number, block.state_diff, IndexMap::new())
+            .unwrap()
+            .commit()
+            .unwrap();
+    });
+}
+
+fn point_lookup_benchmarks(c: &mut Criterion) {
+    let ((reader, _writer), _temp_dir) = prefilled_storage();
+    let mut rng = get_rng();
+    let mut group = c.benchmark_group("point_lookup");
+
+    group.bench_function("block_header", |b| {
+        b.iter_batched(
+            || BlockNumber(rng.gen_range(0..PREFILLED_BLOCKS)),
+            |block_number| {
+                reader.begin_ro_txn().unwrap().get_block_header(block_number).unwrap().unwrap()
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("transaction_by_hash", |b| {
+        b.iter_batched(
+            || {
+                let block_number = BlockNumber(rng.gen_range(0..PREFILLED_BLOCKS));
+                transaction_hash(block_number, rng.gen_range(0..TRANSACTIONS_PER_BLOCK))
+            },
+            |transaction_hash| {
+                let txn = reader.begin_ro_txn().unwrap();
+                let index = txn.get_transaction_idx_by_hash(&transaction_hash).unwrap().unwrap();
+                txn.get_transaction(index).unwrap().unwrap()
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("storage_at", |b| {
+        b.iter_batched(
+            || {
+                (
+                    StateNumber::right_after_block(BlockNumber(rng.gen_range(0..PREFILLED_BLOCKS))),
+                    contract_address(rng.gen_range(0..ACTIVE_CONTRACTS)),
+                    storage_key(rng.gen_range(0..STORAGE_KEYS_PER_CONTRACT)),
+                )
+            },
+            |(state_number, address, key)| {
+                let txn = reader.begin_ro_txn().unwrap();
+                txn.get_state_reader()
+                    .unwrap()
+                    .get_storage_at(state_number, &address, &key)
+                    .unwrap()
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("nonce_at", |b| {
+        b.iter_batched(
+            || {
+                (
+                    StateNumber::right_after_block(BlockNumber(rng.gen_range(0..PREFILLED_BLOCKS))),
+                    contract_address(rng.gen_range(0..ACTIVE_CONTRACTS)),
+                )
+            },
+            |(state_number, address)| {
+                let txn = reader.begin_ro_txn().unwrap();
+                txn.get_state_reader().unwrap().get_nonce_at(state_number, &address).unwrap()
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+// The scans go over the last blocks of the storage, like the queries of recent data.
+const SCANNED_BLOCKS: u64 = 20;
+
+fn range_scan_benchmarks(c: &mut Criterion) {
+    let ((reader, _writer), _temp_dir) = prefilled_storage();
+    let first_scanned_block = BlockNumber(PREFILLED_BLOCKS - SCANNED_BLOCKS);
+    let to_block_number = BlockNumber(PREFILLED_BLOCKS);
+    let first_event_index = EventIndex(
+        TransactionIndex(first_scanned_block, TransactionOffsetInBlock(0)),
+        EventIndexInTransactionOutput(0),
+    );
+    let mut group = c.benchmark_group("range_scan");
+
+    group.throughput(Throughput::Elements(SCANNED_BLOCKS));
+    group.bench_function("block_headers", |b| {
+        b.iter(|| {
+            let txn = reader.begin_ro_txn().unwrap();
+            for block_number in (first_scanned_block.0..to_block_number.0).map(BlockNumber) {
+                txn.get_block_header(block_number).unwrap().unwrap();
+            }
+        })
+    });
+    group.bench_function("block_transactions", |b| {
+        b.iter(|| {
+            let txn = reader.begin_ro_txn().unwrap();
+            for block_number in (first_scanned_block.0..to_block_number.0).map(BlockNumber) {
+                txn.get_block_transactions(block_number).unwrap().unwrap();
+            }
+        })
+    });
+    group.bench_function("events", |b| {
+        b.iter(|| {
+            let txn = reader.begin_ro_txn().unwrap();
+            txn.iter_events(None, first_event_index, to_block_number).unwrap().count()
+        })
+    });
+    group.bench_function("events_by_address", |b| {
+        b.iter(|| {
+            let txn = reader.begin_ro_txn().unwrap();
+            txn.iter_events(Some(contract_address(0)), first_event_index, to_block_number)
+                .unwrap()
+                .count()
+        })
+    });
+    group.bench_function("events_by_first_key", |b| {
+        b.iter(|| {
+            let txn = reader.begin_ro_txn().unwrap();
+            txn.iter_events_by_first_keys(&[EventKey(felt(0))], first_event_index, to_block_number)
+                .unwrap()
+                .count()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, append_benchmarks, point_lookup_benchmarks, range_scan_benchmarks);
+criterion_main!(benches);
@@ -39,16 +39,34 @@
 #[path = "base_layer_test.rs"]
 mod base_layer_test;
 
+use serde::{Deserialize, Serialize};
 use starknet_api::block::BlockNumber;
 
 use crate::db::table_types::Table;
 use crate::db::{TransactionKind, RW};
 use crate::{MarkerKind, StorageResult, StorageTxn};
 
+/// The maximal number of base layer blocks kept for the latest values of the base layer marker.
+/// Older ones are dropped.
+pub const MAX_BASE_LAYER_L1_BLOCKS: usize = 100;
+
+/// A block of the base layer that a value of the base layer marker was read from.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct BaseLayerL1Block {
+    /// The number of the base layer block.
+    pub number: u64,
+    /// The hash of the base layer block.
+    pub hash: [u8; 32],
+}
+
 /// Interface for reading data related to the base layer.
 pub trait BaseLayerStorageReader {
     /// The block number marker is the first block number that doesn't exist yet in the base layer.
     fn get_base_layer_block_marker(&self) -> StorageResult<BlockNumber>;
+
+    /// Returns the base layer blocks that the latest values of the base layer marker were read
+    /// from, by the value of the marker, from the oldest. Values that were reverted are excluded.
+    fn get_base_layer_l1_blocks(&self) -> StorageResult<Vec<(BlockNumber, BaseLayerL1Block)>>;
 }
 
 /// Interface for writing data related to the base layer.
@@ -60,6 +78,14 @@ where
     // To enforce that no commit happen after a failure, we consume and return Self on success.
     fn update_base_layer_block_marker(self, block_number: &BlockNumber) -> StorageResult<Self>;
 
+    /// Updates the block marker of the base layer and records the base layer block it was read
+    /// from, so that a reorg of the base layer that removes the block can be detected.
+    fn update_base_layer_block_marker_from_l1_block(
+        self,
+        block_number: &BlockNumber,
+        l1_block: &BaseLayerL1Block,
+    ) -> StorageResult<Self>;
+
     /// When reverting a block, if the base layer marker points to the block afterward, revert the
     /// marker.
     fn try_revert_base_layer_marker(
@@ -73,15 +99,48 @@ impl<'env, Mode: TransactionKind> BaseLayerStorageReader for StorageTxn<'env, Mo
         let markers_table = self.open_table(&self.tables.markers)?;
         Ok(markers_table.get(&self.txn, &MarkerKind::BaseLayerBlock)?.unwrap_or_default())
     }
+
+    fn get_base_layer_l1_blocks(&self) -> StorageResult<Vec<(BlockNumber, BaseLayerL1Block)>> {
+        let l1_blocks_table = self.open_table(&self.tables.base_layer_l1_blocks)?;
+        let mut cursor = l1_blocks_table.cursor(&self.txn)?;
+        let mut res = Vec::new();
+        while let Some(entry) = cursor.next()? {
+            res.push(entry);
+        }
+        Ok(res)
+    }
 }
 
 impl<'env> BaseLayerStorageWriter for StorageTxn<'env, RW> {
     fn update_base_layer_block_marker(self, block_number: &BlockNumber) -> StorageResult<Self> {
         let markers_table = self.open_table(&self.tables.markers)?;
         markers_table.upsert(&self.txn, &MarkerKind::BaseLayerBlock, block_number)?;
+        // The base layer blocks of later values of the marker were read before a revert.
+        let l1_blocks_table = self.open_table(&self.tables.base_layer_l1_blocks)?;
+        for (marker, _l1_block) in self.get_base_layer_l1_blocks()? {
+            if marker > *block_number {
+                l1_blocks_table.delete(&self.txn, &marker)?;
+            }
+        }
         Ok(self)
     }
 
+    fn update_base_layer_block_marker_from_l1_block(
+        self,
+        block_number: &BlockNumber,
+        l1_block: &BaseLayerL1Block,
+    ) -> StorageResult<Self> {
+        let txn = self.update_base_layer_block_marker(block_number)?;
+        let l1_blocks_table = txn.open_table(&txn.tables.base_layer_l1_blocks)?;
+        l1_blocks_table.upsert(&txn.txn, block_number, l1_block)?;
+        let l1_blocks = txn.get_base_layer_l1_blocks()?;
+        let excess_l1_blocks = l1_blocks.len().saturating_sub(MAX_BASE_LAYER_L1_BLOCKS);
+        for (marker, _l1_block) in &l1_blocks[..excess_l1_blocks] {
+            l1_blocks_table.delete(&txn.txn, marker)?;
+        }
+        Ok(txn)
+    }
+
     fn try_revert_base_layer_marker(
         self,
         reverted_block_number: BlockNumber,
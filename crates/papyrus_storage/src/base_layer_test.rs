@@ -1,6 +1,11 @@
 use starknet_api::block::BlockNumber;
 
-use crate::base_layer::{BaseLayerStorageReader, BaseLayerStorageWriter};
+use crate::base_layer::{
+    BaseLayerL1Block,
+    BaseLayerStorageReader,
+    BaseLayerStorageWriter,
+    MAX_BASE_LAYER_L1_BLOCKS,
+};
 use crate::test_utils::get_test_storage;
 
 #[tokio::test]
@@ -50,3 +55,33 @@ fn try_revert_base_layer_marker() {
     let cur_marker = reader.begin_ro_txn().unwrap().get_base_layer_block_marker().unwrap();
     assert_eq!(cur_marker, BlockNumber(1));
 }
+
+#[test]
+fn base_layer_l1_blocks() {
+    let (reader, mut writer) = get_test_storage().0;
+    let l1_block = |number| BaseLayerL1Block { number, hash: [number as u8; 32] };
+
+    let mut txn = writer.begin_rw_txn().unwrap();
+    for marker in 1..=MAX_BASE_LAYER_L1_BLOCKS as u64 + 2 {
+        txn = txn
+            .update_base_layer_block_marker_from_l1_block(&BlockNumber(marker), &l1_block(marker))
+            .unwrap();
+    }
+    txn.commit().unwrap();
+    // Only the latest are kept.
+    let l1_blocks = reader.begin_ro_txn().unwrap().get_base_layer_l1_blocks().unwrap();
+    assert_eq!(l1_blocks.len(), MAX_BASE_LAYER_L1_BLOCKS);
+    assert_eq!(l1_blocks[0], (BlockNumber(3), l1_block(3)));
+
+    // The base layer blocks of reverted values of the marker are dropped.
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .update_base_layer_block_marker(&BlockNumber(50))
+        .unwrap()
+        .commit()
+        .unwrap();
+    let l1_blocks = reader.begin_ro_txn().unwrap().get_base_layer_l1_blocks().unwrap();
+    assert_eq!(l1_blocks.last(), Some(&(BlockNumber(50), l1_block(50))));
+    assert_eq!(l1_blocks.len(), 48);
+}
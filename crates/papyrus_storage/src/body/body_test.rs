@@ -1,15 +1,37 @@
 use assert_matches::assert_matches;
 use pretty_assertions::assert_eq;
 use starknet_api::block::{BlockBody, BlockNumber};
-use starknet_api::transaction::TransactionOffsetInBlock;
+use starknet_api::core::{ChainId, ContractAddress, Nonce, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::patricia_key;
+use starknet_api::transaction::{
+    DeployAccountTransaction,
+    DeployAccountTransactionOutput,
+    DeployAccountTransactionV1,
+    InvokeTransaction,
+    InvokeTransactionOutput,
+    InvokeTransactionV1,
+    Transaction,
+    TransactionHash,
+    TransactionOffsetInBlock,
+    TransactionOutput,
+};
 use test_case::test_case;
-use test_utils::{get_test_block, get_test_body};
+use test_utils::{
+    get_rng,
+    get_test_block,
+    get_test_body,
+    get_test_body_with_valid_transaction_hashes,
+    GetTestInstance,
+};
 
 use crate::body::events::ThinTransactionOutput;
 use crate::body::{BodyStorageReader, BodyStorageWriter, TransactionIndex};
+use crate::db::table_types::Table;
 use crate::db::{DbError, KeyAlreadyExistsError};
-use crate::test_utils::{get_test_storage, get_test_storage_by_scope};
-use crate::{StorageError, StorageScope, StorageWriter};
+use crate::secondary_index::build_transactions_by_sender_if_needed;
+use crate::test_utils::{get_test_config, get_test_storage, get_test_storage_by_scope};
+use crate::{open_storage, StorageError, StorageReader, StorageScope, StorageWriter};
 
 #[tokio::test]
 async fn append_body() {
@@ -186,6 +208,34 @@ async fn append_body() {
     assert_eq!(txn.get_block_transactions_count(BlockNumber(3)).unwrap(), None);
 }
 
+#[tokio::test]
+async fn append_body_with_every_transaction_type() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let body = get_test_body_with_valid_transaction_hashes(
+        &mut get_rng(),
+        &ChainId("SN_GOERLI".to_owned()),
+    );
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_body(BlockNumber(0), body.clone())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    for (offset, (transaction, transaction_hash)) in
+        body.transactions.iter().zip(body.transaction_hashes.iter()).enumerate()
+    {
+        let transaction_index = TransactionIndex(BlockNumber(0), TransactionOffsetInBlock(offset));
+        assert_eq!(
+            txn.get_transaction_idx_by_hash(transaction_hash).unwrap(),
+            Some(transaction_index)
+        );
+        assert_eq!(txn.get_transaction(transaction_index).unwrap().as_ref(), Some(transaction));
+    }
+}
+
 #[tokio::test]
 async fn append_body_state_only() {
     let ((reader, mut writer), _temp_dir) = get_test_storage_by_scope(StorageScope::StateOnly);
@@ -250,6 +300,89 @@ async fn revert_body_updates_marker(storage_scope: StorageScope) {
     assert_eq!(reader.begin_ro_txn().unwrap().get_body_marker().unwrap(), BlockNumber(1));
 }
 
+#[tokio::test]
+async fn revert_body_updates_receipts_marker() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_2_bodies(&mut writer);
+    assert_eq!(reader.begin_ro_txn().unwrap().get_receipts_marker().unwrap(), BlockNumber(2));
+
+    writer.begin_rw_txn().unwrap().revert_body(BlockNumber(1)).unwrap().0.commit().unwrap();
+    assert_eq!(reader.begin_ro_txn().unwrap().get_receipts_marker().unwrap(), BlockNumber(1));
+}
+
+#[tokio::test]
+async fn append_and_revert_body_without_receipts() {
+    let (mut config, _temp_dir) = get_test_config(None);
+    config.store_receipts = false;
+    let (reader, mut writer) = open_storage(config).unwrap();
+    let body = get_test_block(2, Some(1), None, None).body;
+    let transactions = body.transactions.clone();
+    writer.begin_rw_txn().unwrap().append_body(BlockNumber(0), body).unwrap().commit().unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_body_marker().unwrap(), BlockNumber(1));
+    assert_eq!(txn.get_receipts_marker().unwrap(), BlockNumber(0));
+    assert_eq!(txn.get_block_transactions(BlockNumber(0)).unwrap(), Some(transactions.clone()));
+    assert_eq!(txn.get_block_transaction_outputs(BlockNumber(0)).unwrap(), None);
+    let transaction_index = TransactionIndex(BlockNumber(0), TransactionOffsetInBlock(0));
+    assert_eq!(txn.get_transaction_output(transaction_index).unwrap(), None);
+    assert_eq!(txn.get_transaction_events(transaction_index).unwrap(), None);
+    drop(txn);
+
+    let (txn, reverted_body) = writer.begin_rw_txn().unwrap().revert_body(BlockNumber(0)).unwrap();
+    txn.commit().unwrap();
+    let (reverted_transactions, reverted_outputs, _, reverted_events) = reverted_body.unwrap();
+    assert_eq!(reverted_transactions, transactions);
+    assert!(reverted_outputs.is_empty());
+    assert!(reverted_events.is_empty());
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_transaction(transaction_index).unwrap(), None);
+    assert_eq!(txn.get_receipts_marker().unwrap(), BlockNumber(0));
+}
+
+#[tokio::test]
+async fn append_receipts_of_body_without_receipts() {
+    let (mut config, _temp_dir) = get_test_config(None);
+    config.store_receipts = false;
+    let (reader, mut writer) = open_storage(config).unwrap();
+    let body = get_test_block(2, Some(1), None, None).body;
+    let transaction_outputs = body.transaction_outputs.clone();
+    writer.begin_rw_txn().unwrap().append_body(BlockNumber(0), body).unwrap().commit().unwrap();
+
+    let Err(err) = writer.begin_rw_txn().unwrap().append_receipts(BlockNumber(0), vec![]) else {
+        panic!("Unexpected Ok.");
+    };
+    assert_matches!(
+        err,
+        StorageError::WrongNumberOfReceipts { num_of_txs: 2, num_of_receipts: 0, .. }
+    );
+
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_receipts(BlockNumber(0), transaction_outputs.clone())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_receipts_marker().unwrap(), BlockNumber(1));
+    let thin_outputs: Vec<ThinTransactionOutput> =
+        transaction_outputs.iter().cloned().map(ThinTransactionOutput::from).collect();
+    assert_eq!(txn.get_block_transaction_outputs(BlockNumber(0)).unwrap(), Some(thin_outputs));
+    let transaction_index = TransactionIndex(BlockNumber(0), TransactionOffsetInBlock(0));
+    assert_eq!(
+        txn.get_transaction_events(transaction_index).unwrap(),
+        Some(transaction_outputs[0].events().to_vec())
+    );
+
+    // There's no body of block 1 to append receipts to.
+    let Err(err) = writer.begin_rw_txn().unwrap().append_receipts(BlockNumber(1), vec![]) else {
+        panic!("Unexpected Ok.");
+    };
+    assert_matches!(err, StorageError::ReceiptsForMissingBody { block_number: BlockNumber(1) });
+}
+
 #[tokio::test]
 async fn get_reverted_body_returns_none() {
     let ((reader, mut writer), _temp_dir) = get_test_storage();
@@ -387,6 +520,124 @@ async fn revert_transactions() {
     );
 }
 
+// A body of invoke transactions sent by the given senders, with the given nonces.
+fn invoke_body(senders_and_nonces: &[(ContractAddress, u64)], first_hash: u64) -> BlockBody {
+    let mut rng = get_rng();
+    let transactions = senders_and_nonces
+        .iter()
+        .map(|(sender_address, nonce)| {
+            Transaction::Invoke(InvokeTransaction::V1(InvokeTransactionV1 {
+                sender_address: *sender_address,
+                nonce: Nonce(StarkFelt::from(*nonce)),
+                ..InvokeTransactionV1::get_test_instance(&mut rng)
+            }))
+        })
+        .collect::<Vec<_>>();
+    BlockBody {
+        transaction_outputs: vec![
+            TransactionOutput::Invoke(InvokeTransactionOutput::default());
+            transactions.len()
+        ],
+        transaction_hashes: (first_hash..first_hash + transactions.len() as u64)
+            .map(|hash| TransactionHash(StarkFelt::from(hash)))
+            .collect(),
+        transactions,
+    }
+}
+
+#[tokio::test]
+async fn transactions_by_sender() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let sender = ContractAddress(patricia_key!("0x1"));
+    let other_sender = ContractAddress(patricia_key!("0x2"));
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_body(BlockNumber(0), invoke_body(&[(sender, 0), (other_sender, 0), (sender, 1)], 0))
+        .unwrap()
+        .append_body(BlockNumber(1), invoke_body(&[(sender, 2)], 3))
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let index = |block_number, offset| {
+        TransactionIndex(BlockNumber(block_number), TransactionOffsetInBlock(offset))
+    };
+    let nonce = |nonce: u64| Some(Nonce(StarkFelt::from(nonce)));
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(
+        txn.get_transactions_by_sender(sender, index(0, 0), 10).unwrap(),
+        vec![(index(0, 0), nonce(0)), (index(0, 2), nonce(1)), (index(1, 0), nonce(2))]
+    );
+    // Paginated.
+    assert_eq!(
+        txn.get_transactions_by_sender(sender, index(0, 1), 1).unwrap(),
+        vec![(index(0, 2), nonce(1))]
+    );
+    assert_eq!(
+        txn.get_transactions_by_sender(other_sender, index(0, 0), 10).unwrap(),
+        vec![(index(0, 1), nonce(0))]
+    );
+    assert!(txn
+        .get_transactions_by_sender(ContractAddress(patricia_key!("0x3")), index(0, 0), 10)
+        .unwrap()
+        .is_empty());
+    drop(txn);
+
+    writer.begin_rw_txn().unwrap().revert_body(BlockNumber(1)).unwrap().0.commit().unwrap();
+    assert_eq!(
+        reader.begin_ro_txn().unwrap().get_transactions_by_sender(sender, index(0, 0), 10).unwrap(),
+        vec![(index(0, 0), nonce(0)), (index(0, 2), nonce(1))]
+    );
+}
+
+#[tokio::test]
+async fn deploy_account_transactions_by_sender() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let sender = ContractAddress(patricia_key!("0x1"));
+    let deployed_account = ContractAddress(patricia_key!("0x2"));
+    let mut body = invoke_body(&[(sender, 0)], 0);
+    body.transactions.push(Transaction::DeployAccount(DeployAccountTransaction::V1(
+        DeployAccountTransactionV1::get_test_instance(&mut get_rng()),
+    )));
+    body.transaction_outputs.push(TransactionOutput::DeployAccount(
+        DeployAccountTransactionOutput {
+            contract_address: deployed_account,
+            ..DeployAccountTransactionOutput::default()
+        },
+    ));
+    body.transaction_hashes.push(TransactionHash(StarkFelt::from(1_u8)));
+    writer.begin_rw_txn().unwrap().append_body(BlockNumber(0), body).unwrap().commit().unwrap();
+
+    let index = |offset| TransactionIndex(BlockNumber(0), TransactionOffsetInBlock(offset));
+    let expected_entries = [
+        (sender, vec![(index(0), Some(Nonce::default()))]),
+        (deployed_account, vec![(index(1), Some(Nonce::default()))]),
+    ];
+    let assert_entries = |reader: &StorageReader| {
+        let txn = reader.begin_ro_txn().unwrap();
+        for (address, entries) in &expected_entries {
+            assert_eq!(&txn.get_transactions_by_sender(*address, index(0), 10).unwrap(), entries);
+        }
+    };
+    assert_entries(&reader);
+
+    // Remove the index, as in a storage whose transactions were stored before it existed.
+    let txn = writer.begin_rw_txn().unwrap();
+    txn.open_table(&txn.tables.transactions_by_sender).unwrap().clear(&txn.txn).unwrap();
+    txn.commit().unwrap();
+    build_transactions_by_sender_if_needed(&mut writer).unwrap();
+    assert_entries(&reader);
+
+    writer.begin_rw_txn().unwrap().revert_body(BlockNumber(0)).unwrap().0.commit().unwrap();
+    assert!(reader
+        .begin_ro_txn()
+        .unwrap()
+        .get_transactions_by_sender(deployed_account, index(0), 10)
+        .unwrap()
+        .is_empty());
+}
+
 fn append_2_bodies(writer: &mut StorageWriter) {
     writer
         .begin_rw_txn()
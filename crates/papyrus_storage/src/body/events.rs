@@ -51,21 +51,29 @@ mod events_test;
 use serde::{Deserialize, Serialize};
 use starknet_api::block::BlockNumber;
 use starknet_api::core::ContractAddress;
+use starknet_api::hash::StarkFelt;
 use starknet_api::transaction::{
+    DeclareTransactionOutput,
+    DeployAccountTransactionOutput,
+    DeployTransactionOutput,
+    Event,
     EventContent,
     EventIndexInTransactionOutput,
+    EventKey,
     ExecutionResources,
     Fee,
+    InvokeTransactionOutput,
+    L1HandlerTransactionOutput,
     MessageToL1,
     TransactionExecutionStatus,
     TransactionOutput,
 };
 
-use crate::body::{EventsTable, EventsTableKey, TransactionIndex};
+use crate::body::{EventsByFirstKeyTableKey, EventsTable, EventsTableKey, TransactionIndex};
 use crate::db::serialization::{NoVersionValueWrapper, VersionZeroWrapper};
 use crate::db::table_types::{DbCursor, DbCursorTrait, SimpleTable, Table};
 use crate::db::{DbTransaction, RO};
-use crate::{StorageResult, StorageTxn};
+use crate::{StorageError, StorageResult, StorageTxn};
 
 /// An identifier of an event.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize, PartialOrd, Ord)]
@@ -91,6 +99,25 @@ pub trait EventsReader<'txn, 'env> {
         event_index: EventIndex,
         to_block_number: BlockNumber,
     ) -> StorageResult<EventIter<'txn, 'env>>;
+
+    /// Returns an iterator over the events whose first key is one of the given keys, by the order
+    /// of the event index. Events without keys are never returned. Unlike [`EventIter`], the
+    /// iterator yields the errors it encounters, including events that are indexed by their first
+    /// key but are missing from the events table.
+    ///
+    /// # Arguments
+    /// * first_keys - the keys that the first key of the iterated events is one of.
+    /// * event_index - event index to start iterate from it.
+    /// * to_block_number - block number to stop iterate at it.
+    ///
+    /// # Errors
+    /// Returns [`StorageError`](crate::StorageError) if there was an error.
+    fn iter_events_by_first_keys(
+        &'env self,
+        first_keys: &[EventKey],
+        event_index: EventIndex,
+        to_block_number: BlockNumber,
+    ) -> StorageResult<EventIterByFirstKeys<'txn, 'env>>;
 }
 
 // TODO: support all read transactions (including RW).
@@ -109,17 +136,43 @@ impl<'txn, 'env> EventsReader<'txn, 'env> for StorageTxn<'env, RO> {
 
         Ok(EventIter::ByEventIndex(self.iter_events_by_event_index(event_index, to_block_number)?))
     }
+
+    fn iter_events_by_first_keys(
+        &'env self,
+        first_keys: &[EventKey],
+        event_index: EventIndex,
+        to_block_number: BlockNumber,
+    ) -> StorageResult<EventIterByFirstKeys<'txn, 'env>> {
+        let events_by_first_key_table = self.open_table(&self.tables.events_by_first_key)?;
+        let mut first_keys = first_keys.iter().map(|key| key.0).collect::<Vec<_>>();
+        first_keys.sort();
+        first_keys.dedup();
+        let mut first_key_cursors = Vec::with_capacity(first_keys.len());
+        for first_key in first_keys {
+            let mut cursor = events_by_first_key_table.cursor(&self.txn)?;
+            let current = cursor.lower_bound(&(first_key, event_index))?;
+            let mut first_key_cursor = FirstKeyCursor { first_key, current, cursor };
+            first_key_cursor.skip_other_keys();
+            first_key_cursors.push(first_key_cursor);
+        }
+        Ok(EventIterByFirstKeys {
+            txn: &self.txn,
+            first_key_cursors,
+            events_table: self.open_table(&self.tables.events)?,
+            to_block_number,
+        })
+    }
 }
 
 #[allow(missing_docs)]
-/// A wrapper of two iterators [`EventIterByContractAddress`] and [`EventIterByEventIndex`].
+/// A wrapper of the iterators [`EventIterByContractAddress`] and [`EventIterByEventIndex`].
 pub enum EventIter<'txn, 'env> {
     ByContractAddress(EventIterByContractAddress<'txn>),
     ByEventIndex(EventIterByEventIndex<'txn, 'env>),
 }
 
-/// This iterator is a wrapper of two iterators [`EventIterByContractAddress`]
-/// and [`EventIterByEventIndex`].
+/// This iterator is a wrapper of the iterators [`EventIterByContractAddress`] and
+/// [`EventIterByEventIndex`].
 /// With this wrapper we can execute the same code, regardless the
 /// type of iteration used.
 impl Iterator for EventIter<'_, '_> {
@@ -214,6 +267,82 @@ impl EventIterByEventIndex<'_, '_> {
     }
 }
 
+/// This iterator goes over the events whose first key is one of a set of keys, in the order of the
+/// event index. It merges the entries of the events by first key table of each of the keys.
+pub struct EventIterByFirstKeys<'txn, 'env> {
+    txn: &'txn DbTransaction<'env, RO>,
+    first_key_cursors: Vec<FirstKeyCursor<'txn>>,
+    events_table: EventsTable<'env>,
+    to_block_number: BlockNumber,
+}
+
+impl Iterator for EventIterByFirstKeys<'_, '_> {
+    type Item = StorageResult<EventsTableKeyValue>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_event().transpose()
+    }
+}
+
+impl EventIterByFirstKeys<'_, '_> {
+    /// Returns the next event. If there are no more events, returns None.
+    ///
+    /// # Errors
+    /// Returns [`StorageError`](crate::StorageError) if there was an error, or
+    /// [`StorageError::DBInconsistency`] if the next indexed event is missing from the events
+    /// table.
+    fn next_event(&mut self) -> StorageResult<Option<EventsTableKeyValue>> {
+        let Some(first_key_cursor) = self
+            .first_key_cursors
+            .iter_mut()
+            .filter(|first_key_cursor| first_key_cursor.current.is_some())
+            .min_by_key(|first_key_cursor| first_key_cursor.current_event_index())
+        else {
+            return Ok(None);
+        };
+        let Some(((_, event_index), address)) = first_key_cursor.current.take() else {
+            return Ok(None);
+        };
+        if event_index.0.0 > self.to_block_number {
+            return Ok(None);
+        }
+        first_key_cursor.current = first_key_cursor.cursor.next()?;
+        first_key_cursor.skip_other_keys();
+
+        let key = (address, event_index);
+        let Some(content) = self.events_table.get(self.txn, &key)? else {
+            return Err(StorageError::DBInconsistency {
+                msg: format!(
+                    "Event {event_index:?} of contract {address:?} is indexed by its first key \
+                     but is missing from the events table."
+                ),
+            });
+        };
+        Ok(Some((key, content)))
+    }
+}
+
+/// A cursor over the entries of a single key in the events by first key table.
+struct FirstKeyCursor<'txn> {
+    first_key: StarkFelt,
+    current: Option<EventsByFirstKeyTableKeyValue>,
+    cursor: EventsByFirstKeyTableCursor<'txn>,
+}
+
+impl FirstKeyCursor<'_> {
+    fn current_event_index(&self) -> Option<EventIndex> {
+        self.current.as_ref().map(|((_, event_index), _)| *event_index)
+    }
+
+    // The entries of each key are consecutive in the table, so reaching an entry of another key
+    // means there are no more entries for this key.
+    fn skip_other_keys(&mut self) {
+        if matches!(&self.current, Some(((first_key, _), _)) if *first_key != self.first_key) {
+            self.current = None;
+        }
+    }
+}
+
 impl<'txn, 'env> StorageTxn<'env, RO> {
     /// Returns an events iterator that iterates events by the events table key from the given key.
     ///
@@ -321,6 +450,58 @@ impl ThinTransactionOutput {
             ThinTransactionOutput::L1Handler(tx_output) => tx_output.actual_fee,
         }
     }
+    /// Returns the full transaction output, given the events that the transaction emitted.
+    pub fn into_transaction_output(self, events: Vec<Event>) -> TransactionOutput {
+        match self {
+            ThinTransactionOutput::Declare(tx_output) => {
+                TransactionOutput::Declare(DeclareTransactionOutput {
+                    actual_fee: tx_output.actual_fee,
+                    messages_sent: tx_output.messages_sent,
+                    events,
+                    execution_status: tx_output.execution_status,
+                    execution_resources: tx_output.execution_resources,
+                })
+            }
+            ThinTransactionOutput::Deploy(tx_output) => {
+                TransactionOutput::Deploy(DeployTransactionOutput {
+                    actual_fee: tx_output.actual_fee,
+                    messages_sent: tx_output.messages_sent,
+                    events,
+                    contract_address: tx_output.contract_address,
+                    execution_status: tx_output.execution_status,
+                    execution_resources: tx_output.execution_resources,
+                })
+            }
+            ThinTransactionOutput::DeployAccount(tx_output) => {
+                TransactionOutput::DeployAccount(DeployAccountTransactionOutput {
+                    actual_fee: tx_output.actual_fee,
+                    messages_sent: tx_output.messages_sent,
+                    events,
+                    contract_address: tx_output.contract_address,
+                    execution_status: tx_output.execution_status,
+                    execution_resources: tx_output.execution_resources,
+                })
+            }
+            ThinTransactionOutput::Invoke(tx_output) => {
+                TransactionOutput::Invoke(InvokeTransactionOutput {
+                    actual_fee: tx_output.actual_fee,
+                    messages_sent: tx_output.messages_sent,
+                    events,
+                    execution_status: tx_output.execution_status,
+                    execution_resources: tx_output.execution_resources,
+                })
+            }
+            ThinTransactionOutput::L1Handler(tx_output) => {
+                TransactionOutput::L1Handler(L1HandlerTransactionOutput {
+                    actual_fee: tx_output.actual_fee,
+                    messages_sent: tx_output.messages_sent,
+                    events,
+                    execution_status: tx_output.execution_status,
+                    execution_resources: tx_output.execution_resources,
+                })
+            }
+        }
+    }
 }
 /// A thin version of
 /// [`InvokeTransactionOutput`](starknet_api::transaction::InvokeTransactionOutput), not holding the
@@ -472,6 +653,16 @@ type EventsTableKeyValue = (EventsTableKey, EventContent);
 /// A cursor of the events table.
 type EventsTableCursor<'txn> =
     DbCursor<'txn, RO, EventsTableKey, NoVersionValueWrapper<EventContent>, SimpleTable>;
+/// A key-value pair of the events by first key table.
+type EventsByFirstKeyTableKeyValue = (EventsByFirstKeyTableKey, ContractAddress);
+/// A cursor of the events by first key table.
+type EventsByFirstKeyTableCursor<'txn> = DbCursor<
+    'txn,
+    RO,
+    EventsByFirstKeyTableKey,
+    NoVersionValueWrapper<ContractAddress>,
+    SimpleTable,
+>;
 /// A key-value pair of the transaction outputs table.
 type TransactionOutputsKeyValue = (TransactionIndex, ThinTransactionOutput);
 /// A cursor of the transaction outputs table.
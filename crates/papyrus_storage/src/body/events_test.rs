@@ -5,8 +5,12 @@ use camelpaste::paste;
 use pretty_assertions::assert_eq;
 use starknet_api::core::{ContractAddress, PatriciaKey};
 use starknet_api::hash::StarkHash;
-use starknet_api::patricia_key;
-use starknet_api::transaction::{EventIndexInTransactionOutput, TransactionOffsetInBlock};
+use starknet_api::transaction::{
+    EventIndexInTransactionOutput,
+    EventKey,
+    TransactionOffsetInBlock,
+};
+use starknet_api::{patricia_key, stark_felt};
 use test_utils::get_test_block;
 
 use crate::body::events::{
@@ -23,6 +27,7 @@ use crate::body::{BodyStorageWriter, TransactionIndex};
 use crate::db::table_types::Table;
 use crate::header::HeaderStorageWriter;
 use crate::test_utils::get_test_storage;
+use crate::{StorageError, StorageResult};
 
 #[tokio::test]
 async fn iter_events_by_key() {
@@ -113,6 +118,96 @@ async fn iter_events_by_index() {
     }
 }
 
+#[tokio::test]
+async fn iter_events_by_first_keys() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let first_keys = vec![
+        EventKey(stark_felt!("0x1")),
+        EventKey(stark_felt!("0x2")),
+        EventKey(stark_felt!("0x3")),
+    ];
+    let block = get_test_block(4, Some(5), None, Some(vec![first_keys]));
+    let block_number = block.header.block_number;
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block_number, &block.header)
+        .unwrap()
+        .append_body(block_number, block.body.clone())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    // Create the events emitted starting from event index ((0,0),2) whose first key is 0x1 or 0x3.
+    let requested_keys = vec![EventKey(stark_felt!("0x3")), EventKey(stark_felt!("0x1"))];
+    let mut emitted_events = vec![];
+    for (tx_i, tx_output) in block.body.transaction_outputs.iter().enumerate() {
+        for (event_i, event) in tx_output.events().iter().enumerate() {
+            if (tx_i == 0 && event_i < 2) || !requested_keys.contains(&event.content.keys[0]) {
+                continue;
+            }
+            let event_index = EventIndex(
+                TransactionIndex(block_number, TransactionOffsetInBlock(tx_i)),
+                EventIndexInTransactionOutput(event_i),
+            );
+            emitted_events.push(((event.from_address, event_index), event.content.clone()))
+        }
+    }
+
+    let event_index = EventIndex(
+        TransactionIndex(block_number, TransactionOffsetInBlock(0)),
+        EventIndexInTransactionOutput(2),
+    );
+    let txn = storage_reader.begin_ro_txn().unwrap();
+    let events = txn
+        .iter_events_by_first_keys(&requested_keys, event_index, block_number)
+        .unwrap()
+        .collect::<StorageResult<Vec<_>>>()
+        .unwrap();
+    assert_eq!(events, emitted_events);
+
+    // No event has the first key 0x4.
+    let events = txn
+        .iter_events_by_first_keys(&[EventKey(stark_felt!("0x4"))], event_index, block_number)
+        .unwrap()
+        .collect::<StorageResult<Vec<_>>>()
+        .unwrap();
+    assert!(events.is_empty());
+}
+
+#[tokio::test]
+async fn iter_events_by_first_keys_fails_on_missing_event() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let first_key = EventKey(stark_felt!("0x1"));
+    let block = get_test_block(1, Some(2), None, Some(vec![vec![first_key.clone()]]));
+    let block_number = block.header.block_number;
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block_number, &block.header)
+        .unwrap()
+        .append_body(block_number, block.body.clone())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    // Remove the first event from the events table while keeping it in the first key index.
+    let event = &block.body.transaction_outputs[0].events()[0];
+    let event_index = EventIndex(
+        TransactionIndex(block_number, TransactionOffsetInBlock(0)),
+        EventIndexInTransactionOutput(0),
+    );
+    let txn = storage_writer.begin_rw_txn().unwrap();
+    let events_table = txn.txn.open_table(&txn.tables.events).unwrap();
+    events_table.delete(&txn.txn, &(event.from_address, event_index)).unwrap();
+    txn.commit().unwrap();
+
+    let txn = storage_reader.begin_ro_txn().unwrap();
+    let mut events_iter =
+        txn.iter_events_by_first_keys(&[first_key], event_index, block_number).unwrap();
+    assert_matches!(events_iter.next(), Some(Err(StorageError::DBInconsistency { .. })));
+}
+
 #[tokio::test]
 async fn revert_events() {
     let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
@@ -134,15 +229,13 @@ async fn revert_events() {
     );
 
     // Test iter events using the storage reader.
-    assert!(
-        storage_reader
-            .begin_ro_txn()
-            .unwrap()
-            .iter_events(None, event_index, block_number)
-            .unwrap()
-            .last()
-            .is_some()
-    );
+    assert!(storage_reader
+        .begin_ro_txn()
+        .unwrap()
+        .iter_events(None, event_index, block_number)
+        .unwrap()
+        .last()
+        .is_some());
 
     // Test events raw table.
     let txn = storage_reader.begin_ro_txn().unwrap();
@@ -169,23 +262,26 @@ async fn revert_events() {
         .0
         .commit()
         .unwrap();
-    assert!(
-        storage_reader
-            .begin_ro_txn()
-            .unwrap()
-            .iter_events(None, event_index, block_number)
-            .unwrap()
-            .last()
-            .is_none()
-    );
+    assert!(storage_reader
+        .begin_ro_txn()
+        .unwrap()
+        .iter_events(None, event_index, block_number)
+        .unwrap()
+        .last()
+        .is_none());
 
     let txn = storage_reader.begin_ro_txn().unwrap();
     let events_table = txn.txn.open_table(&txn.tables.events).unwrap();
+    let events_by_first_key_table = txn.txn.open_table(&txn.tables.events_by_first_key).unwrap();
     for (tx_idx, tx_output) in block.body.transaction_outputs.iter().enumerate() {
         let transaction_index = TransactionIndex(block_number, TransactionOffsetInBlock(tx_idx));
         for (event_idx, event) in tx_output.events().iter().enumerate() {
             let event_key = EventIndex(transaction_index, EventIndexInTransactionOutput(event_idx));
             assert_matches!(events_table.get(&txn.txn, &(event.from_address, event_key)), Ok(None));
+            assert_matches!(
+                events_by_first_key_table.get(&txn.txn, &(event.content.keys[0].0, event_key)),
+                Ok(None)
+            );
         }
     }
 }
@@ -216,3 +312,12 @@ test_events_contract_addresses_macro!(Deploy, ThinDeployTransactionOutput);
 test_events_contract_addresses_macro!(DeployAccount, ThinDeployAccountTransactionOutput);
 test_events_contract_addresses_macro!(Invoke, ThinInvokeTransactionOutput);
 test_events_contract_addresses_macro!(L1Handler, ThinL1HandlerTransactionOutput);
+
+#[test]
+fn thin_transaction_output_into_transaction_output() {
+    for tx_output in get_test_block(5, Some(3), None, None).body.transaction_outputs {
+        let events = tx_output.events().to_vec();
+        let thin_tx_output = ThinTransactionOutput::from(tx_output.clone());
+        assert_eq!(thin_tx_output.into_transaction_output(events), tx_output);
+    }
+}
@@ -47,7 +47,8 @@ use std::fmt::Debug;
 use papyrus_proc_macros::latency_histogram;
 use serde::{Deserialize, Serialize};
 use starknet_api::block::{BlockBody, BlockNumber};
-use starknet_api::core::ContractAddress;
+use starknet_api::core::{ContractAddress, Nonce};
+use starknet_api::hash::StarkFelt;
 use starknet_api::transaction::{
     Event,
     EventContent,
@@ -60,9 +61,17 @@ use starknet_api::transaction::{
 use tracing::debug;
 
 use crate::body::events::{EventIndex, ThinTransactionOutput};
+use crate::class_usage::{delete_class_usage, record_class_usage};
 use crate::db::serialization::{NoVersionValueWrapper, ValueSerde, VersionZeroWrapper};
 use crate::db::table_types::{DbCursorTrait, SimpleTable, Table};
 use crate::db::{DbTransaction, TableHandle, TransactionKind, RW};
+use crate::secondary_index::{
+    DeployAccountSendersIndex,
+    EventsByFirstKeyIndex,
+    SecondaryIndex,
+    TransactionHashIndex,
+    TransactionsBySenderIndex,
+};
 use crate::{MarkerKind, MarkersTable, StorageError, StorageResult, StorageScope, StorageTxn};
 
 type TransactionsTable<'env> =
@@ -73,9 +82,22 @@ type TransactionHashToIdxTable<'env> =
     TableHandle<'env, TransactionHash, NoVersionValueWrapper<TransactionIndex>, SimpleTable>;
 type TransactionIdxToHashTable<'env> =
     TableHandle<'env, TransactionIndex, NoVersionValueWrapper<TransactionHash>, SimpleTable>;
+type TransactionsBySenderTable<'env> = TableHandle<
+    'env,
+    (ContractAddress, TransactionIndex),
+    NoVersionValueWrapper<Option<Nonce>>,
+    SimpleTable,
+>;
 type EventsTableKey = (ContractAddress, EventIndex);
 type EventsTable<'env> =
     TableHandle<'env, EventsTableKey, NoVersionValueWrapper<EventContent>, SimpleTable>;
+type EventsByFirstKeyTableKey = (StarkFelt, EventIndex);
+type EventsByFirstKeyTable<'env> = TableHandle<
+    'env,
+    EventsByFirstKeyTableKey,
+    NoVersionValueWrapper<ContractAddress>,
+    SimpleTable,
+>;
 
 /// The index of a transaction in a block.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize, PartialOrd, Ord)]
@@ -87,6 +109,10 @@ pub trait BodyStorageReader {
     /// The body marker is the first block number that doesn't exist yet.
     fn get_body_marker(&self) -> StorageResult<BlockNumber>;
 
+    /// The receipts marker is the first block number whose transaction outputs and events aren't
+    /// stored. It's behind the body marker when the storage doesn't store receipts.
+    fn get_receipts_marker(&self) -> StorageResult<BlockNumber>;
+
     /// Returns the transaction and its execution status at the given index.
     fn get_transaction(
         &self,
@@ -140,8 +166,19 @@ pub trait BodyStorageReader {
         &self,
         block_number: BlockNumber,
     ) -> StorageResult<Option<usize>>;
+
+    /// Returns the indices of at most `limit` transactions sent by the given address, starting at
+    /// the given index, in the order of the chain, with the nonce of each transaction if it has
+    /// one. The sender of a deploy account transaction is the account it deploys.
+    fn get_transactions_by_sender(
+        &self,
+        sender_address: ContractAddress,
+        from_transaction_index: TransactionIndex,
+        limit: usize,
+    ) -> StorageResult<Vec<(TransactionIndex, Option<Nonce>)>>;
 }
 
+// The transaction outputs and the events are empty if the receipts of the block weren't stored.
 type RevertedBlockBody =
     (Vec<Transaction>, Vec<ThinTransactionOutput>, Vec<TransactionHash>, Vec<Vec<EventContent>>);
 
@@ -157,6 +194,14 @@ where
     // TODO(yair): make this work without consuming the body.
     fn append_body(self, block_number: BlockNumber, block_body: BlockBody) -> StorageResult<Self>;
 
+    /// Appends the transaction outputs of a block whose body is stored without them, which
+    /// advances the receipts marker.
+    fn append_receipts(
+        self,
+        block_number: BlockNumber,
+        transaction_outputs: Vec<TransactionOutput>,
+    ) -> StorageResult<Self>;
+
     /// Removes a block body from the storage and returns the removed data.
     fn revert_body(
         self,
@@ -170,6 +215,11 @@ impl<'env, Mode: TransactionKind> BodyStorageReader for StorageTxn<'env, Mode> {
         Ok(markers_table.get(&self.txn, &MarkerKind::Body)?.unwrap_or_default())
     }
 
+    fn get_receipts_marker(&self) -> StorageResult<BlockNumber> {
+        let markers_table = self.open_table(&self.tables.markers)?;
+        Ok(markers_table.get(&self.txn, &MarkerKind::Receipts)?.unwrap_or_default())
+    }
+
     fn get_transaction(
         &self,
         transaction_index: TransactionIndex,
@@ -253,6 +303,9 @@ impl<'env, Mode: TransactionKind> BodyStorageReader for StorageTxn<'env, Mode> {
         &self,
         block_number: BlockNumber,
     ) -> StorageResult<Option<Vec<ThinTransactionOutput>>> {
+        if self.get_receipts_marker()? <= block_number {
+            return Ok(None);
+        }
         let transaction_outputs_table = self.open_table(&self.tables.transaction_outputs)?;
         self.get_transactions_in_block(block_number, transaction_outputs_table)
     }
@@ -282,6 +335,26 @@ impl<'env, Mode: TransactionKind> BodyStorageReader for StorageTxn<'env, Mode> {
 
         Ok(Some(last_tx_index.0 + 1))
     }
+
+    fn get_transactions_by_sender(
+        &self,
+        sender_address: ContractAddress,
+        from_transaction_index: TransactionIndex,
+        limit: usize,
+    ) -> StorageResult<Vec<(TransactionIndex, Option<Nonce>)>> {
+        let transactions_by_sender_table = self.open_table(&self.tables.transactions_by_sender)?;
+        let mut cursor = transactions_by_sender_table.cursor(&self.txn)?;
+        let mut current = cursor.lower_bound(&(sender_address, from_transaction_index))?;
+        let mut res = Vec::new();
+        while let Some(((current_sender_address, transaction_index), nonce)) = current {
+            if current_sender_address != sender_address || res.len() == limit {
+                break;
+            }
+            res.push((transaction_index, nonce));
+            current = cursor.next()?;
+        }
+        Ok(res)
+    }
 }
 
 impl<'env, Mode: TransactionKind> StorageTxn<'env, Mode> {
@@ -319,12 +392,12 @@ impl<'env> BodyStorageWriter for StorageTxn<'env, RW> {
 
         if self.scope != StorageScope::StateOnly {
             let transactions_table = self.open_table(&self.tables.transactions)?;
-            let transaction_outputs_table = self.open_table(&self.tables.transaction_outputs)?;
-            let events_table = self.open_table(&self.tables.events)?;
             let transaction_hash_to_idx_table =
                 self.open_table(&self.tables.transaction_hash_to_idx)?;
             let transaction_idx_to_hash_table =
                 self.open_table(&self.tables.transaction_idx_to_hash)?;
+            let transactions_by_sender_table =
+                self.open_table(&self.tables.transactions_by_sender)?;
 
             write_transactions(
                 &block_body,
@@ -332,17 +405,74 @@ impl<'env> BodyStorageWriter for StorageTxn<'env, RW> {
                 &transactions_table,
                 &transaction_hash_to_idx_table,
                 &transaction_idx_to_hash_table,
+                &transactions_by_sender_table,
                 block_number,
             )?;
-            write_transaction_outputs(
-                block_body,
-                &self.txn,
-                &transaction_outputs_table,
-                &events_table,
+
+            // The receipts are stored only if the receipts of all the previous blocks are stored.
+            let receipts_marker = self.get_receipts_marker()?;
+            if self.store_receipts && receipts_marker == block_number {
+                let transaction_outputs_table =
+                    self.open_table(&self.tables.transaction_outputs)?;
+                let events_table = self.open_table(&self.tables.events)?;
+                let events_by_first_key_table =
+                    self.open_table(&self.tables.events_by_first_key)?;
+                write_transaction_outputs(
+                    block_body.transaction_outputs,
+                    &self.txn,
+                    &transaction_outputs_table,
+                    &events_table,
+                    &events_by_first_key_table,
+                    &transactions_by_sender_table,
+                    block_number,
+                )?;
+                markers_table.upsert(&self.txn, &MarkerKind::Receipts, &block_number.next())?;
+            }
+            record_class_usage(&self)?;
+        }
+
+        Ok(self)
+    }
+
+    fn append_receipts(
+        self,
+        block_number: BlockNumber,
+        transaction_outputs: Vec<TransactionOutput>,
+    ) -> StorageResult<Self> {
+        let markers_table = self.open_table(&self.tables.markers)?;
+        let receipts_marker = self.get_receipts_marker()?;
+        if receipts_marker != block_number {
+            return Err(StorageError::MarkerMismatch {
+                expected: receipts_marker,
+                found: block_number,
+            });
+        }
+        let transactions_count = self
+            .get_block_transactions_count(block_number)?
+            .ok_or(StorageError::ReceiptsForMissingBody { block_number })?;
+        if transactions_count != transaction_outputs.len() {
+            return Err(StorageError::WrongNumberOfReceipts {
                 block_number,
-            )?;
+                num_of_txs: transactions_count,
+                num_of_receipts: transaction_outputs.len(),
+            });
         }
 
+        let transaction_outputs_table = self.open_table(&self.tables.transaction_outputs)?;
+        let events_table = self.open_table(&self.tables.events)?;
+        let events_by_first_key_table = self.open_table(&self.tables.events_by_first_key)?;
+        let transactions_by_sender_table = self.open_table(&self.tables.transactions_by_sender)?;
+        write_transaction_outputs(
+            transaction_outputs,
+            &self.txn,
+            &transaction_outputs_table,
+            &events_table,
+            &events_by_first_key_table,
+            &transactions_by_sender_table,
+            block_number,
+        )?;
+        markers_table.upsert(&self.txn, &MarkerKind::Receipts, &block_number.next())?;
+        record_class_usage(&self)?;
         Ok(self)
     }
 
@@ -374,41 +504,80 @@ impl<'env> BodyStorageWriter for StorageTxn<'env, RW> {
             let transaction_idx_to_hash_table =
                 self.open_table(&self.tables.transaction_idx_to_hash)?;
             let events_table = self.open_table(&self.tables.events)?;
+            let events_by_first_key_table = self.open_table(&self.tables.events_by_first_key)?;
+            let transactions_by_sender_table =
+                self.open_table(&self.tables.transactions_by_sender)?;
 
             let transactions = self
                 .get_block_transactions(block_number)?
                 .unwrap_or_else(|| panic!("Missing transactions for block {block_number}."));
-            let transaction_outputs = self
-                .get_block_transaction_outputs(block_number)?
-                .unwrap_or_else(|| panic!("Missing transaction outputs for block {block_number}."));
+            // The receipts of the block aren't stored if the receipts marker is behind it.
+            let receipts_stored = self.get_receipts_marker()? == block_number.next();
+            let transaction_outputs = if receipts_stored {
+                self.get_block_transaction_outputs(block_number)?.unwrap_or_else(|| {
+                    panic!("Missing transaction outputs for block {block_number}.")
+                })
+            } else {
+                vec![]
+            };
             let transaction_hashes = self
                 .get_block_transaction_hashes(block_number)?
                 .unwrap_or_else(|| panic!("Missing transaction hashes for block {block_number}."));
 
             // Delete the transactions data.
             let mut events = vec![];
-            for (offset, tx_output) in transaction_outputs.iter().enumerate() {
+            for (offset, tx_hash) in transaction_hashes.iter().enumerate() {
                 let tx_index = TransactionIndex(block_number, TransactionOffsetInBlock(offset));
-                let tx_hash = self.get_transaction_hash_by_idx(&tx_index)?.unwrap_or_else(|| {
-                    panic!("Missing transaction hash for transaction index {tx_index:?}.")
-                });
-                let mut tx_events = vec![];
-                for (index, from_address) in
-                    tx_output.events_contract_addresses_as_ref().iter().enumerate()
-                {
-                    let key =
-                        (*from_address, EventIndex(tx_index, EventIndexInTransactionOutput(index)));
-                    tx_events.push(events_table.get(&self.txn, &key)?.unwrap_or_else(|| {
-                        panic!("Missing events for transaction output {tx_index:?}.")
-                    }));
-                    events_table.delete(&self.txn, &key)?;
+                if let Some(tx_output) = transaction_outputs.get(offset) {
+                    let mut tx_events = vec![];
+                    for (index, from_address) in
+                        tx_output.events_contract_addresses_as_ref().iter().enumerate()
+                    {
+                        let key = (
+                            *from_address,
+                            EventIndex(tx_index, EventIndexInTransactionOutput(index)),
+                        );
+                        let event_content =
+                            events_table.get(&self.txn, &key)?.unwrap_or_else(|| {
+                                panic!("Missing events for transaction output {tx_index:?}.")
+                            });
+                        events_table.delete(&self.txn, &key)?;
+                        EventsByFirstKeyIndex::delete_entries(
+                            &self.txn,
+                            &events_by_first_key_table,
+                            &key,
+                            &event_content,
+                        )?;
+                        tx_events.push(event_content);
+                    }
+                    events.push(tx_events);
+                    transaction_outputs_table.delete(&self.txn, &tx_index)?;
+                    DeployAccountSendersIndex::delete_entries(
+                        &self.txn,
+                        &transactions_by_sender_table,
+                        &tx_index,
+                        tx_output,
+                    )?;
                 }
-                events.push(tx_events);
                 transactions_table.delete(&self.txn, &tx_index)?;
-                transaction_outputs_table.delete(&self.txn, &tx_index)?;
-                transaction_hash_to_idx_table.delete(&self.txn, &tx_hash)?;
+                TransactionsBySenderIndex::delete_entries(
+                    &self.txn,
+                    &transactions_by_sender_table,
+                    &tx_index,
+                    &transactions[offset],
+                )?;
+                TransactionHashIndex::delete_entries(
+                    &self.txn,
+                    &transaction_hash_to_idx_table,
+                    &tx_index,
+                    tx_hash,
+                )?;
                 transaction_idx_to_hash_table.delete(&self.txn, &tx_index)?;
             }
+            if receipts_stored {
+                markers_table.upsert(&self.txn, &MarkerKind::Receipts, &block_number)?;
+            }
+            delete_class_usage(&self, block_number)?;
             Some((transactions, transaction_outputs, transaction_hashes, events))
         };
 
@@ -423,6 +592,7 @@ fn write_transactions<'env>(
     transactions_table: &'env TransactionsTable<'env>,
     transaction_hash_to_idx_table: &'env TransactionHashToIdxTable<'env>,
     transaction_idx_to_hash_table: &'env TransactionIdxToHashTable<'env>,
+    transactions_by_sender_table: &'env TransactionsBySenderTable<'env>,
     block_number: BlockNumber,
 ) -> StorageResult<()> {
     for (index, (tx, tx_hash)) in
@@ -438,25 +608,36 @@ fn write_transactions<'env>(
             transaction_index,
         )?;
         transactions_table.insert(txn, &transaction_index, tx)?;
+        TransactionsBySenderIndex::insert_entries(
+            txn,
+            transactions_by_sender_table,
+            &transaction_index,
+            tx,
+        )?;
     }
     Ok(())
 }
 
 fn write_transaction_outputs<'env>(
-    block_body: BlockBody,
+    transaction_outputs: Vec<TransactionOutput>,
     txn: &DbTransaction<'env, RW>,
     transaction_outputs_table: &'env TransactionOutputsTable<'env>,
     events_table: &'env EventsTable<'env>,
+    events_by_first_key_table: &'env EventsByFirstKeyTable<'env>,
+    transactions_by_sender_table: &'env TransactionsBySenderTable<'env>,
     block_number: BlockNumber,
 ) -> StorageResult<()> {
-    for (index, tx_output) in block_body.transaction_outputs.into_iter().enumerate() {
+    for (index, tx_output) in transaction_outputs.into_iter().enumerate() {
         let transaction_index = TransactionIndex(block_number, TransactionOffsetInBlock(index));
 
-        write_events(&tx_output, txn, events_table, transaction_index)?;
-        transaction_outputs_table.insert(
+        write_events(&tx_output, txn, events_table, events_by_first_key_table, transaction_index)?;
+        let thin_tx_output = ThinTransactionOutput::from(tx_output);
+        transaction_outputs_table.insert(txn, &transaction_index, &thin_tx_output)?;
+        DeployAccountSendersIndex::insert_entries(
             txn,
+            transactions_by_sender_table,
             &transaction_index,
-            &ThinTransactionOutput::from(tx_output),
+            &thin_tx_output,
         )?;
     }
     Ok(())
@@ -466,11 +647,21 @@ fn write_events<'env>(
     tx_output: &TransactionOutput,
     txn: &DbTransaction<'env, RW>,
     events_table: &'env EventsTable<'env>,
+    events_by_first_key_table: &'env EventsByFirstKeyTable<'env>,
     transaction_index: TransactionIndex,
 ) -> StorageResult<()> {
     for (index, event) in tx_output.events().iter().enumerate() {
         let event_index = EventIndex(transaction_index, EventIndexInTransactionOutput(index));
-        events_table.insert(txn, &(event.from_address, event_index), &event.content)?;
+        let key = (event.from_address, event_index);
+        events_table.insert(txn, &key, &event.content)?;
+        // Index the events by their first key, so that events can be filtered by it without going
+        // over all the events in the requested range.
+        EventsByFirstKeyIndex::insert_entries(
+            txn,
+            events_by_first_key_table,
+            &key,
+            &event.content,
+        )?;
     }
     Ok(())
 }
@@ -482,7 +673,12 @@ fn update_tx_hash_mapping<'env>(
     tx_hash: &TransactionHash,
     transaction_index: TransactionIndex,
 ) -> Result<(), StorageError> {
-    transaction_hash_to_idx_table.insert(txn, tx_hash, &transaction_index)?;
+    TransactionHashIndex::insert_entries(
+        txn,
+        transaction_hash_to_idx_table,
+        &transaction_index,
+        tx_hash,
+    )?;
     transaction_idx_to_hash_table.insert(txn, &transaction_index, tx_hash)?;
     Ok(())
 }
@@ -0,0 +1,276 @@
+//! Interface for the statistics of the classes that the transactions invoke.
+//!
+//! For every block whose body, receipts and state diff are stored, the storage counts the
+//! transactions of the block that invoke each class. The classes that a transaction invokes are
+//! the classes of the contracts that emitted its events, the class of the contract that handles an
+//! L1 handler transaction or that an invoke transaction of version 0 calls, and the class that a
+//! deploy or deploy account transaction deploys. The account that sent the transaction isn't
+//! counted. The counts are recorded by whichever of the body, the receipts and the state diff of
+//! the block is appended last, and deleted by whichever of them is reverted first. They aren't
+//! recorded in the state only scope and in light mode.
+//!
+//! Import [`ClassUsageStorageReader`] to read the statistics using a [`StorageTxn`].
+#[cfg(test)]
+#[path = "class_usage_test.rs"]
+mod class_usage_test;
+
+use std::collections::{HashMap, HashSet};
+
+use starknet_api::block::BlockNumber;
+use starknet_api::core::{ClassHash, ContractAddress};
+use starknet_api::state::StateNumber;
+use starknet_api::transaction::{
+    DeclareTransaction,
+    DeployAccountTransaction,
+    InvokeTransaction,
+    Transaction,
+};
+use tracing::info;
+
+use crate::body::BodyStorageReader;
+use crate::db::table_types::{DbCursorTrait, Table};
+use crate::db::{TransactionKind, RW};
+use crate::state::StateStorageReader;
+use crate::{
+    MarkerKind,
+    NodeMode,
+    StorageError,
+    StorageResult,
+    StorageScope,
+    StorageTxn,
+    StorageWriter,
+};
+
+// The number of blocks whose class usage is recorded in a single transaction when recording the
+// blocks that were stored before it was recorded.
+const CLASS_USAGE_BUILD_CHUNK_SIZE: u64 = 1000;
+
+/// Interface for reading the statistics of the invoked classes.
+pub trait ClassUsageStorageReader {
+    /// Returns the classes that were invoked by the most transactions in the blocks from
+    /// `from_block_number` to `to_block_number` (both included), with the number of these
+    /// transactions, from the most invoked. Returns at most `limit` classes.
+    fn get_most_used_classes(
+        &self,
+        from_block_number: BlockNumber,
+        to_block_number: BlockNumber,
+        limit: usize,
+    ) -> StorageResult<Vec<(ClassHash, u64)>>;
+
+    /// The class usage marker is the first block number whose class usage isn't recorded.
+    fn get_class_usage_marker(&self) -> StorageResult<BlockNumber>;
+}
+
+impl<'env, Mode: TransactionKind> ClassUsageStorageReader for StorageTxn<'env, Mode> {
+    fn get_most_used_classes(
+        &self,
+        from_block_number: BlockNumber,
+        to_block_number: BlockNumber,
+        limit: usize,
+    ) -> StorageResult<Vec<(ClassHash, u64)>> {
+        let class_usage_table = self.open_table(&self.tables.class_usage)?;
+        let mut cursor = class_usage_table.cursor(&self.txn)?;
+        let mut current = cursor.lower_bound(&(from_block_number, ClassHash::default()))?;
+        let mut invocations = HashMap::<ClassHash, u64>::new();
+        while let Some(((block_number, class_hash), count)) = current {
+            if block_number > to_block_number {
+                break;
+            }
+            *invocations.entry(class_hash).or_default() += count;
+            current = cursor.next()?;
+        }
+        let mut most_used_classes = invocations.into_iter().collect::<Vec<_>>();
+        most_used_classes.sort_unstable_by(|(class_hash_a, count_a), (class_hash_b, count_b)| {
+            count_b.cmp(count_a).then(class_hash_a.cmp(class_hash_b))
+        });
+        most_used_classes.truncate(limit);
+        Ok(most_used_classes)
+    }
+
+    fn get_class_usage_marker(&self) -> StorageResult<BlockNumber> {
+        let markers_table = self.open_table(&self.tables.markers)?;
+        Ok(markers_table.get(&self.txn, &MarkerKind::ClassUsage)?.unwrap_or_default())
+    }
+}
+
+/// Records the class usage of the blocks that were stored before it was recorded. The blocks are
+/// recorded in chunks, each in its own transaction, so an interrupted build resumes where it
+/// stopped.
+pub(crate) fn record_class_usage_if_needed(writer: &mut StorageWriter) -> StorageResult<()> {
+    loop {
+        let txn = writer.begin_rw_txn()?;
+        if !is_recorded(&txn) {
+            return Ok(());
+        }
+        let class_usage_marker = txn.get_class_usage_marker()?;
+        let end = recorded_blocks_end(&txn)?;
+        if class_usage_marker >= end {
+            return Ok(());
+        }
+        let chunk_end = BlockNumber(end.0.min(class_usage_marker.0 + CLASS_USAGE_BUILD_CHUNK_SIZE));
+        record_blocks(&txn, class_usage_marker, chunk_end)?;
+        txn.commit()?;
+        info!("Recorded the class usage of the blocks up to {chunk_end} out of {end}.");
+    }
+}
+
+// Records the number of transactions that invoke each class in the blocks whose body, receipts
+// and state diff are stored and that weren't recorded yet.
+pub(crate) fn record_class_usage(txn: &StorageTxn<'_, RW>) -> StorageResult<()> {
+    if !is_recorded(txn) {
+        return Ok(());
+    }
+    let class_usage_marker = txn.get_class_usage_marker()?;
+    let end = recorded_blocks_end(txn)?;
+    if class_usage_marker >= end {
+        return Ok(());
+    }
+    record_blocks(txn, class_usage_marker, end)
+}
+
+// Deletes the counts of the given block, if they were recorded.
+pub(crate) fn delete_class_usage(
+    txn: &StorageTxn<'_, RW>,
+    block_number: BlockNumber,
+) -> StorageResult<()> {
+    if !is_recorded(txn) || txn.get_class_usage_marker()? <= block_number {
+        return Ok(());
+    }
+    let class_usage_table = txn.open_table(&txn.tables.class_usage)?;
+    let mut cursor = class_usage_table.cursor(&txn.txn)?;
+    let mut keys = vec![];
+    let mut current = cursor.lower_bound(&(block_number, ClassHash::default()))?;
+    while let Some((key, _)) = current {
+        if key.0 != block_number {
+            break;
+        }
+        keys.push(key);
+        current = cursor.next()?;
+    }
+    for key in keys {
+        class_usage_table.delete(&txn.txn, &key)?;
+    }
+    let markers_table = txn.open_table(&txn.tables.markers)?;
+    markers_table.upsert(&txn.txn, &MarkerKind::ClassUsage, &block_number)?;
+    Ok(())
+}
+
+fn is_recorded<Mode: TransactionKind>(txn: &StorageTxn<'_, Mode>) -> bool {
+    txn.scope != StorageScope::StateOnly && txn.mode != NodeMode::Light
+}
+
+// The first block whose class usage can't be recorded yet. The receipts marker is never ahead of
+// the body marker.
+fn recorded_blocks_end<Mode: TransactionKind>(
+    txn: &StorageTxn<'_, Mode>,
+) -> StorageResult<BlockNumber> {
+    Ok(txn.get_receipts_marker()?.min(txn.get_state_marker()?))
+}
+
+// Records the blocks from `from_block_number` up to `to_block_number` (excluded), and advances the
+// class usage marker to `to_block_number`.
+fn record_blocks(
+    txn: &StorageTxn<'_, RW>,
+    from_block_number: BlockNumber,
+    to_block_number: BlockNumber,
+) -> StorageResult<()> {
+    let class_usage_table = txn.open_table(&txn.tables.class_usage)?;
+    for block_number in from_block_number.iter_up_to(to_block_number) {
+        for (class_hash, count) in block_invocations(txn, block_number)? {
+            class_usage_table.upsert(&txn.txn, &(block_number, class_hash), &count)?;
+        }
+    }
+    let markers_table = txn.open_table(&txn.tables.markers)?;
+    markers_table.upsert(&txn.txn, &MarkerKind::ClassUsage, &to_block_number)?;
+    Ok(())
+}
+
+// Returns the number of transactions of the block that invoke each class. The contracts that a
+// transaction invokes are the ones that emitted its events, apart from the account that sent it,
+// and the contract it targets directly, if any.
+fn block_invocations<Mode: TransactionKind>(
+    txn: &StorageTxn<'_, Mode>,
+    block_number: BlockNumber,
+) -> StorageResult<HashMap<ClassHash, u64>> {
+    let missing_data = |data: &str| StorageError::DBInconsistency {
+        msg: format!("Missing {data} of block {block_number} below its marker."),
+    };
+    let transactions =
+        txn.get_block_transactions(block_number)?.ok_or_else(|| missing_data("transactions"))?;
+    let transaction_outputs = txn
+        .get_block_transaction_outputs(block_number)?
+        .ok_or_else(|| missing_data("transaction outputs"))?;
+    let state_reader = txn.get_state_reader()?;
+    // The class the contract had at the start of the block, or the class it was deployed with in
+    // the block.
+    let class_hash_at = |address: &ContractAddress| -> StorageResult<Option<ClassHash>> {
+        match state_reader
+            .get_class_hash_at(StateNumber::right_before_block(block_number), address)?
+        {
+            Some(class_hash) => Ok(Some(class_hash)),
+            None => state_reader
+                .get_class_hash_at(StateNumber::right_after_block(block_number), address),
+        }
+    };
+
+    let mut invocations = HashMap::<ClassHash, u64>::new();
+    for (transaction, transaction_output) in transactions.iter().zip(transaction_outputs.iter()) {
+        let (sender, target) = sender_and_target(transaction);
+        let mut invoked_classes = HashSet::new();
+        if let Some(InvokedContract::Class(class_hash)) = target {
+            invoked_classes.insert(class_hash);
+        }
+        let invoked_addresses = transaction_output
+            .events_contract_addresses_as_ref()
+            .iter()
+            .copied()
+            .chain(match target {
+                Some(InvokedContract::Address(address)) => Some(address),
+                _ => None,
+            })
+            .filter(|address| Some(*address) != sender)
+            .collect::<HashSet<_>>();
+        for address in &invoked_addresses {
+            if let Some(class_hash) = class_hash_at(address)? {
+                invoked_classes.insert(class_hash);
+            }
+        }
+        for class_hash in invoked_classes {
+            *invocations.entry(class_hash).or_default() += 1;
+        }
+    }
+    Ok(invocations)
+}
+
+enum InvokedContract {
+    Address(ContractAddress),
+    Class(ClassHash),
+}
+
+// Returns the account that sent the transaction, if it was sent by an account, and the contract
+// that the transaction targets directly, if any.
+fn sender_and_target(
+    transaction: &Transaction,
+) -> (Option<ContractAddress>, Option<InvokedContract>) {
+    match transaction {
+        Transaction::Declare(DeclareTransaction::V0(tx) | DeclareTransaction::V1(tx)) => {
+            (Some(tx.sender_address), None)
+        }
+        Transaction::Declare(DeclareTransaction::V2(tx)) => (Some(tx.sender_address), None),
+        Transaction::Declare(DeclareTransaction::V3(tx)) => (Some(tx.sender_address), None),
+        Transaction::Deploy(tx) => (None, Some(InvokedContract::Class(tx.class_hash))),
+        Transaction::DeployAccount(DeployAccountTransaction::V1(tx)) => {
+            (None, Some(InvokedContract::Class(tx.class_hash)))
+        }
+        Transaction::DeployAccount(DeployAccountTransaction::V3(tx)) => {
+            (None, Some(InvokedContract::Class(tx.class_hash)))
+        }
+        // Invoke transactions of version 0 call the contract directly, without an account.
+        Transaction::Invoke(InvokeTransaction::V0(tx)) => {
+            (None, Some(InvokedContract::Address(tx.contract_address)))
+        }
+        Transaction::Invoke(InvokeTransaction::V1(tx)) => (Some(tx.sender_address), None),
+        Transaction::Invoke(InvokeTransaction::V3(tx)) => (Some(tx.sender_address), None),
+        Transaction::L1Handler(tx) => (None, Some(InvokedContract::Address(tx.contract_address))),
+    }
+}
@@ -0,0 +1,145 @@
+use indexmap::indexmap;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockBody, BlockNumber};
+use starknet_api::core::{ClassHash, ContractAddress, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::ThinStateDiff;
+use starknet_api::transaction::{
+    DeployAccountTransaction,
+    DeployAccountTransactionV1,
+    Event,
+    EventContent,
+    InvokeTransaction,
+    InvokeTransactionOutput,
+    InvokeTransactionV1,
+    L1HandlerTransaction,
+    Transaction,
+    TransactionHash,
+    TransactionOutput,
+};
+use starknet_api::{class_hash, contract_address, patricia_key, stark_felt};
+
+use crate::body::BodyStorageWriter;
+use crate::class_usage::{record_class_usage_if_needed, ClassUsageStorageReader};
+use crate::db::table_types::Table;
+use crate::state::StateStorageWriter;
+use crate::test_utils::get_test_storage;
+use crate::MarkerKind;
+
+// Returns a body whose transactions all emit an event from each of the given contracts.
+fn body(transactions: Vec<Transaction>, event_emitters: &[ContractAddress]) -> BlockBody {
+    let events = event_emitters
+        .iter()
+        .map(|from_address| Event { from_address: *from_address, content: EventContent::default() })
+        .collect();
+    let transaction_output =
+        TransactionOutput::Invoke(InvokeTransactionOutput { events, ..Default::default() });
+    let transaction_outputs = vec![transaction_output; transactions.len()];
+    let transaction_hashes =
+        (0..transactions.len()).map(|i| TransactionHash(StarkFelt::from(i as u64))).collect();
+    BlockBody { transactions, transaction_outputs, transaction_hashes }
+}
+
+#[test]
+fn most_used_classes() {
+    let account = contract_address!("0x1");
+    let contract = contract_address!("0x2");
+    let account_class = class_hash!("0x10");
+    let contract_class = class_hash!("0x20");
+    let new_account_class = class_hash!("0x30");
+    let invoke = Transaction::Invoke(InvokeTransaction::V1(InvokeTransactionV1 {
+        sender_address: account,
+        ..Default::default()
+    }));
+
+    let block_1_body = body(
+        vec![
+            invoke.clone(),
+            Transaction::L1Handler(L1HandlerTransaction {
+                contract_address: contract,
+                ..Default::default()
+            }),
+            Transaction::DeployAccount(DeployAccountTransaction::V1(DeployAccountTransactionV1 {
+                class_hash: new_account_class,
+                ..Default::default()
+            })),
+        ],
+        &[contract],
+    );
+
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_body(BlockNumber(0), body(vec![], &[]))
+        .unwrap()
+        .append_thin_state_diff(
+            BlockNumber(0),
+            ThinStateDiff {
+                deployed_contracts: indexmap! {
+                    account => account_class,
+                    contract => contract_class,
+                },
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        // The usage of block 1 is recorded when its body is appended after its state diff.
+        .append_thin_state_diff(BlockNumber(1), ThinStateDiff::default())
+        .unwrap()
+        .append_body(BlockNumber(1), block_1_body)
+        .unwrap()
+        // The usage of block 2 is recorded when its state diff is appended after its body. The
+        // events of the account that sent the transactions aren't counted.
+        .append_body(BlockNumber(2), body(vec![invoke.clone(), invoke], &[account, contract]))
+        .unwrap()
+        .append_thin_state_diff(BlockNumber(2), ThinStateDiff::default())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_class_usage_marker().unwrap(), BlockNumber(3));
+    assert_eq!(
+        txn.get_most_used_classes(BlockNumber(0), BlockNumber(2), 10).unwrap(),
+        vec![(contract_class, 5), (new_account_class, 1)]
+    );
+    assert_eq!(
+        txn.get_most_used_classes(BlockNumber(2), BlockNumber(5), 10).unwrap(),
+        vec![(contract_class, 2)]
+    );
+    assert_eq!(
+        txn.get_most_used_classes(BlockNumber(0), BlockNumber(1), 1).unwrap(),
+        vec![(contract_class, 3)]
+    );
+    drop(txn);
+
+    // Reverting the state diff of a block deletes its usage, and it isn't recorded again by
+    // reverting its body.
+    let (txn, _) = writer.begin_rw_txn().unwrap().revert_state_diff(BlockNumber(2)).unwrap();
+    let (txn, _) = txn.revert_body(BlockNumber(2)).unwrap();
+    txn.commit().unwrap();
+    assert_eq!(
+        reader
+            .begin_ro_txn()
+            .unwrap()
+            .get_most_used_classes(BlockNumber(0), BlockNumber(2), 10)
+            .unwrap(),
+        vec![(contract_class, 3), (new_account_class, 1)]
+    );
+
+    // Remove the usage, as in a storage whose blocks were stored before it was recorded.
+    let txn = writer.begin_rw_txn().unwrap();
+    txn.open_table(&txn.tables.class_usage).unwrap().clear(&txn.txn).unwrap();
+    txn.open_table(&txn.tables.markers).unwrap().delete(&txn.txn, &MarkerKind::ClassUsage).unwrap();
+    txn.commit().unwrap();
+    record_class_usage_if_needed(&mut writer).unwrap();
+    assert_eq!(
+        reader
+            .begin_ro_txn()
+            .unwrap()
+            .get_most_used_classes(BlockNumber(0), BlockNumber(2), 10)
+            .unwrap(),
+        vec![(contract_class, 3), (new_account_class, 1)]
+    );
+}
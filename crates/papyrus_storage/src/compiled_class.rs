@@ -73,7 +73,14 @@ impl<'env, Mode: TransactionKind> CasmStorageReader for StorageTxn<'env, Mode> {
     fn get_casm(&self, class_hash: &ClassHash) -> StorageResult<Option<CasmContractClass>> {
         let casm_table = self.open_table(&self.tables.casms)?;
         let casm_location = casm_table.get(&self.txn, class_hash)?;
-        casm_location.map(|location| self.file_handlers.get_casm_unchecked(location)).transpose()
+        casm_location
+            .map(|location| {
+                let casm = self
+                    .class_cache
+                    .get_or_read(class_hash, || self.file_handlers.get_casm_unchecked(location))?;
+                Ok(CasmContractClass::clone(&casm))
+            })
+            .transpose()
     }
 
     fn get_compiled_class_marker(&self) -> StorageResult<BlockNumber> {
@@ -97,8 +104,8 @@ impl<'env> CasmStorageWriter for StorageTxn<'env, RW> {
             &self.txn,
             &markers_table,
             &state_diff_table,
+            &casm_table,
             self.file_handlers.clone(),
-            class_hash,
         )?;
         Ok(self)
     }
@@ -113,24 +120,22 @@ fn update_marker<'env>(
         VersionZeroWrapper<LocationInFile>,
         SimpleTable,
     >,
+    casm_table: &'env TableHandle<'_, ClassHash, VersionZeroWrapper<LocationInFile>, SimpleTable>,
     file_handlers: FileHandlers<RW>,
-    class_hash: &ClassHash,
 ) -> StorageResult<()> {
-    // The marker needs to update if we reached the last class from the state diff. We can continue
-    // advancing it if the next blocks don't have declared classes.
+    // The marker needs to update once the compiled classes of all the classes declared in the
+    // block are stored, whatever order they were stored in. We can continue advancing it if the
+    // next blocks don't have declared classes or their compiled classes were stored already.
     let mut block_number = markers_table.get(txn, &MarkerKind::CompiledClass)?.unwrap_or_default();
     loop {
         let Some(state_diff_location) = state_diffs_table.get(txn, &block_number)? else {
             break;
         };
-        if let Some((last_class_hash, _)) = file_handlers
-            .get_thin_state_diff_unchecked(state_diff_location)?
-            .declared_classes
-            .last()
+        for (declared_class_hash, _) in
+            file_handlers.get_thin_state_diff_unchecked(state_diff_location)?.declared_classes
         {
-            // Not the last class in the state diff, keep the current marker.
-            if last_class_hash != class_hash {
-                break;
+            if casm_table.get(txn, &declared_class_hash)?.is_none() {
+                return Ok(());
             }
         }
         block_number = block_number.next();
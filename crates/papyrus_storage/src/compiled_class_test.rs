@@ -1,11 +1,16 @@
 use assert_matches::assert_matches;
 use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
+use indexmap::indexmap;
 use pretty_assertions::assert_eq;
-use starknet_api::core::ClassHash;
+use starknet_api::block::BlockNumber;
+use starknet_api::core::{ClassHash, CompiledClassHash};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::ThinStateDiff;
 use test_utils::read_json_file;
 
 use crate::compiled_class::{CasmStorageReader, CasmStorageWriter};
 use crate::db::{DbError, KeyAlreadyExistsError};
+use crate::state::StateStorageWriter;
 use crate::test_utils::get_test_storage;
 use crate::StorageError;
 
@@ -53,3 +58,43 @@ fn casm_rewrite() {
         value: _
     })) if key == format!("{:?}", ClassHash::default()));
 }
+
+#[test]
+fn marker_advances_once_all_the_compiled_classes_of_the_block_are_stored() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let first_class_hash = ClassHash(StarkFelt::from(1_u8));
+    let last_class_hash = ClassHash(StarkFelt::from(2_u8));
+    let thin_state_diff = ThinStateDiff {
+        declared_classes: indexmap! {
+            first_class_hash => CompiledClassHash::default(),
+            last_class_hash => CompiledClassHash::default(),
+        },
+        ..Default::default()
+    };
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_thin_state_diff(BlockNumber(0), thin_state_diff)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    // The compiled classes may be stored in any order.
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_casm(&last_class_hash, &CasmContractClass::default())
+        .unwrap()
+        .commit()
+        .unwrap();
+    assert_eq!(reader.begin_ro_txn().unwrap().get_compiled_class_marker().unwrap(), BlockNumber(0));
+
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_casm(&first_class_hash, &CasmContractClass::default())
+        .unwrap()
+        .commit()
+        .unwrap();
+    assert_eq!(reader.begin_ro_txn().unwrap().get_compiled_class_marker().unwrap(), BlockNumber(1));
+}
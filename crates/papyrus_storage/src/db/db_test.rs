@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use assert_matches::assert_matches;
 use libmdbx::PageSize;
 use pretty_assertions::assert_eq;
@@ -5,7 +7,16 @@ use tempfile::TempDir;
 
 use crate::db::serialization::{NoVersionValueWrapper, ValueSerde, VersionZeroWrapper};
 use crate::db::table_types::Table;
-use crate::db::{get_page_size, open_env, DbError, DbIter, DbReader, DbResult, DbWriter};
+use crate::db::{
+    get_page_size,
+    open_env,
+    to_windows_file_name,
+    DbError,
+    DbIter,
+    DbReader,
+    DbResult,
+    DbWriter,
+};
 use crate::test_utils::get_test_config;
 
 pub(crate) fn get_test_env() -> ((DbReader, DbWriter), TempDir) {
@@ -104,6 +115,36 @@ fn txns_scenarios() {
     assert_eq!(table.get(&txn3, b"key").unwrap(), None);
 }
 
+#[test]
+fn write_stats() {
+    let ((_reader, mut writer), _temp_dir) = get_test_env();
+    let table_id =
+        writer.create_simple_table::<[u8; 3], NoVersionValueWrapper<[u8; 5]>>("table").unwrap();
+    let other_table_id = writer
+        .create_simple_table::<[u8; 3], NoVersionValueWrapper<[u8; 5]>>("other_table")
+        .unwrap();
+
+    let wtxn = writer.begin_rw_txn().unwrap();
+    let table = wtxn.open_table(&table_id).unwrap();
+    let other_table = wtxn.open_table(&other_table_id).unwrap();
+    table.insert(&wtxn, b"key", b"data0").unwrap();
+    table.upsert(&wtxn, b"key", b"data1").unwrap();
+    other_table.insert(&wtxn, b"key", b"data0").unwrap();
+    // Failed insertions and deletions aren't counted.
+    assert!(other_table.insert(&wtxn, b"key", b"data1").is_err());
+    table.delete(&wtxn, b"key").unwrap();
+    let write_stats = wtxn.commit().unwrap();
+
+    // Each write is of a 3 bytes key and a 5 bytes value.
+    assert_eq!(write_stats.bytes_written, 3 * (3 + 5));
+    assert_eq!(write_stats.table_writes, BTreeMap::from([("table", 2), ("other_table", 1)]));
+
+    // A transaction without writes.
+    let write_stats = writer.begin_rw_txn().unwrap().commit().unwrap();
+    assert_eq!(write_stats.bytes_written, 0);
+    assert!(write_stats.table_writes.is_empty());
+}
+
 #[test]
 
 fn table_stats() {
@@ -167,6 +208,22 @@ fn get_page_size_test() {
     assert_eq!(get_page_size(2047), PageSize::Set(1024));
 }
 
+#[test]
+fn windows_file_name() {
+    assert_eq!(to_windows_file_name("SN_MAIN"), "SN_MAIN");
+    assert_eq!(to_windows_file_name("a<b>c:d\"e/f\\g|h?i*j\u{1}"), "a_b_c_d_e_f_g_h_i_j_");
+}
+
+#[cfg(windows)]
+#[test]
+fn path_is_valid_on_windows() {
+    let (mut config, _temp_dir) = get_test_config(None);
+    config.db_config.chain_id = starknet_api::core::ChainId("chain:1".to_owned());
+    assert!(config.db_config.path().ends_with("chain_1"));
+    std::fs::create_dir_all(config.db_config.path()).unwrap();
+    open_env(&config.db_config).unwrap();
+}
+
 #[test]
 fn test_iter() {
     // Create an environment and a table.
@@ -21,28 +21,34 @@ pub mod db_stats;
 pub mod serialization;
 pub(crate) mod table_types;
 
+use std::any::type_name;
 use std::borrow::Cow;
 use std::collections::BTreeMap;
 use std::fmt::Debug;
 use std::marker::PhantomData;
 use std::path::PathBuf;
 use std::result;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use libmdbx::{Geometry, PageSize, WriteMap};
+use libmdbx::{EnvironmentFlags, Geometry, Mode, PageSize, SyncMode, WriteMap};
+use metrics::histogram;
+use papyrus_common::chain_id::validate_chain_id;
 use papyrus_config::dumping::{ser_param, SerializeConfig};
-use papyrus_config::validators::{validate_ascii, validate_path_exists};
+use papyrus_config::validators::validate_path_exists;
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 use serde::{Deserialize, Serialize};
 use starknet_api::core::ChainId;
+use tracing::trace_span;
 use validator::Validate;
 
 use self::serialization::{Key, ValueSerde};
 use self::table_types::{DbCursor, DbCursorTrait};
 use crate::db::table_types::TableType;
+use crate::schema::TableSchema;
 
 // Maximum number of Sub-Databases.
-const MAX_DBS: usize = 20;
+const MAX_DBS: usize = 40;
 
 // Note that NO_TLS mode is used by default.
 type EnvironmentKind = WriteMap;
@@ -59,7 +65,7 @@ pub struct DbConfig {
     #[validate(custom = "validate_path_exists")]
     pub path_prefix: PathBuf,
     /// The [chain id](https://docs.rs/starknet_api/latest/starknet_api/core/struct.ChainId.html) of the Starknet network.
-    #[validate(custom = "validate_ascii")]
+    #[validate(custom = "validate_chain_id")]
     pub chain_id: ChainId,
     /// Whether to enforce that the path exists. If true, `open_env` fails when the mdbx.dat file
     /// does not exist.
@@ -132,12 +138,27 @@ impl SerializeConfig for DbConfig {
 }
 
 impl DbConfig {
-    /// Returns the path of the database (path prefix, followed by the chain id).
+    /// Returns the path of the database (path prefix, followed by the chain id). On Windows, the
+    /// characters of the chain id that aren't allowed in file names are replaced.
     pub fn path(&self) -> PathBuf {
-        self.path_prefix.join(self.chain_id.0.as_str())
+        if cfg!(windows) {
+            self.path_prefix.join(to_windows_file_name(&self.chain_id.0))
+        } else {
+            self.path_prefix.join(self.chain_id.0.as_str())
+        }
     }
 }
 
+// The characters that aren't allowed in file names on Windows, in addition to control characters.
+const WINDOWS_RESERVED_CHARS: [char; 9] = ['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+// Replaces the characters that aren't allowed in a file name on Windows with underscores.
+fn to_windows_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_control() || WINDOWS_RESERVED_CHARS.contains(&c) { '_' } else { c })
+        .collect()
+}
+
 /// An error that can occur when interacting with the database.
 #[derive(thiserror::Error, Debug)]
 pub enum DbError {
@@ -201,9 +222,19 @@ pub(crate) fn open_env(config: &DbConfig) -> DbResult<(DbReader, DbWriter)> {
             })
             .set_max_tables(MAX_DBS)
             .set_max_readers(MAX_READERS)
+            .set_flags(environment_flags())
             .open(&config.path())?,
     );
-    Ok((DbReader { env: env.clone() }, DbWriter { env }))
+    Ok((DbReader { env: env.clone() }, DbWriter { env, last_write_lock_wait: Duration::ZERO }))
+}
+
+// On macOS, a durable commit flushes the whole disk cache with F_FULLFSYNC, which makes every
+// commit take tens of milliseconds. There, commits only keep the database consistent, and the last
+// ones may be lost on a power failure.
+fn environment_flags() -> EnvironmentFlags {
+    let sync_mode =
+        if cfg!(target_os = "macos") { SyncMode::SafeNoSync } else { SyncMode::Durable };
+    EnvironmentFlags { mode: Mode::ReadWrite { sync_mode }, ..Default::default() }
 }
 
 // Size in bytes.
@@ -229,11 +260,24 @@ pub(crate) struct DbReader {
 #[derive(Debug)]
 pub(crate) struct DbWriter {
     env: Arc<Environment>,
+    // The time the last write transaction waited for the write lock of the database.
+    last_write_lock_wait: Duration,
 }
 
 impl DbReader {
     pub(crate) fn begin_ro_txn(&self) -> DbResult<DbReadTransaction<'_>> {
-        Ok(DbReadTransaction { txn: self.env.begin_ro_txn()? })
+        Ok(DbReadTransaction {
+            txn: self.env.begin_ro_txn()?,
+            env: &self.env,
+            write_stats: Mutex::default(),
+        })
+    }
+
+    // Returns the serialized value of the entry with the given serialized key in a table.
+    pub(crate) fn get_raw_value(&self, table_name: &str, key: &[u8]) -> DbResult<Option<Vec<u8>>> {
+        let db_txn = self.begin_ro_txn()?;
+        let table = db_txn.txn.open_table(Some(table_name))?;
+        Ok(db_txn.txn.get::<DbValueType<'_>>(&table, key)?.map(|value| value.into_owned()))
     }
 }
 
@@ -241,19 +285,54 @@ type DbReadTransaction<'env> = DbTransaction<'env, RO>;
 
 impl DbWriter {
     pub(crate) fn begin_rw_txn(&mut self) -> DbResult<DbWriteTransaction<'_>> {
-        Ok(DbWriteTransaction { txn: self.env.begin_rw_txn()? })
+        // Beginning a write transaction waits for the write lock of the database, which other
+        // processes that write to it may hold.
+        let start = Instant::now();
+        let txn = self.env.begin_rw_txn()?;
+        self.last_write_lock_wait = start.elapsed();
+        histogram!("storage_write_lock_wait_seconds", self.last_write_lock_wait.as_secs_f64());
+        Ok(DbWriteTransaction { txn, env: &self.env, write_stats: Mutex::default() })
+    }
+
+    // Returns the time the last write transaction waited for the write lock.
+    pub(crate) fn last_write_lock_wait(&self) -> Duration {
+        self.last_write_lock_wait
     }
 }
 
 type DbWriteTransaction<'env> = DbTransaction<'env, RW>;
 
 impl<'a> DbWriteTransaction<'a> {
-    pub(crate) fn commit(self) -> DbResult<()> {
+    // Commits the transaction and returns the statistics of its writes.
+    pub(crate) fn commit(self) -> DbResult<WriteStats> {
+        let last_page_before_commit = self.env.info()?.last_pgno();
         self.txn.commit()?;
-        Ok(())
+        let last_page_after_commit = self.env.info()?.last_pgno();
+        let mut write_stats = self.write_stats.into_inner().expect("Lock should not be poisoned");
+        write_stats.new_pages = last_page_after_commit.saturating_sub(last_page_before_commit);
+        Ok(write_stats)
+    }
+
+    // Records an entry of the given serialized size that was written to a table.
+    fn record_write(&self, table_name: &'static str, bytes: usize) {
+        let mut write_stats = self.write_stats.lock().expect("Lock should not be poisoned");
+        write_stats.bytes_written += bytes;
+        *write_stats.table_writes.entry(table_name).or_default() += 1;
     }
 }
 
+/// Statistics of the writes of a transaction, to measure the write amplification of the storage.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct WriteStats {
+    /// The total size of the serialized keys and values that were written to the tables.
+    pub(crate) bytes_written: usize,
+    /// The number of entries that were inserted or updated in each table.
+    pub(crate) table_writes: BTreeMap<&'static str, usize>,
+    /// The number of pages that the commit added to the end of the database file. Pages that were
+    /// reused from the free list aren't counted.
+    pub(crate) new_pages: usize,
+}
+
 #[doc(hidden)]
 // Transaction wrappers.
 pub trait TransactionKind {
@@ -262,6 +341,9 @@ pub trait TransactionKind {
 
 pub(crate) struct DbTransaction<'env, Mode: TransactionKind> {
     txn: libmdbx::Transaction<'env, Mode::Internal, EnvironmentKind>,
+    env: &'env Environment,
+    // Filled only by write transactions.
+    write_stats: Mutex<WriteStats>,
 }
 
 impl<'a, Mode: TransactionKind> DbTransaction<'a, Mode> {
@@ -269,6 +351,7 @@ impl<'a, Mode: TransactionKind> DbTransaction<'a, Mode> {
         &'env self,
         table_id: &TableIdentifier<K, V, T>,
     ) -> DbResult<TableHandle<'env, K, V, T>> {
+        let _span = trace_span!("open_table", table = table_id.name).entered();
         let database = self.txn.open_table(Some(table_id.name))?;
         Ok(TableHandle {
             database,
@@ -286,6 +369,19 @@ pub(crate) struct TableIdentifier<K: Key + Debug, V: ValueSerde + Debug, T: Tabl
     _table_type: PhantomData<T>,
 }
 
+impl<K: Key + Debug, V: ValueSerde + Debug, T: TableType> TableIdentifier<K, V, T> {
+    // Describes the on-disk layout of the table with the given name.
+    pub(crate) fn schema(name: &'static str) -> TableSchema {
+        TableSchema {
+            name,
+            key: type_name::<K>(),
+            value: type_name::<V::Value>(),
+            value_version: V::VALUE_VERSION,
+            table_type: type_name::<T>(),
+        }
+    }
+}
+
 pub(crate) struct TableHandle<'env, K: Key + Debug, V: ValueSerde + Debug, T: TableType> {
     database: libmdbx::Table<'env>,
     name: &'static str,
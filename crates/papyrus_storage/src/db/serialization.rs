@@ -45,6 +45,8 @@ impl<T> Key for T where T: StorageSerdeEx + Ord + Clone {}
 /// Trait for serializing and deserializing values from the database.
 pub(crate) trait ValueSerde {
     type Value: StorageSerde + Debug;
+    /// The byte that prefixes every serialized value, if the values are versioned.
+    const VALUE_VERSION: Option<u8>;
 
     fn serialize(obj: &Self::Value) -> Result<Vec<u8>, DbError>;
     // TODO(yair): Return a result here.
@@ -59,6 +61,7 @@ pub(crate) struct NoVersionValueWrapper<T: StorageSerde> {
 
 impl<T: StorageSerde + Debug> ValueSerde for NoVersionValueWrapper<T> {
     type Value = T;
+    const VALUE_VERSION: Option<u8> = None;
 
     fn serialize(obj: &Self::Value) -> Result<Vec<u8>, DbError> {
         StorageSerdeEx::serialize(obj)
@@ -81,6 +84,7 @@ const VERSION_ZERO: u8 = 0;
 
 impl<T: StorageSerde + Debug> ValueSerde for VersionZeroWrapper<T> {
     type Value = T;
+    const VALUE_VERSION: Option<u8> = Some(VERSION_ZERO);
 
     fn serialize(obj: &Self::Value) -> Result<Vec<u8>, DbError> {
         let mut res = Vec::new();
@@ -129,6 +133,7 @@ impl<T: StorageSerde + Debug + Migratable, const VERSION: u8> ValueSerde
     for VersionWrapper<T, VERSION>
 {
     type Value = T;
+    const VALUE_VERSION: Option<u8> = Some(VERSION);
 
     fn serialize(obj: &Self::Value) -> Result<Vec<u8>, DbError> {
         let mut res = Vec::new();
@@ -44,6 +44,9 @@ pub(crate) trait Table<'env> {
     ) -> DbResult<()>;
 
     fn delete(&'env self, txn: &DbTransaction<'env, RW>, key: &Self::Key) -> DbResult<()>;
+
+    // Deletes all the entries of the table.
+    fn clear(&'env self, txn: &DbTransaction<'env, RW>) -> DbResult<()>;
 }
 
 pub(crate) trait DbCursorTrait {
@@ -63,6 +66,8 @@ pub(crate) trait DbCursorTrait {
 
 pub(crate) struct DbCursor<'txn, Mode: TransactionKind, K: KeyTrait, V: ValueSerde, T: TableType> {
     cursor: Cursor<'txn, Mode::Internal>,
+    // The name of the table, for the tracing spans of the cursor.
+    table_name: &'static str,
     _key_type: PhantomData<K>,
     _value_type: PhantomData<V>,
     _table_type: PhantomData<T>,
@@ -7,6 +7,7 @@ use std::fmt::Debug;
 use std::marker::PhantomData;
 
 use libmdbx::{TableFlags, WriteFlags};
+use tracing::{field, trace_span};
 
 use super::{DbResult, Table, TableType};
 use crate::db::serialization::{Key as KeyTrait, ValueSerde};
@@ -61,6 +62,7 @@ impl<'env, K: KeyTrait + Debug, V: ValueSerde + Debug> Table<'env>
         let cursor = txn.txn.cursor(&self.database)?;
         Ok(DbCursor {
             cursor,
+            table_name: self.name,
             _key_type: PhantomData {},
             _value_type: PhantomData {},
             _table_type: PhantomData {},
@@ -72,13 +74,17 @@ impl<'env, K: KeyTrait + Debug, V: ValueSerde + Debug> Table<'env>
         txn: &'env DbTransaction<'env, Mode>,
         key: &Self::Key,
     ) -> DbResult<Option<<Self::Value as ValueSerde>::Value>> {
+        let span = trace_span!("get", table = self.name, bytes = field::Empty);
+        let _entered = span.enter();
         // TODO: Support zero-copy. This might require a return type of Cow<'env, ValueType>.
         let bin_key = key.serialize()?;
         let Some(bytes) = txn.txn.get::<Cow<'env, [u8]>>(&self.database, &bin_key)? else {
             return Ok(None);
         };
-        let value =
-            <Self::Value>::deserialize(&mut bytes.as_ref()).ok_or(DbError::InnerDeserialization)?;
+        span.record("bytes", bytes.len());
+        let value = trace_span!("deserialize")
+            .in_scope(|| <Self::Value>::deserialize(&mut bytes.as_ref()))
+            .ok_or(DbError::InnerDeserialization)?;
         Ok(Some(value))
     }
 
@@ -90,7 +96,9 @@ impl<'env, K: KeyTrait + Debug, V: ValueSerde + Debug> Table<'env>
     ) -> DbResult<()> {
         let data = <Self::Value>::serialize(value)?;
         let bin_key = key.serialize()?;
+        let bytes = bin_key.len() + data.len();
         txn.txn.put(&self.database, bin_key, data, WriteFlags::UPSERT)?;
+        txn.record_write(self.name, bytes);
         Ok(())
     }
 
@@ -102,6 +110,7 @@ impl<'env, K: KeyTrait + Debug, V: ValueSerde + Debug> Table<'env>
     ) -> DbResult<()> {
         let data = <Self::Value>::serialize(value)?;
         let bin_key = key.serialize()?;
+        let bytes = bin_key.len() + data.len();
         txn.txn.put(&self.database, bin_key, data, WriteFlags::NO_OVERWRITE).map_err(|err| {
             match err {
                 libmdbx::Error::KeyExist => {
@@ -110,6 +119,7 @@ impl<'env, K: KeyTrait + Debug, V: ValueSerde + Debug> Table<'env>
                 _ => err.into(),
             }
         })?;
+        txn.record_write(self.name, bytes);
         Ok(())
     }
 
@@ -119,6 +129,11 @@ impl<'env, K: KeyTrait + Debug, V: ValueSerde + Debug> Table<'env>
         txn.txn.del(&self.database, bin_key, None)?;
         Ok(())
     }
+
+    fn clear(&'env self, txn: &DbTransaction<'env, RW>) -> DbResult<()> {
+        txn.txn.clear_table(&self.database)?;
+        Ok(())
+    }
 }
 
 impl<'txn, Mode: TransactionKind, K: KeyTrait + Debug, V: ValueSerde + Debug> DbCursorTrait
@@ -160,6 +175,7 @@ impl<'txn, Mode: TransactionKind, K: KeyTrait + Debug, V: ValueSerde + Debug> Db
         &mut self,
         key: &K,
     ) -> DbResult<Option<(K, <Self::Value as ValueSerde>::Value)>> {
+        let _span = trace_span!("lower_bound", table = self.table_name).entered();
         let key_bytes = key.serialize()?;
         let prev_cursor_res =
             self.cursor.set_range::<DbKeyType<'_>, DbValueType<'_>>(&key_bytes)?;
@@ -3,6 +3,11 @@
 //! The block header is the part of the block that contains metadata about the block.
 //! Import [`HeaderStorageReader`] and [`HeaderStorageWriter`] to read and write data related
 //! to the block headers using a [`StorageTxn`].
+//!
+//! The hashes of the stored blocks are accumulated in a Merkle mountain range (see
+//! [`papyrus_common::header_accumulator`]), which is updated whenever a header is appended or
+//! reverted, so that a block can be proven to be an ancestor of the head. The accumulator of the
+//! headers that were stored before it existed is built when the storage is opened.
 //! # Example
 //! ```
 //! use papyrus_storage::open_storage;
@@ -38,6 +43,14 @@
 #[path = "header_test.rs"]
 mod header_test;
 
+use papyrus_common::header_accumulator::{
+    appended_node_ids,
+    appended_nodes,
+    peak_ids,
+    sibling_ids,
+    AccumulatorProof,
+    NodeId,
+};
 use serde::{Deserialize, Serialize};
 use starknet_api::block::{
     BlockHash,
@@ -55,12 +68,25 @@ use starknet_api::core::{
     TransactionCommitment,
 };
 use starknet_api::data_availability::L1DataAvailabilityMode;
-use tracing::debug;
+use starknet_api::hash::StarkFelt;
+use tracing::{debug, info};
+
+use crate::db::table_types::{DbCursorTrait, Table};
+use crate::db::{DbTransaction, TransactionKind, RW};
+use crate::secondary_index::{BlockHashIndex, SecondaryIndex};
+use crate::{
+    HeaderAccumulatorTable,
+    MarkerKind,
+    MarkersTable,
+    StorageError,
+    StorageResult,
+    StorageTxn,
+    StorageWriter,
+};
 
-use crate::db::serialization::NoVersionValueWrapper;
-use crate::db::table_types::{DbCursorTrait, SimpleTable, Table};
-use crate::db::{DbTransaction, TableHandle, TransactionKind, RW};
-use crate::{MarkerKind, MarkersTable, StorageError, StorageResult, StorageTxn};
+// The number of headers whose hashes are added to the header accumulator in each transaction when
+// it's built for headers that were stored before it existed.
+const HEADER_ACCUMULATOR_BUILD_CHUNK_SIZE: u64 = 10000;
 
 #[derive(Debug, Default, Clone, Eq, PartialEq, Hash, Deserialize, Serialize, PartialOrd, Ord)]
 pub(crate) struct StorageBlockHeader {
@@ -79,9 +105,6 @@ pub(crate) struct StorageBlockHeader {
     pub n_events: usize,
 }
 
-type BlockHashToNumberTable<'env> =
-    TableHandle<'env, BlockHash, NoVersionValueWrapper<BlockNumber>, SimpleTable>;
-
 /// Interface for reading data related to the block headers.
 pub trait HeaderStorageReader {
     /// The block marker is the first block number that doesn't exist yet.
@@ -106,6 +129,13 @@ pub trait HeaderStorageReader {
         &self,
         block_number: BlockNumber,
     ) -> StorageResult<Option<BlockSignature>>;
+
+    /// Returns a proof that the hash of the block with the given number is in the header
+    /// accumulator of all the stored headers, or None if the block's header isn't stored.
+    fn get_header_accumulator_proof(
+        &self,
+        block_number: BlockNumber,
+    ) -> StorageResult<Option<AccumulatorProof>>;
 }
 
 /// Interface for writing data related to the block headers.
@@ -216,6 +246,28 @@ impl<'env, Mode: TransactionKind> HeaderStorageReader for StorageTxn<'env, Mode>
         let block_signature = block_signatures_table.get(&self.txn, &block_number)?;
         Ok(block_signature)
     }
+
+    fn get_header_accumulator_proof(
+        &self,
+        block_number: BlockNumber,
+    ) -> StorageResult<Option<AccumulatorProof>> {
+        let leaf_count = self.get_header_marker()?.0;
+        let Some(sibling_ids) = sibling_ids(block_number.0, leaf_count) else {
+            return Ok(None);
+        };
+        let header_accumulator_table = self.open_table(&self.tables.header_accumulator)?;
+        let get_nodes = |node_ids: Vec<NodeId>| {
+            node_ids
+                .into_iter()
+                .map(|node_id| get_accumulator_node(&self.txn, &header_accumulator_table, node_id))
+                .collect::<StorageResult<Vec<_>>>()
+        };
+        Ok(Some(AccumulatorProof {
+            leaf_count,
+            siblings: get_nodes(sibling_ids)?,
+            peaks: get_nodes(peak_ids(leaf_count))?,
+        }))
+    }
 }
 
 impl<'env> HeaderStorageWriter for StorageTxn<'env, RW> {
@@ -227,6 +279,7 @@ impl<'env> HeaderStorageWriter for StorageTxn<'env, RW> {
         let markers_table = self.open_table(&self.tables.markers)?;
         let headers_table = self.open_table(&self.tables.headers)?;
         let block_hash_to_number_table = self.open_table(&self.tables.block_hash_to_number)?;
+        let header_accumulator_table = self.open_table(&self.tables.header_accumulator)?;
 
         update_marker(&self.txn, &markers_table, block_number)?;
 
@@ -248,13 +301,21 @@ impl<'env> HeaderStorageWriter for StorageTxn<'env, RW> {
 
         headers_table.insert(&self.txn, &block_number, &storage_block_header)?;
 
-        update_hash_mapping(
+        BlockHashIndex::insert_entries(
             &self.txn,
             &block_hash_to_number_table,
+            &block_number,
             &storage_block_header,
-            block_number,
         )?;
 
+        let accumulator_nodes =
+            appended_nodes(block_number.0, block_header.block_hash.0, |node_id| {
+                get_accumulator_node(&self.txn, &header_accumulator_table, node_id)
+            })?;
+        for (node_id, node) in accumulator_nodes {
+            header_accumulator_table.insert(&self.txn, &node_id, &node)?;
+        }
+
         self.update_starknet_version(&block_number, &block_header.starknet_version)
     }
 
@@ -286,6 +347,7 @@ impl<'env> HeaderStorageWriter for StorageTxn<'env, RW> {
         let block_hash_to_number_table = self.open_table(&self.tables.block_hash_to_number)?;
         let starknet_version_table = self.open_table(&self.tables.starknet_version)?;
         let block_signatures_table = self.open_table(&self.tables.block_signatures)?;
+        let header_accumulator_table = self.open_table(&self.tables.header_accumulator)?;
 
         // Assert that header marker equals the reverted block number + 1
         let current_header_marker = self.get_header_marker()?;
@@ -305,7 +367,15 @@ impl<'env> HeaderStorageWriter for StorageTxn<'env, RW> {
             .expect("Missing header for block {block_number}.");
         markers_table.upsert(&self.txn, &MarkerKind::Header, &block_number)?;
         headers_table.delete(&self.txn, &block_number)?;
-        block_hash_to_number_table.delete(&self.txn, &reverted_header.block_hash)?;
+        BlockHashIndex::delete_entries(
+            &self.txn,
+            &block_hash_to_number_table,
+            &block_number,
+            &reverted_header,
+        )?;
+        for node_id in appended_node_ids(block_number.0) {
+            header_accumulator_table.delete(&self.txn, &node_id)?;
+        }
 
         // Revert starknet version and get the version.
         // TODO(shahak): Fix code duplication with get_starknet_version.
@@ -328,6 +398,10 @@ impl<'env> HeaderStorageWriter for StorageTxn<'env, RW> {
             block_signatures_table.delete(&self.txn, &block_number)?;
         }
 
+        // The block that replaces the reverted one isn't necessarily quarantined.
+        let quarantined_blocks_table = self.open_table(&self.tables.quarantined_blocks)?;
+        quarantined_blocks_table.delete(&self.txn, &block_number)?;
+
         Ok((
             self,
             Some(BlockHeader {
@@ -369,16 +443,6 @@ impl<'env> HeaderStorageWriter for StorageTxn<'env, RW> {
     }
 }
 
-fn update_hash_mapping<'env>(
-    txn: &DbTransaction<'env, RW>,
-    block_hash_to_number_table: &'env BlockHashToNumberTable<'env>,
-    block_header: &StorageBlockHeader,
-    block_number: BlockNumber,
-) -> Result<(), StorageError> {
-    block_hash_to_number_table.insert(txn, &block_header.block_hash, &block_number)?;
-    Ok(())
-}
-
 fn update_marker<'env>(
     txn: &DbTransaction<'env, RW>,
     markers_table: &'env MarkersTable<'env>,
@@ -394,3 +458,76 @@ fn update_marker<'env>(
     markers_table.upsert(txn, &MarkerKind::Header, &block_number.next())?;
     Ok(())
 }
+
+/// Adds the hashes of the stored headers that are missing from the header accumulator, which are
+/// the headers that were stored before the accumulator existed. The hashes are added in chunks,
+/// each in its own transaction, so an interrupted build resumes where it stopped.
+pub(crate) fn build_header_accumulator_if_needed(writer: &mut StorageWriter) -> StorageResult<()> {
+    loop {
+        let txn = writer.begin_rw_txn()?;
+        let header_marker = txn.get_header_marker()?;
+        let leaf_count = header_accumulator_leaf_count(&txn, header_marker)?;
+        if leaf_count == header_marker.0 {
+            return Ok(());
+        }
+        let chunk_end = header_marker.0.min(leaf_count + HEADER_ACCUMULATOR_BUILD_CHUNK_SIZE);
+        let headers_table = txn.open_table(&txn.tables.headers)?;
+        let header_accumulator_table = txn.open_table(&txn.tables.header_accumulator)?;
+        for leaf_index in leaf_count..chunk_end {
+            let block_hash = headers_table
+                .get(&txn.txn, &BlockNumber(leaf_index))?
+                .ok_or(StorageError::DBInconsistency {
+                    msg: format!("Missing header of block {leaf_index} below the header marker."),
+                })?
+                .block_hash;
+            let accumulator_nodes = appended_nodes(leaf_index, block_hash.0, |node_id| {
+                get_accumulator_node(&txn.txn, &header_accumulator_table, node_id)
+            })?;
+            for (node_id, node) in accumulator_nodes {
+                header_accumulator_table.insert(&txn.txn, &node_id, &node)?;
+            }
+        }
+        txn.commit()?;
+        info!(
+            "Added the hashes of the headers up to block {chunk_end} out of {} to the header \
+             accumulator.",
+            header_marker.0
+        );
+    }
+}
+
+// Returns the number of headers whose hashes are in the header accumulator. The hashes are always
+// added in the order of the headers, so they're the hashes of the first headers.
+fn header_accumulator_leaf_count<Mode: TransactionKind>(
+    txn: &StorageTxn<'_, Mode>,
+    header_marker: BlockNumber,
+) -> StorageResult<u64> {
+    let header_accumulator_table = txn.open_table(&txn.tables.header_accumulator)?;
+    let has_leaf = |leaf_index: u64| -> StorageResult<bool> {
+        Ok(header_accumulator_table.get(&txn.txn, &(0, leaf_index))?.is_some())
+    };
+    if header_marker.0 == 0 || has_leaf(header_marker.0 - 1)? {
+        return Ok(header_marker.0);
+    }
+    // Binary search for the first missing leaf, knowing that the last one is missing.
+    let (mut low, mut high) = (0, header_marker.0 - 1);
+    while low < high {
+        let middle = low + (high - low) / 2;
+        if has_leaf(middle)? {
+            low = middle + 1;
+        } else {
+            high = middle;
+        }
+    }
+    Ok(low)
+}
+
+fn get_accumulator_node<'env, Mode: TransactionKind>(
+    txn: &DbTransaction<'env, Mode>,
+    header_accumulator_table: &'env HeaderAccumulatorTable<'env>,
+    node_id: NodeId,
+) -> StorageResult<StarkFelt> {
+    header_accumulator_table.get(txn, &node_id)?.ok_or_else(|| StorageError::DBInconsistency {
+        msg: format!("Missing node {node_id:?} of the header accumulator."),
+    })
+}
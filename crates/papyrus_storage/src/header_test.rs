@@ -4,7 +4,13 @@ use starknet_api::block::{BlockHash, BlockHeader, BlockNumber, BlockSignature};
 use starknet_api::hash::StarkFelt;
 use starknet_api::stark_felt;
 
-use crate::header::{HeaderStorageReader, HeaderStorageWriter, StarknetVersion};
+use crate::db::table_types::Table;
+use crate::header::{
+    build_header_accumulator_if_needed,
+    HeaderStorageReader,
+    HeaderStorageWriter,
+    StarknetVersion,
+};
 use crate::test_utils::get_test_storage;
 use crate::{StorageError, StorageWriter};
 
@@ -113,14 +119,20 @@ async fn get_reverted_block_number_by_hash_returns_none() {
     let block_hash = BlockHash(stark_felt!("0x1"));
 
     // Verify that we can get block 1 by hash before the revert.
-    assert!(
-        reader.begin_ro_txn().unwrap().get_block_number_by_hash(&block_hash).unwrap().is_some()
-    );
+    assert!(reader
+        .begin_ro_txn()
+        .unwrap()
+        .get_block_number_by_hash(&block_hash)
+        .unwrap()
+        .is_some());
 
     writer.begin_rw_txn().unwrap().revert_header(BlockNumber(1)).unwrap().0.commit().unwrap();
-    assert!(
-        reader.begin_ro_txn().unwrap().get_block_number_by_hash(&block_hash).unwrap().is_none()
-    );
+    assert!(reader
+        .begin_ro_txn()
+        .unwrap()
+        .get_block_number_by_hash(&block_hash)
+        .unwrap()
+        .is_none());
 }
 
 fn append_2_headers(writer: &mut StorageWriter) {
@@ -285,3 +297,92 @@ fn get_reverted_block_signature_returns_none() {
     assert!(maybe_signature.is_some());
     assert!(reader.begin_ro_txn().unwrap().get_block_signature(BlockNumber(0)).unwrap().is_none());
 }
+
+#[test]
+fn header_accumulator_proof() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let block_hash = |block_number: u64| BlockHash(StarkFelt::from(block_number + 1));
+    let append_header = |writer: &mut StorageWriter, block_number: u64| {
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_header(
+                BlockNumber(block_number),
+                &BlockHeader { block_hash: block_hash(block_number), ..BlockHeader::default() },
+            )
+            .unwrap()
+            .commit()
+            .unwrap();
+    };
+    let proof = |block_number: u64| {
+        reader.begin_ro_txn().unwrap().get_header_accumulator_proof(BlockNumber(block_number))
+    };
+    assert_eq!(proof(0).unwrap(), None);
+
+    for block_number in 0..7 {
+        append_header(&mut writer, block_number);
+    }
+    let root = proof(0).unwrap().unwrap().root();
+    for block_number in 0..7 {
+        let proof = proof(block_number).unwrap().unwrap();
+        assert_eq!(proof.leaf_count, 7);
+        assert!(proof.verify(block_number, block_hash(block_number).0, root));
+    }
+    assert_eq!(proof(7).unwrap(), None);
+
+    // Reverting the last header restores the accumulator of the previous headers, and a different
+    // header can be appended instead.
+    let root_before_last_header = {
+        writer.begin_rw_txn().unwrap().revert_header(BlockNumber(6)).unwrap().0.commit().unwrap();
+        proof(0).unwrap().unwrap().root()
+    };
+    assert_ne!(root_before_last_header, root);
+    append_header(&mut writer, 6);
+    assert_eq!(proof(0).unwrap().unwrap().root(), root);
+}
+
+#[test]
+fn header_accumulator_is_built_for_headers_stored_before_it() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    for block_number in 0..7 {
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_header(
+                BlockNumber(block_number),
+                &BlockHeader {
+                    block_hash: BlockHash(StarkFelt::from(block_number + 1)),
+                    ..BlockHeader::default()
+                },
+            )
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+    let root = reader
+        .begin_ro_txn()
+        .unwrap()
+        .get_header_accumulator_proof(BlockNumber(0))
+        .unwrap()
+        .unwrap()
+        .root();
+
+    // Remove the accumulator, as in a storage whose headers were stored before it existed.
+    let txn = writer.begin_rw_txn().unwrap();
+    txn.open_table(&txn.tables.header_accumulator).unwrap().clear(&txn.txn).unwrap();
+    txn.commit().unwrap();
+    assert_matches!(
+        reader.begin_ro_txn().unwrap().get_header_accumulator_proof(BlockNumber(0)),
+        Err(StorageError::DBInconsistency { .. })
+    );
+
+    build_header_accumulator_if_needed(&mut writer).unwrap();
+    let proof = reader
+        .begin_ro_txn()
+        .unwrap()
+        .get_header_accumulator_proof(BlockNumber(3))
+        .unwrap()
+        .unwrap();
+    assert_eq!(proof.root(), root);
+    assert!(proof.verify(3, StarkFelt::from(4_u64), root));
+}
@@ -52,6 +52,7 @@
 
 pub mod base_layer;
 pub mod body;
+pub mod class_usage;
 pub mod compiled_class;
 pub mod utils;
 // TODO(yair): Make the compression_utils module pub(crate) or extract it from the crate.
@@ -60,7 +61,12 @@ pub mod compression_utils;
 pub mod db;
 pub mod header;
 pub mod mmap_file;
+pub mod node_identity;
+pub mod quarantine;
+pub mod schema;
+pub mod secondary_index;
 mod serialization;
+pub mod source_reputation;
 pub mod state;
 mod version;
 
@@ -73,6 +79,7 @@ pub mod test_utils;
 use std::collections::BTreeMap;
 use std::fmt::Debug;
 use std::sync::Arc;
+use std::time::Duration;
 
 use body::events::EventIndex;
 use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
@@ -88,21 +95,24 @@ use mmap_file::{
     Reader,
     Writer,
 };
+use papyrus_common::class_cache::ClassCache;
 use papyrus_config::dumping::{append_sub_config_name, ser_param, SerializeConfig};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 use serde::{Deserialize, Serialize};
 use starknet_api::block::{BlockHash, BlockNumber, BlockSignature, StarknetVersion};
-use starknet_api::core::{ClassHash, ContractAddress, Nonce};
+use starknet_api::core::{ClassHash, ContractAddress, EntryPointSelector, Nonce};
 use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
 use starknet_api::hash::StarkFelt;
-use starknet_api::state::{ContractClass, StorageKey, ThinStateDiff};
+use starknet_api::state::{ContractClass, StateNumber, StorageKey, ThinStateDiff};
 use starknet_api::transaction::{EventContent, Transaction, TransactionHash};
 use tracing::{debug, warn};
 use validator::Validate;
 use version::{StorageVersionError, Version};
 
+use crate::base_layer::BaseLayerL1Block;
 use crate::body::events::ThinTransactionOutput;
 use crate::body::TransactionIndex;
+use crate::class_usage::record_class_usage_if_needed;
 use crate::db::table_types::SimpleTable;
 use crate::db::{
     open_env,
@@ -117,8 +127,17 @@ use crate::db::{
     RO,
     RW,
 };
-use crate::header::StorageBlockHeader;
-use crate::state::data::IndexedDeprecatedContractClass;
+use crate::header::{build_header_accumulator_if_needed, StorageBlockHeader};
+use crate::node_identity::NodeIdentity;
+use crate::quarantine::QuarantinedBlock;
+use crate::secondary_index::{
+    build_transactions_by_sender_if_needed,
+    resume_secondary_index_rebuilds,
+    IndexRebuildProgress,
+};
+use crate::source_reputation::SourceReputation;
+use crate::state::data::{IndexedDeprecatedContractClass, IndexedEntryPoint, StateDiffStats};
+use crate::utils::update_commit_metrics;
 pub use crate::utils::update_storage_metrics;
 use crate::version::{VersionStorageReader, VersionStorageWriter};
 
@@ -130,7 +149,7 @@ pub const STORAGE_VERSION_STATE: Version = Version(13);
 /// Whenever a breaking change is introduced, the version is incremented and a storage
 /// migration is required for existing storages.
 /// This version is only checked for storages that store transactions (StorageScope::FullArchive).
-pub const STORAGE_VERSION_BLOCKS: Version = Version(13);
+pub const STORAGE_VERSION_BLOCKS: Version = Version(14);
 
 /// Opens a storage and returns a [`StorageReader`] and a [`StorageWriter`].
 pub fn open_storage(
@@ -138,25 +157,37 @@ pub fn open_storage(
 ) -> StorageResult<(StorageReader, StorageWriter)> {
     let (db_reader, mut db_writer) = open_env(&storage_config.db_config)?;
     let tables = Arc::new(Tables {
+        base_layer_l1_blocks: db_writer.create_simple_table("base_layer_l1_blocks")?,
         block_hash_to_number: db_writer.create_simple_table("block_hash_to_number")?,
         block_signatures: db_writer.create_simple_table("block_signatures")?,
         casms: db_writer.create_simple_table("casms")?,
+        class_usage: db_writer.create_simple_table("class_usage")?,
         contract_storage: db_writer.create_simple_table("contract_storage")?,
         declared_classes: db_writer.create_simple_table("declared_classes")?,
         declared_classes_block: db_writer.create_simple_table("declared_classes_block")?,
+        deferred_classes: db_writer.create_simple_table("deferred_classes")?,
         deprecated_declared_classes: db_writer
             .create_simple_table("deprecated_declared_classes")?,
         deployed_contracts: db_writer.create_simple_table("deployed_contracts")?,
+        entry_points: db_writer.create_simple_table("entry_points")?,
         events: db_writer.create_simple_table("events")?,
+        events_by_first_key: db_writer.create_simple_table("events_by_first_key")?,
+        header_accumulator: db_writer.create_simple_table("header_accumulator")?,
         headers: db_writer.create_simple_table("headers")?,
         markers: db_writer.create_simple_table("markers")?,
+        node_identity: db_writer.create_simple_table("node_identity")?,
         nonces: db_writer.create_simple_table("nonces")?,
         file_offsets: db_writer.create_simple_table("file_offsets")?,
+        quarantined_blocks: db_writer.create_simple_table("quarantined_blocks")?,
+        secondary_index_rebuilds: db_writer.create_simple_table("secondary_index_rebuilds")?,
+        source_reputation: db_writer.create_simple_table("source_reputation")?,
         state_diffs: db_writer.create_simple_table("state_diffs")?,
+        state_diff_stats: db_writer.create_simple_table("state_diff_stats")?,
         transaction_hash_to_idx: db_writer.create_simple_table("transaction_hash_to_idx")?,
         transaction_idx_to_hash: db_writer.create_simple_table("transaction_idx_to_hash")?,
         transaction_outputs: db_writer.create_simple_table("transaction_outputs")?,
         transactions: db_writer.create_simple_table("transactions")?,
+        transactions_by_sender: db_writer.create_simple_table("transactions_by_sender")?,
 
         // Version tables
         starknet_version: db_writer.create_simple_table("starknet_version")?,
@@ -169,19 +200,54 @@ pub fn open_storage(
         &tables.file_offsets,
     )?;
 
+    let class_cache = Arc::new(ClassCache::new(storage_config.class_cache_size, None));
     let reader = StorageReader {
         db_reader,
         tables: tables.clone(),
+        class_cache: class_cache.clone(),
         scope: storage_config.scope,
+        mode: storage_config.mode,
+        full_mode_state_history: storage_config.full_mode_state_history,
+        store_receipts: storage_config.store_receipts,
         file_readers,
     };
-    let writer = StorageWriter { db_writer, tables, scope: storage_config.scope, file_writers };
+    let writer = StorageWriter {
+        db_writer,
+        tables,
+        class_cache,
+        scope: storage_config.scope,
+        mode: storage_config.mode,
+        full_mode_state_history: storage_config.full_mode_state_history,
+        store_receipts: storage_config.store_receipts,
+        file_writers,
+    };
 
-    let writer = set_version_if_needed(reader.clone(), writer)?;
+    let mut writer = set_version_if_needed(reader.clone(), writer)?;
     verify_storage_version(reader.clone())?;
+    set_receipts_marker_if_needed(&mut writer)?;
+    build_header_accumulator_if_needed(&mut writer)?;
+    resume_secondary_index_rebuilds(&mut writer)?;
+    build_transactions_by_sender_if_needed(&mut writer)?;
+    record_class_usage_if_needed(&mut writer)?;
+    if storage_config.repair_indexes_on_open {
+        repair_secondary_indexes(&mut writer)?;
+    }
     Ok((reader, writer))
 }
 
+// Deletes the secondary index entries that point at blocks that aren't stored, e.g. after a crash,
+// so that they don't serve data of these blocks.
+fn repair_secondary_indexes(writer: &mut StorageWriter) -> StorageResult<()> {
+    for repair in writer.repair_secondary_indexes()? {
+        warn!(
+            "Deleted {} entries of the index {} that pointed at blocks that aren't stored.",
+            repair.deleted_entries,
+            repair.index.name()
+        );
+    }
+    Ok(())
+}
+
 // In case storage version does not exist, set it to the crate version.
 // Expected to happen once - when the node is launched for the first time.
 // If the storage scope has changed, update accordingly.
@@ -230,6 +296,22 @@ fn set_version_if_needed(
     Ok(writer)
 }
 
+// Storages that were created before the receipts had their own marker stored the receipts of every
+// block with its body, so their receipts marker starts at the body marker.
+fn set_receipts_marker_if_needed(writer: &mut StorageWriter) -> StorageResult<()> {
+    if writer.scope == StorageScope::StateOnly {
+        return Ok(());
+    }
+    let txn = writer.begin_rw_txn()?;
+    let markers_table = txn.open_table(&txn.tables.markers)?;
+    if markers_table.get(&txn.txn, &MarkerKind::Receipts)?.is_some() {
+        return Ok(());
+    }
+    let body_marker = markers_table.get(&txn.txn, &MarkerKind::Body)?.unwrap_or_default();
+    markers_table.upsert(&txn.txn, &MarkerKind::Receipts, &body_marker)?;
+    txn.commit()
+}
+
 #[derive(Debug)]
 struct FullArchiveVersion {
     state_version: Version,
@@ -325,13 +407,30 @@ pub enum StorageScope {
     StateOnly,
 }
 
+/// The mode in which the node runs, determining which of the synced data is kept queryable.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub enum NodeMode {
+    /// Keeps the state of every block queryable.
+    #[default]
+    Archive,
+    /// Keeps only the state of the most recent blocks queryable, and prunes the values that only
+    /// older states need.
+    Full,
+    /// Stores only the block headers and signatures.
+    Light,
+}
+
 /// A struct for starting RO transactions ([`StorageTxn`]) to the storage.
 #[derive(Clone)]
 pub struct StorageReader {
     db_reader: DbReader,
     file_readers: FileHandlers<RO>,
     tables: Arc<Tables>,
+    class_cache: Arc<ClassCache>,
     scope: StorageScope,
+    mode: NodeMode,
+    full_mode_state_history: u64,
+    store_receipts: bool,
 }
 
 impl StorageReader {
@@ -342,7 +441,11 @@ impl StorageReader {
             txn: self.db_reader.begin_ro_txn()?,
             file_handlers: self.file_readers.clone(),
             tables: self.tables.clone(),
+            class_cache: self.class_cache.clone(),
             scope: self.scope,
+            mode: self.mode,
+            full_mode_state_history: self.full_mode_state_history,
+            store_receipts: self.store_receipts,
         })
     }
 
@@ -355,10 +458,32 @@ impl StorageReader {
         Ok(DbStats { db_stats: self.db_reader.get_db_stats()?, tables_stats })
     }
 
+    /// Returns the serialized value stored under the given serialized key in the table with the
+    /// given name, or None if there's no such entry. Meant for debugging the storage.
+    pub fn get_raw_value(&self, table_name: &str, key: &[u8]) -> StorageResult<Option<Vec<u8>>> {
+        Ok(self.db_reader.get_raw_value(table_name, key)?)
+    }
+
     /// Returns the scope of the storage.
     pub fn get_scope(&self) -> StorageScope {
         self.scope
     }
+
+    /// Returns whether the storage stores the transaction outputs and events of the blocks.
+    pub fn stores_receipts(&self) -> bool {
+        self.store_receipts
+    }
+
+    /// Returns the node mode of the storage.
+    pub fn get_node_mode(&self) -> NodeMode {
+        self.mode
+    }
+
+    /// Returns the cache of the classes read from the storage, which the other components of the
+    /// node share for their own representations of the classes.
+    pub fn class_cache(&self) -> Arc<ClassCache> {
+        self.class_cache.clone()
+    }
 }
 
 /// A struct for starting RW transactions ([`StorageTxn`]) to the storage.
@@ -368,7 +493,11 @@ pub struct StorageWriter {
     db_writer: DbWriter,
     file_writers: FileHandlers<RW>,
     tables: Arc<Tables>,
+    class_cache: Arc<ClassCache>,
     scope: StorageScope,
+    mode: NodeMode,
+    full_mode_state_history: u64,
+    store_receipts: bool,
 }
 
 impl StorageWriter {
@@ -379,9 +508,19 @@ impl StorageWriter {
             txn: self.db_writer.begin_rw_txn()?,
             file_handlers: self.file_writers.clone(),
             tables: self.tables.clone(),
+            class_cache: self.class_cache.clone(),
             scope: self.scope,
+            mode: self.mode,
+            full_mode_state_history: self.full_mode_state_history,
+            store_receipts: self.store_receipts,
         })
     }
+
+    /// Returns the time the last write transaction waited for the write lock of the database, a
+    /// signal of the contention on the storage.
+    pub fn last_write_lock_wait(&self) -> Duration {
+        self.db_writer.last_write_lock_wait()
+    }
 }
 
 /// A struct for interacting with the storage.
@@ -390,29 +529,44 @@ pub struct StorageTxn<'env, Mode: TransactionKind> {
     txn: DbTransaction<'env, Mode>,
     file_handlers: FileHandlers<Mode>,
     tables: Arc<Tables>,
+    class_cache: Arc<ClassCache>,
     scope: StorageScope,
+    mode: NodeMode,
+    full_mode_state_history: u64,
+    store_receipts: bool,
 }
 
 impl<'env> StorageTxn<'env, RW> {
     /// Commits the changes made in the transaction to the storage.
     pub fn commit(self) -> StorageResult<()> {
-        self.file_handlers.flush();
-        Ok(self.txn.commit()?)
+        let appended_file_bytes = self.file_handlers.flush();
+        let write_stats = self.txn.commit()?;
+        update_commit_metrics(&write_stats, appended_file_bytes);
+        Ok(())
     }
 }
 
 impl<'env, Mode: TransactionKind> StorageTxn<'env, Mode> {
+    /// Returns the cache of the classes read from the storage. A cached class may be used in any
+    /// state in which the class is declared.
+    pub fn class_cache(&self) -> &ClassCache {
+        &self.class_cache
+    }
+
     pub(crate) fn open_table<K: Key + Debug, V: ValueSerde + Debug>(
         &self,
         table_id: &TableIdentifier<K, V, SimpleTable>,
     ) -> StorageResult<TableHandle<'_, K, V, SimpleTable>> {
         if self.scope == StorageScope::StateOnly {
             let unused_tables = [
+                self.tables.class_usage.name,
                 self.tables.events.name,
+                self.tables.events_by_first_key.name,
                 self.tables.transaction_hash_to_idx.name,
                 self.tables.transaction_idx_to_hash.name,
                 self.tables.transaction_outputs.name,
                 self.tables.transactions.name,
+                self.tables.transactions_by_sender.name,
             ];
             if unused_tables.contains(&table_id.name) {
                 return Err(StorageError::ScopeError {
@@ -421,6 +575,35 @@ impl<'env, Mode: TransactionKind> StorageTxn<'env, Mode> {
                 });
             }
         }
+        if self.mode == NodeMode::Light {
+            let unused_tables = [
+                self.tables.casms.name,
+                self.tables.class_usage.name,
+                self.tables.contract_storage.name,
+                self.tables.declared_classes.name,
+                self.tables.declared_classes_block.name,
+                self.tables.deferred_classes.name,
+                self.tables.deprecated_declared_classes.name,
+                self.tables.deployed_contracts.name,
+                self.tables.entry_points.name,
+                self.tables.events.name,
+                self.tables.events_by_first_key.name,
+                self.tables.nonces.name,
+                self.tables.state_diffs.name,
+                self.tables.state_diff_stats.name,
+                self.tables.transaction_hash_to_idx.name,
+                self.tables.transaction_idx_to_hash.name,
+                self.tables.transaction_outputs.name,
+                self.tables.transactions.name,
+                self.tables.transactions_by_sender.name,
+            ];
+            if unused_tables.contains(&table_id.name) {
+                return Err(StorageError::NodeModeError {
+                    table_name: table_id.name.to_owned(),
+                    node_mode: self.mode,
+                });
+            }
+        }
         Ok(self.txn.open_table(table_id)?)
     }
 }
@@ -432,24 +615,36 @@ pub fn table_names() -> &'static [&'static str] {
 
 struct_field_names! {
     struct Tables {
+        base_layer_l1_blocks: TableIdentifier<BlockNumber, VersionZeroWrapper<BaseLayerL1Block>, SimpleTable>,
         block_hash_to_number: TableIdentifier<BlockHash, NoVersionValueWrapper<BlockNumber>, SimpleTable>,
         block_signatures: TableIdentifier<BlockNumber, VersionZeroWrapper<BlockSignature>, SimpleTable>,
         casms: TableIdentifier<ClassHash, VersionZeroWrapper<LocationInFile>, SimpleTable>,
+        class_usage: TableIdentifier<(BlockNumber, ClassHash), NoVersionValueWrapper<u64>, SimpleTable>,
         contract_storage: TableIdentifier<(ContractAddress, StorageKey, BlockNumber), NoVersionValueWrapper<StarkFelt>, SimpleTable>,
         declared_classes: TableIdentifier<ClassHash, VersionZeroWrapper<LocationInFile>, SimpleTable>,
         declared_classes_block: TableIdentifier<ClassHash, NoVersionValueWrapper<BlockNumber>, SimpleTable>,
+        deferred_classes: TableIdentifier<ClassHash, NoVersionValueWrapper<BlockNumber>, SimpleTable>,
         deprecated_declared_classes: TableIdentifier<ClassHash, VersionZeroWrapper<IndexedDeprecatedContractClass>, SimpleTable>,
         deployed_contracts: TableIdentifier<(ContractAddress, BlockNumber), VersionZeroWrapper<ClassHash>, SimpleTable>,
+        entry_points: TableIdentifier<(ClassHash, EntryPointSelector), VersionZeroWrapper<IndexedEntryPoint>, SimpleTable>,
         events: TableIdentifier<(ContractAddress, EventIndex), NoVersionValueWrapper<EventContent>, SimpleTable>,
+        events_by_first_key: TableIdentifier<(StarkFelt, EventIndex), NoVersionValueWrapper<ContractAddress>, SimpleTable>,
+        header_accumulator: TableIdentifier<(u8, u64), NoVersionValueWrapper<StarkFelt>, SimpleTable>,
         headers: TableIdentifier<BlockNumber, VersionZeroWrapper<StorageBlockHeader>, SimpleTable>,
         markers: TableIdentifier<MarkerKind, VersionZeroWrapper<BlockNumber>, SimpleTable>,
+        node_identity: TableIdentifier<String, VersionZeroWrapper<NodeIdentity>, SimpleTable>,
         nonces: TableIdentifier<(ContractAddress, BlockNumber), VersionZeroWrapper<Nonce>, SimpleTable>,
         file_offsets: TableIdentifier<OffsetKind, NoVersionValueWrapper<usize>, SimpleTable>,
+        quarantined_blocks: TableIdentifier<BlockNumber, VersionZeroWrapper<QuarantinedBlock>, SimpleTable>,
+        secondary_index_rebuilds: TableIdentifier<String, VersionZeroWrapper<IndexRebuildProgress>, SimpleTable>,
+        source_reputation: TableIdentifier<String, VersionZeroWrapper<SourceReputation>, SimpleTable>,
         state_diffs: TableIdentifier<BlockNumber, VersionZeroWrapper<LocationInFile>, SimpleTable>,
+        state_diff_stats: TableIdentifier<BlockNumber, VersionZeroWrapper<StateDiffStats>, SimpleTable>,
         transaction_hash_to_idx: TableIdentifier<TransactionHash, NoVersionValueWrapper<TransactionIndex>, SimpleTable>,
         transaction_idx_to_hash: TableIdentifier<TransactionIndex, NoVersionValueWrapper<TransactionHash>, SimpleTable>,
         transaction_outputs: TableIdentifier<TransactionIndex, VersionZeroWrapper<ThinTransactionOutput>, SimpleTable>,
         transactions: TableIdentifier<TransactionIndex, VersionZeroWrapper<Transaction>, SimpleTable>,
+        transactions_by_sender: TableIdentifier<(ContractAddress, TransactionIndex), NoVersionValueWrapper<Option<Nonce>>, SimpleTable>,
 
         // Version tables
         starknet_version: TableIdentifier<BlockNumber, VersionZeroWrapper<StarknetVersion>, SimpleTable>,
@@ -468,6 +663,10 @@ macro_rules! struct_field_names {
                 static NAMES: &'static [&'static str] = &[$(stringify!($fname)),*];
                 NAMES
             }
+
+            fn schemas() -> Vec<$crate::schema::TableSchema> {
+                vec![$(<$ftype>::schema(stringify!($fname))),*]
+            }
         }
     }
 }
@@ -502,6 +701,22 @@ pub enum StorageError {
     StorageVersionInconsistency(#[from] StorageVersionError),
     #[error("The table {table_name} is unused under the {storage_scope:?} storage scope.")]
     ScopeError { table_name: String, storage_scope: StorageScope },
+    #[error("The table {table_name} is unused in {node_mode:?} mode.")]
+    NodeModeError { table_name: String, node_mode: NodeMode },
+    #[error("The state at {state_number:?} is not queryable in {node_mode:?} mode.")]
+    StateNotQueryable { state_number: StateNumber, node_mode: NodeMode },
+    #[error(
+        "The state diff of block {block_number} can't be reverted since the values it overwrote \
+         were pruned in Full mode."
+    )]
+    PrunedStateRevert { block_number: BlockNumber },
+    #[error("The state history can be pruned only in Full mode, not in {node_mode:?} mode.")]
+    StatePruningUnsupported { node_mode: NodeMode },
+    #[error(
+        "The state at {state_number:?} is not queryable because the state diff of block \
+         {quarantined_block_number} was quarantined."
+    )]
+    StateQuarantined { state_number: StateNumber, quarantined_block_number: BlockNumber },
     #[error(transparent)]
     IOError(#[from] std::io::Error),
     #[error(transparent)]
@@ -516,6 +731,13 @@ pub enum StorageError {
          {block_number}."
     )]
     BlockSignatureForNonExistingBlock { block_number: BlockNumber, block_signature: BlockSignature },
+    #[error("Attempt to write the receipts of block {block_number} whose body isn't stored.")]
+    ReceiptsForMissingBody { block_number: BlockNumber },
+    #[error(
+        "Block {block_number} has {num_of_txs} transactions but {num_of_receipts} receipts were \
+         given."
+    )]
+    WrongNumberOfReceipts { block_number: BlockNumber, num_of_txs: usize, num_of_receipts: usize },
 }
 
 /// A type alias that maps to std::result::Result<T, StorageError>.
@@ -523,23 +745,86 @@ pub type StorageResult<V> = std::result::Result<V, StorageError>;
 
 /// A struct for the configuration of the storage.
 #[allow(missing_docs)]
-#[derive(Serialize, Debug, Default, Deserialize, Clone, PartialEq, Validate)]
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq, Validate)]
 pub struct StorageConfig {
     #[validate]
     pub db_config: DbConfig,
     #[validate]
     pub mmap_file_config: MmapFileConfig,
     pub scope: StorageScope,
+    pub mode: NodeMode,
+    pub full_mode_state_history: u64,
+    pub class_cache_size: usize,
+    /// Whether the transaction outputs and events of the blocks are stored. The receipts of
+    /// blocks that were stored while it was false are synced once it's true.
+    pub store_receipts: bool,
+    /// Whether to delete the secondary index entries that point at blocks that aren't stored when
+    /// the storage is opened. Requires a full pass over the indexes.
+    pub repair_indexes_on_open: bool,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            db_config: DbConfig::default(),
+            mmap_file_config: MmapFileConfig::default(),
+            scope: StorageScope::default(),
+            mode: NodeMode::default(),
+            full_mode_state_history: 128,
+            class_cache_size: 400,
+            store_receipts: true,
+            repair_indexes_on_open: false,
+        }
+    }
 }
 
 impl SerializeConfig for StorageConfig {
     fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
-        let mut dumped_config = BTreeMap::from_iter([ser_param(
-            "scope",
-            &self.scope,
-            "The categories of data saved in storage.",
-            ParamPrivacyInput::Public,
-        )]);
+        let mut dumped_config = BTreeMap::from_iter([
+            ser_param(
+                "scope",
+                &self.scope,
+                "The categories of data saved in storage.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "mode",
+                &self.mode,
+                "The node mode: Archive keeps the state of every block queryable, Full keeps only \
+                 the state of the recent blocks queryable and Light stores only the headers.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "full_mode_state_history",
+                &self.full_mode_state_history,
+                "The number of recent blocks whose state is queryable in Full mode. The values \
+                 that only older states need are pruned.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "class_cache_size",
+                &self.class_cache_size,
+                "The number of classes that the storage, the sync and the execution keep in \
+                 memory together, counting each representation of a class separately. 0 disables \
+                 the cache.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "store_receipts",
+                &self.store_receipts,
+                "Whether to store the transaction outputs and events of the blocks. The receipts \
+                 of blocks that were stored without them are synced once it's true.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "repair_indexes_on_open",
+                &self.repair_indexes_on_open,
+                "Whether to delete the entries of the secondary indexes that point at blocks that \
+                 aren't stored, e.g. after a crash, when the storage is opened. Requires a full \
+                 pass over the indexes, which slows down the start of the node.",
+                ParamPrivacyInput::Public,
+            ),
+        ]);
         dumped_config
             .extend(append_sub_config_name(self.mmap_file_config.dump(), "mmap_file_config"));
         dumped_config.extend(append_sub_config_name(self.db_config.dump(), "db_config"));
@@ -561,18 +846,27 @@ pub struct DbStats {
 // Invariants:
 // - CompiledClass <= State <= Header
 // - Body <= Header
+// - Receipts <= Body
 // - BaseLayerBlock <= Header
+// - ClassUsage <= Receipts, ClassUsage <= State
+// - PrunedState <= State, where PrunedState is the first block whose state diff wasn't pruned
 pub(crate) enum MarkerKind {
     Header,
     Body,
     State,
     CompiledClass,
     BaseLayerBlock,
+    Receipts,
+    ClassUsage,
+    PrunedState,
 }
 
 pub(crate) type MarkersTable<'env> =
     TableHandle<'env, MarkerKind, VersionZeroWrapper<BlockNumber>, SimpleTable>;
 
+pub(crate) type HeaderAccumulatorTable<'env> =
+    TableHandle<'env, (u8, u64), NoVersionValueWrapper<StarkFelt>, SimpleTable>;
+
 #[derive(Clone, Debug)]
 struct FileHandlers<Mode: TransactionKind> {
     thin_state_diff: FileHandler<VersionZeroWrapper<ThinStateDiff>, Mode>,
@@ -605,12 +899,14 @@ impl FileHandlers<RW> {
         self.clone().deprecated_contract_class.append(deprecated_contract_class)
     }
 
+    // Flushes the files and returns the number of bytes that were appended to them since the
+    // previous flush.
     // TODO(dan): Consider 1. flushing only the relevant files, 2. flushing concurrently.
-    fn flush(&self) {
-        self.thin_state_diff.flush();
-        self.contract_class.flush();
-        self.casm.flush();
-        self.deprecated_contract_class.flush();
+    fn flush(&self) -> usize {
+        self.thin_state_diff.flush()
+            + self.contract_class.flush()
+            + self.casm.flush()
+            + self.deprecated_contract_class.flush()
     }
 }
 
@@ -122,6 +122,8 @@ fn concurrent_reads_single_write() {
     }
 }
 
+// On Windows the file takes its maximal size when it's mapped, see [`open_file`].
+#[cfg(not(windows))]
 #[test]
 fn grow_file() {
     let data = vec![1, 2];
@@ -183,6 +185,23 @@ fn grow_file() {
     dir.close().unwrap();
 }
 
+#[cfg(windows)]
+#[test]
+fn file_has_max_size_on_windows() {
+    let dir = tempdir().unwrap();
+    let config = get_mmap_file_test_config();
+    let file_path = dir.path().to_path_buf().join("test_file_has_max_size");
+    let (mut writer, reader) =
+        open_file::<NoVersionValueWrapper<Vec<u8>>>(config.clone(), file_path.clone(), 0).unwrap();
+    assert_eq!(std::fs::metadata(&file_path).unwrap().len(), config.max_size as u64);
+
+    let data = vec![1, 2];
+    let location = writer.append(&data);
+    writer.flush();
+    assert_eq!(reader.get(location).unwrap().unwrap(), data);
+    assert_eq!(std::fs::metadata(&file_path).unwrap().len(), config.max_size as u64);
+}
+
 #[tokio::test]
 async fn write_read_different_locations() {
     let dir = tempdir().unwrap();
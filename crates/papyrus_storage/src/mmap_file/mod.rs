@@ -24,7 +24,7 @@ use serde::{Deserialize, Serialize};
 #[cfg(test)]
 use test_utils::GetTestInstance;
 use thiserror::Error;
-use tracing::{debug, instrument, trace};
+use tracing::{debug, instrument, trace, trace_span};
 use validator::{Validate, ValidationError};
 
 use crate::db::serialization::{StorageSerde, ValueSerde};
@@ -107,8 +107,9 @@ pub(crate) trait Writer<V: ValueSerde> {
     /// Inserts an object to the file, returns the [`LocationInFile`] of the object.
     fn append(&mut self, val: &V::Value) -> LocationInFile;
 
-    /// Flushes the mmap to the file.
-    fn flush(&self);
+    /// Flushes the mmap to the file and returns the number of bytes that were appended since the
+    /// previous flush.
+    fn flush(&self) -> usize;
 }
 
 /// A trait for reading from a memory mapped file.
@@ -141,6 +142,8 @@ struct MMapFile<V: ValueSerde> {
     size: usize,
     mmap: MmapMut,
     offset: usize,
+    // The offset at the previous flush of the writer.
+    flushed_offset: usize,
     should_flush: bool,
     _value_type: PhantomData<V>,
 }
@@ -171,8 +174,11 @@ pub(crate) fn open_file<V: ValueSerde>(
     offset: usize,
 ) -> MmapFileResult<(FileHandler<V, RW>, FileHandler<V, RO>)> {
     let file = OpenOptions::new().read(true).write(true).create(true).open(path)?;
-    let size = file.metadata()?.len();
     let mmap = unsafe { MmapOptions::new().len(config.max_size).map_mut(&file)? };
+    // Read after mapping: on Windows, mapping a file extends it to the size of the mapping and the
+    // size of a mapped file can't be changed, so there the file has its maximal size from the start
+    // and never grows.
+    let size = file.metadata()?.len();
     let mmap_ptr = mmap.as_ptr();
     let mmap_file = MMapFile {
         config,
@@ -180,6 +186,7 @@ pub(crate) fn open_file<V: ValueSerde>(
         mmap,
         size: size.try_into().expect("size should fit in usize"),
         offset,
+        flushed_offset: offset,
         should_flush: false,
         _value_type: PhantomData {},
     };
@@ -246,17 +253,21 @@ impl<V: ValueSerde + Debug> Writer<V> for FileHandler<V, RW> {
         location
     }
 
-    fn flush(&self) {
+    fn flush(&self) -> usize {
         let mut mmap_file = self.mmap_file.lock().expect("Lock should not be poisoned");
         if mmap_file.should_flush {
             mmap_file.flush();
         }
+        let appended_bytes = mmap_file.offset - mmap_file.flushed_offset;
+        mmap_file.flushed_offset = mmap_file.offset;
+        appended_bytes
     }
 }
 
 impl<V: ValueSerde, Mode: TransactionKind> Reader<V> for FileHandler<V, Mode> {
     /// Returns an object from the file.
     fn get(&self, location: LocationInFile) -> MmapFileResult<Option<V::Value>> {
+        let _span = trace_span!("mmap_get", bytes = location.len).entered();
         debug!("Reading object at location: {:?}", location);
         let mut bytes = unsafe {
             std::slice::from_raw_parts(
@@ -0,0 +1,85 @@
+//! Interface for handling the identity of the node that owns the storage.
+//!
+//! The identity holds an id that is generated at the first boot of the node and stays the same
+//! across restarts, and the history of the runs of the node. Fleet operators use them to
+//! correlate the metrics and logs of an instance, and to tell which binary and configuration it
+//! ran with.
+//!
+//! Import [`NodeIdentityStorageReader`] and [`NodeIdentityStorageWriter`] to read and write the
+//! identity using a [`StorageTxn`].
+#[cfg(test)]
+#[path = "node_identity_test.rs"]
+mod node_identity_test;
+
+use serde::{Deserialize, Serialize};
+
+use crate::db::table_types::Table;
+use crate::db::{TransactionKind, RW};
+use crate::{StorageResult, StorageTxn};
+
+/// The maximal number of runs kept in the history of the node. Older runs are dropped.
+pub const MAX_NODE_RUNS: usize = 100;
+
+// The key of the single entry of the node identity table.
+const NODE_IDENTITY_KEY: &str = "node_identity";
+
+/// The identity of the node.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct NodeIdentity {
+    /// The id generated at the first boot of the node.
+    pub node_id: String,
+    /// The last runs of the node, from the oldest.
+    pub runs: Vec<NodeRun>,
+}
+
+/// A single run of the node.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct NodeRun {
+    /// The unix timestamp, in seconds, at which the run started.
+    pub start_timestamp: u64,
+    /// The version of the node.
+    pub version: String,
+    /// The commit the node was built from.
+    pub build_commit: String,
+    /// The hash of the public parameters of the node config.
+    pub config_hash: String,
+}
+
+/// Interface for reading the identity of the node.
+pub trait NodeIdentityStorageReader {
+    /// Returns the identity of the node, or None if no run was recorded yet.
+    fn get_node_identity(&self) -> StorageResult<Option<NodeIdentity>>;
+}
+
+/// Interface for writing the identity of the node.
+pub trait NodeIdentityStorageWriter
+where
+    Self: Sized,
+{
+    /// Appends a run to the history of the node. `new_node_id` becomes the id of the node if no
+    /// run was recorded yet, and is ignored otherwise.
+    // To enforce that no commit happen after a failure, we consume and return Self on success.
+    fn record_node_run(self, new_node_id: &str, run: NodeRun) -> StorageResult<Self>;
+}
+
+impl<'env, Mode: TransactionKind> NodeIdentityStorageReader for StorageTxn<'env, Mode> {
+    fn get_node_identity(&self) -> StorageResult<Option<NodeIdentity>> {
+        let node_identity_table = self.open_table(&self.tables.node_identity)?;
+        Ok(node_identity_table.get(&self.txn, &NODE_IDENTITY_KEY.to_owned())?)
+    }
+}
+
+impl<'env> NodeIdentityStorageWriter for StorageTxn<'env, RW> {
+    fn record_node_run(self, new_node_id: &str, run: NodeRun) -> StorageResult<Self> {
+        let mut identity = self
+            .get_node_identity()?
+            .unwrap_or_else(|| NodeIdentity { node_id: new_node_id.to_owned(), runs: Vec::new() });
+        identity.runs.push(run);
+        let excess_runs = identity.runs.len().saturating_sub(MAX_NODE_RUNS);
+        identity.runs.drain(..excess_runs);
+
+        let node_identity_table = self.open_table(&self.tables.node_identity)?;
+        node_identity_table.upsert(&self.txn, &NODE_IDENTITY_KEY.to_owned(), &identity)?;
+        Ok(self)
+    }
+}
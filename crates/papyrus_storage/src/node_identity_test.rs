@@ -0,0 +1,53 @@
+use pretty_assertions::assert_eq;
+
+use crate::node_identity::{
+    NodeIdentity,
+    NodeIdentityStorageReader,
+    NodeIdentityStorageWriter,
+    NodeRun,
+    MAX_NODE_RUNS,
+};
+use crate::test_utils::get_test_storage;
+
+fn run(start_timestamp: u64) -> NodeRun {
+    NodeRun {
+        start_timestamp,
+        version: "0.3.0-dev".to_owned(),
+        build_commit: "abcdef".to_owned(),
+        config_hash: "0x1".to_owned(),
+    }
+}
+
+#[test]
+fn node_id_is_kept_across_runs() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    assert_eq!(reader.begin_ro_txn().unwrap().get_node_identity().unwrap(), None);
+
+    writer.begin_rw_txn().unwrap().record_node_run("first_id", run(1)).unwrap().commit().unwrap();
+    writer.begin_rw_txn().unwrap().record_node_run("second_id", run(2)).unwrap().commit().unwrap();
+
+    assert_eq!(
+        reader.begin_ro_txn().unwrap().get_node_identity().unwrap(),
+        Some(NodeIdentity { node_id: "first_id".to_owned(), runs: vec![run(1), run(2)] })
+    );
+}
+
+#[test]
+fn oldest_runs_are_dropped() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let n_runs = MAX_NODE_RUNS as u64 + 2;
+    for start_timestamp in 0..n_runs {
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .record_node_run("node_id", run(start_timestamp))
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+
+    let identity = reader.begin_ro_txn().unwrap().get_node_identity().unwrap().unwrap();
+    assert_eq!(identity.runs.len(), MAX_NODE_RUNS);
+    assert_eq!(identity.runs.first(), Some(&run(2)));
+    assert_eq!(identity.runs.last(), Some(&run(n_runs - 1)));
+}
@@ -0,0 +1,148 @@
+//! Interface for handling the blocks that the sync quarantined.
+//!
+//! When the sync is configured to quarantine blocks it can't validate, instead of halting at them,
+//! it stores an empty body or state diff for such a block and records the error here, so that the
+//! quarantined blocks can be found. The state at and after a block whose state diff was quarantined
+//! is wrong, so it isn't queryable (see [`verify_state_queryable`]). The record is deleted when the
+//! header of the block is reverted.
+//!
+//! [`verify_state_queryable`]: crate::state::StateStorageReader::verify_state_queryable
+//!
+//! Import [`QuarantineStorageReader`] and [`QuarantineStorageWriter`] to read and write the
+//! quarantined blocks using a [`StorageTxn`].
+//! # Example
+//! ```
+//! # use papyrus_storage::{db::DbConfig, StorageConfig};
+//! # use starknet_api::core::ChainId;
+//! use papyrus_storage::open_storage;
+//! use papyrus_storage::quarantine::{QuarantineStorageReader, QuarantineStorageWriter};
+//! use starknet_api::block::BlockNumber;
+//!
+//! # let dir_handle = tempfile::tempdir().unwrap();
+//! # let dir = dir_handle.path().to_path_buf();
+//! # let db_config = DbConfig {
+//! #     path_prefix: dir,
+//! #     chain_id: ChainId("SN_MAIN".to_owned()),
+//! #     enforce_file_exists: false,
+//! #     min_size: 1 << 20,    // 1MB
+//! #     max_size: 1 << 35,    // 32GB
+//! #     growth_step: 1 << 26, // 64MB
+//! # };
+//! # let storage_config = StorageConfig{db_config, ..Default::default()};
+//! let (reader, mut writer) = open_storage(storage_config)?;
+//! writer
+//!     .begin_rw_txn()?
+//!     .quarantine_body(BlockNumber(7), "Unknown transaction type.")?
+//!     .commit()?;
+//! let quarantined_block = reader.begin_ro_txn()?.get_quarantined_block(BlockNumber(7))?.unwrap();
+//! assert_eq!(quarantined_block.body_error.as_deref(), Some("Unknown transaction type."));
+//! assert_eq!(quarantined_block.state_diff_error, None);
+//! # Ok::<(), papyrus_storage::StorageError>(())
+//! ```
+#[cfg(test)]
+#[path = "quarantine_test.rs"]
+mod quarantine_test;
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use starknet_api::block::BlockNumber;
+
+use crate::db::table_types::{DbCursorTrait, Table};
+use crate::db::{TransactionKind, RW};
+use crate::{StorageResult, StorageTxn};
+
+/// The parts of a block that couldn't be parsed or validated, and were stored empty instead.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct QuarantinedBlock {
+    /// The error because of which the body of the block was quarantined, if it was.
+    pub body_error: Option<String>,
+    /// The error because of which the state diff of the block was quarantined, if it was.
+    pub state_diff_error: Option<String>,
+}
+
+/// Interface for reading the quarantined blocks.
+pub trait QuarantineStorageReader {
+    /// Returns what was quarantined of the given block, or None if nothing was.
+    fn get_quarantined_block(
+        &self,
+        block_number: BlockNumber,
+    ) -> StorageResult<Option<QuarantinedBlock>>;
+
+    /// Returns all the quarantined blocks, keyed by their number.
+    fn get_quarantined_blocks(&self) -> StorageResult<BTreeMap<BlockNumber, QuarantinedBlock>>;
+
+    /// Returns the first block whose state diff was quarantined, if there's one.
+    fn get_first_quarantined_state_diff(&self) -> StorageResult<Option<BlockNumber>>;
+}
+
+/// Interface for recording quarantined blocks.
+pub trait QuarantineStorageWriter
+where
+    Self: Sized,
+{
+    /// Records that the body of the given block was quarantined because of the given error.
+    // To enforce that no commit happen after a failure, we consume and return Self on success.
+    fn quarantine_body(self, block_number: BlockNumber, error: &str) -> StorageResult<Self>;
+
+    /// Records that the state diff of the given block was quarantined because of the given error.
+    fn quarantine_state_diff(self, block_number: BlockNumber, error: &str) -> StorageResult<Self>;
+
+    /// Deletes the record of the given block, if exists.
+    fn delete_quarantined_block(self, block_number: BlockNumber) -> StorageResult<Self>;
+}
+
+impl<'env, Mode: TransactionKind> QuarantineStorageReader for StorageTxn<'env, Mode> {
+    fn get_quarantined_block(
+        &self,
+        block_number: BlockNumber,
+    ) -> StorageResult<Option<QuarantinedBlock>> {
+        let quarantined_blocks_table = self.open_table(&self.tables.quarantined_blocks)?;
+        Ok(quarantined_blocks_table.get(&self.txn, &block_number)?)
+    }
+
+    fn get_quarantined_blocks(&self) -> StorageResult<BTreeMap<BlockNumber, QuarantinedBlock>> {
+        let quarantined_blocks_table = self.open_table(&self.tables.quarantined_blocks)?;
+        let mut cursor = quarantined_blocks_table.cursor(&self.txn)?;
+        let mut res = BTreeMap::new();
+        while let Some((block_number, quarantined_block)) = cursor.next()? {
+            res.insert(block_number, quarantined_block);
+        }
+        Ok(res)
+    }
+
+    fn get_first_quarantined_state_diff(&self) -> StorageResult<Option<BlockNumber>> {
+        let quarantined_blocks_table = self.open_table(&self.tables.quarantined_blocks)?;
+        let mut cursor = quarantined_blocks_table.cursor(&self.txn)?;
+        while let Some((block_number, quarantined_block)) = cursor.next()? {
+            if quarantined_block.state_diff_error.is_some() {
+                return Ok(Some(block_number));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<'env> QuarantineStorageWriter for StorageTxn<'env, RW> {
+    fn quarantine_body(self, block_number: BlockNumber, error: &str) -> StorageResult<Self> {
+        let mut quarantined_block = self.get_quarantined_block(block_number)?.unwrap_or_default();
+        quarantined_block.body_error = Some(error.to_owned());
+        let quarantined_blocks_table = self.open_table(&self.tables.quarantined_blocks)?;
+        quarantined_blocks_table.upsert(&self.txn, &block_number, &quarantined_block)?;
+        Ok(self)
+    }
+
+    fn quarantine_state_diff(self, block_number: BlockNumber, error: &str) -> StorageResult<Self> {
+        let mut quarantined_block = self.get_quarantined_block(block_number)?.unwrap_or_default();
+        quarantined_block.state_diff_error = Some(error.to_owned());
+        let quarantined_blocks_table = self.open_table(&self.tables.quarantined_blocks)?;
+        quarantined_blocks_table.upsert(&self.txn, &block_number, &quarantined_block)?;
+        Ok(self)
+    }
+
+    fn delete_quarantined_block(self, block_number: BlockNumber) -> StorageResult<Self> {
+        let quarantined_blocks_table = self.open_table(&self.tables.quarantined_blocks)?;
+        quarantined_blocks_table.delete(&self.txn, &block_number)?;
+        Ok(self)
+    }
+}
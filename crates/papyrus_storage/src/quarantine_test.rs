@@ -0,0 +1,109 @@
+use std::collections::BTreeMap;
+
+use assert_matches::assert_matches;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockHeader, BlockNumber};
+use starknet_api::state::StateNumber;
+
+use crate::header::HeaderStorageWriter;
+use crate::quarantine::{QuarantineStorageReader, QuarantineStorageWriter, QuarantinedBlock};
+use crate::state::StateStorageReader;
+use crate::test_utils::get_test_storage;
+use crate::StorageError;
+
+const BODY_ERROR: &str = "Invalid transaction.";
+const STATE_DIFF_ERROR: &str = "Invalid state diff.";
+
+#[test]
+fn quarantine_body_and_state_diff() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    assert_eq!(reader.begin_ro_txn().unwrap().get_quarantined_block(BlockNumber(0)).unwrap(), None);
+
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .quarantine_body(BlockNumber(0), BODY_ERROR)
+        .unwrap()
+        .quarantine_state_diff(BlockNumber(2), STATE_DIFF_ERROR)
+        .unwrap()
+        .commit()
+        .unwrap();
+    // Both parts of a block may be quarantined.
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .quarantine_state_diff(BlockNumber(0), STATE_DIFF_ERROR)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(
+        txn.get_quarantined_blocks().unwrap(),
+        BTreeMap::from([
+            (
+                BlockNumber(0),
+                QuarantinedBlock {
+                    body_error: Some(BODY_ERROR.to_owned()),
+                    state_diff_error: Some(STATE_DIFF_ERROR.to_owned()),
+                }
+            ),
+            (
+                BlockNumber(2),
+                QuarantinedBlock {
+                    body_error: None,
+                    state_diff_error: Some(STATE_DIFF_ERROR.to_owned()),
+                }
+            ),
+        ])
+    );
+
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .delete_quarantined_block(BlockNumber(2))
+        .unwrap()
+        .commit()
+        .unwrap();
+    assert_eq!(reader.begin_ro_txn().unwrap().get_quarantined_block(BlockNumber(2)).unwrap(), None);
+}
+
+#[test]
+fn revert_header_deletes_the_quarantined_block() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &BlockHeader::default())
+        .unwrap()
+        .quarantine_body(BlockNumber(0), BODY_ERROR)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    writer.begin_rw_txn().unwrap().revert_header(BlockNumber(0)).unwrap().0.commit().unwrap();
+    assert!(reader.begin_ro_txn().unwrap().get_quarantined_blocks().unwrap().is_empty());
+}
+
+#[test]
+fn state_after_quarantined_state_diff_is_not_queryable() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .quarantine_body(BlockNumber(0), BODY_ERROR)
+        .unwrap()
+        .quarantine_state_diff(BlockNumber(2), STATE_DIFF_ERROR)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_first_quarantined_state_diff().unwrap(), Some(BlockNumber(2)));
+    // A quarantined body doesn't affect the state.
+    txn.verify_state_queryable(StateNumber::right_after_block(BlockNumber(1))).unwrap();
+    assert_matches!(
+        txn.verify_state_queryable(StateNumber::right_after_block(BlockNumber(2))),
+        Err(StorageError::StateQuarantined { quarantined_block_number: BlockNumber(2), .. })
+    );
+}
@@ -0,0 +1,62 @@
+//! A machine-readable description of the on-disk layout of the storage: its versions and, for
+//! every table, the types of its keys and values.
+//!
+//! Tools that read the database directly should compare the schema of the node they read with the
+//! one they were written for, so that a change of the layout fails them loudly instead of making
+//! them read garbage.
+//! # Example
+//! ```
+//! use papyrus_storage::schema::storage_schema;
+//! use papyrus_storage::STORAGE_VERSION_STATE;
+//!
+//! let schema = storage_schema();
+//! assert_eq!(schema.state_version, STORAGE_VERSION_STATE.0);
+//! let headers = schema.tables.iter().find(|table| table.name == "headers").unwrap();
+//! assert_eq!(headers.key, "starknet_api::block::BlockNumber");
+//! ```
+#[cfg(test)]
+#[path = "schema_test.rs"]
+mod schema_test;
+
+use serde::Serialize;
+
+use crate::{Tables, STORAGE_VERSION_BLOCKS, STORAGE_VERSION_STATE};
+
+/// The on-disk layout of the storage.
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct StorageSchema {
+    /// The version of the state part of the layout. See [`STORAGE_VERSION_STATE`].
+    pub state_version: u32,
+    /// The version of the blocks part of the layout. See [`STORAGE_VERSION_BLOCKS`].
+    pub blocks_version: u32,
+    /// The tables of the storage, ordered by name.
+    pub tables: Vec<TableSchema>,
+}
+
+/// The on-disk layout of a table.
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct TableSchema {
+    /// The name of the table in the database.
+    pub name: &'static str,
+    /// The type of the keys. A key is stored in its storage serialization, whose byte order is the
+    /// order of the keys.
+    pub key: &'static str,
+    /// The type of the values. A value is stored in its storage serialization, after the version
+    /// byte if the values are versioned.
+    pub value: &'static str,
+    /// The byte that prefixes every serialized value, if the values are versioned.
+    pub value_version: Option<u8>,
+    /// The type of the table, which determines how the entries are laid out.
+    pub table_type: &'static str,
+}
+
+/// Returns the on-disk layout of the storage that this version of the crate reads and writes.
+pub fn storage_schema() -> StorageSchema {
+    let mut tables = Tables::schemas();
+    tables.sort_by_key(|table| table.name);
+    StorageSchema {
+        state_version: STORAGE_VERSION_STATE.0,
+        blocks_version: STORAGE_VERSION_BLOCKS.0,
+        tables,
+    }
+}
@@ -0,0 +1,27 @@
+use pretty_assertions::assert_eq;
+
+use crate::schema::{storage_schema, TableSchema};
+use crate::table_names;
+
+#[test]
+fn schema_describes_every_table() {
+    let schema = storage_schema();
+    let mut expected_names = table_names().to_vec();
+    expected_names.sort();
+    let names: Vec<_> = schema.tables.iter().map(|table| table.name).collect();
+    assert_eq!(names, expected_names);
+
+    let markers = schema.tables.iter().find(|table| table.name == "markers").unwrap();
+    assert_eq!(
+        *markers,
+        TableSchema {
+            name: "markers",
+            key: "papyrus_storage::MarkerKind",
+            value: "starknet_api::block::BlockNumber",
+            value_version: Some(0),
+            table_type: "papyrus_storage::db::table_types::simple_table::SimpleTable",
+        }
+    );
+    let file_offsets = schema.tables.iter().find(|table| table.name == "file_offsets").unwrap();
+    assert_eq!(file_offsets.value_version, None);
+}
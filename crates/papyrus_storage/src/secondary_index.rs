@@ -0,0 +1,620 @@
+//! Secondary indexes of the storage.
+//!
+//! A secondary index is a table whose entries are derived from the entries of another table, its
+//! source, for looking the data of the source up by something other than its key. An index is
+//! declared once, with the way its entries are derived from an entry of the source. The writers of
+//! the source table maintain the index through this declaration, and it can be rebuilt from its
+//! source at any time with [`StorageWriter::rebuild_secondary_index`], so an index that was added
+//! or fixed doesn't require syncing again. An index is marked as being rebuilt until its rebuild
+//! finishes, and a rebuild that was interrupted is resumed when the storage is opened.
+//!
+//! Every entry of an index points at the block of the source entry it's derived from. Entries that
+//! point at blocks past the marker of their source would serve data of blocks that aren't stored,
+//! and are deleted by [`StorageWriter::repair_secondary_indexes`].
+
+#[cfg(test)]
+#[path = "secondary_index_test.rs"]
+mod secondary_index_test;
+
+use std::fmt::Debug;
+
+use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::core::{ContractAddress, Nonce};
+use starknet_api::hash::StarkFelt;
+use starknet_api::transaction::{
+    DeclareTransaction,
+    EventContent,
+    InvokeTransaction,
+    Transaction,
+    TransactionHash,
+};
+use tracing::info;
+
+use crate::body::events::{EventIndex, ThinTransactionOutput};
+use crate::body::TransactionIndex;
+use crate::db::serialization::{
+    Key,
+    NoVersionValueWrapper,
+    StorageSerdeEx,
+    ValueSerde,
+    VersionZeroWrapper,
+};
+use crate::db::table_types::{DbCursorTrait, SimpleTable, Table};
+use crate::db::{DbTransaction, TableHandle, TableIdentifier, RW};
+use crate::header::StorageBlockHeader;
+use crate::{
+    MarkerKind,
+    NodeMode,
+    StorageError,
+    StorageResult,
+    StorageScope,
+    StorageWriter,
+    Tables,
+};
+
+// The number of source entries that are indexed in a single transaction while rebuilding an index.
+const REBUILD_BATCH_SIZE: usize = 10000;
+
+/// The secondary indexes of the storage.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SecondaryIndexKind {
+    /// The block numbers by the block hashes.
+    BlockHash,
+    /// The events by their first key.
+    EventsByFirstKey,
+    /// The transaction indices by the transaction hashes.
+    TransactionHash,
+    /// The transactions by their sender.
+    TransactionsBySender,
+}
+
+impl SecondaryIndexKind {
+    /// All the secondary indexes of the storage.
+    pub const ALL: [SecondaryIndexKind; 4] = [
+        SecondaryIndexKind::BlockHash,
+        SecondaryIndexKind::EventsByFirstKey,
+        SecondaryIndexKind::TransactionHash,
+        SecondaryIndexKind::TransactionsBySender,
+    ];
+
+    /// The name of the table of the index.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SecondaryIndexKind::BlockHash => BlockHashIndex::NAME,
+            SecondaryIndexKind::EventsByFirstKey => EventsByFirstKeyIndex::NAME,
+            SecondaryIndexKind::TransactionHash => TransactionHashIndex::NAME,
+            SecondaryIndexKind::TransactionsBySender => TransactionsBySenderIndex::NAME,
+        }
+    }
+}
+
+/// The entries of a secondary index that were deleted because they pointed at blocks that aren't
+/// stored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IndexRepair {
+    /// The repaired index.
+    pub index: SecondaryIndexKind,
+    /// The number of entries that were deleted from the index.
+    pub deleted_entries: usize,
+}
+
+/// The progress of a rebuild of a secondary index, stored until the rebuild finishes.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct IndexRebuildProgress {
+    /// The position, among the sources of the index, of the source that is being indexed.
+    pub source: u8,
+    /// The serialized key of the last indexed entry of the source, None if none was indexed yet.
+    pub last_indexed_key: Option<Vec<u8>>,
+}
+
+type SourceValue<I> = <<I as SecondaryIndex>::SourceValue as ValueSerde>::Value;
+type IndexValue<I> = <<I as SecondaryIndex>::IndexValue as ValueSerde>::Value;
+type IndexTable<'env, I> = TableHandle<
+    'env,
+    <I as SecondaryIndex>::IndexKey,
+    <I as SecondaryIndex>::IndexValue,
+    SimpleTable,
+>;
+
+/// A table whose entries are derived from the entries of a source table.
+pub(crate) trait SecondaryIndex {
+    /// The name of the table of the index.
+    const NAME: &'static str;
+    /// The marker of the blocks whose source entries are stored.
+    const SOURCE_MARKER: MarkerKind;
+    type SourceKey: Key + Debug + Clone;
+    type SourceValue: ValueSerde + Debug;
+    type IndexKey: Key + Debug;
+    type IndexValue: ValueSerde + Debug;
+
+    fn source_table(
+        tables: &Tables,
+    ) -> &TableIdentifier<Self::SourceKey, Self::SourceValue, SimpleTable>;
+
+    fn index_table(
+        tables: &Tables,
+    ) -> &TableIdentifier<Self::IndexKey, Self::IndexValue, SimpleTable>;
+
+    /// The entries of the index that are derived from the given entry of the source table.
+    fn index_entries(
+        source_key: &Self::SourceKey,
+        source_value: &SourceValue<Self>,
+    ) -> Vec<(Self::IndexKey, IndexValue<Self>)>;
+
+    /// The block of the source entry that the given entry of the index is derived from.
+    fn indexed_block(index_key: &Self::IndexKey, index_value: &IndexValue<Self>) -> BlockNumber;
+
+    /// Adds the entries of the index that are derived from a new entry of the source table.
+    fn insert_entries<'env>(
+        txn: &DbTransaction<'env, RW>,
+        index_table: &'env IndexTable<'env, Self>,
+        source_key: &Self::SourceKey,
+        source_value: &SourceValue<Self>,
+    ) -> StorageResult<()> {
+        for (key, value) in Self::index_entries(source_key, source_value) {
+            index_table.insert(txn, &key, &value)?;
+        }
+        Ok(())
+    }
+
+    /// Deletes the entries of the index that are derived from a deleted entry of the source table.
+    fn delete_entries<'env>(
+        txn: &DbTransaction<'env, RW>,
+        index_table: &'env IndexTable<'env, Self>,
+        source_key: &Self::SourceKey,
+        source_value: &SourceValue<Self>,
+    ) -> StorageResult<()> {
+        for (key, _value) in Self::index_entries(source_key, source_value) {
+            index_table.delete(txn, &key)?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) struct BlockHashIndex;
+
+impl SecondaryIndex for BlockHashIndex {
+    const NAME: &'static str = "block_hash_to_number";
+    const SOURCE_MARKER: MarkerKind = MarkerKind::Header;
+    type SourceKey = BlockNumber;
+    type SourceValue = VersionZeroWrapper<StorageBlockHeader>;
+    type IndexKey = BlockHash;
+    type IndexValue = NoVersionValueWrapper<BlockNumber>;
+
+    fn source_table(
+        tables: &Tables,
+    ) -> &TableIdentifier<BlockNumber, Self::SourceValue, SimpleTable> {
+        &tables.headers
+    }
+
+    fn index_table(tables: &Tables) -> &TableIdentifier<BlockHash, Self::IndexValue, SimpleTable> {
+        &tables.block_hash_to_number
+    }
+
+    fn index_entries(
+        block_number: &BlockNumber,
+        header: &StorageBlockHeader,
+    ) -> Vec<(BlockHash, BlockNumber)> {
+        vec![(header.block_hash, *block_number)]
+    }
+
+    fn indexed_block(_block_hash: &BlockHash, block_number: &BlockNumber) -> BlockNumber {
+        *block_number
+    }
+}
+
+pub(crate) struct EventsByFirstKeyIndex;
+
+impl SecondaryIndex for EventsByFirstKeyIndex {
+    const NAME: &'static str = "events_by_first_key";
+    const SOURCE_MARKER: MarkerKind = MarkerKind::Receipts;
+    type SourceKey = (ContractAddress, EventIndex);
+    type SourceValue = NoVersionValueWrapper<EventContent>;
+    type IndexKey = (StarkFelt, EventIndex);
+    type IndexValue = NoVersionValueWrapper<ContractAddress>;
+
+    fn source_table(
+        tables: &Tables,
+    ) -> &TableIdentifier<(ContractAddress, EventIndex), Self::SourceValue, SimpleTable> {
+        &tables.events
+    }
+
+    fn index_table(
+        tables: &Tables,
+    ) -> &TableIdentifier<(StarkFelt, EventIndex), Self::IndexValue, SimpleTable> {
+        &tables.events_by_first_key
+    }
+
+    // Events without keys aren't indexed.
+    fn index_entries(
+        (from_address, event_index): &(ContractAddress, EventIndex),
+        content: &EventContent,
+    ) -> Vec<((StarkFelt, EventIndex), ContractAddress)> {
+        content
+            .keys
+            .first()
+            .map(|first_key| ((first_key.0, *event_index), *from_address))
+            .into_iter()
+            .collect()
+    }
+
+    fn indexed_block(
+        (_first_key, EventIndex(TransactionIndex(block_number, _), _)): &(StarkFelt, EventIndex),
+        _from_address: &ContractAddress,
+    ) -> BlockNumber {
+        *block_number
+    }
+}
+
+pub(crate) struct TransactionHashIndex;
+
+impl SecondaryIndex for TransactionHashIndex {
+    const NAME: &'static str = "transaction_hash_to_idx";
+    const SOURCE_MARKER: MarkerKind = MarkerKind::Body;
+    type SourceKey = TransactionIndex;
+    type SourceValue = NoVersionValueWrapper<TransactionHash>;
+    type IndexKey = TransactionHash;
+    type IndexValue = NoVersionValueWrapper<TransactionIndex>;
+
+    fn source_table(
+        tables: &Tables,
+    ) -> &TableIdentifier<TransactionIndex, Self::SourceValue, SimpleTable> {
+        &tables.transaction_idx_to_hash
+    }
+
+    fn index_table(
+        tables: &Tables,
+    ) -> &TableIdentifier<TransactionHash, Self::IndexValue, SimpleTable> {
+        &tables.transaction_hash_to_idx
+    }
+
+    fn index_entries(
+        transaction_index: &TransactionIndex,
+        transaction_hash: &TransactionHash,
+    ) -> Vec<(TransactionHash, TransactionIndex)> {
+        vec![(*transaction_hash, *transaction_index)]
+    }
+
+    fn indexed_block(
+        _transaction_hash: &TransactionHash,
+        transaction_index: &TransactionIndex,
+    ) -> BlockNumber {
+        transaction_index.0
+    }
+}
+
+pub(crate) struct TransactionsBySenderIndex;
+
+impl SecondaryIndex for TransactionsBySenderIndex {
+    const NAME: &'static str = "transactions_by_sender";
+    const SOURCE_MARKER: MarkerKind = MarkerKind::Body;
+    type SourceKey = TransactionIndex;
+    type SourceValue = VersionZeroWrapper<Transaction>;
+    type IndexKey = (ContractAddress, TransactionIndex);
+    type IndexValue = NoVersionValueWrapper<Option<Nonce>>;
+
+    fn source_table(
+        tables: &Tables,
+    ) -> &TableIdentifier<TransactionIndex, Self::SourceValue, SimpleTable> {
+        &tables.transactions
+    }
+
+    fn index_table(
+        tables: &Tables,
+    ) -> &TableIdentifier<(ContractAddress, TransactionIndex), Self::IndexValue, SimpleTable> {
+        &tables.transactions_by_sender
+    }
+
+    // Deploy and L1 handler transactions have no sender and aren't indexed.
+    fn index_entries(
+        transaction_index: &TransactionIndex,
+        transaction: &Transaction,
+    ) -> Vec<((ContractAddress, TransactionIndex), Option<Nonce>)> {
+        transaction_sender(transaction)
+            .map(|(sender_address, nonce)| ((sender_address, *transaction_index), nonce))
+            .into_iter()
+            .collect()
+    }
+
+    fn indexed_block(
+        (_sender_address, transaction_index): &(ContractAddress, TransactionIndex),
+        _nonce: &Option<Nonce>,
+    ) -> BlockNumber {
+        transaction_index.0
+    }
+}
+
+// Adds the deploy account transactions to the transactions by sender. The sender of a deploy
+// account transaction is the account it deploys, whose address is read from the receipt of the
+// transaction.
+pub(crate) struct DeployAccountSendersIndex;
+
+impl SecondaryIndex for DeployAccountSendersIndex {
+    const NAME: &'static str = TransactionsBySenderIndex::NAME;
+    const SOURCE_MARKER: MarkerKind = MarkerKind::Receipts;
+    type SourceKey = TransactionIndex;
+    type SourceValue = VersionZeroWrapper<ThinTransactionOutput>;
+    type IndexKey = (ContractAddress, TransactionIndex);
+    type IndexValue = NoVersionValueWrapper<Option<Nonce>>;
+
+    fn source_table(
+        tables: &Tables,
+    ) -> &TableIdentifier<TransactionIndex, Self::SourceValue, SimpleTable> {
+        &tables.transaction_outputs
+    }
+
+    fn index_table(
+        tables: &Tables,
+    ) -> &TableIdentifier<(ContractAddress, TransactionIndex), Self::IndexValue, SimpleTable> {
+        &tables.transactions_by_sender
+    }
+
+    // A deploy account transaction is accepted only with the nonce 0 of the account it deploys.
+    fn index_entries(
+        transaction_index: &TransactionIndex,
+        transaction_output: &ThinTransactionOutput,
+    ) -> Vec<((ContractAddress, TransactionIndex), Option<Nonce>)> {
+        match transaction_output {
+            ThinTransactionOutput::DeployAccount(output) => {
+                vec![((output.contract_address, *transaction_index), Some(Nonce::default()))]
+            }
+            _ => vec![],
+        }
+    }
+
+    fn indexed_block(
+        (_sender_address, transaction_index): &(ContractAddress, TransactionIndex),
+        _nonce: &Option<Nonce>,
+    ) -> BlockNumber {
+        transaction_index.0
+    }
+}
+
+// Returns the sender of the transaction and its nonce, if it has one. Deploy account transactions
+// are indexed from their receipts.
+fn transaction_sender(transaction: &Transaction) -> Option<(ContractAddress, Option<Nonce>)> {
+    match transaction {
+        Transaction::Declare(DeclareTransaction::V0(tx) | DeclareTransaction::V1(tx)) => {
+            Some((tx.sender_address, Some(tx.nonce)))
+        }
+        Transaction::Declare(DeclareTransaction::V2(tx)) => {
+            Some((tx.sender_address, Some(tx.nonce)))
+        }
+        Transaction::Declare(DeclareTransaction::V3(tx)) => {
+            Some((tx.sender_address, Some(tx.nonce)))
+        }
+        Transaction::Invoke(InvokeTransaction::V0(tx)) => Some((tx.contract_address, None)),
+        Transaction::Invoke(InvokeTransaction::V1(tx)) => Some((tx.sender_address, Some(tx.nonce))),
+        Transaction::Invoke(InvokeTransaction::V3(tx)) => Some((tx.sender_address, Some(tx.nonce))),
+        Transaction::DeployAccount(_) | Transaction::Deploy(_) | Transaction::L1Handler(_) => None,
+    }
+}
+
+/// Builds the index of the transactions by sender of a storage whose transactions were stored
+/// before the index existed, instead of syncing them again.
+pub(crate) fn build_transactions_by_sender_if_needed(
+    writer: &mut StorageWriter,
+) -> StorageResult<()> {
+    if writer.scope == StorageScope::StateOnly || writer.mode == NodeMode::Light {
+        return Ok(());
+    }
+    let txn = writer.begin_rw_txn()?;
+    let transactions_table = txn.open_table(&txn.tables.transactions)?;
+    let transactions_by_sender_table = txn.open_table(&txn.tables.transactions_by_sender)?;
+    let has_transactions = transactions_table.cursor(&txn.txn)?.next()?.is_some();
+    let has_index_entries = transactions_by_sender_table.cursor(&txn.txn)?.next()?.is_some();
+    drop(txn);
+    if !has_transactions || has_index_entries {
+        return Ok(());
+    }
+    info!("Building the index of the transactions by sender of the stored transactions.");
+    writer.rebuild(SecondaryIndexKind::TransactionsBySender, REBUILD_BATCH_SIZE)
+}
+
+/// Resumes the rebuilds of the secondary indexes that were interrupted, so that a partial index
+/// isn't served as if it were complete.
+pub(crate) fn resume_secondary_index_rebuilds(writer: &mut StorageWriter) -> StorageResult<()> {
+    for index in SecondaryIndexKind::ALL {
+        let txn = writer.begin_rw_txn()?;
+        let is_rebuilding = txn
+            .open_table(&txn.tables.secondary_index_rebuilds)?
+            .get(&txn.txn, &index.name().to_owned())?
+            .is_some();
+        drop(txn);
+        if is_rebuilding {
+            writer.rebuild(index, REBUILD_BATCH_SIZE)?;
+        }
+    }
+    Ok(())
+}
+
+impl StorageWriter {
+    /// Rebuilds the given secondary index from its source tables, in a single pass over the sources
+    /// that is committed in batches. The progress of the rebuild is committed with every batch, so
+    /// an interrupted rebuild is resumed where it stopped.
+    pub fn rebuild_secondary_index(&mut self, index: SecondaryIndexKind) -> StorageResult<()> {
+        self.rebuild(index, REBUILD_BATCH_SIZE)
+    }
+
+    pub(crate) fn rebuild(
+        &mut self,
+        index: SecondaryIndexKind,
+        batch_size: usize,
+    ) -> StorageResult<()> {
+        let progress = match index {
+            SecondaryIndexKind::BlockHash => self.start_rebuild::<BlockHashIndex>(index)?,
+            SecondaryIndexKind::EventsByFirstKey => {
+                self.start_rebuild::<EventsByFirstKeyIndex>(index)?
+            }
+            SecondaryIndexKind::TransactionHash => {
+                self.start_rebuild::<TransactionHashIndex>(index)?
+            }
+            SecondaryIndexKind::TransactionsBySender => {
+                self.start_rebuild::<TransactionsBySenderIndex>(index)?
+            }
+        };
+        match index {
+            SecondaryIndexKind::BlockHash => {
+                self.index_source::<BlockHashIndex>(index, 0, &progress, batch_size)?
+            }
+            SecondaryIndexKind::EventsByFirstKey => {
+                self.index_source::<EventsByFirstKeyIndex>(index, 0, &progress, batch_size)?
+            }
+            SecondaryIndexKind::TransactionHash => {
+                self.index_source::<TransactionHashIndex>(index, 0, &progress, batch_size)?
+            }
+            // The transactions by sender are indexed from two sources that share the index table.
+            SecondaryIndexKind::TransactionsBySender => {
+                self.index_source::<TransactionsBySenderIndex>(index, 0, &progress, batch_size)?;
+                self.index_source::<DeployAccountSendersIndex>(index, 1, &progress, batch_size)?;
+            }
+        }
+        let txn = self.begin_rw_txn()?;
+        txn.open_table(&txn.tables.secondary_index_rebuilds)?
+            .delete(&txn.txn, &index.name().to_owned())?;
+        txn.commit()
+    }
+
+    /// Deletes the entries of the secondary indexes that point at blocks past the marker of their
+    /// source, and returns the indexes that had such entries. Indexes that aren't stored in the
+    /// scope and mode of the storage are skipped.
+    pub fn repair_secondary_indexes(&mut self) -> StorageResult<Vec<IndexRepair>> {
+        let mut repairs = vec![];
+        for index in SecondaryIndexKind::ALL {
+            if index != SecondaryIndexKind::BlockHash
+                && (self.scope == StorageScope::StateOnly || self.mode == NodeMode::Light)
+            {
+                continue;
+            }
+            let deleted_entries = match index {
+                SecondaryIndexKind::BlockHash => self.repair_index::<BlockHashIndex>()?,
+                SecondaryIndexKind::EventsByFirstKey => {
+                    self.repair_index::<EventsByFirstKeyIndex>()?
+                }
+                SecondaryIndexKind::TransactionHash => {
+                    self.repair_index::<TransactionHashIndex>()?
+                }
+                SecondaryIndexKind::TransactionsBySender => {
+                    self.repair_index::<TransactionsBySenderIndex>()?
+                }
+            };
+            if deleted_entries > 0 {
+                repairs.push(IndexRepair { index, deleted_entries });
+            }
+        }
+        Ok(repairs)
+    }
+
+    // Deletes the entries of the index that point at blocks past the marker of its source, and
+    // returns their number.
+    fn repair_index<I: SecondaryIndex>(&mut self) -> StorageResult<usize> {
+        let txn = self.begin_rw_txn()?;
+        let marker = txn
+            .open_table(&txn.tables.markers)?
+            .get(&txn.txn, &I::SOURCE_MARKER)?
+            .unwrap_or_default();
+        let index_table = txn.open_table(I::index_table(&txn.tables))?;
+        let mut cursor = index_table.cursor(&txn.txn)?;
+        let mut dangling_keys = vec![];
+        while let Some((key, value)) = cursor.next()? {
+            if I::indexed_block(&key, &value) >= marker {
+                dangling_keys.push(key);
+            }
+        }
+        drop(cursor);
+        for key in &dangling_keys {
+            index_table.delete(&txn.txn, key)?;
+        }
+        txn.commit()?;
+        Ok(dangling_keys.len())
+    }
+
+    // Returns the progress of the rebuild of the index. A new rebuild clears the index in the
+    // transaction that marks it as being rebuilt, so a partial index is always marked.
+    fn start_rebuild<I: SecondaryIndex>(
+        &mut self,
+        index: SecondaryIndexKind,
+    ) -> StorageResult<IndexRebuildProgress> {
+        let txn = self.begin_rw_txn()?;
+        let rebuilds_table = txn.open_table(&txn.tables.secondary_index_rebuilds)?;
+        if let Some(progress) = rebuilds_table.get(&txn.txn, &index.name().to_owned())? {
+            info!("Resuming the interrupted rebuild of the index {}.", index.name());
+            return Ok(progress);
+        }
+        txn.open_table(I::index_table(&txn.tables))?.clear(&txn.txn)?;
+        let progress = IndexRebuildProgress::default();
+        rebuilds_table.insert(&txn.txn, &index.name().to_owned(), &progress)?;
+        txn.commit()?;
+        Ok(progress)
+    }
+
+    // Adds the entries of the index for the entries of its source table that weren't indexed yet
+    // according to the progress of the rebuild, and commits the progress with every batch.
+    fn index_source<I: SecondaryIndex>(
+        &mut self,
+        index: SecondaryIndexKind,
+        source: u8,
+        progress: &IndexRebuildProgress,
+        batch_size: usize,
+    ) -> StorageResult<()> {
+        // The sources that precede the source of the progress were fully indexed.
+        if source < progress.source {
+            return Ok(());
+        }
+        // The last source key that was indexed, None before the first batch.
+        let mut last_indexed_key = match &progress.last_indexed_key {
+            Some(key_bytes) if source == progress.source => {
+                Some(I::SourceKey::deserialize(&mut key_bytes.as_slice()).ok_or_else(|| {
+                    StorageError::DBInconsistency {
+                        msg: format!(
+                            "Invalid last indexed key in the rebuild progress of the index {}.",
+                            index.name()
+                        ),
+                    }
+                })?)
+            }
+            _ => None,
+        };
+        loop {
+            let txn = self.begin_rw_txn()?;
+            let source_table = txn.open_table(I::source_table(&txn.tables))?;
+            let index_table = txn.open_table(I::index_table(&txn.tables))?;
+            let mut cursor = source_table.cursor(&txn.txn)?;
+            let mut current = match &last_indexed_key {
+                None => cursor.next()?,
+                Some(key) => {
+                    cursor.lower_bound(key)?;
+                    cursor.next()?
+                }
+            };
+            let mut batch_last_key = None;
+            let mut indexed_in_batch = 0;
+            while let Some((key, value)) = current {
+                I::insert_entries(&txn.txn, &index_table, &key, &value)?;
+                indexed_in_batch += 1;
+                if indexed_in_batch == batch_size {
+                    batch_last_key = Some(key);
+                    break;
+                }
+                current = cursor.next()?;
+            }
+            drop(cursor);
+            let batch_progress = match &batch_last_key {
+                Some(key) => {
+                    IndexRebuildProgress { source, last_indexed_key: Some(key.serialize()?) }
+                }
+                None => IndexRebuildProgress { source: source + 1, last_indexed_key: None },
+            };
+            txn.open_table(&txn.tables.secondary_index_rebuilds)?.upsert(
+                &txn.txn,
+                &index.name().to_owned(),
+                &batch_progress,
+            )?;
+            txn.commit()?;
+            match batch_last_key {
+                Some(key) => last_indexed_key = Some(key),
+                None => return Ok(()),
+            }
+        }
+    }
+}
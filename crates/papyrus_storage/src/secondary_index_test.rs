@@ -0,0 +1,191 @@
+use std::fmt::Debug;
+
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockHash, BlockHeader, BlockNumber};
+use starknet_api::hash::StarkFelt;
+use starknet_api::transaction::{TransactionHash, TransactionOffsetInBlock};
+use test_utils::get_test_body;
+
+use crate::body::{BodyStorageWriter, TransactionIndex};
+use crate::db::serialization::StorageSerdeEx;
+use crate::db::table_types::{DbCursorTrait, Table};
+use crate::header::HeaderStorageWriter;
+use crate::secondary_index::{
+    resume_secondary_index_rebuilds,
+    BlockHashIndex,
+    EventsByFirstKeyIndex,
+    IndexRebuildProgress,
+    IndexRepair,
+    IndexValue,
+    SecondaryIndex,
+    SecondaryIndexKind,
+    TransactionHashIndex,
+};
+use crate::test_utils::get_test_storage;
+use crate::{StorageReader, StorageWriter};
+
+const N_BLOCKS: u64 = 3;
+
+fn append_blocks(writer: &mut StorageWriter) {
+    for i in 0..N_BLOCKS {
+        let block_number = BlockNumber(i);
+        let header = BlockHeader {
+            block_hash: BlockHash(StarkFelt::from(i + 100)),
+            block_number,
+            ..Default::default()
+        };
+        // The test bodies are identical, so their transaction hashes are made unique.
+        let mut body = get_test_body(1, Some(3), None, None);
+        body.transaction_hashes[0].0 = StarkFelt::from(i + 200);
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_header(block_number, &header)
+            .unwrap()
+            .append_body(block_number, body)
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+}
+
+fn read_index<I: SecondaryIndex>(reader: &StorageReader) -> Vec<(I::IndexKey, IndexValue<I>)> {
+    let txn = reader.begin_ro_txn().unwrap();
+    let index_table = txn.open_table(I::index_table(&txn.tables)).unwrap();
+    let mut cursor = index_table.cursor(&txn.txn).unwrap();
+    let mut entries = vec![];
+    while let Some(entry) = cursor.next().unwrap() {
+        entries.push(entry);
+    }
+    entries
+}
+
+// Rebuilds the index from scratch in batches of the given size, and checks that its entries are
+// the ones that were maintained when its source was written.
+fn assert_rebuilt_index<I: SecondaryIndex>(
+    reader: &StorageReader,
+    writer: &mut StorageWriter,
+    index: SecondaryIndexKind,
+    batch_size: usize,
+) where
+    I::IndexKey: PartialEq,
+    IndexValue<I>: PartialEq + Debug,
+{
+    let maintained_entries = read_index::<I>(reader);
+    assert!(!maintained_entries.is_empty());
+
+    let txn = writer.begin_rw_txn().unwrap();
+    txn.open_table(I::index_table(&txn.tables)).unwrap().clear(&txn.txn).unwrap();
+    txn.commit().unwrap();
+    assert!(read_index::<I>(reader).is_empty());
+
+    writer.rebuild(index, batch_size).unwrap();
+    assert_eq!(read_index::<I>(reader), maintained_entries);
+}
+
+#[test]
+fn rebuild_restores_maintained_entries() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_blocks(&mut writer);
+
+    for batch_size in [1, 2, 1000] {
+        assert_rebuilt_index::<BlockHashIndex>(
+            &reader,
+            &mut writer,
+            SecondaryIndexKind::BlockHash,
+            batch_size,
+        );
+        assert_rebuilt_index::<EventsByFirstKeyIndex>(
+            &reader,
+            &mut writer,
+            SecondaryIndexKind::EventsByFirstKey,
+            batch_size,
+        );
+        assert_rebuilt_index::<TransactionHashIndex>(
+            &reader,
+            &mut writer,
+            SecondaryIndexKind::TransactionHash,
+            batch_size,
+        );
+    }
+}
+
+#[test]
+fn rebuild_secondary_index_by_kind() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_blocks(&mut writer);
+    let entries_before = read_index::<TransactionHashIndex>(&reader);
+
+    for index in SecondaryIndexKind::ALL {
+        writer.rebuild_secondary_index(index).unwrap();
+    }
+    assert_eq!(read_index::<TransactionHashIndex>(&reader), entries_before);
+}
+
+#[test]
+fn interrupted_rebuild_is_resumed() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_blocks(&mut writer);
+    let maintained_entries = read_index::<TransactionHashIndex>(&reader);
+    let index_name = SecondaryIndexKind::TransactionHash.name().to_owned();
+
+    // A rebuild that was interrupted after indexing the first transaction.
+    let txn = writer.begin_rw_txn().unwrap();
+    let index_table = txn.open_table(&txn.tables.transaction_hash_to_idx).unwrap();
+    index_table.clear(&txn.txn).unwrap();
+    let source_table = txn.open_table(&txn.tables.transaction_idx_to_hash).unwrap();
+    let (first_key, first_value) = source_table.cursor(&txn.txn).unwrap().next().unwrap().unwrap();
+    TransactionHashIndex::insert_entries(&txn.txn, &index_table, &first_key, &first_value).unwrap();
+    let progress =
+        IndexRebuildProgress { source: 0, last_indexed_key: Some(first_key.serialize().unwrap()) };
+    txn.open_table(&txn.tables.secondary_index_rebuilds)
+        .unwrap()
+        .insert(&txn.txn, &index_name, &progress)
+        .unwrap();
+    txn.commit().unwrap();
+
+    resume_secondary_index_rebuilds(&mut writer).unwrap();
+    assert_eq!(read_index::<TransactionHashIndex>(&reader), maintained_entries);
+
+    // The index is no longer marked as being rebuilt.
+    let txn = reader.begin_ro_txn().unwrap();
+    let rebuilds_table = txn.open_table(&txn.tables.secondary_index_rebuilds).unwrap();
+    assert_eq!(rebuilds_table.get(&txn.txn, &index_name).unwrap(), None);
+}
+
+#[test]
+fn repair_deletes_entries_past_markers() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_blocks(&mut writer);
+    let block_hash_entries = read_index::<BlockHashIndex>(&reader);
+    let transaction_hash_entries = read_index::<TransactionHashIndex>(&reader);
+
+    // Entries that point at blocks that aren't stored, as if they were left by a crash.
+    let txn = writer.begin_rw_txn().unwrap();
+    txn.open_table(&txn.tables.block_hash_to_number)
+        .unwrap()
+        .insert(&txn.txn, &BlockHash(StarkFelt::from(1000_u64)), &BlockNumber(N_BLOCKS))
+        .unwrap();
+    let transaction_hash_table = txn.open_table(&txn.tables.transaction_hash_to_idx).unwrap();
+    for i in 0..2 {
+        transaction_hash_table
+            .insert(
+                &txn.txn,
+                &TransactionHash(StarkFelt::from(1000 + i)),
+                &TransactionIndex(BlockNumber(N_BLOCKS + i), TransactionOffsetInBlock(0)),
+            )
+            .unwrap();
+    }
+    txn.commit().unwrap();
+
+    assert_eq!(
+        writer.repair_secondary_indexes().unwrap(),
+        vec![
+            IndexRepair { index: SecondaryIndexKind::BlockHash, deleted_entries: 1 },
+            IndexRepair { index: SecondaryIndexKind::TransactionHash, deleted_entries: 2 },
+        ]
+    );
+    assert_eq!(read_index::<BlockHashIndex>(&reader), block_hash_entries);
+    assert_eq!(read_index::<TransactionHashIndex>(&reader), transaction_hash_entries);
+    assert_eq!(writer.repair_secondary_indexes().unwrap(), vec![]);
+}
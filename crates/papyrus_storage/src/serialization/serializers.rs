@@ -107,6 +107,7 @@ use starknet_api::transaction::{
     TransactionVersion,
 };
 
+use crate::base_layer::BaseLayerL1Block;
 use crate::body::events::{
     EventIndex,
     ThinDeclareTransactionOutput,
@@ -127,9 +128,18 @@ use crate::compression_utils::{
 use crate::db::serialization::{StorageSerde, StorageSerdeError};
 use crate::header::StorageBlockHeader;
 use crate::mmap_file::LocationInFile;
+use crate::node_identity::{NodeIdentity, NodeRun};
+use crate::quarantine::QuarantinedBlock;
+use crate::secondary_index::IndexRebuildProgress;
 #[cfg(test)]
 use crate::serialization::serializers_test::{create_storage_serde_test, StorageSerdeTest};
-use crate::state::data::IndexedDeprecatedContractClass;
+use crate::source_reputation::SourceReputation;
+use crate::state::data::{
+    EntryPointLocation,
+    IndexedDeprecatedContractClass,
+    IndexedEntryPoint,
+    StateDiffStats,
+};
 use crate::version::Version;
 use crate::{MarkerKind, OffsetKind};
 
@@ -138,6 +148,10 @@ const COMPRESSION_THRESHOLD_BYTES: usize = 384;
 
 auto_storage_serde! {
     pub struct AccountDeploymentData(pub Vec<StarkFelt>);
+    pub struct BaseLayerL1Block {
+        pub number: u64,
+        pub hash: [u8; 32],
+    }
     pub struct BlockHash(pub StarkHash);
     pub struct StorageBlockHeader {
         pub block_hash: BlockHash,
@@ -231,6 +245,10 @@ auto_storage_serde! {
     }
     pub struct FunctionIndex(pub usize);
     pub struct EntryPointOffset(pub usize);
+    pub enum EntryPointLocation {
+        FunctionIndex(FunctionIndex) = 0,
+        Offset(EntryPointOffset) = 1,
+    }
     pub struct EntryPointSelector(pub StarkHash);
     pub enum EntryPointType {
         Constructor = 0,
@@ -274,6 +292,14 @@ auto_storage_serde! {
         pub block_number: BlockNumber,
         pub location_in_file: LocationInFile,
     }
+    pub struct IndexedEntryPoint {
+        pub entry_point_type: EntryPointType,
+        pub location: EntryPointLocation,
+    }
+    pub struct IndexRebuildProgress {
+        pub source: u8,
+        pub last_indexed_key: Option<Vec<u8>>,
+    }
     pub enum InvokeTransaction {
         V0(InvokeTransactionV0) = 0,
         V1(InvokeTransactionV1) = 1,
@@ -295,6 +321,9 @@ auto_storage_serde! {
         State = 2,
         CompiledClass = 3,
         BaseLayerBlock = 4,
+        Receipts = 5,
+        ClassUsage = 6,
+        PrunedState = 7,
     }
     pub struct MessageToL1 {
         pub to_address: EthAddress,
@@ -309,6 +338,16 @@ auto_storage_serde! {
         Leaf(usize) = 0,
         Node(Vec<NestedIntList>) = 1,
     }
+    pub struct NodeIdentity {
+        pub node_id: String,
+        pub runs: Vec<NodeRun>,
+    }
+    pub struct NodeRun {
+        pub start_timestamp: u64,
+        pub version: String,
+        pub build_commit: String,
+        pub config_hash: String,
+    }
     pub struct Nonce(pub StarkFelt);
     pub enum OffsetKind {
         ThinStateDiff = 0,
@@ -329,6 +368,10 @@ auto_storage_serde! {
         pub prime: serde_json::Value,
         pub reference_manager: serde_json::Value,
     }
+    pub struct QuarantinedBlock {
+        pub body_error: Option<String>,
+        pub state_diff_error: Option<String>,
+    }
     pub enum Resource {
         L1Gas = 0,
         L2Gas = 1,
@@ -343,6 +386,20 @@ auto_storage_serde! {
         pub r: StarkFelt,
         pub s: StarkFelt,
     }
+    pub struct SourceReputation {
+        pub success_count: u64,
+        pub error_count: u64,
+        pub latency_ewma_millis: u64,
+        pub last_ban_timestamp: Option<u64>,
+    }
+    pub struct StateDiffStats {
+        pub storage_writes: u64,
+        pub deployed_contracts: u64,
+        pub declared_classes: u64,
+        pub deprecated_declared_classes: u64,
+        pub nonce_updates: u64,
+        pub replaced_classes: u64,
+    }
     pub struct StructAbiEntry {
         pub name: String,
         pub size: usize,
@@ -458,14 +515,19 @@ auto_storage_serde! {
     binary(u128, read_u128, write_u128);
 
 
+    (BlockNumber, ClassHash);
     (BlockNumber, TransactionOffsetInBlock);
     (BlockHash, ClassHash);
+    (ClassHash, EntryPointSelector);
     (ContractAddress, BlockHash);
     (ContractAddress, BlockNumber);
     (ContractAddress, Nonce);
     (ContractAddress, EventIndex);
+    (ContractAddress, TransactionIndex);
     (ContractAddress, StorageKey, BlockHash);
     (ContractAddress, StorageKey, BlockNumber);
+    (StarkFelt, EventIndex);
+    (u8, u64);
     (usize, Vec<Hint>);
     (usize, Vec<String>);
 }
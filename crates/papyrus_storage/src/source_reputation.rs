@@ -0,0 +1,193 @@
+//! Interface for handling the reputation of the data sources the node syncs from.
+//!
+//! A data source is any endpoint the node downloads data from, e.g. a central feeder gateway or a
+//! P2P peer. Its reputation holds health statistics that should survive a restart of the node, so
+//! that endpoints that misbehaved recently aren't trusted again right away.
+//!
+//! Import [`SourceReputationStorageReader`] and [`SourceReputationStorageWriter`] to read and
+//! write the reputation of a source using a [`StorageTxn`].
+//! # Example
+//! ```
+//! # use papyrus_storage::{db::DbConfig, StorageConfig};
+//! # use starknet_api::core::ChainId;
+//! use std::time::Duration;
+//!
+//! use papyrus_storage::open_storage;
+//! use papyrus_storage::source_reputation::{
+//!     SourceReputation,
+//!     SourceReputationStorageReader,
+//!     SourceReputationStorageWriter,
+//! };
+//!
+//! # let dir_handle = tempfile::tempdir().unwrap();
+//! # let dir = dir_handle.path().to_path_buf();
+//! # let db_config = DbConfig {
+//! #     path_prefix: dir,
+//! #     chain_id: ChainId("SN_MAIN".to_owned()),
+//! #     enforce_file_exists: false,
+//! #     min_size: 1 << 20,    // 1MB
+//! #     max_size: 1 << 35,    // 32GB
+//! #     growth_step: 1 << 26, // 64MB
+//! # };
+//! # let storage_config = StorageConfig{db_config, ..Default::default()};
+//! let (reader, mut writer) = open_storage(storage_config)?;
+//! let mut reputation = SourceReputation::default();
+//! reputation.record_success(Duration::from_millis(100));
+//! writer
+//!     .begin_rw_txn()?
+//!     .update_source_reputation("https://feeder.example", &reputation)?
+//!     .commit()?;
+//! let stored = reader.begin_ro_txn()?.get_source_reputation("https://feeder.example")?;
+//! assert_eq!(stored, Some(reputation));
+//! # Ok::<(), papyrus_storage::StorageError>(())
+//! ```
+#[cfg(test)]
+#[path = "source_reputation_test.rs"]
+mod source_reputation_test;
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::db::table_types::{DbCursorTrait, Table};
+use crate::db::{TransactionKind, RW};
+use crate::{StorageResult, StorageTxn};
+
+// The weight of a new latency sample in the latency exponential moving average, in percents.
+const LATENCY_EWMA_WEIGHT_PERCENT: u64 = 20;
+
+/// Health statistics of a single data source.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct SourceReputation {
+    /// The number of successful requests to the source.
+    pub success_count: u64,
+    /// The number of failed requests to the source.
+    pub error_count: u64,
+    /// An exponential moving average of the latency of successful requests, in milliseconds.
+    pub latency_ewma_millis: u64,
+    /// The unix timestamp, in seconds, of the last time the source was banned, if ever.
+    pub last_ban_timestamp: Option<u64>,
+}
+
+impl SourceReputation {
+    /// Records a successful request that took the given time.
+    pub fn record_success(&mut self, latency: Duration) {
+        let latency_millis = u64::try_from(latency.as_millis()).unwrap_or(u64::MAX);
+        self.latency_ewma_millis = if self.success_count == 0 {
+            latency_millis
+        } else {
+            (latency_millis.saturating_mul(LATENCY_EWMA_WEIGHT_PERCENT)
+                + self.latency_ewma_millis.saturating_mul(100 - LATENCY_EWMA_WEIGHT_PERCENT))
+                / 100
+        };
+        self.success_count = self.success_count.saturating_add(1);
+    }
+
+    /// Records a failed request.
+    pub fn record_error(&mut self) {
+        self.error_count = self.error_count.saturating_add(1);
+    }
+
+    /// Records that the source was banned at the given unix timestamp, in seconds.
+    pub fn record_ban(&mut self, timestamp: u64) {
+        self.last_ban_timestamp = Some(timestamp);
+    }
+
+    /// Returns true if the source was banned less than `ban_duration` before `now` (a unix
+    /// timestamp, in seconds).
+    pub fn is_banned(&self, now: u64, ban_duration: Duration) -> bool {
+        self.last_ban_timestamp
+            .is_some_and(|banned_at| now < banned_at.saturating_add(ban_duration.as_secs()))
+    }
+}
+
+/// Interface for reading the reputation of data sources.
+pub trait SourceReputationStorageReader {
+    /// Returns the reputation of the given source, or None if nothing was recorded for it.
+    fn get_source_reputation(&self, source_id: &str) -> StorageResult<Option<SourceReputation>>;
+
+    /// Returns the reputations of all the sources that have one, keyed by the source id.
+    fn get_all_source_reputations(&self) -> StorageResult<BTreeMap<String, SourceReputation>>;
+}
+
+/// Interface for writing the reputation of data sources.
+pub trait SourceReputationStorageWriter
+where
+    Self: Sized,
+{
+    /// Sets the reputation of the given source, overriding any previous value.
+    // To enforce that no commit happen after a failure, we consume and return Self on success.
+    fn update_source_reputation(
+        self,
+        source_id: &str,
+        reputation: &SourceReputation,
+    ) -> StorageResult<Self>;
+
+    /// Records in the reputation of the given source a successful request that took the given
+    /// time.
+    fn record_source_success(self, source_id: &str, latency: Duration) -> StorageResult<Self>;
+
+    /// Records in the reputation of the given source a failed request.
+    fn record_source_error(self, source_id: &str) -> StorageResult<Self>;
+
+    /// Records in the reputation of the given source that it was banned at the given unix
+    /// timestamp, in seconds.
+    fn record_source_ban(self, source_id: &str, timestamp: u64) -> StorageResult<Self>;
+
+    /// Deletes the reputation of the given source, if exists.
+    fn delete_source_reputation(self, source_id: &str) -> StorageResult<Self>;
+}
+
+impl<'env, Mode: TransactionKind> SourceReputationStorageReader for StorageTxn<'env, Mode> {
+    fn get_source_reputation(&self, source_id: &str) -> StorageResult<Option<SourceReputation>> {
+        let source_reputation_table = self.open_table(&self.tables.source_reputation)?;
+        Ok(source_reputation_table.get(&self.txn, &source_id.to_owned())?)
+    }
+
+    fn get_all_source_reputations(&self) -> StorageResult<BTreeMap<String, SourceReputation>> {
+        let source_reputation_table = self.open_table(&self.tables.source_reputation)?;
+        let mut cursor = source_reputation_table.cursor(&self.txn)?;
+        let mut res = BTreeMap::new();
+        while let Some((source_id, reputation)) = cursor.next()? {
+            res.insert(source_id, reputation);
+        }
+        Ok(res)
+    }
+}
+
+impl<'env> SourceReputationStorageWriter for StorageTxn<'env, RW> {
+    fn update_source_reputation(
+        self,
+        source_id: &str,
+        reputation: &SourceReputation,
+    ) -> StorageResult<Self> {
+        let source_reputation_table = self.open_table(&self.tables.source_reputation)?;
+        source_reputation_table.upsert(&self.txn, &source_id.to_owned(), reputation)?;
+        Ok(self)
+    }
+
+    fn record_source_success(self, source_id: &str, latency: Duration) -> StorageResult<Self> {
+        let mut reputation = self.get_source_reputation(source_id)?.unwrap_or_default();
+        reputation.record_success(latency);
+        self.update_source_reputation(source_id, &reputation)
+    }
+
+    fn record_source_error(self, source_id: &str) -> StorageResult<Self> {
+        let mut reputation = self.get_source_reputation(source_id)?.unwrap_or_default();
+        reputation.record_error();
+        self.update_source_reputation(source_id, &reputation)
+    }
+
+    fn record_source_ban(self, source_id: &str, timestamp: u64) -> StorageResult<Self> {
+        let mut reputation = self.get_source_reputation(source_id)?.unwrap_or_default();
+        reputation.record_ban(timestamp);
+        self.update_source_reputation(source_id, &reputation)
+    }
+
+    fn delete_source_reputation(self, source_id: &str) -> StorageResult<Self> {
+        let source_reputation_table = self.open_table(&self.tables.source_reputation)?;
+        source_reputation_table.delete(&self.txn, &source_id.to_owned())?;
+        Ok(self)
+    }
+}
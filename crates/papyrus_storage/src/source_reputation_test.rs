@@ -0,0 +1,126 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use pretty_assertions::assert_eq;
+
+use crate::source_reputation::{
+    SourceReputation,
+    SourceReputationStorageReader,
+    SourceReputationStorageWriter,
+};
+use crate::test_utils::get_test_storage;
+
+const SOURCE_A: &str = "https://feeder_a";
+const SOURCE_B: &str = "https://feeder_b";
+
+#[test]
+fn rw_source_reputation() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+
+    assert_eq!(reader.begin_ro_txn().unwrap().get_source_reputation(SOURCE_A).unwrap(), None);
+
+    let mut reputation_a = SourceReputation::default();
+    reputation_a.record_error();
+    let mut reputation_b = SourceReputation::default();
+    reputation_b.record_ban(7);
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .update_source_reputation(SOURCE_A, &reputation_a)
+        .unwrap()
+        .update_source_reputation(SOURCE_B, &reputation_b)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_source_reputation(SOURCE_A).unwrap(), Some(reputation_a.clone()));
+    assert_eq!(
+        txn.get_all_source_reputations().unwrap(),
+        BTreeMap::from([
+            (SOURCE_A.to_owned(), reputation_a.clone()),
+            (SOURCE_B.to_owned(), reputation_b)
+        ])
+    );
+    drop(txn);
+
+    // Overriding and deleting.
+    reputation_a.record_error();
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .update_source_reputation(SOURCE_A, &reputation_a)
+        .unwrap()
+        .delete_source_reputation(SOURCE_B)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_source_reputation(SOURCE_A).unwrap().unwrap().error_count, 2);
+    assert_eq!(txn.get_source_reputation(SOURCE_B).unwrap(), None);
+}
+
+#[test]
+fn latency_ewma_and_ban() {
+    let mut reputation = SourceReputation::default();
+    reputation.record_success(Duration::from_millis(100));
+    assert_eq!(reputation.latency_ewma_millis, 100);
+    reputation.record_success(Duration::from_millis(200));
+    assert_eq!(reputation.latency_ewma_millis, 120);
+    assert_eq!(reputation.success_count, 2);
+
+    assert!(!reputation.is_banned(10, Duration::from_secs(5)));
+    reputation.record_ban(10);
+    assert!(reputation.is_banned(14, Duration::from_secs(5)));
+    assert!(!reputation.is_banned(15, Duration::from_secs(5)));
+}
+
+#[test]
+fn record_source_ban_keeps_the_statistics() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let mut reputation = SourceReputation::default();
+    reputation.record_error();
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .update_source_reputation(SOURCE_A, &reputation)
+        .unwrap()
+        .record_source_ban(SOURCE_A, 7)
+        .unwrap()
+        .record_source_ban(SOURCE_B, 8)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    reputation.record_ban(7);
+    assert_eq!(txn.get_source_reputation(SOURCE_A).unwrap(), Some(reputation));
+    assert_eq!(txn.get_source_reputation(SOURCE_B).unwrap().unwrap().last_ban_timestamp, Some(8));
+}
+
+#[test]
+fn record_source_requests() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .record_source_success(SOURCE_A, Duration::from_millis(100))
+        .unwrap()
+        .record_source_ban(SOURCE_A, 7)
+        .unwrap()
+        .record_source_success(SOURCE_A, Duration::from_millis(200))
+        .unwrap()
+        .record_source_error(SOURCE_A)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let mut expected_reputation = SourceReputation::default();
+    expected_reputation.record_success(Duration::from_millis(100));
+    expected_reputation.record_ban(7);
+    expected_reputation.record_success(Duration::from_millis(200));
+    expected_reputation.record_error();
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_source_reputation(SOURCE_A).unwrap(), Some(expected_reputation));
+}
@@ -1,8 +1,12 @@
 use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
 use starknet_api::block::BlockNumber;
 use starknet_api::core::ClassHash;
-use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
-use starknet_api::state::ContractClass;
+use starknet_api::deprecated_contract_class::{
+    ContractClass as DeprecatedContractClass,
+    EntryPointOffset,
+};
+use starknet_api::state::{ContractClass, EntryPointType, FunctionIndex, ThinStateDiff};
 
 use crate::mmap_file::LocationInFile;
 
@@ -16,3 +20,56 @@ pub(crate) struct IndexedDeprecatedContractClass {
     pub block_number: BlockNumber,
     pub location_in_file: LocationInFile,
 }
+
+/// An entry point of a declared class, indexed by the class hash and the entry point selector.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct IndexedEntryPoint {
+    /// The type of the entry point.
+    pub entry_point_type: EntryPointType,
+    /// Where the entry point is in the program of the class.
+    pub location: EntryPointLocation,
+}
+
+/// The location of an entry point in the program of its class.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum EntryPointLocation {
+    /// The index of the entry point's function in the Sierra program of a Cairo 1 class.
+    FunctionIndex(FunctionIndex),
+    /// The offset of the entry point in the program of a deprecated (Cairo 0) class.
+    Offset(EntryPointOffset),
+}
+
+/// The number of changes of each kind in the state diff of a block, recorded when the state diff is
+/// written so that the activity of the chain can be followed without reading the state diffs.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+pub struct StateDiffStats {
+    /// The number of storage entries that were written, across all the contracts.
+    pub storage_writes: u64,
+    /// The number of contracts that were deployed.
+    pub deployed_contracts: u64,
+    /// The number of Cairo 1 classes that were declared.
+    pub declared_classes: u64,
+    /// The number of deprecated (Cairo 0) classes that were declared.
+    pub deprecated_declared_classes: u64,
+    /// The number of contracts whose nonce was updated.
+    pub nonce_updates: u64,
+    /// The number of contracts whose class was replaced.
+    pub replaced_classes: u64,
+}
+
+impl From<&ThinStateDiff> for StateDiffStats {
+    fn from(thin_state_diff: &ThinStateDiff) -> Self {
+        Self {
+            storage_writes: thin_state_diff
+                .storage_diffs
+                .values()
+                .map(|storage_diff| storage_diff.len() as u64)
+                .sum(),
+            deployed_contracts: thin_state_diff.deployed_contracts.len() as u64,
+            declared_classes: thin_state_diff.declared_classes.len() as u64,
+            deprecated_declared_classes: thin_state_diff.deprecated_declared_classes.len() as u64,
+            nonce_updates: thin_state_diff.nonces.len() as u64,
+            replaced_classes: thin_state_diff.replaced_classes.len() as u64,
+        }
+    }
+}
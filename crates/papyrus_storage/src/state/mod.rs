@@ -56,23 +56,42 @@ use std::collections::HashSet;
 
 use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 use indexmap::IndexMap;
+use papyrus_common::class_cache::ClassCache;
 use papyrus_proc_macros::latency_histogram;
 use starknet_api::block::BlockNumber;
-use starknet_api::core::{ClassHash, ContractAddress, Nonce};
-use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
+use starknet_api::core::{ClassHash, ContractAddress, EntryPointSelector, Nonce};
+use starknet_api::deprecated_contract_class::{
+    ContractClass as DeprecatedContractClass,
+    EntryPointType as DeprecatedEntryPointType,
+};
 use starknet_api::hash::StarkFelt;
-use starknet_api::state::{ContractClass, StateDiff, StateNumber, StorageKey, ThinStateDiff};
+use starknet_api::state::{
+    ContractClass,
+    EntryPointType,
+    StateDiff,
+    StateNumber,
+    StorageKey,
+    ThinStateDiff,
+};
 use tracing::debug;
 
+use crate::class_usage::{delete_class_usage, record_class_usage};
 use crate::db::serialization::{NoVersionValueWrapper, VersionZeroWrapper};
 use crate::db::table_types::{DbCursorTrait, SimpleTable, Table};
 use crate::db::{DbError, DbTransaction, TableHandle, TransactionKind, RW};
 use crate::mmap_file::LocationInFile;
-use crate::state::data::IndexedDeprecatedContractClass;
+use crate::quarantine::QuarantineStorageReader;
+use crate::state::data::{
+    EntryPointLocation,
+    IndexedDeprecatedContractClass,
+    IndexedEntryPoint,
+    StateDiffStats,
+};
 use crate::{
     FileHandlers,
     MarkerKind,
     MarkersTable,
+    NodeMode,
     OffsetKind,
     StorageError,
     StorageResult,
@@ -85,6 +104,8 @@ type DeclaredClassesTable<'env> =
     TableHandle<'env, ClassHash, VersionZeroWrapper<LocationInFile>, SimpleTable>;
 type DeclaredClassesBlockTable<'env> =
     TableHandle<'env, ClassHash, NoVersionValueWrapper<BlockNumber>, SimpleTable>;
+type DeferredClassesTable<'env> =
+    TableHandle<'env, ClassHash, NoVersionValueWrapper<BlockNumber>, SimpleTable>;
 type DeprecatedDeclaredClassesTable<'env> =
     TableHandle<'env, ClassHash, VersionZeroWrapper<IndexedDeprecatedContractClass>, SimpleTable>;
 type CompiledClassesTable<'env> =
@@ -99,6 +120,12 @@ type ContractStorageTable<'env> = TableHandle<
 >;
 type NoncesTable<'env> =
     TableHandle<'env, (ContractAddress, BlockNumber), VersionZeroWrapper<Nonce>, SimpleTable>;
+type EntryPointsTable<'env> = TableHandle<
+    'env,
+    (ClassHash, EntryPointSelector),
+    VersionZeroWrapper<IndexedEntryPoint>,
+    SimpleTable,
+>;
 
 /// Interface for reading data related to the state.
 // Structure of state data:
@@ -118,14 +145,33 @@ type NoncesTable<'env> =
 //   block_num.
 // * nonces_table: (contract_address, block_num) -> (nonce). Specifies that at `block_num`, the
 //   nonce of `contract_address` was changed to `nonce`.
+// * entry_points_table: (class_hash, selector) -> (entry_point_type, location). Each entry is an
+//   entry point of a declared class, parsed from the class definition when the class is written.
+// * deferred_classes_table: (class_hash) -> (block_num). Each entry is a class that was declared at
+//   `block_num` by a state diff appended without class definitions, and whose definition wasn't
+//   appended yet. A deferred Cairo 1 class also has an entry in declared_classes_block_table, but
+//   not in declared_classes_table.
+// * state_diff_stats_table: (block_num) -> (state_diff_stats). The number of changes of each kind
+//   in the state diff of `block_num`.
 
 pub trait StateStorageReader<Mode: TransactionKind> {
     /// The state marker is the first block number that doesn't exist yet.
     fn get_state_marker(&self) -> StorageResult<BlockNumber>;
+    /// The pruned state marker is the first block whose state diff didn't leave the history in Full
+    /// mode. The values that the states before it need may have been pruned.
+    fn get_pruned_state_marker(&self) -> StorageResult<BlockNumber>;
     /// Returns the state diff at a given block number.
     fn get_state_diff(&self, block_number: BlockNumber) -> StorageResult<Option<ThinStateDiff>>;
+    /// Returns the number of changes of each kind in the state diff at a given block number.
+    fn get_state_diff_stats(
+        &self,
+        block_number: BlockNumber,
+    ) -> StorageResult<Option<StateDiffStats>>;
     /// Returns a state reader.
     fn get_state_reader(&self) -> StorageResult<StateReader<'_, Mode>>;
+    /// Returns an error if the state at the given state number isn't queryable in the node mode of
+    /// the storage, or if it's after a block whose state diff was quarantined.
+    fn verify_state_queryable(&self, state_number: StateNumber) -> StorageResult<()>;
 }
 
 type RevertedStateDiff = (
@@ -159,11 +205,44 @@ where
         deployed_contract_class_definitions: IndexMap<ClassHash, DeprecatedContractClass>,
     ) -> StorageResult<Self>;
 
+    /// Appends a state diff without the definitions of the classes it declares. The declared
+    /// classes are recorded as deferred, until their definitions are appended with
+    /// [`append_deferred_class`](StateStorageWriter::append_deferred_class) or
+    /// [`append_deferred_deprecated_class`](StateStorageWriter::append_deferred_deprecated_class).
+    /// Classes that were implicitly declared by deploying contracts, before Starknet version 0.11,
+    /// aren't recorded.
+    fn append_thin_state_diff(
+        self,
+        block_number: BlockNumber,
+        thin_state_diff: ThinStateDiff,
+    ) -> StorageResult<Self>;
+
+    /// Appends the definition of a deferred Cairo 1 class. Does nothing if the class isn't
+    /// deferred.
+    fn append_deferred_class(
+        self,
+        class_hash: &ClassHash,
+        contract_class: &ContractClass,
+    ) -> StorageResult<Self>;
+
+    /// Appends the definition of a deferred deprecated class. Does nothing if the class isn't
+    /// deferred.
+    fn append_deferred_deprecated_class(
+        self,
+        class_hash: &ClassHash,
+        deprecated_contract_class: DeprecatedContractClass,
+    ) -> StorageResult<Self>;
+
     /// Removes a state diff from the storage and returns the removed data.
     fn revert_state_diff(
         self,
         block_number: BlockNumber,
     ) -> StorageResult<(Self, Option<RevertedStateDiff>)>;
+
+    /// Prunes the values that are needed only to query the states before the given block, so that
+    /// the state right before it becomes the oldest queryable state. Pruning stops at the state
+    /// marker, and blocks that were already pruned are skipped. Supported only in Full mode.
+    fn prune_state_history_below(self, block_number: BlockNumber) -> StorageResult<Self>;
 }
 
 impl<'env, Mode: TransactionKind> StateStorageReader<Mode> for StorageTxn<'env, Mode> {
@@ -172,6 +251,10 @@ impl<'env, Mode: TransactionKind> StateStorageReader<Mode> for StorageTxn<'env,
         let markers_table = self.open_table(&self.tables.markers)?;
         Ok(markers_table.get(&self.txn, &MarkerKind::State)?.unwrap_or_default())
     }
+    fn get_pruned_state_marker(&self) -> StorageResult<BlockNumber> {
+        let markers_table = self.open_table(&self.tables.markers)?;
+        Ok(markers_table.get(&self.txn, &MarkerKind::PrunedState)?.unwrap_or_default())
+    }
     fn get_state_diff(&self, block_number: BlockNumber) -> StorageResult<Option<ThinStateDiff>> {
         let state_diffs_table = self.open_table(&self.tables.state_diffs)?;
         let state_diff_location = state_diffs_table.get(&self.txn, &block_number)?;
@@ -185,9 +268,47 @@ impl<'env, Mode: TransactionKind> StateStorageReader<Mode> for StorageTxn<'env,
         }
     }
 
+    fn get_state_diff_stats(
+        &self,
+        block_number: BlockNumber,
+    ) -> StorageResult<Option<StateDiffStats>> {
+        let state_diff_stats_table = self.open_table(&self.tables.state_diff_stats)?;
+        if let Some(state_diff_stats) = state_diff_stats_table.get(&self.txn, &block_number)? {
+            return Ok(Some(state_diff_stats));
+        }
+        // State diffs that were written before the stats were recorded have no stats stored.
+        Ok(self.get_state_diff(block_number)?.as_ref().map(StateDiffStats::from))
+    }
+
     fn get_state_reader(&self) -> StorageResult<StateReader<'_, Mode>> {
         StateReader::new(self)
     }
+
+    fn verify_state_queryable(&self, state_number: StateNumber) -> StorageResult<()> {
+        let queryable = match self.mode {
+            NodeMode::Archive => true,
+            // Only the state of the last full_mode_state_history blocks is queryable, and only
+            // if its values weren't pruned.
+            NodeMode::Full => {
+                state_number.0.0.saturating_add(self.full_mode_state_history)
+                    >= self.get_state_marker()?.0
+                    && state_number.0 >= self.get_pruned_state_marker()?
+            }
+            NodeMode::Light => false,
+        };
+        if !queryable {
+            return Err(StorageError::StateNotQueryable { state_number, node_mode: self.mode });
+        }
+        if let Some(quarantined_block_number) = self.get_first_quarantined_state_diff()? {
+            if quarantined_block_number < state_number.0 {
+                return Err(StorageError::StateQuarantined {
+                    state_number,
+                    quarantined_block_number,
+                });
+            }
+        }
+        Ok(())
+    }
 }
 
 /// A single coherent state at a single point in time,
@@ -195,11 +316,14 @@ pub struct StateReader<'env, Mode: TransactionKind> {
     txn: &'env DbTransaction<'env, Mode>,
     declared_classes_table: DeclaredClassesTable<'env>,
     declared_classes_block_table: DeclaredClassesBlockTable<'env>,
+    deferred_classes_table: DeferredClassesTable<'env>,
     deprecated_declared_classes_table: DeprecatedDeclaredClassesTable<'env>,
     deployed_contracts_table: DeployedContractsTable<'env>,
     nonces_table: NoncesTable<'env>,
     storage_table: ContractStorageTable<'env>,
+    entry_points_table: EntryPointsTable<'env>,
     file_handlers: &'env FileHandlers<Mode>,
+    class_cache: &'env ClassCache,
 }
 
 #[allow(dead_code)]
@@ -217,20 +341,25 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
         let declared_classes_table = txn.txn.open_table(&txn.tables.declared_classes)?;
         let declared_classes_block_table =
             txn.txn.open_table(&txn.tables.declared_classes_block)?;
+        let deferred_classes_table = txn.txn.open_table(&txn.tables.deferred_classes)?;
         let deprecated_declared_classes_table =
             txn.txn.open_table(&txn.tables.deprecated_declared_classes)?;
         let deployed_contracts_table = txn.txn.open_table(&txn.tables.deployed_contracts)?;
         let nonces_table = txn.txn.open_table(&txn.tables.nonces)?;
         let storage_table = txn.txn.open_table(&txn.tables.contract_storage)?;
+        let entry_points_table = txn.txn.open_table(&txn.tables.entry_points)?;
         Ok(StateReader {
             txn: &txn.txn,
             declared_classes_table,
             declared_classes_block_table,
+            deferred_classes_table,
             deprecated_declared_classes_table,
             deployed_contracts_table,
             nonces_table,
             storage_table,
+            entry_points_table,
             file_handlers: &txn.file_handlers,
+            class_cache: &txn.class_cache,
         })
     }
 
@@ -335,6 +464,43 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
         }
     }
 
+    /// Returns the values written to a given key of a given contract by the blocks in the given
+    /// range (both ends included), in the order of the blocks, up to the given number of writes.
+    ///
+    /// # Arguments
+    /// * address - contract address to search for.
+    /// * key - key to search for.
+    /// * from_block_number - the first block to search in.
+    /// * to_block_number - the last block to search in.
+    /// * limit - the maximal number of writes to return.
+    ///
+    /// # Errors
+    /// Returns [`StorageError`] if there was an error searching the table.
+    pub fn get_storage_history(
+        &self,
+        address: &ContractAddress,
+        key: &StorageKey,
+        from_block_number: BlockNumber,
+        to_block_number: BlockNumber,
+        limit: usize,
+    ) -> StorageResult<Vec<(BlockNumber, StarkFelt)>> {
+        let mut cursor = self.storage_table.cursor(self.txn)?;
+        let mut current = cursor.lower_bound(&(*address, *key, from_block_number))?;
+        let mut history = vec![];
+        while let Some(((got_address, got_key, block_number), value)) = current {
+            if got_address != *address
+                || got_key != *key
+                || block_number > to_block_number
+                || history.len() == limit
+            {
+                break;
+            }
+            history.push((block_number, value));
+            current = cursor.next()?;
+        }
+        Ok(history)
+    }
+
     /// Returns the class definition at a given state number.
     ///
     /// If class_hash is not found, returns `None`.
@@ -348,7 +514,8 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
     /// Returns [`StorageError`] if there was an error searching the table.
     ///
     /// Returns [`StorageError`]::DBInconsistency if the block number found for the class hash but
-    /// the contract class was not found.
+    /// the contract class was not found, unless the class is deferred, in which case returns
+    /// `None`.
     pub fn get_class_definition_at(
         &self,
         state_number: StateNumber,
@@ -364,13 +531,19 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
         let Some(contract_class_location) =
             self.declared_classes_table.get(self.txn, class_hash)?
         else {
+            if self.deferred_classes_table.get(self.txn, class_hash)?.is_some() {
+                return Ok(None);
+            }
             return Err(StorageError::DBInconsistency {
                 msg: "block number found in declared_classes_block_table but contract class is \
                       not found in declared_classes_table."
                     .to_string(),
             });
         };
-        Ok(Some(self.file_handlers.get_contract_class_unchecked(contract_class_location)?))
+        let class = self.class_cache.get_or_read(class_hash, || {
+            self.file_handlers.get_contract_class_unchecked(contract_class_location)
+        })?;
+        Ok(Some(ContractClass::clone(&class)))
     }
 
     /// Returns the block number for a given class hash (the block in which it was defined).
@@ -388,6 +561,58 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
         Ok(self.declared_classes_block_table.get(self.txn, class_hash)?)
     }
 
+    /// Returns the block number in which a class whose definition wasn't appended yet was
+    /// declared, or `None` if the class isn't deferred.
+    ///
+    /// # Arguments
+    /// * class_hash - class hash to search for.
+    ///
+    /// # Errors
+    /// Returns [`StorageError`] if there was an error searching the table.
+    pub fn get_deferred_class_block_number(
+        &self,
+        class_hash: &ClassHash,
+    ) -> StorageResult<Option<BlockNumber>> {
+        Ok(self.deferred_classes_table.get(self.txn, class_hash)?)
+    }
+
+    /// Returns the entry point of a declared class with the given selector, of either a Cairo 1
+    /// class or a deprecated class. The entry points are indexed regardless of the state number, so
+    /// if the class is not declared or has no such entry point, returns `None`.
+    ///
+    /// # Arguments
+    /// * class_hash - class hash of the class.
+    /// * selector - selector of the entry point.
+    ///
+    /// # Errors
+    /// Returns [`StorageError`] if there was an error searching the table.
+    pub fn get_entry_point(
+        &self,
+        class_hash: &ClassHash,
+        selector: &EntryPointSelector,
+    ) -> StorageResult<Option<IndexedEntryPoint>> {
+        if let Some(entry_point) =
+            self.entry_points_table.get(self.txn, &(*class_hash, *selector))?
+        {
+            return Ok(Some(entry_point));
+        }
+        // Classes that were written before the entry points were indexed have no entry points
+        // stored, so their entry points are taken from their definitions.
+        let latest_state_number = StateNumber(BlockNumber(u64::MAX));
+        let entry_point = if let Some(contract_class) =
+            self.get_class_definition_at(latest_state_number, class_hash)?
+        {
+            find_entry_point(class_entry_points(&contract_class), selector)
+        } else if let Some(deprecated_contract_class) =
+            self.get_deprecated_class_definition_at(latest_state_number, class_hash)?
+        {
+            find_entry_point(deprecated_class_entry_points(&deprecated_contract_class), selector)
+        } else {
+            None
+        };
+        Ok(entry_point)
+    }
+
     // Returns the deprecated contract class at a given state number for a given class hash.
     /// If class is not found, returns `None`.
     /// If class is defined but in a block after given state number, returns `None`.
@@ -409,9 +634,10 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
         if state_number.is_before(value.block_number) {
             return Ok(None);
         }
-        Ok(Some(
-            self.file_handlers.get_deprecated_contract_class_unchecked(value.location_in_file)?,
-        ))
+        let class = self.class_cache.get_or_read(class_hash, || {
+            self.file_handlers.get_deprecated_contract_class_unchecked(value.location_in_file)
+        })?;
+        Ok(Some(DeprecatedContractClass::clone(&class)))
     }
 }
 
@@ -432,6 +658,8 @@ impl<'env> StateStorageWriter for StorageTxn<'env, RW> {
             self.open_table(&self.tables.deprecated_declared_classes)?;
         let storage_table = self.open_table(&self.tables.contract_storage)?;
         let state_diffs_table = self.open_table(&self.tables.state_diffs)?;
+        let state_diff_stats_table = self.open_table(&self.tables.state_diff_stats)?;
+        let entry_points_table = self.open_table(&self.tables.entry_points)?;
         let file_offset_table = self.txn.open_table(&self.tables.file_offsets)?;
 
         update_marker(&self.txn, &markers_table, block_number)?;
@@ -459,6 +687,11 @@ impl<'env> StateStorageWriter for StorageTxn<'env, RW> {
         let location = self.file_handlers.append_thin_state_diff(&thin_state_diff);
         state_diffs_table.insert(&self.txn, &block_number, &location)?;
         file_offset_table.upsert(&self.txn, &OffsetKind::ThinStateDiff, &location.next_offset())?;
+        state_diff_stats_table.insert(
+            &self.txn,
+            &block_number,
+            &StateDiffStats::from(&thin_state_diff),
+        )?;
 
         // Write declared classes.
         write_declared_classes(
@@ -467,6 +700,7 @@ impl<'env> StateStorageWriter for StorageTxn<'env, RW> {
             &declared_classes_table,
             block_number,
             &declared_classes_block_table,
+            &entry_points_table,
             &self.file_handlers,
             &file_offset_table,
         )?;
@@ -492,6 +726,7 @@ impl<'env> StateStorageWriter for StorageTxn<'env, RW> {
                 &self.txn,
                 block_number,
                 &deprecated_declared_classes_table,
+                &entry_points_table,
                 &self.file_handlers,
                 &file_offset_table,
             )?;
@@ -501,14 +736,145 @@ impl<'env> StateStorageWriter for StorageTxn<'env, RW> {
                 &self.txn,
                 block_number,
                 &deprecated_declared_classes_table,
+                &entry_points_table,
                 &self.file_handlers,
                 &file_offset_table,
             )?;
         }
+        record_class_usage(&self)?;
+        prune_state_history(&self, block_number)?;
 
         Ok(self)
     }
 
+    #[latency_histogram("storage_append_thin_state_diff_latency_seconds")]
+    fn append_thin_state_diff(
+        self,
+        block_number: BlockNumber,
+        thin_state_diff: ThinStateDiff,
+    ) -> StorageResult<Self> {
+        let markers_table = self.open_table(&self.tables.markers)?;
+        let nonces_table = self.open_table(&self.tables.nonces)?;
+        let deployed_contracts_table = self.open_table(&self.tables.deployed_contracts)?;
+        let declared_classes_block_table = self.open_table(&self.tables.declared_classes_block)?;
+        let deferred_classes_table = self.open_table(&self.tables.deferred_classes)?;
+        let deprecated_declared_classes_table =
+            self.open_table(&self.tables.deprecated_declared_classes)?;
+        let storage_table = self.open_table(&self.tables.contract_storage)?;
+        let state_diffs_table = self.open_table(&self.tables.state_diffs)?;
+        let state_diff_stats_table = self.open_table(&self.tables.state_diff_stats)?;
+        let file_offset_table = self.txn.open_table(&self.tables.file_offsets)?;
+
+        update_marker(&self.txn, &markers_table, block_number)?;
+
+        write_deployed_contracts(
+            &thin_state_diff.deployed_contracts,
+            &self.txn,
+            block_number,
+            &deployed_contracts_table,
+            &nonces_table,
+        )?;
+        write_storage_diffs(
+            &thin_state_diff.storage_diffs,
+            &self.txn,
+            block_number,
+            &storage_table,
+        )?;
+        write_nonces(&thin_state_diff.nonces, &self.txn, block_number, &nonces_table)?;
+        write_replaced_classes(
+            &thin_state_diff.replaced_classes,
+            &self.txn,
+            block_number,
+            &deployed_contracts_table,
+        )?;
+
+        // Record the declared classes as deferred.
+        for class_hash in thin_state_diff.declared_classes.keys() {
+            declared_classes_block_table.insert(&self.txn, class_hash, &block_number)?;
+            deferred_classes_table.insert(&self.txn, class_hash, &block_number)?;
+        }
+        for class_hash in &thin_state_diff.deprecated_declared_classes {
+            // A deprecated class may be declared again, and only its first declaration counts.
+            if deprecated_declared_classes_table.get(&self.txn, class_hash)?.is_some()
+                || deferred_classes_table.get(&self.txn, class_hash)?.is_some()
+            {
+                continue;
+            }
+            deferred_classes_table.insert(&self.txn, class_hash, &block_number)?;
+        }
+
+        let location = self.file_handlers.append_thin_state_diff(&thin_state_diff);
+        state_diffs_table.insert(&self.txn, &block_number, &location)?;
+        file_offset_table.upsert(&self.txn, &OffsetKind::ThinStateDiff, &location.next_offset())?;
+        state_diff_stats_table.insert(
+            &self.txn,
+            &block_number,
+            &StateDiffStats::from(&thin_state_diff),
+        )?;
+
+        update_compiled_class_marker(
+            &self.txn,
+            &markers_table,
+            &state_diffs_table,
+            &self.file_handlers,
+        )?;
+        record_class_usage(&self)?;
+        prune_state_history(&self, block_number)?;
+
+        Ok(self)
+    }
+
+    fn append_deferred_class(
+        self,
+        class_hash: &ClassHash,
+        contract_class: &ContractClass,
+    ) -> StorageResult<Self> {
+        let declared_classes_table = self.open_table(&self.tables.declared_classes)?;
+        let deferred_classes_table = self.open_table(&self.tables.deferred_classes)?;
+        let entry_points_table = self.open_table(&self.tables.entry_points)?;
+        let file_offset_table = self.txn.open_table(&self.tables.file_offsets)?;
+
+        if deferred_classes_table.get(&self.txn, class_hash)?.is_none() {
+            return Ok(self);
+        }
+        // The block of the class was recorded when the state diff was appended.
+        let location = self.file_handlers.append_contract_class(contract_class);
+        declared_classes_table.insert(&self.txn, class_hash, &location)?;
+        for (selector, entry_point) in class_entry_points(contract_class) {
+            entry_points_table.insert(&self.txn, &(*class_hash, selector), &entry_point)?;
+        }
+        file_offset_table.upsert(&self.txn, &OffsetKind::ContractClass, &location.next_offset())?;
+        deferred_classes_table.delete(&self.txn, class_hash)?;
+        Ok(self)
+    }
+
+    fn append_deferred_deprecated_class(
+        self,
+        class_hash: &ClassHash,
+        deprecated_contract_class: DeprecatedContractClass,
+    ) -> StorageResult<Self> {
+        let deprecated_declared_classes_table =
+            self.open_table(&self.tables.deprecated_declared_classes)?;
+        let deferred_classes_table = self.open_table(&self.tables.deferred_classes)?;
+        let entry_points_table = self.open_table(&self.tables.entry_points)?;
+        let file_offset_table = self.txn.open_table(&self.tables.file_offsets)?;
+
+        let Some(block_number) = deferred_classes_table.get(&self.txn, class_hash)? else {
+            return Ok(self);
+        };
+        write_deprecated_declared_classes(
+            IndexMap::from([(*class_hash, deprecated_contract_class)]),
+            &self.txn,
+            block_number,
+            &deprecated_declared_classes_table,
+            &entry_points_table,
+            &self.file_handlers,
+            &file_offset_table,
+        )?;
+        deferred_classes_table.delete(&self.txn, class_hash)?;
+        Ok(self)
+    }
+
     fn revert_state_diff(
         self,
         block_number: BlockNumber,
@@ -516,6 +882,7 @@ impl<'env> StateStorageWriter for StorageTxn<'env, RW> {
         let markers_table = self.open_table(&self.tables.markers)?;
         let declared_classes_table = self.open_table(&self.tables.declared_classes)?;
         let declared_classes_block_table = self.open_table(&self.tables.declared_classes_block)?;
+        let deferred_classes_table = self.open_table(&self.tables.deferred_classes)?;
         let deprecated_declared_classes_table =
             self.open_table(&self.tables.deprecated_declared_classes)?;
         // TODO(yair): Consider reverting the compiled classes in their own module.
@@ -524,6 +891,8 @@ impl<'env> StateStorageWriter for StorageTxn<'env, RW> {
         let nonces_table = self.open_table(&self.tables.nonces)?;
         let storage_table = self.open_table(&self.tables.contract_storage)?;
         let state_diffs_table = self.open_table(&self.tables.state_diffs)?;
+        let state_diff_stats_table = self.open_table(&self.tables.state_diff_stats)?;
+        let entry_points_table = self.open_table(&self.tables.entry_points)?;
 
         let current_state_marker = self.get_state_marker()?;
 
@@ -537,6 +906,11 @@ impl<'env> StateStorageWriter for StorageTxn<'env, RW> {
             return Ok((self, None));
         }
 
+        // The values that the state diff overwrote may have been pruned.
+        if block_number < self.get_pruned_state_marker()? {
+            return Err(StorageError::PrunedStateRevert { block_number });
+        }
+
         let thin_state_diff = self
             .get_state_diff(block_number)?
             .unwrap_or_else(|| panic!("Missing state diff for block {block_number}."));
@@ -546,11 +920,19 @@ impl<'env> StateStorageWriter for StorageTxn<'env, RW> {
         if compiled_classes_marker == block_number.next() {
             markers_table.upsert(&self.txn, &MarkerKind::CompiledClass, &block_number)?;
         }
+        let deferred_classes = delete_deferred_classes(
+            &self.txn,
+            block_number,
+            &thin_state_diff,
+            &deferred_classes_table,
+        )?;
         let deleted_classes = delete_declared_classes(
             &self.txn,
             &thin_state_diff,
+            &deferred_classes,
             &declared_classes_table,
             &declared_classes_block_table,
+            &entry_points_table,
             &self.file_handlers,
         )?;
         let deleted_deprecated_classes = delete_deprecated_declared_classes(
@@ -558,6 +940,7 @@ impl<'env> StateStorageWriter for StorageTxn<'env, RW> {
             block_number,
             &thin_state_diff,
             &deprecated_declared_classes_table,
+            &entry_points_table,
             &self.file_handlers,
         )?;
         let deleted_compiled_classes = delete_compiled_classes(
@@ -566,6 +949,12 @@ impl<'env> StateStorageWriter for StorageTxn<'env, RW> {
             &compiled_classes_table,
             &self.file_handlers,
         )?;
+        self.class_cache.remove(
+            thin_state_diff
+                .declared_classes
+                .keys()
+                .chain(thin_state_diff.deprecated_declared_classes.iter()),
+        );
         delete_deployed_contracts(
             &self.txn,
             block_number,
@@ -576,6 +965,8 @@ impl<'env> StateStorageWriter for StorageTxn<'env, RW> {
         delete_storage_diffs(&self.txn, block_number, &thin_state_diff, &storage_table)?;
         delete_nonces(&self.txn, block_number, &thin_state_diff, &nonces_table)?;
         state_diffs_table.delete(&self.txn, &block_number)?;
+        state_diff_stats_table.delete(&self.txn, &block_number)?;
+        delete_class_usage(&self, block_number)?;
         delete_replaced_classes(
             &self.txn,
             block_number,
@@ -593,6 +984,112 @@ impl<'env> StateStorageWriter for StorageTxn<'env, RW> {
             )),
         ))
     }
+
+    fn prune_state_history_below(self, block_number: BlockNumber) -> StorageResult<Self> {
+        if self.mode != NodeMode::Full {
+            return Err(StorageError::StatePruningUnsupported { node_mode: self.mode });
+        }
+        // The values of a block are pruned by its state diff, so only stored blocks are pruned.
+        let below = block_number.min(self.get_state_marker()?);
+        let mut pruned_block_number = self.get_pruned_state_marker()?;
+        while pruned_block_number < below {
+            prune_block_state(&self, pruned_block_number)?;
+            pruned_block_number = pruned_block_number.next();
+        }
+        Ok(self)
+    }
+}
+
+// In Full mode, deletes the values that are no longer needed to answer queries on the queryable
+// states. The state right after the block that just left the history is the oldest that stays
+// queryable, so the values that this block overwrote are no longer needed.
+fn prune_state_history(
+    txn: &StorageTxn<'_, RW>,
+    appended_block_number: BlockNumber,
+) -> StorageResult<()> {
+    if txn.mode != NodeMode::Full {
+        return Ok(());
+    }
+    let Some(pruned_block_number) =
+        appended_block_number.0.checked_sub(txn.full_mode_state_history).map(BlockNumber)
+    else {
+        return Ok(());
+    };
+    // The block may have been pruned already by an explicit request to prune further.
+    if pruned_block_number < txn.get_pruned_state_marker()? {
+        return Ok(());
+    }
+    prune_block_state(txn, pruned_block_number)
+}
+
+// Deletes the values that the given block overwrote, and marks the block as pruned.
+fn prune_block_state(
+    txn: &StorageTxn<'_, RW>,
+    pruned_block_number: BlockNumber,
+) -> StorageResult<()> {
+    let thin_state_diff =
+        txn.get_state_diff(pruned_block_number)?.ok_or_else(|| StorageError::DBInconsistency {
+            msg: format!("Missing state diff for block {pruned_block_number}."),
+        })?;
+    let markers_table = txn.open_table(&txn.tables.markers)?;
+    let storage_table = txn.open_table(&txn.tables.contract_storage)?;
+    let nonces_table = txn.open_table(&txn.tables.nonces)?;
+    let deployed_contracts_table = txn.open_table(&txn.tables.deployed_contracts)?;
+
+    let mut overwritten_storage = vec![];
+    let mut cursor = storage_table.cursor(&txn.txn)?;
+    for (address, storage_entries) in &thin_state_diff.storage_diffs {
+        for key in storage_entries.keys() {
+            let mut current = cursor.lower_bound(&(*address, *key, BlockNumber(0)))?;
+            while let Some(((got_address, got_key, block_number), _)) = current {
+                if got_address != *address || got_key != *key || block_number >= pruned_block_number
+                {
+                    break;
+                }
+                overwritten_storage.push((got_address, got_key, block_number));
+                current = cursor.next()?;
+            }
+        }
+    }
+    for db_key in overwritten_storage {
+        storage_table.delete(&txn.txn, &db_key)?;
+    }
+
+    // A deployed contract is written a zero nonce.
+    let mut overwritten_nonces = vec![];
+    let mut cursor = nonces_table.cursor(&txn.txn)?;
+    for address in thin_state_diff.nonces.keys().chain(thin_state_diff.deployed_contracts.keys()) {
+        let mut current = cursor.lower_bound(&(*address, BlockNumber(0)))?;
+        while let Some(((got_address, block_number), _)) = current {
+            if got_address != *address || block_number >= pruned_block_number {
+                break;
+            }
+            overwritten_nonces.push((got_address, block_number));
+            current = cursor.next()?;
+        }
+    }
+    for db_key in overwritten_nonces {
+        nonces_table.delete(&txn.txn, &db_key)?;
+    }
+
+    let mut overwritten_class_hashes = vec![];
+    let mut cursor = deployed_contracts_table.cursor(&txn.txn)?;
+    for address in thin_state_diff.replaced_classes.keys() {
+        let mut current = cursor.lower_bound(&(*address, BlockNumber(0)))?;
+        while let Some(((got_address, block_number), _)) = current {
+            if got_address != *address || block_number >= pruned_block_number {
+                break;
+            }
+            overwritten_class_hashes.push((got_address, block_number));
+            current = cursor.next()?;
+        }
+    }
+    for db_key in overwritten_class_hashes {
+        deployed_contracts_table.delete(&txn.txn, &db_key)?;
+    }
+
+    markers_table.upsert(&txn.txn, &MarkerKind::PrunedState, &pruned_block_number.next())?;
+    Ok(())
 }
 
 fn update_marker<'env>(
@@ -649,6 +1146,7 @@ fn write_declared_classes<'env>(
     declared_classes_table: &'env DeclaredClassesTable<'env>,
     block_number: BlockNumber,
     declared_classes_block_table: &'env DeclaredClassesBlockTable<'env>,
+    entry_points_table: &'env EntryPointsTable<'env>,
     file_handlers: &FileHandlers<RW>,
     file_offset_table: &'env FileOffsetTable<'env>,
 ) -> StorageResult<()> {
@@ -656,6 +1154,9 @@ fn write_declared_classes<'env>(
         let location = file_handlers.append_contract_class(contract_class);
         declared_classes_table.insert(txn, class_hash, &location)?;
         declared_classes_block_table.insert(txn, class_hash, &block_number)?;
+        for (selector, entry_point) in class_entry_points(contract_class) {
+            entry_points_table.insert(txn, &(*class_hash, selector), &entry_point)?;
+        }
         file_offset_table.upsert(txn, &OffsetKind::ContractClass, &location.next_offset())?;
     }
     Ok(())
@@ -666,6 +1167,7 @@ fn write_deprecated_declared_classes<'env>(
     txn: &DbTransaction<'env, RW>,
     block_number: BlockNumber,
     deprecated_declared_classes_table: &'env DeprecatedDeclaredClassesTable<'env>,
+    entry_points_table: &'env EntryPointsTable<'env>,
     file_handlers: &FileHandlers<RW>,
     file_offset_table: &'env FileOffsetTable<'env>,
 ) -> StorageResult<()> {
@@ -673,6 +1175,9 @@ fn write_deprecated_declared_classes<'env>(
         if deprecated_declared_classes_table.get(txn, &class_hash)?.is_some() {
             continue;
         }
+        for (selector, entry_point) in deprecated_class_entry_points(&deprecated_contract_class) {
+            entry_points_table.insert(txn, &(class_hash, selector), &entry_point)?;
+        }
         let location = file_handlers.append_deprecated_contract_class(&deprecated_contract_class);
         let value = IndexedDeprecatedContractClass { block_number, location_in_file: location };
         file_offset_table.upsert(
@@ -685,6 +1190,57 @@ fn write_deprecated_declared_classes<'env>(
     Ok(())
 }
 
+// Returns the entry points of a Cairo 1 class by their selectors.
+fn class_entry_points(
+    contract_class: &ContractClass,
+) -> impl Iterator<Item = (EntryPointSelector, IndexedEntryPoint)> + '_ {
+    contract_class.entry_points_by_type.iter().flat_map(|(entry_point_type, entry_points)| {
+        entry_points.iter().map(move |entry_point| {
+            (
+                entry_point.selector,
+                IndexedEntryPoint {
+                    entry_point_type: *entry_point_type,
+                    location: EntryPointLocation::FunctionIndex(entry_point.function_idx),
+                },
+            )
+        })
+    })
+}
+
+// Returns the entry points of a deprecated class by their selectors.
+fn deprecated_class_entry_points(
+    deprecated_contract_class: &DeprecatedContractClass,
+) -> impl Iterator<Item = (EntryPointSelector, IndexedEntryPoint)> + '_ {
+    deprecated_contract_class.entry_points_by_type.iter().flat_map(
+        |(entry_point_type, entry_points)| {
+            let entry_point_type = match entry_point_type {
+                DeprecatedEntryPointType::Constructor => EntryPointType::Constructor,
+                DeprecatedEntryPointType::External => EntryPointType::External,
+                DeprecatedEntryPointType::L1Handler => EntryPointType::L1Handler,
+            };
+            entry_points.iter().map(move |entry_point| {
+                (
+                    entry_point.selector,
+                    IndexedEntryPoint {
+                        entry_point_type,
+                        location: EntryPointLocation::Offset(entry_point.offset),
+                    },
+                )
+            })
+        },
+    )
+}
+
+// Returns the entry point with the given selector out of the entry points of a class.
+fn find_entry_point(
+    mut entry_points: impl Iterator<Item = (EntryPointSelector, IndexedEntryPoint)>,
+    selector: &EntryPointSelector,
+) -> Option<IndexedEntryPoint> {
+    entry_points
+        .find(|(entry_point_selector, _)| entry_point_selector == selector)
+        .map(|(_, entry_point)| entry_point)
+}
+
 fn write_deployed_contracts<'env>(
     deployed_contracts: &IndexMap<ContractAddress, ClassHash>,
     txn: &DbTransaction<'env, RW>,
@@ -748,22 +1304,52 @@ fn write_storage_diffs<'env>(
     Ok(())
 }
 
+// Deletes the classes of the state diff that are still deferred, and returns their hashes.
+fn delete_deferred_classes<'env>(
+    txn: &'env DbTransaction<'env, RW>,
+    block_number: BlockNumber,
+    thin_state_diff: &ThinStateDiff,
+    deferred_classes_table: &'env DeferredClassesTable<'env>,
+) -> StorageResult<HashSet<ClassHash>> {
+    let mut deleted_class_hashes = HashSet::new();
+    for class_hash in thin_state_diff
+        .declared_classes
+        .keys()
+        .chain(thin_state_diff.deprecated_declared_classes.iter())
+    {
+        // A deprecated class that was declared in a previous block stays deferred.
+        if deferred_classes_table.get(txn, class_hash)? == Some(block_number) {
+            deferred_classes_table.delete(txn, class_hash)?;
+            deleted_class_hashes.insert(*class_hash);
+        }
+    }
+    Ok(deleted_class_hashes)
+}
+
 fn delete_declared_classes<'env>(
     txn: &'env DbTransaction<'env, RW>,
     thin_state_diff: &ThinStateDiff,
+    deferred_classes: &HashSet<ClassHash>,
     declared_classes_table: &'env DeclaredClassesTable<'env>,
     declared_classes_block_table: &'env DeclaredClassesBlockTable<'env>,
+    entry_points_table: &'env EntryPointsTable<'env>,
     file_handlers: &FileHandlers<RW>,
 ) -> StorageResult<IndexMap<ClassHash, ContractClass>> {
     let mut deleted_data = IndexMap::new();
     for class_hash in thin_state_diff.declared_classes.keys() {
+        // The definition of a deferred class was never appended.
+        if deferred_classes.contains(class_hash) {
+            declared_classes_block_table.delete(txn, class_hash)?;
+            continue;
+        }
         let contract_class_location = declared_classes_table
             .get(txn, class_hash)?
             .unwrap_or_else(|| panic!("Missing declared class {class_hash:#?}."));
-        deleted_data.insert(
-            *class_hash,
-            file_handlers.get_contract_class_unchecked(contract_class_location)?,
-        );
+        let contract_class = file_handlers.get_contract_class_unchecked(contract_class_location)?;
+        for (selector, _entry_point) in class_entry_points(&contract_class) {
+            entry_points_table.delete(txn, &(*class_hash, selector))?;
+        }
+        deleted_data.insert(*class_hash, contract_class);
         declared_classes_table.delete(txn, class_hash)?;
         declared_classes_block_table.delete(txn, class_hash)?;
     }
@@ -776,6 +1362,7 @@ fn delete_deprecated_declared_classes<'env>(
     block_number: BlockNumber,
     thin_state_diff: &ThinStateDiff,
     deprecated_declared_classes_table: &'env DeprecatedDeclaredClassesTable<'env>,
+    entry_points_table: &'env EntryPointsTable<'env>,
     file_handlers: &FileHandlers<RW>,
 ) -> StorageResult<IndexMap<ClassHash, DeprecatedContractClass>> {
     // Class hashes of the contracts that were deployed in this block.
@@ -802,10 +1389,14 @@ fn delete_deprecated_declared_classes<'env>(
         {
             // If the class was declared in a different block then we should'nt delete it.
             if block_number == declared_block_number {
-                deleted_data.insert(
-                    *class_hash,
-                    file_handlers.get_deprecated_contract_class_unchecked(location_in_file)?,
-                );
+                let deprecated_contract_class =
+                    file_handlers.get_deprecated_contract_class_unchecked(location_in_file)?;
+                for (selector, _entry_point) in
+                    deprecated_class_entry_points(&deprecated_contract_class)
+                {
+                    entry_points_table.delete(txn, &(*class_hash, selector))?;
+                }
+                deleted_data.insert(*class_hash, deprecated_contract_class);
                 deprecated_declared_classes_table.delete(txn, class_hash)?;
             }
         }
@@ -823,9 +1414,10 @@ fn delete_compiled_classes<'a, 'env>(
     let mut deleted_data = IndexMap::new();
     for class_hash in class_hashes {
         let Some(compiled_class_location) = compiled_classes_table.get(txn, class_hash)?
-        // No compiled class means the rest of the compiled classes weren't downloaded yet.
+        // No compiled class means that it wasn't downloaded yet, or that the class is deferred and
+        // it wasn't requested.
         else {
-            break;
+            continue;
         };
         compiled_classes_table.delete(txn, class_hash)?;
         deleted_data
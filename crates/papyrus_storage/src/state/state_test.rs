@@ -3,17 +3,40 @@ use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 use indexmap::{indexmap, IndexMap};
 use pretty_assertions::assert_eq;
 use starknet_api::block::BlockNumber;
-use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce, PatriciaKey};
-use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
+use starknet_api::core::{
+    ClassHash,
+    CompiledClassHash,
+    ContractAddress,
+    EntryPointSelector,
+    Nonce,
+    PatriciaKey,
+};
+use starknet_api::deprecated_contract_class::{
+    ContractClass as DeprecatedContractClass,
+    EntryPoint as DeprecatedEntryPoint,
+    EntryPointOffset,
+    EntryPointType as DeprecatedEntryPointType,
+};
 use starknet_api::hash::{StarkFelt, StarkHash};
-use starknet_api::state::{ContractClass, StateDiff, StateNumber, StorageKey, ThinStateDiff};
+use starknet_api::state::{
+    ContractClass,
+    EntryPoint,
+    EntryPointType,
+    FunctionIndex,
+    StateDiff,
+    StateNumber,
+    StorageKey,
+    ThinStateDiff,
+};
 use starknet_api::{patricia_key, stark_felt};
 use test_utils::get_test_state_diff;
 
 use crate::compiled_class::{CasmStorageReader, CasmStorageWriter};
+use crate::db::table_types::Table;
+use crate::state::data::{EntryPointLocation, IndexedEntryPoint, StateDiffStats};
 use crate::state::{StateStorageReader, StateStorageWriter};
-use crate::test_utils::get_test_storage;
-use crate::StorageWriter;
+use crate::test_utils::{get_test_config, get_test_storage};
+use crate::{open_storage, NodeMode, StorageError, StorageWriter};
 
 #[test]
 fn append_state_diff_declared_classes() {
@@ -83,6 +106,195 @@ fn append_state_diff_declared_classes() {
     assert_eq!(statetxn.get_class_definition_block_number(&nc1).unwrap(), Some(BlockNumber(1)));
 }
 
+#[test]
+fn append_thin_state_diff_defers_classes() {
+    let deprecated_class_hash = ClassHash(stark_felt!("0x00"));
+    let class_hash = ClassHash(stark_felt!("0x10"));
+    let thin_state_diff = ThinStateDiff {
+        deprecated_declared_classes: vec![deprecated_class_hash],
+        declared_classes: IndexMap::from([(class_hash, CompiledClassHash::default())]),
+        ..Default::default()
+    };
+    let state_number = StateNumber::right_after_block(BlockNumber(0));
+
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_thin_state_diff(BlockNumber(0), thin_state_diff.clone())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_state_marker().unwrap(), BlockNumber(1));
+    assert_eq!(txn.get_state_diff(BlockNumber(0)).unwrap(), Some(thin_state_diff));
+    let state_reader = txn.get_state_reader().unwrap();
+    for hash in [deprecated_class_hash, class_hash] {
+        assert_eq!(
+            state_reader.get_deferred_class_block_number(&hash).unwrap(),
+            Some(BlockNumber(0))
+        );
+    }
+    assert_eq!(state_reader.get_class_definition_at(state_number, &class_hash).unwrap(), None);
+    assert_eq!(
+        state_reader.get_class_definition_block_number(&class_hash).unwrap(),
+        Some(BlockNumber(0))
+    );
+    drop(txn);
+
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_deferred_class(&class_hash, &ContractClass::default())
+        .unwrap()
+        .append_deferred_deprecated_class(
+            &deprecated_class_hash,
+            DeprecatedContractClass::default(),
+        )
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    let state_reader = txn.get_state_reader().unwrap();
+    for hash in [deprecated_class_hash, class_hash] {
+        assert_eq!(state_reader.get_deferred_class_block_number(&hash).unwrap(), None);
+    }
+    assert_eq!(
+        state_reader.get_class_definition_at(state_number, &class_hash).unwrap(),
+        Some(ContractClass::default())
+    );
+    assert_eq!(
+        state_reader
+            .get_deprecated_class_definition_at(state_number, &deprecated_class_hash)
+            .unwrap(),
+        Some(DeprecatedContractClass::default())
+    );
+}
+
+#[test]
+fn revert_thin_state_diff_with_deferred_classes() {
+    let class_hash = ClassHash(stark_felt!("0x10"));
+    let thin_state_diff = ThinStateDiff {
+        deprecated_declared_classes: vec![ClassHash(stark_felt!("0x00"))],
+        declared_classes: IndexMap::from([(class_hash, CompiledClassHash::default())]),
+        ..Default::default()
+    };
+
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_thin_state_diff(BlockNumber(0), thin_state_diff.clone())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let (txn, reverted_state_diff) =
+        writer.begin_rw_txn().unwrap().revert_state_diff(BlockNumber(0)).unwrap();
+    txn.commit().unwrap();
+    let (reverted_thin_state_diff, reverted_classes, reverted_deprecated_classes, _) =
+        reverted_state_diff.unwrap();
+    assert_eq!(reverted_thin_state_diff, thin_state_diff);
+    // The definitions of the deferred classes were never appended.
+    assert!(reverted_classes.is_empty());
+    assert!(reverted_deprecated_classes.is_empty());
+
+    let txn = reader.begin_ro_txn().unwrap();
+    let state_reader = txn.get_state_reader().unwrap();
+    for hash in thin_state_diff.deprecated_declared_classes.iter().chain([&class_hash]) {
+        assert_eq!(state_reader.get_deferred_class_block_number(hash).unwrap(), None);
+    }
+    assert_eq!(state_reader.get_class_definition_block_number(&class_hash).unwrap(), None);
+}
+
+#[test]
+fn entry_points_of_declared_classes() {
+    let selector = EntryPointSelector(stark_felt!("0x1234"));
+    let class_hash = ClassHash(stark_felt!("0x10"));
+    let mut class = ContractClass::default();
+    class.entry_points_by_type.insert(
+        EntryPointType::External,
+        vec![EntryPoint { function_idx: FunctionIndex(7), selector }],
+    );
+    let deprecated_class_hash = ClassHash(stark_felt!("0x20"));
+    let mut deprecated_class = DeprecatedContractClass::default();
+    deprecated_class.entry_points_by_type.insert(
+        DeprecatedEntryPointType::L1Handler,
+        vec![DeprecatedEntryPoint { selector, offset: EntryPointOffset(3) }],
+    );
+    let diff = StateDiff {
+        deprecated_declared_classes: IndexMap::from([(deprecated_class_hash, deprecated_class)]),
+        declared_classes: IndexMap::from([(class_hash, (CompiledClassHash::default(), class))]),
+        ..Default::default()
+    };
+
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(BlockNumber(0), diff, IndexMap::new())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    let state_reader = txn.get_state_reader().unwrap();
+    assert_eq!(
+        state_reader.get_entry_point(&class_hash, &selector).unwrap(),
+        Some(IndexedEntryPoint {
+            entry_point_type: EntryPointType::External,
+            location: EntryPointLocation::FunctionIndex(FunctionIndex(7)),
+        })
+    );
+    assert_eq!(
+        state_reader.get_entry_point(&deprecated_class_hash, &selector).unwrap(),
+        Some(IndexedEntryPoint {
+            entry_point_type: EntryPointType::L1Handler,
+            location: EntryPointLocation::Offset(EntryPointOffset(3)),
+        })
+    );
+    assert!(
+        state_reader
+            .get_entry_point(&class_hash, &EntryPointSelector(stark_felt!("0x1")))
+            .unwrap()
+            .is_none()
+    );
+    drop(txn);
+
+    // The entry points of classes that were written before the entry points were indexed are
+    // taken from the class definitions.
+    let txn = writer.begin_rw_txn().unwrap();
+    txn.open_table(&txn.tables.entry_points).unwrap().clear(&txn.txn).unwrap();
+    txn.commit().unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    let state_reader = txn.get_state_reader().unwrap();
+    assert_eq!(
+        state_reader.get_entry_point(&class_hash, &selector).unwrap(),
+        Some(IndexedEntryPoint {
+            entry_point_type: EntryPointType::External,
+            location: EntryPointLocation::FunctionIndex(FunctionIndex(7)),
+        })
+    );
+    assert_eq!(
+        state_reader.get_entry_point(&deprecated_class_hash, &selector).unwrap(),
+        Some(IndexedEntryPoint {
+            entry_point_type: EntryPointType::L1Handler,
+            location: EntryPointLocation::Offset(EntryPointOffset(3)),
+        })
+    );
+    drop(txn);
+
+    // Reverting the block deletes the entry points of its classes.
+    let (txn, _) = writer.begin_rw_txn().unwrap().revert_state_diff(BlockNumber(0)).unwrap();
+    txn.commit().unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    let state_reader = txn.get_state_reader().unwrap();
+    assert!(state_reader.get_entry_point(&class_hash, &selector).unwrap().is_none());
+    assert!(state_reader.get_entry_point(&deprecated_class_hash, &selector).unwrap().is_none());
+}
+
 #[test]
 fn append_state_diff_replaced_classes() {
     let contract_0 = ContractAddress(patricia_key!("0x00"));
@@ -250,6 +462,61 @@ fn append_state_diff() {
     assert_eq!(statetxn.get_storage_at(state2, &c1, &key0).unwrap(), stark_felt!("0x0"));
 }
 
+#[test]
+fn storage_history() {
+    let c0 = ContractAddress(patricia_key!("0x11"));
+    let c1 = ContractAddress(patricia_key!("0x12"));
+    let key0 = StorageKey(patricia_key!("0x1001"));
+    let key1 = StorageKey(patricia_key!("0x101"));
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let mut txn = writer.begin_rw_txn().unwrap();
+    for (block_number, storage_diffs) in [
+        indexmap! { c0 => indexmap! { key0 => stark_felt!("0x1"), key1 => stark_felt!("0x2") } },
+        indexmap! { c1 => indexmap! { key0 => stark_felt!("0x3") } },
+        indexmap! { c0 => indexmap! { key0 => stark_felt!("0x0") } },
+        indexmap! { c0 => indexmap! { key0 => stark_felt!("0x4") } },
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let thin_state_diff = ThinStateDiff { storage_diffs, ..Default::default() };
+        txn =
+            txn.append_thin_state_diff(BlockNumber(block_number as u64), thin_state_diff).unwrap();
+    }
+    txn.commit().unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    let state_reader = txn.get_state_reader().unwrap();
+    assert_eq!(
+        state_reader.get_storage_history(&c0, &key0, BlockNumber(0), BlockNumber(3), 10).unwrap(),
+        vec![
+            (BlockNumber(0), stark_felt!("0x1")),
+            (BlockNumber(2), stark_felt!("0x0")),
+            (BlockNumber(3), stark_felt!("0x4")),
+        ]
+    );
+    // A sub range.
+    assert_eq!(
+        state_reader.get_storage_history(&c0, &key0, BlockNumber(1), BlockNumber(2), 10).unwrap(),
+        vec![(BlockNumber(2), stark_felt!("0x0"))]
+    );
+    // Limited.
+    assert_eq!(
+        state_reader.get_storage_history(&c0, &key0, BlockNumber(0), BlockNumber(3), 1).unwrap(),
+        vec![(BlockNumber(0), stark_felt!("0x1"))]
+    );
+    assert_eq!(
+        state_reader.get_storage_history(&c0, &key1, BlockNumber(0), BlockNumber(3), 10).unwrap(),
+        vec![(BlockNumber(0), stark_felt!("0x2"))]
+    );
+    assert!(
+        state_reader
+            .get_storage_history(&c1, &key1, BlockNumber(0), BlockNumber(3), 10)
+            .unwrap()
+            .is_empty()
+    );
+}
+
 #[test]
 fn test_update_compiled_class_marker() {
     let ((_, mut writer), _temp_dir) = get_test_storage();
@@ -331,6 +598,161 @@ fn append_2_state_diffs(writer: &mut StorageWriter) {
         .unwrap();
 }
 
+#[test]
+fn full_mode_state_history() {
+    let (mut config, _temp_dir) = get_test_config(None);
+    config.mode = NodeMode::Full;
+    config.full_mode_state_history = 1;
+    let (reader, mut writer) = open_storage(config).unwrap();
+    append_2_state_diffs(&mut writer);
+
+    let txn = reader.begin_ro_txn().unwrap();
+    txn.verify_state_queryable(StateNumber::right_after_block(BlockNumber(1))).unwrap();
+    txn.verify_state_queryable(StateNumber::right_after_block(BlockNumber(0))).unwrap();
+    assert_matches!(
+        txn.verify_state_queryable(StateNumber(BlockNumber(0))),
+        Err(StorageError::StateNotQueryable { node_mode: NodeMode::Full, .. })
+    );
+}
+
+#[test]
+fn full_mode_prunes_overwritten_values() {
+    let c0 = ContractAddress(patricia_key!("0x11"));
+    let key0 = StorageKey(patricia_key!("0x1001"));
+    let (mut config, _temp_dir) = get_test_config(None);
+    config.mode = NodeMode::Full;
+    config.full_mode_state_history = 1;
+    let (reader, mut writer) = open_storage(config).unwrap();
+    let mut txn = writer.begin_rw_txn().unwrap();
+    for (block_number, value) in
+        [stark_felt!("0x1"), stark_felt!("0x2"), stark_felt!("0x3")].into_iter().enumerate()
+    {
+        let thin_state_diff = ThinStateDiff {
+            storage_diffs: indexmap! { c0 => indexmap! { key0 => value } },
+            nonces: indexmap! { c0 => Nonce(StarkHash::from(block_number as u64)) },
+            ..Default::default()
+        };
+        txn =
+            txn.append_thin_state_diff(BlockNumber(block_number as u64), thin_state_diff).unwrap();
+    }
+    txn.commit().unwrap();
+
+    // The value of block 0 was overwritten in block 1, whose state diff left the history.
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_pruned_state_marker().unwrap(), BlockNumber(2));
+    let state_reader = txn.get_state_reader().unwrap();
+    assert_eq!(
+        state_reader.get_storage_history(&c0, &key0, BlockNumber(0), BlockNumber(2), 10).unwrap(),
+        vec![(BlockNumber(1), stark_felt!("0x2")), (BlockNumber(2), stark_felt!("0x3"))]
+    );
+    let state_number = StateNumber::right_after_block(BlockNumber(1));
+    txn.verify_state_queryable(state_number).unwrap();
+    assert_eq!(state_reader.get_storage_at(state_number, &c0, &key0).unwrap(), stark_felt!("0x2"));
+    assert_eq!(
+        state_reader.get_nonce_at(state_number, &c0).unwrap(),
+        Some(Nonce(StarkHash::from(1_u64)))
+    );
+    assert_matches!(
+        txn.verify_state_queryable(StateNumber::right_after_block(BlockNumber(0))),
+        Err(StorageError::StateNotQueryable { node_mode: NodeMode::Full, .. })
+    );
+
+    // The block whose overwritten values were pruned can't be reverted.
+    let (txn, _) = writer.begin_rw_txn().unwrap().revert_state_diff(BlockNumber(2)).unwrap();
+    assert_matches!(
+        txn.revert_state_diff(BlockNumber(1)).err(),
+        Some(StorageError::PrunedStateRevert { block_number: BlockNumber(1) })
+    );
+}
+
+#[test]
+fn prune_state_history_below_a_block() {
+    let c0 = ContractAddress(patricia_key!("0x11"));
+    let key0 = StorageKey(patricia_key!("0x1001"));
+    let (mut config, _temp_dir) = get_test_config(None);
+    config.mode = NodeMode::Full;
+    let (reader, mut writer) = open_storage(config).unwrap();
+    let mut txn = writer.begin_rw_txn().unwrap();
+    for (block_number, value) in
+        [stark_felt!("0x1"), stark_felt!("0x2"), stark_felt!("0x3")].into_iter().enumerate()
+    {
+        let thin_state_diff = ThinStateDiff {
+            storage_diffs: indexmap! { c0 => indexmap! { key0 => value } },
+            ..Default::default()
+        };
+        txn =
+            txn.append_thin_state_diff(BlockNumber(block_number as u64), thin_state_diff).unwrap();
+    }
+    txn.commit().unwrap();
+    // All the blocks are in the history of the Full mode, so nothing was pruned yet.
+    assert_eq!(reader.begin_ro_txn().unwrap().get_pruned_state_marker().unwrap(), BlockNumber(0));
+
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .prune_state_history_below(BlockNumber(2))
+        .unwrap()
+        .commit()
+        .unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_pruned_state_marker().unwrap(), BlockNumber(2));
+    let state_reader = txn.get_state_reader().unwrap();
+    assert_eq!(
+        state_reader.get_storage_history(&c0, &key0, BlockNumber(0), BlockNumber(2), 10).unwrap(),
+        vec![(BlockNumber(1), stark_felt!("0x2")), (BlockNumber(2), stark_felt!("0x3"))]
+    );
+    txn.verify_state_queryable(StateNumber(BlockNumber(2))).unwrap();
+    assert_matches!(
+        txn.verify_state_queryable(StateNumber(BlockNumber(1))),
+        Err(StorageError::StateNotQueryable { node_mode: NodeMode::Full, .. })
+    );
+    drop(txn);
+
+    // Pruning below an already pruned block does nothing, and pruning stops at the state marker.
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .prune_state_history_below(BlockNumber(1))
+        .unwrap()
+        .commit()
+        .unwrap();
+    assert_eq!(reader.begin_ro_txn().unwrap().get_pruned_state_marker().unwrap(), BlockNumber(2));
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .prune_state_history_below(BlockNumber(10))
+        .unwrap()
+        .commit()
+        .unwrap();
+    assert_eq!(reader.begin_ro_txn().unwrap().get_pruned_state_marker().unwrap(), BlockNumber(3));
+}
+
+#[test]
+fn prune_state_history_only_in_full_mode() {
+    let ((_reader, mut writer), _temp_dir) = get_test_storage();
+    assert_matches!(
+        writer.begin_rw_txn().unwrap().prune_state_history_below(BlockNumber(1)).err(),
+        Some(StorageError::StatePruningUnsupported { node_mode: NodeMode::Archive })
+    );
+}
+
+#[test]
+fn light_mode_has_no_state() {
+    let (mut config, _temp_dir) = get_test_config(None);
+    config.mode = NodeMode::Light;
+    let (reader, _writer) = open_storage(config).unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_matches!(
+        txn.verify_state_queryable(StateNumber(BlockNumber(0))),
+        Err(StorageError::StateNotQueryable { node_mode: NodeMode::Light, .. })
+    );
+    assert_matches!(
+        txn.get_state_reader().err(),
+        Some(StorageError::NodeModeError { node_mode: NodeMode::Light, .. })
+    );
+}
+
 #[test]
 fn revert_doesnt_delete_previously_declared_classes() {
     // Append 2 state diffs that use the same declared class.
@@ -700,3 +1122,95 @@ fn declare_revert_declare_scenario() {
             .is_some()
     );
 }
+
+#[test]
+fn state_diff_stats() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let c0 = ContractAddress(patricia_key!("0x100"));
+    let c1 = ContractAddress(patricia_key!("0x101"));
+    let key0 = StorageKey(patricia_key!("0x0"));
+    let key1 = StorageKey(patricia_key!("0x1"));
+    let thin_state_diff = ThinStateDiff {
+        deployed_contracts: indexmap! { c0 => ClassHash(stark_felt!("0x10")) },
+        storage_diffs: indexmap! {
+            c0 => indexmap! { key0 => stark_felt!("0x1"), key1 => stark_felt!("0x2") },
+            c1 => indexmap! { key0 => stark_felt!("0x3") },
+        },
+        declared_classes: indexmap! {
+            ClassHash(stark_felt!("0x11")) => CompiledClassHash::default(),
+        },
+        deprecated_declared_classes: vec![
+            ClassHash(stark_felt!("0x12")),
+            ClassHash(stark_felt!("0x13")),
+        ],
+        nonces: indexmap! { c0 => Nonce(stark_felt!("0x1")), c1 => Nonce(stark_felt!("0x1")) },
+        replaced_classes: indexmap! { c1 => ClassHash(stark_felt!("0x11")) },
+    };
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_thin_state_diff(BlockNumber(0), thin_state_diff)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    assert_eq!(
+        reader.begin_ro_txn().unwrap().get_state_diff_stats(BlockNumber(0)).unwrap(),
+        Some(StateDiffStats {
+            storage_writes: 3,
+            deployed_contracts: 1,
+            declared_classes: 1,
+            deprecated_declared_classes: 2,
+            nonce_updates: 2,
+            replaced_classes: 1,
+        })
+    );
+    assert_eq!(reader.begin_ro_txn().unwrap().get_state_diff_stats(BlockNumber(1)).unwrap(), None);
+
+    writer.begin_rw_txn().unwrap().revert_state_diff(BlockNumber(0)).unwrap().0.commit().unwrap();
+    assert_eq!(reader.begin_ro_txn().unwrap().get_state_diff_stats(BlockNumber(0)).unwrap(), None);
+}
+
+#[test]
+fn reverted_class_is_not_served_from_cache() {
+    let (mut config, _temp_dir) = get_test_config(None);
+    config.class_cache_size = 10;
+    let (reader, mut writer) = open_storage(config).unwrap();
+    let class_hash = ClassHash(StarkHash::from(1_u8));
+    let declare = |class: ContractClass| StateDiff {
+        declared_classes: IndexMap::from([(class_hash, (CompiledClassHash::default(), class))]),
+        ..Default::default()
+    };
+    let get_class = || {
+        reader
+            .begin_ro_txn()
+            .unwrap()
+            .get_state_reader()
+            .unwrap()
+            .get_class_definition_at(StateNumber::right_after_block(BlockNumber(0)), &class_hash)
+            .unwrap()
+    };
+
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(BlockNumber(0), declare(ContractClass::default()), IndexMap::new())
+        .unwrap()
+        .commit()
+        .unwrap();
+    assert_eq!(get_class(), Some(ContractClass::default()));
+
+    // Declaring a different class with the same hash is impossible outside of tests, but it shows
+    // whether the reverted class was removed from the cache.
+    let (txn, _) = writer.begin_rw_txn().unwrap().revert_state_diff(BlockNumber(0)).unwrap();
+    txn.commit().unwrap();
+    let other_class = ContractClass { abi: "abi".to_string(), ..Default::default() };
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(BlockNumber(0), declare(other_class.clone()), IndexMap::new())
+        .unwrap()
+        .commit()
+        .unwrap();
+    assert_eq!(get_class(), Some(other_class));
+}
@@ -7,6 +7,8 @@ use starknet_api::core::{
     TransactionCommitment,
 };
 use starknet_api::data_availability::L1DataAvailabilityMode;
+use starknet_api::deprecated_contract_class::EntryPointOffset;
+use starknet_api::state::{EntryPointType, FunctionIndex};
 use starknet_api::transaction::{
     EventIndexInTransactionOutput,
     ExecutionResources,
@@ -15,8 +17,14 @@ use starknet_api::transaction::{
     TransactionExecutionStatus,
     TransactionOffsetInBlock,
 };
-use test_utils::{auto_impl_get_test_instance, get_number_of_variants, GetTestInstance};
+use test_utils::{
+    auto_impl_get_test_instance,
+    default_impl_get_test_instance,
+    get_number_of_variants,
+    GetTestInstance,
+};
 
+use crate::base_layer::BaseLayerL1Block;
 use crate::body::events::{
     ThinDeclareTransactionOutput,
     ThinDeployAccountTransactionOutput,
@@ -29,7 +37,16 @@ use crate::body::TransactionIndex;
 use crate::compression_utils::IsCompressed;
 use crate::header::StorageBlockHeader;
 use crate::mmap_file::LocationInFile;
-use crate::state::data::IndexedDeprecatedContractClass;
+use crate::node_identity::{NodeIdentity, NodeRun};
+use crate::quarantine::QuarantinedBlock;
+use crate::secondary_index::IndexRebuildProgress;
+use crate::source_reputation::SourceReputation;
+use crate::state::data::{
+    EntryPointLocation,
+    IndexedDeprecatedContractClass,
+    IndexedEntryPoint,
+    StateDiffStats,
+};
 use crate::version::Version;
 use crate::{EventIndex, MarkerKind, OffsetKind};
 
@@ -49,11 +66,23 @@ auto_impl_get_test_instance! {
         pub n_transactions: usize,
         pub n_events: usize,
     }
+    pub enum EntryPointLocation {
+        FunctionIndex(FunctionIndex) = 0,
+        Offset(EntryPointOffset) = 1,
+    }
     struct EventIndex(pub TransactionIndex, pub EventIndexInTransactionOutput);
     pub struct IndexedDeprecatedContractClass {
         pub block_number: BlockNumber,
         pub location_in_file: LocationInFile,
     }
+    pub struct IndexedEntryPoint {
+        pub entry_point_type: EntryPointType,
+        pub location: EntryPointLocation,
+    }
+    pub struct IndexRebuildProgress {
+        pub source: u8,
+        pub last_indexed_key: Option<Vec<u8>>,
+    }
     pub enum IsCompressed {
         No = 0,
         Yes = 1,
@@ -64,12 +93,40 @@ auto_impl_get_test_instance! {
         State = 2,
         CompiledClass = 3,
     }
+    pub struct NodeIdentity {
+        pub node_id: String,
+        pub runs: Vec<NodeRun>,
+    }
+    pub struct NodeRun {
+        pub start_timestamp: u64,
+        pub version: String,
+        pub build_commit: String,
+        pub config_hash: String,
+    }
     pub enum OffsetKind {
         ThinStateDiff = 0,
         ContractClass = 1,
         Casm = 2,
         DeprecatedContractClass = 3,
     }
+    pub struct QuarantinedBlock {
+        pub body_error: Option<String>,
+        pub state_diff_error: Option<String>,
+    }
+    pub struct SourceReputation {
+        pub success_count: u64,
+        pub error_count: u64,
+        pub latency_ewma_millis: u64,
+        pub last_ban_timestamp: Option<u64>,
+    }
+    pub struct StateDiffStats {
+        pub storage_writes: u64,
+        pub deployed_contracts: u64,
+        pub declared_classes: u64,
+        pub deprecated_declared_classes: u64,
+        pub nonce_updates: u64,
+        pub replaced_classes: u64,
+    }
     pub struct ThinDeclareTransactionOutput {
         pub actual_fee: Fee,
         pub messages_sent: Vec<MessageToL1>,
@@ -117,3 +174,5 @@ auto_impl_get_test_instance! {
     struct TransactionIndex(pub BlockNumber, pub TransactionOffsetInBlock);
     pub struct Version(pub u32);
 }
+
+default_impl_get_test_instance!(BaseLayerL1Block);
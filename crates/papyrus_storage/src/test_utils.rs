@@ -6,7 +6,7 @@ use tempfile::{tempdir, TempDir};
 
 use crate::db::DbConfig;
 use crate::mmap_file::MmapFileConfig;
-use crate::{open_storage, StorageConfig, StorageReader, StorageScope, StorageWriter};
+use crate::{open_storage, NodeMode, StorageConfig, StorageReader, StorageScope, StorageWriter};
 
 /// Returns a db config and the temporary directory that holds this db.
 /// The TempDir object is returned as a handler for the lifetime of this object (the temp
@@ -28,6 +28,11 @@ pub fn get_test_config(storage_scope: Option<StorageScope>) -> (StorageConfig, T
                 growth_step: 1 << 26, // 64MB
             },
             scope: storage_scope,
+            mode: NodeMode::Archive,
+            full_mode_state_history: 128,
+            class_cache_size: 0,
+            store_receipts: true,
+            repair_indexes_on_open: false,
             mmap_file_config: get_mmap_file_test_config(),
         },
         dir,
@@ -7,7 +7,7 @@ use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufWriter, Write};
 
-use metrics::{absolute_counter, gauge};
+use metrics::{absolute_counter, counter, gauge, histogram};
 use serde::Serialize;
 use starknet_api::block::BlockNumber;
 use starknet_api::core::{ChainId, ClassHash, CompiledClassHash};
@@ -17,7 +17,7 @@ use tracing::debug;
 
 use crate::compiled_class::CasmStorageReader;
 use crate::db::table_types::Table;
-use crate::db::RO;
+use crate::db::{WriteStats, RO};
 use crate::state::StateStorageReader;
 use crate::{open_storage, StorageConfig, StorageError, StorageReader, StorageResult, StorageTxn};
 
@@ -100,3 +100,14 @@ pub fn update_storage_metrics(reader: &StorageReader) -> StorageResult<()> {
     absolute_counter!("storage_last_transaction_index", info.last_txnid() as u64);
     Ok(())
 }
+
+// Records the sizes of a commit, to quantify the effect of batching and compression on the amount
+// of data the storage writes.
+pub(crate) fn update_commit_metrics(write_stats: &WriteStats, appended_file_bytes: usize) {
+    histogram!("storage_commit_db_bytes", write_stats.bytes_written as f64);
+    histogram!("storage_commit_file_bytes", appended_file_bytes as f64);
+    histogram!("storage_commit_new_pages", write_stats.new_pages as f64);
+    for (table_name, writes) in &write_stats.table_writes {
+        counter!("storage_table_writes", *writes as u64, "table" => *table_name);
+    }
+}
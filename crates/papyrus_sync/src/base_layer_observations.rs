@@ -0,0 +1,82 @@
+//! The base layer blocks that the proved Starknet blocks were read from, used to detect reorgs of
+//! the base layer. A reorg that removes such a block may also remove the proof of the Starknet
+//! blocks, so they are no longer accepted on the base layer until they are proved again.
+#[cfg(test)]
+#[path = "base_layer_observations_test.rs"]
+mod base_layer_observations_test;
+
+use std::collections::BTreeMap;
+
+use papyrus_base_layer::{L1BlockHash, L1BlockReference};
+use starknet_api::block::BlockNumber;
+
+use crate::sources::base_layer::{BaseLayerSourceError, BaseLayerSourceTrait};
+
+// The maximal number of observations kept. The base layer blocks before the oldest observation are
+// assumed to be final.
+const MAX_OBSERVATIONS: usize = 100;
+
+/// The base layer blocks the proved Starknet blocks were read from.
+pub(crate) struct BaseLayerObservations {
+    // The base layer marker according to the base layer blocks before the observations.
+    final_marker: BlockNumber,
+    // The hash of each observed base layer block and the base layer marker according to it, by the
+    // number of the block.
+    observations: BTreeMap<u64, (L1BlockHash, BlockNumber)>,
+}
+
+impl BaseLayerObservations {
+    /// Starts tracking from the stored base layer marker and the stored base layer blocks that its
+    /// latest values were read from, by the value of the marker, from the oldest. The oldest of
+    /// them is assumed to be final, and so is the marker if there are none.
+    pub(crate) fn new(
+        marker: BlockNumber,
+        l1_blocks: Vec<(BlockNumber, L1BlockReference)>,
+    ) -> Self {
+        let mut l1_blocks = l1_blocks.into_iter();
+        let Some((final_marker, _l1_block)) = l1_blocks.next() else {
+            return Self { final_marker: marker, observations: BTreeMap::new() };
+        };
+        let observations = l1_blocks
+            .map(|(marker, l1_block)| (l1_block.number, (l1_block.hash, marker)))
+            .collect();
+        Self { final_marker, observations }
+    }
+
+    /// Records that `block_number` is the latest block proved in the base layer block.
+    pub(crate) fn record(&mut self, l1_block: L1BlockReference, block_number: BlockNumber) {
+        // Observations of later base layer blocks are of blocks that were since removed.
+        self.observations.split_off(&l1_block.number);
+        self.observations.insert(l1_block.number, (l1_block.hash, block_number.next()));
+        while self.observations.len() > MAX_OBSERVATIONS {
+            if let Some((_number, (_hash, marker))) = self.observations.pop_first() {
+                self.final_marker = marker;
+            }
+        }
+    }
+
+    /// Checks the observed base layer blocks against the base layer, from the latest. If any of
+    /// them was removed by a reorg, forgets it and returns the base layer marker according to the
+    /// observed blocks that remain.
+    pub(crate) async fn find_reorg<TBaseLayerSource: BaseLayerSourceTrait + Sync>(
+        &mut self,
+        base_layer_source: &TBaseLayerSource,
+    ) -> Result<Option<BlockNumber>, BaseLayerSourceError> {
+        let mut reorged = false;
+        while let Some((number, (hash, _marker))) = self.observations.last_key_value() {
+            if base_layer_source.l1_block_hash(*number).await? == Some(*hash) {
+                break;
+            }
+            self.observations.pop_last();
+            reorged = true;
+        }
+        if !reorged {
+            return Ok(None);
+        }
+        Ok(Some(
+            self.observations
+                .last_key_value()
+                .map_or(self.final_marker, |(_number, (_hash, marker))| *marker),
+        ))
+    }
+}
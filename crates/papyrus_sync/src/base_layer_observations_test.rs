@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use papyrus_base_layer::{L1BlockHash, L1BlockReference};
+use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
+
+use crate::base_layer_observations::BaseLayerObservations;
+use crate::sources::base_layer::MockBaseLayerSourceTrait;
+
+fn l1_block(number: u64, hash: u8) -> L1BlockReference {
+    L1BlockReference { number, hash: L1BlockHash([hash; 32]) }
+}
+
+// A base layer whose blocks are the given ones.
+fn base_layer(l1_blocks: &[L1BlockReference]) -> MockBaseLayerSourceTrait {
+    let hashes: HashMap<_, _> =
+        l1_blocks.iter().map(|l1_block| (l1_block.number, l1_block.hash)).collect();
+    let mut mock = MockBaseLayerSourceTrait::new();
+    mock.expect_l1_block_hash().returning(move |number| Ok(hashes.get(&number).copied()));
+    mock
+}
+
+#[tokio::test]
+async fn reorg_rolls_back_to_the_remaining_observations() {
+    let mut observations = BaseLayerObservations::new(BlockNumber(5), vec![]);
+    observations.record(l1_block(10, 1), BlockNumber(10));
+    observations.record(l1_block(20, 2), BlockNumber(20));
+    observations.record(l1_block(30, 3), BlockNumber(30));
+
+    // No reorg.
+    let l1_blocks = [l1_block(10, 1), l1_block(20, 2), l1_block(30, 3), l1_block(31, 4)];
+    assert_eq!(observations.find_reorg(&base_layer(&l1_blocks)).await.unwrap(), None);
+
+    // The last two observed blocks were replaced.
+    let l1_blocks = [l1_block(10, 1), l1_block(20, 5), l1_block(30, 6)];
+    assert_eq!(
+        observations.find_reorg(&base_layer(&l1_blocks)).await.unwrap(),
+        Some(BlockNumber(11))
+    );
+    // The replaced blocks are forgotten.
+    assert_eq!(observations.find_reorg(&base_layer(&l1_blocks)).await.unwrap(), None);
+
+    // All the observed blocks were removed.
+    assert_eq!(observations.find_reorg(&base_layer(&[])).await.unwrap(), Some(BlockNumber(5)));
+}
+
+#[tokio::test]
+async fn observation_replaces_later_observations() {
+    let mut observations = BaseLayerObservations::new(BlockNumber(0), vec![]);
+    observations.record(l1_block(10, 1), BlockNumber(10));
+    observations.record(l1_block(20, 2), BlockNumber(20));
+    // A block that replaced the observed block 20.
+    observations.record(l1_block(15, 3), BlockNumber(12));
+
+    let l1_blocks = [l1_block(10, 1), l1_block(15, 3)];
+    assert_eq!(observations.find_reorg(&base_layer(&l1_blocks)).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn stored_observations_are_checked() {
+    // The oldest stored base layer block is assumed to be final.
+    let mut observations = BaseLayerObservations::new(
+        BlockNumber(30),
+        vec![
+            (BlockNumber(10), l1_block(10, 1)),
+            (BlockNumber(20), l1_block(20, 2)),
+            (BlockNumber(30), l1_block(30, 3)),
+        ],
+    );
+    assert_eq!(
+        observations.find_reorg(&base_layer(&[l1_block(20, 2)])).await.unwrap(),
+        Some(BlockNumber(20))
+    );
+    assert_eq!(observations.find_reorg(&base_layer(&[])).await.unwrap(), Some(BlockNumber(10)));
+}
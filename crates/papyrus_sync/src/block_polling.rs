@@ -0,0 +1,63 @@
+//! The interval between polls of the central source for new blocks once the node has the latest
+//! block. The interval adapts to the block time of the chain, estimated from the timestamps of the
+//! last stored headers: right after a block the node waits until the next one is due, and once it's
+//! due the node polls at the minimal interval.
+#[cfg(test)]
+#[path = "block_polling_test.rs"]
+mod block_polling_test;
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use papyrus_storage::header::HeaderStorageReader;
+use papyrus_storage::{StorageReader, StorageResult};
+use starknet_api::block::BlockNumber;
+
+// The number of last blocks whose average time estimates the block time.
+const BLOCK_TIME_SAMPLE_SIZE: u64 = 10;
+
+// The maximal jitter added to a polling interval, in percents of the interval. Spreads the polls
+// of nodes that started together.
+const JITTER_PERCENT: u64 = 10;
+
+/// Returns how long to wait before polling for the block after the last stored header, between
+/// `min_interval` and `max_interval`, given the current unix timestamp in seconds.
+pub(crate) fn block_polling_interval(
+    reader: &StorageReader,
+    min_interval: Duration,
+    max_interval: Duration,
+    now: u64,
+) -> StorageResult<Duration> {
+    let max_interval = max_interval.max(min_interval);
+    let txn = reader.begin_ro_txn()?;
+    let header_marker = txn.get_header_marker()?;
+    // The block time can't be estimated from less than two blocks.
+    let Some(last_block_number) = header_marker.prev().filter(|block_number| block_number.0 > 0)
+    else {
+        return Ok(min_interval);
+    };
+    let first_block_number =
+        BlockNumber(last_block_number.0.saturating_sub(BLOCK_TIME_SAMPLE_SIZE));
+    let (Some(first_header), Some(last_header)) =
+        (txn.get_block_header(first_block_number)?, txn.get_block_header(last_block_number)?)
+    else {
+        return Ok(min_interval);
+    };
+    let block_time = last_header.timestamp.0.saturating_sub(first_header.timestamp.0)
+        / (last_block_number.0 - first_block_number.0);
+    let next_block_due = last_header.timestamp.0.saturating_add(block_time);
+    let until_next_block = Duration::from_secs(next_block_due.saturating_sub(now));
+    Ok(until_next_block.clamp(min_interval, max_interval))
+}
+
+/// Adds a random jitter of up to [`JITTER_PERCENT`] percents to the interval.
+pub(crate) fn with_jitter(interval: Duration) -> Duration {
+    let max_jitter_millis =
+        u64::try_from(interval.as_millis()).unwrap_or(u64::MAX) / 100 * JITTER_PERCENT;
+    if max_jitter_millis == 0 {
+        return interval;
+    }
+    let random = RandomState::new().build_hasher().finish();
+    interval + Duration::from_millis(random % (max_jitter_millis + 1))
+}
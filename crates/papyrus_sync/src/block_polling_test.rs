@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use papyrus_storage::header::HeaderStorageWriter;
+use papyrus_storage::test_utils::get_test_storage;
+use papyrus_storage::StorageWriter;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockHeader, BlockNumber, BlockTimestamp};
+
+use crate::block_polling::{block_polling_interval, with_jitter};
+
+const MIN_INTERVAL: Duration = Duration::from_secs(2);
+const MAX_INTERVAL: Duration = Duration::from_secs(30);
+
+// Appends headers with the given timestamps, starting from the given block.
+fn append_headers(writer: &mut StorageWriter, first_block_number: u64, timestamps: &[u64]) {
+    let mut txn = writer.begin_rw_txn().unwrap();
+    for (i, timestamp) in timestamps.iter().enumerate() {
+        let block_number = BlockNumber(first_block_number + i as u64);
+        let header = BlockHeader {
+            block_number,
+            timestamp: BlockTimestamp(*timestamp),
+            ..Default::default()
+        };
+        txn = txn.append_header(block_number, &header).unwrap();
+    }
+    txn.commit().unwrap();
+}
+
+#[test]
+fn interval_adapts_to_block_time() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let interval = |now| block_polling_interval(&reader, MIN_INTERVAL, MAX_INTERVAL, now).unwrap();
+
+    // Without two blocks the block time is unknown.
+    assert_eq!(interval(0), MIN_INTERVAL);
+    append_headers(&mut writer, 0, &[1000]);
+    assert_eq!(interval(1000), MIN_INTERVAL);
+
+    // A block every 20 seconds, the last one at 1100.
+    append_headers(&mut writer, 1, &[1020, 1040, 1060, 1080, 1100]);
+    assert_eq!(interval(1105), Duration::from_secs(15));
+    // The next block is due soon.
+    assert_eq!(interval(1119), MIN_INTERVAL);
+    // The next block is late.
+    assert_eq!(interval(1200), MIN_INTERVAL);
+    // The clock is behind the timestamps.
+    assert_eq!(interval(1000), MAX_INTERVAL);
+}
+
+#[test]
+fn interval_is_fixed_if_max_is_not_above_min() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_headers(&mut writer, 0, &[1000, 1020]);
+    assert_eq!(
+        block_polling_interval(&reader, MIN_INTERVAL, MIN_INTERVAL, 1025).unwrap(),
+        MIN_INTERVAL
+    );
+    assert_eq!(
+        block_polling_interval(&reader, MIN_INTERVAL, Duration::ZERO, 1025).unwrap(),
+        MIN_INTERVAL
+    );
+}
+
+#[test]
+fn jitter_is_bounded() {
+    let interval = Duration::from_secs(10);
+    for _ in 0..100 {
+        let jittered = with_jitter(interval);
+        assert!(interval <= jittered && jittered <= Duration::from_secs(11), "{jittered:?}");
+    }
+    assert_eq!(with_jitter(Duration::ZERO), Duration::ZERO);
+}
@@ -0,0 +1,255 @@
+//! Importing an initial state at block 0, for app-chains whose sequencer starts from a state that
+//! wasn't created by transactions.
+//!
+//! The genesis state is read from a JSON or a CSV bundle at the node's first boot, and merged into
+//! the state diff of block 0 when the sync stores it. The entries of the state diff of block 0
+//! override the entries of the genesis state. The Cairo 1 classes of the genesis state come with
+//! their compiled classes, since the central source doesn't serve them.
+//!
+//! A file whose extension is `csv` is read as a CSV bundle, with a line for each entry:
+//! ```text
+//! deployed_contract,<address>,<class hash>
+//! storage,<address>,<key>,<value>
+//! nonce,<address>,<nonce>
+//! declared_class,<class hash>,<compiled class hash>,<class file>,<compiled class file>
+//! deprecated_declared_class,<class hash>,<class file>
+//! ```
+//! The class files are JSON files, whose paths are relative to the directory of the bundle. Any
+//! other file is read as a JSON bundle of [`GenesisState`].
+#[cfg(test)]
+#[path = "genesis_test.rs"]
+mod genesis_test;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::hash::Hash;
+use std::path::{Path, PathBuf};
+
+use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
+use indexmap::IndexMap;
+use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
+use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::{ContractClass, StateDiff, StorageKey, ThinStateDiff};
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GenesisConfig {
+    pub state_path: PathBuf,
+}
+
+impl Default for GenesisConfig {
+    fn default() -> Self {
+        GenesisConfig { state_path: PathBuf::from("genesis_state.json") }
+    }
+}
+
+impl SerializeConfig for GenesisConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([ser_param(
+            "state_path",
+            &self.state_path,
+            "Path to a JSON or a CSV file of the state to import at block 0. The file is read \
+             only while the storage has no state diffs.",
+            ParamPrivacyInput::Public,
+        )])
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GenesisStateError {
+    #[error("Failed to read the genesis state from {path:?}: {error}.")]
+    Read { path: PathBuf, error: std::io::Error },
+    #[error("Failed to parse the genesis state from {path:?}: {error}.")]
+    Parse { path: PathBuf, error: serde_json::Error },
+    #[error("Invalid line {line_number} of the genesis state in {path:?}: {msg}.")]
+    InvalidCsvLine { path: PathBuf, line_number: usize, msg: String },
+}
+
+/// The JSON bundle of a genesis state. Every field may be omitted.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GenesisState {
+    pub deployed_contracts: IndexMap<ContractAddress, ClassHash>,
+    pub storage_diffs: IndexMap<ContractAddress, IndexMap<StorageKey, StarkFelt>>,
+    pub declared_classes: IndexMap<ClassHash, GenesisClass>,
+    pub deprecated_declared_classes: IndexMap<ClassHash, DeprecatedContractClass>,
+    pub nonces: IndexMap<ContractAddress, Nonce>,
+}
+
+/// A Cairo 1 class of the genesis state, with its compiled class.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct GenesisClass {
+    pub compiled_class_hash: CompiledClassHash,
+    pub contract_class: ContractClass,
+    pub compiled_class: CasmContractClass,
+}
+
+impl From<GenesisState> for StateDiff {
+    fn from(genesis_state: GenesisState) -> Self {
+        StateDiff {
+            deployed_contracts: genesis_state.deployed_contracts,
+            storage_diffs: genesis_state.storage_diffs,
+            declared_classes: genesis_state
+                .declared_classes
+                .into_iter()
+                .map(|(class_hash, class)| {
+                    (class_hash, (class.compiled_class_hash, class.contract_class))
+                })
+                .collect(),
+            deprecated_declared_classes: genesis_state.deprecated_declared_classes,
+            nonces: genesis_state.nonces,
+            replaced_classes: IndexMap::new(),
+        }
+    }
+}
+
+/// Reads the genesis state from a JSON or a CSV bundle.
+pub fn load_genesis_state(path: &Path) -> Result<GenesisState, GenesisStateError> {
+    if path.extension().is_some_and(|extension| extension == "csv") {
+        return load_csv_genesis_state(path);
+    }
+    read_json_file(path)
+}
+
+fn read_json_file<T: DeserializeOwned>(path: &Path) -> Result<T, GenesisStateError> {
+    let content = fs::read_to_string(path)
+        .map_err(|error| GenesisStateError::Read { path: path.to_path_buf(), error })?;
+    serde_json::from_str(&content)
+        .map_err(|error| GenesisStateError::Parse { path: path.to_path_buf(), error })
+}
+
+fn load_csv_genesis_state(path: &Path) -> Result<GenesisState, GenesisStateError> {
+    let content = fs::read_to_string(path)
+        .map_err(|error| GenesisStateError::Read { path: path.to_path_buf(), error })?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let mut genesis_state = GenesisState::default();
+    for (line_index, line) in content.lines().enumerate() {
+        let fields = line.split(',').map(str::trim).collect::<Vec<_>>();
+        add_csv_entry(&mut genesis_state, dir, &fields).map_err(|msg| {
+            GenesisStateError::InvalidCsvLine {
+                path: path.to_path_buf(),
+                line_number: line_index + 1,
+                msg,
+            }
+        })?;
+    }
+    Ok(genesis_state)
+}
+
+fn add_csv_entry(
+    genesis_state: &mut GenesisState,
+    dir: &Path,
+    fields: &[&str],
+) -> Result<(), String> {
+    match (fields[0], fields.len()) {
+        ("", 1) => {}
+        ("deployed_contract", 3) => {
+            genesis_state.deployed_contracts.insert(csv_value(fields[1])?, csv_value(fields[2])?);
+        }
+        ("storage", 4) => {
+            genesis_state
+                .storage_diffs
+                .entry(csv_value(fields[1])?)
+                .or_default()
+                .insert(csv_value(fields[2])?, csv_value(fields[3])?);
+        }
+        ("nonce", 3) => {
+            genesis_state.nonces.insert(csv_value(fields[1])?, csv_value(fields[2])?);
+        }
+        ("declared_class", 5) => {
+            let class = GenesisClass {
+                compiled_class_hash: csv_value(fields[2])?,
+                contract_class: read_json_file(&dir.join(fields[3]))
+                    .map_err(|err| err.to_string())?,
+                compiled_class: read_json_file(&dir.join(fields[4]))
+                    .map_err(|err| err.to_string())?,
+            };
+            genesis_state.declared_classes.insert(csv_value(fields[1])?, class);
+        }
+        ("deprecated_declared_class", 3) => {
+            let class = read_json_file(&dir.join(fields[2])).map_err(|err| err.to_string())?;
+            genesis_state.deprecated_declared_classes.insert(csv_value(fields[1])?, class);
+        }
+        (kind, n_fields) => return Err(format!("unknown entry {kind} with {n_fields} fields")),
+    }
+    Ok(())
+}
+
+// Parses a value of a CSV bundle the way it's parsed from a JSON bundle.
+fn csv_value<T: DeserializeOwned>(field: &str) -> Result<T, String> {
+    serde_json::from_value(serde_json::Value::String(field.to_owned()))
+        .map_err(|err| err.to_string())
+}
+
+/// Merges the genesis state into the state diff of block 0.
+pub fn apply_genesis_state(genesis_state: StateDiff, state_diff: StateDiff) -> StateDiff {
+    StateDiff {
+        deployed_contracts: merge_maps(
+            genesis_state.deployed_contracts,
+            state_diff.deployed_contracts,
+        ),
+        storage_diffs: merge_storage_diffs(genesis_state.storage_diffs, state_diff.storage_diffs),
+        declared_classes: merge_maps(genesis_state.declared_classes, state_diff.declared_classes),
+        deprecated_declared_classes: merge_maps(
+            genesis_state.deprecated_declared_classes,
+            state_diff.deprecated_declared_classes,
+        ),
+        nonces: merge_maps(genesis_state.nonces, state_diff.nonces),
+        replaced_classes: merge_maps(genesis_state.replaced_classes, state_diff.replaced_classes),
+    }
+}
+
+/// Merges the genesis state, without its classes, into the state diff of block 0.
+pub fn apply_genesis_thin_state(
+    genesis_state: ThinStateDiff,
+    thin_state_diff: ThinStateDiff,
+) -> ThinStateDiff {
+    let mut deprecated_declared_classes = genesis_state.deprecated_declared_classes;
+    for class_hash in thin_state_diff.deprecated_declared_classes {
+        if !deprecated_declared_classes.contains(&class_hash) {
+            deprecated_declared_classes.push(class_hash);
+        }
+    }
+    ThinStateDiff {
+        deployed_contracts: merge_maps(
+            genesis_state.deployed_contracts,
+            thin_state_diff.deployed_contracts,
+        ),
+        storage_diffs: merge_storage_diffs(
+            genesis_state.storage_diffs,
+            thin_state_diff.storage_diffs,
+        ),
+        declared_classes: merge_maps(
+            genesis_state.declared_classes,
+            thin_state_diff.declared_classes,
+        ),
+        deprecated_declared_classes,
+        nonces: merge_maps(genesis_state.nonces, thin_state_diff.nonces),
+        replaced_classes: merge_maps(
+            genesis_state.replaced_classes,
+            thin_state_diff.replaced_classes,
+        ),
+    }
+}
+
+fn merge_maps<K: Hash + Eq, V>(
+    mut base: IndexMap<K, V>,
+    overrides: IndexMap<K, V>,
+) -> IndexMap<K, V> {
+    base.extend(overrides);
+    base
+}
+
+fn merge_storage_diffs(
+    mut base: IndexMap<ContractAddress, IndexMap<StorageKey, StarkFelt>>,
+    overrides: IndexMap<ContractAddress, IndexMap<StorageKey, StarkFelt>>,
+) -> IndexMap<ContractAddress, IndexMap<StorageKey, StarkFelt>> {
+    for (address, storage_entries) in overrides {
+        base.entry(address).or_default().extend(storage_entries);
+    }
+    base
+}
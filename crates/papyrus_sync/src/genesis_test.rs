@@ -0,0 +1,146 @@
+use std::fs;
+
+use assert_matches::assert_matches;
+use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
+use indexmap::indexmap;
+use pretty_assertions::assert_eq;
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce, PatriciaKey};
+use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::{ContractClass, StateDiff, StorageKey, ThinStateDiff};
+use starknet_api::{patricia_key, stark_felt};
+use tempfile::tempdir;
+
+use crate::genesis::{
+    apply_genesis_state,
+    apply_genesis_thin_state,
+    load_genesis_state,
+    GenesisClass,
+    GenesisState,
+    GenesisStateError,
+};
+
+fn genesis_state() -> GenesisState {
+    GenesisState {
+        deployed_contracts: indexmap! {
+            ContractAddress(patricia_key!("0x1")) => ClassHash(stark_felt!("0x10")),
+        },
+        storage_diffs: indexmap! {
+            ContractAddress(patricia_key!("0x1")) => indexmap! {
+                StorageKey(patricia_key!("0x100")) => stark_felt!("0x7"),
+                StorageKey(patricia_key!("0x101")) => stark_felt!("0x8"),
+            },
+        },
+        declared_classes: indexmap! {
+            ClassHash(stark_felt!("0x10")) => GenesisClass {
+                compiled_class_hash: CompiledClassHash(stark_felt!("0x11")),
+                contract_class: ContractClass::default(),
+                compiled_class: CasmContractClass::default(),
+            },
+        },
+        deprecated_declared_classes: indexmap! {
+            ClassHash(stark_felt!("0x20")) => DeprecatedContractClass::default(),
+        },
+        nonces: indexmap! { ContractAddress(patricia_key!("0x1")) => Nonce(stark_felt!("0x1")) },
+    }
+}
+
+#[test]
+fn load_from_json_file() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("genesis_state.json");
+    fs::write(&path, serde_json::to_string(&genesis_state()).unwrap()).unwrap();
+    assert_eq!(load_genesis_state(&path).unwrap(), genesis_state());
+
+    // Omitted fields are empty.
+    fs::write(&path, r#"{"nonces": {"0x1": "0x2"}}"#).unwrap();
+    assert_eq!(
+        load_genesis_state(&path).unwrap(),
+        GenesisState {
+            nonces: indexmap! { ContractAddress(patricia_key!("0x1")) => Nonce(stark_felt!("0x2")) },
+            ..Default::default()
+        }
+    );
+
+    fs::write(&path, r#"{"unknown_field": {}}"#).unwrap();
+    assert_matches!(load_genesis_state(&path), Err(GenesisStateError::Parse { .. }));
+    assert_matches!(
+        load_genesis_state(&dir.path().join("missing.json")),
+        Err(GenesisStateError::Read { .. })
+    );
+}
+
+#[test]
+fn load_from_csv_file() {
+    let dir = tempdir().unwrap();
+    let genesis_state = genesis_state();
+    let class = &genesis_state.declared_classes[&ClassHash(stark_felt!("0x10"))];
+    fs::write(dir.path().join("class.json"), serde_json::to_string(&class.contract_class).unwrap())
+        .unwrap();
+    fs::write(dir.path().join("casm.json"), serde_json::to_string(&class.compiled_class).unwrap())
+        .unwrap();
+    fs::write(
+        dir.path().join("deprecated_class.json"),
+        serde_json::to_string(&DeprecatedContractClass::default()).unwrap(),
+    )
+    .unwrap();
+    let path = dir.path().join("genesis_state.csv");
+    fs::write(
+        &path,
+        "deployed_contract,0x1,0x10\nstorage,0x1,0x100,0x7\nstorage,0x1,0x101,0x8\ndeclared_class,\
+         0x10,0x11,class.json,casm.json\ndeprecated_declared_class,0x20,deprecated_class.json\n\\
+         nnonce,0x1,0x1\n",
+    )
+    .unwrap();
+    assert_eq!(load_genesis_state(&path).unwrap(), genesis_state);
+
+    fs::write(&path, "nonce,0x1\n").unwrap();
+    assert_matches!(
+        load_genesis_state(&path),
+        Err(GenesisStateError::InvalidCsvLine { line_number: 1, .. })
+    );
+    fs::write(&path, "nonce,0x1,0x1\nstorage,0x1,0x100,not_a_felt\n").unwrap();
+    assert_matches!(
+        load_genesis_state(&path),
+        Err(GenesisStateError::InvalidCsvLine { line_number: 2, .. })
+    );
+}
+
+#[test]
+fn block_state_diff_overrides_genesis_state() {
+    let genesis_state = StateDiff::from(genesis_state());
+    let block_state_diff = StateDiff {
+        storage_diffs: indexmap! {
+            ContractAddress(patricia_key!("0x1")) => indexmap! {
+                StorageKey(patricia_key!("0x101")) => stark_felt!("0x9"),
+            },
+            ContractAddress(patricia_key!("0x2")) => indexmap! {
+                StorageKey(patricia_key!("0x100")) => stark_felt!("0x1"),
+            },
+        },
+        nonces: indexmap! { ContractAddress(patricia_key!("0x1")) => Nonce(stark_felt!("0x2")) },
+        ..Default::default()
+    };
+
+    let expected = StateDiff {
+        storage_diffs: indexmap! {
+            ContractAddress(patricia_key!("0x1")) => indexmap! {
+                StorageKey(patricia_key!("0x100")) => stark_felt!("0x7"),
+                StorageKey(patricia_key!("0x101")) => stark_felt!("0x9"),
+            },
+            ContractAddress(patricia_key!("0x2")) => indexmap! {
+                StorageKey(patricia_key!("0x100")) => stark_felt!("0x1"),
+            },
+        },
+        nonces: indexmap! { ContractAddress(patricia_key!("0x1")) => Nonce(stark_felt!("0x2")) },
+        ..genesis_state.clone()
+    };
+    assert_eq!(apply_genesis_state(genesis_state.clone(), block_state_diff.clone()), expected);
+    assert_eq!(
+        apply_genesis_thin_state(
+            ThinStateDiff::from(genesis_state),
+            ThinStateDiff::from(block_state_diff)
+        ),
+        ThinStateDiff::from(expected)
+    );
+}
@@ -5,6 +5,9 @@
 #[cfg(test)]
 mod sync_test;
 
+mod base_layer_observations;
+mod block_polling;
+pub mod genesis;
 mod pending_sync;
 pub mod sources;
 
@@ -16,31 +19,63 @@ use std::time::Duration;
 use async_stream::try_stream;
 use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 use chrono::{TimeZone, Utc};
-use futures_util::{pin_mut, select, Stream, StreamExt};
+use futures_util::{pin_mut, select, stream, Stream, StreamExt};
 use indexmap::IndexMap;
-use papyrus_common::pending_classes::PendingClasses;
+use papyrus_base_layer::{L1BlockHash, L1BlockReference};
+use papyrus_common::block_execution_resources::BlockExecutionResources;
+use papyrus_common::block_signature::verify_block_signature;
+use papyrus_common::deferred_classes::DeferredClassRequests;
+use papyrus_common::disk_space::DiskSpaceStatus;
+use papyrus_common::maintenance_requests::{MaintenanceRequests, PruneRequest};
+use papyrus_common::pending_classes::{ApiContractClass, PendingClasses};
+use papyrus_common::recent_state::{BlockStateWrites, RecentStateCache};
+use papyrus_common::rpc_load::RpcLoad;
+use papyrus_common::source_reports::{SourceReport, SourceReports};
+use papyrus_common::state_diff_commitment::{calculate_state_diff_commitment, StateDiffVersion};
+use papyrus_common::sync_notifications::{SyncNotification, SyncNotifier};
 use papyrus_common::{metrics as papyrus_metrics, BlockHashAndNumber};
-use papyrus_config::converters::deserialize_seconds_to_duration;
+use papyrus_config::converters::{
+    deserialize_milliseconds_to_duration,
+    deserialize_seconds_to_duration,
+};
 use papyrus_config::dumping::{ser_param, SerializeConfig};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 use papyrus_proc_macros::latency_histogram;
-use papyrus_storage::base_layer::BaseLayerStorageWriter;
-use papyrus_storage::body::BodyStorageWriter;
+use papyrus_storage::base_layer::{
+    BaseLayerL1Block,
+    BaseLayerStorageReader,
+    BaseLayerStorageWriter,
+};
+use papyrus_storage::body::{BodyStorageReader, BodyStorageWriter};
 use papyrus_storage::compiled_class::{CasmStorageReader, CasmStorageWriter};
-use papyrus_storage::db::DbError;
+use papyrus_storage::db::{DbError, RW};
 use papyrus_storage::header::{HeaderStorageReader, HeaderStorageWriter};
+use papyrus_storage::quarantine::QuarantineStorageWriter;
+use papyrus_storage::source_reputation::SourceReputationStorageWriter;
 use papyrus_storage::state::{StateStorageReader, StateStorageWriter};
-use papyrus_storage::{StorageError, StorageReader, StorageWriter};
+use papyrus_storage::{
+    NodeMode,
+    StorageError,
+    StorageReader,
+    StorageResult,
+    StorageScope,
+    StorageTxn,
+    StorageWriter,
+};
 use serde::{Deserialize, Serialize};
 use sources::base_layer::BaseLayerSourceError;
-use starknet_api::block::{Block, BlockHash, BlockNumber, BlockSignature};
+use starknet_api::block::{Block, BlockBody, BlockHash, BlockNumber, BlockSignature};
 use starknet_api::core::{ClassHash, CompiledClassHash, SequencerPublicKey};
 use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
-use starknet_api::state::StateDiff;
+use starknet_api::state::{StateDiff, ThinStateDiff};
+use starknet_api::transaction::TransactionOutput;
 use starknet_client::reader::PendingData;
 use tokio::sync::RwLock;
 use tracing::{debug, error, info, instrument, trace, warn};
 
+use crate::base_layer_observations::BaseLayerObservations;
+use crate::block_polling::{block_polling_interval, with_jitter};
+use crate::genesis::{apply_genesis_state, apply_genesis_thin_state, GenesisState};
 use crate::pending_sync::sync_pending_data;
 use crate::sources::base_layer::{BaseLayerSourceTrait, EthereumBaseLayerSource};
 use crate::sources::central::{CentralError, CentralSource, CentralSourceTrait};
@@ -53,17 +88,43 @@ const PENDING_SLEEP_DURATION: Duration = Duration::from_millis(500);
 // Sleep duration, in seconds, between sync progress checks.
 const SLEEP_TIME_SYNC_PROGRESS: Duration = Duration::from_secs(300);
 
+// Sleep duration between checks of the free disk space while the sync is paused because of it.
+const DISK_SPACE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
 pub struct SyncConfig {
     #[serde(deserialize_with = "deserialize_seconds_to_duration")]
     pub block_propagation_sleep_duration: Duration,
+    /// The maximal time to wait before polling for a new block once the node has the latest
+    /// block. The wait adapts to the block time of the chain, from
+    /// `block_propagation_sleep_duration` right before the next block is due up to this.
     #[serde(deserialize_with = "deserialize_seconds_to_duration")]
-    pub base_layer_propagation_sleep_duration: Duration,
+    pub max_block_propagation_sleep_duration: Duration,
     #[serde(deserialize_with = "deserialize_seconds_to_duration")]
-    pub recoverable_error_sleep_duration: Duration,
+    pub base_layer_propagation_sleep_duration: Duration,
     pub blocks_max_stream_size: u32,
     pub state_updates_max_stream_size: u32,
     pub verify_blocks: bool,
+    #[serde(deserialize_with = "deserialize_milliseconds_to_duration")]
+    pub throttling_rpc_latency_threshold: Duration,
+    #[serde(deserialize_with = "deserialize_milliseconds_to_duration")]
+    pub throttling_write_lock_wait_threshold: Duration,
+    #[serde(deserialize_with = "deserialize_milliseconds_to_duration")]
+    pub throttling_sleep_duration: Duration,
+    pub invalid_block_policy: InvalidBlockPolicy,
+    pub defer_class_download: bool,
+}
+
+/// What the sync does with a block whose body or state update fails to be parsed or validated.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum InvalidBlockPolicy {
+    /// Stops syncing at the block, and retries it until the source serves a valid one.
+    #[default]
+    Halt,
+    /// Stores the block with an empty body or state diff, records it as quarantined and continues
+    /// to the next blocks. A block whose header is invalid still halts the sync, since the next
+    /// blocks are verified against it.
+    Quarantine,
 }
 
 impl SerializeConfig for SyncConfig {
@@ -76,16 +137,17 @@ impl SerializeConfig for SyncConfig {
                 ParamPrivacyInput::Public,
             ),
             ser_param(
-                "base_layer_propagation_sleep_duration",
-                &self.base_layer_propagation_sleep_duration.as_secs(),
-                "Time in seconds to poll the base layer to get the latest proved block.",
+                "max_block_propagation_sleep_duration",
+                &self.max_block_propagation_sleep_duration.as_secs(),
+                "Maximal time in seconds before checking for a new block after the node is \
+                 synchronized. The time adapts to the block time of the chain, estimated from the \
+                 last blocks, and is at least block_propagation_sleep_duration.",
                 ParamPrivacyInput::Public,
             ),
             ser_param(
-                "recoverable_error_sleep_duration",
-                &self.recoverable_error_sleep_duration.as_secs(),
-                "Waiting time in seconds before restarting synchronization after a recoverable \
-                 error.",
+                "base_layer_propagation_sleep_duration",
+                &self.base_layer_propagation_sleep_duration.as_secs(),
+                "Time in seconds to poll the base layer to get the latest proved block.",
                 ParamPrivacyInput::Public,
             ),
             ser_param(
@@ -106,6 +168,43 @@ impl SerializeConfig for SyncConfig {
                 "Whether to verify incoming blocks.",
                 ParamPrivacyInput::Public,
             ),
+            ser_param(
+                "throttling_rpc_latency_threshold",
+                &self.throttling_rpc_latency_threshold.as_millis(),
+                "Time in milliseconds of the 95th percentile of the RPC requests latency above \
+                 which the sync slows down its storage writes.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "throttling_write_lock_wait_threshold",
+                &self.throttling_write_lock_wait_threshold.as_millis(),
+                "Time in milliseconds that the last storage write waited for the write lock of \
+                 the database above which the sync slows down its storage writes.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "throttling_sleep_duration",
+                &self.throttling_sleep_duration.as_millis(),
+                "Time in milliseconds to wait before each storage write while the RPC server or \
+                 the storage is under heavy load.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "invalid_block_policy",
+                &self.invalid_block_policy,
+                "What to do with a block whose body or state update fails to be parsed or \
+                 validated: Halt stops syncing at the block, Quarantine stores it with an empty \
+                 body or state diff, records it as quarantined and continues.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "defer_class_download",
+                &self.defer_class_download,
+                "Whether to sync the state diffs without the definitions of the classes they \
+                 declare. A class is then downloaded and stored the first time the RPC server \
+                 needs it, which saves storage on nodes that serve mostly other queries.",
+                ParamPrivacyInput::Public,
+            ),
         ])
     }
 }
@@ -114,11 +213,16 @@ impl Default for SyncConfig {
     fn default() -> Self {
         SyncConfig {
             block_propagation_sleep_duration: Duration::from_secs(2),
+            max_block_propagation_sleep_duration: Duration::from_secs(30),
             base_layer_propagation_sleep_duration: Duration::from_secs(10),
-            recoverable_error_sleep_duration: Duration::from_secs(3),
             blocks_max_stream_size: 1000,
             state_updates_max_stream_size: 1000,
             verify_blocks: true,
+            throttling_rpc_latency_threshold: Duration::from_millis(1000),
+            throttling_write_lock_wait_threshold: Duration::from_millis(500),
+            throttling_sleep_duration: Duration::from_millis(100),
+            invalid_block_policy: InvalidBlockPolicy::default(),
+            defer_class_download: false,
         }
     }
 }
@@ -137,9 +241,18 @@ pub struct GenericStateSync<
     pending_source: Arc<TPendingSource>,
     pending_classes: Arc<RwLock<PendingClasses>>,
     base_layer_source: Arc<TBaseLayerSource>,
+    rpc_load: Arc<RpcLoad>,
+    sync_notifier: Arc<SyncNotifier>,
+    disk_space: Arc<DiskSpaceStatus>,
+    recent_state: Arc<RecentStateCache>,
+    deferred_class_requests: Arc<DeferredClassRequests>,
+    source_reports: Arc<SourceReports>,
+    maintenance_requests: Arc<MaintenanceRequests>,
     reader: StorageReader,
     writer: StorageWriter,
     sequencer_pub_key: Option<SequencerPublicKey>,
+    // The state to merge into the state diff of block 0.
+    genesis_state: Option<GenesisState>,
 }
 
 pub type StateSyncResult = Result<(), StateSyncError>;
@@ -179,6 +292,60 @@ pub enum StateSyncError {
     },
     #[error("Sequencer public key changed from {old:?} to {new:?}.")]
     SequencerPubKeyChanged { old: SequencerPublicKey, new: SequencerPublicKey },
+    #[error(
+        "Signature of block {block_number} is not a signature of the sequencer over the block \
+         hash and the state diff commitment."
+    )]
+    InvalidBlockSignature { block_number: BlockNumber },
+    #[error(
+        "Receipts of block {block_number} belong to a block with hash {receipts_block_hash}, but \
+         the stored block hash is {stored_block_hash}."
+    )]
+    ReceiptsBlockHashMismatch {
+        block_number: BlockNumber,
+        receipts_block_hash: BlockHash,
+        stored_block_hash: BlockHash,
+    },
+}
+
+impl StateSyncError {
+    /// Whether the sync might be able to recover from the error by syncing again after a while.
+    /// The sync stops on any other error.
+    pub fn is_recoverable(&self) -> bool {
+        match self {
+            StateSyncError::NoProgress => true,
+            // The sources can't be created with the given configuration.
+            StateSyncError::CentralSourceError(CentralError::ClientCreation(_)) => false,
+            StateSyncError::BaseLayerSourceError(
+                BaseLayerSourceError::BaseLayerSourceCreationError(_),
+            ) => false,
+            StateSyncError::CentralSourceError(_) => true,
+            StateSyncError::BaseLayerSourceError(_) => true,
+            StateSyncError::StorageError(StorageError::InnerError(_)) => true,
+            StateSyncError::StorageError(_) => false,
+            StateSyncError::PendingSourceError(_) => false,
+            // A revert was detected.
+            StateSyncError::ParentBlockHashMismatch { .. } => true,
+            StateSyncError::BaseLayerHashMismatch { .. } => true,
+            StateSyncError::BaseLayerBlockWithoutMatchingHeader { .. } => true,
+            // The block was reverted while its receipts were downloaded.
+            StateSyncError::ReceiptsBlockHashMismatch { .. } => true,
+            StateSyncError::SequencerPubKeyChanged { .. } => false,
+            StateSyncError::InvalidBlockSignature { .. } => false,
+        }
+    }
+
+    /// Whether the error was caused by a reorg of the chain or of the base layer, after which the
+    /// sync should start again right away.
+    pub fn is_reorg(&self) -> bool {
+        matches!(
+            self,
+            StateSyncError::ParentBlockHashMismatch { .. }
+                | StateSyncError::BaseLayerHashMismatch { .. }
+                | StateSyncError::BaseLayerBlockWithoutMatchingHeader { .. }
+                | StateSyncError::ReceiptsBlockHashMismatch { .. }
+        )
+    }
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -208,6 +375,50 @@ pub enum SyncEvent {
     NewBaseLayerBlock {
         block_number: BlockNumber,
         block_hash: BlockHash,
+        l1_block: L1BlockReference,
+    },
+    // The block, and all the blocks after it, are no longer proved on the base layer because of a
+    // reorg of the base layer.
+    BaseLayerReorg {
+        block_number: BlockNumber,
+    },
+    ReceiptsAvailable {
+        block_number: BlockNumber,
+        block_hash: BlockHash,
+        transaction_outputs: Vec<TransactionOutput>,
+    },
+    // A block whose body is invalid, with an empty body instead.
+    QuarantinedBlockAvailable {
+        block_number: BlockNumber,
+        block: Block,
+        signature: BlockSignature,
+        error: String,
+    },
+    // A block whose state update is invalid.
+    QuarantinedStateDiffAvailable {
+        block_number: BlockNumber,
+        block_hash: BlockHash,
+        error: String,
+    },
+    // A state diff without the definitions of the classes it declares.
+    ThinStateDiffAvailable {
+        block_number: BlockNumber,
+        block_hash: BlockHash,
+        thin_state_diff: ThinStateDiff,
+    },
+    // A class whose download was deferred, with its compiled class if it's a Cairo 1 class.
+    DeferredClassAvailable {
+        class_hash: ClassHash,
+        class: ApiContractClass,
+        compiled_class: Option<CasmContractClass>,
+    },
+    // Reports about data sources from other components, to record in their reputation.
+    SourcesReported {
+        reports: Vec<SourceReport>,
+    },
+    // A request of the operators to prune the state history, received by another component.
+    PruneRequested {
+        request: PruneRequest,
     },
 }
 
@@ -217,14 +428,39 @@ impl<
     TBaseLayerSource: BaseLayerSourceTrait + Sync + Send,
 > GenericStateSync<TCentralSource, TPendingSource, TBaseLayerSource>
 {
+    /// Sets a state to merge into the state diff of block 0 when it's stored, for chains that start
+    /// from an imported state.
+    pub fn with_genesis_state(mut self, genesis_state: GenesisState) -> Self {
+        self.genesis_state = Some(genesis_state);
+        self
+    }
+
+    /// Syncs until a fatal error occurs, syncing again right away after reorgs and after
+    /// `block_propagation_sleep_duration` after other recoverable errors. The node restarts the
+    /// sync with [`run_until_error`](Self::run_until_error) and its own backoff instead.
     pub async fn run(&mut self) -> StateSyncResult {
         info!("State sync started.");
         loop {
             match self.sync_while_ok().await {
                 // A recoverable error occurred. Sleep and try syncing again.
-                Err(err) if is_recoverable(&err) => {
+                Err(err) if err.is_recoverable() => {
+                    if let StateSyncError::ParentBlockHashMismatch {
+                        block_number,
+                        expected_parent_block_hash,
+                        stored_parent_block_hash,
+                    } = &err
+                    {
+                        // A revert detected, log and restart sync loop.
+                        info!(
+                            "Detected revert while processing block {}. Parent hash of the \
+                             incoming block is {}, current block hash is {}.",
+                            block_number, expected_parent_block_hash, stored_parent_block_hash
+                        );
+                    }
                     warn!("Recoverable error encountered while syncing, error: {}", err);
-                    tokio::time::sleep(self.config.recoverable_error_sleep_duration).await;
+                    if !err.is_reorg() {
+                        tokio::time::sleep(self.config.block_propagation_sleep_duration).await;
+                    }
                     continue;
                 }
                 // Unrecoverable errors.
@@ -237,32 +473,13 @@ impl<
                 }
             }
         }
+    }
 
-        // Whitelisting of errors from which we might be able to recover.
-        fn is_recoverable(err: &StateSyncError) -> bool {
-            match err {
-                StateSyncError::NoProgress => true,
-                StateSyncError::CentralSourceError(_) => true,
-                StateSyncError::BaseLayerSourceError(_) => true,
-                StateSyncError::StorageError(StorageError::InnerError(_)) => true,
-                StateSyncError::ParentBlockHashMismatch {
-                    block_number,
-                    expected_parent_block_hash,
-                    stored_parent_block_hash,
-                } => {
-                    // A revert detected, log and restart sync loop.
-                    info!(
-                        "Detected revert while processing block {}. Parent hash of the incoming \
-                         block is {}, current block hash is {}.",
-                        block_number, expected_parent_block_hash, stored_parent_block_hash
-                    );
-                    true
-                }
-                StateSyncError::BaseLayerHashMismatch { .. } => true,
-                StateSyncError::BaseLayerBlockWithoutMatchingHeader { .. } => true,
-                _ => false,
-            }
-        }
+    /// Syncs until the first error, recoverable or not, leaving it to the caller to decide whether
+    /// and when to sync again.
+    pub async fn run_until_error(&mut self) -> StateSyncResult {
+        info!("State sync started.");
+        self.sync_while_ok().await
     }
 
     async fn track_sequencer_public_key_changes(&mut self) -> StateSyncResult {
@@ -307,24 +524,77 @@ impl<
             self.pending_data.clone(),
             self.pending_classes.clone(),
             self.config.block_propagation_sleep_duration,
+            self.config.max_block_propagation_sleep_duration,
             PENDING_SLEEP_DURATION,
             self.config.blocks_max_stream_size,
+            self.config.invalid_block_policy,
         )
         .fuse();
-        let state_diff_stream = stream_new_state_diffs(
-            self.reader.clone(),
-            self.central_source.clone(),
-            self.config.block_propagation_sleep_duration,
-            self.config.state_updates_max_stream_size,
-        )
+        // A light node stores only the headers, so it doesn't sync the state.
+        let sync_state = self.reader.get_node_mode() != NodeMode::Light;
+        let defer_class_download = self.config.defer_class_download;
+        let state_diff_stream = if sync_state && defer_class_download {
+            stream_new_thin_state_diffs(
+                self.reader.clone(),
+                self.central_source.clone(),
+                self.config.block_propagation_sleep_duration,
+                self.config.state_updates_max_stream_size,
+            )
+            .boxed()
+        } else if sync_state {
+            stream_new_state_diffs(
+                self.reader.clone(),
+                self.central_source.clone(),
+                self.config.block_propagation_sleep_duration,
+                self.config.state_updates_max_stream_size,
+                self.config.invalid_block_policy,
+            )
+            .boxed()
+        } else {
+            stream::pending().boxed()
+        }
         .fuse();
-        let compiled_class_stream = stream_new_compiled_classes(
-            self.reader.clone(),
-            self.central_source.clone(),
-            self.config.block_propagation_sleep_duration,
-            // TODO(yair): separate config param.
-            self.config.state_updates_max_stream_size,
-        )
+        // The compiled classes of deferred classes are downloaded along with the classes.
+        let compiled_class_stream = if sync_state && !defer_class_download {
+            stream_new_compiled_classes(
+                self.reader.clone(),
+                self.central_source.clone(),
+                self.config.block_propagation_sleep_duration,
+                // TODO(yair): separate config param.
+                self.config.state_updates_max_stream_size,
+            )
+            .left_stream()
+        } else {
+            stream::pending().right_stream()
+        }
+        .fuse();
+        let deferred_class_stream = if sync_state && defer_class_download {
+            stream_deferred_classes(
+                self.reader.clone(),
+                self.central_source.clone(),
+                self.deferred_class_requests.clone(),
+            )
+            .left_stream()
+        } else {
+            stream::pending().right_stream()
+        }
+        .fuse();
+        // The receipts of blocks that were stored without them are synced separately from the
+        // blocks.
+        let sync_receipts = sync_state
+            && self.reader.get_scope() == StorageScope::FullArchive
+            && self.reader.stores_receipts();
+        let receipts_stream = if sync_receipts {
+            stream_new_receipts(
+                self.reader.clone(),
+                self.central_source.clone(),
+                self.config.block_propagation_sleep_duration,
+                self.config.blocks_max_stream_size,
+            )
+            .left_stream()
+        } else {
+            stream::pending().right_stream()
+        }
         .fuse();
         let base_layer_block_stream = stream_new_base_layer_block(
             self.reader.clone(),
@@ -332,14 +602,22 @@ impl<
             self.config.base_layer_propagation_sleep_duration,
         )
         .fuse();
+        let source_report_stream = stream_source_reports(self.source_reports.clone()).fuse();
+        let maintenance_request_stream =
+            stream_maintenance_requests(self.maintenance_requests.clone()).fuse();
         // TODO(dvir): try use interval instead of stream.
         // TODO: fix the bug and remove this check.
-        let check_sync_progress = check_sync_progress(self.reader.clone()).fuse();
+        let check_sync_progress =
+            check_sync_progress(self.reader.clone(), defer_class_download).fuse();
         pin_mut!(
             block_stream,
             state_diff_stream,
             compiled_class_stream,
+            deferred_class_stream,
+            receipts_stream,
             base_layer_block_stream,
+            source_report_stream,
+            maintenance_request_stream,
             check_sync_progress
         );
 
@@ -349,7 +627,11 @@ impl<
               res = block_stream.next() => res,
               res = state_diff_stream.next() => res,
               res = compiled_class_stream.next() => res,
+              res = deferred_class_stream.next() => res,
+              res = receipts_stream.next() => res,
               res = base_layer_block_stream.next() => res,
+              res = source_report_stream.next() => res,
+              res = maintenance_request_stream.next() => res,
               res = check_sync_progress.next() => res,
               complete => break,
             }
@@ -362,9 +644,17 @@ impl<
 
     // Tries to store the incoming data.
     async fn process_sync_event(&mut self, sync_event: SyncEvent) -> StateSyncResult {
+        // Pruning frees disk space, so it isn't held while the space is low.
+        if !matches!(sync_event, SyncEvent::NoProgress | SyncEvent::PruneRequested { .. }) {
+            self.wait_for_disk_space().await;
+            self.throttle_if_under_load().await;
+        }
         match sync_event {
             SyncEvent::BlockAvailable { block_number, block, signature } => {
-                self.store_block(block_number, block, &signature)
+                self.store_block(block_number, block, &signature, None)
+            }
+            SyncEvent::QuarantinedBlockAvailable { block_number, block, signature, error } => {
+                self.store_block(block_number, block, &signature, Some(&error))
             }
             SyncEvent::StateDiffAvailable {
                 block_number,
@@ -382,20 +672,85 @@ impl<
                 compiled_class_hash,
                 compiled_class,
             } => self.store_compiled_class(class_hash, compiled_class_hash, compiled_class),
-            SyncEvent::NewBaseLayerBlock { block_number, block_hash } => {
-                self.store_base_layer_block(block_number, block_hash)
+            SyncEvent::NewBaseLayerBlock { block_number, block_hash, l1_block } => {
+                self.store_base_layer_block(block_number, block_hash, l1_block)
+            }
+            SyncEvent::BaseLayerReorg { block_number } => {
+                self.revert_base_layer_marker(block_number)
+            }
+            SyncEvent::ReceiptsAvailable { block_number, block_hash, transaction_outputs } => {
+                self.store_receipts(block_number, block_hash, transaction_outputs)
+            }
+            SyncEvent::QuarantinedStateDiffAvailable { block_number, block_hash, error } => {
+                self.store_quarantined_state_diff(block_number, block_hash, &error)
+            }
+            SyncEvent::ThinStateDiffAvailable { block_number, block_hash, thin_state_diff } => {
+                self.store_thin_state_diff(block_number, block_hash, thin_state_diff)
+            }
+            SyncEvent::DeferredClassAvailable { class_hash, class, compiled_class } => {
+                self.store_deferred_class(class_hash, class, compiled_class)
+            }
+            SyncEvent::SourcesReported { reports } => {
+                store_source_reports(&mut self.writer, reports)?;
+                Ok(())
+            }
+            SyncEvent::PruneRequested { request } => {
+                prune_state_history(&mut self.writer, request);
+                Ok(())
             }
             SyncEvent::NoProgress => Err(StateSyncError::NoProgress),
         }
     }
 
+    // Holds the storage writes while the free disk space is low, since running out of space in the
+    // middle of a write corrupts the storage.
+    async fn wait_for_disk_space(&self) {
+        if !self.disk_space.is_low() {
+            return;
+        }
+        warn!("Free disk space is low. Pausing sync until space is freed.");
+        while self.disk_space.is_low() {
+            tokio::time::sleep(DISK_SPACE_POLL_INTERVAL).await;
+        }
+        info!("Free disk space is back above the threshold. Resuming sync.");
+    }
+
+    // Delays the storage writes while the RPC server or the storage is under heavy load, so that
+    // the sync doesn't compete with the RPC requests on the storage.
+    async fn throttle_if_under_load(&self) {
+        if self.is_under_load() {
+            debug!("Throttling sync for {:?}.", self.config.throttling_sleep_duration);
+            tokio::time::sleep(self.config.throttling_sleep_duration).await;
+        }
+    }
+
+    // Returns whether the latency of the RPC requests or the wait for the write lock of the storage
+    // exceed their thresholds.
+    fn is_under_load(&self) -> bool {
+        if let Some(p95_latency) = self.rpc_load.p95_latency() {
+            if p95_latency > self.config.throttling_rpc_latency_threshold {
+                debug!("RPC p95 latency is {p95_latency:?}.");
+                return true;
+            }
+        }
+        let write_lock_wait = self.writer.last_write_lock_wait();
+        if write_lock_wait > self.config.throttling_write_lock_wait_threshold {
+            debug!("The last storage write waited {write_lock_wait:?} for the write lock.");
+            return true;
+        }
+        false
+    }
+
     #[latency_histogram("sync_store_block_latency_seconds")]
     #[instrument(skip(self, block), level = "debug", fields(block_hash = %block.header.block_hash), err)]
+    // A block whose body is invalid is given with an empty body and the error, and is recorded as
+    // quarantined.
     fn store_block(
         &mut self,
         block_number: BlockNumber,
         block: Block,
         signature: &BlockSignature,
+        body_error: Option<&str>,
     ) -> StateSyncResult {
         // Assuming the central source is trusted, detect reverts by comparing the incoming block's
         // parent hash to the current hash.
@@ -403,14 +758,49 @@ impl<
 
         debug!("Storing block.");
         trace!("Block data: {block:#?}, signature: {signature:?}");
-        self.writer
+        let block_hash = block.header.block_hash;
+        let store_body = self.reader.get_node_mode() != NodeMode::Light;
+        // The receipts of a block may be stored after its body, and then they're recorded when
+        // they're stored.
+        let execution_resources = (store_body
+            && body_error.is_none()
+            && block.body.transaction_outputs.len() == block.body.transactions.len())
+        .then(|| {
+            BlockExecutionResources::new(
+                &block.body.transactions,
+                &block.body.transaction_outputs,
+                block.header.l1_gas_price,
+            )
+        });
+        let mut txn = self
+            .writer
             .begin_rw_txn()?
             .append_header(block_number, &block.header)?
-            .append_block_signature(block_number, signature)?
-            .append_body(block_number, block.body)?
-            .commit()?;
+            .append_block_signature(block_number, signature)?;
+        if store_body {
+            txn = txn.append_body(block_number, block.body)?;
+            if let Some(body_error) = body_error {
+                txn = txn.quarantine_body(block_number, body_error)?;
+            }
+        }
+        txn.commit()?;
+        if let (true, Some(body_error)) = (store_body, body_error) {
+            warn!("Quarantined the body of block {block_number}: {body_error}");
+            metrics::increment_counter!(
+                papyrus_metrics::PAPYRUS_QUARANTINED_BLOCKS,
+                "part" => "body"
+            );
+        }
+        self.sync_notifier.notify(SyncNotification::BlockStored { block_number, block_hash });
         metrics::gauge!(papyrus_metrics::PAPYRUS_HEADER_MARKER, block_number.next().0 as f64);
-        metrics::gauge!(papyrus_metrics::PAPYRUS_BODY_MARKER, block_number.next().0 as f64);
+        if store_body {
+            metrics::gauge!(papyrus_metrics::PAPYRUS_BODY_MARKER, block_number.next().0 as f64);
+            let receipts_marker = self.reader.begin_ro_txn()?.get_receipts_marker()?;
+            metrics::gauge!(papyrus_metrics::PAPYRUS_RECEIPTS_MARKER, receipts_marker.0 as f64);
+        }
+        if let Some(execution_resources) = execution_resources {
+            record_block_execution_resources(&execution_resources);
+        }
         let dt = Utc::now()
             - Utc
                 .timestamp_opt(block.header.timestamp.0 as i64, 0)
@@ -434,12 +824,39 @@ impl<
         deployed_contract_class_definitions: IndexMap<ClassHash, DeprecatedContractClass>,
     ) -> StateSyncResult {
         // TODO(dan): verifications - verify state diff against stored header.
+        if self.config.verify_blocks {
+            self.verify_block_signature(
+                block_number,
+                block_hash,
+                &ThinStateDiff::from(state_diff.clone()),
+            )?;
+        }
+        // The signature is over the state diff of the block, without the genesis state.
+        let genesis_state = self.genesis_state.as_ref().filter(|_| block_number == BlockNumber(0));
+        let state_diff = match genesis_state {
+            Some(genesis_state) => {
+                info!("Importing the genesis state into block 0.");
+                let mut state_diff =
+                    apply_genesis_state(StateDiff::from(genesis_state.clone()), state_diff);
+                sort_state_diff(&mut state_diff);
+                state_diff
+            }
+            None => state_diff,
+        };
         debug!("Storing state diff.");
         trace!("StateDiff data: {state_diff:#?}");
-        self.writer
-            .begin_rw_txn()?
-            .append_state_diff(block_number, state_diff, deployed_contract_class_definitions)?
-            .commit()?;
+        let state_writes = BlockStateWrites::from(&state_diff);
+        let mut txn = self.writer.begin_rw_txn()?.append_state_diff(
+            block_number,
+            state_diff,
+            deployed_contract_class_definitions,
+        )?;
+        if let Some(genesis_state) = genesis_state {
+            txn = append_genesis_compiled_classes(txn, genesis_state)?;
+        }
+        txn.commit()?;
+        self.recent_state.push_block(block_number, state_writes);
+        self.sync_notifier.notify(SyncNotification::StateDiffStored { block_number });
         metrics::gauge!(papyrus_metrics::PAPYRUS_STATE_MARKER, block_number.next().0 as f64);
         let compiled_class_marker = self.reader.begin_ro_txn()?.get_compiled_class_marker()?;
         metrics::gauge!(
@@ -453,6 +870,191 @@ impl<
         Ok(())
     }
 
+    #[instrument(skip(self, thin_state_diff), level = "debug", err)]
+    fn store_thin_state_diff(
+        &mut self,
+        block_number: BlockNumber,
+        block_hash: BlockHash,
+        thin_state_diff: ThinStateDiff,
+    ) -> StateSyncResult {
+        if self.config.verify_blocks {
+            self.verify_block_signature(block_number, block_hash, &thin_state_diff)?;
+        }
+        let genesis_state = self.genesis_state.as_ref().filter(|_| block_number == BlockNumber(0));
+        let thin_state_diff = match genesis_state {
+            Some(genesis_state) => {
+                info!("Importing the genesis state into block 0.");
+                apply_genesis_thin_state(
+                    ThinStateDiff::from(StateDiff::from(genesis_state.clone())),
+                    thin_state_diff,
+                )
+            }
+            None => thin_state_diff,
+        };
+        debug!("Storing state diff without its classes.");
+        trace!("ThinStateDiff data: {thin_state_diff:#?}");
+        let state_writes = BlockStateWrites::from(&thin_state_diff);
+        let mut txn =
+            self.writer.begin_rw_txn()?.append_thin_state_diff(block_number, thin_state_diff)?;
+        // The classes of the genesis state aren't served by the central source, so they're stored
+        // right away instead of being deferred.
+        if let Some(genesis_state) = genesis_state {
+            for (class_hash, class) in &genesis_state.declared_classes {
+                txn = txn.append_deferred_class(class_hash, &class.contract_class)?;
+            }
+            for (class_hash, class) in &genesis_state.deprecated_declared_classes {
+                txn = txn.append_deferred_deprecated_class(class_hash, class.clone())?;
+            }
+            txn = append_genesis_compiled_classes(txn, genesis_state)?;
+        }
+        txn.commit()?;
+        self.recent_state.push_block(block_number, state_writes);
+        self.sync_notifier.notify(SyncNotification::StateDiffStored { block_number });
+        metrics::gauge!(papyrus_metrics::PAPYRUS_STATE_MARKER, block_number.next().0 as f64);
+        info!("Added block {} with hash {}.", block_number, block_hash);
+        Ok(())
+    }
+
+    // Stores a class whose download was deferred, and notifies the RPC server that requested it.
+    #[instrument(skip(self, class, compiled_class), level = "debug", err)]
+    fn store_deferred_class(
+        &mut self,
+        class_hash: ClassHash,
+        class: ApiContractClass,
+        compiled_class: Option<CasmContractClass>,
+    ) -> StateSyncResult {
+        let mut txn = match class {
+            ApiContractClass::ContractClass(class) => {
+                self.writer.begin_rw_txn()?.append_deferred_class(&class_hash, &class)?
+            }
+            ApiContractClass::DeprecatedContractClass(class) => {
+                self.writer.begin_rw_txn()?.append_deferred_deprecated_class(&class_hash, class)?
+            }
+        };
+        if let Some(compiled_class) = compiled_class {
+            if txn.get_casm(&class_hash)?.is_none() {
+                txn = txn.append_casm(&class_hash, &compiled_class)?;
+            }
+        }
+        txn.commit()?;
+        debug!("Added deferred class {class_hash}.");
+        self.deferred_class_requests.notify_stored(class_hash);
+        Ok(())
+    }
+
+    // Stores an empty state diff for a block whose state update is invalid, and records it as
+    // quarantined. The signature of the block isn't verified, since it's over the original state
+    // diff.
+    #[instrument(skip(self), level = "debug", err)]
+    fn store_quarantined_state_diff(
+        &mut self,
+        block_number: BlockNumber,
+        block_hash: BlockHash,
+        error: &str,
+    ) -> StateSyncResult {
+        let state_diff = StateDiff::default();
+        let state_writes = BlockStateWrites::from(&state_diff);
+        self.writer
+            .begin_rw_txn()?
+            .append_state_diff(block_number, state_diff, IndexMap::new())?
+            .quarantine_state_diff(block_number, error)?
+            .commit()?;
+        self.recent_state.push_block(block_number, state_writes);
+        self.sync_notifier.notify(SyncNotification::StateDiffStored { block_number });
+        metrics::gauge!(papyrus_metrics::PAPYRUS_STATE_MARKER, block_number.next().0 as f64);
+        warn!("Quarantined the state diff of block {block_number} with hash {block_hash}: {error}");
+        metrics::increment_counter!(
+            papyrus_metrics::PAPYRUS_QUARANTINED_BLOCKS,
+            "part" => "state_diff"
+        );
+        Ok(())
+    }
+
+    // Verifies the stored signature of the block against the sequencer public key. The signature
+    // is over the state diff commitment too, so it can be verified only once the state diff
+    // arrives.
+    fn verify_block_signature(
+        &self,
+        block_number: BlockNumber,
+        block_hash: BlockHash,
+        thin_state_diff: &ThinStateDiff,
+    ) -> StateSyncResult {
+        let Some(sequencer_pub_key) = self.sequencer_pub_key else {
+            return Ok(());
+        };
+        let txn = self.reader.begin_ro_txn()?;
+        let starknet_version = txn.get_starknet_version(block_number)?.ok_or_else(|| {
+            StorageError::DBInconsistency {
+                msg: format!(
+                    "Missing Starknet version of block {block_number} which has a header."
+                ),
+            }
+        })?;
+        // Blocks whose state diff commitment can't be recomputed aren't verified.
+        let Some(state_diff_version) = StateDiffVersion::of_starknet_version(&starknet_version)
+        else {
+            debug!(
+                "Skipping the signature verification of block {block_number}: the state diff \
+                 commitment of Starknet version {starknet_version:?} isn't supported."
+            );
+            return Ok(());
+        };
+        let signature = txn.get_block_signature(block_number)?.ok_or_else(|| {
+            StorageError::DBInconsistency {
+                msg: format!("Missing signature of block {block_number} which has a header."),
+            }
+        })?;
+        let state_diff_commitment =
+            calculate_state_diff_commitment(thin_state_diff, state_diff_version);
+        match verify_block_signature(
+            &sequencer_pub_key,
+            &signature,
+            &state_diff_commitment,
+            &block_hash,
+        ) {
+            Ok(true) => {
+                debug!("Verified signature of block {block_number}.");
+                Ok(())
+            }
+            Ok(false) | Err(_) => Err(StateSyncError::InvalidBlockSignature { block_number }),
+        }
+    }
+
+    #[instrument(skip(self, transaction_outputs), level = "debug", err)]
+    // Stores the receipts of a block that was stored without them. The block may have been
+    // reverted while its receipts were downloaded, so they're stored only if they belong to the
+    // stored block.
+    fn store_receipts(
+        &mut self,
+        block_number: BlockNumber,
+        block_hash: BlockHash,
+        transaction_outputs: Vec<TransactionOutput>,
+    ) -> StateSyncResult {
+        let txn = self.writer.begin_rw_txn()?;
+        let stored_header =
+            txn.get_block_header(block_number)?.ok_or_else(|| StorageError::DBInconsistency {
+                msg: format!("Missing header of block {block_number} which has a body."),
+            })?;
+        let stored_block_hash = stored_header.block_hash;
+        if stored_block_hash != block_hash {
+            return Err(StateSyncError::ReceiptsBlockHashMismatch {
+                block_number,
+                receipts_block_hash: block_hash,
+                stored_block_hash,
+            });
+        }
+        let execution_resources = BlockExecutionResources::new(
+            &txn.get_block_transactions(block_number)?.unwrap_or_default(),
+            &transaction_outputs,
+            stored_header.l1_gas_price,
+        );
+        debug!("Storing receipts.");
+        txn.append_receipts(block_number, transaction_outputs)?.commit()?;
+        metrics::gauge!(papyrus_metrics::PAPYRUS_RECEIPTS_MARKER, block_number.next().0 as f64);
+        record_block_execution_resources(&execution_resources);
+        Ok(())
+    }
+
     #[latency_histogram("sync_store_compiled_class_latency_seconds")]
     #[instrument(skip(self, compiled_class), level = "debug", err)]
     fn store_compiled_class(
@@ -494,6 +1096,7 @@ impl<
         &mut self,
         block_number: BlockNumber,
         block_hash: BlockHash,
+        l1_block: L1BlockReference,
     ) -> StateSyncResult {
         let txn = self.writer.begin_rw_txn()?;
         // Missing header can be because of a base layer reorg, the matching header may be reverted.
@@ -510,11 +1113,30 @@ impl<
             });
         }
         info!("Verified block {block_number} hash against base layer.");
-        txn.update_base_layer_block_marker(&block_number.next())?.commit()?;
+        // The base layer block is stored so that a reorg that removes it is detected also after a
+        // restart.
+        let l1_block = BaseLayerL1Block { number: l1_block.number, hash: l1_block.hash.0 };
+        txn.update_base_layer_block_marker_from_l1_block(&block_number.next(), &l1_block)?
+            .commit()?;
+        self.sync_notifier.notify(SyncNotification::BlockAcceptedOnBaseLayer { block_number });
         metrics::gauge!(papyrus_metrics::PAPYRUS_BASE_LAYER_MARKER, block_number.next().0 as f64);
         Ok(())
     }
 
+    #[instrument(skip(self), level = "debug", err)]
+    // Rolls the base layer marker back to the given block, if it's after it.
+    fn revert_base_layer_marker(&mut self, block_number: BlockNumber) -> StateSyncResult {
+        let txn = self.writer.begin_rw_txn()?;
+        if txn.get_base_layer_block_marker()? <= block_number {
+            return Ok(());
+        }
+        warn!("Base layer reorg, blocks from {block_number} are no longer accepted on it.");
+        txn.update_base_layer_block_marker(&block_number)?.commit()?;
+        self.sync_notifier.notify(SyncNotification::BlockRevertedOnBaseLayer { block_number });
+        metrics::gauge!(papyrus_metrics::PAPYRUS_BASE_LAYER_MARKER, block_number.0 as f64);
+        Ok(())
+    }
+
     // Compares the block's parent hash to the stored block.
     fn verify_parent_block_hash(
         &self,
@@ -581,16 +1203,23 @@ impl<
         if let Some(header) = res.1 {
             reverted_block_hash = Some(header.block_hash);
 
-            let res = txn.revert_body(block_number)?;
-            txn = res.0;
+            // A light node has no body and state to revert.
+            if self.reader.get_node_mode() != NodeMode::Light {
+                let res = txn.revert_body(block_number)?;
+                txn = res.0;
 
-            let res = txn.revert_state_diff(block_number)?;
-            txn = res.0;
+                let res = txn.revert_state_diff(block_number)?;
+                txn = res.0;
+            }
         }
 
+        // Removed before the commit, so that readers never get the state of a reverted block from
+        // the cache.
+        self.recent_state.revert_from(block_number);
         txn.commit()?;
         if let Some(hash) = reverted_block_hash {
             info!(%hash, "Reverted block.");
+            self.sync_notifier.notify(SyncNotification::BlockReverted { block_number });
         }
         Ok(())
     }
@@ -612,6 +1241,31 @@ impl<
         }
     }
 }
+
+// Stores the compiled classes of the Cairo 1 classes of the genesis state, which the central source
+// doesn't serve. Should be called after the state diff of block 0 is appended, so that the compiled
+// class marker advances past it.
+fn append_genesis_compiled_classes<'env>(
+    mut txn: StorageTxn<'env, RW>,
+    genesis_state: &GenesisState,
+) -> StorageResult<StorageTxn<'env, RW>> {
+    for (class_hash, class) in &genesis_state.declared_classes {
+        if txn.get_casm(class_hash)?.is_none() {
+            txn = txn.append_casm(class_hash, &class.compiled_class)?;
+        }
+    }
+    Ok(txn)
+}
+
+fn record_block_execution_resources(execution_resources: &BlockExecutionResources) {
+    metrics::gauge!(papyrus_metrics::PAPYRUS_BLOCK_STEPS, execution_resources.steps as f64);
+    metrics::gauge!(papyrus_metrics::PAPYRUS_BLOCK_L1_GAS, execution_resources.l1_gas as f64);
+    metrics::gauge!(
+        papyrus_metrics::PAPYRUS_BLOCK_TRANSACTIONS,
+        execution_resources.n_transactions as f64
+    );
+}
+
 // TODO(dvir): consider gathering in a single pending argument instead.
 #[allow(clippy::too_many_arguments)]
 fn stream_new_blocks<
@@ -625,8 +1279,10 @@ fn stream_new_blocks<
     pending_data: Arc<RwLock<PendingData>>,
     pending_classes: Arc<RwLock<PendingClasses>>,
     block_propagation_sleep_duration: Duration,
+    max_block_propagation_sleep_duration: Duration,
     pending_sleep_duration: Duration,
     max_stream_size: u32,
+    invalid_block_policy: InvalidBlockPolicy,
 ) -> impl Stream<Item = Result<SyncEvent, StateSyncError>> {
     try_stream! {
         loop {
@@ -654,8 +1310,18 @@ fn stream_new_blocks<
                     ).await?;
                 }
                 else{
-                    debug!("Blocks syncing reached the last known block, waiting for blockchain to advance.");
-                    tokio::time::sleep(block_propagation_sleep_duration).await;
+                    let now = Utc::now().timestamp().try_into().unwrap_or_default();
+                    let sleep_duration = with_jitter(block_polling_interval(
+                        &reader,
+                        block_propagation_sleep_duration,
+                        max_block_propagation_sleep_duration,
+                        now,
+                    )?);
+                    debug!(
+                        "Blocks syncing reached the last known block, waiting {sleep_duration:?} \
+                         for blockchain to advance."
+                    );
+                    tokio::time::sleep(sleep_duration).await;
                 };
                 continue;
             }
@@ -665,7 +1331,22 @@ fn stream_new_blocks<
                 central_source.stream_new_blocks(header_marker, up_to).fuse();
             pin_mut!(block_stream);
             while let Some(maybe_block) = block_stream.next().await {
-                let (block_number, block, signature) = maybe_block?;
+                let (block_number, block, signature) = match maybe_block {
+                    Err(CentralError::InvalidBlock { header, signature, source })
+                        if invalid_block_policy == InvalidBlockPolicy::Quarantine =>
+                    {
+                        // The stream ends at the invalid block, so the blocks after it are
+                        // downloaded again in the next iteration.
+                        yield SyncEvent::QuarantinedBlockAvailable {
+                            block_number: header.block_number,
+                            block: Block { header: *header, body: BlockBody::default() },
+                            signature,
+                            error: source.to_string(),
+                        };
+                        break;
+                    }
+                    maybe_block => maybe_block?,
+                };
                 yield SyncEvent::BlockAvailable { block_number, block , signature };
             }
         }
@@ -677,8 +1358,13 @@ fn stream_new_state_diffs<TCentralSource: CentralSourceTrait + Sync + Send>(
     central_source: Arc<TCentralSource>,
     block_propagation_sleep_duration: Duration,
     max_stream_size: u32,
+    invalid_block_policy: InvalidBlockPolicy,
 ) -> impl Stream<Item = Result<SyncEvent, StateSyncError>> {
     try_stream! {
+        // The stream may fail at an invalid state update before it yields the ones before it. In
+        // that case, the next streams end before the invalid state update, and it's quarantined
+        // only once all the state diffs before it are stored.
+        let mut invalid_state_update: Option<BlockNumber> = None;
         loop {
             let txn = reader.begin_ro_txn()?;
             let state_marker = txn.get_state_marker()?;
@@ -689,19 +1375,54 @@ fn stream_new_state_diffs<TCentralSource: CentralSourceTrait + Sync + Send>(
                 tokio::time::sleep(block_propagation_sleep_duration).await;
                 continue;
             }
-            let up_to = min(last_block_number, BlockNumber(state_marker.0 + max_stream_size as u64));
+            let mut up_to =
+                min(last_block_number, BlockNumber(state_marker.0 + max_stream_size as u64));
+            if let Some(invalid_block_number) =
+                invalid_state_update.filter(|block_number| *block_number > state_marker)
+            {
+                up_to = min(up_to, invalid_block_number);
+            }
             debug!("Downloading state diffs [{} - {}).", state_marker, up_to);
             let state_diff_stream =
                 central_source.stream_state_updates(state_marker, up_to).fuse();
             pin_mut!(state_diff_stream);
 
+            let mut next_block_number = state_marker;
             while let Some(maybe_state_diff) = state_diff_stream.next().await {
                 let (
                     block_number,
                     block_hash,
                     mut state_diff,
                     deployed_contract_class_definitions,
-                ) = maybe_state_diff?;
+                ) = match maybe_state_diff {
+                    Err(CentralError::InvalidStateUpdate { block_number, source })
+                        if invalid_block_policy == InvalidBlockPolicy::Quarantine =>
+                    {
+                        if block_number != next_block_number {
+                            invalid_state_update = Some(block_number);
+                            break;
+                        }
+                        let block_hash = reader
+                            .begin_ro_txn()?
+                            .get_block_header(block_number)?
+                            .ok_or(StorageError::DBInconsistency {
+                                msg: format!(
+                                    "Missing header of block {block_number} which has a state \
+                                     update."
+                                ),
+                            })?
+                            .block_hash;
+                        invalid_state_update = None;
+                        yield SyncEvent::QuarantinedStateDiffAvailable {
+                            block_number,
+                            block_hash,
+                            error: source.to_string(),
+                        };
+                        break;
+                    }
+                    maybe_state_diff => maybe_state_diff?,
+                };
+                next_block_number = block_number.next();
                 sort_state_diff(&mut state_diff);
                 yield SyncEvent::StateDiffAvailable {
                     block_number,
@@ -738,6 +1459,13 @@ impl StateSync {
         central_source: CentralSource,
         pending_source: PendingSource,
         base_layer_source: EthereumBaseLayerSource,
+        rpc_load: Arc<RpcLoad>,
+        sync_notifier: Arc<SyncNotifier>,
+        disk_space: Arc<DiskSpaceStatus>,
+        recent_state: Arc<RecentStateCache>,
+        deferred_class_requests: Arc<DeferredClassRequests>,
+        source_reports: Arc<SourceReports>,
+        maintenance_requests: Arc<MaintenanceRequests>,
         reader: StorageReader,
         writer: StorageWriter,
     ) -> Self {
@@ -749,13 +1477,163 @@ impl StateSync {
             central_source: Arc::new(central_source),
             pending_source: Arc::new(pending_source),
             base_layer_source: Arc::new(base_layer_source),
+            rpc_load,
+            sync_notifier,
+            disk_space,
+            recent_state,
+            deferred_class_requests,
+            source_reports,
+            maintenance_requests,
             reader,
             writer,
             sequencer_pub_key: None,
+            genesis_state: None,
+        }
+    }
+}
+
+fn stream_new_thin_state_diffs<TCentralSource: CentralSourceTrait + Sync + Send>(
+    reader: StorageReader,
+    central_source: Arc<TCentralSource>,
+    block_propagation_sleep_duration: Duration,
+    max_stream_size: u32,
+) -> impl Stream<Item = Result<SyncEvent, StateSyncError>> {
+    try_stream! {
+        loop {
+            let txn = reader.begin_ro_txn()?;
+            let state_marker = txn.get_state_marker()?;
+            let last_block_number = txn.get_header_marker()?;
+            drop(txn);
+            if state_marker == last_block_number {
+                debug!("State updates syncing reached the last downloaded block, waiting for more blocks.");
+                tokio::time::sleep(block_propagation_sleep_duration).await;
+                continue;
+            }
+            let up_to =
+                min(last_block_number, BlockNumber(state_marker.0 + max_stream_size as u64));
+            debug!("Downloading state diffs without their classes [{} - {}).", state_marker, up_to);
+            let thin_state_diff_stream =
+                central_source.stream_thin_state_updates(state_marker, up_to).fuse();
+            pin_mut!(thin_state_diff_stream);
+
+            while let Some(maybe_thin_state_diff) = thin_state_diff_stream.next().await {
+                let (block_number, block_hash, thin_state_diff) = maybe_thin_state_diff?;
+                yield SyncEvent::ThinStateDiffAvailable {
+                    block_number,
+                    block_hash,
+                    thin_state_diff,
+                };
+            }
         }
     }
 }
 
+// Streams the classes that the RPC server requests, out of the classes whose download was
+// deferred. A class that fails to be downloaded is skipped, and the request times out.
+fn stream_deferred_classes<TCentralSource: CentralSourceTrait + Sync + Send>(
+    reader: StorageReader,
+    central_source: Arc<TCentralSource>,
+    deferred_class_requests: Arc<DeferredClassRequests>,
+) -> impl Stream<Item = Result<SyncEvent, StateSyncError>> {
+    try_stream! {
+        loop {
+            let class_hash = deferred_class_requests.next_request().await;
+            let is_deferred = reader
+                .begin_ro_txn()?
+                .get_state_reader()?
+                .get_deferred_class_block_number(&class_hash)?
+                .is_some();
+            // The class was already stored, possibly by a previous request.
+            if !is_deferred {
+                deferred_class_requests.notify_stored(class_hash);
+                continue;
+            }
+            debug!("Downloading deferred class {class_hash}.");
+            let class = match central_source.get_class(class_hash).await {
+                Ok(class) => class,
+                Err(err) => {
+                    warn!("Failed to download deferred class {class_hash}: {err}");
+                    continue;
+                }
+            };
+            let compiled_class = match class {
+                ApiContractClass::ContractClass(_) => {
+                    match central_source.get_compiled_class(class_hash).await {
+                        Ok(compiled_class) => Some(compiled_class),
+                        Err(err) => {
+                            warn!("Failed to download the compiled class of {class_hash}: {err}");
+                            continue;
+                        }
+                    }
+                }
+                ApiContractClass::DeprecatedContractClass(_) => None,
+            };
+            yield SyncEvent::DeferredClassAvailable { class_hash, class, compiled_class };
+        }
+    }
+}
+
+// Streams the reports about data sources that other components queued.
+fn stream_source_reports(
+    source_reports: Arc<SourceReports>,
+) -> impl Stream<Item = Result<SyncEvent, StateSyncError>> {
+    try_stream! {
+        loop {
+            let reports = source_reports.next_reports().await;
+            yield SyncEvent::SourcesReported { reports };
+        }
+    }
+}
+
+fn stream_maintenance_requests(
+    maintenance_requests: Arc<MaintenanceRequests>,
+) -> impl Stream<Item = Result<SyncEvent, StateSyncError>> {
+    try_stream! {
+        loop {
+            let request = maintenance_requests.next_request().await;
+            yield SyncEvent::PruneRequested { request };
+        }
+    }
+}
+
+/// Prunes the state history as the request asks, and responds with the result. A failure to prune
+/// fails only the request. Used by the owner of the storage writer, which is the sync when it runs.
+pub fn prune_state_history(writer: &mut StorageWriter, request: PruneRequest) {
+    let PruneRequest { below, response } = request;
+    info!("Pruning the state history below block {below}.");
+    let result = writer
+        .begin_rw_txn()
+        .and_then(|txn| txn.prune_state_history_below(below))
+        .and_then(|txn| txn.commit())
+        .map_err(|err| {
+            warn!("Failed to prune the state history below block {below}: {err}.");
+            err.to_string()
+        });
+    // The requester may have stopped waiting for the response.
+    let _ = response.send(result);
+}
+
+/// Records the reports in the reputation of their sources. Used by the owner of the storage writer,
+/// which is the sync when it runs.
+pub fn store_source_reports(
+    writer: &mut StorageWriter,
+    reports: Vec<SourceReport>,
+) -> StorageResult<()> {
+    let mut txn = writer.begin_rw_txn()?;
+    for report in reports {
+        txn = match report {
+            SourceReport::Success { source_id, latency } => {
+                txn.record_source_success(&source_id, latency)?
+            }
+            SourceReport::Error { source_id } => txn.record_source_error(&source_id)?,
+            SourceReport::Ban { source_id, timestamp } => {
+                txn.record_source_ban(&source_id, timestamp)?
+            }
+        };
+    }
+    txn.commit()
+}
+
 fn stream_new_compiled_classes<TCentralSource: CentralSourceTrait + Sync + Send>(
     reader: StorageReader,
     central_source: Arc<TCentralSource>,
@@ -804,6 +1682,38 @@ fn stream_new_compiled_classes<TCentralSource: CentralSourceTrait + Sync + Send>
     }
 }
 
+// Streams the receipts of the blocks whose bodies were stored without them.
+fn stream_new_receipts<TCentralSource: CentralSourceTrait + Sync + Send>(
+    reader: StorageReader,
+    central_source: Arc<TCentralSource>,
+    block_propagation_sleep_duration: Duration,
+    max_stream_size: u32,
+) -> impl Stream<Item = Result<SyncEvent, StateSyncError>> {
+    try_stream! {
+        loop {
+            let txn = reader.begin_ro_txn()?;
+            let receipts_marker = txn.get_receipts_marker()?;
+            let body_marker = txn.get_body_marker()?;
+            drop(txn);
+            if receipts_marker == body_marker {
+                debug!("Receipts syncing reached the last downloaded body, waiting for more blocks.");
+                tokio::time::sleep(block_propagation_sleep_duration).await;
+                continue;
+            }
+            let up_to = min(body_marker, BlockNumber(receipts_marker.0 + max_stream_size as u64));
+            debug!("Downloading receipts of blocks [{} - {}).", receipts_marker, up_to);
+            let transaction_outputs_stream =
+                central_source.stream_transaction_outputs(receipts_marker, up_to).fuse();
+            pin_mut!(transaction_outputs_stream);
+
+            while let Some(maybe_transaction_outputs) = transaction_outputs_stream.next().await {
+                let (block_number, block_hash, transaction_outputs) = maybe_transaction_outputs?;
+                yield SyncEvent::ReceiptsAvailable { block_number, block_hash, transaction_outputs };
+            }
+        }
+    }
+}
+
 // TODO(dvir): consider combine this function and store_base_layer_block.
 fn stream_new_base_layer_block<TBaseLayerSource: BaseLayerSourceTrait + Sync>(
     reader: StorageReader,
@@ -811,20 +1721,37 @@ fn stream_new_base_layer_block<TBaseLayerSource: BaseLayerSourceTrait + Sync>(
     base_layer_propagation_sleep_duration: Duration,
 ) -> impl Stream<Item = Result<SyncEvent, StateSyncError>> {
     try_stream! {
+        let txn = reader.begin_ro_txn()?;
+        let stored_l1_blocks = txn
+            .get_base_layer_l1_blocks()?
+            .into_iter()
+            .map(|(marker, l1_block)| {
+                let l1_block =
+                    L1BlockReference { number: l1_block.number, hash: L1BlockHash(l1_block.hash) };
+                (marker, l1_block)
+            })
+            .collect();
+        let mut observations =
+            BaseLayerObservations::new(txn.get_base_layer_block_marker()?, stored_l1_blocks);
+        drop(txn);
         loop {
             tokio::time::sleep(base_layer_propagation_sleep_duration).await;
+            if let Some(block_number) = observations.find_reorg(base_layer_source.as_ref()).await? {
+                yield SyncEvent::BaseLayerReorg { block_number };
+            }
             let txn = reader.begin_ro_txn()?;
             let header_marker = txn.get_header_marker()?;
             match base_layer_source.latest_proved_block().await? {
-                Some((block_number, _block_hash)) if header_marker <= block_number => {
+                Some((block_number, _block_hash, _l1_block)) if header_marker <= block_number => {
                     debug!(
                         "Sync headers ({header_marker}) is behind the base layer tip \
                          ({block_number}), waiting for sync to advance."
                     );
                 }
-                Some((block_number, block_hash)) => {
+                Some((block_number, block_hash, l1_block)) => {
                     debug!("Returns a block from the base layer. Block number: {block_number}.");
-                    yield SyncEvent::NewBaseLayerBlock { block_number, block_hash }
+                    observations.record(l1_block, block_number);
+                    yield SyncEvent::NewBaseLayerBlock { block_number, block_hash, l1_block }
                 }
                 None => {
                     debug!(
@@ -842,7 +1769,12 @@ fn stream_new_base_layer_block<TBaseLayerSource: BaseLayerSourceTrait + Sync>(
 // TODO(dvir): add a test for this scenario.
 fn check_sync_progress(
     reader: StorageReader,
+    defer_class_download: bool,
 ) -> impl Stream<Item = Result<SyncEvent, StateSyncError>> {
+    // A light node doesn't sync the state, so only its headers are expected to progress.
+    let sync_state = reader.get_node_mode() != NodeMode::Light;
+    // The compiled classes of deferred classes are downloaded only on demand.
+    let sync_compiled_classes = sync_state && !defer_class_download;
     try_stream! {
         let mut txn=reader.begin_ro_txn()?;
         let mut header_marker=txn.get_header_marker()?;
@@ -855,7 +1787,10 @@ fn check_sync_progress(
             let new_header_marker=txn.get_header_marker()?;
             let new_state_marker=txn.get_state_marker()?;
             let new_casm_marker=txn.get_compiled_class_marker()?;
-            if header_marker==new_header_marker || state_marker==new_state_marker || casm_marker==new_casm_marker{
+            if header_marker==new_header_marker
+                || (sync_state && state_marker==new_state_marker)
+                || (sync_compiled_classes && casm_marker==new_casm_marker)
+            {
                 debug!("No progress in the sync. Return NoProgress event.");
                 yield SyncEvent::NoProgress;
             }
@@ -2,7 +2,7 @@ use async_trait::async_trait;
 #[cfg(test)]
 use mockall::automock;
 use papyrus_base_layer::ethereum_base_layer_contract::EthereumBaseLayerContract;
-use papyrus_base_layer::BaseLayerContract;
+use papyrus_base_layer::{BaseLayerContract, L1BlockHash, L1BlockReference};
 use starknet_api::block::{BlockHash, BlockNumber};
 
 pub type EthereumBaseLayerSource = EthereumBaseLayerContract;
@@ -22,9 +22,18 @@ impl<Error: std::error::Error + Sync + Send> BaseLayerSourceErrorTrait for Error
 #[cfg_attr(test, automock)]
 #[async_trait]
 pub trait BaseLayerSourceTrait {
+    /// Returns the latest block proved on the base layer, and the base layer block it was read
+    /// from.
     async fn latest_proved_block(
         &self,
-    ) -> Result<Option<(BlockNumber, BlockHash)>, BaseLayerSourceError>;
+    ) -> Result<Option<(BlockNumber, BlockHash, L1BlockReference)>, BaseLayerSourceError>;
+
+    /// Returns the hash of the base layer block with the given number, or None if the base layer
+    /// doesn't have such a block.
+    async fn l1_block_hash(
+        &self,
+        l1_block_number: u64,
+    ) -> Result<Option<L1BlockHash>, BaseLayerSourceError>;
 }
 
 #[async_trait]
@@ -35,8 +44,17 @@ impl<
 {
     async fn latest_proved_block(
         &self,
-    ) -> Result<Option<(BlockNumber, BlockHash)>, BaseLayerSourceError> {
-        self.latest_proved_block(None)
+    ) -> Result<Option<(BlockNumber, BlockHash, L1BlockReference)>, BaseLayerSourceError> {
+        self.latest_proved_block_with_l1_block(None)
+            .await
+            .map_err(|e| BaseLayerSourceError::BaseLayerContractError(Box::new(e)))
+    }
+
+    async fn l1_block_hash(
+        &self,
+        l1_block_number: u64,
+    ) -> Result<Option<L1BlockHash>, BaseLayerSourceError> {
+        BaseLayerContract::l1_block_hash(self, l1_block_number)
             .await
             .map_err(|e| BaseLayerSourceError::BaseLayerContractError(Box::new(e)))
     }
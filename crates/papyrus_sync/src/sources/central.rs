@@ -4,8 +4,10 @@ mod central_test;
 mod state_update_stream;
 
 use std::collections::{BTreeMap, HashMap};
-use std::num::NonZeroUsize;
-use std::sync::{Arc, Mutex};
+use std::fmt::{Debug, Formatter};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use async_stream::stream;
 use async_trait::async_trait;
@@ -14,42 +16,71 @@ use futures::stream::BoxStream;
 use futures_util::StreamExt;
 use indexmap::IndexMap;
 use itertools::chain;
-use lru::LruCache;
 #[cfg(test)]
 use mockall::automock;
+use papyrus_common::class_cache::ClassCache;
+use papyrus_common::memory_budget::MemorySize;
+use papyrus_common::outbound_connection::OutboundConnectionConfig;
 use papyrus_common::pending_classes::ApiContractClass;
+use papyrus_common::source_reports::SourceReports;
 use papyrus_common::BlockHashAndNumber;
 use papyrus_config::converters::{deserialize_optional_map, serialize_optional_map};
-use papyrus_config::dumping::{append_sub_config_name, ser_param, SerializeConfig};
+use papyrus_config::dumping::{
+    append_sub_config_name,
+    ser_optional_param,
+    ser_param,
+    SerializeConfig,
+};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use papyrus_storage::compiled_class::CasmStorageReader;
 use papyrus_storage::state::StateStorageReader;
 use papyrus_storage::{StorageError, StorageReader};
 use serde::{Deserialize, Serialize};
-use starknet_api::block::{Block, BlockHash, BlockNumber, BlockSignature};
+use starknet_api::block::{Block, BlockHash, BlockHeader, BlockNumber, BlockSignature};
 use starknet_api::core::{ClassHash, CompiledClassHash, SequencerPublicKey};
 use starknet_api::crypto::Signature;
 use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
-use starknet_api::state::StateDiff;
+use starknet_api::state::{StateDiff, ThinStateDiff};
+use starknet_api::transaction::TransactionOutput;
 use starknet_api::StarknetApiError;
-use starknet_client::reader::{ReaderClientError, StarknetFeederGatewayClient, StarknetReader};
+use starknet_client::reader::{
+    ReaderClientError,
+    StarknetFeederGatewayClient,
+    StarknetReader,
+    StateUpdate,
+};
 use starknet_client::{ClientCreationError, RetryConfig};
 use tracing::{debug, trace};
 
 use self::state_update_stream::{StateUpdateStream, StateUpdateStreamConfig};
 
 type CentralResult<T> = Result<T, CentralError>;
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+
+// Replaces the secrets of the config when it's formatted with Debug.
+const REDACTED: &str = "<redacted>";
+
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
 pub struct CentralSourceConfig {
     pub concurrent_requests: usize,
     pub url: String,
     #[serde(deserialize_with = "deserialize_optional_map")]
     pub http_headers: Option<HashMap<String, String>>,
+    /// An API key for feeder gateways that require one, sent in the `api_key_header` header.
+    pub api_key: Option<String>,
+    pub api_key_header: String,
     pub max_state_updates_to_download: usize,
     pub max_state_updates_to_store_in_memory: usize,
     pub max_classes_to_download: usize,
-    // TODO(dan): validate that class_cache_size is a positive integer.
-    pub class_cache_size: usize,
     pub retry_config: RetryConfig,
+    /// The retries of a block that the feeder gateway doesn't have yet, even though it reported a
+    /// later block. The blocks after it keep downloading meanwhile. Failed requests are retried
+    /// only by the client, according to `retry_config`.
+    pub block_retry_config: RetryConfig,
+    pub outbound_connection: OutboundConnectionConfig,
+    /// A directory to which the raw responses that fail to be parsed or validated are written, for
+    /// debugging. None if the responses shouldn't be written. See
+    /// [`starknet_client::response_capture`].
+    pub capture_dir: Option<PathBuf>,
 }
 
 impl Default for CentralSourceConfig {
@@ -58,15 +89,23 @@ impl Default for CentralSourceConfig {
             concurrent_requests: 10,
             url: String::from("https://alpha-mainnet.starknet.io/"),
             http_headers: None,
+            api_key: None,
+            api_key_header: String::from("x-api-key"),
             max_state_updates_to_download: 20,
             max_state_updates_to_store_in_memory: 20,
             max_classes_to_download: 20,
-            class_cache_size: 100,
             retry_config: RetryConfig {
                 retry_base_millis: 30,
                 retry_max_delay_millis: 30000,
                 max_retries: 10,
             },
+            block_retry_config: RetryConfig {
+                retry_base_millis: 500,
+                retry_max_delay_millis: 5000,
+                max_retries: 3,
+            },
+            outbound_connection: OutboundConnectionConfig::default(),
+            capture_dir: None,
         }
     }
 }
@@ -93,6 +132,12 @@ impl SerializeConfig for CentralSourceConfig {
                 "'k1:v1 k2:v2 ...' headers for SN-client.",
                 ParamPrivacyInput::Private,
             ),
+            ser_param(
+                "api_key_header",
+                &self.api_key_header,
+                "The header in which the API key is sent to Starknet feeder-gateway.",
+                ParamPrivacyInput::Public,
+            ),
             ser_param(
                 "max_state_updates_to_download",
                 &self.max_state_updates_to_download,
@@ -111,25 +156,85 @@ impl SerializeConfig for CentralSourceConfig {
                 "Maximum number of classes to download at a given time.",
                 ParamPrivacyInput::Public,
             ),
-            ser_param(
-                "class_cache_size",
-                &self.class_cache_size,
-                "Size of class cache, must be a positive integer.",
+        ]);
+        chain!(
+            self_params_dump,
+            ser_optional_param(
+                &self.api_key,
+                String::new(),
+                "api_key",
+                "API key for Starknet feeder-gateways that require one. If not set, no API key is \
+                 sent.",
+                ParamPrivacyInput::Private,
+            ),
+            ser_optional_param(
+                &self.capture_dir,
+                PathBuf::from("central_responses"),
+                "capture_dir",
+                "Directory to which the raw responses of Starknet feeder-gateway that fail to be \
+                 parsed or validated are written, for bug reports. At most 100 responses are \
+                 written to it. If not set, the responses are not written.",
                 ParamPrivacyInput::Public,
             ),
-        ]);
-        chain!(self_params_dump, append_sub_config_name(self.retry_config.dump(), "retry_config"))
-            .collect()
+            append_sub_config_name(self.retry_config.dump(), "retry_config"),
+            append_sub_config_name(self.block_retry_config.dump(), "block_retry_config"),
+            append_sub_config_name(self.outbound_connection.dump(), "outbound_connection")
+        )
+        .collect()
+    }
+}
+
+impl CentralSourceConfig {
+    /// The headers sent in each request to Starknet feeder-gateway, including the API key.
+    pub fn request_headers(&self) -> Option<HashMap<String, String>> {
+        let Some(api_key) = &self.api_key else {
+            return self.http_headers.clone();
+        };
+        let mut headers = self.http_headers.clone().unwrap_or_default();
+        headers.insert(self.api_key_header.clone(), api_key.clone());
+        Some(headers)
+    }
+}
+
+// The header values and the API key are secrets, so they aren't written to the logs.
+impl Debug for CentralSourceConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let redacted_http_headers = self.http_headers.as_ref().map(|http_headers| {
+            http_headers.keys().map(|name| (name.as_str(), REDACTED)).collect::<BTreeMap<_, _>>()
+        });
+        f.debug_struct("CentralSourceConfig")
+            .field("concurrent_requests", &self.concurrent_requests)
+            .field("url", &self.url)
+            .field("http_headers", &redacted_http_headers)
+            .field("api_key", &self.api_key.as_ref().map(|_| REDACTED))
+            .field("api_key_header", &self.api_key_header)
+            .field("max_state_updates_to_download", &self.max_state_updates_to_download)
+            .field(
+                "max_state_updates_to_store_in_memory",
+                &self.max_state_updates_to_store_in_memory,
+            )
+            .field("max_classes_to_download", &self.max_classes_to_download)
+            .field("retry_config", &self.retry_config)
+            .field("block_retry_config", &self.block_retry_config)
+            .field("outbound_connection", &self.outbound_connection)
+            .field("capture_dir", &self.capture_dir)
+            .finish()
     }
 }
 
 pub struct GenericCentralSource<TStarknetClient: StarknetReader + Send + Sync> {
     pub concurrent_requests: usize,
+    // The retries of a block that the feeder gateway doesn't have yet.
+    pub block_retry_config: RetryConfig,
     pub starknet_client: Arc<TStarknetClient>,
     pub storage_reader: StorageReader,
     pub state_update_stream_config: StateUpdateStreamConfig,
-    pub(crate) class_cache: Arc<Mutex<LruCache<ClassHash, ApiContractClass>>>,
-    compiled_class_cache: Arc<Mutex<LruCache<ClassHash, CasmContractClass>>>,
+    // The cache of the classes, shared with the storage reader.
+    pub(crate) class_cache: Arc<ClassCache>,
+    // The id of the source in the reputation storage, which is its URL.
+    pub source_id: String,
+    // Receives the results of the requests to the source, to record in its reputation.
+    pub source_reports: Arc<SourceReports>,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -152,6 +257,15 @@ pub enum CentralError {
     StorageError(#[from] StorageError),
     #[error("Wrong type of contract class")]
     BadContractClassType,
+    #[error("Block {} is invalid: {source}", header.block_number)]
+    InvalidBlock {
+        // The header and the signature are valid, so the block can be stored without its body.
+        header: Box<BlockHeader>,
+        signature: BlockSignature,
+        source: Arc<ReaderClientError>,
+    },
+    #[error("State update of block {block_number} is invalid: {source}")]
+    InvalidStateUpdate { block_number: BlockNumber, source: Arc<ReaderClientError> },
 }
 
 #[cfg_attr(test, automock)]
@@ -168,12 +282,25 @@ pub trait CentralSourceTrait {
         initial_block_number: BlockNumber,
         up_to_block_number: BlockNumber,
     ) -> StateUpdatesStream<'_>;
+    // Like stream_state_updates, but without downloading the classes that the state updates
+    // declare.
+    fn stream_thin_state_updates(
+        &self,
+        initial_block_number: BlockNumber,
+        up_to_block_number: BlockNumber,
+    ) -> ThinStateUpdatesStream<'_>;
 
     async fn get_block_hash(
         &self,
         block_number: BlockNumber,
     ) -> Result<Option<BlockHash>, CentralError>;
 
+    fn stream_transaction_outputs(
+        &self,
+        initial_block_number: BlockNumber,
+        up_to_block_number: BlockNumber,
+    ) -> TransactionOutputsStream<'_>;
+
     fn stream_compiled_classes(
         &self,
         initial_block_number: BlockNumber,
@@ -197,6 +324,11 @@ pub(crate) type BlocksStream<'a> =
 type CentralStateUpdate =
     (BlockNumber, BlockHash, StateDiff, IndexMap<ClassHash, DeprecatedContractClass>);
 pub(crate) type StateUpdatesStream<'a> = BoxStream<'a, CentralResult<CentralStateUpdate>>;
+type CentralThinStateUpdate = (BlockNumber, BlockHash, ThinStateDiff);
+pub(crate) type ThinStateUpdatesStream<'a> = BoxStream<'a, CentralResult<CentralThinStateUpdate>>;
+type CentralTransactionOutputs = (BlockNumber, BlockHash, Vec<TransactionOutput>);
+pub(crate) type TransactionOutputsStream<'a> =
+    BoxStream<'a, CentralResult<CentralTransactionOutputs>>;
 type CentralCompiledClass = (ClassHash, CompiledClassHash, CasmContractClass);
 pub(crate) type CompiledClassesStream<'a> = BoxStream<'a, CentralResult<CentralCompiledClass>>;
 
@@ -243,29 +375,62 @@ impl<TStarknetClient: StarknetReader + Send + Sync + 'static> CentralSourceTrait
         .boxed()
     }
 
+    // Returns a stream of state updates downloaded from the central source, without the classes
+    // they declare.
+    fn stream_thin_state_updates(
+        &self,
+        initial_block_number: BlockNumber,
+        up_to_block_number: BlockNumber,
+    ) -> ThinStateUpdatesStream<'_> {
+        stream! {
+            let mut res =
+                futures_util::stream::iter(initial_block_number.iter_up_to(up_to_block_number))
+                    .map(|bn| async move { (bn, self.starknet_client.state_update(bn).await) })
+                    .buffered(self.concurrent_requests);
+            while let Some((current_block_number, maybe_state_update)) = res.next().await {
+                match maybe_state_update {
+                    Ok(Some(state_update)) => {
+                        debug!(
+                            "Received new state update of block {current_block_number} with hash \
+                             {}.",
+                            state_update.block_hash
+                        );
+                        yield Ok((
+                            current_block_number,
+                            state_update.block_hash,
+                            client_to_thin_state_diff(state_update),
+                        ));
+                    }
+                    Ok(None) => {
+                        yield Err(CentralError::StateUpdateNotFound);
+                        return;
+                    }
+                    Err(err) => {
+                        yield Err(CentralError::ClientError(Arc::new(err)));
+                        return;
+                    }
+                }
+            }
+        }
+        .boxed()
+    }
+
     // TODO(shahak): rename.
-    // Returns a stream of blocks downloaded from the central source.
+    // Returns a stream of blocks downloaded from the central source. Up to `concurrent_requests`
+    // blocks are downloaded ahead of the one the stream is waiting for, and each of them is
+    // downloaded again while the feeder gateway doesn't have it. The stream ends at the first block
+    // that fails.
     fn stream_new_blocks(
         &self,
         initial_block_number: BlockNumber,
         up_to_block_number: BlockNumber,
     ) -> BlocksStream<'_> {
         stream! {
-            // TODO(dan): add explanation.
             let mut res =
                 futures_util::stream::iter(initial_block_number.iter_up_to(up_to_block_number))
-                    .map(|bn| async move {
-                        let block_and_signature = futures_util::try_join!(
-                            self.starknet_client.block(bn),
-                            self.starknet_client.block_signature(bn)
-                        );
-                        (bn, block_and_signature)
-                    })
+                    .map(|bn| async move { (bn, self.download_block(bn).await) })
                     .buffered(self.concurrent_requests);
-            while let Some((current_block_number, maybe_client_block)) = res.next().await
-            {
-                let maybe_central_block =
-                    client_to_central_block(current_block_number, maybe_client_block);
+            while let Some((current_block_number, maybe_central_block)) = res.next().await {
                 match maybe_central_block {
                     Ok((block, signature)) => {
                         yield Ok((current_block_number, block, signature));
@@ -280,6 +445,50 @@ impl<TStarknetClient: StarknetReader + Send + Sync + 'static> CentralSourceTrait
         .boxed()
     }
 
+    // Returns a stream of the transaction outputs of blocks downloaded from the central source,
+    // along with the hashes of the blocks they belong to. The feeder gateway serves the receipts
+    // only as part of the blocks, so the whole blocks are downloaded.
+    fn stream_transaction_outputs(
+        &self,
+        initial_block_number: BlockNumber,
+        up_to_block_number: BlockNumber,
+    ) -> TransactionOutputsStream<'_> {
+        stream! {
+            let mut res =
+                futures_util::stream::iter(initial_block_number.iter_up_to(up_to_block_number))
+                    .map(|bn| async move { (bn, self.starknet_client.block(bn).await) })
+                    .buffered(self.concurrent_requests);
+            while let Some((current_block_number, maybe_client_block)) = res.next().await {
+                let maybe_block = match maybe_client_block {
+                    Ok(Some(block)) => {
+                        block.to_starknet_api_block_and_version().map_err(|err| {
+                            CentralError::ClientError(Arc::new(err))
+                        })
+                    }
+                    Ok(None) => {
+                        Err(CentralError::BlockNotFound { block_number: current_block_number })
+                    }
+                    Err(err) => Err(CentralError::ClientError(Arc::new(err))),
+                };
+                match maybe_block {
+                    Ok(block) => {
+                        debug!("Received transaction outputs of block {current_block_number}.");
+                        yield Ok((
+                            current_block_number,
+                            block.header.block_hash,
+                            block.body.transaction_outputs,
+                        ));
+                    }
+                    Err(err) => {
+                        yield Err(err);
+                        return;
+                    }
+                }
+            }
+        }
+        .boxed()
+    }
+
     // Returns a stream of compiled classes downloaded from the central source.
     fn stream_compiled_classes(
         &self,
@@ -300,13 +509,19 @@ impl<TStarknetClient: StarknetReader + Send + Sync + 'static> CentralSourceTrait
                     }
                 })
                 .flat_map(|maybe_state_diff| match maybe_state_diff {
-                    Ok(state_diff) => {
-                        state_diff
-                            .declared_classes
-                            .into_iter()
-                            .map(Ok)
-                            .collect()
-                    }
+                    // The classes whose compiled classes were stored already, like the classes of
+                    // the genesis state, are skipped.
+                    Ok(state_diff) => state_diff
+                        .declared_classes
+                        .into_iter()
+                        .filter_map(|(class_hash, compiled_class_hash)| {
+                            match txn.get_casm(&class_hash) {
+                                Ok(Some(_)) => None,
+                                Ok(None) => Some(Ok((class_hash, compiled_class_hash))),
+                                Err(err) => Some(Err(CentralError::StorageError(err))),
+                            }
+                        })
+                        .collect(),
                     Err(err) => vec![Err(err)],
                 });
 
@@ -340,23 +555,17 @@ impl<TStarknetClient: StarknetReader + Send + Sync + 'static> CentralSourceTrait
 
     async fn get_class(&self, class_hash: ClassHash) -> Result<ApiContractClass, CentralError> {
         // TODO(shahak): Fix code duplication with StateUpdatesStream.
-        {
-            let mut class_cache = self.class_cache.lock().expect("Failed to lock class cache.");
-            if let Some(class) = class_cache.get(&class_hash) {
-                return Ok(class.clone());
-            }
+        if let Some(class) = self.class_cache.get::<ApiContractClass>(&class_hash) {
+            return Ok(ApiContractClass::clone(&class));
         }
         let client_class =
             self.starknet_client.class_by_hash(class_hash).await.map_err(Arc::new)?;
         match client_class {
             None => Err(CentralError::ClassNotFound),
             Some(class) => {
-                {
-                    let mut class_cache =
-                        self.class_cache.lock().expect("Failed to lock class cache.");
-                    class_cache.put(class_hash, class.clone().into());
-                }
-                Ok(class.into())
+                let class: ApiContractClass = class.into();
+                self.class_cache.insert(class_hash, Arc::new(class.clone()), class.memory_size());
+                Ok(class)
             }
         }
     }
@@ -365,18 +574,13 @@ impl<TStarknetClient: StarknetReader + Send + Sync + 'static> CentralSourceTrait
         &self,
         class_hash: ClassHash,
     ) -> Result<CasmContractClass, CentralError> {
-        {
-            let mut compiled_class_cache =
-                self.compiled_class_cache.lock().expect("Failed to lock class cache.");
-            if let Some(class) = compiled_class_cache.get(&class_hash) {
-                return Ok(class.clone());
-            }
+        if let Some(class) = self.class_cache.get::<CasmContractClass>(&class_hash) {
+            return Ok(CasmContractClass::clone(&class));
         }
         match self.starknet_client.compiled_class_by_hash(class_hash).await {
             Ok(Some(compiled_class)) => {
-                let mut compiled_class_cache =
-                    self.compiled_class_cache.lock().expect("Failed to lock class cache.");
-                compiled_class_cache.put(class_hash, compiled_class.clone());
+                let memory_size = compiled_class.memory_size();
+                self.class_cache.insert(class_hash, Arc::new(compiled_class.clone()), memory_size);
                 Ok(compiled_class)
             }
             Ok(None) => Err(CentralError::CompiledClassNotFound { class_hash }),
@@ -389,6 +593,56 @@ impl<TStarknetClient: StarknetReader + Send + Sync + 'static> CentralSourceTrait
     }
 }
 
+impl<TStarknetClient: StarknetReader + Send + Sync + 'static>
+    GenericCentralSource<TStarknetClient>
+{
+    // Downloads a block and its signature, and downloads them again while the feeder gateway
+    // doesn't have them, as long as the block retry config allows. Failed requests were already
+    // retried by the client, so they aren't retried here.
+    async fn download_block(
+        &self,
+        block_number: BlockNumber,
+    ) -> CentralResult<(Block, BlockSignature)> {
+        let mut retries = 0;
+        loop {
+            let request_start = Instant::now();
+            let maybe_client_block = futures_util::try_join!(
+                self.starknet_client.block(block_number),
+                self.starknet_client.block_signature(block_number)
+            );
+            // A block that the feeder gateway doesn't have yet is a successful request too.
+            match &maybe_client_block {
+                Ok(_) => self
+                    .source_reports
+                    .report_success(self.source_id.clone(), request_start.elapsed()),
+                Err(_) => self.source_reports.report_error(self.source_id.clone()),
+            }
+            match client_to_central_block(block_number, maybe_client_block) {
+                Err(CentralError::BlockNotFound { .. })
+                    if retries < self.block_retry_config.max_retries =>
+                {
+                    let delay = retry_delay(&self.block_retry_config, retries);
+                    debug!("Block {block_number} isn't available yet, retrying in {delay:?}.");
+                    tokio::time::sleep(delay).await;
+                    retries += 1;
+                }
+                maybe_central_block => return maybe_central_block,
+            }
+        }
+    }
+}
+
+// The delay before the given retry, growing exponentially from the base delay up to the maximal
+// delay.
+fn retry_delay(retry_config: &RetryConfig, retry: usize) -> Duration {
+    let exponent = u32::try_from(retry).unwrap_or(u32::MAX);
+    let delay_millis = retry_config
+        .retry_base_millis
+        .saturating_mul(2_u64.saturating_pow(exponent))
+        .min(retry_config.retry_max_delay_millis);
+    Duration::from_millis(delay_millis)
+}
+
 fn client_to_central_block(
     current_block_number: BlockNumber,
     maybe_client_block: Result<
@@ -403,16 +657,23 @@ fn client_to_central_block(
         Ok((Some(block), Some(signature_data))) => {
             debug!("Received new block {current_block_number} with hash {}.", block.block_hash());
             trace!("Block: {block:#?}, signature data: {signature_data:#?}.");
-            let block = block
-                .to_starknet_api_block_and_version()
-                .map_err(|err| CentralError::ClientError(Arc::new(err)))?;
-            Ok((
-                block,
-                BlockSignature(Signature {
-                    r: signature_data.signature[0],
-                    s: signature_data.signature[1],
-                }),
-            ))
+            let signature = BlockSignature(Signature {
+                r: signature_data.signature[0],
+                s: signature_data.signature[1],
+            });
+            // Kept for storing the block without its body if it fails to be validated.
+            let header = block.to_starknet_api_header();
+            let block = block.to_starknet_api_block_and_version().map_err(|err| {
+                if !err.is_invalid_data() {
+                    return CentralError::ClientError(Arc::new(err));
+                }
+                CentralError::InvalidBlock {
+                    header: Box::new(header),
+                    signature,
+                    source: Arc::new(err),
+                }
+            })?;
+            Ok((block, signature))
         }
         Ok((None, Some(_))) => {
             debug!("Block {current_block_number} not found, but signature was found.");
@@ -430,6 +691,51 @@ fn client_to_central_block(
     }
 }
 
+// Converts the state diff of a state update, whose classes aren't downloaded, sorted like the state
+// diffs that the sync stores with their classes.
+fn client_to_thin_state_diff(state_update: StateUpdate) -> ThinStateDiff {
+    let starknet_client::reader::StateDiff {
+        storage_diffs,
+        deployed_contracts,
+        declared_classes,
+        old_declared_contracts,
+        nonces,
+        replaced_classes,
+    } = state_update.state_diff;
+    let mut thin_state_diff = ThinStateDiff {
+        deployed_contracts: deployed_contracts
+            .into_iter()
+            .map(|deployed_contract| (deployed_contract.address, deployed_contract.class_hash))
+            .collect(),
+        storage_diffs: storage_diffs
+            .into_iter()
+            .map(|(address, entries)| {
+                (address, entries.into_iter().map(|entry| (entry.key, entry.value)).collect())
+            })
+            .collect(),
+        declared_classes: declared_classes
+            .into_iter()
+            .map(|hash_entry| (hash_entry.class_hash, hash_entry.compiled_class_hash))
+            .collect(),
+        deprecated_declared_classes: old_declared_contracts,
+        nonces,
+        replaced_classes: replaced_classes
+            .into_iter()
+            .map(|replaced_class| (replaced_class.address, replaced_class.class_hash))
+            .collect(),
+    };
+    thin_state_diff.deployed_contracts.sort_unstable_keys();
+    thin_state_diff.storage_diffs.sort_unstable_keys();
+    for storage_entries in thin_state_diff.storage_diffs.values_mut() {
+        storage_entries.sort_unstable_keys();
+    }
+    thin_state_diff.declared_classes.sort_unstable_keys();
+    thin_state_diff.deprecated_declared_classes.sort_unstable();
+    thin_state_diff.nonces.sort_unstable_keys();
+    thin_state_diff.replaced_classes.sort_unstable_keys();
+    thin_state_diff
+}
+
 pub type CentralSource = GenericCentralSource<StarknetFeederGatewayClient>;
 
 impl CentralSource {
@@ -437,16 +743,23 @@ impl CentralSource {
         config: CentralSourceConfig,
         node_version: &'static str,
         storage_reader: StorageReader,
+        source_reports: Arc<SourceReports>,
     ) -> Result<CentralSource, ClientCreationError> {
-        let starknet_client = StarknetFeederGatewayClient::new(
+        let mut starknet_client = StarknetFeederGatewayClient::new(
             &config.url,
-            config.http_headers,
+            config.request_headers(),
             node_version,
             config.retry_config,
-        )?;
+        )?
+        .with_outbound_connection(&config.outbound_connection)?;
+        if let Some(capture_dir) = &config.capture_dir {
+            starknet_client = starknet_client.with_response_capture(capture_dir.clone());
+        }
 
+        let class_cache = storage_reader.class_cache();
         Ok(CentralSource {
             concurrent_requests: config.concurrent_requests,
+            block_retry_config: config.block_retry_config,
             starknet_client: Arc::new(starknet_client),
             storage_reader,
             state_update_stream_config: StateUpdateStreamConfig {
@@ -454,14 +767,9 @@ impl CentralSource {
                 max_state_updates_to_store_in_memory: config.max_state_updates_to_store_in_memory,
                 max_classes_to_download: config.max_classes_to_download,
             },
-            class_cache: Arc::from(Mutex::new(LruCache::new(
-                NonZeroUsize::new(config.class_cache_size)
-                    .expect("class_cache_size should be a positive integer."),
-            ))),
-            compiled_class_cache: Arc::from(Mutex::new(LruCache::new(
-                NonZeroUsize::new(config.class_cache_size)
-                    .expect("class_cache_size should be a positive integer."),
-            ))),
+            class_cache,
+            source_id: config.url,
+            source_reports,
         })
     }
 }
@@ -1,12 +1,13 @@
 use std::collections::VecDeque;
 use std::pin::Pin;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use std::task::Poll;
 
 use futures_util::stream::FuturesOrdered;
 use futures_util::{Future, Stream, StreamExt};
 use indexmap::IndexMap;
-use lru::LruCache;
+use papyrus_common::class_cache::ClassCache;
+use papyrus_common::memory_budget::MemorySize;
 use papyrus_storage::state::StateStorageReader;
 use papyrus_storage::StorageReader;
 use starknet_api::block::BlockNumber;
@@ -40,7 +41,7 @@ pub(crate) struct StateUpdateStream<TStarknetClient: StarknetReader + Send + 'st
     classes_to_download: VecDeque<ClassHash>,
     download_class_tasks: TasksQueue<CentralResult<Option<ApiContractClass>>>,
     downloaded_classes: VecDeque<ApiContractClass>,
-    class_cache: Arc<Mutex<LruCache<ClassHash, ApiContractClass>>>,
+    class_cache: Arc<ClassCache>,
     config: StateUpdateStreamConfig,
 }
 
@@ -91,7 +92,7 @@ impl<TStarknetClient: StarknetReader + Send + Sync + 'static> StateUpdateStream<
         starknet_client: Arc<TStarknetClient>,
         storage_reader: StorageReader,
         config: StateUpdateStreamConfig,
-        class_cache: Arc<Mutex<LruCache<ClassHash, ApiContractClass>>>,
+        class_cache: Arc<ClassCache>,
     ) -> Self {
         StateUpdateStream {
             initial_block_number,
@@ -238,6 +239,10 @@ impl<TStarknetClient: StarknetReader + Send + Sync + 'static> StateUpdateStream<
             }
             // Class was not found.
             Ok(None) => Err(CentralError::ClassNotFound),
+            // The state update was downloaded whole, but it isn't valid.
+            Err(err) if err.is_invalid_data() => {
+                Err(CentralError::InvalidStateUpdate { block_number, source: Arc::new(err) })
+            }
             // An error occurred while downloading the class.
             Err(err) => Err(CentralError::ClientError(err.into())),
         }
@@ -334,20 +339,18 @@ fn client_to_central_state_update(
 // Given a class hash, returns the corresponding class definition.
 // First tries to retrieve the class from the storage.
 // If not found in the storage, the class is downloaded.
-#[instrument(skip(starknet_client, storage_reader), level = "debug", err)]
+#[instrument(skip(cache, starknet_client, storage_reader), level = "debug", err)]
 async fn download_class_if_necessary<TStarknetClient: StarknetReader>(
-    cache: Arc<Mutex<LruCache<ClassHash, ApiContractClass>>>,
+    cache: Arc<ClassCache>,
     class_hash: ClassHash,
     starknet_client: Arc<TStarknetClient>,
     storage_reader: StorageReader,
 ) -> CentralResult<Option<ApiContractClass>> {
-    {
-        let mut cache = cache.lock().expect("Failed to lock class cache.");
-        if let Some(class) = cache.get(&class_hash) {
-            return Ok(Some(class.clone()));
-        }
+    if let Some(class) = cache.get::<ApiContractClass>(&class_hash) {
+        return Ok(Some(ApiContractClass::clone(&class)));
     }
 
+    // The classes read from the storage are cached by the storage.
     let txn = storage_reader.begin_ro_txn()?;
     let state_reader = txn.get_state_reader()?;
     let block_number = txn.get_state_marker()?;
@@ -356,10 +359,6 @@ async fn download_class_if_necessary<TStarknetClient: StarknetReader>(
     // Check declared classes.
     if let Ok(Some(class)) = state_reader.get_class_definition_at(state_number, &class_hash) {
         trace!("Class {:?} retrieved from storage.", class_hash);
-        {
-            let mut cache = cache.lock().expect("Failed to lock class cache.");
-            cache.put(class_hash, ApiContractClass::ContractClass(class.clone()));
-        }
         return Ok(Some(ApiContractClass::ContractClass(class)));
     };
 
@@ -368,10 +367,6 @@ async fn download_class_if_necessary<TStarknetClient: StarknetReader>(
         state_reader.get_deprecated_class_definition_at(state_number, &class_hash)
     {
         trace!("Deprecated class {:?} retrieved from storage.", class_hash);
-        {
-            let mut cache = cache.lock().expect("Failed to lock class cache.");
-            cache.put(class_hash, ApiContractClass::DeprecatedContractClass(class.clone()));
-        }
         return Ok(Some(ApiContractClass::DeprecatedContractClass(class)));
     }
 
@@ -381,11 +376,9 @@ async fn download_class_if_necessary<TStarknetClient: StarknetReader>(
     match client_class {
         None => Ok(None),
         Some(class) => {
-            {
-                let mut cache = cache.lock().expect("Failed to lock class cache.");
-                cache.put(class_hash, class.clone().into());
-            }
-            Ok(Some(class.into()))
+            let class: ApiContractClass = class.into();
+            cache.insert(class_hash, Arc::new(class.clone()), class.memory_size());
+            Ok(Some(class))
         }
     }
 }
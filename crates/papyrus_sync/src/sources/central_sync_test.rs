@@ -7,20 +7,31 @@ use async_trait::async_trait;
 use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 use futures::StreamExt;
 use indexmap::IndexMap;
+use papyrus_base_layer::{L1BlockHash, L1BlockReference};
 use papyrus_common::pending_classes::{ApiContractClass, PendingClasses};
 use papyrus_common::BlockHashAndNumber;
 use papyrus_storage::base_layer::BaseLayerStorageReader;
 use papyrus_storage::header::HeaderStorageReader;
+use papyrus_storage::quarantine::{QuarantineStorageReader, QuarantinedBlock};
 use papyrus_storage::state::StateStorageReader;
 use papyrus_storage::test_utils::get_test_storage;
 use papyrus_storage::{StorageError, StorageReader, StorageWriter};
-use starknet_api::block::{Block, BlockBody, BlockHash, BlockHeader, BlockNumber, BlockSignature};
+use starknet_api::block::{
+    Block,
+    BlockBody,
+    BlockHash,
+    BlockHeader,
+    BlockNumber,
+    BlockSignature,
+    StarknetVersion,
+};
 use starknet_api::core::{ClassHash, SequencerPublicKey};
 use starknet_api::crypto::PublicKey;
 use starknet_api::hash::StarkFelt;
 use starknet_api::stark_felt;
 use starknet_api::state::StateDiff;
-use starknet_client::reader::PendingData;
+use starknet_api::transaction::TransactionHash;
+use starknet_client::reader::{PendingData, ReaderClientError};
 use tokio::sync::{Mutex, RwLock};
 use tracing::{debug, error};
 
@@ -31,11 +42,14 @@ use crate::sources::central::{
     CompiledClassesStream,
     MockCentralSourceTrait,
     StateUpdatesStream,
+    ThinStateUpdatesStream,
+    TransactionOutputsStream,
 };
 use crate::{
     CentralError,
     CentralSourceTrait,
     GenericStateSync,
+    InvalidBlockPolicy,
     StateSyncError,
     StateSyncResult,
     SyncConfig,
@@ -91,10 +105,10 @@ fn get_test_sync_config(verify_blocks: bool) -> SyncConfig {
     SyncConfig {
         block_propagation_sleep_duration: SYNC_SLEEP_DURATION,
         base_layer_propagation_sleep_duration: BASE_LAYER_SLEEP_DURATION,
-        recoverable_error_sleep_duration: SYNC_SLEEP_DURATION,
         blocks_max_stream_size: STREAM_SIZE,
         state_updates_max_stream_size: STREAM_SIZE,
         verify_blocks,
+        ..Default::default()
     }
 }
 
@@ -118,9 +132,17 @@ async fn run_sync(
         pending_source: Arc::new(pending_source),
         pending_classes: Arc::new(RwLock::new(PendingClasses::default())),
         base_layer_source: Arc::new(base_layer),
+        rpc_load: Arc::default(),
+        sync_notifier: Arc::default(),
+        disk_space: Arc::default(),
+        recent_state: Arc::default(),
+        deferred_class_requests: Arc::default(),
+        source_reports: Arc::default(),
+        maintenance_requests: Arc::default(),
         reader,
         writer,
         sequencer_pub_key: None,
+        genesis_state: None,
     };
 
     state_sync.run().await?;
@@ -230,13 +252,16 @@ async fn sync_happy_flow() {
             2 => Some((
                 BlockNumber(N_BLOCKS - 2),
                 create_block_hash(BlockNumber(N_BLOCKS - 2), false),
+                L1BlockReference::default(),
             )),
             _ => Some((
                 BlockNumber(N_BLOCKS - 1),
                 create_block_hash(BlockNumber(N_BLOCKS - 1), false),
+                L1BlockReference::default(),
             )),
         })
     });
+    base_layer_mock.expect_l1_block_hash().returning(|_| Ok(Some(L1BlockHash::default())));
 
     let ((reader, writer), _temp_dir) = get_test_storage();
     let sync_future = run_sync(
@@ -547,6 +572,24 @@ async fn sync_with_revert() {
             }
         }
 
+        fn stream_thin_state_updates(
+            &self,
+            _initial_block_number: BlockNumber,
+            _up_to_block_number: BlockNumber,
+        ) -> ThinStateUpdatesStream<'_> {
+            // The class download isn't deferred in this test.
+            unimplemented!();
+        }
+
+        fn stream_transaction_outputs(
+            &self,
+            _initial_block_number: BlockNumber,
+            _up_to_block_number: BlockNumber,
+        ) -> TransactionOutputsStream<'_> {
+            // The receipts are stored with the blocks, so they're never streamed.
+            unimplemented!();
+        }
+
         fn stream_compiled_classes(
             &self,
             _initial_block_number: BlockNumber,
@@ -687,6 +730,227 @@ async fn sequencer_pub_key_management() {
     );
 }
 
+// Mocks a chain with a single block of the given Starknet version that isn't signed by the
+// sequencer.
+fn mock_chain_with_unsigned_block(
+    starknet_version: &'static str,
+) -> (MockCentralSourceTrait, MockBaseLayerSourceTrait) {
+    let mut central_mock = MockCentralSourceTrait::new();
+    central_mock
+        .expect_get_sequencer_pub_key()
+        .returning(|| Ok(SequencerPublicKey(PublicKey(stark_felt!("0x111")))));
+    central_mock.expect_get_latest_block().returning(|| {
+        Ok(Some(BlockHashAndNumber {
+            block_number: BlockNumber(0),
+            block_hash: create_block_hash(BlockNumber(0), false),
+        }))
+    });
+    central_mock.expect_stream_new_blocks().returning(move |initial, up_to| {
+        let blocks_stream: BlocksStream<'_> = stream! {
+            for block_number in initial.iter_up_to(up_to) {
+                let header = BlockHeader {
+                    block_number,
+                    block_hash: create_block_hash(block_number, false),
+                    starknet_version: StarknetVersion(starknet_version.to_owned()),
+                    ..BlockHeader::default()
+                };
+                yield Ok((
+                    block_number,
+                    Block { header, body: BlockBody::default() },
+                    BlockSignature::default(),
+                ));
+            }
+        }
+        .boxed();
+        blocks_stream
+    });
+    central_mock.expect_stream_state_updates().returning(|initial, up_to| {
+        let state_stream: StateUpdatesStream<'_> = stream! {
+            for block_number in initial.iter_up_to(up_to) {
+                yield Ok((
+                    block_number,
+                    create_block_hash(block_number, false),
+                    StateDiff::default(),
+                    IndexMap::new(),
+                ));
+            }
+        }
+        .boxed();
+        state_stream
+    });
+
+    // Mock base_layer without any block.
+    let mut base_layer_mock = MockBaseLayerSourceTrait::new();
+    base_layer_mock.expect_latest_proved_block().returning(|| Ok(None));
+
+    (central_mock, base_layer_mock)
+}
+
+#[tokio::test]
+async fn sync_fails_on_invalid_block_signature() {
+    let _ = simple_logger::init_with_env();
+
+    let (central_mock, base_layer_mock) = mock_chain_with_unsigned_block("0.13.1");
+    let ((reader, writer), _temp_dir) = get_test_storage();
+    let config = get_test_sync_config(true);
+    let sync_future = run_sync(reader.clone(), writer, central_mock, base_layer_mock, config);
+
+    let sync_result =
+        tokio::time::timeout(config.block_propagation_sleep_duration * 4, sync_future)
+            .await
+            .unwrap()
+            .expect_err("Expecting sync to fail due to an invalid block signature.");
+
+    assert_matches!(
+        sync_result,
+        StateSyncError::InvalidBlockSignature { block_number } if block_number == BlockNumber(0)
+    );
+    // The state diff of the block isn't stored.
+    assert_eq!(reader.begin_ro_txn().unwrap().get_state_marker().unwrap(), BlockNumber(0));
+}
+
+#[tokio::test]
+async fn sync_skips_signatures_of_versions_without_state_diff_commitment() {
+    let _ = simple_logger::init_with_env();
+
+    // Blocks that predate the state diff commitment are stored without verifying their signature.
+    let (central_mock, base_layer_mock) = mock_chain_with_unsigned_block("0.12.1");
+    let ((reader, writer), _temp_dir) = get_test_storage();
+    let config = get_test_sync_config(true);
+    let sync_future = run_sync(reader.clone(), writer, central_mock, base_layer_mock, config);
+
+    // The sync keeps running once the block is stored.
+    let _ = tokio::time::timeout(config.block_propagation_sleep_duration * 4, sync_future)
+        .await
+        .expect_err("Expecting sync to keep running.");
+    assert_eq!(reader.begin_ro_txn().unwrap().get_state_marker().unwrap(), BlockNumber(1));
+}
+
+#[tokio::test]
+async fn sync_quarantines_invalid_blocks() {
+    const N_BLOCKS: u64 = 3;
+    const INVALID_BODY_BLOCK_NUMBER: BlockNumber = BlockNumber(1);
+    const INVALID_STATE_UPDATE_BLOCK_NUMBER: BlockNumber = BlockNumber(2);
+    const MAX_TIME_TO_SYNC_MS: u64 = 800;
+    let _ = simple_logger::init_with_env();
+
+    let invalid_data_error = || {
+        Arc::new(ReaderClientError::BadTransaction {
+            tx_hash: TransactionHash::default(),
+            msg: "Unknown transaction type.".to_owned(),
+        })
+    };
+    let mut central_mock = MockCentralSourceTrait::new();
+    central_mock.expect_get_latest_block().returning(|| {
+        Ok(Some(BlockHashAndNumber {
+            block_number: BlockNumber(N_BLOCKS - 1),
+            block_hash: create_block_hash(BlockNumber(N_BLOCKS - 1), false),
+        }))
+    });
+    central_mock.expect_stream_new_blocks().returning(move |initial, up_to| {
+        let blocks_stream: BlocksStream<'_> = stream! {
+            for block_number in initial.iter_up_to(up_to) {
+                if block_number.0 >= N_BLOCKS {
+                    yield Err(CentralError::BlockNotFound { block_number });
+                }
+                let header = BlockHeader {
+                    block_number,
+                    block_hash: create_block_hash(block_number, false),
+                    parent_hash: create_block_hash(block_number.prev().unwrap_or_default(), false),
+                    ..BlockHeader::default()
+                };
+                if block_number == INVALID_BODY_BLOCK_NUMBER {
+                    yield Err(CentralError::InvalidBlock {
+                        header: Box::new(header),
+                        signature: BlockSignature::default(),
+                        source: invalid_data_error(),
+                    });
+                    continue;
+                }
+                yield Ok((
+                    block_number,
+                    Block { header, body: BlockBody::default() },
+                    BlockSignature::default(),
+                ));
+            }
+        }
+        .boxed();
+        blocks_stream
+    });
+    central_mock.expect_stream_state_updates().returning(move |initial, up_to| {
+        let state_stream: StateUpdatesStream<'_> = stream! {
+            for block_number in initial.iter_up_to(up_to) {
+                if block_number.0 >= N_BLOCKS {
+                    yield Err(CentralError::BlockNotFound { block_number })
+                }
+                if block_number == INVALID_STATE_UPDATE_BLOCK_NUMBER {
+                    yield Err(CentralError::InvalidStateUpdate {
+                        block_number,
+                        source: invalid_data_error(),
+                    });
+                    continue;
+                }
+                yield Ok((
+                    block_number,
+                    create_block_hash(block_number, false),
+                    StateDiff::default(),
+                    IndexMap::new(),
+                ));
+            }
+        }
+        .boxed();
+        state_stream
+    });
+    central_mock.expect_get_block_hash().returning(|bn| Ok(Some(create_block_hash(bn, false))));
+
+    // Mock base_layer without any block.
+    let mut base_layer_mock = MockBaseLayerSourceTrait::new();
+    base_layer_mock.expect_latest_proved_block().returning(|| Ok(None));
+
+    let ((reader, writer), _temp_dir) = get_test_storage();
+    let config = SyncConfig {
+        invalid_block_policy: InvalidBlockPolicy::Quarantine,
+        ..get_test_sync_config(false)
+    };
+    let sync_future = run_sync(reader.clone(), writer, central_mock, base_layer_mock, config);
+
+    // Check that the sync passed the invalid blocks and quarantined them.
+    let check_storage_future =
+        check_storage(reader, Duration::from_millis(MAX_TIME_TO_SYNC_MS), |reader| {
+            let txn = reader.begin_ro_txn().unwrap();
+            if txn.get_header_marker().unwrap() < BlockNumber(N_BLOCKS)
+                || txn.get_state_marker().unwrap() < BlockNumber(N_BLOCKS)
+            {
+                return CheckStoragePredicateResult::InProgress;
+            }
+            let expected_quarantined_blocks = [
+                (
+                    INVALID_BODY_BLOCK_NUMBER,
+                    QuarantinedBlock {
+                        body_error: Some(invalid_data_error().to_string()),
+                        state_diff_error: None,
+                    },
+                ),
+                (
+                    INVALID_STATE_UPDATE_BLOCK_NUMBER,
+                    QuarantinedBlock {
+                        body_error: None,
+                        state_diff_error: Some(invalid_data_error().to_string()),
+                    },
+                ),
+            ];
+            if txn.get_quarantined_blocks().unwrap() != expected_quarantined_blocks.into() {
+                return CheckStoragePredicateResult::Error;
+            }
+            CheckStoragePredicateResult::Passed
+        });
+
+    tokio::select! {
+        sync_result = sync_future => sync_result.unwrap(),
+        storage_check_result = check_storage_future => assert!(storage_check_result),
+    }
+}
+
 fn create_block_hash(bn: BlockNumber, is_reverted_block: bool) -> BlockHash {
     if is_reverted_block {
         BlockHash(stark_felt!(format!("0x{}10", bn.0).as_str()))
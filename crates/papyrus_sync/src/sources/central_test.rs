@@ -1,12 +1,13 @@
-use std::num::NonZeroUsize;
-use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+use std::sync::Arc;
 
 use assert_matches::assert_matches;
 use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 use futures_util::pin_mut;
 use indexmap::{indexmap, IndexMap};
-use lru::LruCache;
 use mockall::predicate;
+use papyrus_common::class_cache::ClassCache;
+use papyrus_common::source_reports::{SourceReport, SourceReports};
 use papyrus_storage::state::StateStorageWriter;
 use papyrus_storage::test_utils::get_test_storage;
 use pretty_assertions::assert_eq;
@@ -40,14 +41,21 @@ use starknet_client::reader::{
     StateUpdate,
     StorageEntry,
 };
-use starknet_client::ClientError;
+use starknet_client::{ClientError, RetryConfig};
 use tokio_stream::StreamExt;
 
 use super::state_update_stream::StateUpdateStreamConfig;
-use super::ApiContractClass;
-use crate::sources::central::{CentralError, CentralSourceTrait, GenericCentralSource};
+use crate::sources::central::{
+    CentralError,
+    CentralSourceConfig,
+    CentralSourceTrait,
+    GenericCentralSource,
+};
 
 const TEST_CONCURRENT_REQUESTS: usize = 300;
+const TEST_SOURCE_ID: &str = "https://feeder.example";
+const NO_BLOCK_RETRIES: RetryConfig =
+    RetryConfig { retry_base_millis: 1, retry_max_delay_millis: 1, max_retries: 0 };
 
 #[tokio::test]
 async fn last_block_number() {
@@ -66,10 +74,12 @@ async fn last_block_number() {
     let central_source = GenericCentralSource {
         starknet_client: Arc::new(mock),
         concurrent_requests: TEST_CONCURRENT_REQUESTS,
+        block_retry_config: NO_BLOCK_RETRIES,
         storage_reader: reader,
         state_update_stream_config: state_update_stream_config_for_test(),
         class_cache: get_test_class_cache(),
-        compiled_class_cache: get_test_compiled_class_cache(),
+        source_id: TEST_SOURCE_ID.to_owned(),
+        source_reports: Arc::default(),
     };
 
     let last_block_number = central_source.get_latest_block().await.unwrap().unwrap().block_number;
@@ -93,13 +103,16 @@ async fn stream_block_headers() {
         );
     }
     let ((reader, _), _temp_dir) = get_test_storage();
+    let source_reports = Arc::new(SourceReports::default());
     let central_source = GenericCentralSource {
         concurrent_requests: TEST_CONCURRENT_REQUESTS,
+        block_retry_config: NO_BLOCK_RETRIES,
         starknet_client: Arc::new(mock),
         storage_reader: reader,
         state_update_stream_config: state_update_stream_config_for_test(),
         class_cache: get_test_class_cache(),
-        compiled_class_cache: get_test_compiled_class_cache(),
+        source_id: TEST_SOURCE_ID.to_owned(),
+        source_reports: source_reports.clone(),
     };
 
     let mut expected_block_num = BlockNumber(START_BLOCK_NUMBER);
@@ -111,6 +124,14 @@ async fn stream_block_headers() {
         expected_block_num = expected_block_num.next();
     }
     assert_eq!(expected_block_num, BlockNumber(END_BLOCK_NUMBER));
+
+    // Each downloaded block is reported as a successful request.
+    let reports = source_reports.next_reports().await;
+    assert_eq!(reports.len(), (END_BLOCK_NUMBER - START_BLOCK_NUMBER) as usize);
+    assert!(reports.iter().all(|report| matches!(
+        report,
+        SourceReport::Success { source_id, .. } if source_id == TEST_SOURCE_ID
+    )));
 }
 
 #[tokio::test]
@@ -168,11 +189,13 @@ async fn stream_block_headers_some_are_missing() {
         let ((reader, _), _temp_dir) = get_test_storage();
         let central_source = GenericCentralSource {
             concurrent_requests: TEST_CONCURRENT_REQUESTS,
+            block_retry_config: NO_BLOCK_RETRIES,
             starknet_client: Arc::new(mock),
             storage_reader: reader,
             state_update_stream_config: state_update_stream_config_for_test(),
             class_cache: get_test_class_cache(),
-            compiled_class_cache: get_test_compiled_class_cache(),
+            source_id: TEST_SOURCE_ID.to_owned(),
+            source_reports: Arc::default(),
         };
 
         let mut expected_block_num = BlockNumber(START_BLOCK_NUMBER);
@@ -226,11 +249,13 @@ async fn stream_block_headers_error() {
     let ((reader, _), _temp_dir) = get_test_storage();
     let central_source = GenericCentralSource {
         concurrent_requests: TEST_CONCURRENT_REQUESTS,
+        block_retry_config: NO_BLOCK_RETRIES,
         starknet_client: Arc::new(mock),
         storage_reader: reader,
         state_update_stream_config: state_update_stream_config_for_test(),
         class_cache: get_test_class_cache(),
-        compiled_class_cache: get_test_compiled_class_cache(),
+        source_id: TEST_SOURCE_ID.to_owned(),
+        source_reports: Arc::default(),
     };
 
     let mut expected_block_num = BlockNumber(START_BLOCK_NUMBER);
@@ -257,6 +282,149 @@ async fn stream_block_headers_error() {
     assert_eq!(expected_block_num, BlockNumber(ERROR_BLOCK_NUMBER + 1));
 }
 
+#[tokio::test]
+async fn stream_block_headers_retries_transient_errors() {
+    const N_BLOCKS: u64 = 3;
+    const MISSING_ONCE_BLOCK_NUMBER: BlockNumber = BlockNumber(1);
+    let mut mock = MockStarknetReader::new();
+
+    // The feeder gateway doesn't have block 1 on the first attempt.
+    let mut block_1_attempts = 0;
+    mock.expect_block().times(N_BLOCKS as usize + 1).returning(move |block_number| {
+        if block_number == MISSING_ONCE_BLOCK_NUMBER {
+            block_1_attempts += 1;
+            if block_1_attempts == 1 {
+                return Ok(None);
+            }
+        }
+        Ok(Some(BlockOrDeprecated::default()))
+    });
+    mock.expect_block_signature().times(N_BLOCKS as usize + 1).returning(|block_number| {
+        Ok(Some(BlockSignatureData { block_number, ..Default::default() }))
+    });
+    let ((reader, _), _temp_dir) = get_test_storage();
+    let central_source = GenericCentralSource {
+        concurrent_requests: TEST_CONCURRENT_REQUESTS,
+        block_retry_config: RetryConfig { max_retries: 1, ..NO_BLOCK_RETRIES },
+        starknet_client: Arc::new(mock),
+        storage_reader: reader,
+        state_update_stream_config: state_update_stream_config_for_test(),
+        class_cache: get_test_class_cache(),
+        source_id: TEST_SOURCE_ID.to_owned(),
+        source_reports: Arc::default(),
+    };
+
+    let stream = central_source.stream_new_blocks(BlockNumber(0), BlockNumber(N_BLOCKS));
+    let block_numbers = stream.map(|block_tuple| block_tuple.unwrap().0).collect::<Vec<_>>().await;
+    assert_eq!(block_numbers, vec![BlockNumber(0), BlockNumber(1), BlockNumber(2)]);
+}
+
+#[tokio::test]
+async fn stream_block_headers_fails_after_retries() {
+    const MAX_RETRIES: usize = 2;
+    let mut mock = MockStarknetReader::new();
+    mock.expect_block().times(MAX_RETRIES + 1).returning(|_block_number| Ok(None));
+    mock.expect_block_signature().returning(|block_number| {
+        Ok(Some(BlockSignatureData { block_number, ..Default::default() }))
+    });
+    let ((reader, _), _temp_dir) = get_test_storage();
+    let central_source = GenericCentralSource {
+        concurrent_requests: TEST_CONCURRENT_REQUESTS,
+        block_retry_config: RetryConfig { max_retries: MAX_RETRIES, ..NO_BLOCK_RETRIES },
+        starknet_client: Arc::new(mock),
+        storage_reader: reader,
+        state_update_stream_config: state_update_stream_config_for_test(),
+        class_cache: get_test_class_cache(),
+        source_id: TEST_SOURCE_ID.to_owned(),
+        source_reports: Arc::default(),
+    };
+
+    let stream = central_source.stream_new_blocks(BlockNumber(0), BlockNumber(1));
+    pin_mut!(stream);
+    assert_matches!(
+        stream.next().await,
+        Some(Err(CentralError::BlockNotFound { block_number: BlockNumber(0) }))
+    );
+    assert!(stream.next().await.is_none());
+}
+
+#[tokio::test]
+async fn stream_block_headers_doesnt_retry_client_errors() {
+    let mut mock = MockStarknetReader::new();
+    // The client already retried the request, so the block isn't downloaded again.
+    mock.expect_block().times(1).returning(|_block_number| {
+        Err(ReaderClientError::ClientError(ClientError::BadResponseStatus {
+            code: StatusCode::INTERNAL_SERVER_ERROR,
+            message: String::from("msg"),
+        }))
+    });
+    mock.expect_block_signature().returning(|block_number| {
+        Ok(Some(BlockSignatureData { block_number, ..Default::default() }))
+    });
+    let ((reader, _), _temp_dir) = get_test_storage();
+    let source_reports = Arc::new(SourceReports::default());
+    let central_source = GenericCentralSource {
+        concurrent_requests: TEST_CONCURRENT_REQUESTS,
+        block_retry_config: RetryConfig { max_retries: 2, ..NO_BLOCK_RETRIES },
+        starknet_client: Arc::new(mock),
+        storage_reader: reader,
+        state_update_stream_config: state_update_stream_config_for_test(),
+        class_cache: get_test_class_cache(),
+        source_id: TEST_SOURCE_ID.to_owned(),
+        source_reports: source_reports.clone(),
+    };
+
+    let stream = central_source.stream_new_blocks(BlockNumber(0), BlockNumber(1));
+    pin_mut!(stream);
+    assert_matches!(stream.next().await, Some(Err(CentralError::ClientError(_))));
+    assert!(stream.next().await.is_none());
+    assert_eq!(
+        source_reports.next_reports().await,
+        vec![SourceReport::Error { source_id: TEST_SOURCE_ID.to_owned() }]
+    );
+}
+
+#[tokio::test]
+async fn stream_transaction_outputs() {
+    const START_BLOCK_NUMBER: u64 = 5;
+    const END_BLOCK_NUMBER: u64 = 9;
+    const MISSING_BLOCK_NUMBER: u64 = 7;
+    let mut mock = MockStarknetReader::new();
+
+    // We need to perform all the mocks before moving the mock into central_source.
+    for i in START_BLOCK_NUMBER..END_BLOCK_NUMBER {
+        mock.expect_block().with(predicate::eq(BlockNumber(i))).returning(|block_number| {
+            if block_number == BlockNumber(MISSING_BLOCK_NUMBER) {
+                return Ok(None);
+            }
+            Ok(Some(BlockOrDeprecated::default()))
+        });
+    }
+    let ((reader, _), _temp_dir) = get_test_storage();
+    let central_source = GenericCentralSource {
+        concurrent_requests: TEST_CONCURRENT_REQUESTS,
+        block_retry_config: NO_BLOCK_RETRIES,
+        starknet_client: Arc::new(mock),
+        storage_reader: reader,
+        state_update_stream_config: state_update_stream_config_for_test(),
+        class_cache: get_test_class_cache(),
+        source_id: TEST_SOURCE_ID.to_owned(),
+        source_reports: Arc::default(),
+    };
+
+    let mut expected_block_num = BlockNumber(START_BLOCK_NUMBER);
+    let stream = central_source
+        .stream_transaction_outputs(expected_block_num, BlockNumber(END_BLOCK_NUMBER));
+    pin_mut!(stream);
+    while let Some(Ok((block_number, _block_hash, transaction_outputs))) = stream.next().await {
+        assert_eq!(expected_block_num, block_number);
+        assert!(transaction_outputs.is_empty());
+        expected_block_num = expected_block_num.next();
+    }
+    // The stream stops at the missing block.
+    assert_eq!(expected_block_num, BlockNumber(MISSING_BLOCK_NUMBER));
+}
+
 #[tokio::test]
 async fn stream_state_updates() {
     const START_BLOCK_NUMBER: u64 = 5;
@@ -364,12 +532,14 @@ async fn stream_state_updates() {
     let ((reader, _), _temp_dir) = get_test_storage();
     let central_source = GenericCentralSource {
         concurrent_requests: TEST_CONCURRENT_REQUESTS,
+        block_retry_config: NO_BLOCK_RETRIES,
         starknet_client: Arc::new(mock),
         storage_reader: reader,
         state_update_stream_config: state_update_stream_config_for_test(),
         // TODO(shahak): Check that downloaded classes appear in the cache.
         class_cache: get_test_class_cache(),
-        compiled_class_cache: get_test_compiled_class_cache(),
+        source_id: TEST_SOURCE_ID.to_owned(),
+        source_reports: Arc::default(),
     };
     let initial_block_num = BlockNumber(START_BLOCK_NUMBER);
 
@@ -477,11 +647,13 @@ async fn stream_compiled_classes() {
 
     let central_source = GenericCentralSource {
         concurrent_requests: TEST_CONCURRENT_REQUESTS,
+        block_retry_config: NO_BLOCK_RETRIES,
         starknet_client: Arc::new(mock),
         storage_reader: reader,
         state_update_stream_config: state_update_stream_config_for_test(),
         class_cache: get_test_class_cache(),
-        compiled_class_cache: get_test_compiled_class_cache(),
+        source_id: TEST_SOURCE_ID.to_owned(),
+        source_reports: Arc::default(),
     };
 
     let stream = central_source.stream_compiled_classes(BlockNumber(0), BlockNumber(2));
@@ -523,11 +695,13 @@ async fn get_class() {
     let ((reader, _), _temp_dir) = get_test_storage();
     let central_source = GenericCentralSource {
         concurrent_requests: TEST_CONCURRENT_REQUESTS,
+        block_retry_config: NO_BLOCK_RETRIES,
         starknet_client: Arc::new(mock),
         storage_reader: reader,
         state_update_stream_config: state_update_stream_config_for_test(),
         class_cache: get_test_class_cache(),
-        compiled_class_cache: get_test_compiled_class_cache(),
+        source_id: TEST_SOURCE_ID.to_owned(),
+        source_reports: Arc::default(),
     };
 
     assert_eq!(
@@ -560,11 +734,13 @@ async fn get_compiled_class() {
     let ((reader, _), _temp_dir) = get_test_storage();
     let central_source = GenericCentralSource {
         concurrent_requests: TEST_CONCURRENT_REQUESTS,
+        block_retry_config: NO_BLOCK_RETRIES,
         starknet_client: Arc::new(mock),
         storage_reader: reader,
         state_update_stream_config: state_update_stream_config_for_test(),
         class_cache: get_test_class_cache(),
-        compiled_class_cache: get_test_compiled_class_cache(),
+        source_id: TEST_SOURCE_ID.to_owned(),
+        source_reports: Arc::default(),
     };
 
     assert_eq!(central_source.get_compiled_class(class_hash).await.unwrap(), compiled_class);
@@ -584,11 +760,13 @@ async fn get_sequencer_pub_key() {
     let ((reader, _), _temp_dir) = get_test_storage();
     let central_source = GenericCentralSource {
         concurrent_requests: TEST_CONCURRENT_REQUESTS,
+        block_retry_config: NO_BLOCK_RETRIES,
         starknet_client: Arc::new(mock),
         storage_reader: reader,
         state_update_stream_config: state_update_stream_config_for_test(),
         class_cache: get_test_class_cache(),
-        compiled_class_cache: get_test_compiled_class_cache(),
+        source_id: TEST_SOURCE_ID.to_owned(),
+        source_reports: Arc::default(),
     };
 
     assert_eq!(central_source.get_sequencer_pub_key().await.unwrap(), sequencer_pub_key);
@@ -602,10 +780,29 @@ fn state_update_stream_config_for_test() -> StateUpdateStreamConfig {
     }
 }
 
-fn get_test_class_cache() -> Arc<Mutex<LruCache<ClassHash, ApiContractClass>>> {
-    Arc::from(Mutex::new(LruCache::new(NonZeroUsize::new(2).unwrap())))
+fn get_test_class_cache() -> Arc<ClassCache> {
+    Arc::new(ClassCache::new(2, None))
 }
 
-fn get_test_compiled_class_cache() -> Arc<Mutex<LruCache<ClassHash, CasmContractClass>>> {
-    Arc::from(Mutex::new(LruCache::new(NonZeroUsize::new(2).unwrap())))
+#[test]
+fn api_key_is_sent_in_headers_and_redacted() {
+    let mut config = CentralSourceConfig {
+        http_headers: Some(HashMap::from([("auth".to_owned(), "secret_1".to_owned())])),
+        ..Default::default()
+    };
+    assert_eq!(config.request_headers(), config.http_headers);
+
+    config.api_key = Some("secret_2".to_owned());
+    config.api_key_header = "my-api-key".to_owned();
+    assert_eq!(
+        config.request_headers(),
+        Some(HashMap::from([
+            ("auth".to_owned(), "secret_1".to_owned()),
+            ("my-api-key".to_owned(), "secret_2".to_owned()),
+        ]))
+    );
+
+    let debug = format!("{config:?}");
+    assert!(!debug.contains("secret"), "{debug}");
+    assert!(debug.contains("auth") && debug.contains("my-api-key"), "{debug}");
 }
@@ -57,12 +57,16 @@ impl PendingSource {
         config: CentralSourceConfig,
         node_version: &'static str,
     ) -> Result<PendingSource, ClientCreationError> {
-        let starknet_client = StarknetFeederGatewayClient::new(
+        let mut starknet_client = StarknetFeederGatewayClient::new(
             &config.url,
-            config.http_headers,
+            config.request_headers(),
             node_version,
             config.retry_config,
-        )?;
+        )?
+        .with_outbound_connection(&config.outbound_connection)?;
+        if let Some(capture_dir) = config.capture_dir {
+            starknet_client = starknet_client.with_response_capture(capture_dir);
+        }
 
         Ok(PendingSource { starknet_client: Arc::new(starknet_client) })
     }
@@ -1,13 +1,22 @@
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use assert_matches::assert_matches;
 use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 use futures_util::StreamExt;
 use indexmap::IndexMap;
+use papyrus_base_layer::{L1BlockHash, L1BlockReference};
+use papyrus_common::maintenance_requests::PruneRequest;
 use papyrus_common::pending_classes::{ApiContractClass, PendingClasses, PendingClassesTrait};
-use papyrus_storage::base_layer::BaseLayerStorageReader;
+use papyrus_common::source_reports::SourceReport;
+use papyrus_common::sync_notifications::SyncNotification;
+use papyrus_storage::base_layer::{
+    BaseLayerL1Block,
+    BaseLayerStorageReader,
+    BaseLayerStorageWriter,
+};
 use papyrus_storage::header::HeaderStorageWriter;
+use papyrus_storage::source_reputation::{SourceReputation, SourceReputationStorageReader};
 use papyrus_storage::test_utils::get_test_storage;
 use papyrus_storage::{StorageReader, StorageWriter};
 use pretty_assertions::assert_eq;
@@ -33,7 +42,9 @@ use crate::sources::base_layer::MockBaseLayerSourceTrait;
 use crate::sources::central::MockCentralSourceTrait;
 use crate::sources::pending::MockPendingSourceTrait;
 use crate::{
+    prune_state_history,
     sort_state_diff,
+    store_source_reports,
     stream_new_base_layer_block,
     sync_pending_data,
     GenericStateSync,
@@ -137,9 +148,12 @@ async fn stream_new_base_layer_block_test_header_marker() {
     // TODO(dvir): find a better way to do it.
     // Base layer after the header marker, skip 5 and 10 and return only 1 and 4.
     let block_numbers = vec![5, 1, 10, 4];
-    let mut iter = block_numbers.into_iter().map(|bn| (BlockNumber(bn), BlockHash::default()));
+    let mut iter = block_numbers
+        .into_iter()
+        .map(|bn| (BlockNumber(bn), BlockHash::default(), L1BlockReference::default()));
     let mut mock = MockBaseLayerSourceTrait::new();
     mock.expect_latest_proved_block().times(4).returning(move || Ok(iter.next()));
+    mock.expect_l1_block_hash().returning(|_| Ok(Some(L1BlockHash::default())));
     let mut stream =
         stream_new_base_layer_block(reader, Arc::new(mock), Duration::from_millis(0)).boxed();
 
@@ -159,7 +173,9 @@ async fn stream_new_base_layer_block_no_blocks_on_base_layer() {
 
     // In the first polling of the base layer no blocks were found, in the second polling a block
     // was found.
-    let mut values = vec![None, Some((BlockNumber(1), BlockHash::default()))].into_iter();
+    let mut values =
+        vec![None, Some((BlockNumber(1), BlockHash::default(), L1BlockReference::default()))]
+            .into_iter();
     let mut mock = MockBaseLayerSourceTrait::new();
     mock.expect_latest_proved_block().times(2).returning(move || Ok(values.next().unwrap()));
 
@@ -170,6 +186,64 @@ async fn stream_new_base_layer_block_no_blocks_on_base_layer() {
     assert_matches!(event, SyncEvent::NewBaseLayerBlock { block_number: BlockNumber(1), .. });
 }
 
+#[tokio::test]
+async fn stream_new_base_layer_block_reorg() {
+    let (reader, mut writer) = get_test_storage().0;
+    add_headers(5, &mut writer);
+
+    // Block 3 is proved in base layer block 10, which is then replaced by a block in which only
+    // block 1 is proved.
+    let first_l1_block = L1BlockReference { number: 10, hash: L1BlockHash([1; 32]) };
+    let second_l1_block = L1BlockReference { number: 10, hash: L1BlockHash([2; 32]) };
+    let mut values = vec![
+        (BlockNumber(3), BlockHash::default(), first_l1_block),
+        (BlockNumber(1), BlockHash::default(), second_l1_block),
+    ]
+    .into_iter();
+    let mut mock = MockBaseLayerSourceTrait::new();
+    mock.expect_latest_proved_block().times(2).returning(move || Ok(values.next()));
+    mock.expect_l1_block_hash().returning(move |_| Ok(Some(second_l1_block.hash)));
+    let mut stream =
+        stream_new_base_layer_block(reader, Arc::new(mock), Duration::from_millis(0)).boxed();
+
+    let event = stream.next().await.unwrap().unwrap();
+    assert_matches!(event, SyncEvent::NewBaseLayerBlock { block_number: BlockNumber(3), .. });
+    // The base layer marker was 0 before the replaced block was observed.
+    let event = stream.next().await.unwrap().unwrap();
+    assert_matches!(event, SyncEvent::BaseLayerReorg { block_number: BlockNumber(0) });
+    let event = stream.next().await.unwrap().unwrap();
+    assert_matches!(event, SyncEvent::NewBaseLayerBlock { block_number: BlockNumber(1), .. });
+}
+
+#[tokio::test]
+async fn stream_new_base_layer_block_reorg_of_stored_block() {
+    let (reader, mut writer) = get_test_storage().0;
+    add_headers(5, &mut writer);
+    let first_l1_block = BaseLayerL1Block { number: 10, hash: [1; 32] };
+    let second_l1_block = BaseLayerL1Block { number: 20, hash: [2; 32] };
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .update_base_layer_block_marker_from_l1_block(&BlockNumber(2), &first_l1_block)
+        .unwrap()
+        .update_base_layer_block_marker_from_l1_block(&BlockNumber(4), &second_l1_block)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    // The base layer block that block 3 was proved in was replaced while the node was down.
+    let mut mock = MockBaseLayerSourceTrait::new();
+    mock.expect_l1_block_hash().returning(move |number| {
+        Ok(Some(L1BlockHash(if number == 20 { [3; 32] } else { first_l1_block.hash })))
+    });
+    mock.expect_latest_proved_block().returning(|| Ok(None));
+    let mut stream =
+        stream_new_base_layer_block(reader, Arc::new(mock), Duration::from_millis(0)).boxed();
+
+    let event = stream.next().await.unwrap().unwrap();
+    assert_matches!(event, SyncEvent::BaseLayerReorg { block_number: BlockNumber(2) });
+}
+
 #[test]
 fn store_base_layer_block_test() {
     let (reader, mut writer) = get_test_storage().0;
@@ -196,26 +270,145 @@ fn store_base_layer_block_test() {
         pending_source: Arc::new(MockPendingSourceTrait::new()),
         pending_classes: Arc::new(RwLock::new(PendingClasses::default())),
         base_layer_source: Arc::new(MockBaseLayerSourceTrait::new()),
+        rpc_load: Arc::default(),
+        sync_notifier: Arc::default(),
+        disk_space: Arc::default(),
+        recent_state: Arc::default(),
+        deferred_class_requests: Arc::default(),
+        source_reports: Arc::default(),
+        maintenance_requests: Arc::default(),
         reader,
         writer,
         sequencer_pub_key: None,
+        genesis_state: None,
     };
 
     // Trying to store a block without a header in the storage.
-    let res = gen_state_sync.store_base_layer_block(BlockNumber(1), BlockHash::default());
+    let res = gen_state_sync.store_base_layer_block(
+        BlockNumber(1),
+        BlockHash::default(),
+        L1BlockReference::default(),
+    );
     assert_matches!(res, Err(StateSyncError::BaseLayerBlockWithoutMatchingHeader { .. }));
 
     // Trying to store a block with mismatching header.
-    let res =
-        gen_state_sync.store_base_layer_block(BlockNumber(0), BlockHash(stark_felt!("0x666")));
+    let res = gen_state_sync.store_base_layer_block(
+        BlockNumber(0),
+        BlockHash(stark_felt!("0x666")),
+        L1BlockReference::default(),
+    );
     assert_matches!(res, Err(StateSyncError::BaseLayerHashMismatch { .. }));
 
     // Happy flow.
-    let res = gen_state_sync.store_base_layer_block(BlockNumber(0), header_hash);
+    let l1_block = L1BlockReference { number: 10, hash: L1BlockHash([1; 32]) };
+    let res = gen_state_sync.store_base_layer_block(BlockNumber(0), header_hash, l1_block);
     assert!(res.is_ok());
+    let txn = gen_state_sync.reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_base_layer_block_marker().unwrap(), BlockNumber(1));
+    assert_eq!(
+        txn.get_base_layer_l1_blocks().unwrap(),
+        vec![(BlockNumber(1), BaseLayerL1Block { number: 10, hash: [1; 32] })]
+    );
+}
+
+#[test]
+fn revert_base_layer_marker_test() {
+    let (reader, mut writer) = get_test_storage().0;
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .update_base_layer_block_marker(&BlockNumber(5))
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let mut gen_state_sync = GenericStateSync {
+        config: SyncConfig::default(),
+        shared_highest_block: Arc::new(RwLock::new(None)),
+        pending_data: Arc::new(RwLock::new(PendingData::default())),
+        central_source: Arc::new(MockCentralSourceTrait::new()),
+        pending_source: Arc::new(MockPendingSourceTrait::new()),
+        pending_classes: Arc::new(RwLock::new(PendingClasses::default())),
+        base_layer_source: Arc::new(MockBaseLayerSourceTrait::new()),
+        rpc_load: Arc::default(),
+        sync_notifier: Arc::default(),
+        disk_space: Arc::default(),
+        recent_state: Arc::default(),
+        deferred_class_requests: Arc::default(),
+        source_reports: Arc::default(),
+        maintenance_requests: Arc::default(),
+        reader: reader.clone(),
+        writer,
+        sequencer_pub_key: None,
+        genesis_state: None,
+    };
+    let mut notifications = gen_state_sync.sync_notifier.subscribe();
     let base_layer_marker =
-        gen_state_sync.reader.begin_ro_txn().unwrap().get_base_layer_block_marker().unwrap();
-    assert_eq!(base_layer_marker, BlockNumber(1));
+        || reader.begin_ro_txn().unwrap().get_base_layer_block_marker().unwrap();
+
+    gen_state_sync.revert_base_layer_marker(BlockNumber(3)).unwrap();
+    assert_eq!(base_layer_marker(), BlockNumber(3));
+    assert_eq!(
+        notifications.try_recv().unwrap(),
+        SyncNotification::BlockRevertedOnBaseLayer { block_number: BlockNumber(3) }
+    );
+
+    // A marker that is already before the block isn't changed.
+    gen_state_sync.revert_base_layer_marker(BlockNumber(4)).unwrap();
+    assert_eq!(base_layer_marker(), BlockNumber(3));
+    assert!(notifications.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn sync_is_throttled_while_under_load() {
+    let (reader, mut writer) = get_test_storage().0;
+    writer.begin_rw_txn().unwrap().commit().unwrap();
+
+    let config = SyncConfig {
+        throttling_rpc_latency_threshold: Duration::from_millis(100),
+        throttling_write_lock_wait_threshold: Duration::from_secs(60),
+        throttling_sleep_duration: Duration::from_millis(200),
+        ..SyncConfig::default()
+    };
+    let mut gen_state_sync = GenericStateSync {
+        config,
+        shared_highest_block: Arc::new(RwLock::new(None)),
+        pending_data: Arc::new(RwLock::new(PendingData::default())),
+        central_source: Arc::new(MockCentralSourceTrait::new()),
+        pending_source: Arc::new(MockPendingSourceTrait::new()),
+        pending_classes: Arc::new(RwLock::new(PendingClasses::default())),
+        base_layer_source: Arc::new(MockBaseLayerSourceTrait::new()),
+        rpc_load: Arc::default(),
+        sync_notifier: Arc::default(),
+        disk_space: Arc::default(),
+        recent_state: Arc::default(),
+        deferred_class_requests: Arc::default(),
+        source_reports: Arc::default(),
+        maintenance_requests: Arc::default(),
+        reader,
+        writer,
+        sequencer_pub_key: None,
+        genesis_state: None,
+    };
+    assert!(!gen_state_sync.is_under_load());
+    let start = Instant::now();
+    gen_state_sync.throttle_if_under_load().await;
+    assert!(start.elapsed() < gen_state_sync.config.throttling_sleep_duration);
+
+    // Slow RPC requests throttle the sync.
+    for _ in 0..10 {
+        gen_state_sync.rpc_load.record_latency(Duration::from_millis(150));
+    }
+    assert!(gen_state_sync.is_under_load());
+    let start = Instant::now();
+    gen_state_sync.throttle_if_under_load().await;
+    assert!(start.elapsed() >= gen_state_sync.config.throttling_sleep_duration);
+
+    // So does a write that waits for the write lock longer than the threshold.
+    gen_state_sync.rpc_load = Arc::default();
+    gen_state_sync.config.throttling_write_lock_wait_threshold = Duration::ZERO;
+    gen_state_sync.writer.begin_rw_txn().unwrap().commit().unwrap();
+    assert!(gen_state_sync.is_under_load());
 }
 
 // Adds to the storage 'headers_num' headers.
@@ -770,3 +963,40 @@ async fn pending_sync_classes_are_cleaned_on_first_pending_data_from_latest_bloc
     )
     .await
 }
+
+#[tokio::test]
+async fn prune_state_history_responds_with_the_result() {
+    // The storage of the tests is in Archive mode, where the state history isn't pruned.
+    let ((_reader, mut writer), _temp_dir) = get_test_storage();
+    let (response, response_receiver) = tokio::sync::oneshot::channel();
+    prune_state_history(&mut writer, PruneRequest { below: BlockNumber(1), response });
+    let error = response_receiver.await.unwrap().unwrap_err();
+    assert!(error.contains("Full mode"), "{error}");
+}
+
+#[test]
+fn source_reports_are_recorded_in_the_reputation() {
+    const SOURCE_ID: &str = "https://feeder.example";
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    store_source_reports(
+        &mut writer,
+        vec![
+            SourceReport::Success {
+                source_id: SOURCE_ID.to_owned(),
+                latency: Duration::from_millis(5),
+            },
+            SourceReport::Error { source_id: SOURCE_ID.to_owned() },
+            SourceReport::Ban { source_id: SOURCE_ID.to_owned(), timestamp: 7 },
+        ],
+    )
+    .unwrap();
+
+    let mut expected_reputation = SourceReputation::default();
+    expected_reputation.record_success(Duration::from_millis(5));
+    expected_reputation.record_error();
+    expected_reputation.record_ban(7);
+    assert_eq!(
+        reader.begin_ro_txn().unwrap().get_source_reputation(SOURCE_ID).unwrap(),
+        Some(expected_reputation)
+    );
+}
@@ -9,6 +9,7 @@
 //! [`Starknet`]: https://starknet.io/
 
 pub mod reader;
+pub mod response_capture;
 pub mod retry;
 #[cfg(test)]
 mod starknet_client_test;
@@ -19,6 +20,7 @@ pub mod writer;
 
 use std::collections::HashMap;
 
+use papyrus_common::outbound_connection::{OutboundConnectionConfig, OutboundConnectionError};
 use reqwest::header::HeaderMap;
 use reqwest::{Client, RequestBuilder, StatusCode};
 use tracing::warn;
@@ -35,6 +37,7 @@ struct StarknetClient {
     http_headers: HeaderMap,
     pub internal_client: Client,
     retry_config: RetryConfig,
+    user_agent: String,
 }
 
 /// Errors that might be encountered while creating the client.
@@ -46,6 +49,8 @@ pub enum ClientCreationError {
     BuildError(#[from] reqwest::Error),
     #[error(transparent)]
     HttpHeaderError(#[from] http::Error),
+    #[error(transparent)]
+    OutboundConnection(#[from] OutboundConnectionError),
 }
 
 /// Errors that might be solved by retrying mechanism.
@@ -94,10 +99,15 @@ impl StarknetClient {
         node_version: &'static str,
         retry_config: RetryConfig,
     ) -> Result<Self, ClientCreationError> {
-        let header_map = match http_headers {
+        let mut header_map = match http_headers {
             Some(inner) => (&inner).try_into()?,
             None => HeaderMap::new(),
         };
+        // The headers may hold secrets, such as API keys, so they are hidden when the requests are
+        // logged.
+        for value in header_map.values_mut() {
+            value.set_sensitive(true);
+        }
         let info = os_info::get();
         let system_information =
             format!("{}; {}; {}", info.os_type(), info.version(), info.bitness());
@@ -109,11 +119,23 @@ impl StarknetClient {
         );
         Ok(StarknetClient {
             http_headers: header_map,
-            internal_client: Client::builder().user_agent(app_user_agent).build()?,
+            internal_client: Client::builder().user_agent(app_user_agent.clone()).build()?,
             retry_config,
+            user_agent: app_user_agent,
         })
     }
 
+    /// Rebuilds the client such that its requests go through the proxy and trust the CA
+    /// certificates of the given config.
+    pub fn with_outbound_connection(
+        mut self,
+        outbound_connection: &OutboundConnectionConfig,
+    ) -> Result<Self, ClientCreationError> {
+        let client_builder = Client::builder().user_agent(self.user_agent.clone());
+        self.internal_client = outbound_connection.configure(client_builder)?.build()?;
+        Ok(self)
+    }
+
     fn get_retry_error_code(err: &ClientError) -> Option<RetryErrorCode> {
         match err {
             ClientError::BadResponseStatus { code, message: _ } => match *code {
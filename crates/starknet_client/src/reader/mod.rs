@@ -7,11 +7,13 @@ pub mod objects;
 mod starknet_feeder_gateway_client_test;
 
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 use async_trait::async_trait;
 use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 #[cfg(any(feature = "testing", test))]
 use mockall::automock;
+use papyrus_common::outbound_connection::OutboundConnectionConfig;
 use papyrus_common::pending_classes::ApiContractClass;
 use serde::{Deserialize, Serialize};
 use starknet_api::block::BlockNumber;
@@ -40,6 +42,7 @@ pub use crate::reader::objects::state::{
 };
 #[cfg(doc)]
 pub use crate::reader::objects::transaction::TransactionReceipt;
+use crate::response_capture::capture_response;
 use crate::retry::RetryConfig;
 use crate::starknet_error::{KnownStarknetErrorCode, StarknetError, StarknetErrorCode};
 use crate::{ClientCreationError, ClientError, StarknetClient};
@@ -66,6 +69,21 @@ pub enum ReaderClientError {
     BadTransaction { tx_hash: TransactionHash, msg: String },
 }
 
+impl ReaderClientError {
+    /// Returns true if the data was received whole but isn't valid, so that getting it again would
+    /// fail the same way. Failures to get the data, and responses that aren't well formed JSON
+    /// (e.g. a truncated response), may not happen again.
+    pub fn is_invalid_data(&self) -> bool {
+        match self {
+            ReaderClientError::ClientError(_) => false,
+            ReaderClientError::SerdeError(err) => err.is_data(),
+            ReaderClientError::StarknetApiError(_)
+            | ReaderClientError::TransactionReceiptsError(_)
+            | ReaderClientError::BadTransaction { .. } => true,
+        }
+    }
+}
+
 pub type ReaderClientResult<T> = Result<T, ReaderClientError>;
 
 /// A trait describing an object that can communicate with [`Starknet`] and read data from it.
@@ -120,6 +138,8 @@ pub trait StarknetReader {
 pub struct StarknetFeederGatewayClient {
     urls: StarknetUrls,
     client: StarknetClient,
+    // The directory to which responses that fail to be parsed are written, if any.
+    capture_dir: Option<PathBuf>,
 }
 
 #[derive(Clone, Debug)]
@@ -193,9 +213,33 @@ impl StarknetFeederGatewayClient {
         Ok(StarknetFeederGatewayClient {
             urls: StarknetUrls::new(url_str)?,
             client: StarknetClient::new(http_headers, node_version, retry_config)?,
+            capture_dir: None,
         })
     }
 
+    /// Makes the client send its requests through the proxy and trust the CA certificates of the
+    /// given config.
+    pub fn with_outbound_connection(
+        mut self,
+        outbound_connection: &OutboundConnectionConfig,
+    ) -> Result<Self, ClientCreationError> {
+        self.client = self.client.with_outbound_connection(outbound_connection)?;
+        Ok(self)
+    }
+
+    /// Makes the client write every response that fails to be parsed, and every block that fails
+    /// to be validated, to a new file in the given directory. See [`capture_response`].
+    pub fn with_response_capture(mut self, capture_dir: PathBuf) -> Self {
+        self.capture_dir = Some(capture_dir);
+        self
+    }
+
+    // Returns the directory to which a response from the given URL is captured if it fails to be
+    // parsed, along with the URL. None if the responses aren't captured.
+    fn response_capture<'a>(&'a self, url: &'a Url) -> Option<(&'a Path, &'a Url)> {
+        self.capture_dir.as_deref().map(|capture_dir| (capture_dir, url))
+    }
+
     async fn request_with_retry_url(&self, url: Url) -> ReaderClientResult<String> {
         self.client
             .request_with_retry(self.client.internal_client.get(url))
@@ -212,12 +256,28 @@ impl StarknetFeederGatewayClient {
             block_number.map(|bn| bn.to_string()).unwrap_or(String::from(LATEST_BLOCK_NUMBER));
         url.query_pairs_mut().append_pair(BLOCK_NUMBER_QUERY, block_number.as_str());
 
-        let response = self.request_with_retry_url(url).await;
-        load_object_from_response(
+        let response = self.request_with_retry_url(url.clone()).await;
+        // Kept for capturing the block if it fails to be validated.
+        let raw_block = match (&response, &self.capture_dir) {
+            (Ok(raw_block), Some(_)) => Some(raw_block.clone()),
+            _ => None,
+        };
+        let block: Option<BlockOrDeprecated> = load_object_from_response(
             response,
+            self.response_capture(&url),
             Some(KnownStarknetErrorCode::BlockNotFound),
             format!("Failed to get block number {block_number:?} from starknet server."),
-        )
+        )?;
+        // The block is validated when it's converted, so a block that fails to be converted is
+        // captured as it was received.
+        if let (Some(capture_dir), Some(raw_block), Some(block)) =
+            (&self.capture_dir, raw_block, &block)
+        {
+            if let Err(err) = block.clone().to_starknet_api_block_and_version() {
+                capture_response(capture_dir, url.as_str(), &err.to_string(), raw_block.as_bytes());
+            }
+        }
+        Ok(block)
     }
 }
 
@@ -249,9 +309,10 @@ impl StarknetReader for StarknetFeederGatewayClient {
         let class_hash = class_hash_result?;
         url.query_pairs_mut()
             .append_pair(CLASS_HASH_QUERY, &class_hash.as_str()[1..class_hash.len() - 1]);
-        let response = self.request_with_retry_url(url).await;
+        let response = self.request_with_retry_url(url.clone()).await;
         load_object_from_response(
             response,
+            self.response_capture(&url),
             Some(KnownStarknetErrorCode::UndeclaredClass),
             format!("Failed to get class with hash {class_hash:?} from starknet server."),
         )
@@ -264,9 +325,10 @@ impl StarknetReader for StarknetFeederGatewayClient {
     ) -> ReaderClientResult<Option<StateUpdate>> {
         let mut url = self.urls.get_state_update.clone();
         url.query_pairs_mut().append_pair(BLOCK_NUMBER_QUERY, &block_number.to_string());
-        let response = self.request_with_retry_url(url).await;
+        let response = self.request_with_retry_url(url.clone()).await;
         load_object_from_response(
             response,
+            self.response_capture(&url),
             Some(KnownStarknetErrorCode::BlockNotFound),
             format!(
                 "Failed to get state update for block number {block_number} from starknet server."
@@ -335,9 +397,10 @@ impl StarknetReader for StarknetFeederGatewayClient {
         let class_hash = class_hash_result?;
         url.query_pairs_mut()
             .append_pair(CLASS_HASH_QUERY, &class_hash.as_str()[1..class_hash.len() - 1]);
-        let response = self.request_with_retry_url(url).await;
+        let response = self.request_with_retry_url(url.clone()).await;
         load_object_from_response(
             response,
+            self.response_capture(&url),
             Some(KnownStarknetErrorCode::UndeclaredClass),
             format!("Failed to get compiled class with hash {class_hash:?} from starknet server."),
         )
@@ -348,6 +411,7 @@ impl StarknetReader for StarknetFeederGatewayClient {
         let response = self.request_with_retry_url(self.urls.get_pending_data.clone()).await;
         load_object_from_response(
             response,
+            self.response_capture(&self.urls.get_pending_data),
             Some(KnownStarknetErrorCode::BlockNotFound),
             "Failed to get pending data from starknet server.".to_string(),
         )
@@ -367,9 +431,10 @@ impl StarknetReader for StarknetFeederGatewayClient {
     ) -> ReaderClientResult<Option<BlockSignatureData>> {
         let mut url = self.urls.get_block_signature.clone();
         url.query_pairs_mut().append_pair(BLOCK_NUMBER_QUERY, &block_number.to_string());
-        let response = self.request_with_retry_url(url).await;
+        let response = self.request_with_retry_url(url.clone()).await;
         load_object_from_response(
             response,
+            self.response_capture(&url),
             Some(KnownStarknetErrorCode::BlockNotFound),
             format!("Failed to get signature for block {block_number:?} from starknet server."),
         )
@@ -380,6 +445,7 @@ impl StarknetReader for StarknetFeederGatewayClient {
         let response = self.request_with_retry_url(self.urls.get_sequencer_pub_key.clone()).await;
         load_object_from_response(
             response,
+            self.response_capture(&self.urls.get_sequencer_pub_key),
             None,
             "Failed to get sequencer public key from starknet server.".to_string(),
         )
@@ -388,9 +454,11 @@ impl StarknetReader for StarknetFeederGatewayClient {
 }
 
 /// Load an object from a json string response. If there was a StarknetError with
-/// `none_error_code`, return None. If there was a different error, log `error_message`.
+/// `none_error_code`, return None. If there was a different error, log `error_message`. A response
+/// that fails to be parsed is captured to the given directory, along with the requested URL.
 fn load_object_from_response<Object: for<'a> Deserialize<'a>>(
     response: ReaderClientResult<String>,
+    capture: Option<(&Path, &Url)>,
     none_error_code: Option<KnownStarknetErrorCode>,
     error_message: String,
 ) -> ReaderClientResult<Option<Object>> {
@@ -399,6 +467,14 @@ fn load_object_from_response<Object: for<'a> Deserialize<'a>>(
             let result = serde_json::from_str(&raw_object);
             if let Err(err) = &result {
                 error!("Failed to deserialize {raw_object:?}. Error: {err}");
+                if let Some((capture_dir, url)) = capture {
+                    capture_response(
+                        capture_dir,
+                        url.as_str(),
+                        &err.to_string(),
+                        raw_object.as_bytes(),
+                    );
+                }
             }
             Ok(Some(result?))
         }
@@ -280,23 +280,10 @@ impl BlockOrDeprecated {
         }
     }
 
-    // TODO(shahak): Rename to to_starknet_api_block.
-    pub fn to_starknet_api_block_and_version(self) -> ReaderClientResult<starknet_api_block> {
-        // Check that the number of receipts is the same as the number of transactions.
-        let num_of_txs = self.transactions().len();
-        let num_of_receipts = self.transaction_receipts().len();
-        if num_of_txs != num_of_receipts {
-            return Err(ReaderClientError::TransactionReceiptsError(
-                TransactionReceiptsError::WrongNumberOfReceipts {
-                    block_number: self.block_number(),
-                    num_of_txs,
-                    num_of_receipts,
-                },
-            ));
-        }
-
-        // Get the header.
-        let header = starknet_api::block::BlockHeader {
+    /// Returns the starknet_api header of the block. Unlike the whole block, the header is valid
+    /// even if the transactions and receipts of the block aren't.
+    pub fn to_starknet_api_header(&self) -> starknet_api::block::BlockHeader {
+        starknet_api::block::BlockHeader {
             block_hash: self.block_hash(),
             parent_hash: self.parent_block_hash(),
             block_number: self.block_number(),
@@ -314,7 +301,25 @@ impl BlockOrDeprecated {
                 .iter()
                 .fold(0, |acc, receipt| acc + receipt.events.len()),
             starknet_version: StarknetVersion(self.starknet_version()),
-        };
+        }
+    }
+
+    // TODO(shahak): Rename to to_starknet_api_block.
+    pub fn to_starknet_api_block_and_version(self) -> ReaderClientResult<starknet_api_block> {
+        // Check that the number of receipts is the same as the number of transactions.
+        let num_of_txs = self.transactions().len();
+        let num_of_receipts = self.transaction_receipts().len();
+        if num_of_txs != num_of_receipts {
+            return Err(ReaderClientError::TransactionReceiptsError(
+                TransactionReceiptsError::WrongNumberOfReceipts {
+                    block_number: self.block_number(),
+                    num_of_txs,
+                    num_of_receipts,
+                },
+            ));
+        }
+
+        let header = self.to_starknet_api_header();
 
         let (transactions, transaction_receipts) = self.get_body();
 
@@ -142,6 +142,13 @@ impl PendingBlockOrDeprecated {
             PendingBlockOrDeprecated::Current(block) => block.l1_data_gas_price,
         }
     }
+    pub fn l1_da_mode(&self) -> L1DataAvailabilityMode {
+        match self {
+            // In older versions, the data was always published as calldata.
+            PendingBlockOrDeprecated::Deprecated(_) => L1DataAvailabilityMode::default(),
+            PendingBlockOrDeprecated::Current(block) => block.l1_da_mode,
+        }
+    }
 }
 
 #[derive(Debug, Default, Deserialize, Clone, Eq, PartialEq)]
@@ -1,3 +1,7 @@
+#[cfg(test)]
+#[path = "state_test.rs"]
+mod state_test;
+
 use std::collections::HashMap;
 
 use indexmap::IndexMap;
@@ -7,7 +11,8 @@ pub use papyrus_common::state::{
     ReplacedClass,
     StorageEntry,
 };
-use serde::{Deserialize, Serialize};
+use serde::de::Error as DeserializationError;
+use serde::{Deserialize, Deserializer, Serialize};
 use starknet_api::block::BlockHash;
 use starknet_api::core::{ClassHash, ContractAddress, GlobalRoot, Nonce};
 use starknet_api::hash::StarkFelt;
@@ -22,7 +27,9 @@ pub struct StateUpdate {
     pub state_diff: StateDiff,
 }
 
-#[derive(Debug, Default, Deserialize, Serialize, Clone, Eq, PartialEq)]
+/// A state diff as returned by the starknet gateway. It is serialized in the shape of the current
+/// version of the gateway, and deserialized from the shape of any version of it.
+#[derive(Debug, Default, Serialize, Clone, Eq, PartialEq)]
 pub struct StateDiff {
     // IndexMap is serialized as a mapping in json, keeps ordering and is efficiently iterable.
     pub storage_diffs: IndexMap<ContractAddress, Vec<StorageEntry>>,
@@ -33,6 +40,79 @@ pub struct StateDiff {
     pub replaced_classes: Vec<ReplacedClass>,
 }
 
+// The shape of the state diff since Starknet 0.11.0, which introduced Sierra classes. Fields that
+// newer versions add are ignored.
+#[derive(Debug, Deserialize)]
+struct StateDiffV0_11 {
+    storage_diffs: IndexMap<ContractAddress, Vec<StorageEntry>>,
+    deployed_contracts: Vec<DeployedContract>,
+    declared_classes: Vec<DeclaredClassHashEntry>,
+    old_declared_contracts: Vec<ClassHash>,
+    nonces: IndexMap<ContractAddress, Nonce>,
+    replaced_classes: Vec<ReplacedClass>,
+}
+
+impl From<StateDiffV0_11> for StateDiff {
+    fn from(diff: StateDiffV0_11) -> Self {
+        StateDiff {
+            storage_diffs: diff.storage_diffs,
+            deployed_contracts: diff.deployed_contracts,
+            declared_classes: diff.declared_classes,
+            old_declared_contracts: diff.old_declared_contracts,
+            nonces: diff.nonces,
+            replaced_classes: diff.replaced_classes,
+        }
+    }
+}
+
+// The shape of the state diff before Starknet 0.11.0, in which the declared (Cairo 0) classes
+// were named `declared_contracts`. Before Starknet 0.10.0 it had no nonces, and before 0.9.0 no
+// declared classes, so those default to empty. Unknown fields are rejected, so that a state diff of
+// a newer version that failed to deserialize isn't read as an old one without its new fields.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StateDiffV0_9 {
+    storage_diffs: IndexMap<ContractAddress, Vec<StorageEntry>>,
+    deployed_contracts: Vec<DeployedContract>,
+    #[serde(default)]
+    declared_contracts: Vec<ClassHash>,
+    #[serde(default)]
+    nonces: IndexMap<ContractAddress, Nonce>,
+}
+
+impl From<StateDiffV0_9> for StateDiff {
+    fn from(diff: StateDiffV0_9) -> Self {
+        StateDiff {
+            storage_diffs: diff.storage_diffs,
+            deployed_contracts: diff.deployed_contracts,
+            declared_classes: vec![],
+            old_declared_contracts: diff.declared_contracts,
+            nonces: diff.nonces,
+            replaced_classes: vec![],
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for StateDiff {
+    // Tries the shapes of the state diff from the newest version to the oldest, and fails with the
+    // errors of all of them if none matches.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = serde_json::Value::deserialize(deserializer)?;
+        let v0_11_error = match StateDiffV0_11::deserialize(&value) {
+            Ok(diff) => return Ok(diff.into()),
+            Err(err) => err,
+        };
+        let v0_9_error = match StateDiffV0_9::deserialize(&value) {
+            Ok(diff) => return Ok(diff.into()),
+            Err(err) => err,
+        };
+        Err(D::Error::custom(format!(
+            "The state diff matches the shape of no version of Starknet. As of 0.11.0: \
+             {v0_11_error}. Before 0.11.0: {v0_9_error}."
+        )))
+    }
+}
+
 impl StateDiff {
     // Returns the declared class hashes in the following order:
     // [declared classes, deprecated declared class, class hashes of deployed contracts].
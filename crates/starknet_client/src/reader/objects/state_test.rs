@@ -0,0 +1,75 @@
+use indexmap::indexmap;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use starknet_api::core::{ClassHash, ContractAddress, Nonce, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::StorageKey;
+use starknet_api::{patricia_key, stark_felt};
+
+use crate::reader::objects::state::{DeployedContract, StateDiff, StorageEntry};
+
+#[test]
+fn deserialize_state_diff_before_v0_11() {
+    let address = ContractAddress(patricia_key!("0x1"));
+    let state_diff: StateDiff = serde_json::from_value(json!({
+        "storage_diffs": {"0x1": [{"key": "0x2", "value": "0x3"}]},
+        "deployed_contracts": [{"address": "0x1", "class_hash": "0x4"}],
+        "declared_contracts": ["0x5"],
+        "nonces": {"0x1": "0x6"},
+    }))
+    .unwrap();
+    assert_eq!(
+        state_diff,
+        StateDiff {
+            storage_diffs: indexmap!(address => vec![StorageEntry {
+                key: StorageKey(patricia_key!("0x2")),
+                value: stark_felt!("0x3"),
+            }]),
+            deployed_contracts: vec![DeployedContract {
+                address,
+                class_hash: ClassHash(stark_felt!("0x4")),
+            }],
+            old_declared_contracts: vec![ClassHash(stark_felt!("0x5"))],
+            nonces: indexmap!(address => Nonce(stark_felt!("0x6"))),
+            ..Default::default()
+        }
+    );
+
+    // Before Starknet 0.10.0 the state diff had no nonces.
+    let state_diff: StateDiff =
+        serde_json::from_value(json!({"storage_diffs": {}, "deployed_contracts": []})).unwrap();
+    assert_eq!(state_diff, StateDiff::default());
+}
+
+#[test]
+fn serialized_state_diff_is_deserialized_as_current_version() {
+    let state_diff = StateDiff {
+        old_declared_contracts: vec![ClassHash(stark_felt!("0x5"))],
+        ..Default::default()
+    };
+    let serialized = serde_json::to_value(&state_diff).unwrap();
+    assert!(serialized.get("old_declared_contracts").is_some());
+    assert_eq!(serde_json::from_value::<StateDiff>(serialized).unwrap(), state_diff);
+}
+
+#[test]
+fn deserialize_state_diff_of_unknown_shape_fails() {
+    let error = serde_json::from_value::<StateDiff>(json!({"deployed_contracts": []})).unwrap_err();
+    assert!(error.to_string().contains("matches the shape of no version"));
+}
+
+#[test]
+fn invalid_state_diff_of_v0_11_is_not_deserialized_as_older_version() {
+    // The declared classes are invalid, so the state diff must not be read as a pre-0.11 one that
+    // ignores them.
+    let error = serde_json::from_value::<StateDiff>(json!({
+        "storage_diffs": {},
+        "deployed_contracts": [],
+        "declared_classes": [{"class_hash": "0x1"}],
+        "old_declared_contracts": [],
+        "nonces": {},
+        "replaced_classes": [],
+    }))
+    .unwrap_err();
+    assert!(error.to_string().contains("matches the shape of no version"));
+}
@@ -48,7 +48,8 @@ use super::{
     GET_BLOCK_URL,
     GET_STATE_UPDATE_URL,
 };
-use crate::reader::objects::block::{BlockSignatureData, BlockSignatureMessage};
+use crate::reader::objects::block::{BlockSignatureData, BlockSignatureMessage, DeprecatedBlock};
+use crate::reader::objects::transaction::TransactionReceipt;
 use crate::reader::BlockOrDeprecated;
 use crate::test_utils::read_resource::read_resource_file;
 use crate::test_utils::retry::get_test_config;
@@ -345,6 +346,11 @@ async fn deprecated_pending_data() {
     mock_pending.assert();
     let expected_pending_data: PendingData = serde_json::from_str(&raw_pending_data).unwrap();
     assert_eq!(pending_data.unwrap().unwrap(), expected_pending_data);
+    // The pending block comes with the receipts of its transactions, from which the receipts of
+    // pending transactions are served.
+    let pending_block = &expected_pending_data.block;
+    assert!(!pending_block.transaction_receipts().is_empty());
+    assert_eq!(pending_block.transaction_receipts().len(), pending_block.transactions().len());
 
     // Accepted on L2.
     let raw_pending_data = read_resource_file("reader/accepted_on_l2_deprecated_data.json");
@@ -519,6 +525,81 @@ async fn block_unserializable() {
     .await
 }
 
+// Returns the raw responses captured in the directory, along with their context.
+fn read_captures(capture_dir: &std::path::Path) -> Vec<(Vec<u8>, serde_json::Value)> {
+    std::fs::read_dir(capture_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().unwrap() == "response")
+        .map(|path| {
+            let context = std::fs::read(path.with_extension("json")).unwrap();
+            (std::fs::read(path).unwrap(), serde_json::from_slice(&context).unwrap())
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn unserializable_response_captured() {
+    let capture_dir = tempfile::tempdir().unwrap();
+    let starknet_client = StarknetFeederGatewayClient::new(
+        &mockito::server_url(),
+        None,
+        NODE_VERSION,
+        get_test_config(),
+    )
+    .unwrap()
+    .with_response_capture(capture_dir.path().to_path_buf());
+    let mock = mock("GET", "/feeder_gateway/get_block?blockNumber=21")
+        .with_status(200)
+        .with_body("body")
+        .create();
+    starknet_client.block(BlockNumber(21)).await.unwrap_err();
+    mock.assert();
+
+    let captures = read_captures(capture_dir.path());
+    assert_eq!(captures.len(), 1);
+    let (response, context) = &captures[0];
+    assert_eq!(response, b"body");
+    assert_eq!(
+        context["request"],
+        serde_json::json!(format!(
+            "{}/feeder_gateway/get_block?blockNumber=21",
+            mockito::server_url()
+        ))
+    );
+}
+
+#[tokio::test]
+async fn invalid_block_captured() {
+    let capture_dir = tempfile::tempdir().unwrap();
+    let starknet_client = StarknetFeederGatewayClient::new(
+        &mockito::server_url(),
+        None,
+        NODE_VERSION,
+        get_test_config(),
+    )
+    .unwrap()
+    .with_response_capture(capture_dir.path().to_path_buf());
+    // A block with a receipt for a transaction it doesn't have.
+    let block = BlockOrDeprecated::Deprecated(DeprecatedBlock {
+        transaction_receipts: vec![TransactionReceipt::default()],
+        ..Default::default()
+    });
+    let raw_block = serde_json::to_string_pretty(&block).unwrap();
+    let mock = mock("GET", "/feeder_gateway/get_block?blockNumber=22")
+        .with_status(200)
+        .with_body(&raw_block)
+        .create();
+    // The block is returned, and fails when it's converted.
+    assert_eq!(starknet_client.block(BlockNumber(22)).await.unwrap(), Some(block));
+    mock.assert();
+
+    // The block is captured as it was received.
+    let captures = read_captures(capture_dir.path());
+    assert_eq!(captures.len(), 1);
+    assert_eq!(captures[0].0, raw_block.as_bytes());
+}
+
 #[tokio::test]
 async fn class_by_hash_unserializable() {
     test_unserializable(
@@ -539,6 +620,23 @@ async fn state_update_unserializable() {
     .await
 }
 
+#[test]
+fn invalid_data_errors() {
+    // A truncated response may be whole when it's downloaded again.
+    let truncated_error = serde_json::from_str::<StateUpdate>("{\"block_hash\":").unwrap_err();
+    assert!(!ReaderClientError::SerdeError(truncated_error).is_invalid_data());
+
+    // A whole response that doesn't match the expected structure is invalid.
+    let data_error = serde_json::from_str::<StateUpdate>("{\"block_hash\": 7}").unwrap_err();
+    assert!(ReaderClientError::SerdeError(data_error).is_invalid_data());
+
+    let bad_transaction_error = ReaderClientError::BadTransaction {
+        tx_hash: TransactionHash::default(),
+        msg: "".to_owned(),
+    };
+    assert!(bad_transaction_error.is_invalid_data());
+}
+
 #[tokio::test]
 async fn compiled_class_by_hash_unserializable() {
     test_unserializable(
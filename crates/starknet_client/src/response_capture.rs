@@ -0,0 +1,75 @@
+//! Capturing of raw responses of Starknet that failed to be parsed or validated. Each response is
+//! written as received to its own file, next to a file with the request it answered and the error,
+//! so that malformed payloads can be attached to bug reports and turned into regression fixtures.
+#[cfg(test)]
+#[path = "response_capture_test.rs"]
+mod response_capture_test;
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::json;
+use tracing::{debug, info, warn};
+
+/// The maximal number of responses kept in a capture directory. A response that fails repeatedly,
+/// e.g. an invalid block that the sync keeps retrying, is captured until the directory is full.
+pub const MAX_CAPTURED_RESPONSES: usize = 100;
+
+// The extension of the files of the raw responses.
+const RESPONSE_EXTENSION: &str = "response";
+
+// The number of responses captured by the process, which distinguishes the files of responses
+// captured in the same millisecond.
+static CAPTURED_RESPONSES: AtomicUsize = AtomicUsize::new(0);
+
+/// Writes the raw response to a new file in `capture_dir`, and the request and the error to a JSON
+/// file of the same name, creating the directory if needed. Returns the path of the response file.
+/// Nothing is written if the directory already holds [`MAX_CAPTURED_RESPONSES`] responses. Failing
+/// to write is only logged, since the capture is a debugging aid that shouldn't fail the request.
+pub fn capture_response(
+    capture_dir: &Path,
+    request: &str,
+    error: &str,
+    response: &[u8],
+) -> Option<PathBuf> {
+    if captured_responses(capture_dir) >= MAX_CAPTURED_RESPONSES {
+        debug!("Not capturing the response to {request} since {} is full.", capture_dir.display());
+        return None;
+    }
+    let millis = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+    let index = CAPTURED_RESPONSES.fetch_add(1, Ordering::Relaxed);
+    let path = capture_dir.join(format!("response_{millis}_{index}.{RESPONSE_EXTENSION}"));
+    let context = json!({"request": request, "error": error});
+
+    let write_result = std::fs::create_dir_all(capture_dir)
+        .and_then(|_| std::fs::write(path.with_extension("json"), context.to_string()))
+        .and_then(|_| std::fs::write(&path, response));
+    match write_result {
+        Ok(()) => {
+            info!(
+                "Captured the response to {request} that failed with {error} at {}.",
+                path.display()
+            );
+            Some(path)
+        }
+        Err(err) => {
+            warn!("Failed to capture the response to {request} at {}: {err}", path.display());
+            None
+        }
+    }
+}
+
+// Returns the number of responses in the capture directory, which may hold the responses captured
+// before the node restarted.
+fn captured_responses(capture_dir: &Path) -> usize {
+    let Ok(entries) = std::fs::read_dir(capture_dir) else {
+        return 0;
+    };
+    entries
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry.path().extension().is_some_and(|extension| extension == RESPONSE_EXTENSION)
+        })
+        .count()
+}
@@ -0,0 +1,36 @@
+use pretty_assertions::assert_eq;
+use serde_json::{json, Value};
+
+use crate::response_capture::{capture_response, MAX_CAPTURED_RESPONSES};
+
+fn read_context(response_path: &std::path::Path) -> Value {
+    serde_json::from_slice(&std::fs::read(response_path.with_extension("json")).unwrap()).unwrap()
+}
+
+#[test]
+fn captures_raw_responses() {
+    let dir = tempfile::tempdir().unwrap();
+    // The directory is created on the first capture.
+    let capture_dir = dir.path().join("captures");
+
+    let response = br#"{"a":  1 }"#;
+    let path = capture_response(&capture_dir, "get_block 5", "bad field", response).unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), response);
+    assert_eq!(read_context(&path), json!({"request": "get_block 5", "error": "bad field"}));
+
+    // Responses that aren't JSON are kept too, in files of their own.
+    let other_path = capture_response(&capture_dir, "get_block 6", "EOF", b"{\"a\":").unwrap();
+    assert_ne!(path, other_path);
+    assert_eq!(std::fs::read(&other_path).unwrap(), b"{\"a\":");
+}
+
+#[test]
+fn number_of_captured_responses_is_bounded() {
+    let capture_dir = tempfile::tempdir().unwrap();
+    for _ in 0..MAX_CAPTURED_RESPONSES {
+        capture_response(capture_dir.path(), "get_block 5", "bad field", b"{}").unwrap();
+    }
+    assert_eq!(capture_response(capture_dir.path(), "get_block 5", "bad field", b"{}"), None);
+    // A response file and a context file per captured response.
+    assert_eq!(std::fs::read_dir(capture_dir.path()).unwrap().count(), 2 * MAX_CAPTURED_RESPONSES);
+}
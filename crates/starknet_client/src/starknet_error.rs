@@ -57,6 +57,8 @@ pub enum KnownStarknetErrorCode {
     InvalidTransactionNonce,
     #[serde(rename = "StarknetErrorCode.INVALID_TRANSACTION_VERSION")]
     InvalidTransactionVersion,
+    #[serde(rename = "StarknetErrorCode.UNAUTHORIZED_ACTION_ON_VALIDATE")]
+    UnauthorizedActionOnValidate,
     #[serde(rename = "StarknetErrorCode.VALIDATE_FAILURE")]
     ValidateFailure,
     #[serde(rename = "StarknetErrorCode.TRANSACTION_LIMIT_EXCEEDED")]
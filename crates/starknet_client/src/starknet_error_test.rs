@@ -54,6 +54,10 @@ fn known_error_code_deserialization() {
             "StarknetErrorCode.INVALID_TRANSACTION_VERSION",
             KnownStarknetErrorCode::InvalidTransactionVersion,
         ),
+        (
+            "StarknetErrorCode.UNAUTHORIZED_ACTION_ON_VALIDATE",
+            KnownStarknetErrorCode::UnauthorizedActionOnValidate,
+        ),
         ("StarknetErrorCode.VALIDATE_FAILURE", KnownStarknetErrorCode::ValidateFailure),
         (
             "StarknetErrorCode.TRANSACTION_LIMIT_EXCEEDED",
@@ -73,12 +73,23 @@ pub enum DeclareType {
     Declare,
 }
 
-// This enum is required since the GW receives this field with value 0 as a reserved value. Once the
-// feature will be activated this enum should be removed from here and taken from starknet-api.
-#[derive(Debug, Deserialize_repr, Serialize_repr, Clone, Eq, PartialEq)]
+/// The data availability mode of the nonce or the fee of a transaction. The gateway receives it as
+/// an integer rather than the name that starknet_api serializes it to.
+#[derive(Debug, Default, Deserialize_repr, Serialize_repr, Clone, Copy, Eq, PartialEq)]
 #[repr(u8)]
-pub enum ReservedDataAvailabilityMode {
-    Reserved = 0,
+pub enum DataAvailabilityMode {
+    #[default]
+    L1 = 0,
+    L2 = 1,
+}
+
+impl From<starknet_api::data_availability::DataAvailabilityMode> for DataAvailabilityMode {
+    fn from(mode: starknet_api::data_availability::DataAvailabilityMode) -> Self {
+        match mode {
+            starknet_api::data_availability::DataAvailabilityMode::L1 => Self::L1,
+            starknet_api::data_availability::DataAvailabilityMode::L2 => Self::L2,
+        }
+    }
 }
 
 /// A deploy account transaction that can be added to Starknet through the Starknet gateway.
@@ -100,7 +111,6 @@ pub struct DeployAccountV1Transaction {
 /// A deploy account transaction that can be added to Starknet through the Starknet gateway.
 /// It has a serialization format that the Starknet gateway accepts in the `add_transaction`
 /// HTTP method.
-// TODO(Shahak): Add tests for deploy account v3.
 #[derive(Debug, Deserialize, Serialize, Clone, Eq, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct DeployAccountV3Transaction {
@@ -111,8 +121,8 @@ pub struct DeployAccountV3Transaction {
     pub constructor_calldata: Calldata,
     pub nonce: Nonce,
     pub signature: TransactionSignature,
-    pub nonce_data_availability_mode: ReservedDataAvailabilityMode,
-    pub fee_data_availability_mode: ReservedDataAvailabilityMode,
+    pub nonce_data_availability_mode: DataAvailabilityMode,
+    pub fee_data_availability_mode: DataAvailabilityMode,
     pub paymaster_data: PaymasterData,
     pub version: TransactionVersion,
     pub r#type: DeployAccountType,
@@ -132,7 +142,6 @@ pub enum DeployAccountTransaction {
 /// The invoke is a V0 transaction.
 /// It has a serialization format that the Starknet gateway accepts in the `add_transaction`
 /// HTTP method.
-// TODO(Shahak): Add tests for invoke v0.
 #[derive(Debug, Default, Deserialize, Serialize, Clone, Eq, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct InvokeV0Transaction {
@@ -165,7 +174,6 @@ pub struct InvokeV1Transaction {
 /// The invoke is a V3 transaction.
 /// It has a serialization format that the Starknet gateway accepts in the `add_transaction`
 /// HTTP method.
-// TODO(Shahak): Add tests for invoke v3.
 #[derive(Debug, Deserialize, Serialize, Clone, Eq, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct InvokeV3Transaction {
@@ -175,8 +183,8 @@ pub struct InvokeV3Transaction {
     pub sender_address: ContractAddress,
     pub nonce: Nonce,
     pub signature: TransactionSignature,
-    pub nonce_data_availability_mode: ReservedDataAvailabilityMode,
-    pub fee_data_availability_mode: ReservedDataAvailabilityMode,
+    pub nonce_data_availability_mode: DataAvailabilityMode,
+    pub fee_data_availability_mode: DataAvailabilityMode,
     pub paymaster_data: PaymasterData,
     pub account_deployment_data: AccountDeploymentData,
     pub version: TransactionVersion,
@@ -230,7 +238,6 @@ pub struct DeclareV2Transaction {
 /// Starknet gateway.
 /// It has a serialization format that the Starknet gateway accepts in the `add_transaction`
 /// HTTP method.
-// TODO(shahak): Add tests for declare v3.
 #[derive(Debug, Deserialize, Serialize, Clone, Eq, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct DeclareV3Transaction {
@@ -241,8 +248,8 @@ pub struct DeclareV3Transaction {
     pub nonce: Nonce,
     pub compiled_class_hash: CompiledClassHash,
     pub sender_address: ContractAddress,
-    pub nonce_data_availability_mode: ReservedDataAvailabilityMode,
-    pub fee_data_availability_mode: ReservedDataAvailabilityMode,
+    pub nonce_data_availability_mode: DataAvailabilityMode,
+    pub fee_data_availability_mode: DataAvailabilityMode,
     pub paymaster_data: PaymasterData,
     pub account_deployment_data: AccountDeploymentData,
     pub version: TransactionVersion,
@@ -1,11 +1,19 @@
-use test_utils::validate_load_and_dump;
+use assert_matches::assert_matches;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use test_utils::{read_json_file, validate_load_and_dump};
 
 use super::{
+    DataAvailabilityMode,
+    DeclareTransaction,
     DeclareV1Transaction,
     DeclareV2Transaction,
     DeclareV3Transaction,
+    DeployAccountTransaction,
     DeployAccountV1Transaction,
     DeployAccountV3Transaction,
+    InvokeTransaction,
+    InvokeV0Transaction,
     InvokeV1Transaction,
     InvokeV3Transaction,
 };
@@ -20,6 +28,11 @@ fn load_and_dump_deploy_account_v3_same_string() {
     validate_load_and_dump::<DeployAccountV3Transaction>("writer/deploy_account_v3.json");
 }
 
+#[test]
+fn load_and_dump_invoke_v0_same_string() {
+    validate_load_and_dump::<InvokeV0Transaction>("writer/invoke_v0.json");
+}
+
 #[test]
 fn load_and_dump_invoke_v1_same_string() {
     validate_load_and_dump::<InvokeV1Transaction>("writer/invoke_v1.json");
@@ -44,3 +57,52 @@ fn load_and_dump_declare_v2_same_string() {
 fn load_and_dump_declare_v3_same_string() {
     validate_load_and_dump::<DeclareV3Transaction>("writer/declare_v3.json");
 }
+
+// The transaction enums are untagged, so a transaction of one version mustn't be loaded as another
+// version that lacks some of its fields.
+#[test]
+fn load_every_version_as_its_own_variant() {
+    let load = |path| read_json_file(path);
+    assert_matches!(
+        serde_json::from_value(load("writer/deploy_account_v1.json")).unwrap(),
+        DeployAccountTransaction::DeployAccountV1(_)
+    );
+    assert_matches!(
+        serde_json::from_value(load("writer/deploy_account_v3.json")).unwrap(),
+        DeployAccountTransaction::DeployAccountV3(_)
+    );
+    assert_matches!(
+        serde_json::from_value(load("writer/invoke_v0.json")).unwrap(),
+        InvokeTransaction::InvokeV0(_)
+    );
+    assert_matches!(
+        serde_json::from_value(load("writer/invoke_v1.json")).unwrap(),
+        InvokeTransaction::InvokeV1(_)
+    );
+    assert_matches!(
+        serde_json::from_value(load("writer/invoke_v3.json")).unwrap(),
+        InvokeTransaction::InvokeV3(_)
+    );
+    assert_matches!(
+        serde_json::from_value(load("writer/declare_v1.json")).unwrap(),
+        DeclareTransaction::DeclareV1(_)
+    );
+    assert_matches!(
+        serde_json::from_value(load("writer/declare_v2.json")).unwrap(),
+        DeclareTransaction::DeclareV2(_)
+    );
+    assert_matches!(
+        serde_json::from_value(load("writer/declare_v3.json")).unwrap(),
+        DeclareTransaction::DeclareV3(_)
+    );
+}
+
+#[test]
+fn data_availability_mode_is_sent_as_an_integer() {
+    assert_eq!(serde_json::to_value(DataAvailabilityMode::L1).unwrap(), json!(0));
+    assert_eq!(serde_json::to_value(DataAvailabilityMode::L2).unwrap(), json!(1));
+    assert_eq!(
+        DataAvailabilityMode::from(starknet_api::data_availability::DataAvailabilityMode::L2),
+        DataAvailabilityMode::L2
+    );
+}
@@ -4,13 +4,13 @@ mod precision_test;
 
 use std::cmp::max;
 use std::collections::{BTreeMap, HashMap};
-use std::env;
 use std::fs::read_to_string;
 use std::hash::Hash;
 use std::net::SocketAddr;
 use std::ops::{Deref, Index};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::{env, iter};
 
 use cairo_lang_casm::hints::{CoreHint, CoreHintBase, Hint};
 use cairo_lang_casm::operand::{
@@ -30,6 +30,8 @@ use cairo_lang_starknet_classes::NestedIntList;
 use cairo_lang_utils::bigint::BigUintAsHex;
 use indexmap::IndexMap;
 use num_bigint::BigUint;
+use papyrus_common::transaction_hash::get_transaction_hash;
+use papyrus_common::TransactionOptions;
 use primitive_types::H160;
 use prometheus_parse::Value;
 use rand::{Rng, RngCore, SeedableRng};
@@ -50,6 +52,7 @@ use starknet_api::block::{
     StarknetVersion,
 };
 use starknet_api::core::{
+    ChainId,
     ClassHash,
     CompiledClassHash,
     ContractAddress,
@@ -244,6 +247,89 @@ fn get_rand_test_block_with_events(
     }
 }
 
+fn get_test_transaction_of_every_type(rng: &mut ChaCha8Rng) -> Vec<Transaction> {
+    let declare_v0 = DeclareTransactionV0V1 {
+        class_hash: ClassHash(random_felt(rng)),
+        ..DeclareTransactionV0V1::get_test_instance(rng)
+    };
+    let declare_v1 = DeclareTransactionV0V1 {
+        class_hash: ClassHash(random_felt(rng)),
+        ..DeclareTransactionV0V1::get_test_instance(rng)
+    };
+    let declare_v2 = DeclareTransactionV2 {
+        class_hash: ClassHash(random_felt(rng)),
+        ..DeclareTransactionV2::get_test_instance(rng)
+    };
+    let declare_v3 = DeclareTransactionV3 {
+        class_hash: ClassHash(random_felt(rng)),
+        resource_bounds: get_test_resource_bounds(rng),
+        ..DeclareTransactionV3::get_test_instance(rng)
+    };
+    let deploy = DeployTransaction {
+        version: TransactionVersion::ZERO,
+        contract_address_salt: ContractAddressSalt(random_felt(rng)),
+        ..DeployTransaction::get_test_instance(rng)
+    };
+    let deploy_account_v1 = DeployAccountTransactionV1 {
+        contract_address_salt: ContractAddressSalt(random_felt(rng)),
+        ..DeployAccountTransactionV1::get_test_instance(rng)
+    };
+    let deploy_account_v3 = DeployAccountTransactionV3 {
+        contract_address_salt: ContractAddressSalt(random_felt(rng)),
+        resource_bounds: get_test_resource_bounds(rng),
+        ..DeployAccountTransactionV3::get_test_instance(rng)
+    };
+    let invoke_v0 = InvokeTransactionV0 {
+        entry_point_selector: EntryPointSelector(random_felt(rng)),
+        ..InvokeTransactionV0::get_test_instance(rng)
+    };
+    let invoke_v1 = InvokeTransactionV1 {
+        nonce: Nonce(random_felt(rng)),
+        ..InvokeTransactionV1::get_test_instance(rng)
+    };
+    let invoke_v3 = InvokeTransactionV3 {
+        nonce: Nonce(random_felt(rng)),
+        resource_bounds: get_test_resource_bounds(rng),
+        ..InvokeTransactionV3::get_test_instance(rng)
+    };
+    let l1_handler = L1HandlerTransaction {
+        version: TransactionVersion::ZERO,
+        nonce: Nonce(random_felt(rng)),
+        ..L1HandlerTransaction::get_test_instance(rng)
+    };
+    vec![
+        Transaction::Declare(DeclareTransaction::V0(declare_v0)),
+        Transaction::Declare(DeclareTransaction::V1(declare_v1)),
+        Transaction::Declare(DeclareTransaction::V2(declare_v2)),
+        Transaction::Declare(DeclareTransaction::V3(declare_v3)),
+        Transaction::Deploy(deploy),
+        Transaction::DeployAccount(DeployAccountTransaction::V1(deploy_account_v1)),
+        Transaction::DeployAccount(DeployAccountTransaction::V3(deploy_account_v3)),
+        Transaction::Invoke(InvokeTransaction::V0(invoke_v0)),
+        Transaction::Invoke(InvokeTransaction::V1(invoke_v1)),
+        Transaction::Invoke(InvokeTransaction::V3(invoke_v3)),
+        Transaction::L1Handler(l1_handler),
+    ]
+}
+
+// A random felt to set in a field of each transaction, so that the hashes of the transactions are
+// unique.
+fn random_felt(rng: &mut ChaCha8Rng) -> StarkFelt {
+    StarkFelt::from(rng.next_u64())
+}
+
+// Returns resource bounds with both the L1 gas and the L2 gas resources, as required by the hash
+// of V3 transactions.
+fn get_test_resource_bounds(rng: &mut ChaCha8Rng) -> ResourceBoundsMapping {
+    ResourceBoundsMapping(BTreeMap::from([
+        (
+            Resource::L1Gas,
+            ResourceBounds { max_amount: rng.next_u64(), max_price_per_unit: rng.gen() },
+        ),
+        (Resource::L2Gas, ResourceBounds { max_amount: 0, max_price_per_unit: 0 }),
+    ]))
+}
+
 // TODO(Dan, 01/11/2023): Remove this util once v3 tests are ready and transaction generation is
 // using randomness more stably.
 fn is_v3_transaction(transaction: &Transaction) -> bool {
@@ -255,7 +341,11 @@ fn is_v3_transaction(transaction: &Transaction) -> bool {
     )
 }
 
-/// Returns a test block body with a variable number of transactions and events.
+// The chain on which the hashes of the transactions of the test bodies are calculated.
+const TEST_BODY_CHAIN_ID: &str = "SN_GOERLI";
+
+/// Returns a test block body with a variable number of transactions and events. The transactions
+/// are valid and of every type and version other than V3, and each has its hash on the test chain.
 fn get_rand_test_body_with_events(
     rng: &mut ChaCha8Rng,
     transaction_count: usize,
@@ -263,21 +353,14 @@ fn get_rand_test_body_with_events(
     from_addresses: Option<Vec<ContractAddress>>,
     keys: Option<Vec<Vec<EventKey>>>,
 ) -> BlockBody {
-    let mut transactions = vec![];
-    let mut transaction_outputs = vec![];
-    let mut transaction_hashes = vec![];
-    let mut transaction_execution_statuses = vec![];
-    for i in 0..transaction_count {
-        let mut transaction = Transaction::get_test_instance(rng);
-        while is_v3_transaction(&transaction) {
-            transaction = Transaction::get_test_instance(rng);
-        }
-        transaction_hashes.push(TransactionHash(StarkHash::from(i as u128)));
-        let transaction_output = get_test_transaction_output(&transaction);
-        transactions.push(transaction);
-        transaction_outputs.push(transaction_output);
-        transaction_execution_statuses.push(TransactionExecutionStatus::default());
-    }
+    let chain_id = ChainId(TEST_BODY_CHAIN_ID.to_owned());
+    let (transactions, transaction_hashes): (Vec<_>, Vec<_>) =
+        iter::repeat_with(|| get_test_transactions_with_hashes(rng, &chain_id))
+            .flatten()
+            .filter(|(transaction, _)| !is_v3_transaction(transaction))
+            .take(transaction_count)
+            .unzip();
+    let transaction_outputs = transactions.iter().map(get_test_transaction_output).collect();
     let mut body = BlockBody { transactions, transaction_outputs, transaction_hashes };
     for tx_output in &mut body.transaction_outputs {
         let mut events = vec![];
@@ -395,6 +478,37 @@ pub fn get_test_state_diff() -> StateDiff {
     res
 }
 
+/// Returns a random transaction of every type and version, each with its hash on the given chain.
+/// Unlike `Transaction::get_test_instance`, the transactions are valid: their versions and
+/// resource bounds are set as required, and they differ from each other so that their hashes are
+/// unique.
+pub fn get_test_transactions_with_hashes(
+    rng: &mut ChaCha8Rng,
+    chain_id: &ChainId,
+) -> Vec<(Transaction, TransactionHash)> {
+    get_test_transaction_of_every_type(rng)
+        .into_iter()
+        .map(|transaction| {
+            let transaction_hash =
+                get_transaction_hash(&transaction, chain_id, &TransactionOptions::default())
+                    .expect("Failed to calculate the hash of a test transaction.");
+            (transaction, transaction_hash)
+        })
+        .collect()
+}
+
+/// Returns a block body with a random transaction of every type and version, each with its hash
+/// on the given chain.
+pub fn get_test_body_with_valid_transaction_hashes(
+    rng: &mut ChaCha8Rng,
+    chain_id: &ChainId,
+) -> BlockBody {
+    let (transactions, transaction_hashes): (Vec<_>, Vec<_>) =
+        get_test_transactions_with_hashes(rng, chain_id).into_iter().unzip();
+    let transaction_outputs = transactions.iter().map(get_test_transaction_output).collect();
+    BlockBody { transactions, transaction_outputs, transaction_hashes }
+}
+
 ////////////////////////////////////////////////////////////////////////
 // Implementation of GetTestInstance
 ////////////////////////////////////////////////////////////////////////